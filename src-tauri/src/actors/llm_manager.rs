@@ -2,14 +2,19 @@
 //
 // 用消息传递替代锁机制，消除Arc<Mutex<LLMManager>>的锁竞争
 
-use crate::llm::{CodexConfig, LLMConfig, LLMManager, QwenConfig, SessionBrief, SessionSummary};
+use crate::llm::{
+    CodexConfig, DayType, LLMConfig, LLMManager, MeetingNotes, QwenConfig, SessionBrief,
+    SessionSummary,
+};
 use anyhow::Result;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::llm::{TimelineAnalysis, TimelineCard, VideoSegment};
-use crate::storage::Database;
+use crate::models::VideoUploadConfig;
+use crate::storage::{CardCorrectionRecord, Database};
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
+use tracing::{Instrument, Span};
 
 /// LLM管理器命令
 pub enum LLMCommand {
@@ -25,6 +30,12 @@ pub enum LLMCommand {
         reply: oneshot::Sender<Result<()>>,
     },
 
+    /// 配置外部插件 provider
+    ConfigurePlugin {
+        config: serde_json::Value,
+        reply: oneshot::Sender<Result<()>>,
+    },
+
     /// 分析帧
     AnalyzeFrames {
         frames: Vec<String>,
@@ -46,6 +57,12 @@ pub enum LLMCommand {
         reply: oneshot::Sender<()>,
     },
 
+    /// 设置视频上传配置
+    SetVideoUpload {
+        config: VideoUploadConfig,
+        reply: oneshot::Sender<()>,
+    },
+
     /// 设置会话时间范围
     SetSessionWindow {
         start: Option<DateTime<Utc>>,
@@ -53,6 +70,12 @@ pub enum LLMCommand {
         reply: oneshot::Sender<()>,
     },
 
+    /// 设置用户纠正记录（提示词示例）
+    SetCorrectionExamples {
+        corrections: Vec<CardCorrectionRecord>,
+        reply: oneshot::Sender<()>,
+    },
+
     /// 设置provider的数据库连接
     SetProviderDatabase {
         db: Arc<Database>,
@@ -85,6 +108,20 @@ pub enum LLMCommand {
     GenerateDaySummary {
         date: String,
         sessions: Vec<SessionBrief>,
+        day_type: DayType,
+        reply: oneshot::Sender<Result<String>>,
+    },
+
+    /// 生成会议纪要
+    GenerateMeetingNotes {
+        frames: Vec<String>,
+        context: String,
+        reply: oneshot::Sender<Result<MeetingNotes>>,
+    },
+
+    /// 生成周计划
+    GenerateWeekPlan {
+        prompt: String,
         reply: oneshot::Sender<Result<String>>,
     },
 
@@ -106,7 +143,8 @@ pub enum LLMCommand {
 
 /// LLM Manager Actor（无需外层Mutex）
 pub struct LLMManagerActor {
-    receiver: mpsc::Receiver<LLMCommand>,
+    /// 命令及发送方所在的 tracing span（让会话关联 ID 延续到 Actor 内部的日志）
+    receiver: mpsc::Receiver<(LLMCommand, Span)>,
     manager: LLMManager, // 直接持有，无需锁
 }
 
@@ -123,109 +161,146 @@ impl LLMManagerActor {
     pub async fn run(mut self) {
         tracing::info!("LLM Manager Actor 已启动");
 
-        while let Some(cmd) = self.receiver.recv().await {
-            match cmd {
-                LLMCommand::Configure { config, reply } => {
-                    let result = self.manager.configure(config).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::ConfigureCodex { config, reply } => {
-                    let result = self.manager.configure_codex(config).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::AnalyzeFrames { frames, reply } => {
-                    let result = self.manager.analyze_frames(frames).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::GetConfig { reply } => {
-                    let config = self.manager.get_config().await;
-                    let _ = reply.send(config);
-                }
-
-                LLMCommand::SetVideoPath { video_path, reply } => {
-                    self.manager.set_video_path(video_path);
-                    let _ = reply.send(()); // 发送确认
-                }
-
-                LLMCommand::SetVideoSpeed {
-                    speed_multiplier,
-                    reply,
-                } => {
-                    self.manager.set_video_speed(speed_multiplier);
-                    let _ = reply.send(()); // 发送确认
-                }
-
-                LLMCommand::SetSessionWindow { start, end, reply } => {
-                    self.manager.set_session_window(start, end);
-                    let _ = reply.send(()); // 发送确认
-                }
-
-                LLMCommand::SetProviderDatabase {
-                    db,
-                    session_id,
-                    reply,
-                } => {
-                    self.manager.set_provider_database(db, session_id);
-                    let _ = reply.send(()); // 发送确认
-                }
-
-                LLMCommand::SegmentVideoAndGenerateTimeline {
-                    frames,
-                    duration,
-                    previous_cards,
-                    reply,
-                } => {
-                    let result = self
-                        .manager
-                        .segment_video_and_generate_timeline(frames, duration, previous_cards)
-                        .await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::GetLastCallId { call_type, reply } => {
-                    let id = self.manager.get_last_call_id(&call_type);
-                    let _ = reply.send(id);
-                }
-
-                LLMCommand::GenerateTimeline {
-                    segments,
-                    previous_cards,
-                    reply,
-                } => {
-                    let result = self
-                        .manager
-                        .generate_timeline(segments, previous_cards)
-                        .await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::GenerateDaySummary {
-                    date,
-                    sessions,
-                    reply,
-                } => {
-                    let result = self.manager.generate_day_summary(&date, &sessions).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::SwitchProvider { provider, reply } => {
-                    let result = self.manager.switch_provider(&provider).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::ConfigureClaude { config, reply } => {
-                    let result = self.manager.configure_claude(config).await;
-                    let _ = reply.send(result);
-                }
-
-                LLMCommand::HealthCheck { reply } => {
-                    // 立即响应，表明Actor正常运行
-                    let _ = reply.send(());
+        while let Some((cmd, span)) = self.receiver.recv().await {
+            async {
+                match cmd {
+                    LLMCommand::Configure { config, reply } => {
+                        let result = self.manager.configure(config).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::ConfigureCodex { config, reply } => {
+                        let result = self.manager.configure_codex(config).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::ConfigurePlugin { config, reply } => {
+                        let result = self.manager.configure_plugin(config).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::AnalyzeFrames { frames, reply } => {
+                        let result = self.manager.analyze_frames(frames).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::GetConfig { reply } => {
+                        let config = self.manager.get_config().await;
+                        let _ = reply.send(config);
+                    }
+
+                    LLMCommand::SetVideoPath { video_path, reply } => {
+                        self.manager.set_video_path(video_path);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SetVideoSpeed {
+                        speed_multiplier,
+                        reply,
+                    } => {
+                        self.manager.set_video_speed(speed_multiplier);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SetVideoUpload { config, reply } => {
+                        self.manager.set_video_upload(config);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SetSessionWindow { start, end, reply } => {
+                        self.manager.set_session_window(start, end);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SetCorrectionExamples { corrections, reply } => {
+                        self.manager.set_correction_examples(corrections);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SetProviderDatabase {
+                        db,
+                        session_id,
+                        reply,
+                    } => {
+                        self.manager.set_provider_database(db, session_id);
+                        let _ = reply.send(()); // 发送确认
+                    }
+
+                    LLMCommand::SegmentVideoAndGenerateTimeline {
+                        frames,
+                        duration,
+                        previous_cards,
+                        reply,
+                    } => {
+                        let result = self
+                            .manager
+                            .segment_video_and_generate_timeline(frames, duration, previous_cards)
+                            .await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::GetLastCallId { call_type, reply } => {
+                        let id = self.manager.get_last_call_id(&call_type);
+                        let _ = reply.send(id);
+                    }
+
+                    LLMCommand::GenerateTimeline {
+                        segments,
+                        previous_cards,
+                        reply,
+                    } => {
+                        let result = self
+                            .manager
+                            .generate_timeline(segments, previous_cards)
+                            .await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::GenerateDaySummary {
+                        date,
+                        sessions,
+                        day_type,
+                        reply,
+                    } => {
+                        let result = self
+                            .manager
+                            .generate_day_summary(&date, &sessions, day_type)
+                            .await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::GenerateMeetingNotes {
+                        frames,
+                        context,
+                        reply,
+                    } => {
+                        let result = self.manager.generate_meeting_notes(frames, &context).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::GenerateWeekPlan { prompt, reply } => {
+                        let result = self.manager.generate_week_plan(&prompt).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::SwitchProvider { provider, reply } => {
+                        let result = self.manager.switch_provider(&provider).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::ConfigureClaude { config, reply } => {
+                        let result = self.manager.configure_claude(config).await;
+                        let _ = reply.send(result);
+                    }
+
+                    LLMCommand::HealthCheck { reply } => {
+                        // 立即响应，表明Actor正常运行
+                        let _ = reply.send(());
+                    }
                 }
             }
+            .instrument(span)
+            .await;
         }
 
         tracing::info!("LLM Manager Actor 已停止");
@@ -235,15 +310,22 @@ impl LLMManagerActor {
 /// LLM Handle（用于与Actor通信，可克隆）
 #[derive(Clone)]
 pub struct LLMHandle {
-    sender: mpsc::Sender<LLMCommand>,
+    sender: mpsc::Sender<(LLMCommand, Span)>,
 }
 
 impl LLMHandle {
+    /// 发送命令（附带当前 span，Actor 处理命令时的日志归属于调用方的会话）
+    async fn send(
+        &self,
+        cmd: LLMCommand,
+    ) -> std::result::Result<(), mpsc::error::SendError<(LLMCommand, Span)>> {
+        self.sender.send((cmd, Span::current())).await
+    }
+
     /// 配置LLM
     pub async fn configure(&self, config: QwenConfig) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::Configure { config, reply })
+        self.send(LLMCommand::Configure { config, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
@@ -251,8 +333,16 @@ impl LLMHandle {
 
     pub async fn configure_codex(&self, config: CodexConfig) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::ConfigureCodex { config, reply })
+        self.send(LLMCommand::ConfigureCodex { config, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
+    }
+
+    /// 配置外部插件 provider
+    pub async fn configure_plugin(&self, config: serde_json::Value) -> Result<()> {
+        let (reply, rx) = oneshot::channel();
+        self.send(LLMCommand::ConfigurePlugin { config, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
@@ -261,8 +351,7 @@ impl LLMHandle {
     /// 分析帧
     pub async fn analyze_frames(&self, frames: Vec<String>) -> Result<SessionSummary> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::AnalyzeFrames { frames, reply })
+        self.send(LLMCommand::AnalyzeFrames { frames, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
@@ -271,8 +360,7 @@ impl LLMHandle {
     /// 获取配置
     pub async fn get_config(&self) -> Result<LLMConfig> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::GetConfig { reply })
+        self.send(LLMCommand::GetConfig { reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         Ok(rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?)
@@ -281,8 +369,7 @@ impl LLMHandle {
     /// 设置视频路径
     pub async fn set_video_path(&self, video_path: Option<String>) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SetVideoPath { video_path, reply })
+        self.send(LLMCommand::SetVideoPath { video_path, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
@@ -292,11 +379,20 @@ impl LLMHandle {
     /// 设置视频速率
     pub async fn set_video_speed(&self, speed_multiplier: f32) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SetVideoSpeed {
-                speed_multiplier,
-                reply,
-            })
+        self.send(LLMCommand::SetVideoSpeed {
+            speed_multiplier,
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
+        Ok(())
+    }
+
+    /// 设置视频上传配置
+    pub async fn set_video_upload(&self, config: VideoUploadConfig) -> Result<()> {
+        let (reply, rx) = oneshot::channel();
+        self.send(LLMCommand::SetVideoUpload { config, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
@@ -310,8 +406,20 @@ impl LLMHandle {
         end: Option<DateTime<Utc>>,
     ) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SetSessionWindow { start, end, reply })
+        self.send(LLMCommand::SetSessionWindow { start, end, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
+        Ok(())
+    }
+
+    /// 设置用户纠正记录（提示词示例）
+    pub async fn set_correction_examples(
+        &self,
+        corrections: Vec<CardCorrectionRecord>,
+    ) -> Result<()> {
+        let (reply, rx) = oneshot::channel();
+        self.send(LLMCommand::SetCorrectionExamples { corrections, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
@@ -325,14 +433,13 @@ impl LLMHandle {
         session_id: Option<i64>,
     ) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SetProviderDatabase {
-                db,
-                session_id,
-                reply,
-            })
-            .await
-            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        self.send(LLMCommand::SetProviderDatabase {
+            db,
+            session_id,
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?;
         Ok(())
     }
@@ -345,28 +452,26 @@ impl LLMHandle {
         previous_cards: Option<Vec<TimelineCard>>,
     ) -> Result<TimelineAnalysis> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SegmentVideoAndGenerateTimeline {
-                frames,
-                duration,
-                previous_cards,
-                reply,
-            })
-            .await
-            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        self.send(LLMCommand::SegmentVideoAndGenerateTimeline {
+            frames,
+            duration,
+            previous_cards,
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
     }
 
     /// 获取最后一次LLM调用的ID
     pub async fn get_last_call_id(&self, call_type: &str) -> Option<i64> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::GetLastCallId {
-                call_type: call_type.to_string(),
-                reply,
-            })
-            .await
-            .ok()?;
+        self.send(LLMCommand::GetLastCallId {
+            call_type: call_type.to_string(),
+            reply,
+        })
+        .await
+        .ok()?;
         rx.await.ok().flatten()
     }
 
@@ -377,14 +482,13 @@ impl LLMHandle {
         previous_cards: Option<Vec<TimelineCard>>,
     ) -> Result<Vec<TimelineCard>> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::GenerateTimeline {
-                segments,
-                previous_cards,
-                reply,
-            })
-            .await
-            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        self.send(LLMCommand::GenerateTimeline {
+            segments,
+            previous_cards,
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
     }
 
@@ -395,37 +499,65 @@ impl LLMHandle {
         &self,
         date: &str,
         sessions: &[SessionBrief],
+        day_type: DayType,
     ) -> Result<String> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::GenerateDaySummary {
-                date: date.to_string(),
-                sessions: sessions.to_vec(),
-                reply,
-            })
-            .await
-            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        self.send(LLMCommand::GenerateDaySummary {
+            date: date.to_string(),
+            sessions: sessions.to_vec(),
+            day_type,
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
+    }
+
+    /// 生成会议纪要
+    pub async fn generate_meeting_notes(
+        &self,
+        frames: Vec<String>,
+        context: &str,
+    ) -> Result<MeetingNotes> {
+        let (reply, rx) = oneshot::channel();
+        self.send(LLMCommand::GenerateMeetingNotes {
+            frames,
+            context: context.to_string(),
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
+    }
+
+    /// 生成周计划
+    pub async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        let (reply, rx) = oneshot::channel();
+        self.send(LLMCommand::GenerateWeekPlan {
+            prompt: prompt.to_string(),
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
     }
 
     /// 切换 LLM provider
     pub async fn switch_provider(&self, provider: &str) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::SwitchProvider {
-                provider: provider.to_string(),
-                reply,
-            })
-            .await
-            .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
+        self.send(LLMCommand::SwitchProvider {
+            provider: provider.to_string(),
+            reply,
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
     }
 
     /// 配置 Claude provider
     pub async fn configure_claude(&self, config: serde_json::Value) -> Result<()> {
         let (reply, rx) = oneshot::channel();
-        self.sender
-            .send(LLMCommand::ConfigureClaude { config, reply })
+        self.send(LLMCommand::ConfigureClaude { config, reply })
             .await
             .map_err(|_| anyhow::anyhow!("Actor通道已关闭"))?;
         rx.await.map_err(|_| anyhow::anyhow!("Actor已停止"))?
@@ -0,0 +1,133 @@
+// 音频设备活动检测 - 只判断麦克风/扬声器当前是否在使用，不读取任何音频数据
+//
+// macOS 通过 `pmset -g assertions` 中 coreaudiod 为运行中的音频设备创建的防休眠断言判断；
+// Windows 通过隐私设置中麦克风的使用记录（LastUsedTimeStop 为 0 表示正在使用）判断，不支持扬声器；
+// Linux 通过 `pactl` 查询录音流和处于 RUNNING 状态的输出设备。无法判断时返回 None
+
+/// 音频设备状态（None 表示当前平台无法判断）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AudioActivity {
+    pub mic_active: Option<bool>,
+    pub speaker_active: Option<bool>,
+}
+
+/// 检测音频设备是否在使用（阻塞调用，异步代码中请放到 spawn_blocking 中执行）
+pub fn audio_activity() -> AudioActivity {
+    platform::audio_activity()
+}
+
+/// 从 `pmset -g assertions` 的输出中判断音频设备状态
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset_assertions(output: &str) -> AudioActivity {
+    let audio_lines: Vec<String> = output
+        .lines()
+        .map(str::to_lowercase)
+        .filter(|line| line.contains("com.apple.audio") && line.contains("preventuseridle"))
+        .collect();
+    let any = |keywords: &[&str]| {
+        audio_lines
+            .iter()
+            .any(|line| keywords.iter().any(|keyword| line.contains(keyword)))
+    };
+    AudioActivity {
+        mic_active: Some(any(&["microphone", "input"])),
+        speaker_active: Some(any(&["speaker", "headphone", "output"])),
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{parse_pmset_assertions, AudioActivity};
+    use std::process::Command;
+
+    pub fn audio_activity() -> AudioActivity {
+        match Command::new("pmset").args(["-g", "assertions"]).output() {
+            Ok(output) if output.status.success() => {
+                parse_pmset_assertions(&String::from_utf8_lossy(&output.stdout))
+            }
+            _ => AudioActivity::default(),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::AudioActivity;
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    const MICROPHONE_CONSENT_KEY: &str =
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone";
+
+    /// 应用的使用记录中开始时间不为 0 且结束时间为 0，表示正在使用
+    fn in_use(key: &RegKey) -> bool {
+        let start = key.get_value::<u64, _>("LastUsedTimeStart").unwrap_or(0);
+        let stop = key.get_value::<u64, _>("LastUsedTimeStop").unwrap_or(1);
+        start != 0 && stop == 0
+    }
+
+    /// 检查商店应用和桌面应用（NonPackaged 下按可执行文件路径分组）的使用记录
+    fn any_app_in_use(key: &RegKey) -> bool {
+        key.enum_keys().flatten().any(|name| {
+            let Ok(app) = key.open_subkey(&name) else {
+                return false;
+            };
+            if name == "NonPackaged" {
+                any_app_in_use(&app)
+            } else {
+                in_use(&app)
+            }
+        })
+    }
+
+    pub fn audio_activity() -> AudioActivity {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        AudioActivity {
+            mic_active: hkcu
+                .open_subkey(MICROPHONE_CONSENT_KEY)
+                .ok()
+                .map(|key| any_app_in_use(&key)),
+            speaker_active: None,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::AudioActivity;
+    use std::process::Command;
+
+    fn pactl(args: &[&str]) -> Option<String> {
+        let output = Command::new("pactl").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn audio_activity() -> AudioActivity {
+        AudioActivity {
+            mic_active: pactl(&["list", "short", "source-outputs"])
+                .map(|output| output.lines().any(|line| !line.trim().is_empty())),
+            speaker_active: pactl(&["list", "short", "sinks"])
+                .map(|output| output.lines().any(|line| line.contains("RUNNING"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pmset_audio_assertions() {
+        let output = "Listed by owning process:\n   pid 312(coreaudiod): [0x0000a1b2] 00:12:03 PreventUserIdleSleep named: \"com.apple.audio.BuiltInMicrophoneDevice.context.preventuseridlesleep\"\n   pid 88(WindowServer): [0x0000a1b3] 01:00:00 UserIsActive named: \"com.apple.iohideventsystem.queue.tickle\"";
+        assert_eq!(
+            parse_pmset_assertions(output),
+            AudioActivity {
+                mic_active: Some(true),
+                speaker_active: Some(false),
+            }
+        );
+    }
+}
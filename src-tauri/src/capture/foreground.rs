@@ -0,0 +1,167 @@
+// 前台应用检测 - 获取当前位于前台的应用和窗口标题，并支持把它最小化（供专注模式拦截干扰应用）
+//
+// macOS 通过 AppleScript（System Events）查询和隐藏应用，需要授予辅助功能权限；
+// Windows 通过 Win32 API 获取前台窗口所属进程并最小化窗口；其他平台不支持
+
+/// 前台应用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundApp {
+    /// 应用名称（macOS 为进程显示名称，Windows 为可执行文件名）
+    pub name: String,
+    /// Windows 下的前台窗口句柄，用于最小化
+    #[cfg(target_os = "windows")]
+    window: isize,
+}
+
+/// 获取当前前台应用（阻塞调用，异步代码中请放到 spawn_blocking 中执行）
+pub fn foreground_app() -> Option<ForegroundApp> {
+    platform::foreground_app()
+}
+
+/// 获取当前前台窗口的标题（阻塞调用，异步代码中请放到 spawn_blocking 中执行）
+pub fn foreground_window_title() -> Option<String> {
+    platform::foreground_window_title()
+}
+
+/// 最小化（macOS 为隐藏）应用，返回是否成功
+pub fn minimize_app(app: &ForegroundApp) -> bool {
+    platform::minimize_app(app)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::ForegroundApp;
+    use std::process::Command;
+    use tracing::debug;
+
+    fn run_osascript(script: &str) -> Option<String> {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            debug!(
+                "osascript 执行失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    pub fn foreground_app() -> Option<ForegroundApp> {
+        let name = run_osascript(
+            "tell application \"System Events\" to get name of first application process whose frontmost is true",
+        )?;
+        (!name.is_empty()).then_some(ForegroundApp { name })
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        let title = run_osascript(
+            "tell application \"System Events\" to get name of front window of first application process whose frontmost is true",
+        )?;
+        (!title.is_empty()).then_some(title)
+    }
+
+    pub fn minimize_app(app: &ForegroundApp) -> bool {
+        let name = app.name.replace('\\', "\\\\").replace('"', "\\\"");
+        run_osascript(&format!(
+            "tell application \"System Events\" to set visible of application process \"{}\" to false",
+            name
+        ))
+        .is_some()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::ForegroundApp;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, ShowWindow, SW_MINIMIZE,
+    };
+
+    pub fn foreground_app() -> Option<ForegroundApp> {
+        // SAFETY: 以下均为只读查询，句柄在使用后关闭
+        unsafe {
+            let window = GetForegroundWindow();
+            if window == 0 {
+                return None;
+            }
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(window, &mut pid);
+            if pid == 0 {
+                return None;
+            }
+
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process == 0 {
+                return None;
+            }
+            let mut buffer = [0u16; 1024];
+            let mut length = buffer.len() as u32;
+            let ok = QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                buffer.as_mut_ptr(),
+                &mut length,
+            );
+            CloseHandle(process);
+            if ok == 0 {
+                return None;
+            }
+
+            let path = String::from_utf16_lossy(&buffer[..length as usize]);
+            let name = Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())?;
+            Some(ForegroundApp { name, window })
+        }
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        // SAFETY: 只读查询，缓冲区长度与传入的长度一致
+        unsafe {
+            let window = GetForegroundWindow();
+            if window == 0 {
+                return None;
+            }
+            let mut buffer = [0u16; 512];
+            let length = GetWindowTextW(window, buffer.as_mut_ptr(), buffer.len() as i32);
+            if length <= 0 {
+                return None;
+            }
+            Some(String::from_utf16_lossy(&buffer[..length as usize]))
+        }
+    }
+
+    pub fn minimize_app(app: &ForegroundApp) -> bool {
+        // SAFETY: 窗口句柄来自 GetForegroundWindow，窗口已关闭时调用会直接失败
+        // （ShowWindow 的返回值表示窗口之前是否可见，不代表是否成功）
+        unsafe { ShowWindow(app.window, SW_MINIMIZE) };
+        true
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::ForegroundApp;
+
+    pub fn foreground_app() -> Option<ForegroundApp> {
+        None
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        None
+    }
+
+    pub fn minimize_app(_app: &ForegroundApp) -> bool {
+        false
+    }
+}
@@ -0,0 +1,92 @@
+// 输入空闲检测 - 距离最近一次键盘/鼠标输入的时长
+//
+// 截屏暂停时没有画面变化可用来判断是否在使用电脑，改为查询系统的输入空闲时长：
+// macOS 读取 ioreg 中 IOHIDSystem 的 HIDIdleTime（纳秒）；
+// Windows 通过 GetLastInputInfo；Linux 调用 xprintidle（毫秒，未安装时不支持）
+
+/// 距离最近一次输入的秒数，平台不支持或查询失败时返回 None
+/// （阻塞调用，异步代码中请放到 spawn_blocking 中执行）
+pub fn idle_seconds() -> Option<u64> {
+    platform::idle_seconds()
+}
+
+/// 从 ioreg 输出中解析 HIDIdleTime（纳秒）并换算为秒
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_hid_idle_time(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))
+        .and_then(|line| line.split('=').nth(1))
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|nanos| nanos / 1_000_000_000)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    pub fn idle_seconds() -> Option<u64> {
+        let output = Command::new("ioreg")
+            .args(["-c", "IOHIDSystem", "-d", "4"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        super::parse_hid_idle_time(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    pub fn idle_seconds() -> Option<u64> {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        // SAFETY: 只读查询，结构体大小已正确设置
+        unsafe {
+            if GetLastInputInfo(&mut info) == 0 {
+                return None;
+            }
+            // 两者都是开机以来的毫秒数（约 49 天回绕一次），用 wrapping_sub 处理回绕
+            Some(GetTickCount().wrapping_sub(info.dwTime) as u64 / 1000)
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::process::Command;
+
+    pub fn idle_seconds() -> Option<u64> {
+        let output = Command::new("xprintidle").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(|millis| millis / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hid_idle_time() {
+        let output = r#"
+    | |   "HIDIdleTimeDelta" = 1
+    | |   "HIDIdleTime" = 125000000000
+    | |   "HIDParameters" = {}
+"#;
+        assert_eq!(parse_hid_idle_time(output), Some(125));
+        assert_eq!(parse_hid_idle_time("\"HIDParameters\" = {}"), None);
+    }
+}
@@ -1,14 +1,15 @@
 // 截屏模块 - 负责定时捕获屏幕截图
 
-use crate::models::CaptureSettings;
+use crate::domains::telemetry::{self, Metric};
+use crate::models::{BlackScreenMode, CaptureSettings};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use image::imageops;
 use image::DynamicImage;
-use screenshots::display_info::DisplayInfo;
-use screenshots::Screen;
+use rayon::prelude::*;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 use tracing::{info, trace, warn};
 
@@ -18,7 +19,65 @@ use std::process::Command;
 #[cfg(not(target_os = "macos"))]
 use tracing::debug;
 
+pub mod audio;
+pub mod foreground;
+pub mod input_idle;
 pub mod scheduler;
+pub mod source;
+pub mod writer;
+
+use source::{DisplayBounds, FrameSource, ScreenshotSource};
+use writer::FrameWriter;
+
+/// 遮盖本应用窗口时填充的颜色
+pub(crate) const OWN_WINDOW_MASK: image::Rgba<u8> = image::Rgba([32, 32, 32, 255]);
+
+/// 屏幕坐标系中的矩形区域（逻辑坐标，与 DisplayBounds 一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 查询本应用窗口的位置（窗口隐藏或最小化时返回 None）
+///
+/// 在截屏线程池中调用，实现可以阻塞
+pub type WindowLocator = Arc<dyn Fn() -> Option<WindowRect> + Send + Sync>;
+
+/// 遮盖屏幕截图中与窗口重叠的部分，返回是否有重叠
+///
+/// `screen` 为该屏幕的逻辑坐标区域，截图像素按截图与屏幕的尺寸比例换算
+fn mask_window(image: &mut image::RgbaImage, screen: WindowRect, window: WindowRect) -> bool {
+    let left = window.x.max(screen.x);
+    let top = window.y.max(screen.y);
+    let right = (window.x + window.width as i32).min(screen.x + screen.width as i32);
+    let bottom = (window.y + window.height as i32).min(screen.y + screen.height as i32);
+    if right <= left || bottom <= top || screen.width == 0 || screen.height == 0 {
+        return false;
+    }
+
+    let scale_x = image.width() as f64 / screen.width as f64;
+    let scale_y = image.height() as f64 / screen.height as f64;
+    let to_px = |value: i32, origin: i32, scale: f64, max: u32| {
+        (((value - origin) as f64 * scale).round().max(0.0) as u32).min(max)
+    };
+    let (x0, x1) = (
+        to_px(left, screen.x, scale_x, image.width()),
+        to_px(right, screen.x, scale_x, image.width()),
+    );
+    let (y0, y1) = (
+        to_px(top, screen.y, scale_y, image.height()),
+        to_px(bottom, screen.y, scale_y, image.height()),
+    );
+    for y in y0..y1 {
+        for x in x0..x1 {
+            image.put_pixel(x, y, OWN_WINDOW_MASK);
+        }
+    }
+    true
+}
 
 /// 截屏帧数据结构
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -31,48 +90,434 @@ pub struct ScreenFrame {
     pub screen_id: usize,
 }
 
+/// 自身窗口检查用的测试截图
+pub struct OwnWindowProbe {
+    /// 本应用窗口的位置（窗口隐藏或最小化时为 None）
+    pub window: Option<WindowRect>,
+    /// 各屏幕的逻辑坐标区域及其在合成截图中的位置
+    pub screens: Vec<(WindowRect, crate::video::ScreenRegion)>,
+    /// 按当前配置截取的画面（与实际保存的截图一致）
+    pub captured: DynamicImage,
+    /// 不遮盖本应用窗口的对照画面
+    pub unmasked: DynamicImage,
+    /// 是否开启了遮盖本应用窗口
+    pub exclude_enabled: bool,
+}
+
+/// 截屏预览帧（用于设置页实时预览）
+pub struct CapturePreview {
+    /// 截取时间
+    pub timestamp: DateTime<Utc>,
+    /// 实际保存截图的宽度
+    pub width: u32,
+    /// 实际保存截图的高度
+    pub height: u32,
+    /// 缩小后的 JPEG 数据
+    pub jpeg: Vec<u8>,
+}
+
+/// 截屏/编码线程池的最大线程数
+const MAX_CAPTURE_THREADS: usize = 4;
+/// 黑屏占位帧的 JPEG 质量（纯黑画面在最低质量下也没有失真，文件只有几 KB）
+const PLACEHOLDER_QUALITY: u8 = 10;
+
+/// 截屏耗时统计（平均值为指数移动平均）
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CaptureLatencyStats {
+    /// 屏幕数量
+    pub screen_count: usize,
+    /// 已保存的帧数
+    pub frames: u64,
+    /// 写盘队列已满丢弃的帧数
+    pub dropped_frames: u64,
+    /// 最近一次截取+合成耗时（毫秒）
+    pub last_capture_ms: u64,
+    /// 平均截取+合成耗时（毫秒）
+    pub avg_capture_ms: f64,
+    /// 最近一次 JPEG 编码耗时（毫秒）
+    pub last_encode_ms: u64,
+    /// 平均 JPEG 编码耗时（毫秒）
+    pub avg_encode_ms: f64,
+    /// 单帧总耗时的最大值（毫秒）
+    pub max_total_ms: u64,
+}
+
+impl CaptureLatencyStats {
+    fn record(&mut self, capture_ms: u64, encode_ms: u64) {
+        const ALPHA: f64 = 0.1;
+        let ema = |avg: f64, value: u64| {
+            if avg == 0.0 {
+                value as f64
+            } else {
+                avg + (value as f64 - avg) * ALPHA
+            }
+        };
+        self.frames += 1;
+        self.last_capture_ms = capture_ms;
+        self.avg_capture_ms = ema(self.avg_capture_ms, capture_ms);
+        self.last_encode_ms = encode_ms;
+        self.avg_encode_ms = ema(self.avg_encode_ms, encode_ms);
+        self.max_total_ms = self.max_total_ms.max(capture_ms + encode_ms);
+    }
+}
+
+/// 截屏基准测试结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptureBenchmark {
+    pub rounds: usize,
+    /// 输出分辨率
+    pub width: u32,
+    pub height: u32,
+    /// 平均截取+合成耗时（毫秒）
+    pub avg_capture_ms: f64,
+    /// 最大截取+合成耗时（毫秒）
+    pub max_capture_ms: u64,
+    /// 平均 JPEG 编码耗时（毫秒）
+    pub avg_encode_ms: f64,
+    /// 平均每帧 JPEG 大小
+    pub avg_jpeg_bytes: u64,
+}
+
 /// 截屏管理器
 pub struct ScreenCapture {
-    /// 可用屏幕列表
-    screens: Vec<Screen>,
+    /// 截图来源（真实屏幕或测试用的合成图像）
+    source: Arc<dyn FrameSource>,
     /// 输出目录
     output_dir: PathBuf,
     /// 当前会话的帧数据
     current_session: Arc<Mutex<Vec<ScreenFrame>>>,
     /// 截屏配置
     capture_settings: Arc<Mutex<CaptureSettings>>,
+    /// 屏幕活动跟踪（画面指纹变化即视为有活动）
+    activity: Arc<std::sync::Mutex<ScreenActivity>>,
+    /// 多屏截取和 JPEG 编码使用的线程池
+    pool: Arc<rayon::ThreadPool>,
+    /// 截屏耗时统计
+    latency: Arc<std::sync::Mutex<CaptureLatencyStats>>,
+    /// 截图写盘队列
+    writer: Arc<FrameWriter>,
+    /// 上一张正常截图的 JPEG 数据（黑屏时重复上一帧使用）
+    last_frame: Arc<std::sync::Mutex<Option<Arc<Vec<u8>>>>>,
+    /// 本应用窗口的位置查询（用于从截图中遮盖自身窗口）
+    own_window: Arc<std::sync::RwLock<Option<WindowLocator>>>,
+    /// 标记为私人屏幕的显示器 ID（与截屏配置同步，供同步调用的 screen_regions 使用）
+    personal_display: Arc<std::sync::RwLock<Option<u32>>>,
+    /// 本轮连续黑屏的开始时间
+    blank_since: Arc<std::sync::Mutex<Option<DateTime<Utc>>>>,
+}
+
+/// 屏幕活动跟踪状态
+#[derive(Default)]
+struct ScreenActivity {
+    /// 上一帧的画面指纹
+    last_fingerprint: Option<u64>,
+    /// 最近一次画面发生变化的时间
+    last_change_at: Option<DateTime<Utc>>,
+}
+
+/// 计算画面指纹（8x8 均值哈希），用于判断画面是否变化
+pub(crate) fn image_fingerprint(img: &DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(8, 8, imageops::FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<u8> = small.pixels().map(|p| p[0]).collect();
+    let avg = pixels.iter().map(|&p| p as u32).sum::<u32>() / pixels.len().max(1) as u32;
+
+    pixels.iter().enumerate().fold(0u64, |hash, (i, &p)| {
+        if p as u32 > avg {
+            hash | (1 << i)
+        } else {
+            hash
+        }
+    })
+}
+
+/// 画面的“媒体程度”（0-1，越高越接近视频/图片，越低越接近文字/界面）
+///
+/// 在缩小后的灰度图上统计亮度直方图熵和边缘强度：文字/界面画面颜色少、边缘锐利；
+/// 视频、图片等纹理丰富的画面熵高、以柔和过渡为主
+fn media_score(img: &DynamicImage) -> f64 {
+    let luma = img
+        .resize(320, 320, imageops::FilterType::Triangle)
+        .to_luma8();
+    let (width, height) = luma.dimensions();
+    if width < 2 || height < 2 {
+        return 0.0;
+    }
+
+    // 亮度直方图熵（0-8 bit）
+    let mut histogram = [0u32; 256];
+    for pixel in luma.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    let total = (width * height) as f64;
+    let entropy: f64 = histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+
+    // 边缘统计：锐利边缘（文字）占所有边缘的比例
+    let (mut edges, mut sharp_edges) = (0u32, 0u32);
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let value = luma.get_pixel(x, y)[0] as i32;
+            let gradient = (value - luma.get_pixel(x + 1, y)[0] as i32).abs()
+                + (value - luma.get_pixel(x, y + 1)[0] as i32).abs();
+            if gradient > 16 {
+                edges += 1;
+                if gradient > 96 {
+                    sharp_edges += 1;
+                }
+            }
+        }
+    }
+    let sharp_ratio = if edges == 0 {
+        0.0
+    } else {
+        sharp_edges as f64 / edges as f64
+    };
+
+    // 熵低于 4 bit 视为界面画面，高于 7 bit 视为完全的自然图像
+    let complexity = ((entropy - 4.0) / 3.0).clamp(0.0, 1.0);
+    complexity * (1.0 - sharp_ratio)
+}
+
+/// 按画面内容在 [min, max] 区间内选择 JPEG 质量
+///
+/// 文字/界面画面降低质量会让文字糊掉，保持较高质量（平坦区域本身压缩率就很高）；
+/// 视频、图片等画面压缩伪影不明显，可以降低质量
+fn adaptive_quality(img: &DynamicImage, min: u8, max: u8) -> u8 {
+    let (min, max) = (min.min(max), max.max(min));
+    let reduction = media_score(img);
+    (max as f64 - (max - min) as f64 * reduction).round() as u8
+}
+
+/// 自适应分辨率时，媒体程度不低于该值的画面按视频/图片处理
+const MEDIA_SCORE_THRESHOLD: f64 = 0.5;
+
+/// 选择本帧的目标分辨率（None 表示保持原始分辨率）
+///
+/// 开启自适应分辨率时，代码、文档等文字多的画面保持配置的分辨率，便于之后 OCR 和 LLM 阅读；
+/// 视频、图片等画面降到 media_resolution，节省编码和存储
+fn frame_dimensions(img: &DynamicImage, settings: &CaptureSettings) -> Option<(u32, u32)> {
+    let dimensions = settings.resolution.dimensions();
+    if !settings.adaptive_resolution {
+        return dimensions;
+    }
+    let Some(media) = settings.media_resolution.dimensions() else {
+        return dimensions;
+    };
+    // 媒体分辨率不低于文字画面的分辨率时无需判断内容
+    let (width, height) = dimensions.unwrap_or((img.width(), img.height()));
+    if media.0 as u64 * media.1 as u64 >= width as u64 * height as u64 {
+        return dimensions;
+    }
+    let score = media_score(img);
+    trace!("画面媒体程度: {:.2}", score);
+    if score >= MEDIA_SCORE_THRESHOLD {
+        Some(media)
+    } else {
+        dimensions
+    }
+}
+
+/// 曝光校正后的目标平均亮度
+const EXPOSURE_TARGET_BRIGHTNESS: f64 = 110.0;
+
+/// 计算偏暗画面的亮度映射表，平均亮度不低于 threshold 时返回 None
+///
+/// 先按 1%/99% 分位数拉伸直方图，再用 gamma 校正把平均亮度提到目标值附近；
+/// gamma 不低于 0.5，避免把噪点和压缩块放大得过于明显
+fn exposure_lut(img: &DynamicImage, threshold: u8) -> Option<[u8; 256]> {
+    let luma = img
+        .resize(320, 320, imageops::FilterType::Triangle)
+        .to_luma8();
+    let total = luma.pixels().len();
+    if total == 0 {
+        return None;
+    }
+
+    let mut histogram = [0usize; 256];
+    let mut sum = 0usize;
+    for pixel in luma.pixels() {
+        histogram[pixel[0] as usize] += 1;
+        sum += pixel[0] as usize;
+    }
+    if sum / total >= threshold as usize {
+        return None;
+    }
+
+    let percentile = |fraction: f64| {
+        let target = (total as f64 * fraction) as usize;
+        let mut count = 0;
+        histogram
+            .iter()
+            .position(|&n| {
+                count += n;
+                count > target
+            })
+            .unwrap_or(255) as f64
+    };
+    let low = percentile(0.01);
+    let high = percentile(0.99).max(low + 1.0);
+    let stretch = |value: f64| ((value - low) / (high - low)).clamp(0.0, 1.0);
+
+    let stretched_mean = histogram
+        .iter()
+        .enumerate()
+        .map(|(value, &n)| stretch(value as f64) * n as f64)
+        .sum::<f64>()
+        / total as f64;
+    let gamma = if stretched_mean > 0.0 && stretched_mean < 1.0 {
+        ((EXPOSURE_TARGET_BRIGHTNESS / 255.0).ln() / stretched_mean.ln()).clamp(0.5, 1.0)
+    } else {
+        1.0
+    };
+
+    let mut lut = [0u8; 256];
+    for (value, mapped) in lut.iter_mut().enumerate() {
+        *mapped = (stretch(value as f64).powf(gamma) * 255.0).round() as u8;
+    }
+    Some(lut)
+}
+
+/// 偏暗画面（夜间模式、自动降低亮度）做曝光校正，提高文字识别效果
+fn normalize_exposure(img: DynamicImage, threshold: u8) -> DynamicImage {
+    let Some(lut) = exposure_lut(&img, threshold) else {
+        return img;
+    };
+
+    let mut rgb = img.to_rgb8();
+    for pixel in rgb.pixels_mut() {
+        for channel in pixel.0.iter_mut() {
+            *channel = lut[*channel as usize];
+        }
+    }
+    trace!("已校正偏暗画面的曝光");
+    DynamicImage::ImageRgb8(rgb)
+}
+
+/// 编码与截图尺寸相同的纯黑占位帧，保证合成视频时各帧尺寸一致
+fn encode_placeholder(width: u32, height: u32) -> Result<Vec<u8>> {
+    use image::codecs::jpeg::JpegEncoder;
+
+    let image = DynamicImage::new_rgb8(width, height);
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, PLACEHOLDER_QUALITY).encode(
+        image.as_bytes(),
+        width,
+        height,
+        image.color(),
+    )?;
+    Ok(data)
+}
+
+/// 按配置的质量（或自适应质量）编码 JPEG
+fn encode_jpeg(image: &DynamicImage, settings: &CaptureSettings) -> Result<Vec<u8>> {
+    use image::codecs::jpeg::JpegEncoder;
+
+    let quality = if settings.adaptive_quality {
+        let quality = adaptive_quality(image, settings.min_image_quality, settings.image_quality);
+        trace!("自适应图片质量: {}", quality);
+        quality
+    } else {
+        settings.image_quality
+    };
+    // 存储接近配额且开启了截图降级时降低质量
+    let quality = crate::domains::storage_quota::apply_to_jpeg(quality);
+
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, quality).encode(
+        image.as_bytes(),
+        image.width(),
+        image.height(),
+        image.color(),
+    )?;
+    Ok(data)
 }
 
 impl ScreenCapture {
     /// 创建新的截屏管理器
     pub fn new(output_dir: PathBuf) -> Result<Self> {
+        Self::with_source(output_dir, Arc::new(ScreenshotSource::default()))
+    }
+
+    /// 使用指定截图来源创建截屏管理器（测试中传入 SyntheticSource）
+    pub fn with_source(output_dir: PathBuf, source: Arc<dyn FrameSource>) -> Result<Self> {
         // 确保输出目录存在
         if !output_dir.exists() {
             std::fs::create_dir_all(&output_dir)?;
         }
 
-        let screens = Screen::all()?;
-        info!("检测到 {} 个屏幕", screens.len());
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_CAPTURE_THREADS);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("screen-capture-{}", index))
+            .build()?;
 
-        // 打印每个屏幕的详细信息
-        for (index, screen) in screens.iter().enumerate() {
-            let display_info = screen.display_info;
-            info!(
-                "屏幕 #{}: {}x{} @ ({}, {})",
-                index, display_info.width, display_info.height, display_info.x, display_info.y
-            );
-        }
+        let current_session = Arc::new(Mutex::new(Vec::new()));
+        let writer = FrameWriter::spawn(writer::DEFAULT_QUEUE_CAPACITY, current_session.clone());
 
         Ok(Self {
-            screens,
+            source,
             output_dir,
-            current_session: Arc::new(Mutex::new(Vec::new())),
+            current_session,
             capture_settings: Arc::new(Mutex::new(CaptureSettings::default())),
+            activity: Arc::new(std::sync::Mutex::new(ScreenActivity::default())),
+            pool: Arc::new(pool),
+            latency: Arc::new(std::sync::Mutex::new(CaptureLatencyStats::default())),
+            writer,
+            last_frame: Arc::new(std::sync::Mutex::new(None)),
+            own_window: Arc::new(std::sync::RwLock::new(None)),
+            personal_display: Arc::new(std::sync::RwLock::new(None)),
+            blank_since: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
+    /// 可用屏幕列表，首次调用时枚举屏幕（枚举失败或没有屏幕时下次调用重试）
+    ///
+    /// 枚举可能较慢，异步上下文中应放到阻塞线程执行
+    pub fn screens(&self) -> Vec<DisplayBounds> {
+        let displays = self.source.displays();
+        if let Ok(mut stats) = self.latency.lock() {
+            stats.screen_count = displays.len();
+        }
+        displays
+    }
+
+    /// 实际截取的屏幕（排除标记为私人屏幕的显示器），返回（截图来源中的序号, 屏幕）
+    fn captured_screens(&self, personal_display_id: Option<u32>) -> Vec<(usize, DisplayBounds)> {
+        self.screens()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, info)| Some(info.id) != personal_display_id)
+            .collect()
+    }
+
+    /// 当前截屏配置
+    pub async fn settings(&self) -> CaptureSettings {
+        self.capture_settings.lock().await.clone()
+    }
+
+    /// 设置本应用窗口的位置查询，截图时据此遮盖自身窗口
+    pub fn set_own_window_locator(&self, locator: WindowLocator) {
+        if let Ok(mut current) = self.own_window.write() {
+            *current = Some(locator);
+        }
+    }
+
     /// 更新截屏配置
     pub async fn update_settings(&self, settings: CaptureSettings) {
+        if let Ok(mut personal) = self.personal_display.write() {
+            *personal = settings.personal_display_id;
+        }
         let mut current = self.capture_settings.lock().await;
         *current = settings;
         info!("截屏配置已更新: {:?}", *current);
@@ -213,74 +658,128 @@ impl ScreenCapture {
         is_black
     }
 
+    /// 记录画面指纹，画面发生明显变化时更新最近活动时间
+    fn record_activity(&self, fingerprint: u64, timestamp: DateTime<Utc>) {
+        if let Ok(mut activity) = self.activity.lock() {
+            let changed = activity
+                .last_fingerprint
+                .map_or(true, |last| (last ^ fingerprint).count_ones() > 2);
+            if changed {
+                activity.last_change_at = Some(timestamp);
+            }
+            activity.last_fingerprint = Some(fingerprint);
+        }
+    }
+
+    /// 获取最近一次画面变化的时间（用于空闲检测）
+    pub fn last_activity_at(&self) -> Option<DateTime<Utc>> {
+        self.activity
+            .lock()
+            .ok()
+            .and_then(|activity| activity.last_change_at)
+    }
+
+    /// 截图写盘前被丢弃的帧数（写盘队列已满）
+    pub fn dropped_frames(&self) -> u64 {
+        self.writer.dropped_frames()
+    }
+
+    /// 获取截屏耗时统计
+    pub fn latency_stats(&self) -> CaptureLatencyStats {
+        let mut stats = self
+            .latency
+            .lock()
+            .map(|stats| stats.clone())
+            .unwrap_or_default();
+        stats.dropped_frames = self.dropped_frames();
+        stats
+    }
+
     /// 捕获单个帧
     pub async fn capture_frame(&self) -> Result<ScreenFrame> {
         let timestamp = crate::storage::local_now();
+        let settings = self.capture_settings.lock().await.clone();
 
-        if self.screens.is_empty() {
-            return Err(anyhow::anyhow!("未找到可用屏幕"));
+        let capture_started = Instant::now();
+        let resized = self.grab_image(&settings).await?;
+        let capture_ms = capture_started.elapsed().as_millis() as u64;
+
+        // 检测是否为黑屏：跳过，或写入占位帧/重复上一帧以保持视频时间连续
+        let black = self.is_black_screen(&resized).await;
+        // 显示器长时间休眠时不再写入占位帧/重复帧，截图中断后调度器会提前结束当前会话
+        if let Ok(mut blank_since) = self.blank_since.lock() {
+            if !black {
+                *blank_since = None;
+            } else if settings.blank_session_minutes > 0
+                && timestamp - *blank_since.get_or_insert(timestamp)
+                    >= chrono::Duration::minutes(settings.blank_session_minutes as i64)
+            {
+                return Err(anyhow::anyhow!(
+                    "黑屏持续超过 {} 分钟，暂停写入截图",
+                    settings.blank_session_minutes
+                ));
+            }
         }
-
-        // 所有平台统一使用 screenshots crate 进行多屏幕截图
-        let combined = {
-            let mut captures = Vec::new();
-
-            for (index, screen) in self.screens.iter().enumerate() {
-                match screen.capture() {
-                    Ok(image) => {
-                        let info = screen.display_info;
-                        captures.push((info, DynamicImage::ImageRgba8(image)));
-                        trace!("截取屏幕 #{} 成功", index);
-                    }
-                    Err(err) => {
-                        warn!("截取屏幕 #{} 失败: {}", index, err);
-                    }
+        let mut repeated = None;
+        if black {
+            match settings.black_screen_mode {
+                BlackScreenMode::Skip => {
+                    info!("检测到黑屏，跳过保存");
+                    return Err(anyhow::anyhow!("黑屏图像，已跳过"));
                 }
+                BlackScreenMode::RepeatLast => {
+                    // 还没有正常截图时退回占位帧
+                    repeated = self.last_frame.lock().ok().and_then(|last| last.clone());
+                }
+                BlackScreenMode::Placeholder => {}
             }
-
-            if captures.is_empty() {
-                return Err(anyhow::anyhow!("未能获取到任何屏幕截图"));
-            }
-
-            self.combine_screens(captures)?
-        };
-
-        // 根据配置调整分辨率
-        let settings = self.capture_settings.lock().await.clone();
-        let resized = if let Some((width, height)) = settings.resolution.dimensions() {
-            self.resize_image(combined, width, height)?
         } else {
-            // 原始分辨率，不调整
-            combined
-        };
-
-        // 检测是否为黑屏
-        if self.is_black_screen(&resized).await {
-            info!("检测到黑屏，跳过保存");
-            return Err(anyhow::anyhow!("黑屏图像，已跳过"));
+            // 更新屏幕活动状态（指纹差异超过阈值视为画面变化），黑屏不计入活动
+            self.record_activity(image_fingerprint(&resized), timestamp);
         }
 
         // 生成文件名
         let file_name = format!("{}.jpg", timestamp.timestamp_millis());
         let file_path = self.output_dir.join(&file_name);
 
-        // 保存为JPEG格式，使用配置的质量
-        // 使用 JpegEncoder 来指定质量参数
-        use image::codecs::jpeg::JpegEncoder;
-        use std::fs::File;
-        use std::io::BufWriter;
-
-        let output_file =
-            File::create(&file_path).map_err(|e| anyhow::anyhow!("创建文件失败: {}", e))?;
-        let writer = BufWriter::new(output_file);
-        let mut encoder = JpegEncoder::new_with_quality(writer, settings.image_quality);
-
-        encoder.encode(
-            resized.as_bytes(),
-            resized.width(),
-            resized.height(),
-            resized.color(),
-        )?;
+        // 在线程池中编码为JPEG格式，使用配置的质量
+        let encode_started = Instant::now();
+        let data = match repeated {
+            Some(last) => last.as_ref().clone(),
+            None => {
+                let keep_last = !black && settings.black_screen_mode == BlackScreenMode::RepeatLast;
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                self.pool.spawn(move || {
+                    let data = if black {
+                        encode_placeholder(resized.width(), resized.height())
+                    } else if settings.normalize_exposure {
+                        encode_jpeg(
+                            &normalize_exposure(resized, settings.exposure_threshold),
+                            &settings,
+                        )
+                    } else {
+                        encode_jpeg(&resized, &settings)
+                    };
+                    let _ = tx.send(data);
+                });
+                let data = rx
+                    .await
+                    .map_err(|_| anyhow::anyhow!("编码任务意外中断"))??;
+                if keep_last {
+                    if let Ok(mut last) = self.last_frame.lock() {
+                        *last = Some(Arc::new(data.clone()));
+                    }
+                }
+                data
+            }
+        };
+        let encode_ms = encode_started.elapsed().as_millis() as u64;
+
+        if let Ok(mut stats) = self.latency.lock() {
+            stats.record(capture_ms, encode_ms);
+        }
+        telemetry::inc(Metric::FramesCaptured, &[]);
+        telemetry::observe(Metric::FrameEncodeSeconds, &[], encode_ms as f64 / 1000.0);
 
         let file_path_str = file_path.to_string_lossy().to_string().replace('\\', "/");
 
@@ -290,14 +789,278 @@ impl ScreenCapture {
             screen_id: 0,
         };
 
-        // 添加到当前会话
-        self.current_session.lock().await.push(frame.clone());
+        // 交给写盘队列，写入成功后加入当前会话
+        self.writer.enqueue(frame.clone(), data);
 
-        trace!("截屏保存成功: {}", frame.file_path);
+        trace!(
+            "截屏完成: {}（截取 {}ms，编码 {}ms）",
+            frame.file_path,
+            capture_ms,
+            encode_ms
+        );
         Ok(frame)
     }
 
-    fn combine_screens(&self, captures: Vec<(DisplayInfo, DynamicImage)>) -> Result<DynamicImage> {
+    /// 基准测试：按当前配置截取并编码若干帧，不保存文件、不计入统计
+    ///
+    /// 同时返回最后一帧的 JPEG 数据，供视频编码测试使用
+    pub async fn benchmark(&self, rounds: usize) -> Result<(CaptureBenchmark, Vec<u8>)> {
+        let settings = self.capture_settings.lock().await.clone();
+        let rounds = rounds.max(1);
+        let mut capture_total = 0u64;
+        let mut max_capture_ms = 0u64;
+        let mut encode_total = 0u64;
+        let mut jpeg_total = 0u64;
+        let mut size = (0, 0);
+        let mut sample = Vec::new();
+
+        for _ in 0..rounds {
+            let capture_started = Instant::now();
+            let image = self.grab_image(&settings).await?;
+            let capture_ms = capture_started.elapsed().as_millis() as u64;
+            size = (image.width(), image.height());
+
+            let encode_started = Instant::now();
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let encode_settings = settings.clone();
+            self.pool.spawn(move || {
+                let _ = tx.send(encode_jpeg(&image, &encode_settings));
+            });
+            let data = rx
+                .await
+                .map_err(|_| anyhow::anyhow!("编码任务意外中断"))??;
+            let encode_ms = encode_started.elapsed().as_millis() as u64;
+
+            capture_total += capture_ms;
+            max_capture_ms = max_capture_ms.max(capture_ms);
+            encode_total += encode_ms;
+            jpeg_total += data.len() as u64;
+            sample = data;
+        }
+
+        Ok((
+            CaptureBenchmark {
+                rounds,
+                width: size.0,
+                height: size.1,
+                avg_capture_ms: capture_total as f64 / rounds as f64,
+                max_capture_ms,
+                avg_encode_ms: encode_total as f64 / rounds as f64,
+                avg_jpeg_bytes: jpeg_total / rounds as u64,
+            },
+            sample,
+        ))
+    }
+
+    /// 截取一帧低分辨率预览（JPEG），不保存文件、不计入会话和活动检测
+    ///
+    /// 传入 settings 时按该配置预览（设置页尚未保存的配置），否则使用当前配置
+    pub async fn capture_preview(
+        &self,
+        settings: Option<&CaptureSettings>,
+        max_width: u32,
+    ) -> Result<CapturePreview> {
+        let settings = match settings {
+            Some(settings) => settings.clone(),
+            None => self.capture_settings.lock().await.clone(),
+        };
+        let mut image = self.grab_image(&settings).await?;
+        if settings.normalize_exposure {
+            image = normalize_exposure(image, settings.exposure_threshold);
+        }
+        let (width, height) = (image.width(), image.height());
+        let preview = if width > max_width {
+            image.resize(max_width, u32::MAX, imageops::FilterType::Triangle)
+        } else {
+            image
+        };
+
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 60).encode(
+            preview.to_rgb8().as_raw(),
+            preview.width(),
+            preview.height(),
+            image::ColorType::Rgb8,
+        )?;
+
+        Ok(CapturePreview {
+            timestamp: crate::storage::local_now(),
+            width,
+            height,
+            jpeg,
+        })
+    }
+
+    /// 自身窗口检查：按当前配置截取一帧，再截取一帧不遮盖本应用窗口的画面作为对照（均不保存）
+    pub async fn probe_own_window(&self) -> Result<OwnWindowProbe> {
+        let settings = self.capture_settings.lock().await.clone();
+        let locator = self
+            .own_window
+            .read()
+            .ok()
+            .and_then(|locator| locator.clone());
+        let window = match locator {
+            Some(locate) => tokio::task::spawn_blocking(move || locate()).await?,
+            None => None,
+        };
+
+        let captured = self.grab_image(&settings).await?;
+        let unmasked = self
+            .grab_image(&CaptureSettings {
+                exclude_own_window: false,
+                ..settings.clone()
+            })
+            .await?;
+        let screens = self
+            .captured_screens(settings.personal_display_id)
+            .iter()
+            .map(|(_, info)| WindowRect {
+                x: info.x,
+                y: info.y,
+                width: info.width,
+                height: info.height,
+            })
+            .zip(self.screen_regions())
+            .collect();
+
+        Ok(OwnWindowProbe {
+            window,
+            screens,
+            captured,
+            unmasked,
+            exclude_enabled: settings.exclude_own_window,
+        })
+    }
+
+    /// 各屏幕在合成截图中的位置（按屏幕像素排列，与 combine_screens 一致，不含私人屏幕）
+    pub fn screen_regions(&self) -> Vec<crate::video::ScreenRegion> {
+        let personal_display_id = self
+            .personal_display
+            .read()
+            .ok()
+            .and_then(|personal| *personal);
+        let rects: Vec<(f64, f64, f64, f64, bool)> = self
+            .captured_screens(personal_display_id)
+            .iter()
+            .map(|(_, info)| {
+                let scale = if info.scale_factor.is_finite() && info.scale_factor > 0.0 {
+                    info.scale_factor as f64
+                } else {
+                    1.0
+                };
+                (
+                    info.x as f64 * scale,
+                    info.y as f64 * scale,
+                    info.width as f64 * scale,
+                    info.height as f64 * scale,
+                    info.is_primary,
+                )
+            })
+            .collect();
+
+        let min_x = rects.iter().map(|r| r.0).fold(f64::INFINITY, f64::min);
+        let min_y = rects.iter().map(|r| r.1).fold(f64::INFINITY, f64::min);
+        let max_x = rects
+            .iter()
+            .map(|r| r.0 + r.2)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let max_y = rects
+            .iter()
+            .map(|r| r.1 + r.3)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let (canvas_width, canvas_height) = (max_x - min_x, max_y - min_y);
+        if rects.is_empty() || canvas_width <= 0.0 || canvas_height <= 0.0 {
+            return Vec::new();
+        }
+
+        rects
+            .into_iter()
+            .map(
+                |(x, y, width, height, primary)| crate::video::ScreenRegion {
+                    x: (x - min_x) / canvas_width,
+                    y: (y - min_y) / canvas_height,
+                    width: width / canvas_width,
+                    height: height / canvas_height,
+                    primary,
+                },
+            )
+            .collect()
+    }
+
+    /// 截取所有屏幕、合成并按配置调整分辨率（与实际保存的截图一致）
+    ///
+    /// 各屏幕在线程池中并行截取，整个过程在阻塞线程中执行，不占用异步运行时
+    async fn grab_image(&self, settings: &CaptureSettings) -> Result<DynamicImage> {
+        let screens = self.captured_screens(settings.personal_display_id);
+        if screens.is_empty() {
+            if settings.personal_display_id.is_some() && !self.screens().is_empty() {
+                return Err(anyhow::anyhow!("仅有的屏幕已标记为私人屏幕，不截取"));
+            }
+            return Err(anyhow::anyhow!("未找到可用屏幕"));
+        }
+
+        let source = self.source.clone();
+        let pool = self.pool.clone();
+        let settings = settings.clone();
+        // macOS 以外的平台截图会包含本应用窗口，录进视频后形成套娃画面，按窗口区域遮盖
+        let own_window = if cfg!(not(target_os = "macos")) && settings.exclude_own_window {
+            self.own_window
+                .read()
+                .ok()
+                .and_then(|locator| locator.clone())
+        } else {
+            None
+        };
+
+        tokio::task::spawn_blocking(move || {
+            let own_window = own_window.and_then(|locate| locate());
+
+            let captures: Vec<(DisplayBounds, DynamicImage)> = pool.install(|| {
+                // 私人屏幕已在 captured_screens 中排除，不会被截取
+                screens
+                    .par_iter()
+                    .filter_map(|(index, info)| match source.capture(*index) {
+                        Ok(mut image) => {
+                            trace!("截取屏幕 #{} 成功", index);
+                            if let Some(window) = own_window {
+                                let screen_rect = WindowRect {
+                                    x: info.x,
+                                    y: info.y,
+                                    width: info.width,
+                                    height: info.height,
+                                };
+                                if mask_window(&mut image, screen_rect, window) {
+                                    trace!("已遮盖屏幕 #{} 中的本应用窗口", index);
+                                }
+                            }
+                            Some((*info, DynamicImage::ImageRgba8(image)))
+                        }
+                        Err(err) => {
+                            warn!("截取屏幕 #{} 失败: {}", index, err);
+                            None
+                        }
+                    })
+                    .collect()
+            });
+
+            if captures.is_empty() {
+                return Err(anyhow::anyhow!("未能获取到任何屏幕截图"));
+            }
+
+            let combined = Self::combine_screens(captures)?;
+
+            // 根据配置（开启自适应分辨率时按画面内容）调整分辨率
+            if let Some((width, height)) = frame_dimensions(&combined, &settings) {
+                Self::resize_image(combined, width, height)
+            } else {
+                // 原始分辨率，不调整
+                Ok(combined)
+            }
+        })
+        .await?
+    }
+
+    fn combine_screens(captures: Vec<(DisplayBounds, DynamicImage)>) -> Result<DynamicImage> {
         if captures.is_empty() {
             return Err(anyhow::anyhow!("没有可合成的屏幕图像"));
         }
@@ -382,7 +1145,7 @@ impl ScreenCapture {
     }
 
     /// 调整图像尺寸
-    fn resize_image(&self, img: DynamicImage, width: u32, height: u32) -> Result<DynamicImage> {
+    fn resize_image(img: DynamicImage, width: u32, height: u32) -> Result<DynamicImage> {
         Ok(img.resize_exact(width, height, image::imageops::FilterType::Lanczos3))
     }
 
@@ -422,6 +1185,7 @@ impl ScreenCapture {
 
 #[cfg(test)]
 mod tests {
+    use super::source::SyntheticSource;
     use super::*;
     use tempfile::tempdir;
 
@@ -431,4 +1195,250 @@ mod tests {
         let capture = ScreenCapture::new(temp_dir.path().to_path_buf());
         assert!(capture.is_ok());
     }
+
+    /// 等待写盘队列把帧加入当前会话
+    async fn wait_for_frames(capture: &ScreenCapture, count: usize) -> Vec<ScreenFrame> {
+        for _ in 0..200 {
+            let frames = capture.get_current_session_frames().await;
+            if frames.len() >= count {
+                return frames;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        capture.get_current_session_frames().await
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_black_frames_are_skipped() {
+        let temp_dir = tempdir().unwrap();
+        let source = Arc::new(SyntheticSource::solid(
+            640,
+            360,
+            image::Rgba([0, 0, 0, 255]),
+        ));
+        let capture =
+            ScreenCapture::with_source(temp_dir.path().to_path_buf(), source.clone()).unwrap();
+        capture
+            .update_settings(CaptureSettings {
+                black_screen_mode: BlackScreenMode::Skip,
+                ..CaptureSettings::default()
+            })
+            .await;
+
+        assert!(capture.capture_frame().await.is_err());
+        assert_eq!(source.captures(0), 1);
+        // 黑屏不计入活动，也不加入会话
+        assert!(capture.last_activity_at().is_none());
+        assert!(capture.get_current_session_frames().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_frames_are_combined_resized_deduped_and_accumulated() {
+        let temp_dir = tempdir().unwrap();
+        // 两个并排的 320x180 屏幕；预览和前两帧画面相同，第三帧右侧屏幕上半部分变暗
+        let displays = vec![
+            DisplayBounds {
+                id: 1,
+                x: 0,
+                y: 0,
+                width: 320,
+                height: 180,
+                scale_factor: 1.0,
+                is_primary: true,
+            },
+            DisplayBounds {
+                id: 2,
+                x: 320,
+                y: 0,
+                width: 320,
+                height: 180,
+                scale_factor: 1.0,
+                is_primary: false,
+            },
+        ];
+        let source = Arc::new(SyntheticSource::new(displays, |index, round| {
+            image::RgbaImage::from_fn(320, 180, |_, y| {
+                let dark = index == 0 || (round >= 3 && y < 90);
+                let v = if dark { 40 } else { 220 };
+                image::Rgba([v, v, v, 255])
+            })
+        }));
+        let capture =
+            ScreenCapture::with_source(temp_dir.path().to_path_buf(), source.clone()).unwrap();
+
+        // 标记为私人屏幕的显示器不截取、不合成
+        let personal = capture
+            .capture_preview(
+                Some(&CaptureSettings {
+                    resolution: crate::models::CaptureResolution::Original,
+                    personal_display_id: Some(2),
+                    ..CaptureSettings::default()
+                }),
+                160,
+            )
+            .await
+            .unwrap();
+        assert_eq!((personal.width, personal.height), (320, 180));
+        assert_eq!(source.captures(1), 0);
+
+        // 原始分辨率：按屏幕位置横向拼接
+        let preview = capture
+            .capture_preview(
+                Some(&CaptureSettings {
+                    resolution: crate::models::CaptureResolution::Original,
+                    ..CaptureSettings::default()
+                }),
+                160,
+            )
+            .await
+            .unwrap();
+        assert_eq!((preview.width, preview.height), (640, 180));
+        assert_eq!(capture.screens().len(), 2);
+
+        // 默认 1080P：保存的截图缩放到 1920x1080
+        let first = capture.capture_frame().await.unwrap();
+        let changed_at = capture.last_activity_at().unwrap();
+        assert_eq!(changed_at, first.timestamp);
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        capture.capture_frame().await.unwrap();
+        // 画面没有变化，活动时间不更新
+        assert_eq!(capture.last_activity_at(), Some(changed_at));
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let third = capture.capture_frame().await.unwrap();
+        assert_eq!(capture.last_activity_at(), Some(third.timestamp));
+
+        let frames = wait_for_frames(&capture, 3).await;
+        assert_eq!(frames.len(), 3);
+        let saved = image::open(&frames[0].file_path).unwrap();
+        assert_eq!((saved.width(), saved.height()), (1920, 1080));
+        assert_eq!(capture.latency_stats().frames, 3);
+    }
+
+    #[test]
+    fn test_mask_window_covers_only_overlap_in_screen_pixels() {
+        // 2 倍缩放的屏幕：逻辑 100x50，截图 200x100
+        let mut image = image::RgbaImage::from_pixel(200, 100, image::Rgba([255, 255, 255, 255]));
+        let screen = WindowRect {
+            x: 100,
+            y: 0,
+            width: 100,
+            height: 50,
+        };
+        // 窗口横跨左侧屏幕和本屏幕，本屏幕内重叠逻辑区域 (100..120, 10..30)
+        let window = WindowRect {
+            x: 60,
+            y: 10,
+            width: 60,
+            height: 20,
+        };
+        assert!(mask_window(&mut image, screen, window));
+        assert_eq!(*image.get_pixel(0, 20), OWN_WINDOW_MASK);
+        assert_eq!(*image.get_pixel(39, 59), OWN_WINDOW_MASK);
+        assert_eq!(*image.get_pixel(40, 20), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(10, 60), image::Rgba([255, 255, 255, 255]));
+
+        let elsewhere = WindowRect {
+            x: 300,
+            y: 0,
+            width: 50,
+            height: 50,
+        };
+        assert!(!mask_window(&mut image, screen, elsewhere));
+    }
+
+    #[test]
+    fn test_placeholder_frame_is_small_and_keeps_size() {
+        let data = encode_placeholder(1920, 1080).unwrap();
+        assert!(data.len() < 50 * 1024, "placeholder = {} bytes", data.len());
+        let image = image::load_from_memory(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (1920, 1080));
+    }
+
+    #[test]
+    fn test_exposure_normalization_only_brightens_dark_frames() {
+        // 夜间模式下的暗色界面：亮度集中在 10-60
+        let dark = DynamicImage::ImageRgb8(image::RgbImage::from_fn(320, 180, |x, _| {
+            let v = 10 + (x % 50) as u8;
+            image::Rgb([v, v, v])
+        }));
+        let lut = exposure_lut(&dark, 80).unwrap();
+        assert_eq!(lut[5], 0);
+        assert_eq!(lut[60], 255);
+        assert!(lut[35] > 100, "lut[35] = {}", lut[35]);
+
+        let bright = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            320,
+            180,
+            image::Rgb([200, 200, 200]),
+        ));
+        assert!(exposure_lut(&bright, 80).is_none());
+    }
+
+    #[test]
+    fn test_adaptive_quality_keeps_text_sharp() {
+        // 白底黑字：颜色少、边缘锐利，保持最高质量
+        let mut img = image::RgbImage::from_pixel(640, 360, image::Rgb([255, 255, 255]));
+        for y in (20..340).step_by(24) {
+            for x in (20..620).step_by(6) {
+                for dy in 0..12 {
+                    img.put_pixel(x, y + dy, image::Rgb([0, 0, 0]));
+                }
+            }
+        }
+        assert_eq!(adaptive_quality(&DynamicImage::ImageRgb8(img), 60, 90), 90);
+    }
+
+    #[test]
+    fn test_adaptive_quality_lowers_natural_content() {
+        // 平滑渐变叠加轻微纹理，模拟视频画面
+        let img = image::RgbImage::from_fn(640, 360, |x, y| {
+            let v = ((x * 255 / 640 + y * 255 / 360) / 2 + (x * 7 + y * 13) % 9) as u8;
+            image::Rgb([v, v, v])
+        });
+        let quality = adaptive_quality(&DynamicImage::ImageRgb8(img), 60, 90);
+        assert!(quality < 75, "quality = {}", quality);
+    }
+
+    #[test]
+    fn test_adaptive_resolution_downscales_media_only() {
+        let mut text = image::RgbImage::from_pixel(640, 360, image::Rgb([255, 255, 255]));
+        for y in (20..340).step_by(24) {
+            for x in (20..620).step_by(6) {
+                for dy in 0..12 {
+                    text.put_pixel(x, y + dy, image::Rgb([0, 0, 0]));
+                }
+            }
+        }
+        let media = image::RgbImage::from_fn(640, 360, |x, y| {
+            let v = ((x * 255 / 640 + y * 255 / 360) / 2 + (x * 7 + y * 13) % 9) as u8;
+            image::Rgb([v, v, v])
+        });
+        let (text, media) = (
+            DynamicImage::ImageRgb8(text),
+            DynamicImage::ImageRgb8(media),
+        );
+
+        let settings = CaptureSettings {
+            resolution: crate::models::CaptureResolution::QHD,
+            adaptive_resolution: true,
+            ..CaptureSettings::default()
+        };
+        assert_eq!(frame_dimensions(&text, &settings), Some((2560, 1440)));
+        assert_eq!(frame_dimensions(&media, &settings), Some((1280, 720)));
+
+        // 未开启时两类画面都使用配置的分辨率
+        let fixed = CaptureSettings {
+            adaptive_resolution: false,
+            ..settings.clone()
+        };
+        assert_eq!(frame_dimensions(&media, &fixed), Some((2560, 1440)));
+        // 媒体分辨率不低于配置的分辨率时不降级
+        let low = CaptureSettings {
+            resolution: crate::models::CaptureResolution::HD,
+            ..settings
+        };
+        assert_eq!(frame_dimensions(&media, &low), Some((1280, 720)));
+    }
 }
@@ -2,14 +2,27 @@
 //
 // 使用事件驱动架构,通过EventBus发布SessionCompleted事件
 // 解耦调度器与业务逻辑处理
+//
+// 尚未结束的会话窗口保存到窗口状态文件。应用在窗口结束前重启时，重启前后的截图间隔不算空白，
+// 重启后的截图继续累积到该窗口，而不是提前结束它并另起一个窗口
+//
+// 自适应会话模式下每隔 CONTEXT_SAMPLE_SECS 秒记录一次前台应用，窗口从第一张截图开始，
+// 其他应用持续占据前台超过切换时长时在切换处提前结束；到达会话时长时仍在主要应用中则延长，
+// 最长到配置的上限。无法获取前台应用的平台上与从首次活动开始的滚动窗口相同
 
 use super::ScreenCapture;
+use crate::domains::capture_pauses::PauseReason;
 use crate::event_bus::{AppEvent, EventBus};
+use crate::models::{AdaptiveSessionSettings, SessionAlignment};
 use anyhow::{anyhow, Result};
-use std::collections::{BTreeMap, HashSet, VecDeque};
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
 use tokio::time::{interval, Duration};
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 /// 窗口跟踪器 - 用于跟踪已处理的窗口，防止内存泄漏
 struct WindowTracker {
@@ -63,8 +76,58 @@ pub struct CaptureScheduler {
     capture_interval: u64,
     /// 会话时长（分钟）
     session_duration: u64,
+    /// 用户手动暂停截屏
+    paused: AtomicBool,
+    /// 会话窗口的对齐方式（每次扫描时读取，修改后对之后的窗口生效）
+    alignment: RwLock<SessionAlignment>,
+    /// 尚未结束的会话窗口的开始时间（毫秒，NO_OPEN_WINDOW 表示没有）
+    open_window_start: AtomicI64,
+    /// 尚未结束的会话窗口的状态文件（未设置时重启后不续接窗口）
+    window_state_path: Option<PathBuf>,
+    /// 本次启动的时间（毫秒）
+    started_ms: i64,
+    /// 自适应会话设置
+    adaptive: RwLock<AdaptiveSessionSettings>,
+    /// 自适应会话模式下的前台应用采样：时间（毫秒）→ 应用名
+    app_samples: std::sync::Mutex<BTreeMap<i64, String>>,
+}
+
+/// 保存到状态文件的尚未结束的会话窗口
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct OpenWindowState {
+    start_ms: i64,
+    end_ms: i64,
+    alignment: SessionAlignment,
+}
+
+async fn load_window_state(path: &Path) -> Option<OpenWindowState> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&bytes).ok()
 }
 
+async fn save_window_state(path: &Path, state: Option<OpenWindowState>) {
+    let result = match state {
+        Some(state) => match serde_json::to_vec(&state) {
+            Ok(json) => tokio::fs::write(path, json).await,
+            Err(e) => Err(std::io::Error::other(e)),
+        },
+        None => match tokio::fs::remove_file(path).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    };
+    if let Err(e) = result {
+        warn!("保存会话窗口状态失败 {:?}: {}", path, e);
+    }
+}
+
+/// 没有尚未结束的会话窗口
+const NO_OPEN_WINDOW: i64 = i64::MIN;
+/// 自适应会话模式下前台应用的采样间隔（秒）
+const CONTEXT_SAMPLE_SECS: u64 = 10;
+/// 前台应用采样的保留时长（毫秒），截图处理完之前重新分组需要用到
+const CONTEXT_RETENTION_MS: i64 = 24 * 3_600_000;
+
 impl CaptureScheduler {
     /// 创建新的调度器
     pub fn new(capture: Arc<ScreenCapture>) -> Self {
@@ -72,7 +135,85 @@ impl CaptureScheduler {
             capture,
             capture_interval: 1,  // 默认1秒一次（1 FPS）
             session_duration: 15, // 默认15分钟一个会话
+            paused: AtomicBool::new(false),
+            alignment: RwLock::new(SessionAlignment::default()),
+            open_window_start: AtomicI64::new(NO_OPEN_WINDOW),
+            window_state_path: None,
+            started_ms: crate::storage::local_now().timestamp_millis(),
+            adaptive: RwLock::new(AdaptiveSessionSettings::default()),
+            app_samples: std::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 设置尚未结束的会话窗口的状态文件，重启后在窗口结束前继续累积到该窗口
+    pub fn with_window_state(mut self, path: PathBuf) -> Self {
+        self.window_state_path = Some(path);
+        self
+    }
+
+    /// 设置会话窗口的对齐方式
+    pub fn set_alignment(&self, alignment: SessionAlignment) {
+        if let Ok(mut current) = self.alignment.write() {
+            if *current != alignment {
+                info!("会话窗口对齐方式更新为: {:?}", alignment);
+            }
+            *current = alignment;
+        }
+    }
+
+    /// 当前的会话窗口对齐方式
+    pub fn alignment(&self) -> SessionAlignment {
+        self.alignment
+            .read()
+            .map(|alignment| *alignment)
+            .unwrap_or_default()
+    }
+
+    /// 设置自适应会话的切换时长和最长时长
+    pub fn set_adaptive_session(&self, settings: AdaptiveSessionSettings) {
+        if let Ok(mut current) = self.adaptive.write() {
+            *current = settings;
+        }
+    }
+
+    /// 自适应会话模式下按前台应用调整窗口结束时间的规则（其他模式为 None）
+    fn context_close(&self, alignment: SessionAlignment) -> Option<ContextClose> {
+        if alignment != SessionAlignment::Adaptive {
+            return None;
         }
+        let adaptive = self.adaptive.read().ok()?.clone();
+        let samples = self.app_samples.lock().ok()?.clone();
+        Some(ContextClose {
+            samples,
+            switch_ms: adaptive.switch_minutes.max(1) as i64 * 60_000,
+            max_ms: adaptive.max_minutes as i64 * 60_000,
+        })
+    }
+
+    /// 当前会话窗口的开始时间
+    ///
+    /// 整点对齐时按会话时长计算；滚动窗口和自适应会话取最近一次扫描时尚未结束的窗口，没有截图时返回 None
+    pub fn current_window_start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        use chrono::TimeZone;
+
+        let start_ms = match self.alignment() {
+            SessionAlignment::Clock => {
+                if self.session_duration == 0 {
+                    return None;
+                }
+                let interval_ms = self.session_duration as i64 * 60_000;
+                let now_ms = now.timestamp_millis();
+                now_ms - now_ms.rem_euclid(interval_ms)
+            }
+            SessionAlignment::FirstActivity | SessionAlignment::Adaptive => {
+                let start_ms = self.open_window_start.load(Ordering::SeqCst);
+                if start_ms == NO_OPEN_WINDOW {
+                    return None;
+                }
+                start_ms
+            }
+        };
+        Utc.timestamp_millis_opt(start_ms).single()
     }
 
     /// 配置调度参数
@@ -85,8 +226,18 @@ impl CaptureScheduler {
         );
     }
 
+    /// 手动暂停或恢复截屏，返回暂停状态是否发生变化
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::SeqCst) != paused
+    }
+
+    /// 是否处于手动暂停状态
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
     /// 启动截屏任务
-    pub fn start_capture_task(self: Arc<Self>) {
+    pub fn start_capture_task(self: Arc<Self>, event_bus: Arc<EventBus>) {
         let capture = self.capture.clone();
         let interval_secs = self.capture_interval;
 
@@ -97,9 +248,20 @@ impl CaptureScheduler {
             info!("截屏任务已启动，间隔: {}秒", interval_secs);
             let mut interval = interval(Duration::from_secs(interval_secs));
 
+            // 锁屏状态变化时发布暂停/恢复事件（记录到截屏暂停表）
+            let mut locked = super::ScreenCapture::is_screen_locked();
+            if locked {
+                event_bus.publish(AppEvent::CapturePaused {
+                    reason: PauseReason::ScreenLocked,
+                    timestamp: crate::storage::local_now(),
+                });
+            }
+
             // 立即执行第一次截屏（检查锁屏状态）
-            if super::ScreenCapture::is_screen_locked() {
+            if locked {
                 trace!("系统锁屏中，跳过初始截屏");
+            } else if self.is_paused() {
+                trace!("截屏已手动暂停，跳过初始截屏");
             } else {
                 match capture.capture_frame().await {
                     Ok(frame) => {
@@ -120,11 +282,32 @@ impl CaptureScheduler {
                 interval.tick().await;
 
                 // 检查锁屏状态
-                if super::ScreenCapture::is_screen_locked() {
+                let now_locked = super::ScreenCapture::is_screen_locked();
+                if now_locked != locked {
+                    locked = now_locked;
+                    let timestamp = crate::storage::local_now();
+                    event_bus.publish(if locked {
+                        AppEvent::CapturePaused {
+                            reason: PauseReason::ScreenLocked,
+                            timestamp,
+                        }
+                    } else {
+                        AppEvent::CaptureResumed {
+                            reason: PauseReason::ScreenLocked,
+                            timestamp,
+                        }
+                    });
+                }
+                if locked {
                     info!("系统锁屏中，跳过截屏");
                     continue;
                 }
 
+                if self.is_paused() {
+                    trace!("截屏已手动暂停，跳过截屏");
+                    continue;
+                }
+
                 match capture.capture_frame().await {
                     Ok(frame) => {
                         trace!("自动截屏成功: {}", frame.timestamp);
@@ -142,28 +325,105 @@ impl CaptureScheduler {
         });
     }
 
+    /// 启动前台应用采样任务（仅在自适应会话模式下采样）
+    pub fn start_context_task(self: Arc<Self>) {
+        tokio::task::spawn(async move {
+            let mut interval = interval(Duration::from_secs(CONTEXT_SAMPLE_SECS));
+            loop {
+                interval.tick().await;
+
+                if self.alignment() != SessionAlignment::Adaptive {
+                    if let Ok(mut samples) = self.app_samples.lock() {
+                        samples.clear();
+                    }
+                    continue;
+                }
+                if self.is_paused() || super::ScreenCapture::is_screen_locked() {
+                    continue;
+                }
+
+                let app = tokio::task::spawn_blocking(super::foreground::foreground_app)
+                    .await
+                    .ok()
+                    .flatten();
+                let Some(app) = app else {
+                    continue;
+                };
+                let now_ms = crate::storage::local_now().timestamp_millis();
+                if let Ok(mut samples) = self.app_samples.lock() {
+                    samples.insert(now_ms, app.name);
+                    *samples = samples.split_off(&(now_ms - CONTEXT_RETENTION_MS));
+                }
+            }
+        });
+    }
+
     /// 启动会话处理任务(事件驱动版本)
     pub fn start_session_task(self: Arc<Self>, event_bus: Arc<EventBus>) {
         let capture = self.capture.clone();
         let session_mins = self.session_duration;
 
         tokio::task::spawn(async move {
+            let scheduler = self;
             // 使用 WindowTracker 限制内存使用，最多保留 1000 个窗口记录
             let mut processed_windows = WindowTracker::new(1000);
             let check_interval = Duration::from_secs(60);
 
+            // 上次运行时尚未结束、且本次启动时仍未到结束时间的窗口
+            let mut saved = match &scheduler.window_state_path {
+                Some(path) => load_window_state(path).await,
+                None => None,
+            };
+            let resume = saved
+                .filter(|state| {
+                    state.alignment == scheduler.alignment()
+                        && state.start_ms <= scheduler.started_ms
+                        && scheduler.started_ms < state.end_ms
+                })
+                .map(|state| ResumedWindow {
+                    start_ms: state.start_ms,
+                    restart_ms: scheduler.started_ms,
+                });
+            if let Some(resume) = resume {
+                info!(
+                    "继续累积重启前尚未结束的会话窗口 (开始于 {})",
+                    resume.start_ms
+                );
+            }
+
             info!("会话处理任务已启动，每60秒扫描待处理图片（事件驱动模式）");
 
             loop {
-                if let Err(e) = CaptureScheduler::scan_pending_sessions(
+                let alignment = scheduler.alignment();
+                match CaptureScheduler::scan_pending_sessions(
                     capture.clone(),
                     event_bus.clone(),
                     session_mins,
+                    alignment,
+                    resume,
+                    scheduler.context_close(alignment),
                     &mut processed_windows,
                 )
                 .await
                 {
-                    error!("扫描待处理图片失败: {}", e);
+                    Ok(open_window) => {
+                        scheduler.open_window_start.store(
+                            open_window.map_or(NO_OPEN_WINDOW, |(start, _)| start),
+                            Ordering::SeqCst,
+                        );
+                        let state = open_window.map(|(start_ms, end_ms)| OpenWindowState {
+                            start_ms,
+                            end_ms,
+                            alignment,
+                        });
+                        if let Some(path) = &scheduler.window_state_path {
+                            if state != saved {
+                                save_window_state(path, state).await;
+                                saved = state;
+                            }
+                        }
+                    }
+                    Err(e) => error!("扫描待处理图片失败: {}", e),
                 }
 
                 tokio::time::sleep(check_interval).await;
@@ -176,7 +436,10 @@ impl CaptureScheduler {
         info!("启动截屏调度器（事件驱动模式）...");
 
         // 启动截屏任务
-        self.clone().start_capture_task();
+        self.clone().start_capture_task(event_bus.clone());
+
+        // 启动前台应用采样任务（自适应会话）
+        self.clone().start_context_task();
 
         // 启动会话处理任务
         self.start_session_task(event_bus);
@@ -184,13 +447,17 @@ impl CaptureScheduler {
         info!("所有调度任务已启动");
     }
 
+    /// 扫描截图目录并发布已结束的会话，返回尚未结束的会话窗口的开始和结束时间（毫秒）
     async fn scan_pending_sessions(
         capture: Arc<ScreenCapture>,
         event_bus: Arc<EventBus>,
         session_duration: u64,
+        alignment: SessionAlignment,
+        resume: Option<ResumedWindow>,
+        context: Option<ContextClose>,
         processed_windows: &mut WindowTracker,
-    ) -> Result<()> {
-        use chrono::{TimeZone, Utc};
+    ) -> Result<Option<(i64, i64)>> {
+        use chrono::TimeZone;
 
         if session_duration == 0 {
             return Err(anyhow!("会话时长必须大于0"));
@@ -198,11 +465,17 @@ impl CaptureScheduler {
 
         let frames_dir = capture.frames_dir();
         if !frames_dir.exists() {
-            return Ok(());
+            return Ok(None);
+        }
+
+        // 存储离线时不扫描，避免窗口被标记为已处理；重新连接后下次扫描会补上
+        if !crate::storage::availability::is_available(&frames_dir).await {
+            debug!("截图目录不可用（网络共享可能已断开），跳过本次扫描");
+            return Ok(None);
         }
 
         let interval_ms = session_duration as i64 * 60_000;
-        let mut grouped: BTreeMap<i64, Vec<super::ScreenFrame>> = BTreeMap::new();
+        let mut frames = Vec::new();
         let mut entries = tokio::fs::read_dir(&frames_dir).await?;
 
         while let Some(entry) = entries.next_entry().await? {
@@ -231,27 +504,34 @@ impl CaptureScheduler {
                 continue;
             };
 
-            let frame = super::ScreenFrame {
+            frames.push(super::ScreenFrame {
                 timestamp,
                 file_path: path.to_string_lossy().to_string(),
                 screen_id: 0,
-            };
-
-            let bucket = (timestamp_ms / interval_ms) * interval_ms;
-            grouped.entry(bucket).or_default().push(frame);
+            });
         }
 
-        if grouped.is_empty() {
+        if frames.is_empty() {
             trace!("未发现待处理图片");
-            return Ok(());
+            return Ok(None);
         }
 
         let now_ms = crate::storage::local_now().timestamp_millis();
         let cutoff_ms = now_ms - 30_000; // 留出缓冲，避免处理仍在写入的区间
+        let mut open_window = None;
 
-        for (bucket_start_ms, mut frames) in grouped.into_iter() {
-            let bucket_end_ms = bucket_start_ms + interval_ms;
+        let blank_minutes = capture.settings().await.blank_session_minutes;
+        let blank = (blank_minutes > 0).then(|| BlankClose {
+            gap_ms: blank_minutes as i64 * 60_000,
+            now_ms,
+            resume,
+        });
+
+        for (bucket_start_ms, (bucket_end_ms, frames)) in
+            group_frames(frames, interval_ms, alignment, blank, context.as_ref())
+        {
             if bucket_end_ms > cutoff_ms {
+                open_window = Some((bucket_start_ms, bucket_end_ms));
                 continue;
             }
 
@@ -263,8 +543,6 @@ impl CaptureScheduler {
                 continue;
             }
 
-            frames.sort_by_key(|f| f.timestamp);
-
             let Some(window_start) = Utc.timestamp_millis_opt(bucket_start_ms).single() else {
                 continue;
             };
@@ -278,33 +556,227 @@ impl CaptureScheduler {
             };
 
             let frame_count = frames.len();
-            info!(
-                "发现待处理会话: {} - {}, 帧数 {}",
-                window.start, window.end, frame_count
-            );
+            // 会话关联 ID 从这里开始，后续视频、LLM 和存储日志沿用同一个 ID
+            crate::logger::session_span(window.start).in_scope(|| {
+                info!(
+                    "发现待处理会话: {} - {}, 帧数 {}",
+                    window.start, window.end, frame_count
+                );
 
-            // 发布SessionCompleted事件（事件驱动架构）
-            // 不再直接调用processor，而是发布事件让订阅者处理
-            event_bus.publish(AppEvent::SessionCompleted {
-                session_id: bucket_start_ms, // 使用bucket_start_ms作为临时session_id
-                frame_count,
-                window_start: window.start,
-                window_end: window.end,
-            });
+                // 发布SessionCompleted事件（事件驱动架构）
+                // 不再直接调用processor，而是发布事件让订阅者处理
+                event_bus.publish(AppEvent::SessionCompleted {
+                    session_id: bucket_start_ms, // 使用bucket_start_ms作为临时session_id
+                    frame_count,
+                    window_start: window.start,
+                    window_end: window.end,
+                });
 
-            // 标记为已处理
-            processed_windows.insert(bucket_start_ms);
+                // 标记为已处理
+                processed_windows.insert(bucket_start_ms);
 
-            info!(
-                "会话事件已发布: {} - {} (session_id: {})",
-                window.start, window.end, bucket_start_ms
-            );
+                info!(
+                    "会话事件已发布: {} - {} (session_id: {})",
+                    window.start, window.end, bucket_start_ms
+                );
+            });
 
             // 注意：不再在这里清理图片，由事件订阅者（LLMProcessor）处理后决定是否清理
         }
 
-        Ok(())
+        Ok(open_window)
+    }
+}
+
+/// 长时间没有截图（显示器休眠、锁屏或暂停）时提前结束会话窗口的规则
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlankClose {
+    /// 截图间隔超过该时长（毫秒）视为空白
+    pub gap_ms: i64,
+    /// 当前时间（毫秒），最后一张截图之后已空白超过 gap_ms 时结束尚未结束的窗口
+    pub now_ms: i64,
+    /// 重启前尚未结束的窗口，窗口中的空白从重启时开始计算
+    pub resume: Option<ResumedWindow>,
+}
+
+/// 应用重启前尚未结束的会话窗口
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResumedWindow {
+    /// 窗口开始时间（毫秒）
+    pub start_ms: i64,
+    /// 本次启动的时间（毫秒）
+    pub restart_ms: i64,
+}
+
+impl BlankClose {
+    /// 空白的起点：重启前尚未结束的窗口中，重启前的最后一张截图之后的空白从重启时开始计算
+    fn since(&self, window_start: i64, last_ms: i64) -> i64 {
+        match self.resume {
+            Some(resume) if resume.start_ms == window_start && last_ms < resume.restart_ms => {
+                resume.restart_ms
+            }
+            _ => last_ms,
+        }
+    }
+}
+
+/// 自适应会话按前台应用切换调整窗口结束时间的规则
+#[derive(Debug, Clone)]
+pub(crate) struct ContextClose {
+    /// 前台应用采样：时间（毫秒）→ 应用名
+    pub samples: BTreeMap<i64, String>,
+    /// 其他应用持续占据前台超过该时长（毫秒）视为切换了活动
+    pub switch_ms: i64,
+    /// 同一活动持续时窗口的最长时长（毫秒，不短于会话时长）
+    pub max_ms: i64,
+}
+
+impl ContextClose {
+    /// 从 start 开始的窗口的结束时间
+    ///
+    /// 主要应用为窗口内采样次数最多的应用。其他应用持续占据前台超过切换时长时，
+    /// 窗口在切换开始的秒结束；到达会话时长时最近一次采样仍是主要应用则延长窗口，
+    /// 直到切换或达到最长时长；没有采样时按会话时长结束
+    fn window_end(&self, start: i64, interval_ms: i64) -> i64 {
+        let nominal_end = start + interval_ms;
+        let max_end = start + self.max_ms.max(interval_ms);
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut dominant: Option<&str> = None;
+        let mut switched_at: Option<i64> = None;
+        let mut extended = false;
+
+        for (&ms, app) in self.samples.range(start..max_end) {
+            let app = app.as_str();
+            if ms >= nominal_end && !extended {
+                if dominant.is_none() || switched_at.is_some() {
+                    return nominal_end;
+                }
+                extended = true;
+            }
+
+            let count = counts.entry(app).or_default();
+            *count += 1;
+            let count = *count;
+            if dominant.map_or(true, |current| count > counts[current]) {
+                dominant = Some(app);
+            }
+
+            if dominant == Some(app) {
+                switched_at = None;
+            } else {
+                let at = *switched_at.get_or_insert(ms);
+                if ms - at >= self.switch_ms {
+                    return (at - at.rem_euclid(1000)).max(start + 1000);
+                }
+            }
+        }
+
+        if extended {
+            max_end
+        } else {
+            nominal_end
+        }
+    }
+}
+
+/// 向上取整到秒（窗口在最后一张截图所在的秒之后结束）
+fn ceil_to_second(timestamp_ms: i64) -> i64 {
+    timestamp_ms - timestamp_ms.rem_euclid(1000) + 1000
+}
+
+/// 按对齐方式把截图分组为会话窗口，返回 窗口开始（毫秒）→（窗口结束，按时间排序的截图）
+///
+/// - 整点对齐：按会话时长对齐到时间刻度分桶
+/// - 滚动窗口：窗口从第一张截图所在的秒开始，之后的截图落在窗口外时从该截图开始新的窗口
+///   （连续截屏时新窗口紧接上一个窗口；处理后的截图被删除，剩余截图重新分组得到相同的窗口）
+/// - 自适应会话：与滚动窗口相同，但窗口结束时间按 `context` 中的前台应用采样调整
+/// - 指定 `blank` 时，截图中断超过空白时长的窗口在最后一张截图处结束，恢复后的截图从所在的秒开始新窗口；
+///   整点对齐时刻度开始后空白超过该时长才出现的第一张截图同样从所在的秒开始，
+///   这样前一个窗口处理完、截图被删除后重新分组仍得到相同的窗口；
+///   重启前尚未结束的窗口不因重启造成的截图中断而结束
+pub(crate) fn group_frames(
+    mut frames: Vec<super::ScreenFrame>,
+    interval_ms: i64,
+    alignment: SessionAlignment,
+    blank: Option<BlankClose>,
+    context: Option<&ContextClose>,
+) -> BTreeMap<i64, (i64, Vec<super::ScreenFrame>)> {
+    frames.sort_by_key(|frame| frame.timestamp);
+    let mut grouped: BTreeMap<i64, (i64, Vec<super::ScreenFrame>)> = BTreeMap::new();
+    let mut current: Option<(i64, i64)> = None;
+    let mut last_ms: Option<i64> = None;
+    for frame in frames {
+        let timestamp_ms = frame.timestamp.timestamp_millis();
+        let second_ms = timestamp_ms - timestamp_ms.rem_euclid(1000);
+        let after_blank = |since: i64| blank.map_or(false, |b| timestamp_ms - since >= b.gap_ms);
+
+        // 与上一张截图之间空白过长：上一个窗口在其最后一张截图处结束
+        let resumed = match (blank, current, last_ms) {
+            (Some(blank), Some((start, _)), Some(last)) => after_blank(blank.since(start, last)),
+            _ => false,
+        };
+        if resumed {
+            if let (Some((start, _)), Some(last)) = (current, last_ms) {
+                if let Some(window) = grouped.get_mut(&start) {
+                    window.0 = window.0.min(ceil_to_second(last));
+                }
+            }
+            current = None;
+        }
+
+        let (start, end) = match (alignment, current) {
+            (SessionAlignment::Clock, Some((start, end))) if timestamp_ms < end => (start, end),
+            (SessionAlignment::Clock, _) => {
+                let bucket = timestamp_ms - timestamp_ms.rem_euclid(interval_ms);
+                let start = if after_blank(bucket) {
+                    second_ms
+                } else {
+                    bucket
+                };
+                (start, bucket + interval_ms)
+            }
+            (SessionAlignment::FirstActivity, Some((start, end))) if timestamp_ms < end => {
+                (start, end)
+            }
+            (SessionAlignment::FirstActivity, _) => (second_ms, second_ms + interval_ms),
+            (SessionAlignment::Adaptive, Some((start, end))) if timestamp_ms < end => (start, end),
+            (SessionAlignment::Adaptive, _) => {
+                let end = context.map_or(second_ms + interval_ms, |context| {
+                    context.window_end(second_ms, interval_ms)
+                });
+                (second_ms, end)
+            }
+        };
+        current = Some((start, end));
+        last_ms = Some(timestamp_ms);
+        grouped
+            .entry(start)
+            .or_insert_with(|| (end, Vec::new()))
+            .1
+            .push(frame);
+    }
+
+    // 最后一张截图之后已空白过长：尚未结束的窗口提前结束
+    if let (Some(blank), Some((start, _)), Some(last)) = (blank, current, last_ms) {
+        if blank.now_ms - blank.since(start, last) >= blank.gap_ms {
+            if let Some(window) = grouped.get_mut(&start) {
+                window.0 = window.0.min(ceil_to_second(last));
+            }
+        }
     }
+    grouped
+}
+
+/// 会话视频的文件名（开始-结束）：窗口对齐到整分钟时精确到分钟，否则精确到秒
+pub fn window_file_label(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    use chrono::Timelike;
+
+    let format = if start.second() == 0 && end.second() == 0 {
+        "%Y%m%d%H%M"
+    } else {
+        "%Y%m%d%H%M%S"
+    };
+    format!("{}-{}", start.format(format), end.format(format))
 }
 
 /// 会话时间窗
@@ -316,6 +788,13 @@ pub struct SessionWindow {
     pub end: chrono::DateTime<chrono::Utc>,
 }
 
+impl SessionWindow {
+    /// 会话视频的文件名（不含扩展名）
+    pub fn file_label(&self) -> String {
+        window_file_label(self.start, self.end)
+    }
+}
+
 /// 会话处理器trait
 #[async_trait::async_trait]
 pub trait SessionProcessor {
@@ -347,3 +826,289 @@ impl SessionProcessor for DefaultSessionProcessor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn frame(timestamp_ms: i64) -> super::super::ScreenFrame {
+        super::super::ScreenFrame {
+            timestamp: Utc.timestamp_millis_opt(timestamp_ms).unwrap(),
+            file_path: format!("{}.jpg", timestamp_ms),
+            screen_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_windows_follow_alignment() {
+        let quarter = 15 * 60_000;
+        // 10:07:12.5 开始截屏，每 5 分钟一张，持续到 10:37
+        let first = Utc
+            .with_ymd_and_hms(2025, 10, 9, 10, 7, 12)
+            .unwrap()
+            .timestamp_millis()
+            + 500;
+        let frames: Vec<_> = (0..7).map(|i| frame(first + i * 5 * 60_000)).collect();
+
+        let clock = group_frames(frames.clone(), quarter, SessionAlignment::Clock, None, None);
+        let starts: Vec<String> = clock
+            .keys()
+            .map(|start| {
+                Utc.timestamp_millis_opt(*start)
+                    .unwrap()
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(starts, vec!["10:00:00", "10:15:00", "10:30:00"]);
+
+        let rolling = group_frames(frames, quarter, SessionAlignment::FirstActivity, None, None);
+        let windows: Vec<(String, usize)> = rolling
+            .iter()
+            .map(|(start, (end, frames))| {
+                (
+                    window_file_label(
+                        Utc.timestamp_millis_opt(*start).unwrap(),
+                        Utc.timestamp_millis_opt(*end).unwrap(),
+                    ),
+                    frames.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            windows,
+            vec![
+                ("20251009100712-20251009102212".to_string(), 3),
+                ("20251009102212-20251009103712".to_string(), 3),
+                ("20251009103712-20251009105212".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            window_file_label(
+                Utc.with_ymd_and_hms(2025, 10, 9, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 10, 9, 10, 15, 0).unwrap(),
+            ),
+            "202510091000-202510091015"
+        );
+    }
+
+    #[test]
+    fn test_blank_gap_closes_window_early_and_resumes_in_new_window() {
+        let quarter = 15 * 60_000;
+        let at = |h: u32, m: u32, s: u32| {
+            Utc.with_ymd_and_hms(2025, 10, 9, h, m, s)
+                .unwrap()
+                .timestamp_millis()
+        };
+        // 10:00-10:03 截屏，显示器休眠，10:12-10:14 恢复，每 30 秒一张
+        let mut frames: Vec<_> = (0..=6).map(|i| frame(at(10, 0, 0) + i * 30_000)).collect();
+        frames.extend((0..=4).map(|i| frame(at(10, 12, 0) + i * 30_000)));
+        let blank = |now_ms| {
+            Some(BlankClose {
+                gap_ms: 5 * 60_000,
+                now_ms,
+                resume: None,
+            })
+        };
+        let windows = |grouped: BTreeMap<i64, (i64, Vec<super::super::ScreenFrame>)>| {
+            grouped
+                .iter()
+                .map(|(start, (end, frames))| {
+                    (
+                        window_file_label(
+                            Utc.timestamp_millis_opt(*start).unwrap(),
+                            Utc.timestamp_millis_opt(*end).unwrap(),
+                        ),
+                        frames.len(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // 没有前台应用采样时自适应会话与滚动窗口相同
+        for alignment in [
+            SessionAlignment::Clock,
+            SessionAlignment::FirstActivity,
+            SessionAlignment::Adaptive,
+        ] {
+            let grouped = group_frames(
+                frames.clone(),
+                quarter,
+                alignment,
+                blank(at(10, 14, 30)),
+                None,
+            );
+            let expected_open_end = match alignment {
+                SessionAlignment::Clock => "202510091012-202510091015",
+                SessionAlignment::FirstActivity | SessionAlignment::Adaptive => {
+                    "202510091012-202510091027"
+                }
+            };
+            assert_eq!(
+                windows(grouped),
+                vec![
+                    ("20251009100000-20251009100301".to_string(), 7),
+                    (expected_open_end.to_string(), 5),
+                ]
+            );
+
+            // 恢复后再次空白过长：尚未结束的窗口在最后一张截图处结束
+            let grouped = group_frames(
+                frames.clone(),
+                quarter,
+                alignment,
+                blank(at(10, 20, 0)),
+                None,
+            );
+            assert_eq!(
+                windows(grouped)[1],
+                ("20251009101200-20251009101401".to_string(), 5)
+            );
+
+            // 前一个窗口处理完、截图删除后重新分组，得到相同的窗口
+            let grouped = group_frames(
+                frames[7..].to_vec(),
+                quarter,
+                alignment,
+                blank(at(10, 14, 30)),
+                None,
+            );
+            assert_eq!(windows(grouped), vec![(expected_open_end.to_string(), 5)]);
+        }
+    }
+
+    #[test]
+    fn test_restart_within_window_resumes_accumulating() {
+        let quarter = 15 * 60_000;
+        let at = |h: u32, m: u32, s: u32| {
+            Utc.with_ymd_and_hms(2025, 10, 9, h, m, s)
+                .unwrap()
+                .timestamp_millis()
+        };
+        // 10:00-10:03 截屏后应用退出，10:10 重启，10:10:30 起继续截屏
+        let mut frames: Vec<_> = (0..=6).map(|i| frame(at(10, 0, 0) + i * 30_000)).collect();
+        let resume = ResumedWindow {
+            start_ms: at(10, 0, 0),
+            restart_ms: at(10, 10, 0),
+        };
+        let blank = |now_ms, resume| {
+            Some(BlankClose {
+                gap_ms: 5 * 60_000,
+                now_ms,
+                resume,
+            })
+        };
+
+        // 重启后还没有新截图：窗口不会因重启前的中断提前结束
+        let grouped = group_frames(
+            frames.clone(),
+            quarter,
+            SessionAlignment::Clock,
+            blank(at(10, 10, 20), Some(resume)),
+            None,
+        );
+        assert_eq!(grouped[&at(10, 0, 0)].0, at(10, 15, 0));
+
+        frames.extend((1..=4).map(|i| frame(at(10, 10, 0) + i * 30_000)));
+        let grouped = group_frames(
+            frames.clone(),
+            quarter,
+            SessionAlignment::Clock,
+            blank(at(10, 12, 10), Some(resume)),
+            None,
+        );
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[&at(10, 0, 0)].0, at(10, 15, 0));
+        assert_eq!(grouped[&at(10, 0, 0)].1.len(), frames.len());
+
+        // 没有续接信息时重启被当作空白，窗口分成两段
+        let grouped = group_frames(
+            frames,
+            quarter,
+            SessionAlignment::Clock,
+            blank(at(10, 12, 10), None),
+            None,
+        );
+        assert_eq!(grouped.len(), 2);
+    }
+
+    #[test]
+    fn test_adaptive_windows_follow_foreground_app() {
+        let quarter = 15 * 60_000;
+        let at = |h: u32, m: u32, s: u32| {
+            Utc.with_ymd_and_hms(2025, 10, 9, h, m, s)
+                .unwrap()
+                .timestamp_millis()
+        };
+        let samples = |ranges: &[(i64, i64, &str)]| {
+            let mut samples = BTreeMap::new();
+            for &(from, to, app) in ranges {
+                let mut ms = from;
+                while ms < to {
+                    samples.insert(ms, app.to_string());
+                    ms += 10_000;
+                }
+            }
+            samples
+        };
+        let context = |samples| ContextClose {
+            samples,
+            switch_ms: 3 * 60_000,
+            max_ms: 45 * 60_000,
+        };
+        let frames: Vec<_> = (0..120).map(|i| frame(at(10, 0, 0) + i * 30_000)).collect();
+
+        // 10:06 切到浏览器，10:07 短暂切回编辑器，切换从 10:07:10 重新计算；
+        // 之后浏览器持续超过 3 分钟：窗口在 10:07:10 结束，下一张截图开始新窗口
+        let switched = context(samples(&[
+            (at(10, 0, 0), at(10, 6, 0), "Code"),
+            (at(10, 6, 0), at(10, 7, 0), "Chrome"),
+            (at(10, 7, 0), at(10, 7, 10), "Code"),
+            (at(10, 7, 10), at(11, 0, 0), "Chrome"),
+        ]));
+        let grouped = group_frames(
+            frames.clone(),
+            quarter,
+            SessionAlignment::Adaptive,
+            None,
+            Some(&switched),
+        );
+        assert_eq!(grouped[&at(10, 0, 0)].0, at(10, 7, 10));
+        let second = grouped.keys().nth(1).copied().unwrap();
+        assert_eq!(second, at(10, 7, 30));
+
+        // 同一应用持续：到达会话时长后延长，直到最长时长
+        let continuous = context(samples(&[(at(10, 0, 0), at(11, 0, 0), "Code")]));
+        let grouped = group_frames(
+            frames.clone(),
+            quarter,
+            SessionAlignment::Adaptive,
+            None,
+            Some(&continuous),
+        );
+        assert_eq!(grouped[&at(10, 0, 0)].0, at(10, 45, 0));
+        assert_eq!(grouped[&at(10, 0, 0)].1.len(), 90);
+
+        // 延长期间切换：在切换处结束
+        let extended = context(samples(&[
+            (at(10, 0, 0), at(10, 20, 0), "Code"),
+            (at(10, 20, 0), at(11, 0, 0), "Slack"),
+        ]));
+        assert_eq!(extended.window_end(at(10, 0, 0), quarter), at(10, 20, 0));
+
+        // 到达会话时长时正在切换（尚未持续够切换时长）：按会话时长结束
+        let switching = context(samples(&[
+            (at(10, 0, 0), at(10, 14, 0), "Code"),
+            (at(10, 14, 0), at(10, 16, 0), "Slack"),
+            (at(10, 16, 0), at(11, 0, 0), "Code"),
+        ]));
+        assert_eq!(switching.window_end(at(10, 0, 0), quarter), at(10, 15, 0));
+
+        // 没有采样时按会话时长结束
+        assert_eq!(
+            context(BTreeMap::new()).window_end(at(10, 0, 0), quarter),
+            at(10, 15, 0)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+// 截图来源 - 把“枚举屏幕、截取单个屏幕”抽象成 FrameSource
+//
+// 正常运行时使用 ScreenshotSource（screenshots crate 截取真实屏幕）；
+// SyntheticSource 按给定的屏幕布局生成图像，不需要显示器和截屏权限，
+// 用于在 CI 等无桌面环境中测试黑屏检测、分辨率调整、画面去重和会话累积等截屏逻辑
+
+use anyhow::Result;
+use image::RgbaImage;
+use screenshots::display_info::DisplayInfo;
+use screenshots::Screen;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+
+/// 屏幕在桌面坐标系中的位置（逻辑坐标，与 DisplayInfo 一致）
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct DisplayBounds {
+    /// 显示器 ID（系统分配，用于标记私人屏幕）
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// 缩放比例（截图像素 / 逻辑坐标）
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+impl From<DisplayInfo> for DisplayBounds {
+    fn from(info: DisplayInfo) -> Self {
+        Self {
+            id: info.id,
+            x: info.x,
+            y: info.y,
+            width: info.width,
+            height: info.height,
+            scale_factor: info.scale_factor,
+            is_primary: info.is_primary,
+        }
+    }
+}
+
+/// 截图来源
+///
+/// 截屏线程池中并行调用 capture，实现可以阻塞
+pub trait FrameSource: Send + Sync {
+    /// 当前可用的屏幕（没有屏幕或枚举失败时返回空列表）
+    fn displays(&self) -> Vec<DisplayBounds>;
+
+    /// 截取第 index 个屏幕（序号与 displays 一致）
+    fn capture(&self, index: usize) -> Result<RgbaImage>;
+}
+
+/// 真实屏幕截图（screenshots crate）
+#[derive(Default)]
+pub struct ScreenshotSource {
+    /// 可用屏幕列表（首次使用时枚举，避免阻塞启动）
+    screens: std::sync::Mutex<Option<Vec<Screen>>>,
+}
+
+impl ScreenshotSource {
+    /// 可用屏幕列表，首次调用时枚举屏幕（枚举失败或没有屏幕时下次调用重试）
+    fn screens(&self) -> Vec<Screen> {
+        let Ok(mut cached) = self.screens.lock() else {
+            return Vec::new();
+        };
+        if let Some(screens) = cached.as_ref() {
+            return screens.clone();
+        }
+
+        let screens = match Screen::all() {
+            Ok(screens) => screens,
+            Err(e) => {
+                warn!("枚举屏幕失败: {}", e);
+                return Vec::new();
+            }
+        };
+        info!("检测到 {} 个屏幕", screens.len());
+
+        // 打印每个屏幕的详细信息
+        for (index, screen) in screens.iter().enumerate() {
+            let display_info = screen.display_info;
+            info!(
+                "屏幕 #{}: {}x{} @ ({}, {})",
+                index, display_info.width, display_info.height, display_info.x, display_info.y
+            );
+        }
+
+        if !screens.is_empty() {
+            *cached = Some(screens.clone());
+        }
+        screens
+    }
+}
+
+impl FrameSource for ScreenshotSource {
+    fn displays(&self) -> Vec<DisplayBounds> {
+        self.screens()
+            .iter()
+            .map(|screen| screen.display_info.into())
+            .collect()
+    }
+
+    fn capture(&self, index: usize) -> Result<RgbaImage> {
+        let screens = self.screens();
+        let screen = screens
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("屏幕 #{} 不存在", index))?;
+        screen.capture()
+    }
+}
+
+/// 生成合成图像：(屏幕序号, 该屏幕第几次被截取) -> 图像
+pub type FrameGenerator = Box<dyn Fn(usize, u64) -> RgbaImage + Send + Sync>;
+
+/// 合成截图来源，不依赖显示器
+pub struct SyntheticSource {
+    displays: Vec<DisplayBounds>,
+    generator: FrameGenerator,
+    /// 每个屏幕已被截取的次数
+    counters: Vec<AtomicU64>,
+}
+
+impl SyntheticSource {
+    /// 按屏幕布局和图像生成函数创建
+    ///
+    /// 生成的图像尺寸应为逻辑尺寸乘以缩放比例，与真实截图一致
+    pub fn new(
+        displays: Vec<DisplayBounds>,
+        generator: impl Fn(usize, u64) -> RgbaImage + Send + Sync + 'static,
+    ) -> Self {
+        let counters = displays.iter().map(|_| AtomicU64::new(0)).collect();
+        Self {
+            displays,
+            generator: Box::new(generator),
+            counters,
+        }
+    }
+
+    /// 单个主屏幕，每次截取都返回同一纯色画面
+    pub fn solid(width: u32, height: u32, color: image::Rgba<u8>) -> Self {
+        Self::new(
+            vec![DisplayBounds {
+                id: 0,
+                x: 0,
+                y: 0,
+                width,
+                height,
+                scale_factor: 1.0,
+                is_primary: true,
+            }],
+            move |_, _| RgbaImage::from_pixel(width, height, color),
+        )
+    }
+
+    /// 第 index 个屏幕已被截取的次数
+    pub fn captures(&self, index: usize) -> u64 {
+        self.counters
+            .get(index)
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+}
+
+impl FrameSource for SyntheticSource {
+    fn displays(&self) -> Vec<DisplayBounds> {
+        self.displays.clone()
+    }
+
+    fn capture(&self, index: usize) -> Result<RgbaImage> {
+        let counter = self
+            .counters
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("屏幕 #{} 不存在", index))?;
+        let round = counter.fetch_add(1, Ordering::Relaxed);
+        Ok((self.generator)(index, round))
+    }
+}
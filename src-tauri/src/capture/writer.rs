@@ -0,0 +1,137 @@
+// 截图写盘队列 - 把编码好的截图异步写入磁盘
+//
+// 数据目录在 NAS、U 盘等慢速磁盘上时，同步写文件会拖慢截屏循环。
+// 截屏只负责把编码后的数据放进有界队列，由后台任务按顺序写盘；
+// 队列满时丢弃最早的一帧（保证最新画面可用），并记录丢弃数量。
+// 写入成功后帧才加入当前会话，避免后续处理读到尚未落盘的文件
+
+use super::ScreenFrame;
+use crate::domains::telemetry::{self, Metric};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, trace, warn};
+
+/// 默认队列容量（帧）
+pub const DEFAULT_QUEUE_CAPACITY: usize = 8;
+
+/// 等待写盘的帧
+struct PendingFrame {
+    frame: ScreenFrame,
+    data: Vec<u8>,
+}
+
+/// 上报写盘队列长度
+fn report_depth(len: usize) {
+    telemetry::set(Metric::QueueDepth, &[("queue", "frame_write")], len as f64);
+}
+
+/// 有界写盘队列
+pub struct FrameWriter {
+    queue: std::sync::Mutex<VecDeque<PendingFrame>>,
+    capacity: usize,
+    notify: Notify,
+    /// 因队列满被丢弃的帧数
+    dropped: AtomicU64,
+    /// 写盘失败的帧数
+    failed: AtomicU64,
+}
+
+impl FrameWriter {
+    /// 创建写盘队列并启动后台写盘任务，写入成功的帧追加到 session
+    pub fn spawn(capacity: usize, session: Arc<Mutex<Vec<ScreenFrame>>>) -> Arc<Self> {
+        let writer = Arc::new(Self {
+            queue: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+            notify: Notify::new(),
+            dropped: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        });
+
+        let worker = writer.clone();
+        tokio::spawn(async move {
+            loop {
+                let next = {
+                    let mut queue = worker.queue.lock().unwrap();
+                    let next = queue.pop_front();
+                    report_depth(queue.len());
+                    next
+                };
+                let Some(pending) = next else {
+                    worker.notify.notified().await;
+                    continue;
+                };
+
+                match tokio::fs::write(&pending.frame.file_path, &pending.data).await {
+                    Ok(()) => {
+                        trace!("截图已写盘: {}", pending.frame.file_path);
+                        session.lock().await.push(pending.frame);
+                    }
+                    Err(e) => {
+                        worker.failed.fetch_add(1, Ordering::Relaxed);
+                        error!("截图写盘失败 {}: {}", pending.frame.file_path, e);
+                    }
+                }
+            }
+        });
+
+        writer
+    }
+
+    /// 加入写盘队列；队列已满时丢弃最早的一帧
+    pub fn enqueue(&self, frame: ScreenFrame, data: Vec<u8>) {
+        let dropped = {
+            let mut queue = self.queue.lock().unwrap();
+            let dropped = push_bounded(&mut queue, self.capacity, PendingFrame { frame, data });
+            report_depth(queue.len());
+            dropped
+        };
+        if let Some(dropped) = dropped {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            telemetry::inc(Metric::FramesDropped, &[("reason", "write_queue_full")]);
+            warn!("写盘队列已满，丢弃最早的截图: {}", dropped.frame.file_path);
+        }
+        self.notify.notify_one();
+    }
+
+    /// 因队列满被丢弃的帧数
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 写盘失败的帧数
+    pub fn failed_frames(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// 当前排队等待写盘的帧数
+    pub fn pending_frames(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+}
+
+/// 追加到有界队列，超出容量时弹出并返回最早的元素
+fn push_bounded<T>(queue: &mut VecDeque<T>, capacity: usize, item: T) -> Option<T> {
+    let dropped = if queue.len() >= capacity {
+        queue.pop_front()
+    } else {
+        None
+    };
+    queue.push_back(item);
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_bounded_drops_oldest() {
+        let mut queue = VecDeque::new();
+        assert_eq!(push_bounded(&mut queue, 2, 1), None);
+        assert_eq!(push_bounded(&mut queue, 2, 2), None);
+        assert_eq!(push_bounded(&mut queue, 2, 3), Some(1));
+        assert_eq!(queue, VecDeque::from(vec![2, 3]));
+    }
+}
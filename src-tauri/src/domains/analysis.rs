@@ -3,25 +3,166 @@
 // 负责 LLM 分析和视频处理相关的功能
 // 包含 LLMHandle 和 VideoProcessor 两个核心组件
 // 使用Actor模式管理LLM状态，消除锁竞争
+//
+// 长会话分块分析：会话时长超过单次分析上限时，按时间均分为若干块分别分析，
+// 再将各块的时间线卡片按时间拼接（相邻块边界处的同一活动合并为一张卡片）
+//
+// 分析队列：未分析的视频按配置的顺序（最早/最新/今天优先）排队，历史日期可设每轮预算；
+// 用户指定"立即分析"的视频插到队首并唤醒扫描任务
+//
+// 夜间分析：开启后会话照常生成视频，但只在每天的分析时段内提交 LLM 分析，
+// 时段外只处理用户指定"立即分析"的视频，其余视频留到时段内由周期性扫描处理
+//
+// 并发分析：每个工作者使用独立的 LLM Actor（LLM 管理器持有当前视频路径和会话等状态，
+// 不能在多个分析之间共享），第 0 个工作者复用主 LLM Handle。服务商返回限流错误时
+// 全部工作者暂停一段时间，之后只保留一个工作者继续
+//
+// 额度耗尽：服务商返回额度/欠费错误时，重试很快也只会继续失败。此时停止本轮分析，
+// 周期性扫描进入长退避：按服务商在错误中给出的重试时间（没有时从 1 小时起逐次翻倍，最长 24 小时）
+// 暂停自动分析，状态栏显示“额度已用完，X 后重试”；到期后自动再试一次，成功分析即恢复正常
 
-use crate::actors::LLMHandle;
+use super::analysis_hold::AnalysisHold;
+use super::provider_health::ProviderHealthMonitor;
+use crate::actors::{LLMHandle, LLMManagerActor};
+use crate::llm::{LLMManager, TimelineCard};
+use crate::models::{
+    AnalysisOrder, AnalysisQueueSettings, LLMProviderConfig, NightAnalysisSettings,
+};
 use crate::video::processor::VideoProcessor;
-use std::sync::Arc;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
 
 /// 分析领域管理器 - 负责 LLM 分析和视频处理
 #[derive(Clone)]
 pub struct AnalysisDomain {
     llm_handle: LLMHandle,
     video_processor: Arc<VideoProcessor>,
+    /// 用户要求立即分析的视频（按请求顺序）
+    prioritized: Arc<Mutex<Vec<PathBuf>>>,
+    /// 唤醒周期性扫描任务
+    scan_notify: Arc<Notify>,
+    /// 分析前的暂缓登记表
+    analysis_hold: Arc<AnalysisHold>,
+    /// 创建额外 LLM 实例时使用的 HTTP 客户端
+    http_client: reqwest::Client,
+    /// 并发分析时额外的 LLM 实例（第 1 个工作者起使用）
+    extra_workers: Arc<tokio::sync::Mutex<Vec<LLMHandle>>>,
+    /// 触发服务商限流后暂停分析的截止时间
+    rate_limited_until: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// 服务商健康监测
+    provider_health: Arc<ProviderHealthMonitor>,
+    /// 服务商额度耗尽的退避状态
+    quota: Arc<Mutex<Option<QuotaBackoff>>>,
+}
+
+/// 额度耗尽的退避状态
+#[derive(Debug, Clone)]
+struct QuotaBackoff {
+    provider: String,
+    until: DateTime<Utc>,
+    /// 连续遇到额度错误的次数（没有重试时间时据此翻倍退避）
+    strikes: u32,
+    message: String,
 }
 
+/// 额度耗尽状态（显示在状态栏）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LlmQuotaStatus {
+    pub provider: String,
+    /// 恢复尝试的时间（本地时间，YYYY-MM-DD HH:MM:SS）
+    pub until: String,
+    /// 服务商返回的错误
+    pub message: String,
+}
+
+/// 尚未分析的视频（积压面板中可选择立即分析）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UnanalyzedVideo {
+    pub path: String,
+    /// 录制开始时间（YYYY-MM-DD HH:MM，无法从文件名解析时为空）
+    pub start: Option<String>,
+    /// 已在立即分析队列中
+    pub prioritized: bool,
+}
+
+/// 触发限流后暂停分析的时长（秒）
+pub const RATE_LIMIT_BACKOFF_SECS: i64 = 60;
+
+/// 额度耗尽且服务商没有给出重试时间时的首次退避时长（秒），之后逐次翻倍
+pub const QUOTA_BACKOFF_SECS: i64 = 3600;
+
+/// 额度耗尽退避的最长时长（秒）
+pub const MAX_QUOTA_BACKOFF_SECS: i64 = 24 * 3600;
+
 impl AnalysisDomain {
     /// 创建新的分析领域管理器
-    pub fn new(llm_handle: LLMHandle, video_processor: Arc<VideoProcessor>) -> Self {
+    pub fn new(
+        llm_handle: LLMHandle,
+        video_processor: Arc<VideoProcessor>,
+        http_client: reqwest::Client,
+    ) -> Self {
         Self {
             llm_handle,
             video_processor,
+            prioritized: Arc::new(Mutex::new(Vec::new())),
+            scan_notify: Arc::new(Notify::new()),
+            analysis_hold: Arc::new(AnalysisHold::new()),
+            http_client,
+            extra_workers: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            rate_limited_until: Arc::new(Mutex::new(None)),
+            provider_health: Arc::new(ProviderHealthMonitor::new()),
+            quota: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 获取分析暂缓登记表
+    pub fn get_analysis_hold(&self) -> &Arc<AnalysisHold> {
+        &self.analysis_hold
+    }
+
+    /// 将视频插到分析队列最前面，并唤醒扫描任务
+    pub fn prioritize_video(&self, path: PathBuf) {
+        {
+            let mut prioritized = self.prioritized.lock().unwrap();
+            prioritized.retain(|p| p != &path);
+            prioritized.push(path);
         }
+        self.scan_notify.notify_one();
+    }
+
+    /// 从候选视频中取出下一个被优先的视频（不在候选中的请求会被丢弃）
+    pub fn take_prioritized(&self, candidates: &[PathBuf]) -> Option<PathBuf> {
+        let mut prioritized = self.prioritized.lock().unwrap();
+        while !prioritized.is_empty() {
+            let path = prioritized.remove(0);
+            if candidates.contains(&path) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// 等待中的立即分析请求
+    pub fn prioritized_videos(&self) -> Vec<PathBuf> {
+        self.prioritized.lock().unwrap().clone()
+    }
+
+    /// 等待中的立即分析请求数
+    pub fn prioritized_count(&self) -> usize {
+        self.prioritized.lock().unwrap().len()
+    }
+
+    /// 等待立即分析请求（周期性扫描在两次扫描之间调用）
+    pub async fn wait_for_priority(&self) {
+        self.scan_notify.notified().await;
+    }
+
+    /// 获取服务商健康监测器
+    pub fn get_provider_health(&self) -> &Arc<ProviderHealthMonitor> {
+        &self.provider_health
     }
 
     /// 获取 LLM Handle
@@ -33,4 +174,579 @@ impl AnalysisDomain {
     pub fn get_video_processor(&self) -> &Arc<VideoProcessor> {
         &self.video_processor
     }
+
+    /// 获取第 index 个分析工作者使用的 LLM Handle
+    ///
+    /// 额外的实例按需创建并按当前 provider 配置，之后一直复用
+    pub async fn llm_worker(
+        &self,
+        index: usize,
+        provider: &str,
+        llm_config: Option<&LLMProviderConfig>,
+    ) -> anyhow::Result<LLMHandle> {
+        if index == 0 {
+            return Ok(self.llm_handle.clone());
+        }
+
+        let mut workers = self.extra_workers.lock().await;
+        while workers.len() < index {
+            let (actor, handle) = LLMManagerActor::new(LLMManager::new(self.http_client.clone()));
+            tokio::spawn(actor.run());
+            handle.switch_provider(provider).await?;
+            if let (Some(config), "claude") = (llm_config, provider) {
+                handle
+                    .configure_claude(serde_json::json!({
+                        "model": config.model,
+                        "auth_token": config.auth_token,
+                        "base_url": config.base_url
+                    }))
+                    .await?;
+            }
+            if provider.starts_with(crate::llm::external::PLUGIN_PREFIX) {
+                handle
+                    .configure_plugin(
+                        llm_config
+                            .and_then(|config| config.plugin_config.clone())
+                            .unwrap_or_default(),
+                    )
+                    .await?;
+            }
+            workers.push(handle);
+        }
+        Ok(workers[index - 1].clone())
+    }
+
+    /// 创建一个独立的 LLM 实例并按指定服务商配置（不复用、不影响正在进行的分析）
+    pub async fn standalone_llm(
+        &self,
+        provider: &str,
+        llm_config: Option<&LLMProviderConfig>,
+    ) -> anyhow::Result<LLMHandle> {
+        let (actor, handle) = LLMManagerActor::new(LLMManager::new(self.http_client.clone()));
+        tokio::spawn(actor.run());
+        handle.switch_provider(provider).await?;
+        match (provider, llm_config) {
+            ("openai", Some(config)) => {
+                handle
+                    .configure(crate::llm::QwenConfig {
+                        api_key: config.api_key.clone(),
+                        model: config.model.clone(),
+                        base_url: config.base_url.clone(),
+                        use_video_mode: config.use_video_mode,
+                        video_path: None,
+                    })
+                    .await?;
+            }
+            ("claude", Some(config)) => {
+                handle
+                    .configure_claude(serde_json::json!({
+                        "model": config.model,
+                        "auth_token": config.auth_token,
+                        "base_url": config.base_url
+                    }))
+                    .await?;
+            }
+            ("codex", config) => {
+                handle
+                    .configure_codex(
+                        config
+                            .and_then(|config| config.codex_config.clone())
+                            .and_then(|raw| serde_json::from_value(raw).ok())
+                            .unwrap_or_default(),
+                    )
+                    .await?;
+            }
+            (name, config) if name.starts_with(crate::llm::external::PLUGIN_PREFIX) => {
+                handle
+                    .configure_plugin(
+                        config
+                            .and_then(|config| config.plugin_config.clone())
+                            .unwrap_or_default(),
+                    )
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(handle)
+    }
+
+    /// 丢弃额外的 LLM 实例（LLM 配置变更后调用，下次分析时按新配置重建）
+    pub async fn reset_llm_workers(&self) {
+        self.extra_workers.lock().await.clear();
+    }
+
+    /// 记录服务商限流，暂停分析一段时间
+    pub fn note_rate_limited(&self) {
+        let until = crate::storage::local_now() + Duration::seconds(RATE_LIMIT_BACKOFF_SECS);
+        *self.rate_limited_until.lock().unwrap() = Some(until);
+    }
+
+    /// 限流暂停的剩余时间（未限流时为 None）
+    pub fn rate_limit_remaining(&self) -> Option<std::time::Duration> {
+        let until = (*self.rate_limited_until.lock().unwrap())?;
+        (until - crate::storage::local_now()).to_std().ok()
+    }
+
+    /// 记录服务商额度耗尽，返回恢复尝试的时间
+    pub fn note_quota_exhausted(&self, provider: &str, error: &str) -> DateTime<Utc> {
+        let now = crate::storage::local_now();
+        let mut quota = self.quota.lock().unwrap();
+        let strikes = quota
+            .as_ref()
+            .filter(|quota| quota.provider == provider)
+            .map_or(1, |quota| quota.strikes + 1);
+        let until = now + quota_backoff(strikes, parse_retry_after(error));
+        *quota = Some(QuotaBackoff {
+            provider: provider.to_string(),
+            until,
+            strikes,
+            message: error.to_string(),
+        });
+        until
+    }
+
+    /// 分析成功后清除额度耗尽状态
+    pub fn clear_quota_exhausted(&self) {
+        self.quota.lock().unwrap().take();
+    }
+
+    /// 额度耗尽退避的剩余时间（未耗尽或已到重试时间时为 None）
+    pub fn quota_remaining(&self) -> Option<std::time::Duration> {
+        let until = self.quota.lock().unwrap().as_ref()?.until;
+        (until - crate::storage::local_now()).to_std().ok()
+    }
+
+    /// 当前的额度耗尽状态（已到重试时间时为 None）
+    pub fn quota_status(&self) -> Option<LlmQuotaStatus> {
+        self.quota_remaining()?;
+        let quota = self.quota.lock().unwrap().clone()?;
+        Some(LlmQuotaStatus {
+            provider: quota.provider,
+            until: quota.until.format("%Y-%m-%d %H:%M:%S").to_string(),
+            message: quota.message,
+        })
+    }
+}
+
+/// 实际使用的工作者数量（限制在 1 到性能设置中的分析并发上限之间）
+pub fn analysis_worker_count(settings: &AnalysisQueueSettings, max_workers: usize) -> usize {
+    (settings.workers as usize).clamp(1, max_workers.max(1))
+}
+
+/// 当前时间是否允许提交 LLM 分析（未开启夜间分析时总是允许）
+pub fn analysis_allowed_at(settings: &NightAnalysisSettings, now: DateTime<Utc>) -> bool {
+    if !settings.enabled {
+        return true;
+    }
+    hour_in_window(settings.start_hour, settings.end_hour, now.hour() as u8)
+}
+
+/// 小时是否落在 [start_hour, end_hour) 时段内（结束小于开始表示跨越午夜，两者相同表示全天）
+pub fn hour_in_window(start_hour: u8, end_hour: u8, hour: u8) -> bool {
+    let (start, end) = (start_hour % 24, end_hour % 24);
+    match start.cmp(&end) {
+        std::cmp::Ordering::Less => start <= hour && hour < end,
+        std::cmp::Ordering::Greater => hour >= start || hour < end,
+        // 开始与结束相同表示全天
+        std::cmp::Ordering::Equal => true,
+    }
+}
+
+/// 判断 LLM 错误是否为服务商限流
+pub fn is_rate_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "429",
+        "rate limit",
+        "rate_limit",
+        "ratelimit",
+        "too many requests",
+        "throttl",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// 判断 LLM 错误是否为额度耗尽或欠费（与限流不同，短时间内重试不会恢复）
+pub fn is_quota_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "insufficient_quota",
+        "exceeded your current quota",
+        "quota exceeded",
+        "quota_exceeded",
+        "out of quota",
+        "arrearage",
+        "billing",
+        "credit balance",
+        "payment required",
+        "余额不足",
+        "欠费",
+        "额度已用完",
+        "额度不足",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// 从错误信息中解析服务商给出的重试时间（如 "retry after 3600"、"try again in 2h"、"30分钟后重试"）
+pub fn parse_retry_after(message: &str) -> Option<Duration> {
+    static PATTERNS: std::sync::OnceLock<[regex::Regex; 2]> = std::sync::OnceLock::new();
+    let [english, chinese] = PATTERNS.get_or_init(|| {
+        [
+            regex::Regex::new(
+                r"(?i)(?:retry[-_ ]?after|try again in|retry in|resets? in)[\s:=\x22]*(\d+(?:\.\d+)?)\s*(hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s)?\b",
+            )
+            .unwrap(),
+            regex::Regex::new(r"(\d+(?:\.\d+)?)\s*(小时|分钟|秒)后").unwrap(),
+        ]
+    });
+
+    let (value, unit) = if let Some(captures) = english.captures(message) {
+        (
+            captures[1].to_string(),
+            captures.get(2).map(|m| m.as_str().to_lowercase()),
+        )
+    } else {
+        let captures = chinese.captures(message)?;
+        (captures[1].to_string(), Some(captures[2].to_string()))
+    };
+    let value: f64 = value.parse().ok()?;
+    let seconds = match unit.as_deref() {
+        Some(unit) if unit.starts_with('h') || unit == "小时" => value * 3600.0,
+        Some(unit) if unit.starts_with('m') || unit == "分钟" => value * 60.0,
+        // Retry-After 头没有单位，按秒计
+        _ => value,
+    };
+    (seconds > 0.0).then(|| Duration::seconds(seconds.ceil() as i64))
+}
+
+/// 额度耗尽的退避时长：优先使用服务商给出的重试时间，否则从 QUOTA_BACKOFF_SECS 起逐次翻倍
+pub fn quota_backoff(strikes: u32, retry_after: Option<Duration>) -> Duration {
+    let seconds = match retry_after {
+        Some(retry_after) => retry_after.num_seconds(),
+        None => QUOTA_BACKOFF_SECS.saturating_mul(1 << strikes.saturating_sub(1).min(8)),
+    };
+    Duration::seconds(seconds.clamp(RATE_LIMIT_BACKOFF_SECS, MAX_QUOTA_BACKOFF_SECS))
+}
+
+/// 将会话时间窗口均分为不超过 `max_chunk_minutes` 的若干块
+///
+/// 均分而不是按上限依次切分，避免最后一块过短导致分析失败
+pub fn plan_analysis_chunks(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    max_chunk_minutes: u32,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let total_seconds = (end - start).num_seconds();
+    let max_seconds = max_chunk_minutes.max(1) as i64 * 60;
+    if total_seconds <= max_seconds {
+        return vec![(start, end)];
+    }
+
+    let count = (total_seconds + max_seconds - 1) / max_seconds;
+    let chunk_seconds = total_seconds / count;
+    (0..count)
+        .map(|i| {
+            let chunk_start = start + Duration::seconds(chunk_seconds * i);
+            let chunk_end = if i == count - 1 {
+                end
+            } else {
+                chunk_start + Duration::seconds(chunk_seconds)
+            };
+            (chunk_start, chunk_end)
+        })
+        .collect()
+}
+
+/// 时刻是否属于某一块：各块为左闭右开区间，相邻块的边界只归入后一块；最后一块包含会话结束时刻
+pub fn chunk_contains(
+    chunk: (DateTime<Utc>, DateTime<Utc>),
+    is_last: bool,
+    timestamp: DateTime<Utc>,
+) -> bool {
+    let (start, end) = chunk;
+    timestamp >= start && (timestamp < end || (is_last && timestamp == end))
+}
+
+/// 将下一块的时间线卡片拼接到已有卡片之后
+///
+/// 卡片时间需已转换为绝对时间。边界两侧类别和标题都相同的卡片视为同一活动，合并为一张。
+/// 返回下一块的第一张卡片是否并入了已有的最后一张
+pub fn stitch_timeline_cards(merged: &mut Vec<TimelineCard>, next: Vec<TimelineCard>) -> bool {
+    let mut stitched = false;
+    let mut next = next.into_iter();
+    if let Some(last) = merged.last_mut() {
+        if let Some(first) = next.next() {
+            if last.category == first.category
+                && last.subcategory == first.subcategory
+                && last.title == first.title
+            {
+                last.end_time = first.end_time;
+                if let Some(distractions) = first.distractions {
+                    last.distractions
+                        .get_or_insert_with(Vec::new)
+                        .extend(distractions);
+                }
+                stitched = true;
+            } else {
+                merged.push(first);
+            }
+        }
+    }
+    merged.extend(next);
+    stitched
+}
+
+/// 按分析队列设置排列未分析的视频
+///
+/// `videos` 为视频路径及其开始时间（无法从文件名解析时为 None，视为最早）；
+/// 每日预算只限制历史日期，今天的视频和用户要求立即分析的视频（`prioritized`）总是保留，且不占预算
+pub fn order_analysis_queue(
+    mut videos: Vec<(PathBuf, Option<DateTime<Utc>>)>,
+    settings: &AnalysisQueueSettings,
+    today: NaiveDate,
+    prioritized: &[PathBuf],
+) -> Vec<PathBuf> {
+    let is_today = |start: &Option<DateTime<Utc>>| {
+        start
+            .map(|start| start.date_naive() == today)
+            .unwrap_or(false)
+    };
+
+    videos.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    match settings.order {
+        AnalysisOrder::Oldest => {}
+        AnalysisOrder::Newest => videos.reverse(),
+        // 稳定排序：今天的排在前面，各自保持时间顺序
+        AnalysisOrder::TodayFirst => videos.sort_by_key(|(_, start)| !is_today(start)),
+    }
+
+    let mut per_day: HashMap<Option<NaiveDate>, u32> = HashMap::new();
+    videos
+        .into_iter()
+        .filter(|(path, start)| {
+            if settings.per_day_limit == 0 || is_today(start) || prioritized.contains(path) {
+                return true;
+            }
+            let count = per_day.entry(start.map(|s| s.date_naive())).or_insert(0);
+            *count += 1;
+            *count <= settings.per_day_limit
+        })
+        .map(|(path, _)| path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::plugin::AppSites;
+
+    fn queued(name: &str, start: &str) -> (PathBuf, Option<DateTime<Utc>>) {
+        (
+            PathBuf::from(name),
+            Some(
+                DateTime::parse_from_rfc3339(start)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_order_analysis_queue_today_first_with_budget() {
+        let videos = vec![
+            queued("old-1", "2025-10-07T09:00:00Z"),
+            queued("today-2", "2025-10-09T10:00:00Z"),
+            queued("old-2", "2025-10-07T10:00:00Z"),
+            queued("mid-1", "2025-10-08T09:00:00Z"),
+            queued("today-1", "2025-10-09T09:00:00Z"),
+            queued("today-3", "2025-10-09T11:00:00Z"),
+        ];
+        let settings = AnalysisQueueSettings {
+            order: AnalysisOrder::TodayFirst,
+            per_day_limit: 1,
+            workers: 1,
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 10, 9).unwrap();
+        let ordered: Vec<String> = order_analysis_queue(videos.clone(), &settings, today, &[])
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(ordered, ["today-1", "today-2", "today-3", "old-1", "mid-1"]);
+
+        // 要求立即分析的视频不受每日预算限制，也不占用当天的预算
+        let prioritized = [PathBuf::from("old-2")];
+        let ordered: Vec<String> = order_analysis_queue(videos, &settings, today, &prioritized)
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            ordered,
+            ["today-1", "today-2", "today-3", "old-1", "old-2", "mid-1"]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_errors_and_worker_count() {
+        assert!(is_rate_limit_error("HTTP 429 Too Many Requests"));
+        assert!(is_rate_limit_error(
+            "Throttling.RateQuota: Requests rate limit exceeded"
+        ));
+        assert!(!is_rate_limit_error("VIDEO_TOO_SHORT"));
+
+        let mut settings = AnalysisQueueSettings::default();
+        assert_eq!(analysis_worker_count(&settings, 4), 1);
+        settings.workers = 0;
+        assert_eq!(analysis_worker_count(&settings, 4), 1);
+        settings.workers = 9;
+        assert_eq!(analysis_worker_count(&settings, 4), 4);
+        assert_eq!(analysis_worker_count(&settings, 0), 1);
+    }
+
+    #[test]
+    fn test_quota_errors_and_backoff() {
+        assert!(is_quota_error(
+            "429: You exceeded your current quota, please check your plan and billing details"
+        ));
+        assert!(is_quota_error(
+            "Arrearage: Access denied, please make sure your account is in good standing"
+        ));
+        assert!(is_quota_error("账户余额不足"));
+        // 限流不是额度耗尽
+        assert!(!is_quota_error(
+            "Throttling.RateQuota: Requests rate limit exceeded"
+        ));
+
+        assert_eq!(
+            parse_retry_after("quota exceeded, Retry-After: 7200"),
+            Some(Duration::hours(2))
+        );
+        assert_eq!(
+            parse_retry_after("insufficient_quota, please try again in 30m"),
+            Some(Duration::minutes(30))
+        );
+        assert_eq!(
+            parse_retry_after("额度已用完，请 5 小时后重试"),
+            Some(Duration::hours(5))
+        );
+        assert_eq!(parse_retry_after("billing hard limit reached"), None);
+
+        // 服务商给出的时间优先，否则从 1 小时起翻倍，最长 24 小时
+        assert_eq!(
+            quota_backoff(3, Some(Duration::minutes(30))),
+            Duration::minutes(30)
+        );
+        assert_eq!(quota_backoff(1, None), Duration::hours(1));
+        assert_eq!(quota_backoff(3, None), Duration::hours(4));
+        assert_eq!(quota_backoff(10, None), Duration::hours(24));
+        assert_eq!(
+            quota_backoff(1, Some(Duration::seconds(5))),
+            Duration::seconds(60)
+        );
+    }
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::minutes(minutes)
+    }
+
+    fn card(title: &str, start: &str, end: &str) -> TimelineCard {
+        TimelineCard {
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: "work".to_string(),
+            subcategory: "coding".to_string(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: AppSites {
+                primary: "vscode".to_string(),
+                secondary: None,
+            },
+            video_preview_path: None,
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_analysis_allowed_only_inside_night_window() {
+        let at = |hour: u32| {
+            DateTime::parse_from_rfc3339(&format!("2025-10-09T{:02}:30:00Z", hour))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let mut settings = NightAnalysisSettings::default();
+        assert!(analysis_allowed_at(&settings, at(14)));
+
+        settings.enabled = true;
+        let allowed: Vec<u32> = (0..24)
+            .filter(|h| analysis_allowed_at(&settings, at(*h)))
+            .collect();
+        assert_eq!(allowed, [1, 2, 3, 4, 5]);
+
+        // 跨越午夜的时段
+        settings.start_hour = 22;
+        settings.end_hour = 2;
+        let allowed: Vec<u32> = (0..24)
+            .filter(|h| analysis_allowed_at(&settings, at(*h)))
+            .collect();
+        assert_eq!(allowed, [0, 1, 22, 23]);
+    }
+
+    #[test]
+    fn test_plan_analysis_chunks_splits_evenly() {
+        assert_eq!(
+            plan_analysis_chunks(at(0), at(15), 20),
+            vec![(at(0), at(15))]
+        );
+
+        let chunks = plan_analysis_chunks(at(0), at(50), 20);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].0, at(0));
+        assert_eq!(chunks[2].1, at(50));
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0);
+        }
+        assert!(chunks.iter().all(|(s, e)| (*e - *s).num_minutes() >= 16));
+    }
+
+    #[test]
+    fn test_boundary_frames_belong_to_one_chunk() {
+        let chunks = plan_analysis_chunks(at(0), at(50), 20);
+        let last = chunks.len() - 1;
+        for timestamp in [at(0), chunks[0].1, chunks[1].1, at(50)] {
+            let owners = chunks
+                .iter()
+                .enumerate()
+                .filter(|(index, chunk)| chunk_contains(**chunk, *index == last, timestamp))
+                .count();
+            assert_eq!(owners, 1);
+        }
+    }
+
+    #[test]
+    fn test_stitch_merges_same_activity_across_boundary() {
+        let mut merged = vec![card("写代码", "09:00", "09:20")];
+        assert!(stitch_timeline_cards(
+            &mut merged,
+            vec![
+                card("写代码", "09:20", "09:30"),
+                card("开会", "09:30", "09:40"),
+            ],
+        ));
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].end_time, "09:30");
+        assert_eq!(merged[1].title, "开会");
+
+        let mut empty = Vec::new();
+        assert!(!stitch_timeline_cards(
+            &mut empty,
+            vec![card("开会", "09:30", "09:40")]
+        ));
+        assert_eq!(empty.len(), 1);
+    }
 }
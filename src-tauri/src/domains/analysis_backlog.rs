@@ -0,0 +1,157 @@
+// 未分析视频积压提醒 - 等待分析的视频越积越多时及时提醒，而不是几周后才发现
+//
+// 周期性扫描视频目录后统计未分析的视频数，超过阈值时在状态栏显示常驻警告，
+// 并发送系统通知（仍未处理时按间隔重复），同时给出原因和对应的一键处理：
+// - 未配置 API Key：打开设置填写
+// - 服务商不可用或分析反复失败：重试分析
+// - 不再需要这些视频：删除积压（视频及其旁路文件）
+// 积压回落到阈值以下后警告自动消失
+
+use crate::models::AnalysisBacklogSettings;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::{OnceLock, RwLock};
+
+/// 积压原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BacklogCause {
+    /// 未配置 API Key
+    MissingApiKey,
+    /// 服务商持续不可用
+    ProviderUnavailable,
+    /// 分析反复失败
+    AnalysisFailing,
+    /// 原因不明（如夜间分析时段外、分析速度跟不上）
+    Unknown,
+}
+
+/// 积压状态（仅在超过阈值时出现在系统状态中）
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisBacklogStatus {
+    /// 未分析的视频数
+    pub count: usize,
+    pub threshold: usize,
+    /// 最早的未分析视频时间（本地时间，YYYY-MM-DD HH:MM）
+    pub oldest: Option<String>,
+    pub cause: BacklogCause,
+    /// 原因说明
+    pub message: String,
+}
+
+static STATUS: OnceLock<RwLock<Option<AnalysisBacklogStatus>>> = OnceLock::new();
+
+fn status_cell() -> &'static RwLock<Option<AnalysisBacklogStatus>> {
+    STATUS.get_or_init(|| RwLock::new(None))
+}
+
+/// 当前的积压状态（未超过阈值时为 None）
+pub fn current_status() -> Option<AnalysisBacklogStatus> {
+    status_cell()
+        .read()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
+
+pub fn set_current_status(status: Option<AnalysisBacklogStatus>) {
+    if let Ok(mut current) = status_cell().write() {
+        *current = status;
+    }
+}
+
+/// 判断积压原因（按优先级：缺少 API Key > 服务商不可用 > 分析失败）
+pub fn diagnose(
+    missing_api_key: bool,
+    provider_unavailable: Option<String>,
+    last_error: Option<String>,
+) -> (BacklogCause, String) {
+    if missing_api_key {
+        return (
+            BacklogCause::MissingApiKey,
+            "未配置 API Key，录制的视频不会被分析".to_string(),
+        );
+    }
+    if let Some(reason) = provider_unavailable {
+        return (BacklogCause::ProviderUnavailable, reason);
+    }
+    if let Some(error) = last_error {
+        return (
+            BacklogCause::AnalysisFailing,
+            format!("分析反复失败: {}", error),
+        );
+    }
+    (
+        BacklogCause::Unknown,
+        "分析速度跟不上录制（或处于夜间分析时段外）".to_string(),
+    )
+}
+
+/// 积压通知状态机（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct BacklogTracker {
+    /// 本次积压最近一次通知的时间
+    last_notified: Option<DateTime<Utc>>,
+}
+
+impl BacklogTracker {
+    /// 推进一次检查，需要发送通知时返回 true
+    pub fn tick(
+        &mut self,
+        now: DateTime<Utc>,
+        count: usize,
+        settings: &AnalysisBacklogSettings,
+    ) -> bool {
+        if !settings.enabled || count <= settings.threshold {
+            self.last_notified = None;
+            return false;
+        }
+        // 重复间隔为 0 时每次积压只通知一次
+        if let Some(last) = self.last_notified {
+            if settings.repeat_hours == 0
+                || now - last < Duration::hours(settings.repeat_hours as i64)
+            {
+                return false;
+            }
+        }
+        self.last_notified = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::hours(hours)
+    }
+
+    #[test]
+    fn test_notifies_when_backlog_exceeds_threshold() {
+        let settings = AnalysisBacklogSettings {
+            enabled: true,
+            threshold: 20,
+            repeat_hours: 24,
+        };
+        let mut tracker = BacklogTracker::default();
+
+        assert!(!tracker.tick(at(0), 20, &settings));
+        assert!(tracker.tick(at(1), 21, &settings));
+        // 重复间隔内不再通知
+        assert!(!tracker.tick(at(10), 30, &settings));
+        assert!(tracker.tick(at(25), 40, &settings));
+
+        // 回落后重新计算
+        assert!(!tracker.tick(at(26), 5, &settings));
+        assert!(tracker.tick(at(27), 25, &settings));
+
+        let (cause, _) = diagnose(true, Some("服务商不可用".to_string()), None);
+        assert_eq!(cause, BacklogCause::MissingApiKey);
+        let (cause, message) = diagnose(false, None, Some("超时".to_string()));
+        assert_eq!(cause, BacklogCause::AnalysisFailing);
+        assert!(message.contains("超时"));
+    }
+}
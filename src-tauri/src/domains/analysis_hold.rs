@@ -0,0 +1,152 @@
+// 分析暂缓窗口 - 会话分析前留出一段时间让用户选择"不分析"
+//
+// 会话时间窗结束后先登记为待分析，在配置的分钟数内（默认 0，即不暂缓）用户可以
+// 把它标记为不分析，例如误录了敏感工作内容。被跳过的会话只在本地生成视频并创建
+// 一张占位卡片，截图和视频都不会发送给 LLM，也不会同步到远程存储
+// 每个会话在各自的任务中等待，暂缓中的会话不影响之后完成的会话登记
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// 等待分析的会话
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingAnalysis {
+    pub id: u64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub frame_count: usize,
+    /// 到期后开始分析（或跳过）
+    pub deadline: DateTime<Utc>,
+    /// 用户已标记为不分析
+    pub skipped: bool,
+}
+
+/// 暂缓结束后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldDecision {
+    Analyze,
+    Skip,
+}
+
+/// 待分析会话登记表
+pub struct AnalysisHold {
+    next_id: AtomicU64,
+    pending: Mutex<Vec<PendingAnalysis>>,
+    notify: Notify,
+}
+
+impl Default for AnalysisHold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalysisHold {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(Vec::new()),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 登记会话，暂缓到时间窗结束后 hold_minutes 分钟
+    pub fn hold(
+        &self,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        frame_count: usize,
+        hold_minutes: u32,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().push(PendingAnalysis {
+            id,
+            window_start,
+            window_end,
+            frame_count,
+            deadline: window_end + Duration::minutes(hold_minutes as i64),
+            skipped: false,
+        });
+        id
+    }
+
+    /// 当前等待中的会话
+    pub fn list(&self) -> Vec<PendingAnalysis> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// 标记是否跳过分析，返回会话是否仍在等待
+    pub fn set_skipped(&self, id: u64, skipped: bool) -> bool {
+        let found = self
+            .pending
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|p| p.id == id)
+            .map(|p| p.skipped = skipped)
+            .is_some();
+        self.notify.notify_waiters();
+        found
+    }
+
+    /// 立即结束暂缓，返回会话是否仍在等待
+    pub fn release_now(&self, id: u64) -> bool {
+        let found = self
+            .pending
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|p| p.id == id)
+            .map(|p| p.deadline = crate::storage::local_now())
+            .is_some();
+        self.notify.notify_waiters();
+        found
+    }
+
+    /// 等待暂缓结束，移出登记表并返回处理方式
+    pub async fn wait(&self, id: u64) -> HoldDecision {
+        loop {
+            let notified = self.notify.notified();
+            let remaining = {
+                let mut pending = self.pending.lock().unwrap();
+                let Some(index) = pending.iter().position(|p| p.id == id) else {
+                    return HoldDecision::Analyze;
+                };
+                let remaining = pending[index].deadline - crate::storage::local_now();
+                if remaining <= Duration::zero() {
+                    let entry = pending.remove(index);
+                    return if entry.skipped {
+                        HoldDecision::Skip
+                    } else {
+                        HoldDecision::Analyze
+                    };
+                }
+                remaining
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(remaining.to_std().unwrap_or_default()) => {}
+                _ = notified => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_skipped_session_is_released_as_skip() {
+        let hold = AnalysisHold::new();
+        let end = crate::storage::local_now();
+        let id = hold.hold(end - Duration::minutes(15), end, 10, 5);
+        assert!(hold.set_skipped(id, true));
+        assert!(hold.release_now(id));
+        assert_eq!(hold.wait(id).await, HoldDecision::Skip);
+        assert!(hold.list().is_empty());
+    }
+}
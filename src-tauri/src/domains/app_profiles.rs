@@ -0,0 +1,259 @@
+// 应用画像 - 用户自定义的应用/网站分类规则
+//
+// 用户可以将特定应用或域名标记为"始终高效"或"始终干扰"：
+// - 主要应用命中规则时覆盖 LLM 给出的类别（高效 → work，干扰 → personal）
+// - 次要应用命中干扰规则时补充到卡片的干扰活动列表，供干扰检测使用
+// 新生成的卡片在保存前应用规则，历史卡片可通过 reclassify_history 命令重新分类；
+// 重新分类会直接改写卡片的类别，执行前先以 dry_run 预览会改动哪些类别
+
+use crate::llm::plugin::Distraction;
+use crate::models::{AppProfileKind, AppProfileRule};
+use crate::storage::{Database, TimelineCardRecord};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// 高效应用覆盖后的类别
+const PRODUCTIVE_CATEGORY: &str = "work";
+/// 干扰应用覆盖后的类别
+const DISTRACTION_CATEGORY: &str = "personal";
+
+/// 查找匹配应用的规则（不区分大小写的子串匹配，先定义的规则优先）
+pub fn classify_app<'a>(app: &str, rules: &'a [AppProfileRule]) -> Option<&'a AppProfileRule> {
+    let app = app.trim().to_lowercase();
+    if app.is_empty() {
+        return None;
+    }
+    rules.iter().find(|rule| {
+        let pattern = rule.pattern.trim().to_lowercase();
+        !pattern.is_empty() && app.contains(&pattern)
+    })
+}
+
+/// 对时间线卡片应用分类规则，返回卡片是否被修改
+pub fn apply_app_profiles(card: &mut TimelineCardRecord, rules: &[AppProfileRule]) -> bool {
+    if rules.is_empty() {
+        return false;
+    }
+
+    let app_sites: serde_json::Value =
+        serde_json::from_str(&card.app_sites).unwrap_or(serde_json::Value::Null);
+    let primary = app_sites["primary"].as_str().unwrap_or_default();
+    let secondary: Vec<&str> = app_sites["secondary"]
+        .as_array()
+        .map(|apps| apps.iter().filter_map(|a| a.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut changed = false;
+
+    // 1. 主要应用命中规则：覆盖类别
+    if let Some(rule) = classify_app(primary, rules) {
+        let category = match rule.kind {
+            AppProfileKind::Productive => PRODUCTIVE_CATEGORY,
+            AppProfileKind::Distraction => DISTRACTION_CATEGORY,
+        };
+        if card.category != category {
+            card.category = category.to_string();
+            changed = true;
+        }
+    }
+
+    // 2. 次要应用命中干扰规则：补充干扰活动
+    let mut distractions: Vec<Distraction> = card
+        .distractions
+        .as_deref()
+        .and_then(|d| serde_json::from_str(d).ok())
+        .unwrap_or_default();
+    let mut distractions_changed = false;
+
+    for app in secondary {
+        let Some(rule) = classify_app(app, rules) else {
+            continue;
+        };
+        if rule.kind != AppProfileKind::Distraction {
+            continue;
+        }
+        let title = format!("使用了 {}", app);
+        if distractions.iter().any(|d| d.title == title) {
+            continue;
+        }
+        distractions.push(Distraction {
+            start_time: card.start_time.clone(),
+            end_time: card.end_time.clone(),
+            title,
+            summary: format!("命中干扰应用规则: {}", rule.pattern),
+            video_summary_url: None,
+        });
+        distractions_changed = true;
+    }
+
+    if distractions_changed {
+        card.distractions = serde_json::to_string(&distractions).ok();
+        changed = true;
+    }
+
+    changed
+}
+
+/// 一种类别改动
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryChange {
+    pub from: String,
+    pub to: String,
+    pub cards: usize,
+}
+
+/// 历史卡片重新分类报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReclassifyReport {
+    pub dry_run: bool,
+    pub scanned_cards: usize,
+    /// 需要修改（dry_run 时为可以修改）的卡片数
+    pub updated_cards: usize,
+    /// 类别改动
+    pub category_changes: Vec<CategoryChange>,
+    /// 补充了干扰活动的卡片数
+    pub distraction_cards: usize,
+}
+
+/// 按规则重新分类所有历史卡片，dry_run 为 false 时写入
+pub async fn reclassify_existing(
+    db: &Database,
+    rules: &[AppProfileRule],
+    dry_run: bool,
+) -> Result<ReclassifyReport, String> {
+    let mut report = ReclassifyReport {
+        dry_run,
+        ..ReclassifyReport::default()
+    };
+    if rules.is_empty() {
+        return Ok(report);
+    }
+
+    let mut changes: BTreeMap<(String, String), usize> = BTreeMap::new();
+    let mut last_id = 0;
+    loop {
+        let cards = db
+            .get_timeline_cards_after(last_id, 500)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        if cards.is_empty() {
+            break;
+        }
+
+        for mut card in cards {
+            let Some(card_id) = card.id else {
+                continue;
+            };
+            last_id = last_id.max(card_id);
+            report.scanned_cards += 1;
+
+            let original_category = card.category.clone();
+            let original_distractions = card.distractions.clone();
+            if !apply_app_profiles(&mut card, rules) {
+                continue;
+            }
+            if card.category != original_category {
+                *changes
+                    .entry((original_category, card.category.clone()))
+                    .or_default() += 1;
+            }
+            if card.distractions != original_distractions {
+                report.distraction_cards += 1;
+            }
+            if !dry_run {
+                db.update_timeline_card_classification(
+                    card_id,
+                    &card.category,
+                    card.distractions.as_deref(),
+                )
+                .await
+                .map_err(|e| format!("更新卡片 {} 失败: {}", card_id, e))?;
+            }
+            report.updated_cards += 1;
+        }
+    }
+
+    report.category_changes = changes
+        .into_iter()
+        .map(|((from, to), cards)| CategoryChange { from, to, cards })
+        .collect();
+    info!(
+        "按应用画像重新分类{}: 扫描 {} 张卡片，{} 张{}",
+        if dry_run { "（仅预览）" } else { "" },
+        report.scanned_cards,
+        report.updated_cards,
+        if dry_run { "可以修改" } else { "已修改" }
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(category: &str, app_sites: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T09:00:00+08:00".to_string(),
+            end_time: "2025-10-09T09:15:00+08:00".to_string(),
+            category: category.to_string(),
+            subcategory: "General".to_string(),
+            title: "title".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: app_sites.to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    fn rules() -> Vec<AppProfileRule> {
+        vec![
+            AppProfileRule {
+                pattern: "github.com".to_string(),
+                kind: AppProfileKind::Productive,
+            },
+            AppProfileRule {
+                pattern: "bilibili".to_string(),
+                kind: AppProfileKind::Distraction,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_primary_app_overrides_category() {
+        let mut c = card("personal", r#"{"primary":"GitHub.com","secondary":null}"#);
+        assert!(apply_app_profiles(&mut c, &rules()));
+        assert_eq!(c.category, "work");
+
+        let mut c = card(
+            "learning",
+            r#"{"primary":"www.bilibili.com","secondary":[]}"#,
+        );
+        assert!(apply_app_profiles(&mut c, &rules()));
+        assert_eq!(c.category, "personal");
+    }
+
+    #[test]
+    fn test_secondary_distraction_added_once() {
+        let mut c = card("work", r#"{"primary":"vscode","secondary":["bilibili"]}"#);
+        assert!(apply_app_profiles(&mut c, &rules()));
+        assert_eq!(c.category, "work");
+        assert!(c.distractions.as_deref().unwrap().contains("bilibili"));
+
+        // 再次应用不会重复添加
+        assert!(!apply_app_profiles(&mut c, &rules()));
+    }
+}
@@ -0,0 +1,285 @@
+// 音频活动（可选）- 每分钟记录麦克风/扬声器是否在使用，不录音也不保存任何音频
+//
+// 开启后每 10 秒检测一次，按分钟合并（该分钟内任一次检测到使用即为使用）写入 audio_presence 表。
+// 会话分析保存卡片时，麦克风在用的分钟数达到卡片时长一半（至少 3 分钟）的卡片归为会议，
+// 即使会议窗口不在屏幕上也能识别通话；会话详情可获取按分钟的布尔序列
+
+use super::meetings::is_meeting_card;
+use super::metrics::parse_series_time;
+use crate::capture::audio::{self, AudioActivity};
+use crate::settings::SettingsManager;
+use crate::storage::{AudioPresenceRecord, Database, TimelineCardRecord};
+use chrono::{DateTime, Duration, DurationRound, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// 检测间隔（秒）
+const POLL_SECONDS: u64 = 10;
+/// 判定为通话的最少麦克风使用分钟数
+const MIN_CALL_MINUTES: usize = 3;
+/// 判定为通话时麦克风使用分钟数占卡片时长的最低比例
+const CALL_MINUTES_RATIO: f64 = 0.5;
+/// 归为通话时使用的类别
+const CALL_CATEGORY: &str = "meeting";
+
+/// 一分钟的音频活动
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioMinute {
+    /// 分钟 (HH:MM)
+    pub minute: String,
+    pub mic_active: bool,
+    pub speaker_active: bool,
+}
+
+/// 会话的音频活动序列
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAudioPresence {
+    pub session_id: i64,
+    pub minutes: Vec<AudioMinute>,
+    pub mic_minutes: usize,
+    pub speaker_minutes: usize,
+}
+
+fn minute_of(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::minutes(1)).unwrap_or(time)
+}
+
+/// 按分钟合并检测结果
+#[derive(Debug, Default)]
+pub struct MinuteAccumulator {
+    current: Option<AudioPresenceRecord>,
+}
+
+impl MinuteAccumulator {
+    /// 记录一次检测结果，需要写入时（进入新的一分钟，或本分钟内首次检测到使用）返回该分钟的记录
+    pub fn add(
+        &mut self,
+        now: DateTime<Utc>,
+        activity: AudioActivity,
+    ) -> Option<AudioPresenceRecord> {
+        let minute = minute_of(now);
+        let mic = activity.mic_active.unwrap_or(false);
+        let speaker = activity.speaker_active.unwrap_or(false);
+        match &mut self.current {
+            Some(current) if current.minute == minute => {
+                let changed = (mic && !current.mic_active) || (speaker && !current.speaker_active);
+                current.mic_active |= mic;
+                current.speaker_active |= speaker;
+                changed.then(|| current.clone())
+            }
+            _ => {
+                let record = AudioPresenceRecord {
+                    id: None,
+                    minute,
+                    mic_active: mic,
+                    speaker_active: speaker,
+                };
+                self.current = Some(record.clone());
+                Some(record)
+            }
+        }
+    }
+}
+
+/// 生成时间范围内逐分钟的序列（没有记录的分钟视为未使用）
+pub fn minute_series(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    records: &[AudioPresenceRecord],
+) -> Vec<AudioMinute> {
+    let by_minute: HashMap<DateTime<Utc>, &AudioPresenceRecord> = records
+        .iter()
+        .map(|record| (record.minute, record))
+        .collect();
+    let mut minutes = Vec::new();
+    let mut minute = minute_of(start);
+    while minute < end {
+        let record = by_minute.get(&minute);
+        minutes.push(AudioMinute {
+            minute: minute.format("%H:%M").to_string(),
+            mic_active: record.is_some_and(|record| record.mic_active),
+            speaker_active: record.is_some_and(|record| record.speaker_active),
+        });
+        minute += Duration::minutes(1);
+    }
+    minutes
+}
+
+/// 麦克风在用的分钟足够多时把卡片归为会议，返回卡片是否被修改
+pub fn mark_call_card(
+    card: &mut TimelineCardRecord,
+    records: &[AudioPresenceRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> bool {
+    if records.is_empty() || is_meeting_card(card) {
+        return false;
+    }
+    let card_start: NaiveDateTime = parse_series_time(&card.start_time, start, end);
+    let card_end: NaiveDateTime = parse_series_time(&card.end_time, start, end);
+    let total_minutes = (card_end - card_start).num_minutes().max(1) as f64;
+    let mic_minutes = records
+        .iter()
+        .filter(|record| record.mic_active)
+        .filter(|record| {
+            let minute = record.minute.naive_utc();
+            card_start <= minute && minute < card_end
+        })
+        .count();
+
+    if mic_minutes < MIN_CALL_MINUTES || (mic_minutes as f64) < total_minutes * CALL_MINUTES_RATIO {
+        return false;
+    }
+    info!(
+        "卡片 {} 期间麦克风使用 {} 分钟，归为会议（原类别 {}）",
+        card.title, mic_minutes, card.category
+    );
+    card.category = CALL_CATEGORY.to_string();
+    true
+}
+
+/// 获取时间范围内的音频活动（读取失败时返回空）
+pub async fn load_records(
+    db: &Database,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<AudioPresenceRecord> {
+    match db.get_audio_presence_between(start, end).await {
+        Ok(records) => records,
+        Err(e) => {
+            warn!("获取音频活动记录失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 获取会话的逐分钟音频活动
+pub async fn get_session_audio_presence(
+    db: &Database,
+    session_id: i64,
+) -> Result<SessionAudioPresence, String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let records = db
+        .get_audio_presence_between(minute_of(session.start_time), session.end_time)
+        .await
+        .map_err(|e| format!("获取音频活动记录失败: {}", e))?;
+    let minutes = minute_series(session.start_time, session.end_time, &records);
+    Ok(SessionAudioPresence {
+        session_id,
+        mic_minutes: minutes.iter().filter(|minute| minute.mic_active).count(),
+        speaker_minutes: minutes
+            .iter()
+            .filter(|minute| minute.speaker_active)
+            .count(),
+        minutes,
+    })
+}
+
+/// 启动音频活动检测任务（未开启时只检查设置）
+pub fn start_detector(settings: Arc<SettingsManager>, db: Arc<Database>) {
+    tokio::spawn(async move {
+        info!("音频活动检测任务已启动");
+        let mut accumulator = MinuteAccumulator::default();
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(POLL_SECONDS)).await;
+
+            if !settings.get().await.audio_presence_enabled.unwrap_or(false) {
+                continue;
+            }
+            let Ok(activity) = tokio::task::spawn_blocking(audio::audio_activity).await else {
+                continue;
+            };
+            let Some(record) = accumulator.add(crate::storage::local_now(), activity) else {
+                continue;
+            };
+            if let Err(e) = db.upsert_audio_presence(&record).await {
+                error!("保存音频活动记录失败: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mic_minutes_mark_card_as_meeting() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let active = AudioActivity {
+            mic_active: Some(true),
+            speaker_active: None,
+        };
+
+        let mut accumulator = MinuteAccumulator::default();
+        let first = accumulator
+            .add(start + Duration::seconds(5), AudioActivity::default())
+            .unwrap();
+        assert!(!first.mic_active);
+        assert!(accumulator
+            .add(start + Duration::seconds(15), AudioActivity::default())
+            .is_none());
+        assert!(
+            accumulator
+                .add(start + Duration::seconds(25), active)
+                .unwrap()
+                .mic_active
+        );
+        assert!(accumulator
+            .add(start + Duration::seconds(35), active)
+            .is_none());
+
+        // 10:04 - 10:09 麦克风在用
+        let records: Vec<AudioPresenceRecord> = (4..10)
+            .map(|minute| AudioPresenceRecord {
+                id: None,
+                minute: start + Duration::minutes(minute),
+                mic_active: true,
+                speaker_active: false,
+            })
+            .collect();
+        let series = minute_series(start, start + Duration::minutes(12), &records);
+        assert_eq!(series.len(), 12);
+        assert_eq!(series[4].minute, "10:04");
+        assert!(series[4].mic_active && !series[3].mic_active);
+
+        let card = |start_time: &str, end_time: &str| TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: start_time.to_string(),
+            end_time: end_time.to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: "编码".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let end = start + Duration::minutes(30);
+        let mut call = card("04:00", "12:00");
+        assert!(mark_call_card(&mut call, &records, start, end));
+        assert_eq!(call.category, "meeting");
+        let mut coding = card("00:00", "20:00");
+        assert!(!mark_call_card(&mut coding, &records, start, end));
+        assert_eq!(coding.category, "work");
+    }
+}
@@ -0,0 +1,103 @@
+// 离开模式 - 休假等长时间不在电脑前时停止截屏、不发送暂停提醒，可选暂停自动清理
+//
+// 进入离开模式时暂停截屏并发布原因为 away 的 CapturePaused 事件，离开时段由截屏暂停记录保存；
+// 状态保存在设置中，重启后继续保持。统计平均值（趋势、异常基线）时，
+// 离开时段覆盖半天以上的日期视为离开日，不计入平均
+
+use super::capture_pauses::{day_bounds, overlap_minutes, PauseReason};
+use crate::storage::{CapturePauseRecord, Database};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeSet;
+
+/// 离开时段覆盖一天中至少这么多分钟时视为离开日
+const AWAY_DAY_MIN_MINUTES: i64 = 12 * 60;
+
+/// 日期范围内（含首尾）的离开日
+pub fn away_days(
+    pauses: &[CapturePauseRecord],
+    start: NaiveDate,
+    end: NaiveDate,
+    now: DateTime<Utc>,
+) -> BTreeSet<NaiveDate> {
+    let away: Vec<&CapturePauseRecord> = pauses
+        .iter()
+        .filter(|pause| PauseReason::parse(&pause.reason) == Some(PauseReason::Away))
+        .collect();
+    start
+        .iter_days()
+        .take_while(|day| *day <= end)
+        .filter(|day| {
+            let (day_start, day_end) = day_bounds(*day);
+            let minutes: i64 = away
+                .iter()
+                .map(|pause| overlap_minutes(pause, day_start, day_end, now))
+                .sum();
+            minutes >= AWAY_DAY_MIN_MINUTES
+        })
+        .collect()
+}
+
+/// 从截屏暂停记录中读取日期范围内的离开日
+pub async fn load_away_days(
+    db: &Database,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<BTreeSet<NaiveDate>, String> {
+    if end < start {
+        return Ok(BTreeSet::new());
+    }
+    let (range_start, _) = day_bounds(start);
+    let (_, range_end) = day_bounds(end);
+    let pauses = db
+        .get_capture_pauses_between(range_start, range_end)
+        .await
+        .map_err(|e| format!("获取离开记录失败: {}", e))?;
+    Ok(away_days(&pauses, start, end, crate::storage::local_now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn pause(start: &str, end: Option<&str>, reason: PauseReason) -> CapturePauseRecord {
+        CapturePauseRecord {
+            id: None,
+            start_time: at(start),
+            end_time: end.map(at),
+            reason: reason.as_str().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_away_days_need_half_a_day_of_away_pause() {
+        let pauses = vec![
+            // 10-01 18:00 出发，10-04 10:00 回来
+            pause(
+                "2025-10-01T18:00:00Z",
+                Some("2025-10-04T10:00:00Z"),
+                PauseReason::Away,
+            ),
+            // 锁屏不算离开
+            pause(
+                "2025-10-05T00:00:00Z",
+                Some("2025-10-05T23:00:00Z"),
+                PauseReason::ScreenLocked,
+            ),
+            // 仍在离开中（未结束的记录按 now 计算）
+            pause("2025-10-06T06:00:00Z", None, PauseReason::Away),
+        ];
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+
+        let days = away_days(&pauses, day(1), day(6), at("2025-10-06T20:00:00Z"));
+        assert_eq!(
+            days.into_iter().collect::<Vec<_>>(),
+            vec![day(2), day(3), day(6)]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+// 性能基准测试领域 - 测量截屏、编码、数据库写入和 LLM 往返耗时
+//
+// 供低配机器的用户在调整截图分辨率、质量等设置前了解瓶颈在哪一环。
+// 各项测试互不依赖，某项失败只记录错误，不影响其他项；
+// 测试产生的文件和数据库都放在临时目录，结束后删除，不影响真实数据
+
+use crate::capture::CaptureBenchmark;
+use crate::storage::{DatabaseRepository, Frame, Session, SqliteRepository};
+use crate::video::{VideoConfig, VideoProcessor};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Instant;
+
+/// 截屏测试轮数
+pub const CAPTURE_ROUNDS: usize = 5;
+/// 视频编码测试帧数
+pub const VIDEO_FRAMES: usize = 60;
+/// 数据库写入测试行数
+pub const DATABASE_ROWS: usize = 500;
+
+/// 视频编码测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoBenchmark {
+    pub frames: usize,
+    pub elapsed_ms: u64,
+    /// 每秒编码的帧数
+    pub fps: f64,
+}
+
+/// 数据库写入测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseBenchmark {
+    pub rows: usize,
+    pub elapsed_ms: u64,
+    /// 每秒写入的行数
+    pub rows_per_sec: f64,
+}
+
+/// LLM 往返测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmBenchmark {
+    pub provider: String,
+    pub latency_ms: u64,
+}
+
+/// 基准测试报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BenchmarkReport {
+    pub capture: Option<CaptureBenchmark>,
+    pub video: Option<VideoBenchmark>,
+    pub database: Option<DatabaseBenchmark>,
+    pub llm: Option<LlmBenchmark>,
+    /// 失败的测试项（项目: 错误信息）
+    pub errors: Vec<String>,
+    /// 根据结果给出的调整建议
+    pub suggestions: Vec<String>,
+}
+
+/// 用截屏测试得到的一帧重复生成视频，测量 ffmpeg 编码速度
+pub async fn benchmark_video(
+    processor: &VideoProcessor,
+    sample_jpeg: &[u8],
+    work_dir: &Path,
+) -> Result<VideoBenchmark> {
+    tokio::fs::create_dir_all(work_dir).await?;
+    let result = encode_sample_video(processor, sample_jpeg, work_dir).await;
+    let _ = tokio::fs::remove_dir_all(work_dir).await;
+    result
+}
+
+async fn encode_sample_video(
+    processor: &VideoProcessor,
+    sample_jpeg: &[u8],
+    work_dir: &Path,
+) -> Result<VideoBenchmark> {
+    let mut frame_paths = Vec::with_capacity(VIDEO_FRAMES);
+    for index in 0..VIDEO_FRAMES {
+        let path = work_dir.join(format!("{:03}.jpg", index));
+        tokio::fs::write(&path, sample_jpeg).await?;
+        frame_paths.push(path.to_string_lossy().to_string());
+    }
+
+    let config = VideoConfig::default();
+    let output_path = work_dir.join(format!("benchmark.{}", config.format.extension()));
+    let started = Instant::now();
+    processor
+        .create_summary_video(frame_paths, &output_path, &config)
+        .await?;
+    let elapsed = started.elapsed();
+
+    Ok(VideoBenchmark {
+        frames: VIDEO_FRAMES,
+        elapsed_ms: elapsed.as_millis() as u64,
+        fps: per_second(VIDEO_FRAMES, elapsed.as_secs_f64()),
+    })
+}
+
+/// 在临时 SQLite 数据库中批量写入帧记录，测量数据目录所在磁盘的写入速度
+pub async fn benchmark_database(work_dir: &Path) -> Result<DatabaseBenchmark> {
+    tokio::fs::create_dir_all(work_dir).await?;
+    let db_path = work_dir.join("benchmark.db");
+    let result = write_sample_rows(&db_path.to_string_lossy()).await;
+    let _ = tokio::fs::remove_dir_all(work_dir).await;
+    result
+}
+
+async fn write_sample_rows(db_path: &str) -> Result<DatabaseBenchmark> {
+    let repo = SqliteRepository::new(db_path).await?;
+    let now = crate::storage::local_now();
+    let session_id = repo
+        .insert_session(&Session {
+            id: None,
+            start_time: now,
+            end_time: now,
+            title: "benchmark".to_string(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        })
+        .await?;
+
+    let frames: Vec<Frame> = (0..DATABASE_ROWS)
+        .map(|index| Frame {
+            id: None,
+            session_id,
+            timestamp: now,
+            file_path: format!("{}.jpg", index),
+            metadata: None,
+        })
+        .collect();
+
+    let started = Instant::now();
+    repo.insert_frames(&frames).await?;
+    let elapsed = started.elapsed();
+    repo.get_pool().close().await;
+
+    Ok(DatabaseBenchmark {
+        rows: DATABASE_ROWS,
+        elapsed_ms: elapsed.as_millis() as u64,
+        rows_per_sec: per_second(DATABASE_ROWS, elapsed.as_secs_f64()),
+    })
+}
+
+fn per_second(count: usize, seconds: f64) -> f64 {
+    if seconds > 0.0 {
+        count as f64 / seconds
+    } else {
+        count as f64
+    }
+}
+
+/// 根据测试结果给出调整建议
+pub fn suggestions(report: &BenchmarkReport) -> Vec<String> {
+    let mut suggestions = Vec::new();
+
+    if let Some(capture) = &report.capture {
+        if capture.avg_capture_ms + capture.avg_encode_ms > 1000.0 {
+            suggestions.push("单帧截屏加编码超过 1 秒，建议降低截图分辨率".to_string());
+        } else if capture.avg_encode_ms > 300.0 {
+            suggestions.push("JPEG 编码较慢，建议降低图片质量或关闭自适应质量".to_string());
+        }
+    }
+    if let Some(video) = &report.video {
+        if video.fps < 30.0 {
+            suggestions.push(
+                "视频编码低于 30 帧/秒，会话分析前的视频生成会较慢，建议降低截图分辨率".to_string(),
+            );
+        }
+    }
+    if let Some(database) = &report.database {
+        if database.rows_per_sec < 500.0 {
+            suggestions
+                .push("数据库写入较慢，数据目录可能位于慢速磁盘（网络共享、U 盘等）".to_string());
+        }
+    }
+    if let Some(llm) = &report.llm {
+        if llm.latency_ms > 10_000 {
+            suggestions.push("LLM 响应超过 10 秒，建议检查网络或更换更快的模型".to_string());
+        }
+    }
+
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions_flag_slow_stages() {
+        let mut report = BenchmarkReport {
+            video: Some(VideoBenchmark {
+                frames: 60,
+                elapsed_ms: 6000,
+                fps: 10.0,
+            }),
+            database: Some(DatabaseBenchmark {
+                rows: 500,
+                elapsed_ms: 50,
+                rows_per_sec: 10_000.0,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(suggestions(&report).len(), 1);
+
+        report.video.as_mut().unwrap().fps = 120.0;
+        assert!(suggestions(&report).is_empty());
+    }
+}
@@ -0,0 +1,498 @@
+// BI 数据集导出 - 按星型模型导出一组 CSV，供 Metabase、Power BI 等工具导入
+//
+// 事实表 cards（每张时间线卡片一行）通过 session_id / category_key / app_key / date
+// 关联维度表 sessions、categories、apps、days。card_id 由设备、会话开始时间和卡片开始时间
+// 计算得到，重复导出同一数据时保持不变，便于 BI 工具增量刷新。
+// 同时写出 data_dictionary.csv 说明每个文件的每一列。
+// 隐私敏感度高于设置值的卡片不导出。
+// 只导出 CSV（UTF-8、逗号分隔、首行为列名），Parquet 可在 BI 工具中由 CSV 转换
+
+use super::live::FOCUS_PRODUCTIVITY;
+use super::metrics::{category_productivity, parse_series_time, StatsPeriod};
+use crate::models::PrivacyLevel;
+use crate::storage::{Database, Session, TimelineCardRecord};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+/// 列定义：列名、类型、说明
+type Column = (&'static str, &'static str, &'static str);
+
+const SESSION_COLUMNS: &[Column] = &[
+    ("session_id", "integer", "会话 ID（主键）"),
+    ("date", "date", "会话开始日期（关联 days.date）"),
+    ("device_name", "text", "采集设备名称"),
+    ("start_time", "datetime", "开始时间（本地时间）"),
+    ("end_time", "datetime", "结束时间（本地时间）"),
+    ("duration_minutes", "integer", "会话时长（分钟）"),
+    ("title", "text", "会话标题"),
+    ("summary", "text", "会话摘要"),
+    ("card_count", "integer", "会话内的卡片数"),
+];
+
+const CARD_COLUMNS: &[Column] = &[
+    ("card_id", "text", "卡片 ID（主键，重复导出时保持不变）"),
+    ("source_card_id", "integer", "数据库中的卡片 ID"),
+    (
+        "session_id",
+        "integer",
+        "所属会话（关联 sessions.session_id）",
+    ),
+    ("date", "date", "卡片开始日期（关联 days.date）"),
+    ("start_time", "datetime", "开始时间（本地时间）"),
+    ("end_time", "datetime", "结束时间（本地时间）"),
+    ("duration_minutes", "integer", "卡片时长（分钟）"),
+    (
+        "category_key",
+        "text",
+        "活动类别（关联 categories.category_key）",
+    ),
+    ("subcategory", "text", "子类别"),
+    (
+        "app_key",
+        "text",
+        "主要应用/网站（关联 apps.app_key，未知时为空）",
+    ),
+    ("title", "text", "卡片标题"),
+    ("summary", "text", "卡片摘要"),
+    ("confidence", "decimal", "卡片置信度（0-1，未评估时为空）"),
+    (
+        "review_status",
+        "text",
+        "复核状态（pending/confirmed/corrected，无需复核时为空）",
+    ),
+];
+
+const CATEGORY_COLUMNS: &[Column] = &[
+    ("category_key", "text", "活动类别（主键，小写）"),
+    ("productivity", "decimal", "生产力强度（0-1）"),
+    ("is_focus", "boolean", "是否计入专注时长"),
+    ("card_count", "integer", "导出范围内的卡片数"),
+    ("total_minutes", "integer", "导出范围内的总时长（分钟）"),
+];
+
+const APP_COLUMNS: &[Column] = &[
+    ("app_key", "text", "应用/网站（主键，小写）"),
+    ("app_name", "text", "首次出现时的原始名称"),
+    ("card_count", "integer", "导出范围内作为主要应用的卡片数"),
+    ("total_minutes", "integer", "导出范围内的总时长（分钟）"),
+];
+
+const DAY_COLUMNS: &[Column] = &[
+    ("date", "date", "日期（主键）"),
+    ("weekday", "integer", "星期（1=周一，7=周日）"),
+    ("session_count", "integer", "会话数"),
+    ("card_count", "integer", "卡片数"),
+    ("tracked_minutes", "integer", "卡片总时长（分钟）"),
+    ("focus_minutes", "integer", "专注类别的时长（分钟）"),
+    ("top_category", "text", "时长最多的类别"),
+];
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BiExportReport {
+    pub path: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub files: Vec<String>,
+    pub session_count: usize,
+    pub card_count: usize,
+    /// 因隐私敏感度被排除的卡片数
+    pub privacy_excluded: usize,
+}
+
+/// 一个导出文件
+struct Table {
+    name: &'static str,
+    description: &'static str,
+    columns: &'static [Column],
+    rows: Vec<Vec<String>>,
+}
+
+/// CSV 字段转义（包含逗号、引号或换行时用双引号包裹）
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = header.join(",");
+    out.push('\n');
+    for row in rows {
+        let line: Vec<String> = row.iter().map(|value| csv_field(value)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// 稳定的卡片 ID
+fn card_key(session: &Session, card: &TimelineCardRecord) -> String {
+    let source = format!(
+        "{}|{}|{}",
+        session.device_name.as_deref().unwrap_or_default(),
+        session.start_time.format("%Y-%m-%dT%H:%M:%S"),
+        card.start_time
+    );
+    Sha256::digest(source.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 卡片的主要应用
+fn primary_app(card: &TimelineCardRecord) -> String {
+    serde_json::from_str::<serde_json::Value>(&card.app_sites)
+        .ok()
+        .and_then(|value| value["primary"].as_str().map(|app| app.trim().to_string()))
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct Totals {
+    card_count: usize,
+    minutes: i64,
+}
+
+#[derive(Default)]
+struct DayTotals {
+    session_count: usize,
+    card_count: usize,
+    minutes: i64,
+    focus_minutes: i64,
+    categories: BTreeMap<String, i64>,
+}
+
+/// 由会话和卡片构建全部表
+fn build_tables(sessions: &[(Session, Vec<TimelineCardRecord>)]) -> Vec<Table> {
+    let mut session_rows = Vec::new();
+    let mut card_rows = Vec::new();
+    let mut categories: BTreeMap<String, Totals> = BTreeMap::new();
+    let mut apps: BTreeMap<String, (String, Totals)> = BTreeMap::new();
+    let mut days: BTreeMap<NaiveDate, DayTotals> = BTreeMap::new();
+
+    for (session, cards) in sessions {
+        let session_id = session.id.unwrap_or_default();
+        let session_date = session.start_time.date_naive();
+        days.entry(session_date).or_default().session_count += 1;
+        session_rows.push(vec![
+            session_id.to_string(),
+            session_date.to_string(),
+            session.device_name.clone().unwrap_or_default(),
+            session.start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            session.end_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            (session.end_time - session.start_time)
+                .num_minutes()
+                .to_string(),
+            session.title.clone(),
+            session.summary.clone(),
+            cards.len().to_string(),
+        ]);
+
+        for card in cards {
+            let start = parse_series_time(&card.start_time, session.start_time, session.end_time);
+            let end = parse_series_time(&card.end_time, session.start_time, session.end_time);
+            let minutes = (end - start).num_minutes().max(0);
+            let category = card.category.trim().to_lowercase();
+            let app = primary_app(card);
+            let app_key = app.to_lowercase();
+
+            let totals = categories.entry(category.clone()).or_default();
+            totals.card_count += 1;
+            totals.minutes += minutes;
+            if !app_key.is_empty() {
+                let (_, totals) = apps
+                    .entry(app_key.clone())
+                    .or_insert_with(|| (app.clone(), Totals::default()));
+                totals.card_count += 1;
+                totals.minutes += minutes;
+            }
+            let day = days.entry(start.date()).or_default();
+            day.card_count += 1;
+            day.minutes += minutes;
+            *day.categories.entry(category.clone()).or_insert(0) += minutes;
+            if category_productivity(&category) >= FOCUS_PRODUCTIVITY {
+                day.focus_minutes += minutes;
+            }
+
+            card_rows.push(vec![
+                card_key(session, card),
+                card.id.map(|id| id.to_string()).unwrap_or_default(),
+                session_id.to_string(),
+                start.date().to_string(),
+                start.format("%Y-%m-%d %H:%M:%S").to_string(),
+                end.format("%Y-%m-%d %H:%M:%S").to_string(),
+                minutes.to_string(),
+                category,
+                card.subcategory.clone(),
+                app_key,
+                card.title.clone(),
+                card.summary.clone(),
+                card.confidence
+                    .map(|confidence| format!("{:.2}", confidence))
+                    .unwrap_or_default(),
+                card.review_status.clone().unwrap_or_default(),
+            ]);
+        }
+    }
+
+    let category_rows = categories
+        .into_iter()
+        .map(|(category, totals)| {
+            let productivity = category_productivity(&category);
+            vec![
+                category,
+                format!("{:.2}", productivity),
+                (productivity >= FOCUS_PRODUCTIVITY).to_string(),
+                totals.card_count.to_string(),
+                totals.minutes.to_string(),
+            ]
+        })
+        .collect();
+    let app_rows = apps
+        .into_iter()
+        .map(|(key, (name, totals))| {
+            vec![
+                key,
+                name,
+                totals.card_count.to_string(),
+                totals.minutes.to_string(),
+            ]
+        })
+        .collect();
+    let day_rows = days
+        .into_iter()
+        .map(|(date, totals)| {
+            let top_category = totals
+                .categories
+                .iter()
+                .max_by_key(|(_, minutes)| **minutes)
+                .map(|(category, _)| category.clone())
+                .unwrap_or_default();
+            vec![
+                date.to_string(),
+                date.weekday().number_from_monday().to_string(),
+                totals.session_count.to_string(),
+                totals.card_count.to_string(),
+                totals.minutes.to_string(),
+                totals.focus_minutes.to_string(),
+                top_category,
+            ]
+        })
+        .collect();
+
+    vec![
+        Table {
+            name: "cards",
+            description: "事实表：时间线卡片",
+            columns: CARD_COLUMNS,
+            rows: card_rows,
+        },
+        Table {
+            name: "sessions",
+            description: "维度表：会话",
+            columns: SESSION_COLUMNS,
+            rows: session_rows,
+        },
+        Table {
+            name: "categories",
+            description: "维度表：活动类别",
+            columns: CATEGORY_COLUMNS,
+            rows: category_rows,
+        },
+        Table {
+            name: "apps",
+            description: "维度表：应用/网站",
+            columns: APP_COLUMNS,
+            rows: app_rows,
+        },
+        Table {
+            name: "days",
+            description: "维度表：日期",
+            columns: DAY_COLUMNS,
+            rows: day_rows,
+        },
+    ]
+}
+
+/// 数据字典：每个文件每一列的类型和说明
+fn data_dictionary(tables: &[Table]) -> String {
+    let rows: Vec<Vec<String>> = tables
+        .iter()
+        .flat_map(|table| {
+            table.columns.iter().map(|(column, kind, description)| {
+                vec![
+                    format!("{}.csv", table.name),
+                    table.description.to_string(),
+                    column.to_string(),
+                    kind.to_string(),
+                    description.to_string(),
+                ]
+            })
+        })
+        .collect();
+    to_csv(
+        &["file", "file_description", "column", "type", "description"],
+        &rows,
+    )
+}
+
+/// 导出包含锚点日期的统计周期内的数据到 `dir` 目录，排除敏感度高于 `max_level` 的卡片
+pub async fn export_bi_dataset(
+    db: &Database,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+    dir: &Path,
+    max_level: PrivacyLevel,
+) -> Result<BiExportReport, String> {
+    let (start, end) = period.date_range(anchor);
+    let mut sessions = Vec::new();
+    let mut privacy_excluded = 0;
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let mut day_sessions = db
+            .get_sessions_by_date(&date.to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        day_sessions.sort_by_key(|session| session.start_time);
+        for session in day_sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            let cards = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+            let (cards, excluded) = super::privacy::filter_cards(cards, max_level);
+            privacy_excluded += excluded;
+            sessions.push((session, cards));
+        }
+    }
+
+    let tables = build_tables(&sessions);
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("创建目录 {:?} 失败: {}", dir, e))?;
+    let mut files = Vec::new();
+    for table in &tables {
+        let header: Vec<&str> = table.columns.iter().map(|(name, _, _)| *name).collect();
+        let name = format!("{}.csv", table.name);
+        tokio::fs::write(dir.join(&name), to_csv(&header, &table.rows))
+            .await
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        files.push(name);
+    }
+    let name = "data_dictionary.csv".to_string();
+    tokio::fs::write(dir.join(&name), data_dictionary(&tables))
+        .await
+        .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    files.push(name);
+
+    let card_count = sessions.iter().map(|(_, cards)| cards.len()).sum();
+    info!(
+        "BI 数据集已导出到 {:?}: {} 至 {}，{} 个会话，{} 张卡片",
+        dir,
+        start,
+        end,
+        sessions.len(),
+        card_count
+    );
+    Ok(BiExportReport {
+        path: dir.to_string_lossy().to_string(),
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        files,
+        session_count: sessions.len(),
+        card_count,
+        privacy_excluded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn local(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("{}Z", value))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn card(category: &str, start: &str, end: &str, app: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(7),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: format!("2025-10-09T{}:00+08:00", start),
+            end_time: format!("2025-10-09T{}:00+08:00", end),
+            category: category.to_string(),
+            subcategory: "General".to_string(),
+            title: "写代码, 修 \"bug\"".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: format!(r#"{{"primary":"{}","secondary":null}}"#, app),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_star_schema_tables_and_stable_card_id() {
+        let session = Session {
+            id: Some(1),
+            start_time: local("2025-10-09T09:00:00"),
+            end_time: local("2025-10-09T10:00:00"),
+            title: "编写代码".to_string(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: Some("mac".to_string()),
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let cards = vec![
+            card("work", "09:00", "09:40", "VS Code"),
+            card("entertainment", "09:40", "10:00", "bilibili"),
+        ];
+        let tables = build_tables(&[(session.clone(), cards.clone())]);
+        let table = |name: &str| tables.iter().find(|t| t.name == name).unwrap();
+
+        let card_rows = &table("cards").rows;
+        assert_eq!(card_rows.len(), 2);
+        assert_eq!(card_rows[0].len(), CARD_COLUMNS.len());
+        assert_eq!(card_rows[0][0], card_key(&session, &cards[0]));
+        assert_ne!(card_rows[0][0], card_rows[1][0]);
+        assert_eq!(card_rows[0][6], "40");
+        assert_eq!(card_rows[0][9], "vs code");
+
+        let days = &table("days").rows;
+        assert_eq!(
+            days[0],
+            vec!["2025-10-09", "4", "1", "2", "60", "40", "work"]
+        );
+        assert_eq!(table("categories").rows[1][0], "work");
+        assert_eq!(table("apps").rows.len(), 2);
+
+        let csv = to_csv(&["title"], &[vec![cards[0].title.clone()]]);
+        assert_eq!(csv, "title\n\"写代码, 修 \"\"bug\"\"\"\n");
+        assert!(data_dictionary(&tables).contains("cards.csv,事实表：时间线卡片,card_id,text"));
+    }
+}
@@ -0,0 +1,174 @@
+// 会话书签 - 回看会话视频时标记某个时刻并写下备注（如"这里出现了 bug"）
+//
+// 书签按视频播放位置保存，显示和导出时按视频元数据换算成真实时间：
+// 会话详情中与关键时刻一起列出，单日离线包和分享页中附在对应会话下
+
+use super::day_bundle::BundleBookmark;
+use super::playback::{session_mapping, VideoTimeMapping};
+use crate::settings::SettingsManager;
+use crate::storage::{Database, Session, SessionBookmarkRecord};
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+
+/// 备注最大长度（字符）
+const MAX_NOTE_CHARS: usize = 500;
+
+/// 会话书签（附带换算后的真实时间）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBookmark {
+    pub id: i64,
+    pub session_id: i64,
+    /// 视频播放位置（秒）
+    pub video_time: f64,
+    /// 对应的真实时间 (YYYY-MM-DD HH:MM:SS)
+    pub real_time: String,
+    pub note: String,
+}
+
+/// 视频位置对应的真实时间（不超出会话结束时间）
+fn real_time(session: &Session, mapping: &VideoTimeMapping, video_time: f64) -> NaiveDateTime {
+    let offset = Duration::milliseconds((mapping.video_to_real(video_time) * 1000.0) as i64);
+    (session.start_time + offset)
+        .min(session.end_time)
+        .naive_utc()
+}
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// 整理备注：去掉首尾空白，超长时截断
+fn normalize_note(note: &str) -> String {
+    note.trim().chars().take(MAX_NOTE_CHARS).collect()
+}
+
+/// 添加书签，返回书签 ID
+pub async fn add_bookmark(
+    db: &Database,
+    session_id: i64,
+    video_time: f64,
+    note: &str,
+) -> Result<i64, String> {
+    if !video_time.is_finite() || video_time < 0.0 {
+        return Err("视频位置无效".to_string());
+    }
+    db.get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    db.insert_session_bookmark(&SessionBookmarkRecord {
+        id: None,
+        session_id,
+        video_time,
+        note: normalize_note(note),
+        created_at: crate::storage::local_now(),
+    })
+    .await
+    .map_err(|e| format!("保存书签失败: {}", e))
+}
+
+/// 按视频位置换算书签的真实时间
+pub fn to_bookmarks(
+    session: &Session,
+    records: &[SessionBookmarkRecord],
+    mapping: &VideoTimeMapping,
+) -> Vec<SessionBookmark> {
+    records
+        .iter()
+        .filter_map(|record| {
+            Some(SessionBookmark {
+                id: record.id?,
+                session_id: record.session_id,
+                video_time: record.video_time,
+                real_time: format_time(real_time(session, mapping, record.video_time)),
+                note: record.note.clone(),
+            })
+        })
+        .collect()
+}
+
+/// 获取会话的书签
+pub async fn session_bookmarks(
+    db: &Database,
+    settings: &SettingsManager,
+    session_id: i64,
+) -> Result<Vec<SessionBookmark>, String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let records = db
+        .get_session_bookmarks(session_id)
+        .await
+        .map_err(|e| format!("获取书签失败: {}", e))?;
+    let mapping = session_mapping(&session, settings).await;
+    Ok(to_bookmarks(&session, &records, &mapping))
+}
+
+/// 导出用的书签，`has_video` 为 false 时不附带视频位置
+pub fn bundle_bookmarks(
+    session: &Session,
+    records: &[SessionBookmarkRecord],
+    mapping: &VideoTimeMapping,
+    has_video: bool,
+) -> Vec<BundleBookmark> {
+    records
+        .iter()
+        .map(|record| BundleBookmark {
+            time: format_time(real_time(session, mapping, record.video_time)),
+            note: record.note.clone(),
+            video_secs: has_video.then_some(record.video_time),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::playback::current_mapping;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_bookmark_real_time_follows_video_speed() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(7),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let record = |id, video_time| SessionBookmarkRecord {
+            id: Some(id),
+            session_id: 7,
+            video_time,
+            note: "这里出现了 bug".to_string(),
+            created_at: start,
+        };
+
+        // 8 倍速、每 5 秒取一帧：视频 1 秒 = 真实 40 秒，超出会话的位置按结束时间算
+        let mapping = current_mapping(8.0, 1);
+        let bookmarks = to_bookmarks(&session, &[record(1, 7.5), record(2, 60.0)], &mapping);
+        assert_eq!(bookmarks[0].real_time, "2025-10-09 09:05:00");
+        assert_eq!(bookmarks[1].real_time, "2025-10-09 09:15:00");
+
+        let exported = bundle_bookmarks(&session, &[record(1, 7.5)], &mapping, false);
+        assert_eq!(exported[0].time, "2025-10-09 09:05:00");
+        assert_eq!(exported[0].video_secs, None);
+
+        assert_eq!(
+            normalize_note(&format!("  {}  ", "a".repeat(600))).len(),
+            MAX_NOTE_CHARS
+        );
+    }
+}
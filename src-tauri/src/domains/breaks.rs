@@ -0,0 +1,379 @@
+// 休息提醒引擎
+//
+// 基于实时截屏的画面变化判断用户是否在持续使用屏幕（而不是按挂钟时间计时）：
+// - 画面持续变化累计超过阈值后提醒休息
+// - 屏幕无变化（或锁屏）超过一定时长视为已休息，重新计时
+// - 支持稍后提醒；提醒后继续活动超过稍后提醒时长视为跳过
+// 每次提醒的结果（已休息/跳过）写入数据库，在每日总结的使用模式中统计；阈值在设置中调整
+
+use crate::capture::ScreenCapture;
+use crate::models::BreakReminderSettings;
+use crate::settings::SettingsManager;
+use crate::storage::{BreakRecord, Database};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+/// 屏幕无变化多久仍视为在活动（截屏间隔内的短暂静止不算空闲）
+const ACTIVE_GRACE_SECONDS: i64 = 120;
+/// 检测间隔（秒）
+const TICK_SECONDS: u64 = 30;
+
+/// 引擎在一次检测中产生的动作
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakAction {
+    /// 需要提醒休息（已连续活动的分钟数）
+    Remind { active_minutes: i64 },
+    /// 一次提醒已有结果，需要记录
+    Resolved {
+        reminded_at: DateTime<Utc>,
+        active_minutes: i64,
+        outcome: BreakOutcome,
+        resolved_at: DateTime<Utc>,
+    },
+}
+
+/// 提醒结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakOutcome {
+    Taken,
+    Skipped,
+}
+
+impl BreakOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Taken => "taken",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// 休息状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakStatus {
+    /// 是否启用
+    pub enabled: bool,
+    /// 当前连续活动分钟数
+    pub active_minutes: i64,
+    /// 是否有待处理的提醒
+    pub pending_reminder: bool,
+    /// 下次允许提醒的时间（稍后提醒/跳过后）
+    pub next_reminder_at: Option<String>,
+}
+
+/// 待处理的提醒
+#[derive(Debug, Clone)]
+struct PendingReminder {
+    reminded_at: DateTime<Utc>,
+    active_minutes: i64,
+}
+
+/// 休息跟踪状态机（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct BreakTracker {
+    /// 本轮连续活动的开始时间
+    streak_start: Option<DateTime<Utc>>,
+    /// 最近一次检测到活动的时间
+    last_active: Option<DateTime<Utc>>,
+    /// 待处理的提醒
+    pending: Option<PendingReminder>,
+    /// 在此之前不再提醒
+    quiet_until: Option<DateTime<Utc>>,
+}
+
+impl BreakTracker {
+    /// 推进一次检测
+    ///
+    /// `active` 表示当前是否在使用屏幕
+    pub fn tick(
+        &mut self,
+        now: DateTime<Utc>,
+        active: bool,
+        settings: &BreakReminderSettings,
+    ) -> Vec<BreakAction> {
+        let mut actions = Vec::new();
+        let idle_reset = Duration::minutes(settings.idle_reset_minutes as i64);
+
+        if active {
+            // 距离上次活动已超过休息时长：视为已休息
+            if let Some(last) = self.last_active {
+                if now - last >= idle_reset {
+                    actions.extend(self.finish_break(last));
+                }
+            }
+            if self.streak_start.is_none() {
+                self.streak_start = Some(now);
+            }
+            self.last_active = Some(now);
+        } else if let Some(last) = self.last_active {
+            if now - last >= idle_reset {
+                actions.extend(self.finish_break(last));
+                self.last_active = None;
+            }
+            return actions;
+        } else {
+            return actions;
+        }
+
+        let active_minutes = self.active_minutes(now);
+
+        // 提醒后仍持续活动超过稍后提醒时长：视为跳过
+        if let Some(pending) = &self.pending {
+            if now - pending.reminded_at >= Duration::minutes(settings.snooze_minutes as i64) {
+                actions.push(BreakAction::Resolved {
+                    reminded_at: pending.reminded_at,
+                    active_minutes: pending.active_minutes,
+                    outcome: BreakOutcome::Skipped,
+                    resolved_at: now,
+                });
+                self.pending = None;
+                self.quiet_until = Some(now + Duration::minutes(settings.snooze_minutes as i64));
+            }
+            return actions;
+        }
+
+        let quiet = self.quiet_until.map_or(false, |until| now < until);
+        if active_minutes >= settings.active_minutes_threshold as i64 && !quiet {
+            self.pending = Some(PendingReminder {
+                reminded_at: now,
+                active_minutes,
+            });
+            actions.push(BreakAction::Remind { active_minutes });
+        }
+
+        actions
+    }
+
+    /// 稍后提醒
+    pub fn snooze(&mut self, now: DateTime<Utc>, minutes: u64) {
+        self.pending = None;
+        self.quiet_until = Some(now + Duration::minutes(minutes as i64));
+    }
+
+    /// 主动跳过本次休息
+    pub fn skip(&mut self, now: DateTime<Utc>, snooze_minutes: u64) -> Option<BreakAction> {
+        let pending = self.pending.take()?;
+        self.quiet_until = Some(now + Duration::minutes(snooze_minutes as i64));
+        Some(BreakAction::Resolved {
+            reminded_at: pending.reminded_at,
+            active_minutes: pending.active_minutes,
+            outcome: BreakOutcome::Skipped,
+            resolved_at: now,
+        })
+    }
+
+    /// 当前连续活动分钟数
+    pub fn active_minutes(&self, now: DateTime<Utc>) -> i64 {
+        self.streak_start
+            .map(|start| (now - start).num_minutes())
+            .unwrap_or(0)
+    }
+
+    /// 休息结束：重置计时，如有待处理的提醒则记为已休息
+    fn finish_break(&mut self, break_start: DateTime<Utc>) -> Option<BreakAction> {
+        self.streak_start = None;
+        self.quiet_until = None;
+        self.pending.take().map(|pending| BreakAction::Resolved {
+            reminded_at: pending.reminded_at,
+            active_minutes: pending.active_minutes,
+            outcome: BreakOutcome::Taken,
+            resolved_at: break_start,
+        })
+    }
+}
+
+/// 休息提醒器
+pub struct BreakReminder {
+    capture: Arc<ScreenCapture>,
+    settings: Arc<SettingsManager>,
+    tracker: Mutex<BreakTracker>,
+}
+
+impl BreakReminder {
+    pub fn new(capture: Arc<ScreenCapture>, settings: Arc<SettingsManager>) -> Self {
+        Self {
+            capture,
+            settings,
+            tracker: Mutex::new(BreakTracker::default()),
+        }
+    }
+
+    /// 启动休息提醒任务，需要提醒时调用 `notify`（参数为已连续活动的分钟数）
+    pub fn start<F>(self: Arc<Self>, db: Arc<Database>, notify: F)
+    where
+        F: Fn(i64) + Send + Sync + 'static,
+    {
+        tokio::spawn(async move {
+            info!("休息提醒任务已启动");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(TICK_SECONDS)).await;
+
+                let settings = self.current_settings().await;
+                if !settings.enabled {
+                    continue;
+                }
+
+                let now = crate::storage::local_now();
+                let active = !ScreenCapture::is_screen_locked()
+                    && self.capture.last_activity_at().map_or(false, |at| {
+                        now - at <= Duration::seconds(ACTIVE_GRACE_SECONDS)
+                    });
+
+                let actions = self.tracker.lock().await.tick(now, active, &settings);
+                for action in actions {
+                    match action {
+                        BreakAction::Remind { active_minutes } => {
+                            info!("已连续活动 {} 分钟，提醒休息", active_minutes);
+                            notify(active_minutes);
+                        }
+                        resolved => Self::save_record(&db, resolved).await,
+                    }
+                }
+            }
+        });
+    }
+
+    /// 稍后提醒
+    pub async fn snooze(&self) {
+        let settings = self.current_settings().await;
+        self.tracker
+            .lock()
+            .await
+            .snooze(crate::storage::local_now(), settings.snooze_minutes);
+    }
+
+    /// 跳过本次休息
+    pub async fn skip(&self, db: &Database) {
+        let settings = self.current_settings().await;
+        let action = self
+            .tracker
+            .lock()
+            .await
+            .skip(crate::storage::local_now(), settings.snooze_minutes);
+        if let Some(action) = action {
+            Self::save_record(db, action).await;
+        }
+    }
+
+    /// 获取当前休息状态
+    pub async fn status(&self) -> BreakStatus {
+        let settings = self.current_settings().await;
+        let tracker = self.tracker.lock().await;
+        BreakStatus {
+            enabled: settings.enabled,
+            active_minutes: tracker.active_minutes(crate::storage::local_now()),
+            pending_reminder: tracker.pending.is_some(),
+            next_reminder_at: tracker
+                .quiet_until
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+        }
+    }
+
+    async fn current_settings(&self) -> BreakReminderSettings {
+        self.settings
+            .get()
+            .await
+            .break_reminder_settings
+            .unwrap_or_default()
+    }
+
+    async fn save_record(db: &Database, action: BreakAction) {
+        if let BreakAction::Resolved {
+            reminded_at,
+            active_minutes,
+            outcome,
+            resolved_at,
+        } = action
+        {
+            let (device_name, _) = crate::storage::get_device_info();
+            let record = BreakRecord {
+                id: None,
+                reminded_at,
+                outcome: outcome.as_str().to_string(),
+                active_minutes,
+                resolved_at,
+                device_name: Some(device_name),
+            };
+            if let Err(e) = db.insert_break_record(&record).await {
+                error!("保存休息记录失败: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::minutes(minutes)
+    }
+
+    fn settings() -> BreakReminderSettings {
+        BreakReminderSettings {
+            enabled: true,
+            active_minutes_threshold: 50,
+            idle_reset_minutes: 5,
+            snooze_minutes: 10,
+        }
+    }
+
+    #[test]
+    fn test_remind_after_continuous_activity_then_taken() {
+        let mut tracker = BreakTracker::default();
+        let settings = settings();
+
+        for minute in 0..50 {
+            assert!(tracker.tick(at(minute), true, &settings).is_empty());
+        }
+        assert_eq!(
+            tracker.tick(at(50), true, &settings),
+            vec![BreakAction::Remind { active_minutes: 50 }]
+        );
+
+        // 离开 6 分钟后回来：记为已休息
+        assert!(tracker.tick(at(53), false, &settings).is_empty());
+        let actions = tracker.tick(at(56), false, &settings);
+        assert!(matches!(
+            actions.as_slice(),
+            [BreakAction::Resolved {
+                outcome: BreakOutcome::Taken,
+                ..
+            }]
+        ));
+        assert_eq!(tracker.active_minutes(at(57)), 0);
+    }
+
+    #[test]
+    fn test_skip_when_activity_continues() {
+        let mut tracker = BreakTracker::default();
+        let settings = settings();
+
+        for minute in 0..=50 {
+            tracker.tick(at(minute), true, &settings);
+        }
+        let actions: Vec<_> = (51..=60)
+            .flat_map(|minute| tracker.tick(at(minute), true, &settings))
+            .collect();
+        assert!(matches!(
+            actions.as_slice(),
+            [BreakAction::Resolved {
+                outcome: BreakOutcome::Skipped,
+                ..
+            }]
+        ));
+
+        // 跳过后在稍后提醒时长内不再提醒
+        assert!(tracker.tick(at(65), true, &settings).is_empty());
+        assert_eq!(
+            tracker.tick(at(70), true, &settings),
+            vec![BreakAction::Remind { active_minutes: 70 }]
+        );
+    }
+}
@@ -0,0 +1,397 @@
+// 浏览器扩展伴侣 - 接收扩展实时上报的活动标签页
+//
+// 扩展在切换标签页、页面跳转、窗口获得/失去焦点时向本地服务 POST /browser/tab，
+// 浏览器处于前台期间至少每 30 秒重复上报一次当前标签页作为心跳。
+// 每段标签页停留写入 external_events（来源 browser，metadata 中记录 URL 和域名），
+// 保存帧时据此写入帧的 metadata，时间线卡片的 app_sites 和按域名的统计也以此为准，不依赖画面识别
+// 标签页停留发生在本机屏幕上，时长已计入本机会话，只作为上下文，不计入外部时长和设备统计
+
+use crate::storage::{Database, ExternalEventRecord, TimelineCardRecord};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// 浏览器事件的来源名称
+pub const BROWSER_SOURCE: &str = "browser";
+
+/// 超过该时长没有心跳时，认为标签页在最后一次上报后不久就结束了（扩展被关闭、电脑休眠等）
+const HEARTBEAT_GRACE_SECS: i64 = 60;
+
+/// 短于该时长的停留（快速切换标签页）不记录
+const MIN_SPAN_SECS: i64 = 2;
+
+/// 被视为“浏览器”本身、可以用域名替换的主要应用名称
+const GENERIC_BROWSER_NAMES: &[&str] = &[
+    "browser",
+    "浏览器",
+    "chrome",
+    "google chrome",
+    "edge",
+    "microsoft edge",
+    "firefox",
+    "safari",
+    "arc",
+];
+
+/// 扩展上报的标签页状态
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabUpdate {
+    /// 活动标签页 URL，浏览器失去焦点或没有标签页时为空
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    /// 浏览器窗口是否在前台
+    #[serde(default = "default_focused")]
+    pub focused: bool,
+}
+
+fn default_focused() -> bool {
+    true
+}
+
+/// 按域名的浏览时长
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainStat {
+    pub domain: String,
+    pub minutes: i64,
+    /// 停留次数
+    pub visits: usize,
+}
+
+/// 从 URL 中取出域名（去掉 www. 前缀），非 http(s) 页面返回 None
+pub fn domain_of(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('@')
+        .next()
+        .unwrap_or_default()
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// 当前停留的标签页
+#[derive(Debug, Clone)]
+struct ActiveTab {
+    url: String,
+    title: String,
+    domain: String,
+    since: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+impl ActiveTab {
+    /// 结束停留，生成事件记录（过短的停留返回 None）
+    fn finish(self, now: DateTime<Utc>) -> Option<ExternalEventRecord> {
+        let end = now.min(self.last_seen + Duration::seconds(HEARTBEAT_GRACE_SECS));
+        if (end - self.since).num_seconds() < MIN_SPAN_SECS {
+            return None;
+        }
+        Some(ExternalEventRecord {
+            id: None,
+            source: BROWSER_SOURCE.to_string(),
+            category: BROWSER_SOURCE.to_string(),
+            title: self.title,
+            start_time: self.since,
+            end_time: end,
+            metadata: Some(
+                serde_json::json!({ "url": self.url, "domain": self.domain }).to_string(),
+            ),
+        })
+    }
+}
+
+/// 活动标签页跟踪器
+#[derive(Default)]
+pub struct BrowserTracker {
+    current: Mutex<Option<ActiveTab>>,
+}
+
+impl BrowserTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一次上报，返回需要写入的已结束停留
+    async fn apply(&self, update: TabUpdate, now: DateTime<Utc>) -> Option<ExternalEventRecord> {
+        let next = update
+            .url
+            .filter(|_| update.focused)
+            .and_then(|url| domain_of(&url).map(|domain| (url, domain)));
+
+        let mut current = self.current.lock().await;
+        match (current.as_mut(), next) {
+            // 同一标签页的心跳
+            (Some(tab), Some((url, _))) if tab.url == url && tab.title == update.title.trim() => {
+                tab.last_seen = now;
+                None
+            }
+            (_, next) => {
+                let finished = current.take().and_then(|tab| tab.finish(now));
+                *current = next.map(|(url, domain)| ActiveTab {
+                    url,
+                    title: update.title.trim().to_string(),
+                    domain,
+                    since: now,
+                    last_seen: now,
+                });
+                finished
+            }
+        }
+    }
+
+    /// 处理扩展上报的标签页状态
+    pub async fn update(&self, db: &Database, update: TabUpdate) -> Result<(), String> {
+        if let Some(record) = self.apply(update, crate::storage::local_now()).await {
+            db.insert_external_events(&[record])
+                .await
+                .map_err(|e| format!("保存浏览记录失败: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// 浏览事件中的 URL 和域名
+fn event_target(event: &ExternalEventRecord) -> Option<(String, String)> {
+    let metadata: serde_json::Value = serde_json::from_str(event.metadata.as_deref()?).ok()?;
+    Some((
+        metadata["url"].as_str()?.to_string(),
+        metadata["domain"].as_str()?.to_string(),
+    ))
+}
+
+/// 获取时间范围内的浏览事件
+pub async fn load_browser_events(
+    db: &Database,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<ExternalEventRecord> {
+    match db.get_external_events_between(start, end).await {
+        Ok(events) => events
+            .into_iter()
+            .filter(|event| event.source == BROWSER_SOURCE)
+            .collect(),
+        Err(e) => {
+            warn!("获取浏览记录失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 帧截取时的活动标签页（写入帧的 metadata）
+pub fn frame_metadata(events: &[ExternalEventRecord], timestamp: DateTime<Utc>) -> Option<String> {
+    let event = events
+        .iter()
+        .find(|event| event.start_time <= timestamp && timestamp < event.end_time)?;
+    let (url, domain) = event_target(event)?;
+    Some(
+        serde_json::json!({
+            "browser": { "url": url, "title": event.title, "domain": domain }
+        })
+        .to_string(),
+    )
+}
+
+/// 按域名汇总时间范围内的浏览时长，按时长降序
+pub fn domain_stats(
+    events: &[ExternalEventRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<DomainStat> {
+    let mut totals: HashMap<String, (i64, usize)> = HashMap::new();
+    for event in events {
+        let Some((_, domain)) = event_target(event) else {
+            continue;
+        };
+        let seconds = (event.end_time.min(end) - event.start_time.max(start)).num_seconds();
+        if seconds > 0 {
+            let entry = totals.entry(domain).or_default();
+            entry.0 += seconds;
+            entry.1 += 1;
+        }
+    }
+
+    let mut totals: Vec<(String, (i64, usize))> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(&b.0)));
+    totals
+        .into_iter()
+        .map(|(domain, (seconds, visits))| DomainStat {
+            domain,
+            minutes: seconds / 60,
+            visits,
+        })
+        .collect()
+}
+
+/// 某天按域名的浏览时长
+pub async fn day_domain_stats(db: &Database, date: &str) -> Result<Vec<DomainStat>, String> {
+    let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("日期格式错误: {}", e))?;
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = start + Duration::days(1);
+    Ok(domain_stats(
+        &load_browser_events(db, start, end).await,
+        start,
+        end,
+    ))
+}
+
+/// 用卡片时间范围内的浏览记录修正 app_sites：
+/// 域名按停留时长加入 secondary，主要应用是浏览器本身时替换为停留最久的域名
+pub fn apply_browser_domains(card: &mut TimelineCardRecord, events: &[ExternalEventRecord]) {
+    let parse = |value: &str| {
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|time| time.naive_local().and_utc())
+    };
+    let (Some(start), Some(end)) = (parse(&card.start_time), parse(&card.end_time)) else {
+        return;
+    };
+    let domains: Vec<String> = domain_stats(events, start, end)
+        .into_iter()
+        .map(|stat| stat.domain)
+        .collect();
+    let Some(top_domain) = domains.first().cloned() else {
+        return;
+    };
+
+    let mut app_sites: serde_json::Value =
+        serde_json::from_str(&card.app_sites).unwrap_or_else(|_| serde_json::json!({}));
+    if !app_sites.is_object() {
+        app_sites = serde_json::json!({});
+    }
+    let primary = app_sites["primary"]
+        .as_str()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if primary.is_empty() || GENERIC_BROWSER_NAMES.contains(&primary.as_str()) {
+        app_sites["primary"] = serde_json::json!(top_domain);
+    }
+
+    let primary = app_sites["primary"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let mut secondary: Vec<String> = domains
+        .into_iter()
+        .filter(|domain| *domain != primary)
+        .collect();
+    for app in app_sites["secondary"].as_array().into_iter().flatten() {
+        if let Some(app) = app.as_str() {
+            if app != primary && !secondary.iter().any(|existing| existing == app) {
+                secondary.push(app.to_string());
+            }
+        }
+    }
+    app_sites["secondary"] = serde_json::json!(secondary);
+    card.app_sites = app_sites.to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc()
+    }
+
+    fn tab(url: &str, title: &str) -> TabUpdate {
+        TabUpdate {
+            url: Some(url.to_string()),
+            title: title.to_string(),
+            focused: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tab_updates_become_domain_spans() {
+        assert_eq!(
+            domain_of("https://www.GitHub.com:443/org/repo?tab=1").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(domain_of("chrome://newtab"), None);
+
+        let tracker = BrowserTracker::new();
+        let docs = "https://docs.rs/tokio";
+        assert!(tracker
+            .apply(tab(docs, "tokio"), at("2025-10-09 10:00:00"))
+            .await
+            .is_none());
+        // 心跳不产生记录
+        assert!(tracker
+            .apply(tab(docs, "tokio"), at("2025-10-09 10:00:30"))
+            .await
+            .is_none());
+        // 切换标签页时结束上一段；距最后一次心跳超过宽限时间，按心跳后 60 秒结束
+        let first = tracker
+            .apply(
+                tab("https://github.com/pulls", "Pull requests"),
+                at("2025-10-09 10:05:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.end_time, at("2025-10-09 10:01:30"));
+        let second = tracker
+            .apply(
+                TabUpdate {
+                    url: None,
+                    title: String::new(),
+                    focused: false,
+                },
+                at("2025-10-09 10:05:40"),
+            )
+            .await
+            .unwrap();
+
+        let events = vec![first, second];
+        assert!(frame_metadata(&events, at("2025-10-09 10:01:00"))
+            .unwrap()
+            .contains("docs.rs"));
+
+        let mut card = TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T10:00:00+08:00".to_string(),
+            end_time: "2025-10-09T10:10:00+08:00".to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: "查阅文档".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: r#"{"primary":"Chrome","secondary":["vscode"]}"#.to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        apply_browser_domains(&mut card, &events);
+        let app_sites: serde_json::Value = serde_json::from_str(&card.app_sites).unwrap();
+        assert_eq!(app_sites["primary"], "docs.rs");
+        assert_eq!(
+            app_sites["secondary"],
+            serde_json::json!(["github.com", "vscode"])
+        );
+    }
+}
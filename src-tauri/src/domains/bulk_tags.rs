@@ -0,0 +1,374 @@
+// 批量标签 - 给符合筛选条件的所有会话添加或移除同一个标签
+//
+// 筛选条件：日期范围（必填）、类别（会话标签类别或卡片类别）、应用（卡片的主要/次要应用）、设备名称，
+// 文字条件不区分大小写，应用按包含匹配。先以 dry_run 统计会受影响的会话数，确认后在一个事务中写入。
+// 标签相同指类别相同且关键词集合相同（不区分大小写）：添加时已有相同标签的会话跳过，移除时删除所有相同标签。
+// 标签无法解析的会话不改写，避免丢失原有标签
+
+use crate::models::{ActivityCategory, ActivityTag};
+use crate::storage::{Database, Session, TimelineCardRecord};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tracing::info;
+
+/// 单次批量操作的最长日期范围（天）
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// 会话筛选条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkTagFilter {
+    /// 开始日期 (YYYY-MM-DD，含)
+    pub start_date: String,
+    /// 结束日期 (YYYY-MM-DD，含)
+    pub end_date: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+/// 批量标签结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkTagReport {
+    /// 符合筛选条件的会话数
+    pub matched: usize,
+    /// 标签发生（或将发生）变化的会话数
+    pub changed: usize,
+    /// 标签无法解析而跳过的会话数
+    pub skipped: usize,
+    /// 是否只是统计（未写入）
+    pub dry_run: bool,
+}
+
+/// 类别的序列化名称（如 work）
+fn category_name(category: &ActivityCategory) -> String {
+    serde_json::to_value(category)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// 关键词集合（小写，去除空白）
+fn keyword_set(tag: &ActivityTag) -> BTreeSet<String> {
+    tag.keywords
+        .iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .filter(|keyword| !keyword.is_empty())
+        .collect()
+}
+
+/// 两个标签是否相同
+fn same_tag(a: &ActivityTag, b: &ActivityTag) -> bool {
+    category_name(&a.category) == category_name(&b.category) && keyword_set(a) == keyword_set(b)
+}
+
+/// 添加或移除标签，标签有变化时返回新的标签列表
+pub fn apply_tag(
+    tags: &[ActivityTag],
+    tag: &ActivityTag,
+    remove: bool,
+) -> Option<Vec<ActivityTag>> {
+    if remove {
+        let kept: Vec<ActivityTag> = tags
+            .iter()
+            .filter(|existing| !same_tag(existing, tag))
+            .cloned()
+            .collect();
+        (kept.len() != tags.len()).then_some(kept)
+    } else if tags.iter().any(|existing| same_tag(existing, tag)) {
+        None
+    } else {
+        let mut updated = tags.to_vec();
+        updated.push(tag.clone());
+        Some(updated)
+    }
+}
+
+/// 去掉空白，空字符串视为未设置
+fn normalized(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+}
+
+/// 卡片的主要和次要应用（小写）
+fn card_apps(card: &TimelineCardRecord) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&card.app_sites) else {
+        return Vec::new();
+    };
+    let primary = value.get("primary").and_then(|v| v.as_str());
+    let secondary = value
+        .get("secondary")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str());
+    primary
+        .into_iter()
+        .chain(secondary)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// 会话（及其卡片）是否符合筛选条件（日期范围之外的条件）
+pub fn session_matches(
+    filter: &BulkTagFilter,
+    session: &Session,
+    tags: &[ActivityTag],
+    cards: &[TimelineCardRecord],
+) -> bool {
+    if let Some(device) = normalized(&filter.device) {
+        let name = session.device_name.as_deref().unwrap_or_default();
+        if name.trim().to_lowercase() != device {
+            return false;
+        }
+    }
+    if let Some(category) = normalized(&filter.category) {
+        let in_tags = tags
+            .iter()
+            .any(|tag| category_name(&tag.category) == category);
+        let in_cards = cards
+            .iter()
+            .any(|card| card.category.trim().to_lowercase() == category);
+        if !in_tags && !in_cards {
+            return false;
+        }
+    }
+    if let Some(app) = normalized(&filter.app) {
+        if !cards
+            .iter()
+            .any(|card| card_apps(card).iter().any(|name| name.contains(&app)))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// 按筛选条件批量添加（remove 为 false）或移除标签；dry_run 时只统计不写入
+pub async fn bulk_tag(
+    db: &Database,
+    filter: &BulkTagFilter,
+    tag: &ActivityTag,
+    remove: bool,
+    dry_run: bool,
+) -> Result<BulkTagReport, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))
+    };
+    let start = parse(&filter.start_date)?;
+    let end = parse(&filter.end_date)?;
+    if end < start {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end - start).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("日期范围不能超过 {} 天", MAX_RANGE_DAYS));
+    }
+    let needs_cards = normalized(&filter.category).is_some() || normalized(&filter.app).is_some();
+
+    let mut report = BulkTagReport {
+        matched: 0,
+        changed: 0,
+        skipped: 0,
+        dry_run,
+    };
+    let mut seen_sessions = BTreeSet::new();
+    let mut updates: Vec<(i64, String)> = Vec::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let sessions = db
+            .get_sessions_by_date(&date.to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        for session in sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            if !seen_sessions.insert(session_id) {
+                continue;
+            }
+            let tags: Option<Vec<ActivityTag>> = if session.tags.trim().is_empty() {
+                Some(Vec::new())
+            } else {
+                serde_json::from_str(&session.tags).ok()
+            };
+            let cards = if needs_cards {
+                db.get_timeline_cards_by_session(session_id)
+                    .await
+                    .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+            } else {
+                Vec::new()
+            };
+            if !session_matches(
+                filter,
+                &session,
+                tags.as_deref().unwrap_or_default(),
+                &cards,
+            ) {
+                continue;
+            }
+            report.matched += 1;
+
+            let Some(tags) = tags else {
+                report.skipped += 1;
+                continue;
+            };
+            if let Some(updated) = apply_tag(&tags, tag, remove) {
+                let json = serde_json::to_string(&updated).map_err(|e| e.to_string())?;
+                updates.push((session_id, json));
+            }
+        }
+    }
+    report.changed = updates.len();
+
+    if !dry_run && !updates.is_empty() {
+        db.update_sessions_tags(&updates)
+            .await
+            .map_err(|e| format!("更新会话标签失败: {}", e))?;
+        info!(
+            "批量{}标签: {} 个会话（符合条件 {} 个）",
+            if remove { "移除" } else { "添加" },
+            report.changed,
+            report.matched
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn tag(category: ActivityCategory, keywords: &[&str]) -> ActivityTag {
+        ActivityTag {
+            category,
+            confidence: 1.0,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_filter_matching_and_tag_application() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start,
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: String::new(),
+            created_at: None,
+            device_name: Some("MacBook-Pro".to_string()),
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let card = TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T09:00:00+08:00".to_string(),
+            end_time: "2025-10-09T09:15:00+08:00".to_string(),
+            category: "Work".to_string(),
+            subcategory: "Design".to_string(),
+            title: "首页视觉稿".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: r#"{"primary":"Figma","secondary":["Google Chrome"]}"#.to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let tags = vec![tag(ActivityCategory::Learning, &["Rust"])];
+
+        let filter =
+            |category: Option<&str>, app: Option<&str>, device: Option<&str>| BulkTagFilter {
+                category: category.map(str::to_string),
+                app: app.map(str::to_string),
+                device: device.map(str::to_string),
+                ..Default::default()
+            };
+        let cards = std::slice::from_ref(&card);
+        assert!(session_matches(
+            &filter(None, None, None),
+            &session,
+            &tags,
+            cards
+        ));
+        // 类别可以来自卡片或会话标签
+        assert!(session_matches(
+            &filter(Some("work"), None, None),
+            &session,
+            &tags,
+            cards
+        ));
+        assert!(session_matches(
+            &filter(Some("learning"), None, None),
+            &session,
+            &tags,
+            cards
+        ));
+        assert!(!session_matches(
+            &filter(Some("personal"), None, None),
+            &session,
+            &tags,
+            cards
+        ));
+        assert!(session_matches(
+            &filter(None, Some("chrome"), Some("macbook-pro")),
+            &session,
+            &tags,
+            cards
+        ));
+        assert!(!session_matches(
+            &filter(None, Some("slack"), None),
+            &session,
+            &tags,
+            cards
+        ));
+        assert!(!session_matches(
+            &filter(None, None, Some("iMac")),
+            &session,
+            &tags,
+            cards
+        ));
+
+        // 已有相同标签（关键词不区分大小写）时不重复添加
+        let project = tag(ActivityCategory::Work, &["项目A", "Design"]);
+        let added = apply_tag(&tags, &project, false).unwrap();
+        assert_eq!(added.len(), 2);
+        assert!(apply_tag(
+            &added,
+            &tag(ActivityCategory::Work, &["design", "项目A"]),
+            false
+        )
+        .is_none());
+        // 类别不同不算相同标签
+        assert!(apply_tag(
+            &added,
+            &tag(ActivityCategory::Other, &["项目A", "Design"]),
+            true
+        )
+        .is_none());
+        let removed = apply_tag(&added, &project, true).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(keyword_set(&removed[0]), keyword_set(&tags[0]));
+    }
+}
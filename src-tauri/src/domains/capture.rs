@@ -1,22 +1,50 @@
 // 捕获领域管理器
 //
 // 负责屏幕截取和调度相关的功能
-// 包含 ScreenCapture 和 CaptureScheduler 两个核心组件
+// 包含 ScreenCapture、CaptureScheduler、基于实时截屏的 BreakReminder、专注模式的 FocusGuard 和设置页的截屏预览
 
-use crate::capture::{scheduler::CaptureScheduler, ScreenCapture};
-use std::sync::Arc;
+use super::breaks::BreakReminder;
+use super::focus::FocusGuard;
+use crate::capture::{scheduler::CaptureScheduler, CapturePreview, ScreenCapture};
+use crate::models::CaptureSettings;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, Instant};
+use tracing::{info, warn};
+
+/// 预览帧间隔
+const PREVIEW_INTERVAL: Duration = Duration::from_secs(1);
+/// 预览最长持续时间（前端未调用停止时自动结束）
+const PREVIEW_MAX_DURATION: Duration = Duration::from_secs(10 * 60);
+/// 预览图最大宽度
+const PREVIEW_MAX_WIDTH: u32 = 640;
 
 /// 捕获领域管理器 - 负责屏幕截取和调度
 #[derive(Clone)]
 pub struct CaptureDomain {
     capture: Arc<ScreenCapture>,
     scheduler: Arc<CaptureScheduler>,
+    break_reminder: Arc<BreakReminder>,
+    focus_guard: Arc<FocusGuard>,
+    /// 正在运行的预览任务
+    preview_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl CaptureDomain {
     /// 创建新的捕获领域管理器
-    pub fn new(capture: Arc<ScreenCapture>, scheduler: Arc<CaptureScheduler>) -> Self {
-        Self { capture, scheduler }
+    pub fn new(
+        capture: Arc<ScreenCapture>,
+        scheduler: Arc<CaptureScheduler>,
+        break_reminder: Arc<BreakReminder>,
+        focus_guard: Arc<FocusGuard>,
+    ) -> Self {
+        Self {
+            capture,
+            scheduler,
+            break_reminder,
+            focus_guard,
+            preview_task: Arc::new(Mutex::new(None)),
+        }
     }
 
     /// 获取截屏管理器
@@ -28,4 +56,50 @@ impl CaptureDomain {
     pub fn get_scheduler(&self) -> &Arc<CaptureScheduler> {
         &self.scheduler
     }
+
+    /// 获取休息提醒器
+    pub fn get_break_reminder(&self) -> &Arc<BreakReminder> {
+        &self.break_reminder
+    }
+
+    /// 获取专注模式守卫
+    pub fn get_focus_guard(&self) -> &Arc<FocusGuard> {
+        &self.focus_guard
+    }
+
+    /// 开始截屏预览：每秒截取一帧低分辨率画面交给回调，重复调用会替换之前的预览
+    pub fn start_preview<F>(&self, settings: Option<CaptureSettings>, on_frame: F)
+    where
+        F: Fn(CapturePreview) + Send + 'static,
+    {
+        let capture = self.capture.clone();
+        let task = tokio::spawn(async move {
+            let started = Instant::now();
+            let mut ticker = interval(PREVIEW_INTERVAL);
+            while started.elapsed() < PREVIEW_MAX_DURATION {
+                ticker.tick().await;
+                match capture
+                    .capture_preview(settings.as_ref(), PREVIEW_MAX_WIDTH)
+                    .await
+                {
+                    Ok(preview) => on_frame(preview),
+                    Err(e) => warn!("截屏预览失败: {}", e),
+                }
+            }
+            info!("截屏预览已超时结束");
+        });
+
+        if let Some(previous) = self.preview_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+        info!("截屏预览已开始");
+    }
+
+    /// 停止截屏预览
+    pub fn stop_preview(&self) {
+        if let Some(task) = self.preview_task.lock().unwrap().take() {
+            task.abort();
+            info!("截屏预览已停止");
+        }
+    }
 }
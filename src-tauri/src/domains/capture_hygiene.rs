@@ -0,0 +1,296 @@
+// 截图自检 - 检查本应用窗口是否出现在截图中，避免录进视频后形成套娃画面
+//
+// 按窗口与各屏幕的交集算出窗口在合成截图中的区域，再检查该区域：
+// - 遮盖颜色的占比：达到阈值视为窗口已被遮盖
+// - 与不遮盖的对照画面的相似度（缩小为灰度图后逐像素比较）：相似说明窗口内容出现在截图中
+// 根据结果给出配置建议。系统通知的位置无法获取，不在检查范围内
+
+use crate::capture::{OwnWindowProbe, ScreenCapture, WindowRect, OWN_WINDOW_MASK};
+use crate::video::ScreenRegion;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde::Serialize;
+
+/// 遮盖颜色占比达到该值时视为窗口已被遮盖
+const MASKED_RATIO: f64 = 0.9;
+
+/// 与对照画面的相似度达到该值时视为窗口出现在截图中
+const LEAK_SIMILARITY: f64 = 0.9;
+
+/// 比较时缩小到的边长（像素）
+const COMPARE_SIZE: u32 = 32;
+
+/// 与遮盖颜色的允许偏差（缩放截图时边缘会混色）
+const MASK_TOLERANCE: i32 = 12;
+
+/// 截图自检结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureHygieneReport {
+    /// 本应用窗口是否显示在某个屏幕上
+    pub window_visible: bool,
+    /// 是否开启了遮盖本应用窗口
+    pub exclude_enabled: bool,
+    /// 窗口区域中遮盖颜色的占比
+    pub masked_ratio: Option<f64>,
+    /// 窗口区域与不遮盖的对照画面的相似度
+    pub similarity: Option<f64>,
+    /// 本应用窗口是否出现在截图中
+    pub leaked: bool,
+    /// 配置建议
+    pub fixes: Vec<String>,
+}
+
+/// 窗口在合成截图中的区域（按截图宽高的比例），窗口不在任何屏幕上时为空
+pub fn window_regions(
+    window: WindowRect,
+    screens: &[(WindowRect, ScreenRegion)],
+) -> Vec<ScreenRegion> {
+    screens
+        .iter()
+        .filter_map(|(screen, region)| {
+            let left = window.x.max(screen.x);
+            let top = window.y.max(screen.y);
+            let right = (window.x + window.width as i32).min(screen.x + screen.width as i32);
+            let bottom = (window.y + window.height as i32).min(screen.y + screen.height as i32);
+            if right <= left || bottom <= top || screen.width == 0 || screen.height == 0 {
+                return None;
+            }
+            let fx = |value: i32| (value - screen.x) as f64 / screen.width as f64;
+            let fy = |value: i32| (value - screen.y) as f64 / screen.height as f64;
+            Some(ScreenRegion {
+                x: region.x + fx(left) * region.width,
+                y: region.y + fy(top) * region.height,
+                width: (fx(right) - fx(left)) * region.width,
+                height: (fy(bottom) - fy(top)) * region.height,
+                primary: region.primary,
+            })
+        })
+        .collect()
+}
+
+/// 比例区域换算为截图中的像素区域 (x, y, 宽, 高)
+fn to_pixels(region: &ScreenRegion, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let x0 = ((region.x * width as f64).round().max(0.0) as u32).min(width);
+    let y0 = ((region.y * height as f64).round().max(0.0) as u32).min(height);
+    let x1 = (((region.x + region.width) * width as f64).round().max(0.0) as u32).min(width);
+    let y1 = (((region.y + region.height) * height as f64)
+        .round()
+        .max(0.0) as u32)
+        .min(height);
+    (x1 > x0 && y1 > y0).then_some((x0, y0, x1 - x0, y1 - y0))
+}
+
+/// 区域中接近遮盖颜色的像素数
+fn masked_pixels(image: &DynamicImage, (x, y, width, height): (u32, u32, u32, u32)) -> u64 {
+    let rgb = image.crop_imm(x, y, width, height).to_rgb8();
+    rgb.pixels()
+        .filter(|pixel| {
+            pixel
+                .0
+                .iter()
+                .zip(OWN_WINDOW_MASK.0.iter())
+                .all(|(a, b)| (*a as i32 - *b as i32).abs() <= MASK_TOLERANCE)
+        })
+        .count() as u64
+}
+
+fn thumbnail(image: &DynamicImage, (x, y, width, height): (u32, u32, u32, u32)) -> GrayImage {
+    image
+        .crop_imm(x, y, width, height)
+        .resize_exact(COMPARE_SIZE, COMPARE_SIZE, FilterType::Triangle)
+        .to_luma8()
+}
+
+/// 两张截图同一区域的相似度（0-1）
+fn region_similarity(a: &DynamicImage, b: &DynamicImage, rect: (u32, u32, u32, u32)) -> f64 {
+    let (a, b) = (thumbnail(a, rect), thumbnail(b, rect));
+    let total: u64 = a
+        .pixels()
+        .zip(b.pixels())
+        .map(|(p, q)| (p.0[0] as i64 - q.0[0] as i64).unsigned_abs())
+        .sum();
+    1.0 - total as f64 / (255.0 * (COMPARE_SIZE * COMPARE_SIZE) as f64)
+}
+
+/// 根据测试截图判断本应用窗口是否出现在截图中
+pub fn evaluate(probe: &OwnWindowProbe) -> CaptureHygieneReport {
+    let mut report = CaptureHygieneReport {
+        window_visible: false,
+        exclude_enabled: probe.exclude_enabled,
+        masked_ratio: None,
+        similarity: None,
+        leaked: false,
+        fixes: Vec::new(),
+    };
+    let Some(window) = probe.window else {
+        return report;
+    };
+
+    let (width, height) = (probe.captured.width(), probe.captured.height());
+    let (unmasked_width, unmasked_height) = (probe.unmasked.width(), probe.unmasked.height());
+    let rects: Vec<_> = window_regions(window, &probe.screens)
+        .iter()
+        .filter_map(|region| to_pixels(region, width, height))
+        .collect();
+    if rects.is_empty() {
+        return report;
+    }
+    report.window_visible = true;
+
+    let area: u64 = rects.iter().map(|r| r.2 as u64 * r.3 as u64).sum();
+    let masked: u64 = rects
+        .iter()
+        .map(|rect| masked_pixels(&probe.captured, *rect))
+        .sum();
+    let masked_ratio = masked as f64 / area as f64;
+    report.masked_ratio = Some(masked_ratio);
+
+    // 两次截图分辨率一致时才比较内容
+    if (width, height) == (unmasked_width, unmasked_height) {
+        let weighted: f64 = rects
+            .iter()
+            .map(|rect| {
+                region_similarity(&probe.captured, &probe.unmasked, *rect)
+                    * (rect.2 as u64 * rect.3 as u64) as f64
+            })
+            .sum();
+        report.similarity = Some(weighted / area as f64);
+    }
+
+    report.leaked = masked_ratio < MASKED_RATIO
+        && report
+            .similarity
+            .is_none_or(|similarity| similarity >= LEAK_SIMILARITY);
+    if report.leaked {
+        if cfg!(target_os = "macos") {
+            report
+                .fixes
+                .push("macOS 上不会遮盖本应用窗口，截屏期间请最小化或隐藏主窗口".to_string());
+        } else if !probe.exclude_enabled {
+            report
+                .fixes
+                .push("开启截屏设置中的「遮盖本应用窗口」".to_string());
+        } else {
+            report.fixes.push(
+                "已开启遮盖，但窗口仍出现在截图中，遮盖位置可能与显示缩放不符；截屏期间请最小化主窗口，并反馈该问题"
+                    .to_string(),
+            );
+        }
+        if masked_ratio > 0.0 {
+            report.fixes.push(format!(
+                "窗口只有约 {:.0}% 的区域被遮盖，可检查多屏排列和缩放比例",
+                masked_ratio * 100.0
+            ));
+        }
+    }
+    report
+}
+
+/// 截取测试画面并检查本应用窗口是否出现在截图中（不保存截图）
+pub async fn verify_capture_hygiene(
+    capture: &ScreenCapture,
+) -> Result<CaptureHygieneReport, String> {
+    let probe = capture
+        .probe_own_window()
+        .await
+        .map_err(|e| format!("截取测试画面失败: {}", e))?;
+    Ok(evaluate(&probe))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn probe(mask: bool, exclude_enabled: bool) -> OwnWindowProbe {
+        // 两块 100×50 的屏幕左右排列，窗口跨在两块屏幕之间
+        let screens = vec![
+            (
+                WindowRect {
+                    x: 0,
+                    y: 0,
+                    width: 100,
+                    height: 50,
+                },
+                ScreenRegion {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 0.5,
+                    height: 1.0,
+                    primary: true,
+                },
+            ),
+            (
+                WindowRect {
+                    x: 100,
+                    y: 0,
+                    width: 100,
+                    height: 50,
+                },
+                ScreenRegion {
+                    x: 0.5,
+                    y: 0.0,
+                    width: 0.5,
+                    height: 1.0,
+                    primary: false,
+                },
+            ),
+        ];
+        let window = WindowRect {
+            x: 80,
+            y: 10,
+            width: 40,
+            height: 20,
+        };
+        let unmasked = RgbaImage::from_fn(200, 50, |x, y| {
+            Rgba([(x * 7 % 256) as u8, (y * 5) as u8, 200, 255])
+        });
+        let mut captured = unmasked.clone();
+        if mask {
+            for y in 10..30 {
+                for x in 80..120 {
+                    captured.put_pixel(x, y, OWN_WINDOW_MASK);
+                }
+            }
+        }
+        OwnWindowProbe {
+            window: Some(window),
+            screens,
+            captured: DynamicImage::ImageRgba8(captured),
+            unmasked: DynamicImage::ImageRgba8(unmasked),
+            exclude_enabled,
+        }
+    }
+
+    #[test]
+    fn test_detects_own_window_in_capture() {
+        let regions = window_regions(
+            probe(false, false).window.unwrap(),
+            &probe(false, false).screens,
+        );
+        assert_eq!(regions.len(), 2);
+        assert!((regions[0].x - 0.4).abs() < 1e-9);
+        assert!((regions[1].width - 0.1).abs() < 1e-9);
+
+        // 未遮盖：窗口出现在截图中
+        let report = evaluate(&probe(false, false));
+        assert!(report.window_visible);
+        assert!(report.leaked);
+        assert_eq!(report.similarity, Some(1.0));
+        if cfg!(not(target_os = "macos")) {
+            assert!(report.fixes[0].contains("遮盖本应用窗口"));
+        }
+
+        // 已遮盖：不会出现在截图中
+        let report = evaluate(&probe(true, true));
+        assert!(!report.leaked);
+        assert_eq!(report.masked_ratio, Some(1.0));
+        assert!(report.fixes.is_empty());
+
+        // 窗口隐藏时不检查
+        let mut hidden = probe(false, true);
+        hidden.window = None;
+        let report = evaluate(&hidden);
+        assert!(!report.window_visible && !report.leaked);
+    }
+}
@@ -0,0 +1,216 @@
+// 截屏暂停记录 - 把截屏停止的时间段和原因写入 capture_pauses 表
+//
+// 调度器检测到锁屏状态变化、toggle_capture 手动暂停/恢复时发布 CapturePaused/CaptureResumed 事件，
+// 记录任务订阅事件写入数据库，供记录空档和每日总结使用。
+// 应用退出时仍在进行的暂停没有结束时间，下次启动时以启动时间结束（期间截屏同样没有进行）
+
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::storage::{CapturePauseRecord, Database};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 截屏暂停原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    /// 系统锁屏
+    ScreenLocked,
+    /// 用户手动暂停
+    User,
+    /// 离开模式（休假等）
+    Away,
+}
+
+impl PauseReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ScreenLocked => "screen_locked",
+            Self::User => "user",
+            Self::Away => "away",
+        }
+    }
+
+    /// 显示给用户的说明
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ScreenLocked => "锁屏",
+            Self::User => "手动暂停",
+            Self::Away => "离开模式",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "screen_locked" => Some(Self::ScreenLocked),
+            "user" => Some(Self::User),
+            "away" => Some(Self::Away),
+            _ => None,
+        }
+    }
+}
+
+/// 暂停记录中某个原因的显示说明（未知原因原样显示）
+pub fn reason_label(reason: &str) -> String {
+    PauseReason::parse(reason)
+        .map(|reason| reason.label().to_string())
+        .unwrap_or_else(|| reason.to_string())
+}
+
+/// 暂停与时间范围重叠的分钟数（未结束的暂停按 now 计算）
+pub fn overlap_minutes(
+    pause: &CapturePauseRecord,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> i64 {
+    let pause_end = pause.end_time.unwrap_or(now).min(end);
+    let pause_start = pause.start_time.max(start);
+    (pause_end - pause_start).num_minutes().max(0)
+}
+
+/// 按原因汇总某天的暂停时长（分钟），按时长降序
+pub fn pause_minutes_by_reason(
+    pauses: &[CapturePauseRecord],
+    day: NaiveDate,
+    now: DateTime<Utc>,
+) -> Vec<(String, i64)> {
+    let (start, end) = day_bounds(day);
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for pause in pauses {
+        *totals.entry(pause.reason.as_str()).or_default() +=
+            overlap_minutes(pause, start, end, now);
+    }
+
+    let mut totals: Vec<(String, i64)> = totals
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(reason, minutes)| (reason.to_string(), minutes))
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1));
+    totals
+}
+
+/// 某天的时间范围（按 local_now() 约定的本地时间值）
+pub(crate) fn day_bounds(day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (start, start + Duration::days(1))
+}
+
+/// 获取与某天有重叠的暂停记录
+pub async fn load_day_pauses(db: &Database, date: &str) -> Result<Vec<CapturePauseRecord>, String> {
+    let day =
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))?;
+    let (start, end) = day_bounds(day);
+    db.get_capture_pauses_between(start, end)
+        .await
+        .map_err(|e| format!("获取截屏暂停记录失败: {}", e))
+}
+
+/// 启动暂停记录任务
+pub fn start_pause_recorder(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>) {
+    let mut receiver = event_bus.subscribe_topics("截屏暂停记录任务", &[EventTopic::Capture]);
+
+    tokio::spawn(async move {
+        match storage.get_db().await {
+            Ok(db) => match db
+                .close_open_capture_pauses(crate::storage::local_now())
+                .await
+            {
+                Ok(closed) if closed > 0 => info!("已结束 {} 条上次未结束的截屏暂停记录", closed),
+                Ok(_) => {}
+                Err(e) => warn!("结束截屏暂停记录失败: {}", e),
+            },
+            Err(e) => warn!("截屏暂停记录任务无法访问数据库: {}", e),
+        }
+
+        let mut open: HashMap<PauseReason, i64> = HashMap::new();
+        loop {
+            let Some(event) = receiver.recv().await else {
+                break;
+            };
+
+            let Ok(db) = storage.get_db().await else {
+                continue;
+            };
+            match event {
+                AppEvent::CapturePaused { reason, timestamp } => {
+                    if open.contains_key(&reason) {
+                        continue;
+                    }
+                    let record = CapturePauseRecord {
+                        id: None,
+                        start_time: timestamp,
+                        end_time: None,
+                        reason: reason.as_str().to_string(),
+                    };
+                    match db.insert_capture_pause(&record).await {
+                        Ok(id) => {
+                            open.insert(reason, id);
+                        }
+                        Err(e) => warn!("保存截屏暂停记录失败: {}", e),
+                    }
+                }
+                AppEvent::CaptureResumed { reason, timestamp } => {
+                    if let Some(id) = open.remove(&reason) {
+                        if let Err(e) = db.end_capture_pause(id, timestamp).await {
+                            warn!("结束截屏暂停记录失败: {}", e);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        warn!("截屏暂停记录任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn pause(start: &str, end: Option<&str>, reason: PauseReason) -> CapturePauseRecord {
+        CapturePauseRecord {
+            id: None,
+            start_time: at(start),
+            end_time: end.map(at),
+            reason: reason.as_str().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pause_minutes_are_clipped_to_the_day() {
+        let pauses = vec![
+            // 前一天晚上锁屏到早上 8 点，只计算当天的 8 小时
+            pause(
+                "2025-10-08T22:00:00Z",
+                Some("2025-10-09T08:00:00Z"),
+                PauseReason::ScreenLocked,
+            ),
+            pause(
+                "2025-10-09T12:00:00Z",
+                Some("2025-10-09T12:30:00Z"),
+                PauseReason::User,
+            ),
+            // 仍在进行中的暂停按当前时间计算
+            pause("2025-10-09T17:00:00Z", None, PauseReason::User),
+        ];
+        let day = NaiveDate::from_ymd_opt(2025, 10, 9).unwrap();
+
+        let totals = pause_minutes_by_reason(&pauses, day, at("2025-10-09T18:00:00Z"));
+        assert_eq!(
+            totals,
+            vec![("screen_locked".to_string(), 480), ("user".to_string(), 90)]
+        );
+        assert_eq!(reason_label("user"), "手动暂停");
+    }
+}
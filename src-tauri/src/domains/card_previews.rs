@@ -0,0 +1,209 @@
+// 卡片预览短片 - 会话分析完成后为每张时间线卡片截取几秒视频
+//
+// 卡片的 video_preview_path 指向整段会话视频，时间线悬浮时无法直接看出卡片在做什么，
+// 因此以卡片在视频中的中点为中心截取 4 秒左右的小尺寸 WebM，路径写入卡片的 preview_clip_path。
+// 短片放在会话视频旁的 <视频文件名>.previews 目录，重新分析时整体重建，视频删除时一并删除
+
+use super::playback::{card_video_range, session_mapping, CardVideoRange};
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::storage::Database;
+use crate::video::{VideoMetadata, VideoUtils};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 预览短片时长（秒）
+const CLIP_SECONDS: f64 = 4.0;
+
+/// 卡片在视频中很短时，短片的最短时长（秒）
+const MIN_CLIP_SECONDS: f64 = 1.0;
+
+/// 预览目录后缀
+const PREVIEW_DIR_SUFFIX: &str = ".previews";
+
+/// 会话视频对应的预览目录
+pub fn preview_dir(video_path: &Path) -> PathBuf {
+    let mut path = video_path.as_os_str().to_owned();
+    path.push(PREVIEW_DIR_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// 删除会话视频的全部预览短片（视频删除时调用）
+pub async fn remove_previews(video_path: &Path) {
+    let _ = tokio::fs::remove_dir_all(preview_dir(video_path)).await;
+}
+
+/// 计算短片的截取位置：以卡片中点为中心，不超出卡片和视频范围，返回 (开始秒数, 时长)
+pub fn clip_window(range: &CardVideoRange, video_duration: Option<f64>) -> (f64, f64) {
+    let card_length = (range.end_secs - range.start_secs).max(0.0);
+    let mut length = CLIP_SECONDS.min(card_length).max(MIN_CLIP_SECONDS);
+    if let Some(duration) = video_duration.filter(|d| *d > 0.0) {
+        length = length.min(duration);
+    }
+
+    let middle = (range.start_secs + range.end_secs) / 2.0;
+    let mut start = (middle - length / 2.0).max(0.0);
+    if let Some(duration) = video_duration.filter(|d| *d > 0.0) {
+        start = start.min(duration - length);
+    }
+    (start, length)
+}
+
+/// 卡片预览短片（供时间线悬浮播放）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardPreview {
+    pub card_id: i64,
+    pub title: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub clip_path: String,
+}
+
+/// 会话中已生成预览短片的卡片（短片文件已被删除的跳过）
+pub async fn session_card_previews(
+    db: &Database,
+    session_id: i64,
+) -> Result<Vec<CardPreview>, String> {
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    Ok(cards
+        .into_iter()
+        .filter_map(|card| {
+            let clip_path = card.preview_clip_path?;
+            Path::new(&clip_path).is_file().then(|| CardPreview {
+                card_id: card.id?,
+                title: card.title,
+                start_time: card.start_time,
+                end_time: card.end_time,
+                clip_path,
+            })
+        })
+        .collect())
+}
+
+/// 为会话的所有卡片生成预览短片，返回生成的数量
+pub async fn generate_session_previews(
+    storage: &StorageDomain,
+    session_id: i64,
+) -> Result<usize, String> {
+    let db = storage.get_db().await?;
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let video_path = session
+        .video_path
+        .clone()
+        .ok_or_else(|| "会话没有视频".to_string())?;
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    if cards.is_empty() {
+        return Ok(0);
+    }
+
+    let local_video = storage
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+        .map_err(|e| format!("获取会话视频失败: {}", e))?;
+    let mapping = session_mapping(&session, storage.get_settings()).await;
+    let video_duration = VideoMetadata::load(&local_video)
+        .await
+        .map(|metadata| metadata.video_duration_secs());
+
+    // 重新分析后卡片 ID 会变化，先清掉旧短片
+    let dir = preview_dir(&local_video);
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("创建预览目录失败: {}", e))?;
+
+    let mut generated = 0;
+    for card in &cards {
+        let Some(card_id) = card.id else {
+            continue;
+        };
+        let (start, length) =
+            clip_window(&card_video_range(&session, card, &mapping), video_duration);
+        let clip_path = dir.join(format!("card_{}.webm", card_id));
+        if let Err(e) =
+            VideoUtils::generate_preview_clip(&local_video, &clip_path, start, length).await
+        {
+            warn!("卡片 {} 生成预览短片失败: {}", card_id, e);
+            continue;
+        }
+        let clip_path = clip_path.to_string_lossy().to_string();
+        db.update_timeline_card_preview_clip(card_id, Some(&clip_path))
+            .await
+            .map_err(|e| format!("保存预览短片路径失败: {}", e))?;
+        generated += 1;
+    }
+
+    info!("会话 {} 已生成 {} 个卡片预览短片", session_id, generated);
+    Ok(generated)
+}
+
+/// 启动预览短片任务：会话分析完成后为卡片生成预览短片
+pub fn start_preview_worker(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>) {
+    let mut receiver = event_bus.subscribe_topics("预览短片任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let enabled = storage
+                .get_settings()
+                .get()
+                .await
+                .card_previews
+                .unwrap_or(true);
+            if !enabled {
+                continue;
+            }
+
+            if let Err(e) = generate_session_previews(&storage, session_id).await {
+                warn!("会话 {} 生成卡片预览短片失败: {}", session_id, e);
+            }
+        }
+        warn!("预览短片任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start_secs: f64, end_secs: f64) -> CardVideoRange {
+        CardVideoRange {
+            card_id: 1,
+            start_secs,
+            end_secs,
+        }
+    }
+
+    #[test]
+    fn test_clip_window_centers_on_card_middle() {
+        // 卡片足够长：以中点为中心截取 4 秒
+        assert_eq!(clip_window(&range(10.0, 30.0), Some(60.0)), (18.0, 4.0));
+        // 卡片比短片短：只截卡片本身
+        assert_eq!(clip_window(&range(10.0, 12.0), Some(60.0)), (10.0, 2.0));
+        // 卡片几乎没有时长：至少截 1 秒
+        assert_eq!(clip_window(&range(10.0, 10.0), None), (9.5, 1.0));
+        // 不超出视频开头和结尾
+        assert_eq!(clip_window(&range(0.0, 2.0), Some(60.0)), (0.0, 2.0));
+        assert_eq!(clip_window(&range(50.0, 70.0), Some(60.0)), (56.0, 4.0));
+        // 视频比短片还短：截整段视频
+        assert_eq!(clip_window(&range(0.0, 10.0), Some(3.0)), (0.0, 3.0));
+    }
+}
@@ -0,0 +1,358 @@
+// 剪贴板记录（可选）- 记录复制事件并与会话关联
+//
+// 开启后每 2 秒读取一次剪贴板文本，内容变化时记为一次复制：默认只保存时间、SHA-256 哈希、
+// 字数/行数、类型（代码/链接/文本）和当时的前台应用，开启“保存文本”后才保存内容。
+// 会话分析保存卡片时，把卡片时间内复制的大段内容写进卡片详情（如“14:12 复制了大段代码（58 行）”）；
+// 按片段查找时用哈希精确匹配，保存了文本的记录还能按包含关系匹配
+
+use super::metrics::parse_series_time;
+use crate::capture::foreground;
+use crate::models::ClipboardLogSettings;
+use crate::settings::SettingsManager;
+use crate::storage::{ClipboardEventRecord, Database, TimelineCardRecord};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// 读取剪贴板的间隔（秒）
+const POLL_SECONDS: u64 = 2;
+/// 保存文本时的最大长度（字符）
+const MAX_STORED_CHARS: usize = 20_000;
+/// 视为“大段内容”的字数或行数，达到其一才写进卡片详情
+const LARGE_CLIP_CHARS: i64 = 200;
+const LARGE_CLIP_LINES: i64 = 5;
+/// 查找结果的默认条数和上限
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const MAX_SEARCH_LIMIT: usize = 200;
+
+/// 代码特征：出现其中任意一个即视为代码
+const CODE_MARKERS: &[&str] = &[
+    ";\n",
+    "{\n",
+    "}\n",
+    "=>",
+    "->",
+    "fn ",
+    "def ",
+    "function ",
+    "import ",
+    "#include",
+    "const ",
+    "let ",
+    "return ",
+    "class ",
+    "</",
+];
+
+/// 查找结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardHit {
+    pub event: ClipboardEventRecord,
+    /// 复制时所在的会话
+    pub session_id: Option<i64>,
+    pub session_title: Option<String>,
+}
+
+/// 内容哈希（SHA-256，十六进制）
+pub fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 判断复制内容的类型：code、url 或 text
+pub fn classify(text: &str) -> &'static str {
+    let trimmed = text.trim();
+    if !trimmed.contains(char::is_whitespace)
+        && (trimmed.starts_with("http://") || trimmed.starts_with("https://"))
+    {
+        return "url";
+    }
+    let multiline = trimmed.lines().count() >= 2;
+    if multiline && CODE_MARKERS.iter().any(|marker| text.contains(marker)) {
+        "code"
+    } else {
+        "text"
+    }
+}
+
+/// 生成剪贴板记录
+pub fn to_record(
+    text: &str,
+    copied_at: DateTime<Utc>,
+    app_name: Option<String>,
+    settings: &ClipboardLogSettings,
+) -> ClipboardEventRecord {
+    ClipboardEventRecord {
+        id: None,
+        copied_at,
+        content_hash: content_hash(text),
+        char_count: text.chars().count() as i64,
+        line_count: text.lines().count().max(1) as i64,
+        kind: classify(text).to_string(),
+        app_name,
+        content: settings
+            .store_text
+            .then(|| text.chars().take(MAX_STORED_CHARS).collect()),
+    }
+}
+
+fn is_large(event: &ClipboardEventRecord) -> bool {
+    event.char_count >= LARGE_CLIP_CHARS || event.line_count >= LARGE_CLIP_LINES
+}
+
+/// 描述一次复制，如“14:12 复制了大段代码（58 行）”
+pub fn describe(event: &ClipboardEventRecord) -> String {
+    let what = match event.kind.as_str() {
+        "code" if is_large(event) => format!("大段代码（{} 行）", event.line_count),
+        "code" => format!("代码（{} 行）", event.line_count),
+        "url" => "链接".to_string(),
+        _ if event.line_count > 1 => {
+            format!("{} 字文本（{} 行）", event.char_count, event.line_count)
+        }
+        _ => format!("{} 字文本", event.char_count),
+    };
+    let app = event
+        .app_name
+        .as_deref()
+        .map(|app| format!("（{}）", app))
+        .unwrap_or_default();
+    format!("{} 复制了{}{}", event.copied_at.format("%H:%M"), what, app)
+}
+
+/// 把卡片时间内复制的大段内容写进卡片详情
+pub fn annotate_cards(
+    cards: &mut [TimelineCardRecord],
+    events: &[ClipboardEventRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) {
+    for card in cards {
+        let card_start = parse_series_time(&card.start_time, start, end);
+        let card_end = parse_series_time(&card.end_time, start, end);
+        let notes: Vec<String> = events
+            .iter()
+            .filter(|event| is_large(event))
+            .filter(|event| {
+                let time = event.copied_at.naive_utc();
+                card_start <= time && time < card_end
+            })
+            .map(describe)
+            .collect();
+        if notes.is_empty() {
+            continue;
+        }
+
+        let note = format!("剪贴板：{}", notes.join("；"));
+        if card.detailed_summary.trim().is_empty() {
+            card.detailed_summary = note;
+        } else {
+            card.detailed_summary = format!("{}\n\n{}", card.detailed_summary, note);
+        }
+    }
+}
+
+/// 获取时间范围内的剪贴板记录（读取失败时返回空）
+pub async fn load_events(
+    db: &Database,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<ClipboardEventRecord> {
+    match db.get_clipboard_events_between(start, end).await {
+        Ok(events) => events,
+        Err(e) => {
+            warn!("获取剪贴板记录失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 获取某天（YYYY-MM-DD）的剪贴板记录
+pub async fn get_day_events(
+    db: &Database,
+    date: &str,
+) -> Result<Vec<ClipboardEventRecord>, String> {
+    let day =
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))?;
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    db.get_clipboard_events_between(start, start + Duration::days(1))
+        .await
+        .map_err(|e| format!("获取剪贴板记录失败: {}", e))
+}
+
+/// 查找复制过某段内容的时间和所在会话
+pub async fn search(
+    db: &Database,
+    snippet: &str,
+    limit: Option<usize>,
+) -> Result<Vec<ClipboardHit>, String> {
+    if snippet.trim().is_empty() {
+        return Err("查找内容不能为空".to_string());
+    }
+    let escaped: String = snippet
+        .chars()
+        .flat_map(|c| match c {
+            '%' | '_' | '!' => vec!['!', c],
+            _ => vec![c],
+        })
+        .collect();
+    let limit = limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let events = db
+        .search_clipboard_events(
+            &content_hash(snippet),
+            &format!("%{}%", escaped),
+            limit as i64,
+        )
+        .await
+        .map_err(|e| format!("查找剪贴板记录失败: {}", e))?;
+
+    // 按复制时间找到所在的会话（同一天的会话只查询一次）
+    let mut sessions_by_date = HashMap::new();
+    let mut hits = Vec::new();
+    for event in events {
+        let date = event.copied_at.format("%Y-%m-%d").to_string();
+        if !sessions_by_date.contains_key(&date) {
+            let sessions = db.get_sessions_by_date(&date).await.unwrap_or_default();
+            sessions_by_date.insert(date.clone(), sessions);
+        }
+        let session = sessions_by_date[&date].iter().find(|session| {
+            session.start_time <= event.copied_at && event.copied_at < session.end_time
+        });
+        hits.push(ClipboardHit {
+            session_id: session.and_then(|session| session.id),
+            session_title: session.map(|session| session.title.clone()),
+            event,
+        });
+    }
+    Ok(hits)
+}
+
+/// 读取剪贴板中的文本（阻塞调用）
+fn read_clipboard_text() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+/// 剪贴板记录器
+pub struct ClipboardLogger {
+    settings: Arc<SettingsManager>,
+    /// 上次读到的内容哈希（开启前已在剪贴板中的内容不记录）
+    last_hash: Mutex<Option<String>>,
+}
+
+impl ClipboardLogger {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self {
+            settings,
+            last_hash: Mutex::new(None),
+        }
+    }
+
+    /// 启动剪贴板读取任务（未开启时只检查设置）
+    pub fn start(self: Arc<Self>, db: Arc<Database>) {
+        tokio::spawn(async move {
+            info!("剪贴板记录任务已启动");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(POLL_SECONDS)).await;
+
+                let settings = self.settings.get().await.clipboard_log.unwrap_or_default();
+                if !settings.enabled {
+                    *self.last_hash.lock().await = None;
+                    continue;
+                }
+
+                let text = match tokio::task::spawn_blocking(read_clipboard_text).await {
+                    Ok(Some(text)) if !text.trim().is_empty() => text,
+                    _ => continue,
+                };
+                let hash = content_hash(&text);
+                let previous = self.last_hash.lock().await.replace(hash.clone());
+                match previous {
+                    Some(previous) if previous != hash => {}
+                    _ => continue,
+                }
+
+                let app_name = tokio::task::spawn_blocking(foreground::foreground_app)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|app| app.name);
+                let record = to_record(&text, crate::storage::local_now(), app_name, &settings);
+                if let Err(e) = db.insert_clipboard_event(&record).await {
+                    error!("保存剪贴板记录失败: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_code_copy_is_noted_on_card() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T14:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let code = (0..58)
+            .map(|i| format!("    let value_{} = compute({});\n", i, i))
+            .collect::<String>();
+        let settings = ClipboardLogSettings {
+            enabled: true,
+            store_text: false,
+        };
+        let events = vec![
+            to_record(
+                &code,
+                start + Duration::minutes(12),
+                Some("Code".to_string()),
+                &settings,
+            ),
+            to_record("https://example.com/a", start, None, &settings),
+        ];
+        assert_eq!(events[0].kind, "code");
+        assert_eq!(events[0].line_count, 58);
+        assert!(events[0].content.is_none());
+        assert_eq!(events[0].content_hash, content_hash(&code));
+        assert_eq!(events[1].kind, "url");
+        assert_eq!(classify("会议纪要\n下周一开始"), "text");
+
+        let card = |start_time: &str, end_time: &str| TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: start_time.to_string(),
+            end_time: end_time.to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: "调试".to_string(),
+            summary: String::new(),
+            detailed_summary: "排查构建失败".to_string(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let mut cards = vec![card("00:00", "10:00"), card("10:00", "20:00")];
+        annotate_cards(&mut cards, &events, start, start + Duration::minutes(30));
+        assert_eq!(cards[0].detailed_summary, "排查构建失败");
+        assert_eq!(
+            cards[1].detailed_summary,
+            "排查构建失败\n\n剪贴板：14:12 复制了大段代码（58 行）（Code）"
+        );
+    }
+}
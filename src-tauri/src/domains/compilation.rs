@@ -0,0 +1,344 @@
+// 主题合集导出 - 把一段日期内某个标签或类别的卡片片段拼成一个视频
+//
+// 例如“本周所有设计工作”：按日期加载会话和卡片，类别、子类别、卡片关键词标签或会话标签
+// 与给定值一致的卡片入选。按卡片在会话视频中的位置截取片段（与卡片预览短片同样的时间换算），
+// 每段前插入写有日期、时间和卡片标题的标题卡，统一转码成相同尺寸和帧率后用 concat 拼接
+
+use super::metrics::parse_series_time;
+use super::playback::{card_video_range, session_mapping, CardVideoRange};
+use super::storage::StorageDomain;
+use crate::storage::{Session, TimelineCardRecord};
+use crate::video::{VideoMetadata, VideoUtils};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 单次导出的最长日期范围（天）
+const MAX_RANGE_DAYS: i64 = 31;
+/// 单个片段的最长时长（秒），避免一张长卡片占满合集
+const MAX_SEGMENT_SECONDS: f64 = 60.0;
+/// 片段的最短时长（秒），更短的卡片跳过
+const MIN_SEGMENT_SECONDS: f64 = 0.5;
+/// 标题卡时长（秒）
+const TITLE_CARD_SECONDS: f64 = 2.0;
+/// 合集画面尺寸
+const OUTPUT_SIZE: (u32, u32) = (1280, 720);
+
+/// 合集导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilationReport {
+    pub path: String,
+    /// 拼入合集的卡片数
+    pub clip_count: usize,
+    /// 匹配但因视频缺失、截取失败等原因跳过的卡片数
+    pub skipped_count: usize,
+    /// 合集时长（秒，含标题卡）
+    pub duration_secs: f64,
+}
+
+/// 合集中的一个片段
+struct Segment {
+    title: String,
+    video_path: String,
+    start_secs: f64,
+    length_secs: f64,
+}
+
+/// 会话标签中的类别和关键词
+fn session_tag_values(session: &Session) -> Vec<String> {
+    let Ok(tags) = serde_json::from_str::<Vec<serde_json::Value>>(&session.tags) else {
+        return Vec::new();
+    };
+    let mut values = Vec::new();
+    for tag in tags {
+        if let Some(category) = tag.get("category").and_then(|v| v.as_str()) {
+            values.push(category.to_string());
+        }
+        if let Some(keywords) = tag.get("keywords").and_then(|v| v.as_array()) {
+            values.extend(
+                keywords
+                    .iter()
+                    .filter_map(|k| k.as_str())
+                    .map(str::to_string),
+            );
+        }
+    }
+    values
+}
+
+/// 卡片是否属于给定的标签或类别（不区分大小写）
+pub fn card_matches(card: &TimelineCardRecord, session: &Session, tag_or_category: &str) -> bool {
+    let needle = tag_or_category.trim().to_lowercase();
+    if needle.is_empty() {
+        return false;
+    }
+    let keyword_tags: Vec<String> = card
+        .keyword_tags
+        .as_deref()
+        .and_then(|tags| serde_json::from_str(tags).ok())
+        .unwrap_or_default();
+    [card.category.clone(), card.subcategory.clone()]
+        .into_iter()
+        .chain(keyword_tags)
+        .chain(session_tag_values(session))
+        .any(|value| value.trim().to_lowercase() == needle)
+}
+
+/// 片段在视频中的截取位置：不超出视频结尾，过长的截取开头部分，过短的返回 None
+pub fn segment_window(range: &CardVideoRange, video_duration: Option<f64>) -> Option<(f64, f64)> {
+    let start = range.start_secs.max(0.0);
+    let mut end = range.end_secs;
+    if let Some(duration) = video_duration.filter(|d| *d > 0.0) {
+        end = end.min(duration);
+    }
+    let length = (end - start).min(MAX_SEGMENT_SECONDS);
+    (length >= MIN_SEGMENT_SECONDS).then_some((start, length))
+}
+
+/// 标题卡文字：日期、时间段和卡片标题
+fn segment_title(session: &Session, card: &TimelineCardRecord) -> String {
+    let start = parse_series_time(&card.start_time, session.start_time, session.end_time);
+    let end = parse_series_time(&card.end_time, session.start_time, session.end_time);
+    format!(
+        "{} {}-{}\n{}",
+        start.format("%Y-%m-%d"),
+        start.format("%H:%M"),
+        end.format("%H:%M"),
+        card.title
+    )
+}
+
+/// 导出日期范围内（含首尾两天，YYYY-MM-DD）某个标签或类别的合集视频到 `output_path`
+pub async fn export_compilation(
+    storage: &StorageDomain,
+    tag_or_category: &str,
+    start_date: &str,
+    end_date: &str,
+    output_path: &Path,
+) -> Result<CompilationReport, String> {
+    let tag_or_category = tag_or_category.trim();
+    if tag_or_category.is_empty() {
+        return Err("请指定标签或类别".to_string());
+    }
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))
+    };
+    let start = parse(start_date)?;
+    let end = parse(end_date)?;
+    if end < start {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end - start).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("日期范围不能超过 {} 天", MAX_RANGE_DAYS));
+    }
+
+    // 收集匹配的卡片片段（按时间顺序）
+    let db = storage.get_db().await?;
+    let mut seen_sessions = HashSet::new();
+    let mut segments = Vec::new();
+    let mut skipped_count = 0;
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let mut sessions = db
+            .get_sessions_by_date(&date.to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        sessions.sort_by_key(|session| session.start_time);
+        for session in sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            if !seen_sessions.insert(session_id) {
+                continue;
+            }
+            let mut cards: Vec<TimelineCardRecord> = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+                .into_iter()
+                .filter(|card| card_matches(card, &session, tag_or_category))
+                .collect();
+            if cards.is_empty() {
+                continue;
+            }
+            let Some(video_path) = session.video_path.clone() else {
+                skipped_count += cards.len();
+                continue;
+            };
+            cards.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+
+            let mapping = session_mapping(&session, storage.get_settings()).await;
+            let video_duration = VideoMetadata::load(Path::new(&video_path))
+                .await
+                .map(|metadata| metadata.video_duration_secs());
+            for card in &cards {
+                match segment_window(&card_video_range(&session, card, &mapping), video_duration) {
+                    Some((start_secs, length_secs)) => segments.push(Segment {
+                        title: segment_title(&session, card),
+                        video_path: video_path.clone(),
+                        start_secs,
+                        length_secs,
+                    }),
+                    None => skipped_count += 1,
+                }
+            }
+        }
+    }
+    if segments.is_empty() {
+        return Err(format!(
+            "{} 至 {} 没有“{}”的卡片片段",
+            start_date, end_date, tag_or_category
+        ));
+    }
+
+    // 逐段截取并生成标题卡，统一编码后拼接
+    let work_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let mut parts: Vec<PathBuf> = Vec::new();
+    let mut duration_secs = 0.0;
+
+    let cover = work_dir.path().join("cover.mp4");
+    let cover_text = format!("{}\n{} ~ {}", tag_or_category, start_date, end_date);
+    VideoUtils::generate_title_card(&cover, &cover_text, TITLE_CARD_SECONDS, OUTPUT_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+    parts.push(cover);
+    duration_secs += TITLE_CARD_SECONDS;
+
+    let mut clip_count = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        let local_video = match storage
+            .get_media()
+            .ensure_local_video(Path::new(&segment.video_path))
+            .await
+        {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("合集片段的视频不可用 {}: {}", segment.video_path, e);
+                skipped_count += 1;
+                continue;
+            }
+        };
+        let clip = work_dir.path().join(format!("clip_{:04}.mp4", index));
+        if let Err(e) = VideoUtils::cut_normalized_clip(
+            &local_video,
+            &clip,
+            segment.start_secs,
+            segment.length_secs,
+            OUTPUT_SIZE,
+        )
+        .await
+        {
+            warn!("截取合集片段失败 {}: {}", segment.video_path, e);
+            skipped_count += 1;
+            continue;
+        }
+        let title = work_dir.path().join(format!("title_{:04}.mp4", index));
+        VideoUtils::generate_title_card(&title, &segment.title, TITLE_CARD_SECONDS, OUTPUT_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        parts.push(title);
+        parts.push(clip);
+        duration_secs += TITLE_CARD_SECONDS + segment.length_secs;
+        clip_count += 1;
+    }
+    if clip_count == 0 {
+        return Err("所有片段都截取失败，请检查会话视频是否存在".to_string());
+    }
+
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+    VideoUtils::concatenate_videos(parts, output_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    info!(
+        "已导出“{}”合集: {:?}（{} 个片段，跳过 {} 个）",
+        tag_or_category, output_path, clip_count, skipped_count
+    );
+    Ok(CompilationReport {
+        path: output_path.to_string_lossy().to_string(),
+        clip_count,
+        skipped_count,
+        duration_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_card_matching_and_segment_window() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start,
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: r#"[{"category":"work","confidence":0.9,"keywords":["Figma"]}]"#.to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let card = TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T09:00:00+08:00".to_string(),
+            end_time: "2025-10-09T09:15:00+08:00".to_string(),
+            category: "work".to_string(),
+            subcategory: "Design".to_string(),
+            title: "首页视觉稿".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "[]".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: Some(r#"["官网改版"]"#.to_string()),
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        assert!(card_matches(&card, &session, " design "));
+        assert!(card_matches(&card, &session, "官网改版"));
+        assert!(card_matches(&card, &session, "figma"));
+        assert!(!card_matches(&card, &session, "meeting"));
+        assert!(!card_matches(&card, &session, ""));
+
+        let range = |start_secs, end_secs| CardVideoRange {
+            card_id: 1,
+            start_secs,
+            end_secs,
+        };
+        assert_eq!(
+            segment_window(&range(10.0, 20.0), Some(60.0)),
+            Some((10.0, 10.0))
+        );
+        // 过长的卡片只截开头
+        assert_eq!(segment_window(&range(0.0, 300.0), None), Some((0.0, 60.0)));
+        // 不超出视频结尾
+        assert_eq!(
+            segment_window(&range(50.0, 80.0), Some(55.0)),
+            Some((50.0, 5.0))
+        );
+        // 过短或完全在视频之外的跳过
+        assert_eq!(segment_window(&range(10.0, 10.2), None), None);
+        assert_eq!(segment_window(&range(70.0, 80.0), Some(60.0)), None);
+    }
+}
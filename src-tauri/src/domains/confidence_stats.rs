@@ -0,0 +1,283 @@
+// 置信度加权统计 - 在原始统计之外，按卡片置信度给时长加权，避免过度相信噪声较大的分析结果
+//
+// 每张卡片的权重：
+// - 人工确认或修正过的卡片权重为 1
+// - 其余卡片权重为模型给出的置信度；没有置信度的旧卡片按 1 计入，并单独统计数量
+// - 置信度低于复核阈值的卡片不计入加权统计
+// - 与同一设备上另一张不同类别的卡片重叠超过较短卡片一半的，视为互相矛盾，未经人工复核的一方不计入
+// 原始统计计入所有卡片。专注时长按类别生产力计算（不要求连续时长），两种口径一致，便于对比
+
+use super::live::FOCUS_PRODUCTIVITY;
+use super::metrics::{category_productivity, ActivitySpan};
+use super::range_summary::{clip_span, load_range_cards, parse_range, to_stats, RangeStat};
+use super::review::{REVIEW_CONFIRMED, REVIEW_CORRECTED};
+use super::summary::get_category_display_name;
+use crate::storage::{Database, TimelineCardRecord};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 重叠超过较短卡片时长的这一比例时视为矛盾
+const CONTRADICTION_OVERLAP: f64 = 0.5;
+
+/// 一组统计数字
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatFigures {
+    pub active_minutes: i64,
+    pub focus_minutes: i64,
+    pub categories: Vec<RangeStat>,
+}
+
+/// 原始统计和置信度加权统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfidenceStats {
+    /// 使用的置信度阈值
+    pub threshold: f64,
+    pub card_count: usize,
+    pub raw: StatFigures,
+    pub weighted: StatFigures,
+    /// 置信度低于阈值而排除的卡片数
+    pub low_confidence_cards: usize,
+    /// 与其他卡片矛盾而排除的卡片数
+    pub contradictory_cards: usize,
+    /// 排除的卡片时长（分钟）
+    pub excluded_minutes: i64,
+    /// 没有置信度、按权重 1 计入的卡片数
+    pub unrated_cards: usize,
+}
+
+/// 参与统计的卡片（已裁剪到时段内）
+#[derive(Debug, Clone)]
+pub struct WeightedSpan {
+    pub span: ActivitySpan,
+    pub confidence: Option<f64>,
+    /// 是否经过人工确认或修正
+    pub reviewed: bool,
+}
+
+impl WeightedSpan {
+    pub fn from_card(span: ActivitySpan, card: &TimelineCardRecord) -> Self {
+        Self {
+            span,
+            confidence: card.confidence,
+            reviewed: matches!(
+                card.review_status.as_deref(),
+                Some(REVIEW_CONFIRMED) | Some(REVIEW_CORRECTED)
+            ),
+        }
+    }
+}
+
+fn overlap_minutes(a: &ActivitySpan, b: &ActivitySpan) -> i64 {
+    (a.end.min(b.end) - a.start.max(b.start))
+        .num_minutes()
+        .max(0)
+}
+
+/// 找出互相矛盾的卡片：同一设备、类别不同且重叠超过较短卡片的一半，人工复核过的卡片不算
+fn contradictory(spans: &[WeightedSpan]) -> Vec<bool> {
+    let mut flags = vec![false; spans.len()];
+    for i in 0..spans.len() {
+        for j in i + 1..spans.len() {
+            let (a, b) = (&spans[i].span, &spans[j].span);
+            if a.device != b.device || a.category.eq_ignore_ascii_case(&b.category) {
+                continue;
+            }
+            let shorter = a.duration_minutes().min(b.duration_minutes());
+            if shorter <= 0
+                || (overlap_minutes(a, b) as f64) <= shorter as f64 * CONTRADICTION_OVERLAP
+            {
+                continue;
+            }
+            flags[i] |= !spans[i].reviewed;
+            flags[j] |= !spans[j].reviewed;
+        }
+    }
+    flags
+}
+
+fn figures(entries: &[(&ActivitySpan, f64)]) -> StatFigures {
+    let mut active = 0.0;
+    let mut focus = 0.0;
+    let mut categories: HashMap<String, f64> = HashMap::new();
+    for (span, weight) in entries {
+        let minutes = span.duration_minutes() as f64 * weight;
+        active += minutes;
+        if category_productivity(&span.category) >= FOCUS_PRODUCTIVITY {
+            focus += minutes;
+        }
+        *categories
+            .entry(get_category_display_name(&span.category))
+            .or_default() += minutes;
+    }
+    let active_minutes = active.round() as i64;
+    StatFigures {
+        active_minutes,
+        focus_minutes: focus.round() as i64,
+        categories: to_stats(
+            categories
+                .into_iter()
+                .map(|(name, minutes)| (name, minutes.round() as i64))
+                .collect(),
+            active_minutes,
+        ),
+    }
+}
+
+/// 计算原始统计和加权统计
+pub fn weigh_spans(spans: &[WeightedSpan], threshold: f64) -> ConfidenceStats {
+    let contradictions = contradictory(spans);
+    let mut stats = ConfidenceStats {
+        threshold,
+        card_count: spans.len(),
+        raw: StatFigures::default(),
+        weighted: StatFigures::default(),
+        low_confidence_cards: 0,
+        contradictory_cards: 0,
+        excluded_minutes: 0,
+        unrated_cards: 0,
+    };
+
+    let mut weighted = Vec::new();
+    for (item, is_contradictory) in spans.iter().zip(contradictions) {
+        let weight = if item.reviewed {
+            1.0
+        } else {
+            match item.confidence {
+                Some(confidence) if confidence < threshold => {
+                    stats.low_confidence_cards += 1;
+                    stats.excluded_minutes += item.span.duration_minutes();
+                    continue;
+                }
+                Some(confidence) => confidence.min(1.0),
+                None => {
+                    stats.unrated_cards += 1;
+                    1.0
+                }
+            }
+        };
+        if is_contradictory {
+            stats.contradictory_cards += 1;
+            stats.excluded_minutes += item.span.duration_minutes();
+            continue;
+        }
+        weighted.push((&item.span, weight));
+    }
+
+    let raw: Vec<(&ActivitySpan, f64)> = spans.iter().map(|item| (&item.span, 1.0)).collect();
+    stats.raw = figures(&raw);
+    stats.weighted = figures(&weighted);
+    stats
+}
+
+/// 获取任意时段的原始统计和置信度加权统计
+pub async fn get_confidence_stats(
+    db: &Database,
+    start_ts: &str,
+    end_ts: &str,
+    threshold: f64,
+) -> Result<ConfidenceStats, String> {
+    let (start, end) = parse_range(start_ts, end_ts)?;
+    let (_, cards) = load_range_cards(db, start, end).await?;
+    let spans: Vec<WeightedSpan> = cards
+        .iter()
+        .filter_map(|(span, card)| {
+            clip_span(span, start, end).map(|span| WeightedSpan::from_card(span, card))
+        })
+        .collect();
+    Ok(weigh_spans(&spans, threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn item(
+        start: &str,
+        end: &str,
+        category: &str,
+        confidence: Option<f64>,
+        reviewed: bool,
+    ) -> WeightedSpan {
+        WeightedSpan {
+            span: ActivitySpan {
+                start: DateTime::parse_from_rfc3339(start).unwrap(),
+                end: DateTime::parse_from_rfc3339(end).unwrap(),
+                category: category.to_string(),
+                subcategory: String::new(),
+                app: String::new(),
+                device: String::new(),
+            },
+            confidence,
+            reviewed,
+        }
+    }
+
+    #[test]
+    fn test_weights_minutes_and_excludes_unreliable_cards() {
+        let spans = vec![
+            // 60 分钟，置信度 0.9 → 54 分钟
+            item(
+                "2025-10-09T09:00:00+08:00",
+                "2025-10-09T10:00:00+08:00",
+                "work",
+                Some(0.9),
+                false,
+            ),
+            // 低置信度，排除
+            item(
+                "2025-10-09T10:00:00+08:00",
+                "2025-10-09T10:30:00+08:00",
+                "work",
+                Some(0.3),
+                false,
+            ),
+            // 人工确认过，即使置信度低也按 1 计入
+            item(
+                "2025-10-09T10:30:00+08:00",
+                "2025-10-09T11:00:00+08:00",
+                "meeting",
+                Some(0.2),
+                true,
+            ),
+            // 没有置信度，按 1 计入
+            item(
+                "2025-10-09T11:00:00+08:00",
+                "2025-10-09T11:20:00+08:00",
+                "learning",
+                None,
+                false,
+            ),
+            // 同一时段两张类别不同的卡片互相矛盾，都排除
+            item(
+                "2025-10-09T13:00:00+08:00",
+                "2025-10-09T13:40:00+08:00",
+                "work",
+                Some(0.8),
+                false,
+            ),
+            item(
+                "2025-10-09T13:10:00+08:00",
+                "2025-10-09T13:40:00+08:00",
+                "entertainment",
+                Some(0.7),
+                false,
+            ),
+        ];
+
+        let stats = weigh_spans(&spans, 0.6);
+        assert_eq!(stats.card_count, 6);
+        assert_eq!(stats.raw.active_minutes, 210);
+        assert_eq!(stats.raw.focus_minutes, 150);
+        assert_eq!(stats.low_confidence_cards, 1);
+        assert_eq!(stats.contradictory_cards, 2);
+        assert_eq!(stats.excluded_minutes, 100);
+        assert_eq!(stats.unrated_cards, 1);
+        assert_eq!(stats.weighted.active_minutes, 104);
+        assert_eq!(stats.weighted.focus_minutes, 74);
+        assert_eq!(stats.weighted.categories[0].name, "工作");
+        assert_eq!(stats.weighted.categories[0].minutes, 54);
+    }
+}
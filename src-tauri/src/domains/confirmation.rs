@@ -0,0 +1,164 @@
+// 危险操作确认 - 删除会话、清理存储、时区迁移等不可逆命令需要先申请确认令牌
+//
+// 前端在用户确认后调用 request_confirmation(action) 取得令牌，再把令牌随命令一起传入。
+// 令牌只对申请时的操作有效、只能使用一次，且很快过期，
+// 避免前端误调用或外部脚本直接调用命令造成数据丢失
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 令牌有效期
+const TOKEN_TTL: Duration = Duration::from_secs(120);
+
+/// 需要确认的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestructiveAction {
+    /// 删除会话
+    DeleteSession,
+    /// 立即清理存储
+    CleanupStorage,
+    /// 数据库时区迁移
+    MigrateTimezone,
+    /// 删除积压的未分析视频
+    DeleteAnalysisBacklog,
+    /// 删除全部数据
+    ResetAllData,
+}
+
+impl DestructiveAction {
+    /// 操作名称（用于错误信息）
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DeleteSession => "删除会话",
+            Self::CleanupStorage => "清理存储",
+            Self::MigrateTimezone => "时区迁移",
+            Self::DeleteAnalysisBacklog => "删除未分析视频",
+            Self::ResetAllData => "删除全部数据",
+        }
+    }
+}
+
+/// 确认令牌
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationToken {
+    pub token: String,
+    pub action: DestructiveAction,
+    pub expires_in_secs: u64,
+}
+
+/// 确认令牌管理
+#[derive(Default)]
+pub struct ConfirmationGuard {
+    pending: Mutex<HashMap<String, (DestructiveAction, Instant)>>,
+}
+
+impl ConfirmationGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为操作签发一次性令牌
+    pub fn issue(&self, action: DestructiveAction) -> ConfirmationToken {
+        self.issue_at(action, Instant::now())
+    }
+
+    fn issue_at(&self, action: DestructiveAction, now: Instant) -> ConfirmationToken {
+        let token = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.retain(|_, (_, issued_at)| now.duration_since(*issued_at) < TOKEN_TTL);
+            pending.insert(token.clone(), (action, now));
+        }
+        ConfirmationToken {
+            token,
+            action,
+            expires_in_secs: TOKEN_TTL.as_secs(),
+        }
+    }
+
+    /// 校验并消耗令牌（无论是否通过，令牌都会失效）
+    pub fn consume(&self, action: DestructiveAction, token: Option<&str>) -> Result<(), String> {
+        self.consume_at(action, token, Instant::now())
+    }
+
+    fn consume_at(
+        &self,
+        action: DestructiveAction,
+        token: Option<&str>,
+        now: Instant,
+    ) -> Result<(), String> {
+        let token = token.filter(|t| !t.is_empty()).ok_or_else(|| {
+            format!(
+                "{}需要确认令牌，请先调用 request_confirmation",
+                action.label()
+            )
+        })?;
+        let entry = self
+            .pending
+            .lock()
+            .map_err(|_| "确认令牌状态异常".to_string())?
+            .remove(token);
+        match entry {
+            Some((issued_for, issued_at))
+                if issued_for == action && now.duration_since(issued_at) < TOKEN_TTL =>
+            {
+                Ok(())
+            }
+            Some((issued_for, _)) if issued_for != action => Err(format!(
+                "确认令牌无效：该令牌用于{}，不能用于{}",
+                issued_for.label(),
+                action.label()
+            )),
+            _ => Err(format!(
+                "{}的确认令牌无效或已过期，请重新确认",
+                action.label()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_single_use_action_bound_and_expire() {
+        let guard = ConfirmationGuard::new();
+        let now = Instant::now();
+
+        assert!(guard
+            .consume_at(DestructiveAction::DeleteSession, None, now)
+            .is_err());
+
+        let token = guard.issue_at(DestructiveAction::DeleteSession, now).token;
+        assert!(guard
+            .consume_at(DestructiveAction::DeleteSession, Some(&token), now)
+            .is_ok());
+        // 只能使用一次
+        assert!(guard
+            .consume_at(DestructiveAction::DeleteSession, Some(&token), now)
+            .is_err());
+
+        // 不能用于其他操作，且校验失败后同样作废
+        let token = guard.issue_at(DestructiveAction::CleanupStorage, now).token;
+        assert!(guard
+            .consume_at(DestructiveAction::MigrateTimezone, Some(&token), now)
+            .is_err());
+        assert!(guard
+            .consume_at(DestructiveAction::CleanupStorage, Some(&token), now)
+            .is_err());
+
+        // 过期
+        let token = guard.issue_at(DestructiveAction::CleanupStorage, now).token;
+        assert!(guard
+            .consume_at(
+                DestructiveAction::CleanupStorage,
+                Some(&token),
+                now + TOKEN_TTL + Duration::from_secs(1),
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,735 @@
+// 数据体检 - 检查并修复视频分段和时间线卡片中的时间格式
+//
+// 早期版本有时把 LLM 返回的相对时间（MM:SS）原样写入数据库，或写入不带时区的时间，
+// 导致同一张表里混有相对时间和 RFC 3339。这里逐个会话检查：
+// - RFC 3339：正常，不处理
+// - 不带时区的日期时间：按本地时区补上时区
+// - 相对时间：按会话时间窗口换算为绝对时间；有视频元数据时与分析时一样判断是否为视频时间并按视频元数据换算（智能变速分段换算）
+// 无法解析的值（空字符串、乱码）不修改，列在报告中。
+//
+// 时间格式修复后再检查跨表不变量（报告中以 snake_case 的不变量名标识，便于脚本处理）：
+// - card_within_session：卡片时间在会话时间窗口内，略微超出的截到窗口边界，完全在窗口外的只标记
+// - segments_sorted：同一会话的分段按开始时间排列，只标记
+// - segments_non_overlapping：同一会话相邻分段不重叠，把前一个分段的结束时间截到后一个分段开始
+// - llm_call_exists：分段和卡片的 llm_call_id 引用的 LLM 调用记录存在，只标记
+// - video_file_exists：会话的 video_path 文件存在（已归档和使用远程存储的会话不检查），只标记
+// dry_run 时只报告不写入
+
+use crate::storage::{Database, Session};
+use crate::video::VideoMetadata;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// 报告中最多列出的修复示例条数
+const MAX_LISTED_FIXES: usize = 200;
+
+/// 卡片超出会话时间窗口的容差（秒），容差内不算违反
+const CARD_WINDOW_TOLERANCE_SECS: i64 = 60;
+
+/// 不带时区的日期时间格式
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// 一个时间值的检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeRepair {
+    /// 已是 RFC 3339
+    Valid,
+    /// 修复后的值
+    Fixed(String),
+    /// 无法修复的原因
+    Unfixable(String),
+}
+
+/// 一行修复记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorFix {
+    /// 表名（video_segments / timeline_cards）
+    pub table: String,
+    pub row_id: i64,
+    pub session_id: i64,
+    pub old_start: String,
+    pub old_end: String,
+    pub new_start: String,
+    pub new_end: String,
+}
+
+/// 无法修复的行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorIssue {
+    pub table: String,
+    pub row_id: i64,
+    pub session_id: i64,
+    pub start: String,
+    pub end: String,
+    pub reason: String,
+}
+
+/// 跨表不变量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// 卡片时间在会话时间窗口内
+    CardWithinSession,
+    /// 分段按开始时间排列
+    SegmentsSorted,
+    /// 相邻分段不重叠
+    SegmentsNonOverlapping,
+    /// llm_call_id 引用的记录存在
+    LlmCallExists,
+    /// video_path 文件存在
+    VideoFileExists,
+}
+
+/// 违反不变量的行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvariantViolation {
+    pub invariant: Invariant,
+    /// 表名（sessions / video_segments / timeline_cards）
+    pub table: String,
+    pub row_id: i64,
+    pub session_id: i64,
+    pub detail: String,
+    /// 是否已修复（dry_run 时为可以修复）
+    pub fixed: bool,
+}
+
+/// 体检报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDoctorReport {
+    pub dry_run: bool,
+    pub scanned_sessions: usize,
+    pub scanned_rows: usize,
+    /// 需要修复（dry_run 时为可以修复）的行数
+    pub fixed_rows: usize,
+    /// 修复示例（最多 200 条）
+    pub fixes: Vec<DoctorFix>,
+    pub unfixable: Vec<DoctorIssue>,
+    /// 违反不变量的行数（含已修复的）
+    pub violation_count: usize,
+    /// 违反不变量的行（最多 200 条）
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl DataDoctorReport {
+    fn push_violation(&mut self, violation: InvariantViolation) {
+        self.violation_count += 1;
+        if self.violations.len() < MAX_LISTED_FIXES {
+            self.violations.push(violation);
+        }
+    }
+}
+
+fn relative_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\d{1,4}(:\d{1,2}){0,2}(\.\d+)?$").unwrap())
+}
+
+/// 相对时间的秒数（不是相对时间时返回 None）
+fn relative_secs(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if !relative_pattern().is_match(value) {
+        return None;
+    }
+    Some(value.split(':').fold(0.0, |total, part| {
+        total * 60.0 + part.parse::<f64>().unwrap_or(0.0)
+    }))
+}
+
+fn to_local_rfc3339(naive: NaiveDateTime) -> Option<String> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.to_rfc3339())
+}
+
+/// 检查一个会话的时间值
+///
+/// `start`/`end` 为会话时间窗口（本地时间），`metadata` 为会话视频的元数据
+pub fn plan_repairs(
+    values: &[&str],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    metadata: Option<&VideoMetadata>,
+) -> Vec<TimeRepair> {
+    // 与分析时一致：所有相对时间都落在视频时长内时视为视频时间
+    let max_relative_secs = values
+        .iter()
+        .filter_map(|value| relative_secs(value))
+        .fold(0.0, f64::max);
+    let video_time = metadata.filter(|metadata| {
+        metadata.timestamp_scale(max_relative_secs, (end - start).num_seconds().max(0) as f64)
+            != 1.0
+    });
+
+    values
+        .iter()
+        .map(|value| {
+            let trimmed = value.trim();
+            if DateTime::parse_from_rfc3339(trimmed).is_ok() {
+                return TimeRepair::Valid;
+            }
+            if trimmed.is_empty() {
+                return TimeRepair::Unfixable("时间为空".to_string());
+            }
+            if let Some(naive) = NAIVE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(trimmed, format).ok())
+            {
+                return to_local_rfc3339(naive).map_or_else(
+                    || TimeRepair::Unfixable(format!("本地时间不存在: {}", trimmed)),
+                    TimeRepair::Fixed,
+                );
+            }
+            if relative_secs(trimmed).is_some() {
+                if end <= start {
+                    return TimeRepair::Unfixable("会话时间窗口无效".to_string());
+                }
+                let absolute =
+                    crate::llm::relative_to_absolute_video(start, end, trimmed, video_time);
+                return to_local_rfc3339(absolute.naive_utc()).map_or_else(
+                    || TimeRepair::Unfixable(format!("本地时间不存在: {}", trimmed)),
+                    TimeRepair::Fixed,
+                );
+            }
+            TimeRepair::Unfixable(format!("无法识别的时间格式: {}", trimmed))
+        })
+        .collect()
+}
+
+/// 合并一行的开始/结束检查结果：都正常时返回 None
+fn row_result(
+    start: &str,
+    end: &str,
+    repairs: &[TimeRepair],
+) -> Option<Result<(String, String), String>> {
+    let resolve = |original: &str, repair: &TimeRepair| match repair {
+        TimeRepair::Valid => Ok(original.to_string()),
+        TimeRepair::Fixed(value) => Ok(value.clone()),
+        TimeRepair::Unfixable(reason) => Err(reason.clone()),
+    };
+    if repairs.iter().all(|repair| *repair == TimeRepair::Valid) {
+        return None;
+    }
+    Some(
+        resolve(start, &repairs[0])
+            .and_then(|start| resolve(end, &repairs[1]).map(|end| (start, end))),
+    )
+}
+
+/// RFC 3339 时间换算为本地时间（与会话时间窗口的存储方式一致）
+fn parse_local(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|time| time.with_timezone(&Local).naive_local())
+}
+
+/// 卡片与会话时间窗口的关系
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardWindow {
+    Inside,
+    /// 略微超出，截到窗口边界后的时间
+    Clamped(NaiveDateTime, NaiveDateTime),
+    /// 完全在窗口外
+    Outside,
+}
+
+/// 检查卡片是否在会话时间窗口内
+pub fn check_card_window(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    window_start: NaiveDateTime,
+    window_end: NaiveDateTime,
+) -> CardWindow {
+    let tolerance = chrono::Duration::seconds(CARD_WINDOW_TOLERANCE_SECS);
+    if start >= window_start - tolerance && end <= window_end + tolerance {
+        return CardWindow::Inside;
+    }
+    let (clamped_start, clamped_end) = (start.max(window_start), end.min(window_end));
+    if clamped_end <= clamped_start {
+        return CardWindow::Outside;
+    }
+    CardWindow::Clamped(clamped_start, clamped_end)
+}
+
+/// 分段顺序检查发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentProblem {
+    pub invariant: Invariant,
+    pub row_id: i64,
+    /// 修复后的结束时间（只标记时为 None）
+    pub new_end: Option<NaiveDateTime>,
+}
+
+/// 按写入顺序检查分段：开始时间不能早于前一个分段，且不能与前一个分段重叠
+pub fn check_segment_order(rows: &[(i64, NaiveDateTime, NaiveDateTime)]) -> Vec<SegmentProblem> {
+    rows.windows(2)
+        .filter_map(|pair| {
+            let ((prev_id, prev_start, prev_end), (row_id, start, _)) = (pair[0], pair[1]);
+            if start < prev_start {
+                return Some(SegmentProblem {
+                    invariant: Invariant::SegmentsSorted,
+                    row_id,
+                    new_end: None,
+                });
+            }
+            (start < prev_end).then(|| SegmentProblem {
+                invariant: Invariant::SegmentsNonOverlapping,
+                row_id: prev_id,
+                // 截短后前一个分段没有剩余时长时只标记
+                new_end: (start > prev_start).then_some(start),
+            })
+        })
+        .collect()
+}
+
+struct SessionRows {
+    table: &'static str,
+    /// (行 ID, 开始, 结束, llm_call_id)
+    rows: Vec<(i64, String, String, Option<i64>)>,
+}
+
+/// 检查时使用的上下文
+pub struct DoctorContext {
+    pub dry_run: bool,
+    /// 使用远程存储后端时本地没有视频不代表视频丢失，不检查 video_path
+    pub remote_media: bool,
+    llm_call_ids: HashSet<i64>,
+}
+
+async fn check_session(
+    db: &Database,
+    session: &Session,
+    ctx: &DoctorContext,
+    report: &mut DataDoctorReport,
+) -> Result<(), String> {
+    let dry_run = ctx.dry_run;
+    let Some(session_id) = session.id else {
+        return Ok(());
+    };
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    let tables = [
+        SessionRows {
+            table: "video_segments",
+            rows: segments
+                .into_iter()
+                .filter_map(|s| Some((s.id?, s.start_timestamp, s.end_timestamp, s.llm_call_id)))
+                .collect(),
+        },
+        SessionRows {
+            table: "timeline_cards",
+            rows: cards
+                .into_iter()
+                .filter_map(|c| Some((c.id?, c.start_time, c.end_time, c.llm_call_id)))
+                .collect(),
+        },
+    ];
+
+    let metadata = match &session.video_path {
+        Some(path) => VideoMetadata::load(Path::new(path)).await,
+        None => None,
+    };
+    // 分段和卡片来自同一次分析，一起推断缩放比例
+    let values: Vec<&str> = tables
+        .iter()
+        .flat_map(|table| table.rows.iter())
+        .flat_map(|(_, start, end, _)| [start.as_str(), end.as_str()])
+        .collect();
+    let repairs = plan_repairs(
+        &values,
+        session.start_time,
+        session.end_time,
+        metadata.as_ref(),
+    );
+
+    let mut changed = false;
+    let mut index = 0;
+    // 时间格式修复后的各行（无法修复的行不参与不变量检查）
+    let mut resolved: [Vec<(i64, String, String)>; 2] = [Vec::new(), Vec::new()];
+    for (table_index, table) in tables.iter().enumerate() {
+        for (row_id, start, end, llm_call_id) in &table.rows {
+            report.scanned_rows += 1;
+            if let Some(call_id) = llm_call_id.filter(|id| !ctx.llm_call_ids.contains(id)) {
+                report.push_violation(InvariantViolation {
+                    invariant: Invariant::LlmCallExists,
+                    table: table.table.to_string(),
+                    row_id: *row_id,
+                    session_id,
+                    detail: format!("LLM 调用记录 {} 不存在", call_id),
+                    fixed: false,
+                });
+            }
+            let result = row_result(start, end, &repairs[index..index + 2]);
+            index += 2;
+            match result {
+                None => resolved[table_index].push((*row_id, start.clone(), end.clone())),
+                Some(Ok((new_start, new_end))) => {
+                    resolved[table_index].push((*row_id, new_start.clone(), new_end.clone()));
+                    if !dry_run {
+                        let saved = match table.table {
+                            "video_segments" => {
+                                db.update_video_segment_times(*row_id, &new_start, &new_end)
+                                    .await
+                            }
+                            _ => {
+                                db.update_timeline_card_times(*row_id, &new_start, &new_end)
+                                    .await
+                            }
+                        };
+                        if let Err(e) = saved {
+                            report.unfixable.push(DoctorIssue {
+                                table: table.table.to_string(),
+                                row_id: *row_id,
+                                session_id,
+                                start: start.clone(),
+                                end: end.clone(),
+                                reason: format!("写入失败: {}", e),
+                            });
+                            resolved[table_index].pop();
+                            continue;
+                        }
+                        changed = true;
+                    }
+                    report.fixed_rows += 1;
+                    if report.fixes.len() < MAX_LISTED_FIXES {
+                        report.fixes.push(DoctorFix {
+                            table: table.table.to_string(),
+                            row_id: *row_id,
+                            session_id,
+                            old_start: start.clone(),
+                            old_end: end.clone(),
+                            new_start,
+                            new_end,
+                        });
+                    }
+                }
+                Some(Err(reason)) => report.unfixable.push(DoctorIssue {
+                    table: table.table.to_string(),
+                    row_id: *row_id,
+                    session_id,
+                    start: start.clone(),
+                    end: end.clone(),
+                    reason,
+                }),
+            }
+        }
+    }
+
+    let [segments, cards] = resolved;
+    changed |= check_session_invariants(db, session, &segments, &cards, ctx, report).await;
+    if changed {
+        db.invalidate_session(session_id).await;
+    }
+    Ok(())
+}
+
+fn parse_rows(rows: &[(i64, String, String)]) -> Vec<(i64, NaiveDateTime, NaiveDateTime)> {
+    rows.iter()
+        .filter_map(|(row_id, start, end)| Some((*row_id, parse_local(start)?, parse_local(end)?)))
+        .collect()
+}
+
+/// 检查一个会话的卡片时间窗口、分段顺序和视频文件，返回是否写入了修复
+async fn check_session_invariants(
+    db: &Database,
+    session: &Session,
+    segments: &[(i64, String, String)],
+    cards: &[(i64, String, String)],
+    ctx: &DoctorContext,
+    report: &mut DataDoctorReport,
+) -> bool {
+    let Some(session_id) = session.id else {
+        return false;
+    };
+    let mut changed = false;
+    let (window_start, window_end) = (session.start_time.naive_utc(), session.end_time.naive_utc());
+    let format_time = |time: NaiveDateTime| to_local_rfc3339(time).unwrap_or_default();
+
+    if window_end > window_start {
+        for (row_id, start, end) in parse_rows(cards) {
+            let (detail, fixed) = match check_card_window(start, end, window_start, window_end) {
+                CardWindow::Inside => continue,
+                CardWindow::Outside => ("卡片完全在会话时间窗口外".to_string(), false),
+                CardWindow::Clamped(new_start, new_end) => {
+                    let fixed = ctx.dry_run
+                        || db
+                            .update_timeline_card_times(
+                                row_id,
+                                &format_time(new_start),
+                                &format_time(new_end),
+                            )
+                            .await
+                            .map_err(|e| warn!("修正卡片 {} 的时间失败: {}", row_id, e))
+                            .is_ok();
+                    changed |= fixed && !ctx.dry_run;
+                    (
+                        format!(
+                            "卡片时间 {} ~ {} 超出会话时间窗口，截为 {} ~ {}",
+                            start, end, new_start, new_end
+                        ),
+                        fixed,
+                    )
+                }
+            };
+            report.push_violation(InvariantViolation {
+                invariant: Invariant::CardWithinSession,
+                table: "timeline_cards".to_string(),
+                row_id,
+                session_id,
+                detail,
+                fixed,
+            });
+        }
+    }
+
+    let segment_rows = parse_rows(segments);
+    for problem in check_segment_order(&segment_rows) {
+        let (detail, fixed) = match (problem.invariant, problem.new_end) {
+            (Invariant::SegmentsNonOverlapping, Some(new_end)) => {
+                let start = segment_rows
+                    .iter()
+                    .find(|(row_id, _, _)| *row_id == problem.row_id)
+                    .map(|(_, start, _)| *start)
+                    .unwrap_or(new_end);
+                let fixed = ctx.dry_run
+                    || db
+                        .update_video_segment_times(
+                            problem.row_id,
+                            &format_time(start),
+                            &format_time(new_end),
+                        )
+                        .await
+                        .map_err(|e| warn!("修正分段 {} 的时间失败: {}", problem.row_id, e))
+                        .is_ok();
+                changed |= fixed && !ctx.dry_run;
+                (format!("与下一个分段重叠，结束时间截为 {}", new_end), fixed)
+            }
+            (Invariant::SegmentsNonOverlapping, None) => {
+                ("与下一个分段重叠且开始时间相同".to_string(), false)
+            }
+            _ => ("开始时间早于前一个分段".to_string(), false),
+        };
+        report.push_violation(InvariantViolation {
+            invariant: problem.invariant,
+            table: "video_segments".to_string(),
+            row_id: problem.row_id,
+            session_id,
+            detail,
+            fixed,
+        });
+    }
+
+    if !ctx.remote_media && !session.archived {
+        if let Some(path) = session
+            .video_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            // 无法判断时（如权限错误）视为存在，避免误报
+            if !tokio::fs::try_exists(path).await.unwrap_or(true) {
+                report.push_violation(InvariantViolation {
+                    invariant: Invariant::VideoFileExists,
+                    table: "sessions".to_string(),
+                    row_id: session_id,
+                    session_id,
+                    detail: format!("视频文件不存在: {}", path),
+                    fixed: false,
+                });
+            }
+        }
+    }
+    changed
+}
+
+/// 检查所有会话的时间格式和跨表不变量，dry_run 为 false 时写入修复结果
+pub async fn run(
+    db: &Database,
+    dry_run: bool,
+    remote_media: bool,
+) -> Result<DataDoctorReport, String> {
+    let sessions = db
+        .get_all_sessions()
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let ctx = DoctorContext {
+        dry_run,
+        remote_media,
+        llm_call_ids: db
+            .get_llm_call_ids()
+            .await
+            .map_err(|e| format!("获取 LLM 调用记录失败: {}", e))?
+            .into_iter()
+            .collect(),
+    };
+    let mut report = DataDoctorReport {
+        dry_run,
+        ..DataDoctorReport::default()
+    };
+    for session in &sessions {
+        if let Err(e) = check_session(db, session, &ctx, &mut report).await {
+            warn!("检查会话 {:?} 失败: {}", session.id, e);
+            continue;
+        }
+        report.scanned_sessions += 1;
+    }
+    info!(
+        "数据体检完成{}: {} 个会话 {} 行，修复 {} 行，无法修复 {} 行，违反不变量 {} 处",
+        if dry_run { "（仅检查）" } else { "" },
+        report.scanned_sessions,
+        report.scanned_rows,
+        report.fixed_rows,
+        report.unfixable.len(),
+        report.violation_count
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_and_naive_times_are_repaired() {
+        let start = NaiveDateTime::parse_from_str("2025-10-09 14:00:00", "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc();
+        let end = start + chrono::Duration::minutes(30);
+        let local = |value: &str| {
+            to_local_rfc3339(NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap())
+                .unwrap()
+        };
+
+        let valid = local("2025-10-09 14:01:00");
+        let repairs = plan_repairs(
+            &[&valid, "05:30", "2025-10-09T14:20:00", "", "soon", "45:00"],
+            start,
+            end,
+            None,
+        );
+        assert_eq!(
+            repairs,
+            vec![
+                TimeRepair::Valid,
+                TimeRepair::Fixed(local("2025-10-09 14:05:30")),
+                TimeRepair::Fixed(local("2025-10-09 14:20:00")),
+                TimeRepair::Unfixable("时间为空".to_string()),
+                TimeRepair::Unfixable("无法识别的时间格式: soon".to_string()),
+                // 超出会话的相对时间截到会话结束
+                TimeRepair::Fixed(local("2025-10-09 14:30:00")),
+            ]
+        );
+
+        assert_eq!(row_result(&valid, &valid, &repairs[0..1].repeat(2)), None);
+        assert_eq!(
+            row_result(&valid, "05:30", &repairs[0..2]),
+            Some(Ok((valid.clone(), local("2025-10-09 14:05:30"))))
+        );
+        assert!(matches!(
+            row_result("", "soon", &repairs[3..5]),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn test_video_times_follow_speed_map() {
+        let start = NaiveDateTime::parse_from_str("2025-10-09 14:00:00", "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc();
+        let end = start + chrono::Duration::minutes(30);
+        let local = |value: &str| {
+            to_local_rfc3339(NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap())
+                .unwrap()
+        };
+        // 每 5 秒一帧、8 倍速：前 300 帧静止（每帧 0.25 秒），后 60 帧有变化（每帧 1 秒），视频约 16.9 秒
+        let metadata = VideoMetadata::new(&crate::video::VideoConfig::default(), 5.0, 360)
+            .with_speed_runs(vec![
+                crate::video::SpeedRun {
+                    frames: 300,
+                    display_secs: 0.25,
+                },
+                crate::video::SpeedRun {
+                    frames: 60,
+                    display_secs: 1.0,
+                },
+            ]);
+
+        // 按变速映射分段换算，而不是按平均倍速线性换算（00:10 线性换算会落在 14:17:46）
+        let repairs = plan_repairs(&["00:05", "00:10"], start, end, Some(&metadata));
+        assert_eq!(
+            repairs,
+            vec![
+                TimeRepair::Fixed(local("2025-10-09 14:13:20")),
+                TimeRepair::Fixed(local("2025-10-09 14:25:25")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_card_window_and_segment_order_invariants() {
+        let at = |value: &str| {
+            NaiveDateTime::parse_from_str(&format!("2025-10-09 {}", value), "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+        };
+        let (start, end) = (at("14:00:00"), at("14:30:00"));
+
+        assert_eq!(
+            check_card_window(at("14:00:00"), at("14:30:30"), start, end),
+            CardWindow::Inside
+        );
+        assert_eq!(
+            check_card_window(at("13:50:00"), at("14:10:00"), start, end),
+            CardWindow::Clamped(at("14:00:00"), at("14:10:00"))
+        );
+        assert_eq!(
+            check_card_window(at("15:00:00"), at("15:10:00"), start, end),
+            CardWindow::Outside
+        );
+
+        let problems = check_segment_order(&[
+            (1, at("14:00:00"), at("14:10:00")),
+            (2, at("14:08:00"), at("14:20:00")),
+            (3, at("14:05:00"), at("14:06:00")),
+            (4, at("14:05:00"), at("14:07:00")),
+        ]);
+        assert_eq!(
+            problems,
+            vec![
+                SegmentProblem {
+                    invariant: Invariant::SegmentsNonOverlapping,
+                    row_id: 1,
+                    new_end: Some(at("14:08:00")),
+                },
+                SegmentProblem {
+                    invariant: Invariant::SegmentsSorted,
+                    row_id: 3,
+                    new_end: None,
+                },
+                SegmentProblem {
+                    invariant: Invariant::SegmentsNonOverlapping,
+                    row_id: 3,
+                    new_end: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// 数据重置 - 一键删除应用记录的全部数据（截图、视频、数据库、配置和日志），便于评估后彻底卸载
+//
+// reset_all_data 在调用方暂停截屏后执行：
+// 1. 可选地先把将要删除的全部文件打包为一个 zip，作为最后一份备份
+// 2. 删除应用数据目录下的所有文件、自定义数据目录中属于本应用的目录和数据库文件，以及日志目录
+// 3. 运行中被占用无法删除的文件（如 Windows 上打开中的 SQLite 数据库）记录到 reset_pending.json，
+//    下次启动时在打开数据库前删除
+// MariaDB 数据库和远程对象存储中的视频位于用户自己的服务器上，不在删除范围内
+
+use crate::instance::LOCK_FILE_NAME;
+use crate::storage::data_location::{collect_files, DATABASE_FILES, DATA_SUBDIRS};
+use serde::Serialize;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 待删除文件记录（位于应用数据目录）
+pub const PENDING_FILE: &str = "reset_pending.json";
+
+/// 自定义数据目录中除 DATA_SUBDIRS 外由本应用创建的目录
+const EXTRA_DATA_DIRS: [&str; 2] = ["temp", "trash"];
+
+/// 已经是压缩格式的文件，写入备份时直接存储
+const STORED_EXTENSIONS: [&str; 5] = ["mp4", "jpg", "jpeg", "png", "gz"];
+
+/// 删除结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetReport {
+    /// 备份文件路径（未备份时为 None）
+    pub backup_path: Option<String>,
+    /// 备份文件大小（字节）
+    pub backup_bytes: u64,
+    /// 已释放的空间（字节）
+    pub freed_bytes: u64,
+    /// 已删除的文件数
+    pub deleted_files: u64,
+    /// 暂时无法删除、下次启动时删除的文件
+    pub pending: Vec<String>,
+    /// 下次启动时删除的文件大小（字节）
+    pub pending_bytes: u64,
+}
+
+/// 要删除的数据
+#[derive(Debug, Clone)]
+struct ResetTarget {
+    /// 备份中的顶层目录名
+    label: &'static str,
+    /// 备份中相对路径的起点
+    base: PathBuf,
+    /// 要删除的文件或目录
+    path: PathBuf,
+}
+
+/// 应用数据目录下的所有文件、自定义数据目录中属于本应用的部分和日志目录
+fn reset_targets(app_dir: &Path, data_dir: &Path, log_dir: Option<&Path>) -> Vec<ResetTarget> {
+    let mut targets = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(app_dir) {
+        for entry in entries.flatten() {
+            // 实例锁由运行中的本进程持有，删除后其他实例会误以为可以启动，退出时自行释放
+            if entry.file_name() == LOCK_FILE_NAME {
+                continue;
+            }
+            targets.push(ResetTarget {
+                label: "app",
+                base: app_dir.to_path_buf(),
+                path: entry.path(),
+            });
+        }
+    }
+
+    if !data_dir.starts_with(app_dir) {
+        let names = DATA_SUBDIRS
+            .iter()
+            .chain(EXTRA_DATA_DIRS.iter())
+            .chain(DATABASE_FILES.iter());
+        for name in names {
+            let path = data_dir.join(name);
+            if path.exists() {
+                targets.push(ResetTarget {
+                    label: "data",
+                    base: data_dir.to_path_buf(),
+                    path,
+                });
+            }
+        }
+    }
+
+    if let Some(log_dir) = log_dir.filter(|dir| dir.exists() && !dir.starts_with(app_dir)) {
+        targets.push(ResetTarget {
+            label: "logs",
+            base: log_dir.to_path_buf(),
+            path: log_dir.to_path_buf(),
+        });
+    }
+    targets
+}
+
+/// 目标下的所有文件及大小
+fn target_files(path: &Path) -> Vec<(PathBuf, u64)> {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => collect_files(path),
+        Ok(meta) => vec![(path.to_path_buf(), meta.len())],
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 把全部数据打包为 zip，返回备份文件大小
+fn write_backup(targets: &[ResetTarget], path: &Path) -> Result<u64, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    }
+    let file = File::create(path).map_err(|e| format!("创建备份文件失败: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    let stored = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    for target in targets {
+        for (source, _) in target_files(&target.path) {
+            let relative = source.strip_prefix(&target.base).unwrap_or(&source);
+            let name = format!(
+                "{}/{}",
+                target.label,
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            let compressed = source
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| STORED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+            // 写入中的日志等文件可能打不开，跳过不影响其他数据的备份
+            let mut input = match File::open(&source) {
+                Ok(input) => input,
+                Err(e) => {
+                    warn!("备份时无法读取 {:?}: {}", source, e);
+                    continue;
+                }
+            };
+            zip.start_file(name.as_str(), if compressed { stored } else { deflated })
+                .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+            std::io::copy(&mut input, &mut zip)
+                .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        }
+    }
+
+    zip.finish().map_err(|e| format!("完成备份失败: {}", e))?;
+    std::fs::metadata(path)
+        .map(|meta| meta.len())
+        .map_err(|e| format!("读取备份文件失败: {}", e))
+}
+
+/// 删除全部数据（阻塞调用），`backup` 不为空时先备份到该路径
+pub fn reset_all_data(
+    app_dir: &Path,
+    data_dir: &Path,
+    log_dir: Option<&Path>,
+    backup: Option<&Path>,
+) -> Result<ResetReport, String> {
+    let targets = reset_targets(app_dir, data_dir, log_dir);
+
+    let mut report = ResetReport {
+        backup_path: None,
+        backup_bytes: 0,
+        freed_bytes: 0,
+        deleted_files: 0,
+        pending: Vec::new(),
+        pending_bytes: 0,
+    };
+
+    if let Some(backup) = backup {
+        let inside_targets = backup.starts_with(app_dir)
+            || targets
+                .iter()
+                .any(|target| backup.starts_with(&target.path));
+        if inside_targets {
+            return Err("备份文件不能保存在将被删除的目录中".to_string());
+        }
+        report.backup_bytes = write_backup(&targets, backup)?;
+        report.backup_path = Some(backup.to_string_lossy().to_string());
+        info!(
+            "已备份全部数据到 {:?} ({} 字节)",
+            backup, report.backup_bytes
+        );
+    }
+
+    for target in &targets {
+        for (file, size) in target_files(&target.path) {
+            match std::fs::remove_file(&file) {
+                Ok(()) => {
+                    report.freed_bytes += size;
+                    report.deleted_files += 1;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    warn!("暂时无法删除 {:?}: {}，下次启动时删除", file, e);
+                    report.pending_bytes += size;
+                    report.pending.push(file.to_string_lossy().to_string());
+                }
+            }
+        }
+        if target.path.is_dir() {
+            // 删除剩下的空目录（仍有文件被占用时保留该文件所在的目录）
+            let _ = std::fs::remove_dir_all(&target.path);
+        }
+    }
+
+    if !report.pending.is_empty() {
+        let json = serde_json::to_string_pretty(&report.pending)
+            .map_err(|e| format!("序列化待删除文件失败: {}", e))?;
+        std::fs::create_dir_all(app_dir)
+            .and_then(|_| std::fs::write(app_dir.join(PENDING_FILE), json))
+            .map_err(|e| format!("记录待删除文件失败: {}", e))?;
+    }
+
+    info!(
+        "已删除全部数据：{} 个文件，释放 {} 字节，{} 个文件待下次启动删除",
+        report.deleted_files,
+        report.freed_bytes,
+        report.pending.len()
+    );
+    Ok(report)
+}
+
+/// 启动时（打开数据库前）删除上次重置时未能删除的文件
+pub fn finish_pending_reset(app_dir: &Path) {
+    let path = app_dir.join(PENDING_FILE);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let pending: Vec<String> = serde_json::from_str(&content).unwrap_or_default();
+
+    let remaining: Vec<String> = pending
+        .into_iter()
+        .filter(|file| match std::fs::remove_file(file) {
+            Ok(()) => false,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                warn!("删除上次重置遗留的文件失败 {}: {}", file, e);
+                true
+            }
+        })
+        .collect();
+
+    let result = if remaining.is_empty() {
+        info!("已删除上次重置时未能删除的文件");
+        std::fs::remove_file(&path)
+    } else {
+        serde_json::to_string_pretty(&remaining)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&path, json))
+    };
+    if let Err(e) = result {
+        warn!("更新待删除文件记录失败 {:?}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_reset_backs_up_and_removes_everything() {
+        let root = tempfile::tempdir().unwrap();
+        let app_dir = root.path().join("app");
+        let data_dir = root.path().join("data");
+        let log_dir = root.path().join("logs");
+        let files = [
+            (app_dir.join("config.json"), "{}"),
+            (app_dir.join("plugins/plugin.json"), "[]"),
+            (data_dir.join("data.db"), "sqlite"),
+            (data_dir.join("videos/segment.mp4"), "video"),
+            (data_dir.join("frames/kept/segment/1.jpg"), "jpg"),
+            (log_dir.join("app.log"), "log line"),
+        ];
+        for (path, content) in &files {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        // 自定义数据目录中不属于本应用的文件不删除
+        std::fs::write(data_dir.join("notes.txt"), "mine").unwrap();
+        // 运行中的实例锁保留
+        std::fs::write(app_dir.join(LOCK_FILE_NAME), "lock").unwrap();
+
+        // 备份不能放在将被删除的目录中
+        assert!(reset_all_data(
+            &app_dir,
+            &data_dir,
+            Some(&log_dir),
+            Some(&data_dir.join("videos/backup.zip"))
+        )
+        .is_err());
+
+        let backup = root.path().join("backup/final.zip");
+        let report = reset_all_data(&app_dir, &data_dir, Some(&log_dir), Some(&backup)).unwrap();
+        assert_eq!(report.deleted_files, files.len() as u64);
+        let total: usize = files.iter().map(|(_, content)| content.len()).sum();
+        assert_eq!(report.freed_bytes, total as u64);
+        assert!(report.pending.is_empty());
+        assert!(!data_dir.join("videos").exists());
+        assert!(!log_dir.exists());
+        assert_eq!(std::fs::read_dir(&app_dir).unwrap().count(), 1);
+        assert!(app_dir.join(LOCK_FILE_NAME).exists());
+        assert!(data_dir.join("notes.txt").exists());
+
+        let mut archive = zip::ZipArchive::new(File::open(&backup).unwrap()).unwrap();
+        assert_eq!(archive.len(), files.len());
+        let mut config = String::new();
+        archive
+            .by_name("app/config.json")
+            .unwrap()
+            .read_to_string(&mut config)
+            .unwrap();
+        assert_eq!(config, "{}");
+        assert!(archive.by_name("data/videos/segment.mp4").is_ok());
+        assert!(archive.by_name("logs/app.log").is_ok());
+
+        // 上次未能删除的文件在下次启动时删除
+        let leftover = data_dir.join("data.db-wal");
+        std::fs::write(&leftover, "wal").unwrap();
+        std::fs::write(
+            app_dir.join(PENDING_FILE),
+            serde_json::to_string(&[leftover.to_string_lossy()]).unwrap(),
+        )
+        .unwrap();
+        finish_pending_reset(&app_dir);
+        assert!(!leftover.exists());
+        assert!(!app_dir.join(PENDING_FILE).exists());
+    }
+}
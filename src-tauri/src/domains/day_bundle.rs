@@ -0,0 +1,396 @@
+// 单日离线包 - 把某天的会话视频、时间线数据和一个离线 HTML 查看器打包成 zip
+//
+// 便于把一天的活动记录交给别人查看，而不需要对方安装应用或访问数据库：
+// - data.json：当天的会话、时间线卡片、书签和每日总结（卡片和书签附带在视频中的位置）
+// - index.html：解压后直接用浏览器打开的查看器（数据内嵌在页面中，file:// 下也能使用）
+// - videos/：会话视频（远程存储中的视频会先拉回本地）
+// 隐私敏感度高于设置值的卡片不写入离线包
+
+use super::bookmarks::bundle_bookmarks;
+use super::metrics::parse_series_time;
+use super::playback::{card_video_range, session_mapping, VideoTimeMapping};
+use super::storage::StorageDomain;
+use crate::storage::{Session, TimelineCardRecord};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 查看器页面模板，数据替换 __BUNDLE_DATA__ 占位符
+const VIEWER_TEMPLATE: &str = include_str!("day_bundle_viewer.html");
+
+/// 离线包中的时间线卡片
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleCard {
+    pub title: String,
+    pub category: String,
+    pub subcategory: String,
+    pub summary: String,
+    /// 本地时间 (YYYY-MM-DD HH:MM:SS)
+    pub start_time: String,
+    pub end_time: String,
+    /// 卡片在会话视频中的位置（秒），没有视频时为 None
+    pub video_start_secs: Option<f64>,
+    pub video_end_secs: Option<f64>,
+}
+
+/// 离线包中的书签
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleBookmark {
+    /// 本地时间 (YYYY-MM-DD HH:MM:SS)
+    pub time: String,
+    pub note: String,
+    /// 书签在会话视频中的位置（秒），没有视频时为 None
+    pub video_secs: Option<f64>,
+}
+
+/// 离线包中的会话
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSession {
+    pub id: i64,
+    pub title: String,
+    pub summary: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub device_name: Option<String>,
+    /// 视频在包内的相对路径
+    pub video: Option<String>,
+    pub cards: Vec<BundleCard>,
+    pub bookmarks: Vec<BundleBookmark>,
+}
+
+/// 离线包数据（data.json）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleData {
+    pub date: String,
+    pub generated_at: String,
+    pub summary_text: Option<String>,
+    pub sessions: Vec<BundleSession>,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleReport {
+    pub path: String,
+    pub session_count: usize,
+    pub video_count: usize,
+    /// 本地和存储后端都找不到的视频数
+    pub missing_videos: usize,
+    pub size_bytes: u64,
+    /// 因隐私敏感度被排除的卡片数
+    pub privacy_excluded: usize,
+}
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// 转换会话及其卡片，`video` 为视频在包内的路径（没有视频时不计算卡片的视频位置）
+pub fn bundle_session(
+    session: &Session,
+    cards: &[TimelineCardRecord],
+    video: Option<String>,
+    mapping: &VideoTimeMapping,
+) -> BundleSession {
+    let cards = cards
+        .iter()
+        .map(|card| {
+            let range = video
+                .as_ref()
+                .map(|_| card_video_range(session, card, mapping));
+            BundleCard {
+                title: card.title.clone(),
+                category: card.category.clone(),
+                subcategory: card.subcategory.clone(),
+                summary: card.summary.clone(),
+                start_time: format_time(parse_series_time(
+                    &card.start_time,
+                    session.start_time,
+                    session.end_time,
+                )),
+                end_time: format_time(parse_series_time(
+                    &card.end_time,
+                    session.start_time,
+                    session.end_time,
+                )),
+                video_start_secs: range.as_ref().map(|range| range.start_secs),
+                video_end_secs: range.as_ref().map(|range| range.end_secs),
+            }
+        })
+        .collect();
+
+    BundleSession {
+        id: session.id.unwrap_or_default(),
+        title: session.title.clone(),
+        summary: session.summary.clone(),
+        start_time: format_time(session.start_time.naive_utc()),
+        end_time: format_time(session.end_time.naive_utc()),
+        device_name: session.device_name.clone(),
+        video,
+        cards,
+        bookmarks: Vec::new(),
+    }
+}
+
+/// 渲染查看器页面（转义 "</" 避免数据中的文本提前结束 script 标签）
+pub fn render_viewer(data: &BundleData) -> Result<String, String> {
+    let json = serde_json::to_string(data)
+        .map_err(|e| format!("序列化离线包数据失败: {}", e))?
+        .replace("</", "<\\/");
+    Ok(VIEWER_TEMPLATE.replace("__BUNDLE_DATA__", &json))
+}
+
+/// 写入 zip：视频已经是压缩格式，直接存储不再压缩
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    data: &BundleData,
+    videos: &[(String, PathBuf)],
+) -> Result<W, String> {
+    let mut zip = ZipWriter::new(writer);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let stored = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    let json =
+        serde_json::to_string_pretty(data).map_err(|e| format!("序列化离线包数据失败: {}", e))?;
+    let entries = [("index.html", render_viewer(data)?), ("data.json", json)];
+    for (name, content) in entries {
+        zip.start_file(name, deflated)
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    }
+
+    for (name, source) in videos {
+        let mut file =
+            File::open(source).map_err(|e| format!("打开视频 {:?} 失败: {}", source, e))?;
+        zip.start_file(name.as_str(), stored)
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        std::io::copy(&mut file, &mut zip).map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    }
+
+    zip.finish().map_err(|e| format!("完成离线包失败: {}", e))
+}
+
+/// 导出某天的离线包到 `path`
+pub async fn export_day_bundle(
+    storage: &StorageDomain,
+    date: &str,
+    path: &Path,
+) -> Result<BundleReport, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))?;
+    let db = storage.get_db().await?;
+    let mut sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    sessions.sort_by_key(|session| session.start_time);
+    let max_level = super::privacy::max_export_level(&storage.get_settings().get().await);
+
+    let mut bundle_sessions = Vec::new();
+    let mut videos = Vec::new();
+    let mut missing_videos = 0;
+    let mut privacy_excluded = 0;
+    for session in &sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let cards = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        let (cards, excluded) = super::privacy::filter_cards(cards, max_level);
+        privacy_excluded += excluded;
+
+        let mut video = None;
+        if let Some(video_path) = session.video_path.as_deref().map(PathBuf::from) {
+            match storage.get_media().ensure_local_video(&video_path).await {
+                Ok(video_path) => {
+                    let extension = video_path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .unwrap_or("mp4");
+                    let name = format!("videos/session_{}.{}", session_id, extension);
+                    videos.push((name.clone(), video_path.clone()));
+                    video = Some(name);
+                }
+                Err(e) => {
+                    warn!("会话 {} 的视频不可用，离线包中不包含: {}", session_id, e);
+                    missing_videos += 1;
+                }
+            }
+        }
+
+        let bookmarks = db
+            .get_session_bookmarks(session_id)
+            .await
+            .map_err(|e| format!("获取书签失败: {}", e))?;
+
+        let mapping = session_mapping(session, storage.get_settings()).await;
+        let has_video = video.is_some();
+        let mut bundled = bundle_session(session, &cards, video, &mapping);
+        bundled.bookmarks = bundle_bookmarks(session, &bookmarks, &mapping, has_video);
+        bundle_sessions.push(bundled);
+    }
+
+    let summary_text = db
+        .get_day_summary(date)
+        .await
+        .ok()
+        .flatten()
+        .map(|summary| summary.summary_text)
+        .filter(|text| !text.trim().is_empty());
+    let data = BundleData {
+        date: date.to_string(),
+        generated_at: format_time(crate::storage::local_now().naive_utc()),
+        summary_text,
+        sessions: bundle_sessions,
+    };
+
+    // 先写入临时文件，完成后再改名，避免留下不完整的压缩包
+    let target = path.to_path_buf();
+    let partial = target.with_extension("zip.part");
+    let video_count = videos.len();
+    let size_bytes = tokio::task::spawn_blocking(move || {
+        let file =
+            File::create(&partial).map_err(|e| format!("创建文件 {:?} 失败: {}", partial, e))?;
+        let result = write_zip(file, &data, &videos).and_then(|file| {
+            file.sync_all()
+                .map_err(|e| format!("写入离线包失败: {}", e))?;
+            std::fs::rename(&partial, &target).map_err(|e| format!("保存离线包失败: {}", e))
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+        result?;
+        Ok::<u64, String>(std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0))
+    })
+    .await
+    .map_err(|e| format!("导出离线包失败: {}", e))??;
+
+    info!(
+        "已导出 {} 的离线包: {:?}（{} 个会话，{} 个视频）",
+        date,
+        path,
+        sessions.len(),
+        video_count
+    );
+    Ok(BundleReport {
+        path: path.to_string_lossy().to_string(),
+        session_count: sessions.len(),
+        video_count,
+        missing_videos,
+        size_bytes,
+        privacy_excluded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::playback::current_mapping;
+    use chrono::{DateTime, Duration, Utc};
+    use std::io::{Cursor, Read};
+
+    fn read_entry<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_bundle_contains_viewer_data_and_videos() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(7),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: "编码".to_string(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let card = TimelineCardRecord {
+            id: Some(1),
+            session_id: 7,
+            llm_call_id: None,
+            start_time: "05:00".to_string(),
+            end_time: "10:00".to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: "修复 </script> 转义".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+
+        // 8 倍速、每 5 秒取一帧：视频 1 秒 = 真实 40 秒
+        let bundled = bundle_session(
+            &session,
+            &[card],
+            Some("videos/session_7.mp4".to_string()),
+            &current_mapping(8.0, 1),
+        );
+        assert_eq!(bundled.cards[0].start_time, "2025-10-09 09:05:00");
+        assert_eq!(bundled.cards[0].video_start_secs, Some(7.5));
+
+        let data = BundleData {
+            date: "2025-10-09".to_string(),
+            generated_at: "2025-10-10 08:00:00".to_string(),
+            summary_text: None,
+            sessions: vec![bundled],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("video.mp4");
+        std::fs::write(&video, b"fake video").unwrap();
+
+        let cursor = write_zip(
+            Cursor::new(Vec::new()),
+            &data,
+            &[("videos/session_7.mp4".to_string(), video)],
+        )
+        .unwrap();
+        let mut archive = zip::ZipArchive::new(cursor).unwrap();
+        assert_eq!(archive.len(), 3);
+
+        let html = read_entry(&mut archive, "index.html");
+        assert!(!html.contains("__BUNDLE_DATA__"));
+        assert!(html.contains("修复 <\\/script> 转义"));
+        assert!(read_entry(&mut archive, "data.json").contains("\"videoStartSecs\": 7.5"));
+        assert_eq!(
+            read_entry(&mut archive, "videos/session_7.mp4"),
+            "fake video"
+        );
+    }
+}
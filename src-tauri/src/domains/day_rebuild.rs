@@ -0,0 +1,168 @@
+// 一键修复某天 - 按依赖顺序重跑一天的处理流程
+//
+// 依次执行：
+// 1. 缺视频的会话用保留的截图重新生成视频
+// 2. 没有卡片的会话（分析失败或重新分析中断）重新分析
+// 3. 其余有视频分段的会话重新生成时间线（仅 SQLite 模式）；已锁定的日期和
+//    有用户确认、纠正过的卡片的会话保留原有时间线，不被重新生成的内容覆盖
+// 4. 重新生成每日总结
+// 5. 重新分析过的会话和每日总结同步到 Notion
+// Notion 同步每次都会新建页面，时间线重新生成的会话不再同步，避免产生大量重复页面。
+// 每一步通过 day-rebuild-progress 事件推送进度，单个会话失败只记入报告，不中止后续步骤
+
+use crate::domains::review::{REVIEW_CONFIRMED, REVIEW_CORRECTED};
+use crate::storage::TimelineCardRecord;
+use serde::Serialize;
+
+/// 进度事件名
+pub const PROGRESS_EVENT: &str = "day-rebuild-progress";
+
+/// 修复步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildStage {
+    Videos,
+    Analysis,
+    Timeline,
+    DaySummary,
+    Notion,
+}
+
+/// 进度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebuildProgress {
+    pub date: String,
+    pub stage: RebuildStage,
+    /// 当前步骤已完成的数量
+    pub done: usize,
+    pub total: usize,
+    pub message: String,
+}
+
+/// 修复报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayRebuildReport {
+    pub date: String,
+    pub sessions: usize,
+    pub videos_generated: usize,
+    pub sessions_reanalyzed: usize,
+    pub timelines_regenerated: usize,
+    pub timeline_cards: usize,
+    pub summary_regenerated: bool,
+    pub notion_synced: usize,
+    /// 失败和跳过的说明
+    pub messages: Vec<String>,
+}
+
+/// 一个会话需要执行的修复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SessionRepair {
+    pub generate_video: bool,
+    pub reanalyze: bool,
+}
+
+/// 根据会话现状决定修复内容
+///
+/// - `has_video`：视频文件存在
+/// - `has_frames`：截图仍保留在磁盘上
+/// - `card_count`：已有的时间线卡片数（跳过分析的会话有一张占位卡片）
+pub fn plan_session(has_video: bool, has_frames: bool, card_count: usize) -> SessionRepair {
+    let generate_video = !has_video && has_frames;
+    SessionRepair {
+        generate_video,
+        reanalyze: card_count == 0 && (has_video || generate_video),
+    }
+}
+
+/// 会话的卡片是否被用户确认、纠正过或补充了会议纪要，重新生成时间线会丢失这些修改
+pub fn has_user_edits(cards: &[TimelineCardRecord]) -> bool {
+    cards.iter().any(|card| {
+        matches!(
+            card.review_status.as_deref(),
+            Some(REVIEW_CONFIRMED) | Some(REVIEW_CORRECTED)
+        ) || card
+            .meeting_notes
+            .as_deref()
+            .is_some_and(|notes| !notes.trim().is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_session() {
+        // 视频丢失但截图还在：重新生成视频，已有卡片的不重新分析
+        assert_eq!(
+            plan_session(false, true, 3),
+            SessionRepair {
+                generate_video: true,
+                reanalyze: false
+            }
+        );
+        // 视频生成后仍需分析
+        assert_eq!(
+            plan_session(false, true, 0),
+            SessionRepair {
+                generate_video: true,
+                reanalyze: true
+            }
+        );
+        // 分析失败的会话
+        assert_eq!(
+            plan_session(true, false, 0),
+            SessionRepair {
+                generate_video: false,
+                reanalyze: true
+            }
+        );
+        // 视频和截图都没有：无法修复
+        assert_eq!(plan_session(false, false, 0), SessionRepair::default());
+        assert_eq!(plan_session(true, false, 2), SessionRepair::default());
+    }
+
+    fn card(review_status: Option<&str>, meeting_notes: Option<&str>) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T09:00:00+08:00".to_string(),
+            end_time: "2025-10-09T09:05:00+08:00".to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: String::new(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: chrono::Utc::now(),
+            confidence: None,
+            review_status: review_status.map(str::to_string),
+            meeting_notes: meeting_notes.map(str::to_string),
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_has_user_edits() {
+        assert!(!has_user_edits(&[]));
+        // 待复核的卡片还没被用户处理过，可以重新生成
+        assert!(!has_user_edits(&[
+            card(None, None),
+            card(Some("pending"), None)
+        ]));
+        assert!(has_user_edits(&[
+            card(None, None),
+            card(Some("corrected"), None)
+        ]));
+        assert!(has_user_edits(&[card(Some("confirmed"), None)]));
+        assert!(has_user_edits(&[card(None, Some("讨论上线计划"))]));
+        assert!(!has_user_edits(&[card(None, Some("  "))]));
+    }
+}
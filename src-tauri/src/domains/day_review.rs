@@ -0,0 +1,137 @@
+// 当日复核 - 一天结束时集中处理不确定的卡片、未打标签的会话和记录空档
+//
+// get_day_review 汇总需要用户处理的内容；submit_day_review 批量应用卡片纠正和会话标签，
+// 重新生成当日总结后将其锁定，之后后台或强制刷新都不会再覆盖用户确认过的总结
+
+use super::gaps::{get_day_gaps, IdleGap};
+use super::review::{self, ReviewDecision, REVIEW_PENDING};
+use crate::llm::plugin::ActivityTag;
+use crate::storage::{Database, Session, TimelineCardRecord};
+use serde::{Deserialize, Serialize};
+
+/// 当日待复核内容
+#[derive(Debug, Clone, Serialize)]
+pub struct DayReview {
+    pub date: String,
+    /// 当日总结是否已锁定
+    pub finalized: bool,
+    /// 待复核的低置信度卡片
+    pub uncertain_cards: Vec<TimelineCardRecord>,
+    /// 没有任何标签的会话
+    pub untagged_sessions: Vec<Session>,
+    pub gaps: Vec<IdleGap>,
+}
+
+/// 单张卡片的处理
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardDecision {
+    pub card_id: i64,
+    pub decision: ReviewDecision,
+}
+
+/// 为会话设置的标签
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionTagDecision {
+    pub session_id: i64,
+    pub tags: Vec<ActivityTag>,
+}
+
+/// 用户提交的当日复核结果
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DayReviewDecisions {
+    #[serde(default)]
+    pub cards: Vec<CardDecision>,
+    #[serde(default)]
+    pub session_tags: Vec<SessionTagDecision>,
+}
+
+/// 复核结果统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DayReviewOutcome {
+    pub cards_confirmed: usize,
+    pub cards_corrected: usize,
+    pub sessions_tagged: usize,
+}
+
+/// 提交复核后的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DayReviewResult {
+    pub outcome: DayReviewOutcome,
+    /// 重新生成并锁定的当日总结
+    pub summary: super::DaySummary,
+}
+
+fn is_untagged(session: &Session) -> bool {
+    serde_json::from_str::<Vec<serde_json::Value>>(&session.tags)
+        .map(|tags| tags.is_empty())
+        .unwrap_or(true)
+}
+
+/// 汇总某天需要复核的内容
+pub async fn build_day_review(db: &Database, date: &str) -> Result<DayReview, String> {
+    let sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+
+    let mut uncertain_cards = Vec::new();
+    for session in &sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let cards = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        uncertain_cards.extend(
+            cards
+                .into_iter()
+                .filter(|card| card.review_status.as_deref() == Some(REVIEW_PENDING)),
+        );
+    }
+
+    let finalized = db
+        .get_day_summary(date)
+        .await
+        .map_err(|e| format!("获取每日总结失败: {}", e))?
+        .is_some_and(|summary| summary.finalized);
+
+    Ok(DayReview {
+        date: date.to_string(),
+        finalized,
+        uncertain_cards,
+        untagged_sessions: sessions
+            .iter()
+            .filter(|s| is_untagged(s))
+            .cloned()
+            .collect(),
+        gaps: get_day_gaps(db, date).await?,
+    })
+}
+
+/// 批量应用卡片处理和会话标签
+pub async fn apply_day_review(
+    db: &Database,
+    decisions: DayReviewDecisions,
+) -> Result<DayReviewOutcome, String> {
+    let mut outcome = DayReviewOutcome::default();
+
+    for CardDecision { card_id, decision } in decisions.cards {
+        let card = review::apply_review(db, card_id, decision).await?;
+        if card.review_status.as_deref() == Some(review::REVIEW_CORRECTED) {
+            outcome.cards_corrected += 1;
+        } else {
+            outcome.cards_confirmed += 1;
+        }
+    }
+
+    for SessionTagDecision { session_id, tags } in decisions.session_tags {
+        let tags_json = serde_json::to_string(&tags).map_err(|e| e.to_string())?;
+        db.update_session_tags(session_id, &tags_json)
+            .await
+            .map_err(|e| format!("更新会话 {} 标签失败: {}", session_id, e))?;
+        outcome.sessions_tagged += 1;
+    }
+
+    Ok(outcome)
+}
@@ -0,0 +1,343 @@
+// 文字版时间线 - 把某天的卡片按时间顺序渲染成纯文本或 Markdown
+//
+// 供读屏软件、通过 HTTP 接口访问的终端用户使用，也方便直接交给其他 LLM 处理。
+// 不使用表格、颜色、图标等只有视觉意义的标记，时间段写成“09:00 至 10:30”以便朗读；
+// 每张卡片一段，详细程度分三级：
+// - brief：时间段和标题
+// - normal：再加类别、时长和摘要
+// - detailed：再加详细摘要、使用的应用/网站和干扰活动
+
+use super::summary::format_duration;
+use crate::llm::plugin::{AppSites, Distraction};
+use crate::storage::{Database, TimelineCardRecord};
+use chrono::DateTime;
+
+/// 详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimelineVerbosity {
+    Brief,
+    #[default]
+    Normal,
+    Detailed,
+}
+
+impl TimelineVerbosity {
+    /// 从字符串解析（brief / normal / detailed）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "brief" => Ok(Self::Brief),
+            "normal" => Ok(Self::Normal),
+            "detailed" => Ok(Self::Detailed),
+            other => Err(format!(
+                "不支持的详细程度: {}（可选 brief/normal/detailed）",
+                other
+            )),
+        }
+    }
+}
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimelineTextFormat {
+    Text,
+    #[default]
+    Markdown,
+}
+
+impl TimelineTextFormat {
+    /// 从字符串解析（text / markdown）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "text" | "plain" => Ok(Self::Text),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!("不支持的输出格式: {}（可选 text/markdown）", other)),
+        }
+    }
+
+    /// HTTP 响应的 Content-Type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+}
+
+/// 把多行文字合并为一行（换行会打断读屏和列表结构）
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// RFC3339 时间取本地时分，无法解析时原样返回
+fn clock(time: &str) -> String {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.format("%H:%M").to_string())
+        .unwrap_or_else(|_| time.to_string())
+}
+
+fn card_minutes(card: &TimelineCardRecord) -> i64 {
+    match (
+        DateTime::parse_from_rfc3339(&card.start_time),
+        DateTime::parse_from_rfc3339(&card.end_time),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_minutes().max(0),
+        _ => 0,
+    }
+}
+
+fn category_label(card: &TimelineCardRecord) -> String {
+    match card.subcategory.trim() {
+        "" => card.category.clone(),
+        subcategory => format!("{} / {}", card.category, subcategory),
+    }
+}
+
+fn app_sites_label(card: &TimelineCardRecord) -> Option<String> {
+    let apps: AppSites = serde_json::from_str(&card.app_sites).ok()?;
+    let mut names = vec![apps.primary];
+    names.extend(apps.secondary.unwrap_or_default());
+    let names: Vec<String> = names
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    (!names.is_empty()).then(|| names.join("、"))
+}
+
+fn distraction_labels(card: &TimelineCardRecord) -> Vec<String> {
+    let distractions: Vec<Distraction> = card
+        .distractions
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    distractions
+        .into_iter()
+        .filter(|d| !d.title.trim().is_empty())
+        .map(|d| {
+            match (
+                DateTime::parse_from_rfc3339(&d.start_time),
+                DateTime::parse_from_rfc3339(&d.end_time),
+            ) {
+                (Ok(start), Ok(end)) => format!(
+                    "{}（{} 至 {}）",
+                    one_line(&d.title),
+                    start.format("%H:%M"),
+                    end.format("%H:%M")
+                ),
+                _ => one_line(&d.title),
+            }
+        })
+        .collect()
+}
+
+/// 一张卡片的各个字段（标签, 内容），按详细程度取舍
+fn card_fields(
+    card: &TimelineCardRecord,
+    verbosity: TimelineVerbosity,
+) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    if verbosity == TimelineVerbosity::Brief {
+        return fields;
+    }
+    fields.push(("类别", category_label(card)));
+    fields.push(("时长", format_duration(card_minutes(card))));
+    if !card.summary.trim().is_empty() {
+        fields.push(("摘要", one_line(&card.summary)));
+    }
+    if verbosity == TimelineVerbosity::Detailed {
+        if !card.detailed_summary.trim().is_empty() {
+            fields.push(("详情", one_line(&card.detailed_summary)));
+        }
+        if let Some(apps) = app_sites_label(card) {
+            fields.push(("应用/网站", apps));
+        }
+        let distractions = distraction_labels(card);
+        if !distractions.is_empty() {
+            fields.push(("干扰", distractions.join("；")));
+        }
+    }
+    fields
+}
+
+/// 按时间顺序渲染某天的卡片
+pub fn render_day_text(
+    date: &str,
+    cards: &[TimelineCardRecord],
+    verbosity: TimelineVerbosity,
+    format: TimelineTextFormat,
+) -> String {
+    let mut cards: Vec<&TimelineCardRecord> = cards.iter().collect();
+    cards.sort_by_key(|card| {
+        (
+            DateTime::parse_from_rfc3339(&card.start_time).ok(),
+            card.start_time.clone(),
+        )
+    });
+
+    let markdown = format == TimelineTextFormat::Markdown;
+    let mut out = if markdown {
+        format!("# {} 时间线\n\n", date)
+    } else {
+        format!("{} 时间线\n\n", date)
+    };
+    if cards.is_empty() {
+        out.push_str("这一天没有活动记录。\n");
+        return out;
+    }
+    let total: i64 = cards.iter().map(|card| card_minutes(card)).sum();
+    out.push_str(&format!(
+        "共 {} 项活动，记录时长 {}。\n\n",
+        cards.len(),
+        format_duration(total)
+    ));
+
+    for card in cards {
+        let heading = format!(
+            "{} 至 {}，{}",
+            clock(&card.start_time),
+            clock(&card.end_time),
+            one_line(&card.title)
+        );
+        let fields = card_fields(card, verbosity);
+        match (markdown, fields.is_empty()) {
+            (true, true) => out.push_str(&format!("- {}\n", heading)),
+            (true, false) => {
+                out.push_str(&format!("## {}\n\n", heading));
+                for (label, value) in fields {
+                    out.push_str(&format!("- {}：{}\n", label, value));
+                }
+                out.push('\n');
+            }
+            (false, true) => out.push_str(&format!("{}\n", heading)),
+            (false, false) => {
+                out.push_str(&format!("{}\n", heading));
+                for (label, value) in fields {
+                    out.push_str(&format!("{}：{}\n", label, value));
+                }
+                out.push('\n');
+            }
+        }
+    }
+    format!("{}\n", out.trim_end())
+}
+
+/// 读取某天所有会话的时间线卡片
+pub async fn load_day_cards(db: &Database, date: &str) -> Result<Vec<TimelineCardRecord>, String> {
+    let sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+
+    let mut cards = Vec::new();
+    for session in sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        cards.extend(
+            db.get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?,
+        );
+    }
+    Ok(cards)
+}
+
+/// 获取某天的文字版时间线
+pub async fn get_day_timeline_text(
+    db: &Database,
+    date: &str,
+    verbosity: TimelineVerbosity,
+    format: TimelineTextFormat,
+) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("日期格式错误: {}", e))?;
+    let cards = load_day_cards(db, date).await?;
+    Ok(render_day_text(date, &cards, verbosity, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(title: &str, start: &str, end: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: format!("2025-10-09T{}:00+08:00", start),
+            end_time: format!("2025-10-09T{}:00+08:00", end),
+            category: "Work".to_string(),
+            subcategory: "Coding".to_string(),
+            title: title.to_string(),
+            summary: "修复登录\n超时问题".to_string(),
+            detailed_summary: "在 VS Code 中调试".to_string(),
+            distractions: Some(
+                r#"[{"startTime":"2025-10-09T10:05:00+08:00","endTime":"2025-10-09T10:10:00+08:00","title":"刷微博","summary":""}]"#
+                    .to_string(),
+            ),
+            app_sites: r#"{"primary":"vscode","secondary":["github.com"]}"#.to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_render_levels_in_order() {
+        let cards = vec![
+            card("写周报", "14:00", "14:30"),
+            card("修复登录", "09:00", "10:30"),
+        ];
+
+        let brief = render_day_text(
+            "2025-10-09",
+            &cards,
+            TimelineVerbosity::Brief,
+            TimelineTextFormat::Markdown,
+        );
+        assert_eq!(
+            brief,
+            "# 2025-10-09 时间线\n\n共 2 项活动，记录时长 2h。\n\n- 09:00 至 10:30，修复登录\n- 14:00 至 14:30，写周报\n"
+        );
+
+        let normal = render_day_text(
+            "2025-10-09",
+            &cards,
+            TimelineVerbosity::Normal,
+            TimelineTextFormat::Text,
+        );
+        assert!(normal.contains("09:00 至 10:30，修复登录\n类别：Work / Coding\n时长：1h 30m\n摘要：修复登录 超时问题\n"));
+        assert!(!normal.contains('#'));
+        assert!(!normal.contains("应用/网站"));
+
+        let detailed = render_day_text(
+            "2025-10-09",
+            &cards,
+            TimelineVerbosity::Detailed,
+            TimelineTextFormat::Markdown,
+        );
+        assert!(detailed.contains("## 09:00 至 10:30，修复登录\n"));
+        assert!(detailed.contains("- 应用/网站：vscode、github.com\n"));
+        assert!(detailed.contains("- 干扰：刷微博（10:05 至 10:10）\n"));
+
+        assert_eq!(
+            render_day_text(
+                "2025-10-10",
+                &[],
+                TimelineVerbosity::Normal,
+                TimelineTextFormat::Text
+            ),
+            "2025-10-10 时间线\n\n这一天没有活动记录。\n"
+        );
+        assert!(TimelineVerbosity::parse("verbose").is_err());
+        assert_eq!(
+            TimelineTextFormat::parse("MD"),
+            Ok(TimelineTextFormat::Markdown)
+        );
+    }
+}
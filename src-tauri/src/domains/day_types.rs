@@ -0,0 +1,163 @@
+// 日期类型日历 - 区分工作日、周末和假期，每日总结按日期类型选择模板和语气
+//
+// 未标记的日期按星期自动判断（周六、周日为周末），用户可以把某天标记为假期，
+// 或把调休上班的周末标记为工作日。标记保存在 day_types 表中
+
+use crate::llm::DayType;
+use crate::storage::{Database, DayTypeRecord};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// 一次最多查询的天数
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// 某天的日期类型
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayTypeInfo {
+    pub date: String,
+    pub day_type: DayType,
+    /// 是否为用户标记（否则为按星期自动判断）
+    pub marked: bool,
+    pub note: Option<String>,
+}
+
+/// 未标记时按星期判断日期类型
+pub fn default_day_type(date: NaiveDate) -> DayType {
+    match date.weekday() {
+        Weekday::Sat | Weekday::Sun => DayType::Weekend,
+        _ => DayType::Workday,
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))
+}
+
+/// 合并用户标记和自动判断，得到范围内每天的日期类型
+fn fill_range(start: NaiveDate, end: NaiveDate, records: Vec<DayTypeRecord>) -> Vec<DayTypeInfo> {
+    let marked: HashMap<String, DayTypeRecord> = records
+        .into_iter()
+        .map(|record| (record.date.clone(), record))
+        .collect();
+    start
+        .iter_days()
+        .take_while(|day| *day <= end)
+        .map(|day| {
+            let date = day.format("%Y-%m-%d").to_string();
+            match marked
+                .get(&date)
+                .and_then(|record| DayType::parse(&record.day_type).map(|t| (t, record)))
+            {
+                Some((day_type, record)) => DayTypeInfo {
+                    date,
+                    day_type,
+                    marked: true,
+                    note: record.note.clone(),
+                },
+                None => DayTypeInfo {
+                    date,
+                    day_type: default_day_type(day),
+                    marked: false,
+                    note: None,
+                },
+            }
+        })
+        .collect()
+}
+
+/// 获取日期范围内（含首尾）每天的日期类型
+pub async fn get_day_types(
+    db: &Database,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<DayTypeInfo>, String> {
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+    if end < start || (end - start).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("无效的日期范围: {} 至 {}", start_date, end_date));
+    }
+    let records = db
+        .get_day_types_between(start_date, end_date)
+        .await
+        .map_err(|e| format!("获取日期类型失败: {}", e))?;
+    Ok(fill_range(start, end, records))
+}
+
+/// 获取某天的日期类型（读取失败时按星期判断）
+pub async fn resolve_day_type(db: &Database, date: &str) -> DayType {
+    let Ok(day) = parse_date(date) else {
+        return DayType::Workday;
+    };
+    match db.get_day_types_between(date, date).await {
+        Ok(records) => fill_range(day, day, records)
+            .pop()
+            .map(|info| info.day_type)
+            .unwrap_or_else(|| default_day_type(day)),
+        Err(e) => {
+            warn!("获取日期类型失败，按星期判断: {}", e);
+            default_day_type(day)
+        }
+    }
+}
+
+/// 标记日期类型，传入 None 时清除标记
+pub async fn set_day_type(
+    db: &Database,
+    date: &str,
+    day_type: Option<DayType>,
+    note: Option<String>,
+) -> Result<(), String> {
+    parse_date(date)?;
+    let result = match day_type {
+        Some(day_type) => {
+            db.set_day_type(&DayTypeRecord {
+                date: date.to_string(),
+                day_type: day_type.as_str().to_string(),
+                note: note.filter(|n| !n.trim().is_empty()),
+            })
+            .await
+        }
+        None => db.clear_day_type(date).await,
+    };
+    result.map_err(|e| format!("保存日期类型失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_range_prefers_marks_over_weekday() {
+        // 2025-10-03 周五，10-04 周六，10-05 周日，10-06 周一
+        let start = NaiveDate::from_ymd_opt(2025, 10, 3).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 10, 6).unwrap();
+        let records = vec![
+            DayTypeRecord {
+                date: "2025-10-03".to_string(),
+                day_type: "vacation".to_string(),
+                note: Some("国庆假期".to_string()),
+            },
+            DayTypeRecord {
+                date: "2025-10-05".to_string(),
+                day_type: "workday".to_string(),
+                note: None,
+            },
+        ];
+
+        let days = fill_range(start, end, records);
+        let types: Vec<(DayType, bool)> = days.iter().map(|d| (d.day_type, d.marked)).collect();
+        assert_eq!(
+            types,
+            vec![
+                (DayType::Vacation, true),
+                (DayType::Weekend, false),
+                (DayType::Workday, true),
+                (DayType::Workday, false),
+            ]
+        );
+        assert_eq!(days[0].note.as_deref(), Some("国庆假期"));
+    }
+}
@@ -0,0 +1,183 @@
+// 深度链接 - screenanalyzer://session/<会话ID>?t=<秒> 打开应用并跳转到会话视频的指定位置
+//
+// 供 Notion、Obsidian 等笔记中的链接直接回到卡片对应的时刻。t 为视频内的秒数，
+// 也可以写成 MM:SS 或 HH:MM:SS；省略时只打开会话。
+// macOS 由系统把链接交给运行中的实例；Windows/Linux 点击链接会启动新进程，
+// 新进程发现已有主实例时把链接写入应用数据目录下的交接文件后退出，由主实例轮询读取
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// URL scheme（与 tauri.conf.json 中 plugins.deep-link 的配置一致）
+pub const SCHEME: &str = "screenanalyzer";
+
+/// 推送给前端的 Tauri 事件名
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// 新进程交给主实例的链接文件
+const HANDOFF_FILE: &str = "deep_link.pending";
+
+/// 主实例检查交接文件的间隔
+const HANDOFF_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 启动时收到、前端尚未加载时暂存的链接
+static PENDING: Mutex<Option<DeepLink>> = Mutex::new(None);
+
+/// 解析后的链接
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLink {
+    pub session_id: i64,
+    /// 视频内的秒数
+    pub seconds: Option<f64>,
+}
+
+/// 解析时间参数：秒数，或 MM:SS / HH:MM:SS
+fn parse_seconds(value: &str) -> Option<f64> {
+    let value = value.trim().trim_end_matches('s');
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds.is_finite() && seconds >= 0.0).then_some(seconds);
+    }
+    let parts: Vec<f64> = value
+        .split(':')
+        .map(|part| part.parse::<f64>().ok().filter(|v| *v >= 0.0))
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [minutes, seconds] => Some(minutes * 60.0 + seconds),
+        [hours, minutes, seconds] => Some(hours * 3600.0 + minutes * 60.0 + seconds),
+        _ => None,
+    }
+}
+
+/// 解析链接，不是本应用的会话链接时返回 None
+pub fn parse(url: &str) -> Option<DeepLink> {
+    let rest = url.trim().strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let session_id = path
+        .trim_end_matches('/')
+        .strip_prefix("session/")?
+        .parse::<i64>()
+        .ok()
+        .filter(|id| *id > 0)?;
+    let seconds = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "t")
+        .and_then(|(_, value)| parse_seconds(value));
+    Some(DeepLink {
+        session_id,
+        seconds,
+    })
+}
+
+/// 生成会话链接
+pub fn session_link(session_id: i64, seconds: Option<f64>) -> String {
+    match seconds {
+        Some(seconds) => format!(
+            "{}://session/{}?t={}",
+            SCHEME,
+            session_id,
+            seconds.max(0.0).floor() as u64
+        ),
+        None => format!("{}://session/{}", SCHEME, session_id),
+    }
+}
+
+/// 从命令行参数中找出本应用的链接
+pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let prefix = format!("{}://", SCHEME);
+    args.into_iter().find(|arg| arg.starts_with(&prefix))
+}
+
+/// 暂存前端加载前收到的链接
+pub fn set_pending(link: DeepLink) {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(link);
+    }
+}
+
+/// 取出暂存的链接（只返回一次）
+pub fn take_pending() -> Option<DeepLink> {
+    PENDING.lock().ok().and_then(|mut pending| pending.take())
+}
+
+fn handoff_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(HANDOFF_FILE)
+}
+
+/// 把链接交给主实例（新进程调用后退出）
+pub fn hand_off(app_dir: &Path, url: &str) -> std::io::Result<()> {
+    std::fs::write(handoff_path(app_dir), url)
+}
+
+/// 主实例轮询交接文件，读到链接后调用 `open`
+pub fn start_handoff_watcher<F>(app_dir: PathBuf, open: F)
+where
+    F: Fn(String) + Send + Sync + 'static,
+{
+    let path = handoff_path(&app_dir);
+    // 清理上次运行遗留的交接文件，避免启动时打开过期的链接
+    let _ = std::fs::remove_file(&path);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(HANDOFF_POLL_INTERVAL).await;
+            let Ok(url) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                warn!("删除深度链接交接文件失败: {}", e);
+            }
+            info!("收到其他进程转交的深度链接: {}", url.trim());
+            open(url.trim().to_string());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_build_session_links() {
+        assert_eq!(
+            parse("screenanalyzer://session/123?t=432"),
+            Some(DeepLink {
+                session_id: 123,
+                seconds: Some(432.0),
+            })
+        );
+        assert_eq!(
+            parse("screenanalyzer://session/7/?foo=1&t=01:02:03"),
+            Some(DeepLink {
+                session_id: 7,
+                seconds: Some(3723.0),
+            })
+        );
+        assert_eq!(
+            parse("screenanalyzer://session/7?t=bad").unwrap().seconds,
+            None
+        );
+        assert_eq!(parse("screenanalyzer://session/abc"), None);
+        assert_eq!(parse("screenanalyzer://day/2025-10-09"), None);
+        assert_eq!(parse("https://session/1"), None);
+
+        assert_eq!(
+            session_link(123, Some(432.7)),
+            "screenanalyzer://session/123?t=432"
+        );
+        assert_eq!(
+            parse(&session_link(5, None)),
+            Some(DeepLink {
+                session_id: 5,
+                seconds: None,
+            })
+        );
+        assert_eq!(
+            from_args(["app".to_string(), "screenanalyzer://session/1".to_string()]),
+            Some("screenanalyzer://session/1".to_string())
+        );
+    }
+}
@@ -0,0 +1,648 @@
+// 演示数据领域 - 生成看起来真实的会话、时间线卡片、每日总结和占位视频
+//
+// 供前端开发使用：无需录屏、也无需配置 LLM Key 就能看到完整的界面数据。
+// 生成的会话设备名统一为 DEMO_DEVICE_NAME、每日总结以 DEMO_SUMMARY_PREFIX 开头，便于识别和清理；
+// 使用固定种子的伪随机数，同一天多次生成的内容一致，重复生成时跳过已有的时段，不会重复写入。
+// 与真实会话重叠的时段和已有真实总结的日期不生成，避免覆盖真实数据
+
+use crate::storage::{Database, DaySummaryRecord, Session, TimelineCardRecord};
+use crate::video::{VideoConfig, VideoMetadata, VideoProcessor, VideoUtils};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// 演示会话的设备名
+pub const DEMO_DEVICE_NAME: &str = "演示数据";
+
+/// 演示每日总结的开头，用于区分真实总结
+pub const DEMO_SUMMARY_PREFIX: &str = "（演示数据）";
+
+/// 每个会话的时长（与默认的会话切分一致）
+const SESSION_MINUTES: i64 = 15;
+
+/// 占位视频的帧数（每帧代表 SESSION_MINUTES * 60 / PLACEHOLDER_FRAMES 秒）
+const PLACEHOLDER_FRAMES: usize = 12;
+
+/// 最多生成的天数
+const MAX_DAYS: u32 = 90;
+
+/// 活动模板：类别、子类别、标题、摘要、主要应用、次要应用
+struct ActivityTemplate {
+    category: &'static str,
+    subcategory: &'static str,
+    title: &'static str,
+    summary: &'static str,
+    primary: &'static str,
+    secondary: &'static [&'static str],
+    /// 被选中的权重
+    weight: u32,
+    /// 占位视频中使用的颜色
+    color: [u8; 3],
+}
+
+const ACTIVITIES: &[ActivityTemplate] = &[
+    ActivityTemplate {
+        category: "work",
+        subcategory: "coding",
+        title: "实现登录模块",
+        summary: "在编辑器中编写并调试登录逻辑，运行单元测试",
+        primary: "VS Code",
+        secondary: &["Terminal", "GitHub"],
+        weight: 30,
+        color: [46, 125, 50],
+    },
+    ActivityTemplate {
+        category: "work",
+        subcategory: "review",
+        title: "代码评审",
+        summary: "审阅同事提交的合并请求并留下修改建议",
+        primary: "GitHub",
+        secondary: &["Slack"],
+        weight: 10,
+        color: [56, 142, 60],
+    },
+    ActivityTemplate {
+        category: "work",
+        subcategory: "docs",
+        title: "编写设计文档",
+        summary: "整理接口设计并更新项目文档",
+        primary: "Notion",
+        secondary: &["Figma"],
+        weight: 10,
+        color: [67, 160, 71],
+    },
+    ActivityTemplate {
+        category: "communication",
+        subcategory: "meeting",
+        title: "团队站会",
+        summary: "参加视频会议同步项目进度和风险",
+        primary: "Zoom",
+        secondary: &["Slack"],
+        weight: 8,
+        color: [30, 136, 229],
+    },
+    ActivityTemplate {
+        category: "communication",
+        subcategory: "chat",
+        title: "处理消息",
+        summary: "回复工作群消息和邮件",
+        primary: "Slack",
+        secondary: &["Outlook"],
+        weight: 10,
+        color: [66, 165, 245],
+    },
+    ActivityTemplate {
+        category: "learning",
+        subcategory: "reading",
+        title: "阅读技术文章",
+        summary: "阅读 Rust 异步编程相关文章并做笔记",
+        primary: "Chrome",
+        secondary: &["rust-lang.org", "Obsidian"],
+        weight: 8,
+        color: [142, 36, 170],
+    },
+    ActivityTemplate {
+        category: "learning",
+        subcategory: "video",
+        title: "观看教程",
+        summary: "观看前端框架教程视频",
+        primary: "YouTube",
+        secondary: &[],
+        weight: 5,
+        color: [171, 71, 188],
+    },
+    ActivityTemplate {
+        category: "personal",
+        subcategory: "browsing",
+        title: "浏览新闻",
+        summary: "浏览新闻网站和社交媒体",
+        primary: "Chrome",
+        secondary: &["weibo.com", "zhihu.com"],
+        weight: 6,
+        color: [251, 140, 0],
+    },
+    ActivityTemplate {
+        category: "personal",
+        subcategory: "shopping",
+        title: "网上购物",
+        summary: "浏览购物网站比较商品价格",
+        primary: "Taobao",
+        secondary: &[],
+        weight: 3,
+        color: [255, 167, 38],
+    },
+    ActivityTemplate {
+        category: "idle",
+        subcategory: "away",
+        title: "离开电脑",
+        summary: "屏幕长时间无变化，用户可能离开了电脑",
+        primary: "锁屏",
+        secondary: &[],
+        weight: 3,
+        color: [97, 97, 97],
+    },
+];
+
+/// 演示数据生成结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoDataReport {
+    /// 生成的天数
+    pub days: u32,
+    /// 生成的会话数
+    pub sessions: usize,
+    /// 生成的时间线卡片数
+    pub cards: usize,
+    /// 生成的每日总结数
+    pub summaries: usize,
+    /// 生成的占位视频数
+    pub videos: usize,
+    /// 已经生成过或与真实会话重叠而跳过的时段数
+    pub skipped: usize,
+}
+
+/// 演示数据清理结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoCleanupReport {
+    /// 删除的会话数
+    pub sessions: usize,
+    /// 删除的占位视频数
+    pub videos: usize,
+    /// 删除的每日总结数
+    pub summaries: usize,
+}
+
+/// 简单的线性同余伪随机数（避免为演示数据引入随机数依赖）
+struct DemoRng(u64);
+
+impl DemoRng {
+    fn new(seed: u64) -> Self {
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as u32
+    }
+
+    /// [0, bound) 范围内的随机数
+    fn below(&mut self, bound: u32) -> u32 {
+        if bound == 0 {
+            0
+        } else {
+            self.next() % bound
+        }
+    }
+
+    /// 按权重选择活动模板
+    fn pick_activity(&mut self) -> &'static ActivityTemplate {
+        let total: u32 = ACTIVITIES.iter().map(|a| a.weight).sum();
+        let mut roll = self.below(total);
+        for activity in ACTIVITIES {
+            if roll < activity.weight {
+                return activity;
+            }
+            roll -= activity.weight;
+        }
+        &ACTIVITIES[0]
+    }
+}
+
+/// 规划好的一张卡片
+struct PlannedCard {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    activity: &'static ActivityTemplate,
+}
+
+/// 规划一天的会话：9:00-18:00 之间每 15 分钟一个会话，午休和随机空档不生成
+fn plan_day(date: NaiveDate, rng: &mut DemoRng) -> Vec<(NaiveDateTime, Vec<PlannedCard>)> {
+    let mut sessions = Vec::new();
+    let day_start = date.and_hms_opt(9, 0, 0).unwrap();
+    let slots = (9 * 60) / SESSION_MINUTES;
+
+    for slot in 0..slots {
+        let start = day_start + Duration::minutes(slot * SESSION_MINUTES);
+        let hour = (slot * SESSION_MINUTES) / 60 + 9;
+        // 午休
+        if hour == 12 {
+            continue;
+        }
+        // 约 15% 的时段没有记录
+        if rng.below(100) < 15 {
+            continue;
+        }
+
+        // 每个会话 1-3 张卡片，按分钟切分
+        let card_count = 1 + rng.below(3) as i64;
+        let mut cards = Vec::new();
+        let mut cursor = 0;
+        for index in 0..card_count {
+            let remaining = SESSION_MINUTES - cursor;
+            // 最后一张卡片（或剩余时间不足时）填满会话剩余时间
+            let is_last = index == card_count - 1 || remaining <= 3;
+            let length = if is_last {
+                remaining
+            } else {
+                3 + rng.below((remaining - 3) as u32) as i64
+            };
+            cards.push(PlannedCard {
+                start: start + Duration::minutes(cursor),
+                end: start + Duration::minutes(cursor + length),
+                activity: rng.pick_activity(),
+            });
+            cursor += length;
+            if is_last {
+                break;
+            }
+        }
+        sessions.push((start, cards));
+    }
+
+    sessions
+}
+
+/// 本地时间值 → 带时区的 RFC3339（与分析结果的存储格式一致）
+fn to_rfc3339(value: NaiveDateTime) -> String {
+    Local
+        .from_local_datetime(&value)
+        .earliest()
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| Utc.from_utc_datetime(&value).to_rfc3339())
+}
+
+/// 生成最近 days 天的演示数据（今天只生成已经结束的时段）
+pub async fn generate_demo_data(
+    db: &Database,
+    video_processor: Option<&VideoProcessor>,
+    days: u32,
+) -> Result<DemoDataReport, String> {
+    let days = days.clamp(1, MAX_DAYS);
+    let now = crate::storage::local_now().naive_utc();
+    let today = now.date();
+
+    let video_processor = match video_processor {
+        Some(processor) if VideoUtils::check_ffmpeg().await.unwrap_or(false) => Some(processor),
+        Some(_) => {
+            warn!("FFmpeg 不可用，演示数据不生成占位视频");
+            None
+        }
+        None => None,
+    };
+
+    let mut report = DemoDataReport {
+        days,
+        ..Default::default()
+    };
+
+    for day_offset in (0..days).rev() {
+        let date = today - Duration::days(day_offset as i64);
+        let date_key = date.format("%Y-%m-%d").to_string();
+        let mut rng = DemoRng::new(date.num_days_from_ce() as u64);
+        let mut day_categories: Vec<&'static str> = Vec::new();
+        let existing = db
+            .get_sessions_by_date(&date_key)
+            .await
+            .map_err(|e| format!("读取已有会话失败: {}", e))?;
+        let has_real_sessions = existing.iter().any(|session| !is_demo_session(session));
+
+        for (start, planned) in plan_day(date, &mut rng) {
+            let end = start + Duration::minutes(SESSION_MINUTES);
+            if end > now || planned.is_empty() {
+                continue;
+            }
+            // 已经生成过（同一天的内容固定）或与真实会话重叠的时段不再写入
+            let overlapping = existing.iter().find(|session| {
+                session.start_time.naive_utc() < end && start < session.end_time.naive_utc()
+            });
+            if let Some(session) = overlapping {
+                if is_demo_session(session) {
+                    day_categories.extend(planned.iter().map(|c| c.activity.category));
+                }
+                report.skipped += 1;
+                continue;
+            }
+
+            let session_id = insert_demo_session(db, start, end, &planned).await?;
+            report.sessions += 1;
+
+            let records: Vec<TimelineCardRecord> = planned
+                .iter()
+                .map(|card| demo_card_record(session_id, card))
+                .collect();
+            db.insert_timeline_cards(&records)
+                .await
+                .map_err(|e| format!("保存演示卡片失败: {}", e))?;
+            report.cards += records.len();
+            day_categories.extend(planned.iter().map(|c| c.activity.category));
+
+            if let Some(processor) = video_processor {
+                match create_placeholder_video(processor, session_id, start, &planned).await {
+                    Ok(video_path) => {
+                        db.update_session_video_path(session_id, &video_path)
+                            .await
+                            .map_err(|e| format!("更新演示视频路径失败: {}", e))?;
+                        report.videos += 1;
+                    }
+                    Err(e) => warn!("生成演示视频失败 (会话 {}): {}", session_id, e),
+                }
+            }
+        }
+
+        // 有真实会话或真实总结的日期不写演示总结
+        if day_categories.is_empty() || has_real_sessions {
+            continue;
+        }
+        let existing_summary = db
+            .get_day_summary(&date_key)
+            .await
+            .map_err(|e| format!("读取每日总结失败: {}", e))?;
+        if existing_summary.is_some_and(|summary| !is_demo_summary(&summary)) {
+            continue;
+        }
+        save_demo_day_summary(db, date, &day_categories).await?;
+        report.summaries += 1;
+    }
+
+    info!(
+        "演示数据生成完成: {} 天, {} 个会话, {} 张卡片, {} 个视频",
+        report.days, report.sessions, report.cards, report.videos
+    );
+    Ok(report)
+}
+
+/// 删除所有演示会话（连同卡片和占位视频）以及演示每日总结，真实数据不受影响
+pub async fn remove_demo_data(db: &Database) -> Result<DemoCleanupReport, String> {
+    let mut report = DemoCleanupReport::default();
+    let mut dates = BTreeSet::new();
+    let sessions = db
+        .get_all_sessions()
+        .await
+        .map_err(|e| format!("读取会话失败: {}", e))?;
+
+    for session in sessions.iter().filter(|session| is_demo_session(session)) {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        db.delete_session(session_id)
+            .await
+            .map_err(|e| format!("删除演示会话失败: {}", e))?;
+        report.sessions += 1;
+        dates.insert(session.start_time.naive_utc().date());
+
+        if let Some(video_path) = session.video_path.as_deref().map(Path::new) {
+            if tokio::fs::remove_file(video_path).await.is_ok() {
+                report.videos += 1;
+            }
+            let _ = tokio::fs::remove_file(VideoMetadata::sidecar_path(video_path)).await;
+        }
+    }
+
+    for date in dates {
+        let date_key = date.format("%Y-%m-%d").to_string();
+        let summary = db
+            .get_day_summary(&date_key)
+            .await
+            .map_err(|e| format!("读取每日总结失败: {}", e))?;
+        if summary.is_some_and(|summary| is_demo_summary(&summary)) {
+            db.delete_day_summary(&date_key)
+                .await
+                .map_err(|e| format!("删除演示每日总结失败: {}", e))?;
+            report.summaries += 1;
+        }
+    }
+
+    info!(
+        "演示数据已清理: {} 个会话, {} 个视频, {} 个每日总结",
+        report.sessions, report.videos, report.summaries
+    );
+    Ok(report)
+}
+
+fn is_demo_session(session: &Session) -> bool {
+    session.device_name.as_deref() == Some(DEMO_DEVICE_NAME)
+}
+
+fn is_demo_summary(summary: &DaySummaryRecord) -> bool {
+    summary.summary_text.starts_with(DEMO_SUMMARY_PREFIX)
+}
+
+/// 写入一个演示会话（标题和标签取占比最大的活动）
+async fn insert_demo_session(
+    db: &Database,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    planned: &[PlannedCard],
+) -> Result<i64, String> {
+    let main = planned
+        .iter()
+        .max_by_key(|card| card.end - card.start)
+        .map(|card| card.activity)
+        .unwrap_or(&ACTIVITIES[0]);
+    let tags = json!([{
+        "category": main.category,
+        "confidence": 0.9,
+        "keywords": [main.subcategory, main.primary],
+    }]);
+
+    let session = Session {
+        id: None,
+        start_time: Utc.from_utc_datetime(&start),
+        end_time: Utc.from_utc_datetime(&end),
+        title: main.title.to_string(),
+        summary: planned
+            .iter()
+            .map(|card| card.activity.summary)
+            .collect::<Vec<_>>()
+            .join("；"),
+        video_path: None,
+        tags: tags.to_string(),
+        created_at: Some(Utc.from_utc_datetime(&end)),
+        device_name: Some(DEMO_DEVICE_NAME.to_string()),
+        device_type: Some("demo".to_string()),
+        pinned: false,
+        archived: false,
+        archive_key: None,
+    };
+
+    db.insert_session(&session)
+        .await
+        .map_err(|e| format!("保存演示会话失败: {}", e))
+}
+
+fn demo_card_record(session_id: i64, card: &PlannedCard) -> TimelineCardRecord {
+    let activity = card.activity;
+    TimelineCardRecord {
+        id: None,
+        session_id,
+        llm_call_id: None,
+        start_time: to_rfc3339(card.start),
+        end_time: to_rfc3339(card.end),
+        category: activity.category.to_string(),
+        subcategory: activity.subcategory.to_string(),
+        title: activity.title.to_string(),
+        summary: activity.summary.to_string(),
+        detailed_summary: format!(
+            "{}。主要使用 {}{}。",
+            activity.summary,
+            activity.primary,
+            if activity.secondary.is_empty() {
+                String::new()
+            } else {
+                format!("，同时打开了 {}", activity.secondary.join("、"))
+            }
+        ),
+        distractions: None,
+        app_sites: json!({
+            "primary": activity.primary,
+            "secondary": activity.secondary,
+        })
+        .to_string(),
+        video_preview_path: None,
+        created_at: Utc.from_utc_datetime(&card.end),
+        confidence: None,
+        review_status: None,
+        meeting_notes: None,
+        keyword_tags: None,
+        preview_clip_path: None,
+        privacy_level: None,
+    }
+}
+
+/// 保存演示的每日总结（按类别统计卡片数）
+async fn save_demo_day_summary(
+    db: &Database,
+    date: NaiveDate,
+    categories: &[&str],
+) -> Result<(), String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for category in categories {
+        match counts.iter_mut().find(|(c, _)| c == category) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((category, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let breakdown = counts
+        .iter()
+        .map(|(category, count)| format!("{} {} 段", category, count))
+        .collect::<Vec<_>>()
+        .join("，");
+    let summary_text = format!(
+        "{}今天主要在进行 {} 相关的活动。活动分布：{}。",
+        DEMO_SUMMARY_PREFIX,
+        counts.first().map(|(c, _)| *c).unwrap_or("work"),
+        breakdown
+    );
+
+    let now = crate::storage::local_now();
+    let record = DaySummaryRecord {
+        id: None,
+        date,
+        summary_text,
+        device_stats: "[]".to_string(),
+        parallel_work: "[]".to_string(),
+        usage_patterns: "[]".to_string(),
+        active_device_count: 1,
+        llm_call_id: None,
+        created_at: now,
+        updated_at: now,
+        finalized: false,
+    };
+
+    db.save_day_summary(&date.format("%Y-%m-%d").to_string(), &record)
+        .await
+        .map_err(|e| format!("保存演示每日总结失败: {}", e))
+}
+
+/// 生成低分辨率的纯色占位视频，颜色随卡片类别变化，并写入视频元数据
+async fn create_placeholder_video(
+    processor: &VideoProcessor,
+    session_id: i64,
+    start: NaiveDateTime,
+    planned: &[PlannedCard],
+) -> Result<String, String> {
+    let frame_interval = Duration::seconds(SESSION_MINUTES * 60 / PLACEHOLDER_FRAMES as i64);
+    let frames_dir = processor.temp_dir.join(format!("demo_{}", session_id));
+    tokio::fs::create_dir_all(&frames_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut frame_paths = Vec::with_capacity(PLACEHOLDER_FRAMES);
+    for index in 0..PLACEHOLDER_FRAMES {
+        let at = start + frame_interval * index as i32;
+        let color = planned
+            .iter()
+            .find(|card| card.start <= at && at < card.end)
+            .map(|card| card.activity.color)
+            .unwrap_or([0, 0, 0]);
+        let path = frames_dir.join(format!("{:03}.png", index));
+        write_placeholder_frame(&path, color)?;
+        frame_paths.push(path.to_string_lossy().to_string());
+    }
+
+    let config = VideoConfig {
+        resolution: (320, 180),
+        ..VideoConfig::default()
+    };
+    let output_path = processor.output_dir.join(format!(
+        "demo-{}.{}",
+        start.format("%Y%m%d%H%M"),
+        config.format.extension()
+    ));
+    let result = processor
+        .create_summary_video(frame_paths, &output_path, &config)
+        .await
+        .map_err(|e| e.to_string());
+    let _ = tokio::fs::remove_dir_all(&frames_dir).await;
+    let result = result?;
+
+    VideoMetadata::new(
+        &config,
+        frame_interval.num_seconds() as f64,
+        PLACEHOLDER_FRAMES,
+    )
+    .save_or_warn(&output_path)
+    .await;
+    Ok(result.file_path)
+}
+
+fn write_placeholder_frame(path: &Path, color: [u8; 3]) -> Result<(), String> {
+    image::RgbImage::from_pixel(320, 180, image::Rgb(color))
+        .save(path)
+        .map_err(|e| format!("生成占位帧失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_day_cards_cover_sessions() {
+        let date = NaiveDate::from_ymd_opt(2025, 10, 9).unwrap();
+        let mut rng = DemoRng::new(42);
+        let sessions = plan_day(date, &mut rng);
+        assert!(!sessions.is_empty());
+
+        for (start, cards) in &sessions {
+            assert_ne!(start.format("%H").to_string(), "12");
+            assert_eq!(cards.first().unwrap().start, *start);
+            assert_eq!(
+                cards.last().unwrap().end,
+                *start + Duration::minutes(SESSION_MINUTES)
+            );
+            for pair in cards.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+                assert!(pair[0].start < pair[0].end);
+            }
+        }
+    }
+}
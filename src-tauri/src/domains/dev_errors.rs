@@ -0,0 +1,250 @@
+// 报错检测 - 从屏幕文字中找出堆栈、编译错误和错误码，记为会话的报错时刻
+//
+// 屏幕文字识别（text_timeline）对每一帧的识别结果做正则匹配：
+// - 出现堆栈标记（Traceback、panicked at 等）或多行调用帧时记为堆栈，报错文本取异常行和前几行调用帧
+// - rustc/tsc/gcc/msbuild 格式的编译错误行
+// - 没有堆栈的异常行（如 TypeError: ...，需带报错信息）和 Windows 错误码（如 0x80070005）
+// 同一会话内相同的报错只记录首次出现的时间，get_errors_seen 按天还原调试过程
+
+use crate::storage::{Database, ErrorMomentRecord};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// 判定为堆栈的最少调用帧行数（没有堆栈标记时）
+const MIN_TRACE_FRAMES: usize = 2;
+/// 报错文本中保留的调用帧行数
+const MAX_TRACE_FRAMES: usize = 3;
+/// 报错文本的最大长度（字符）
+const MAX_ERROR_CHARS: usize = 500;
+
+/// 报错类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    StackTrace,
+    CompilerError,
+    Exception,
+    ErrorCode,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::StackTrace => "stack_trace",
+            Self::CompilerError => "compiler_error",
+            Self::Exception => "exception",
+            Self::ErrorCode => "error_code",
+        }
+    }
+}
+
+/// 识别出的一条报错
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedError {
+    pub kind: ErrorKind,
+    pub text: String,
+}
+
+struct Patterns {
+    trace_header: Regex,
+    trace_frame: Regex,
+    exception: Regex,
+    compiler: Regex,
+    error_code: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        trace_header: Regex::new(
+            r"(?i)(Traceback \(most recent call last\)|panicked at|^goroutine \d+ \[|Exception in thread|^Caused by:|stack backtrace:)",
+        )
+        .unwrap(),
+        // Java/JS 的 at 行、Python 的 File 行、Rust 的 backtrace 行
+        trace_frame: Regex::new(
+            r#"^(at\s+[\w$.<>\[\]/]+\s*[(:]|File "[^"]+", line \d+|\d+:\s+0x[0-9a-fA-F]+ - )"#,
+        )
+        .unwrap(),
+        exception: Regex::new(
+            r"^(Uncaught\s+)?([a-z_]\w*\.)*([A-Z]\w*)?(Error|Exception):\s*\S.*$",
+        )
+        .unwrap(),
+        compiler: Regex::new(
+            r"(?i)(error\[E\d{4}\]|error TS\d{4,5}:|\.\w{1,5}:\d+(:\d+)?:\s*(fatal )?error\b|\.\w{1,5}\(\d+,\d+\): error [A-Z]+\d+)",
+        )
+        .unwrap(),
+        error_code: Regex::new(r"\b0[xX][8cC][0-9a-fA-F]{7}\b").unwrap(),
+    })
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_ERROR_CHARS).collect()
+}
+
+/// 检查一帧的识别结果
+pub fn detect(text: &str) -> Vec<DetectedError> {
+    let patterns = patterns();
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let mut found = Vec::new();
+
+    let frames: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|line| patterns.trace_frame.is_match(line))
+        .collect();
+    let header = lines
+        .iter()
+        .copied()
+        .find(|line| patterns.trace_header.is_match(line));
+    let exceptions: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|line| patterns.exception.is_match(line))
+        .collect();
+
+    if header.is_some() || frames.len() >= MIN_TRACE_FRAMES {
+        // Python 的异常行在堆栈末尾，其他语言在开头，优先用异常行作为标题
+        let headline = exceptions
+            .first()
+            .copied()
+            .or(header)
+            .or(frames.first().copied());
+        let text: Vec<&str> = headline
+            .into_iter()
+            .chain(
+                frames
+                    .iter()
+                    .copied()
+                    .filter(|frame| Some(*frame) != headline)
+                    .take(MAX_TRACE_FRAMES),
+            )
+            .collect();
+        found.push(DetectedError {
+            kind: ErrorKind::StackTrace,
+            text: truncate(&text.join("\n")),
+        });
+    } else {
+        found.extend(exceptions.iter().map(|line| DetectedError {
+            kind: ErrorKind::Exception,
+            text: truncate(line),
+        }));
+    }
+
+    found.extend(
+        lines
+            .iter()
+            .filter(|line| patterns.compiler.is_match(line))
+            .map(|line| DetectedError {
+                kind: ErrorKind::CompilerError,
+                text: truncate(line),
+            }),
+    );
+
+    let mut codes = HashSet::new();
+    for code in patterns.error_code.find_iter(text) {
+        let code = code.as_str().to_uppercase().replacen("0X", "0x", 1);
+        if codes.insert(code.clone()) {
+            found.push(DetectedError {
+                kind: ErrorKind::ErrorCode,
+                text: code,
+            });
+        }
+    }
+    found
+}
+
+/// 检查会话各帧的识别结果，相同的报错只保留首次出现
+pub fn detect_moments(
+    session_id: i64,
+    samples: &[(NaiveDateTime, String)],
+    now: DateTime<Utc>,
+) -> Vec<ErrorMomentRecord> {
+    let mut seen = HashSet::new();
+    let mut records = Vec::new();
+    for (time, text) in samples {
+        for error in detect(text) {
+            if !seen.insert((error.kind, error.text.to_lowercase())) {
+                continue;
+            }
+            records.push(ErrorMomentRecord {
+                id: None,
+                session_id,
+                kind: error.kind.as_str().to_string(),
+                error_text: error.text,
+                occurred_at: time.and_utc(),
+                created_at: now,
+            });
+        }
+    }
+    records
+}
+
+/// 获取某天（YYYY-MM-DD）屏幕上出现过的报错
+pub async fn get_errors_seen(db: &Database, date: &str) -> Result<Vec<ErrorMomentRecord>, String> {
+    let day =
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))?;
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    db.get_error_moments_between(start, start + Duration::days(1))
+        .await
+        .map_err(|e| format!("获取报错记录失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_traces_compiler_errors_and_codes() {
+        let python = "Traceback (most recent call last):\n  File \"app.py\", line 12, in <module>\n    main()\n  File \"app.py\", line 8, in main\nKeyError: 'user_id'";
+        let errors = detect(python);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ErrorKind::StackTrace);
+        assert_eq!(
+            errors[0].text,
+            "KeyError: 'user_id'\nFile \"app.py\", line 12, in <module>\nFile \"app.py\", line 8, in main"
+        );
+
+        let build =
+            "Compiling app v0.1.0\nerror[E0308]: mismatched types\n安装失败，错误代码 0x80070005";
+        let errors = detect(build);
+        assert_eq!(
+            errors,
+            vec![
+                DetectedError {
+                    kind: ErrorKind::CompilerError,
+                    text: "error[E0308]: mismatched types".to_string(),
+                },
+                DetectedError {
+                    kind: ErrorKind::ErrorCode,
+                    text: "0x80070005".to_string(),
+                },
+            ]
+        );
+        assert!(detect("An error occurred while loading\nError handling guide").is_empty());
+
+        let start = NaiveDate::from_ymd_opt(2025, 10, 9)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        let samples = vec![
+            (start, build.to_string()),
+            (start + Duration::minutes(1), build.to_string()),
+            (
+                start + Duration::minutes(2),
+                "TypeError: x is undefined".to_string(),
+            ),
+        ];
+        let moments = detect_moments(1, &samples, start.and_utc());
+        assert_eq!(moments.len(), 3);
+        assert_eq!(moments[2].kind, "exception");
+        assert_eq!(
+            moments[2].occurred_at,
+            (start + Duration::minutes(2)).and_utc()
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// 脱敏诊断包 - 把会话的截图和时间信息打包成 zip，便于附在 GitHub issue 中复现问题
+//
+// 截图经过马赛克处理：保留窗口布局和大致配色，文字和图像内容无法辨认。
+// 包中不含标题、摘要等文本内容，只保留时间、类别、尺寸等元数据：
+// - manifest.json：应用版本、系统、会话时间、截图时间戳与尺寸、卡片时间与类别、视频参数
+// - frames/：脱敏后的截图（截图已清理的会话从视频中抽帧）
+// - README.txt：说明包中包含哪些内容
+
+use super::playback::session_mapping;
+use super::storage::StorageDomain;
+use crate::video::{VideoInfo, VideoUtils};
+use chrono::{DateTime, Duration, Utc};
+use image::imageops::FilterType;
+use image::RgbImage;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 诊断包中最多包含的截图数量（均匀采样）
+const MAX_FRAMES: usize = 24;
+
+/// 马赛克块的最小边长（像素），大屏按宽度的 1/48 放大
+const MIN_BLOCK_SIZE: u32 = 16;
+
+/// 包内说明文件
+const README: &str = "Screen Analyzer 脱敏诊断包\n\n\
+frames/ 中的截图已做马赛克处理，只保留窗口布局和大致配色，无法辨认文字和图像内容。\n\
+manifest.json 只包含时间、类别、尺寸和视频参数，不含会话标题、摘要和应用名称。\n\
+附到 issue 前请再自行检查一遍。\n";
+
+/// 截图脱敏：缩小后再用最近邻放大回原尺寸，得到保留布局的马赛克
+pub fn anonymize_frame(path: &Path) -> Result<RgbImage, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("读取截图 {:?} 失败: {}", path, e))?
+        .to_rgb8();
+    Ok(pixelate(&image))
+}
+
+fn pixelate(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let block = (width / 48).max(MIN_BLOCK_SIZE);
+    let small = image::imageops::resize(
+        image,
+        (width / block).max(1),
+        (height / block).max(1),
+        FilterType::Triangle,
+    );
+    image::imageops::resize(&small, width, height, FilterType::Nearest)
+}
+
+/// 截图元数据
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameEntry {
+    pub file_name: String,
+    pub timestamp: String,
+    /// 距会话开始的秒数
+    pub offset_secs: i64,
+    pub width: u32,
+    pub height: u32,
+    /// 原始截图文件大小（从视频抽帧时为 None）
+    pub original_bytes: Option<u64>,
+}
+
+/// 卡片元数据（不含标题和摘要）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardEntry {
+    pub start_time: String,
+    pub end_time: String,
+    pub category: String,
+    pub title_chars: usize,
+    pub summary_chars: usize,
+    pub confidence: Option<f64>,
+    pub review_status: Option<String>,
+}
+
+/// 诊断包清单
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsManifest {
+    pub generated_at: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub session_id: i64,
+    pub session_start: String,
+    pub session_end: String,
+    pub device_type: Option<String>,
+    pub capture_interval_secs: u64,
+    pub frame_count: usize,
+    /// 截图是否从视频中抽取（原始截图已清理）
+    pub frames_from_video: bool,
+    pub video: Option<VideoInfo>,
+    pub frames: Vec<FrameEntry>,
+    pub cards: Vec<CardEntry>,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    pub path: String,
+    pub frame_count: usize,
+    pub size_bytes: u64,
+}
+
+/// 均匀取出最多 `max` 个下标
+fn sample_indices(total: usize, max: usize) -> Vec<usize> {
+    if total <= max {
+        return (0..total).collect();
+    }
+    (0..max).map(|i| i * (total - 1) / (max - 1)).collect()
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    manifest: &DiagnosticsManifest,
+    frames: &[(String, RgbImage)],
+) -> Result<W, String> {
+    let mut zip = ZipWriter::new(writer);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let json =
+        serde_json::to_string_pretty(manifest).map_err(|e| format!("序列化诊断信息失败: {}", e))?;
+    for (name, content) in [("manifest.json", json.as_str()), ("README.txt", README)] {
+        zip.start_file(name, deflated)
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    }
+
+    for (name, image) in frames {
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 80)
+            .encode(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                image::ColorType::Rgb8,
+            )
+            .map_err(|e| format!("编码 {} 失败: {}", name, e))?;
+        zip.start_file(name.as_str(), stored)
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+        zip.write_all(&jpeg)
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    }
+
+    zip.finish().map_err(|e| format!("完成诊断包失败: {}", e))
+}
+
+/// 导出会话的脱敏诊断包到 `path`
+pub async fn export_diagnostics_bundle(
+    storage: &StorageDomain,
+    session_id: i64,
+    path: &Path,
+) -> Result<DiagnosticsReport, String> {
+    let db = storage.get_db().await?;
+    let (session, cards) = super::playback::load_session_cards(&db, session_id).await?;
+    let config = storage.get_settings().get().await;
+
+    // 优先使用仍在磁盘上的截图
+    let frames = db
+        .get_frames_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取截图失败: {}", e))?;
+    let on_disk: Vec<_> = frames
+        .into_iter()
+        .filter(|frame| Path::new(&frame.file_path).is_file())
+        .collect();
+    let mut sources: Vec<(PathBuf, DateTime<Utc>, Option<u64>)> =
+        sample_indices(on_disk.len(), MAX_FRAMES)
+            .into_iter()
+            .map(|index| {
+                let frame = &on_disk[index];
+                let bytes = std::fs::metadata(&frame.file_path).ok().map(|m| m.len());
+                (PathBuf::from(&frame.file_path), frame.timestamp, bytes)
+            })
+            .collect();
+
+    // 截图已清理时从会话视频中均匀抽帧
+    let mut video = None;
+    let mut extracted = Vec::new();
+    if let Some(video_path) = session.video_path.as_deref() {
+        match storage
+            .get_media()
+            .ensure_local_video(Path::new(video_path))
+            .await
+        {
+            Ok(local) => {
+                let probe = local.clone();
+                video = tokio::task::spawn_blocking(move || VideoUtils::get_video_info(&probe))
+                    .await
+                    .ok()
+                    .and_then(|info| info.ok());
+                if sources.is_empty() {
+                    let duration = video
+                        .as_ref()
+                        .map(|info| info.duration as f64)
+                        .unwrap_or(0.0);
+                    let mapping = session_mapping(&session, storage.get_settings()).await;
+                    // 视频时长未知时不抽帧
+                    let count = if duration > 0.0 { MAX_FRAMES / 2 } else { 0 };
+                    for index in 0..count {
+                        let offset = duration * (index as f64 + 0.5) / count as f64;
+                        let output = std::env::temp_dir()
+                            .join(format!("diagnostics_{}_{}.jpg", session_id, index));
+                        if let Err(e) =
+                            VideoUtils::extract_frame(&local, &output, offset as f32).await
+                        {
+                            warn!("从视频抽帧失败: {}", e);
+                            continue;
+                        }
+                        let real_offset = mapping.video_to_real(offset);
+                        let timestamp = session.start_time
+                            + Duration::milliseconds((real_offset * 1000.0) as i64);
+                        extracted.push(output.clone());
+                        sources.push((output, timestamp, None));
+                    }
+                }
+            }
+            Err(e) => warn!("会话 {} 的视频不可用: {}", session_id, e),
+        }
+    }
+    let frames_from_video = !extracted.is_empty();
+
+    let session_start = session.start_time;
+    let anonymized = tokio::task::spawn_blocking(move || {
+        sources
+            .into_iter()
+            .filter_map(
+                |(source, timestamp, bytes)| match anonymize_frame(&source) {
+                    Ok(image) => Some((timestamp, bytes, image)),
+                    Err(e) => {
+                        warn!("{}", e);
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("截图脱敏失败: {}", e))?;
+    for path in &extracted {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+
+    let mut frame_entries = Vec::new();
+    let mut frame_images = Vec::new();
+    for (index, (timestamp, bytes, image)) in anonymized.into_iter().enumerate() {
+        let file_name = format!("frames/frame_{:03}.jpg", index);
+        frame_entries.push(FrameEntry {
+            file_name: file_name.clone(),
+            timestamp: format_time(timestamp),
+            offset_secs: (timestamp - session_start).num_seconds(),
+            width: image.width(),
+            height: image.height(),
+            original_bytes: bytes,
+        });
+        frame_images.push((file_name, image));
+    }
+
+    let manifest = DiagnosticsManifest {
+        generated_at: format_time(crate::storage::local_now()),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        session_id,
+        session_start: format_time(session.start_time),
+        session_end: format_time(session.end_time),
+        device_type: session.device_type.clone(),
+        capture_interval_secs: config.capture_interval,
+        frame_count: frame_entries.len(),
+        frames_from_video,
+        video,
+        frames: frame_entries,
+        cards: cards
+            .iter()
+            .map(|card| CardEntry {
+                start_time: card.start_time.clone(),
+                end_time: card.end_time.clone(),
+                category: card.category.clone(),
+                title_chars: card.title.chars().count(),
+                summary_chars: card.summary.chars().count(),
+                confidence: card.confidence,
+                review_status: card.review_status.clone(),
+            })
+            .collect(),
+    };
+
+    // 先写入临时文件，完成后再改名，避免留下不完整的压缩包
+    let target = path.to_path_buf();
+    let partial = target.with_extension("zip.part");
+    let frame_count = frame_images.len();
+    let size_bytes = tokio::task::spawn_blocking(move || {
+        let file =
+            File::create(&partial).map_err(|e| format!("创建文件 {:?} 失败: {}", partial, e))?;
+        let result = write_zip(file, &manifest, &frame_images).and_then(|file| {
+            file.sync_all()
+                .map_err(|e| format!("写入诊断包失败: {}", e))?;
+            std::fs::rename(&partial, &target).map_err(|e| format!("保存诊断包失败: {}", e))
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+        result?;
+        Ok::<u64, String>(std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0))
+    })
+    .await
+    .map_err(|e| format!("导出诊断包失败: {}", e))??;
+
+    info!(
+        "已导出会话 {} 的脱敏诊断包: {:?}（{} 张截图）",
+        session_id, path, frame_count
+    );
+    Ok(DiagnosticsReport {
+        path: path.to_string_lossy().to_string(),
+        frame_count,
+        size_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixelate_keeps_size_and_hides_detail() {
+        // 1 像素宽的黑白条纹（类似文字笔画）
+        let image = RgbImage::from_fn(96, 64, |x, _| {
+            if x % 2 == 0 {
+                image::Rgb([0, 0, 0])
+            } else {
+                image::Rgb([255, 255, 255])
+            }
+        });
+        let anonymized = pixelate(&image);
+
+        assert_eq!(anonymized.dimensions(), (96, 64));
+        // 同一马赛克块内颜色一致，条纹被抹平
+        let first = anonymized.get_pixel(0, 0);
+        assert!((0..16).all(|x| anonymized.get_pixel(x, 0) == first));
+        assert!(first[0] > 64 && first[0] < 192);
+
+        assert_eq!(sample_indices(3, 24), vec![0, 1, 2]);
+        assert_eq!(sample_indices(100, 3), vec![0, 49, 99]);
+    }
+}
@@ -0,0 +1,230 @@
+// 数据外发记录 - 记录每一次把用户内容发出本机的传输，供用户核实哪些数据离开了电脑
+//
+// 覆盖 LLM 调用（截图、视频上传、提示词）、Notion 同步、远程媒体存储（S3/WebDAV）、RAG 导出和处理流程的 webhook，
+// 记录目标主机、字节数和内容类别。发往本机回环地址的请求不记录；
+// 外部插件由插件进程自行联网，不在记录范围内。
+// 记录在后台写入 egress_log 表，写入失败只记日志，不影响传输本身
+
+use super::metrics::StatsPeriod;
+use crate::storage::{Database, EgressRecord};
+use chrono::{Duration, NaiveDate};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::warn;
+
+static DATABASE: OnceLock<RwLock<Option<Arc<Database>>>> = OnceLock::new();
+
+fn database() -> &'static RwLock<Option<Arc<Database>>> {
+    DATABASE.get_or_init(|| RwLock::new(None))
+}
+
+/// 设置写入记录的数据库（数据库就绪后调用）
+pub fn set_database(db: Arc<Database>) {
+    if let Ok(mut state) = database().write() {
+        *state = Some(db);
+    }
+}
+
+/// 外发渠道
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EgressChannel {
+    Llm,
+    Notion,
+    MediaStore,
+    Rag,
+    Webhook,
+    TimeTracking,
+}
+
+impl EgressChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Llm => "llm",
+            Self::Notion => "notion",
+            Self::MediaStore => "media_store",
+            Self::Rag => "rag",
+            Self::Webhook => "webhook",
+            Self::TimeTracking => "time_tracking",
+        }
+    }
+}
+
+/// 外发内容类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EgressContent {
+    /// 截图（图片帧）
+    Screenshots,
+    /// 会话视频
+    Video,
+    /// 文本（提示词、卡片、总结等）
+    Text,
+}
+
+impl EgressContent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Screenshots => "screenshots",
+            Self::Video => "video",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// 从地址中取出目标主机（无法解析时原样保留）
+fn destination_host(destination: &str) -> String {
+    Url::parse(destination)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| destination.to_string())
+}
+
+/// 目标是否为本机回环地址（数据没有离开本机）
+fn is_loopback(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
+
+/// 记录一次外发（后台写入，不阻塞调用方）
+///
+/// `destination` 可以是完整地址或主机名，只保存主机部分
+pub fn record(
+    channel: EgressChannel,
+    destination: &str,
+    content: EgressContent,
+    bytes: u64,
+    session_id: Option<i64>,
+) {
+    let destination = destination_host(destination);
+    if is_loopback(&destination) {
+        return;
+    }
+    let Some(db) = database().read().ok().and_then(|state| state.clone()) else {
+        return;
+    };
+
+    let record = EgressRecord {
+        id: None,
+        channel: channel.as_str().to_string(),
+        destination,
+        category: content.as_str().to_string(),
+        bytes: bytes as i64,
+        session_id,
+        created_at: crate::storage::local_now(),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = db.insert_egress_event(&record).await {
+            warn!("写入数据外发记录失败: {}", e);
+        }
+    });
+}
+
+/// 按目标和内容类别汇总的外发量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EgressTotal {
+    pub destination: String,
+    pub category: String,
+    pub bytes: i64,
+    pub count: usize,
+}
+
+/// 一个周期的外发记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EgressLog {
+    pub events: Vec<EgressRecord>,
+    pub totals: Vec<EgressTotal>,
+    pub total_bytes: i64,
+}
+
+/// 按目标和内容类别汇总（按字节数从大到小）
+pub fn summarize(events: &[EgressRecord]) -> Vec<EgressTotal> {
+    let mut totals: BTreeMap<(&str, &str), EgressTotal> = BTreeMap::new();
+    for event in events {
+        let total = totals
+            .entry((event.destination.as_str(), event.category.as_str()))
+            .or_insert_with(|| EgressTotal {
+                destination: event.destination.clone(),
+                category: event.category.clone(),
+                bytes: 0,
+                count: 0,
+            });
+        total.bytes += event.bytes;
+        total.count += 1;
+    }
+
+    let mut totals: Vec<EgressTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    totals
+}
+
+/// 获取包含锚点日期的周期内的外发记录
+pub async fn get_egress_log(
+    db: &Database,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+) -> Result<EgressLog, String> {
+    let (start, end) = period.date_range(anchor);
+    let start = start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc() + Duration::days(1);
+    let events = db
+        .get_egress_events_between(start, end)
+        .await
+        .map_err(|e| format!("获取数据外发记录失败: {}", e))?;
+
+    let totals = summarize(&events);
+    let total_bytes = events.iter().map(|event| event.bytes).sum();
+    Ok(EgressLog {
+        events,
+        totals,
+        total_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(destination: &str, category: &str, bytes: i64) -> EgressRecord {
+        EgressRecord {
+            id: None,
+            channel: "llm".to_string(),
+            destination: destination.to_string(),
+            category: category.to_string(),
+            bytes,
+            session_id: None,
+            created_at: crate::storage::local_now(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_groups_by_destination_and_category() {
+        let events = vec![
+            event("dashscope.aliyuncs.com", "screenshots", 300),
+            event("api.notion.com", "text", 50),
+            event("dashscope.aliyuncs.com", "screenshots", 200),
+            event("dashscope.aliyuncs.com", "text", 10),
+        ];
+        let totals = summarize(&events);
+        assert_eq!(totals.len(), 3);
+        assert_eq!(totals[0].destination, "dashscope.aliyuncs.com");
+        assert_eq!(totals[0].category, "screenshots");
+        assert_eq!(totals[0].bytes, 500);
+        assert_eq!(totals[0].count, 2);
+        assert_eq!(totals[2].bytes, 10);
+
+        assert_eq!(
+            destination_host("https://dashscope.aliyuncs.com/compatible-mode/v1"),
+            "dashscope.aliyuncs.com"
+        );
+        assert!(is_loopback(&destination_host("http://127.0.0.1:11434/v1")));
+        assert!(!is_loopback("192.168.1.20"));
+    }
+}
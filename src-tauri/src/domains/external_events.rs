@@ -0,0 +1,313 @@
+// 外部活动事件 - 接收截屏之外的活动来源（手机屏幕使用时间导出、浏览器扩展上报的活动标签页等）
+//
+// 事件通过 ingest_external_events 命令或本地服务的 POST /events 接口写入 external_events 表，
+// 相同来源、开始时间和标题的事件只保存一次，来源可以放心重复上报。
+// 每日总结中每个来源作为一个“设备”统计时长，日历统计中记入 external_minutes
+
+use crate::storage::{Activity, Database, ExternalEventRecord};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 单次最多接收的事件数
+pub const MAX_EVENTS_PER_REQUEST: usize = 1000;
+
+/// 单个事件的最长时长
+const MAX_EVENT_HOURS: i64 = 24;
+
+/// 来源名称的最大长度
+const MAX_SOURCE_CHARS: usize = 64;
+
+/// 标题的最大长度（超出部分截断）
+const MAX_TITLE_CHARS: usize = 255;
+
+/// 外部来源上报的活动事件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalEventInput {
+    /// 来源，如 phone、browser
+    pub source: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    /// 开始时间：RFC 3339（带时区），或本地时间 "YYYY-MM-DD HH:MM:SS"
+    pub start_time: String,
+    /// 结束时间，与 duration_seconds 二选一
+    #[serde(default)]
+    pub end_time: Option<String>,
+    #[serde(default)]
+    pub duration_seconds: Option<i64>,
+    /// 来源附带的原始数据，原样保存
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// 写入结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestResult {
+    pub received: usize,
+    /// 新增条数（已存在的事件不重复计入）
+    pub inserted: u64,
+}
+
+/// 解析事件时间，统一为 local_now() 约定的本地时间值
+fn parse_event_time(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Local).naive_local().and_utc());
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|time| time.and_utc())
+        .ok_or_else(|| format!("无法解析时间: {}", value))
+}
+
+/// 校验并转换为数据库记录
+pub fn to_record(input: &ExternalEventInput) -> Result<ExternalEventRecord, String> {
+    let source = input.source.trim().to_lowercase();
+    if source.is_empty() || source.chars().count() > MAX_SOURCE_CHARS {
+        return Err(format!(
+            "来源名称不能为空且不超过 {} 个字符",
+            MAX_SOURCE_CHARS
+        ));
+    }
+
+    let start_time = parse_event_time(&input.start_time)?;
+    let end_time = match (&input.end_time, input.duration_seconds) {
+        (Some(end_time), _) => parse_event_time(end_time)?,
+        (None, Some(seconds)) => start_time + Duration::seconds(seconds),
+        (None, None) => return Err("需要提供 endTime 或 durationSeconds".to_string()),
+    };
+    if end_time <= start_time {
+        return Err("结束时间必须晚于开始时间".to_string());
+    }
+    if end_time - start_time > Duration::hours(MAX_EVENT_HOURS) {
+        return Err(format!("单个事件不能超过 {} 小时", MAX_EVENT_HOURS));
+    }
+
+    let category = input
+        .category
+        .as_deref()
+        .map(|category| category.trim().to_lowercase())
+        .filter(|category| !category.is_empty())
+        .unwrap_or_else(|| "other".to_string());
+
+    Ok(ExternalEventRecord {
+        id: None,
+        source,
+        category,
+        title: input.title.trim().chars().take(MAX_TITLE_CHARS).collect(),
+        start_time,
+        end_time,
+        metadata: input.metadata.as_ref().map(|metadata| metadata.to_string()),
+    })
+}
+
+/// 写入一批外部事件（任一事件不合法时整批拒绝，便于来源端发现问题）
+pub async fn ingest(db: &Database, inputs: &[ExternalEventInput]) -> Result<IngestResult, String> {
+    if inputs.len() > MAX_EVENTS_PER_REQUEST {
+        return Err(format!("单次最多提交 {} 个事件", MAX_EVENTS_PER_REQUEST));
+    }
+    let records = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            to_record(input).map_err(|e| format!("第 {} 个事件: {}", index + 1, e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let inserted = db
+        .insert_external_events(&records)
+        .await
+        .map_err(|e| format!("保存外部事件失败: {}", e))?;
+    Ok(IngestResult {
+        received: records.len(),
+        inserted,
+    })
+}
+
+/// 来源的显示名称（未知来源原样显示）
+pub fn source_label(source: &str) -> String {
+    match source {
+        "phone" => "手机".to_string(),
+        "browser" => "浏览器".to_string(),
+        "tablet" => "平板".to_string(),
+        "rescuetime" => "RescueTime".to_string(),
+        "toggl" => "Toggl".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 只作为上下文的来源：浏览器扩展记录的是本机前台的标签页，时长已计入本机会话，不再单独计时
+pub fn is_context_only(source: &str) -> bool {
+    source == super::browser::BROWSER_SOURCE
+}
+
+/// 事件在各天的时长（分钟），跨天的事件按天拆分，不含只作为上下文的来源
+pub fn minutes_by_day(events: &[ExternalEventRecord]) -> BTreeMap<NaiveDate, i64> {
+    let mut totals = BTreeMap::new();
+    for event in events
+        .iter()
+        .filter(|event| !is_context_only(&event.source))
+    {
+        let mut cursor = event.start_time;
+        while cursor < event.end_time {
+            let day = cursor.date_naive();
+            let next_day = day
+                .succ_opt()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc())
+                .unwrap_or(event.end_time);
+            let until = next_day.min(event.end_time);
+            *totals.entry(day).or_default() += (until - cursor).num_minutes();
+            cursor = until;
+        }
+    }
+    totals
+}
+
+/// 某天各来源的时长（分钟），按时长降序，不含只作为上下文的来源
+pub fn minutes_by_source(events: &[ExternalEventRecord], day: NaiveDate) -> Vec<(String, i64)> {
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = start + Duration::days(1);
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for event in events
+        .iter()
+        .filter(|event| !is_context_only(&event.source))
+    {
+        let minutes = (event.end_time.min(end) - event.start_time.max(start)).num_minutes();
+        if minutes > 0 {
+            *totals.entry(event.source.as_str()).or_default() += minutes;
+        }
+    }
+
+    let mut totals: Vec<(String, i64)> = totals
+        .into_iter()
+        .map(|(source, minutes)| (source.to_string(), minutes))
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1));
+    totals
+}
+
+/// 获取日期范围内（含两端）的外部事件
+pub async fn load_events(
+    db: &Database,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<ExternalEventRecord>, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))
+    };
+    let start = parse(start_date)?
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+    let end = parse(end_date)?
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+        + Duration::days(1);
+    db.get_external_events_between(start, end)
+        .await
+        .map_err(|e| format!("获取外部事件失败: {}", e))
+}
+
+/// 把外部事件时长并入日历统计，只有外部事件的日期也会出现
+pub fn merge_into_activities(
+    mut activities: Vec<Activity>,
+    events: &[ExternalEventRecord],
+    start_date: &str,
+    end_date: &str,
+) -> Vec<Activity> {
+    for (day, minutes) in minutes_by_day(events) {
+        let date = day.format("%Y-%m-%d").to_string();
+        if date.as_str() < start_date || date.as_str() > end_date {
+            continue;
+        }
+        match activities.iter_mut().find(|activity| activity.date == date) {
+            Some(activity) => activity.external_minutes += minutes as i32,
+            None => activities.push(Activity {
+                date,
+                session_count: 0,
+                total_duration_minutes: 0,
+                main_categories: Vec::new(),
+                external_minutes: minutes as i32,
+            }),
+        }
+    }
+    activities.sort_by(|a, b| b.date.cmp(&a.date));
+    activities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(start: &str, end: Option<&str>, duration: Option<i64>) -> ExternalEventInput {
+        ExternalEventInput {
+            source: " Phone ".to_string(),
+            category: None,
+            title: "微信".to_string(),
+            start_time: start.to_string(),
+            end_time: end.map(str::to_string),
+            duration_seconds: duration,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_events_are_validated_and_split_by_day() {
+        let late = to_record(&input("2025-10-09 23:30:00", None, Some(3600))).unwrap();
+        assert_eq!(late.source, "phone");
+        assert_eq!(late.category, "other");
+        let morning = to_record(&input(
+            "2025-10-10T08:00:00",
+            Some("2025-10-10T08:20:00"),
+            None,
+        ))
+        .unwrap();
+
+        assert!(to_record(&input("2025-10-10 08:00:00", None, None)).is_err());
+        assert!(to_record(&input(
+            "2025-10-10 08:00:00",
+            Some("2025-10-10 07:00:00"),
+            None
+        ))
+        .is_err());
+
+        // 浏览器标签页的时长已计入本机会话，不再作为外部时长统计
+        let mut browser = to_record(&input(
+            "2025-10-10T09:00:00",
+            Some("2025-10-10T09:30:00"),
+            None,
+        ))
+        .unwrap();
+        browser.source = "browser".to_string();
+
+        let events = vec![late, morning, browser];
+        let by_day: Vec<(String, i64)> = minutes_by_day(&events)
+            .into_iter()
+            .map(|(day, minutes)| (day.to_string(), minutes))
+            .collect();
+        assert_eq!(
+            by_day,
+            vec![
+                ("2025-10-09".to_string(), 30),
+                ("2025-10-10".to_string(), 50)
+            ]
+        );
+        let day = NaiveDate::from_ymd_opt(2025, 10, 10).unwrap();
+        assert_eq!(
+            minutes_by_source(&events, day),
+            vec![("phone".to_string(), 50)]
+        );
+
+        let activities = merge_into_activities(Vec::new(), &events, "2025-10-10", "2025-10-10");
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].external_minutes, 50);
+        assert_eq!(activities[0].session_count, 0);
+    }
+}
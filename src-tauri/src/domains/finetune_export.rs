@@ -0,0 +1,423 @@
+// 微调数据集导出 - 把历史 LLM 调用整理成 JSONL 格式的指令微调数据
+//
+// 每行一个样本，采用通用的 chat 格式：
+// {"messages":[{"role":"system",...},{"role":"user",...},{"role":"assistant",...}],"metadata":{...}}
+// 可直接用于为 Ollama 等本地模型做 LoRA 微调。
+// - 只导出成功且有响应内容的调用，默认只包含视频分段和时间线生成两类
+// - 请求里的图片/视频（记录时已去掉 base64）替换为 <image>/<video> 占位符，
+//   如需训练多模态模型，需要自行按会话重新附上画面
+// - 用户修正过的卡片，会把助手输出中对应卡片的标题/类别替换为修正后的值，
+//   并在 metadata.corrections 中保留修正前后的对照
+// - 会话中有卡片敏感度高于设置值时，该会话的调用都不导出
+
+use crate::models::PrivacyLevel;
+use crate::storage::{CardCorrectionRecord, Database, LLMCallRecord};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+/// 默认导出的调用类型
+pub const DEFAULT_CALL_TYPES: &[&str] = &["segment_video", "generate_timeline"];
+
+/// 读取修正记录的上限（修正记录只有用户手动编辑才会产生，数量不大）
+const CORRECTION_LIMIT: i64 = 100_000;
+
+/// 图片和视频的占位符
+const IMAGE_PLACEHOLDER: &str = "<image>";
+const VIDEO_PLACEHOLDER: &str = "<video>";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinetuneExportReport {
+    pub path: String,
+    /// 导出的样本数
+    pub examples: usize,
+    /// 其中应用了用户修正的样本数
+    pub corrected_examples: usize,
+    /// 失败、无响应或无法解析而跳过的调用数
+    pub skipped: usize,
+    /// 因隐私敏感度被排除的调用数
+    pub privacy_excluded: usize,
+}
+
+/// 把一段消息内容转为纯文本，媒体内容替换为占位符
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part.get("type").and_then(Value::as_str) {
+                Some("text") => part.get("text").and_then(Value::as_str).map(str::to_string),
+                Some("image_url" | "image") => Some(IMAGE_PLACEHOLDER.to_string()),
+                Some("video_url" | "video") => Some(VIDEO_PLACEHOLDER.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 从记录的请求体中取出（系统提示词, 用户提示词）
+///
+/// 兼容 OpenAI 兼容接口的 messages、Claude 的请求快照和 Codex 的 prompt 三种格式
+fn prompt_messages(request_body: &str) -> Option<(Option<String>, String)> {
+    let request: Value = serde_json::from_str(request_body).ok()?;
+    if let Some(messages) = request.get("messages").and_then(Value::as_array) {
+        let mut system = Vec::new();
+        let mut user = Vec::new();
+        for message in messages {
+            let text = content_text(message.get("content").unwrap_or(&Value::Null));
+            match message.get("role").and_then(Value::as_str) {
+                Some("system") => system.push(text),
+                Some("user") => user.push(text),
+                _ => {}
+            }
+        }
+        let system = (!system.is_empty()).then(|| system.join("\n"));
+        return (!user.is_empty()).then(|| (system, user.join("\n")));
+    }
+    if let Some(message) = request.get("message") {
+        let system = request
+            .get("system_prompt")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        return Some((
+            system,
+            content_text(message.get("content").unwrap_or(&Value::Null)),
+        ));
+    }
+    let prompt = request.get("prompt").and_then(Value::as_str)?;
+    Some((None, prompt.to_string()))
+}
+
+/// 从记录的响应中取出模型输出的文本
+///
+/// OpenAI 兼容接口取 choices[0].message.content，Claude 取事件流中的结果或最后一条助手消息，
+/// 其他（如 Codex 的标准输出）按原文返回
+fn response_text(response_body: &str) -> Option<String> {
+    let text = match serde_json::from_str::<Value>(response_body) {
+        Ok(Value::Object(response)) => response
+            .get("choices")
+            .and_then(|choices| choices.get(0))
+            .and_then(|choice| choice.pointer("/message/content"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        Ok(Value::Array(events)) => {
+            let result = events.iter().rev().find_map(|event| {
+                (event.get("type").and_then(Value::as_str) == Some("result"))
+                    .then(|| event.get("result").and_then(Value::as_str))
+                    .flatten()
+                    .map(str::to_string)
+            });
+            result.or_else(|| {
+                events.iter().rev().find_map(|event| {
+                    let content = event.pointer("/message/content")?;
+                    (event.get("type").and_then(Value::as_str) == Some("assistant"))
+                        .then(|| content_text(content))
+                        .filter(|text| !text.trim().is_empty())
+                })
+            })
+        }
+        _ => Some(response_body.to_string()),
+    }?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// 去掉 Markdown 代码块围栏
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let rest = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+    rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// 输出中的卡片数组：顶层数组，或对象中第一个元素带 title 的数组字段
+fn cards_mut(output: &mut Value) -> Option<&mut Vec<Value>> {
+    fn is_card_list(items: &[Value]) -> bool {
+        items.first().and_then(|item| item.get("title")).is_some()
+    }
+    match output {
+        Value::Array(items) => is_card_list(items).then_some(items),
+        Value::Object(map) => map.values_mut().find_map(|value| match value {
+            Value::Array(items) if is_card_list(items) => Some(items),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// 把用户修正应用到模型输出中对应的卡片（按修正前的标题和类别匹配），
+/// 返回修正后的输出文本和实际应用的修正；输出无法解析为 JSON 时原样返回
+fn apply_corrections<'a>(
+    output: &str,
+    corrections: &[&'a CardCorrectionRecord],
+) -> (String, Vec<&'a CardCorrectionRecord>) {
+    if corrections.is_empty() {
+        return (output.to_string(), Vec::new());
+    }
+    let Ok(mut value) = serde_json::from_str::<Value>(strip_code_fence(output)) else {
+        return (output.to_string(), Vec::new());
+    };
+    let Some(cards) = cards_mut(&mut value) else {
+        return (output.to_string(), Vec::new());
+    };
+
+    let mut applied = Vec::new();
+    for correction in corrections {
+        let card = cards.iter_mut().find(|card| {
+            card.get("title").and_then(Value::as_str) == Some(correction.original_title.as_str())
+                && card
+                    .get("category")
+                    .and_then(Value::as_str)
+                    .map_or(true, |category| {
+                        category.eq_ignore_ascii_case(&correction.original_category)
+                    })
+        });
+        let Some(Value::Object(card)) = card else {
+            continue;
+        };
+        card.insert("title".into(), json!(correction.corrected_title));
+        card.insert("category".into(), json!(correction.corrected_category));
+        card.insert(
+            "subcategory".into(),
+            json!(correction.corrected_subcategory),
+        );
+        applied.push(*correction);
+    }
+    if applied.is_empty() {
+        return (output.to_string(), applied);
+    }
+    (value.to_string(), applied)
+}
+
+/// 把一次 LLM 调用转为一个样本，调用未成功或无法解析时返回 None
+fn build_example(call: &LLMCallRecord, corrections: &[&CardCorrectionRecord]) -> Option<Value> {
+    if call.error_message.is_some() || call.status_code.is_some_and(|code| code >= 400) {
+        return None;
+    }
+    let (system, user) = prompt_messages(&call.request_body)?;
+    let output = response_text(call.response_body.as_deref()?)?;
+    let (output, applied) = apply_corrections(&output, corrections);
+
+    let mut messages = Vec::new();
+    if let Some(system) = system.filter(|system| !system.trim().is_empty()) {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": user }));
+    messages.push(json!({ "role": "assistant", "content": output }));
+
+    let corrections: Vec<Value> = applied
+        .iter()
+        .map(|c| {
+            json!({
+                "card_id": c.card_id,
+                "original": { "title": c.original_title, "category": c.original_category, "subcategory": c.original_subcategory },
+                "corrected": { "title": c.corrected_title, "category": c.corrected_category, "subcategory": c.corrected_subcategory },
+            })
+        })
+        .collect();
+    Some(json!({
+        "messages": messages,
+        "metadata": {
+            "llm_call_id": call.id,
+            "session_id": call.session_id,
+            "call_type": call.call_type,
+            "provider": call.provider,
+            "model": call.model,
+            "prompt_version": call.prompt_version,
+            "corrected": !corrections.is_empty(),
+            "corrections": corrections,
+        }
+    }))
+}
+
+/// 导出微调数据集到 `path`（JSONL 文件）
+///
+/// `call_types` 为空时使用 DEFAULT_CALL_TYPES；`only_corrected` 为 true 时只导出应用了用户修正的样本
+pub async fn export_finetune_dataset(
+    db: &Database,
+    path: &Path,
+    call_types: &[String],
+    only_corrected: bool,
+    max_level: PrivacyLevel,
+) -> Result<FinetuneExportReport, String> {
+    let call_types: Vec<&str> = if call_types.is_empty() {
+        DEFAULT_CALL_TYPES.to_vec()
+    } else {
+        call_types.iter().map(String::as_str).collect()
+    };
+    let corrections = db
+        .get_recent_card_corrections(CORRECTION_LIMIT)
+        .await
+        .map_err(|e| format!("获取卡片修正记录失败: {}", e))?;
+    let mut corrections_by_card: HashMap<i64, Vec<&CardCorrectionRecord>> = HashMap::new();
+    for correction in &corrections {
+        corrections_by_card
+            .entry(correction.card_id)
+            .or_default()
+            .push(correction);
+    }
+
+    let mut sessions = db
+        .get_all_sessions()
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    sessions.sort_by_key(|session| session.start_time);
+
+    let mut lines = String::new();
+    let mut report = FinetuneExportReport {
+        path: path.to_string_lossy().to_string(),
+        examples: 0,
+        corrected_examples: 0,
+        skipped: 0,
+        privacy_excluded: 0,
+    };
+    for session in sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let calls: Vec<LLMCallRecord> = db
+            .get_llm_calls_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取 LLM 调用记录失败: {}", e))?
+            .into_iter()
+            .filter(|call| call_types.contains(&call.call_type.as_str()))
+            .collect();
+        if calls.is_empty() {
+            continue;
+        }
+        let cards = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        if cards
+            .iter()
+            .any(|card| super::privacy::card_level(card) > max_level)
+        {
+            report.privacy_excluded += calls.len();
+            continue;
+        }
+
+        for call in &calls {
+            // 只有生成了某张卡片的调用才应用该卡片的修正
+            let call_corrections: Vec<&CardCorrectionRecord> = cards
+                .iter()
+                .filter(|card| call.id.is_some() && card.llm_call_id == call.id)
+                .filter_map(|card| card.id.and_then(|id| corrections_by_card.get(&id)))
+                .flatten()
+                .copied()
+                .collect();
+            let Some(example) = build_example(call, &call_corrections) else {
+                report.skipped += 1;
+                continue;
+            };
+            let corrected = example["metadata"]["corrected"].as_bool() == Some(true);
+            if only_corrected && !corrected {
+                continue;
+            }
+            lines.push_str(&example.to_string());
+            lines.push('\n');
+            report.examples += 1;
+            if corrected {
+                report.corrected_examples += 1;
+            }
+        }
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建目录 {:?} 失败: {}", parent, e))?;
+    }
+    tokio::fs::write(path, lines)
+        .await
+        .map_err(|e| format!("写入 {:?} 失败: {}", path, e))?;
+
+    info!(
+        "微调数据集已导出到 {:?}: {} 个样本（{} 个含用户修正），跳过 {} 个调用",
+        path, report.examples, report.corrected_examples, report.skipped
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(request_body: Value, response_body: &str) -> LLMCallRecord {
+        LLMCallRecord {
+            id: Some(7),
+            session_id: Some(1),
+            provider: "qwen".to_string(),
+            model: "qwen-vl-max".to_string(),
+            call_type: "generate_timeline".to_string(),
+            request_headers: "{}".to_string(),
+            request_body: request_body.to_string(),
+            response_headers: None,
+            response_body: Some(response_body.to_string()),
+            status_code: Some(200),
+            error_message: None,
+            latency_ms: None,
+            token_usage: None,
+            created_at: crate::storage::local_now(),
+            prompt_version: Some("v3".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_example_applies_corrections() {
+        let request = json!({
+            "messages": [{ "role": "user", "content": [
+                { "type": "image_url", "image_url": { "url": "[BASE64_REMOVED]" } },
+                { "type": "text", "text": "生成时间线卡片" }
+            ]}]
+        });
+        let output = r#"{"cards":[{"title":"浏览网页","category":"browsing","subcategory":""},{"title":"写代码","category":"work","subcategory":"coding"}]}"#;
+        let response = json!({ "choices": [{ "message": { "content": format!("```json\n{}\n```", output) } }] });
+        let correction = CardCorrectionRecord {
+            id: None,
+            card_id: 3,
+            original_title: "浏览网页".to_string(),
+            original_category: "Browsing".to_string(),
+            original_subcategory: String::new(),
+            corrected_title: "查阅 Rust 文档".to_string(),
+            corrected_category: "work".to_string(),
+            corrected_subcategory: "research".to_string(),
+            summary: String::new(),
+            app_sites: "{}".to_string(),
+            created_at: crate::storage::local_now(),
+        };
+
+        let example = build_example(&call(request, &response.to_string()), &[&correction]).unwrap();
+        let messages = example["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["content"], "<image>\n生成时间线卡片");
+        let assistant: Value =
+            serde_json::from_str(messages[1]["content"].as_str().unwrap()).unwrap();
+        assert_eq!(assistant["cards"][0]["title"], "查阅 Rust 文档");
+        assert_eq!(assistant["cards"][0]["subcategory"], "research");
+        assert_eq!(assistant["cards"][1]["title"], "写代码");
+        assert_eq!(example["metadata"]["corrected"], true);
+        assert_eq!(example["metadata"]["corrections"][0]["card_id"], 3);
+
+        // Claude 请求快照和事件流
+        let claude = call(
+            json!({ "system_prompt": "You are creating timeline cards.", "message": { "role": "user", "content": "分析" } }),
+            r#"[{"type":"assistant","message":{"content":[{"type":"text","text":"[]"}]}},{"type":"result","result":"[{\"title\":\"开会\"}]"}]"#,
+        );
+        let example = build_example(&claude, &[]).unwrap();
+        assert_eq!(example["messages"][0]["role"], "system");
+        assert_eq!(example["messages"][2]["content"], r#"[{"title":"开会"}]"#);
+        assert_eq!(example["metadata"]["corrected"], false);
+
+        let mut failed = claude.clone();
+        failed.error_message = Some("timeout".to_string());
+        assert!(build_example(&failed, &[]).is_none());
+    }
+}
@@ -0,0 +1,325 @@
+// 专注模式 - 在用户声明的专注时段内拦截干扰应用
+//
+// 开始专注后定时检测前台应用，命中设置中的干扰应用时发送提醒（可选自动最小化），
+// 每次拦截作为一次“挡下的干扰”写入数据库，供每日总结统计。
+// 同一应用在冷却时间内反复切到前台只记录一次，避免刷屏
+
+use crate::capture::foreground::{self, ForegroundApp};
+use crate::models::{FocusAction, FocusModeSettings};
+use crate::settings::SettingsManager;
+use crate::storage::{Database, DeflectedDistractionRecord};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+/// 前台应用检测间隔（秒）
+const POLL_SECONDS: u64 = 2;
+/// 同一应用两次拦截的最小间隔（秒）
+const INTERCEPT_COOLDOWN_SECONDS: i64 = 30;
+/// 单次专注时段的最长时长（分钟）
+const MAX_BLOCK_MINUTES: u32 = 8 * 60;
+
+/// 一次拦截
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interception {
+    /// 命中的应用名称
+    pub app_name: String,
+    pub action: FocusAction,
+    /// 本次专注时段的剩余分钟数
+    pub remaining_minutes: i64,
+}
+
+/// 专注状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStatus {
+    pub active: bool,
+    pub started_at: Option<String>,
+    pub ends_at: Option<String>,
+    pub remaining_minutes: i64,
+    /// 本次专注时段内的拦截次数
+    pub deflected_count: u32,
+}
+
+/// 应用名称是否命中干扰列表（不区分大小写，忽略 .exe 后缀，包含即算）
+pub fn matches_distraction(app_name: &str, distraction_apps: &[String]) -> bool {
+    let normalize = |name: &str| {
+        let name = name.trim().to_lowercase();
+        name.strip_suffix(".exe")
+            .map(str::to_string)
+            .unwrap_or(name)
+    };
+    let app_name = normalize(app_name);
+    !app_name.is_empty()
+        && distraction_apps
+            .iter()
+            .map(|pattern| normalize(pattern))
+            .any(|pattern| !pattern.is_empty() && app_name.contains(&pattern))
+}
+
+/// 专注时段状态机（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct FocusTracker {
+    started_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    /// 各应用最近一次拦截的时间
+    last_intercepted: HashMap<String, DateTime<Utc>>,
+    deflected_count: u32,
+}
+
+impl FocusTracker {
+    /// 开始专注时段（已在专注中则重新计时）
+    pub fn start(&mut self, now: DateTime<Utc>, minutes: u32) {
+        *self = Self {
+            started_at: Some(now),
+            ends_at: Some(now + Duration::minutes(minutes as i64)),
+            ..Self::default()
+        };
+    }
+
+    /// 结束专注时段
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 当前是否在专注时段内（到期时自动结束）
+    pub fn is_active(&mut self, now: DateTime<Utc>) -> bool {
+        match self.ends_at {
+            Some(ends_at) if now < ends_at => true,
+            Some(_) => {
+                info!("专注时段已结束，共拦截 {} 次干扰", self.deflected_count);
+                self.stop();
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// 检查前台应用，命中干扰应用且不在冷却时间内时返回拦截
+    pub fn check(
+        &mut self,
+        now: DateTime<Utc>,
+        app_name: &str,
+        settings: &FocusModeSettings,
+    ) -> Option<Interception> {
+        if !self.is_active(now) || !matches_distraction(app_name, &settings.distraction_apps) {
+            return None;
+        }
+
+        let key = app_name.to_lowercase();
+        let cooling = self.last_intercepted.get(&key).map_or(false, |at| {
+            now - *at < Duration::seconds(INTERCEPT_COOLDOWN_SECONDS)
+        });
+        if cooling {
+            return None;
+        }
+        self.last_intercepted.insert(key, now);
+        self.deflected_count += 1;
+
+        Some(Interception {
+            app_name: app_name.to_string(),
+            action: settings.action,
+            remaining_minutes: self.remaining_minutes(now),
+        })
+    }
+
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        self.started_at
+    }
+
+    fn remaining_minutes(&self, now: DateTime<Utc>) -> i64 {
+        self.ends_at
+            .map(|ends_at| (ends_at - now).num_minutes().max(0))
+            .unwrap_or(0)
+    }
+
+    fn status(&mut self, now: DateTime<Utc>) -> FocusStatus {
+        let active = self.is_active(now);
+        let format =
+            |time: Option<DateTime<Utc>>| time.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+        FocusStatus {
+            active,
+            started_at: format(self.started_at),
+            ends_at: format(self.ends_at),
+            remaining_minutes: self.remaining_minutes(now),
+            deflected_count: self.deflected_count,
+        }
+    }
+}
+
+/// 专注模式守卫
+pub struct FocusGuard {
+    settings: Arc<SettingsManager>,
+    tracker: Mutex<FocusTracker>,
+}
+
+impl FocusGuard {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self {
+            settings,
+            tracker: Mutex::new(FocusTracker::default()),
+        }
+    }
+
+    /// 启动前台应用检测任务，拦截干扰应用时调用 `notify`
+    pub fn start<F>(self: Arc<Self>, db: Arc<Database>, notify: F)
+    where
+        F: Fn(&Interception) + Send + Sync + 'static,
+    {
+        tokio::spawn(async move {
+            info!("专注模式检测任务已启动");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(POLL_SECONDS)).await;
+
+                if !self
+                    .tracker
+                    .lock()
+                    .await
+                    .is_active(crate::storage::local_now())
+                {
+                    continue;
+                }
+                let settings = self.current_settings().await;
+                if settings.distraction_apps.is_empty() {
+                    continue;
+                }
+
+                let app = match tokio::task::spawn_blocking(foreground::foreground_app).await {
+                    Ok(Some(app)) => app,
+                    _ => continue,
+                };
+
+                let now = crate::storage::local_now();
+                let (interception, focus_started_at) = {
+                    let mut tracker = self.tracker.lock().await;
+                    (
+                        tracker.check(now, &app.name, &settings),
+                        tracker.started_at(),
+                    )
+                };
+                let Some(interception) = interception else {
+                    continue;
+                };
+
+                info!("专注时段内拦截干扰应用: {}", app.name);
+                if interception.action == FocusAction::Minimize {
+                    Self::minimize(app).await;
+                }
+                notify(&interception);
+
+                let (device_name, _) = crate::storage::get_device_info();
+                let record = DeflectedDistractionRecord {
+                    id: None,
+                    intercepted_at: now,
+                    app_name: interception.app_name.clone(),
+                    action: interception.action.as_str().to_string(),
+                    focus_started_at: focus_started_at.unwrap_or(now),
+                    device_name: Some(device_name),
+                };
+                if let Err(e) = db.insert_deflected_distraction(&record).await {
+                    error!("保存干扰拦截记录失败: {}", e);
+                }
+            }
+        });
+    }
+
+    /// 开始专注，未指定时长时使用设置中的默认时长
+    pub async fn start_block(&self, minutes: Option<u32>) -> Result<FocusStatus, String> {
+        let minutes = match minutes {
+            Some(minutes) => minutes,
+            None => self.current_settings().await.default_minutes,
+        };
+        if minutes == 0 || minutes > MAX_BLOCK_MINUTES {
+            return Err(format!("专注时长需在 1 到 {} 分钟之间", MAX_BLOCK_MINUTES));
+        }
+
+        let now = crate::storage::local_now();
+        let mut tracker = self.tracker.lock().await;
+        tracker.start(now, minutes);
+        info!("开始专注 {} 分钟", minutes);
+        Ok(tracker.status(now))
+    }
+
+    /// 提前结束专注
+    pub async fn stop_block(&self) -> FocusStatus {
+        let now = crate::storage::local_now();
+        let mut tracker = self.tracker.lock().await;
+        tracker.stop();
+        tracker.status(now)
+    }
+
+    /// 获取当前专注状态
+    pub async fn status(&self) -> FocusStatus {
+        self.tracker
+            .lock()
+            .await
+            .status(crate::storage::local_now())
+    }
+
+    async fn minimize(app: ForegroundApp) {
+        let name = app.name.clone();
+        let minimized = tokio::task::spawn_blocking(move || foreground::minimize_app(&app))
+            .await
+            .unwrap_or(false);
+        if !minimized {
+            error!("最小化干扰应用失败: {}", name);
+        }
+    }
+
+    async fn current_settings(&self) -> FocusModeSettings {
+        self.settings.get().await.focus_mode.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn test_intercepts_distraction_apps_during_block() {
+        let settings = FocusModeSettings {
+            distraction_apps: vec!["WeChat".to_string(), "steam.exe".to_string()],
+            action: FocusAction::Minimize,
+            default_minutes: 25,
+        };
+        assert!(matches_distraction(
+            "WeChat.exe",
+            &settings.distraction_apps
+        ));
+        assert!(matches_distraction(
+            "steamwebhelper",
+            &settings.distraction_apps
+        ));
+        assert!(!matches_distraction("Code", &settings.distraction_apps));
+
+        let mut tracker = FocusTracker::default();
+        // 未开始专注时不拦截
+        assert!(tracker.check(at(0), "WeChat", &settings).is_none());
+
+        tracker.start(at(0), 25);
+        let interception = tracker.check(at(10), "WeChat", &settings).unwrap();
+        assert_eq!(interception.action, FocusAction::Minimize);
+        assert_eq!(interception.remaining_minutes, 24);
+        assert!(tracker.check(at(10), "Code", &settings).is_none());
+
+        // 冷却时间内不重复拦截
+        assert!(tracker.check(at(30), "WeChat", &settings).is_none());
+        assert!(tracker.check(at(45), "WeChat", &settings).is_some());
+        assert_eq!(tracker.status(at(50)).deflected_count, 2);
+
+        // 专注时段到期后自动结束
+        assert!(tracker.check(at(25 * 60), "WeChat", &settings).is_none());
+        assert!(!tracker.status(at(25 * 60)).active);
+    }
+}
@@ -0,0 +1,108 @@
+// 单帧快速分析 - 把一张截图发给当前配置的视觉模型，返回类别、标题和描述
+//
+// 用于稀疏会话的兜底分析、测试服务商的识图效果，以及"我正在看什么"这类即时识别。
+// 不传截图路径时立即截取当前屏幕（不保存到截图目录，不计入会话）
+
+use crate::actors::LLMHandle;
+use crate::capture::ScreenCapture;
+use crate::llm::SessionSummary;
+use serde::Serialize;
+use std::path::Path;
+use tracing::info;
+
+/// 即时截屏的最大宽度（与视频分析时的画面大小相当）
+const CAPTURE_MAX_WIDTH: u32 = 1920;
+
+/// 单帧分析结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameAnalysis {
+    /// 活动类别（work/communication/learning/personal/idle/other）
+    pub category: String,
+    pub title: String,
+    pub description: String,
+    pub keywords: Vec<String>,
+    /// 模型给出的生产力评分（0-100）
+    pub productivity_score: Option<f32>,
+}
+
+impl From<SessionSummary> for FrameAnalysis {
+    fn from(summary: SessionSummary) -> Self {
+        let tag = summary.tags.first();
+        let category = tag
+            .and_then(|tag| serde_json::to_value(&tag.category).ok())
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_else(|| "other".to_string());
+        Self {
+            category,
+            title: summary.title,
+            description: summary.summary,
+            keywords: tag.map(|tag| tag.keywords.clone()).unwrap_or_default(),
+            productivity_score: summary.productivity_score,
+        }
+    }
+}
+
+/// 分析一张截图
+pub async fn analyze_frame(llm_handle: &LLMHandle, path: &str) -> Result<FrameAnalysis, String> {
+    if !Path::new(path).is_file() {
+        return Err(format!("截图不存在: {}", path));
+    }
+    let summary = llm_handle
+        .analyze_frames(vec![path.to_string()])
+        .await
+        .map_err(|e| format!("单帧分析失败: {}", e))?;
+    let analysis = FrameAnalysis::from(summary);
+    info!("单帧分析完成: [{}] {}", analysis.category, analysis.title);
+    Ok(analysis)
+}
+
+/// 立即截取当前屏幕并分析
+pub async fn analyze_current_screen(
+    llm_handle: &LLMHandle,
+    capture: &ScreenCapture,
+) -> Result<FrameAnalysis, String> {
+    let preview = capture
+        .capture_preview(None, CAPTURE_MAX_WIDTH)
+        .await
+        .map_err(|e| format!("截屏失败: {}", e))?;
+    let still = std::env::temp_dir().join(format!(
+        "frame_analysis_{}.jpg",
+        preview.timestamp.format("%Y%m%d%H%M%S%3f")
+    ));
+    tokio::fs::write(&still, &preview.jpeg)
+        .await
+        .map_err(|e| format!("保存截图失败: {}", e))?;
+
+    let result = analyze_frame(llm_handle, &still.to_string_lossy()).await;
+    let _ = tokio::fs::remove_file(&still).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ActivityCategory, ActivityTag};
+
+    #[test]
+    fn test_frame_analysis_takes_first_tag() {
+        let summary = SessionSummary {
+            title: "编写 Rust 代码".to_string(),
+            summary: "在编辑器中修改视频处理模块".to_string(),
+            tags: vec![ActivityTag {
+                category: ActivityCategory::Work,
+                confidence: 0.9,
+                keywords: vec!["Rust".to_string()],
+            }],
+            productivity_score: Some(85.0),
+            ..Default::default()
+        };
+        let analysis = FrameAnalysis::from(summary);
+        assert_eq!(analysis.category, "work");
+        assert_eq!(analysis.description, "在编辑器中修改视频处理模块");
+        assert_eq!(analysis.keywords, ["Rust"]);
+
+        let untagged = FrameAnalysis::from(SessionSummary::default());
+        assert_eq!(untagged.category, "other");
+        assert!(untagged.keywords.is_empty());
+    }
+}
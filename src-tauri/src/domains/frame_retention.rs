@@ -0,0 +1,171 @@
+// 截图延迟删除 - 生成视频后先保留原始截图，会话分析成功后再删除
+//
+// 默认生成视频后立即删除截图，之后分析失败或结果不理想时只能基于已有视频重试。
+// 启用后截图移到截图目录下的 kept/<视频文件名>/ 中（留在截图目录会被调度器当作新截图再次分组），
+// 协调方式：
+// - LLMProcessor 生成视频后移入保留目录，分析成功（或本来就不分析）后删除
+// - 夜间分析、仅采集等稍后分析的视频，由 analyze_video_once 分析成功后删除
+// - 重新分析会话时如果截图还在，先用截图重新生成视频再分析
+// - 清理任务删除超过 max_days 天仍未分析成功的保留目录
+
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 保留目录（位于截图目录下）
+pub const KEPT_DIR: &str = "kept";
+
+/// 会话的保留目录，`label` 为会话视频的文件名（不含扩展名）
+pub fn kept_dir(frames_dir: &Path, label: &str) -> PathBuf {
+    frames_dir.join(KEPT_DIR).join(label)
+}
+
+/// 视频对应的保留目录名
+pub fn label_for_video(video_path: &Path) -> Option<&str> {
+    video_path.file_stem().and_then(|stem| stem.to_str())
+}
+
+/// 保留目录是否已超过保留天数
+fn is_expired(age_secs: u64, max_days: u32) -> bool {
+    age_secs >= max_days.max(1) as u64 * 86_400
+}
+
+/// 把截图移到保留目录，返回保留的数量
+///
+/// 移动失败的截图直接删除，避免被当作新截图再次处理
+pub async fn keep_frames(frame_paths: &[String], label: &str) -> usize {
+    let Some(frames_dir) = frame_paths
+        .first()
+        .and_then(|path| Path::new(path).parent())
+    else {
+        return 0;
+    };
+    let dir = kept_dir(frames_dir, label);
+    if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+        warn!("创建截图保留目录失败 {:?}: {}，直接删除截图", dir, e);
+    }
+
+    let mut kept = 0;
+    for frame_path in frame_paths {
+        let source = Path::new(frame_path);
+        let Some(name) = source.file_name() else {
+            continue;
+        };
+        match tokio::fs::rename(source, dir.join(name)).await {
+            Ok(()) => kept += 1,
+            Err(e) => {
+                warn!("保留截图失败 {}: {}，直接删除", frame_path, e);
+                let _ = tokio::fs::remove_file(source).await;
+            }
+        }
+    }
+    info!("已保留 {} 张截图到 {:?}，分析成功后删除", kept, dir);
+    kept
+}
+
+/// 保留目录中的截图（按文件名即截图时间排序）
+pub async fn kept_frames(frames_dir: &Path, label: &str) -> Vec<String> {
+    let Ok(mut entries) = tokio::fs::read_dir(kept_dir(frames_dir, label)).await else {
+        return Vec::new();
+    };
+    let mut frames = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_jpg = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg"));
+        if is_jpg {
+            frames.push(path.to_string_lossy().to_string());
+        }
+    }
+    frames.sort();
+    frames
+}
+
+/// 会话分析成功后删除保留的截图，返回删除的数量
+pub async fn release(frames_dir: &Path, label: &str) -> usize {
+    let dir = kept_dir(frames_dir, label);
+    let count = kept_frames(frames_dir, label).await.len();
+    match tokio::fs::remove_dir_all(&dir).await {
+        Ok(()) => {
+            info!("会话分析成功，已删除 {} 张保留的截图", count);
+            count
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => {
+            warn!("删除保留的截图失败 {:?}: {}", dir, e);
+            0
+        }
+    }
+}
+
+/// 删除超过保留天数的保留目录，返回删除的目录数
+pub async fn purge_expired(frames_dir: &Path, max_days: u32) -> usize {
+    let Ok(mut entries) = tokio::fs::read_dir(frames_dir.join(KEPT_DIR)).await else {
+        return 0;
+    };
+    let mut purged = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let age_secs = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age.as_secs())
+            .unwrap_or_default();
+        if !metadata.is_dir() || !is_expired(age_secs, max_days) {
+            continue;
+        }
+        match tokio::fs::remove_dir_all(entry.path()).await {
+            Ok(()) => purged += 1,
+            Err(e) => warn!("删除过期的保留截图失败 {:?}: {}", entry.path(), e),
+        }
+    }
+    if purged > 0 {
+        info!(
+            "已删除 {} 个超过 {} 天未分析成功的截图保留目录",
+            purged, max_days
+        );
+    }
+    purged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keep_and_release_frames() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let frames_dir = temp_dir.path();
+        let mut frame_paths = Vec::new();
+        for timestamp in ["1700000002000", "1700000001000"] {
+            let path = frames_dir.join(format!("{}.jpg", timestamp));
+            tokio::fs::write(&path, b"jpg").await.unwrap();
+            frame_paths.push(path.to_string_lossy().to_string());
+        }
+
+        let label = "segment_20251009120000_20251009121500";
+        assert_eq!(keep_frames(&frame_paths, label).await, 2);
+        // 截图目录中不再有截图，调度器不会重复处理
+        assert!(!Path::new(&frame_paths[0]).exists());
+        let kept = kept_frames(frames_dir, label).await;
+        assert_eq!(kept.len(), 2);
+        assert!(kept[0].ends_with("1700000001000.jpg"));
+        assert_eq!(
+            label_for_video(Path::new(&format!("/videos/{}.mp4", label))),
+            Some(label)
+        );
+
+        // 未过期的目录不删除
+        assert_eq!(purge_expired(frames_dir, 3).await, 0);
+        assert!(is_expired(3 * 86_400, 3));
+        assert!(!is_expired(3 * 86_400 - 1, 3));
+
+        assert_eq!(release(frames_dir, label).await, 2);
+        assert!(kept_frames(frames_dir, label).await.is_empty());
+        assert_eq!(release(frames_dir, label).await, 0);
+    }
+}
@@ -0,0 +1,251 @@
+// 记录空档 - 一天中没有会话的时间段及其原因
+//
+// 空档来自会话之间的间隔（多设备的会话合并后计算）。原因按能获取到的信息推断：
+// 空档内发生过开机说明电脑关机或重启过；与截屏暂停记录重叠时使用暂停原因（锁屏、手动暂停）；
+// 空档内应用启动说明应用之前没有运行。开机和启动只能判断最近一次，更早的空档原因标记为未知
+
+use super::capture_pauses::{load_day_pauses, overlap_minutes, reason_label};
+use crate::storage::{CapturePauseRecord, Database, Session};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::Serialize;
+
+/// 会话之间超过该时长（分钟）的间隔视为空档
+pub const GAP_MINUTES: i64 = 15;
+
+/// 空档原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapKind {
+    /// 电脑关机或重启
+    MachineOff,
+    /// 截屏暂停（锁屏、手动暂停）
+    CapturePaused,
+    /// 应用未运行
+    AppNotRunning,
+    /// 无法判断（休眠、锁屏或较早的关机）
+    Unknown,
+}
+
+impl GapKind {
+    /// 显示给用户的说明
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MachineOff => "电脑关机或重启",
+            Self::CapturePaused => "截屏暂停",
+            Self::AppNotRunning => "应用未运行",
+            Self::Unknown => "未记录",
+        }
+    }
+}
+
+/// 没有会话的时间段
+#[derive(Debug, Clone, Serialize)]
+pub struct IdleGap {
+    /// 开始时间 (YYYY-MM-DD HH:MM:SS)
+    pub start: String,
+    /// 结束时间 (YYYY-MM-DD HH:MM:SS)
+    pub end: String,
+    pub minutes: i64,
+    pub kind: GapKind,
+    /// 截屏暂停原因（screen_locked / user），仅 capture_paused 时有值
+    pub pause_reason: Option<String>,
+    pub label: String,
+}
+
+/// 用于推断空档原因的运行时刻（按 local_now() 约定的本地时间值）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuntimeMarkers {
+    /// 最近一次开机时间
+    pub boot_time: Option<DateTime<Utc>>,
+    /// 本次应用启动时间
+    pub app_started: Option<DateTime<Utc>>,
+}
+
+fn local_from_epoch(secs: u64) -> Option<DateTime<Utc>> {
+    Local
+        .timestamp_opt(secs as i64, 0)
+        .single()
+        .map(|time| time.naive_local().and_utc())
+}
+
+impl RuntimeMarkers {
+    /// 读取当前系统的开机时间和应用进程的启动时间
+    pub fn current() -> Self {
+        use sysinfo::{Pid, ProcessesToUpdate, System};
+
+        let pid = Pid::from_u32(std::process::id());
+        let mut sys = System::new();
+        sys.refresh_processes(ProcessesToUpdate::Some(&[pid]));
+
+        Self {
+            boot_time: local_from_epoch(System::boot_time()),
+            app_started: sys
+                .process(pid)
+                .and_then(|process| local_from_epoch(process.start_time())),
+        }
+    }
+
+    /// 推断空档原因
+    pub fn classify(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> GapKind {
+        let within = |time: Option<DateTime<Utc>>| time.is_some_and(|t| t > start && t <= end);
+        if within(self.boot_time) {
+            GapKind::MachineOff
+        } else if within(self.app_started) {
+            GapKind::AppNotRunning
+        } else {
+            GapKind::Unknown
+        }
+    }
+}
+
+/// 找出会话之间不短于 min_minutes 的间隔
+pub fn idle_periods(sessions: &[Session], min_minutes: i64) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut ranges: Vec<(DateTime<Utc>, DateTime<Utc>)> = sessions
+        .iter()
+        .map(|session| (session.start_time, session.end_time))
+        .collect();
+    ranges.sort_by_key(|(start, _)| *start);
+
+    let mut periods = Vec::new();
+    let mut covered_until: Option<DateTime<Utc>> = None;
+    for (start, end) in ranges {
+        if let Some(until) = covered_until {
+            if (start - until).num_minutes() >= min_minutes {
+                periods.push((until, start));
+            }
+        }
+        covered_until = Some(covered_until.map_or(end, |until| until.max(end)));
+    }
+    periods
+}
+
+/// 找出会话之间的空档并标注原因
+///
+/// `pauses` 为当天的截屏暂停记录，未结束的暂停按 `now` 计算
+pub fn find_gaps(
+    sessions: &[Session],
+    markers: &RuntimeMarkers,
+    pauses: &[CapturePauseRecord],
+    now: DateTime<Utc>,
+) -> Vec<IdleGap> {
+    idle_periods(sessions, GAP_MINUTES)
+        .into_iter()
+        .map(|(start, end)| {
+            let mut kind = markers.classify(start, end);
+            let mut pause_reason = None;
+            if kind != GapKind::MachineOff {
+                // 与空档重叠最多的暂停记录
+                let pause = pauses
+                    .iter()
+                    .map(|pause| (overlap_minutes(pause, start, end, now), pause))
+                    .filter(|(minutes, _)| *minutes > 0)
+                    .max_by_key(|(minutes, _)| *minutes);
+                if let Some((_, pause)) = pause {
+                    kind = GapKind::CapturePaused;
+                    pause_reason = Some(pause.reason.clone());
+                }
+            }
+
+            let label = match &pause_reason {
+                Some(reason) => format!("{}（{}）", kind.label(), reason_label(reason)),
+                None => kind.label().to_string(),
+            };
+            IdleGap {
+                start: start.format("%Y-%m-%d %H:%M:%S").to_string(),
+                end: end.format("%Y-%m-%d %H:%M:%S").to_string(),
+                minutes: (end - start).num_minutes(),
+                kind,
+                pause_reason,
+                label,
+            }
+        })
+        .collect()
+}
+
+/// 获取某天的记录空档
+pub async fn get_day_gaps(db: &Database, date: &str) -> Result<Vec<IdleGap>, String> {
+    let sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let pauses = load_day_pauses(db, date).await?;
+    Ok(find_gaps(
+        &sessions,
+        &RuntimeMarkers::current(),
+        &pauses,
+        crate::storage::local_now(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn base() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn session(start_minute: i64, end_minute: i64) -> Session {
+        Session {
+            id: None,
+            start_time: base() + Duration::minutes(start_minute),
+            end_time: base() + Duration::minutes(end_minute),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        }
+    }
+
+    #[test]
+    fn test_gaps_merge_overlapping_sessions_and_classify() {
+        // 第二台设备的会话覆盖了 15-25 分钟，剩下 40-60 和 70-120 分钟两个空档；
+        // 第一个空档内有锁屏记录，第二个空档内开过机
+        let sessions = vec![
+            session(0, 15),
+            session(60, 70),
+            session(10, 25),
+            session(25, 40),
+            session(120, 130),
+        ];
+        let markers = RuntimeMarkers {
+            boot_time: Some(base() + Duration::minutes(110)),
+            app_started: Some(base() + Duration::minutes(112)),
+        };
+
+        let pauses = vec![CapturePauseRecord {
+            id: None,
+            start_time: base() + Duration::minutes(45),
+            end_time: Some(base() + Duration::minutes(58)),
+            reason: "screen_locked".to_string(),
+        }];
+        let now = base() + Duration::minutes(130);
+
+        let gaps = find_gaps(&sessions, &markers, &pauses, now);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].start, "2025-10-09 09:40:00");
+        assert_eq!(gaps[0].end, "2025-10-09 10:00:00");
+        assert_eq!(gaps[0].minutes, 20);
+        assert_eq!(gaps[0].kind, GapKind::CapturePaused);
+        assert_eq!(gaps[0].label, "截屏暂停（锁屏）");
+        assert_eq!(gaps[1].kind, GapKind::MachineOff);
+
+        let app_only = RuntimeMarkers {
+            boot_time: None,
+            ..markers
+        };
+        assert_eq!(
+            find_gaps(&sessions, &app_only, &[], now)[1].kind,
+            GapKind::AppNotRunning
+        );
+    }
+}
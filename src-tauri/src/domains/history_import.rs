@@ -0,0 +1,364 @@
+// 历史记录导入 - 把 RescueTime、Toggl 导出的 CSV 导入为外部活动事件
+//
+// 长期使用这些工具的用户导入后，日历统计和每日总结可以连续展示多年的数据：
+// - RescueTime：活动数据导出（按小时汇总，列 Date / Time Spent (seconds) / Activity / Category / Productivity），
+//   类别按 RescueTime 类别映射，无法识别时按效率评分归为 work / personal / other
+// - Toggl：详细报表导出（列 Description / Project / Start date / Start time / End date / End time / Tags），
+//   主动记录的时间归为 work
+// 导入的事件来源为 rescuetime / toggl，metadata 中标记 imported 并保留原始字段。
+// 写入时按来源、开始时间和标题去重，重复导入同一文件不会重复计时；无法解析的行跳过并在结果中说明
+
+use super::external_events::{source_label, to_record, ExternalEventInput};
+use super::performance::db_batch_size;
+use crate::storage::{Database, ExternalEventRecord};
+use serde::Serialize;
+use tracing::info;
+
+/// 结果中最多保留的错误示例数
+const MAX_ERROR_SAMPLES: usize = 10;
+
+/// 导入来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    RescueTime,
+    Toggl,
+}
+
+impl ImportSource {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "rescuetime" => Ok(Self::RescueTime),
+            "toggl" => Ok(Self::Toggl),
+            other => Err(format!("不支持的导入来源: {}", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RescueTime => "rescuetime",
+            Self::Toggl => "toggl",
+        }
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub source: String,
+    /// 数据行数（不含表头）
+    pub rows: usize,
+    /// 新增事件数（已导入过的不重复计入）
+    pub inserted: u64,
+    /// 无法解析而跳过的行数
+    pub skipped: usize,
+    /// 跳过原因示例
+    pub errors: Vec<String>,
+    /// 导入数据覆盖的日期范围
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
+/// 解析 CSV（支持双引号包裹、字段内换行和 "" 转义），忽略空行
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+/// 按表头名称（不区分大小写）查找列
+struct Columns(Vec<String>);
+
+impl Columns {
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        names
+            .iter()
+            .find_map(|name| self.0.iter().position(|header| header == name))
+    }
+
+    fn require(&self, names: &[&str]) -> Result<usize, String> {
+        self.find(names)
+            .ok_or_else(|| format!("缺少列: {}", names[0]))
+    }
+}
+
+fn cell(row: &[String], index: Option<usize>) -> &str {
+    index
+        .and_then(|index| row.get(index))
+        .map(|value| value.trim())
+        .unwrap_or_default()
+}
+
+/// RescueTime 类别映射为本应用的类别，无法识别时按效率评分（-2 到 2）判断
+fn rescuetime_category(category: &str, productivity: Option<i64>) -> &'static str {
+    let category = category.to_lowercase();
+    let mapped = [
+        ("software development", "coding"),
+        ("design", "design"),
+        ("communication", "communication"),
+        ("reference", "learning"),
+        ("learning", "learning"),
+        ("business", "work"),
+        ("entertainment", "entertainment"),
+        ("social networking", "social_media"),
+        ("shopping", "shopping"),
+        ("news", "personal"),
+    ]
+    .iter()
+    .find(|(pattern, _)| category.contains(pattern))
+    .map(|(_, mapped)| *mapped);
+
+    mapped.unwrap_or(match productivity {
+        Some(score) if score > 0 => "work",
+        Some(score) if score < 0 => "personal",
+        _ => "other",
+    })
+}
+
+/// 把 RescueTime 导出的数据行转换为事件
+fn rescuetime_events(
+    rows: &[Vec<String>],
+) -> Result<Vec<Result<ExternalEventInput, String>>, String> {
+    let (header, rows) = rows.split_first().ok_or("文件为空")?;
+    let columns = Columns(header.iter().map(|h| h.trim().to_lowercase()).collect());
+    let date = columns.require(&["date", "start time", "start"])?;
+    let activity = columns.require(&["activity", "name"])?;
+    let seconds = columns.find(&["time spent (seconds)", "duration (seconds)", "seconds"]);
+    let end = columns.find(&["end time", "end"]);
+    if seconds.is_none() && end.is_none() {
+        return Err("缺少列: Time Spent (seconds)".to_string());
+    }
+    let category = columns.find(&["category"]);
+    let productivity = columns.find(&["productivity"]);
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let duration_seconds = match seconds {
+                Some(index) => Some(
+                    cell(row, Some(index))
+                        .parse::<f64>()
+                        .map_err(|_| format!("时长无效: {}", cell(row, Some(index))))?
+                        .round() as i64,
+                ),
+                None => None,
+            };
+            let source_category = cell(row, category);
+            let score = cell(row, productivity).parse::<i64>().ok();
+            Ok(ExternalEventInput {
+                source: ImportSource::RescueTime.as_str().to_string(),
+                category: Some(rescuetime_category(source_category, score).to_string()),
+                title: cell(row, Some(activity)).to_string(),
+                start_time: cell(row, Some(date)).to_string(),
+                end_time: end
+                    .map(|index| cell(row, Some(index)).to_string())
+                    .filter(|_| duration_seconds.is_none()),
+                duration_seconds,
+                metadata: Some(serde_json::json!({
+                    "imported": true,
+                    "category": source_category,
+                    "productivity": score,
+                })),
+            })
+        })
+        .collect())
+}
+
+/// 把 Toggl 详细报表的数据行转换为事件
+fn toggl_events(rows: &[Vec<String>]) -> Result<Vec<Result<ExternalEventInput, String>>, String> {
+    let (header, rows) = rows.split_first().ok_or("文件为空")?;
+    let columns = Columns(header.iter().map(|h| h.trim().to_lowercase()).collect());
+    let start_date = columns.require(&["start date"])?;
+    let start_time = columns.require(&["start time"])?;
+    let end_date = columns.require(&["end date"])?;
+    let end_time = columns.require(&["end time"])?;
+    let description = columns.find(&["description"]);
+    let project = columns.find(&["project"]);
+    let client = columns.find(&["client"]);
+    let tags = columns.find(&["tags"]);
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let description = cell(row, description);
+            let project = cell(row, project);
+            let title = match (description.is_empty(), project.is_empty()) {
+                (false, false) => format!("{}（{}）", description, project),
+                (false, true) => description.to_string(),
+                (true, false) => project.to_string(),
+                (true, true) => "Toggl 计时".to_string(),
+            };
+            Ok(ExternalEventInput {
+                source: ImportSource::Toggl.as_str().to_string(),
+                category: Some("work".to_string()),
+                title,
+                start_time: format!(
+                    "{} {}",
+                    cell(row, Some(start_date)),
+                    cell(row, Some(start_time))
+                ),
+                end_time: Some(format!(
+                    "{} {}",
+                    cell(row, Some(end_date)),
+                    cell(row, Some(end_time))
+                )),
+                duration_seconds: None,
+                metadata: Some(serde_json::json!({
+                    "imported": true,
+                    "project": project,
+                    "client": cell(row, client),
+                    "tags": cell(row, tags),
+                })),
+            })
+        })
+        .collect())
+}
+
+/// 解析导出文件，返回可导入的事件、数据行数和跳过原因
+pub fn parse_export(
+    source: ImportSource,
+    text: &str,
+) -> Result<(Vec<ExternalEventRecord>, usize, Vec<String>), String> {
+    let rows = parse_csv(text);
+    let events = match source {
+        ImportSource::RescueTime => rescuetime_events(&rows)?,
+        ImportSource::Toggl => toggl_events(&rows)?,
+    };
+
+    let total = events.len();
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (index, event) in events.into_iter().enumerate() {
+        // 表头占第 1 行
+        match event.and_then(|event| to_record(&event)) {
+            Ok(record) => records.push(record),
+            Err(e) => errors.push(format!("第 {} 行: {}", index + 2, e)),
+        }
+    }
+    Ok((records, total, errors))
+}
+
+/// 导入导出文件
+pub async fn import_file(
+    db: &Database,
+    source: ImportSource,
+    path: &str,
+) -> Result<ImportReport, String> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("读取文件 {} 失败: {}", path, e))?;
+    let (records, rows, errors) = parse_export(source, &text)?;
+    if records.is_empty() && rows > 0 {
+        return Err(format!(
+            "没有可导入的记录: {}",
+            errors.first().cloned().unwrap_or_default()
+        ));
+    }
+
+    let mut inserted = 0;
+    for batch in records.chunks(db_batch_size()) {
+        inserted += db
+            .insert_external_events(batch)
+            .await
+            .map_err(|e| format!("保存导入记录失败: {}", e))?;
+    }
+
+    let first_date = records.iter().map(|r| r.start_time).min();
+    let last_date = records.iter().map(|r| r.end_time).max();
+    info!(
+        "导入 {} 历史记录: {} 行，新增 {} 条，跳过 {} 行",
+        source_label(source.as_str()),
+        rows,
+        inserted,
+        errors.len()
+    );
+
+    Ok(ImportReport {
+        source: source.as_str().to_string(),
+        rows,
+        inserted,
+        skipped: errors.len(),
+        errors: errors.into_iter().take(MAX_ERROR_SAMPLES).collect(),
+        first_date: first_date.map(|time| time.format("%Y-%m-%d").to_string()),
+        last_date: last_date.map(|time| time.format("%Y-%m-%d").to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rescuetime_and_toggl_exports() {
+        let rescuetime =
+            "\u{feff}Date,Time Spent (seconds),Number of People,Activity,Category,Productivity\r\n\
+2019-03-01T10:00:00,1200,1,Visual Studio Code,Software Development,2\r\n\
+2019-03-01T10:00:00,300,1,\"youtube.com, music\",Video,-2\r\n\
+2019-03-01T11:00:00,abc,1,slack,Communication & Scheduling,1\r\n";
+        let (records, rows, errors) = parse_export(ImportSource::RescueTime, rescuetime).unwrap();
+        assert_eq!(rows, 3);
+        assert_eq!(records.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("第 4 行"));
+        assert_eq!(records[0].source, "rescuetime");
+        assert_eq!(records[0].category, "coding");
+        assert_eq!(
+            (records[0].end_time - records[0].start_time).num_minutes(),
+            20
+        );
+        assert_eq!(records[1].title, "youtube.com, music");
+        assert_eq!(records[1].category, "personal");
+        assert!(records[1]
+            .metadata
+            .as_deref()
+            .unwrap()
+            .contains("\"imported\":true"));
+
+        let toggl = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\n\
+me,me@example.com,ACME,Website,,\"Fix \"\"login\"\" bug\",Yes,2023-01-05,23:30:00,2023-01-06,00:15:00,00:45:00,dev\n";
+        let (records, rows, errors) = parse_export(ImportSource::Toggl, toggl).unwrap();
+        assert_eq!((rows, errors.len()), (1, 0));
+        assert_eq!(records[0].title, "Fix \"login\" bug（Website）");
+        assert_eq!(records[0].category, "work");
+        assert_eq!(
+            (records[0].end_time - records[0].start_time).num_minutes(),
+            45
+        );
+
+        assert!(parse_export(ImportSource::Toggl, rescuetime).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+// 自动化钩子 - 事件发生时执行用户配置的 shell 命令或 Rhai 脚本
+//
+// 订阅事件总线中的会话结束、分析完成、每日总结生成和存储清理事件，
+// 事件内容以 JSON 传给钩子：
+// - shell 命令从标准输入读取 JSON，也可以读取环境变量 SCREEN_ANALYZER_EVENT（事件名）
+//   和 SCREEN_ANALYZER_PAYLOAD（JSON）
+// - Rhai 脚本通过变量 event（对象）和 payload（JSON 字符串）读取，
+//   可调用 run("命令") 执行 shell 命令（返回退出码），print() 的输出写入日志
+// 钩子在后台执行，失败只记录日志，不影响截屏和分析
+
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::models::{AutomationHook, HookEvent, HookKind};
+use crate::settings::SettingsManager;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// 事件名环境变量
+pub const HOOK_EVENT_ENV: &str = "SCREEN_ANALYZER_EVENT";
+
+/// 事件内容环境变量
+pub const HOOK_PAYLOAD_ENV: &str = "SCREEN_ANALYZER_PAYLOAD";
+
+/// Rhai 脚本的最大运算次数，防止死循环
+const RHAI_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// 启动钩子执行任务
+pub fn start_hook_runner(event_bus: Arc<EventBus>, settings: Arc<SettingsManager>) {
+    // 钩子只关心会话、分析和存储清理事件，不订阅视频和配置事件
+    let mut receiver = event_bus.subscribe_topics(
+        "自动化钩子任务",
+        &[
+            EventTopic::Capture,
+            EventTopic::Analysis,
+            EventTopic::Storage,
+        ],
+    );
+
+    tokio::spawn(async move {
+        info!("自动化钩子任务已启动");
+        loop {
+            let Some(event) = receiver.recv().await else {
+                break;
+            };
+            let Some((hook_event, payload)) = event_payload(&event) else {
+                continue;
+            };
+
+            let hooks = settings.get().await.automation_hooks.unwrap_or_default();
+            for hook in hooks
+                .into_iter()
+                .filter(|hook| hook.enabled && hook.event == hook_event)
+            {
+                let payload = payload.clone();
+                tokio::spawn(async move {
+                    match run_hook(&hook, &payload).await {
+                        Ok(_) => info!("自动化钩子 {} 执行完成", hook_label(&hook)),
+                        Err(e) => warn!("自动化钩子 {} 执行失败: {}", hook_label(&hook), e),
+                    }
+                });
+            }
+        }
+        warn!("自动化钩子任务已停止");
+    });
+}
+
+/// 把事件转换为钩子事件和 JSON 内容（钩子不关心的事件返回 None）
+pub fn event_payload(event: &AppEvent) -> Option<(HookEvent, Value)> {
+    let (hook_event, mut payload) = match event {
+        AppEvent::SessionCompleted {
+            session_id,
+            frame_count,
+            window_start,
+            window_end,
+        } => (
+            HookEvent::SessionCompleted,
+            // 会话结束时还没有写入数据库，这里的 ID 是时间窗口的起始毫秒时间戳，不是会话 ID
+            json!({
+                "window_id": session_id,
+                "frame_count": frame_count,
+                "start_time": format_local(window_start),
+                "end_time": format_local(window_end),
+            }),
+        ),
+        AppEvent::AnalysisCompleted {
+            session_id,
+            summary,
+        } => (
+            HookEvent::AnalysisCompleted,
+            json!({
+                "session_id": session_id,
+                "summary": summary,
+            }),
+        ),
+        AppEvent::DaySummaryReady { date, summary_text } => (
+            HookEvent::DaySummaryReady,
+            json!({
+                "date": date,
+                "summary_text": summary_text,
+            }),
+        ),
+        AppEvent::StorageCleaned {
+            sessions_deleted,
+            space_freed,
+        } => (
+            HookEvent::StorageCleaned,
+            json!({
+                "sessions_deleted": sessions_deleted,
+                "space_freed": space_freed,
+            }),
+        ),
+        _ => return None,
+    };
+
+    payload["event"] = serde_json::to_value(hook_event).ok()?;
+    Some((hook_event, payload))
+}
+
+/// 用于测试钩子的示例事件内容
+pub fn sample_payload(hook_event: HookEvent) -> Value {
+    let now = crate::storage::local_now();
+    let event = match hook_event {
+        HookEvent::SessionCompleted => AppEvent::SessionCompleted {
+            session_id: 0,
+            frame_count: 900,
+            window_start: now - chrono::Duration::minutes(15),
+            window_end: now,
+        },
+        HookEvent::AnalysisCompleted => AppEvent::AnalysisCompleted {
+            session_id: 0,
+            summary: crate::llm::SessionSummary {
+                title: "示例会话".to_string(),
+                ..Default::default()
+            },
+        },
+        HookEvent::DaySummaryReady => AppEvent::DaySummaryReady {
+            date: now.format("%Y-%m-%d").to_string(),
+            summary_text: "示例每日总结".to_string(),
+        },
+        HookEvent::StorageCleaned => AppEvent::StorageCleaned {
+            sessions_deleted: 0,
+            space_freed: 0,
+        },
+    };
+    event_payload(&event)
+        .map(|(_, payload)| payload)
+        .unwrap_or_default()
+}
+
+/// 执行单个钩子，返回标准输出（Rhai 脚本为 print 输出和返回值）
+pub async fn run_hook(hook: &AutomationHook, payload: &Value) -> Result<String, String> {
+    if hook.command.trim().is_empty() {
+        return Err("命令或脚本为空".to_string());
+    }
+
+    let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+    match hook.kind {
+        HookKind::Shell => run_shell(&hook.command, payload, timeout).await,
+        HookKind::Rhai => {
+            let script = hook.command.clone();
+            let payload = payload.clone();
+            let task = tokio::task::spawn_blocking(move || run_rhai(&script, &payload));
+            match tokio::time::timeout(timeout, task).await {
+                Ok(result) => result.map_err(|e| e.to_string())?,
+                Err(_) => Err(format!("执行超时（>{} 秒）", timeout.as_secs())),
+            }
+        }
+    }
+}
+
+async fn run_shell(command: &str, payload: &Value, timeout: Duration) -> Result<String, String> {
+    let payload_json = payload.to_string();
+    let mut child = shell_command(command, payload)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("启动命令失败: {}", e))?;
+
+    // 命令不读取标准输入时写入会失败，忽略即可
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(payload_json.as_bytes()).await;
+    }
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(result) => result.map_err(|e| e.to_string())?,
+        Err(_) => return Err(format!("执行超时（>{} 秒），已终止", timeout.as_secs())),
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "退出码 {}: {}",
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 构建带事件环境变量的 shell 命令
+fn shell_command(command: &str, payload: &Value) -> Command {
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        // Windows 下隐藏控制台窗口
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+
+    cmd.env(
+        HOOK_EVENT_ENV,
+        payload["event"].as_str().unwrap_or_default(),
+    )
+    .env(HOOK_PAYLOAD_ENV, payload.to_string());
+    cmd
+}
+
+fn run_rhai(script: &str, payload: &Value) -> Result<String, String> {
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(RHAI_MAX_OPERATIONS);
+
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let printed = output.clone();
+    engine.on_print(move |text| {
+        info!("[钩子脚本] {}", text);
+        if let Ok(mut lines) = printed.lock() {
+            lines.push(text.to_string());
+        }
+    });
+
+    let run_payload = payload.clone();
+    engine.register_fn("run", move |command: &str| -> i64 {
+        let mut cmd = shell_command(command, &run_payload);
+        let handle = tokio::runtime::Handle::current();
+        match handle.block_on(async { cmd.stdin(Stdio::null()).status().await }) {
+            Ok(status) => status.code().unwrap_or(-1) as i64,
+            Err(e) => {
+                warn!("钩子脚本执行命令失败: {}", e);
+                -1
+            }
+        }
+    });
+
+    let mut scope = rhai::Scope::new();
+    scope.push_dynamic(
+        "event",
+        rhai::serde::to_dynamic(payload).map_err(|e| e.to_string())?,
+    );
+    scope.push("payload", payload.to_string());
+
+    let result = engine
+        .eval_with_scope::<rhai::Dynamic>(&mut scope, script)
+        .map_err(|e| e.to_string())?;
+
+    let mut lines = output.lock().map(|lines| lines.clone()).unwrap_or_default();
+    if !result.is_unit() {
+        lines.push(result.to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+fn hook_label(hook: &AutomationHook) -> String {
+    if hook.name.trim().is_empty() {
+        format!("{:?}", hook.event)
+    } else {
+        hook.name.clone()
+    }
+}
+
+fn format_local(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_cleaned_payload_reaches_rhai_script() {
+        let (hook_event, payload) = event_payload(&AppEvent::StorageCleaned {
+            sessions_deleted: 3,
+            space_freed: 2048,
+        })
+        .unwrap();
+        assert_eq!(hook_event, HookEvent::StorageCleaned);
+        assert_eq!(payload["event"], "storage_cleaned");
+
+        let output = run_rhai(
+            r#"print(event.event); event.sessions_deleted * 2"#,
+            &payload,
+        )
+        .unwrap();
+        assert_eq!(output, "storage_cleaned\n6");
+
+        assert!(event_payload(&AppEvent::AnalysisStarted { session_id: 1 }).is_none());
+    }
+}
@@ -0,0 +1,350 @@
+// 洞察领域 - 基于统计基线的异常检测
+//
+// 以过去 4 周同一星期几的平均值作为基线，检测以下异常：
+// - 社交媒体使用时长明显高于基线
+// - 深夜（23:00-05:00）仍在工作
+// - 连续 5 小时以上没有休息
+// 检测结果写入每日总结的 insights 部分，并由后台任务以系统通知的形式提醒。
+// 离开模式的日期不作为基线
+
+use super::metrics::{load_activity_spans, ActivitySpan};
+use crate::storage::Database;
+use chrono::{Duration, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 基线使用的历史周数
+pub const BASELINE_WEEKS: i64 = 4;
+/// 连续无休息的告警阈值（分钟）
+pub const NO_BREAK_ALERT_MINUTES: i64 = 300;
+/// 两段活动间隔达到该分钟数视为休息
+const BREAK_GAP_MINUTES: i64 = 10;
+/// 深夜工作告警阈值（分钟）
+const LATE_NIGHT_ALERT_MINUTES: i64 = 30;
+/// 社交媒体使用的最低告警时长（分钟），避免基线很低时频繁告警
+const SOCIAL_MIN_ALERT_MINUTES: i64 = 60;
+
+/// 社交媒体关键词（匹配子类别或主要应用）
+const SOCIAL_KEYWORDS: &[&str] = &[
+    "social",
+    "社交",
+    "weibo",
+    "微博",
+    "douyin",
+    "抖音",
+    "tiktok",
+    "xiaohongshu",
+    "小红书",
+    "bilibili",
+    "twitter",
+    "x.com",
+    "facebook",
+    "instagram",
+    "reddit",
+    "youtube",
+    "zhihu",
+    "知乎",
+];
+
+/// 洞察（异常提示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Insight {
+    /// 类型标识 (social_media / late_night_work / no_break)
+    pub kind: String,
+    /// 严重程度 (info / warning)
+    pub severity: String,
+    /// 标题
+    pub title: String,
+    /// 描述
+    pub message: String,
+}
+
+/// 单日行为指标
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DayMetrics {
+    /// 社交媒体时长（分钟）
+    pub social_minutes: i64,
+    /// 深夜工作时长（分钟）
+    pub late_night_work_minutes: i64,
+    /// 最长连续无休息时长（分钟）
+    pub longest_no_break_minutes: i64,
+    /// 总活动时长（分钟）
+    pub active_minutes: i64,
+}
+
+/// 是否为社交媒体活动
+fn is_social(span: &ActivitySpan) -> bool {
+    span.category == "personal"
+        && SOCIAL_KEYWORDS
+            .iter()
+            .any(|k| span.subcategory.contains(k) || span.app.contains(k))
+}
+
+/// 是否为休息类活动（空闲或其他）
+fn is_rest(span: &ActivitySpan) -> bool {
+    span.category == "idle" || span.category == "other"
+}
+
+/// 是否处于深夜时段
+fn is_late_night(hour: u32) -> bool {
+    hour >= 23 || hour < 5
+}
+
+/// 计算单日指标
+pub fn compute_day_metrics(spans: &[ActivitySpan]) -> DayMetrics {
+    let mut metrics = DayMetrics::default();
+    let mut sorted: Vec<&ActivitySpan> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+
+    let mut streak_start = None;
+    let mut streak_end = None;
+
+    for span in sorted {
+        let minutes = span.duration_minutes();
+        metrics.active_minutes += minutes;
+
+        if is_social(span) {
+            metrics.social_minutes += minutes;
+        }
+        if span.category == "work" && is_late_night(span.start.hour()) {
+            metrics.late_night_work_minutes += minutes;
+        }
+
+        // 连续无休息：遇到休息类活动或较长间隔时重新计时
+        if is_rest(span) && minutes >= 5 {
+            streak_start = None;
+            streak_end = None;
+            continue;
+        }
+        match (streak_start, streak_end) {
+            (Some(_), Some(end)) if span.start - end < Duration::minutes(BREAK_GAP_MINUTES) => {
+                streak_end = Some(span.end.max(end));
+            }
+            _ => {
+                streak_start = Some(span.start);
+                streak_end = Some(span.end);
+            }
+        }
+        if let (Some(start), Some(end)) = (streak_start, streak_end) {
+            metrics.longest_no_break_minutes = metrics
+                .longest_no_break_minutes
+                .max((end - start).num_minutes());
+        }
+    }
+
+    metrics
+}
+
+/// 根据当日指标和历史基线检测异常
+///
+/// `baseline` 为过去几周同一星期几的指标（没有数据的日期不包含在内）
+pub fn detect_anomalies(today: &DayMetrics, baseline: &[DayMetrics]) -> Vec<Insight> {
+    let mut insights = Vec::new();
+
+    let avg = |f: fn(&DayMetrics) -> i64| -> Option<f64> {
+        if baseline.is_empty() {
+            None
+        } else {
+            Some(baseline.iter().map(f).sum::<i64>() as f64 / baseline.len() as f64)
+        }
+    };
+
+    // 1. 社交媒体时长：高于基线 50% 且多出 30 分钟以上
+    let social_avg = avg(|m| m.social_minutes);
+    let social_alert = match social_avg {
+        Some(avg) => {
+            today.social_minutes >= SOCIAL_MIN_ALERT_MINUTES
+                && today.social_minutes as f64 > (avg * 1.5).max(avg + 30.0)
+        }
+        // 没有基线时仅在时长特别长时提醒
+        None => today.social_minutes >= SOCIAL_MIN_ALERT_MINUTES * 2,
+    };
+    if social_alert {
+        let message = match social_avg {
+            Some(avg) => format!(
+                "今天社交媒体使用 {} 分钟，过去 {} 周同期平均 {:.0} 分钟",
+                today.social_minutes, BASELINE_WEEKS, avg
+            ),
+            None => format!("今天社交媒体使用 {} 分钟", today.social_minutes),
+        };
+        insights.push(Insight {
+            kind: "social_media".to_string(),
+            severity: "warning".to_string(),
+            title: "社交媒体使用时间偏长".to_string(),
+            message,
+        });
+    }
+
+    // 2. 深夜工作：超过阈值且明显高于基线
+    let late_avg = avg(|m| m.late_night_work_minutes).unwrap_or(0.0);
+    if today.late_night_work_minutes >= LATE_NIGHT_ALERT_MINUTES
+        && today.late_night_work_minutes as f64 > late_avg * 2.0
+    {
+        insights.push(Insight {
+            kind: "late_night_work".to_string(),
+            severity: "warning".to_string(),
+            title: "深夜仍在工作".to_string(),
+            message: format!(
+                "23:00 之后工作了 {} 分钟，注意休息",
+                today.late_night_work_minutes
+            ),
+        });
+    }
+
+    // 3. 连续无休息
+    if today.longest_no_break_minutes >= NO_BREAK_ALERT_MINUTES {
+        insights.push(Insight {
+            kind: "no_break".to_string(),
+            severity: "warning".to_string(),
+            title: "长时间没有休息".to_string(),
+            message: format!(
+                "已连续活动 {} 小时 {} 分钟没有休息",
+                today.longest_no_break_minutes / 60,
+                today.longest_no_break_minutes % 60
+            ),
+        });
+    }
+
+    insights
+}
+
+/// 生成指定日期的洞察
+pub async fn generate_insights(db: &Database, date: NaiveDate) -> Result<Vec<Insight>, String> {
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let spans = load_activity_spans(db, &date_str).await?;
+    if spans.is_empty() {
+        return Ok(Vec::new());
+    }
+    let today = compute_day_metrics(&spans);
+
+    let away_days = super::away::load_away_days(
+        db,
+        date - Duration::weeks(BASELINE_WEEKS),
+        date - Duration::weeks(1),
+    )
+    .await?;
+    let mut baseline = Vec::new();
+    for week in 1..=BASELINE_WEEKS {
+        let past_date = date - Duration::weeks(week);
+        if away_days.contains(&past_date) {
+            continue;
+        }
+        let past = past_date.format("%Y-%m-%d").to_string();
+        let past_spans = load_activity_spans(db, &past).await?;
+        if !past_spans.is_empty() {
+            baseline.push(compute_day_metrics(&past_spans));
+        }
+    }
+
+    Ok(detect_anomalies(&today, &baseline))
+}
+
+/// 启动异常监控任务：每 30 分钟检测当天数据，对新出现的异常调用 `notify`
+///
+/// 同一天同一类型的异常只提醒一次
+pub fn start_insight_monitor<F>(db: Arc<Database>, notify: F)
+where
+    F: Fn(&Insight) + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut notified: HashSet<(NaiveDate, String)> = HashSet::new();
+        info!("异常监控任务已启动，每 30 分钟检测一次");
+
+        loop {
+            let today = crate::storage::local_now().date_naive();
+            notified.retain(|(date, _)| *date == today);
+
+            match generate_insights(&db, today).await {
+                Ok(insights) => {
+                    for insight in insights {
+                        if notified.insert((today, insight.kind.clone())) {
+                            notify(&insight);
+                        }
+                    }
+                }
+                Err(e) => warn!("异常检测失败: {}", e),
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(30 * 60)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn span(start: &str, end: &str, category: &str, subcategory: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(start).unwrap(),
+            end: DateTime::parse_from_rfc3339(end).unwrap(),
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+            app: String::new(),
+            device: "mac".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_no_break_streak_resets_on_rest() {
+        let spans = vec![
+            span(
+                "2025-10-09T08:00:00+08:00",
+                "2025-10-09T11:00:00+08:00",
+                "work",
+                "coding",
+            ),
+            span(
+                "2025-10-09T11:05:00+08:00",
+                "2025-10-09T13:30:00+08:00",
+                "work",
+                "coding",
+            ),
+            span(
+                "2025-10-09T13:30:00+08:00",
+                "2025-10-09T13:45:00+08:00",
+                "idle",
+                "",
+            ),
+            span(
+                "2025-10-09T13:45:00+08:00",
+                "2025-10-09T15:00:00+08:00",
+                "work",
+                "coding",
+            ),
+        ];
+
+        let metrics = compute_day_metrics(&spans);
+        assert_eq!(metrics.longest_no_break_minutes, 330);
+
+        let insights = detect_anomalies(&metrics, &[]);
+        assert!(insights.iter().any(|i| i.kind == "no_break"));
+    }
+
+    #[test]
+    fn test_social_media_against_baseline() {
+        let today = DayMetrics {
+            social_minutes: 150,
+            ..Default::default()
+        };
+        let normal = DayMetrics {
+            social_minutes: 40,
+            ..Default::default()
+        };
+        let heavy = DayMetrics {
+            social_minutes: 140,
+            ..Default::default()
+        };
+
+        assert!(detect_anomalies(&today, &[normal.clone(), normal.clone()])
+            .iter()
+            .any(|i| i.kind == "social_media"));
+        assert!(detect_anomalies(&today, &[heavy.clone(), heavy])
+            .iter()
+            .all(|i| i.kind != "social_media"));
+    }
+}
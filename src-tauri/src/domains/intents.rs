@@ -0,0 +1,355 @@
+// 时段意图 - 用户在一段工作开始时声明意图（如“写设计文档”），分析完成后对比实际活动
+//
+// 开始新的意图时结束上一个仍在进行的意图；未手动结束的意图最多按 MAX_BLOCK_HOURS 计算。
+// 吻合度 = 时段内与意图吻合的卡片时长 / 时段内卡片总时长（只计本机设备的卡片，卡片裁剪到时段内）。
+// 卡片的标题、摘要、子类别和应用/网站中出现意图中的任一关键词即视为吻合：
+// 英文按单词（去掉常见虚词和过短的词），中文按相邻两字切分。
+// 会话分析完成后重新评估与会话有重叠的意图，结果保存在 block_intents 表中
+
+use super::capture_pauses::day_bounds;
+use super::metrics::ActivitySpan;
+use super::range_summary::{clip_span, load_range_cards};
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::storage::{BlockIntentRecord, Database, TimelineCardRecord};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 未手动结束的意图最多计算的时长（小时）
+const MAX_BLOCK_HOURS: i64 = 4;
+/// 意图的最大长度（字符）
+const MAX_INTENT_CHARS: usize = 200;
+/// 不作为关键词的英文虚词
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "into", "from", "about", "some", "that", "this", "then", "work",
+    "working", "on", "of", "to", "in", "at", "my", "do",
+];
+
+/// 某天的意图及吻合度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayIntentReport {
+    pub date: String,
+    pub blocks: Vec<BlockIntentRecord>,
+    pub aligned_minutes: i64,
+    pub active_minutes: i64,
+    /// 全天吻合度（0-1），没有已评估的活动时为空
+    pub alignment: Option<f64>,
+}
+
+fn is_cjk(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c) || ('\u{3400}'..='\u{4dbf}').contains(&c)
+}
+
+/// 从意图中提取关键词（小写）
+pub fn intent_keywords(intent: &str) -> Vec<String> {
+    let lower = intent.to_lowercase();
+    let mut keywords = BTreeSet::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+    let mut flush = |word: &mut String, cjk: &mut Vec<char>| {
+        if word.chars().count() >= 3 && !STOPWORDS.contains(&word.as_str()) {
+            keywords.insert(word.clone());
+        }
+        word.clear();
+        for pair in cjk.windows(2) {
+            keywords.insert(pair.iter().collect::<String>());
+        }
+        cjk.clear();
+    };
+    for c in lower.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                flush(&mut word, &mut cjk);
+            }
+            cjk.push(c);
+        } else if c.is_alphanumeric() {
+            if !cjk.is_empty() {
+                flush(&mut word, &mut cjk);
+            }
+            word.push(c);
+        } else {
+            flush(&mut word, &mut cjk);
+        }
+    }
+    flush(&mut word, &mut cjk);
+    keywords.into_iter().collect()
+}
+
+/// 卡片是否与意图吻合
+pub fn card_matches(card: &TimelineCardRecord, keywords: &[String]) -> bool {
+    let text = [
+        card.title.as_str(),
+        card.summary.as_str(),
+        card.subcategory.as_str(),
+        card.app_sites.as_str(),
+    ]
+    .join(" ")
+    .to_lowercase();
+    keywords
+        .iter()
+        .any(|keyword| text.contains(keyword.as_str()))
+}
+
+/// 统计时段内吻合的时长和总时长（卡片已裁剪到时段内）
+pub fn block_alignment(
+    cards: &[(ActivitySpan, &TimelineCardRecord)],
+    keywords: &[String],
+) -> (i64, i64) {
+    cards
+        .iter()
+        .fold((0, 0), |(aligned, active), (span, card)| {
+            let minutes = span.duration_minutes();
+            if card_matches(card, keywords) {
+                (aligned + minutes, active + minutes)
+            } else {
+                (aligned, active + minutes)
+            }
+        })
+}
+
+/// 吻合度（0-1），没有活动时为空
+fn ratio(aligned: i64, active: i64) -> Option<f64> {
+    (active > 0).then(|| (aligned as f64 / active as f64 * 1000.0).round() / 1000.0)
+}
+
+/// 本地时间值（local_now 约定）转为带时区的时间
+fn to_fixed(time: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+    Local
+        .from_local_datetime(&time.naive_utc())
+        .earliest()
+        .map(|time| time.fixed_offset())
+}
+
+/// 意图的计算结束时间
+fn effective_end(record: &BlockIntentRecord, now: DateTime<Utc>) -> DateTime<Utc> {
+    let cap = record.start_time + Duration::hours(MAX_BLOCK_HOURS);
+    record.end_time.unwrap_or(now).min(cap)
+}
+
+/// 评估一个意图并保存结果
+async fn evaluate_intent(
+    db: &Database,
+    record: &BlockIntentRecord,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let Some(id) = record.id else {
+        return Ok(());
+    };
+    let (Some(start), Some(end)) = (
+        to_fixed(record.start_time),
+        to_fixed(effective_end(record, now)),
+    ) else {
+        return Ok(());
+    };
+    if end <= start {
+        return Ok(());
+    }
+
+    let (_, cards) = load_range_cards(db, start, end).await?;
+    let device = record.device_name.clone().unwrap_or_default();
+    let clipped: Vec<(ActivitySpan, &TimelineCardRecord)> = cards
+        .iter()
+        .filter(|(span, _)| device.is_empty() || span.device.is_empty() || span.device == device)
+        .filter_map(|(span, card)| clip_span(span, start, end).map(|span| (span, card)))
+        .collect();
+    let (aligned, active) = block_alignment(&clipped, &intent_keywords(&record.intent));
+
+    db.update_block_intent_alignment(id, ratio(aligned, active), aligned, active)
+        .await
+        .map_err(|e| format!("保存意图吻合度失败: {}", e))
+}
+
+/// 重新评估与时间范围有重叠的意图
+pub async fn evaluate_between(
+    db: &Database,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<usize, String> {
+    let now = crate::storage::local_now();
+    let intents = db
+        .get_block_intents_between(start, end)
+        .await
+        .map_err(|e| format!("获取时段意图失败: {}", e))?;
+    let mut evaluated = 0;
+    for record in intents
+        .iter()
+        .filter(|record| effective_end(record, now) > start)
+    {
+        evaluate_intent(db, record, now).await?;
+        evaluated += 1;
+    }
+    Ok(evaluated)
+}
+
+/// 开始一个新的意图（结束上一个仍在进行的意图）
+pub async fn start_intent(db: &Database, intent: &str) -> Result<BlockIntentRecord, String> {
+    let intent = intent.trim();
+    if intent.is_empty() {
+        return Err("意图不能为空".to_string());
+    }
+    if intent.chars().count() > MAX_INTENT_CHARS {
+        return Err(format!("意图不能超过 {} 个字符", MAX_INTENT_CHARS));
+    }
+    end_intent(db).await?;
+
+    let mut record = BlockIntentRecord {
+        id: None,
+        intent: intent.to_string(),
+        start_time: crate::storage::local_now(),
+        end_time: None,
+        device_name: Some(crate::storage::get_device_info().0),
+        alignment: None,
+        aligned_minutes: None,
+        active_minutes: None,
+    };
+    record.id = Some(
+        db.insert_block_intent(&record)
+            .await
+            .map_err(|e| format!("保存时段意图失败: {}", e))?,
+    );
+    info!("开始时段意图: {}", record.intent);
+    Ok(record)
+}
+
+/// 结束仍在进行的意图并评估，返回结束的数量
+pub async fn end_intent(db: &Database) -> Result<u64, String> {
+    let now = crate::storage::local_now();
+    let ended = db
+        .end_open_block_intents(now)
+        .await
+        .map_err(|e| format!("结束时段意图失败: {}", e))?;
+    if ended > 0 {
+        evaluate_between(db, now - Duration::minutes(1), now).await?;
+    }
+    Ok(ended)
+}
+
+/// 某天的意图及吻合度
+pub async fn day_intents(db: &Database, date: &str) -> Result<DayIntentReport, String> {
+    let day =
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))?;
+    let (start, end) = day_bounds(day);
+    let blocks = db
+        .get_block_intents_between(start, end)
+        .await
+        .map_err(|e| format!("获取时段意图失败: {}", e))?;
+    let aligned_minutes = blocks.iter().filter_map(|b| b.aligned_minutes).sum();
+    let active_minutes = blocks.iter().filter_map(|b| b.active_minutes).sum();
+    Ok(DayIntentReport {
+        date: date.to_string(),
+        blocks,
+        aligned_minutes,
+        active_minutes,
+        alignment: ratio(aligned_minutes, active_minutes),
+    })
+}
+
+/// 启动意图评估任务：会话分析完成后重新评估与会话有重叠的意图
+pub fn start_intent_worker(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>) {
+    let mut receiver = event_bus.subscribe_topics("意图评估任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let Ok(db) = storage.get_db().await else {
+                continue;
+            };
+            let session = match db.get_session(session_id).await {
+                Ok(session) => session,
+                Err(e) => {
+                    warn!("获取会话 {} 失败: {}", session_id, e);
+                    continue;
+                }
+            };
+            if let Err(e) = evaluate_between(&db, session.start_time, session.end_time).await {
+                warn!("会话 {} 的意图评估失败: {}", session_id, e);
+            }
+        }
+        warn!("意图评估任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(title: &str, subcategory: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: String::new(),
+            end_time: String::new(),
+            category: "work".to_string(),
+            subcategory: subcategory.to_string(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: r#"{"primary":"Notion"}"#.to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    fn span(start: &str, end: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(start).unwrap(),
+            end: DateTime::parse_from_rfc3339(end).unwrap(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            app: String::new(),
+            device: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_measures_alignment_with_declared_intent() {
+        assert_eq!(
+            intent_keywords("Write the design doc"),
+            ["design", "doc", "write"]
+        );
+        assert_eq!(
+            intent_keywords("写设计文档"),
+            ["写设", "文档", "计文", "设计"]
+        );
+        assert_eq!(intent_keywords("review PR 评审"), ["review", "评审"]);
+
+        let design = card("编写同步模块的设计文档", "文档");
+        let slack = card("回复 Slack 消息", "沟通");
+        let english = card("Design doc review", "docs");
+        let cards = vec![
+            (
+                span("2025-10-09T09:00:00+08:00", "2025-10-09T09:45:00+08:00"),
+                &design,
+            ),
+            (
+                span("2025-10-09T09:45:00+08:00", "2025-10-09T10:00:00+08:00"),
+                &slack,
+            ),
+        ];
+        assert_eq!(
+            block_alignment(&cards, &intent_keywords("写设计文档")),
+            (45, 60)
+        );
+        assert_eq!(ratio(45, 60), Some(0.75));
+        assert_eq!(ratio(0, 0), None);
+        assert!(card_matches(&english, &intent_keywords("write design doc")));
+        assert!(!card_matches(&slack, &intent_keywords("write design doc")));
+    }
+}
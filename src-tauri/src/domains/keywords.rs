@@ -0,0 +1,350 @@
+// 关注关键词 - 在 LLM 输出中发现用户关注的内容（如“线上故障”“面试”）
+//
+// 会话分析完成后检查视频分段描述和时间线卡片的标题/摘要：
+// - 命中的关键词写入卡片的 keyword_tags（分段命中时标记分段所在的卡片）
+// - 每次命中写入 keyword_hits 表，供按周期回顾
+// - 有命中时发送一次系统通知
+// 重新分析会话时命中记录整体替换，不会重复累计
+
+use super::metrics::{parse_series_time, StatsPeriod};
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::storage::{Database, KeywordHitRecord, Session, TimelineCardRecord, VideoSegmentRecord};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 摘录中命中位置前后保留的字符数
+const EXCERPT_CONTEXT_CHARS: usize = 30;
+
+/// 一个会话的命中汇总（用于通知）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeywordAlert {
+    pub session_id: i64,
+    /// 命中的关键词（去重）
+    pub keywords: Vec<String>,
+    /// 第一条命中的摘录
+    pub excerpt: String,
+    pub hit_count: usize,
+}
+
+/// 查找文本中命中的关键词（不区分大小写），返回关键词和摘录
+fn match_keywords(text: &str, keywords: &[String]) -> Vec<(String, String)> {
+    let lower = text.to_lowercase();
+    keywords
+        .iter()
+        .map(|keyword| keyword.trim())
+        .filter(|keyword| !keyword.is_empty())
+        .filter_map(|keyword| {
+            let position = lower.find(&keyword.to_lowercase())?;
+            Some((keyword.to_string(), excerpt(text, &lower, position)))
+        })
+        .collect()
+}
+
+/// 截取命中位置附近的文本（按字符截取，小写后长度不变时才能直接对应原文位置）
+fn excerpt(text: &str, lower: &str, byte_position: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let char_position = if lower.len() == text.len() {
+        text[..byte_position].chars().count()
+    } else {
+        lower[..byte_position].chars().count().min(chars.len())
+    };
+    let start = char_position.saturating_sub(EXCERPT_CONTEXT_CHARS);
+    let end = (char_position + EXCERPT_CONTEXT_CHARS).min(chars.len());
+    let mut excerpt: String = chars[start..end].iter().collect();
+    if start > 0 {
+        excerpt.insert(0, '…');
+    }
+    if end < chars.len() {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// 查找会话中的命中，返回命中记录和各卡片命中的关键词
+pub fn find_hits(
+    session: &Session,
+    cards: &[TimelineCardRecord],
+    segments: &[VideoSegmentRecord],
+    keywords: &[String],
+) -> (Vec<KeywordHitRecord>, BTreeMap<i64, BTreeSet<String>>) {
+    let session_id = session.id.unwrap_or_default();
+    let parse = |value: &str| parse_series_time(value, session.start_time, session.end_time);
+    let now = crate::storage::local_now();
+    let hit = |card_id: Option<i64>,
+               keyword: String,
+               source: &str,
+               excerpt: String,
+               at: NaiveDateTime| {
+        KeywordHitRecord {
+            id: None,
+            session_id,
+            card_id,
+            keyword,
+            source: source.to_string(),
+            excerpt,
+            occurred_at: at.and_utc(),
+            created_at: now,
+        }
+    };
+
+    let mut hits = Vec::new();
+    let mut card_tags: BTreeMap<i64, BTreeSet<String>> = BTreeMap::new();
+
+    for card in cards {
+        let text = format!("{}\n{}", card.title, card.summary);
+        for (keyword, excerpt) in match_keywords(&text, keywords) {
+            if let Some(card_id) = card.id {
+                card_tags
+                    .entry(card_id)
+                    .or_default()
+                    .insert(keyword.clone());
+            }
+            hits.push(hit(
+                card.id,
+                keyword,
+                "card",
+                excerpt,
+                parse(&card.start_time),
+            ));
+        }
+    }
+
+    for segment in segments {
+        let (start, end) = (
+            parse(&segment.start_timestamp),
+            parse(&segment.end_timestamp),
+        );
+        let middle = start + (end - start) / 2;
+        // 分段中点所在的卡片
+        let card_id = cards
+            .iter()
+            .filter(|card| parse(&card.start_time) <= middle && middle < parse(&card.end_time))
+            .last()
+            .and_then(|card| card.id);
+        for (keyword, excerpt) in match_keywords(&segment.description, keywords) {
+            if let Some(card_id) = card_id {
+                card_tags
+                    .entry(card_id)
+                    .or_default()
+                    .insert(keyword.clone());
+            }
+            hits.push(hit(card_id, keyword, "segment", excerpt, start));
+        }
+    }
+
+    (hits, card_tags)
+}
+
+/// 检查会话并保存命中，有命中时返回汇总
+pub async fn scan_session(
+    db: &Database,
+    session_id: i64,
+    keywords: &[String],
+) -> Result<Option<KeywordAlert>, String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+
+    let (hits, card_tags) = find_hits(&session, &cards, &segments, keywords);
+
+    for card in &cards {
+        let Some(card_id) = card.id else {
+            continue;
+        };
+        let tags = card_tags
+            .get(&card_id)
+            .map(|tags| serde_json::to_string(tags).unwrap_or_default());
+        if tags != card.keyword_tags {
+            db.update_timeline_card_keyword_tags(card_id, tags.as_deref())
+                .await
+                .map_err(|e| format!("标记卡片失败: {}", e))?;
+        }
+    }
+    db.replace_keyword_hits(session_id, &hits)
+        .await
+        .map_err(|e| format!("保存关键词命中失败: {}", e))?;
+
+    let Some(first) = hits.first() else {
+        return Ok(None);
+    };
+    let keywords: BTreeSet<String> = hits.iter().map(|hit| hit.keyword.clone()).collect();
+    Ok(Some(KeywordAlert {
+        session_id,
+        keywords: keywords.into_iter().collect(),
+        excerpt: first.excerpt.clone(),
+        hit_count: hits.len(),
+    }))
+}
+
+/// 获取周期内的关键词命中
+pub async fn get_keyword_hits(
+    db: &Database,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+) -> Result<Vec<KeywordHitRecord>, String> {
+    let (start, end) = period.date_range(anchor);
+    let start = start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc() + Duration::days(1);
+    db.get_keyword_hits_between(start, end)
+        .await
+        .map_err(|e| format!("获取关键词命中失败: {}", e))
+}
+
+/// 启动关键词检查任务：会话分析完成后检查命中，有命中时调用 `notify`
+pub fn start_keyword_worker<F>(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>, notify: F)
+where
+    F: Fn(&KeywordAlert) + Send + Sync + 'static,
+{
+    let mut receiver = event_bus.subscribe_topics("关键词检查任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let keywords = storage
+                .get_settings()
+                .get()
+                .await
+                .watch_keywords
+                .unwrap_or_default();
+            if keywords.iter().all(|keyword| keyword.trim().is_empty()) {
+                continue;
+            }
+
+            let Ok(db) = storage.get_db().await else {
+                continue;
+            };
+            match scan_session(&db, session_id, &keywords).await {
+                Ok(Some(alert)) => {
+                    info!(
+                        "会话 {} 命中关注关键词: {}",
+                        session_id,
+                        alert.keywords.join("、")
+                    );
+                    notify(&alert);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("会话 {} 关键词检查失败: {}", session_id, e),
+            }
+        }
+        warn!("关键词检查任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_hits_from_cards_and_segments_tag_cards() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(3),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let card =
+            |id: i64, title: &str, summary: &str, start: &str, end: &str| TimelineCardRecord {
+                id: Some(id),
+                session_id: 3,
+                llm_call_id: None,
+                start_time: start.to_string(),
+                end_time: end.to_string(),
+                category: "work".to_string(),
+                subcategory: String::new(),
+                title: title.to_string(),
+                summary: summary.to_string(),
+                detailed_summary: String::new(),
+                distractions: None,
+                app_sites: "{}".to_string(),
+                video_preview_path: None,
+                created_at: start,
+                confidence: None,
+                review_status: None,
+                meeting_notes: None,
+                keyword_tags: None,
+                preview_clip_path: None,
+                privacy_level: None,
+            };
+        let cards = vec![
+            card(
+                1,
+                "处理线上故障",
+                "排查 Production Incident 告警",
+                "00:00",
+                "05:00",
+            ),
+            card(2, "编写文档", "整理接口说明", "05:00", "15:00"),
+        ];
+        let segment = VideoSegmentRecord {
+            id: None,
+            session_id: 3,
+            llm_call_id: None,
+            start_timestamp: "06:00".to_string(),
+            end_timestamp: "08:00".to_string(),
+            description: "在日历中安排 interview".to_string(),
+            created_at: start,
+        };
+        let keywords = vec![
+            "production incident".to_string(),
+            "Interview".to_string(),
+            "  ".to_string(),
+        ];
+
+        let (hits, tags) = find_hits(&session, &cards, &[segment], &keywords);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].source, "card");
+        assert_eq!(
+            hits[0].excerpt,
+            "处理线上故障\n排查 Production Incident 告警"
+        );
+        assert_eq!(hits[1].card_id, Some(2));
+        assert_eq!(hits[1].occurred_at.format("%H:%M").to_string(), "09:06");
+        assert_eq!(
+            tags.get(&1).unwrap().iter().collect::<Vec<_>>(),
+            vec!["production incident"]
+        );
+        assert_eq!(
+            tags.get(&2).unwrap().iter().collect::<Vec<_>>(),
+            vec!["Interview"]
+        );
+
+        let long = format!("{}线上故障{}", "甲".repeat(40), "乙".repeat(40));
+        let matched = match_keywords(&long, &["线上故障".to_string()]);
+        assert_eq!(
+            matched[0].1,
+            format!("…{}线上故障{}…", "甲".repeat(30), "乙".repeat(26))
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// 实时状态领域 - 供直播叠加层（OBS）、Stream Deck 等第三方小部件使用
+//
+// 后台任务每隔 LIVE_TICK_SECS 秒汇总一次当前状态：当前活动类别、专注连续时长、
+// 当前会话已进行时长等，通过 Tauri 事件 live-state 推送给前端，
+// 同时广播给本地 WebSocket 订阅者（见 live_server）。
+// 活动类别来自时间线卡片，会比实际活动滞后一个会话周期（分析完成后才更新）；
+// 启用实时活动估计后，live_activity 中附带按前台窗口估计的当前活动（见 live_activity）
+
+use super::metrics::{category_productivity, ActivitySpan, SWITCH_WINDOW_MINUTES};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::sync::RwLock;
+use tokio::sync::broadcast;
+
+/// 推送给前端的 Tauri 事件名
+pub const LIVE_STATE_EVENT: &str = "live-state";
+
+/// 状态刷新间隔（秒）
+pub const LIVE_TICK_SECS: u64 = 10;
+
+/// 最近一张卡片结束超过该分钟数后视为没有当前活动
+const CURRENT_ACTIVITY_MAX_AGE_MINUTES: i64 = 30;
+
+/// 生产力强度不低于该值的类别计入专注
+pub const FOCUS_PRODUCTIVITY: f32 = 0.8;
+
+/// 实时状态快照
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LiveState {
+    /// 快照时间（本地时间）
+    pub timestamp: String,
+    /// 是否正在截屏
+    pub is_capturing: bool,
+    /// 当前会话开始时间（本地时间）
+    pub session_start: Option<String>,
+    /// 当前会话已进行的秒数
+    pub session_elapsed_secs: i64,
+    /// 当前活动类别（最近一张卡片，过旧时为空）
+    pub current_category: Option<String>,
+    /// 当前活动子类别
+    pub current_subcategory: Option<String>,
+    /// 专注连续时长（分钟，连续的高生产力卡片）
+    pub focus_streak_minutes: i64,
+    /// 连续使用屏幕的分钟数（与休息提醒一致）
+    pub active_minutes: i64,
+    /// 实时活动估计（会话分析完成前的当前活动，未启用时为空）
+    pub live_activity: Option<super::live_activity::LiveActivity>,
+}
+
+/// 实时状态广播
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveState>,
+    latest: RwLock<LiveState>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(16);
+        Self {
+            sender,
+            latest: RwLock::new(LiveState::default()),
+        }
+    }
+
+    /// 订阅状态更新
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveState> {
+        self.sender.subscribe()
+    }
+
+    /// 最近一次的状态
+    pub fn latest(&self) -> LiveState {
+        self.latest
+            .read()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// 发布新状态（没有订阅者时只更新最近状态）
+    pub fn publish(&self, state: LiveState) {
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = state.clone();
+        }
+        let _ = self.sender.send(state);
+    }
+}
+
+/// 根据当前时间和活动片段构建状态快照
+///
+/// `spans` 为本设备当天的活动片段，`session_start` 为截屏调度器当前会话窗口的开始时间
+pub fn build_live_state(
+    now: DateTime<Utc>,
+    spans: &[ActivitySpan],
+    session_start: Option<DateTime<Utc>>,
+    is_capturing: bool,
+    active_minutes: i64,
+) -> LiveState {
+    let now_local = now.naive_utc();
+    let current = current_activity(spans, now_local);
+
+    LiveState {
+        timestamp: now.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        is_capturing,
+        session_start: session_start.map(|start| start.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        session_elapsed_secs: session_start
+            .map(|start| (now - start).num_seconds())
+            .unwrap_or(0),
+        current_category: current.map(|span| span.category.clone()),
+        current_subcategory: current.map(|span| span.subcategory.clone()),
+        focus_streak_minutes: focus_streak_minutes(spans, now_local),
+        active_minutes,
+        live_activity: None,
+    }
+}
+
+/// 最近结束、且未过旧的活动片段
+fn current_activity(spans: &[ActivitySpan], now: NaiveDateTime) -> Option<&ActivitySpan> {
+    spans
+        .iter()
+        .filter(|span| span.start.naive_local() <= now)
+        .max_by_key(|span| span.end.naive_local())
+        .filter(|span| {
+            now - span.end.naive_local() <= Duration::minutes(CURRENT_ACTIVITY_MAX_AGE_MINUTES)
+        })
+}
+
+/// 专注连续时长：从最近的片段往前，累计间隔不超过切换窗口的高生产力片段
+fn focus_streak_minutes(spans: &[ActivitySpan], now: NaiveDateTime) -> i64 {
+    let Some(current) = current_activity(spans, now) else {
+        return 0;
+    };
+    if category_productivity(&current.category) < FOCUS_PRODUCTIVITY {
+        return 0;
+    }
+
+    let mut ordered: Vec<&ActivitySpan> = spans
+        .iter()
+        .filter(|span| span.end.naive_local() <= current.end.naive_local())
+        .collect();
+    ordered.sort_by_key(|span| span.start.naive_local());
+
+    let streak_end = current.end.naive_local();
+    let mut streak_start = current.start.naive_local();
+    for span in ordered.iter().rev() {
+        if span.start.naive_local() >= streak_start {
+            continue;
+        }
+        let gap = streak_start - span.end.naive_local();
+        if gap > Duration::minutes(SWITCH_WINDOW_MINUTES)
+            || category_productivity(&span.category) < FOCUS_PRODUCTIVITY
+        {
+            break;
+        }
+        streak_start = span.start.naive_local();
+    }
+
+    (streak_end - streak_start).num_minutes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn span(category: &str, start: &str, end: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(start).unwrap(),
+            end: DateTime::parse_from_rfc3339(end).unwrap(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            app: String::new(),
+            device: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_live_state_focus_streak_and_session() {
+        let spans = vec![
+            span(
+                "entertainment",
+                "2025-10-09T08:30:00+08:00",
+                "2025-10-09T09:00:00+08:00",
+            ),
+            span(
+                "work",
+                "2025-10-09T09:00:00+08:00",
+                "2025-10-09T09:40:00+08:00",
+            ),
+            span(
+                "learning",
+                "2025-10-09T09:42:00+08:00",
+                "2025-10-09T10:00:00+08:00",
+            ),
+        ];
+        // 本地时间以 DateTime<Utc> 表示
+        let now = Utc.with_ymd_and_hms(2025, 10, 9, 10, 7, 30).unwrap();
+
+        let session_start = Utc.with_ymd_and_hms(2025, 10, 9, 10, 0, 0).single();
+        let state = build_live_state(now, &spans, session_start, true, 20);
+        assert_eq!(state.current_category.as_deref(), Some("learning"));
+        assert_eq!(state.focus_streak_minutes, 60);
+        assert_eq!(state.session_start.as_deref(), Some("2025-10-09T10:00:00"));
+        assert_eq!(state.session_elapsed_secs, 450);
+
+        let later = Utc.with_ymd_and_hms(2025, 10, 9, 11, 0, 0).unwrap();
+        let state = build_live_state(later, &spans, session_start, true, 0);
+        assert_eq!(state.current_category, None);
+        assert_eq!(state.focus_streak_minutes, 0);
+    }
+}
@@ -0,0 +1,270 @@
+// 实时活动估计 - 会话分析完成前，按前台窗口和最新截图的文字粗略估计当前活动类别
+//
+// 时间线卡片要等一个会话周期（默认 15 分钟）分析完成后才有，实时状态中的当前类别因此滞后。
+// 启用后后台任务每隔 LIVE_TICK_SECS 秒读取前台应用名和窗口标题，按关键词规则归类
+// （自定义规则优先于内置规则）；窗口标题判断不出时，可选地用 tesseract 识别最新一帧截图，
+// 再用同一套规则匹配屏幕文字。结果附在实时状态（live-state 事件和 WebSocket）中，
+// 只包含类别、应用名和命中的关键词，不包含窗口标题和屏幕文字。
+// 这只是估计，分析完成后以时间线卡片为准
+
+use super::live::LIVE_TICK_SECS;
+use crate::capture::foreground;
+use crate::capture::ScreenCapture;
+use crate::models::{LiveActivityRule, LiveActivitySettings};
+use crate::settings::SettingsManager;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::{debug, info};
+
+/// 估计结果超过该秒数未更新时视为失效（后台任务停止或关闭了功能）
+const STALE_SECS: i64 = 3 * LIVE_TICK_SECS as i64;
+
+/// 内置规则：关键词、类别、子类别，按顺序匹配
+const BUILTIN_RULES: &[(&str, &str, &str)] = &[
+    ("zoom", "meeting", "视频会议"),
+    ("teams", "meeting", "视频会议"),
+    ("腾讯会议", "meeting", "视频会议"),
+    ("飞书会议", "meeting", "视频会议"),
+    ("google meet", "meeting", "视频会议"),
+    ("visual studio", "work", "编程"),
+    ("code", "work", "编程"),
+    ("intellij", "work", "编程"),
+    ("pycharm", "work", "编程"),
+    ("xcode", "work", "编程"),
+    ("terminal", "work", "编程"),
+    ("iterm", "work", "编程"),
+    ("github", "work", "编程"),
+    ("figma", "design", "设计"),
+    ("photoshop", "design", "设计"),
+    ("microsoft word", "writing", "文档"),
+    ("notion", "writing", "文档"),
+    ("obsidian", "writing", "笔记"),
+    ("slack", "communication", "即时通讯"),
+    ("微信", "communication", "即时通讯"),
+    ("wechat", "communication", "即时通讯"),
+    ("钉钉", "communication", "即时通讯"),
+    ("outlook", "communication", "邮件"),
+    ("mail", "communication", "邮件"),
+    ("stack overflow", "learning", "查资料"),
+    ("docs", "learning", "查资料"),
+    ("文档", "learning", "查资料"),
+    ("youtube", "entertainment", "视频"),
+    ("bilibili", "entertainment", "视频"),
+    ("哔哩哔哩", "entertainment", "视频"),
+    ("netflix", "entertainment", "视频"),
+    ("spotify", "entertainment", "音乐"),
+    ("steam", "entertainment", "游戏"),
+    ("微博", "social", "社交媒体"),
+    ("twitter", "social", "社交媒体"),
+    ("reddit", "social", "社交媒体"),
+];
+
+/// 估计依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveActivitySource {
+    /// 前台应用名和窗口标题
+    Window,
+    /// 最新截图的屏幕文字
+    Ocr,
+}
+
+/// 当前活动的估计结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveActivity {
+    pub category: String,
+    pub subcategory: String,
+    pub source: LiveActivitySource,
+    /// 命中的关键词
+    pub keyword: String,
+    /// 前台应用名
+    pub app: Option<String>,
+    /// 估计时间（本地时间）
+    pub updated_at: String,
+}
+
+fn latest() -> &'static RwLock<Option<(chrono::DateTime<chrono::Utc>, LiveActivity)>> {
+    static LATEST: OnceLock<RwLock<Option<(chrono::DateTime<chrono::Utc>, LiveActivity)>>> =
+        OnceLock::new();
+    LATEST.get_or_init(|| RwLock::new(None))
+}
+
+fn set_latest(activity: Option<LiveActivity>) {
+    if let Ok(mut latest) = latest().write() {
+        *latest = activity.map(|activity| (crate::storage::local_now(), activity));
+    }
+}
+
+/// 最近一次估计结果（未启用、无法判断或已失效时为 None）
+pub fn current() -> Option<LiveActivity> {
+    let latest = latest().read().ok()?;
+    let (at, activity) = latest.as_ref()?;
+    ((crate::storage::local_now() - *at).num_seconds() <= STALE_SECS).then(|| activity.clone())
+}
+
+/// 按规则匹配文字（不区分大小写），自定义规则优先，返回（关键词, 类别, 子类别）
+pub fn match_rules(rules: &[LiveActivityRule], text: &str) -> Option<(String, String, String)> {
+    let text = text.to_lowercase();
+    if text.trim().is_empty() {
+        return None;
+    }
+    let custom = rules.iter().map(|rule| {
+        (
+            rule.keyword.as_str(),
+            rule.category.as_str(),
+            rule.subcategory.as_str(),
+        )
+    });
+    custom
+        .chain(BUILTIN_RULES.iter().copied())
+        .find(|(keyword, category, _)| {
+            let keyword = keyword.trim();
+            !keyword.is_empty()
+                && !category.trim().is_empty()
+                && text.contains(&keyword.to_lowercase())
+        })
+        .map(|(keyword, category, subcategory)| {
+            (
+                keyword.trim().to_string(),
+                category.trim().to_string(),
+                subcategory.trim().to_string(),
+            )
+        })
+}
+
+/// 先按窗口（应用名 + 窗口标题）判断，判断不出时再用屏幕文字
+pub fn classify(
+    rules: &[LiveActivityRule],
+    app: Option<&str>,
+    title: Option<&str>,
+    screen_text: Option<&str>,
+) -> Option<LiveActivity> {
+    let window = [app, title]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (source, (keyword, category, subcategory)) = match match_rules(rules, &window) {
+        Some(matched) => (LiveActivitySource::Window, matched),
+        None => (
+            LiveActivitySource::Ocr,
+            match_rules(rules, screen_text.unwrap_or_default())?,
+        ),
+    };
+    Some(LiveActivity {
+        category,
+        subcategory,
+        source,
+        keyword,
+        app: app.map(str::to_string),
+        updated_at: crate::storage::local_now()
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string(),
+    })
+}
+
+/// 最近一次的屏幕文字识别（识别时间, 文字）
+type OcrSample = Option<(chrono::DateTime<chrono::Utc>, String)>;
+
+/// 需要时识别最新一帧截图的文字，间隔未到时沿用上次结果
+async fn refresh_ocr(
+    capture: &ScreenCapture,
+    settings: &SettingsManager,
+    live: &LiveActivitySettings,
+    sample: &mut OcrSample,
+) {
+    let now = crate::storage::local_now();
+    if let Some((at, _)) = sample {
+        if (now - *at).num_seconds() < live.ocr_interval_secs as i64 {
+            return;
+        }
+    }
+    let Some(frame) = capture.get_recent_frames(1).await.pop() else {
+        return;
+    };
+    let text_settings = settings.get().await.text_timeline.unwrap_or_default();
+    match super::text_timeline::recognize(&text_settings, Path::new(&frame.file_path)).await {
+        Ok(text) => *sample = Some((now, text)),
+        Err(e) => {
+            debug!("实时活动估计识别截图文字失败: {}", e);
+            *sample = Some((now, String::new()));
+        }
+    }
+}
+
+/// 启动实时活动估计任务（未启用时仅空转检查配置）
+pub fn start_live_activity_worker(capture: Arc<ScreenCapture>, settings: Arc<SettingsManager>) {
+    tokio::spawn(async move {
+        info!("实时活动估计任务已启动");
+        let mut ocr_sample: OcrSample = None;
+        loop {
+            let live = settings.get().await.live_activity.unwrap_or_default();
+            if live.enabled {
+                let app = tokio::task::spawn_blocking(foreground::foreground_app)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|app| app.name);
+                let title = tokio::task::spawn_blocking(foreground::foreground_window_title)
+                    .await
+                    .ok()
+                    .flatten();
+                let mut activity = classify(&live.rules, app.as_deref(), title.as_deref(), None);
+                if activity.is_none() && live.ocr_enabled {
+                    refresh_ocr(&capture, &settings, &live, &mut ocr_sample).await;
+                    activity = classify(
+                        &live.rules,
+                        app.as_deref(),
+                        title.as_deref(),
+                        ocr_sample.as_ref().map(|(_, text)| text.as_str()),
+                    );
+                }
+                set_latest(activity);
+            } else {
+                ocr_sample = None;
+                set_latest(None);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(LIVE_TICK_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_window_then_ocr() {
+        let rules = vec![LiveActivityRule {
+            keyword: "Jira".to_string(),
+            category: "work".to_string(),
+            subcategory: "项目管理".to_string(),
+        }];
+
+        let activity =
+            classify(&rules, Some("Google Chrome"), Some("JIRA-123 - Jira"), None).unwrap();
+        assert_eq!(activity.category, "work");
+        assert_eq!(activity.subcategory, "项目管理");
+        assert_eq!(activity.source, LiveActivitySource::Window);
+        assert_eq!(activity.app.as_deref(), Some("Google Chrome"));
+
+        // 自定义规则优先于内置规则
+        let activity = classify(&rules, Some("Slack"), Some("jira 通知"), None).unwrap();
+        assert_eq!(activity.keyword, "Jira");
+
+        // 窗口判断不出时使用屏幕文字
+        let activity = classify(
+            &rules,
+            Some("Google Chrome"),
+            Some("新标签页"),
+            Some("哔哩哔哩 (゜-゜)つロ 干杯"),
+        )
+        .unwrap();
+        assert_eq!(activity.category, "entertainment");
+        assert_eq!(activity.source, LiveActivitySource::Ocr);
+
+        assert!(classify(&rules, Some("Finder"), None, Some("")).is_none());
+        assert!(classify(&[], None, None, None).is_none());
+    }
+}
@@ -0,0 +1,218 @@
+// 本地模式 - 开启后禁止任何截图、视频或分析数据离开本机/局域网
+//
+// 所有 HTTP 客户端都通过 `http_client_builder` 创建，由客户端本身拦截外部地址：
+// IP 地址直接判断，域名在 DNS 解析后只保留本机/局域网地址，系统代理在本地模式下不使用。
+// 因此即使服务商配置错误（例如仍指向云端 API），截图也不会被发出去。
+// Claude、Codex 和插件在独立进程中联网，本地模式下直接拒绝使用；Notion 同步同样停用
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Proxy, Url};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 本地模式开关（启动时及设置变更时更新，客户端在每次建立连接时读取）
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// 被拦截的请求转发到这个不存在的代理，由 DNS 解析阶段拒绝
+const BLOCKED_PROXY_HOST: &str = "local-only.blocked.invalid";
+
+/// 更新本地模式开关
+pub fn set_enabled(enabled: bool) {
+    let previous = LOCAL_ONLY.swap(enabled, Ordering::SeqCst);
+    if previous != enabled {
+        info!("本地模式已{}", if enabled { "开启" } else { "关闭" });
+    }
+}
+
+/// 本地模式是否开启
+pub fn is_enabled() -> bool {
+    LOCAL_ONLY.load(Ordering::SeqCst)
+}
+
+/// 本机或局域网地址（回环、私有网段、IPv6 唯一本地地址）
+pub fn is_local_addr(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_private(),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_local_addr(IpAddr::V4(v4));
+            }
+            v6.is_loopback() || (v6.segments()[0] & 0xfe00) == 0xfc00
+        }
+    }
+}
+
+/// 从主机名判断是否为本地服务（localhost、.local/.lan 以及不带点的局域网主机名）
+fn is_local_hostname(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.ends_with(".local")
+        || host.ends_with(".lan")
+        || !host.contains('.')
+}
+
+/// 地址中的 IP（主机为域名时返回 None）
+fn host_ip(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// 地址是否指向本地服务
+pub fn is_local_url(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) => match host_ip(host) {
+            Some(ip) => is_local_addr(ip),
+            None => is_local_hostname(host),
+        },
+        None => false,
+    }
+}
+
+/// 本地模式下检查服务地址，返回可以直接展示给用户的错误
+pub fn ensure_local_endpoint(endpoint: &str) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    let url = Url::parse(endpoint).map_err(|e| format!("服务地址无效: {}", e))?;
+    if is_local_url(&url) {
+        Ok(())
+    } else {
+        Err(format!(
+            "本地模式已开启，不能访问外部服务 {}，请改用本机或局域网的模型服务（如 Ollama）",
+            url.host_str().unwrap_or(endpoint)
+        ))
+    }
+}
+
+/// 本地模式下检查服务商：Claude、Codex 和插件在独立进程中联网，无法拦截
+pub fn ensure_local_provider(provider: &str) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    match provider {
+        "openai" | "qwen" | "mock" => Ok(()),
+        other => Err(format!(
+            "本地模式已开启，不能使用 {}，请改用本机或局域网的 OpenAI 兼容服务（如 Ollama）",
+            other
+        )),
+    }
+}
+
+/// 非本地模式下沿用 HTTP(S)_PROXY 环境变量（自定义代理后 reqwest 不再自动读取系统代理）
+fn env_proxy(url: &Url) -> Option<Url> {
+    let read = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+    };
+
+    let host = url.host_str()?.to_ascii_lowercase();
+    if let Some(no_proxy) = read(&["NO_PROXY", "no_proxy"]) {
+        let bypass = no_proxy.split(',').map(str::trim).any(|entry| {
+            let entry = entry.trim_start_matches('.').to_ascii_lowercase();
+            entry == "*"
+                || (!entry.is_empty() && (host == entry || host.ends_with(&format!(".{}", entry))))
+        });
+        if bypass {
+            return None;
+        }
+    }
+
+    let proxy = match url.scheme() {
+        "https" => read(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]),
+        _ => read(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]),
+    }?;
+    Url::parse(&proxy).ok()
+}
+
+/// 为每个请求选择代理：本地模式下外部 IP 地址转到拦截用的代理，本地地址直连
+fn route(url: &Url) -> Option<Url> {
+    if !is_enabled() {
+        return env_proxy(url);
+    }
+    let blocked = match url.host_str() {
+        // 域名在 DNS 解析阶段检查
+        Some(host) => host_ip(host).is_some_and(|ip| !is_local_addr(ip)),
+        None => true,
+    };
+    if blocked {
+        warn!("本地模式：已拦截对 {} 的请求", url.host_str().unwrap_or(""));
+        return Url::parse(&format!("http://{}", BLOCKED_PROXY_HOST)).ok();
+    }
+    None
+}
+
+/// DNS 解析：本地模式下只保留本机/局域网地址
+async fn resolve_local(host: String) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    if host == BLOCKED_PROXY_HOST {
+        return Err("本地模式已开启，已阻止访问外部网络".into());
+    }
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+    if !is_enabled() {
+        return Ok(Box::new(addrs.into_iter()));
+    }
+
+    let local: Vec<SocketAddr> = addrs
+        .into_iter()
+        .filter(|addr| is_local_addr(addr.ip()))
+        .collect();
+    if local.is_empty() {
+        warn!("本地模式：已拦截对 {} 的请求", host);
+        return Err(format!("本地模式已开启，已阻止访问外部服务 {}", host).into());
+    }
+    Ok(Box::new(local.into_iter()))
+}
+
+struct LocalOnlyResolver;
+
+impl Resolve for LocalOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve_local(name.as_str().to_string()))
+    }
+}
+
+/// 创建 HTTP 客户端的统一入口，本地模式下客户端只能访问本机/局域网地址
+///
+/// 开关在每次建立连接时读取，切换本地模式后无需重建客户端
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .proxy(Proxy::custom(route))
+        .dns_resolver(Arc::new(LocalOnlyResolver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_loopback_and_lan_urls_are_local() {
+        let local = [
+            "http://localhost:11434/v1/chat/completions",
+            "http://127.0.0.1:8080",
+            "http://192.168.1.20:11434",
+            "http://10.0.0.5",
+            "http://[::1]:11434",
+            "http://ollama.lan",
+            "http://gpu-box:11434",
+        ];
+        for url in local {
+            assert!(is_local_url(&Url::parse(url).unwrap()), "{}", url);
+        }
+
+        let remote = [
+            "https://dashscope.aliyuncs.com/compatible-mode/v1/chat/completions",
+            "https://api.openai.com/v1",
+            "http://8.8.8.8",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[2001:db8::1]",
+        ];
+        for url in remote {
+            assert!(!is_local_url(&Url::parse(url).unwrap()), "{}", url);
+        }
+    }
+}
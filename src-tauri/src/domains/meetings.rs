@@ -0,0 +1,240 @@
+// 会议纪要 - 对会议时段做一次更细的分析
+//
+// 时间线卡片被识别为会议（类别为 meeting，或子类别/标题中包含“会议”“meeting”）后，
+// 按卡片的时间范围从会话视频中密集抽帧，由模型识别幻灯片标题、参会人和可见的议程，
+// 生成的纪要以 JSON 保存在卡片的 meeting_notes 字段。
+// 可以手动对单张卡片生成；开启 meeting_notes_enabled 后，会话分析完成时自动处理其中的会议卡片
+
+use super::playback::{card_video_range, session_mapping, CardVideoRange};
+use super::storage::StorageDomain;
+use crate::actors::LLMHandle;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::llm::MeetingNotes;
+use crate::storage::TimelineCardRecord;
+use crate::video::VideoUtils;
+use chrono::DateTime;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 每次分析最多抽取的帧数
+pub const MAX_MEETING_FRAMES: usize = 20;
+
+/// 相邻两帧的最小间隔（视频秒），短会议不会抽出大量重复画面
+const MIN_FRAME_STEP_SECS: f64 = 0.5;
+
+/// 卡片是否为会议
+pub fn is_meeting_card(card: &TimelineCardRecord) -> bool {
+    let contains_meeting =
+        |text: &str| text.contains("会议") || text.to_lowercase().contains("meeting");
+    card.category.eq_ignore_ascii_case("meeting")
+        || contains_meeting(&card.subcategory)
+        || contains_meeting(&card.title)
+}
+
+/// 在卡片的视频范围内均匀选取抽帧位置（取每段的中点，避开卡片边界的切换画面）
+pub fn frame_positions(range: &CardVideoRange, max_frames: usize) -> Vec<f64> {
+    let length = range.end_secs - range.start_secs;
+    if length <= 0.0 || max_frames == 0 {
+        return Vec::new();
+    }
+
+    let count = ((length / MIN_FRAME_STEP_SECS) as usize).clamp(1, max_frames);
+    let step = length / count as f64;
+    (0..count)
+        .map(|index| range.start_secs + step * (index as f64 + 0.5))
+        .collect()
+}
+
+/// 卡片时长（分钟）
+fn card_minutes(card: &TimelineCardRecord) -> i64 {
+    match (
+        DateTime::parse_from_rfc3339(&card.start_time),
+        DateTime::parse_from_rfc3339(&card.end_time),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_minutes().max(0),
+        _ => 0,
+    }
+}
+
+/// 为一张会议卡片生成会议纪要并保存
+pub async fn generate_for_card(
+    storage: &StorageDomain,
+    llm_handle: &LLMHandle,
+    card_id: i64,
+) -> Result<MeetingNotes, String> {
+    let db = storage.get_db().await?;
+    let card = db
+        .get_timeline_card(card_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+        .ok_or_else(|| format!("卡片 {} 不存在", card_id))?;
+    if !is_meeting_card(&card) {
+        return Err("该卡片不是会议".to_string());
+    }
+
+    let session = db
+        .get_session(card.session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let video_path = session
+        .video_path
+        .clone()
+        .ok_or_else(|| "会话没有视频，无法生成会议纪要".to_string())?;
+    let video_path = storage
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+        .map_err(|e| format!("获取会话视频失败: {}", e))?;
+
+    let mapping = session_mapping(&session, storage.get_settings()).await;
+    let positions = frame_positions(
+        &card_video_range(&session, &card, &mapping),
+        MAX_MEETING_FRAMES,
+    );
+
+    let frames_dir = std::env::temp_dir().join(format!("meeting_frames_{}", card_id));
+    tokio::fs::create_dir_all(&frames_dir)
+        .await
+        .map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+    let mut frames = Vec::new();
+    for (index, position) in positions.iter().enumerate() {
+        let frame_path = frames_dir.join(format!("frame_{:02}.jpg", index));
+        match VideoUtils::extract_frame(&video_path, &frame_path, *position as f32).await {
+            Ok(()) => frames.push(frame_path.to_string_lossy().to_string()),
+            Err(e) => warn!("会议抽帧失败 ({:.1}s): {}", position, e),
+        }
+    }
+
+    let context = format!("{}\n{}", card.title, card.summary);
+    let result = if frames.is_empty() {
+        Err("没有抽取到会议画面".to_string())
+    } else {
+        llm_handle
+            .generate_meeting_notes(frames, &context)
+            .await
+            .map_err(|e| format!("生成会议纪要失败: {}", e))
+    };
+    let _ = tokio::fs::remove_dir_all(&frames_dir).await;
+
+    let mut notes = result?;
+    notes.duration_minutes = card_minutes(&card);
+    let notes_json = serde_json::to_string(&notes).map_err(|e| e.to_string())?;
+    db.update_timeline_card_meeting_notes(card_id, &notes_json)
+        .await
+        .map_err(|e| format!("保存会议纪要失败: {}", e))?;
+
+    info!("已生成卡片 {} 的会议纪要: {}", card_id, notes.topic);
+    Ok(notes)
+}
+
+/// 启动自动会议纪要任务：会话分析完成后，为其中尚无纪要的会议卡片生成纪要
+pub fn start_meeting_notes_worker(
+    event_bus: Arc<EventBus>,
+    storage: Arc<StorageDomain>,
+    llm_handle: LLMHandle,
+) {
+    let mut receiver = event_bus.subscribe_topics("会议纪要任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let enabled = storage
+                .get_settings()
+                .get()
+                .await
+                .meeting_notes_enabled
+                .unwrap_or(false);
+            if !enabled {
+                continue;
+            }
+
+            let Ok(db) = storage.get_db().await else {
+                continue;
+            };
+            let cards = match db.get_timeline_cards_by_session(session_id).await {
+                Ok(cards) => cards,
+                Err(e) => {
+                    warn!("会议纪要任务获取时间线卡片失败: {}", e);
+                    continue;
+                }
+            };
+
+            for card in cards
+                .iter()
+                .filter(|card| is_meeting_card(card) && card.meeting_notes.is_none())
+            {
+                let Some(card_id) = card.id else {
+                    continue;
+                };
+                if let Err(e) = generate_for_card(&storage, &llm_handle, card_id).await {
+                    warn!("自动生成卡片 {} 的会议纪要失败: {}", card_id, e);
+                }
+            }
+        }
+        warn!("会议纪要任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meeting_detection_and_frame_positions() {
+        let mut card = TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T10:00:00+08:00".to_string(),
+            end_time: "2025-10-09T10:45:00+08:00".to_string(),
+            category: "work".to_string(),
+            subcategory: "编码".to_string(),
+            title: "编写接口文档".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        assert!(!is_meeting_card(&card));
+        card.title = "季度规划会议".to_string();
+        assert!(is_meeting_card(&card));
+        assert_eq!(card_minutes(&card), 45);
+
+        // 长会议最多抽 20 帧，均匀分布在卡片范围内
+        let long = CardVideoRange {
+            card_id: 1,
+            start_secs: 100.0,
+            end_secs: 140.0,
+        };
+        let positions = frame_positions(&long, MAX_MEETING_FRAMES);
+        assert_eq!(positions.len(), 20);
+        assert_eq!(positions[0], 101.0);
+        assert_eq!(positions[19], 139.0);
+
+        // 短会议按最小间隔抽帧
+        let short = CardVideoRange {
+            card_id: 1,
+            start_secs: 10.0,
+            end_secs: 11.0,
+        };
+        assert_eq!(
+            frame_positions(&short, MAX_MEETING_FRAMES),
+            vec![10.25, 10.75]
+        );
+    }
+}
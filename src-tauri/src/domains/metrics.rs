@@ -0,0 +1,573 @@
+// 指标领域 - 基于时间线卡片计算生产力相关指标
+//
+// 上下文切换：同一设备上相邻两张卡片在短时间窗口内发生类别或主要应用的变化即记为一次切换，
+// 频繁切换（碎片化）是时间线卡片能揭示的最重要的生产力信号
+//
+// 会话活动序列：按分钟给出会话内的活动类别和生产力强度，供前端在视频进度条下方绘制强度色带
+
+use crate::storage::{Database, TimelineCardRecord, VideoSegmentRecord};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 两张卡片间隔不超过该分钟数时才视为连续（超过则视为中断，不计切换）
+pub const SWITCH_WINDOW_MINUTES: i64 = 5;
+
+/// 统计周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl StatsPeriod {
+    /// 从字符串解析（day / week / month）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            other => Err(format!(
+                "不支持的统计周期: {}（可选 day/week/month）",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    /// 计算包含锚点日期的日期范围（闭区间，周从周一开始）
+    pub fn date_range(&self, anchor: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Self::Day => (anchor, anchor),
+            Self::Week => {
+                let start = anchor - Duration::days(anchor.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::days(6))
+            }
+            Self::Month => {
+                let start = anchor.with_day(1).unwrap_or(anchor);
+                let next_month = if start.month() == 12 {
+                    NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+                };
+                let end = next_month.map(|d| d - Duration::days(1)).unwrap_or(anchor);
+                (start, end)
+            }
+        }
+    }
+}
+
+/// 活动片段（由时间线卡片解析而来）
+#[derive(Debug, Clone)]
+pub struct ActivitySpan {
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub category: String,
+    /// 子类别（小写）
+    pub subcategory: String,
+    /// 主要应用/网站（小写）
+    pub app: String,
+    /// 设备名称（不同设备的活动分别计算切换）
+    pub device: String,
+}
+
+impl ActivitySpan {
+    /// 从时间线卡片构建，时间无法解析时返回 None
+    pub fn from_card(card: &TimelineCardRecord, device: &str) -> Option<Self> {
+        let start = DateTime::parse_from_rfc3339(&card.start_time).ok()?;
+        let end = DateTime::parse_from_rfc3339(&card.end_time).ok()?;
+        let app = serde_json::from_str::<serde_json::Value>(&card.app_sites)
+            .ok()
+            .and_then(|v| {
+                v.get("primary")
+                    .and_then(|p| p.as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        Some(Self {
+            start,
+            end: end.max(start),
+            category: card.category.to_lowercase(),
+            subcategory: card.subcategory.to_lowercase(),
+            app,
+            device: device.to_string(),
+        })
+    }
+
+    /// 持续时长（分钟）
+    pub fn duration_minutes(&self) -> i64 {
+        (self.end - self.start).num_minutes()
+    }
+}
+
+/// 每小时切换统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HourlySwitchStat {
+    /// 小时 (0-23)
+    pub hour: u32,
+    /// 切换次数
+    pub switches: usize,
+}
+
+/// 每日切换统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailySwitchStat {
+    /// 日期 (YYYY-MM-DD)
+    pub date: String,
+    /// 切换次数
+    pub switches: usize,
+    /// 活动时长（分钟）
+    pub active_minutes: i64,
+    /// 每小时切换次数
+    pub switches_per_hour: f64,
+}
+
+/// 上下文切换统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSwitchStats {
+    /// 统计周期 (day/week/month)
+    pub period: String,
+    /// 开始日期
+    pub start_date: String,
+    /// 结束日期
+    pub end_date: String,
+    /// 总切换次数
+    pub total_switches: usize,
+    /// 总活动时长（分钟）
+    pub active_minutes: i64,
+    /// 平均每小时切换次数
+    pub switches_per_hour: f64,
+    /// 按小时分布（24 个桶，跨天累加）
+    pub hourly: Vec<HourlySwitchStat>,
+    /// 按天分布
+    pub daily: Vec<DailySwitchStat>,
+}
+
+/// 计算上下文切换发生的时间点
+///
+/// 按设备分组后按开始时间排序，相邻片段间隔不超过 `SWITCH_WINDOW_MINUTES`
+/// 且类别或主要应用不同即记为一次切换
+pub fn detect_context_switches(spans: &[ActivitySpan]) -> Vec<DateTime<FixedOffset>> {
+    let mut by_device: BTreeMap<&str, Vec<&ActivitySpan>> = BTreeMap::new();
+    for span in spans {
+        by_device
+            .entry(span.device.as_str())
+            .or_default()
+            .push(span);
+    }
+
+    let mut switches = Vec::new();
+    for (_, mut device_spans) in by_device {
+        device_spans.sort_by_key(|s| s.start);
+        for pair in device_spans.windows(2) {
+            let (prev, next) = (pair[0], pair[1]);
+            if next.start - prev.end > Duration::minutes(SWITCH_WINDOW_MINUTES) {
+                continue;
+            }
+            let app_changed = !prev.app.is_empty() && !next.app.is_empty() && prev.app != next.app;
+            if prev.category != next.category || app_changed {
+                switches.push(next.start);
+            }
+        }
+    }
+
+    switches.sort();
+    switches
+}
+
+/// 汇总上下文切换统计
+pub fn summarize_context_switches(
+    period: StatsPeriod,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    spans_by_date: &BTreeMap<String, Vec<ActivitySpan>>,
+) -> ContextSwitchStats {
+    let mut hourly = vec![0usize; 24];
+    let mut daily = Vec::new();
+    let mut total_switches = 0;
+    let mut active_minutes = 0;
+
+    for (date, spans) in spans_by_date {
+        let switches = detect_context_switches(spans);
+        for switch in &switches {
+            hourly[switch.hour() as usize] += 1;
+        }
+
+        let minutes: i64 = spans.iter().map(|s| s.duration_minutes()).sum();
+        total_switches += switches.len();
+        active_minutes += minutes;
+
+        daily.push(DailySwitchStat {
+            date: date.clone(),
+            switches: switches.len(),
+            active_minutes: minutes,
+            switches_per_hour: per_hour(switches.len(), minutes),
+        });
+    }
+
+    ContextSwitchStats {
+        period: period.as_str().to_string(),
+        start_date: start_date.format("%Y-%m-%d").to_string(),
+        end_date: end_date.format("%Y-%m-%d").to_string(),
+        total_switches,
+        active_minutes,
+        switches_per_hour: per_hour(total_switches, active_minutes),
+        hourly: hourly
+            .into_iter()
+            .enumerate()
+            .map(|(hour, switches)| HourlySwitchStat {
+                hour: hour as u32,
+                switches,
+            })
+            .collect(),
+        daily,
+    }
+}
+
+/// 加载某一天所有会话的活动片段
+pub async fn load_activity_spans(db: &Database, date: &str) -> Result<Vec<ActivitySpan>, String> {
+    let sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+
+    let mut spans = Vec::new();
+    for session in sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let device = session.device_name.clone().unwrap_or_default();
+        let cards = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        spans.extend(
+            cards
+                .iter()
+                .filter_map(|c| ActivitySpan::from_card(c, &device)),
+        );
+    }
+
+    Ok(spans)
+}
+
+/// 获取指定周期的上下文切换统计
+///
+/// # 参数
+/// * `period` - 统计周期 (day/week/month)
+/// * `anchor` - 锚点日期，统计包含该日期的周期
+pub async fn get_context_switch_stats(
+    db: &Database,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+) -> Result<ContextSwitchStats, String> {
+    let (start_date, end_date) = period.date_range(anchor);
+
+    let mut spans_by_date = BTreeMap::new();
+    let mut date = start_date;
+    while date <= end_date {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let spans = load_activity_spans(db, &date_str).await?;
+        if !spans.is_empty() {
+            spans_by_date.insert(date_str, spans);
+        }
+        date += Duration::days(1);
+    }
+
+    Ok(summarize_context_switches(
+        period,
+        start_date,
+        end_date,
+        &spans_by_date,
+    ))
+}
+
+/// 会话中某一分钟的活动
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityPoint {
+    /// 距会话开始的分钟数
+    pub offset_minutes: u32,
+    /// 时刻 (HH:MM)
+    pub time: String,
+    /// 活动类别（没有卡片覆盖时为 idle）
+    pub category: String,
+    /// 生产力强度 (0-1)
+    pub productivity: f32,
+    /// 是否处于干扰活动中
+    pub distracted: bool,
+    /// 是否有视频分段覆盖（即该分钟有可识别的屏幕活动）
+    pub has_activity: bool,
+}
+
+/// 会话活动序列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionActivitySeries {
+    pub session_id: i64,
+    /// 会话开始时间 (YYYY-MM-DD HH:MM:SS)
+    pub start_time: String,
+    /// 会话结束时间 (YYYY-MM-DD HH:MM:SS)
+    pub end_time: String,
+    /// 按分钟的活动点
+    pub points: Vec<ActivityPoint>,
+}
+
+/// 各类别的生产力强度
+pub fn category_productivity(category: &str) -> f32 {
+    match category.to_lowercase().as_str() {
+        "work" | "coding" | "writing" | "design" => 1.0,
+        "learning" | "research" => 0.8,
+        "meeting" | "communication" => 0.6,
+        "other" => 0.3,
+        "personal" | "entertainment" | "social" => 0.2,
+        _ => 0.0,
+    }
+}
+
+/// 干扰活动期间的生产力上限
+const DISTRACTED_PRODUCTIVITY_CAP: f32 = 0.2;
+
+/// 解析卡片/分段中的时间为本地时间
+///
+/// 新数据为 RFC3339 格式；旧数据可能仍是相对会话开始的 MM:SS 格式
+pub(crate) fn parse_series_time(
+    value: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> NaiveDateTime {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => time.naive_local(),
+        Err(_) => crate::llm::relative_to_absolute(start, end, value).naive_utc(),
+    }
+}
+
+/// 构建会话的分钟级活动序列
+///
+/// `start`/`end` 为会话时间（按 local_now() 约定存储的本地时间值）
+pub fn build_activity_series(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    cards: &[TimelineCardRecord],
+    segments: &[VideoSegmentRecord],
+) -> Vec<ActivityPoint> {
+    let parse = |value: &str| parse_series_time(value, start, end);
+
+    let card_ranges: Vec<(NaiveDateTime, NaiveDateTime, &TimelineCardRecord)> = cards
+        .iter()
+        .map(|card| (parse(&card.start_time), parse(&card.end_time), card))
+        .collect();
+    let distraction_ranges: Vec<(NaiveDateTime, NaiveDateTime)> = cards
+        .iter()
+        .filter_map(|card| card.distractions.as_deref())
+        .filter_map(|d| serde_json::from_str::<Vec<crate::llm::plugin::Distraction>>(d).ok())
+        .flatten()
+        .map(|d| (parse(&d.start_time), parse(&d.end_time)))
+        .collect();
+    let segment_ranges: Vec<(NaiveDateTime, NaiveDateTime)> = segments
+        .iter()
+        .map(|seg| (parse(&seg.start_timestamp), parse(&seg.end_timestamp)))
+        .collect();
+
+    let covers =
+        |(from, to): &(NaiveDateTime, NaiveDateTime), t: NaiveDateTime| *from <= t && t < *to;
+
+    let session_start = start.naive_utc();
+    let total_minutes = ((end - start).num_seconds().max(0) + 59) / 60;
+
+    (0..total_minutes)
+        .map(|minute| {
+            let minute_start = session_start + Duration::minutes(minute);
+            // 以分钟中点判断覆盖关系
+            let t = minute_start + Duration::seconds(30);
+
+            let category = card_ranges
+                .iter()
+                .rev()
+                .find(|(from, to, _)| covers(&(*from, *to), t))
+                .map(|(_, _, card)| card.category.to_lowercase())
+                .unwrap_or_else(|| "idle".to_string());
+            let distracted = distraction_ranges.iter().any(|r| covers(r, t));
+            let mut productivity = category_productivity(&category);
+            if distracted {
+                productivity = productivity.min(DISTRACTED_PRODUCTIVITY_CAP);
+            }
+
+            ActivityPoint {
+                offset_minutes: minute as u32,
+                time: minute_start.format("%H:%M").to_string(),
+                category,
+                productivity,
+                distracted,
+                has_activity: segment_ranges.iter().any(|r| covers(r, t)),
+            }
+        })
+        .collect()
+}
+
+/// 获取会话的分钟级活动序列
+pub async fn get_session_activity_series(
+    db: &Database,
+    session_id: i64,
+) -> Result<SessionActivitySeries, String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+
+    Ok(SessionActivitySeries {
+        session_id,
+        start_time: session.start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        end_time: session.end_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        points: build_activity_series(session.start_time, session.end_time, &cards, &segments),
+    })
+}
+
+/// 计算每小时切换次数（保留一位小数）
+pub(crate) fn per_hour(switches: usize, minutes: i64) -> f64 {
+    if minutes <= 0 {
+        return 0.0;
+    }
+    (switches as f64 * 60.0 / minutes as f64 * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: &str, end: &str, category: &str, app: &str, device: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(start).unwrap(),
+            end: DateTime::parse_from_rfc3339(end).unwrap(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            app: app.to_string(),
+            device: device.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_detect_context_switches() {
+        let spans = vec![
+            span(
+                "2025-10-09T09:00:00+08:00",
+                "2025-10-09T09:10:00+08:00",
+                "work",
+                "vscode",
+                "mac",
+            ),
+            // 类别相同、应用变化：切换
+            span(
+                "2025-10-09T09:11:00+08:00",
+                "2025-10-09T09:20:00+08:00",
+                "work",
+                "chrome",
+                "mac",
+            ),
+            // 间隔超过窗口：不计切换
+            span(
+                "2025-10-09T09:40:00+08:00",
+                "2025-10-09T09:50:00+08:00",
+                "personal",
+                "wechat",
+                "mac",
+            ),
+            // 类别变化：切换
+            span(
+                "2025-10-09T09:52:00+08:00",
+                "2025-10-09T10:05:00+08:00",
+                "work",
+                "wechat",
+                "mac",
+            ),
+            // 其他设备的并行活动不与本设备比较
+            span(
+                "2025-10-09T09:12:00+08:00",
+                "2025-10-09T09:30:00+08:00",
+                "meeting",
+                "zoom",
+                "pc",
+            ),
+        ];
+
+        let switches = detect_context_switches(&spans);
+        assert_eq!(switches.len(), 2);
+        assert_eq!(switches[0].hour(), 9);
+    }
+
+    #[test]
+    fn test_activity_series_marks_distractions() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let card = TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "00:00".to_string(),
+            end_time: "03:00".to_string(),
+            category: "Work".to_string(),
+            subcategory: "coding".to_string(),
+            title: "写代码".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: Some(
+                r#"[{"startTime":"01:00","endTime":"02:00","title":"刷微博","summary":""}]"#
+                    .to_string(),
+            ),
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+
+        let points = build_activity_series(start, start + Duration::minutes(4), &[card], &[]);
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0].category, "work");
+        assert_eq!(points[0].productivity, 1.0);
+        assert!(points[1].distracted);
+        assert_eq!(points[1].productivity, DISTRACTED_PRODUCTIVITY_CAP);
+        assert_eq!(points[3].category, "idle");
+        assert_eq!(points[3].time, "09:03");
+    }
+
+    #[test]
+    fn test_week_range_starts_on_monday() {
+        let anchor = NaiveDate::from_ymd_opt(2025, 10, 9).unwrap(); // 周四
+        let (start, end) = StatsPeriod::Week.date_range(anchor);
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 10, 6).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2025, 10, 12).unwrap());
+
+        let (start, end) =
+            StatsPeriod::Month.date_range(NaiveDate::from_ymd_opt(2025, 12, 15).unwrap());
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 12, 1).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2025, 12, 31).unwrap());
+    }
+}
@@ -1,13 +1,95 @@
 // 领域模块 - 用于组织应用的业务逻辑
 //
 // 将原本混乱的 AppState 按业务领域分组,实现单一职责原则
-// 包含5个领域:捕获、分析、存储、系统、总结
+// 核心领域:捕获、分析、存储、系统、总结、指标、洞察,其余模块为各项具体功能
 
 pub mod analysis;
+pub mod analysis_backlog;
+pub mod analysis_hold;
+pub mod app_profiles;
+pub mod audio_presence;
+pub mod away;
+pub mod benchmark;
+pub mod bi_export;
+pub mod bookmarks;
+pub mod breaks;
+pub mod browser;
+pub mod bulk_tags;
 pub mod capture;
+pub mod capture_hygiene;
+pub mod capture_pauses;
+pub mod card_previews;
+pub mod clipboard;
+pub mod compilation;
+pub mod confidence_stats;
+pub mod confirmation;
+pub mod data_doctor;
+pub mod data_reset;
+pub mod day_bundle;
+pub mod day_rebuild;
+pub mod day_review;
+pub mod day_text;
+pub mod day_types;
+pub mod deep_link;
+pub mod demo;
+pub mod dev_errors;
+pub mod diagnostics;
+pub mod egress;
+pub mod external_events;
+pub mod finetune_export;
+pub mod focus;
+pub mod frame_analysis;
+pub mod frame_retention;
+pub mod gaps;
+pub mod history_import;
+pub mod hooks;
+pub mod insights;
+pub mod intents;
+pub mod keywords;
+pub mod live;
+pub mod live_activity;
+pub mod local_only;
+pub mod meetings;
+pub mod metrics;
+pub mod mood_strip;
+pub mod pause_reminder;
+pub mod performance;
+pub mod pipeline;
+pub mod playback;
+pub mod privacy;
+pub mod provenance;
+pub mod provider_comparison;
+pub mod provider_health;
+pub mod quiet_zones;
+pub mod range_summary;
+pub mod records;
+pub mod reencode;
+pub mod review;
+pub mod screen_language;
+pub mod session_titles;
+pub mod share;
+pub mod snapshot_diff;
+pub mod sparse_sessions;
+pub mod standup;
+pub mod startup_check;
+pub mod startup_progress;
 pub mod storage;
+pub mod storage_quota;
+pub mod storage_usage;
+pub mod subcategories;
+pub mod subtitles;
 pub mod summary;
 pub mod system;
+pub mod telemetry;
+pub mod text_timeline;
+pub mod time_tracking;
+pub mod trends;
+pub mod updates;
+pub mod video_claims;
+pub mod web_view;
+pub mod webhooks;
+pub mod week_plan;
+pub mod workflows;
 
 pub use analysis::AnalysisDomain;
 pub use capture::CaptureDomain;
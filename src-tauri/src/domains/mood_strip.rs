@@ -0,0 +1,169 @@
+// 会话色带 - 每分钟取一张截图的平均颜色，拼成一张 1 像素高的 PNG
+//
+// 不用播放视频就能看出一段时间大致在做什么（深色 IDE、白底文档、色彩丰富的视频），
+// 时间线上并排显示一天的色带即可得到当天的视觉指纹。
+// 色带在生成视频、删除截图之前计算，放在会话视频旁的 <视频文件名>.mood.png，
+// 没有截图的分钟为透明像素；视频删除时一并删除
+
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 色带文件后缀
+const STRIP_SUFFIX: &str = ".mood.png";
+
+/// 取平均颜色前缩小到的边长（像素）
+const SAMPLE_SIZE: u32 = 32;
+
+/// 色带最多覆盖的分钟数（避免异常时间窗生成超长图片）
+const MAX_MINUTES: usize = 24 * 60;
+
+/// 会话视频对应的色带路径
+pub fn strip_path(video_path: &Path) -> PathBuf {
+    let mut path = video_path.as_os_str().to_owned();
+    path.push(STRIP_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// 删除会话视频的色带（视频删除时调用）
+pub async fn remove(video_path: &Path) {
+    let _ = tokio::fs::remove_file(strip_path(video_path)).await;
+}
+
+/// 每分钟取最早的一张截图，返回按分钟排列的截图路径（没有截图的分钟为 None）
+pub fn sample_per_minute(frames: &[(DateTime<Utc>, String)]) -> Vec<Option<&str>> {
+    let mut sorted: Vec<&(DateTime<Utc>, String)> = frames.iter().collect();
+    sorted.sort_by_key(|(timestamp, _)| *timestamp);
+    let Some(start) = sorted.first().map(|(timestamp, _)| *timestamp) else {
+        return Vec::new();
+    };
+    let mut minutes: Vec<Option<&str>> = Vec::new();
+    for (timestamp, path) in sorted {
+        let minute = (*timestamp - start).num_minutes() as usize;
+        if minute >= MAX_MINUTES {
+            continue;
+        }
+        if minutes.len() <= minute {
+            minutes.resize(minute + 1, None);
+        }
+        minutes[minute].get_or_insert(path.as_str());
+    }
+    minutes
+}
+
+/// 截图的平均颜色
+fn average_color(path: &str) -> Option<[u8; 3]> {
+    let image = image::open(path)
+        .ok()?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels = image.pixels().len() as u64;
+    if pixels == 0 {
+        return None;
+    }
+    let mut sum = [0u64; 3];
+    for pixel in image.pixels() {
+        for (total, channel) in sum.iter_mut().zip(pixel.0) {
+            *total += channel as u64;
+        }
+    }
+    Some(sum.map(|total| (total / pixels) as u8))
+}
+
+/// 按分钟的颜色生成色带图片
+pub fn render_strip(colors: &[Option<[u8; 3]>]) -> RgbaImage {
+    let mut strip = RgbaImage::new(colors.len().max(1) as u32, 1);
+    for (x, color) in colors.iter().enumerate() {
+        if let Some([r, g, b]) = color {
+            strip.put_pixel(x as u32, 0, Rgba([*r, *g, *b, 255]));
+        }
+    }
+    strip
+}
+
+/// 计算截图的色带并写到视频旁，失败只记录警告
+pub async fn write_for_video(frames: Vec<(DateTime<Utc>, String)>, video_path: &Path) {
+    let output = strip_path(video_path);
+    let result = tokio::task::spawn_blocking(move || {
+        let colors: Vec<Option<[u8; 3]>> = sample_per_minute(&frames)
+            .into_iter()
+            .map(|path| path.and_then(average_color))
+            .collect();
+        if colors.iter().all(Option::is_none) {
+            return Ok(0);
+        }
+        render_strip(&colors).save(&output)?;
+        anyhow::Ok(colors.len())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(0)) => {}
+        Ok(Ok(minutes)) => info!("已生成会话色带（{} 分钟）: {:?}", minutes, video_path),
+        Ok(Err(e)) => warn!("生成会话色带失败 {:?}: {}", video_path, e),
+        Err(e) => warn!("生成会话色带任务异常 {:?}: {}", video_path, e),
+    }
+}
+
+/// 会话色带
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoodStrip {
+    pub session_id: i64,
+    pub start_time: String,
+    pub end_time: String,
+    pub path: String,
+}
+
+/// 某天各会话的色带（没有色带的会话跳过）
+pub async fn day_strips(db: &Database, date: &str) -> Result<Vec<MoodStrip>, String> {
+    let sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    Ok(sessions
+        .into_iter()
+        .filter_map(|session| {
+            let path = strip_path(Path::new(session.video_path.as_ref()?));
+            path.is_file().then(|| MoodStrip {
+                session_id: session.id?,
+                // 数据库中存的是本地时间，与会话列表一样不带时区输出
+                start_time: session.start_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                end_time: session.end_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_sample_per_minute_takes_earliest_frame_and_keeps_gaps() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let frame =
+            |seconds: i64, name: &str| (start + Duration::seconds(seconds), name.to_string());
+        let frames = vec![
+            frame(30, "b"),
+            frame(0, "a"),
+            frame(65, "c"),
+            frame(119, "d"),
+            frame(190, "e"),
+        ];
+        assert_eq!(
+            sample_per_minute(&frames),
+            vec![Some("a"), Some("c"), None, Some("e")]
+        );
+
+        let strip = render_strip(&[Some([10, 20, 30]), None]);
+        assert_eq!(strip.dimensions(), (2, 1));
+        assert_eq!(strip.get_pixel(0, 0), &Rgba([10, 20, 30, 255]));
+        assert_eq!(strip.get_pixel(1, 0)[3], 0);
+    }
+}
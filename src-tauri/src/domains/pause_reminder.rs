@@ -0,0 +1,136 @@
+// 暂停截屏提醒 - 手动暂停后忘记恢复时提醒用户
+//
+// 手动暂停截屏超过设定时长、且电脑明显在使用（最近有键盘鼠标输入）时发送提醒，
+// 仍未恢复时按间隔重复提醒，避免不知不觉出现好几天的记录空档。
+// 锁屏暂停和离开模式不提醒；平台不支持输入空闲检测时无法判断是否在使用，同样不提醒
+
+use crate::capture::input_idle;
+use crate::capture::scheduler::CaptureScheduler;
+use crate::models::PauseReminderSettings;
+use crate::settings::SettingsManager;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tracing::info;
+
+/// 最近一次输入在多少秒内视为正在使用电脑
+const IN_USE_IDLE_SECONDS: u64 = 120;
+/// 检测间隔（秒）
+const TICK_SECONDS: u64 = 60;
+
+/// 暂停提醒状态机（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct PauseReminderTracker {
+    /// 本次手动暂停的开始时间（检测到暂停的时间）
+    paused_since: Option<DateTime<Utc>>,
+    /// 本次暂停最近一次提醒的时间
+    last_reminded: Option<DateTime<Utc>>,
+}
+
+impl PauseReminderTracker {
+    /// 推进一次检测，需要提醒时返回已暂停的分钟数
+    pub fn tick(
+        &mut self,
+        now: DateTime<Utc>,
+        paused: bool,
+        in_use: bool,
+        settings: &PauseReminderSettings,
+    ) -> Option<i64> {
+        if !paused {
+            *self = Self::default();
+            return None;
+        }
+        let paused_since = *self.paused_since.get_or_insert(now);
+        if !settings.enabled || !in_use {
+            return None;
+        }
+
+        let paused_minutes = (now - paused_since).num_minutes();
+        if paused_minutes < settings.after_minutes as i64 {
+            return None;
+        }
+        // 重复间隔为 0 时每次暂停只提醒一次
+        if let Some(last) = self.last_reminded {
+            if settings.repeat_minutes == 0
+                || now - last < Duration::minutes(settings.repeat_minutes as i64)
+            {
+                return None;
+            }
+        }
+
+        self.last_reminded = Some(now);
+        Some(paused_minutes)
+    }
+}
+
+/// 启动暂停提醒任务，需要提醒时调用 `notify`（参数为已暂停的分钟数）
+pub fn start_pause_reminder<F>(
+    scheduler: Arc<CaptureScheduler>,
+    settings: Arc<SettingsManager>,
+    notify: F,
+) where
+    F: Fn(i64) + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        info!("暂停截屏提醒任务已启动");
+        let mut tracker = PauseReminderTracker::default();
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(TICK_SECONDS)).await;
+
+            let persisted = settings.get().await;
+            let away = persisted.away_mode.map_or(false, |away| away.active);
+            let paused = scheduler.is_paused() && !away;
+            let config = persisted.pause_reminder_settings.unwrap_or_default();
+            let in_use = paused
+                && config.enabled
+                && tokio::task::spawn_blocking(input_idle::idle_seconds)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map_or(false, |idle| idle <= IN_USE_IDLE_SECONDS);
+
+            if let Some(paused_minutes) =
+                tracker.tick(crate::storage::local_now(), paused, in_use, &config)
+            {
+                info!("截屏已暂停 {} 分钟且电脑仍在使用，提醒恢复", paused_minutes);
+                notify(paused_minutes);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_reminds_only_when_paused_long_and_in_use() {
+        let settings = PauseReminderSettings {
+            enabled: true,
+            after_minutes: 60,
+            repeat_minutes: 30,
+        };
+        let mut tracker = PauseReminderTracker::default();
+
+        assert_eq!(tracker.tick(at(0), true, true, &settings), None);
+        assert_eq!(tracker.tick(at(59), true, true, &settings), None);
+        // 暂停够久但电脑没人用：不提醒
+        assert_eq!(tracker.tick(at(70), true, false, &settings), None);
+        assert_eq!(tracker.tick(at(75), true, true, &settings), Some(75));
+        // 重复间隔内不再提醒
+        assert_eq!(tracker.tick(at(100), true, true, &settings), None);
+        assert_eq!(tracker.tick(at(105), true, true, &settings), Some(105));
+
+        // 恢复后重新计时
+        assert_eq!(tracker.tick(at(110), false, true, &settings), None);
+        assert_eq!(tracker.tick(at(111), true, true, &settings), None);
+        assert_eq!(tracker.tick(at(150), true, true, &settings), None);
+        assert_eq!(tracker.tick(at(171), true, true, &settings), Some(60));
+    }
+}
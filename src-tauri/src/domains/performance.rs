@@ -0,0 +1,176 @@
+// 性能设置 - 统一管理视频编码、模型分析、数据库批量写入和事件总线的并发与队列上限
+//
+// 设置保存时校验范围，随后立即应用到各子系统（启动时按配置初始化）：
+// - 生成会话视频前先取得编码名额，同时编码的视频数达到上限时排队等待；调小上限不会中断正在编码的视频
+// - 分析队列的并发分析数不超过模型调用上限，下一轮分析生效
+// - 历史记录导入等批量写入按批大小分批提交
+// - 事件总线容量对之后创建的订阅生效，全局通道在重启后生效
+
+use crate::event_bus::EventBus;
+use crate::models::PerformanceSettings;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::Notify;
+use tracing::info;
+
+/// 同时编码的会话视频数范围
+pub const ENCODE_RANGE: RangeInclusive<u8> = 1..=8;
+/// 同时分析的视频数范围
+pub const LLM_CALL_RANGE: RangeInclusive<u8> = 1..=8;
+/// 数据库批量写入条数范围
+pub const DB_BATCH_RANGE: RangeInclusive<u32> = 100..=10_000;
+/// 事件总线容量范围
+pub const EVENT_BUS_CAPACITY_RANGE: RangeInclusive<u32> = 100..=10_000;
+
+static MAX_ENCODES: AtomicUsize = AtomicUsize::new(4);
+static ACTIVE_ENCODES: AtomicUsize = AtomicUsize::new(0);
+static MAX_LLM_CALLS: AtomicUsize = AtomicUsize::new(4);
+static DB_BATCH_SIZE: AtomicUsize = AtomicUsize::new(1000);
+
+/// 编码名额释放或上限调大时唤醒等待者
+fn encode_released() -> &'static Notify {
+    static NOTIFY: OnceLock<Notify> = OnceLock::new();
+    NOTIFY.get_or_init(Notify::new)
+}
+
+/// 校验性能设置
+pub fn validate(settings: &PerformanceSettings) -> Result<(), String> {
+    fn check<T: PartialOrd + std::fmt::Display>(
+        name: &str,
+        value: T,
+        range: RangeInclusive<T>,
+    ) -> Result<(), String> {
+        if range.contains(&value) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}应在 {} 到 {} 之间: {}",
+                name,
+                range.start(),
+                range.end(),
+                value
+            ))
+        }
+    }
+
+    check(
+        "同时编码的视频数",
+        settings.max_concurrent_encodes,
+        ENCODE_RANGE,
+    )?;
+    check(
+        "同时分析的视频数",
+        settings.max_concurrent_llm_calls,
+        LLM_CALL_RANGE,
+    )?;
+    check("数据库批量写入条数", settings.db_batch_size, DB_BATCH_RANGE)?;
+    check(
+        "事件总线容量",
+        settings.event_bus_capacity,
+        EVENT_BUS_CAPACITY_RANGE,
+    )
+}
+
+/// 应用性能设置（启动时及设置变更时调用）
+pub fn apply(settings: &PerformanceSettings, event_bus: Option<&EventBus>) {
+    MAX_ENCODES.store(
+        settings.max_concurrent_encodes.max(1) as usize,
+        Ordering::SeqCst,
+    );
+    MAX_LLM_CALLS.store(
+        settings.max_concurrent_llm_calls.max(1) as usize,
+        Ordering::SeqCst,
+    );
+    DB_BATCH_SIZE.store(settings.db_batch_size.max(1) as usize, Ordering::SeqCst);
+    if let Some(event_bus) = event_bus {
+        event_bus.set_capacity(settings.event_bus_capacity as usize);
+    }
+    encode_released().notify_waiters();
+    info!(
+        "性能设置已应用: 编码并发 {}，分析并发 {}，批量写入 {} 条，事件总线容量 {}",
+        settings.max_concurrent_encodes,
+        settings.max_concurrent_llm_calls,
+        settings.db_batch_size,
+        settings.event_bus_capacity
+    );
+}
+
+/// 同时分析的视频数上限
+pub fn max_llm_calls() -> usize {
+    MAX_LLM_CALLS.load(Ordering::SeqCst)
+}
+
+/// 数据库批量写入时每批的记录数
+pub fn db_batch_size() -> usize {
+    DB_BATCH_SIZE.load(Ordering::SeqCst)
+}
+
+/// 编码名额，释放时归还
+pub struct EncodePermit(());
+
+impl Drop for EncodePermit {
+    fn drop(&mut self) {
+        ACTIVE_ENCODES.fetch_sub(1, Ordering::SeqCst);
+        encode_released().notify_waiters();
+    }
+}
+
+/// 取得一个编码名额，达到上限时等待
+pub async fn acquire_encode() -> EncodePermit {
+    loop {
+        // 先登记等待再检查名额，避免检查之后、等待之前的释放被错过
+        let released = encode_released().notified();
+        tokio::pin!(released);
+        released.as_mut().enable();
+
+        let limit = MAX_ENCODES.load(Ordering::SeqCst);
+        if ACTIVE_ENCODES
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                (active < limit).then_some(active + 1)
+            })
+            .is_ok()
+        {
+            return EncodePermit(());
+        }
+        released.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_validates_and_limits_encodes_live() {
+        let mut settings = PerformanceSettings::default();
+        assert!(validate(&settings).is_ok());
+        settings.max_concurrent_encodes = 0;
+        assert!(validate(&settings).is_err());
+        settings.max_concurrent_encodes = 1;
+        settings.db_batch_size = 50;
+        assert!(validate(&settings).is_err());
+        settings.db_batch_size = 500;
+
+        apply(&settings, None);
+        assert_eq!(db_batch_size(), 500);
+        let first = acquire_encode().await;
+        // 上限为 1 时第二个编码需要等待
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), acquire_encode())
+                .await
+                .is_err()
+        );
+
+        // 调大上限后立即可以取得名额
+        settings.max_concurrent_encodes = 2;
+        apply(&settings, None);
+        let second = tokio::time::timeout(Duration::from_millis(50), acquire_encode())
+            .await
+            .expect("调大上限后应取得名额");
+        drop(first);
+        drop(second);
+        apply(&PerformanceSettings::default(), None);
+    }
+}
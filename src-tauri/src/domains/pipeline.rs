@@ -0,0 +1,324 @@
+// 会话处理流程 - 把会话生成视频之后的处理拆成可按方案排序和开关的步骤
+//
+// 步骤分两个阶段：
+// - 分析阶段（启发式标注、LLM 分析）依次修改同一份卡片列表，结束后保存会话
+// - 交付阶段（Notion 同步、webhook）读取已保存的会话，失败只记录日志
+// 方案中的顺序只在阶段内生效，交付步骤排在分析步骤之前时仍在保存之后执行。
+// 启发式标注只作用于之前步骤生成的卡片，因此通常放在 LLM 分析之后；
+// 关闭 LLM 分析时会话保存为一张覆盖整个时间窗的占位卡片
+
+use crate::capture::scheduler::SessionWindow;
+use crate::llm::{SessionSummary, TimelineCard, VideoSegment};
+use crate::models::{PipelineProfile, PipelineSettings, PipelineStep, PrivacyLevel};
+use crate::notion::NotionManager;
+use crate::settings::SettingsManager;
+use crate::storage::{Database, TimelineCardRecord};
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 步骤所属阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// 保存会话之前，修改卡片
+    Analysis,
+    /// 保存会话之后，把结果发往其他地方
+    Delivery,
+}
+
+pub fn phase(step: PipelineStep) -> Phase {
+    match step {
+        PipelineStep::HeuristicTagger | PipelineStep::LlmAnalyzer => Phase::Analysis,
+        PipelineStep::NotionSync | PipelineStep::Webhook => Phase::Delivery,
+    }
+}
+
+/// 当前方案（找不到时使用第一个方案，没有方案时使用默认方案）
+fn active_profile(settings: &PipelineSettings) -> PipelineProfile {
+    settings
+        .profiles
+        .iter()
+        .find(|profile| profile.name == settings.active_profile)
+        .or_else(|| settings.profiles.first())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 当前方案中启用的步骤（按阶段排列，阶段内保持方案中的顺序，重复的步骤只保留第一个）
+pub fn ordered_steps(settings: &PipelineSettings) -> Vec<PipelineStep> {
+    let mut steps: Vec<PipelineStep> = Vec::new();
+    for config in active_profile(settings).steps {
+        if config.enabled && !steps.contains(&config.step) {
+            steps.push(config.step);
+        }
+    }
+    steps.sort_by_key(|step| phase(*step));
+    steps
+}
+
+/// 一个会话在流程中传递的数据
+pub struct SessionContext {
+    pub session_id: i64,
+    pub window: SessionWindow,
+    pub video_path: Option<String>,
+    /// LLM 分析结果（LLM 分析步骤未执行时为空）
+    pub segments: Vec<VideoSegment>,
+    pub timeline_cards: Vec<TimelineCard>,
+    pub segment_call_id: Option<i64>,
+    pub timeline_call_id: Option<i64>,
+    /// 分块分析时每个分段所在块的调用 ID（与 segments 一一对应，为空时统一使用 segment_call_id）
+    pub segment_call_ids: Vec<Option<i64>>,
+    /// 待保存的卡片
+    pub card_records: Vec<TimelineCardRecord>,
+    /// 是否已有分析结果（LLM 分析步骤完成后为 true）
+    pub analyzed: bool,
+    /// 保存后的会话标题和总结（交付阶段使用）
+    pub title: String,
+    pub summary: Option<SessionSummary>,
+}
+
+impl SessionContext {
+    pub fn new(session_id: i64, window: SessionWindow, video_path: Option<String>) -> Self {
+        Self {
+            session_id,
+            window,
+            video_path,
+            segments: Vec::new(),
+            timeline_cards: Vec::new(),
+            segment_call_id: None,
+            timeline_call_id: None,
+            segment_call_ids: Vec::new(),
+            card_records: Vec::new(),
+            analyzed: false,
+            title: String::new(),
+            summary: None,
+        }
+    }
+}
+
+/// 流程中的一个步骤
+#[async_trait::async_trait]
+pub trait SessionStage: Send + Sync {
+    fn step(&self) -> PipelineStep;
+
+    async fn run(&self, ctx: &mut SessionContext) -> Result<()>;
+}
+
+/// 按方案排好顺序的步骤
+pub struct SessionPipeline<'a> {
+    stages: Vec<Box<dyn SessionStage + 'a>>,
+}
+
+impl<'a> SessionPipeline<'a> {
+    /// 从可用的步骤中按方案的顺序取出启用的步骤
+    pub fn new(steps: &[PipelineStep], available: Vec<Box<dyn SessionStage + 'a>>) -> Self {
+        let mut available: Vec<Option<Box<dyn SessionStage + 'a>>> =
+            available.into_iter().map(Some).collect();
+        let stages = steps
+            .iter()
+            .filter_map(|step| {
+                available
+                    .iter_mut()
+                    .find(|stage| matches!(stage, Some(stage) if stage.step() == *step))
+                    .and_then(Option::take)
+            })
+            .collect();
+        Self { stages }
+    }
+
+    /// 执行一个阶段的步骤：分析阶段出错时中止，交付阶段出错只记录日志
+    pub async fn run_phase(&self, current: Phase, ctx: &mut SessionContext) -> Result<()> {
+        for stage in self
+            .stages
+            .iter()
+            .filter(|stage| phase(stage.step()) == current)
+        {
+            match stage.run(ctx).await {
+                Ok(()) => {}
+                Err(e) if current == Phase::Delivery => {
+                    warn!(
+                        "会话 {} 的处理步骤 {:?} 失败: {}",
+                        ctx.session_id,
+                        stage.step(),
+                        e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 启发式标注：浏览器标签页修正 app_sites、长时间通话归为会议、子类别归一化、
+/// 应用画像覆盖类别、低置信度卡片进入复核队列，并把剪贴板内容写进卡片详情
+pub struct HeuristicTagger {
+    pub db: Arc<Database>,
+    pub settings: Arc<SettingsManager>,
+}
+
+#[async_trait::async_trait]
+impl SessionStage for HeuristicTagger {
+    fn step(&self) -> PipelineStep {
+        PipelineStep::HeuristicTagger
+    }
+
+    async fn run(&self, ctx: &mut SessionContext) -> Result<()> {
+        if ctx.card_records.is_empty() {
+            return Ok(());
+        }
+        let window = &ctx.window;
+
+        let config = self.settings.get().await;
+        let app_profiles = config.app_profiles.unwrap_or_default();
+        let subcategories = super::subcategories::SubcategoryNormalizer::new(
+            &config.subcategory_synonyms.unwrap_or_default(),
+        );
+        let review_threshold = config
+            .review_confidence_threshold
+            .unwrap_or(super::review::DEFAULT_REVIEW_THRESHOLD);
+        // 浏览器扩展上报的标签页比画面识别更准确，先用它修正 app_sites
+        let browser_events =
+            super::browser::load_browser_events(&self.db, window.start, window.end).await;
+        // 开启音频活动检测时，麦克风长时间在用的卡片归为会议（会议窗口不在屏幕上时也能识别）
+        let audio_records =
+            super::audio_presence::load_records(&self.db, window.start, window.end).await;
+        for card in ctx.card_records.iter_mut() {
+            super::browser::apply_browser_domains(card, &browser_events);
+            super::audio_presence::mark_call_card(card, &audio_records, window.start, window.end);
+            subcategories.apply(card);
+            super::app_profiles::apply_app_profiles(card, &app_profiles);
+            super::review::mark_for_review(card, review_threshold);
+        }
+        // 开启剪贴板记录时，把卡片时间内复制的大段内容写进卡片详情
+        let clipboard_events =
+            super::clipboard::load_events(&self.db, window.start, window.end).await;
+        super::clipboard::annotate_cards(
+            &mut ctx.card_records,
+            &clipboard_events,
+            window.start,
+            window.end,
+        );
+        Ok(())
+    }
+}
+
+/// Notion 同步（后台执行，不阻塞流程），隐私敏感度高于设置值的会话不同步
+pub struct NotionSyncer {
+    pub db: Arc<Database>,
+    pub manager: Arc<NotionManager>,
+    pub max_privacy_level: PrivacyLevel,
+}
+
+#[async_trait::async_trait]
+impl SessionStage for NotionSyncer {
+    fn step(&self) -> PipelineStep {
+        PipelineStep::NotionSync
+    }
+
+    async fn run(&self, ctx: &mut SessionContext) -> Result<()> {
+        if !self.manager.is_enabled().await {
+            return Ok(());
+        }
+        // 保存卡片时尚未评估敏感度，这里先评估（结果同时保存到卡片）
+        let level = super::privacy::rate_session(&self.db, ctx.session_id)
+            .await
+            .map_err(|e| anyhow!(e))?;
+        if level > self.max_privacy_level {
+            info!(
+                "会话 {} 隐私敏感度为{}，跳过 Notion 同步",
+                ctx.session_id,
+                level.display_name()
+            );
+            return Ok(());
+        }
+        let session = self.db.get_session(ctx.session_id).await?;
+        info!("触发 Notion 同步：会话 {}", ctx.session_id);
+        let details = crate::notion::load_session_details(&self.db, &session).await;
+        self.manager.sync_session_async(session, details).await;
+        Ok(())
+    }
+}
+
+/// 把会话、分段和卡片签名后推送到 webhook 地址（后台投递，失败时按退避间隔重试）
+pub struct WebhookNotifier {
+    pub db: Arc<Database>,
+    pub url: String,
+    pub secret: String,
+    /// 敏感度高于该级别的卡片不推送
+    pub max_privacy_level: PrivacyLevel,
+}
+
+#[async_trait::async_trait]
+impl SessionStage for WebhookNotifier {
+    fn step(&self) -> PipelineStep {
+        PipelineStep::Webhook
+    }
+
+    async fn run(&self, ctx: &mut SessionContext) -> Result<()> {
+        let url = self.url.trim();
+        if url.is_empty() {
+            return Err(anyhow!("未设置 webhook 地址"));
+        }
+        super::local_only::ensure_local_endpoint(url).map_err(|e| anyhow!(e))?;
+        super::webhooks::spawn_delivery(
+            self.db.clone(),
+            url.to_string(),
+            self.secret.clone(),
+            ctx.session_id,
+            self.max_privacy_level,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PipelineStepConfig;
+
+    #[test]
+    fn test_ordered_steps_keep_profile_order_within_phase() {
+        let step = |step, enabled| PipelineStepConfig { step, enabled };
+        let settings = PipelineSettings {
+            active_profile: "仅本地".to_string(),
+            profiles: vec![
+                PipelineProfile::default(),
+                PipelineProfile {
+                    name: "仅本地".to_string(),
+                    steps: vec![
+                        step(PipelineStep::Webhook, true),
+                        step(PipelineStep::HeuristicTagger, true),
+                        step(PipelineStep::NotionSync, false),
+                        step(PipelineStep::LlmAnalyzer, true),
+                        step(PipelineStep::HeuristicTagger, false),
+                    ],
+                },
+            ],
+            webhook_url: String::new(),
+            webhook_secret: String::new(),
+        };
+        assert_eq!(
+            ordered_steps(&settings),
+            vec![
+                PipelineStep::HeuristicTagger,
+                PipelineStep::LlmAnalyzer,
+                PipelineStep::Webhook
+            ]
+        );
+
+        // 当前方案不存在时回退到第一个方案
+        let fallback = PipelineSettings {
+            active_profile: "已删除".to_string(),
+            ..settings
+        };
+        assert_eq!(
+            ordered_steps(&fallback),
+            vec![
+                PipelineStep::LlmAnalyzer,
+                PipelineStep::HeuristicTagger,
+                PipelineStep::NotionSync
+            ]
+        );
+    }
+}
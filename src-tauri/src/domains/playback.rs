@@ -0,0 +1,248 @@
+// 视频播放位置与时间线卡片的映射
+//
+// 会话视频由抽帧后的截图合成：每隔 N 张原始截图取一帧，每帧展示 1 秒后再按倍速加速，
+// 因此视频中的 1 秒对应真实时间 speed_multiplier × 每帧代表的秒数。
+// 智能变速的视频每帧展示时长不同，按元数据中的变速映射分段换算。
+// 前端点击卡片跳转视频、拖动进度条高亮卡片都通过这里换算，避免在前端各自实现
+
+use super::metrics::parse_series_time;
+use crate::settings::SettingsManager;
+use crate::storage::{Database, Session, TimelineCardRecord};
+use crate::video::{SpeedMap, VideoMetadata};
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use std::path::Path;
+
+/// 视频时间换算参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoTimeMapping {
+    /// 视频倍速
+    pub speed_multiplier: f32,
+    /// 视频中每一帧代表的真实秒数（抽帧间隔 × 截屏间隔）
+    pub seconds_per_frame: f64,
+    /// 智能变速的时间映射（固定倍速时为 None）
+    pub speed_map: Option<SpeedMap>,
+}
+
+impl VideoTimeMapping {
+    /// 视频中 1 秒对应的真实秒数
+    pub fn real_seconds_per_video_second(&self) -> f64 {
+        self.speed_multiplier.max(0.01) as f64 * self.seconds_per_frame
+    }
+
+    /// 视频播放位置 → 距会话开始的真实秒数
+    pub fn video_to_real(&self, video_position_secs: f64) -> f64 {
+        let position = video_position_secs.max(0.0);
+        match &self.speed_map {
+            Some(map) => map.display_to_real(position * self.speed_multiplier.max(0.01) as f64),
+            None => position * self.real_seconds_per_video_second(),
+        }
+    }
+
+    /// 距会话开始的真实秒数 → 视频播放位置
+    pub fn real_to_video(&self, real_offset_secs: f64) -> f64 {
+        let offset = real_offset_secs.max(0.0);
+        match &self.speed_map {
+            Some(map) => map.real_to_display(offset) / self.speed_multiplier.max(0.01) as f64,
+            None => offset / self.real_seconds_per_video_second(),
+        }
+    }
+}
+
+/// 视频位置对应的卡片
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardAtPosition {
+    /// 当前位置所在的卡片（位于卡片间隙时为 None）
+    pub card_id: Option<i64>,
+    /// 对应的真实时间 (YYYY-MM-DD HH:MM:SS)
+    pub real_time: String,
+    /// 距会话开始的真实秒数
+    pub real_offset_secs: f64,
+}
+
+/// 卡片对应的视频位置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardVideoRange {
+    pub card_id: i64,
+    /// 卡片开始时的视频位置（秒）
+    pub start_secs: f64,
+    /// 卡片结束时的视频位置（秒）
+    pub end_secs: f64,
+}
+
+/// 卡片的本地时间范围
+fn card_range(session: &Session, card: &TimelineCardRecord) -> (NaiveDateTime, NaiveDateTime) {
+    (
+        parse_series_time(&card.start_time, session.start_time, session.end_time),
+        parse_series_time(&card.end_time, session.start_time, session.end_time),
+    )
+}
+
+/// 查找视频位置对应的卡片
+pub fn find_card_at(
+    session: &Session,
+    cards: &[TimelineCardRecord],
+    mapping: &VideoTimeMapping,
+    video_position_secs: f64,
+) -> CardAtPosition {
+    let real_offset_secs = mapping.video_to_real(video_position_secs);
+    let time =
+        session.start_time.naive_utc() + Duration::milliseconds((real_offset_secs * 1000.0) as i64);
+
+    let card_id = cards
+        .iter()
+        .filter(|card| {
+            let (start, end) = card_range(session, card);
+            start <= time && time < end
+        })
+        .last()
+        .and_then(|card| card.id);
+
+    CardAtPosition {
+        card_id,
+        real_time: time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        real_offset_secs,
+    }
+}
+
+/// 计算卡片在视频中的位置范围
+pub fn card_video_range(
+    session: &Session,
+    card: &TimelineCardRecord,
+    mapping: &VideoTimeMapping,
+) -> CardVideoRange {
+    let session_start = session.start_time.naive_utc();
+    let (start, end) = card_range(session, card);
+    let offset = |t: NaiveDateTime| (t - session_start).num_milliseconds() as f64 / 1000.0;
+
+    CardVideoRange {
+        card_id: card.id.unwrap_or_default(),
+        start_secs: mapping.real_to_video(offset(start)),
+        end_secs: mapping.real_to_video(offset(end)),
+    }
+}
+
+/// 抽帧后视频中相邻两帧的真实间隔（抽帧间隔 × 截屏间隔）
+pub fn frame_interval_secs(capture_interval_secs: u64) -> f64 {
+    crate::FRAME_SAMPLE_INTERVAL_SECONDS as f64 * capture_interval_secs.max(1) as f64
+}
+
+/// 获取会话视频的换算参数（按当前截屏和视频设置）
+pub fn current_mapping(speed_multiplier: f32, capture_interval_secs: u64) -> VideoTimeMapping {
+    VideoTimeMapping {
+        speed_multiplier,
+        seconds_per_frame: frame_interval_secs(capture_interval_secs),
+        speed_map: None,
+    }
+}
+
+/// 按视频生成时记录的元数据换算（修改设置后旧视频仍能正确对应）
+pub fn metadata_mapping(metadata: &VideoMetadata) -> VideoTimeMapping {
+    VideoTimeMapping {
+        speed_multiplier: metadata.speed_multiplier,
+        seconds_per_frame: metadata.frame_interval_secs / metadata.frame_display_secs.max(0.01),
+        speed_map: metadata.speed_map(),
+    }
+}
+
+/// 获取会话视频的换算参数（优先使用视频元数据，没有元数据的旧视频按当前设置）
+pub async fn session_mapping(session: &Session, settings: &SettingsManager) -> VideoTimeMapping {
+    if let Some(video_path) = &session.video_path {
+        if let Some(metadata) = VideoMetadata::load(Path::new(video_path)).await {
+            return metadata_mapping(&metadata);
+        }
+    }
+
+    let config = settings.get().await;
+    current_mapping(
+        config.video_config.speed_multiplier,
+        config.capture_interval,
+    )
+}
+
+/// 加载会话及其卡片
+pub async fn load_session_cards(
+    db: &Database,
+    session_id: i64,
+) -> Result<(Session, Vec<TimelineCardRecord>), String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+    Ok((session, cards))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn session() -> Session {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        }
+    }
+
+    fn card(id: i64, start: &str, end: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(id),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: String::new(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: session().start_time,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_between_video_and_cards() {
+        // 8 倍速、每 5 秒取一帧：视频 1 秒 = 真实 40 秒
+        let mapping = current_mapping(8.0, 1);
+        let session = session();
+        let cards = vec![card(1, "00:00", "05:00"), card(2, "05:00", "15:00")];
+
+        let at = find_card_at(&session, &cards, &mapping, 9.0);
+        assert_eq!(at.card_id, Some(2));
+        assert_eq!(at.real_offset_secs, 360.0);
+        assert_eq!(at.real_time, "2025-10-09 09:06:00");
+
+        let range = card_video_range(&session, &cards[1], &mapping);
+        assert_eq!(range.start_secs, 7.5);
+        assert_eq!(range.end_secs, 22.5);
+    }
+}
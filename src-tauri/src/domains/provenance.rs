@@ -0,0 +1,235 @@
+// 时间线卡片溯源 - 说明一张卡片由哪些分段、哪次 LLM 调用生成
+//
+// 卡片只直接记录生成它的时间线调用（llm_call_id）；参与生成的分段按时间重叠从会话分段中找出，
+// 模型、提示词版本从 LLM 调用记录读取，视频位置按会话视频的时间换算参数计算。
+// 用户可据此回看原始视频片段和请求/响应，理解卡片内容的来源
+
+use super::metrics::parse_series_time;
+use super::playback::{card_video_range, CardVideoRange, VideoTimeMapping};
+use crate::storage::{LLMCallRecord, Session, TimelineCardRecord, VideoSegmentRecord};
+use serde::Serialize;
+
+/// 参与生成卡片的分段
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceSegment {
+    pub id: i64,
+    pub start_timestamp: String,
+    pub end_timestamp: String,
+    pub description: String,
+    pub llm_call_id: Option<i64>,
+}
+
+/// 生成卡片（或分段）的 LLM 调用摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceCall {
+    pub id: i64,
+    pub provider: String,
+    pub model: String,
+    pub call_type: String,
+    pub prompt_version: Option<String>,
+    pub status_code: Option<i32>,
+    pub latency_ms: Option<i64>,
+    pub created_at: String,
+}
+
+/// 卡片溯源信息
+#[derive(Debug, Clone, Serialize)]
+pub struct CardProvenance {
+    pub card_id: i64,
+    pub session_id: i64,
+    /// 生成卡片的时间线调用（调用记录已被清理时为 None）
+    pub timeline_call: Option<ProvenanceCall>,
+    /// 生成相关分段的分段调用（去重）
+    pub segment_calls: Vec<ProvenanceCall>,
+    /// 与卡片时间范围重叠的分段
+    pub segments: Vec<ProvenanceSegment>,
+    pub segment_ids: Vec<i64>,
+    /// 会话视频路径（已归档或删除时为 None）
+    pub video_path: Option<String>,
+    /// 卡片在会话视频中的位置
+    pub video_range: CardVideoRange,
+}
+
+fn summarize_call(call: &LLMCallRecord) -> Option<ProvenanceCall> {
+    Some(ProvenanceCall {
+        id: call.id?,
+        provider: call.provider.clone(),
+        model: call.model.clone(),
+        call_type: call.call_type.clone(),
+        prompt_version: call.prompt_version.clone(),
+        status_code: call.status_code,
+        latency_ms: call.latency_ms,
+        created_at: call.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+    })
+}
+
+/// 根据会话数据组装卡片的溯源信息
+pub fn build_card_provenance(
+    session: &Session,
+    card: &TimelineCardRecord,
+    segments: &[VideoSegmentRecord],
+    calls: &[LLMCallRecord],
+    mapping: &VideoTimeMapping,
+) -> CardProvenance {
+    let parse = |value: &str| parse_series_time(value, session.start_time, session.end_time);
+    let (card_start, card_end) = (parse(&card.start_time), parse(&card.end_time));
+
+    // 与卡片时间范围有交集的分段（零长度卡片取覆盖其时间点的分段）
+    let segments: Vec<ProvenanceSegment> = segments
+        .iter()
+        .filter(|segment| {
+            let (start, end) = (
+                parse(&segment.start_timestamp),
+                parse(&segment.end_timestamp),
+            );
+            start < card_end.max(card_start + chrono::Duration::seconds(1)) && card_start < end
+        })
+        .filter_map(|segment| {
+            Some(ProvenanceSegment {
+                id: segment.id?,
+                start_timestamp: segment.start_timestamp.clone(),
+                end_timestamp: segment.end_timestamp.clone(),
+                description: segment.description.clone(),
+                llm_call_id: segment.llm_call_id,
+            })
+        })
+        .collect();
+
+    let find_call = |id: Option<i64>| {
+        id.and_then(|id| calls.iter().find(|call| call.id == Some(id)))
+            .and_then(summarize_call)
+    };
+
+    let mut segment_call_ids: Vec<i64> = segments.iter().filter_map(|s| s.llm_call_id).collect();
+    segment_call_ids.sort_unstable();
+    segment_call_ids.dedup();
+
+    CardProvenance {
+        card_id: card.id.unwrap_or_default(),
+        session_id: card.session_id,
+        timeline_call: find_call(card.llm_call_id),
+        segment_calls: segment_call_ids
+            .into_iter()
+            .filter_map(|id| find_call(Some(id)))
+            .collect(),
+        segment_ids: segments.iter().map(|s| s.id).collect(),
+        segments,
+        video_path: card
+            .video_preview_path
+            .clone()
+            .or_else(|| session.video_path.clone()),
+        video_range: card_video_range(session, card, mapping),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::playback::current_mapping;
+    use chrono::{DateTime, Duration, Utc};
+
+    fn session() -> Session {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: String::new(),
+            summary: String::new(),
+            video_path: Some("/videos/a.mp4".to_string()),
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        }
+    }
+
+    fn segment(id: i64, start: &str, end: &str, call: i64) -> VideoSegmentRecord {
+        VideoSegmentRecord {
+            id: Some(id),
+            session_id: 1,
+            llm_call_id: Some(call),
+            start_timestamp: start.to_string(),
+            end_timestamp: end.to_string(),
+            description: format!("分段 {}", id),
+            created_at: session().start_time,
+        }
+    }
+
+    fn call(id: i64, call_type: &str) -> LLMCallRecord {
+        LLMCallRecord {
+            id: Some(id),
+            session_id: Some(1),
+            provider: "openai".to_string(),
+            model: "qwen-vl-max-latest".to_string(),
+            call_type: call_type.to_string(),
+            request_headers: "{}".to_string(),
+            request_body: "{}".to_string(),
+            response_headers: None,
+            response_body: None,
+            status_code: Some(200),
+            error_message: None,
+            latency_ms: Some(1200),
+            token_usage: None,
+            created_at: session().start_time,
+            prompt_version: Some("qwen-v1".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_provenance_links_overlapping_segments_and_calls() {
+        let card = TimelineCardRecord {
+            id: Some(7),
+            session_id: 1,
+            llm_call_id: Some(11),
+            start_time: "05:00".to_string(),
+            end_time: "10:00".to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: String::new(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: session().start_time,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let segments = vec![
+            segment(1, "00:00", "05:00", 10),
+            segment(2, "04:00", "08:00", 10),
+            segment(3, "08:00", "15:00", 10),
+        ];
+        let calls = vec![call(10, "segment_video"), call(11, "generate_timeline")];
+
+        let provenance = build_card_provenance(
+            &session(),
+            &card,
+            &segments,
+            &calls,
+            &current_mapping(8.0, 1),
+        );
+        assert_eq!(provenance.segment_ids, vec![2, 3]);
+        assert_eq!(
+            provenance.timeline_call.unwrap().call_type,
+            "generate_timeline"
+        );
+        assert_eq!(provenance.segment_calls.len(), 1);
+        assert_eq!(
+            provenance.segment_calls[0].prompt_version.as_deref(),
+            Some("qwen-v1")
+        );
+        assert_eq!(provenance.video_path.as_deref(), Some("/videos/a.mp4"));
+        assert_eq!(provenance.video_range.start_secs, 7.5);
+    }
+}
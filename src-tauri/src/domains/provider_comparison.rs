@@ -0,0 +1,552 @@
+// 服务商对比 - 同一会话视频交给多个服务商分析，并排比较输出、耗时和 token 用量
+//
+// 每个服务商使用独立的 LLM 实例（不影响正在进行的自动分析），依次分析会话视频，
+// 结果保存在 provider_comparisons 表（同一次对比共用一个 run_id），不修改会话原有的卡片。
+// 差异按分钟对齐各服务商卡片的类别，给出一致率和类别不一致的时段，
+// 帮助用户在切换服务商前判断哪个对自己的工作分类更准确
+
+use super::analysis::AnalysisDomain;
+use super::metrics::parse_series_time;
+use super::storage::StorageDomain;
+use crate::llm::{absolutize_analysis, TimelineAnalysis, TimelineCard};
+use crate::models::{LLMProviderConfig, VideoUploadConfig};
+use crate::storage::{Database, ProviderComparisonRecord, Session};
+use crate::video::VideoMetadata;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// 一次最多对比的服务商数
+pub const MAX_COMPARISON_PROVIDERS: usize = 4;
+
+/// 参与对比的服务商
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComparisonCandidate {
+    pub provider: String,
+    /// 服务商配置；为空时，与当前服务商相同则沿用当前配置
+    #[serde(default)]
+    pub config: Option<LLMProviderConfig>,
+}
+
+/// 单个服务商的输出
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderOutput {
+    pub provider: String,
+    pub model: String,
+    /// 对比中的显示名称（同一服务商的不同模型可以同时对比）
+    pub label: String,
+    pub error: Option<String>,
+    pub latency_ms: i64,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub cards: Vec<TimelineCard>,
+}
+
+/// 各服务商类别不一致的时段
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DisagreementSpan {
+    /// 开始时间（HH:MM）
+    pub start: String,
+    /// 结束时间（HH:MM，不含）
+    pub end: String,
+    /// 各服务商在该时段给出的类别（没有卡片覆盖时为空字符串）
+    pub categories: BTreeMap<String, String>,
+}
+
+/// 对比差异
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ComparisonDiff {
+    /// 各服务商每个类别的分钟数
+    pub category_minutes: BTreeMap<String, BTreeMap<String, i64>>,
+    /// 所有服务商类别一致的分钟占比（成功的服务商少于两个时为空）
+    pub agreement: Option<f64>,
+    pub disagreements: Vec<DisagreementSpan>,
+}
+
+/// 一次对比
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonRun {
+    pub run_id: String,
+    pub session_id: i64,
+    pub created_at: String,
+    pub outputs: Vec<ProviderOutput>,
+    pub diff: ComparisonDiff,
+}
+
+/// 从 token 用量 JSON 中读取输入、输出 token 数（兼容 OpenAI 与 Anthropic 的字段名）
+fn token_counts(usage: &str) -> (Option<i64>, Option<i64>) {
+    let Ok(value) = serde_json::from_str::<Value>(usage) else {
+        return (None, None);
+    };
+    let read = |keys: &[&str]| keys.iter().find_map(|key| value.get(*key)?.as_i64());
+    (
+        read(&["input_tokens", "prompt_tokens"]),
+        read(&["output_tokens", "completion_tokens"]),
+    )
+}
+
+fn add_tokens(total: Option<i64>, value: Option<i64>) -> Option<i64> {
+    match (total, value) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 显示名称：模型为空时只用服务商名，重名时追加序号
+fn labels(outputs: &[(String, String)]) -> Vec<String> {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    outputs
+        .iter()
+        .map(|(provider, model)| {
+            let base = if model.is_empty() {
+                provider.clone()
+            } else {
+                format!("{} / {}", provider, model)
+            };
+            let count = seen.entry(base.clone()).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                format!("{} #{}", base, count)
+            } else {
+                base
+            }
+        })
+        .collect()
+}
+
+/// 按分钟对齐各服务商卡片的类别，计算差异
+pub fn diff_outputs(
+    outputs: &[ProviderOutput],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ComparisonDiff {
+    let parse = |value: &str| parse_series_time(value, start, end);
+    let ranges: Vec<(&str, Vec<(NaiveDateTime, NaiveDateTime, String)>)> = outputs
+        .iter()
+        .filter(|output| output.error.is_none())
+        .map(|output| {
+            let cards = output
+                .cards
+                .iter()
+                .map(|card| {
+                    (
+                        parse(&card.start_time),
+                        parse(&card.end_time),
+                        card.category.to_lowercase(),
+                    )
+                })
+                .collect();
+            (output.label.as_str(), cards)
+        })
+        .collect();
+
+    let mut diff = ComparisonDiff::default();
+    for (label, _) in &ranges {
+        diff.category_minutes
+            .insert(label.to_string(), BTreeMap::new());
+    }
+
+    let session_start = start.naive_utc();
+    let total_minutes = (end - start).num_minutes().max(0);
+    let mut agreed = 0;
+    let mut current: Option<(NaiveDateTime, NaiveDateTime, BTreeMap<String, String>)> = None;
+    for minute in 0..total_minutes {
+        let minute_start = session_start + Duration::minutes(minute);
+        let midpoint = minute_start + Duration::seconds(30);
+        let categories: BTreeMap<String, String> = ranges
+            .iter()
+            .map(|(label, cards)| {
+                let category = cards
+                    .iter()
+                    .find(|(card_start, card_end, _)| {
+                        *card_start <= midpoint && midpoint < *card_end
+                    })
+                    .map(|(_, _, category)| category.clone())
+                    .unwrap_or_default();
+                (label.to_string(), category)
+            })
+            .collect();
+
+        for (label, category) in &categories {
+            if !category.is_empty() {
+                *diff
+                    .category_minutes
+                    .entry(label.clone())
+                    .or_default()
+                    .entry(category.clone())
+                    .or_insert(0) += 1;
+            }
+        }
+
+        let unanimous = categories
+            .values()
+            .all(|c| Some(c) == categories.values().next());
+        if unanimous {
+            agreed += 1;
+        }
+
+        let minute_end = minute_start + Duration::minutes(1);
+        current = match current.take() {
+            Some((span_start, _, span)) if !unanimous && span == categories => {
+                Some((span_start, minute_end, span))
+            }
+            previous => {
+                if let Some(span) = previous {
+                    diff.disagreements.push(to_span(span));
+                }
+                (!unanimous).then_some((minute_start, minute_end, categories))
+            }
+        };
+    }
+    if let Some(span) = current {
+        diff.disagreements.push(to_span(span));
+    }
+
+    if ranges.len() >= 2 && total_minutes > 0 {
+        diff.agreement = Some(agreed as f64 / total_minutes as f64);
+    }
+    diff
+}
+
+fn to_span(
+    (start, end, categories): (NaiveDateTime, NaiveDateTime, BTreeMap<String, String>),
+) -> DisagreementSpan {
+    DisagreementSpan {
+        start: start.format("%H:%M").to_string(),
+        end: end.format("%H:%M").to_string(),
+        categories,
+    }
+}
+
+/// 用一个服务商分析会话视频，返回输出（失败时记录错误）
+async fn run_candidate(
+    analysis_domain: &AnalysisDomain,
+    db: &Arc<Database>,
+    session: &Session,
+    video_path: &Path,
+    candidate: &ComparisonCandidate,
+    config: Option<&LLMProviderConfig>,
+    upload: VideoUploadConfig,
+) -> (String, Result<(TimelineAnalysis, i64), String>) {
+    let model = config.map(|c| c.model.clone()).unwrap_or_default();
+    let session_id = session.id.unwrap_or_default();
+    let started = Instant::now();
+    let result: anyhow::Result<TimelineAnalysis> = async {
+        let handle = analysis_domain
+            .standalone_llm(&candidate.provider, config)
+            .await?;
+        let metadata = VideoMetadata::load(video_path).await;
+        handle
+            .set_video_path(Some(video_path.to_string_lossy().to_string()))
+            .await?;
+        handle
+            .set_provider_database(db.clone(), Some(session_id))
+            .await?;
+        handle
+            .set_session_window(Some(session.start_time), Some(session.end_time))
+            .await?;
+        if let Some(metadata) = &metadata {
+            handle
+                .set_video_speed(metadata.effective_speed_multiplier())
+                .await?;
+        }
+        handle.set_video_upload(upload).await?;
+
+        let duration_minutes = ((session.end_time - session.start_time).num_seconds().max(0) as f64
+            / 60.0)
+            .ceil() as u32;
+        let mut analysis = handle
+            .segment_video_and_generate_timeline(vec![], duration_minutes, None)
+            .await?;
+        absolutize_analysis(
+            &mut analysis,
+            session.start_time,
+            session.end_time,
+            metadata.as_ref(),
+        );
+        Ok(analysis)
+    }
+    .await;
+    let latency_ms = started.elapsed().as_millis() as i64;
+    (
+        model,
+        result.map(|a| (a, latency_ms)).map_err(|e| e.to_string()),
+    )
+}
+
+/// 读取分析所用 LLM 调用的 token 用量合计
+async fn analysis_tokens(
+    db: &Database,
+    session_id: i64,
+    call_ids: &[Option<i64>],
+) -> (Option<i64>, Option<i64>) {
+    let calls = match db.get_llm_calls_by_session(session_id).await {
+        Ok(calls) => calls,
+        Err(e) => {
+            warn!("读取对比分析的 LLM 调用记录失败: {}", e);
+            return (None, None);
+        }
+    };
+    calls
+        .iter()
+        .filter(|call| call.id.is_some() && call_ids.contains(&call.id))
+        .filter_map(|call| call.token_usage.as_deref())
+        .map(token_counts)
+        .fold((None, None), |(input, output), (i, o)| {
+            (add_tokens(input, i), add_tokens(output, o))
+        })
+}
+
+/// 用多个服务商分析同一会话视频并保存结果
+pub async fn compare_providers(
+    analysis_domain: &AnalysisDomain,
+    storage: &StorageDomain,
+    session_id: i64,
+    candidates: Vec<ComparisonCandidate>,
+) -> Result<ComparisonRun, String> {
+    if candidates.is_empty() || candidates.len() > MAX_COMPARISON_PROVIDERS {
+        return Err(format!(
+            "无效的服务商数量: {}（1 到 {} 个）",
+            candidates.len(),
+            MAX_COMPARISON_PROVIDERS
+        ));
+    }
+
+    let db = storage.get_db().await?;
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("会话不存在: {} ({})", session_id, e))?;
+    let video_path = session
+        .video_path
+        .clone()
+        .ok_or_else(|| "该会话没有生成视频".to_string())?;
+    let video_path = storage
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+        .map_err(|e| format!("获取会话视频失败: {}", e))?;
+
+    let settings = storage.get_settings().get().await;
+    let upload = settings.video_upload_config.clone().unwrap_or_default();
+    let now = crate::storage::local_now();
+    let run_id = now.format("%Y%m%d%H%M%S%3f").to_string();
+
+    let mut records = Vec::new();
+    for candidate in &candidates {
+        // 未提供配置时沿用当前服务商的配置
+        let config = candidate.config.clone().or_else(|| {
+            (candidate.provider == settings.llm_provider)
+                .then(|| settings.llm_config.clone())
+                .flatten()
+        });
+        info!("服务商对比：{} 分析会话 {}", candidate.provider, session_id);
+        let (model, result) = run_candidate(
+            analysis_domain,
+            &db,
+            &session,
+            &video_path,
+            candidate,
+            config.as_ref(),
+            upload.clone(),
+        )
+        .await;
+
+        let record = match result {
+            Ok((analysis, latency_ms)) => {
+                let (input_tokens, output_tokens) = analysis_tokens(
+                    &db,
+                    session_id,
+                    &[analysis.segment_call_id, analysis.timeline_call_id],
+                )
+                .await;
+                ProviderComparisonRecord {
+                    id: None,
+                    session_id,
+                    run_id: run_id.clone(),
+                    provider: candidate.provider.clone(),
+                    model,
+                    error_message: None,
+                    latency_ms,
+                    input_tokens,
+                    output_tokens,
+                    timeline_cards: serde_json::to_string(&analysis.timeline_cards)
+                        .unwrap_or_else(|_| "[]".to_string()),
+                    created_at: now,
+                }
+            }
+            Err(e) => {
+                warn!("服务商对比：{} 分析失败: {}", candidate.provider, e);
+                ProviderComparisonRecord {
+                    id: None,
+                    session_id,
+                    run_id: run_id.clone(),
+                    provider: candidate.provider.clone(),
+                    model,
+                    error_message: Some(e),
+                    latency_ms: 0,
+                    input_tokens: None,
+                    output_tokens: None,
+                    timeline_cards: "[]".to_string(),
+                    created_at: now,
+                }
+            }
+        };
+        db.insert_provider_comparison(&record)
+            .await
+            .map_err(|e| format!("保存对比结果失败: {}", e))?;
+        records.push(record);
+    }
+
+    Ok(build_run(&session, run_id, records))
+}
+
+/// 把同一次对比的记录组装为对比结果
+fn build_run(
+    session: &Session,
+    run_id: String,
+    records: Vec<ProviderComparisonRecord>,
+) -> ComparisonRun {
+    let created_at = records
+        .first()
+        .map(|r| r.created_at.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    let names: Vec<(String, String)> = records
+        .iter()
+        .map(|r| (r.provider.clone(), r.model.clone()))
+        .collect();
+    let outputs: Vec<ProviderOutput> = records
+        .into_iter()
+        .zip(labels(&names))
+        .map(|(record, label)| ProviderOutput {
+            provider: record.provider,
+            model: record.model,
+            label,
+            error: record.error_message,
+            latency_ms: record.latency_ms,
+            input_tokens: record.input_tokens,
+            output_tokens: record.output_tokens,
+            cards: serde_json::from_str(&record.timeline_cards).unwrap_or_default(),
+        })
+        .collect();
+    let diff = diff_outputs(&outputs, session.start_time, session.end_time);
+    ComparisonRun {
+        run_id,
+        session_id: session.id.unwrap_or_default(),
+        created_at,
+        outputs,
+        diff,
+    }
+}
+
+/// 获取会话的历次对比（最新的在前）
+pub async fn get_comparisons(db: &Database, session_id: i64) -> Result<Vec<ComparisonRun>, String> {
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("会话不存在: {} ({})", session_id, e))?;
+    let records = db
+        .get_provider_comparisons(session_id)
+        .await
+        .map_err(|e| format!("获取对比结果失败: {}", e))?;
+
+    let mut runs: Vec<(String, Vec<ProviderComparisonRecord>)> = Vec::new();
+    for record in records {
+        match runs.iter_mut().find(|(run_id, _)| *run_id == record.run_id) {
+            Some((_, group)) => group.push(record),
+            None => runs.push((record.run_id.clone(), vec![record])),
+        }
+    }
+    Ok(runs
+        .into_iter()
+        .map(|(run_id, records)| build_run(&session, run_id, records))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::AppSites;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn card(start: &str, end: &str, category: &str) -> TimelineCard {
+        TimelineCard {
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            title: String::new(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: AppSites {
+                primary: String::new(),
+                secondary: None,
+            },
+            video_preview_path: None,
+            confidence: None,
+        }
+    }
+
+    fn output(label: &str, cards: Vec<TimelineCard>) -> ProviderOutput {
+        ProviderOutput {
+            provider: label.to_string(),
+            model: String::new(),
+            label: label.to_string(),
+            error: None,
+            latency_ms: 0,
+            input_tokens: None,
+            output_tokens: None,
+            cards,
+        }
+    }
+
+    #[test]
+    fn test_diff_outputs_reports_agreement_and_spans() {
+        let start = at("2025-10-09T09:00:00Z");
+        let end = at("2025-10-09T09:10:00Z");
+        // 相对时间（MM:SS）按会话开始换算
+        let outputs = vec![
+            output("claude", vec![card("00:00", "10:00", "work")]),
+            output(
+                "openai",
+                vec![
+                    card("00:00", "06:00", "Work"),
+                    card("06:00", "08:00", "personal"),
+                    card("08:00", "10:00", "work"),
+                ],
+            ),
+        ];
+
+        let diff = diff_outputs(&outputs, start, end);
+        assert_eq!(diff.agreement, Some(0.8));
+        assert_eq!(diff.category_minutes["openai"]["personal"], 2);
+        assert_eq!(diff.category_minutes["claude"]["work"], 10);
+        assert_eq!(diff.disagreements.len(), 1);
+        assert_eq!(diff.disagreements[0].start, "09:06");
+        assert_eq!(diff.disagreements[0].end, "09:08");
+        assert_eq!(diff.disagreements[0].categories["openai"], "personal");
+
+        assert_eq!(
+            token_counts(r#"{"prompt_tokens":1200,"completion_tokens":300}"#),
+            (Some(1200), Some(300))
+        );
+        assert_eq!(
+            labels(&[
+                ("openai".to_string(), "qwen-vl-max".to_string()),
+                ("openai".to_string(), "qwen-vl-max".to_string()),
+            ]),
+            ["openai / qwen-vl-max", "openai / qwen-vl-max #2"]
+        );
+    }
+}
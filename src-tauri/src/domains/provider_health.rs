@@ -0,0 +1,254 @@
+// LLM 服务商健康监测 - 定期轻量探测当前服务商，记录延迟和错误率
+//
+// 探测不消耗 token：OpenAI 兼容接口和 Claude 请求模型列表接口，Codex 执行 --version，
+// 插件检查清单是否存在。实际分析的成败也计入统计。
+// 服务商连续失败超过一小时后暂停自动分析，直到探测或分析再次成功，
+// 避免反复向已经不可用的接口提交视频
+
+use crate::models::LLMProviderConfig;
+use crate::settings::SettingsManager;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// 探测间隔（秒）
+pub const HEALTH_CHECK_INTERVAL_SECS: u64 = 300;
+
+/// 统计窗口（分钟）：只保留最近一小时的记录
+const HEALTH_WINDOW_MINUTES: i64 = 60;
+
+/// 连续失败超过该时长（分钟）后暂停自动分析
+const UNHEALTHY_AFTER_MINUTES: i64 = 60;
+
+/// 单条探测/调用记录
+#[derive(Debug, Clone)]
+struct HealthSample {
+    at: DateTime<Utc>,
+    ok: bool,
+    latency_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct ProviderRecord {
+    samples: VecDeque<HealthSample>,
+    last_error: Option<String>,
+    /// 本轮连续失败开始的时间（最近一次成功后的第一次失败）
+    failing_since: Option<DateTime<Utc>>,
+}
+
+/// 服务商健康状态（显示在系统状态中）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    /// 连续失败未超过一小时
+    pub healthy: bool,
+    pub last_check: Option<DateTime<Utc>>,
+    pub last_latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// 最近一小时的平均延迟（仅统计成功的请求）
+    pub avg_latency_ms: Option<u64>,
+    /// 最近一小时的错误率（0-1）
+    pub error_rate: f64,
+    /// 最近一小时的记录数
+    pub samples: usize,
+    pub failing_since: Option<DateTime<Utc>>,
+}
+
+/// 服务商健康监测器
+#[derive(Default)]
+pub struct ProviderHealthMonitor {
+    records: Mutex<HashMap<String, ProviderRecord>>,
+}
+
+impl ProviderHealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次探测或分析结果
+    pub fn record(&self, provider: &str, latency_ms: u64, error: Option<String>) {
+        self.record_at(provider, latency_ms, error, crate::storage::local_now());
+    }
+
+    fn record_at(&self, provider: &str, latency_ms: u64, error: Option<String>, at: DateTime<Utc>) {
+        let mut records = self.records.lock().unwrap();
+        let record = records.entry(provider.to_string()).or_default();
+
+        let ok = error.is_none();
+        record
+            .samples
+            .push_back(HealthSample { at, ok, latency_ms });
+        let window_start = at - Duration::minutes(HEALTH_WINDOW_MINUTES);
+        while record
+            .samples
+            .front()
+            .is_some_and(|sample| sample.at < window_start)
+        {
+            record.samples.pop_front();
+        }
+
+        if ok {
+            if record.failing_since.take().is_some() {
+                info!("LLM 服务商 {} 已恢复", provider);
+            }
+        } else {
+            record.failing_since.get_or_insert(at);
+            record.last_error = error;
+        }
+    }
+
+    /// 服务商已连续失败超过一小时时返回原因，此时不应再提交分析
+    pub fn unhealthy_reason(&self, provider: &str) -> Option<String> {
+        self.unhealthy_reason_at(provider, crate::storage::local_now())
+    }
+
+    fn unhealthy_reason_at(&self, provider: &str, now: DateTime<Utc>) -> Option<String> {
+        let records = self.records.lock().unwrap();
+        let record = records.get(provider)?;
+        let since = record.failing_since?;
+        if now - since < Duration::minutes(UNHEALTHY_AFTER_MINUTES) {
+            return None;
+        }
+        Some(format!(
+            "服务商 {} 自 {} 起持续不可用: {}",
+            provider,
+            since.format("%H:%M"),
+            record.last_error.as_deref().unwrap_or("未知错误")
+        ))
+    }
+
+    /// 所有服务商的健康状态
+    pub fn snapshot(&self) -> Vec<ProviderHealth> {
+        let now = crate::storage::local_now();
+        let records = self.records.lock().unwrap();
+        let mut health: Vec<ProviderHealth> = records
+            .iter()
+            .map(|(provider, record)| {
+                let total = record.samples.len();
+                let failures = record.samples.iter().filter(|s| !s.ok).count();
+                let ok_latencies: Vec<u64> = record
+                    .samples
+                    .iter()
+                    .filter(|s| s.ok)
+                    .map(|s| s.latency_ms)
+                    .collect();
+                let last = record.samples.back();
+                ProviderHealth {
+                    provider: provider.clone(),
+                    healthy: record.failing_since.is_none_or(|since| {
+                        now - since < Duration::minutes(UNHEALTHY_AFTER_MINUTES)
+                    }),
+                    last_check: last.map(|s| s.at),
+                    last_latency_ms: last.map(|s| s.latency_ms),
+                    last_error: record.failing_since.and(record.last_error.clone()),
+                    avg_latency_ms: (!ok_latencies.is_empty())
+                        .then(|| ok_latencies.iter().sum::<u64>() / ok_latencies.len() as u64),
+                    error_rate: if total == 0 {
+                        0.0
+                    } else {
+                        failures as f64 / total as f64
+                    },
+                    samples: total,
+                    failing_since: record.failing_since,
+                }
+            })
+            .collect();
+        health.sort_by(|a, b| a.provider.cmp(&b.provider));
+        health
+    }
+}
+
+/// 启动周期性健康探测（探测当前配置的服务商）
+pub fn start_health_monitor(monitor: Arc<ProviderHealthMonitor>, settings: Arc<SettingsManager>) {
+    tokio::spawn(async move {
+        info!("LLM 服务商健康监测已启动");
+        loop {
+            let config = settings.get().await;
+            let provider = config.llm_provider.clone();
+            let started = std::time::Instant::now();
+            let result = ping_provider(&provider, config.llm_config.as_ref()).await;
+            let latency_ms = started.elapsed().as_millis() as u64;
+            if let Err(e) = &result {
+                warn!("LLM 服务商 {} 健康探测失败: {}", provider, e);
+            }
+            monitor.record(&provider, latency_ms, result.err().map(|e| e.to_string()));
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// 轻量探测服务商是否可用
+pub async fn ping_provider(
+    provider: &str,
+    config: Option<&LLMProviderConfig>,
+) -> anyhow::Result<()> {
+    match provider {
+        "openai" | "claude" => {
+            let config = config.ok_or_else(|| anyhow::anyhow!("服务商未配置"))?;
+            let request = serde_json::json!({
+                "api_key": config.api_key,
+                "auth_token": config.auth_token,
+                "base_url": config.base_url,
+            });
+            crate::llm::catalog::probe(provider, &request).await
+        }
+        "codex" => {
+            let binary = config
+                .and_then(|config| config.codex_config.clone())
+                .and_then(|value| serde_json::from_value::<crate::llm::CodexConfig>(value).ok())
+                .and_then(|codex| codex.binary_path)
+                .filter(|path| !path.trim().is_empty())
+                .unwrap_or_else(|| "codex".to_string());
+            let output = tokio::time::timeout(
+                std::time::Duration::from_secs(30),
+                tokio::process::Command::new(&binary)
+                    .arg("--version")
+                    .kill_on_drop(true)
+                    .output(),
+            )
+            .await
+            .map_err(|_| anyhow::anyhow!("{} --version 超时", binary))??;
+            if !output.status.success() {
+                anyhow::bail!("{} --version 退出码 {:?}", binary, output.status.code());
+            }
+            Ok(())
+        }
+        name if name.starts_with(crate::llm::external::PLUGIN_PREFIX) => {
+            crate::llm::external::find_plugin(name).map(|_| ())
+        }
+        _ => Err(anyhow::anyhow!("不支持的 provider: {}", provider)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_unhealthy_after_an_hour_of_failures() {
+        let monitor = ProviderHealthMonitor::new();
+        let start = Utc::now();
+        let at = |minutes: i64| start + Duration::minutes(minutes);
+
+        monitor.record_at("openai", 120, None, at(0));
+        monitor.record_at("openai", 0, Some("HTTP 502".to_string()), at(5));
+        monitor.record_at("openai", 0, Some("HTTP 503".to_string()), at(50));
+        assert!(monitor.unhealthy_reason_at("openai", at(50)).is_none());
+
+        monitor.record_at("openai", 0, Some("HTTP 503".to_string()), at(70));
+        let reason = monitor.unhealthy_reason_at("openai", at(70)).unwrap();
+        assert!(reason.contains("HTTP 503"));
+
+        // 最早的成功记录已移出一小时窗口
+        let health = &monitor.snapshot()[0];
+        assert_eq!(health.samples, 2);
+        assert_eq!(health.error_rate, 1.0);
+
+        monitor.record_at("openai", 150, None, at(75));
+        assert!(monitor.unhealthy_reason_at("openai", at(75)).is_none());
+        assert!(monitor.unhealthy_reason_at("claude", at(75)).is_none());
+    }
+}
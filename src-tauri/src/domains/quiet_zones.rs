@@ -0,0 +1,168 @@
+// 安静时段 - 每天固定的个人时间（如 12:00–13:00 午休）不做 LLM 分析
+//
+// 时段内照常截屏并生成视频，保留画面记录；会话窗口完全落在某个安静时段内时，
+// 跳过 LLM 分析，直接生成一张覆盖整个会话的休息卡片，节省模型调用。
+// 只和时段部分重叠的会话照常分析；手动重新分析某个会话时不受安静时段限制
+
+use crate::llm::{AppSites, TimelineAnalysis, TimelineCard};
+use crate::models::QuietZone;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+
+/// 休息卡片的类别和子类别
+const BREAK_CATEGORY: &str = "personal";
+const BREAK_SUBCATEGORY: &str = "休息";
+
+/// 未命名时段的卡片标题
+const DEFAULT_TITLE: &str = "休息";
+
+fn parse_hm(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// 时段的开始时间和时长（结束早于开始时跨越午夜），时间格式不对或时长为 0 时返回 None
+fn zone_span(zone: &QuietZone) -> Option<(NaiveTime, Duration)> {
+    let start = parse_hm(&zone.start)?;
+    let end = parse_hm(&zone.end)?;
+    let mut length = end - start;
+    if length < Duration::zero() {
+        length += Duration::days(1);
+    }
+    (length > Duration::zero()).then_some((start, length))
+}
+
+/// 校验安静时段设置
+pub fn validate(zones: &[QuietZone]) -> Result<(), String> {
+    for zone in zones {
+        if zone_span(zone).is_none() {
+            return Err(format!(
+                "安静时段“{}”的时间无效：{} - {}（格式为 HH:MM，开始和结束不能相同）",
+                zone.name, zone.start, zone.end
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 完整覆盖会话窗口的安静时段（时间为本地时间）
+pub fn zone_covering(
+    zones: &[QuietZone],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<QuietZone> {
+    let (start, end) = (start.naive_utc(), end.naive_utc());
+    zones
+        .iter()
+        .find(|zone| {
+            let Some((zone_start, length)) = zone_span(zone) else {
+                return false;
+            };
+            // 跨越午夜的时段可能从前一天开始
+            [start.date() - Duration::days(1), start.date()]
+                .iter()
+                .any(|day| {
+                    let from = day.and_time(zone_start);
+                    from <= start && end <= from + length
+                })
+        })
+        .cloned()
+}
+
+/// 相对会话开始的 MM:SS 时间
+fn relative_label(secs: i64) -> String {
+    let secs = secs.max(0);
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// 安静时段的休息卡片，时间为相对会话开始的 MM:SS
+pub fn break_card(zone: &QuietZone, start: DateTime<Utc>, end: DateTime<Utc>) -> TimelineCard {
+    let title = match zone.name.trim() {
+        "" => DEFAULT_TITLE.to_string(),
+        name => name.to_string(),
+    };
+    let span = format!("{}–{}", zone.start.trim(), zone.end.trim());
+    TimelineCard {
+        start_time: relative_label(0),
+        end_time: relative_label((end - start).num_seconds()),
+        category: BREAK_CATEGORY.to_string(),
+        subcategory: BREAK_SUBCATEGORY.to_string(),
+        title: title.clone(),
+        summary: format!("{}（安静时段 {}）", title, span),
+        detailed_summary: format!("安静时段 {} 内照常录制，未进行 AI 分析", span),
+        distractions: None,
+        app_sites: AppSites {
+            primary: String::new(),
+            secondary: None,
+        },
+        video_preview_path: None,
+        // 固定生成的卡片不需要复核
+        confidence: Some(1.0),
+    }
+}
+
+/// 安静时段的分析结果：只有一张休息卡片，没有模型调用
+pub fn break_analysis(
+    zone: &QuietZone,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> TimelineAnalysis {
+    TimelineAnalysis {
+        segments: Vec::new(),
+        timeline_cards: vec![break_card(zone, start, end)],
+        segment_call_id: None,
+        timeline_call_id: None,
+        segment_call_ids: Vec::new(),
+        timeline_call_ids: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(name: &str, start: &str, end: &str) -> QuietZone {
+        QuietZone {
+            name: name.to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn test_zone_covers_whole_session_only() {
+        let zones = vec![zone("午休", "12:00", "13:00"), zone("", "23:30", "00:30")];
+
+        let lunch = zone_covering(&zones, at("2025-10-09 12:15"), at("2025-10-09 12:30"));
+        assert_eq!(lunch.as_ref().map(|z| z.name.as_str()), Some("午休"));
+        assert!(zone_covering(&zones, at("2025-10-09 12:45"), at("2025-10-09 13:00")).is_some());
+        // 部分重叠的会话照常分析
+        assert!(zone_covering(&zones, at("2025-10-09 12:50"), at("2025-10-09 13:05")).is_none());
+        // 跨越午夜的时段
+        assert!(zone_covering(&zones, at("2025-10-10 00:00"), at("2025-10-10 00:15")).is_some());
+        assert!(zone_covering(&zones, at("2025-10-09 23:45"), at("2025-10-10 00:15")).is_some());
+
+        let card = break_card(
+            lunch.as_ref().unwrap(),
+            at("2025-10-09 12:15"),
+            at("2025-10-09 12:30"),
+        );
+        assert_eq!(card.start_time, "00:00");
+        assert_eq!(card.end_time, "15:00");
+        assert_eq!(card.title, "午休");
+        assert_eq!(card.summary, "午休（安静时段 12:00–13:00）");
+        assert_eq!(card.confidence, Some(1.0));
+        assert_eq!(
+            break_card(&zones[1], at("2025-10-09 23:30"), at("2025-10-09 23:45")).title,
+            "休息"
+        );
+
+        assert!(validate(&zones).is_ok());
+        assert!(validate(&[zone("坏", "12:00", "12:00")]).is_err());
+        assert!(validate(&[zone("坏", "25:00", "12:00")]).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+// 任意时段统计 - “今天上午”“下午两点以来”等不足一天的时段
+//
+// 按日期加载时段覆盖到的会话卡片（包含前一天开始、跨过零点的会话），把每张卡片裁剪到时段边界内再汇总，
+// 只有一部分落在时段内的会话按重叠部分计时。专注时长、类别占比和上下文切换的口径与每日统计一致
+
+use super::metrics::{self, ActivitySpan};
+use super::records::{day_focus, FocusBlock};
+use super::summary::{format_duration, get_category_display_name};
+use crate::storage::{Database, TimelineCardRecord};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 单次查询的最长时段（天）
+const MAX_RANGE_DAYS: i64 = 31;
+/// 返回的主要应用数量
+const TOP_APP_COUNT: usize = 5;
+
+/// 时段内的一项时长统计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeStat {
+    pub name: String,
+    pub minutes: i64,
+    /// 格式化的时长 (如 "1h 20m")
+    pub total_time: String,
+    /// 占时段内活动时长的百分比
+    pub percentage: f64,
+}
+
+/// 任意时段的统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeSummary {
+    /// 开始时间（本地时间，YYYY-MM-DD HH:MM）
+    pub start: String,
+    /// 结束时间（本地时间，YYYY-MM-DD HH:MM）
+    pub end: String,
+    /// 与时段有重叠的会话数
+    pub session_count: usize,
+    /// 有活动的时长（分钟，多台设备分别计算）
+    pub active_minutes: i64,
+    pub focus_minutes: i64,
+    pub longest_focus_block: Option<FocusBlock>,
+    pub context_switches: usize,
+    pub categories: Vec<RangeStat>,
+    pub devices: Vec<RangeStat>,
+    /// 时长最多的应用/网站
+    pub top_apps: Vec<RangeStat>,
+}
+
+/// 解析时段边界：RFC 3339，或本地时间 YYYY-MM-DD HH:MM[:SS]（也可用 T 分隔）
+pub fn parse_range_time(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time);
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .ok_or_else(|| format!("时间格式错误: {}（应为 YYYY-MM-DD HH:MM）", value))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.fixed_offset())
+        .ok_or_else(|| format!("本地时间不存在: {}", value))
+}
+
+/// 把活动片段裁剪到时段内，完全在时段外时返回 None
+pub fn clip_span(
+    span: &ActivitySpan,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+) -> Option<ActivitySpan> {
+    (span.start < end && span.end > start).then(|| ActivitySpan {
+        start: span.start.max(start),
+        end: span.end.min(end),
+        ..span.clone()
+    })
+}
+
+/// 把活动片段裁剪到时段内，完全在时段外的片段丢弃
+pub fn clip_spans(
+    spans: &[ActivitySpan],
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+) -> Vec<ActivitySpan> {
+    spans
+        .iter()
+        .filter_map(|span| clip_span(span, start, end))
+        .collect()
+}
+
+pub(crate) fn to_stats(minutes: HashMap<String, i64>, total: i64) -> Vec<RangeStat> {
+    let mut stats: Vec<RangeStat> = minutes
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(name, minutes)| RangeStat {
+            total_time: format_duration(minutes),
+            percentage: if total > 0 {
+                (minutes as f64 * 1000.0 / total as f64).round() / 10.0
+            } else {
+                0.0
+            },
+            name,
+            minutes,
+        })
+        .collect();
+    stats.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.name.cmp(&b.name)));
+    stats
+}
+
+/// 汇总已裁剪到时段内的活动片段
+pub fn summarize_spans(
+    spans: &[ActivitySpan],
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    session_count: usize,
+) -> RangeSummary {
+    let focus = day_focus(spans);
+    let active_minutes: i64 = spans.iter().map(ActivitySpan::duration_minutes).sum();
+
+    let mut categories = HashMap::new();
+    let mut devices = HashMap::new();
+    let mut apps = HashMap::new();
+    for span in spans {
+        let minutes = span.duration_minutes();
+        *categories
+            .entry(get_category_display_name(&span.category))
+            .or_default() += minutes;
+        let device = if span.device.is_empty() {
+            "本机".to_string()
+        } else {
+            span.device.clone()
+        };
+        *devices.entry(device).or_default() += minutes;
+        if !span.app.is_empty() {
+            *apps.entry(span.app.clone()).or_default() += minutes;
+        }
+    }
+
+    let mut top_apps = to_stats(apps, active_minutes);
+    top_apps.truncate(TOP_APP_COUNT);
+    RangeSummary {
+        start: start
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        end: end
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        session_count,
+        active_minutes,
+        focus_minutes: focus.focus_minutes,
+        longest_focus_block: focus.longest_block,
+        context_switches: metrics::detect_context_switches(spans).len(),
+        categories: to_stats(categories, active_minutes),
+        devices: to_stats(devices, active_minutes),
+        top_apps,
+    }
+}
+
+/// 解析并检查时段边界
+pub fn parse_range(
+    start_ts: &str,
+    end_ts: &str,
+) -> Result<(DateTime<FixedOffset>, DateTime<FixedOffset>), String> {
+    let start = parse_range_time(start_ts)?;
+    let end = parse_range_time(end_ts)?;
+    if end <= start {
+        return Err("结束时间必须晚于开始时间".to_string());
+    }
+    if end - start > Duration::days(MAX_RANGE_DAYS) {
+        return Err(format!("时段不能超过 {} 天", MAX_RANGE_DAYS));
+    }
+    Ok((start, end))
+}
+
+/// 加载与时段有重叠的会话中的卡片（未裁剪），返回会话数和（活动片段, 卡片）
+pub async fn load_range_cards(
+    db: &Database,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+) -> Result<(usize, Vec<(ActivitySpan, TimelineCardRecord)>), String> {
+    // 会话按开始日期查询，从前一天开始查询以包含跨过零点的会话；会话时间为本地时间
+    let local_start = start.with_timezone(&Local).naive_local();
+    let local_end = end.with_timezone(&Local).naive_local();
+    let mut session_count = 0;
+    let mut spans = Vec::new();
+    let mut date = local_start.date() - Duration::days(1);
+    while date <= local_end.date() {
+        let sessions = db
+            .get_sessions_by_date(&date.format("%Y-%m-%d").to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        for session in sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            if session.start_time.naive_utc() >= local_end
+                || session.end_time.naive_utc() <= local_start
+            {
+                continue;
+            }
+            session_count += 1;
+            let device = session.device_name.clone().unwrap_or_default();
+            let cards = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+            spans.extend(cards.into_iter().filter_map(|card| {
+                ActivitySpan::from_card(&card, &device).map(|span| (span, card))
+            }));
+        }
+        date += Duration::days(1);
+    }
+    Ok((session_count, spans))
+}
+
+/// 获取任意时段的统计
+pub async fn get_range_summary(
+    db: &Database,
+    start_ts: &str,
+    end_ts: &str,
+) -> Result<RangeSummary, String> {
+    let (start, end) = parse_range(start_ts, end_ts)?;
+    let (session_count, cards) = load_range_cards(db, start, end).await?;
+    let spans: Vec<ActivitySpan> = cards.into_iter().map(|(span, _)| span).collect();
+
+    Ok(summarize_spans(
+        &clip_spans(&spans, start, end),
+        start,
+        end,
+        session_count,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: &str, end: &str, category: &str, app: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(start).unwrap(),
+            end: DateTime::parse_from_rfc3339(end).unwrap(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            app: app.to_string(),
+            device: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_partial_cards_are_clipped_at_range_boundaries() {
+        let spans = vec![
+            span(
+                "2025-10-09T13:30:00+08:00",
+                "2025-10-09T14:20:00+08:00",
+                "work",
+                "code",
+            ),
+            span(
+                "2025-10-09T14:20:00+08:00",
+                "2025-10-09T14:50:00+08:00",
+                "meeting",
+                "zoom",
+            ),
+            span(
+                "2025-10-09T14:50:00+08:00",
+                "2025-10-09T16:30:00+08:00",
+                "work",
+                "code",
+            ),
+            span(
+                "2025-10-09T09:00:00+08:00",
+                "2025-10-09T10:00:00+08:00",
+                "work",
+                "code",
+            ),
+        ];
+        let start = DateTime::parse_from_rfc3339("2025-10-09T14:00:00+08:00").unwrap();
+        let end = DateTime::parse_from_rfc3339("2025-10-09T16:00:00+08:00").unwrap();
+
+        let clipped = clip_spans(&spans, start, end);
+        assert_eq!(clipped.len(), 3);
+        assert_eq!(clipped[0].start, start);
+        assert_eq!(clipped[2].end, end);
+
+        let summary = summarize_spans(&clipped, start, end, 2);
+        assert_eq!(summary.active_minutes, 120);
+        assert_eq!(summary.focus_minutes, 90);
+        assert_eq!(summary.context_switches, 2);
+        assert_eq!(
+            summary.categories[0],
+            RangeStat {
+                name: "工作".to_string(),
+                minutes: 90,
+                total_time: "1h 30m".to_string(),
+                percentage: 75.0,
+            }
+        );
+        assert_eq!(summary.top_apps[1].name, "zoom");
+        assert_eq!(summary.devices[0].minutes, 120);
+    }
+}
@@ -0,0 +1,287 @@
+// 个人纪录 - 基于时间线卡片的轻量成就统计
+//
+// 最长专注时段：同一设备上间隔不超过切换窗口的连续高生产力卡片合并为一个时段；
+// 本月最佳日：本月专注总时长最多的一天；连续达标：截至今天专注时长达到每日目标的连续天数
+// （今天尚未达标时从昨天开始计算，不会因为一天还没过完而中断）。
+// 每次查询时按本月数据重新计算，历史最长专注时段和最长连续天数只会被更高的纪录覆盖
+
+use super::live::FOCUS_PRODUCTIVITY;
+use super::metrics::{
+    category_productivity, load_activity_spans, ActivitySpan, SWITCH_WINDOW_MINUTES,
+};
+use crate::storage::{Database, PersonalRecord};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// 历史最长专注时段
+pub const LONGEST_FOCUS_BLOCK: &str = "longest_focus_block";
+/// 当前连续达标天数
+pub const CURRENT_STREAK: &str = "current_streak";
+/// 历史最长连续达标天数
+pub const LONGEST_STREAK: &str = "longest_streak";
+
+/// 连续达标最多往前追溯的天数
+const MAX_STREAK_LOOKBACK_DAYS: i64 = 366;
+
+/// 一段不间断的专注时间
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FocusBlock {
+    /// 开始时间 (HH:MM)
+    pub start: String,
+    /// 结束时间 (HH:MM)
+    pub end: String,
+    pub minutes: i64,
+}
+
+/// 某天的专注统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DayFocus {
+    /// 专注总时长（分钟）
+    pub focus_minutes: i64,
+    /// 当天最长的专注时段
+    pub longest_block: Option<FocusBlock>,
+}
+
+/// 个人纪录汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonalRecords {
+    /// 每日专注目标（分钟）
+    pub goal_minutes: i64,
+    /// 今天的专注时长（分钟）
+    pub today_focus_minutes: i64,
+    /// 历史最长专注时段
+    pub longest_focus_block: Option<PersonalRecord>,
+    /// 本月最佳日
+    pub best_day_this_month: Option<PersonalRecord>,
+    /// 当前连续达标天数
+    pub current_streak_days: i64,
+    /// 历史最长连续达标
+    pub longest_streak: Option<PersonalRecord>,
+}
+
+/// 计算一天的专注统计
+pub fn day_focus(spans: &[ActivitySpan]) -> DayFocus {
+    let mut by_device: BTreeMap<&str, Vec<&ActivitySpan>> = BTreeMap::new();
+    for span in spans
+        .iter()
+        .filter(|span| category_productivity(&span.category) >= FOCUS_PRODUCTIVITY)
+    {
+        by_device
+            .entry(span.device.as_str())
+            .or_default()
+            .push(span);
+    }
+
+    let mut focus = DayFocus::default();
+    for (_, mut device_spans) in by_device {
+        device_spans.sort_by_key(|span| span.start);
+        let mut blocks: Vec<ActivitySpan> = Vec::new();
+        for span in device_spans {
+            focus.focus_minutes += span.duration_minutes();
+            match blocks.last_mut() {
+                Some(block)
+                    if span.start - block.end <= Duration::minutes(SWITCH_WINDOW_MINUTES) =>
+                {
+                    block.end = block.end.max(span.end);
+                }
+                _ => blocks.push(span.clone()),
+            }
+        }
+
+        for block in blocks {
+            let minutes = block.duration_minutes();
+            if focus
+                .longest_block
+                .as_ref()
+                .map_or(minutes > 0, |longest| minutes > longest.minutes)
+            {
+                focus.longest_block = Some(FocusBlock {
+                    start: block.start.format("%H:%M").to_string(),
+                    end: block.end.format("%H:%M").to_string(),
+                    minutes,
+                });
+            }
+        }
+    }
+
+    focus
+}
+
+/// 读取某天的专注统计（同一次计算中重复使用）
+async fn load_day_focus(
+    db: &Database,
+    cache: &mut HashMap<NaiveDate, DayFocus>,
+    date: NaiveDate,
+) -> Result<DayFocus, String> {
+    if let Some(focus) = cache.get(&date) {
+        return Ok(focus.clone());
+    }
+    let spans = load_activity_spans(db, &date.format("%Y-%m-%d").to_string()).await?;
+    let focus = day_focus(&spans);
+    cache.insert(date, focus.clone());
+    Ok(focus)
+}
+
+async fn save_record(db: &Database, record: &PersonalRecord) -> Result<(), String> {
+    db.save_personal_record(record)
+        .await
+        .map_err(|e| format!("保存个人纪录失败: {}", e))
+}
+
+/// 计算并保存个人纪录
+///
+/// # 参数
+/// * `goal_minutes` - 每日专注目标（分钟），0 表示不统计连续达标
+/// * `today` - 当天日期
+pub async fn refresh_records(
+    db: &Database,
+    goal_minutes: i64,
+    today: NaiveDate,
+) -> Result<PersonalRecords, String> {
+    let mut stored: HashMap<String, PersonalRecord> = db
+        .get_personal_records()
+        .await
+        .map_err(|e| format!("获取个人纪录失败: {}", e))?
+        .into_iter()
+        .map(|record| (record.record_key.clone(), record))
+        .collect();
+
+    let mut cache = HashMap::new();
+    let mut best_day: Option<(NaiveDate, i64)> = None;
+    let mut longest_block: Option<(NaiveDate, FocusBlock)> = None;
+    let mut date = today.with_day(1).unwrap_or(today);
+    while date <= today {
+        let focus = load_day_focus(db, &mut cache, date).await?;
+        if focus.focus_minutes > best_day.map_or(0, |(_, minutes)| minutes) {
+            best_day = Some((date, focus.focus_minutes));
+        }
+        if let Some(block) = focus.longest_block {
+            if longest_block
+                .as_ref()
+                .is_none_or(|(_, longest)| block.minutes > longest.minutes)
+            {
+                longest_block = Some((date, block));
+            }
+        }
+        date += Duration::days(1);
+    }
+
+    let best_day_key = format!("best_day:{}", today.format("%Y-%m"));
+    if let Some((date, minutes)) = best_day {
+        let record = PersonalRecord {
+            record_key: best_day_key.clone(),
+            value: minutes,
+            date: date.format("%Y-%m-%d").to_string(),
+            detail: None,
+        };
+        save_record(db, &record).await?;
+        stored.insert(best_day_key.clone(), record);
+    }
+
+    if let Some((date, block)) = longest_block {
+        let beaten = stored
+            .get(LONGEST_FOCUS_BLOCK)
+            .is_none_or(|record| block.minutes > record.value);
+        if beaten {
+            let record = PersonalRecord {
+                record_key: LONGEST_FOCUS_BLOCK.to_string(),
+                value: block.minutes,
+                date: date.format("%Y-%m-%d").to_string(),
+                detail: Some(format!("{}-{}", block.start, block.end)),
+            };
+            save_record(db, &record).await?;
+            stored.insert(LONGEST_FOCUS_BLOCK.to_string(), record);
+        }
+    }
+
+    let today_focus_minutes = load_day_focus(db, &mut cache, today).await?.focus_minutes;
+    let mut current_streak_days = 0;
+    if goal_minutes > 0 {
+        let mut date = if today_focus_minutes >= goal_minutes {
+            today
+        } else {
+            today - Duration::days(1)
+        };
+        let streak_end = date;
+        while current_streak_days < MAX_STREAK_LOOKBACK_DAYS
+            && load_day_focus(db, &mut cache, date).await?.focus_minutes >= goal_minutes
+        {
+            current_streak_days += 1;
+            date -= Duration::days(1);
+        }
+
+        let record = PersonalRecord {
+            record_key: CURRENT_STREAK.to_string(),
+            value: current_streak_days,
+            date: streak_end.format("%Y-%m-%d").to_string(),
+            detail: None,
+        };
+        save_record(db, &record).await?;
+        let beaten = stored
+            .get(LONGEST_STREAK)
+            .map_or(current_streak_days > 0, |record| {
+                current_streak_days > record.value
+            });
+        if beaten {
+            let record = PersonalRecord {
+                record_key: LONGEST_STREAK.to_string(),
+                ..record
+            };
+            save_record(db, &record).await?;
+            stored.insert(LONGEST_STREAK.to_string(), record);
+        }
+    }
+
+    Ok(PersonalRecords {
+        goal_minutes,
+        today_focus_minutes,
+        longest_focus_block: stored.remove(LONGEST_FOCUS_BLOCK),
+        best_day_this_month: stored.remove(&best_day_key),
+        current_streak_days,
+        longest_streak: stored.remove(LONGEST_STREAK),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn span(device: &str, category: &str, start: &str, end: &str) -> ActivitySpan {
+        ActivitySpan {
+            start: DateTime::parse_from_rfc3339(&format!("2025-10-09T{}:00+08:00", start)).unwrap(),
+            end: DateTime::parse_from_rfc3339(&format!("2025-10-09T{}:00+08:00", end)).unwrap(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            app: String::new(),
+            device: device.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_focus_blocks_merge_short_gaps_per_device() {
+        let spans = vec![
+            span("laptop", "work", "09:00", "09:40"),
+            span("laptop", "learning", "09:43", "10:30"),
+            span("laptop", "personal", "10:30", "10:50"),
+            span("laptop", "work", "10:50", "11:20"),
+            // 另一台设备的专注时段不与笔记本上的时段合并
+            span("desktop", "work", "10:31", "11:00"),
+        ];
+
+        let focus = day_focus(&spans);
+        assert_eq!(focus.focus_minutes, 40 + 47 + 30 + 29);
+        assert_eq!(
+            focus.longest_block,
+            Some(FocusBlock {
+                start: "09:00".to_string(),
+                end: "10:30".to_string(),
+                minutes: 90,
+            })
+        );
+        assert!(day_focus(&[span("laptop", "personal", "09:00", "10:00")])
+            .longest_block
+            .is_none());
+    }
+}
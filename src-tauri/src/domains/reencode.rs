@@ -0,0 +1,432 @@
+// 旧视频转码 - 在空闲时段把较早的 H.264 会话视频转为 HEVC/AV1，节省存储空间
+//
+// 每 30 分钟检查一次，只在设置的转码时段内工作；一次转码一个视频并占用编码名额，
+// 不和新会话的视频生成抢资源。转码结果先写到同目录的 .part 临时文件，校验能完整解码、
+// 编码正确、时长与原视频一致且体积确实变小后才替换原文件（路径不变，元数据 sidecar
+// 继续有效），再同步到存储后端并从当天的视频用量中扣除节省的空间；
+// 校验不通过时删除临时文件、保留原视频，本次运行期间不再尝试该视频。
+// 已归档和本地没有副本的视频不处理
+
+use super::analysis::hour_in_window;
+use crate::models::{ReencodeCodec, VideoReencodeSettings};
+use crate::settings::SettingsManager;
+use crate::storage::media_store::MediaLibrary;
+use crate::storage::{Database, Session};
+use crate::video::processor::{muxer_for, partial_path};
+use crate::video::{VideoInfo, VideoUtils};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tracing::{error, info, warn};
+
+/// 检查间隔（秒）
+const CHECK_INTERVAL_SECS: u64 = 1800;
+/// 需要转码的原编码
+const SOURCE_CODEC: &str = "h264";
+/// 转码后体积至少要比原视频小这么多才替换
+const MIN_SAVING_RATIO: f64 = 0.1;
+/// 允许的时长误差（秒）
+const DURATION_TOLERANCE_SECS: f32 = 1.0;
+
+/// 转码任务状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReencodeStatus {
+    /// 正在转码的视频
+    pub current: Option<String>,
+    /// 本次运行以来转码成功的视频数
+    pub converted: u64,
+    /// 本次运行以来转码失败或校验不通过的视频数
+    pub failed: u64,
+    /// 本次运行以来节省的字节数
+    pub saved_bytes: i64,
+    /// 最近一次检查时间（本地时间，YYYY-MM-DD HH:MM）
+    pub last_check: Option<String>,
+}
+
+static STATUS: OnceLock<RwLock<ReencodeStatus>> = OnceLock::new();
+
+/// 已确认不需要（或无法）转码的视频，避免每次检查都重新探测
+static SKIPPED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn status_cell() -> &'static RwLock<ReencodeStatus> {
+    STATUS.get_or_init(|| RwLock::new(ReencodeStatus::default()))
+}
+
+fn skipped() -> &'static Mutex<HashSet<String>> {
+    SKIPPED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn update_status(apply: impl FnOnce(&mut ReencodeStatus)) {
+    if let Ok(mut status) = status_cell().write() {
+        apply(&mut status);
+    }
+}
+
+/// 当前的转码状态
+pub fn current_status() -> ReencodeStatus {
+    status_cell()
+        .read()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
+
+/// 现在是否处于转码时段
+pub fn in_window(settings: &VideoReencodeSettings, now: DateTime<Utc>) -> bool {
+    settings.enabled && hour_in_window(settings.start_hour, settings.end_hour, now.hour() as u8)
+}
+
+/// 会话视频是否可能需要转码（按时间和归档状态筛选，编码需另行探测）
+pub fn is_candidate(session: &Session, cutoff: DateTime<Utc>) -> bool {
+    !session.archived && session.end_time < cutoff && session.video_path.is_some()
+}
+
+fn encoder(codec: ReencodeCodec) -> &'static str {
+    match codec {
+        ReencodeCodec::Hevc => "libx265",
+        ReencodeCodec::Av1 => "libsvtav1",
+    }
+}
+
+/// ffprobe 报告的编码名称
+fn codec_name(codec: ReencodeCodec) -> &'static str {
+    match codec {
+        ReencodeCodec::Hevc => "hevc",
+        ReencodeCodec::Av1 => "av1",
+    }
+}
+
+/// 转码的 FFmpeg 参数（保留字幕等其他流，只重新编码视频）
+pub fn ffmpeg_args(input: &Path, output: &Path, settings: &VideoReencodeSettings) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-i",
+        &*input.to_string_lossy(),
+        "-map",
+        "0",
+        "-c",
+        "copy",
+        "-c:v",
+        encoder(settings.codec),
+        "-crf",
+        &*settings.crf.to_string(),
+        "-pix_fmt",
+        "yuv420p",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if settings.codec == ReencodeCodec::Hevc {
+        // 让 QuickTime / Safari 能识别 MP4 中的 HEVC
+        args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+    }
+    args.extend([
+        "-movflags".to_string(),
+        "+faststart".to_string(),
+        "-f".to_string(),
+        muxer_for(input).to_string(),
+        "-y".to_string(),
+        output.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+/// 校验转码结果：编码正确、时长一致、体积足够小
+pub fn verify(
+    original: &VideoInfo,
+    converted: &VideoInfo,
+    codec: ReencodeCodec,
+) -> Result<(), String> {
+    if converted.codec != codec_name(codec) {
+        return Err(format!("转码后的编码为 {}", converted.codec));
+    }
+    if (converted.duration - original.duration).abs() > DURATION_TOLERANCE_SECS {
+        return Err(format!(
+            "时长不一致：原视频 {:.1} 秒，转码后 {:.1} 秒",
+            original.duration, converted.duration
+        ));
+    }
+    let limit = original.file_size as f64 * (1.0 - MIN_SAVING_RATIO);
+    if converted.file_size == 0 || converted.file_size as f64 > limit {
+        return Err(format!(
+            "体积没有明显减小：{} -> {} 字节",
+            original.file_size, converted.file_size
+        ));
+    }
+    Ok(())
+}
+
+fn hide_console(command: &mut tokio::process::Command) {
+    // Windows下隐藏控制台窗口
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)]
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = command;
+}
+
+/// 完整解码一遍，确认转码结果可以播放
+async fn check_decodable(ffmpeg_path: &Path, video_path: &Path) -> Result<()> {
+    let mut command = tokio::process::Command::new(ffmpeg_path);
+    command.args([
+        "-v",
+        "error",
+        "-i",
+        &*video_path.to_string_lossy(),
+        "-map",
+        "0:v:0",
+        "-f",
+        "null",
+        "-",
+    ]);
+    hide_console(&mut command);
+    let output = command.output().await?;
+    let errors = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !errors.trim().is_empty() {
+        return Err(anyhow!("转码结果解码失败: {}", errors.trim()));
+    }
+    Ok(())
+}
+
+async fn probe(video_path: &Path) -> Result<VideoInfo> {
+    let path = video_path.to_path_buf();
+    tokio::task::spawn_blocking(move || VideoUtils::get_video_info(&path)).await?
+}
+
+/// 转码一个视频并在校验通过后替换原文件，返回节省的字节数
+pub async fn reencode_video(video_path: &Path, settings: &VideoReencodeSettings) -> Result<i64> {
+    let original = probe(video_path).await?;
+    let temp = partial_path(video_path);
+    let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+
+    let result = async {
+        let _permit = super::performance::acquire_encode().await;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(ffmpeg_args(video_path, &temp, settings));
+        hide_console(&mut command);
+        let output = command.output().await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "FFmpeg 转码失败: {}",
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+            ));
+        }
+
+        check_decodable(&ffmpeg_path, &temp).await?;
+        let converted = probe(&temp).await?;
+        verify(&original, &converted, settings.codec).map_err(|e| anyhow!(e))?;
+        tokio::fs::rename(&temp, video_path).await?;
+        Ok(original.file_size as i64 - converted.file_size as i64)
+    }
+    .await;
+
+    if result.is_err() {
+        tokio::fs::remove_file(&temp).await.ok();
+    }
+    result
+}
+
+/// 处理一个会话的视频，返回是否进行了转码
+async fn process_session(
+    db: &Database,
+    media: &MediaLibrary,
+    session: &Session,
+    settings: &VideoReencodeSettings,
+) -> bool {
+    let Some(stored) = session.video_path.as_deref() else {
+        return false;
+    };
+    let local = media.local_path_for(Path::new(stored));
+    let mark_skipped = || {
+        if let Ok(mut skipped) = skipped().lock() {
+            skipped.insert(stored.to_string());
+        }
+    };
+    if !local.is_file() {
+        mark_skipped();
+        return false;
+    }
+    match probe(&local).await {
+        Ok(info) if info.codec == SOURCE_CODEC => {}
+        Ok(_) => {
+            mark_skipped();
+            return false;
+        }
+        Err(e) => {
+            warn!("探测视频编码失败 {:?}: {}", local, e);
+            mark_skipped();
+            return false;
+        }
+    }
+    // 共享存储上的视频可能正被其他机器分析
+    if !super::video_claims::try_claim(db, stored).await {
+        return false;
+    }
+
+    update_status(|status| status.current = Some(stored.to_string()));
+    let result = reencode_video(&local, settings).await;
+    super::video_claims::release(db, stored).await;
+    update_status(|status| status.current = None);
+    mark_skipped();
+
+    match result {
+        Ok(saved) => {
+            info!(
+                "会话 {:?} 的视频已转码为 {}，节省 {:.1} MB",
+                session.id,
+                codec_name(settings.codec),
+                saved as f64 / 1024.0 / 1024.0
+            );
+            if let Err(e) = media.publish_video(&local).await {
+                warn!("转码后的视频同步到存储后端失败: {}", e);
+            }
+            let device_name = session
+                .device_name
+                .clone()
+                .unwrap_or_else(|| crate::storage::get_device_info().0);
+            super::storage_usage::record_reencode(
+                db,
+                session.start_time.date_naive(),
+                &device_name,
+                saved,
+            )
+            .await;
+            update_status(|status| {
+                status.converted += 1;
+                status.saved_bytes += saved;
+            });
+            true
+        }
+        Err(e) => {
+            warn!("会话 {:?} 的视频转码未完成，保留原视频: {}", session.id, e);
+            update_status(|status| status.failed += 1);
+            false
+        }
+    }
+}
+
+/// 检查一次：在转码时段内依次转码符合条件的视频，离开时段后停止
+pub async fn run_once(db: &Database, media: &MediaLibrary, settings: &SettingsManager) {
+    let reencode = settings.get().await.video_reencode.unwrap_or_default();
+    let now = crate::storage::local_now();
+    update_status(|status| status.last_check = Some(now.format("%Y-%m-%d %H:%M").to_string()));
+    if !in_window(&reencode, now) {
+        return;
+    }
+
+    let cutoff = now - Duration::days(reencode.older_than_days as i64);
+    let sessions = match db.get_all_sessions().await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            error!("获取会话列表失败: {}", e);
+            return;
+        }
+    };
+    for session in sessions.iter().filter(|s| is_candidate(s, cutoff)) {
+        let already_checked = skipped()
+            .lock()
+            .map(|skipped| skipped.contains(session.video_path.as_deref().unwrap_or_default()))
+            .unwrap_or(false);
+        if already_checked {
+            continue;
+        }
+        // 每个视频开始前重新读取设置，关闭或离开时段后不再开始新的转码
+        let reencode = settings.get().await.video_reencode.unwrap_or_default();
+        if !in_window(&reencode, crate::storage::local_now()) {
+            break;
+        }
+        process_session(db, media, session, &reencode).await;
+    }
+}
+
+/// 启动旧视频转码任务（未启用时仅空转检查配置）
+pub fn start_reencode_worker(
+    db: Arc<Database>,
+    media: Arc<MediaLibrary>,
+    settings: Arc<SettingsManager>,
+) {
+    tokio::spawn(async move {
+        info!("旧视频转码任务已启动");
+        loop {
+            run_once(&db, &media, &settings).await;
+            tokio::time::sleep(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(codec: &str, duration: f32, file_size: u64) -> VideoInfo {
+        VideoInfo {
+            duration,
+            file_size,
+            resolution: (1920, 1080),
+            fps: 1.0,
+            codec: codec.to_string(),
+            format: "mov,mp4,m4a,3gp,3g2,mj2".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reencode_rules() {
+        let settings = VideoReencodeSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        let at = |hour: u32| {
+            chrono::NaiveDate::from_ymd_opt(2025, 10, 9)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        assert!(in_window(&settings, at(3)));
+        assert!(!in_window(&settings, at(14)));
+        assert!(!in_window(&VideoReencodeSettings::default(), at(3)));
+
+        let path = Path::new("/data/videos/session_1.mp4");
+        let args = ffmpeg_args(path, &partial_path(path), &settings);
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libx265"]));
+        assert!(args.windows(2).any(|w| w == ["-tag:v", "hvc1"]));
+        // 写到 .part 文件时需要显式指定封装格式
+        assert!(args.windows(2).any(|w| w == ["-f", "mp4"]));
+        assert_eq!(args.last().unwrap(), "/data/videos/session_1.mp4.part");
+
+        let original = info("h264", 120.0, 10_000_000);
+        assert!(verify(
+            &original,
+            &info("hevc", 120.4, 6_000_000),
+            ReencodeCodec::Hevc
+        )
+        .is_ok());
+        // 编码不对、时长不一致、体积没有明显减小都不替换
+        assert!(verify(
+            &original,
+            &info("h264", 120.0, 6_000_000),
+            ReencodeCodec::Hevc
+        )
+        .is_err());
+        assert!(verify(
+            &original,
+            &info("hevc", 60.0, 6_000_000),
+            ReencodeCodec::Hevc
+        )
+        .is_err());
+        assert!(verify(
+            &original,
+            &info("hevc", 120.0, 9_500_000),
+            ReencodeCodec::Hevc
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,279 @@
+// 卡片复核 - 低置信度的时间线卡片进入复核队列，由用户确认或纠正
+//
+// 置信度优先使用模型返回的 confidence 字段；模型未返回时按卡片内容估算
+// （类别为 other、主要应用未识别、标题或摘要过短等都会降低置信度）。
+// 低于阈值的卡片保存时标记为 pending，用户纠正的结果记录到 card_corrections 表，
+// 作为后续提示词的示例
+
+use crate::llm::plugin::TimelineCard;
+use crate::storage::{CardCorrectionRecord, Database, TimelineCardRecord};
+use serde::Deserialize;
+
+/// 默认复核阈值
+pub const DEFAULT_REVIEW_THRESHOLD: f64 = 0.6;
+
+/// 等待复核
+pub const REVIEW_PENDING: &str = "pending";
+/// 用户确认卡片内容无误
+pub const REVIEW_CONFIRMED: &str = "confirmed";
+/// 用户修改了卡片内容
+pub const REVIEW_CORRECTED: &str = "corrected";
+
+/// 模型可选择的类别
+const KNOWN_CATEGORIES: &[&str] = &[
+    "work",
+    "communication",
+    "learning",
+    "personal",
+    "idle",
+    "other",
+];
+
+/// 卡片的置信度（0-1），模型未返回时按内容估算
+pub fn card_confidence(card: &TimelineCard) -> f64 {
+    if let Some(confidence) = card.confidence.filter(|c| c.is_finite()) {
+        return confidence.clamp(0.0, 1.0);
+    }
+
+    let mut confidence: f64 = 1.0;
+    let category = card.category.trim().to_lowercase();
+    if category == "other" {
+        confidence -= 0.3;
+    } else if !KNOWN_CATEGORIES.contains(&category.as_str()) {
+        confidence -= 0.2;
+    }
+    let primary = card.app_sites.primary.trim();
+    if primary.is_empty() || primary.eq_ignore_ascii_case("unknown") {
+        confidence -= 0.25;
+    }
+    if card.title.trim().chars().count() < 2 {
+        confidence -= 0.2;
+    }
+    if card.summary.trim().chars().count() < 8 {
+        confidence -= 0.15;
+    }
+    // 兜底生成的卡片直接复用分段描述，摘要和详细摘要完全相同
+    if card.summary.trim() == card.detailed_summary.trim() {
+        confidence -= 0.1;
+    }
+
+    (confidence.clamp(0.0, 1.0) * 100.0).round() / 100.0
+}
+
+/// 置信度低于阈值时将卡片标记为待复核
+pub fn mark_for_review(card: &mut TimelineCardRecord, threshold: f64) {
+    if card
+        .confidence
+        .is_some_and(|confidence| confidence < threshold)
+    {
+        card.review_status = Some(REVIEW_PENDING.to_string());
+    }
+}
+
+/// 用户对待复核卡片的处理
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// 确认卡片内容无误
+    Confirm,
+    /// 纠正卡片内容（未提供的字段保持原值）
+    Correct {
+        title: Option<String>,
+        category: Option<String>,
+        subcategory: Option<String>,
+    },
+}
+
+/// 复核结果：更新后的卡片字段，以及需要保存的纠正记录
+#[derive(Debug, Clone)]
+pub struct ReviewResolution {
+    pub title: String,
+    pub category: String,
+    pub subcategory: String,
+    pub review_status: &'static str,
+    pub correction: Option<CardCorrectionRecord>,
+}
+
+/// 根据用户的处理计算卡片的新内容（纠正后内容没有变化时视为确认）
+pub fn resolve_review(card: &TimelineCardRecord, decision: ReviewDecision) -> ReviewResolution {
+    let ReviewDecision::Correct {
+        title,
+        category,
+        subcategory,
+    } = decision
+    else {
+        return ReviewResolution {
+            title: card.title.clone(),
+            category: card.category.clone(),
+            subcategory: card.subcategory.clone(),
+            review_status: REVIEW_CONFIRMED,
+            correction: None,
+        };
+    };
+
+    let pick = |value: Option<String>, original: &str| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| original.to_string())
+    };
+    let title = pick(title, &card.title);
+    let category = pick(category.map(|c| c.to_lowercase()), &card.category);
+    let subcategory = pick(subcategory, &card.subcategory);
+
+    let changed =
+        title != card.title || category != card.category || subcategory != card.subcategory;
+    let correction = changed.then(|| CardCorrectionRecord {
+        id: None,
+        card_id: card.id.unwrap_or_default(),
+        original_title: card.title.clone(),
+        original_category: card.category.clone(),
+        original_subcategory: card.subcategory.clone(),
+        corrected_title: title.clone(),
+        corrected_category: category.clone(),
+        corrected_subcategory: subcategory.clone(),
+        summary: card.summary.clone(),
+        app_sites: card.app_sites.clone(),
+        created_at: crate::storage::local_now(),
+    });
+
+    ReviewResolution {
+        title,
+        category,
+        subcategory,
+        review_status: if changed {
+            REVIEW_CORRECTED
+        } else {
+            REVIEW_CONFIRMED
+        },
+        correction,
+    }
+}
+
+/// 确认或纠正一张卡片：更新卡片并保存纠正记录，返回更新后的卡片
+pub async fn apply_review(
+    db: &Database,
+    card_id: i64,
+    decision: ReviewDecision,
+) -> Result<TimelineCardRecord, String> {
+    let card = db
+        .get_timeline_card(card_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+        .ok_or_else(|| format!("卡片 {} 不存在", card_id))?;
+
+    let resolution = resolve_review(&card, decision);
+    db.update_timeline_card_review(
+        card_id,
+        &resolution.title,
+        &resolution.category,
+        &resolution.subcategory,
+        resolution.review_status,
+    )
+    .await
+    .map_err(|e| format!("更新卡片失败: {}", e))?;
+    if let Some(correction) = &resolution.correction {
+        db.insert_card_correction(correction)
+            .await
+            .map_err(|e| format!("保存纠正记录失败: {}", e))?;
+    }
+
+    Ok(TimelineCardRecord {
+        title: resolution.title,
+        category: resolution.category,
+        subcategory: resolution.subcategory,
+        review_status: Some(resolution.review_status.to_string()),
+        ..card
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::plugin::AppSites;
+
+    fn timeline_card(category: &str, primary: &str, summary: &str) -> TimelineCard {
+        TimelineCard {
+            start_time: "00:00".to_string(),
+            end_time: "15:00".to_string(),
+            category: category.to_string(),
+            subcategory: "Development".to_string(),
+            title: "功能开发".to_string(),
+            summary: summary.to_string(),
+            detailed_summary: "在编辑器中实现用户管理模块并运行单元测试".to_string(),
+            distractions: None,
+            app_sites: AppSites {
+                primary: primary.to_string(),
+                secondary: None,
+            },
+            video_preview_path: None,
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_confidence_heuristics_and_correction() {
+        let clear = timeline_card("work", "vscode", "持续开发用户管理模块");
+        assert_eq!(card_confidence(&clear), 1.0);
+
+        let vague = timeline_card("other", "unknown", "未知");
+        assert!(card_confidence(&vague) < DEFAULT_REVIEW_THRESHOLD);
+
+        let reported = TimelineCard {
+            confidence: Some(1.7),
+            ..vague
+        };
+        assert_eq!(card_confidence(&reported), 1.0);
+
+        let mut record = TimelineCardRecord {
+            id: Some(3),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "00:00".to_string(),
+            end_time: "15:00".to_string(),
+            category: "other".to_string(),
+            subcategory: "General".to_string(),
+            title: "未知活动".to_string(),
+            summary: "未知".to_string(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: Some(0.25),
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        mark_for_review(&mut record, DEFAULT_REVIEW_THRESHOLD);
+        assert_eq!(record.review_status.as_deref(), Some(REVIEW_PENDING));
+
+        let resolution = resolve_review(
+            &record,
+            ReviewDecision::Correct {
+                title: Some("代码评审".to_string()),
+                category: Some("Work".to_string()),
+                subcategory: None,
+            },
+        );
+        assert_eq!(resolution.review_status, REVIEW_CORRECTED);
+        assert_eq!(resolution.category, "work");
+        assert_eq!(resolution.subcategory, "General");
+        let correction = resolution.correction.unwrap();
+        assert_eq!(correction.original_category, "other");
+        assert_eq!(correction.corrected_title, "代码评审");
+
+        let unchanged = resolve_review(
+            &record,
+            ReviewDecision::Correct {
+                title: Some("未知活动".to_string()),
+                category: None,
+                subcategory: None,
+            },
+        );
+        assert_eq!(unchanged.review_status, REVIEW_CONFIRMED);
+        assert!(unchanged.correction.is_none());
+    }
+}
@@ -0,0 +1,261 @@
+// 屏幕语言 - 按屏幕文字识别结果判断每个分段屏幕内容的主要语言
+//
+// 分为中文、英文、代码和其他文字四类：像代码的行（以 ; { } 结尾、含 => :: 等符号、
+// 以 fn / def / import 等关键字开头）整行计入代码，其余按汉字和拉丁字母计数
+// （一个汉字约等于两个字母的信息量）。语言随屏幕文字一起保存，用于：
+// - 每日总结给 LLM 的会话简介标注屏幕内容（代码、中文文档、英文邮件）
+// - 按语言统计时长（如“这周读英文资料花了多久”）和按语言筛选屏幕文字搜索
+
+use crate::storage::{Database, ScreenTextRecord};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 判断语言所需的最少文字量（加权字符数）
+const MIN_WEIGHT: usize = 20;
+
+/// 代码行的起始关键字
+const CODE_KEYWORDS: &[&str] = &[
+    "fn ",
+    "pub ",
+    "let ",
+    "const ",
+    "def ",
+    "class ",
+    "import ",
+    "return ",
+    "func ",
+    "package ",
+    "#include",
+    "public ",
+    "private ",
+    "function ",
+    "var ",
+    "impl ",
+];
+
+/// 代码中常见、自然语言中少见的符号
+const CODE_SYMBOLS: &[&str] = &["=>", "->", "::", "==", "!=", "&&", "||", "();", "</", "/>"];
+
+/// 屏幕内容的主要语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenLanguage {
+    Chinese,
+    English,
+    Code,
+    Other,
+}
+
+impl ScreenLanguage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chinese => "chinese",
+            Self::English => "english",
+            Self::Code => "code",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "chinese" => Some(Self::Chinese),
+            "english" => Some(Self::English),
+            "code" => Some(Self::Code),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Chinese => "中文",
+            Self::English => "英文",
+            Self::Code => "代码",
+            Self::Other => "其他文字",
+        }
+    }
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}')
+}
+
+fn looks_like_code(line: &str) -> bool {
+    let line = line.trim();
+    if CODE_KEYWORDS
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+    {
+        return true;
+    }
+    if line.ends_with(';') || line.ends_with('{') || line == "}" {
+        return true;
+    }
+    CODE_SYMBOLS.iter().any(|symbol| line.contains(symbol))
+}
+
+/// 判断一段屏幕文字的主要语言，文字太少时返回 None
+pub fn detect(text: &str) -> Option<ScreenLanguage> {
+    let mut weights: HashMap<ScreenLanguage, usize> = HashMap::new();
+    for line in text.lines() {
+        if looks_like_code(line) {
+            *weights.entry(ScreenLanguage::Code).or_default() +=
+                line.chars().filter(|c| !c.is_whitespace()).count();
+            continue;
+        }
+        for c in line.chars() {
+            let (language, weight) = if is_han(c) {
+                (ScreenLanguage::Chinese, 2)
+            } else if c.is_ascii_alphabetic() {
+                (ScreenLanguage::English, 1)
+            } else if c.is_alphabetic() {
+                (ScreenLanguage::Other, 1)
+            } else {
+                continue;
+            };
+            *weights.entry(language).or_default() += weight;
+        }
+    }
+
+    if weights.values().sum::<usize>() < MIN_WEIGHT {
+        return None;
+    }
+    weights
+        .into_iter()
+        .max_by_key(|(language, weight)| (*weight, *language == ScreenLanguage::Code))
+        .map(|(language, _)| language)
+}
+
+/// 各语言的时长
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageUsage {
+    pub language: ScreenLanguage,
+    pub label: String,
+    pub minutes: i64,
+}
+
+/// 按分段时长汇总各语言的分钟数（只统计识别出语言的分段），时长多的在前
+pub fn usage(records: &[ScreenTextRecord]) -> Vec<LanguageUsage> {
+    let mut minutes: HashMap<ScreenLanguage, i64> = HashMap::new();
+    for record in records {
+        let Some(language) = record.language.as_deref().and_then(ScreenLanguage::parse) else {
+            continue;
+        };
+        *minutes.entry(language).or_default() += (record.segment_end - record.segment_start)
+            .num_minutes()
+            .max(0);
+    }
+    let mut usage: Vec<LanguageUsage> = minutes
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(language, minutes)| LanguageUsage {
+            language,
+            label: language.label().to_string(),
+            minutes,
+        })
+        .collect();
+    usage.sort_by(|a, b| b.minutes.cmp(&a.minutes));
+    usage
+}
+
+/// 日期范围内（含首尾两天，YYYY-MM-DD）的屏幕文字记录
+pub async fn load_records(
+    db: &Database,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<ScreenTextRecord>, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误: {}", e))
+    };
+    let start = parse(start_date)?;
+    let end = parse(end_date)?;
+    if end < start {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    let start = start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc() + Duration::days(1);
+    db.get_screen_text_between(start, end)
+        .await
+        .map_err(|e| format!("获取屏幕文字失败: {}", e))
+}
+
+/// 日期范围内各语言的屏幕时长
+pub async fn usage_between(
+    db: &Database,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<LanguageUsage>, String> {
+    Ok(usage(&load_records(db, start_date, end_date).await?))
+}
+
+/// 各会话时长最多的屏幕语言
+pub fn dominant_by_session(records: &[ScreenTextRecord]) -> HashMap<i64, ScreenLanguage> {
+    let mut by_session: HashMap<i64, Vec<ScreenTextRecord>> = HashMap::new();
+    for record in records {
+        by_session
+            .entry(record.session_id)
+            .or_default()
+            .push(record.clone());
+    }
+    by_session
+        .into_iter()
+        .filter_map(|(session_id, records)| {
+            usage(&records)
+                .first()
+                .map(|usage| (session_id, usage.language))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_detect_and_usage() {
+        assert_eq!(
+            detect("关于季度预算的调整说明\n请各部门在周五前提交修改意见"),
+            Some(ScreenLanguage::Chinese)
+        );
+        assert_eq!(
+            detect("Hi team,\nPlease review the attached proposal before Friday."),
+            Some(ScreenLanguage::English)
+        );
+        assert_eq!(
+            detect("fn main() {\n    let value = parse(input)?;\n    println!(\"{}\", value);\n}"),
+            Some(ScreenLanguage::Code)
+        );
+        assert_eq!(detect("OK\n确定"), None);
+
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let record = |session_id, minutes: (i64, i64), language: Option<&str>| ScreenTextRecord {
+            id: None,
+            session_id,
+            segment_start: start + Duration::minutes(minutes.0),
+            segment_end: start + Duration::minutes(minutes.1),
+            text: String::new(),
+            language: language.map(str::to_string),
+            created_at: start,
+        };
+        let records = vec![
+            record(1, (0, 10), Some("code")),
+            record(1, (10, 15), Some("english")),
+            record(2, (30, 50), Some("english")),
+            record(2, (50, 55), None),
+        ];
+        let totals = usage(&records);
+        assert_eq!(totals[0].language, ScreenLanguage::English);
+        assert_eq!(totals[0].minutes, 25);
+        assert_eq!(totals[1].minutes, 10);
+        assert_eq!(totals.len(), 2);
+
+        let dominant = dominant_by_session(&records);
+        assert_eq!(dominant[&1], ScreenLanguage::Code);
+        assert_eq!(dominant[&2], ScreenLanguage::English);
+    }
+}
@@ -0,0 +1,211 @@
+// 会话标题 - 按配置选取标题卡片，同一天内标题重复时追加区分信息
+//
+// 会话标题默认取时长最长的卡片（第一张卡片常常只是热身活动），
+// 也可以按时长乘以类别生产力强度选取，或沿用第一张卡片。
+// 相邻会话经常得到完全相同的 LLM 标题（如连续几个"编写代码"），时间线上难以区分。
+// 保存新会话标题前与当天其他会话比较：重复时追加时间段和主要应用，
+// 仍然重复时再追加序号。已有会话的标题（包括手动修改的）保持不变
+
+use crate::llm::plugin::TimelineCard;
+use crate::models::SessionTitleStrategy;
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// 按选取方式挑出作为会话标题的卡片（得分相同时取先出现的）
+pub fn pick_title_card(
+    cards: &[TimelineCard],
+    strategy: SessionTitleStrategy,
+) -> Option<&TimelineCard> {
+    let score = |card: &TimelineCard| {
+        let duration = crate::llm::card_duration_secs(card);
+        match strategy {
+            SessionTitleStrategy::Longest => duration,
+            SessionTitleStrategy::MostProductive => {
+                duration * super::metrics::category_productivity(&card.category)
+            }
+            SessionTitleStrategy::Chronological => 0.0,
+        }
+    };
+    cards
+        .iter()
+        .fold(None, |best: Option<(&TimelineCard, f32)>, card| {
+            let card_score = score(card);
+            match best {
+                Some((_, best_score)) if best_score >= card_score => best,
+                _ => Some((card, card_score)),
+            }
+        })
+        .map(|(card, _)| card)
+}
+
+/// 卡片中出现次数最多的主要应用（次数相同时取先出现的）
+pub fn dominant_app(cards: &[TimelineCard]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut order: Vec<&str> = Vec::new();
+    for card in cards {
+        let app = card.app_sites.primary.trim();
+        if app.is_empty() {
+            continue;
+        }
+        let count = counts.entry(app).or_insert(0);
+        if *count == 0 {
+            order.push(app);
+        }
+        *count += 1;
+    }
+    let max = counts.values().copied().max()?;
+    order
+        .into_iter()
+        .find(|app| counts[app] == max)
+        .map(str::to_string)
+}
+
+/// 标题与当天已有标题重复时生成带区分信息的标题，不重复时原样返回
+pub fn distinguish_title(
+    title: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    app: Option<&str>,
+    existing: &[String],
+) -> String {
+    let title = title.trim();
+    // 已追加过区分信息的标题也视为同一标题
+    let prefix = format!("{}（", title);
+    let repeated = existing
+        .iter()
+        .any(|other| other == title || other.starts_with(&prefix));
+    if !repeated {
+        return title.to_string();
+    }
+
+    let range = format!("{}-{}", start.format("%H:%M"), end.format("%H:%M"));
+    let base = match app {
+        Some(app) if !title.contains(app) => format!("{}（{} · {}）", title, range, app),
+        _ => format!("{}（{}）", title, range),
+    };
+    if !existing.contains(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{} #{}", base, n))
+        .find(|candidate| !existing.contains(candidate))
+        .unwrap_or(base)
+}
+
+/// 对照会话当天的其他会话为新标题去重（查询失败时返回原标题）
+pub async fn dedupe_session_title(
+    db: &Database,
+    session_id: i64,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    title: &str,
+    cards: &[TimelineCard],
+) -> String {
+    let date = start.format("%Y-%m-%d").to_string();
+    let sessions = match db.get_sessions_by_date(&date).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            warn!("查询当天会话失败，跳过标题去重: {}", e);
+            return title.to_string();
+        }
+    };
+    let existing: Vec<String> = sessions
+        .into_iter()
+        .filter(|s| s.id != Some(session_id))
+        .map(|s| s.title)
+        .collect();
+    distinguish_title(title, start, end, dominant_app(cards).as_deref(), &existing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2025-10-09T{}:00Z", time))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_repeated_title_gets_range_app_and_number() {
+        let existing = vec!["编写代码".to_string(), "阅读文档".to_string()];
+        assert_eq!(
+            distinguish_title(
+                "整理邮件",
+                at("10:00"),
+                at("10:15"),
+                Some("Mail"),
+                &existing
+            ),
+            "整理邮件"
+        );
+        assert_eq!(
+            distinguish_title(
+                "编写代码",
+                at("10:00"),
+                at("10:15"),
+                Some("VS Code"),
+                &existing
+            ),
+            "编写代码（10:00-10:15 · VS Code）"
+        );
+        // 标题已包含主要应用时只追加时间段
+        assert_eq!(
+            distinguish_title(
+                "在 Chrome 中浏览",
+                at("10:00"),
+                at("10:15"),
+                Some("Chrome"),
+                &["在 Chrome 中浏览".to_string()]
+            ),
+            "在 Chrome 中浏览（10:00-10:15）"
+        );
+
+        // 已有带区分信息的同名标题，且区分信息也相同时追加序号
+        let existing = vec![
+            "编写代码".to_string(),
+            "编写代码（10:00-10:15）".to_string(),
+        ];
+        assert_eq!(
+            distinguish_title("编写代码", at("10:00"), at("10:15"), None, &existing),
+            "编写代码（10:00-10:15） #2"
+        );
+    }
+
+    fn card(title: &str, category: &str, start: &str, end: &str) -> TimelineCard {
+        TimelineCard {
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: crate::llm::plugin::AppSites {
+                primary: String::new(),
+                secondary: None,
+            },
+            video_preview_path: None,
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_pick_title_card_by_strategy() {
+        let cards = vec![
+            card("查看邮件", "communication", "00:00", "02:00"),
+            card("刷社交媒体", "personal", "02:00", "09:00"),
+            card("编写代码", "coding", "09:00", "15:00"),
+        ];
+        let title = |strategy| pick_title_card(&cards, strategy).unwrap().title.as_str();
+
+        assert_eq!(title(SessionTitleStrategy::Longest), "刷社交媒体");
+        assert_eq!(title(SessionTitleStrategy::MostProductive), "编写代码");
+        assert_eq!(title(SessionTitleStrategy::Chronological), "查看邮件");
+        assert!(pick_title_card(&[], SessionTitleStrategy::Longest).is_none());
+    }
+}
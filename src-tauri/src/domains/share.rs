@@ -0,0 +1,247 @@
+// 会话分享 - 把单个会话导出为可以直接发给别人的视频和 HTML 页面
+//
+// 导出前可以选择脱敏：
+// - 模糊画面中的指定区域（如聊天窗口、侧边栏），视频会重新编码
+// - 标题或摘要包含敏感关键词的卡片隐去摘要（标题也命中时一并隐去）
+// - 隐私敏感度高于设置值的卡片默认不出现在页面中（可选择包含）
+// 页面复用单日离线包的查看器，视频与页面放在同一目录，浏览器直接打开即可播放
+
+use super::bookmarks::bundle_bookmarks;
+use super::day_bundle::{bundle_session, render_viewer, BundleData, BundleSession};
+use super::playback::session_mapping;
+use super::storage::StorageDomain;
+use crate::video::{BlurRegion, VideoUtils};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// 隐去的内容显示为
+const REDACTED: &str = "（已隐去）";
+
+/// 分享选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareOptions {
+    /// 需要模糊的画面区域
+    #[serde(default)]
+    pub blur_regions: Vec<BlurRegion>,
+    /// 敏感关键词（不区分大小写）
+    #[serde(default)]
+    pub sensitive_keywords: Vec<String>,
+    /// 不分享视频，只导出页面
+    #[serde(default)]
+    pub skip_video: bool,
+    /// 包含隐私敏感度高于设置值的卡片
+    #[serde(default)]
+    pub include_sensitive: bool,
+}
+
+/// 分享结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareReport {
+    /// 导出目录
+    pub directory: String,
+    /// 页面路径
+    pub page_path: String,
+    pub video_path: Option<String>,
+    /// 隐去摘要的卡片数（会话摘要和书签备注命中也计入）
+    pub redacted_count: usize,
+    /// 因隐私敏感度被排除的卡片数
+    pub privacy_excluded: usize,
+}
+
+/// 文本是否包含敏感关键词
+fn contains_keyword(text: &str, keywords: &[String]) -> bool {
+    let text = text.to_lowercase();
+    keywords
+        .iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .any(|keyword| !keyword.is_empty() && text.contains(&keyword))
+}
+
+/// 隐去包含敏感关键词的摘要，返回隐去的条数
+pub fn redact_session(session: &mut BundleSession, keywords: &[String]) -> usize {
+    let mut redacted = 0;
+    if contains_keyword(&session.title, keywords) {
+        session.title = REDACTED.to_string();
+    }
+    if contains_keyword(&session.summary, keywords) {
+        session.summary = REDACTED.to_string();
+        redacted += 1;
+    }
+    for bookmark in &mut session.bookmarks {
+        if contains_keyword(&bookmark.note, keywords) {
+            bookmark.note = REDACTED.to_string();
+            redacted += 1;
+        }
+    }
+    for card in &mut session.cards {
+        let title_hit = contains_keyword(&card.title, keywords);
+        if title_hit || contains_keyword(&card.summary, keywords) {
+            card.summary = REDACTED.to_string();
+            if title_hit {
+                card.title = REDACTED.to_string();
+            }
+            redacted += 1;
+        }
+    }
+    redacted
+}
+
+/// 导出可分享的会话到 `output_dir`
+pub async fn export_shareable_session(
+    storage: &StorageDomain,
+    session_id: i64,
+    options: &ShareOptions,
+    output_dir: &Path,
+) -> Result<ShareReport, String> {
+    let db = storage.get_db().await?;
+    let (session, cards) = super::playback::load_session_cards(&db, session_id).await?;
+    let (cards, privacy_excluded) = if options.include_sensitive {
+        (cards, 0)
+    } else {
+        let max_level = super::privacy::max_export_level(&storage.get_settings().get().await);
+        super::privacy::filter_cards(cards, max_level)
+    };
+    tokio::fs::create_dir_all(output_dir)
+        .await
+        .map_err(|e| format!("创建导出目录失败: {}", e))?;
+
+    let mut video = None;
+    if let (Some(source), false) = (session.video_path.as_deref(), options.skip_video) {
+        let source = storage
+            .get_media()
+            .ensure_local_video(Path::new(source))
+            .await
+            .map_err(|e| format!("获取会话视频失败: {}", e))?;
+        let extension = source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("mp4");
+        let name = format!("session_{}.{}", session_id, extension);
+        let target = output_dir.join(&name);
+
+        if options.blur_regions.is_empty() {
+            tokio::fs::copy(&source, &target)
+                .await
+                .map_err(|e| format!("复制会话视频失败: {}", e))?;
+        } else {
+            VideoUtils::blur_regions(&source, &target, &options.blur_regions)
+                .await
+                .map_err(|e| format!("模糊视频区域失败: {}", e))?;
+        }
+        video = Some(name);
+    }
+
+    let bookmarks = db
+        .get_session_bookmarks(session_id)
+        .await
+        .map_err(|e| format!("获取书签失败: {}", e))?;
+    let mapping = session_mapping(&session, storage.get_settings()).await;
+    let mut shared = bundle_session(&session, &cards, video.clone(), &mapping);
+    shared.bookmarks = bundle_bookmarks(&session, &bookmarks, &mapping, video.is_some());
+    let redacted_count = redact_session(&mut shared, &options.sensitive_keywords);
+    let data = BundleData {
+        date: session.start_time.format("%Y-%m-%d").to_string(),
+        generated_at: crate::storage::local_now()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        summary_text: None,
+        sessions: vec![shared],
+    };
+
+    let page_path = output_dir.join("index.html");
+    tokio::fs::write(&page_path, render_viewer(&data)?)
+        .await
+        .map_err(|e| format!("写入分享页面失败: {}", e))?;
+
+    info!(
+        "已导出会话 {} 的分享页面: {:?}（隐去 {} 条摘要）",
+        session_id, page_path, redacted_count
+    );
+    Ok(ShareReport {
+        directory: output_dir.to_string_lossy().to_string(),
+        page_path: page_path.to_string_lossy().to_string(),
+        video_path: video.map(|name| output_dir.join(name).to_string_lossy().to_string()),
+        redacted_count,
+        privacy_excluded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::day_bundle::{BundleBookmark, BundleCard};
+    use crate::video::blur_filter;
+
+    fn card(title: &str, summary: &str) -> BundleCard {
+        BundleCard {
+            title: title.to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            summary: summary.to_string(),
+            start_time: "2025-10-09 09:00:00".to_string(),
+            end_time: "2025-10-09 09:10:00".to_string(),
+            video_start_secs: None,
+            video_end_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_redacts_sensitive_summaries_and_builds_blur_filter() {
+        let mut session = BundleSession {
+            id: 1,
+            title: "编码".to_string(),
+            summary: "处理 Project Phoenix 的需求".to_string(),
+            start_time: "2025-10-09 09:00:00".to_string(),
+            end_time: "2025-10-09 10:00:00".to_string(),
+            device_name: None,
+            video: None,
+            cards: vec![
+                card("修复登录", "查看 phoenix 的工单"),
+                card("Phoenix 评审", "评审代码"),
+                card("写文档", "整理 README"),
+            ],
+            bookmarks: vec![BundleBookmark {
+                time: "2025-10-09 09:30:00".to_string(),
+                note: "Phoenix 登录报错".to_string(),
+                video_secs: None,
+            }],
+        };
+        let keywords = vec!["phoenix".to_string(), " ".to_string()];
+
+        assert_eq!(redact_session(&mut session, &keywords), 4);
+        assert_eq!(session.bookmarks[0].note, REDACTED);
+        assert_eq!(session.title, "编码");
+        assert_eq!(session.summary, REDACTED);
+        assert_eq!(session.cards[0].title, "修复登录");
+        assert_eq!(session.cards[0].summary, REDACTED);
+        assert_eq!(session.cards[1].title, REDACTED);
+        assert_eq!(session.cards[2].summary, "整理 README");
+
+        let filter = blur_filter(&[
+            BlurRegion {
+                x: 0.0,
+                y: 0.0,
+                width: 0.25,
+                height: 1.0,
+            },
+            BlurRegion {
+                x: 0.9,
+                y: 0.5,
+                width: 0.5,
+                height: 0.2,
+            },
+        ]);
+        assert_eq!(
+            filter,
+            "[0:v]split=3[v0][s0][s1];\
+             [s0]crop=iw*0.2500:ih*1.0000:iw*0.0000:ih*0.0000,gblur=sigma=30[b0];\
+             [v0][b0]overlay=main_w*0.0000:main_h*0.0000[v1];\
+             [s1]crop=iw*0.1000:ih*0.2000:iw*0.9000:ih*0.5000,gblur=sigma=30[b1];\
+             [v1][b1]overlay=main_w*0.9000:main_h*0.5000[out]"
+        );
+        assert_eq!(blur_filter(&[]), "[0:v]null[out]");
+    }
+}
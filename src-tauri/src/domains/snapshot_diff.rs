@@ -0,0 +1,422 @@
+// 数据库快照对比 - 比较两份 SQLite 数据库（备份、复制的数据目录或当前数据库）中的会话和卡片
+//
+// 用于在同步、迁移数据目录或升级之后确认数据没有悄悄丢失。
+// 两份数据库中的自增 ID 可能不同，会话按开始时间对应，卡片按所属会话和开始时间对应；
+// 同一开始时间出现多次时按出现顺序对应。只读取建表时就存在的列，兼容旧版本的数据库。
+// 结果包括新增/删除/修改的数量和可直接阅读的文字报告，每类只列出前若干项
+
+use serde::Serialize;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// 报告中每类变化最多列出的条目数
+const MAX_LISTED: usize = 30;
+
+/// 快照中的会话
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotSession {
+    pub start_time: String,
+    pub end_time: String,
+    pub title: String,
+    pub summary: String,
+    pub tags: String,
+}
+
+/// 快照中的卡片
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotCard {
+    /// 所属会话的开始时间
+    pub session_start: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub category: String,
+    pub subcategory: String,
+    pub title: String,
+    pub summary: String,
+}
+
+/// 一份数据库快照
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub sessions: Vec<SnapshotSession>,
+    pub cards: Vec<SnapshotCard>,
+}
+
+/// 新增/删除/修改的数量
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffCounts {
+    /// 旧快照中的数量
+    pub before: usize,
+    /// 新快照中的数量
+    pub after: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+/// 对比结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub sessions: DiffCounts,
+    pub cards: DiffCounts,
+    /// 可直接阅读的报告
+    pub report: String,
+}
+
+/// 没有指定文件时使用目录中的 data.db
+fn database_file(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("data.db")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// 以只读方式读取快照
+pub async fn load_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let file = database_file(path);
+    if !file.is_file() {
+        return Err(format!("数据库文件不存在: {}", file.display()));
+    }
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&format!("sqlite:{}?mode=ro", file.to_string_lossy()))
+        .await
+        .map_err(|e| format!("打开数据库失败 {}: {}", file.display(), e))?;
+
+    let text = |row: &sqlx::sqlite::SqliteRow, column: &str| -> Result<String, String> {
+        row.try_get::<Option<String>, _>(column)
+            .map(Option::unwrap_or_default)
+            .map_err(|e| format!("读取 {} 失败: {}", column, e))
+    };
+
+    let session_rows =
+        sqlx::query("SELECT id, start_time, end_time, title, summary, tags FROM sessions")
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("读取会话失败: {}", e))?;
+    let mut session_starts = HashMap::new();
+    let mut sessions = Vec::with_capacity(session_rows.len());
+    for row in &session_rows {
+        let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+        let session = SnapshotSession {
+            start_time: text(row, "start_time")?,
+            end_time: text(row, "end_time")?,
+            title: text(row, "title")?,
+            summary: text(row, "summary")?,
+            tags: text(row, "tags")?,
+        };
+        session_starts.insert(id, session.start_time.clone());
+        sessions.push(session);
+    }
+
+    let card_rows = sqlx::query(
+        "SELECT session_id, start_time, end_time, category, subcategory, title, summary
+         FROM timeline_cards",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("读取时间线卡片失败: {}", e))?;
+    let mut cards = Vec::with_capacity(card_rows.len());
+    for row in &card_rows {
+        let session_id: i64 = row.try_get("session_id").map_err(|e| e.to_string())?;
+        // 会话已不存在的卡片不参与对比
+        let Some(session_start) = session_starts.get(&session_id) else {
+            continue;
+        };
+        cards.push(SnapshotCard {
+            session_start: session_start.clone(),
+            start_time: text(row, "start_time")?,
+            end_time: text(row, "end_time")?,
+            category: text(row, "category")?,
+            subcategory: text(row, "subcategory")?,
+            title: text(row, "title")?,
+            summary: text(row, "summary")?,
+        });
+    }
+    pool.close().await;
+
+    Ok(Snapshot { sessions, cards })
+}
+
+/// 按键分组，同一键出现多次时加上序号
+fn keyed<T>(items: &[T], key: impl Fn(&T) -> String) -> BTreeMap<(String, usize), &T> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    items
+        .iter()
+        .map(|item| {
+            let key = key(item);
+            let index = seen.entry(key.clone()).or_default();
+            *index += 1;
+            ((key, *index), item)
+        })
+        .collect()
+}
+
+/// 一类记录的对比结果：(数量, 新增, 删除, 修改)
+type Changes<'a, T> = (DiffCounts, Vec<&'a T>, Vec<&'a T>, Vec<(&'a T, &'a T)>);
+
+fn compare<'a, T: PartialEq>(
+    before: &'a [T],
+    after: &'a [T],
+    key: impl Fn(&T) -> String,
+) -> Changes<'a, T> {
+    let old = keyed(before, &key);
+    let new = keyed(after, &key);
+    let added: Vec<&T> = new
+        .iter()
+        .filter(|(key, _)| !old.contains_key(*key))
+        .map(|(_, item)| *item)
+        .collect();
+    let removed: Vec<&T> = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(*key))
+        .map(|(_, item)| *item)
+        .collect();
+    let changed: Vec<(&T, &T)> = old
+        .iter()
+        .filter_map(|(key, item)| new.get(key).map(|other| (*item, *other)))
+        .filter(|(a, b)| a != b)
+        .collect();
+    let counts = DiffCounts {
+        before: before.len(),
+        after: after.len(),
+        added: added.len(),
+        removed: removed.len(),
+        changed: changed.len(),
+    };
+    (counts, added, removed, changed)
+}
+
+/// 时间只显示到分钟
+fn short_time(value: &str) -> &str {
+    value.get(..16).unwrap_or(value)
+}
+
+fn session_line(session: &SnapshotSession) -> String {
+    format!(
+        "{} ~ {}  {}",
+        short_time(&session.start_time),
+        short_time(&session.end_time),
+        session.title
+    )
+}
+
+fn card_line(card: &SnapshotCard) -> String {
+    format!(
+        "{} ~ {}  [{}] {}",
+        short_time(&card.start_time),
+        short_time(&card.end_time),
+        card.category,
+        card.title
+    )
+}
+
+/// 变化了的字段名
+fn session_fields(a: &SnapshotSession, b: &SnapshotSession) -> Vec<&'static str> {
+    [
+        ("结束时间", a.end_time != b.end_time),
+        ("标题", a.title != b.title),
+        ("摘要", a.summary != b.summary),
+        ("标签", a.tags != b.tags),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect()
+}
+
+fn card_fields(a: &SnapshotCard, b: &SnapshotCard) -> Vec<&'static str> {
+    [
+        ("结束时间", a.end_time != b.end_time),
+        (
+            "类别",
+            a.category != b.category || a.subcategory != b.subcategory,
+        ),
+        ("标题", a.title != b.title),
+        ("摘要", a.summary != b.summary),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect()
+}
+
+/// 追加一组条目，超过上限时只列出前若干项
+fn push_list(report: &mut Vec<String>, title: &str, lines: Vec<String>) {
+    if lines.is_empty() {
+        return;
+    }
+    report.push(String::new());
+    report.push(format!("{}（{}）:", title, lines.len()));
+    for line in lines.iter().take(MAX_LISTED) {
+        report.push(format!("  - {}", line));
+    }
+    if lines.len() > MAX_LISTED {
+        report.push(format!("  … 还有 {} 项", lines.len() - MAX_LISTED));
+    }
+}
+
+fn counts_line(name: &str, counts: &DiffCounts) -> String {
+    format!(
+        "{}: {} → {}，新增 {}，删除 {}，修改 {}",
+        name, counts.before, counts.after, counts.added, counts.removed, counts.changed
+    )
+}
+
+/// 对比两份快照，`before_label`/`after_label` 用于报告标题
+pub fn diff_snapshots(
+    before: &Snapshot,
+    after: &Snapshot,
+    before_label: &str,
+    after_label: &str,
+) -> SnapshotDiff {
+    let (sessions, added_sessions, removed_sessions, changed_sessions) =
+        compare(&before.sessions, &after.sessions, |session| {
+            session.start_time.clone()
+        });
+    let (cards, added_cards, removed_cards, changed_cards) =
+        compare(&before.cards, &after.cards, |card| {
+            format!("{}|{}", card.session_start, card.start_time)
+        });
+
+    let mut report = vec![
+        format!("快照对比: {} → {}", before_label, after_label),
+        counts_line("会话", &sessions),
+        counts_line("卡片", &cards),
+    ];
+    if sessions.removed > 0 || cards.removed > 0 {
+        report.push(format!(
+            "⚠ 新快照中缺少 {} 个会话、{} 张卡片，请确认是否为有意删除",
+            sessions.removed, cards.removed
+        ));
+    } else if sessions.added + sessions.changed + cards.added + cards.changed == 0 {
+        report.push("两份快照的会话和卡片一致".to_string());
+    }
+
+    push_list(
+        &mut report,
+        "删除的会话",
+        removed_sessions.into_iter().map(session_line).collect(),
+    );
+    push_list(
+        &mut report,
+        "删除的卡片",
+        removed_cards.into_iter().map(card_line).collect(),
+    );
+    push_list(
+        &mut report,
+        "新增的会话",
+        added_sessions.into_iter().map(session_line).collect(),
+    );
+    push_list(
+        &mut report,
+        "新增的卡片",
+        added_cards.into_iter().map(card_line).collect(),
+    );
+    push_list(
+        &mut report,
+        "修改的会话",
+        changed_sessions
+            .into_iter()
+            .map(|(a, b)| format!("{}（{}）", session_line(b), session_fields(a, b).join("、")))
+            .collect(),
+    );
+    push_list(
+        &mut report,
+        "修改的卡片",
+        changed_cards
+            .into_iter()
+            .map(|(a, b)| format!("{}（{}）", card_line(b), card_fields(a, b).join("、")))
+            .collect(),
+    );
+
+    SnapshotDiff {
+        sessions,
+        cards,
+        report: report.join("\n"),
+    }
+}
+
+/// 读取并对比两份数据库快照
+pub async fn diff_database_files(before: &Path, after: &Path) -> Result<SnapshotDiff, String> {
+    let old = load_snapshot(before).await?;
+    let new = load_snapshot(after).await?;
+    Ok(diff_snapshots(
+        &old,
+        &new,
+        &database_file(before).display().to_string(),
+        &database_file(after).display().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(start: &str, title: &str) -> SnapshotSession {
+        SnapshotSession {
+            start_time: start.to_string(),
+            end_time: start.to_string(),
+            title: title.to_string(),
+            summary: String::new(),
+            tags: "[]".to_string(),
+        }
+    }
+
+    fn card(session_start: &str, start: &str, category: &str) -> SnapshotCard {
+        SnapshotCard {
+            session_start: session_start.to_string(),
+            start_time: start.to_string(),
+            end_time: start.to_string(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            title: "编写代码".to_string(),
+            summary: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_reports_added_removed_and_changed_records() {
+        let s1 = "2025-10-09 09:00:00";
+        let s2 = "2025-10-09 10:00:00";
+        let s3 = "2025-10-09 11:00:00";
+        let before = Snapshot {
+            sessions: vec![session(s1, "上午"), session(s2, "开会")],
+            cards: vec![
+                card(s1, "2025-10-09T09:00:00+08:00", "work"),
+                card(s2, "2025-10-09T10:00:00+08:00", "meeting"),
+            ],
+        };
+        let after = Snapshot {
+            sessions: vec![session(s1, "上午编码"), session(s3, "下午")],
+            cards: vec![
+                card(s1, "2025-10-09T09:00:00+08:00", "learning"),
+                card(s3, "2025-10-09T11:00:00+08:00", "work"),
+            ],
+        };
+
+        let diff = diff_snapshots(&before, &after, "backup.db", "data.db");
+        let expected = DiffCounts {
+            before: 2,
+            after: 2,
+            added: 1,
+            removed: 1,
+            changed: 1,
+        };
+        assert_eq!(diff.sessions, expected);
+        assert_eq!(diff.cards, expected);
+        assert!(diff.report.contains("⚠ 新快照中缺少 1 个会话、1 张卡片"));
+        assert!(diff
+            .report
+            .contains("2025-10-09 10:00 ~ 2025-10-09 10:00  开会"));
+        assert!(diff.report.contains("上午编码（标题）"));
+        assert!(diff.report.contains("[learning] 编写代码（类别）"));
+
+        let same = diff_snapshots(&before, &before, "a", "b");
+        assert_eq!(same.sessions.changed + same.sessions.added, 0);
+        assert!(same.report.contains("两份快照的会话和卡片一致"));
+    }
+}
@@ -0,0 +1,179 @@
+// 稀疏会话 - 时间窗口内只有零星几帧（电脑大部分时间处于休眠）时的兜底分析
+//
+// 帧数太少时视频分析会返回 VIDEO_TOO_SHORT。此时不再删除会话和视频，
+// 而是取一帧画面做单帧分析，生成一张覆盖实际有截图时段的卡片（如"短暂活动 13:05–13:07"），
+// 保留这段记录。单帧分析失败时仍生成卡片，只是没有模型给出的描述
+
+use super::frame_analysis::{analyze_frame, FrameAnalysis};
+use crate::actors::LLMHandle;
+use crate::llm::{AppSites, TimelineAnalysis, TimelineCard};
+use crate::video::VideoUtils;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// 单帧分析生成的卡片置信度（只看到一帧画面，默认进入待复核）
+const STILL_CARD_CONFIDENCE: f64 = 0.3;
+
+/// 相对会话开始的 MM:SS 时间
+fn relative_label(start: DateTime<Utc>, at: DateTime<Utc>) -> String {
+    let secs = (at - start).num_seconds().max(0);
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// 根据单帧分析结果（可能没有）生成稀疏会话的卡片，时间为相对会话开始的 MM:SS
+pub fn still_image_card(
+    session_start: DateTime<Utc>,
+    active_start: DateTime<Utc>,
+    active_end: DateTime<Utc>,
+    analysis: Option<&FrameAnalysis>,
+) -> TimelineCard {
+    let active_end = active_end.max(active_start);
+    let span = format!(
+        "{}–{}",
+        active_start.format("%H:%M"),
+        active_end.format("%H:%M")
+    );
+    let (category, title, detail) = match analysis {
+        Some(analysis) => (
+            analysis.category.clone(),
+            analysis.title.clone(),
+            analysis.description.clone(),
+        ),
+        None => ("other".to_string(), "短暂活动".to_string(), String::new()),
+    };
+    let brief = format!("短暂活动 {}", span);
+
+    TimelineCard {
+        start_time: relative_label(session_start, active_start),
+        end_time: relative_label(session_start, active_end),
+        category,
+        subcategory: "短暂活动".to_string(),
+        title,
+        summary: brief.clone(),
+        detailed_summary: if detail.is_empty() {
+            format!("{}，期间只截取到少量画面", brief)
+        } else {
+            format!("{}，期间只截取到少量画面。{}", brief, detail)
+        },
+        distractions: None,
+        app_sites: AppSites {
+            primary: String::new(),
+            secondary: None,
+        },
+        video_preview_path: None,
+        confidence: Some(STILL_CARD_CONFIDENCE),
+    }
+}
+
+/// 准备用于单帧分析的画面：优先使用仍在磁盘上的截图，否则从视频开头抽一帧
+async fn prepare_still(frame_paths: &[String], video_path: Option<&Path>) -> Option<String> {
+    let existing: Vec<&String> = frame_paths
+        .iter()
+        .filter(|path| Path::new(path).is_file())
+        .collect();
+    if let Some(path) = existing.get(existing.len() / 2) {
+        return Some((*path).clone());
+    }
+
+    let video_path = video_path?;
+    let stem = video_path.file_stem()?.to_string_lossy();
+    let still = std::env::temp_dir().join(format!("sparse_still_{}.jpg", stem));
+    match VideoUtils::extract_frame(video_path, &still, 0.0).await {
+        Ok(()) => Some(still.to_string_lossy().to_string()),
+        Err(e) => {
+            warn!("稀疏会话抽帧失败: {}", e);
+            None
+        }
+    }
+}
+
+/// 视频过短时的兜底分析：单帧分析后生成一张卡片
+///
+/// 返回的卡片时间为相对会话开始的 MM:SS，按会话时间换算（不按视频倍速缩放）
+pub async fn analyze_sparse_session(
+    llm_handle: &LLMHandle,
+    frame_paths: &[String],
+    video_path: Option<&Path>,
+    session_start: DateTime<Utc>,
+    active_start: DateTime<Utc>,
+    active_end: DateTime<Utc>,
+) -> TimelineAnalysis {
+    let still = prepare_still(frame_paths, video_path).await;
+    let analysis = match &still {
+        Some(still) => match analyze_frame(llm_handle, still).await {
+            Ok(analysis) => Some(analysis),
+            Err(e) => {
+                warn!("稀疏会话单帧分析失败，仅保留占位卡片: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(still) = still.filter(|s| s.contains("sparse_still_")) {
+        let _ = tokio::fs::remove_file(still).await;
+    }
+
+    info!(
+        "稀疏会话：生成单帧卡片 {} - {}",
+        active_start.format("%H:%M:%S"),
+        active_end.format("%H:%M:%S")
+    );
+    TimelineAnalysis {
+        segments: Vec::new(),
+        timeline_cards: vec![still_image_card(
+            session_start,
+            active_start,
+            active_end,
+            analysis.as_ref(),
+        )],
+        segment_call_id: None,
+        timeline_call_id: None,
+        segment_call_ids: Vec::new(),
+        timeline_call_ids: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_still_image_card_covers_active_span() {
+        let start = at("2025-10-09T13:00:00Z");
+        let card = still_image_card(
+            start,
+            at("2025-10-09T13:05:10Z"),
+            at("2025-10-09T13:07:40Z"),
+            None,
+        );
+        assert_eq!(card.start_time, "05:10");
+        assert_eq!(card.end_time, "07:40");
+        assert_eq!(card.title, "短暂活动");
+        assert_eq!(card.summary, "短暂活动 13:05–13:07");
+        assert_eq!(card.category, "other");
+
+        let analysis = FrameAnalysis {
+            category: "communication".to_string(),
+            title: "查看邮件".to_string(),
+            description: "在邮箱中浏览收件箱".to_string(),
+            keywords: vec![],
+            productivity_score: None,
+        };
+        let card = still_image_card(
+            start,
+            at("2025-10-09T13:05:10Z"),
+            at("2025-10-09T13:05:10Z"),
+            Some(&analysis),
+        );
+        assert_eq!(card.title, "查看邮件");
+        assert_eq!(card.category, "communication");
+        assert!(card.detailed_summary.ends_with("在邮箱中浏览收件箱"));
+    }
+}
@@ -0,0 +1,221 @@
+// 站会笔记 - 把某天的时间线整理成可直接粘贴到站会群里的 Markdown
+//
+// 内容包括：时长最多的 5 项活动（按卡片标题合并）、专注总时长，
+// 以及卡片中记录的干扰活动（作为阻碍，按时长排序）
+
+use super::day_text::load_day_cards;
+use super::metrics::{load_activity_spans, ActivitySpan};
+use super::records::day_focus;
+use super::summary::format_duration;
+use crate::llm::plugin::Distraction;
+use crate::storage::{Database, TimelineCardRecord};
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 最多列出的活动数
+const TOP_ACTIVITIES: usize = 5;
+
+/// 最多列出的阻碍数
+const MAX_BLOCKERS: usize = 3;
+
+/// 站会笔记中的一项活动
+#[derive(Debug, Clone, Serialize)]
+pub struct StandupItem {
+    pub title: String,
+    pub category: String,
+    pub minutes: i64,
+}
+
+/// 站会笔记
+#[derive(Debug, Clone, Serialize)]
+pub struct StandupNote {
+    pub date: String,
+    pub top_activities: Vec<StandupItem>,
+    /// 专注总时长（分钟）
+    pub focus_minutes: i64,
+    /// 干扰活动（阻碍）
+    pub blockers: Vec<StandupItem>,
+    pub markdown: String,
+}
+
+fn card_minutes(card: &TimelineCardRecord) -> i64 {
+    ActivitySpan::from_card(card, "")
+        .map(|span| span.duration_minutes())
+        .unwrap_or(0)
+}
+
+fn distraction_minutes(distraction: &Distraction) -> i64 {
+    match (
+        DateTime::parse_from_rfc3339(&distraction.start_time),
+        DateTime::parse_from_rfc3339(&distraction.end_time),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_minutes().max(0),
+        _ => 0,
+    }
+}
+
+/// 按标题合并并按时长降序，时长相同时按标题排序
+fn rank(items: HashMap<String, StandupItem>, limit: usize) -> Vec<StandupItem> {
+    let mut items: Vec<StandupItem> = items.into_values().collect();
+    items.sort_by(|a, b| b.minutes.cmp(&a.minutes).then(a.title.cmp(&b.title)));
+    items.truncate(limit);
+    items
+}
+
+/// 由时间线卡片生成站会笔记
+pub fn build_standup_note(
+    date: &str,
+    cards: &[TimelineCardRecord],
+    focus_minutes: i64,
+) -> StandupNote {
+    let mut activities: HashMap<String, StandupItem> = HashMap::new();
+    let mut blockers: HashMap<String, StandupItem> = HashMap::new();
+    for card in cards {
+        let title = card.title.trim();
+        if !title.is_empty() && !card.category.eq_ignore_ascii_case("idle") {
+            activities
+                .entry(title.to_string())
+                .or_insert_with(|| StandupItem {
+                    title: title.to_string(),
+                    category: card.category.clone(),
+                    minutes: 0,
+                })
+                .minutes += card_minutes(card);
+        }
+
+        let distractions: Vec<Distraction> = card
+            .distractions
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        for distraction in distractions {
+            let title = distraction.title.trim().to_string();
+            if title.is_empty() {
+                continue;
+            }
+            blockers
+                .entry(title.clone())
+                .or_insert_with(|| StandupItem {
+                    title,
+                    category: card.category.clone(),
+                    minutes: 0,
+                })
+                .minutes += distraction_minutes(&distraction);
+        }
+    }
+
+    let top_activities = rank(activities, TOP_ACTIVITIES);
+    let blockers = rank(blockers, MAX_BLOCKERS);
+
+    let mut markdown = format!("**{} 工作记录**\n\n", date);
+    if top_activities.is_empty() {
+        markdown.push_str("- 没有活动记录\n");
+    }
+    for (index, item) in top_activities.iter().enumerate() {
+        markdown.push_str(&format!(
+            "{}. {}（{}）\n",
+            index + 1,
+            item.title,
+            format_duration(item.minutes)
+        ));
+    }
+    markdown.push_str(&format!(
+        "\n**专注时长**：{}\n",
+        format_duration(focus_minutes)
+    ));
+    markdown.push_str("\n**阻碍**：");
+    if blockers.is_empty() {
+        markdown.push_str("无\n");
+    } else {
+        markdown.push('\n');
+        for item in &blockers {
+            if item.minutes > 0 {
+                markdown.push_str(&format!(
+                    "- {}（{}）\n",
+                    item.title,
+                    format_duration(item.minutes)
+                ));
+            } else {
+                markdown.push_str(&format!("- {}\n", item.title));
+            }
+        }
+    }
+
+    StandupNote {
+        date: date.to_string(),
+        top_activities,
+        focus_minutes,
+        blockers,
+        markdown,
+    }
+}
+
+/// 导出某天的站会笔记
+pub async fn export_standup_note(db: &Database, date: &str) -> Result<StandupNote, String> {
+    let cards = load_day_cards(db, date).await?;
+    let focus_minutes = day_focus(&load_activity_spans(db, date).await?).focus_minutes;
+    Ok(build_standup_note(date, &cards, focus_minutes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(
+        title: &str,
+        category: &str,
+        start: &str,
+        end: &str,
+        distractions: Option<&str>,
+    ) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: format!("2025-10-09T{}:00+08:00", start),
+            end_time: format!("2025-10-09T{}:00+08:00", end),
+            category: category.to_string(),
+            subcategory: String::new(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: distractions.map(str::to_string),
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_standup_note_merges_titles_and_lists_blockers() {
+        let distractions = serde_json::json!([{
+            "startTime": "2025-10-09T10:10:00+08:00",
+            "endTime": "2025-10-09T10:25:00+08:00",
+            "title": "刷微博",
+            "summary": "浏览微博热搜"
+        }])
+        .to_string();
+        let cards = vec![
+            card("代码评审", "work", "09:00", "09:30", None),
+            card("接口开发", "work", "09:30", "10:30", Some(&distractions)),
+            card("代码评审", "work", "11:00", "11:45", None),
+            card("离开", "idle", "12:00", "13:30", None),
+        ];
+
+        let note = build_standup_note("2025-10-09", &cards, 135);
+        assert_eq!(note.top_activities.len(), 2);
+        assert_eq!(note.top_activities[0].title, "代码评审");
+        assert_eq!(note.top_activities[0].minutes, 75);
+        assert_eq!(note.blockers[0].minutes, 15);
+        assert!(note.markdown.contains("1. 代码评审（1h 15m）"));
+        assert!(note.markdown.contains("**专注时长**：2h 15m"));
+        assert!(note.markdown.contains("- 刷微博（15m）"));
+    }
+}
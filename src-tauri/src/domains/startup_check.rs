@@ -0,0 +1,308 @@
+// 启动自检 - 启动时按顺序检查运行所需的条件，结果以可操作的提示展示在界面上
+//
+// 检查项：ffmpeg 是否可用、数据库是否可访问、截图目录是否可写、LLM 服务商是否可用、
+// 截屏权限是否已授予。每项给出结果、原因和建议操作，避免问题只留在日志里。
+// 仅采集的机器不检查 LLM，仅分析的机器不检查截屏权限和截图目录。
+// LLM 检查复用健康监测的轻量探测，不消耗 token
+
+use crate::models::{MachineRole, PersistedAppConfig};
+use crate::storage::Database;
+use serde::Serialize;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// 检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    /// 可以运行，但部分功能不可用
+    Warning,
+    /// 核心功能不可用
+    Failed,
+    /// 本机不需要或无法检查
+    Skipped,
+}
+
+/// 单项检查
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupCheck {
+    /// 检查项标识（ffmpeg / database / frames_dir / llm / screen_permission）
+    pub id: String,
+    pub title: String,
+    pub status: CheckStatus,
+    /// 检查结果说明
+    pub message: String,
+    /// 建议操作（通过时为空）
+    pub action: Option<String>,
+}
+
+impl StartupCheck {
+    fn new(id: &str, title: &str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            status,
+            message: message.into(),
+            action: None,
+        }
+    }
+
+    fn with_action(mut self, action: &str) -> Self {
+        self.action = Some(action.to_string());
+        self
+    }
+}
+
+/// 启动自检报告
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub checked_at: String,
+    pub checks: Vec<StartupCheck>,
+    /// 是否存在失败项
+    pub has_failures: bool,
+}
+
+/// 按顺序执行全部检查
+pub async fn run_startup_checks(
+    config: &PersistedAppConfig,
+    db: Option<&Database>,
+    frames_dir: &Path,
+) -> StartupReport {
+    let role = config
+        .instance_settings
+        .as_ref()
+        .map(|s| s.machine_role)
+        .unwrap_or_default();
+
+    let mut checks = vec![check_ffmpeg().await, check_database(db).await];
+    if role.captures() {
+        checks.push(check_frames_dir(frames_dir));
+        checks.push(check_screen_permission().await);
+    }
+    checks.push(check_llm(config, role).await);
+
+    for check in &checks {
+        match check.status {
+            CheckStatus::Failed => warn!("启动自检未通过 [{}]: {}", check.title, check.message),
+            CheckStatus::Warning => warn!("启动自检警告 [{}]: {}", check.title, check.message),
+            _ => {}
+        }
+    }
+    let has_failures = checks.iter().any(|c| c.status == CheckStatus::Failed);
+    info!(
+        "启动自检完成: {} 项检查，{}",
+        checks.len(),
+        if has_failures {
+            "存在失败项"
+        } else {
+            "全部可用"
+        }
+    );
+
+    StartupReport {
+        checked_at: crate::storage::local_now()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        checks,
+        has_failures,
+    }
+}
+
+async fn check_ffmpeg() -> StartupCheck {
+    const TITLE: &str = "ffmpeg";
+    if crate::video::ffmpeg_helper::check_ffmpeg_available().await {
+        return StartupCheck::new("ffmpeg", TITLE, CheckStatus::Passed, "ffmpeg 可用");
+    }
+    let reason = match crate::video::ffmpeg_helper::get_ffmpeg_path() {
+        Ok(path) => format!("无法执行 {}", path.display()),
+        Err(e) => e.to_string(),
+    };
+    StartupCheck::new(
+        "ffmpeg",
+        TITLE,
+        CheckStatus::Failed,
+        format!("ffmpeg 不可用，无法生成视频: {}", reason),
+    )
+    .with_action("安装 ffmpeg（macOS 可执行 brew install ffmpeg）并确保在 PATH 中，或重新安装自带 ffmpeg 的安装包")
+}
+
+async fn check_database(db: Option<&Database>) -> StartupCheck {
+    const TITLE: &str = "数据库";
+    let Some(db) = db else {
+        return StartupCheck::new("database", TITLE, CheckStatus::Failed, "数据库未能初始化")
+            .with_action("查看设置中的数据库配置；使用 MariaDB 时确认地址、账号和网络可达，使用本地数据库时确认数据目录可写");
+    };
+    match db.get_stats().await {
+        Ok(_) => match db.failover_status().filter(|status| status.degraded) {
+            Some(_) => StartupCheck::new(
+                "database",
+                TITLE,
+                CheckStatus::Warning,
+                "无法连接远程数据库，新数据暂存本地",
+            )
+            .with_action("检查 MariaDB 服务和网络，恢复连接后暂存的数据会自动同步"),
+            None => StartupCheck::new("database", TITLE, CheckStatus::Passed, "数据库可访问"),
+        },
+        Err(e) => StartupCheck::new(
+            "database",
+            TITLE,
+            CheckStatus::Failed,
+            format!("数据库查询失败: {}", e),
+        )
+        .with_action("查看设置中的数据库配置，或在设置中运行数据检查修复数据库"),
+    }
+}
+
+/// 检查截图目录可写（写入并删除一个临时文件）
+fn check_frames_dir(frames_dir: &Path) -> StartupCheck {
+    const TITLE: &str = "截图目录";
+    let probe = frames_dir.join(".startup_check");
+    let result = std::fs::create_dir_all(frames_dir)
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => StartupCheck::new(
+            "frames_dir",
+            TITLE,
+            CheckStatus::Passed,
+            format!("{} 可写", frames_dir.display()),
+        ),
+        Err(e) => StartupCheck::new(
+            "frames_dir",
+            TITLE,
+            CheckStatus::Failed,
+            format!("{} 不可写: {}", frames_dir.display(), e),
+        )
+        .with_action("确认磁盘未满、目录权限正确（网络共享已挂载），或在设置中更换数据目录"),
+    }
+}
+
+/// 当前 LLM 服务商是否缺少 API Key
+pub fn missing_api_key(config: &PersistedAppConfig) -> bool {
+    match (config.llm_provider.as_str(), config.llm_config.as_ref()) {
+        (_, None) => true,
+        ("openai", Some(llm)) => llm.api_key.trim().is_empty(),
+        _ => false,
+    }
+}
+
+async fn check_llm(config: &PersistedAppConfig, role: MachineRole) -> StartupCheck {
+    const TITLE: &str = "LLM 服务";
+    if !role.analyzes() {
+        return StartupCheck::new(
+            "llm",
+            TITLE,
+            CheckStatus::Skipped,
+            "本机仅采集，由分析机器调用 LLM",
+        );
+    }
+
+    let provider = config.llm_provider.as_str();
+    if missing_api_key(config) {
+        return StartupCheck::new(
+            "llm",
+            TITLE,
+            CheckStatus::Warning,
+            format!("{} 未配置 API Key，录制的视频不会被分析", provider),
+        )
+        .with_action("在设置的 LLM 配置中填写 API Key 并测试连接");
+    }
+
+    match super::provider_health::ping_provider(provider, config.llm_config.as_ref()).await {
+        Ok(()) => StartupCheck::new(
+            "llm",
+            TITLE,
+            CheckStatus::Passed,
+            format!("{} 可用", provider),
+        ),
+        Err(e) => StartupCheck::new(
+            "llm",
+            TITLE,
+            CheckStatus::Failed,
+            format!("{} 不可用: {}", provider, e),
+        )
+        .with_action("在设置中检查 API Key、服务地址和网络代理，然后测试连接"),
+    }
+}
+
+async fn check_screen_permission() -> StartupCheck {
+    const TITLE: &str = "截屏权限";
+    match tokio::task::spawn_blocking(screen_capture_allowed)
+        .await
+        .ok()
+        .flatten()
+    {
+        Some(true) => StartupCheck::new(
+            "screen_permission",
+            TITLE,
+            CheckStatus::Passed,
+            "已允许截屏",
+        ),
+        Some(false) => StartupCheck::new(
+            "screen_permission",
+            TITLE,
+            CheckStatus::Failed,
+            "没有截屏权限，截图只有桌面背景或无法截取",
+        )
+        .with_action(SCREEN_PERMISSION_ACTION),
+        None => StartupCheck::new(
+            "screen_permission",
+            TITLE,
+            CheckStatus::Skipped,
+            "无法检测截屏权限",
+        ),
+    }
+}
+
+#[cfg(target_os = "macos")]
+const SCREEN_PERMISSION_ACTION: &str =
+    "打开 系统设置 → 隐私与安全性 → 屏幕录制，允许本应用后重启应用";
+#[cfg(not(target_os = "macos"))]
+const SCREEN_PERMISSION_ACTION: &str = "确认显示器已连接，远程桌面会话未断开";
+
+/// 是否允许截屏（None 表示无法检测）
+#[cfg(target_os = "macos")]
+fn screen_capture_allowed() -> Option<bool> {
+    // 与锁屏检测相同，通过 Quartz 查询（CGPreflightScreenCaptureAccess 不会弹出授权提示）
+    let output = std::process::Command::new("python3")
+        .arg("-c")
+        .arg("import Quartz; print(Quartz.CGPreflightScreenCaptureAccess())")
+        .output()
+        .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "True" => Some(true),
+        "False" => Some(false),
+        _ => None,
+    }
+}
+
+/// 是否允许截屏：Windows/Linux 不需要授权，能枚举到显示器即可截屏
+#[cfg(not(target_os = "macos"))]
+fn screen_capture_allowed() -> Option<bool> {
+    screenshots::Screen::all()
+        .ok()
+        .map(|screens| !screens.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_dir_check_reports_unwritable_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let frames = dir.path().join("frames");
+        let check = check_frames_dir(&frames);
+        assert_eq!(check.status, CheckStatus::Passed);
+        assert!(check.action.is_none());
+        assert!(!frames.join(".startup_check").exists());
+
+        // 路径被普通文件占用，无法创建目录
+        let blocked = dir.path().join("blocked");
+        std::fs::write(&blocked, b"file").unwrap();
+        let check = check_frames_dir(&blocked.join("frames"));
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.action.is_some());
+    }
+}
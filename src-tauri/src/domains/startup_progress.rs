@@ -0,0 +1,164 @@
+// 启动进度 - 窗口先显示，截屏、数据库、存储清理和 LLM 在后台逐步初始化
+//
+// 每个阶段开始、完成或失败时更新进度，并通过 startup-progress 事件通知前端；
+// 前端加载晚于事件时调用 get_startup_progress 读取当前进度
+
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// 启动阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupStage {
+    /// 枚举屏幕
+    Capture,
+    /// 连接数据库
+    Database,
+    /// 存储清理器
+    Cleaner,
+    /// 加载 LLM 配置
+    Llm,
+    /// 启动后台任务
+    Tasks,
+}
+
+impl StartupStage {
+    const ALL: [StartupStage; 5] = [
+        Self::Capture,
+        Self::Database,
+        Self::Cleaner,
+        Self::Llm,
+        Self::Tasks,
+    ];
+
+    /// 阶段名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Capture => "截屏",
+            Self::Database => "数据库",
+            Self::Cleaner => "存储清理",
+            Self::Llm => "LLM 服务",
+            Self::Tasks => "后台任务",
+        }
+    }
+}
+
+/// 阶段状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+/// 单个阶段的进度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageProgress {
+    pub stage: StartupStage,
+    pub label: &'static str,
+    pub state: StageState,
+    /// 完成或失败时的说明（例如检测到的屏幕数、失败原因）
+    pub message: Option<String>,
+}
+
+/// 启动进度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProgress {
+    pub stages: Vec<StageProgress>,
+    /// 所有阶段都已结束（完成或失败）
+    pub ready: bool,
+}
+
+/// 启动进度跟踪
+pub struct StartupTracker {
+    progress: RwLock<StartupProgress>,
+}
+
+impl Default for StartupTracker {
+    fn default() -> Self {
+        let stages = StartupStage::ALL
+            .iter()
+            .map(|stage| StageProgress {
+                stage: *stage,
+                label: stage.label(),
+                state: StageState::Pending,
+                message: None,
+            })
+            .collect();
+        Self {
+            progress: RwLock::new(StartupProgress {
+                stages,
+                ready: false,
+            }),
+        }
+    }
+}
+
+impl StartupTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 更新阶段状态，返回更新后的进度
+    pub fn update(
+        &self,
+        stage: StartupStage,
+        state: StageState,
+        message: Option<String>,
+    ) -> StartupProgress {
+        let mut progress = self
+            .progress
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(entry) = progress.stages.iter_mut().find(|s| s.stage == stage) {
+            entry.state = state;
+            entry.message = message;
+        }
+        progress.ready = progress
+            .stages
+            .iter()
+            .all(|s| matches!(s.state, StageState::Done | StageState::Failed));
+        progress.clone()
+    }
+
+    /// 当前进度
+    pub fn snapshot(&self) -> StartupProgress {
+        self.progress
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_once_every_stage_has_finished() {
+        let tracker = StartupTracker::new();
+        assert!(!tracker.snapshot().ready);
+
+        tracker.update(StartupStage::Capture, StageState::Running, None);
+        for stage in StartupStage::ALL {
+            let progress = tracker.update(stage, StageState::Done, None);
+            assert_eq!(progress.ready, stage == StartupStage::Tasks);
+        }
+
+        // 失败的阶段同样视为已结束
+        let progress = tracker.update(
+            StartupStage::Database,
+            StageState::Failed,
+            Some("数据库初始化失败".to_string()),
+        );
+        assert!(progress.ready);
+        assert_eq!(
+            progress.stages[1].message.as_deref(),
+            Some("数据库初始化失败")
+        );
+    }
+}
@@ -1,12 +1,14 @@
 // 存储领域管理器
 //
 // 负责数据库、存储清理和设置管理相关的功能
-// 包含 Database、StorageCleaner 和 SettingsManager 三个核心组件
+// 包含 Database、StorageCleaner、SettingsManager 和 MediaLibrary 等核心组件
 
 use crate::notion::NotionManager;
+use crate::rag::RagExporter;
 use crate::settings::SettingsManager;
 use crate::storage::cleaner::StorageCleaner;
 use crate::storage::database::Database;
+use crate::storage::media_store::MediaLibrary;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -34,22 +36,33 @@ pub struct StorageDomain {
     settings: Arc<SettingsManager>,
     /// Notion 同步管理器
     notion_manager: Arc<NotionManager>,
+    /// RAG 向量库导出器
+    rag_exporter: Arc<RagExporter>,
+    /// 媒体库（视频的存储后端）
+    media: Arc<MediaLibrary>,
 }
 
 impl StorageDomain {
     /// 创建新的存储领域管理器（数据库未初始化）
-    pub fn new_pending(settings: Arc<SettingsManager>) -> Self {
+    pub fn new_pending(
+        settings: Arc<SettingsManager>,
+        rag_exporter: Arc<RagExporter>,
+        media: Arc<MediaLibrary>,
+    ) -> Self {
         Self {
             db: Arc::new(RwLock::new(None)),
             db_status: Arc::new(RwLock::new(DatabaseStatus::Initializing)),
             cleaner: Arc::new(RwLock::new(None)),
             settings,
             notion_manager: Arc::new(NotionManager::new()),
+            rag_exporter,
+            media,
         }
     }
 
     /// 设置数据库实例（异步初始化完成后调用）
     pub async fn set_database(&self, db: Arc<Database>) {
+        super::egress::set_database(db.clone());
         let mut db_lock = self.db.write().await;
         *db_lock = Some(db);
 
@@ -182,4 +195,14 @@ impl StorageDomain {
     pub fn get_notion_manager(&self) -> &Arc<NotionManager> {
         &self.notion_manager
     }
+
+    /// 获取 RAG 导出器
+    pub fn get_rag_exporter(&self) -> &Arc<RagExporter> {
+        &self.rag_exporter
+    }
+
+    /// 获取媒体库
+    pub fn get_media(&self) -> &Arc<MediaLibrary> {
+        &self.media
+    }
 }
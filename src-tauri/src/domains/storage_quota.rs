@@ -0,0 +1,261 @@
+// 存储配额 - 接近配额时自动降低新视频的画质，而不是更早地删除历史
+//
+// 每 10 分钟统计一次数据目录占用（数据库 + 截图 + 视频），按占用比例分三级降级：
+// - 80%：视频 CRF +4
+// - 90%：视频 CRF +8，高度上限 1080
+// - 97%：视频 CRF +12，高度上限 720
+// 开启“同时降低截图质量”时，截图的 JPEG 质量每级降低 10（不低于 40）。
+// 降级只影响之后生成的视频和截图，已有的文件不会重新编码；占用回落后自动恢复
+
+use crate::models::StorageQuotaSettings;
+use crate::settings::SettingsManager;
+use crate::storage::cleaner::StorageCleaner;
+use crate::video::VideoConfig;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::{error, info};
+
+/// 检查间隔（秒）
+const CHECK_INTERVAL_SECS: u64 = 600;
+/// 各级降级的占用比例下限
+const LEVEL_THRESHOLDS: [f64; 3] = [0.80, 0.90, 0.97];
+/// 每级增加的 CRF
+const CRF_STEP: u8 = 4;
+/// 降级后的 CRF 上限
+const MAX_DEGRADED_CRF: u8 = 40;
+/// 各级的视频高度上限（0 级、1 级不限制）
+const LEVEL_MAX_HEIGHTS: [Option<u32>; 4] = [None, None, Some(1080), Some(720)];
+/// 每级降低的 JPEG 质量
+const JPEG_STEP: u8 = 10;
+/// 降级后的 JPEG 质量下限
+const MIN_DEGRADED_JPEG: u8 = 40;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// 画质降级状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityDegradeStatus {
+    /// 降级级别（0 表示未降级）
+    pub level: u8,
+    /// 当前占用（字节）
+    pub usage_bytes: u64,
+    /// 配额（字节，0 表示未设置）
+    pub quota_bytes: u64,
+    /// 降级视频
+    pub degrade_video: bool,
+    /// 降级截图
+    pub degrade_frames: bool,
+    /// 进入当前级别的时间（本地时间，YYYY-MM-DD HH:MM）
+    pub since: Option<String>,
+    /// 说明（如“视频 CRF +8，高度上限 1080”）
+    pub description: String,
+}
+
+impl QualityDegradeStatus {
+    /// 是否处于降级状态
+    pub fn is_degraded(&self) -> bool {
+        self.level > 0 && (self.degrade_video || self.degrade_frames)
+    }
+}
+
+static STATUS: OnceLock<RwLock<QualityDegradeStatus>> = OnceLock::new();
+
+fn status_cell() -> &'static RwLock<QualityDegradeStatus> {
+    STATUS.get_or_init(|| RwLock::new(QualityDegradeStatus::default()))
+}
+
+/// 当前的降级状态
+pub fn current_status() -> QualityDegradeStatus {
+    status_cell()
+        .read()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
+
+/// 按占用比例计算降级级别
+pub fn level_for(usage_bytes: u64, quota_bytes: u64) -> u8 {
+    if quota_bytes == 0 {
+        return 0;
+    }
+    let ratio = usage_bytes as f64 / quota_bytes as f64;
+    LEVEL_THRESHOLDS
+        .iter()
+        .filter(|threshold| ratio >= **threshold)
+        .count() as u8
+}
+
+/// 按级别降低视频配置
+pub fn degrade_video_config(config: &mut VideoConfig, level: u8) {
+    if level == 0 {
+        return;
+    }
+    let degraded = config.quality.saturating_add(CRF_STEP * level);
+    config.quality = degraded.min(MAX_DEGRADED_CRF).max(config.quality);
+    if let Some(max_height) = LEVEL_MAX_HEIGHTS[level.min(3) as usize] {
+        config.max_height = Some(config.max_height.map_or(max_height, |h| h.min(max_height)));
+    }
+}
+
+/// 按级别降低 JPEG 质量
+pub fn degrade_jpeg_quality(quality: u8, level: u8) -> u8 {
+    if level == 0 {
+        return quality;
+    }
+    quality
+        .saturating_sub(JPEG_STEP * level)
+        .max(MIN_DEGRADED_JPEG)
+        .min(quality)
+}
+
+fn describe(level: u8, settings: &StorageQuotaSettings) -> String {
+    if level == 0 {
+        return "未降级".to_string();
+    }
+    let mut parts = Vec::new();
+    if settings.degrade_video {
+        let mut video = format!("视频 CRF +{}", CRF_STEP * level);
+        if let Some(max_height) = LEVEL_MAX_HEIGHTS[level.min(3) as usize] {
+            video.push_str(&format!("，高度上限 {}", max_height));
+        }
+        parts.push(video);
+    }
+    if settings.degrade_frames {
+        parts.push(format!("截图质量 -{}", JPEG_STEP * level));
+    }
+    parts.join("；")
+}
+
+/// 根据当前占用计算降级状态（级别不变时保留原来的开始时间）
+pub fn evaluate(
+    usage_bytes: u64,
+    settings: &StorageQuotaSettings,
+    previous: &QualityDegradeStatus,
+    now: &str,
+) -> QualityDegradeStatus {
+    let quota_bytes = (settings.quota_gb.max(0.0) * BYTES_PER_GB) as u64;
+    let level = level_for(usage_bytes, quota_bytes);
+    let since = if level == 0 {
+        None
+    } else if level == previous.level {
+        previous.since.clone()
+    } else {
+        Some(now.to_string())
+    };
+    QualityDegradeStatus {
+        level,
+        usage_bytes,
+        quota_bytes,
+        degrade_video: settings.degrade_video,
+        degrade_frames: settings.degrade_frames,
+        since,
+        description: describe(level, settings),
+    }
+}
+
+/// 按当前降级状态调整新视频的配置
+pub fn apply_to_video(config: &mut VideoConfig) {
+    let status = current_status();
+    if status.degrade_video && status.level > 0 {
+        degrade_video_config(config, status.level);
+        info!(
+            "存储接近配额，降级编码: CRF {}，高度上限 {:?}",
+            config.quality, config.max_height
+        );
+    }
+}
+
+/// 按当前降级状态调整截图的 JPEG 质量
+pub fn apply_to_jpeg(quality: u8) -> u8 {
+    let status = current_status();
+    if status.degrade_frames {
+        degrade_jpeg_quality(quality, status.level)
+    } else {
+        quality
+    }
+}
+
+/// 重新统计占用并更新降级状态
+pub async fn refresh(cleaner: &StorageCleaner, settings: &SettingsManager) {
+    let quota = settings.get().await.storage_quota.unwrap_or_default();
+    let usage_bytes = if quota.quota_gb > 0.0 {
+        match cleaner.get_storage_stats().await {
+            Ok(stats) => stats.total_size.max(0) as u64,
+            Err(e) => {
+                error!("统计存储占用失败: {}", e);
+                return;
+            }
+        }
+    } else {
+        0
+    };
+
+    let now = crate::storage::local_now()
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
+    let previous = current_status();
+    let status = evaluate(usage_bytes, &quota, &previous, &now);
+    if status.level != previous.level {
+        info!(
+            "存储占用 {:.2} GB / 配额 {:.2} GB，画质降级级别 {} -> {}（{}）",
+            usage_bytes as f64 / BYTES_PER_GB,
+            quota.quota_gb,
+            previous.level,
+            status.level,
+            status.description
+        );
+    }
+    if let Ok(mut current) = status_cell().write() {
+        *current = status;
+    }
+}
+
+/// 启动存储配额检查任务
+pub fn start_quota_monitor(cleaner: Arc<StorageCleaner>, settings: Arc<SettingsManager>) {
+    tokio::spawn(async move {
+        info!("存储配额检查任务已启动");
+        loop {
+            refresh(&cleaner, &settings).await;
+            tokio::time::sleep(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrade_steps_with_usage() {
+        let gb = BYTES_PER_GB as u64;
+        let settings = StorageQuotaSettings {
+            quota_gb: 10.0,
+            degrade_video: true,
+            degrade_frames: true,
+        };
+        let idle = QualityDegradeStatus::default();
+
+        let status = evaluate(7 * gb, &settings, &idle, "2025-10-09 10:00");
+        assert_eq!(status.level, 0);
+        assert!(!status.is_degraded());
+
+        let status = evaluate(9 * gb + gb / 2, &settings, &idle, "2025-10-09 10:00");
+        assert_eq!(status.level, 2);
+        assert_eq!(status.since.as_deref(), Some("2025-10-09 10:00"));
+        assert_eq!(
+            status.description,
+            "视频 CRF +8，高度上限 1080；截图质量 -20"
+        );
+        // 级别不变时保留开始时间
+        let later = evaluate(9 * gb + gb / 2, &settings, &status, "2025-10-09 10:10");
+        assert_eq!(later.since.as_deref(), Some("2025-10-09 10:00"));
+
+        let mut config = VideoConfig::default();
+        degrade_video_config(&mut config, 3);
+        assert_eq!(config.quality, 35);
+        assert_eq!(config.max_height, Some(720));
+        assert_eq!(degrade_jpeg_quality(85, 2), 65);
+        assert_eq!(degrade_jpeg_quality(45, 3), 40);
+        assert_eq!(degrade_jpeg_quality(30, 1), 30);
+        assert_eq!(level_for(100, 0), 0);
+    }
+}
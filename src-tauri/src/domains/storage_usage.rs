@@ -0,0 +1,265 @@
+// 存储用量领域 - 截图原始大小 / 视频编码后大小 / 清理后保留大小
+//
+// 截图在生成视频后会被删除，因此在编码时把原始截图和视频的大小记入 storage_usage 表；
+// 清理后的保留量按磁盘上仍存在的视频和截图实时统计。
+// 结合保留天数推算稳定后的存储占用，帮助用户根据真实数据调整画质和保留天数
+
+use super::metrics::StatsPeriod;
+use crate::storage::{Database, StorageUsageRecord};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::warn;
+
+/// 单日压缩统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyCompressionStat {
+    pub date: String,
+    /// 原始截图大小
+    pub captured_bytes: i64,
+    pub captured_frames: i64,
+    /// 视频编码后大小
+    pub encoded_bytes: i64,
+    pub encoded_videos: i64,
+    /// 清理后仍保留在磁盘上的大小（视频 + 未编码的截图）
+    pub retained_bytes: i64,
+    /// 编码后大小 / 原始大小
+    pub compression_ratio: Option<f64>,
+}
+
+/// 压缩统计与存储预测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub period: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub daily: Vec<DailyCompressionStat>,
+    pub total_captured_bytes: i64,
+    pub total_encoded_bytes: i64,
+    pub total_retained_bytes: i64,
+    pub compression_ratio: Option<f64>,
+    /// 有录制的日子平均每天新增的视频大小
+    pub avg_daily_encoded_bytes: i64,
+    pub retention_days: i64,
+    /// 当前总占用（数据库 + 截图 + 视频）
+    pub current_usage_bytes: i64,
+    /// 按当前速率、保留天数推算的稳定占用
+    pub projected_usage_bytes: i64,
+    /// 数据目录所在磁盘的可用空间
+    pub free_bytes: Option<u64>,
+    /// 按当前速率磁盘写满前的天数（稳定占用放得下时为 None）
+    pub days_until_full: Option<i64>,
+}
+
+/// 计算一组文件的总大小（不存在的文件忽略）
+pub async fn files_size(paths: &[String]) -> i64 {
+    let mut total = 0i64;
+    for path in paths {
+        if let Ok(meta) = tokio::fs::metadata(path).await {
+            total += meta.len() as i64;
+        }
+    }
+    total
+}
+
+/// 记录一次视频编码的用量（需在删除原始截图前调用），失败只记录警告
+pub async fn record_encoding(
+    db: &Database,
+    date: NaiveDate,
+    frame_paths: &[String],
+    video_path: &str,
+) {
+    let video_bytes = tokio::fs::metadata(video_path)
+        .await
+        .map(|meta| meta.len() as i64)
+        .unwrap_or(0);
+    let usage = StorageUsageRecord {
+        date: date.format("%Y-%m-%d").to_string(),
+        device_name: crate::storage::get_device_info().0,
+        captured_bytes: files_size(frame_paths).await,
+        captured_frames: frame_paths.len() as i64,
+        encoded_bytes: video_bytes,
+        encoded_videos: 1,
+    };
+
+    if let Err(e) = db.add_storage_usage(&usage).await {
+        warn!("记录存储用量失败: {}", e);
+    }
+}
+
+/// 记录旧视频转码节省的空间（从当天的视频大小中扣除），失败只记录警告
+pub async fn record_reencode(db: &Database, date: NaiveDate, device_name: &str, saved_bytes: i64) {
+    let usage = StorageUsageRecord {
+        date: date.format("%Y-%m-%d").to_string(),
+        device_name: device_name.to_string(),
+        captured_bytes: 0,
+        captured_frames: 0,
+        encoded_bytes: -saved_bytes,
+        encoded_videos: 0,
+    };
+
+    if let Err(e) = db.add_storage_usage(&usage).await {
+        warn!("记录转码节省的空间失败: {}", e);
+    }
+}
+
+fn ratio(encoded: i64, captured: i64) -> Option<f64> {
+    (captured > 0 && encoded > 0).then(|| encoded as f64 / captured as f64)
+}
+
+/// 获取压缩统计
+///
+/// `current_usage_bytes` 和 `retention_days` 来自存储清理器的统计
+pub async fn get_compression_stats(
+    db: &Database,
+    frames_dir: &Path,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+    retention_days: i64,
+    current_usage_bytes: i64,
+) -> Result<CompressionStats, String> {
+    let (start_date, end_date) = period.date_range(anchor);
+    let start = start_date.format("%Y-%m-%d").to_string();
+    let end = end_date.format("%Y-%m-%d").to_string();
+    let device_name = crate::storage::get_device_info().0;
+
+    let mut daily: BTreeMap<String, DailyCompressionStat> = BTreeMap::new();
+    let mut date = start_date;
+    while date <= end_date {
+        let key = date.format("%Y-%m-%d").to_string();
+        daily.insert(
+            key.clone(),
+            DailyCompressionStat {
+                date: key,
+                ..Default::default()
+            },
+        );
+        date += Duration::days(1);
+    }
+
+    let usage = db
+        .get_storage_usage(&device_name, &start, &end)
+        .await
+        .map_err(|e| e.to_string())?;
+    for record in usage {
+        if let Some(stat) = daily.get_mut(&record.date) {
+            stat.captured_bytes = record.captured_bytes;
+            stat.captured_frames = record.captured_frames;
+            stat.encoded_bytes = record.encoded_bytes;
+            stat.encoded_videos = record.encoded_videos;
+        }
+    }
+
+    // 保留量：本机会话仍存在的视频
+    for (date, stat) in daily.iter_mut() {
+        let sessions = db
+            .get_sessions_by_date(date)
+            .await
+            .map_err(|e| e.to_string())?;
+        let videos: Vec<String> = sessions
+            .into_iter()
+            .filter(|s| s.device_name.as_deref() == Some(device_name.as_str()))
+            .filter_map(|s| s.video_path)
+            .collect();
+        stat.retained_bytes += files_size(&videos).await;
+    }
+
+    // 保留量：尚未编码的截图（文件名为毫秒时间戳）
+    if let Ok(mut entries) = tokio::fs::read_dir(frames_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let Some(timestamp_ms) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<i64>().ok())
+            else {
+                continue;
+            };
+            let Some(timestamp) = Utc.timestamp_millis_opt(timestamp_ms).single() else {
+                continue;
+            };
+            let key = timestamp.date_naive().format("%Y-%m-%d").to_string();
+            if let (Some(stat), Ok(meta)) = (daily.get_mut(&key), entry.metadata().await) {
+                stat.retained_bytes += meta.len() as i64;
+            }
+        }
+    }
+
+    let mut daily: Vec<DailyCompressionStat> = daily.into_values().collect();
+    for stat in &mut daily {
+        stat.compression_ratio = ratio(stat.encoded_bytes, stat.captured_bytes);
+    }
+
+    let total_captured_bytes: i64 = daily.iter().map(|d| d.captured_bytes).sum();
+    let total_encoded_bytes: i64 = daily.iter().map(|d| d.encoded_bytes).sum();
+    let total_retained_bytes: i64 = daily.iter().map(|d| d.retained_bytes).sum();
+    let recorded_days = daily.iter().filter(|d| d.encoded_videos > 0).count() as i64;
+    let avg_daily_encoded_bytes = if recorded_days > 0 {
+        total_encoded_bytes / recorded_days
+    } else {
+        0
+    };
+
+    let free_bytes = crate::storage::data_location::available_space(frames_dir);
+    let (projected_usage_bytes, days_until_full) = project_usage(
+        current_usage_bytes,
+        avg_daily_encoded_bytes,
+        retention_days,
+        free_bytes,
+    );
+
+    Ok(CompressionStats {
+        period: period.as_str().to_string(),
+        start_date: start,
+        end_date: end,
+        daily,
+        total_captured_bytes,
+        total_encoded_bytes,
+        total_retained_bytes,
+        compression_ratio: ratio(total_encoded_bytes, total_captured_bytes),
+        avg_daily_encoded_bytes,
+        retention_days,
+        current_usage_bytes,
+        projected_usage_bytes,
+        free_bytes,
+        days_until_full,
+    })
+}
+
+/// 推算稳定占用和磁盘写满前的天数
+///
+/// 保留期内每天新增 `daily_bytes`，超过保留天数的数据被清理，
+/// 因此稳定占用约为 `daily_bytes × retention_days`（不低于当前占用）
+fn project_usage(
+    current: i64,
+    daily_bytes: i64,
+    retention_days: i64,
+    free_bytes: Option<u64>,
+) -> (i64, Option<i64>) {
+    let projected = (daily_bytes * retention_days.max(1)).max(current);
+    let days_until_full = free_bytes.and_then(|free| {
+        let growth = projected - current;
+        (daily_bytes > 0 && growth > free as i64).then(|| free as i64 / daily_bytes)
+    });
+    (projected, days_until_full)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_usage() {
+        // 每天 100 MB、保留 7 天：稳定占用 700 MB
+        let mb = 1024 * 1024;
+        assert_eq!(project_usage(200 * mb, 100 * mb, 7, None), (700 * mb, None));
+        // 还需增长 500 MB，但只剩 300 MB：3 天后写满
+        assert_eq!(
+            project_usage(200 * mb, 100 * mb, 7, Some(300 * mb as u64)),
+            (700 * mb, Some(3))
+        );
+        // 当前占用已超过推算值时取当前值
+        assert_eq!(project_usage(900 * mb, 100 * mb, 7, None), (900 * mb, None));
+    }
+}
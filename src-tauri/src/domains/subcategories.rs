@@ -0,0 +1,191 @@
+// 子类别归一化 - 统一 LLM 给出的各种子类别写法
+//
+// LLM 对同一种活动会给出 "coding"、"Coding"、"程序开发" 等不同的子类别，筛选和统计因此被拆散。
+// 保存卡片前按用户可编辑的同义词表归一化：
+// - 比较时忽略大小写、空格、下划线和连字符
+// - 命中同义词表（别名或标准写法本身）时改为标准写法
+// - 未命中时整理格式：下划线和连字符视为空格并合并空白，全小写/全大写的英文单词改为首字母大写
+//   （不超过 4 个字母的全大写缩写和 GitHub 这样的大小写混合写法保留）
+// 历史卡片可通过 normalize_existing_subcategories 命令按同样的规则批量改写
+
+use crate::models::SubcategorySynonym;
+use crate::storage::{Database, TimelineCardRecord};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+/// 子类别归一化器
+pub struct SubcategoryNormalizer {
+    /// 比较用的写法 → 标准写法
+    synonyms: HashMap<String, String>,
+}
+
+/// 比较用的写法：小写并去掉空白、下划线和连字符
+fn fold(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 整理一个英文单词的大小写
+fn tidy_word(word: &str) -> String {
+    let has_upper = word.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = word.chars().any(|c| c.is_ascii_lowercase());
+    let keep = !word.is_ascii() || (has_upper && has_lower) || (has_upper && word.len() <= 4);
+    if keep {
+        return word.to_string();
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+    }
+}
+
+/// 整理格式：下划线和连字符视为空格并合并空白，整理英文单词的大小写
+fn tidy(value: &str) -> String {
+    value
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty())
+        .map(tidy_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl SubcategoryNormalizer {
+    pub fn new(synonyms: &[SubcategorySynonym]) -> Self {
+        let mut map = HashMap::new();
+        for synonym in synonyms {
+            let canonical = synonym.canonical.trim();
+            if canonical.is_empty() {
+                continue;
+            }
+            // 先定义的规则优先
+            for alias in std::iter::once(canonical).chain(synonym.aliases.iter().map(|a| a.trim()))
+            {
+                let key = fold(alias);
+                if !key.is_empty() {
+                    map.entry(key).or_insert_with(|| canonical.to_string());
+                }
+            }
+        }
+        Self { synonyms: map }
+    }
+
+    /// 归一化一个子类别
+    pub fn normalize(&self, value: &str) -> String {
+        match self.synonyms.get(&fold(value)) {
+            Some(canonical) => canonical.clone(),
+            None => tidy(value),
+        }
+    }
+
+    /// 归一化卡片的子类别，返回是否修改
+    pub fn apply(&self, card: &mut TimelineCardRecord) -> bool {
+        let normalized = self.normalize(&card.subcategory);
+        if normalized == card.subcategory {
+            return false;
+        }
+        card.subcategory = normalized;
+        true
+    }
+}
+
+/// 一种子类别写法的改写
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubcategoryRename {
+    pub from: String,
+    pub to: String,
+    pub cards: usize,
+}
+
+/// 历史卡片归一化报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubcategoryNormalizationReport {
+    pub dry_run: bool,
+    pub scanned_cards: usize,
+    /// 需要修改（dry_run 时为可以修改）的卡片数
+    pub updated_cards: usize,
+    pub renames: Vec<SubcategoryRename>,
+}
+
+/// 按同义词表归一化所有历史卡片的子类别，dry_run 为 false 时写入
+pub async fn normalize_existing(
+    db: &Database,
+    normalizer: &SubcategoryNormalizer,
+    dry_run: bool,
+) -> Result<SubcategoryNormalizationReport, String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut scanned_cards = 0;
+    let mut last_id = 0;
+    loop {
+        let cards = db
+            .get_timeline_cards_after(last_id, 500)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        if cards.is_empty() {
+            break;
+        }
+        for card in cards {
+            last_id = last_id.max(card.id.unwrap_or_default());
+            scanned_cards += 1;
+            *counts.entry(card.subcategory).or_default() += 1;
+        }
+    }
+
+    let mut report = SubcategoryNormalizationReport {
+        dry_run,
+        scanned_cards,
+        ..SubcategoryNormalizationReport::default()
+    };
+    for (from, cards) in counts {
+        let to = normalizer.normalize(&from);
+        if to == from {
+            continue;
+        }
+        if !dry_run {
+            db.rename_timeline_card_subcategory(&from, &to)
+                .await
+                .map_err(|e| format!("更新子类别 {} 失败: {}", from, e))?;
+        }
+        report.updated_cards += cards;
+        report.renames.push(SubcategoryRename { from, to, cards });
+    }
+
+    info!(
+        "子类别归一化{}: 扫描 {} 张卡片，{} 种写法共 {} 张卡片{}",
+        if dry_run { "（仅检查）" } else { "" },
+        report.scanned_cards,
+        report.renames.len(),
+        report.updated_cards,
+        if dry_run { "可以修改" } else { "已修改" }
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants_collapse_to_canonical() {
+        let normalizer = SubcategoryNormalizer::new(&[SubcategorySynonym {
+            canonical: "Coding".to_string(),
+            aliases: vec!["programming".to_string(), "程序开发".to_string()],
+        }]);
+
+        for variant in ["coding", "Coding", " CODING ", "Programming", "程序开发"] {
+            assert_eq!(normalizer.normalize(variant), "Coding");
+        }
+        // 未命中同义词表时只整理格式
+        assert_eq!(normalizer.normalize("code   review"), "Code Review");
+        assert_eq!(normalizer.normalize("code_review"), "Code Review");
+        assert_eq!(normalizer.normalize("AI research"), "AI Research");
+        assert_eq!(normalizer.normalize("GitHub REVIEWS"), "GitHub Reviews");
+        assert_eq!(normalizer.normalize("文档编写"), "文档编写");
+    }
+}
@@ -0,0 +1,316 @@
+// 视频字幕 - 把时间线卡片和视频分段描述生成字幕轨
+//
+// 按卡片和分段的边界把会话切成若干区间，每个区间显示所在卡片的标题和分段描述，
+// 时间按视频元数据换算到视频播放位置（与回放跳转一致，智能变速的视频同样适用）。
+// 可以导出 SRT/WebVTT 文本；开启 embed_subtitles 后，会话分析完成时把字幕封装进会话视频，
+// 外部播放器拖动进度条时即可看到当时在做什么
+
+use super::metrics::parse_series_time;
+use super::playback::{load_session_cards, session_mapping, VideoTimeMapping};
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::storage::{Session, TimelineCardRecord, VideoSegmentRecord};
+use crate::video::VideoUtils;
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 字幕格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+/// 一条字幕（时间为视频播放位置，单位秒）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+/// 按卡片和分段的边界生成字幕，相邻且内容相同的区间合并为一条
+pub fn build_cues(
+    session: &Session,
+    cards: &[TimelineCardRecord],
+    segments: &[VideoSegmentRecord],
+    mapping: &VideoTimeMapping,
+) -> Vec<SubtitleCue> {
+    let parse = |value: &str| parse_series_time(value, session.start_time, session.end_time);
+    let card_ranges: Vec<(NaiveDateTime, NaiveDateTime, &str)> = cards
+        .iter()
+        .map(|card| {
+            (
+                parse(&card.start_time),
+                parse(&card.end_time),
+                card.title.trim(),
+            )
+        })
+        .collect();
+    let segment_ranges: Vec<(NaiveDateTime, NaiveDateTime, &str)> = segments
+        .iter()
+        .map(|segment| {
+            (
+                parse(&segment.start_timestamp),
+                parse(&segment.end_timestamp),
+                segment.description.trim(),
+            )
+        })
+        .collect();
+
+    let mut boundaries: Vec<NaiveDateTime> = card_ranges
+        .iter()
+        .chain(&segment_ranges)
+        .flat_map(|(start, end, _)| [*start, *end])
+        .collect();
+    boundaries.sort();
+    boundaries.dedup();
+
+    let session_start = session.start_time.naive_utc();
+    let to_video = |time: NaiveDateTime| {
+        mapping.real_to_video((time - session_start).num_milliseconds() as f64 / 1000.0)
+    };
+
+    let mut cues: Vec<SubtitleCue> = Vec::new();
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let middle = start + (end - start) / 2;
+        // 区间中点所在的最后一个范围（与回放高亮卡片的规则一致）
+        let find = |ranges: &[(NaiveDateTime, NaiveDateTime, &str)]| {
+            ranges
+                .iter()
+                .filter(|(range_start, range_end, _)| *range_start <= middle && middle < *range_end)
+                .last()
+                .map(|(_, _, text)| text.to_string())
+                .filter(|text| !text.is_empty())
+        };
+        let lines: Vec<String> = [find(&card_ranges), find(&segment_ranges)]
+            .into_iter()
+            .flatten()
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+
+        let text = lines.join("\n");
+        let (start_secs, end_secs) = (to_video(start), to_video(end));
+        match cues.last_mut() {
+            Some(last) if last.text == text && last.end_secs >= start_secs => {
+                last.end_secs = end_secs;
+            }
+            _ => cues.push(SubtitleCue {
+                start_secs,
+                end_secs,
+                text,
+            }),
+        }
+    }
+    cues
+}
+
+/// 格式化字幕时间（SRT 用逗号分隔毫秒，WebVTT 用点）
+fn format_timestamp(secs: f64, format: SubtitleFormat) -> String {
+    let total_millis = (secs.max(0.0) * 1000.0).round() as u64;
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_millis / 3_600_000,
+        total_millis / 60_000 % 60,
+        total_millis / 1000 % 60,
+        separator,
+        total_millis % 1000
+    )
+}
+
+/// 渲染字幕文本
+pub fn render(cues: &[SubtitleCue], format: SubtitleFormat) -> String {
+    let mut output = String::new();
+    if format == SubtitleFormat::Vtt {
+        output.push_str("WEBVTT\n\n");
+    }
+    for (index, cue) in cues.iter().enumerate() {
+        output.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            format_timestamp(cue.start_secs, format),
+            format_timestamp(cue.end_secs, format),
+            cue.text
+        ));
+    }
+    output
+}
+
+/// 生成会话视频的字幕
+pub async fn session_subtitles(
+    storage: &StorageDomain,
+    session_id: i64,
+    format: SubtitleFormat,
+) -> Result<String, String> {
+    let db = storage.get_db().await?;
+    let (session, cards) = load_session_cards(&db, session_id).await?;
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+    let mapping = session_mapping(&session, storage.get_settings()).await;
+    Ok(render(
+        &build_cues(&session, &cards, &segments, &mapping),
+        format,
+    ))
+}
+
+/// 把字幕封装进会话视频并同步到存储后端
+pub async fn embed_into_video(storage: &StorageDomain, session_id: i64) -> Result<(), String> {
+    let db = storage.get_db().await?;
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let video_path = session
+        .video_path
+        .clone()
+        .ok_or_else(|| "会话没有视频".to_string())?;
+
+    let subtitles = session_subtitles(storage, session_id, SubtitleFormat::Srt).await?;
+    if subtitles.is_empty() {
+        return Err("会话没有可用的时间线卡片".to_string());
+    }
+
+    let video_path = storage
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+        .map_err(|e| format!("获取会话视频失败: {}", e))?;
+    let video_path = video_path.as_path();
+
+    let subtitle_path = std::env::temp_dir().join(format!("session_{}.srt", session_id));
+    tokio::fs::write(&subtitle_path, subtitles)
+        .await
+        .map_err(|e| format!("写入字幕文件失败: {}", e))?;
+    let result = VideoUtils::embed_subtitles(video_path, &subtitle_path)
+        .await
+        .map_err(|e| format!("封装字幕失败: {}", e));
+    let _ = tokio::fs::remove_file(&subtitle_path).await;
+    result?;
+
+    if let Err(e) = storage.get_media().publish_video(video_path).await {
+        warn!("同步带字幕的视频失败 {:?}: {}", video_path, e);
+    }
+    info!("已为会话 {} 的视频封装字幕", session_id);
+    Ok(())
+}
+
+/// 启动字幕封装任务：会话分析完成后把字幕封装进会话视频
+pub fn start_subtitle_worker(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>) {
+    let mut receiver = event_bus.subscribe_topics("字幕封装任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let enabled = storage
+                .get_settings()
+                .get()
+                .await
+                .embed_subtitles
+                .unwrap_or(false);
+            if !enabled {
+                continue;
+            }
+
+            if let Err(e) = embed_into_video(&storage, session_id).await {
+                warn!("会话 {} 封装字幕失败: {}", session_id, e);
+            }
+        }
+        warn!("字幕封装任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::playback::current_mapping;
+    use chrono::{DateTime, Duration, Utc};
+
+    #[test]
+    fn test_cues_follow_cards_and_segments() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start + Duration::minutes(15),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let card = |title: &str, start: &str, end: &str| TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: start,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let segment = VideoSegmentRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_timestamp: "02:00".to_string(),
+            end_timestamp: "04:00".to_string(),
+            description: "查看 CI 日志".to_string(),
+            created_at: start,
+        };
+        let cards = vec![
+            card("修复构建", "00:00", "05:00"),
+            card("代码评审", "05:00", "10:00"),
+        ];
+
+        // 8 倍速、每 5 秒取一帧：视频 1 秒 = 真实 40 秒
+        let cues = build_cues(&session, &cards, &[segment], &current_mapping(8.0, 1));
+        assert_eq!(cues.len(), 4);
+        assert_eq!(cues[1].text, "修复构建\n查看 CI 日志");
+        assert_eq!((cues[1].start_secs, cues[1].end_secs), (3.0, 6.0));
+
+        let srt = render(&cues, SubtitleFormat::Srt);
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:03,000\n修复构建\n\n"));
+        let vtt = render(&cues[3..], SubtitleFormat::Vtt);
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n1\n00:00:07.500 --> 00:00:15.000\n代码评审\n\n"
+        );
+    }
+}
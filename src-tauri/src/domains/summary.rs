@@ -1,6 +1,9 @@
 // 总结领域 - 负责生成每日活动总结、统计分析等
 
+use super::insights::Insight;
+use super::screen_language::ScreenLanguage;
 use crate::actors::LLMHandle;
+use crate::llm::DayType;
 use crate::storage::{Database, Session};
 use chrono::Timelike;
 use serde::{Deserialize, Serialize};
@@ -24,6 +27,12 @@ pub struct DaySummary {
     pub usage_patterns: Vec<UsagePattern>,
     /// 活跃设备数量
     pub active_device_count: usize,
+    /// 异常洞察（实时计算，不缓存）
+    #[serde(default)]
+    pub insights: Vec<Insight>,
+    /// 用户已完成当日复核，总结已锁定
+    #[serde(default)]
+    pub finalized: bool,
 }
 
 /// 设备统计
@@ -100,35 +109,34 @@ impl SummaryGenerator {
     ) -> Result<DaySummary, String> {
         info!("生成每日总结: {} (force_refresh={})", date, force_refresh);
 
-        // 如果不是强制刷新，先尝试从数据库读取缓存
-        if !force_refresh {
-            match self.db.get_day_summary(date).await {
-                Ok(Some(cached)) => {
-                    info!("使用缓存的每日总结: {}", date);
-
-                    // 反序列化 JSON 字段
-                    let device_stats =
-                        serde_json::from_str(&cached.device_stats).unwrap_or_default();
-                    let parallel_work =
-                        serde_json::from_str(&cached.parallel_work).unwrap_or_default();
-                    let usage_patterns =
-                        serde_json::from_str(&cached.usage_patterns).unwrap_or_default();
-
-                    return Ok(DaySummary {
-                        date: cached.date.format("%Y-%m-%d").to_string(),
-                        summary_text: cached.summary_text,
-                        device_stats,
-                        parallel_work,
-                        usage_patterns,
-                        active_device_count: cached.active_device_count as usize,
-                    });
-                }
-                Ok(None) => {
-                    info!("数据库中无缓存，重新生成总结");
-                }
-                Err(e) => {
-                    warn!("读取缓存失败: {}, 将重新生成", e);
-                }
+        // 先尝试从数据库读取缓存（强制刷新时跳过，但用户已锁定的总结始终使用缓存）
+        match self.db.get_day_summary(date).await {
+            Ok(Some(cached)) if !force_refresh || cached.finalized => {
+                info!("使用缓存的每日总结: {}", date);
+
+                // 反序列化 JSON 字段
+                let device_stats = serde_json::from_str(&cached.device_stats).unwrap_or_default();
+                let parallel_work = serde_json::from_str(&cached.parallel_work).unwrap_or_default();
+                let usage_patterns =
+                    serde_json::from_str(&cached.usage_patterns).unwrap_or_default();
+
+                return Ok(DaySummary {
+                    date: cached.date.format("%Y-%m-%d").to_string(),
+                    summary_text: cached.summary_text,
+                    device_stats,
+                    parallel_work,
+                    usage_patterns,
+                    active_device_count: cached.active_device_count as usize,
+                    insights: self.generate_insights(date).await,
+                    finalized: cached.finalized,
+                });
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                info!("数据库中无缓存，重新生成总结");
+            }
+            Err(e) => {
+                warn!("读取缓存失败: {}, 将重新生成", e);
             }
         }
 
@@ -147,6 +155,8 @@ impl SummaryGenerator {
                 parallel_work: vec![],
                 usage_patterns: vec![],
                 active_device_count: 0,
+                insights: vec![],
+                finalized: false,
             });
         }
 
@@ -157,17 +167,123 @@ impl SummaryGenerator {
             .collect();
         let active_device_count = active_devices.len();
 
-        // 计算设备统计
-        let device_stats = self.calculate_device_stats(&sessions).await?;
+        // 计算设备统计，外部来源（手机、平板等）各作为一个设备；浏览器扩展只提供域名上下文，不计入
+        let mut device_stats = self.calculate_device_stats(&sessions).await?;
+        match super::external_events::load_events(&self.db, date, date).await {
+            Ok(events) => {
+                if let Ok(day) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                    for (source, minutes) in super::external_events::minutes_by_source(&events, day)
+                    {
+                        device_stats.push(DeviceStat {
+                            name: super::external_events::source_label(&source),
+                            device_type: "external".to_string(),
+                            total_minutes: minutes,
+                            total_time: format_duration(minutes),
+                            screenshots: 0,
+                        });
+                    }
+                }
+            }
+            Err(e) => warn!("获取外部事件失败: {}", e),
+        }
 
         // 检测并行工作
         let parallel_work = self.detect_parallel_work(&sessions);
 
         // 分析使用模式
-        let usage_patterns = self.analyze_usage_patterns(&sessions, active_device_count);
+        let mut usage_patterns = self.analyze_usage_patterns(&sessions, active_device_count);
+
+        // 上下文切换（碎片化程度）
+        match super::metrics::load_activity_spans(&self.db, date).await {
+            Ok(spans) if !spans.is_empty() => {
+                let switches = super::metrics::detect_context_switches(&spans).len();
+                let active_minutes: i64 = spans.iter().map(|s| s.duration_minutes()).sum();
+                let per_hour = super::metrics::per_hour(switches, active_minutes);
+                usage_patterns.push(UsagePattern {
+                    label: "上下文切换".to_string(),
+                    value: format!("{} 次（{:.1} 次/小时）", switches, per_hour),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => warn!("计算上下文切换失败: {}", e),
+        }
+
+        // 休息情况（已休息/跳过的提醒次数）
+        if let Ok(records) = self.db.get_break_records_by_date(date).await {
+            if !records.is_empty() {
+                let taken = records.iter().filter(|r| r.outcome == "taken").count();
+                usage_patterns.push(UsagePattern {
+                    label: "休息提醒".to_string(),
+                    value: format!("已休息 {} 次，跳过 {} 次", taken, records.len() - taken),
+                });
+            }
+        }
+
+        // 专注时段内拦截的干扰
+        if let Ok(records) = self.db.get_deflected_distractions_by_date(date).await {
+            if !records.is_empty() {
+                let mut apps: Vec<&str> = records.iter().map(|r| r.app_name.as_str()).collect();
+                apps.sort();
+                apps.dedup();
+                usage_patterns.push(UsagePattern {
+                    label: "拦截干扰".to_string(),
+                    value: format!("{} 次（{}）", records.len(), apps.join("、")),
+                });
+            }
+        }
+
+        // 截屏暂停（锁屏、手动暂停）时长
+        if let Ok(pauses) = super::capture_pauses::load_day_pauses(&self.db, date).await {
+            if let Ok(day) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                let totals = super::capture_pauses::pause_minutes_by_reason(
+                    &pauses,
+                    day,
+                    crate::storage::local_now(),
+                );
+                if !totals.is_empty() {
+                    usage_patterns.push(UsagePattern {
+                        label: "截屏暂停".to_string(),
+                        value: totals
+                            .iter()
+                            .map(|(reason, minutes)| {
+                                format!(
+                                    "{} {} 分钟",
+                                    super::capture_pauses::reason_label(reason),
+                                    minutes
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("，"),
+                    });
+                }
+            }
+        }
+
+        // 屏幕内容的语言（开启屏幕文字识别时）
+        let screen_text = match super::screen_language::load_records(&self.db, date, date).await {
+            Ok(records) => records,
+            Err(e) => {
+                warn!("获取屏幕文字失败: {}", e);
+                Vec::new()
+            }
+        };
+        let languages = super::screen_language::usage(&screen_text);
+        if !languages.is_empty() {
+            usage_patterns.push(UsagePattern {
+                label: "屏幕语言".to_string(),
+                value: languages
+                    .iter()
+                    .map(|usage| format!("{} {}", usage.label, format_duration(usage.minutes)))
+                    .collect::<Vec<_>>()
+                    .join("，"),
+            });
+        }
+        let session_languages = super::screen_language::dominant_by_session(&screen_text);
 
         // 生成总结文本
-        let summary_text = self.generate_summary_text(date, &sessions).await;
+        let summary_text = self
+            .generate_summary_text(date, &sessions, &session_languages)
+            .await;
 
         let summary = DaySummary {
             date: date.to_string(),
@@ -176,6 +292,8 @@ impl SummaryGenerator {
             parallel_work: parallel_work.clone(),
             usage_patterns: usage_patterns.clone(),
             active_device_count,
+            insights: self.generate_insights(date).await,
+            finalized: false,
         };
 
         // 保存到数据库
@@ -194,6 +312,7 @@ impl SummaryGenerator {
             llm_call_id: None, // TODO: 关联 LLM 调用记录
             created_at: crate::storage::local_now(),
             updated_at: crate::storage::local_now(),
+            finalized: false,
         };
 
         if let Err(e) = self.db.save_day_summary(date, &record).await {
@@ -206,6 +325,20 @@ impl SummaryGenerator {
         Ok(summary)
     }
 
+    /// 生成异常洞察（失败时返回空列表，不影响总结）
+    async fn generate_insights(&self, date: &str) -> Vec<Insight> {
+        let Ok(naive_date) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+            return Vec::new();
+        };
+        match super::insights::generate_insights(&self.db, naive_date).await {
+            Ok(insights) => insights,
+            Err(e) => {
+                warn!("生成异常洞察失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
     /// 计算设备统计
     async fn calculate_device_stats(
         &self,
@@ -437,17 +570,25 @@ impl SummaryGenerator {
     }
 
     /// 生成总结文本（优先使用LLM，fallback到规则）
-    async fn generate_summary_text(&self, date: &str, sessions: &[Session]) -> String {
+    async fn generate_summary_text(
+        &self,
+        date: &str,
+        sessions: &[Session],
+        session_languages: &HashMap<i64, ScreenLanguage>,
+    ) -> String {
         // 计算总时长
         let total_minutes: i64 = sessions
             .iter()
             .map(|s| (s.end_time - s.start_time).num_minutes())
             .sum();
 
+        // 工作日、周末和假期使用不同的模板，避免周末总结评价"生产力"
+        let day_type = super::day_types::resolve_day_type(&self.db, date).await;
+
         // 如果有 LLM handle，尝试使用 LLM 生成
         if let Some(llm_handle) = &self.llm_handle {
             match self
-                .generate_summary_with_llm(llm_handle, date, sessions)
+                .generate_summary_with_llm(llm_handle, date, sessions, session_languages, day_type)
                 .await
             {
                 Ok(summary) => {
@@ -469,7 +610,7 @@ impl SummaryGenerator {
         let active_device_count = active_devices.len();
 
         // 规则生成（fallback）
-        self.generate_summary_with_rules(sessions, total_minutes, active_device_count)
+        self.generate_summary_with_rules(sessions, total_minutes, active_device_count, day_type)
     }
 
     /// 使用 LLM 生成总结
@@ -478,21 +619,28 @@ impl SummaryGenerator {
         llm_handle: &LLMHandle,
         date: &str,
         sessions: &[Session],
+        session_languages: &HashMap<i64, ScreenLanguage>,
+        day_type: DayType,
     ) -> Result<String, String> {
-        // 将 Session 转换为 SessionBrief
+        // 将 Session 转换为 SessionBrief，标注屏幕内容的主要语言（区分写代码、读中文文档、处理英文邮件）
         let session_briefs: Vec<crate::llm::SessionBrief> = sessions
             .iter()
             .map(|s| crate::llm::SessionBrief {
                 start_time: s.start_time,
                 end_time: s.end_time,
                 title: s.title.clone(),
-                summary: s.summary.clone(),
+                summary: match s.id.and_then(|id| session_languages.get(&id)) {
+                    Some(language) => {
+                        format!("{}（屏幕内容以{}为主）", s.summary, language.label())
+                    }
+                    None => s.summary.clone(),
+                },
             })
             .collect();
 
         // 调用 LLM
         llm_handle
-            .generate_day_summary(date, &session_briefs)
+            .generate_day_summary(date, &session_briefs, day_type)
             .await
             .map_err(|e| e.to_string())
     }
@@ -503,6 +651,7 @@ impl SummaryGenerator {
         sessions: &[Session],
         total_minutes: i64,
         active_device_count: usize,
+        day_type: DayType,
     ) -> String {
         // 统计主要活动类别
         let mut category_counts: HashMap<String, usize> = HashMap::new();
@@ -517,21 +666,31 @@ impl SummaryGenerator {
             .map(|(cat, _)| cat.clone())
             .unwrap_or_else(|| "Work".to_string());
 
-        // 生成总结文本
-        format!(
-            "High productivity day with {} work sessions across {} devices. {} dominated the day with {} total tracked time.",
-            sessions.len(),
-            active_device_count,
-            get_category_display_name(&main_category),
-            format_duration(total_minutes)
-        )
+        // 生成总结文本（非工作日不评价生产力）
+        match day_type {
+            DayType::Workday => format!(
+                "High productivity day with {} work sessions across {} devices. {} dominated the day with {} total tracked time.",
+                sessions.len(),
+                active_device_count,
+                get_category_display_name(&main_category),
+                format_duration(total_minutes)
+            ),
+            DayType::Weekend | DayType::Vacation => format!(
+                "A relaxed {} with {} sessions across {} devices, mostly {}, {} tracked in total.",
+                if day_type == DayType::Weekend { "weekend" } else { "day off" },
+                sessions.len(),
+                active_device_count,
+                get_category_display_name(&main_category),
+                format_duration(total_minutes)
+            ),
+        }
     }
 }
 
 // ==================== 辅助函数 ====================
 
 /// 格式化时长
-fn format_duration(minutes: i64) -> String {
+pub(crate) fn format_duration(minutes: i64) -> String {
     if minutes < 60 {
         format!("{}m", minutes)
     } else {
@@ -575,7 +734,7 @@ fn extract_activity_name(session: &Session) -> String {
 }
 
 /// 获取类别显示名称
-fn get_category_display_name(category: &str) -> String {
+pub(crate) fn get_category_display_name(category: &str) -> String {
     match category.to_lowercase().as_str() {
         "work" | "coding" => "工作".to_string(),
         "communication" | "meeting" => "会议".to_string(),
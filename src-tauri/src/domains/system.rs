@@ -3,10 +3,19 @@
 // 负责系统状态、日志和基础设施相关的功能
 // 包含 SystemStatusHandle、LogBroadcaster 和 HTTP 客户端三个核心组件
 // 使用Actor模式管理系统状态，消除锁竞争
+// 同时持有实例锁，并记录当前实例是否处于只读模式
+// 以及实时状态广播和对外推送服务，并保存最近一次启动自检的结果
+// 危险操作的确认令牌也由这里签发和校验，启动进度也记录在这里
 
+use super::confirmation::ConfirmationGuard;
+use super::live::LiveFeed;
+use super::startup_check::StartupReport;
+use super::startup_progress::StartupTracker;
 use crate::actors::SystemStatusHandle;
+use crate::instance::InstanceLock;
+use crate::live_server::LiveServer;
 use crate::logger::LogBroadcaster;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 /// 系统领域管理器 - 负责系统状态、日志和基础设施
 #[derive(Clone)]
@@ -14,6 +23,20 @@ pub struct SystemDomain {
     system_status_handle: SystemStatusHandle,
     log_broadcaster: Arc<LogBroadcaster>,
     http_client: Arc<reqwest::Client>,
+    /// 实例锁（主实例持有期间保持锁文件存在）
+    instance_lock: Option<Arc<InstanceLock>>,
+    /// 只读模式原因（None 表示可写）
+    read_only_reason: Arc<RwLock<Option<String>>>,
+    /// 实时状态广播
+    live_feed: Arc<LiveFeed>,
+    /// 实时状态 WebSocket 服务
+    live_server: Arc<LiveServer>,
+    /// 最近一次启动自检结果（自检完成前为 None）
+    startup_report: Arc<RwLock<Option<StartupReport>>>,
+    /// 危险操作确认令牌
+    confirmations: Arc<ConfirmationGuard>,
+    /// 后台初始化进度
+    startup_progress: Arc<StartupTracker>,
 }
 
 impl SystemDomain {
@@ -23,13 +46,76 @@ impl SystemDomain {
         log_broadcaster: Arc<LogBroadcaster>,
         http_client: Arc<reqwest::Client>,
     ) -> Self {
+        let live_feed = Arc::new(LiveFeed::new());
         Self {
             system_status_handle,
             log_broadcaster,
             http_client,
+            instance_lock: None,
+            read_only_reason: Arc::new(RwLock::new(None)),
+            live_server: Arc::new(LiveServer::new(live_feed.clone())),
+            live_feed,
+            startup_report: Arc::new(RwLock::new(None)),
+            confirmations: Arc::new(ConfirmationGuard::new()),
+            startup_progress: Arc::new(StartupTracker::new()),
         }
     }
 
+    /// 附加实例锁（次实例会同时进入只读模式）
+    pub fn with_instance_lock(mut self, lock: Arc<InstanceLock>) -> Self {
+        if let crate::instance::InstanceRole::Secondary { owner } = lock.role() {
+            self.set_read_only(Some(format!(
+                "另一个实例正在运行 (PID {})，当前实例为只读模式",
+                owner.pid
+            )));
+        }
+        self.instance_lock = Some(lock);
+        self
+    }
+
+    /// 获取实例锁
+    pub fn get_instance_lock(&self) -> Option<&Arc<InstanceLock>> {
+        self.instance_lock.as_ref()
+    }
+
+    /// 设置只读模式（传入 None 恢复可写）
+    pub fn set_read_only(&self, reason: Option<String>) {
+        if let Ok(mut current) = self.read_only_reason.write() {
+            *current = reason;
+        }
+    }
+
+    /// 获取只读模式原因
+    pub fn read_only_reason(&self) -> Option<String> {
+        self.read_only_reason
+            .read()
+            .ok()
+            .and_then(|reason| reason.clone())
+    }
+
+    /// 是否处于只读模式
+    pub fn is_read_only(&self) -> bool {
+        self.read_only_reason().is_some()
+    }
+
+    /// 确保当前实例可写，只读模式下返回错误信息
+    pub fn ensure_writable(&self) -> Result<(), String> {
+        match self.read_only_reason() {
+            Some(reason) => Err(format!("当前处于只读模式，无法执行该操作：{}", reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// 获取危险操作确认令牌管理
+    pub fn get_confirmations(&self) -> &Arc<ConfirmationGuard> {
+        &self.confirmations
+    }
+
+    /// 获取启动进度跟踪
+    pub fn get_startup_progress(&self) -> &Arc<StartupTracker> {
+        &self.startup_progress
+    }
+
     /// 获取系统状态Handle
     pub fn get_status_handle(&self) -> &SystemStatusHandle {
         &self.system_status_handle
@@ -44,4 +130,29 @@ impl SystemDomain {
     pub fn get_http_client(&self) -> &Arc<reqwest::Client> {
         &self.http_client
     }
+
+    /// 获取实时状态广播
+    pub fn get_live_feed(&self) -> &Arc<LiveFeed> {
+        &self.live_feed
+    }
+
+    /// 获取实时状态 WebSocket 服务
+    pub fn get_live_server(&self) -> &Arc<LiveServer> {
+        &self.live_server
+    }
+
+    /// 保存启动自检结果
+    pub fn set_startup_report(&self, report: StartupReport) {
+        if let Ok(mut current) = self.startup_report.write() {
+            *current = Some(report);
+        }
+    }
+
+    /// 获取最近一次启动自检结果
+    pub fn startup_report(&self) -> Option<StartupReport> {
+        self.startup_report
+            .read()
+            .ok()
+            .and_then(|report| report.clone())
+    }
 }
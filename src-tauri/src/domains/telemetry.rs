@@ -0,0 +1,286 @@
+// 运行指标 - 以 Prometheus 文本格式导出计数器、仪表和直方图，供自托管用户接入现有的 Grafana
+//
+// 指标保存在进程内，由各环节直接上报：
+// - 截图数、丢帧数（按原因）、截图 JPEG 编码耗时和会话视频编码耗时
+// - LLM 调用耗时和错误数（按服务商、调用类型，取自每次调用的 llm_calls 记录）
+// - 队列长度（截图写盘队列、视频分析队列）
+// - 数据库操作耗时（分析流程的主要读写）
+// 实时状态服务开启 metrics 后通过 GET /metrics 暴露，访问令牌与其他接口相同
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// 指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Metric {
+    FramesCaptured,
+    FramesDropped,
+    FrameEncodeSeconds,
+    VideoEncodeSeconds,
+    LlmRequestSeconds,
+    LlmErrors,
+    QueueDepth,
+    DbQuerySeconds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Metric {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::FramesCaptured => "screen_analyzer_frames_captured_total",
+            Self::FramesDropped => "screen_analyzer_frames_dropped_total",
+            Self::FrameEncodeSeconds => "screen_analyzer_frame_encode_seconds",
+            Self::VideoEncodeSeconds => "screen_analyzer_video_encode_seconds",
+            Self::LlmRequestSeconds => "screen_analyzer_llm_request_seconds",
+            Self::LlmErrors => "screen_analyzer_llm_errors_total",
+            Self::QueueDepth => "screen_analyzer_queue_depth",
+            Self::DbQuerySeconds => "screen_analyzer_db_query_seconds",
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        match self {
+            Self::FramesCaptured => "已保存的截图数",
+            Self::FramesDropped => "写盘前被丢弃的截图数",
+            Self::FrameEncodeSeconds => "单帧截图 JPEG 编码耗时",
+            Self::VideoEncodeSeconds => "会话视频编码耗时",
+            Self::LlmRequestSeconds => "LLM 调用耗时",
+            Self::LlmErrors => "失败的 LLM 调用数",
+            Self::QueueDepth => "等待处理的队列长度",
+            Self::DbQuerySeconds => "数据库操作耗时",
+        }
+    }
+
+    fn kind(&self) -> Kind {
+        match self {
+            Self::FramesCaptured | Self::FramesDropped | Self::LlmErrors => Kind::Counter,
+            Self::QueueDepth => Kind::Gauge,
+            _ => Kind::Histogram,
+        }
+    }
+
+    /// 直方图的桶上限（秒）
+    fn buckets(&self) -> &'static [f64] {
+        match self {
+            Self::FrameEncodeSeconds => &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0],
+            Self::VideoEncodeSeconds => &[1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0],
+            Self::LlmRequestSeconds => &[0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0],
+            Self::DbQuerySeconds => &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    /// 各桶的计数（不累加，输出时再累加）
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Counter(u64),
+    Gauge(f64),
+    Histogram(Histogram),
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+static REGISTRY: OnceLock<Mutex<BTreeMap<(Metric, Labels), Value>>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<BTreeMap<(Metric, Labels), Value>> {
+    REGISTRY.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn update(metric: Metric, labels: &[(&'static str, &str)], apply: impl FnOnce(&mut Value)) {
+    let labels: Labels = labels
+        .iter()
+        .map(|(key, value)| (*key, value.to_string()))
+        .collect();
+    let Ok(mut registry) = registry().lock() else {
+        return;
+    };
+    let value = registry
+        .entry((metric, labels))
+        .or_insert_with(|| match metric.kind() {
+            Kind::Counter => Value::Counter(0),
+            Kind::Gauge => Value::Gauge(0.0),
+            Kind::Histogram => Value::Histogram(Histogram {
+                counts: vec![0; metric.buckets().len()],
+                sum: 0.0,
+                count: 0,
+            }),
+        });
+    apply(value);
+}
+
+/// 计数器加一
+pub fn inc(metric: Metric, labels: &[(&'static str, &str)]) {
+    update(metric, labels, |value| {
+        if let Value::Counter(count) = value {
+            *count += 1;
+        }
+    });
+}
+
+/// 设置仪表的当前值
+pub fn set(metric: Metric, labels: &[(&'static str, &str)], current: f64) {
+    update(metric, labels, |value| {
+        if let Value::Gauge(gauge) = value {
+            *gauge = current;
+        }
+    });
+}
+
+/// 向直方图记录一次观测（秒）
+pub fn observe(metric: Metric, labels: &[(&'static str, &str)], seconds: f64) {
+    let buckets = metric.buckets();
+    update(metric, labels, |value| {
+        if let Value::Histogram(histogram) = value {
+            if let Some(index) = buckets.iter().position(|bound| seconds <= *bound) {
+                histogram.counts[index] += 1;
+            }
+            histogram.sum += seconds;
+            histogram.count += 1;
+        }
+    });
+}
+
+/// 记录一次数据库操作的耗时
+pub async fn time_db<T>(operation: &'static str, future: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let result = future.await;
+    observe(
+        Metric::DbQuerySeconds,
+        &[("operation", operation)],
+        started.elapsed().as_secs_f64(),
+    );
+    result
+}
+
+/// 记录一次 LLM 调用（耗时和是否失败取自调用记录）
+pub fn record_llm_call(record: &crate::storage::LLMCallRecord) {
+    let labels = [
+        ("provider", record.provider.as_str()),
+        ("call_type", record.call_type.as_str()),
+    ];
+    if let Some(latency_ms) = record.latency_ms {
+        observe(
+            Metric::LlmRequestSeconds,
+            &labels,
+            latency_ms as f64 / 1000.0,
+        );
+    }
+    if record.error_message.is_some() {
+        inc(Metric::LlmErrors, &labels);
+    }
+}
+
+/// 转义标签值中的反斜杠、引号和换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_labels(labels: &[(&'static str, String)], extra: Option<(&str, String)>) -> String {
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| (*key, value.clone()))
+        .chain(extra)
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(&value)))
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+/// 以 Prometheus 文本格式输出所有指标
+pub fn render() -> String {
+    let snapshot = match registry().lock() {
+        Ok(registry) => registry.clone(),
+        Err(_) => return String::new(),
+    };
+
+    let mut output = String::new();
+    let mut current = None;
+    for ((metric, labels), value) in &snapshot {
+        let name = metric.name();
+        if current != Some(*metric) {
+            current = Some(*metric);
+            let kind = match metric.kind() {
+                Kind::Counter => "counter",
+                Kind::Gauge => "gauge",
+                Kind::Histogram => "histogram",
+            };
+            let _ = writeln!(output, "# HELP {} {}", name, metric.help());
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+        }
+
+        match value {
+            Value::Counter(count) => {
+                let _ = writeln!(output, "{}{} {}", name, format_labels(labels, None), count);
+            }
+            Value::Gauge(gauge) => {
+                let _ = writeln!(output, "{}{} {}", name, format_labels(labels, None), gauge);
+            }
+            Value::Histogram(histogram) => {
+                let mut cumulative = 0;
+                for (bound, count) in metric.buckets().iter().zip(&histogram.counts) {
+                    cumulative += count;
+                    let le = format_labels(labels, Some(("le", bound.to_string())));
+                    let _ = writeln!(output, "{}_bucket{} {}", name, le, cumulative);
+                }
+                let le = format_labels(labels, Some(("le", "+Inf".to_string())));
+                let _ = writeln!(output, "{}_bucket{} {}", name, le, histogram.count);
+                let plain = format_labels(labels, None);
+                let _ = writeln!(output, "{}_sum{} {}", name, plain, histogram.sum);
+                let _ = writeln!(output, "{}_count{} {}", name, plain, histogram.count);
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text_format() {
+        inc(Metric::FramesDropped, &[("reason", "test\"quoted")]);
+        inc(Metric::FramesDropped, &[("reason", "test\"quoted")]);
+        observe(Metric::VideoEncodeSeconds, &[], 3.0);
+        observe(Metric::VideoEncodeSeconds, &[], 900.0);
+        set(Metric::QueueDepth, &[("queue", "test_queue")], 7.0);
+
+        let text = render();
+        assert!(text.contains("# TYPE screen_analyzer_frames_dropped_total counter"));
+        assert!(text.contains("screen_analyzer_frames_dropped_total{reason=\"test\\\"quoted\"} 2"));
+        assert!(text.contains("screen_analyzer_video_encode_seconds_bucket{le=\"1\"} 0"));
+        assert!(text.contains("screen_analyzer_video_encode_seconds_bucket{le=\"5\"} 1"));
+        assert!(text.contains("screen_analyzer_video_encode_seconds_bucket{le=\"600\"} 1"));
+        assert!(text.contains("screen_analyzer_video_encode_seconds_bucket{le=\"+Inf\"} 2"));
+        assert!(text.contains("screen_analyzer_video_encode_seconds_sum 903"));
+        assert!(text.contains("screen_analyzer_queue_depth{queue=\"test_queue\"} 7"));
+        assert_eq!(
+            text.matches("# TYPE screen_analyzer_video_encode_seconds")
+                .count(),
+            1
+        );
+    }
+}
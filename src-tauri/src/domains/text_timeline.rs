@@ -0,0 +1,428 @@
+// 屏幕文字时间线 - 对会话视频抽帧做 OCR，按分段保存屏幕上出现过的文字
+//
+// 原始截图在合成视频后即被删除，因此从会话视频中按真实时间间隔抽帧（经视频时间映射换算），
+// 调用本机的 tesseract 识别文字。同一会话内已出现过的文字行不再重复记录，
+// 每行只记在首次出现的分段（视频分段，没有分段时按 5 分钟切分）中，
+// 便于精确搜索“什么时候看到过错误码 0x80070005”。
+// 各帧的识别结果同时交给报错检测（dev_errors），记录堆栈、编译错误等报错时刻；
+// 每个分段按其中全部文字（不只新出现的行）判断屏幕内容的主要语言（screen_language）；
+// 识别完成后结合屏幕文字重新评估卡片的隐私敏感度（privacy）
+
+use super::metrics::parse_series_time;
+use super::playback::session_mapping;
+use super::screen_language::{self, ScreenLanguage};
+use super::storage::StorageDomain;
+use crate::event_bus::{AppEvent, EventBus, EventTopic};
+use crate::models::TextTimelineSettings;
+use crate::storage::{Database, ScreenTextRecord, Session, VideoSegmentRecord};
+use crate::video::VideoUtils;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 没有视频分段时的切分长度（分钟）
+const FALLBACK_SEGMENT_MINUTES: i64 = 5;
+/// 最短的有效文字行（字符数，更短的多为识别噪声）
+const MIN_LINE_CHARS: usize = 3;
+/// 最小抽帧间隔（秒）
+const MIN_SAMPLE_INTERVAL_SECS: u64 = 10;
+/// 单帧识别超时（秒）
+const OCR_TIMEOUT_SECS: u64 = 60;
+/// 搜索结果的默认条数和上限
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const MAX_SEARCH_LIMIT: usize = 200;
+
+/// 搜索命中
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenTextHit {
+    pub session_id: i64,
+    /// 分段时间（本地时间 YYYY-MM-DD HH:MM:SS）
+    pub segment_start: String,
+    pub segment_end: String,
+    /// 分段屏幕内容的主要语言
+    pub language: Option<String>,
+    /// 包含搜索文字的行
+    pub lines: Vec<String>,
+}
+
+/// 规整一行识别结果（合并空白），过短或没有字母数字的行视为噪声
+fn normalize_line(line: &str) -> Option<String> {
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    let valid = line.chars().count() >= MIN_LINE_CHARS && line.chars().any(|c| c.is_alphanumeric());
+    valid.then_some(line)
+}
+
+/// 会话的分段（按视频分段；没有分段时按固定时长切分）
+pub fn segment_windows(
+    session: &Session,
+    segments: &[VideoSegmentRecord],
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let parse = |value: &str| parse_series_time(value, session.start_time, session.end_time);
+    let mut windows: Vec<(NaiveDateTime, NaiveDateTime)> = segments
+        .iter()
+        .map(|segment| {
+            (
+                parse(&segment.start_timestamp),
+                parse(&segment.end_timestamp),
+            )
+        })
+        .filter(|(start, end)| end > start)
+        .collect();
+    windows.sort();
+    if !windows.is_empty() {
+        return windows;
+    }
+
+    let end = session.end_time.naive_utc();
+    let mut cursor = session.start_time.naive_utc();
+    while cursor < end {
+        let next = (cursor + Duration::minutes(FALLBACK_SEGMENT_MINUTES)).min(end);
+        windows.push((cursor, next));
+        cursor = next;
+    }
+    windows
+}
+
+/// 抽帧位置（距会话开始的真实秒数）
+pub fn sample_offsets(duration_secs: f64, interval_secs: u64) -> Vec<f64> {
+    let interval = interval_secs.max(MIN_SAMPLE_INTERVAL_SECS) as f64;
+    let mut offsets = Vec::new();
+    let mut offset = 0.0;
+    while offset < duration_secs {
+        offsets.push(offset);
+        offset += interval;
+    }
+    offsets
+}
+
+/// 帧所在分段的序号（落在分段之外的帧记入之后最近的分段）
+fn window_index(windows: &[(NaiveDateTime, NaiveDateTime)], time: &NaiveDateTime) -> Option<usize> {
+    windows
+        .iter()
+        .position(|(_, end)| time < end)
+        .or(windows.len().checked_sub(1))
+}
+
+/// 各分段屏幕内容的主要语言（按分段内各帧的全部文字判断）
+pub fn segment_languages(
+    windows: &[(NaiveDateTime, NaiveDateTime)],
+    samples: &[(NaiveDateTime, String)],
+) -> Vec<Option<ScreenLanguage>> {
+    let mut texts: Vec<String> = vec![String::new(); windows.len()];
+    for (time, text) in samples {
+        if let Some(index) = window_index(windows, time) {
+            texts[index].push_str(text);
+            texts[index].push('\n');
+        }
+    }
+    texts
+        .iter()
+        .map(|text| screen_language::detect(text))
+        .collect()
+}
+
+/// 按时间顺序合并各帧的识别结果：每行只保留首次出现，记入所在的分段
+///
+/// 返回（分段开始，分段结束，文字），没有新文字的分段不返回
+pub fn build_transcript(
+    windows: &[(NaiveDateTime, NaiveDateTime)],
+    samples: &[(NaiveDateTime, String)],
+) -> Vec<(NaiveDateTime, NaiveDateTime, String)> {
+    let mut seen = HashSet::new();
+    let mut lines: Vec<Vec<String>> = vec![Vec::new(); windows.len()];
+    for (time, text) in samples {
+        let Some(index) = window_index(windows, time) else {
+            break;
+        };
+        for line in text.lines().filter_map(normalize_line) {
+            if seen.insert(line.to_lowercase()) {
+                lines[index].push(line);
+            }
+        }
+    }
+
+    windows
+        .iter()
+        .zip(lines)
+        .filter(|(_, lines)| !lines.is_empty())
+        .map(|((start, end), lines)| (*start, *end, lines.join("\n")))
+        .collect()
+}
+
+/// 调用 tesseract 识别图片中的文字
+pub async fn recognize(settings: &TextTimelineSettings, image: &Path) -> Result<String, String> {
+    let binary = settings
+        .tesseract_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .unwrap_or("tesseract");
+    let mut command = tokio::process::Command::new(binary);
+    command
+        .arg(image)
+        .arg("stdout")
+        .args(["-l", settings.languages.trim()])
+        .kill_on_drop(true);
+
+    // Windows下隐藏控制台窗口
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)]
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(OCR_TIMEOUT_SECS),
+        command.output(),
+    )
+    .await
+    .map_err(|_| "文字识别超时".to_string())?
+    .map_err(|e| format!("无法运行 tesseract（请确认已安装）: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "文字识别失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 识别会话视频中的文字并保存，返回保存的分段数
+pub async fn build_for_session(storage: &StorageDomain, session_id: i64) -> Result<usize, String> {
+    let settings = storage
+        .get_settings()
+        .get()
+        .await
+        .text_timeline
+        .unwrap_or_default();
+    let db = storage.get_db().await?;
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let video_path = session
+        .video_path
+        .clone()
+        .ok_or_else(|| "会话没有视频，无法识别屏幕文字".to_string())?;
+    let video_path = storage
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+        .map_err(|e| format!("获取会话视频失败: {}", e))?;
+    let video_path = video_path.as_path();
+
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+    let windows = segment_windows(&session, &segments);
+    let mapping = session_mapping(&session, storage.get_settings()).await;
+    let duration_secs = (session.end_time - session.start_time).num_seconds() as f64;
+
+    let frames_dir = std::env::temp_dir().join(format!("text_timeline_{}", session_id));
+    tokio::fs::create_dir_all(&frames_dir)
+        .await
+        .map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+    let session_start = session.start_time.naive_utc();
+    let mut samples = Vec::new();
+    let mut last_error = None;
+    for (index, offset) in sample_offsets(duration_secs, settings.sample_interval_secs)
+        .into_iter()
+        .enumerate()
+    {
+        let frame_path = frames_dir.join(format!("frame_{:04}.png", index));
+        let position = mapping.real_to_video(offset) as f32;
+        if let Err(e) = VideoUtils::extract_frame(video_path, &frame_path, position).await {
+            warn!("文字识别抽帧失败 ({:.1}s): {}", position, e);
+            continue;
+        }
+        match recognize(&settings, &frame_path).await {
+            Ok(text) => samples.push((
+                session_start + Duration::milliseconds((offset * 1000.0) as i64),
+                text,
+            )),
+            Err(e) => last_error = Some(e),
+        }
+        let _ = tokio::fs::remove_file(&frame_path).await;
+    }
+    let _ = tokio::fs::remove_dir_all(&frames_dir).await;
+
+    if samples.is_empty() {
+        return Err(last_error.unwrap_or_else(|| "没有抽取到会话画面".to_string()));
+    }
+
+    let now = crate::storage::local_now();
+    let languages = segment_languages(&windows, &samples);
+    let records: Vec<ScreenTextRecord> = build_transcript(&windows, &samples)
+        .into_iter()
+        .map(|(start, end, text)| ScreenTextRecord {
+            id: None,
+            session_id,
+            segment_start: start.and_utc(),
+            segment_end: end.and_utc(),
+            text,
+            language: windows
+                .iter()
+                .position(|(window_start, _)| *window_start == start)
+                .and_then(|index| languages[index])
+                .map(|language| language.as_str().to_string()),
+            created_at: now,
+        })
+        .collect();
+    db.replace_screen_text(session_id, &records)
+        .await
+        .map_err(|e| format!("保存屏幕文字失败: {}", e))?;
+
+    let errors = super::dev_errors::detect_moments(session_id, &samples, now);
+    db.replace_error_moments(session_id, &errors)
+        .await
+        .map_err(|e| format!("保存报错记录失败: {}", e))?;
+    if let Err(e) = super::privacy::rate_session(&db, session_id).await {
+        warn!("会话 {} 敏感度评估失败: {}", session_id, e);
+    }
+
+    info!(
+        "会话 {} 屏幕文字识别完成: {} 帧，{} 个分段，{} 条报错",
+        session_id,
+        samples.len(),
+        records.len(),
+        errors.len()
+    );
+    Ok(records.len())
+}
+
+/// 搜索屏幕上出现过的文字（不区分大小写），最新的在前；指定语言时只搜索该语言的分段
+pub async fn search(
+    db: &Database,
+    query: &str,
+    language: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<ScreenTextHit>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("搜索内容不能为空".to_string());
+    }
+    if let Some(language) = language {
+        ScreenLanguage::parse(language).ok_or_else(|| format!("未知的语言: {}", language))?;
+    }
+    let escaped: String = query
+        .chars()
+        .flat_map(|c| match c {
+            '%' | '_' | '!' => vec!['!', c],
+            _ => vec![c],
+        })
+        .collect();
+    let limit = limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let records = db
+        .search_screen_text(&format!("%{}%", escaped), language, limit as i64)
+        .await
+        .map_err(|e| format!("搜索屏幕文字失败: {}", e))?;
+
+    let lower = query.to_lowercase();
+    let to_local =
+        |time: chrono::DateTime<chrono::Utc>| time.format("%Y-%m-%d %H:%M:%S").to_string();
+    Ok(records
+        .into_iter()
+        .map(|record| ScreenTextHit {
+            session_id: record.session_id,
+            segment_start: to_local(record.segment_start),
+            segment_end: to_local(record.segment_end),
+            language: record.language,
+            lines: record
+                .text
+                .lines()
+                .filter(|line| line.to_lowercase().contains(&lower))
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect())
+}
+
+/// 启动屏幕文字识别任务：会话分析完成后识别会话视频中的文字（需在设置中开启）
+pub fn start_text_timeline_worker(event_bus: Arc<EventBus>, storage: Arc<StorageDomain>) {
+    let mut receiver = event_bus.subscribe_topics("屏幕文字识别任务", &[EventTopic::Analysis]);
+
+    tokio::spawn(async move {
+        loop {
+            let session_id = match receiver.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => session_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let enabled = storage
+                .get_settings()
+                .get()
+                .await
+                .text_timeline
+                .is_some_and(|settings| settings.enabled);
+            if !enabled {
+                continue;
+            }
+
+            if let Err(e) = build_for_session(&storage, session_id).await {
+                warn!("会话 {} 屏幕文字识别失败: {}", session_id, e);
+            }
+        }
+        warn!("屏幕文字识别任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_transcript_keeps_first_occurrence_per_segment() {
+        let start = DateTime::parse_from_rfc3339("2025-10-09T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let session = Session {
+            id: Some(1),
+            start_time: start,
+            end_time: start + Duration::minutes(12),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+
+        // 没有视频分段时按 5 分钟切分
+        let windows = segment_windows(&session, &[]);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[2].1, session.end_time.naive_utc());
+        assert_eq!(sample_offsets(150.0, 60), vec![0.0, 60.0, 120.0]);
+
+        let at = |minutes: i64| start.naive_utc() + Duration::minutes(minutes);
+        let samples = vec![
+            (at(1), "Installer\n错误 0x80070005  拒绝访问\n|".to_string()),
+            (at(3), "installer\n错误 0x80070005 拒绝访问".to_string()),
+            (at(11), "Event Viewer\nInstaller".to_string()),
+        ];
+        let transcript = build_transcript(&windows, &samples);
+        assert_eq!(transcript.len(), 2);
+        assert_eq!(transcript[0].2, "Installer\n错误 0x80070005 拒绝访问");
+        assert_eq!(transcript[1].0, at(10));
+        assert_eq!(transcript[1].2, "Event Viewer");
+        // 语言按分段内全部文字判断，第一个分段重复的行也计入
+        let languages = segment_languages(&windows, &samples);
+        assert_eq!(languages[0], Some(ScreenLanguage::Chinese));
+        assert_eq!(languages[1], None);
+    }
+}
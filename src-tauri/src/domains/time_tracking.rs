@@ -0,0 +1,530 @@
+// 计时服务导出 - 把时间线卡片导出为 Toggl/Clockify 的时间记录
+//
+// 每张卡片对应一条记录：描述为卡片标题，时间为卡片起止时间。项目依次取：
+// 项目规则（标题、摘要、子类别或应用中出现关键词）、类别规则中的默认项目、卡片子类别；
+// 计时服务中不存在的项目自动创建。类别规则决定哪些类别导出（默认不导出 idle 和 personal），
+// 隐私敏感度高于设置值的卡片同样不导出。
+// 幂等键由服务、工作区、会话和卡片时间段计算，导出成功后记录在 time_entry_exports 表中，
+// 重复导出同一范围时跳过已导出的卡片。本地模式下不可用
+
+use super::egress::{self, EgressChannel, EgressContent};
+use super::metrics::parse_series_time;
+use crate::models::{PersistedAppConfig, TimeTrackingProvider, TimeTrackingSettings};
+use crate::storage::{local_now, Database, TimeEntryExportRecord, TimelineCardRecord};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const TOGGL_API: &str = "https://api.track.toggl.com/api/v9";
+const CLOCKIFY_API: &str = "https://api.clockify.me/api/v1";
+/// 单次导出的最长日期范围（天）
+const MAX_RANGE_DAYS: i64 = 31;
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// 两次请求之间的间隔（Toggl 限制每秒约 1 次请求）
+const REQUEST_INTERVAL: Duration = Duration::from_millis(1100);
+/// 报告中最多保留的错误信息条数
+const MAX_MESSAGES: usize = 10;
+
+/// 导出结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTrackingReport {
+    /// 本次新导出的记录数
+    pub exported: usize,
+    /// 之前已导出而跳过的卡片数
+    pub already_exported: usize,
+    /// 按类别规则排除的卡片数
+    pub excluded: usize,
+    /// 因隐私敏感度排除的卡片数
+    pub privacy_excluded: usize,
+    /// 导出失败的卡片数
+    pub failed: usize,
+    pub messages: Vec<String>,
+}
+
+impl TimeTrackingReport {
+    /// 记录一条导出失败
+    fn fail(&mut self, message: String) {
+        warn!("{}", message);
+        self.failed += 1;
+        if self.messages.len() < MAX_MESSAGES {
+            self.messages.push(message);
+        }
+    }
+}
+
+/// 待导出的时间记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub description: String,
+    pub project: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// 卡片类别是否导出，以及类别规则中的默认项目
+pub fn category_rule(settings: &TimeTrackingSettings, category: &str) -> (bool, Option<String>) {
+    let category = category.trim().to_lowercase();
+    settings
+        .category_rules
+        .iter()
+        .find(|rule| rule.category.trim().to_lowercase() == category)
+        .map(|rule| {
+            let project = rule
+                .project
+                .as_deref()
+                .map(str::trim)
+                .filter(|project| !project.is_empty())
+                .map(str::to_string);
+            (rule.include, project)
+        })
+        .unwrap_or((settings.include_unlisted, None))
+}
+
+/// 识别卡片所属项目：项目规则优先，其次类别规则中的默认项目，最后使用子类别
+pub fn detect_project(
+    settings: &TimeTrackingSettings,
+    card: &TimelineCardRecord,
+) -> Option<String> {
+    let text = [
+        card.title.as_str(),
+        card.summary.as_str(),
+        card.subcategory.as_str(),
+        card.app_sites.as_str(),
+    ]
+    .join("\n")
+    .to_lowercase();
+    settings
+        .project_rules
+        .iter()
+        .find(|rule| {
+            let keyword = rule.keyword.trim().to_lowercase();
+            !keyword.is_empty() && !rule.project.trim().is_empty() && text.contains(&keyword)
+        })
+        .map(|rule| rule.project.trim().to_string())
+        .or_else(|| category_rule(settings, &card.category).1)
+        .or_else(|| {
+            let subcategory = card.subcategory.trim();
+            (!subcategory.is_empty()).then(|| subcategory.to_string())
+        })
+}
+
+/// 幂等键：同一服务、工作区中同一会话的同一时间段只导出一次
+pub fn idempotency_key(
+    settings: &TimeTrackingSettings,
+    session_id: i64,
+    entry: &TimeEntry,
+) -> String {
+    let source = format!(
+        "{}|{}|{}|{}|{}",
+        settings.provider.as_str(),
+        settings.workspace_id.trim(),
+        session_id,
+        entry.start.timestamp(),
+        entry.end.timestamp()
+    );
+    format!("{:x}", Sha256::digest(source.as_bytes()))
+}
+
+/// 本地时间转为 UTC
+fn to_utc(value: chrono::NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&value)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// 卡片对应的时间记录（时间无效或时长为零时返回 None）
+pub fn card_entry(
+    settings: &TimeTrackingSettings,
+    card: &TimelineCardRecord,
+    session_start: DateTime<Utc>,
+    session_end: DateTime<Utc>,
+) -> Option<TimeEntry> {
+    let start = to_utc(parse_series_time(
+        &card.start_time,
+        session_start,
+        session_end,
+    ))?;
+    let end = to_utc(parse_series_time(
+        &card.end_time,
+        session_start,
+        session_end,
+    ))?;
+    if end <= start {
+        return None;
+    }
+    let title = card.title.trim();
+    Some(TimeEntry {
+        description: if title.is_empty() {
+            card.category.clone()
+        } else {
+            title.to_string()
+        },
+        project: detect_project(settings, card),
+        start,
+        end,
+    })
+}
+
+/// 计时服务 API 客户端
+struct TimeTrackingClient {
+    provider: TimeTrackingProvider,
+    token: String,
+    workspace_id: String,
+    http: reqwest::Client,
+    /// 项目名称（小写） -> 项目 ID
+    projects: HashMap<String, Value>,
+}
+
+impl TimeTrackingClient {
+    fn new(settings: &TimeTrackingSettings) -> Result<Self, String> {
+        let token = settings.api_token.trim();
+        let workspace_id = settings.workspace_id.trim();
+        if token.is_empty() || workspace_id.is_empty() {
+            return Err("请先设置计时服务的 API 令牌和工作区 ID".to_string());
+        }
+        if settings.provider == TimeTrackingProvider::Toggl && workspace_id.parse::<i64>().is_err()
+        {
+            return Err("Toggl 工作区 ID 应为数字".to_string());
+        }
+        let http = super::local_only::http_client_builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        Ok(Self {
+            provider: settings.provider,
+            token: token.to_string(),
+            workspace_id: workspace_id.to_string(),
+            http,
+            projects: HashMap::new(),
+        })
+    }
+
+    fn base_url(&self) -> String {
+        match self.provider {
+            TimeTrackingProvider::Toggl => {
+                format!("{}/workspaces/{}", TOGGL_API, self.workspace_id)
+            }
+            TimeTrackingProvider::Clockify => {
+                format!("{}/workspaces/{}", CLOCKIFY_API, self.workspace_id)
+            }
+        }
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.provider {
+            TimeTrackingProvider::Toggl => request.basic_auth(&self.token, Some("api_token")),
+            TimeTrackingProvider::Clockify => request.header("X-Api-Key", &self.token),
+        }
+    }
+
+    /// 发送请求并解析 JSON 响应
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &str,
+        body_bytes: u64,
+        session_id: Option<i64>,
+    ) -> Result<Value, String> {
+        tokio::time::sleep(REQUEST_INTERVAL).await;
+        let response = self
+            .authorized(request)
+            .send()
+            .await
+            .map_err(|e| format!("请求计时服务失败: {}", e))?;
+        if body_bytes > 0 {
+            egress::record(
+                EgressChannel::TimeTracking,
+                url,
+                EgressContent::Text,
+                body_bytes,
+                session_id,
+            );
+        }
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            let detail: String = text.chars().take(200).collect();
+            return Err(format!("计时服务返回 {}: {}", status, detail));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("解析计时服务响应失败: {}", e))
+    }
+
+    async fn post(
+        &self,
+        path: &str,
+        body: Value,
+        session_id: Option<i64>,
+    ) -> Result<Value, String> {
+        let url = format!("{}/{}", self.base_url(), path);
+        let body = serde_json::to_vec(&body).map_err(|e| e.to_string())?;
+        let bytes = body.len() as u64;
+        let request = self
+            .http
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(body);
+        self.send(request, &url, bytes, session_id).await
+    }
+
+    /// 项目 ID，不存在时创建
+    async fn project_id(&mut self, name: &str) -> Result<Value, String> {
+        let key = name.to_lowercase();
+        if self.projects.is_empty() {
+            let url = format!("{}/projects", self.base_url());
+            let request = match self.provider {
+                TimeTrackingProvider::Toggl => self.http.get(&url),
+                TimeTrackingProvider::Clockify => self
+                    .http
+                    .get(&url)
+                    .query(&[("page-size", "5000"), ("archived", "false")]),
+            };
+            let projects = self.send(request, &url, 0, None).await?;
+            for project in projects.as_array().into_iter().flatten() {
+                if let (Some(name), Some(id)) = (
+                    project.get("name").and_then(|v| v.as_str()),
+                    project.get("id"),
+                ) {
+                    self.projects.insert(name.to_lowercase(), id.clone());
+                }
+            }
+        }
+        if let Some(id) = self.projects.get(&key) {
+            return Ok(id.clone());
+        }
+
+        let body = match self.provider {
+            TimeTrackingProvider::Toggl => json!({ "name": name, "active": true }),
+            TimeTrackingProvider::Clockify => json!({ "name": name }),
+        };
+        let created = self.post("projects", body, None).await?;
+        let id = created
+            .get("id")
+            .cloned()
+            .ok_or_else(|| "创建项目失败: 响应中没有项目 ID".to_string())?;
+        info!("已在计时服务中创建项目: {}", name);
+        self.projects.insert(key, id.clone());
+        Ok(id)
+    }
+
+    /// 创建时间记录，返回记录 ID
+    async fn create_entry(&mut self, entry: &TimeEntry, session_id: i64) -> Result<String, String> {
+        let project_id = match &entry.project {
+            Some(project) => Some(self.project_id(project).await?),
+            None => None,
+        };
+        let (path, body) = match self.provider {
+            TimeTrackingProvider::Toggl => (
+                "time_entries",
+                json!({
+                    "created_with": "screen-analyzer",
+                    "description": entry.description,
+                    "start": entry.start.to_rfc3339(),
+                    "stop": entry.end.to_rfc3339(),
+                    "duration": (entry.end - entry.start).num_seconds(),
+                    "workspace_id": self.workspace_id.parse::<i64>().unwrap_or_default(),
+                    "project_id": project_id,
+                }),
+            ),
+            TimeTrackingProvider::Clockify => (
+                "time-entries",
+                json!({
+                    "description": entry.description,
+                    "start": entry.start.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                    "end": entry.end.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                    "projectId": project_id,
+                }),
+            ),
+        };
+        let created = self.post(path, body, Some(session_id)).await?;
+        match created.get("id") {
+            Some(Value::String(id)) => Ok(id.clone()),
+            Some(Value::Number(id)) => Ok(id.to_string()),
+            _ => Err("创建时间记录失败: 响应中没有记录 ID".to_string()),
+        }
+    }
+}
+
+/// 导出日期范围内（含首尾两天）的时间线卡片到计时服务
+pub async fn export_time_entries(
+    db: &Database,
+    config: &PersistedAppConfig,
+    start_date: &str,
+    end_date: &str,
+) -> Result<TimeTrackingReport, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))
+    };
+    let start = parse(start_date)?;
+    let end = parse(end_date)?;
+    if end < start {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end - start).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("日期范围不能超过 {} 天", MAX_RANGE_DAYS));
+    }
+    let settings = config.time_tracking.clone().unwrap_or_default();
+    let endpoint = match settings.provider {
+        TimeTrackingProvider::Toggl => TOGGL_API,
+        TimeTrackingProvider::Clockify => CLOCKIFY_API,
+    };
+    super::local_only::ensure_local_endpoint(endpoint)?;
+    let mut client = TimeTrackingClient::new(&settings)?;
+    let max_level = super::privacy::max_export_level(config);
+
+    let mut report = TimeTrackingReport::default();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let mut sessions = db
+            .get_sessions_by_date(&date.to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        sessions.sort_by_key(|session| session.start_time);
+        for session in sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            let cards = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+            let (cards, privacy_excluded) = super::privacy::filter_cards(cards, max_level);
+            report.privacy_excluded += privacy_excluded;
+
+            for card in cards {
+                if !category_rule(&settings, &card.category).0 {
+                    report.excluded += 1;
+                    continue;
+                }
+                let Some(entry) =
+                    card_entry(&settings, &card, session.start_time, session.end_time)
+                else {
+                    continue;
+                };
+                let key = idempotency_key(&settings, session_id, &entry);
+                match db.get_time_entry_export(&key).await {
+                    Ok(Some(_)) => {
+                        report.already_exported += 1;
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(e) => return Err(format!("读取导出记录失败: {}", e)),
+                }
+
+                let external_id = match client.create_entry(&entry, session_id).await {
+                    Ok(id) => id,
+                    Err(e) => {
+                        report.fail(format!("卡片「{}」导出失败: {}", card.title, e));
+                        continue;
+                    }
+                };
+                let record = TimeEntryExportRecord {
+                    idempotency_key: key,
+                    provider: settings.provider.as_str().to_string(),
+                    session_id,
+                    card_id: card.id,
+                    external_id,
+                    exported_at: local_now(),
+                };
+                db.insert_time_entry_export(&record)
+                    .await
+                    .map_err(|e| format!("保存导出记录失败: {}", e))?;
+                report.exported += 1;
+            }
+        }
+    }
+
+    info!(
+        "导出到 {}: 新增 {} 条，已导出 {} 条，排除 {} 条，失败 {} 条",
+        settings.provider.as_str(),
+        report.exported,
+        report.already_exported,
+        report.excluded + report.privacy_excluded,
+        report.failed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProjectRule, TimeTrackingCategoryRule};
+
+    fn card(category: &str, subcategory: &str, title: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: Some(1),
+            session_id: 1,
+            llm_call_id: None,
+            start_time: "2025-10-09T09:00:00+08:00".to_string(),
+            end_time: "2025-10-09T09:45:00+08:00".to_string(),
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+            title: title.to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: r#"{"primary":"VS Code"}"#.to_string(),
+            video_preview_path: None,
+            created_at: local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_rules_projects_and_idempotency_keys() {
+        let mut settings = TimeTrackingSettings {
+            workspace_id: "123".to_string(),
+            project_rules: vec![ProjectRule {
+                keyword: "screen-analyzer".to_string(),
+                project: "Screen Analyzer".to_string(),
+            }],
+            ..Default::default()
+        };
+        settings.category_rules.push(TimeTrackingCategoryRule {
+            category: "communication".to_string(),
+            include: true,
+            project: Some("沟通".to_string()),
+        });
+
+        // 默认不导出 idle 和 personal，未列出的类别导出
+        assert!(!category_rule(&settings, "Idle").0);
+        assert!(!category_rule(&settings, "personal").0);
+        assert_eq!(category_rule(&settings, "work"), (true, None));
+
+        // 项目规则 > 类别默认项目 > 子类别
+        let coding = card("work", "编程", "修复 screen-analyzer 截屏去重");
+        assert_eq!(
+            detect_project(&settings, &coding).as_deref(),
+            Some("Screen Analyzer")
+        );
+        let chat = card("communication", "即时通讯", "回复群消息");
+        assert_eq!(detect_project(&settings, &chat).as_deref(), Some("沟通"));
+        let design = card("work", "设计", "首页视觉稿");
+        assert_eq!(detect_project(&settings, &design).as_deref(), Some("设计"));
+        assert_eq!(detect_project(&settings, &card("work", " ", "杂项")), None);
+
+        let session_start = Utc::now();
+        let entry = card_entry(&settings, &coding, session_start, session_start).unwrap();
+        assert_eq!(entry.description, "修复 screen-analyzer 截屏去重");
+        assert_eq!((entry.end - entry.start).num_minutes(), 45);
+
+        // 同一时间段的键稳定；不同会话或工作区的键不同
+        let key = idempotency_key(&settings, 1, &entry);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, idempotency_key(&settings, 1, &entry));
+        assert_ne!(key, idempotency_key(&settings, 2, &entry));
+        settings.workspace_id = "456".to_string();
+        assert_ne!(key, idempotency_key(&settings, 1, &entry));
+    }
+}
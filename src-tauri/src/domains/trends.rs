@@ -0,0 +1,543 @@
+// 长期趋势 - 按周或按月汇总专注时长、类别占比和开始工作时间，并做简单的线性预测
+//
+// 每个周期的时长统一换算为“每周小时数”（未过完的周期按已过去的天数换算），便于比较；
+// 对每个指标按周期做最小二乘直线拟合：
+// - 上升的指标预测何时达到下一个 5 小时整数档（如“按当前趋势，会议将在 2026-06 达到每周 20 小时”）
+// - 类别占比变化超过 5 个百分点、开始工作时间每周期漂移超过 5 分钟时给出提示
+// 月度总结包含所在月份的统计、截至该月的趋势提示和本月的重复流程（见 workflows 模块）。
+// 离开模式的日期（见 away 模块）不计入换算天数，休假不会拉低每周小时数
+
+use super::metrics::{load_activity_spans, StatsPeriod};
+use super::records::day_focus;
+use super::summary::get_category_display_name;
+use crate::storage::Database;
+use chrono::{Datelike, Duration, NaiveDate, Timelike};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 默认统计的周期数
+pub const DEFAULT_TREND_PERIODS: usize = 6;
+/// 最多统计的周期数
+const MAX_TREND_PERIODS: usize = 24;
+/// 滚动平均的窗口（周期数）
+const ROLLING_WINDOW: usize = 3;
+/// 拟合至少需要的周期数
+const MIN_FIT_POINTS: usize = 3;
+/// 预测的整数档（每周小时数）
+const FORECAST_STEP_HOURS: f64 = 5.0;
+/// 最多往后预测的周期数
+const MAX_FORECAST_PERIODS: f64 = 12.0;
+/// 视为有变化的最小斜率（每周期每周小时数）
+const MIN_SLOPE_HOURS: f64 = 0.1;
+/// 类别占比变化的提示阈值（百分点）
+const DRIFT_THRESHOLD: f64 = 5.0;
+/// 开始时间漂移的提示阈值（每周期分钟数）
+const START_DRIFT_THRESHOLD_MINUTES: f64 = 5.0;
+
+/// 某天的汇总
+#[derive(Debug, Clone, Default)]
+pub struct DayAggregate {
+    pub focus_minutes: i64,
+    /// 各类别（显示名称）的时长（分钟）
+    pub category_minutes: BTreeMap<String, i64>,
+    /// 当天第一张卡片的开始时间（距零点的分钟数）
+    pub first_start_minutes: Option<i64>,
+}
+
+/// 一个周期的统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendPoint {
+    /// 周期名称（按月为 YYYY-MM，按周为周一的日期）
+    pub label: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// 有活动的天数
+    pub active_days: usize,
+    /// 离开模式的天数（不计入换算）
+    pub away_days: usize,
+    /// 专注时长（每周小时数）
+    pub focus_hours_per_week: f64,
+    /// 专注时长的滚动平均（最近 3 个周期）
+    pub rolling_focus_hours: f64,
+    /// 各类别时长（每周小时数）
+    pub category_hours_per_week: BTreeMap<String, f64>,
+    /// 平均开始工作时间 (HH:MM)
+    pub avg_start_time: Option<String>,
+}
+
+/// 类别占比变化
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryDrift {
+    pub category: String,
+    /// 第一个有活动的周期中的占比（百分比）
+    pub first_share: f64,
+    /// 最后一个有活动的周期中的占比（百分比）
+    pub last_share: f64,
+}
+
+/// 预测
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendForecast {
+    /// 指标名称（“专注”或类别显示名称）
+    pub metric: String,
+    /// 拟合得到的当前值（每周小时数）
+    pub current_hours_per_week: f64,
+    /// 每个周期的变化（每周小时数）
+    pub slope_per_period: f64,
+    /// 下一个整数档（每周小时数）
+    pub target_hours_per_week: f64,
+    /// 预计达到的周期
+    pub reach_label: String,
+    pub message: String,
+}
+
+/// 趋势报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendReport {
+    pub period: String,
+    pub points: Vec<TrendPoint>,
+    pub category_drift: Vec<CategoryDrift>,
+    /// 开始工作时间每个周期的变化（分钟，正数为推迟）
+    pub start_time_drift_minutes: Option<f64>,
+    pub forecasts: Vec<TrendForecast>,
+    /// 文字提示
+    pub insights: Vec<String>,
+}
+
+/// 月度总结
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlySummary {
+    pub month: String,
+    /// 本月统计
+    pub stats: TrendPoint,
+    /// 本月专注总时长（小时）
+    pub focus_hours: f64,
+    /// 截至本月的趋势提示
+    pub trend_insights: Vec<String>,
+    pub forecasts: Vec<TrendForecast>,
+    /// 本月反复手动执行的流程及自动化建议
+    pub repeated_workflows: Vec<super::workflows::RepeatedWorkflow>,
+}
+
+/// 汇总某天的活动片段
+pub fn aggregate_day(spans: &[super::metrics::ActivitySpan]) -> DayAggregate {
+    let mut aggregate = DayAggregate {
+        focus_minutes: day_focus(spans).focus_minutes,
+        ..DayAggregate::default()
+    };
+    for span in spans {
+        *aggregate
+            .category_minutes
+            .entry(get_category_display_name(&span.category))
+            .or_default() += span.duration_minutes();
+        let start = span.start.time();
+        let minutes = (start.hour() * 60 + start.minute()) as i64;
+        aggregate.first_start_minutes = Some(
+            aggregate
+                .first_start_minutes
+                .map_or(minutes, |first| first.min(minutes)),
+        );
+    }
+    aggregate
+}
+
+/// 截至锚点日期的最近 `count` 个周期（从早到晚）
+pub fn period_ranges(
+    period: StatsPeriod,
+    anchor: NaiveDate,
+    count: usize,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut ranges = Vec::new();
+    let mut cursor = anchor;
+    for _ in 0..count {
+        let range = period.date_range(cursor);
+        ranges.push(range);
+        cursor = range.0 - Duration::days(1);
+    }
+    ranges.reverse();
+    ranges
+}
+
+/// 最小二乘拟合，返回（截距，斜率），x 为周期序号
+fn linear_fit(values: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if values.len() < MIN_FIT_POINTS {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean_x = values.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = values.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let variance: f64 = values.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let covariance: f64 = values
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let slope = covariance / variance;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn format_minutes_of_day(minutes: f64) -> String {
+    let minutes = minutes.round().clamp(0.0, 24.0 * 60.0 - 1.0) as i64;
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// 往后第 `periods` 个周期的名称
+fn future_label(period: StatsPeriod, last_start: NaiveDate, periods: u32) -> String {
+    match period {
+        StatsPeriod::Month => {
+            let months = last_start.year() * 12 + last_start.month0() as i32 + periods as i32;
+            format!("{}-{:02}", months / 12, months % 12 + 1)
+        }
+        _ => (last_start + Duration::weeks(periods as i64))
+            .format("%Y-%m-%d 那周")
+            .to_string(),
+    }
+}
+
+/// 汇总各周期并计算趋势，`away_days` 中的日期不参与统计
+pub fn build_report(
+    period: StatsPeriod,
+    ranges: &[(NaiveDate, NaiveDate)],
+    days: &BTreeMap<NaiveDate, DayAggregate>,
+    away_days: &BTreeSet<NaiveDate>,
+    today: NaiveDate,
+) -> TrendReport {
+    let period_name = match period {
+        StatsPeriod::Month => "月",
+        _ => "周",
+    };
+
+    let mut points: Vec<TrendPoint> = Vec::new();
+    let mut start_minutes: Vec<Option<f64>> = Vec::new();
+    for (start, end) in ranges {
+        let elapsed_end = (*end).min(today);
+        let away_count = away_days.range(*start..=elapsed_end).count();
+        let elapsed_days =
+            ((elapsed_end - *start).num_days() + 1 - away_count as i64).max(1) as f64;
+        let per_week = |minutes: i64| round1(minutes as f64 / 60.0 * 7.0 / elapsed_days);
+
+        let in_range: Vec<&DayAggregate> = days
+            .range(*start..=*end)
+            .filter(|(date, _)| !away_days.contains(date))
+            .map(|(_, day)| day)
+            .collect();
+        let mut categories: BTreeMap<String, i64> = BTreeMap::new();
+        for day in &in_range {
+            for (category, minutes) in &day.category_minutes {
+                *categories.entry(category.clone()).or_default() += minutes;
+            }
+        }
+        let starts: Vec<i64> = in_range
+            .iter()
+            .filter_map(|day| day.first_start_minutes)
+            .collect();
+        let avg_start =
+            (!starts.is_empty()).then(|| starts.iter().sum::<i64>() as f64 / starts.len() as f64);
+        start_minutes.push(avg_start);
+
+        points.push(TrendPoint {
+            label: match period {
+                StatsPeriod::Month => start.format("%Y-%m").to_string(),
+                _ => start.format("%Y-%m-%d").to_string(),
+            },
+            start_date: start.format("%Y-%m-%d").to_string(),
+            end_date: end.format("%Y-%m-%d").to_string(),
+            active_days: in_range
+                .iter()
+                .filter(|day| !day.category_minutes.is_empty())
+                .count(),
+            away_days: away_count,
+            focus_hours_per_week: per_week(in_range.iter().map(|day| day.focus_minutes).sum()),
+            rolling_focus_hours: 0.0,
+            category_hours_per_week: categories
+                .into_iter()
+                .map(|(category, minutes)| (category, per_week(minutes)))
+                .collect(),
+            avg_start_time: avg_start.map(format_minutes_of_day),
+        });
+    }
+
+    for index in 0..points.len() {
+        let window = &points[index.saturating_sub(ROLLING_WINDOW - 1)..=index];
+        points[index].rolling_focus_hours = round1(
+            window.iter().map(|p| p.focus_hours_per_week).sum::<f64>() / window.len() as f64,
+        );
+    }
+
+    let mut insights = Vec::new();
+
+    // 预测：专注和各类别
+    let mut series: Vec<(String, Vec<f64>)> = vec![(
+        "专注".to_string(),
+        points.iter().map(|p| p.focus_hours_per_week).collect(),
+    )];
+    let mut categories: Vec<&String> = points
+        .iter()
+        .flat_map(|p| p.category_hours_per_week.keys())
+        .collect();
+    categories.sort();
+    categories.dedup();
+    for category in categories {
+        series.push((
+            category.clone(),
+            points
+                .iter()
+                .map(|p| {
+                    p.category_hours_per_week
+                        .get(category)
+                        .copied()
+                        .unwrap_or(0.0)
+                })
+                .collect(),
+        ));
+    }
+
+    let mut forecasts = Vec::new();
+    let last_start = ranges.last().map(|(start, _)| *start);
+    for (metric, values) in series {
+        let samples: Vec<(f64, f64)> = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index as f64, *value))
+            .collect();
+        let (Some((intercept, slope)), Some(last_start)) = (linear_fit(&samples), last_start)
+        else {
+            continue;
+        };
+        let current = (intercept + slope * (samples.len() - 1) as f64).max(0.0);
+
+        if slope >= MIN_SLOPE_HOURS {
+            let target = ((current / FORECAST_STEP_HOURS).floor() + 1.0) * FORECAST_STEP_HOURS;
+            let periods = ((target - current) / slope).ceil();
+            if periods > MAX_FORECAST_PERIODS {
+                continue;
+            }
+            let reach_label = future_label(period, last_start, periods as u32);
+            let message = format!(
+                "按当前趋势，{}将在 {} 达到每周 {} 小时（每{}增加约 {:.1} 小时）",
+                metric, reach_label, target, period_name, slope
+            );
+            insights.push(message.clone());
+            forecasts.push(TrendForecast {
+                metric,
+                current_hours_per_week: round1(current),
+                slope_per_period: round1(slope),
+                target_hours_per_week: target,
+                reach_label,
+                message,
+            });
+        } else if slope <= -MIN_SLOPE_HOURS && current > 0.0 {
+            insights.push(format!(
+                "{}时间在减少，每{}约少 {:.1} 小时（目前约每周 {:.1} 小时）",
+                metric, period_name, -slope, current
+            ));
+        }
+    }
+
+    // 类别占比变化：第一个和最后一个有活动的周期对比
+    let shares = |point: &TrendPoint| -> Option<BTreeMap<String, f64>> {
+        let total: f64 = point.category_hours_per_week.values().sum();
+        (total > 0.0).then(|| {
+            point
+                .category_hours_per_week
+                .iter()
+                .map(|(category, hours)| (category.clone(), hours / total * 100.0))
+                .collect()
+        })
+    };
+    let active: Vec<BTreeMap<String, f64>> = points.iter().filter_map(shares).collect();
+    let mut category_drift = Vec::new();
+    if let (Some(first), Some(last)) = (active.first(), active.last()) {
+        if active.len() >= 2 {
+            let mut keys: Vec<&String> = first.keys().chain(last.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for category in keys {
+                let first_share = first.get(category).copied().unwrap_or(0.0);
+                let last_share = last.get(category).copied().unwrap_or(0.0);
+                if (last_share - first_share).abs() >= DRIFT_THRESHOLD {
+                    insights.push(format!(
+                        "{}占比从 {:.0}% {}至 {:.0}%",
+                        category,
+                        first_share,
+                        if last_share > first_share {
+                            "升"
+                        } else {
+                            "降"
+                        },
+                        last_share
+                    ));
+                    category_drift.push(CategoryDrift {
+                        category: category.clone(),
+                        first_share: round1(first_share),
+                        last_share: round1(last_share),
+                    });
+                }
+            }
+        }
+    }
+
+    // 开始工作时间漂移
+    let start_samples: Vec<(f64, f64)> = start_minutes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, minutes)| minutes.map(|m| (index as f64, m)))
+        .collect();
+    let start_time_drift_minutes = linear_fit(&start_samples).map(|(_, slope)| round1(slope));
+    if let Some(drift) = start_time_drift_minutes {
+        if drift.abs() >= START_DRIFT_THRESHOLD_MINUTES {
+            let latest = start_samples
+                .last()
+                .map(|(_, minutes)| format_minutes_of_day(*minutes))
+                .unwrap_or_default();
+            insights.push(format!(
+                "开始工作时间每{}{}约 {:.0} 分钟（最近平均 {}）",
+                period_name,
+                if drift > 0.0 { "推迟" } else { "提前" },
+                drift.abs(),
+                latest
+            ));
+        }
+    }
+
+    TrendReport {
+        period: period.as_str().to_string(),
+        points,
+        category_drift,
+        start_time_drift_minutes,
+        forecasts,
+        insights,
+    }
+}
+
+/// 计算截至锚点日期的趋势
+///
+/// # 参数
+/// * `period` - 周期粒度（week/month）
+/// * `count` - 统计的周期数
+pub async fn get_trends(
+    db: &Database,
+    period: StatsPeriod,
+    anchor: NaiveDate,
+    count: usize,
+) -> Result<TrendReport, String> {
+    if period == StatsPeriod::Day {
+        return Err("趋势统计仅支持按周（week）或按月（month）".to_string());
+    }
+    let count = count.clamp(2, MAX_TREND_PERIODS);
+    let ranges = period_ranges(period, anchor, count);
+    let today = crate::storage::local_now().date_naive();
+
+    let mut days = BTreeMap::new();
+    let mut away_days = BTreeSet::new();
+    if let (Some((start, _)), Some((_, end))) = (ranges.first(), ranges.last()) {
+        away_days = super::away::load_away_days(db, *start, (*end).min(today)).await?;
+        let mut date = *start;
+        while date <= (*end).min(today) {
+            let spans = load_activity_spans(db, &date.format("%Y-%m-%d").to_string()).await?;
+            if !spans.is_empty() {
+                days.insert(date, aggregate_day(&spans));
+            }
+            date += Duration::days(1);
+        }
+    }
+
+    Ok(build_report(period, &ranges, &days, &away_days, today))
+}
+
+/// 生成月度总结（month 为 YYYY-MM）
+pub async fn get_monthly_summary(db: &Database, month: &str) -> Result<MonthlySummary, String> {
+    let first_day = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|e| format!("月份格式错误: {}", e))?;
+    let (_, last_day) = StatsPeriod::Month.date_range(first_day);
+
+    let report = get_trends(db, StatsPeriod::Month, last_day, DEFAULT_TREND_PERIODS).await?;
+    let stats = report
+        .points
+        .last()
+        .cloned()
+        .ok_or_else(|| "没有统计数据".to_string())?;
+    let today = crate::storage::local_now().date_naive();
+    let elapsed_days =
+        ((last_day.min(today) - first_day).num_days() + 1 - stats.away_days as i64).max(1) as f64;
+    let repeated_workflows =
+        super::workflows::detect_repeated_workflows(db, first_day, last_day.min(today)).await?;
+
+    Ok(MonthlySummary {
+        month: month.to_string(),
+        focus_hours: round1(stats.focus_hours_per_week * elapsed_days / 7.0),
+        stats,
+        trend_insights: report.insights,
+        forecasts: report.forecasts,
+        repeated_workflows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(focus: i64, meeting: i64, start: i64) -> DayAggregate {
+        DayAggregate {
+            focus_minutes: focus,
+            category_minutes: BTreeMap::from([
+                ("工作".to_string(), focus),
+                ("会议".to_string(), meeting),
+            ]),
+            first_start_minutes: Some(start),
+        }
+    }
+
+    #[test]
+    fn test_monthly_trend_forecasts_rising_meetings() {
+        let anchor = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let ranges = period_ranges(StatsPeriod::Month, anchor, 4);
+        assert_eq!(ranges[0].0, NaiveDate::from_ymd_opt(2025, 12, 1).unwrap());
+
+        // 每月会议每周增加约 2 小时，开始时间每月推迟 10 分钟，专注时长不变
+        let mut days = BTreeMap::new();
+        for (index, (start, end)) in ranges.iter().enumerate() {
+            let mut date = *start;
+            while date <= *end {
+                let meeting = 60 + index as i64 * 24;
+                days.insert(date, day(120, meeting, 9 * 60 + index as i64 * 10));
+                date += Duration::days(1);
+            }
+        }
+
+        let report = build_report(StatsPeriod::Month, &ranges, &days, &BTreeSet::new(), anchor);
+        assert_eq!(report.points.len(), 4);
+        assert_eq!(report.points[0].label, "2025-12");
+        assert_eq!(report.points[0].focus_hours_per_week, 14.0);
+        assert_eq!(report.points[0].category_hours_per_week["会议"], 7.0);
+        assert_eq!(report.points[3].avg_start_time.as_deref(), Some("09:30"));
+
+        let forecast = &report.forecasts[0];
+        assert_eq!(forecast.metric, "会议");
+        assert_eq!(forecast.current_hours_per_week, 15.4);
+        assert_eq!(forecast.target_hours_per_week, 20.0);
+        assert_eq!(forecast.reach_label, "2026-05");
+        assert!(report.forecasts.iter().all(|f| f.metric != "专注"));
+
+        assert_eq!(report.start_time_drift_minutes, Some(10.0));
+        assert!(report
+            .insights
+            .iter()
+            .any(|insight| insight.starts_with("开始工作时间每月推迟约 10 分钟")));
+        assert!(report
+            .category_drift
+            .iter()
+            .any(|drift| drift.category == "会议"));
+    }
+}
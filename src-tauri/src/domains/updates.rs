@@ -0,0 +1,303 @@
+// 新版本检查 - 后台定期查询 GitHub Releases，有新版本时提醒并给出更新说明和下载地址
+//
+// 只查询最新的正式版本（GitHub 的 latest 接口不包含预发布版本），版本号按数字逐段比较。
+// 更新说明按 Markdown 标题拆成分组，列表项作为条目；下载地址优先选择与当前平台和架构匹配的安装包，
+// 没有匹配的安装包时使用发布页地址。
+// 用户选择"跳过此版本"后该版本不再提醒，更新的版本仍会提醒；手动检查不受跳过影响
+
+use crate::models::UpdateCheckSettings;
+use crate::settings::SettingsManager;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 最新正式版本接口
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/deletexiumu/screen-analyzer/releases/latest";
+
+/// 当前版本
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 启动后首次检查前的等待时间（秒），避免与启动任务争用网络
+const STARTUP_DELAY_SECS: u64 = 60;
+/// 后台任务检查设置的间隔（秒）
+const TICK_SECS: u64 = 600;
+/// 请求超时（秒）
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// 更新说明中的一组条目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReleaseNoteSection {
+    /// 分组标题（说明开头没有标题的部分为空）
+    pub title: String,
+    pub items: Vec<String>,
+}
+
+/// 新版本信息
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    /// 发布标题
+    pub name: String,
+    pub published_at: Option<String>,
+    pub notes: Vec<ReleaseNoteSection>,
+    /// 发布页地址
+    pub release_url: String,
+    /// 当前平台的安装包下载地址（没有匹配的安装包时为发布页地址）
+    pub download_url: String,
+}
+
+/// 解析版本号为数字段（忽略开头的 v 和预发布/构建后缀）
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let core = version.split(['-', '+']).next()?;
+    core.split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+/// latest 是否比 current 新
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(mut latest), Some(mut current)) => {
+            let len = latest.len().max(current.len());
+            latest.resize(len, 0);
+            current.resize(len, 0);
+            latest > current
+        }
+        _ => false,
+    }
+}
+
+/// 把 Markdown 更新说明拆成分组：标题开始新分组，列表项和其他文字行作为条目
+pub fn parse_release_notes(body: &str) -> Vec<ReleaseNoteSection> {
+    let mut sections = vec![ReleaseNoteSection {
+        title: String::new(),
+        items: Vec::new(),
+    }];
+    for line in body.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            sections.push(ReleaseNoteSection {
+                title: line.trim_start_matches('#').trim().to_string(),
+                items: Vec::new(),
+            });
+            continue;
+        }
+        let item = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .unwrap_or(line)
+            .trim();
+        if let Some(section) = sections.last_mut() {
+            section.items.push(item.to_string());
+        }
+    }
+    sections.retain(|section| !section.items.is_empty());
+    sections
+}
+
+/// 选择与平台和架构匹配的安装包
+fn pick_download<'a>(assets: &'a [GithubAsset], os: &str, arch: &str) -> Option<&'a GithubAsset> {
+    let suffixes: &[&str] = match os {
+        "macos" => &[".dmg"],
+        "windows" => &["-setup.exe", ".msi", ".exe"],
+        "linux" => &[".appimage", ".deb"],
+        _ => &[],
+    };
+    let arch_names: &[&str] = match arch {
+        "aarch64" => &["aarch64", "arm64"],
+        "x86_64" => &["x86_64", "x64", "amd64"],
+        _ => &[],
+    };
+
+    let candidates = suffixes.iter().flat_map(|suffix| {
+        assets
+            .iter()
+            .filter(move |asset| asset.name.to_lowercase().ends_with(suffix))
+    });
+    // 优先选择文件名包含当前架构的安装包
+    candidates
+        .clone()
+        .find(|asset| {
+            let name = asset.name.to_lowercase();
+            arch_names.iter().any(|arch| name.contains(arch))
+        })
+        .or_else(|| candidates.clone().next())
+}
+
+/// 查询最新版本，比当前版本新时返回版本信息
+pub async fn check_for_update(client: &reqwest::Client) -> Result<Option<UpdateInfo>> {
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header("User-Agent", format!("screen-analyzer/{}", CURRENT_VERSION))
+        .header("Accept", "application/vnd.github+json")
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("查询最新版本失败: HTTP {}", response.status()));
+    }
+    let release: GithubRelease = response.json().await?;
+
+    let latest_version = release.tag_name.trim_start_matches(['v', 'V']).to_string();
+    if !is_newer(&latest_version, CURRENT_VERSION) {
+        return Ok(None);
+    }
+
+    let download_url = pick_download(
+        &release.assets,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    )
+    .map(|asset| asset.browser_download_url.clone())
+    .unwrap_or_else(|| release.html_url.clone());
+
+    Ok(Some(UpdateInfo {
+        current_version: CURRENT_VERSION.to_string(),
+        name: release
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| release.tag_name.clone()),
+        latest_version,
+        published_at: release.published_at,
+        notes: parse_release_notes(release.body.as_deref().unwrap_or_default()),
+        release_url: release.html_url,
+        download_url,
+    }))
+}
+
+/// 启动后台版本检查任务，发现未跳过的新版本时调用 `notify`（同一版本只提醒一次）
+pub fn start_update_checker<F>(
+    client: Arc<reqwest::Client>,
+    settings: Arc<SettingsManager>,
+    notify: F,
+) where
+    F: Fn(UpdateInfo) + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(STARTUP_DELAY_SECS)).await;
+        info!("新版本检查任务已启动");
+
+        let mut last_checked: Option<Instant> = None;
+        let mut notified: Option<String> = None;
+        loop {
+            let config = settings.get().await.update_check.unwrap_or_default();
+            let interval = Duration::from_secs(config.interval_hours.max(1) * 3600);
+            let due = last_checked.map_or(true, |at| at.elapsed() >= interval);
+
+            if config.enabled && due {
+                last_checked = Some(Instant::now());
+                match check_for_update(&client).await {
+                    Ok(Some(update)) => {
+                        let skipped = is_skipped(&config, &update.latest_version);
+                        if !skipped && notified.as_deref() != Some(update.latest_version.as_str()) {
+                            info!(
+                                "发现新版本 {}（当前 {}）",
+                                update.latest_version, update.current_version
+                            );
+                            notified = Some(update.latest_version.clone());
+                            notify(update);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("检查新版本失败: {}", e),
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(TICK_SECS)).await;
+        }
+    });
+}
+
+/// 版本是否已被跳过
+fn is_skipped(config: &UpdateCheckSettings, version: &str) -> bool {
+    config.skipped_version.as_deref().map_or(false, |skipped| {
+        skipped.trim_start_matches(['v', 'V']) == version
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_compare_notes_and_asset_choice() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+
+        let notes = parse_release_notes(
+            "修复了若干问题\n\n## 新功能\n- 暂停提醒\n* 启动自检\n\n## 修复\n\n### 空分组\n",
+        );
+        assert_eq!(
+            notes,
+            vec![
+                ReleaseNoteSection {
+                    title: String::new(),
+                    items: vec!["修复了若干问题".to_string()],
+                },
+                ReleaseNoteSection {
+                    title: "新功能".to_string(),
+                    items: vec!["暂停提醒".to_string(), "启动自检".to_string()],
+                },
+            ]
+        );
+
+        let assets: Vec<GithubAsset> = [
+            "screen-analyzer_0.2.0_x64.dmg",
+            "screen-analyzer_0.2.0_aarch64.dmg",
+            "screen-analyzer_0.2.0_x64-setup.exe",
+            "screen-analyzer_0.2.0_x64_en-US.msi",
+        ]
+        .iter()
+        .map(|name| GithubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+        })
+        .collect();
+        let pick = |os, arch| pick_download(&assets, os, arch).map(|a| a.name.as_str());
+        assert_eq!(
+            pick("macos", "aarch64"),
+            Some("screen-analyzer_0.2.0_aarch64.dmg")
+        );
+        assert_eq!(
+            pick("macos", "x86_64"),
+            Some("screen-analyzer_0.2.0_x64.dmg")
+        );
+        assert_eq!(
+            pick("windows", "x86_64"),
+            Some("screen-analyzer_0.2.0_x64-setup.exe")
+        );
+        assert_eq!(pick("linux", "x86_64"), None);
+    }
+}
@@ -0,0 +1,97 @@
+// 待分析视频认领 - 多台机器分析同一共享存储中的视频时避免重复分析
+//
+// 分析前先在数据库中认领视频（带有效期的租约），认领成功才分析。分析失败时释放认领；
+// 分析成功时保留到租约到期：其他机器本轮的待分析列表可能是在分析完成前取得的，
+// 租约到期前会话已写入数据库，之后不会再被当作未分析。
+// 分析机器崩溃时认领在租约到期后失效，其他机器可以接管。
+// 认领失败（数据库出错）时仍然分析：单机使用时不应因为认领表不可用而停止分析
+
+use crate::storage::Database;
+use chrono::Duration;
+use tracing::{info, warn};
+
+/// 认领的有效期（分钟），足够覆盖一次视频分析
+pub const CLAIM_LEASE_MINUTES: i64 = 30;
+
+/// 本机的认领标识（设备名称）
+pub fn claim_owner() -> String {
+    crate::storage::get_device_info().0
+}
+
+/// 认领视频，返回是否可以分析（已被其他机器认领时返回 false）
+pub async fn try_claim(db: &Database, video_path: &str) -> bool {
+    let now = crate::storage::local_now();
+    let lease_until = now + Duration::minutes(CLAIM_LEASE_MINUTES);
+    match db
+        .claim_video(video_path, &claim_owner(), now, lease_until)
+        .await
+    {
+        Ok(true) => true,
+        Ok(false) => {
+            info!("视频已被其他机器认领，跳过: {}", video_path);
+            false
+        }
+        Err(e) => {
+            warn!("认领视频失败，仍继续分析: {}", e);
+            true
+        }
+    }
+}
+
+/// 释放本机对视频的认领
+pub async fn release(db: &Database, video_path: &str) {
+    if let Err(e) = db.release_video_claim(video_path, &claim_owner()).await {
+        warn!("释放视频认领失败 {}: {}", video_path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{DatabaseRepository, SqliteRepository};
+    use chrono::{Duration, NaiveDateTime};
+
+    #[tokio::test]
+    async fn test_claim_is_exclusive_until_released_or_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = SqliteRepository::new(&dir.path().join("claims.db").to_string_lossy())
+            .await
+            .unwrap();
+        let now = NaiveDateTime::parse_from_str("2025-10-09 14:00:00", "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc();
+        let lease = now + Duration::minutes(30);
+        let video = "videos/2025-10-09_14-00-00.mp4";
+
+        assert!(repo
+            .claim_video(video, "machine-a", now, lease)
+            .await
+            .unwrap());
+        assert!(!repo
+            .claim_video(video, "machine-b", now, lease)
+            .await
+            .unwrap());
+        // 自己可以续期，其他机器不能释放
+        assert!(repo
+            .claim_video(video, "machine-a", now, lease)
+            .await
+            .unwrap());
+        repo.release_video_claim(video, "machine-b").await.unwrap();
+        assert!(!repo
+            .claim_video(video, "machine-b", now, lease)
+            .await
+            .unwrap());
+
+        // 租约到期后可以接管
+        let later = lease + Duration::minutes(1);
+        assert!(repo
+            .claim_video(video, "machine-b", later, later + Duration::minutes(30))
+            .await
+            .unwrap());
+
+        repo.release_video_claim(video, "machine-b").await.unwrap();
+        assert!(repo
+            .claim_video(video, "machine-a", now, lease)
+            .await
+            .unwrap());
+    }
+}
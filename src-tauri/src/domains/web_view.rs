@@ -0,0 +1,160 @@
+// 只读网页 - 由实时状态服务在 /web 提供，供同一网络的手机等设备查看"今天做了什么"
+//
+// 页面本身不含数据，通过 /api/day?date=YYYY-MM-DD 获取当天的会话、时间线卡片、
+// 每日总结和按类别统计的时长。页面和接口都需要与实时状态服务相同的访问令牌，
+// 页面从地址中的 ?token= 读取令牌并用于接口请求。会话和卡片的格式与单日离线包一致
+
+use super::day_bundle::{bundle_session, BundleSession};
+use super::playback::session_mapping;
+use super::storage::StorageDomain;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 网页内容（手机优先的单页，数据通过接口获取）
+pub const WEB_PAGE: &str = include_str!("web_view.html");
+
+/// 类别时长统计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryMinutes {
+    pub category: String,
+    pub minutes: i64,
+}
+
+/// 网页展示的一天
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDay {
+    pub date: String,
+    pub generated_at: String,
+    pub summary_text: Option<String>,
+    /// 有记录的总时长（分钟）
+    pub total_minutes: i64,
+    /// 各类别时长，按时长从多到少排列
+    pub categories: Vec<CategoryMinutes>,
+    pub sessions: Vec<BundleSession>,
+}
+
+/// 按卡片时间统计各类别时长
+pub fn category_minutes(sessions: &[BundleSession]) -> Vec<CategoryMinutes> {
+    let parse = |value: &str| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok();
+    let mut minutes: HashMap<String, i64> = HashMap::new();
+    for card in sessions.iter().flat_map(|session| &session.cards) {
+        let (Some(start), Some(end)) = (parse(&card.start_time), parse(&card.end_time)) else {
+            continue;
+        };
+        let duration = (end - start).num_minutes().max(0);
+        *minutes.entry(card.category.to_lowercase()).or_insert(0) += duration;
+    }
+
+    let mut categories: Vec<CategoryMinutes> = minutes
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(category, minutes)| CategoryMinutes { category, minutes })
+        .collect();
+    categories.sort_by(|a, b| {
+        b.minutes
+            .cmp(&a.minutes)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    categories
+}
+
+/// 汇总某天的网页数据
+pub async fn build_web_day(storage: &StorageDomain, date: &str) -> Result<WebDay, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("日期格式错误: {}", e))?;
+    let db = storage.get_db().await?;
+    let mut sessions = db
+        .get_sessions_by_date(date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    sessions.sort_by_key(|session| session.start_time);
+
+    let mut web_sessions = Vec::new();
+    for session in &sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let cards = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+        let mapping = session_mapping(session, storage.get_settings()).await;
+        web_sessions.push(bundle_session(session, &cards, None, &mapping));
+    }
+
+    let summary_text = db
+        .get_day_summary(date)
+        .await
+        .ok()
+        .flatten()
+        .map(|summary| summary.summary_text)
+        .filter(|text| !text.trim().is_empty());
+    let categories = category_minutes(&web_sessions);
+
+    Ok(WebDay {
+        date: date.to_string(),
+        generated_at: crate::storage::local_now()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        summary_text,
+        total_minutes: categories.iter().map(|c| c.minutes).sum(),
+        categories,
+        sessions: web_sessions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::day_bundle::BundleCard;
+
+    fn card(category: &str, start: &str, end: &str) -> BundleCard {
+        BundleCard {
+            title: String::new(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            summary: String::new(),
+            start_time: format!("2025-10-09 {}:00", start),
+            end_time: format!("2025-10-09 {}:00", end),
+            video_start_secs: None,
+            video_end_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_category_minutes_sorted_by_duration() {
+        let session = BundleSession {
+            id: 1,
+            title: String::new(),
+            summary: String::new(),
+            start_time: "2025-10-09 09:00:00".to_string(),
+            end_time: "2025-10-09 10:00:00".to_string(),
+            device_name: None,
+            video: None,
+            cards: vec![
+                card("Work", "09:00", "09:20"),
+                card("meeting", "09:20", "09:50"),
+                card("work", "09:50", "10:00"),
+                card("idle", "10:00", "10:00"),
+                card("work", "bad", "10:00"),
+            ],
+            bookmarks: Vec::new(),
+        };
+        assert_eq!(
+            category_minutes(&[session]),
+            vec![
+                CategoryMinutes {
+                    category: "meeting".to_string(),
+                    minutes: 30,
+                },
+                CategoryMinutes {
+                    category: "work".to_string(),
+                    minutes: 30,
+                },
+            ]
+        );
+    }
+}
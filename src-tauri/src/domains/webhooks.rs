@@ -0,0 +1,324 @@
+// 分析结果 webhook - 把会话、分段和卡片签名后推送到用户配置的地址
+//
+// 请求体为 JSON，结构见 payload_schema()（可通过 get_webhook_schema 命令获取）。
+// 设置了签名密钥时，请求头带上：
+// - X-Screen-Analyzer-Delivery：投递 ID，重试时不变，可用于去重
+// - X-Screen-Analyzer-Timestamp：Unix 秒级时间戳
+// - X-Screen-Analyzer-Signature：sha256=HMAC-SHA256(密钥, "{时间戳}.{请求体}") 的十六进制
+// 网络错误、429 和 5xx 按指数退避重试，其他状态码不重试；
+// 每次投递的最终结果写入 webhook_deliveries 表，可在应用中查询
+
+use super::egress::{self, EgressChannel, EgressContent};
+use crate::models::PrivacyLevel;
+use crate::storage::repository::failover::backoff_delay;
+use crate::storage::{Database, WebhookDeliveryRecord};
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 请求体结构版本，字段有不兼容的变化时递增
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 投递 ID 请求头
+pub const DELIVERY_HEADER: &str = "X-Screen-Analyzer-Delivery";
+
+/// 时间戳请求头
+pub const TIMESTAMP_HEADER: &str = "X-Screen-Analyzer-Timestamp";
+
+/// 签名请求头
+pub const SIGNATURE_HEADER: &str = "X-Screen-Analyzer-Signature";
+
+/// 单次请求超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 最多请求次数（含首次）
+const MAX_ATTEMPTS: u32 = 5;
+
+/// 重试间隔：从 2 秒开始翻倍，最长 60 秒
+const RETRY_BASE_SECS: u64 = 2;
+const RETRY_MAX_SECS: u64 = 60;
+
+/// 查询投递记录的默认条数
+pub const DEFAULT_DELIVERY_LIMIT: i64 = 100;
+
+/// 投递结果
+pub const STATUS_DELIVERED: &str = "delivered";
+pub const STATUS_FAILED: &str = "failed";
+
+fn hmac_hex(secret: &str, data: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 计算签名请求头的值
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut data = format!("{}.", timestamp).into_bytes();
+    data.extend_from_slice(body);
+    format!("sha256={}", hmac_hex(secret, &data))
+}
+
+/// 是否值得重试（限流和服务端错误）
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// 请求体的 JSON Schema
+pub fn payload_schema() -> Value {
+    let time = json!({ "type": "string", "description": "RFC3339 时间" });
+    let local_time =
+        json!({ "type": "string", "description": "本地时间（YYYY-MM-DDTHH:MM:SS，不带时区）" });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("https://screen-analyzer.local/schemas/webhook-payload-v{}.json", SCHEMA_VERSION),
+        "title": "Screen Analyzer 分析结果",
+        "type": "object",
+        "required": ["event", "schema_version", "delivery_id", "session", "segments", "cards"],
+        "properties": {
+            "event": { "const": "session_processed" },
+            "schema_version": { "const": SCHEMA_VERSION },
+            "delivery_id": { "type": "string", "description": "投递 ID，重试时不变" },
+            "session": {
+                "type": "object",
+                "required": ["id", "start_time", "end_time", "title", "summary"],
+                "properties": {
+                    "id": { "type": "integer" },
+                    "start_time": local_time,
+                    "end_time": local_time,
+                    "title": { "type": "string" },
+                    "summary": { "type": "string" },
+                    "video_path": { "type": ["string", "null"] },
+                    "tags": { "type": "string", "description": "JSON 格式的标签" },
+                    "device_name": { "type": ["string", "null"] }
+                }
+            },
+            "segments": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["start_timestamp", "end_timestamp", "description"],
+                    "properties": {
+                        "id": { "type": ["integer", "null"] },
+                        "start_timestamp": time,
+                        "end_timestamp": time,
+                        "description": { "type": "string" }
+                    }
+                }
+            },
+            "cards": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["start_time", "end_time", "category", "title", "summary"],
+                    "properties": {
+                        "id": { "type": ["integer", "null"] },
+                        "start_time": time,
+                        "end_time": time,
+                        "category": { "type": "string" },
+                        "subcategory": { "type": "string" },
+                        "title": { "type": "string" },
+                        "summary": { "type": "string" },
+                        "detailed_summary": { "type": "string" },
+                        "app_sites": { "type": "string", "description": "JSON 格式的应用/网站信息" },
+                        "confidence": { "type": ["number", "null"] }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// 读取会话的完整分析结果，组装请求体（敏感度高于 `max_level` 的卡片不推送）
+pub async fn build_payload(
+    db: &Database,
+    session_id: i64,
+    delivery_id: &str,
+    max_level: PrivacyLevel,
+) -> Result<Value> {
+    let session = db.get_session(session_id).await?;
+    let segments = db.get_video_segments_by_session(session_id).await?;
+    let (cards, _) = super::privacy::filter_cards(
+        db.get_timeline_cards_by_session(session_id).await?,
+        max_level,
+    );
+    Ok(json!({
+        "event": "session_processed",
+        "schema_version": SCHEMA_VERSION,
+        "delivery_id": delivery_id,
+        "session": session,
+        "segments": segments,
+        "cards": cards,
+    }))
+}
+
+/// 发送一次请求，返回状态码
+async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    delivery_id: &str,
+    body: &[u8],
+) -> Result<reqwest::StatusCode> {
+    let mut request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(DELIVERY_HEADER, delivery_id);
+    if !secret.is_empty() {
+        let timestamp = chrono::Utc::now().timestamp();
+        request = request
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign(secret, timestamp, body));
+    }
+    let response = request.body(body.to_vec()).send().await?;
+    Ok(response.status())
+}
+
+/// 投递会话的分析结果（失败时按退避间隔重试），返回写入的投递记录
+pub async fn deliver(
+    db: &Database,
+    url: &str,
+    secret: &str,
+    session_id: i64,
+    max_level: PrivacyLevel,
+) -> Result<WebhookDeliveryRecord> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(anyhow!("未设置 webhook 地址"));
+    }
+    super::local_only::ensure_local_endpoint(url).map_err(|e| anyhow!(e))?;
+
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let body = serde_json::to_vec(&build_payload(db, session_id, &delivery_id, max_level).await?)?;
+    let client = super::local_only::http_client_builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?;
+
+    let mut record = WebhookDeliveryRecord {
+        id: None,
+        delivery_id,
+        session_id,
+        url: url.to_string(),
+        status: STATUS_FAILED.to_string(),
+        attempts: 0,
+        response_status: None,
+        error: None,
+        payload_bytes: body.len() as i64,
+        created_at: crate::storage::local_now(),
+    };
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(backoff_delay(attempt - 1, RETRY_BASE_SECS, RETRY_MAX_SECS)).await;
+        }
+        record.attempts = attempt as i64 + 1;
+        match post(&client, url, secret, &record.delivery_id, &body).await {
+            Ok(status) => {
+                egress::record(
+                    EgressChannel::Webhook,
+                    url,
+                    EgressContent::Text,
+                    body.len() as u64,
+                    Some(session_id),
+                );
+                record.response_status = Some(status.as_u16() as i64);
+                if status.is_success() {
+                    record.status = STATUS_DELIVERED.to_string();
+                    record.error = None;
+                    break;
+                }
+                record.error = Some(format!("webhook 返回 {}", status));
+                if !is_retryable(status) {
+                    break;
+                }
+            }
+            Err(e) => {
+                record.response_status = None;
+                record.error = Some(e.to_string());
+            }
+        }
+        warn!(
+            "会话 {} 推送到 webhook 失败（第 {} 次）: {}",
+            session_id,
+            record.attempts,
+            record.error.as_deref().unwrap_or_default()
+        );
+    }
+
+    record.id = Some(db.insert_webhook_delivery(&record).await?);
+    if record.status == STATUS_DELIVERED {
+        info!("会话 {} 已推送到 webhook", session_id);
+    }
+    Ok(record)
+}
+
+/// 在后台投递（重试期间不阻塞会话处理）
+pub fn spawn_delivery(
+    db: Arc<Database>,
+    url: String,
+    secret: String,
+    session_id: i64,
+    max_level: PrivacyLevel,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = deliver(&db, &url, &secret, session_id, max_level).await {
+            warn!("会话 {} 的 webhook 投递失败: {}", session_id, e);
+        }
+    });
+}
+
+/// 查询最近的投递记录
+pub async fn get_deliveries(
+    db: &Database,
+    session_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<WebhookDeliveryRecord>, String> {
+    db.get_webhook_deliveries(session_id, limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).max(1))
+        .await
+        .map_err(|e| format!("获取 webhook 投递记录失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_and_retry_rules() {
+        // RFC 4231 测试用例 2
+        assert_eq!(
+            hmac_hex("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            sign("secret", 1700000000, br#"{"a":1}"#),
+            format!("sha256={}", hmac_hex("secret", br#"1700000000.{"a":1}"#))
+        );
+        assert_ne!(
+            sign("secret", 1700000000, b"{}"),
+            sign("secret", 1700000001, b"{}")
+        );
+
+        assert!(is_retryable(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(is_retryable(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(reqwest::StatusCode::UNAUTHORIZED));
+
+        let schema = payload_schema();
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("segments")));
+    }
+}
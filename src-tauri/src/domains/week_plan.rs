@@ -0,0 +1,588 @@
+// 周计划建议 - 根据最近几周的历史记录建议下一周的日程
+//
+// 统计最近 4 周每个星期几的活动、会议和专注时长，各小时的专注分布，以及固定在同一星期几、
+// 同一时段反复出现的会议（例会），交给 LLM 生成一周的日程块：会议多的日子少排深度工作，
+// 深度工作放在历史上最专注的时段，例会原样保留。LLM 不可用或返回无法解析时，
+// 按同样的统计生成规则版计划。日程块可导出为 ICS 文件导入日历
+
+use super::live::FOCUS_PRODUCTIVITY;
+use super::meetings::is_meeting_card;
+use super::metrics::{category_productivity, parse_series_time};
+use crate::actors::LLMHandle;
+use crate::storage::Database;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tracing::warn;
+
+/// 统计最近几周的历史
+const LOOKBACK_WEEKS: i64 = 4;
+/// 平均会议时长达到该值（分钟）的日子视为会议日，只安排较短的深度工作
+const MEETING_HEAVY_MINUTES: i64 = 120;
+/// 至少在几个不同的周出现才算例会
+const RECURRING_MIN_WEEKS: usize = 2;
+/// 深度工作时段（小时），会议日减半
+const DEEP_WORK_HOURS: u32 = 2;
+/// 返回的最佳专注小时数
+const BEST_FOCUS_HOUR_COUNT: usize = 3;
+
+const WEEKDAY_LABELS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
+/// 历史上的一张卡片（计划只关心时间、是否会议、是否专注）
+#[derive(Debug, Clone)]
+pub struct PlanSample {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub meeting: bool,
+    pub focus: bool,
+    pub title: String,
+}
+
+/// 某个星期几的平均情况（分钟/天）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekdayStat {
+    /// 1 = 周一 … 7 = 周日
+    pub weekday: u32,
+    pub label: String,
+    pub active_minutes: i64,
+    pub meeting_minutes: i64,
+    pub focus_minutes: i64,
+}
+
+/// 例会：多个周在同一星期几、同一小时开始的会议
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringMeeting {
+    pub weekday: u32,
+    /// 开始时间 (HH:MM，取最近一次)
+    pub start: String,
+    /// 结束时间 (HH:MM，取最近一次)
+    pub end: String,
+    pub title: String,
+    /// 出现过的周数
+    pub weeks: usize,
+}
+
+/// 用于生成计划的历史统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekHistory {
+    pub weeks: i64,
+    pub weekdays: Vec<WeekdayStat>,
+    /// 各小时的专注总分钟数（下标为小时）
+    pub focus_by_hour: Vec<i64>,
+    /// 专注最多的几个小时（升序）
+    pub best_focus_hours: Vec<u32>,
+    pub recurring_meetings: Vec<RecurringMeeting>,
+}
+
+/// 日程块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanBlockKind {
+    DeepWork,
+    Meeting,
+    Shallow,
+    Break,
+}
+
+impl PlanBlockKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DeepWork => "深度工作",
+            Self::Meeting => "会议",
+            Self::Shallow => "事务处理",
+            Self::Break => "休息",
+        }
+    }
+}
+
+/// 计划中的一个日程块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanBlock {
+    /// 日期 (YYYY-MM-DD)
+    pub date: String,
+    /// 开始时间 (HH:MM)
+    pub start: String,
+    /// 结束时间 (HH:MM)
+    pub end: String,
+    pub kind: PlanBlockKind,
+    pub title: String,
+    /// 这样安排的理由
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// 周计划
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekPlan {
+    /// 周一的日期 (YYYY-MM-DD)
+    pub week_start: String,
+    pub blocks: Vec<PlanBlock>,
+    /// llm 或 history（LLM 不可用时的规则版计划）
+    pub generated_by: String,
+    /// 使用规则版计划的原因
+    pub note: Option<String>,
+    pub history: WeekHistory,
+}
+
+/// 计划从哪一周开始：指定日期所在周的周一，未指定时为下周一
+pub fn plan_week_start(date: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+    match date {
+        Some(date) => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        None => today + Duration::days(7 - today.weekday().num_days_from_monday() as i64),
+    }
+}
+
+/// 把一段时间按小时拆分，累加到各小时的分钟数
+fn add_hour_minutes(hours: &mut [i64], start: NaiveDateTime, end: NaiveDateTime) {
+    let mut cursor = start;
+    while cursor < end {
+        let next_hour = (cursor + Duration::hours(1))
+            .with_minute(0)
+            .and_then(|t| t.with_second(0))
+            .unwrap_or(end);
+        let segment_end = next_hour.min(end);
+        hours[cursor.hour() as usize] += (segment_end - cursor).num_minutes();
+        cursor = segment_end;
+    }
+}
+
+/// 根据历史卡片统计星期几、小时分布和例会
+pub fn build_history(samples: &[PlanSample], weeks: i64) -> WeekHistory {
+    let weeks = weeks.max(1);
+    let mut totals = [(0i64, 0i64, 0i64); 7];
+    let mut focus_by_hour = vec![0i64; 24];
+    // (星期几, 开始小时) -> 出现过的周、最近一次的会议
+    let mut meetings: BTreeMap<(u32, u32), (HashSet<(i32, u32)>, &PlanSample)> = BTreeMap::new();
+
+    for sample in samples {
+        let minutes = (sample.end - sample.start).num_minutes().max(0);
+        let weekday = sample.start.weekday().num_days_from_monday();
+        let total = &mut totals[weekday as usize];
+        total.0 += minutes;
+        if sample.meeting {
+            total.1 += minutes;
+            let week = sample.start.iso_week();
+            let entry = meetings
+                .entry((weekday, sample.start.hour()))
+                .or_insert_with(|| (HashSet::new(), sample));
+            entry.0.insert((week.year(), week.week()));
+            if sample.start > entry.1.start {
+                entry.1 = sample;
+            }
+        } else if sample.focus {
+            total.2 += minutes;
+            add_hour_minutes(&mut focus_by_hour, sample.start, sample.end);
+        }
+    }
+
+    let weekdays = totals
+        .iter()
+        .enumerate()
+        .map(|(index, (active, meeting, focus))| WeekdayStat {
+            weekday: index as u32 + 1,
+            label: WEEKDAY_LABELS[index].to_string(),
+            active_minutes: active / weeks,
+            meeting_minutes: meeting / weeks,
+            focus_minutes: focus / weeks,
+        })
+        .collect();
+
+    let mut ranked: Vec<u32> = (0..24).filter(|h| focus_by_hour[*h as usize] > 0).collect();
+    ranked.sort_by_key(|h| std::cmp::Reverse(focus_by_hour[*h as usize]));
+    let mut best_focus_hours: Vec<u32> = ranked.into_iter().take(BEST_FOCUS_HOUR_COUNT).collect();
+    best_focus_hours.sort_unstable();
+
+    let recurring_meetings = meetings
+        .into_iter()
+        .filter(|(_, (weeks, _))| weeks.len() >= RECURRING_MIN_WEEKS)
+        .map(|((weekday, _), (weeks, latest))| RecurringMeeting {
+            weekday: weekday + 1,
+            start: latest.start.format("%H:%M").to_string(),
+            end: latest.end.format("%H:%M").to_string(),
+            title: latest.title.clone(),
+            weeks: weeks.len(),
+        })
+        .collect();
+
+    WeekHistory {
+        weeks,
+        weekdays,
+        focus_by_hour,
+        best_focus_hours,
+        recurring_meetings,
+    }
+}
+
+fn parse_hm(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// 规则版计划：工作日保留例会，在历史最专注、且不与例会冲突的时段安排深度工作
+pub fn fallback_plan(history: &WeekHistory, week_start: NaiveDate) -> Vec<PlanBlock> {
+    let has_history = history.weekdays.iter().any(|day| day.active_minutes > 0);
+    let mut blocks = Vec::new();
+    for stat in history.weekdays.iter() {
+        // 没有历史时只安排周一到周五；有历史时安排有活动记录的日子
+        let planned = if has_history {
+            stat.active_minutes > 0
+        } else {
+            stat.weekday <= 5
+        };
+        if !planned {
+            continue;
+        }
+        let date = week_start + Duration::days(stat.weekday as i64 - 1);
+        let date_str = date.format("%Y-%m-%d").to_string();
+
+        let mut busy: Vec<(NaiveTime, NaiveTime)> = Vec::new();
+        for meeting in history
+            .recurring_meetings
+            .iter()
+            .filter(|meeting| meeting.weekday == stat.weekday)
+        {
+            if let (Some(start), Some(end)) = (parse_hm(&meeting.start), parse_hm(&meeting.end)) {
+                busy.push((start, end));
+            }
+            blocks.push(PlanBlock {
+                date: date_str.clone(),
+                start: meeting.start.clone(),
+                end: meeting.end.clone(),
+                kind: PlanBlockKind::Meeting,
+                title: meeting.title.clone(),
+                reason: format!("最近 {} 周都在这个时间开会", meeting.weeks),
+            });
+        }
+
+        let meeting_heavy = stat.meeting_minutes >= MEETING_HEAVY_MINUTES;
+        let length = if meeting_heavy {
+            DEEP_WORK_HOURS / 2
+        } else {
+            DEEP_WORK_HOURS
+        };
+        let window = (7..=(21 - length))
+            .filter(|hour| {
+                let start = NaiveTime::from_hms_opt(*hour, 0, 0).unwrap_or_default();
+                let end = NaiveTime::from_hms_opt(hour + length, 0, 0).unwrap_or_default();
+                busy.iter()
+                    .all(|(b_start, b_end)| end <= *b_start || start >= *b_end)
+            })
+            .max_by_key(|hour| {
+                let score: i64 = (*hour..hour + length)
+                    .map(|h| history.focus_by_hour[h as usize])
+                    .sum();
+                // 分数相同时选较早的时段
+                (score, std::cmp::Reverse(*hour))
+            });
+        let Some(hour) = window else {
+            continue;
+        };
+        let reason = match (meeting_heavy, history.focus_by_hour[hour as usize] > 0) {
+            (true, _) => format!(
+                "{}平均开会 {} 分钟，只安排 {} 小时深度工作",
+                stat.label, stat.meeting_minutes, length
+            ),
+            (false, true) => "历史上这个时段最专注".to_string(),
+            (false, false) => "上午安排深度工作".to_string(),
+        };
+        blocks.push(PlanBlock {
+            date: date_str,
+            start: format!("{:02}:00", hour),
+            end: format!("{:02}:00", hour + length),
+            kind: PlanBlockKind::DeepWork,
+            title: PlanBlockKind::DeepWork.label().to_string(),
+            reason,
+        });
+    }
+    blocks.sort_by(|a, b| (&a.date, &a.start).cmp(&(&b.date, &b.start)));
+    blocks
+}
+
+/// 构建给 LLM 的提示词
+pub fn build_prompt(history: &WeekHistory, week_start: NaiveDate) -> String {
+    let mut weekday_lines = String::new();
+    for day in &history.weekdays {
+        weekday_lines.push_str(&format!(
+            "\n- {}（{}）: 活动 {} 分钟，会议 {} 分钟，专注 {} 分钟",
+            day.label,
+            (week_start + Duration::days(day.weekday as i64 - 1)).format("%Y-%m-%d"),
+            day.active_minutes,
+            day.meeting_minutes,
+            day.focus_minutes
+        ));
+    }
+    let best_hours = if history.best_focus_hours.is_empty() {
+        "暂无数据".to_string()
+    } else {
+        history
+            .best_focus_hours
+            .iter()
+            .map(|h| format!("{:02}:00", h))
+            .collect::<Vec<_>>()
+            .join("、")
+    };
+    let mut meeting_lines = String::new();
+    for meeting in &history.recurring_meetings {
+        meeting_lines.push_str(&format!(
+            "\n- {} {}-{} {}（最近 {} 周出现）",
+            WEEKDAY_LABELS[(meeting.weekday as usize).saturating_sub(1).min(6)],
+            meeting.start,
+            meeting.end,
+            meeting.title,
+            meeting.weeks
+        ));
+    }
+    if meeting_lines.is_empty() {
+        meeting_lines.push_str("\n- 无");
+    }
+
+    format!(
+        r#"根据以下最近 {} 周的屏幕活动统计，为 {} 开始的一周安排一份现实可行的日程。
+
+每个星期几的平均情况:{}
+
+历史上最专注的时段: {}
+
+固定的例会:{}
+
+要求：
+1. 例会保留在原来的时间
+2. 深度工作安排在历史上最专注的时段，每段 1-2 小时；会议多的日子少排深度工作
+3. 按历史活动时长安排每天的工作量，没有活动记录的日子不安排
+4. 不要安排得过满，留出事务处理和休息时间
+5. kind 只能是 deep_work、meeting、shallow、break；reason 用一句中文说明依据
+
+只返回JSON数组，不要其他说明：
+[{{"date": "YYYY-MM-DD", "start": "HH:MM", "end": "HH:MM", "kind": "deep_work", "title": "标题", "reason": "理由"}}]"#,
+        history.weeks,
+        week_start.format("%Y-%m-%d"),
+        weekday_lines,
+        best_hours,
+        meeting_lines
+    )
+}
+
+/// 解析 LLM 返回的日程块，丢弃日期不在该周或时间无效的块
+pub fn parse_blocks(response: &str, week_start: NaiveDate) -> Result<Vec<PlanBlock>, String> {
+    let json = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => response,
+    };
+    let blocks: Vec<PlanBlock> =
+        serde_json::from_str(json).map_err(|e| format!("无法解析周计划: {}", e))?;
+    let week_end = week_start + Duration::days(7);
+
+    let mut valid: Vec<PlanBlock> = blocks
+        .into_iter()
+        .filter(|block| {
+            let in_week = NaiveDate::parse_from_str(&block.date, "%Y-%m-%d")
+                .map(|date| date >= week_start && date < week_end)
+                .unwrap_or(false);
+            let ordered = matches!(
+                (parse_hm(&block.start), parse_hm(&block.end)),
+                (Some(start), Some(end)) if start < end
+            );
+            in_week && ordered && !block.title.trim().is_empty()
+        })
+        .collect();
+    if valid.is_empty() {
+        return Err("周计划中没有有效的日程块".to_string());
+    }
+    valid.sort_by(|a, b| (&a.date, &a.start).cmp(&(&b.date, &b.start)));
+    Ok(valid)
+}
+
+/// 转义 ICS 文本字段
+fn escape_ics(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// 把日程块导出为 ICS 日历（使用本地浮动时间，导入后按日历所在时区显示）
+pub fn to_ics(blocks: &[PlanBlock], stamp: NaiveDateTime) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%S").to_string();
+    let mut ics = String::from(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//screen-analyzer//week-plan//CN\r\nCALSCALE:GREGORIAN\r\n",
+    );
+    for (index, block) in blocks.iter().enumerate() {
+        let time = |value: &str| {
+            format!(
+                "{}T{}00",
+                block.date.replace('-', ""),
+                value.replace(':', "")
+            )
+        };
+        ics.push_str("BEGIN:VEVENT\r\n");
+        ics.push_str(&format!(
+            "UID:week-plan-{}-{}@screen-analyzer\r\n",
+            stamp, index
+        ));
+        ics.push_str(&format!("DTSTAMP:{}\r\n", stamp));
+        ics.push_str(&format!("DTSTART:{}\r\n", time(&block.start)));
+        ics.push_str(&format!("DTEND:{}\r\n", time(&block.end)));
+        ics.push_str(&format!("SUMMARY:{}\r\n", escape_ics(&block.title)));
+        ics.push_str(&format!(
+            "DESCRIPTION:{}\r\n",
+            escape_ics(&format!("{}：{}", block.kind.label(), block.reason))
+        ));
+        ics.push_str(&format!("CATEGORIES:{}\r\n", block.kind.label()));
+        ics.push_str("END:VEVENT\r\n");
+    }
+    ics.push_str("END:VCALENDAR\r\n");
+    ics
+}
+
+/// 加载日期范围内（含首尾两天）的卡片
+async fn load_samples(
+    db: &Database,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<PlanSample>, String> {
+    let mut samples = Vec::new();
+    let mut seen_sessions = HashSet::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let sessions = db
+            .get_sessions_by_date(&date.to_string())
+            .await
+            .map_err(|e| format!("获取会话失败: {}", e))?;
+        for session in sessions {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            if !seen_sessions.insert(session_id) {
+                continue;
+            }
+            let cards = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .map_err(|e| format!("获取时间线卡片失败: {}", e))?;
+            samples.extend(cards.iter().map(|card| {
+                let start =
+                    parse_series_time(&card.start_time, session.start_time, session.end_time);
+                let end = parse_series_time(&card.end_time, session.start_time, session.end_time);
+                PlanSample {
+                    start,
+                    end: end.max(start),
+                    meeting: is_meeting_card(card),
+                    focus: category_productivity(&card.category) >= FOCUS_PRODUCTIVITY,
+                    title: card.title.clone(),
+                }
+            }));
+        }
+    }
+    Ok(samples)
+}
+
+/// 根据最近几周的历史建议一周的日程（LLM 不可用时使用规则版计划）
+pub async fn suggest_week_plan(
+    db: &Database,
+    llm_handle: &LLMHandle,
+    week_start: NaiveDate,
+    today: NaiveDate,
+) -> Result<WeekPlan, String> {
+    let history_end = today - Duration::days(1);
+    let history_start = history_end - Duration::days(LOOKBACK_WEEKS * 7 - 1);
+    let samples = load_samples(db, history_start, history_end).await?;
+    let history = build_history(&samples, LOOKBACK_WEEKS);
+
+    let llm_result = match llm_handle
+        .generate_week_plan(&build_prompt(&history, week_start))
+        .await
+    {
+        Ok(response) => parse_blocks(&response, week_start),
+        Err(e) => Err(e.to_string()),
+    };
+    let (blocks, generated_by, note) = match llm_result {
+        Ok(blocks) => (blocks, "llm", None),
+        Err(e) => {
+            warn!("LLM 生成周计划失败，使用规则版计划: {}", e);
+            (
+                fallback_plan(&history, week_start),
+                "history",
+                Some(format!("LLM 生成失败（{}），已按历史统计生成", e)),
+            )
+        }
+    };
+
+    Ok(WeekPlan {
+        week_start: week_start.format("%Y-%m-%d").to_string(),
+        blocks,
+        generated_by: generated_by.to_string(),
+        note,
+        history,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(start: &str, end: &str, meeting: bool, title: &str) -> PlanSample {
+        let parse = |value| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        PlanSample {
+            start: parse(start),
+            end: parse(end),
+            meeting,
+            focus: !meeting,
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_history_and_fallback_plan() {
+        // 两周：周一 10 点例会（另有 3 小时会议），周二上午 9-11 点专注写代码
+        let samples = vec![
+            sample("2025-09-29 10:00", "2025-09-29 10:30", true, "周会"),
+            sample("2025-09-29 14:00", "2025-09-29 17:00", true, "评审会"),
+            sample("2025-09-30 09:00", "2025-09-30 11:00", false, "写代码"),
+            sample("2025-10-06 10:00", "2025-10-06 10:45", true, "周会"),
+            sample("2025-10-07 09:30", "2025-10-07 11:00", false, "写代码"),
+        ];
+        let history = build_history(&samples, 2);
+        assert_eq!(history.weekdays[0].meeting_minutes, (30 + 180 + 45) / 2);
+        assert_eq!(history.weekdays[1].focus_minutes, (120 + 90) / 2);
+        assert_eq!(history.focus_by_hour[9], 60 + 30);
+        assert_eq!(history.best_focus_hours, vec![9, 10]);
+        assert_eq!(history.recurring_meetings.len(), 1);
+        assert_eq!(history.recurring_meetings[0].start, "10:00");
+        assert_eq!(history.recurring_meetings[0].end, "10:45");
+
+        let week_start = plan_week_start(None, NaiveDate::from_ymd_opt(2025, 10, 9).unwrap());
+        assert_eq!(week_start, NaiveDate::from_ymd_opt(2025, 10, 13).unwrap());
+        let blocks = fallback_plan(&history, week_start);
+        // 周一：会议日只排 1 小时深度工作且避开例会，例会保留
+        assert_eq!(blocks[0].date, "2025-10-13");
+        assert_eq!(blocks[0].kind, PlanBlockKind::DeepWork);
+        assert_eq!(
+            (blocks[0].start.as_str(), blocks[0].end.as_str()),
+            ("09:00", "10:00")
+        );
+        assert_eq!(blocks[1].kind, PlanBlockKind::Meeting);
+        assert_eq!(blocks[1].start, "10:00");
+        // 周二：最专注的 9-11 点
+        assert_eq!(blocks[2].date, "2025-10-14");
+        assert_eq!(
+            (blocks[2].start.as_str(), blocks[2].end.as_str()),
+            ("09:00", "11:00")
+        );
+        assert_eq!(blocks.len(), 3);
+
+        let response = r#"好的：[
+            {"date": "2025-10-14", "start": "09:00", "end": "11:00", "kind": "deep_work", "title": "写代码"},
+            {"date": "2025-10-20", "start": "09:00", "end": "10:00", "kind": "meeting", "title": "下周的会"},
+            {"date": "2025-10-13", "start": "11:00", "end": "10:00", "kind": "shallow", "title": "倒序"}
+        ]"#;
+        let parsed = parse_blocks(response, week_start).unwrap();
+        assert_eq!(parsed.len(), 1);
+
+        let ics = to_ics(&parsed, week_start.and_hms_opt(8, 0, 0).unwrap());
+        assert!(ics.contains("DTSTART:20251014T090000\r\n"));
+        assert!(ics.contains("SUMMARY:写代码\r\n"));
+    }
+}
@@ -0,0 +1,319 @@
+// 重复流程识别 - 从视频分段描述中找出每天反复手动执行的操作序列
+//
+// 每个分段描述按动作词表（导出、重命名、发送邮件等）提取动作，结合所在卡片的主要应用得到步骤
+// （如“excel 导出”），同一天内按时间排成步骤序列，相邻重复的步骤合并。
+// 在每天的序列中挖掘长度 2-4 的连续子序列，统计出现的天数；一个月内至少 4 天出现的视为重复流程，
+// 被更长且出现天数相同的流程包含的短序列不再单独列出。
+// 结果写入月度总结，并按流程中的动作给出自动化建议
+
+use super::metrics::parse_series_time;
+use crate::storage::{Database, TimelineCardRecord};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// 至少在几天中出现才算重复流程
+const MIN_DAYS: usize = 4;
+/// 流程的最短步骤数
+const MIN_STEPS: usize = 2;
+/// 流程的最长步骤数
+const MAX_STEPS: usize = 4;
+/// 最多返回的流程数
+const MAX_WORKFLOWS: usize = 5;
+
+/// 动作词表：(动作名称, 关键词)，关键词按小写匹配
+const ACTIONS: &[(&str, &[&str])] = &[
+    ("导出", &["导出", "export"]),
+    ("下载", &["下载", "download"]),
+    ("上传", &["上传", "upload"]),
+    ("重命名", &["重命名", "rename"]),
+    ("复制粘贴", &["复制", "粘贴", "copy", "paste"]),
+    ("填写表单", &["填写", "录入", "fill in", "fill out"]),
+    ("发送邮件", &["发送邮件", "发邮件", "邮件", "email"]),
+    ("提交", &["提交", "submit"]),
+    ("登录", &["登录", "log in", "login", "sign in"]),
+    ("打印", &["打印", "print"]),
+    ("整理文件", &["整理文件", "移动文件", "归档", "move file"]),
+];
+
+/// 流程中的一步
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowStep {
+    /// 步骤名称（应用 + 动作）
+    pub label: String,
+    pub minutes: f64,
+}
+
+/// 重复流程
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeatedWorkflow {
+    pub steps: Vec<String>,
+    /// 出现的天数
+    pub days: usize,
+    /// 出现的总次数
+    pub occurrences: usize,
+    /// 平均每次耗时（分钟）
+    pub avg_minutes: i64,
+    /// 最近出现的日期 (YYYY-MM-DD)
+    pub last_seen: String,
+    /// 自动化建议
+    pub suggestion: String,
+}
+
+/// 按出现顺序提取描述中的动作
+pub fn extract_actions(description: &str) -> Vec<&'static str> {
+    let text = description.to_lowercase();
+    let mut found: Vec<(usize, &'static str)> = ACTIONS
+        .iter()
+        .filter_map(|(action, keywords)| {
+            keywords
+                .iter()
+                .filter_map(|keyword| text.find(keyword))
+                .min()
+                .map(|position| (position, *action))
+        })
+        .collect();
+    found.sort_by_key(|(position, _)| *position);
+    found.into_iter().map(|(_, action)| action).collect()
+}
+
+/// 根据流程中的动作给出自动化建议
+fn suggestion(steps: &[String], days: usize, avg_minutes: i64) -> String {
+    let has = |action: &str| steps.iter().any(|step| step.ends_with(action));
+    let hint = if has("发送邮件") && (has("导出") || has("下载")) {
+        "可用脚本或自动化工具（如快捷指令、Power Automate）把导出和发送串起来，定时自动执行"
+    } else if has("发送邮件") {
+        "可用邮件模板或定时发送规则代替手动发送"
+    } else if has("重命名") || has("整理文件") {
+        "可用批量重命名或文件夹监控规则自动处理文件"
+    } else if has("填写表单") || has("复制粘贴") {
+        "可用表单自动填充或文本扩展工具减少重复录入"
+    } else if has("登录") {
+        "可用密码管理器自动填充或保持登录状态"
+    } else {
+        "可整理成脚本或检查清单，减少每次手动操作"
+    };
+    format!(
+        "最近有 {} 天重复这一流程（每次约 {} 分钟），{}",
+        days, avg_minutes, hint
+    )
+}
+
+/// 在每天的步骤序列中挖掘重复流程
+pub fn mine_workflows(days: &[(NaiveDate, Vec<WorkflowStep>)]) -> Vec<RepeatedWorkflow> {
+    // 步骤序列 -> (出现的日期, 出现次数, 总耗时)
+    let mut candidates: HashMap<Vec<String>, (BTreeSet<NaiveDate>, usize, f64)> = HashMap::new();
+    for (date, steps) in days {
+        for length in MIN_STEPS..=MAX_STEPS {
+            for window in steps.windows(length) {
+                let labels: Vec<String> = window.iter().map(|step| step.label.clone()).collect();
+                let entry = candidates.entry(labels).or_default();
+                entry.0.insert(*date);
+                entry.1 += 1;
+                entry.2 += window.iter().map(|step| step.minutes).sum::<f64>();
+            }
+        }
+    }
+    candidates.retain(|_, (dates, _, _)| dates.len() >= MIN_DAYS);
+
+    // 去掉被更长、出现天数相同的流程包含的短序列
+    let keys: Vec<Vec<String>> = candidates.keys().cloned().collect();
+    let subsumed: Vec<Vec<String>> = keys
+        .iter()
+        .filter(|short| {
+            keys.iter().any(|long| {
+                long.len() > short.len()
+                    && candidates[long].0.len() >= candidates[*short].0.len()
+                    && long.windows(short.len()).any(|w| w == short.as_slice())
+            })
+        })
+        .cloned()
+        .collect();
+    for key in subsumed {
+        candidates.remove(&key);
+    }
+
+    let mut workflows: Vec<RepeatedWorkflow> = candidates
+        .into_iter()
+        .map(|(steps, (dates, occurrences, minutes))| {
+            let avg_minutes = (minutes / occurrences.max(1) as f64).round() as i64;
+            RepeatedWorkflow {
+                suggestion: suggestion(&steps, dates.len(), avg_minutes),
+                days: dates.len(),
+                occurrences,
+                avg_minutes,
+                last_seen: dates
+                    .last()
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+                steps,
+            }
+        })
+        .collect();
+    workflows.sort_by(|a, b| {
+        (b.days, b.steps.len(), b.avg_minutes)
+            .cmp(&(a.days, a.steps.len(), a.avg_minutes))
+            .then_with(|| a.steps.cmp(&b.steps))
+    });
+    workflows.truncate(MAX_WORKFLOWS);
+    workflows
+}
+
+/// 卡片的主要应用（小写），没有时使用子类别
+fn card_app(card: &TimelineCardRecord) -> String {
+    serde_json::from_str::<serde_json::Value>(&card.app_sites)
+        .ok()
+        .and_then(|v| {
+            v.get("primary")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+        })
+        .filter(|app| !app.trim().is_empty())
+        .unwrap_or_else(|| card.subcategory.clone())
+        .trim()
+        .to_lowercase()
+}
+
+/// 加载某天按时间排列的步骤序列
+async fn load_day_steps(db: &Database, date: NaiveDate) -> Result<Vec<WorkflowStep>, String> {
+    let mut sessions = db
+        .get_sessions_by_date(&date.format("%Y-%m-%d").to_string())
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    sessions.sort_by_key(|session| session.start_time);
+
+    let mut timed: Vec<(NaiveDateTime, WorkflowStep)> = Vec::new();
+    for session in sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let parse = |value: &str| parse_series_time(value, session.start_time, session.end_time);
+        let cards: Vec<(NaiveDateTime, NaiveDateTime, String)> = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+            .iter()
+            .map(|card| {
+                (
+                    parse(&card.start_time),
+                    parse(&card.end_time),
+                    card_app(card),
+                )
+            })
+            .collect();
+        let segments = db
+            .get_video_segments_by_session(session_id)
+            .await
+            .map_err(|e| format!("获取视频分段失败: {}", e))?;
+
+        for segment in segments {
+            let actions = extract_actions(&segment.description);
+            if actions.is_empty() {
+                continue;
+            }
+            let start = parse(&segment.start_timestamp);
+            let end = parse(&segment.end_timestamp).max(start);
+            let app = cards
+                .iter()
+                .find(|(card_start, card_end, _)| *card_start <= start && start < *card_end)
+                .map(|(_, _, app)| app.clone())
+                .unwrap_or_default();
+            let minutes = (end - start).num_seconds() as f64 / 60.0 / actions.len() as f64;
+            for (index, action) in actions.iter().enumerate() {
+                let label = if app.is_empty() {
+                    action.to_string()
+                } else {
+                    format!("{} {}", app, action)
+                };
+                timed.push((
+                    start + Duration::milliseconds(index as i64),
+                    WorkflowStep { label, minutes },
+                ));
+            }
+        }
+    }
+    timed.sort_by_key(|(time, _)| *time);
+
+    // 相邻的相同步骤合并（同一操作被拆成多个分段）
+    let mut steps: Vec<WorkflowStep> = Vec::new();
+    for (_, step) in timed {
+        match steps.last_mut() {
+            Some(last) if last.label == step.label => last.minutes += step.minutes,
+            _ => steps.push(step),
+        }
+    }
+    Ok(steps)
+}
+
+/// 找出日期范围内（含首尾两天）的重复流程
+pub async fn detect_repeated_workflows(
+    db: &Database,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<RepeatedWorkflow>, String> {
+    let mut days = Vec::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let steps = load_day_steps(db, date).await?;
+        if steps.len() >= MIN_STEPS {
+            days.push((date, steps));
+        }
+    }
+    Ok(mine_workflows(&days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(label: &str) -> WorkflowStep {
+        WorkflowStep {
+            label: label.to_string(),
+            minutes: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_mines_daily_export_rename_email_workflow() {
+        assert_eq!(
+            extract_actions("在 Excel 中导出销售报表，重命名后通过邮件发送给主管"),
+            vec!["导出", "重命名", "发送邮件"]
+        );
+        assert!(extract_actions("阅读技术文档").is_empty());
+
+        let start = NaiveDate::from_ymd_opt(2025, 10, 1).unwrap();
+        let mut days = Vec::new();
+        for offset in 0..5 {
+            let mut steps = vec![step("vscode 提交")];
+            // 第 5 天只做了前两步
+            steps.extend([step("excel 导出"), step("finder 重命名")]);
+            if offset < 4 {
+                steps.push(step("outlook 发送邮件"));
+            }
+            days.push((start + Duration::days(offset), steps));
+        }
+
+        let workflows = mine_workflows(&days);
+        // 5 天都有 “提交 → 导出 → 重命名”；4 天完整的 4 步流程
+        assert_eq!(
+            workflows[0].steps,
+            vec!["vscode 提交", "excel 导出", "finder 重命名"]
+        );
+        assert_eq!(workflows[0].days, 5);
+        assert_eq!(
+            workflows[1].steps,
+            vec![
+                "vscode 提交",
+                "excel 导出",
+                "finder 重命名",
+                "outlook 发送邮件"
+            ]
+        );
+        assert_eq!(workflows[1].days, 4);
+        assert_eq!(workflows[1].avg_minutes, 8);
+        assert_eq!(workflows[1].last_seen, "2025-10-04");
+        assert!(workflows[1].suggestion.contains("导出和发送"));
+        // 被包含且天数相同的短序列不单独列出
+        assert_eq!(workflows.len(), 2);
+    }
+}
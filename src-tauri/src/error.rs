@@ -0,0 +1,198 @@
+// 命令错误 - Tauri 命令统一返回的错误类型
+//
+// 前端收到 { code, message, detail }：code 区分错误种类（数据库未就绪、未配置 API Key、视频缺失等），
+// message 面向用户（按界面语言翻译，见 i18n），detail 为原始错误信息，便于排查。
+// 领域层仍返回 String / anyhow 错误，转换时按错误信息归类，命令中可以直接使用 `?`
+
+use crate::i18n::{self, Language, Message};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// 命令错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    /// 数据库尚未就绪或初始化失败
+    DatabaseNotReady(String),
+    /// 未配置模型服务商或 API Key
+    MissingApiKey(String),
+    /// 会话视频不存在或尚未生成
+    VideoMissing(String),
+    /// 视频过短，无法分析
+    VideoTooShort(String),
+    /// 要操作的记录不存在
+    NotFound(String),
+    /// 参数不合法
+    InvalidInput(String),
+    /// 只读模式下不能写入
+    ReadOnly(String),
+    /// 危险操作缺少有效的确认令牌
+    ConfirmationRequired(String),
+    /// 其他错误
+    Internal(String),
+}
+
+impl AppError {
+    /// 按错误信息归类（领域层的错误信息均为中文描述）
+    pub fn classify(message: String) -> Self {
+        if message.contains("只读模式") {
+            Self::ReadOnly(message)
+        } else if message.contains("确认令牌") {
+            Self::ConfirmationRequired(message)
+        } else if message.starts_with("数据库初始化") || message.contains("数据库未初始化")
+        {
+            Self::DatabaseNotReady(message)
+        } else if is_missing_api_key(&message) {
+            Self::MissingApiKey(message)
+        } else if message.contains("VIDEO_TOO_SHORT") {
+            Self::VideoTooShort(message)
+        } else if message.contains("视频")
+            && (message.contains("不存在") || message.contains("没有"))
+        {
+            Self::VideoMissing(message)
+        } else if message.contains("不存在") {
+            Self::NotFound(message)
+        } else if message.starts_with("无效的")
+            || message.contains("格式错误")
+            || message.contains("不能为空")
+        {
+            Self::InvalidInput(message)
+        } else {
+            Self::Internal(message)
+        }
+    }
+
+    /// 错误代码（前端按它区分处理）
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DatabaseNotReady(_) => "database_not_ready",
+            Self::MissingApiKey(_) => "missing_api_key",
+            Self::VideoMissing(_) => "video_missing",
+            Self::VideoTooShort(_) => "video_too_short",
+            Self::NotFound(_) => "not_found",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::ReadOnly(_) => "read_only",
+            Self::ConfirmationRequired(_) => "confirmation_required",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// 原始错误信息（面向开发者）
+    pub fn detail(&self) -> &str {
+        match self {
+            Self::DatabaseNotReady(detail)
+            | Self::MissingApiKey(detail)
+            | Self::VideoMissing(detail)
+            | Self::VideoTooShort(detail)
+            | Self::NotFound(detail)
+            | Self::InvalidInput(detail)
+            | Self::ReadOnly(detail)
+            | Self::ConfirmationRequired(detail)
+            | Self::Internal(detail) => detail,
+        }
+    }
+
+    /// 面向用户的提示（当前界面语言），有固定处理方式的错误给出统一提示，其余沿用原始信息
+    pub fn user_message(&self) -> String {
+        self.localized_message(i18n::current())
+    }
+
+    /// 指定语言的面向用户提示
+    pub fn localized_message(&self, language: Language) -> String {
+        let message = match self {
+            Self::DatabaseNotReady(_) => Message::DatabaseNotReady,
+            Self::MissingApiKey(_) => Message::MissingApiKey,
+            Self::VideoMissing(_) => Message::VideoMissing,
+            Self::VideoTooShort(_) => Message::VideoTooShort,
+            Self::ReadOnly(_) => Message::ReadOnly,
+            Self::ConfirmationRequired(_) => Message::ConfirmationRequired,
+            Self::NotFound(detail) | Self::InvalidInput(detail) | Self::Internal(detail) => {
+                return i18n::translate(detail, language);
+            }
+        };
+        i18n::text(message, language).to_string()
+    }
+}
+
+/// 未配置服务商或 API Key 的错误信息
+fn is_missing_api_key(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    let mentions_key = lower.contains("api key") || lower.contains("api_key");
+    let missing = ["未配置", "不能为空", "请先"]
+        .iter()
+        .any(|hint| message.contains(hint));
+    (mentions_key && missing) || message.contains("LLM 未配置") || message.contains("服务商未配置")
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.detail())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::classify(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::classify(message.to_string())
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::classify(error.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.user_message())?;
+        state.serialize_field("detail", self.detail())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_messages_and_serializes_code() {
+        let cases = [
+            ("数据库初始化超时（30秒）", "database_not_ready"),
+            ("Qwen API key未配置，请先配置 API key", "missing_api_key"),
+            ("通义千问 API Key 不能为空", "missing_api_key"),
+            ("LLM 未配置", "missing_api_key"),
+            ("该会话没有生成视频", "video_missing"),
+            ("视频文件不存在: /tmp/a.mp4", "video_missing"),
+            ("VIDEO_TOO_SHORT: 视频时长不足", "video_too_short"),
+            ("会话不存在: 3", "not_found"),
+            ("无效的会话 ID: -1", "invalid_input"),
+            (
+                "当前处于只读模式，无法执行该操作：另一实例正在运行",
+                "read_only",
+            ),
+            (
+                "删除会话需要确认令牌，请先调用 request_confirmation",
+                "confirmation_required",
+            ),
+            ("网络请求失败", "internal"),
+        ];
+        for (message, code) in cases {
+            assert_eq!(AppError::from(message).code(), code, "{}", message);
+        }
+
+        let value = serde_json::to_value(AppError::from("该会话没有生成视频")).unwrap();
+        assert_eq!(value["code"], "video_missing");
+        assert_eq!(value["message"], "会话视频不存在或尚未生成");
+        assert_eq!(value["detail"], "该会话没有生成视频");
+    }
+}
@@ -2,11 +2,21 @@
 //
 // 实现发布/订阅模式,消除模块间的直接依赖关系
 // 使用 tokio::sync::broadcast 实现高效的事件分发
+//
+// 事件按主题分组（capture/analysis/video/storage/system）。subscribe() 接收所有事件；
+// subscribe_topics() 为订阅者创建独立的通道，只投递所订阅主题的事件，
+// 高频的截屏事件不会唤醒只关心分析结果的订阅者，一个订阅者处理过慢也不会挤掉其他订阅者的事件。
+// 按主题统计发布数量和订阅者落后跳过的事件数（多主题订阅落后时计入其订阅的每个主题）
 
+use crate::domains::capture_pauses::PauseReason;
 use crate::llm::SessionSummary;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 
 /// 应用事件枚举 - 定义所有可能的系统事件
 #[derive(Debug, Clone)]
@@ -19,7 +29,7 @@ pub enum AppEvent {
         timestamp: DateTime<Utc>,
     },
 
-    /// 会话结束事件
+    /// 会话结束事件（session_id 为时间窗口的起始毫秒时间戳，会话此时尚未写入数据库）
     SessionCompleted {
         session_id: i64,
         frame_count: usize,
@@ -27,6 +37,18 @@ pub enum AppEvent {
         window_end: DateTime<Utc>,
     },
 
+    /// 截屏暂停事件（锁屏、手动暂停）
+    CapturePaused {
+        reason: PauseReason,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// 截屏恢复事件
+    CaptureResumed {
+        reason: PauseReason,
+        timestamp: DateTime<Utc>,
+    },
+
     // --- 分析事件 ---
     /// 分析开始事件
     AnalysisStarted { session_id: i64 },
@@ -40,6 +62,9 @@ pub enum AppEvent {
     /// 分析失败事件
     AnalysisFailed { session_id: i64, error: String },
 
+    /// 每日总结生成事件（不含读取缓存）
+    DaySummaryReady { date: String, summary_text: String },
+
     // --- 视频事件 ---
     /// 视频生成开始事件
     VideoGenerationStarted { session_id: i64 },
@@ -67,12 +92,137 @@ pub enum AppEvent {
     },
 }
 
+/// 事件主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    /// 截屏和会话切分
+    Capture,
+    /// 会话分析和每日总结
+    Analysis,
+    /// 视频生成
+    Video,
+    /// 存储清理
+    Storage,
+    /// 配置等系统事件
+    System,
+}
+
+impl EventTopic {
+    pub const ALL: [EventTopic; 5] = [
+        Self::Capture,
+        Self::Analysis,
+        Self::Video,
+        Self::Storage,
+        Self::System,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Capture => "capture",
+            Self::Analysis => "analysis",
+            Self::Video => "video",
+            Self::Storage => "storage",
+            Self::System => "system",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl AppEvent {
+    /// 事件所属的主题
+    pub fn topic(&self) -> EventTopic {
+        match self {
+            Self::ScreenshotCaptured { .. }
+            | Self::SessionCompleted { .. }
+            | Self::CapturePaused { .. }
+            | Self::CaptureResumed { .. } => EventTopic::Capture,
+            Self::AnalysisStarted { .. }
+            | Self::AnalysisCompleted { .. }
+            | Self::AnalysisFailed { .. }
+            | Self::DaySummaryReady { .. } => EventTopic::Analysis,
+            Self::VideoGenerationStarted { .. }
+            | Self::VideoGenerated { .. }
+            | Self::VideoGenerationFailed { .. } => EventTopic::Video,
+            Self::StorageCleanupStarted | Self::StorageCleaned { .. } => EventTopic::Storage,
+            Self::ConfigUpdated { .. } => EventTopic::System,
+        }
+    }
+}
+
+/// 单个主题的计数
+#[derive(Default)]
+struct TopicCounters {
+    published: AtomicU64,
+    lagged: AtomicU64,
+}
+
+/// 单个主题的统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicStats {
+    pub topic: EventTopic,
+    /// 已发布的事件数
+    pub published: u64,
+    /// 订阅者因处理过慢跳过的事件数
+    pub lagged: u64,
+    /// 当前按主题订阅的订阅者数
+    pub subscribers: usize,
+}
+
+/// 按主题订阅的订阅者
+struct TopicSubscriber {
+    topics: Vec<EventTopic>,
+    sender: broadcast::Sender<AppEvent>,
+}
+
+/// 按主题的订阅句柄
+///
+/// recv() 在订阅者落后时记录跳过的事件数并继续接收，事件总线关闭时返回 None
+pub struct Subscription {
+    name: &'static str,
+    topics: Vec<EventTopic>,
+    receiver: broadcast::Receiver<AppEvent>,
+    counters: Arc<[TopicCounters; 5]>,
+}
+
+impl Subscription {
+    /// 接收下一个事件
+    pub async fn recv(&mut self) -> Option<AppEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("{}落后，跳过 {} 个事件", self.name, skipped);
+                    for topic in &self.topics {
+                        self.counters[topic.index()]
+                            .lagged
+                            .fetch_add(skipped, Ordering::Relaxed);
+                    }
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// 订阅的主题
+    pub fn topics(&self) -> &[EventTopic] {
+        &self.topics
+    }
+}
+
 /// 事件总线 - 用于模块间解耦通信
 ///
 /// 使用 broadcast channel 实现发布/订阅模式
 /// 支持多个订阅者同时接收事件
 pub struct EventBus {
     sender: broadcast::Sender<AppEvent>,
+    capacity: AtomicUsize,
+    topic_subscribers: Mutex<Vec<TopicSubscriber>>,
+    counters: Arc<[TopicCounters; 5]>,
 }
 
 impl EventBus {
@@ -82,13 +232,35 @@ impl EventBus {
     /// - `capacity`: 事件缓冲区大小,建议 100-1000
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        Self {
+            sender,
+            capacity: AtomicUsize::new(capacity),
+            topic_subscribers: Mutex::new(Vec::new()),
+            counters: Arc::new(Default::default()),
+        }
     }
 
     /// 发布事件
     ///
     /// 如果没有订阅者,事件会被丢弃(这是正常的)
     pub fn publish(&self, event: AppEvent) {
+        let topic = event.topic();
+        self.counters[topic.index()]
+            .published
+            .fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut subscribers) = self.topic_subscribers.lock() {
+            // 订阅句柄已释放的订阅者在这里移除
+            subscribers.retain(|subscriber| {
+                if subscriber.sender.receiver_count() == 0 {
+                    return false;
+                }
+                if subscriber.topics.contains(&topic) {
+                    let _ = subscriber.sender.send(event.clone());
+                }
+                true
+            });
+        }
+
         match self.sender.send(event) {
             Ok(receiver_count) => {
                 tracing::trace!("事件已发布，订阅者数量: {}", receiver_count);
@@ -107,9 +279,71 @@ impl EventBus {
         self.sender.subscribe()
     }
 
+    /// 按主题订阅，只接收所订阅主题的事件
+    ///
+    /// `name` 用于日志中标识订阅者
+    pub fn subscribe_topics(&self, name: &'static str, topics: &[EventTopic]) -> Subscription {
+        let (sender, receiver) = broadcast::channel(self.capacity.load(Ordering::Relaxed));
+        if let Ok(mut subscribers) = self.topic_subscribers.lock() {
+            subscribers.push(TopicSubscriber {
+                topics: topics.to_vec(),
+                sender,
+            });
+        }
+        Subscription {
+            name,
+            topics: topics.to_vec(),
+            receiver,
+            counters: self.counters.clone(),
+        }
+    }
+
+    /// 调整之后创建的订阅通道的容量（全局通道的容量在创建后不能修改）
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+    }
+
     /// 获取当前订阅者数量
     pub fn subscriber_count(&self) -> usize {
         self.sender.receiver_count()
+            + self
+                .topic_subscribers
+                .lock()
+                .map(|subscribers| {
+                    subscribers
+                        .iter()
+                        .map(|subscriber| subscriber.sender.receiver_count())
+                        .sum::<usize>()
+                })
+                .unwrap_or(0)
+    }
+
+    /// 各主题的发布数、落后跳过数和订阅者数
+    pub fn topic_stats(&self) -> Vec<TopicStats> {
+        let subscribers = self.topic_subscribers.lock();
+        EventTopic::ALL
+            .iter()
+            .map(|topic| {
+                let counters = &self.counters[topic.index()];
+                TopicStats {
+                    topic: *topic,
+                    published: counters.published.load(Ordering::Relaxed),
+                    lagged: counters.lagged.load(Ordering::Relaxed),
+                    subscribers: subscribers
+                        .as_ref()
+                        .map(|subscribers| {
+                            subscribers
+                                .iter()
+                                .filter(|subscriber| {
+                                    subscriber.topics.contains(topic)
+                                        && subscriber.sender.receiver_count() > 0
+                                })
+                                .count()
+                        })
+                        .unwrap_or(0),
+                }
+            })
+            .collect()
     }
 }
 
@@ -156,4 +390,44 @@ mod tests {
         assert!(receiver1.try_recv().is_ok());
         assert!(receiver2.try_recv().is_ok());
     }
+
+    #[tokio::test]
+    async fn test_topic_subscription_filters_and_counts_lag() {
+        let bus = EventBus::new(2);
+        let mut analysis = bus.subscribe_topics("分析订阅", &[EventTopic::Analysis]);
+        let capture = bus.subscribe_topics("截屏订阅", &[EventTopic::Capture]);
+
+        for _ in 0..3 {
+            bus.publish(AppEvent::CapturePaused {
+                reason: PauseReason::User,
+                timestamp: crate::storage::local_now(),
+            });
+        }
+        bus.publish(AppEvent::AnalysisStarted { session_id: 7 });
+
+        // 分析订阅者只收到分析事件
+        match analysis.recv().await {
+            Some(AppEvent::AnalysisStarted { session_id }) => assert_eq!(session_id, 7),
+            other => panic!("未收到预期事件: {:?}", other),
+        }
+
+        // 截屏订阅者容量为 2，落后 1 个事件
+        let mut capture = capture;
+        assert!(matches!(
+            capture.recv().await,
+            Some(AppEvent::CapturePaused { .. })
+        ));
+        let stats = bus.topic_stats();
+        assert_eq!(stats[0].topic, EventTopic::Capture);
+        assert_eq!(stats[0].published, 3);
+        assert_eq!(stats[0].lagged, 1);
+        assert_eq!(stats[1].published, 1);
+        assert_eq!(stats[1].lagged, 0);
+        assert_eq!(stats[1].subscribers, 1);
+
+        // 订阅句柄释放后不再投递
+        drop(analysis);
+        bus.publish(AppEvent::AnalysisStarted { session_id: 8 });
+        assert_eq!(bus.topic_stats()[1].subscribers, 0);
+    }
 }
@@ -0,0 +1,142 @@
+// 无界面运行模式 - 适用于只负责录制的工作站/信息亭
+//
+// 以 `screen-analyzer --headless`（或环境变量 SCREEN_ANALYZER_HEADLESS=1）启动时：
+// - 不创建主窗口，截屏、视频生成、分析和清理任务照常运行
+// - 关闭所有窗口不会退出进程
+// - 通过直接编辑 config.json 调整设置，进程定期检测文件变化并重新加载
+// 录制的数据可通过 MariaDB 共享或复制数据目录，在另一台设备上查看；
+// 也可在 live_api 中开启只读网页（web_ui），用同一网络的手机浏览器查看当天记录
+
+use crate::AppState;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
+
+/// 命令行参数
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// 环境变量（值为 1 或 true 时启用）
+pub const HEADLESS_ENV: &str = "SCREEN_ANALYZER_HEADLESS";
+
+/// 配置文件检测间隔
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 是否以无界面模式启动
+pub fn is_headless() -> bool {
+    std::env::args().any(|arg| arg == HEADLESS_FLAG)
+        || std::env::var(HEADLESS_ENV)
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+}
+
+/// 监听配置文件变化，变化后重新加载并应用到运行中的组件
+pub fn start_config_watcher(state: AppState) {
+    tokio::spawn(async move {
+        let settings = state.storage_domain.get_settings().clone();
+        let mut last_modified = modified_time(settings.path()).await;
+        info!("无界面模式：监听配置文件 {:?}", settings.path());
+
+        loop {
+            tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+
+            let modified = modified_time(settings.path()).await;
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match settings.reload().await {
+                Ok(true) => {
+                    info!("检测到配置文件变化，重新加载配置");
+                    apply_settings(&state).await;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("重新加载配置文件失败（保持当前配置）: {}", e),
+            }
+        }
+    });
+}
+
+async fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .ok()
+        .and_then(|meta| meta.modified().ok())
+}
+
+/// 将重新加载的配置应用到运行中的组件
+///
+/// 视频、分析分块、上传等配置在每次分析时读取，无需额外处理；
+/// LLM provider 和密钥只在启动时加载，修改后需要重启进程
+async fn apply_settings(state: &AppState) {
+    let config = state.storage_domain.get_settings().get().await;
+
+    if let Err(e) = state
+        .storage_domain
+        .get_media()
+        .configure(&config.media_store.clone().unwrap_or_default())
+        .await
+    {
+        error!("更新媒体存储设置失败: {}", e);
+    }
+
+    if let Ok(cleaner) = state.storage_domain.get_cleaner().await {
+        if let Err(e) = cleaner.set_retention_days(config.retention_days).await {
+            error!("更新保留天数失败: {}", e);
+        }
+        if let Err(e) = cleaner
+            .set_retention_rules(config.retention_rules.clone().unwrap_or_default())
+            .await
+        {
+            error!("更新类别保留规则失败: {}", e);
+        }
+        cleaner
+            .set_kept_frames_days(config.frame_retention.clone().unwrap_or_default().max_days)
+            .await;
+        if let Err(e) = cleaner
+            .set_archive_settings(config.archive_settings.clone().unwrap_or_default())
+            .await
+        {
+            error!("更新冷存储归档设置失败: {}", e);
+        }
+    }
+
+    if let Some(capture_settings) = config.capture_settings {
+        state
+            .capture_domain
+            .get_capture()
+            .update_settings(capture_settings)
+            .await;
+    }
+
+    if let Some(logger_settings) = config.logger_settings {
+        state
+            .system_domain
+            .get_logger()
+            .set_enabled(logger_settings.enable_frontend_logging);
+    }
+
+    if let Some(archive_settings) = config.llm_debug_archive {
+        crate::llm::debug_archive::update_settings(archive_settings);
+    }
+
+    if let Some(performance) = config.performance {
+        match crate::domains::performance::validate(&performance) {
+            Ok(()) => crate::domains::performance::apply(&performance, Some(&state.event_bus)),
+            Err(e) => error!("性能设置无效，保留当前设置: {}", e),
+        }
+    }
+
+    if let Err(e) = state
+        .system_domain
+        .get_live_server()
+        .apply(
+            &config.live_api.clone().unwrap_or_default(),
+            &state.storage_domain,
+        )
+        .await
+    {
+        error!("更新实时状态服务失败: {}", e);
+    }
+
+    info!("配置已应用（LLM provider 和密钥的修改需重启后生效）");
+}
@@ -0,0 +1,211 @@
+// 界面语言 - 按 UISettings.language 本地化命令返回给前端的提示
+//
+// 后端的错误和日志统一使用中文书写，只有返回给前端的错误提示（AppError 的 message）按界面语言翻译：
+// 有固定提示的错误种类使用消息目录中的整句，其余错误按短语目录逐段替换，未收录的部分保留原文。
+// detail 始终为原始信息，日志不翻译。界面语言在加载和保存配置时更新
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese,
+    English,
+}
+
+impl Language {
+    /// 按语言代码解析（en、en-US 等为英文，其余为中文）
+    pub fn parse(code: &str) -> Self {
+        let code = code.trim().to_lowercase();
+        if code == "en" || code.starts_with("en-") || code.starts_with("en_") {
+            Self::English
+        } else {
+            Self::Chinese
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// 设置当前界面语言
+pub fn set_language(code: &str) {
+    let value = match Language::parse(code) {
+        Language::Chinese => 0,
+        Language::English => 1,
+    };
+    LANGUAGE.store(value, Ordering::Relaxed);
+}
+
+/// 当前界面语言
+pub fn current() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::English,
+        _ => Language::Chinese,
+    }
+}
+
+/// 有固定提示的消息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    DatabaseNotReady,
+    MissingApiKey,
+    VideoMissing,
+    VideoTooShort,
+    ReadOnly,
+    ConfirmationRequired,
+}
+
+/// 消息目录
+pub fn text(message: Message, language: Language) -> &'static str {
+    match (message, language) {
+        (Message::DatabaseNotReady, Language::Chinese) => "数据库尚未就绪，请稍后重试",
+        (Message::DatabaseNotReady, Language::English) => {
+            "The database is not ready yet, please try again later"
+        }
+        (Message::MissingApiKey, Language::Chinese) => {
+            "尚未配置模型服务的 API Key，请先在设置中填写"
+        }
+        (Message::MissingApiKey, Language::English) => {
+            "No API key is configured for the model provider, please enter one in Settings"
+        }
+        (Message::VideoMissing, Language::Chinese) => "会话视频不存在或尚未生成",
+        (Message::VideoMissing, Language::English) => {
+            "The session video does not exist or has not been generated yet"
+        }
+        (Message::VideoTooShort, Language::Chinese) => "会话时长过短，无法进行分析",
+        (Message::VideoTooShort, Language::English) => "The session is too short to analyze",
+        (Message::ReadOnly, Language::Chinese) => "当前为只读模式，无法执行该操作",
+        (Message::ReadOnly, Language::English) => {
+            "The app is in read-only mode and cannot perform this action"
+        }
+        (Message::ConfirmationRequired, Language::Chinese) => "该操作需要重新确认后才能执行",
+        (Message::ConfirmationRequired, Language::English) => {
+            "This action must be confirmed again before it can run"
+        }
+    }
+}
+
+/// 常见短语（中文 → 英文），翻译时长的短语优先
+const PHRASES: &[(&str, &str)] = &[
+    ("获取会话详情失败", "Failed to load session details"),
+    ("获取会话视频失败", "Failed to load session videos"),
+    ("获取会话失败", "Failed to load sessions"),
+    ("获取时间线卡片失败", "Failed to load timeline cards"),
+    ("获取视频分段失败", "Failed to load video segments"),
+    ("获取屏幕文字失败", "Failed to load screen text"),
+    ("获取书签失败", "Failed to load bookmarks"),
+    ("获取应用目录失败", "Failed to locate the app directory"),
+    ("更新会话信息失败", "Failed to update the session"),
+    ("删除会话失败", "Failed to delete the session"),
+    ("保存配置失败", "Failed to save settings"),
+    ("保存时间线卡片失败", "Failed to save timeline cards"),
+    ("保存视频分段失败", "Failed to save video segments"),
+    ("数据库初始化失败", "Database initialization failed"),
+    ("数据库初始化超时", "Database initialization timed out"),
+    ("创建目录失败", "Failed to create the directory"),
+    ("创建导出目录失败", "Failed to create the export directory"),
+    ("读取视频文件失败", "Failed to read the video file"),
+    ("视频分析失败", "Video analysis failed"),
+    ("视频生成失败", "Video generation failed"),
+    ("网络请求失败", "Network request failed"),
+    ("截屏失败", "Screen capture failed"),
+    ("同步失败", "Sync failed"),
+    ("检查新版本失败", "Failed to check for updates"),
+    ("打开数据库失败", "Failed to open the database"),
+    ("卡片不存在", "Card not found"),
+    ("会话不存在", "Session not found"),
+    ("视频分段不存在", "Video segment not found"),
+    ("视频文件不存在", "Video file not found"),
+    ("数据库文件不存在", "Database file not found"),
+    ("文件不存在", "File not found"),
+    ("本地时间不存在", "Local time does not exist"),
+    ("无效的会话 ID", "Invalid session ID"),
+    ("无效的时间", "Invalid time"),
+    ("无效的文件路径", "Invalid file path"),
+    ("日期格式错误", "Invalid date format"),
+    ("时间格式错误", "Invalid time format"),
+    ("（应为 YYYY-MM-DD HH:MM）", " (expected YYYY-MM-DD HH:MM)"),
+    (
+        "结束时间必须晚于开始时间",
+        "The end time must be after the start time",
+    ),
+    (
+        "结束日期不能早于开始日期",
+        "The end date cannot be before the start date",
+    ),
+    ("日期范围不能超过", "The date range cannot exceed"),
+    ("时段不能超过", "The time range cannot exceed"),
+    ("不支持的提供商", "Unsupported provider"),
+    ("不能为空", " cannot be empty"),
+    ("失败", " failed"),
+    (" 天", " days"),
+    ("：", ": "),
+    ("，", ", "),
+    ("。", ". "),
+    ("（", " ("),
+    ("）", ")"),
+];
+
+/// 按长度从长到短排列的短语目录
+fn phrases() -> &'static [(&'static str, &'static str)] {
+    static SORTED: OnceLock<Vec<(&str, &str)>> = OnceLock::new();
+    SORTED.get_or_init(|| {
+        let mut sorted = PHRASES.to_vec();
+        sorted.sort_by_key(|(zh, _)| std::cmp::Reverse(zh.chars().count()));
+        sorted
+    })
+}
+
+/// 把中文提示翻译为指定语言，目录中没有的部分保留原文
+pub fn translate(message: &str, language: Language) -> String {
+    match language {
+        Language::Chinese => message.to_string(),
+        Language::English => {
+            let mut translated = message.to_string();
+            for (zh, en) in phrases() {
+                if translated.contains(zh) {
+                    translated = translated.replace(zh, en);
+                }
+            }
+            translated.trim().to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translates_messages_for_english_ui() {
+        assert_eq!(Language::parse("en-US"), Language::English);
+        assert_eq!(Language::parse("zh-CN"), Language::Chinese);
+        assert_eq!(Language::parse(""), Language::Chinese);
+
+        assert_eq!(
+            text(Message::ReadOnly, Language::English),
+            "The app is in read-only mode and cannot perform this action"
+        );
+        assert_eq!(
+            translate("获取会话失败: database is locked", Language::English),
+            "Failed to load sessions: database is locked"
+        );
+        assert_eq!(
+            translate(
+                "时间格式错误: 9点（应为 YYYY-MM-DD HH:MM）",
+                Language::English
+            ),
+            "Invalid time format: 9点 (expected YYYY-MM-DD HH:MM)"
+        );
+        assert_eq!(
+            translate("日期范围不能超过 366 天", Language::English),
+            "The date range cannot exceed 366 days"
+        );
+        // 中文界面保持原文
+        assert_eq!(
+            translate("会话不存在: 3", Language::Chinese),
+            "会话不存在: 3"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+// 多实例保护模块 - 防止多个应用实例同时写入同一份数据
+//
+// 通过应用数据目录下的锁文件（记录持有者 PID）实现实例互斥：
+// - 第一个启动的实例成为主实例，负责截屏、分析和清理
+// - 后续启动的实例检测到锁被占用时，根据配置以只读模式运行或直接退出
+// - 持有者进程已退出（或 PID 已被其他程序复用）的残留锁文件会被自动接管
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 锁文件名
+pub const LOCK_FILE_NAME: &str = "instance.lock";
+
+/// 锁文件为空或内容不完整时的重读次数（持有者可能刚创建文件、还没写完）
+const LOCK_READ_ATTEMPTS: u32 = 5;
+
+/// 重读锁文件的间隔（毫秒）
+const LOCK_READ_INTERVAL_MS: u64 = 100;
+
+/// 锁文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    /// 持有锁的进程ID
+    pub pid: u32,
+    /// 持有锁的可执行文件路径（用于区分开发版与发布版）
+    pub executable: String,
+    /// 获取锁的时间
+    pub acquired_at: String,
+}
+
+/// 实例角色
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum InstanceRole {
+    /// 主实例（持有锁）
+    Primary,
+    /// 次实例（锁已被其他进程持有）
+    Secondary { owner: LockInfo },
+}
+
+/// 实例锁 - 主实例持有期间锁文件存在，Drop 时自动释放
+pub struct InstanceLock {
+    path: PathBuf,
+    role: InstanceRole,
+}
+
+impl InstanceLock {
+    /// 尝试获取实例锁
+    ///
+    /// 锁被其他存活进程持有时不会报错，而是返回 `InstanceRole::Secondary`，
+    /// 由调用方决定以只读模式运行还是退出
+    pub fn acquire(app_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(app_dir)?;
+        let path = app_dir.join(LOCK_FILE_NAME);
+
+        // 最多尝试两次：第一次失败且锁已失效时清理后重试
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let info = LockInfo {
+                        pid: std::process::id(),
+                        executable: std::env::current_exe()
+                            .map(|p| p.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        acquired_at: crate::storage::local_now().to_rfc3339(),
+                    };
+                    file.write_all(serde_json::to_string(&info)?.as_bytes())?;
+                    info!("已获取实例锁: {:?} (PID {})", path, info.pid);
+                    return Ok(Self {
+                        path,
+                        role: InstanceRole::Primary,
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    match read_lock_info(&path) {
+                        Some(owner)
+                            if owner.pid != std::process::id() && is_owner_alive(&owner) =>
+                        {
+                            warn!(
+                                "检测到另一个实例正在运行 (PID {}, {})",
+                                owner.pid, owner.executable
+                            );
+                            return Ok(Self {
+                                path,
+                                role: InstanceRole::Secondary { owner },
+                            });
+                        }
+                        _ => {
+                            warn!("发现残留的实例锁文件，持有进程已退出，正在接管: {:?}", path);
+                            match std::fs::remove_file(&path) {
+                                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                                    return Err(e.into())
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                Err(e) => return Err(anyhow!("创建实例锁文件失败: {}", e)),
+            }
+        }
+
+        Err(anyhow!("无法获取实例锁: {:?}", path))
+    }
+
+    /// 获取当前实例角色
+    pub fn role(&self) -> &InstanceRole {
+        &self.role
+    }
+
+    /// 是否为主实例
+    pub fn is_primary(&self) -> bool {
+        matches!(self.role, InstanceRole::Primary)
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // 只有主实例才删除锁文件，避免次实例误删主实例的锁
+        if self.is_primary() {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                warn!("释放实例锁失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 读取锁文件内容（内容为空或不完整时稍等后重读）
+fn read_lock_info(path: &Path) -> Option<LockInfo> {
+    for attempt in 0..LOCK_READ_ATTEMPTS {
+        if attempt > 0 {
+            std::thread::sleep(std::time::Duration::from_millis(LOCK_READ_INTERVAL_MS));
+        }
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                if let Ok(info) = serde_json::from_str(&content) {
+                    return Some(info);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(_) => {}
+        }
+    }
+    None
+}
+
+/// 检查锁的持有进程是否仍在运行
+///
+/// PID 可能已被其他程序复用，进程存在时还要求可执行文件与锁文件中记录的一致
+fn is_owner_alive(owner: &LockInfo) -> bool {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+    let target = Pid::from_u32(owner.pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[target]),
+        ProcessRefreshKind::new().with_exe(UpdateKind::Always),
+    );
+    let Some(process) = sys.process(target) else {
+        return false;
+    };
+    if owner.executable.is_empty() {
+        return true;
+    }
+    let recorded = Path::new(&owner.executable);
+    if process.exe() == Some(recorded) {
+        return true;
+    }
+    // 取不到路径或程序文件已被替换（升级）时比较进程名，Linux 的进程名最多保留 15 个字符
+    let name = process.name().to_string_lossy();
+    !name.is_empty()
+        && recorded
+            .file_name()
+            .is_some_and(|file_name| file_name.to_string_lossy().starts_with(name.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_info(executable: &str) -> LockInfo {
+        LockInfo {
+            pid: std::process::id(),
+            executable: executable.to_string(),
+            acquired_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_reused_pid_is_not_treated_as_owner() {
+        let current = std::env::current_exe().unwrap();
+        assert!(is_owner_alive(&lock_info(&current.to_string_lossy())));
+        assert!(!is_owner_alive(&lock_info("/nonexistent/other-program")));
+    }
+
+    #[test]
+    fn test_half_written_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(LOCK_FILE_NAME), "{\"pid\":").unwrap();
+
+        let lock = InstanceLock::acquire(dir.path()).unwrap();
+        assert!(lock.is_primary());
+        let owner = read_lock_info(&dir.path().join(LOCK_FILE_NAME)).unwrap();
+        assert_eq!(owner.pid, std::process::id());
+    }
+}
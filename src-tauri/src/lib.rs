@@ -4,25 +4,35 @@
 pub mod actors;
 pub mod capture;
 pub mod domains;
+pub mod error;
 pub mod event_bus;
+pub mod headless;
+pub mod i18n;
+pub mod instance;
+pub mod live_server;
 pub mod llm;
 pub mod logger;
 pub mod models;
 pub mod notion;
+pub mod rag;
 pub mod settings;
 pub mod storage;
 pub mod video;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 // Actor模式不再需要Mutex和RwLock
 // use tokio::sync::{Mutex, RwLock};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 // 导入必要的类型
 use capture::{scheduler::CaptureScheduler, ScreenCapture};
+use domains::confirmation::{ConfirmationToken, DestructiveAction};
+use domains::startup_progress::{StageState, StartupStage};
 use domains::{AnalysisDomain, CaptureDomain, StorageDomain, SystemDomain};
+use error::AppError;
 use event_bus::EventBus;
 use llm::{plugin::LLMProvider, CodexProvider, LLMManager};
 use models::*;
@@ -85,7 +95,7 @@ fn validate_session_id(id: i64) -> Result<(), String> {
 #[tauri::command]
 async fn get_database_status(
     state: tauri::State<'_, AppState>,
-) -> Result<domains::storage::DatabaseStatus, String> {
+) -> Result<domains::storage::DatabaseStatus, AppError> {
     Ok(state.storage_domain.get_db_status().await)
 }
 
@@ -104,11 +114,19 @@ async fn get_activities(
     state: tauri::State<'_, AppState>,
     start_date: String,
     end_date: String,
-) -> Result<Vec<Activity>, String> {
+) -> Result<Vec<Activity>, AppError> {
     let db = state.storage_domain.get_db().await?;
-    db.get_activities(&start_date, &end_date)
+    let activities = db
+        .get_activities(&start_date, &end_date)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    let events = domains::external_events::load_events(&db, &start_date, &end_date).await?;
+    Ok(domains::external_events::merge_into_activities(
+        activities,
+        &events,
+        &start_date,
+        &end_date,
+    ))
 }
 
 /// 获取某天的会话列表
@@ -116,14 +134,14 @@ async fn get_activities(
 async fn get_day_sessions(
     state: tauri::State<'_, AppState>,
     date: String,
-) -> Result<Vec<Session>, String> {
+) -> Result<Vec<Session>, AppError> {
     state
         .storage_domain
         .get_db()
         .await?
         .get_sessions_by_date(&date)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 获取某天的总结数据
@@ -136,13 +154,135 @@ async fn get_day_summary(
     state: tauri::State<'_, AppState>,
     date: String,
     force_refresh: Option<bool>,
-) -> Result<domains::summary::DaySummary, String> {
+) -> Result<domains::summary::DaySummary, AppError> {
+    load_day_summary(&state, &date, force_refresh.unwrap_or(false)).await
+}
+
+/// 读取或生成某天的总结，新生成时发布 DaySummaryReady 事件
+async fn load_day_summary(
+    state: &AppState,
+    date: &str,
+    force_refresh: bool,
+) -> Result<domains::summary::DaySummary, AppError> {
     let db = state.storage_domain.get_db().await?;
+    // 只有新生成的总结才触发事件，读取缓存不算
+    let cached = !force_refresh && matches!(db.get_day_summary(date).await, Ok(Some(_)));
+
     let llm_handle = state.analysis_domain.get_llm_handle();
     let generator = domains::summary::SummaryGenerator::with_llm(db, llm_handle.clone());
-    generator
-        .generate_day_summary(&date, force_refresh.unwrap_or(false))
+    let summary = generator.generate_day_summary(date, force_refresh).await?;
+
+    if !cached && summary.active_device_count > 0 {
+        state
+            .event_bus
+            .publish(event_bus::AppEvent::DaySummaryReady {
+                date: summary.date.clone(),
+                summary_text: summary.summary_text.clone(),
+            });
+    }
+    Ok(summary)
+}
+
+/// 获取日期范围内每天的日期类型（工作日/周末/假期），未标记的日期按星期判断
+#[tauri::command]
+async fn get_day_types(
+    state: tauri::State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<domains::day_types::DayTypeInfo>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::day_types::get_day_types(&db, &start_date, &end_date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 标记日期类型（day_type 为空时清除标记），影响之后生成的每日总结的模板和语气
+#[tauri::command]
+async fn set_day_type(
+    state: tauri::State<'_, AppState>,
+    date: String,
+    day_type: Option<llm::DayType>,
+    note: Option<String>,
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::day_types::set_day_type(&db, &date, day_type, note)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取任意时段（如“今天上午”“下午两点以来”）的统计，跨过边界的卡片按重叠部分计时
+///
+/// # 参数
+/// * `start_ts` - 开始时间（RFC 3339，或本地时间 YYYY-MM-DD HH:MM）
+/// * `end_ts` - 结束时间（格式同上）
+#[tauri::command]
+async fn get_range_summary(
+    state: tauri::State<'_, AppState>,
+    start_ts: String,
+    end_ts: String,
+) -> Result<domains::range_summary::RangeSummary, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::range_summary::get_range_summary(&db, &start_ts, &end_ts)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取任意时段的原始统计和置信度加权统计
+///
+/// 置信度阈值与复核阈值相同，低于阈值和互相矛盾的卡片不计入加权统计
+#[tauri::command]
+async fn get_confidence_stats(
+    state: tauri::State<'_, AppState>,
+    start_ts: String,
+    end_ts: String,
+) -> Result<domains::confidence_stats::ConfidenceStats, AppError> {
+    let threshold = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .review_confidence_threshold
+        .unwrap_or(domains::review::DEFAULT_REVIEW_THRESHOLD);
+    let db = state.storage_domain.get_db().await?;
+    domains::confidence_stats::get_confidence_stats(&db, &start_ts, &end_ts, threshold)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 声明接下来这段时间的意图（结束上一个仍在进行的意图）
+#[tauri::command]
+async fn start_block_intent(
+    state: tauri::State<'_, AppState>,
+    intent: String,
+) -> Result<storage::BlockIntentRecord, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::intents::start_intent(&db, &intent)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 结束当前意图并评估吻合度
+#[tauri::command]
+async fn end_block_intent(state: tauri::State<'_, AppState>) -> Result<u64, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::intents::end_intent(&db)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天的意图及与实际活动的吻合度
+#[tauri::command]
+async fn get_day_intents(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<domains::intents::DayIntentReport, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::intents::day_intents(&db, &date)
         .await
+        .map_err(AppError::from)
 }
 
 /// 获取会话详情
@@ -150,29 +290,115 @@ async fn get_day_summary(
 async fn get_session_detail(
     state: tauri::State<'_, AppState>,
     session_id: i64,
-) -> Result<SessionDetail, String> {
+) -> Result<SessionDetail, AppError> {
     validate_session_id(session_id)?;
-    state
+    let mut detail = state
         .storage_domain
         .get_db()
         .await?
         .get_session_detail(session_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))?;
+
+    // 存储占用统计失败不影响查看详情
+    match state.storage_domain.get_cleaner().await {
+        Ok(cleaner) => match cleaner.get_session_storage(&detail.session).await {
+            Ok(storage) => detail.storage = Some(storage),
+            Err(e) => warn!("统计会话 {} 的存储占用失败: {}", session_id, e),
+        },
+        Err(e) => warn!("统计会话 {} 的存储占用失败: {}", session_id, e),
+    }
+    Ok(detail)
 }
 
 /// 获取应用配置
 #[tauri::command]
-async fn get_app_config(state: tauri::State<'_, AppState>) -> Result<PersistedAppConfig, String> {
+async fn get_app_config(state: tauri::State<'_, AppState>) -> Result<PersistedAppConfig, AppError> {
     Ok(state.storage_domain.get_settings().get().await)
 }
 
+/// 获取后台初始化进度（窗口显示后各组件逐步初始化）
+#[tauri::command]
+async fn get_startup_progress(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::startup_progress::StartupProgress, AppError> {
+    Ok(state.system_domain.get_startup_progress().snapshot())
+}
+
+/// 获取本机设备名（截屏间隔、视频和截屏设置按设备名分别保存）
+#[tauri::command]
+async fn get_device_name(state: tauri::State<'_, AppState>) -> Result<String, AppError> {
+    Ok(state
+        .storage_domain
+        .get_settings()
+        .device_name()
+        .to_string())
+}
+
+/// 清除本机的设备设置覆盖，恢复继承全局默认值
+#[tauri::command]
+async fn reset_device_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<PersistedAppConfig, AppError> {
+    state.system_domain.ensure_writable()?;
+    let config = state
+        .storage_domain
+        .get_settings()
+        .reset_device_settings()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(capture_settings) = config.capture_settings.clone() {
+        state
+            .capture_domain
+            .get_capture()
+            .update_settings(capture_settings)
+            .await;
+    }
+    info!("本机设置已恢复为全局默认值");
+
+    Ok(config)
+}
+
 /// 更新配置
 #[tauri::command]
 async fn update_config(
     state: tauri::State<'_, AppState>,
     config: AppConfig,
-) -> Result<PersistedAppConfig, String> {
+) -> Result<PersistedAppConfig, AppError> {
+    if let Some(performance) = &config.performance {
+        domains::performance::validate(performance)?;
+    }
+    if let Some(zones) = &config.quiet_zones {
+        domains::quiet_zones::validate(zones)?;
+    }
+    // 先校验再保存，避免无效设置写入磁盘后启动时无法应用
+    if let Some(rules) = &config.retention_rules {
+        storage::cleaner::validate_rules(rules).map_err(|e| e.to_string())?;
+    }
+    if let Some(archive_settings) = &config.archive_settings {
+        storage::cleaner::validate_archive_settings(archive_settings).map_err(|e| e.to_string())?;
+    }
+    // 切换媒体存储位置前把本地缓存清理掉的视频拉回，拉不回来时不切换，避免这些视频之后无法回放
+    if let Some(media_store) = &config.media_store {
+        let current = state
+            .storage_domain
+            .get_settings()
+            .get()
+            .await
+            .media_store
+            .unwrap_or_default();
+        if !current.same_target(media_store) {
+            state
+                .storage_domain
+                .get_cleaner()
+                .await?
+                .pull_back_evicted_videos()
+                .await
+                .map_err(|e| format!("无法切换媒体存储后端: {}", e))?;
+        }
+    }
+
     let updated_config = state
         .storage_domain
         .get_settings()
@@ -192,6 +418,76 @@ async fn update_config(
             .map_err(|e| e.to_string())?;
     }
 
+    // 更新截图保留天数
+    if let Some(frame_retention) = &config.frame_retention {
+        state
+            .storage_domain
+            .get_cleaner()
+            .await?
+            .set_kept_frames_days(frame_retention.max_days)
+            .await;
+    }
+
+    // 更新类别保留规则
+    if let Some(retention_rules) = config.retention_rules {
+        state
+            .storage_domain
+            .get_cleaner()
+            .await?
+            .set_retention_rules(retention_rules)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // 更新媒体存储后端
+    if let Some(media_store) = config.media_store {
+        state
+            .storage_domain
+            .get_media()
+            .configure(&media_store)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // 更新冷存储归档设置
+    if let Some(archive_settings) = config.archive_settings {
+        state
+            .storage_domain
+            .get_cleaner()
+            .await?
+            .set_archive_settings(archive_settings)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // 本地模式立即对所有 HTTP 客户端生效
+    if let Some(local_only) = config.local_only {
+        domains::local_only::set_enabled(local_only);
+    }
+
+    // 会话对齐方式对之后的会话窗口生效
+    if let Some(alignment) = config.session_alignment {
+        state
+            .capture_domain
+            .get_scheduler()
+            .set_alignment(alignment);
+    }
+    if let Some(adaptive) = config.adaptive_session.clone() {
+        state
+            .capture_domain
+            .get_scheduler()
+            .set_adaptive_session(adaptive);
+    }
+
+    // 配额变化后立即重新计算画质降级状态
+    if config.storage_quota.is_some() {
+        domains::storage_quota::refresh(
+            &*state.storage_domain.get_cleaner().await?,
+            state.storage_domain.get_settings(),
+        )
+        .await;
+    }
+
     // 更新LLM配置（现在只有Qwen）
     if let Some(_llm_provider) = config.llm_provider {
         // 现在只支持Qwen，不需要切换provider
@@ -232,12 +528,39 @@ async fn update_config(
         );
     }
 
+    // 更新 LLM 调试归档配置
+    if let Some(archive_settings) = config.llm_debug_archive {
+        info!(
+            "LLM 调试归档已{}",
+            if archive_settings.enabled {
+                "开启"
+            } else {
+                "关闭"
+            }
+        );
+        llm::debug_archive::update_settings(archive_settings);
+    }
+
+    // 性能设置立即应用到视频编码、分析队列、批量写入和事件总线
+    if let Some(performance) = config.performance {
+        domains::performance::apply(&performance, Some(&state.event_bus));
+    }
+
+    // 按新设置重启实时状态服务
+    if let Some(live_api) = config.live_api {
+        state
+            .system_domain
+            .get_live_server()
+            .apply(&live_api, &state.storage_domain)
+            .await?;
+    }
+
     Ok(updated_config)
 }
 
 /// 读取当前进程中的 Claude 环境变量
 #[tauri::command]
-fn get_anthropic_env() -> Result<serde_json::Value, String> {
+fn get_anthropic_env() -> Result<serde_json::Value, AppError> {
     let auth_token = std::env::var("ANTHROPIC_AUTH_TOKEN")
         .ok()
         .filter(|v| !v.is_empty());
@@ -263,7 +586,8 @@ async fn add_manual_tag(
     state: tauri::State<'_, AppState>,
     session_id: i64,
     tag: ActivityTag,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
     validate_session_id(session_id)?;
     // 获取当前会话
     let session_detail = state
@@ -298,7 +622,8 @@ async fn remove_tag(
     state: tauri::State<'_, AppState>,
     session_id: i64,
     tag_index: usize,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
     validate_session_id(session_id)?;
     // 获取当前会话
     let session_detail = state
@@ -312,7 +637,7 @@ async fn remove_tag(
     // 删除指定索引的标签
     let mut tags = session_detail.tags;
     if tag_index >= tags.len() {
-        return Err("标签索引超出范围".to_string());
+        return Err("标签索引超出范围".into());
     }
     tags.remove(tag_index);
 
@@ -330,9 +655,32 @@ async fn remove_tag(
     Ok(())
 }
 
+/// 按筛选条件批量添加或移除会话标签（先以 dry_run 统计受影响的会话数，确认后在一个事务中写入）
+///
+/// # 参数
+/// * `filter` - 日期范围（必填）、类别、应用、设备
+/// * `remove` - 为 true 时移除相同的标签
+/// * `dry_run` - 为 true 时只统计不写入
+#[tauri::command]
+async fn bulk_tag(
+    state: tauri::State<'_, AppState>,
+    filter: domains::bulk_tags::BulkTagFilter,
+    tag: ActivityTag,
+    remove: bool,
+    dry_run: bool,
+) -> Result<domains::bulk_tags::BulkTagReport, AppError> {
+    if !dry_run {
+        state.system_domain.ensure_writable()?;
+    }
+    let db = state.storage_domain.get_db().await?;
+    domains::bulk_tags::bulk_tag(&db, &filter, &tag, remove, dry_run)
+        .await
+        .map_err(AppError::from)
+}
+
 /// 获取系统状态
 #[tauri::command]
-async fn get_system_status(state: tauri::State<'_, AppState>) -> Result<SystemStatus, String> {
+async fn get_system_status(state: tauri::State<'_, AppState>) -> Result<SystemStatus, AppError> {
     let mut status = state.system_domain.get_status_handle().get().await;
 
     // 获取存储统计信息
@@ -349,32 +697,89 @@ async fn get_system_status(state: tauri::State<'_, AppState>) -> Result<SystemSt
         }
     }
 
+    // 只读模式原因（次实例等）
+    status.read_only_reason = state.system_domain.read_only_reason();
+    status.dropped_frames = state.capture_domain.get_capture().dropped_frames();
+    status.provider_health = state.analysis_domain.get_provider_health().snapshot();
+    status.quality_degrade = domains::storage_quota::current_status();
+    status.database_failover = state
+        .storage_domain
+        .try_get_db()
+        .await
+        .and_then(|db| db.failover_status());
+    status.analysis_backlog = domains::analysis_backlog::current_status();
+    status.llm_quota = state.analysis_domain.quota_status();
+
     Ok(status)
 }
 
 /// 切换截屏状态（暂停/恢复）
 #[tauri::command]
-async fn toggle_capture(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+async fn toggle_capture(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    // 离开模式下恢复截屏即退出离开模式
+    let away = state.storage_domain.get_settings().get().await.away_mode;
+    if enabled && away.map_or(false, |away| away.active) {
+        apply_away_mode(&state, false, false).await?;
+        return Ok(());
+    }
+
     state
         .system_domain
         .get_status_handle()
         .set_capturing(enabled)
         .await;
 
+    if !state.capture_domain.get_scheduler().set_paused(!enabled) {
+        return Ok(());
+    }
+
+    let reason = domains::capture_pauses::PauseReason::User;
+    let timestamp = storage::local_now();
     if enabled {
         info!("恢复截屏");
-        // TODO: 恢复调度器
+        state
+            .event_bus
+            .publish(event_bus::AppEvent::CaptureResumed { reason, timestamp });
     } else {
         info!("暂停截屏");
-        // TODO: 暂停调度器
+        state
+            .event_bus
+            .publish(event_bus::AppEvent::CapturePaused { reason, timestamp });
     }
 
     Ok(())
 }
 
+/// 获取离开模式状态
+#[tauri::command]
+async fn get_away_mode(state: tauri::State<'_, AppState>) -> Result<AwayModeState, AppError> {
+    Ok(state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .away_mode
+        .unwrap_or_default())
+}
+
+/// 进入或退出离开模式（freeze_retention 为 true 时离开期间暂停自动清理）
+#[tauri::command]
+async fn set_away_mode(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+    freeze_retention: Option<bool>,
+) -> Result<AwayModeState, AppError> {
+    state.system_domain.ensure_writable()?;
+    apply_away_mode(&state, enabled, freeze_retention.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
+}
+
 /// 手动触发分析 - 分析video文件夹中未分析的视频
 #[tauri::command]
-async fn trigger_analysis(state: tauri::State<'_, AppState>) -> Result<String, String> {
+async fn trigger_analysis(state: tauri::State<'_, AppState>) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     info!("手动触发分析 - 分析视频文件");
 
     // 已移除 analysis_lock 临时方案，直接执行分析
@@ -400,7 +805,8 @@ async fn trigger_analysis(state: tauri::State<'_, AppState>) -> Result<String, S
 async fn retry_session_analysis(
     state: tauri::State<'_, AppState>,
     session_id: i64,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     validate_session_id(session_id)?;
     info!("重新分析会话: {}", session_id);
 
@@ -416,75 +822,7 @@ async fn retry_session_analysis(
         .set_error(None)
         .await;
 
-    let result = async {
-        let session_detail = state
-            .storage_domain
-            .get_db()
-            .await?
-            .get_session_detail(session_id)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        let video_path = session_detail
-            .session
-            .video_path
-            .clone()
-            .ok_or_else(|| "该会话没有关联视频，无法重新分析".to_string())?;
-
-        let session_start = session_detail.session.start_time;
-        let session_end = session_detail.session.end_time;
-        let diff = session_end.signed_duration_since(session_start);
-        let duration_minutes = if diff.num_seconds() > 0 {
-            ((diff.num_seconds() as f64) / 60.0).ceil() as u32
-        } else {
-            1
-        };
-
-        // 使用 Database 方法删除和更新
-        state
-            .storage_domain
-            .get_db()
-            .await?
-            .delete_video_segments_by_session(session_id)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        state
-            .storage_domain
-            .get_db()
-            .await?
-            .delete_timeline_cards_by_session(session_id)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        state
-            .storage_domain
-            .get_db()
-            .await?
-            .update_session(
-                session_id,
-                &session_detail.session.title,
-                "重新分析中...",
-                None,
-                "[]",
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-
-        let video_path_buf = PathBuf::from(&video_path);
-        let outcome = analyze_video_once(
-            &state,
-            &video_path_buf,
-            session_start,
-            session_end,
-            duration_minutes,
-            Some(session_id),
-        )
-        .await?;
-
-        Ok(outcome)
-    }
-    .await;
+    let result = reanalyze_session(&state, session_id).await;
 
     let last_error = result.as_ref().err().cloned();
 
@@ -509,40 +847,186 @@ async fn retry_session_analysis(
             "重新分析完成: {} ({} 片段, {} 卡片)",
             outcome.summary.title, outcome.segments_count, outcome.timeline_count
         )),
-        Err(err) => Err(err),
+        Err(err) => Err(err.into()),
     }
 }
 
-fn parse_video_window_from_stem(
-    stem: &str,
-) -> Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
-    use chrono::{NaiveDateTime, TimeZone, Utc};
+/// 删除会话已有的分析结果，用关联视频重新分析
+async fn reanalyze_session(
+    state: &AppState,
+    session_id: i64,
+) -> Result<VideoAnalysisOutcome, String> {
+    let session_detail = state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_session_detail(session_id)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    // 处理 segment_YYYYMMDDHHMMSS_YYYYMMDDHHMMSS 格式
-    if stem.starts_with("segment_") {
-        let parts: Vec<&str> = stem
-            .strip_prefix("segment_")?
-            .split('_')
-            .filter(|p| !p.is_empty())
-            .collect();
+    let video_path = session_detail
+        .session
+        .video_path
+        .clone()
+        .ok_or_else(|| "该会话没有关联视频，无法重新分析".to_string())?;
 
-        if parts.len() != 2 {
-            return None;
+    // 使用远程存储后端时本地副本可能已被清理，先拉回（失败时由下面的检查报告）
+    let video_path = match state
+        .storage_domain
+        .get_media()
+        .ensure_local_video(Path::new(&video_path))
+        .await
+    {
+        Ok(local) => local.to_string_lossy().to_string(),
+        Err(e) => {
+            warn!("拉取会话视频失败: {}", e);
+            video_path
         }
+    };
 
-        let start = parts[0];
-        let end = parts[1];
+    // 保留了原始截图时先用截图重新生成视频，重新分析不受旧视频画质的影响
+    rebuild_video_from_kept_frames(state, Path::new(&video_path)).await?;
+
+    // 先确认视频可访问再删除旧的分析结果，避免网络共享断开时丢失数据
+    if !tokio::fs::try_exists(&video_path).await.unwrap_or(false) {
+        let video_dir = std::path::Path::new(&video_path)
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+        return Err(if storage::availability::is_available(&video_dir).await {
+            format!("视频文件不存在: {}", video_path)
+        } else {
+            "视频所在存储不可用（网络共享可能已断开），请重新连接后重试".to_string()
+        });
+    }
+
+    let session_start = session_detail.session.start_time;
+    let session_end = session_detail.session.end_time;
+    let diff = session_end.signed_duration_since(session_start);
+    let duration_minutes = if diff.num_seconds() > 0 {
+        ((diff.num_seconds() as f64) / 60.0).ceil() as u32
+    } else {
+        1
+    };
+
+    // 使用 Database 方法删除和更新
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .delete_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .delete_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .update_session(
+            session_id,
+            &session_detail.session.title,
+            "重新分析中...",
+            None,
+            "[]",
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let video_path_buf = PathBuf::from(&video_path);
+    let outcome = analyze_video_once(
+        state,
+        state.analysis_domain.get_llm_handle(),
+        &video_path_buf,
+        session_start,
+        session_end,
+        duration_minutes,
+        Some(session_id),
+    )
+    .instrument(logger::session_span(session_start))
+    .await?;
+
+    Ok(outcome)
+}
+
+/// 用保留的原始截图（见 frame_retention）重新生成会话视频，没有保留截图时返回 false
+async fn rebuild_video_from_kept_frames(
+    state: &AppState,
+    video_path: &Path,
+) -> Result<bool, String> {
+    let Some(label) = domains::frame_retention::label_for_video(video_path) else {
+        return Ok(false);
+    };
+    let frames_dir = state.capture_domain.get_capture().frames_dir();
+    let frames = domains::frame_retention::kept_frames(&frames_dir, label).await;
+    if frames.is_empty() {
+        return Ok(false);
+    }
+
+    let frame_paths =
+        video::filter_frames_by_interval(frames, FRAME_SAMPLE_INTERVAL_SECONDS as usize);
+    info!(
+        "使用保留的 {} 张截图重新生成视频: {:?}",
+        frame_paths.len(),
+        video_path
+    );
+    let app_config = state.storage_domain.get_settings().get().await;
+    let mut config = video::VideoConfig::default();
+    config.speed_multiplier = app_config.video_config.speed_multiplier;
+    config.quality = app_config.video_config.quality;
+    config.add_timestamp = app_config.video_config.add_timestamp;
+    config.smart_speed = app_config.video_config.smart_speed;
+    config.layout = app_config.video_config.layout;
+    domains::storage_quota::apply_to_video(&mut config);
+
+    let frame_count = frame_paths.len();
+    let processor = state.analysis_domain.get_video_processor();
+    let result = processor
+        .create_summary_video(frame_paths, video_path, &config)
+        .await
+        .map_err(|e| format!("用保留的截图重新生成视频失败: {}", e))?;
+    video::VideoMetadata::new(
+        &config,
+        domains::playback::frame_interval_secs(app_config.capture_interval),
+        frame_count,
+    )
+    .with_speed_runs(result.speed_runs.clone())
+    .save_or_warn(video_path)
+    .await;
+    processor.publish(video_path).await;
+    Ok(true)
+}
+
+fn parse_video_window_from_stem(
+    stem: &str,
+) -> Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+    use chrono::{TimeZone, Utc};
 
-        if start.len() != 12 || end.len() != 12 {
+    // 处理 segment_YYYYMMDDHHMMSS_YYYYMMDDHHMMSS 格式
+    if stem.starts_with("segment_") {
+        let parts: Vec<&str> = stem
+            .strip_prefix("segment_")?
+            .split('_')
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        if parts.len() != 2 {
             return None;
         }
 
-        let start_naive = NaiveDateTime::parse_from_str(start, "%Y%m%d%H%M").ok()?;
-        let end_naive = NaiveDateTime::parse_from_str(end, "%Y%m%d%H%M").ok()?;
+        let start = parts[0];
+        let end = parts[1];
 
         return Some((
-            Utc.from_utc_datetime(&start_naive),
-            Utc.from_utc_datetime(&end_naive),
+            Utc.from_utc_datetime(&parse_window_stamp(start)?),
+            Utc.from_utc_datetime(&parse_window_stamp(end)?),
         ));
     }
 
@@ -559,25 +1043,28 @@ fn parse_video_window_from_stem(
         return None;
     }
 
-    if start.len() != 12 || end.len() != 12 {
-        return None;
-    }
-
-    let start_naive = NaiveDateTime::parse_from_str(start, "%Y%m%d%H%M").ok()?;
-    let end_naive = NaiveDateTime::parse_from_str(end, "%Y%m%d%H%M").ok()?;
-
     Some((
-        Utc.from_utc_datetime(&start_naive),
-        Utc.from_utc_datetime(&end_naive),
+        Utc.from_utc_datetime(&parse_window_stamp(start)?),
+        Utc.from_utc_datetime(&parse_window_stamp(end)?),
     ))
 }
 
+/// 解析视频文件名中的时间：整点对齐的窗口精确到分钟（12 位），滚动窗口精确到秒（14 位）
+fn parse_window_stamp(stamp: &str) -> Option<chrono::NaiveDateTime> {
+    let format = match stamp.len() {
+        12 => "%Y%m%d%H%M",
+        14 => "%Y%m%d%H%M%S",
+        _ => return None,
+    };
+    chrono::NaiveDateTime::parse_from_str(stamp, format).ok()
+}
+
 /// 获取视频文件内容（返回二进制数据）
 #[tauri::command]
 async fn get_video_data(
     state: tauri::State<'_, AppState>,
     session_id: i64,
-) -> Result<Vec<u8>, String> {
+) -> Result<Vec<u8>, AppError> {
     validate_session_id(session_id)?;
     use tokio::fs;
 
@@ -591,13 +1078,15 @@ async fn get_video_data(
         .map_err(|e| e.to_string())?;
 
     if let Some(video_path) = session.session.video_path {
+        let video_path = ensure_local_video(&state, &video_path).await?;
+
         // 读取视频文件
         let data = fs::read(&video_path)
             .await
             .map_err(|e| format!("读取视频文件失败: {}", e))?;
         Ok(data)
     } else {
-        Err("该会话没有生成视频".to_string())
+        Err("该会话没有生成视频".into())
     }
 }
 
@@ -606,7 +1095,7 @@ async fn get_video_data(
 async fn get_video_url(
     state: tauri::State<'_, AppState>,
     session_id: i64,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     validate_session_id(session_id)?;
     // 获取会话详情
     let session = state
@@ -618,10 +1107,13 @@ async fn get_video_url(
         .map_err(|e| e.to_string())?;
 
     if let Some(video_path) = session.session.video_path {
+        // 视频可能在其他设备上录制，返回本机上的路径
+        let video_path = ensure_local_video(&state, &video_path).await?;
+
         // 直接返回文件路径，前端使用convertFileSrc处理
         Ok(video_path)
     } else {
-        Err("该会话没有生成视频".to_string())
+        Err("该会话没有生成视频".into())
     }
 }
 
@@ -631,8 +1123,18 @@ async fn generate_video(
     state: tauri::State<'_, AppState>,
     session_id: i64,
     speed_multiplier: Option<f32>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     validate_session_id(session_id)?;
+    generate_session_video(&state, session_id, speed_multiplier).await
+}
+
+/// 用会话保留的截图生成视频，成功后清理截图
+async fn generate_session_video(
+    state: &AppState,
+    session_id: i64,
+    speed_multiplier: Option<f32>,
+) -> Result<String, AppError> {
     info!("生成会话视频: session_id={}", session_id);
 
     // 获取会话详情
@@ -659,7 +1161,7 @@ async fn generate_video(
         {
             error!("删除空会话失败: {}", e);
         }
-        return Err("该会话没有截图帧，已删除该会话".to_string());
+        return Err("该会话没有截图帧，已删除该会话".into());
     }
 
     // 应用帧过滤：每5秒选择一张图片（假设原始截图是1fps）
@@ -691,6 +1193,8 @@ async fn generate_video(
     let mut config = video::VideoConfig::default();
     config.quality = app_config.video_config.quality;
     config.add_timestamp = app_config.video_config.add_timestamp;
+    config.smart_speed = app_config.video_config.smart_speed;
+    config.layout = app_config.video_config.layout;
 
     if let Some(speed) = speed_multiplier {
         config.speed_multiplier = speed;
@@ -700,12 +1204,34 @@ async fn generate_video(
     }
 
     // 生成视频
+    let frame_count = frame_paths.len();
     let result = state
         .analysis_domain
         .get_video_processor()
         .create_summary_video(frame_paths, &output_path, &config)
         .await
         .map_err(|e| e.to_string())?;
+    video::VideoMetadata::new(
+        &config,
+        domains::playback::frame_interval_secs(app_config.capture_interval),
+        frame_count,
+    )
+    .with_speed_runs(result.speed_runs.clone())
+    .save_or_warn(&output_path)
+    .await;
+    domains::mood_strip::write_for_video(
+        all_frames
+            .iter()
+            .map(|frame| (frame.timestamp, frame.file_path.clone()))
+            .collect(),
+        &output_path,
+    )
+    .await;
+    state
+        .analysis_domain
+        .get_video_processor()
+        .publish(&output_path)
+        .await;
 
     // 更新数据库中的视频路径
     state
@@ -719,6 +1245,16 @@ async fn generate_video(
             e.to_string()
         })?;
 
+    // 删除原始图片前记录截图和视频的大小（压缩统计）
+    let all_frame_paths: Vec<String> = all_frames.iter().map(|f| f.file_path.clone()).collect();
+    domains::storage_usage::record_encoding(
+        &state.storage_domain.get_db().await?,
+        session_detail.session.start_time.date_naive(),
+        &all_frame_paths,
+        &result.file_path,
+    )
+    .await;
+
     // 清理frame文件夹中的图片（视频已生成，不再需要原始图片）
     let mut deleted_count = 0;
     let mut failed_count = 0;
@@ -754,7 +1290,8 @@ async fn generate_video(
 async fn test_generate_videos(
     state: tauri::State<'_, AppState>,
     settings: VideoSettings,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
+    state.system_domain.ensure_writable()?;
     use chrono::{Duration, TimeZone, Timelike, Utc};
     use std::collections::BTreeMap;
 
@@ -833,6 +1370,14 @@ async fn test_generate_videos(
     video_config.speed_multiplier = settings.speed_multiplier;
     video_config.quality = settings.quality;
     video_config.add_timestamp = settings.add_timestamp;
+    video_config.smart_speed = settings.smart_speed;
+    video_config.layout = settings.layout;
+    let capture_interval = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .capture_interval;
 
     let mut generated_videos = Vec::new();
     let mut failed_segments = Vec::new();
@@ -872,6 +1417,7 @@ async fn test_generate_videos(
             filtered_frame_list.len()
         );
 
+        let frame_count = filtered_frame_list.len();
         match state
             .analysis_domain
             .get_video_processor()
@@ -880,6 +1426,14 @@ async fn test_generate_videos(
         {
             Ok(result) => {
                 info!("视频生成成功: {}", result.file_path);
+                video::VideoMetadata::new(
+                    &video_config,
+                    domains::playback::frame_interval_secs(capture_interval),
+                    frame_count,
+                )
+                .with_speed_runs(result.speed_runs.clone())
+                .save_or_warn(&output_path)
+                .await;
 
                 // 删除已使用的帧
                 let mut deleted_count = 0;
@@ -913,7 +1467,7 @@ async fn test_generate_videos(
             .map(|(name, err)| format!("{}: {}", name, err))
             .collect::<Vec<_>>()
             .join("; ");
-        return Err(format!("所有视频段生成失败: {}", error_summary));
+        return Err(format!("所有视频段生成失败: {}", error_summary).into());
     }
 
     // 如果有部分成功，记录失败的段但返回成功
@@ -932,9 +1486,27 @@ async fn test_generate_videos(
     Ok(generated_videos)
 }
 
+/// 申请危险操作的确认令牌（删除会话、清理存储、时区迁移需要先确认）
+#[tauri::command]
+fn request_confirmation(
+    state: tauri::State<'_, AppState>,
+    action: DestructiveAction,
+) -> ConfirmationToken {
+    info!("签发确认令牌: {}", action.label());
+    state.system_domain.get_confirmations().issue(action)
+}
+
 /// 清理存储
 #[tauri::command]
-async fn cleanup_storage(state: tauri::State<'_, AppState>) -> Result<(), String> {
+async fn cleanup_storage(
+    state: tauri::State<'_, AppState>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    state.system_domain.get_confirmations().consume(
+        DestructiveAction::CleanupStorage,
+        confirmation_token.as_deref(),
+    )?;
     info!("手动触发存储清理");
     state
         .storage_domain
@@ -942,26 +1514,66 @@ async fn cleanup_storage(state: tauri::State<'_, AppState>) -> Result<(), String
         .await?
         .trigger_cleanup()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?
+        .publish(&state.event_bus);
+    Ok(())
 }
 
 /// 获取存储统计
 #[tauri::command]
 async fn get_storage_stats(
     state: tauri::State<'_, AppState>,
-) -> Result<storage::cleaner::StorageStats, String> {
+) -> Result<storage::cleaner::StorageStats, AppError> {
     state
         .storage_domain
         .get_cleaner()
         .await?
         .get_storage_stats()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 获取按日期和类型拆分的存储占用
+#[tauri::command]
+async fn get_storage_breakdown(
+    state: tauri::State<'_, AppState>,
+    largest_limit: Option<usize>,
+) -> Result<storage::cleaner::StorageBreakdown, AppError> {
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .get_storage_breakdown(largest_limit.unwrap_or(10))
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 获取占用最大的会话（视频、保留的截图和数据库行合计），便于挑选重新编码或删除
+#[tauri::command]
+async fn get_largest_sessions(
+    state: tauri::State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<storage::cleaner::SessionStorage>, AppError> {
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .get_largest_sessions(limit.unwrap_or(20))
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 迁移数据库时区：将 UTC 时间转换为本地时间
 #[tauri::command]
-async fn migrate_timezone_to_local(state: tauri::State<'_, AppState>) -> Result<String, String> {
+async fn migrate_timezone_to_local(
+    state: tauri::State<'_, AppState>,
+    confirmation_token: Option<String>,
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
+    state.system_domain.get_confirmations().consume(
+        DestructiveAction::MigrateTimezone,
+        confirmation_token.as_deref(),
+    )?;
     info!("开始数据库时区迁移");
 
     let db = state.storage_domain.get_db().await?;
@@ -970,7 +1582,7 @@ async fn migrate_timezone_to_local(state: tauri::State<'_, AppState>) -> Result<
         .await
         .map_err(|e| format!("时区迁移失败: {}", e))?;
 
-    let message = format!(
+    let mut message = format!(
         "时区迁移完成！\n\
          - 会话记录: {} 条\n\
          - 帧记录: {} 条\n\
@@ -981,13 +1593,28 @@ async fn migrate_timezone_to_local(state: tauri::State<'_, AppState>) -> Result<
         sessions, frames, llm_calls, video_segments, timeline_cards, day_summaries
     );
 
+    // 迁移后做一次只检查不修复的数据体检，提示用户是否需要修复
+    let remote_media = state.storage_domain.get_media().is_remote().await;
+    match domains::data_doctor::run(&db, true, remote_media).await {
+        Ok(report) if report.violation_count > 0 || !report.unfixable.is_empty() => {
+            message.push_str(&format!(
+                "\n数据体检发现 {} 处不一致、{} 行无法识别的时间，可在数据体检中查看并修复",
+                report.violation_count,
+                report.unfixable.len()
+            ));
+        }
+        Ok(_) => {}
+        Err(e) => warn!("时区迁移后的数据体检失败: {}", e),
+    }
+
     info!("{}", message);
     Ok(message)
 }
 
 /// 刷新历史数据的设备信息
 #[tauri::command]
-async fn refresh_device_info(state: tauri::State<'_, AppState>) -> Result<u64, String> {
+async fn refresh_device_info(state: tauri::State<'_, AppState>) -> Result<u64, AppError> {
+    state.system_domain.ensure_writable()?;
     info!("刷新历史数据的设备信息");
     state
         .storage_domain
@@ -995,7 +1622,7 @@ async fn refresh_device_info(state: tauri::State<'_, AppState>) -> Result<u64, S
         .await?
         .update_device_info_for_all_sessions()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 同步 SQLite 数据到 MariaDB
@@ -1003,12 +1630,13 @@ async fn refresh_device_info(state: tauri::State<'_, AppState>) -> Result<u64, S
 async fn sync_data_to_mariadb(
     state: tauri::State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     info!("开始同步数据到 MariaDB");
 
     // 检查当前是否为 MariaDB 模式
     if !state.storage_domain.get_db().await?.is_mariadb() {
-        return Err("当前不是 MariaDB 模式，无法同步数据".to_string());
+        return Err("当前不是 MariaDB 模式，无法同步数据".into());
     }
 
     // 获取 SQLite 数据库路径
@@ -1016,10 +1644,10 @@ async fn sync_data_to_mariadb(
         .path()
         .app_data_dir()
         .map_err(|e| format!("获取应用目录失败: {}", e))?;
-    let sqlite_db_path = app_dir.join("data.db");
+    let sqlite_db_path = storage::data_location::resolve_data_dir(&app_dir).join("data.db");
 
     if !sqlite_db_path.exists() {
-        return Err("本地 SQLite 数据库不存在".to_string());
+        return Err("本地 SQLite 数据库不存在".into());
     }
 
     // 执行同步
@@ -1039,13 +1667,13 @@ async fn sync_data_to_mariadb(
 async fn configure_qwen(
     state: tauri::State<'_, AppState>,
     config: llm::QwenConfig,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state
         .analysis_domain
         .get_llm_handle()
         .configure(config)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 配置LLM提供商（统一接口）
@@ -1054,7 +1682,7 @@ async fn configure_llm_provider(
     state: tauri::State<'_, AppState>,
     provider: String,
     config: serde_json::Value,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     info!("配置LLM提供商: {}", provider);
 
     // 根据 provider 构建配置
@@ -1069,7 +1697,7 @@ async fn configure_llm_provider(
 
             // 验证 API key 不能为空
             if api_key.trim().is_empty() {
-                return Err("通义千问 API Key 不能为空".to_string());
+                return Err("通义千问 API Key 不能为空".into());
             }
 
             let qwen_config = llm::QwenConfig {
@@ -1101,6 +1729,7 @@ async fn configure_llm_provider(
                 use_video_mode: qwen_config.use_video_mode,
                 auth_token: String::new(), // Qwen 不使用 auth_token
                 codex_config: None,
+                plugin_config: None,
             }
         }
         "claude" => {
@@ -1144,6 +1773,7 @@ async fn configure_llm_provider(
                 use_video_mode: true, // Claude 支持视频模式
                 auth_token,           // 添加 auth_token 字段
                 codex_config: None,
+                plugin_config: None,
             }
         }
         "codex" => {
@@ -1167,10 +1797,33 @@ async fn configure_llm_provider(
                 use_video_mode: false,
                 auth_token: String::new(),
                 codex_config: Some(stored),
+                plugin_config: None,
+            }
+        }
+        name if name.starts_with(llm::external::PLUGIN_PREFIX) => {
+            // 插件配置原样保存并传给插件，切换后立即生效
+            let llm_handle = state.analysis_domain.get_llm_handle();
+            llm_handle
+                .switch_provider(name)
+                .await
+                .map_err(|e| e.to_string())?;
+            llm_handle
+                .configure_plugin(config.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+
+            models::LLMProviderConfig {
+                api_key: String::new(),
+                model: String::new(),
+                base_url: String::new(),
+                use_video_mode: false,
+                auth_token: String::new(),
+                codex_config: None,
+                plugin_config: Some(config.clone()),
             }
         }
         _ => {
-            return Err(format!("不支持的提供商: {}", provider));
+            return Err(format!("不支持的提供商: {}", provider).into());
         }
     };
 
@@ -1186,6 +1839,7 @@ async fn configure_llm_provider(
         logger_settings: None,
         database_config: None,
         notion_config: None,
+        ..Default::default()
     };
 
     state
@@ -1234,13 +1888,38 @@ async fn configure_llm_provider(
     }
     // Claude 的配置会在应用启动时或切换 provider 时自动加载
 
+    // 并发分析使用的额外 LLM 实例按新配置重建
+    state.analysis_domain.reset_llm_workers().await;
+
     info!("LLM配置已保存并应用");
     Ok(())
 }
 
+/// 列出插件目录中的 LLM 插件
+#[tauri::command]
+fn list_llm_plugins() -> Result<serde_json::Value, AppError> {
+    let dir = llm::external::plugins_dir().ok_or("插件目录未初始化")?;
+    Ok(serde_json::json!({
+        "directory": dir.to_string_lossy(),
+        "plugins": llm::external::discover_plugins(dir),
+    }))
+}
+
+/// 获取提供商的视觉模型列表
+#[tauri::command]
+async fn list_llm_models(
+    provider: String,
+    config: serde_json::Value,
+) -> Result<Vec<llm::catalog::ModelInfo>, AppError> {
+    llm::catalog::list_models(&provider, &config)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
 /// 测试截屏功能
 #[tauri::command]
-async fn test_capture(state: tauri::State<'_, AppState>) -> Result<String, String> {
+async fn test_capture(state: tauri::State<'_, AppState>) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     info!("测试截屏功能...");
     match state.capture_domain.get_capture().capture_frame().await {
         Ok(frame) => {
@@ -1250,64 +1929,122 @@ async fn test_capture(state: tauri::State<'_, AppState>) -> Result<String, Strin
         Err(e) => {
             let error_msg = format!("截屏失败: {}", e);
             error!("{}", error_msg);
-            Err(error_msg)
+            Err(error_msg.into())
         }
     }
 }
 
-/// 删除会话
+/// 手动修改会话标题
 #[tauri::command]
-async fn delete_session(
+async fn rename_session(
     state: tauri::State<'_, AppState>,
     session_id: i64,
-) -> Result<String, String> {
+    title: String,
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
     validate_session_id(session_id)?;
-    info!("删除会话: {}", session_id);
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("会话标题不能为空".into());
+    }
 
-    // 获取会话详情
-    let session_detail = state
-        .storage_domain
-        .get_db()
-        .await?
-        .get_session_detail(session_id)
+    let db = state.storage_domain.get_db().await?;
+    let session = db
+        .get_session(session_id)
         .await
-        .map_err(|e| format!("获取会话详情失败: {}", e))?;
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    db.update_session(
+        session_id,
+        title,
+        &session.summary,
+        session.video_path.as_deref(),
+        &session.tags,
+    )
+    .await
+    .map_err(|e| format!("修改会话标题失败: {}", e))?;
+
+    info!("会话 {} 标题已修改为: {}", session_id, title);
+    Ok(())
+}
 
-    // 删除视频文件（如果存在）
-    if let Some(video_path) = &session_detail.session.video_path {
-        if let Err(e) = tokio::fs::remove_file(video_path).await {
-            error!("删除视频文件失败: {}", e);
-        }
+/// 处理深度链接：显示主窗口并通知前端打开会话
+fn open_deep_link(handle: &tauri::AppHandle, url: &str) {
+    let Some(link) = domains::deep_link::parse(url) else {
+        warn!("无法识别的深度链接: {}", url);
+        return;
+    };
+    info!("打开深度链接: {}", url);
+    if let Some(window) = handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
     }
+    // 前端可能尚未加载，先暂存，由前端启动后取走
+    domains::deep_link::set_pending(link.clone());
+    let _ = handle.emit(domains::deep_link::DEEP_LINK_EVENT, link);
+}
 
-    // 删除帧文件
-    for frame in &session_detail.frames {
-        if let Err(e) = tokio::fs::remove_file(&frame.file_path).await {
-            error!("删除帧文件失败: {}", e);
-        }
-    }
+/// 取出启动前收到、尚未处理的深度链接
+#[tauri::command]
+fn take_pending_deep_link() -> Option<domains::deep_link::DeepLink> {
+    domains::deep_link::take_pending()
+}
+
+/// 删除会话（移入回收站，撤销窗口内可通过 undo_delete_session 恢复）
+#[tauri::command]
+async fn delete_session(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    confirmation_token: Option<String>,
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    state.system_domain.get_confirmations().consume(
+        DestructiveAction::DeleteSession,
+        confirmation_token.as_deref(),
+    )?;
+    info!("删除会话: {}", session_id);
 
-    // 删除数据库记录 - 使用 Database 提供的方法
     state
         .storage_domain
-        .get_db()
+        .get_cleaner()
         .await?
-        .delete_session(session_id)
+        .delete_session_with_undo(session_id)
         .await
         .map_err(|e| format!("删除会话失败: {}", e))?;
 
     Ok("会话已成功删除".to_string())
 }
 
+/// 撤销删除会话（按原 ID 恢复），返回会话 ID
+#[tauri::command]
+async fn undo_delete_session(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<i64, AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    info!("撤销删除会话: {}", session_id);
+
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .undo_session_deletion(session_id)
+        .await
+        .map_err(|e| AppError::from(format!("撤销删除失败: {}", e)))
+}
+
 /// 重新生成timeline
 #[tauri::command]
 async fn regenerate_timeline(
     state: tauri::State<'_, AppState>,
     date: Option<String>, // 日期格式: YYYY-MM-DD，不提供则为当天
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
     // 仅在 SQLite 模式下支持
     if !state.storage_domain.get_db().await?.is_sqlite() {
-        return Err("重新生成timeline功能仅在 SQLite 模式下支持".to_string());
+        return Err("重新生成timeline功能仅在 SQLite 模式下支持".into());
     }
 
     info!("重新生成timeline: date={:?}", date);
@@ -1336,149 +2073,441 @@ async fn regenerate_timeline(
 
     // 处理每个session
     for session in sessions {
-        let session_id = match session.id {
-            Some(id) => id,
-            None => continue,
-        };
-        let session_start = session.start_time;
-        let session_end = session.end_time;
-
-        // 获取该session的所有video_segments
-        let segments = db
-            .get_video_segments_by_session(session_id)
-            .await
-            .map_err(|e| format!("获取视频分段失败: {}", e))?;
-
-        if segments.is_empty() {
-            continue;
+        if let Some((segments, cards)) = regenerate_session_timeline(&state, &session).await? {
+            total_segments += segments;
+            total_cards += cards;
         }
+    }
 
-        total_segments += segments.len();
+    Ok(format!(
+        "重新生成完成：处理了 {} 个分段，生成了 {} 个时间线卡片",
+        total_segments, total_cards
+    ))
+}
 
-        // 转换为LLM需要的格式 - 需要转换为相对时间
-        let video_segments: Vec<llm::plugin::VideoSegment> = segments
-            .iter()
-            .map(|s| {
-                // 将ISO时间转换为相对时间（MM:SS格式）
-                let start_dt = chrono::DateTime::parse_from_rfc3339(&s.start_timestamp)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or(session_start);
-                let end_dt = chrono::DateTime::parse_from_rfc3339(&s.end_timestamp)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or(session_end);
-
-                // 计算相对于session开始的秒数
-                let start_seconds = (start_dt - session_start).num_seconds();
-                let end_seconds = (end_dt - session_start).num_seconds();
-
-                llm::plugin::VideoSegment {
-                    // 格式化为MM:SS（分钟:秒）
-                    start_timestamp: format!("{:02}:{:02}", start_seconds / 60, start_seconds % 60),
-                    end_timestamp: format!("{:02}:{:02}", end_seconds / 60, end_seconds % 60),
-                    description: s.description.clone(),
-                }
-            })
-            .collect();
+/// 用会话已有的视频分段重新生成时间线卡片，返回（分段数，卡片数），没有分段时返回 None
+async fn regenerate_session_timeline(
+    state: &AppState,
+    session: &storage::Session,
+) -> Result<Option<(usize, usize)>, AppError> {
+    let Some(session_id) = session.id else {
+        return Ok(None);
+    };
+    let session_start = session.start_time;
+    let session_end = session.end_time;
+    let db = state.storage_domain.get_db().await?;
 
-        // 先删除相关的 LLM 调用记录（避免外键冲突）
-        db.delete_llm_calls_by_session(session_id)
-            .await
-            .map_err(|e| format!("清除LLM调用记录失败: {}", e))?;
+    // 获取该session的所有video_segments
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
 
-        // 清空该session的timeline_cards
-        db.delete_timeline_cards_by_session(session_id)
-            .await
-            .map_err(|e| format!("清除旧时间线失败: {}", e))?;
-
-        // 使用LLM重新生成timeline
-        let llm_handle = state.analysis_domain.get_llm_handle();
-        // 设置当前的 session_id，以便 LLM 调用记录能正确关联
-        llm_handle
-            .set_provider_database(
-                state.storage_domain.get_db().await?.clone(),
-                Some(session_id),
-            )
-            .await
-            .map_err(|e| format!("设置数据库失败: {}", e))?;
+    if segments.is_empty() {
+        return Ok(None);
+    }
 
-        // 设置视频速率乘数（虽然generate_timeline不直接使用，但保持一致性）
-        let app_config = state.storage_domain.get_settings().get().await;
-        let speed_multiplier = app_config.video_config.speed_multiplier;
-        llm_handle
-            .set_video_speed(speed_multiplier)
-            .await
-            .map_err(|e| format!("设置视频速率失败: {}", e))?;
-        let timeline_cards = match llm_handle.generate_timeline(video_segments, None).await {
-            Ok(cards) => cards,
-            Err(e) => {
-                error!("生成timeline失败: {}", e);
-                continue;
+    // 转换为LLM需要的格式 - 需要转换为相对时间
+    let video_segments: Vec<llm::plugin::VideoSegment> = segments
+        .iter()
+        .map(|s| {
+            // 将ISO时间转换为相对时间（MM:SS格式）
+            let start_dt = chrono::DateTime::parse_from_rfc3339(&s.start_timestamp)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .unwrap_or(session_start);
+            let end_dt = chrono::DateTime::parse_from_rfc3339(&s.end_timestamp)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .unwrap_or(session_end);
+
+            // 计算相对于session开始的秒数
+            let start_seconds = (start_dt - session_start).num_seconds();
+            let end_seconds = (end_dt - session_start).num_seconds();
+
+            llm::plugin::VideoSegment {
+                // 格式化为MM:SS（分钟:秒）
+                start_timestamp: format!("{:02}:{:02}", start_seconds / 60, start_seconds % 60),
+                end_timestamp: format!("{:02}:{:02}", end_seconds / 60, end_seconds % 60),
+                description: s.description.clone(),
             }
-        };
+        })
+        .collect();
 
-        // 获取LLM调用ID
-        let timeline_call_id = llm_handle.get_last_call_id("generate_timeline").await;
+    // 先删除相关的 LLM 调用记录（避免外键冲突）
+    db.delete_llm_calls_by_session(session_id)
+        .await
+        .map_err(|e| format!("清除LLM调用记录失败: {}", e))?;
 
-        total_cards += timeline_cards.len();
+    // 清空该session的timeline_cards
+    db.delete_timeline_cards_by_session(session_id)
+        .await
+        .map_err(|e| format!("清除旧时间线失败: {}", e))?;
 
-        // 保存新的timeline_cards - 需要处理时间格式
-        if !timeline_cards.is_empty() {
-            let card_records: Vec<storage::TimelineCardRecord> = timeline_cards
-                .iter()
-                .map(|card| {
-                    // 处理时间格式：如果是相对时间（如 "10:00 AM"），需要转换为绝对时间
-                    let (start_time, end_time) = if card.start_time.contains("AM")
-                        || card.start_time.contains("PM")
-                        || !card.start_time.contains("T")
-                    {
-                        // 是相对时间，需要转换
-                        let start_abs =
-                            llm::relative_to_absolute(session_start, session_end, &card.start_time);
-                        let end_abs =
-                            llm::relative_to_absolute(session_start, session_end, &card.end_time);
-                        (start_abs.to_rfc3339(), end_abs.to_rfc3339())
-                    } else {
-                        // 已经是ISO格式，直接使用
-                        (card.start_time.clone(), card.end_time.clone())
-                    };
+    // 使用LLM重新生成timeline
+    let llm_handle = state.analysis_domain.get_llm_handle();
+    // 设置当前的 session_id，以便 LLM 调用记录能正确关联
+    llm_handle
+        .set_provider_database(
+            state.storage_domain.get_db().await?.clone(),
+            Some(session_id),
+        )
+        .await
+        .map_err(|e| format!("设置数据库失败: {}", e))?;
 
-                    storage::TimelineCardRecord {
-                        id: None,
-                        session_id,
-                        llm_call_id: timeline_call_id, // 使用实际的LLM调用ID
-                        start_time,
-                        end_time,
-                        category: card.category.clone(),
-                        subcategory: card.subcategory.clone(),
-                        title: card.title.clone(),
-                        summary: card.summary.clone(),
-                        detailed_summary: card.detailed_summary.clone(),
-                        distractions: Some(
-                            serde_json::to_string(&card.distractions).unwrap_or_default(),
-                        ),
-                        app_sites: serde_json::to_string(&card.app_sites).unwrap_or_default(),
-                        video_preview_path: None,
-                        created_at: storage::local_now(),
-                    }
-                })
-                .collect();
+    // 设置视频速率乘数（虽然generate_timeline不直接使用，但保持一致性）
+    let app_config = state.storage_domain.get_settings().get().await;
+    let speed_multiplier = app_config.video_config.speed_multiplier;
+    llm_handle
+        .set_video_speed(speed_multiplier)
+        .await
+        .map_err(|e| format!("设置视频速率失败: {}", e))?;
+    let corrections = db
+        .get_recent_card_corrections(llm::personalization::MAX_CORRECTIONS)
+        .await
+        .unwrap_or_default();
+    llm_handle
+        .set_correction_examples(corrections)
+        .await
+        .map_err(|e| format!("设置个性化示例失败: {}", e))?;
+    let timeline_cards = match llm_handle.generate_timeline(video_segments, None).await {
+        Ok(cards) => cards,
+        Err(e) => {
+            error!("生成timeline失败: {}", e);
+            return Ok(Some((segments.len(), 0)));
+        }
+    };
 
-            if let Err(e) = state
-                .storage_domain
-                .get_db()
-                .await?
-                .insert_timeline_cards(&card_records)
+    // 获取LLM调用ID
+    let timeline_call_id = llm_handle.get_last_call_id("generate_timeline").await;
+
+    // 保存新的timeline_cards - 需要处理时间格式
+    if !timeline_cards.is_empty() {
+        let mut card_records: Vec<storage::TimelineCardRecord> = timeline_cards
+            .iter()
+            .map(|card| {
+                // 处理时间格式：如果是相对时间（如 "10:00 AM"），需要转换为绝对时间
+                let (start_time, end_time) = if card.start_time.contains("AM")
+                    || card.start_time.contains("PM")
+                    || !card.start_time.contains("T")
+                {
+                    // 是相对时间，需要转换
+                    let start_abs =
+                        llm::relative_to_absolute(session_start, session_end, &card.start_time);
+                    let end_abs =
+                        llm::relative_to_absolute(session_start, session_end, &card.end_time);
+                    (start_abs.to_rfc3339(), end_abs.to_rfc3339())
+                } else {
+                    // 已经是ISO格式，直接使用
+                    (card.start_time.clone(), card.end_time.clone())
+                };
+
+                storage::TimelineCardRecord {
+                    id: None,
+                    session_id,
+                    llm_call_id: timeline_call_id, // 使用实际的LLM调用ID
+                    start_time,
+                    end_time,
+                    category: card.category.clone(),
+                    subcategory: card.subcategory.clone(),
+                    title: card.title.clone(),
+                    summary: card.summary.clone(),
+                    detailed_summary: card.detailed_summary.clone(),
+                    distractions: Some(
+                        serde_json::to_string(&card.distractions).unwrap_or_default(),
+                    ),
+                    app_sites: serde_json::to_string(&card.app_sites).unwrap_or_default(),
+                    video_preview_path: None,
+                    created_at: storage::local_now(),
+                    confidence: Some(domains::review::card_confidence(card)),
+                    review_status: None,
+                    meeting_notes: None,
+                    keyword_tags: None,
+                    preview_clip_path: None,
+                    privacy_level: None,
+                }
+            })
+            .collect();
+
+        let config = state.storage_domain.get_settings().get().await;
+        let app_profiles = config.app_profiles.unwrap_or_default();
+        let subcategories = domains::subcategories::SubcategoryNormalizer::new(
+            &config.subcategory_synonyms.unwrap_or_default(),
+        );
+        let review_threshold = config
+            .review_confidence_threshold
+            .unwrap_or(domains::review::DEFAULT_REVIEW_THRESHOLD);
+        for card in card_records.iter_mut() {
+            subcategories.apply(card);
+            domains::app_profiles::apply_app_profiles(card, &app_profiles);
+            domains::review::mark_for_review(card, review_threshold);
+        }
+
+        if let Err(e) = state
+            .storage_domain
+            .get_db()
+            .await?
+            .insert_timeline_cards(&card_records)
+            .await
+        {
+            error!("保存时间线卅片失败: {}", e);
+        }
+    }
+
+    Ok(Some((segments.len(), timeline_cards.len())))
+}
+
+/// 一键修复某天：补生成缺失的视频、重新分析失败的会话、重新生成时间线和每日总结，并同步到 Notion
+///
+/// 通过 day-rebuild-progress 事件推送进度，单个会话失败不中止，记入报告
+#[tauri::command]
+async fn rebuild_day(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<domains::day_rebuild::DayRebuildReport, AppError> {
+    use domains::day_rebuild::{
+        plan_session, DayRebuildReport, RebuildProgress, RebuildStage, PROGRESS_EVENT,
+    };
+
+    state.system_domain.ensure_writable()?;
+    chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("日期格式错误: {}", e))?;
+    info!("一键修复: {}", date);
+
+    let progress = |stage, done, total, message: String| {
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            RebuildProgress {
+                date: date.clone(),
+                stage,
+                done,
+                total,
+                message,
+            },
+        );
+    };
+
+    let db = state.storage_domain.get_db().await?;
+    let sessions = db
+        .get_sessions_by_date(&date)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let mut report = DayRebuildReport {
+        date: date.clone(),
+        sessions: sessions.len(),
+        ..Default::default()
+    };
+
+    // 检查每个会话的视频、截图和卡片（使用远程存储时视频可能只在远端，视为存在）
+    let remote_media = state.storage_domain.get_media().is_remote().await;
+    let mut video_ids = Vec::new();
+    let mut analysis_ids = Vec::new();
+    for session in &sessions {
+        let Some(session_id) = session.id else {
+            continue;
+        };
+        let has_video = match &session.video_path {
+            Some(path) => remote_media || tokio::fs::try_exists(path).await.unwrap_or(false),
+            None => false,
+        };
+        let has_frames = if has_video {
+            false
+        } else {
+            match db.get_session_detail(session_id).await {
+                Ok(detail) => match detail.frames.first() {
+                    Some(frame) => tokio::fs::try_exists(&frame.file_path)
+                        .await
+                        .unwrap_or(false),
+                    None => false,
+                },
+                Err(_) => false,
+            }
+        };
+        let card_count = db
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .map(|cards| cards.len())
+            .unwrap_or(0);
+
+        let repair = plan_session(has_video, has_frames, card_count);
+        if repair.generate_video {
+            video_ids.push(session_id);
+        }
+        if repair.reanalyze {
+            analysis_ids.push(session_id);
+        }
+    }
+
+    // 1. 重新生成视频
+    for (index, session_id) in video_ids.iter().enumerate() {
+        progress(
+            RebuildStage::Videos,
+            index,
+            video_ids.len(),
+            format!("生成会话 {} 的视频", session_id),
+        );
+        match generate_session_video(&state, *session_id, None).await {
+            Ok(_) => report.videos_generated += 1,
+            Err(e) => {
+                report
+                    .messages
+                    .push(format!("会话 {} 生成视频失败: {}", session_id, e));
+                analysis_ids.retain(|id| id != session_id);
+            }
+        }
+    }
+
+    // 2. 重新分析没有卡片的会话
+    let mut reanalyzed = Vec::new();
+    for (index, session_id) in analysis_ids.iter().enumerate() {
+        progress(
+            RebuildStage::Analysis,
+            index,
+            analysis_ids.len(),
+            format!("重新分析会话 {}", session_id),
+        );
+        match reanalyze_session(&state, *session_id).await {
+            Ok(_) => reanalyzed.push(*session_id),
+            Err(e) => report
+                .messages
+                .push(format!("会话 {} 重新分析失败: {}", session_id, e)),
+        }
+    }
+    report.sessions_reanalyzed = reanalyzed.len();
+
+    // 3. 其余会话用已有的视频分段重新生成时间线
+    // 已锁定的日期和用户确认、纠正过卡片的会话保留原有时间线，重新生成会覆盖用户的修改
+    let finalized = db
+        .get_day_summary(&date)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|summary| summary.finalized);
+    if finalized {
+        report
+            .messages
+            .push("这一天已锁定，保留原有时间线，未重新生成".to_string());
+    } else if db.is_sqlite() {
+        let mut timeline_sessions: Vec<&storage::Session> = Vec::new();
+        for session in &sessions {
+            let Some(session_id) = session.id.filter(|id| !analysis_ids.contains(id)) else {
+                continue;
+            };
+            let cards = db
+                .get_timeline_cards_by_session(session_id)
+                .await
+                .unwrap_or_default();
+            if domains::day_rebuild::has_user_edits(&cards) {
+                report.messages.push(format!(
+                    "会话「{}」有确认或修改过的卡片，保留原有时间线",
+                    session.title
+                ));
+                continue;
+            }
+            timeline_sessions.push(session);
+        }
+        for (index, session) in timeline_sessions.iter().enumerate() {
+            progress(
+                RebuildStage::Timeline,
+                index,
+                timeline_sessions.len(),
+                format!("重新生成「{}」的时间线", session.title),
+            );
+            match regenerate_session_timeline(&state, session).await {
+                Ok(Some((_, cards))) => {
+                    report.timelines_regenerated += 1;
+                    report.timeline_cards += cards;
+                }
+                Ok(None) => {}
+                Err(e) => report
+                    .messages
+                    .push(format!("会话 {:?} 重新生成时间线失败: {}", session.id, e)),
+            }
+        }
+    } else {
+        report
+            .messages
+            .push("重新生成时间线仅在 SQLite 模式下支持，已跳过".to_string());
+    }
+
+    // 4. 重新生成每日总结
+    progress(
+        RebuildStage::DaySummary,
+        0,
+        1,
+        "重新生成每日总结".to_string(),
+    );
+    let summary = match load_day_summary(&state, &date, true).await {
+        Ok(summary) => {
+            report.summary_regenerated = true;
+            Some(summary)
+        }
+        Err(e) => {
+            report.messages.push(format!("生成每日总结失败: {}", e));
+            None
+        }
+    };
+
+    // 5. 同步到 Notion（只同步重新分析过的会话，其余会话已同步过，再同步会产生重复页面）
+    let notion = state.storage_domain.get_notion_manager();
+    if notion.is_enabled().await {
+        let max_level =
+            domains::privacy::max_export_level(&state.storage_domain.get_settings().get().await);
+        for (index, session_id) in reanalyzed.iter().enumerate() {
+            progress(
+                RebuildStage::Notion,
+                index,
+                reanalyzed.len(),
+                format!("同步会话 {} 到 Notion", session_id),
+            );
+            // 隐私敏感度高于设置值的会话不同步
+            match domains::privacy::rate_session(&db, *session_id).await {
+                Ok(level) if level > max_level => {
+                    report.messages.push(format!(
+                        "会话 {} 隐私敏感度为{}，未同步到 Notion",
+                        session_id,
+                        level.display_name()
+                    ));
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    report
+                        .messages
+                        .push(format!("会话 {} 敏感度评估失败: {}", session_id, e));
+                    continue;
+                }
+            }
+            let result = match db.get_session(*session_id).await {
+                Ok(session) => {
+                    let details = notion::load_session_details(&db, &session).await;
+                    notion.sync_session(&session, &details).await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => report.notion_synced += 1,
+                Err(e) => report
+                    .messages
+                    .push(format!("会话 {} 同步到 Notion 失败: {}", session_id, e)),
+            }
+        }
+        if let Some(summary) = &summary {
+            if let Err(e) = notion
+                .sync_daily_summary(&date, &summary.summary_text)
                 .await
             {
-                error!("保存时间线卅片失败: {}", e);
+                report
+                    .messages
+                    .push(format!("每日总结同步到 Notion 失败: {}", e));
             }
         }
     }
 
-    Ok(format!(
-        "重新生成完成：处理了 {} 个分段，生成了 {} 个时间线卡片",
-        total_segments, total_cards
-    ))
+    info!(
+        "一键修复完成: {}，生成视频 {}，重新分析 {}，重新生成时间线 {}",
+        date, report.videos_generated, report.sessions_reanalyzed, report.timelines_regenerated
+    );
+    Ok(report)
 }
 
 /// 通用的打开文件夹函数，支持跨平台
@@ -1521,7 +2550,7 @@ fn open_folder_in_explorer(path: &Path) -> Result<(), String> {
 async fn open_storage_folder(
     state: tauri::State<'_, AppState>,
     folder_type: FolderType,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let path = match folder_type {
         FolderType::Frames => state.capture_domain.get_capture().frames_dir(),
         FolderType::Videos => state
@@ -1531,12 +2560,50 @@ async fn open_storage_folder(
             .clone(),
     };
 
-    open_folder_in_explorer(&path)
+    open_folder_in_explorer(&path).map_err(AppError::from)
+}
+
+/// 获取最近的日志（重新打开日志面板时显示历史）
+#[tauri::command]
+fn get_recent_logs(
+    state: tauri::State<'_, AppState>,
+    filter: Option<logger::LogFilter>,
+) -> Result<Vec<logger::LogMessage>, AppError> {
+    Ok(state
+        .system_domain
+        .get_logger()
+        .recent_logs(&filter.unwrap_or_default()))
+}
+
+/// 获取一个会话从调度、视频、LLM 到存储的完整日志
+#[tauri::command]
+async fn get_session_trace(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<logger::SessionTrace, AppError> {
+    validate_session_id(session_id)?;
+    let db = state.storage_domain.get_db().await?;
+    let session = db
+        .get_session(session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    Ok(state
+        .system_domain
+        .get_logger()
+        .session_trace(session.start_time))
+}
+
+/// 获取事件总线各主题的发布数、订阅者落后跳过数和订阅者数
+#[tauri::command]
+fn get_event_bus_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<event_bus::TopicStats>, AppError> {
+    Ok(state.event_bus.topic_stats())
 }
 
 /// 获取日志目录路径
 #[tauri::command]
-fn get_log_dir() -> Result<String, String> {
+fn get_log_dir() -> Result<String, AppError> {
     let log_dir = if cfg!(target_os = "macos") {
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
         PathBuf::from(home).join("Library/Logs/screen-analyzer")
@@ -1553,7 +2620,7 @@ fn get_log_dir() -> Result<String, String> {
 
 /// 打开日志文件夹
 #[tauri::command]
-fn open_log_folder() -> Result<(), String> {
+fn open_log_folder() -> Result<(), AppError> {
     let log_dir = if cfg!(target_os = "macos") {
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
         PathBuf::from(home).join("Library/Logs/screen-analyzer")
@@ -1566,7 +2633,7 @@ fn open_log_folder() -> Result<(), String> {
     };
 
     info!("打开日志文件夹: {:?}", log_dir);
-    open_folder_in_explorer(&log_dir)
+    open_folder_in_explorer(&log_dir).map_err(AppError::from)
 }
 
 /// 测试LLM API连接
@@ -1575,7 +2642,7 @@ async fn test_llm_api(
     _state: tauri::State<'_, AppState>,
     provider: String,
     config: serde_json::Value,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     info!("测试LLM API连接: provider={}", provider);
 
     // 对于测试连接，我们使用简单的文本测试而不是图像分析
@@ -1600,7 +2667,7 @@ async fn test_llm_api(
         }
         Err(e) => {
             error!("API测试失败: {}", e);
-            Err(format!("API连接失败: {}", e))
+            Err(format!("API连接失败: {}", e).into())
         }
     }
 }
@@ -1624,7 +2691,6 @@ async fn test_codex_cli(config: serde_json::Value) -> Result<String, String> {
 }
 
 async fn test_openai_text_api(config: serde_json::Value) -> Result<String, String> {
-    use reqwest::Client;
     use serde_json::json;
 
     let api_key = config
@@ -1642,7 +2708,10 @@ async fn test_openai_text_api(config: serde_json::Value) -> Result<String, Strin
         .and_then(|v| v.as_str())
         .unwrap_or("https://dashscope.aliyuncs.com/compatible-mode/v1/chat/completions");
 
-    let client = Client::new();
+    domains::local_only::ensure_local_endpoint(base_url)?;
+    let client = domains::local_only::http_client_builder()
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
     let endpoint = base_url.to_string();
 
     let request_body = json!({
@@ -1958,7 +3027,7 @@ async fn test_claude_sdk_api(config: serde_json::Value) -> Result<String, String
 async fn test_notion_connection(
     _state: tauri::State<'_, AppState>,
     api_token: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     info!("测试 Notion API 连接");
 
     // 创建临时配置用于测试
@@ -1978,7 +3047,7 @@ async fn test_notion_connection(
         .search_pages()
         .await
         .map(|pages| format!("连接成功！可以访问 {} 个页面/数据库", pages.len()))
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 更新 Notion 配置
@@ -1986,7 +3055,7 @@ async fn test_notion_connection(
 async fn update_notion_config(
     state: tauri::State<'_, AppState>,
     config: models::NotionConfig,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     info!("更新 Notion 配置");
 
     // 保存配置到设置文件
@@ -2019,7 +3088,7 @@ async fn update_notion_config(
 async fn search_notion_pages(
     state: tauri::State<'_, AppState>,
     api_token: String,
-) -> Result<Vec<notion::NotionPage>, String> {
+) -> Result<Vec<notion::NotionPage>, AppError> {
     info!("搜索 Notion 页面和数据库");
 
     state
@@ -2027,7 +3096,7 @@ async fn search_notion_pages(
         .get_notion_manager()
         .search_pages(&api_token)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
 /// 在指定页面下创建 Notion 数据库
@@ -2037,7 +3106,7 @@ async fn create_notion_database(
     api_token: String,
     parent_page_id: String,
     database_name: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     info!("在页面 {} 下创建数据库: {}", parent_page_id, database_name);
 
     state
@@ -2045,89 +3114,2023 @@ async fn create_notion_database(
         .get_notion_manager()
         .create_database(&api_token, &parent_page_id, &database_name)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
-// ==================== 辅助函数 ====================
-
-/// 处理历史图片，生成视频并清理
-async fn process_historical_frames(state: &AppState) -> Result<(), String> {
-    // 仅在 SQLite 模式下处理历史图片
+/// 立即执行一次 RAG 向量库增量导出
+#[tauri::command]
+async fn export_rag_now(
+    state: tauri::State<'_, AppState>,
+) -> Result<rag::RagExportReport, AppError> {
+    info!("手动触发 RAG 导出");
     let db = state.storage_domain.get_db().await?;
-    if !db.is_sqlite() {
-        info!("跳过历史图片处理（仅 SQLite 模式支持）");
-        return Ok(());
-    }
+    state
+        .storage_domain
+        .get_rag_exporter()
+        .export_incremental(&db)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
 
-    info!("开始处理历史图片");
+/// 获取 RAG 导出进度
+#[tauri::command]
+async fn get_rag_export_state(
+    state: tauri::State<'_, AppState>,
+) -> Result<rag::RagExportState, AppError> {
+    Ok(state.storage_domain.get_rag_exporter().get_state().await)
+}
 
-    // 查询所有会话，筛选出未生成视频的
-    let all_sessions = db.get_all_sessions().await.map_err(|e| e.to_string())?;
+/// 获取上下文切换统计
+///
+/// # 参数
+/// * `period` - 统计周期 (day/week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+#[tauri::command]
+async fn get_context_switch_stats(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    date: Option<String>,
+) -> Result<domains::metrics::ContextSwitchStats, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
 
-    let sessions_without_video: Vec<_> = all_sessions
-        .into_iter()
-        .filter(|s| s.video_path.is_none() || s.video_path.as_ref().map_or(false, |p| p.is_empty()))
-        .take(10)
-        .collect();
+    let db = state.storage_domain.get_db().await?;
+    domains::metrics::get_context_switch_stats(&db, period, anchor)
+        .await
+        .map_err(AppError::from)
+}
 
-    info!("找到 {} 个未生成视频的会话", sessions_without_video.len());
+/// 导出 BI 数据集（星型模型 CSV 和数据字典）到 `path` 目录
+///
+/// # 参数
+/// * `period` - 导出周期 (day/week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+#[tauri::command]
+async fn export_bi_dataset(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    path: String,
+    date: Option<String>,
+) -> Result<domains::bi_export::BiExportReport, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
 
-    for session in sessions_without_video {
-        let session_id = match session.id {
-            Some(id) => id,
-            None => continue,
-        };
+    let db = state.storage_domain.get_db().await?;
+    let max_level =
+        domains::privacy::max_export_level(&state.storage_domain.get_settings().get().await);
+    domains::bi_export::export_bi_dataset(
+        &db,
+        period,
+        anchor,
+        std::path::Path::new(&path),
+        max_level,
+    )
+    .await
+    .map_err(AppError::from)
+}
 
-        info!(
-            "处理会话 {}: {} - {}",
-            session_id, session.start_time, session.end_time
-        );
+/// 导出微调数据集（JSONL，每行一组提示词、模型输出和用户修正）到 `path` 文件
+///
+/// # 参数
+/// * `call_types` - 导出的调用类型，不提供则为视频分段和时间线生成
+/// * `only_corrected` - 只导出应用了用户修正的样本
+#[tauri::command]
+async fn export_finetune_dataset(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    call_types: Option<Vec<String>>,
+    only_corrected: Option<bool>,
+) -> Result<domains::finetune_export::FinetuneExportReport, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    let max_level =
+        domains::privacy::max_export_level(&state.storage_domain.get_settings().get().await);
+    domains::finetune_export::export_finetune_dataset(
+        &db,
+        std::path::Path::new(&path),
+        &call_types.unwrap_or_default(),
+        only_corrected.unwrap_or(false),
+        max_level,
+    )
+    .await
+    .map_err(AppError::from)
+}
 
-        // 获取该会话的所有帧
-        let frames = match db.get_frames_by_session(session_id).await {
-            Ok(frames) => frames,
-            Err(e) => {
-                error!("获取会话 {} 的帧失败: {}", session_id, e);
-                continue;
-            }
-        };
+/// 导出主题合集：把日期范围内某个标签或类别的所有卡片片段拼成一个视频，片段之间插入标题卡
+///
+/// # 参数
+/// * `tag_or_category` - 类别、子类别或标签（不区分大小写）
+/// * `start_date` / `end_date` - 日期范围（YYYY-MM-DD，含首尾两天）
+/// * `output_dir` - 导出目录，不提供时导出到下载目录
+#[tauri::command]
+async fn export_compilation(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    tag_or_category: String,
+    start_date: String,
+    end_date: String,
+    output_dir: Option<String>,
+) -> Result<domains::compilation::CompilationReport, AppError> {
+    let output_dir = match output_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => app.path().download_dir().map_err(|e| e.to_string())?,
+    };
+    let name: String = tag_or_category
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let output_path = output_dir.join(format!(
+        "screen-analyzer-{}-{}-{}.mp4",
+        name, start_date, end_date
+    ));
+    domains::compilation::export_compilation(
+        &state.storage_domain,
+        &tag_or_category,
+        &start_date,
+        &end_date,
+        &output_path,
+    )
+    .await
+    .map_err(AppError::from)
+}
 
-        if !frames.is_empty() {
-            let frame_paths: Vec<String> = frames.into_iter().map(|f| f.file_path).collect();
+/// 导出时间线卡片到 Toggl/Clockify（已导出的卡片按幂等键跳过，不会重复创建）
+///
+/// # 参数
+/// * `start_date` / `end_date` - 日期范围（YYYY-MM-DD，含首尾两天）
+#[tauri::command]
+async fn export_time_entries(
+    state: tauri::State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<domains::time_tracking::TimeTrackingReport, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    let config = state.storage_domain.get_settings().get().await;
+    domains::time_tracking::export_time_entries(&db, &config, &start_date, &end_date)
+        .await
+        .map_err(AppError::from)
+}
 
-            if !frame_paths.is_empty() {
-                info!(
-                    "为会话 {} 生成视频，共 {} 帧",
-                    session_id,
-                    frame_paths.len()
-                );
+/// 识别会话视频中的屏幕文字（重新识别时替换旧结果），返回有文字的分段数
+#[tauri::command]
+async fn build_text_timeline(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<usize, AppError> {
+    state.system_domain.ensure_writable()?;
+    domains::text_timeline::build_for_session(&state.storage_domain, session_id)
+        .await
+        .map_err(AppError::from)
+}
 
-                // 生成视频
-                let video_config = crate::video::VideoConfig::default();
-                let video_filename = format!(
-                    "{}-{}.mp4",
-                    session.start_time.format("%Y%m%d%H%M"),
-                    session.end_time.format("%Y%m%d%H%M")
-                );
+/// 获取会话的屏幕文字时间线
+#[tauri::command]
+async fn get_text_timeline(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Vec<storage::ScreenTextRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    db.get_screen_text_by_session(session_id)
+        .await
+        .map_err(|e| AppError::from(format!("获取屏幕文字失败: {}", e)))
+}
 
-                let video_path_buf = state
-                    .analysis_domain
-                    .get_video_processor()
-                    .output_dir
-                    .join(&video_filename);
-                match state
-                    .analysis_domain
-                    .get_video_processor()
-                    .create_summary_video(frame_paths.clone(), &video_path_buf, &video_config)
-                    .await
-                {
-                    Ok(video_result) => {
-                        info!(
+/// 搜索屏幕上出现过的文字
+///
+/// # 参数
+/// * `query` - 搜索内容（如错误码）
+/// * `language` - 只搜索该语言的分段（chinese、english、code、other），不传则不限
+/// * `limit` - 最多返回的分段数，默认 50
+#[tauri::command]
+async fn search_screen_text(
+    state: tauri::State<'_, AppState>,
+    query: String,
+    language: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<domains::text_timeline::ScreenTextHit>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::text_timeline::search(&db, &query, language.as_deref(), limit)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 统计日期范围内各语言的屏幕时长（如这周读英文资料的时间），需开启屏幕文字识别
+///
+/// # 参数
+/// * `start_date` / `end_date` - 日期范围 (YYYY-MM-DD，含首尾两天)
+#[tauri::command]
+async fn get_screen_language_usage(
+    state: tauri::State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<domains::screen_language::LanguageUsage>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::screen_language::usage_between(&db, &start_date, &end_date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天屏幕上出现过的报错（堆栈、编译错误、错误码），需开启屏幕文字识别
+///
+/// # 参数
+/// * `date` - 日期 (YYYY-MM-DD)
+#[tauri::command]
+async fn get_errors_seen(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<storage::ErrorMomentRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::dev_errors::get_errors_seen(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天的剪贴板记录（需在设置中开启剪贴板记录）
+#[tauri::command]
+async fn get_clipboard_events(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<storage::ClipboardEventRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::clipboard::get_day_events(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 查找复制过某段内容的时间和所在会话（按内容哈希精确匹配，保存了文本的记录按包含匹配）
+#[tauri::command]
+async fn search_clipboard(
+    state: tauri::State<'_, AppState>,
+    snippet: String,
+    limit: Option<usize>,
+) -> Result<Vec<domains::clipboard::ClipboardHit>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::clipboard::search(&db, &snippet, limit)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取会话的逐分钟音频活动（麦克风/扬声器是否在使用，需在设置中开启音频活动检测）
+#[tauri::command]
+async fn get_session_audio_presence(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<domains::audio_presence::SessionAudioPresence, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::audio_presence::get_session_audio_presence(&db, session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 添加会话书签（回看视频时标记当前位置）
+///
+/// # 参数
+/// * `video_time` - 视频播放位置（秒）
+/// * `note` - 备注
+#[tauri::command]
+async fn add_session_bookmark(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    video_time: f64,
+    note: String,
+) -> Result<i64, AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    let db = state.storage_domain.get_db().await?;
+    domains::bookmarks::add_bookmark(&db, session_id, video_time, &note)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取会话的书签（按视频位置排序，附带对应的真实时间）
+#[tauri::command]
+async fn get_session_bookmarks(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Vec<domains::bookmarks::SessionBookmark>, AppError> {
+    validate_session_id(session_id)?;
+    let db = state.storage_domain.get_db().await?;
+    domains::bookmarks::session_bookmarks(&db, state.storage_domain.get_settings(), session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 删除会话书签
+#[tauri::command]
+async fn delete_session_bookmark(
+    state: tauri::State<'_, AppState>,
+    bookmark_id: i64,
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .delete_session_bookmark(bookmark_id)
+        .await
+        .map_err(|e| AppError::from(format!("删除书签失败: {}", e)))
+}
+
+/// 获取会话中各卡片的预览短片
+#[tauri::command]
+async fn get_card_previews(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Vec<domains::card_previews::CardPreview>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::card_previews::session_card_previews(&db, session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天各会话的色带（每分钟一个平均颜色的小图）
+#[tauri::command]
+async fn get_day_mood_strips(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<domains::mood_strip::MoodStrip>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::mood_strip::day_strips(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 数据体检：检查视频分段和时间线卡片中的相对时间/不带时区的时间并修复，列出无法修复的行，
+/// 再检查卡片时间窗口、分段顺序、LLM 调用引用和视频文件等跨表不变量
+///
+/// # 参数
+/// * `dry_run` - 只检查不写入（默认 true）
+#[tauri::command]
+async fn run_data_doctor(
+    state: tauri::State<'_, AppState>,
+    dry_run: Option<bool>,
+) -> Result<domains::data_doctor::DataDoctorReport, AppError> {
+    let dry_run = dry_run.unwrap_or(true);
+    if !dry_run {
+        state.system_domain.ensure_writable()?;
+    }
+    let db = state.storage_domain.get_db().await?;
+    let remote_media = state.storage_domain.get_media().is_remote().await;
+    domains::data_doctor::run(&db, dry_run, remote_media)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取 MariaDB 写入 outbox 状态（远程数据库离线期间暂存的写入数量和重放情况）
+#[tauri::command]
+async fn get_outbox_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<storage::repository::outbox::OutboxStatus, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    db.outbox_status()
+        .await
+        .map_err(|e| AppError::from(format!("获取 outbox 状态失败: {}", e)))
+}
+
+/// 执行启动自检并保存结果
+async fn run_startup_checks(state: &AppState) -> domains::startup_check::StartupReport {
+    let config = state.storage_domain.get_settings().get().await;
+    let db = state.storage_domain.try_get_db().await;
+    let frames_dir = state.capture_domain.get_capture().frames_dir();
+    let report =
+        domains::startup_check::run_startup_checks(&config, db.as_deref(), &frames_dir).await;
+    state.system_domain.set_startup_report(report.clone());
+    report
+}
+
+/// 获取启动自检报告（自检尚未完成时返回 None）
+///
+/// # 参数
+/// * `refresh` - 重新执行检查（处理完问题后重新检查），默认 false
+#[tauri::command]
+async fn get_startup_report(
+    state: tauri::State<'_, AppState>,
+    refresh: Option<bool>,
+) -> Result<Option<domains::startup_check::StartupReport>, AppError> {
+    if refresh.unwrap_or(false) {
+        return Ok(Some(run_startup_checks(&state).await));
+    }
+    Ok(state.system_domain.startup_report())
+}
+
+/// 手动检查新版本（不受"跳过此版本"影响），没有新版本时返回 None
+#[tauri::command]
+async fn check_for_updates(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<domains::updates::UpdateInfo>, AppError> {
+    domains::updates::check_for_update(state.system_domain.get_http_client())
+        .await
+        .map_err(|e| AppError::from(format!("检查新版本失败: {}", e)))
+}
+
+/// 跳过某个版本（该版本不再自动提醒）
+#[tauri::command]
+async fn skip_update_version(
+    state: tauri::State<'_, AppState>,
+    version: String,
+) -> Result<(), AppError> {
+    let settings = state.storage_domain.get_settings();
+    let mut update_check = settings.get().await.update_check.unwrap_or_default();
+    update_check.skipped_version = Some(version);
+    settings
+        .update(models::AppConfig {
+            update_check: Some(update_check),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("保存设置失败: {}", e))?;
+    Ok(())
+}
+
+/// 获取长期趋势（滚动专注时长、类别占比变化、开始工作时间漂移和预测）
+///
+/// # 参数
+/// * `period` - 周期粒度 (week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+/// * `count` - 统计的周期数，默认 6
+#[tauri::command]
+async fn get_trends(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    date: Option<String>,
+    count: Option<usize>,
+) -> Result<domains::trends::TrendReport, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
+
+    let db = state.storage_domain.get_db().await?;
+    domains::trends::get_trends(
+        &db,
+        period,
+        anchor,
+        count.unwrap_or(domains::trends::DEFAULT_TREND_PERIODS),
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 获取月度总结（本月统计、截至本月的趋势提示和重复流程的自动化建议）
+///
+/// # 参数
+/// * `month` - 月份 (YYYY-MM)
+#[tauri::command]
+async fn get_monthly_summary(
+    state: tauri::State<'_, AppState>,
+    month: String,
+) -> Result<domains::trends::MonthlySummary, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::trends::get_monthly_summary(&db, &month)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取关注关键词的命中记录
+///
+/// # 参数
+/// * `period` - 统计周期 (day/week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+#[tauri::command]
+async fn get_keyword_hits(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    date: Option<String>,
+) -> Result<Vec<storage::KeywordHitRecord>, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
+
+    let db = state.storage_domain.get_db().await?;
+    domains::keywords::get_keyword_hits(&db, period, anchor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取 webhook 请求体的 JSON Schema
+#[tauri::command]
+async fn get_webhook_schema() -> Result<serde_json::Value, AppError> {
+    Ok(domains::webhooks::payload_schema())
+}
+
+/// 获取最近的 webhook 投递记录（按时间倒序）
+///
+/// # 参数
+/// * `session_id` - 只看该会话的投递，不提供则不限
+/// * `limit` - 最多返回条数，默认 100
+#[tauri::command]
+async fn get_webhook_deliveries(
+    state: tauri::State<'_, AppState>,
+    session_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<storage::WebhookDeliveryRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::webhooks::get_deliveries(&db, session_id, limit)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取数据外发记录（LLM 上传、Notion 同步等离开本机的传输）
+///
+/// # 参数
+/// * `period` - 统计周期 (day/week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+#[tauri::command]
+async fn get_egress_log(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    date: Option<String>,
+) -> Result<domains::egress::EgressLog, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
+
+    let db = state.storage_domain.get_db().await?;
+    domains::egress::get_egress_log(&db, period, anchor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取个人纪录（最长专注时段、本月最佳日、连续达标天数）
+#[tauri::command]
+async fn get_records(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::records::PersonalRecords, AppError> {
+    let goal_minutes = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .daily_focus_goal_minutes
+        .unwrap_or(240);
+    let db = state.storage_domain.get_db().await?;
+    domains::records::refresh_records(&db, goal_minutes as i64, storage::local_now().date_naive())
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取休息提醒状态
+#[tauri::command]
+async fn get_break_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::breaks::BreakStatus, AppError> {
+    Ok(state.capture_domain.get_break_reminder().status().await)
+}
+
+/// 用示例事件执行一次自动化钩子，返回输出
+#[tauri::command]
+async fn test_automation_hook(hook: models::AutomationHook) -> Result<String, AppError> {
+    let payload = domains::hooks::sample_payload(hook.event);
+    domains::hooks::run_hook(&hook, &payload)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取实时状态快照（当前类别、专注连续时长、会话已进行时长）
+#[tauri::command]
+async fn get_live_state(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::live::LiveState, AppError> {
+    collect_live_state(&state).await.map_err(AppError::from)
+}
+
+/// 获取实时活动估计（按前台窗口和最新截图的文字估计，未启用或无法判断时为空）
+#[tauri::command]
+async fn get_live_activity() -> Result<Option<domains::live_activity::LiveActivity>, AppError> {
+    Ok(domains::live_activity::current())
+}
+
+/// 稍后提醒休息
+#[tauri::command]
+async fn snooze_break_reminder(state: tauri::State<'_, AppState>) -> Result<(), AppError> {
+    state.capture_domain.get_break_reminder().snooze().await;
+    Ok(())
+}
+
+/// 跳过本次休息
+#[tauri::command]
+async fn skip_break_reminder(state: tauri::State<'_, AppState>) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    state.capture_domain.get_break_reminder().skip(&db).await;
+    Ok(())
+}
+
+/// 开始专注时段（minutes 为空时使用设置中的默认时长）
+#[tauri::command]
+async fn start_focus_block(
+    state: tauri::State<'_, AppState>,
+    minutes: Option<u32>,
+) -> Result<domains::focus::FocusStatus, AppError> {
+    state
+        .capture_domain
+        .get_focus_guard()
+        .start_block(minutes)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 提前结束专注时段
+#[tauri::command]
+async fn stop_focus_block(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::focus::FocusStatus, AppError> {
+    Ok(state.capture_domain.get_focus_guard().stop_block().await)
+}
+
+/// 获取专注状态
+#[tauri::command]
+async fn get_focus_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::focus::FocusStatus, AppError> {
+    Ok(state.capture_domain.get_focus_guard().status().await)
+}
+
+/// 获取某一天专注时段内拦截的干扰
+#[tauri::command]
+async fn get_deflected_distractions(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<storage::DeflectedDistractionRecord>, AppError> {
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_deflected_distractions_by_date(&date)
+        .await
+        .map_err(|e| AppError::from(format!("获取干扰拦截记录失败: {}", e)))
+}
+
+/// 获取某一天的休息记录
+#[tauri::command]
+async fn get_break_records(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<storage::BreakRecord>, AppError> {
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_break_records_by_date(&date)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 按子类别同义词表归一化历史时间线卡片的子类别
+///
+/// # 参数
+/// * `dry_run` - 只检查不写入（默认 true）
+#[tauri::command]
+async fn normalize_existing_subcategories(
+    state: tauri::State<'_, AppState>,
+    dry_run: Option<bool>,
+) -> Result<domains::subcategories::SubcategoryNormalizationReport, AppError> {
+    let dry_run = dry_run.unwrap_or(true);
+    if !dry_run {
+        state.system_domain.ensure_writable()?;
+    }
+    let synonyms = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .subcategory_synonyms
+        .unwrap_or_default();
+    let normalizer = domains::subcategories::SubcategoryNormalizer::new(&synonyms);
+    let db = state.storage_domain.get_db().await?;
+    domains::subcategories::normalize_existing(&db, &normalizer, dry_run)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 按当前应用画像规则重新分类历史时间线卡片
+///
+/// dry_run 为 true 时只统计会改动的类别，不写入
+#[tauri::command]
+async fn reclassify_history(
+    state: tauri::State<'_, AppState>,
+    dry_run: bool,
+) -> Result<domains::app_profiles::ReclassifyReport, AppError> {
+    if !dry_run {
+        state.system_domain.ensure_writable()?;
+    }
+    let rules = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .app_profiles
+        .unwrap_or_default();
+    let db = state.storage_domain.get_db().await?;
+    domains::app_profiles::reclassify_existing(&db, &rules, dry_run)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 打开 LLM 调试归档文件夹
+#[tauri::command]
+fn open_llm_debug_folder() -> Result<(), AppError> {
+    let dir = llm::debug_archive::archive_dir().ok_or("LLM 调试归档目录尚未初始化")?;
+    info!("打开 LLM 调试归档文件夹: {:?}", dir);
+    open_folder_in_explorer(&dir).map_err(AppError::from)
+}
+
+/// 获取会话的分钟级活动序列（用于在视频进度条下方绘制强度色带）
+#[tauri::command]
+async fn get_session_activity_series(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<domains::metrics::SessionActivitySeries, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::metrics::get_session_activity_series(&db, session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 会话的时间线卡片（会话详情中与视频播放联动）
+#[tauri::command]
+async fn get_session_cards(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Vec<storage::TimelineCardRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    let (_, cards) = domains::playback::load_session_cards(&db, session_id).await?;
+    Ok(cards)
+}
+
+/// 视频播放位置 → 时间线卡片（拖动进度条时高亮当前卡片）
+#[tauri::command]
+async fn map_time_to_card(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    video_position_secs: f64,
+) -> Result<domains::playback::CardAtPosition, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    let (session, cards) = domains::playback::load_session_cards(&db, session_id).await?;
+    let mapping = session_video_mapping(&state, &session).await;
+    Ok(domains::playback::find_card_at(
+        &session,
+        &cards,
+        &mapping,
+        video_position_secs,
+    ))
+}
+
+/// 时间线卡片 → 视频播放位置（点击卡片时跳转视频）
+#[tauri::command]
+async fn map_card_to_time(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    card_id: i64,
+) -> Result<domains::playback::CardVideoRange, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    let (session, cards) = domains::playback::load_session_cards(&db, session_id).await?;
+    let card = cards
+        .iter()
+        .find(|c| c.id == Some(card_id))
+        .ok_or_else(|| format!("会话 {} 中没有卡片 {}", session_id, card_id))?;
+    let mapping = session_video_mapping(&state, &session).await;
+    Ok(domains::playback::card_video_range(
+        &session, card, &mapping,
+    ))
+}
+
+/// 时间线卡片溯源：生成卡片的分段、LLM 调用、模型、提示词版本和视频位置
+#[tauri::command]
+async fn get_card_provenance(
+    state: tauri::State<'_, AppState>,
+    card_id: i64,
+) -> Result<domains::provenance::CardProvenance, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    let card = db
+        .get_timeline_card(card_id)
+        .await
+        .map_err(|e| format!("获取时间线卡片失败: {}", e))?
+        .ok_or_else(|| format!("卡片 {} 不存在", card_id))?;
+    let session = db
+        .get_session(card.session_id)
+        .await
+        .map_err(|e| format!("获取会话失败: {}", e))?;
+    let segments = db
+        .get_video_segments_by_session(card.session_id)
+        .await
+        .map_err(|e| format!("获取视频分段失败: {}", e))?;
+    let calls = db
+        .get_llm_calls_by_session(card.session_id)
+        .await
+        .map_err(|e| format!("获取 LLM 调用记录失败: {}", e))?;
+    let mapping = session_video_mapping(&state, &session).await;
+
+    Ok(domains::provenance::build_card_provenance(
+        &session, &card, &segments, &calls, &mapping,
+    ))
+}
+
+/// 获取待复核的低置信度卡片
+#[tauri::command]
+async fn get_cards_needing_review(
+    state: tauri::State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<storage::TimelineCardRecord>, AppError> {
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_cards_needing_review(limit.unwrap_or(50))
+        .await
+        .map_err(|e| AppError::from(format!("获取待复核卡片失败: {}", e)))
+}
+
+/// 确认或纠正待复核的卡片，纠正结果会保存为后续分析的示例
+#[tauri::command]
+async fn resolve_card_review(
+    state: tauri::State<'_, AppState>,
+    card_id: i64,
+    decision: domains::review::ReviewDecision,
+) -> Result<storage::TimelineCardRecord, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::review::apply_review(&db, card_id, decision)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 导出站会笔记（时长最多的活动、专注时长、阻碍）
+///
+/// # 参数
+/// * `date` - 日期 (YYYY-MM-DD)，不提供则为昨天
+#[tauri::command]
+async fn export_standup_note(
+    state: tauri::State<'_, AppState>,
+    date: Option<String>,
+) -> Result<domains::standup::StandupNote, AppError> {
+    let date = date.unwrap_or_else(|| {
+        (storage::local_now() - chrono::Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+    let db = state.storage_domain.get_db().await?;
+    domains::standup::export_standup_note(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天的文字版时间线（纯文本或 Markdown，供读屏软件和其他工具使用）
+///
+/// # 参数
+/// * `date` - 日期 (YYYY-MM-DD)，不提供则为当天
+/// * `verbosity` - 详细程度 (brief/normal/detailed)，默认 normal
+/// * `format` - 输出格式 (text/markdown)，默认 markdown
+#[tauri::command]
+async fn get_day_timeline_text(
+    state: tauri::State<'_, AppState>,
+    date: Option<String>,
+    verbosity: Option<String>,
+    format: Option<String>,
+) -> Result<String, AppError> {
+    let date = date.unwrap_or_else(|| storage::local_now().format("%Y-%m-%d").to_string());
+    let verbosity = verbosity
+        .as_deref()
+        .map(domains::day_text::TimelineVerbosity::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let format = format
+        .as_deref()
+        .map(domains::day_text::TimelineTextFormat::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let db = state.storage_domain.get_db().await?;
+    domains::day_text::get_day_timeline_text(&db, &date, verbosity, format)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 为会议卡片生成会议纪要（密集抽帧后识别幻灯片标题、参会人和议程）
+#[tauri::command]
+async fn generate_meeting_notes(
+    state: tauri::State<'_, AppState>,
+    card_id: i64,
+) -> Result<llm::MeetingNotes, AppError> {
+    state.system_domain.ensure_writable()?;
+    domains::meetings::generate_for_card(
+        &state.storage_domain,
+        state.analysis_domain.get_llm_handle(),
+        card_id,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 根据最近几周的历史建议一周的日程（会议日、最佳深度工作时段）
+///
+/// # 参数
+/// * `week_start` - 计划所在周的任意一天 (YYYY-MM-DD)，不提供时为下周
+#[tauri::command]
+async fn suggest_week_plan(
+    state: tauri::State<'_, AppState>,
+    week_start: Option<String>,
+) -> Result<domains::week_plan::WeekPlan, AppError> {
+    let date = week_start
+        .map(|d| {
+            chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+                .map_err(|e| format!("日期格式错误: {}", e))
+        })
+        .transpose()?;
+    let today = storage::local_now().date_naive();
+    let db = state.storage_domain.get_db().await?;
+    domains::week_plan::suggest_week_plan(
+        &db,
+        state.analysis_domain.get_llm_handle(),
+        domains::week_plan::plan_week_start(date, today),
+        today,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 把周计划的日程块导出为 ICS 日历文件
+#[tauri::command]
+async fn export_week_plan_ics(
+    blocks: Vec<domains::week_plan::PlanBlock>,
+    path: String,
+) -> Result<(), AppError> {
+    let ics = domains::week_plan::to_ics(&blocks, storage::local_now().naive_utc());
+    tokio::fs::write(&path, ics)
+        .await
+        .map_err(|e| AppError::from(format!("写入日历文件失败: {}", e)))
+}
+
+/// 导出会话视频的字幕（时间线卡片和分段描述，format 为 srt 或 vtt）
+#[tauri::command]
+async fn export_session_subtitles(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    format: domains::subtitles::SubtitleFormat,
+) -> Result<String, AppError> {
+    domains::subtitles::session_subtitles(&state.storage_domain, session_id, format)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 导出某天的离线包（会话视频、时间线数据和离线 HTML 查看器打包为 zip）
+///
+/// # 参数
+/// * `date` - 日期 (YYYY-MM-DD)
+/// * `path` - 保存的 zip 文件路径
+#[tauri::command]
+async fn export_day_bundle(
+    state: tauri::State<'_, AppState>,
+    date: String,
+    path: String,
+) -> Result<domains::day_bundle::BundleReport, AppError> {
+    domains::day_bundle::export_day_bundle(
+        &state.storage_domain,
+        &date,
+        std::path::Path::new(&path),
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 导出会话的脱敏诊断包（马赛克截图和时间元数据），用于附在问题反馈中
+///
+/// # 参数
+/// * `session_id` - 会话ID
+/// * `path` - 保存的 zip 文件路径
+#[tauri::command]
+async fn export_diagnostics_bundle(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    path: String,
+) -> Result<domains::diagnostics::DiagnosticsReport, AppError> {
+    validate_session_id(session_id)?;
+    domains::diagnostics::export_diagnostics_bundle(
+        &state.storage_domain,
+        session_id,
+        std::path::Path::new(&path),
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 导出可分享的会话（视频和 HTML 页面），可模糊画面区域、隐去含敏感关键词的摘要
+///
+/// # 参数
+/// * `session_id` - 会话ID
+/// * `options` - 脱敏选项
+/// * `output_dir` - 导出目录，不提供时导出到下载目录
+#[tauri::command]
+async fn export_shareable_session(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    options: domains::share::ShareOptions,
+    output_dir: Option<String>,
+) -> Result<domains::share::ShareReport, AppError> {
+    validate_session_id(session_id)?;
+    let output_dir = match output_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => app
+            .path()
+            .download_dir()
+            .map_err(|e| e.to_string())?
+            .join(format!("screen-analyzer-session-{}", session_id)),
+    };
+    domains::share::export_shareable_session(
+        &state.storage_domain,
+        session_id,
+        &options,
+        &output_dir,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 把字幕轨封装进会话视频
+#[tauri::command]
+async fn embed_session_subtitles(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    domains::subtitles::embed_into_video(&state.storage_domain, session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 写入外部来源的活动事件（手机屏幕使用时间导出、浏览器扩展等）
+#[tauri::command]
+async fn ingest_external_events(
+    state: tauri::State<'_, AppState>,
+    events: Vec<domains::external_events::ExternalEventInput>,
+) -> Result<domains::external_events::IngestResult, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::external_events::ingest(&db, &events)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 导入 RescueTime / Toggl 导出的 CSV 历史记录（source 为 rescuetime 或 toggl）
+#[tauri::command]
+async fn import_activity_history(
+    state: tauri::State<'_, AppState>,
+    source: String,
+    path: String,
+) -> Result<domains::history_import::ImportReport, AppError> {
+    state.system_domain.ensure_writable()?;
+    let source = domains::history_import::ImportSource::parse(&source)?;
+    let db = state.storage_domain.get_db().await?;
+    domains::history_import::import_file(&db, source, &path)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天按域名的浏览时长（来自浏览器扩展上报的标签页）
+#[tauri::command]
+async fn get_domain_stats(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<domains::browser::DomainStat>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::browser::day_domain_stats(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取日期范围内的外部活动事件
+#[tauri::command]
+async fn get_external_events(
+    state: tauri::State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<storage::ExternalEventRecord>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::external_events::load_events(&db, &start_date, &end_date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天没有会话的时间段及原因（关机、应用未运行等）
+#[tauri::command]
+async fn get_day_gaps(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<domains::gaps::IdleGap>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::gaps::get_day_gaps(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取某天待复核的内容：低置信度卡片、未打标签的会话和记录空档
+#[tauri::command]
+async fn get_day_review(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<domains::day_review::DayReview, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::day_review::build_day_review(&db, &date)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 提交当日复核：批量应用纠正和标签，重新生成当日总结并锁定
+#[tauri::command]
+async fn submit_day_review(
+    state: tauri::State<'_, AppState>,
+    date: String,
+    decisions: domains::day_review::DayReviewDecisions,
+) -> Result<domains::day_review::DayReviewResult, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    let outcome = domains::day_review::apply_day_review(&db, decisions).await?;
+
+    // 已锁定的总结需要先解锁，才能按新的复核结果重新生成
+    db.set_day_summary_finalized(&date, false)
+        .await
+        .map_err(|e| format!("解锁每日总结失败: {}", e))?;
+    let llm_handle = state.analysis_domain.get_llm_handle();
+    let generator = domains::summary::SummaryGenerator::with_llm(db.clone(), llm_handle);
+    let mut summary = generator.generate_day_summary(&date, true).await?;
+    db.set_day_summary_finalized(&date, true)
+        .await
+        .map_err(|e| format!("锁定每日总结失败: {}", e))?;
+    summary.finalized = true;
+
+    Ok(domains::day_review::DayReviewResult { outcome, summary })
+}
+
+/// 会话视频的时间换算参数（优先使用视频生成时记录的元数据，旧视频回退到当前设置）
+async fn session_video_mapping(
+    state: &AppState,
+    session: &storage::Session,
+) -> domains::playback::VideoTimeMapping {
+    domains::playback::session_mapping(session, state.storage_domain.get_settings()).await
+}
+
+/// 生成演示数据（仅开发模式，无需录屏和 LLM 即可调试前端）
+#[tauri::command]
+async fn generate_demo_data(
+    state: tauri::State<'_, AppState>,
+    days: u32,
+) -> Result<domains::demo::DemoDataReport, AppError> {
+    if !cfg!(debug_assertions) {
+        return Err("演示数据仅在开发模式下可用".into());
+    }
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    let video_processor = state.analysis_domain.get_video_processor();
+    domains::demo::generate_demo_data(&db, Some(video_processor.as_ref()), days)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 删除生成的演示数据（演示会话、卡片、占位视频和演示每日总结）
+#[tauri::command]
+async fn remove_demo_data(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::demo::DemoCleanupReport, AppError> {
+    state.system_domain.ensure_writable()?;
+    let db = state.storage_domain.get_db().await?;
+    domains::demo::remove_demo_data(&db)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取当前数据目录（截图、视频和默认数据库所在位置）
+#[tauri::command]
+fn get_data_directory(app: tauri::AppHandle) -> Result<String, AppError> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(storage::data_location::resolve_data_dir(&app_dir)
+        .to_string_lossy()
+        .to_string())
+}
+
+/// 对比两份数据库快照中的会话和卡片
+///
+/// # 参数
+/// * `before` - 旧快照（数据库文件，或包含 data.db 的目录）
+/// * `after` - 新快照，为空时使用当前数据目录中的数据库
+#[tauri::command]
+async fn diff_database_snapshots(
+    app: tauri::AppHandle,
+    before: String,
+    after: Option<String>,
+) -> Result<domains::snapshot_diff::SnapshotDiff, AppError> {
+    let after = match after.filter(|path| !path.trim().is_empty()) {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+            storage::data_location::resolve_data_dir(&app_dir)
+        }
+    };
+    domains::snapshot_diff::diff_database_files(std::path::Path::new(&before), &after)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 检查目录是否适合作为数据目录（可写、写入延迟、可用空间）
+#[tauri::command]
+async fn check_data_directory(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<storage::data_location::DataDirCheck, AppError> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let current = storage::data_location::resolve_data_dir(&app_dir);
+    let target = std::path::PathBuf::from(path);
+    tokio::task::spawn_blocking(move || {
+        storage::data_location::check_data_directory(&target, &current)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 迁移数据目录
+///
+/// 先校验目标目录，再复制已有文件（通过 data-migration-progress 事件推送进度），
+/// 然后记录待完成的迁移并重启；数据库文件和路径在下次启动时迁移
+#[tauri::command]
+async fn set_data_directory(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<(), AppError> {
+    use storage::data_location::{check_data_directory, copy_data, DataLocation};
+
+    state.system_domain.ensure_writable()?;
+
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let mut location = DataLocation::load(&app_dir);
+    if location.pending_from.is_some() {
+        return Err("上一次数据目录迁移尚未完成，请重启应用后再试".into());
+    }
+    let current = location.resolve(&app_dir);
+    let target = std::path::PathBuf::from(&path);
+
+    let check = {
+        let (target, current) = (target.clone(), current.clone());
+        tokio::task::spawn_blocking(move || check_data_directory(&target, &current))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    if !check.ok {
+        return Err(check.message.into());
+    }
+
+    info!("开始迁移数据目录: {:?} -> {:?}", current, target);
+    {
+        let (from, to, handle) = (current.clone(), target.clone(), app.clone());
+        tokio::task::spawn_blocking(move || {
+            let progress = copy_data(&from, &to, |progress| {
+                let _ = handle.emit("data-migration-progress", progress);
+            })?;
+            // 新目录写入存储标记，之后可据此判断网络共享是否在线
+            for dir in ["frames", "videos"] {
+                let dir = to.join(dir);
+                std::fs::create_dir_all(&dir)?;
+                storage::availability::ensure_marker(&dir, true)?;
+            }
+            anyhow::Ok(progress)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("复制数据失败: {}", e))?;
+    }
+
+    location.data_dir = if target == app_dir {
+        None
+    } else {
+        Some(target)
+    };
+    location.pending_from = Some(current);
+    location
+        .save(&app_dir)
+        .map_err(|e| format!("保存数据目录失败: {}", e))?;
+
+    info!("文件复制完成，重启应用以完成数据库迁移");
+    app.restart()
+}
+
+/// 获取旧视频转码任务的状态
+#[tauri::command]
+async fn get_video_reencode_status() -> Result<domains::reencode::ReencodeStatus, AppError> {
+    Ok(domains::reencode::current_status())
+}
+
+/// 获取截图/视频压缩统计和存储预测
+///
+/// # 参数
+/// * `period` - 统计周期 (day/week/month)
+/// * `date` - 锚点日期 (YYYY-MM-DD)，不提供则为当天
+#[tauri::command]
+async fn get_compression_stats(
+    state: tauri::State<'_, AppState>,
+    period: String,
+    date: Option<String>,
+) -> Result<domains::storage_usage::CompressionStats, AppError> {
+    let period = domains::metrics::StatsPeriod::parse(&period)?;
+    let anchor = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式错误: {}", e))?,
+        None => storage::local_now().date_naive(),
+    };
+
+    let storage_stats = state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .get_storage_stats()
+        .await
+        .map_err(|e| e.to_string())?;
+    let frames_dir = state.capture_domain.get_capture().frames_dir();
+    let db = state.storage_domain.get_db().await?;
+    domains::storage_usage::get_compression_stats(
+        &db,
+        &frames_dir,
+        period,
+        anchor,
+        storage_stats.retention_days,
+        storage_stats.total_size,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 预览保留策略会删除的会话
+///
+/// # 参数
+/// * `retention_days` - 全局保留天数，不提供则使用当前设置
+/// * `rules` - 类别保留规则，不提供则使用当前设置（用于预览尚未保存的修改）
+#[tauri::command]
+async fn preview_retention_policy(
+    state: tauri::State<'_, AppState>,
+    retention_days: Option<i64>,
+    rules: Option<Vec<models::CategoryRetentionRule>>,
+) -> Result<storage::cleaner::RetentionPreview, AppError> {
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .preview_cleanup(retention_days, rules)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 预览下次清理时会删除的空会话
+///
+/// 空会话指开始超过 24 小时、没有时间线卡片、没有截图记录、视频也不存在的会话
+/// （通常是崩溃或截屏权限失败留下的），置顶和已归档的会话不计入
+#[tauri::command]
+async fn preview_garbage_sessions(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<storage::cleaner::GarbageSession>, AppError> {
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .preview_garbage_sessions()
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 置顶会话（置顶的会话及其视频不会被自动清理）
+#[tauri::command]
+async fn pin_session(state: tauri::State<'_, AppState>, session_id: i64) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    info!("置顶会话: {}", session_id);
+
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .set_session_pinned(session_id, true)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 取消置顶会话
+#[tauri::command]
+async fn unpin_session(state: tauri::State<'_, AppState>, session_id: i64) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    info!("取消置顶会话: {}", session_id);
+
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .set_session_pinned(session_id, false)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 获取所有置顶会话
+#[tauri::command]
+async fn get_pinned_sessions(state: tauri::State<'_, AppState>) -> Result<Vec<Session>, AppError> {
+    state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_pinned_sessions()
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// 从冷存储归档恢复会话视频（用于回放或重新分析），返回本地视频路径
+#[tauri::command]
+async fn restore_from_archive(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<String, AppError> {
+    state.system_domain.ensure_writable()?;
+    validate_session_id(session_id)?;
+    info!("从归档恢复会话: {}", session_id);
+
+    let session = state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_session_detail(session_id)
+        .await
+        .map_err(|e| format!("获取会话详情失败: {}", e))?
+        .session;
+
+    state
+        .storage_domain
+        .get_cleaner()
+        .await?
+        .restore_archived_session(&session)
+        .await
+        .map_err(|e| AppError::from(format!("从归档恢复失败: {}", e)))
+}
+
+/// 截屏预览帧（通过 capture-preview-frame 事件推送到前端）
+#[derive(Clone, serde::Serialize)]
+struct CapturePreviewFrame {
+    timestamp: String,
+    width: u32,
+    height: u32,
+    /// data:image/jpeg;base64 格式的预览图
+    data_url: String,
+}
+
+/// 开始截屏预览（1 fps 低分辨率），用于在设置页保存前确认实际截取的画面
+///
+/// capture_settings 为设置页当前（可能未保存）的截屏配置，为空时使用已生效的配置
+#[tauri::command]
+async fn start_capture_preview(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    capture_settings: Option<models::CaptureSettings>,
+) -> Result<(), AppError> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    state
+        .capture_domain
+        .start_preview(capture_settings, move |preview| {
+            let frame = CapturePreviewFrame {
+                timestamp: preview.timestamp.to_rfc3339(),
+                width: preview.width,
+                height: preview.height,
+                data_url: format!(
+                    "data:image/jpeg;base64,{}",
+                    general_purpose::STANDARD.encode(&preview.jpeg)
+                ),
+            };
+            let _ = app.emit("capture-preview-frame", frame);
+        });
+    Ok(())
+}
+
+/// 停止截屏预览
+#[tauri::command]
+async fn stop_capture_preview(state: tauri::State<'_, AppState>) -> Result<(), AppError> {
+    state.capture_domain.stop_preview();
+    Ok(())
+}
+
+/// 获取截屏耗时统计（多屏截取、合成与编码的耗时）
+#[tauri::command]
+async fn get_capture_latency_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<capture::CaptureLatencyStats, AppError> {
+    Ok(state.capture_domain.get_capture().latency_stats())
+}
+
+/// 获取当前屏幕列表（用于选择私人屏幕）
+#[tauri::command]
+async fn get_displays(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<capture::source::DisplayBounds>, AppError> {
+    let capture = state.capture_domain.get_capture().clone();
+    let displays = tokio::task::spawn_blocking(move || capture.screens())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(displays)
+}
+
+/// 截图自检：截取测试画面，检查本应用窗口是否出现在截图中，并给出配置建议
+#[tauri::command]
+async fn verify_capture_hygiene(
+    state: tauri::State<'_, AppState>,
+) -> Result<domains::capture_hygiene::CaptureHygieneReport, AppError> {
+    domains::capture_hygiene::verify_capture_hygiene(state.capture_domain.get_capture())
+        .await
+        .map_err(AppError::from)
+}
+
+/// 单帧快速分析：把一张截图发给视觉模型，返回类别、标题和描述
+///
+/// path 为空时立即截取当前屏幕
+#[tauri::command]
+async fn analyze_single_frame(
+    state: tauri::State<'_, AppState>,
+    path: Option<String>,
+) -> Result<domains::frame_analysis::FrameAnalysis, AppError> {
+    let llm_handle = state.analysis_domain.get_llm_handle();
+    let result = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => domains::frame_analysis::analyze_frame(llm_handle, &path).await,
+        None => {
+            domains::frame_analysis::analyze_current_screen(
+                llm_handle,
+                state.capture_domain.get_capture(),
+            )
+            .await
+        }
+    };
+    result.map_err(AppError::from)
+}
+
+/// 服务商对比：用多个服务商分析同一会话视频，保存各自的输出、耗时和 token 用量
+#[tauri::command]
+async fn compare_providers(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+    providers: Vec<domains::provider_comparison::ComparisonCandidate>,
+) -> Result<domains::provider_comparison::ComparisonRun, AppError> {
+    state.system_domain.ensure_writable()?;
+    domains::provider_comparison::compare_providers(
+        &state.analysis_domain,
+        &state.storage_domain,
+        session_id,
+        providers,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// 获取会话的历次服务商对比及差异
+#[tauri::command]
+async fn get_provider_comparisons(
+    state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Vec<domains::provider_comparison::ComparisonRun>, AppError> {
+    let db = state.storage_domain.get_db().await?;
+    domains::provider_comparison::get_comparisons(&db, session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 立即分析指定视频：插到分析队列最前面并唤醒扫描任务
+#[tauri::command]
+async fn prioritize_video(state: tauri::State<'_, AppState>, path: String) -> Result<(), AppError> {
+    state.system_domain.ensure_writable()?;
+
+    let videos_dir = state
+        .analysis_domain
+        .get_video_processor()
+        .output_dir
+        .clone();
+    let video_path = PathBuf::from(&path);
+    if video_path.parent() != Some(videos_dir.as_path())
+        || video_path.extension().and_then(|s| s.to_str()) != Some("mp4")
+    {
+        return Err(format!("不是视频目录中的视频: {}", path).into());
+    }
+    if !video_path.is_file() {
+        return Err(format!("视频不存在: {}", path).into());
+    }
+
+    info!("立即分析视频: {}", path);
+    state.analysis_domain.prioritize_video(video_path);
+    Ok(())
+}
+
+/// 尚未分析的视频（最新的在前），供积压面板选择立即分析
+#[tauri::command]
+async fn get_unanalyzed_videos(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<domains::analysis::UnanalyzedVideo>, AppError> {
+    let prioritized = state.analysis_domain.prioritized_videos();
+    let mut videos: Vec<domains::analysis::UnanalyzedVideo> = list_unanalyzed_videos(&state)
+        .await?
+        .into_iter()
+        .map(|path| domains::analysis::UnanalyzedVideo {
+            start: path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(parse_video_window_from_stem)
+                .map(|(start, _)| start.format("%Y-%m-%d %H:%M").to_string()),
+            prioritized: prioritized.contains(&path),
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    videos.reverse();
+    Ok(videos)
+}
+
+/// 获取暂缓中等待分析的会话
+#[tauri::command]
+async fn get_pending_analyses(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<domains::analysis_hold::PendingAnalysis>, AppError> {
+    Ok(state.analysis_domain.get_analysis_hold().list())
+}
+
+/// 标记暂缓中的会话是否跳过分析
+#[tauri::command]
+async fn set_pending_analysis_skipped(
+    state: tauri::State<'_, AppState>,
+    id: u64,
+    skipped: bool,
+) -> Result<(), AppError> {
+    if !state
+        .analysis_domain
+        .get_analysis_hold()
+        .set_skipped(id, skipped)
+    {
+        return Err("该会话已开始处理".into());
+    }
+    info!(
+        "暂缓会话 {} 标记为{}",
+        id,
+        if skipped { "不分析" } else { "分析" }
+    );
+    Ok(())
+}
+
+/// 结束暂缓，立即处理会话
+#[tauri::command]
+async fn release_pending_analysis(
+    state: tauri::State<'_, AppState>,
+    id: u64,
+) -> Result<(), AppError> {
+    if !state.analysis_domain.get_analysis_hold().release_now(id) {
+        return Err("该会话已开始处理".into());
+    }
+    Ok(())
+}
+
+/// 运行性能基准测试（截屏、JPEG 编码、视频编码、数据库写入、LLM 往返）
+#[tauri::command]
+async fn run_benchmark(
+    state: tauri::State<'_, AppState>,
+    include_llm: Option<bool>,
+) -> Result<domains::benchmark::BenchmarkReport, AppError> {
+    use domains::benchmark;
+
+    info!("开始性能基准测试");
+    let mut report = benchmark::BenchmarkReport::default();
+    let capture = state.capture_domain.get_capture();
+    let work_dir = capture
+        .frames_dir()
+        .parent()
+        .map(|p| p.join("benchmark"))
+        .unwrap_or_else(|| std::env::temp_dir().join("screen-analyzer-benchmark"));
+
+    // 截屏与 JPEG 编码
+    let sample = match capture.benchmark(benchmark::CAPTURE_ROUNDS).await {
+        Ok((result, sample)) => {
+            report.capture = Some(result);
+            Some(sample)
+        }
+        Err(e) => {
+            report.errors.push(format!("截屏: {}", e));
+            None
+        }
+    };
+
+    // 视频编码（复用截屏测试的画面）
+    if let Some(sample) = sample {
+        match benchmark::benchmark_video(
+            state.analysis_domain.get_video_processor(),
+            &sample,
+            &work_dir.join("video"),
+        )
+        .await
+        {
+            Ok(result) => report.video = Some(result),
+            Err(e) => report.errors.push(format!("视频编码: {}", e)),
+        }
+    }
+
+    // 数据库写入
+    match benchmark::benchmark_database(&work_dir.join("database")).await {
+        Ok(result) => report.database = Some(result),
+        Err(e) => report.errors.push(format!("数据库写入: {}", e)),
+    }
+    let _ = tokio::fs::remove_dir(&work_dir).await;
+
+    // LLM 往返（使用已保存的配置发送一条简短的文本请求）
+    if include_llm.unwrap_or(true) {
+        let config = state.storage_domain.get_settings().get().await;
+        let provider = config.llm_provider.clone();
+        let started = std::time::Instant::now();
+        let result = match config.llm_config {
+            None => Err("LLM 未配置".to_string()),
+            Some(llm_config) => match provider.as_str() {
+                "openai" => {
+                    test_openai_text_api(serde_json::json!({
+                        "api_key": llm_config.api_key,
+                        "model": llm_config.model,
+                        "base_url": llm_config.base_url,
+                    }))
+                    .await
+                }
+                "claude" | "anthropic" => {
+                    test_claude_sdk_api(serde_json::json!({
+                        "model": llm_config.model,
+                        "auth_token": llm_config.auth_token,
+                        "base_url": llm_config.base_url,
+                    }))
+                    .await
+                }
+                "codex" => {
+                    test_codex_cli(llm_config.codex_config.unwrap_or(serde_json::json!({}))).await
+                }
+                _ => Err(format!("不支持的提供商: {}", provider)),
+            },
+        };
+        match result {
+            Ok(_) => {
+                report.llm = Some(benchmark::LlmBenchmark {
+                    provider,
+                    latency_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+            Err(e) => report.errors.push(format!("LLM: {}", e)),
+        }
+    }
+
+    report.suggestions = benchmark::suggestions(&report);
+    info!(
+        "性能基准测试完成，{} 项失败，{} 条建议",
+        report.errors.len(),
+        report.suggestions.len()
+    );
+    Ok(report)
+}
+
+// ==================== 辅助函数 ====================
+
+/// 更新后台初始化进度并通知前端
+fn report_startup(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    stage: StartupStage,
+    stage_state: StageState,
+    message: Option<String>,
+) {
+    let progress = state
+        .system_domain
+        .get_startup_progress()
+        .update(stage, stage_state, message);
+    let _ = app.emit("startup-progress", progress);
+}
+
+/// 进入或退出离开模式：暂停或恢复截屏、记录离开时段，并按需暂停自动清理
+async fn apply_away_mode(
+    state: &AppState,
+    enabled: bool,
+    freeze_retention: bool,
+) -> Result<AwayModeState, String> {
+    let settings = state.storage_domain.get_settings();
+    let current = settings.get().await.away_mode.unwrap_or_default();
+    let timestamp = storage::local_now();
+    let away = if enabled {
+        AwayModeState {
+            active: true,
+            since: current.since.filter(|_| current.active).or(Some(timestamp)),
+            freeze_retention,
+        }
+    } else {
+        AwayModeState::default()
+    };
+    settings
+        .update(AppConfig {
+            away_mode: Some(away.clone()),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("保存离开模式失败: {}", e))?;
+
+    state.capture_domain.get_scheduler().set_paused(enabled);
+    state
+        .system_domain
+        .get_status_handle()
+        .set_capturing(!enabled)
+        .await;
+
+    // 离开模式接管手动暂停；暂停记录器会忽略重复的暂停和未开始的恢复
+    use domains::capture_pauses::PauseReason;
+    state
+        .event_bus
+        .publish(event_bus::AppEvent::CaptureResumed {
+            reason: PauseReason::User,
+            timestamp,
+        });
+    let reason = PauseReason::Away;
+    if enabled {
+        info!("进入离开模式");
+        state
+            .event_bus
+            .publish(event_bus::AppEvent::CapturePaused { reason, timestamp });
+    } else {
+        info!("退出离开模式");
+        state
+            .event_bus
+            .publish(event_bus::AppEvent::CaptureResumed { reason, timestamp });
+    }
+
+    if let Ok(cleaner) = state.storage_domain.get_cleaner().await {
+        cleaner
+            .set_retention_frozen(away.active && away.freeze_retention)
+            .await;
+    }
+
+    Ok(away)
+}
+
+/// 汇总当前的实时状态（只统计本设备当天的活动）
+async fn collect_live_state(state: &AppState) -> Result<domains::live::LiveState, String> {
+    let now = storage::local_now();
+    let is_capturing = state
+        .system_domain
+        .get_status_handle()
+        .get()
+        .await
+        .is_capturing;
+    let active_minutes = state
+        .capture_domain
+        .get_break_reminder()
+        .status()
+        .await
+        .active_minutes;
+
+    let db = state.storage_domain.get_db().await?;
+    let (device_name, _) = storage::get_device_info();
+    let spans: Vec<_> = domains::metrics::load_activity_spans(
+        &db,
+        &now.date_naive().format("%Y-%m-%d").to_string(),
+    )
+    .await?
+    .into_iter()
+    .filter(|span| span.device.is_empty() || span.device == device_name)
+    .collect();
+
+    let mut snapshot = domains::live::build_live_state(
+        now,
+        &spans,
+        state
+            .capture_domain
+            .get_scheduler()
+            .current_window_start(now),
+        is_capturing,
+        active_minutes,
+    );
+    snapshot.live_activity = domains::live_activity::current();
+    Ok(snapshot)
+}
+
+/// 确保会话视频在本地可用（使用远程存储后端时本地副本可能已被清理），返回本地路径
+async fn ensure_local_video(state: &AppState, video_path: &str) -> Result<String, String> {
+    state
+        .storage_domain
+        .get_media()
+        .ensure_local_video(Path::new(video_path))
+        .await
+        .map(|local| local.to_string_lossy().to_string())
+        .map_err(|e| format!("获取视频文件失败: {}", e))
+}
+
+/// 处理历史图片，生成视频并清理
+async fn process_historical_frames(state: &AppState) -> Result<(), String> {
+    // 仅在 SQLite 模式下处理历史图片
+    let db = state.storage_domain.get_db().await?;
+    if !db.is_sqlite() {
+        info!("跳过历史图片处理（仅 SQLite 模式支持）");
+        return Ok(());
+    }
+
+    info!("开始处理历史图片");
+    let capture_interval = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .capture_interval;
+
+    // 查询所有会话，筛选出未生成视频的
+    let all_sessions = db.get_all_sessions().await.map_err(|e| e.to_string())?;
+
+    let sessions_without_video: Vec<_> = all_sessions
+        .into_iter()
+        // 已归档会话的视频在归档目标中，本地已没有截图
+        .filter(|s| !s.archived)
+        .filter(|s| s.video_path.is_none() || s.video_path.as_ref().map_or(false, |p| p.is_empty()))
+        .take(10)
+        .collect();
+
+    info!("找到 {} 个未生成视频的会话", sessions_without_video.len());
+
+    for session in sessions_without_video {
+        let session_id = match session.id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        info!(
+            "处理会话 {}: {} - {}",
+            session_id, session.start_time, session.end_time
+        );
+
+        // 获取该会话的所有帧
+        let frames = match db.get_frames_by_session(session_id).await {
+            Ok(frames) => frames,
+            Err(e) => {
+                error!("获取会话 {} 的帧失败: {}", session_id, e);
+                continue;
+            }
+        };
+
+        if !frames.is_empty() {
+            let frame_paths: Vec<String> = frames.into_iter().map(|f| f.file_path).collect();
+
+            if !frame_paths.is_empty() {
+                info!(
+                    "为会话 {} 生成视频，共 {} 帧",
+                    session_id,
+                    frame_paths.len()
+                );
+
+                // 生成视频
+                let video_config = crate::video::VideoConfig::default();
+                let video_filename = format!(
+                    "{}.mp4",
+                    capture::scheduler::window_file_label(session.start_time, session.end_time)
+                );
+
+                let video_path_buf = state
+                    .analysis_domain
+                    .get_video_processor()
+                    .output_dir
+                    .join(&video_filename);
+                match state
+                    .analysis_domain
+                    .get_video_processor()
+                    .create_summary_video(frame_paths.clone(), &video_path_buf, &video_config)
+                    .await
+                {
+                    Ok(video_result) => {
+                        info!(
                             "视频生成成功: {} ({}字节, {}ms)",
                             video_path_buf.display(),
                             video_result.file_size,
                             video_result.processing_time_ms
                         );
+                        // 历史图片未抽帧，每帧间隔即截屏间隔
+                        video::VideoMetadata::new(
+                            &video_config,
+                            capture_interval.max(1) as f64,
+                            frame_paths.len(),
+                        )
+                        .with_speed_runs(video_result.speed_runs.clone())
+                        .save_or_warn(&video_path_buf)
+                        .await;
+                        state
+                            .analysis_domain
+                            .get_video_processor()
+                            .publish(&video_path_buf)
+                            .await;
 
                         let video_path_str = video_path_buf.to_string_lossy();
                         // 更新数据库中的视频路径
@@ -2173,10 +5176,21 @@ pub fn run() {
     // 初始化日志系统（带前端推送功能）
     logger::init_with_broadcaster(log_broadcaster.clone()).expect("Failed to initialize logger");
 
+    let headless_mode = headless::is_headless();
+
     tauri::Builder::default()
         .setup(move |app| {
             info!("初始化屏幕活动分析器...");
 
+            // 主窗口在这里按配置创建，无界面模式下不创建
+            if headless_mode {
+                info!("以无界面模式运行，不创建主窗口");
+                #[cfg(target_os = "macos")]
+                app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+            } else if let Some(window_config) = app.config().app.windows.first().cloned() {
+                tauri::WebviewWindowBuilder::from_config(app.handle(), &window_config)?.build()?;
+            }
+
             // 设置 PATH 环境变量，确保能找到 claude 等命令
             // macOS 应用运行时不会继承 shell 的 PATH，需要手动添加常见路径
             #[cfg(target_os = "macos")]
@@ -2380,17 +5394,69 @@ pub fn run() {
 
             let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
 
+            // 获取实例锁，防止多个实例同时写入同一份数据
+            let instance_lock = Arc::new(
+                instance::InstanceLock::acquire(&app_dir).map_err(|e| e.to_string())?,
+            );
+
+            // 点击深度链接启动的次实例：把链接交给主实例后直接退出
+            if !instance_lock.is_primary() {
+                if let Some(url) = domains::deep_link::from_args(std::env::args()) {
+                    match domains::deep_link::hand_off(&app_dir, &url) {
+                        Ok(()) => info!("已将深度链接转交给运行中的实例: {}", url),
+                        Err(e) => error!("转交深度链接失败: {}", e),
+                    }
+                    std::process::exit(0);
+                }
+            }
+
+            // 深度链接（macOS 由系统直接交给运行中的实例）
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                #[cfg(any(windows, target_os = "linux"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    warn!("注册深度链接协议失败: {}", e);
+                }
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    if let Some(url) = urls.first() {
+                        open_deep_link(app.handle(), url.as_str());
+                    }
+                }
+                let link_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    if let Some(url) = event.urls().first() {
+                        open_deep_link(&link_handle, url.as_str());
+                    }
+                });
+            }
+
+            // 上次删除全部数据时被占用的文件（如数据库）在打开数据库前删除
+            if instance_lock.is_primary() {
+                domains::data_reset::finish_pending_reset(&app_dir);
+            }
+
+            // 数据目录（可迁移到其他磁盘），打开数据库前先补齐未完成的迁移
+            let data_dir = storage::data_location::prepare_data_dir(&app_dir);
+            info!("数据目录: {:?}", data_dir);
+
             // 创建必要的目录
-            let frames_dir = app_dir.join("frames");
-            let videos_dir = app_dir.join("videos");
-            let temp_dir = app_dir.join("temp");
+            let frames_dir = data_dir.join("frames");
+            let videos_dir = data_dir.join("videos");
+            let temp_dir = data_dir.join("temp");
 
             std::fs::create_dir_all(&frames_dir).map_err(|e| e.to_string())?;
             std::fs::create_dir_all(&videos_dir).map_err(|e| e.to_string())?;
             std::fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
 
-            // 初始化运行时（仅用于初始化，不用于运行 Actor）
-            let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+            // 存储标记用于检测网络共享是否在线；自定义目录为空时可能是共享未挂载，不自动创建
+            let is_default_dir = data_dir == app_dir;
+            for dir in [&frames_dir, &videos_dir] {
+                match storage::availability::ensure_marker(dir, is_default_dir) {
+                    Ok(true) => {}
+                    Ok(false) => warn!("数据目录 {:?} 可能尚未挂载，清理和分析将等待其可用", dir),
+                    Err(e) => warn!("写入存储标记失败 {:?}: {}", dir, e),
+                }
+            }
 
             let (
                 state,
@@ -2401,7 +5467,11 @@ pub fn run() {
                 db_config_to_load,
                 frames_dir_clone,
                 videos_dir_clone,
-            ) = runtime.block_on(async {
+                allow_read_only_secondary,
+            ) = tauri::async_runtime::block_on(async {
+                // 这里只组装各组件，不做耗时操作（屏幕枚举、数据库连接等在后台任务中进行），
+                // 让窗口尽快显示；后台初始化进度通过 startup-progress 事件通知前端
+
                 // 先初始化设置管理器，以便读取数据库配置
                 let settings = Arc::new(
                     SettingsManager::new(app_dir.join("config.json"))
@@ -2411,6 +5481,11 @@ pub fn run() {
 
                 // 读取初始配置
                 let initial_config = settings.get().await;
+                let allow_read_only_secondary = initial_config
+                    .instance_settings
+                    .clone()
+                    .unwrap_or_default()
+                    .allow_read_only_secondary;
 
                 // 准备数据库配置（延迟初始化）
                 let db_config_to_load =
@@ -2422,12 +5497,33 @@ pub fn run() {
                         None
                     };
 
-                // 初始化截屏管理器
+                // 初始化截屏管理器（屏幕在后台任务或首次截屏时枚举）
                 let capture =
                     Arc::new(ScreenCapture::new(frames_dir.clone()).expect("截屏管理器初始化失败"));
 
+                // 截图时遮盖本应用窗口（窗口隐藏或最小化时不遮盖）
+                if let Some(window) = app.get_webview_window("main") {
+                    capture.set_own_window_locator(Arc::new(move || {
+                        if !window.is_visible().unwrap_or(false)
+                            || window.is_minimized().unwrap_or(false)
+                        {
+                            return None;
+                        }
+                        let scale = window.scale_factor().ok()?;
+                        let position = window.outer_position().ok()?.to_logical::<f64>(scale);
+                        let size = window.outer_size().ok()?.to_logical::<f64>(scale);
+                        Some(capture::WindowRect {
+                            x: position.x.round() as i32,
+                            y: position.y.round() as i32,
+                            width: size.width.round() as u32,
+                            height: size.height.round() as u32,
+                        })
+                    }));
+                }
+
                 // 创建共享的 HTTP 客户端（用于 LLM API 调用，复用连接池提升性能）
-                let http_client = reqwest::Client::builder()
+                domains::local_only::set_enabled(initial_config.local_only.unwrap_or(false));
+                let http_client = domains::local_only::http_client_builder()
                     .timeout(std::time::Duration::from_secs(300))
                     .pool_max_idle_per_host(10)
                     .build()
@@ -2448,18 +5544,36 @@ pub fn run() {
                 let llm_provider_name = initial_config.llm_provider.clone();
                 let llm_config_to_load = initial_config.llm_config.clone();
 
+                // 媒体库：本地数据目录作为工作目录，生成的视频同步到配置的存储后端
+                let media = Arc::new(storage::media_store::MediaLibrary::new(data_dir.clone()));
+                if let Err(e) = media
+                    .configure(&initial_config.media_store.clone().unwrap_or_default())
+                    .await
+                {
+                    error!("媒体存储配置无效，使用本地存储: {}", e);
+                }
+
                 // 初始化视频处理器
                 let video_processor = Arc::new(
                     VideoProcessor::new(videos_dir.clone(), temp_dir)
-                        .expect("视频处理器初始化失败"),
+                        .expect("视频处理器初始化失败")
+                        .with_media(media.clone())
+                        .with_screen_regions({
+                            let capture = capture.clone();
+                            Arc::new(move || capture.screen_regions())
+                        }),
                 );
 
                 // 初始化调度器
-                let mut scheduler_inner = CaptureScheduler::new(capture.clone());
+                let mut scheduler_inner = CaptureScheduler::new(capture.clone())
+                    .with_window_state(app_dir.join("session_window.json"));
                 scheduler_inner.configure(
                     initial_config.capture_interval,
                     initial_config.summary_interval,
                 );
+                scheduler_inner.set_alignment(initial_config.session_alignment.unwrap_or_default());
+                scheduler_inner
+                    .set_adaptive_session(initial_config.adaptive_session.clone().unwrap_or_default());
                 let scheduler = Arc::new(scheduler_inner);
 
                 // 初始化系统状态（使用Actor模式，无需锁）
@@ -2474,33 +5588,81 @@ pub fn run() {
                     initial_logger_settings.enable_frontend_logging
                 );
 
+                // LLM 插件目录（插件是程序而不是数据，不随数据目录迁移）
+                llm::external::set_plugins_dir(app_dir.join("plugins"));
+
+                // 配置 LLM 调试归档（默认关闭）
+                llm::debug_archive::configure(
+                    data_dir.join("llm_debug"),
+                    initial_config.llm_debug_archive.clone().unwrap_or_default(),
+                );
+
                 // 将 HTTP 客户端包装为 Arc 以便在 AppState 中共享
                 let http_client = Arc::new(http_client);
 
                 // ==================== 组装领域管理器 ====================
 
                 // 创建捕获领域
-                let capture_domain =
-                    Arc::new(CaptureDomain::new(capture.clone(), scheduler.clone()));
+                let break_reminder = Arc::new(domains::breaks::BreakReminder::new(
+                    capture.clone(),
+                    settings.clone(),
+                ));
+                let focus_guard = Arc::new(domains::focus::FocusGuard::new(settings.clone()));
+                let capture_domain = Arc::new(CaptureDomain::new(
+                    capture.clone(),
+                    scheduler.clone(),
+                    break_reminder,
+                    focus_guard,
+                ));
 
                 // 创建分析领域（使用LLM Handle）
                 let analysis_domain = Arc::new(AnalysisDomain::new(
                     llm_handle.clone(),
                     video_processor.clone(),
+                    http_client.as_ref().clone(),
                 ));
 
                 // 创建存储领域（数据库未初始化）
-                let storage_domain = Arc::new(StorageDomain::new_pending(settings.clone()));
+                let rag_exporter = Arc::new(rag::RagExporter::new(
+                    settings.clone(),
+                    app_dir.join("rag_export_state.json"),
+                ));
+                let storage_domain = Arc::new(StorageDomain::new_pending(
+                    settings.clone(),
+                    rag_exporter,
+                    media,
+                ));
 
                 // 创建系统领域（使用SystemStatus Handle）
-                let system_domain = Arc::new(SystemDomain::new(
-                    status_handle.clone(),
-                    log_broadcaster.clone(),
-                    http_client,
-                ));
+                let system_domain = Arc::new(
+                    SystemDomain::new(status_handle.clone(), log_broadcaster.clone(), http_client)
+                        .with_instance_lock(instance_lock.clone()),
+                );
+
+                // 查看模式：仅浏览共享 MariaDB 中所有设备的数据，本机不截屏和分析
+                let viewer_mode = initial_config
+                    .instance_settings
+                    .as_ref()
+                    .map(|s| s.viewer_mode)
+                    .unwrap_or(false);
+                if viewer_mode && !system_domain.is_read_only() {
+                    if matches!(
+                        db_config_to_load,
+                        Some(crate::storage::config::DatabaseConfig::MariaDB { .. })
+                    ) {
+                        info!("已启用查看模式，本机将不进行截屏和分析");
+                        system_domain.set_read_only(Some(
+                            "查看模式：仅浏览共享数据库中的数据，本机不进行截屏和分析".to_string(),
+                        ));
+                    } else {
+                        warn!("查看模式仅在 MariaDB 模式下生效，当前使用 SQLite，已忽略");
+                    }
+                }
 
-                // 创建事件总线（容量1000,足够缓冲）
-                let event_bus = Arc::new(EventBus::new(1000));
+                // 按性能设置创建事件总线，并应用编码、分析和批量写入上限
+                let performance = initial_config.performance.clone().unwrap_or_default();
+                let event_bus = Arc::new(EventBus::new(performance.event_bus_capacity as usize));
+                domains::performance::apply(&performance, None);
 
                 info!("领域管理器已初始化完成");
 
@@ -2522,36 +5684,113 @@ pub fn run() {
                     db_config_to_load,
                     frames_dir.clone(),
                     videos_dir.clone(),
+                    allow_read_only_secondary,
                 )
             });
 
+            // 次实例且不允许只读运行：提示错误并退出
+            if let instance::InstanceRole::Secondary { owner } = instance_lock.role() {
+                if !allow_read_only_secondary {
+                    error!("另一个实例正在运行 (PID {})，当前实例将退出", owner.pid);
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.hide();
+                    }
+                    let handle = app.handle().clone();
+                    app.dialog()
+                        .message(format!(
+                            "Screen Analyzer 已在运行 (PID {})。\n\n同时运行多个实例会导致会话错乱和重复截屏，请先关闭已运行的实例。",
+                            owner.pid
+                        ))
+                        .kind(MessageDialogKind::Error)
+                        .title("应用已在运行")
+                        .show(move |_| handle.exit(1));
+                    app.manage(state);
+                    return Ok(());
+                }
+            }
+
             // 启动后台任务
             {
                 let state_clone = state.clone();
                 let app_dir_clone = app_dir.clone();
+                let is_primary = instance_lock.is_primary();
+                let data_dir_clone = data_dir.clone();
+                let app_handle = app.handle().clone();
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new()
                         .expect("无法创建 Tokio 运行时，程序无法继续运行");
                     rt.block_on(async {
                         info!("启动后台任务...");
 
+                        // 启动 Actor（在这个长期运行的运行时中），先于数据库初始化，
+                        // 数据库连接较慢时系统状态等命令也能立即响应
+                        info!("启动 LLM Manager Actor 和 System Status Actor...");
+                        tokio::spawn(llm_actor.run());
+                        tokio::spawn(status_actor.run());
+                        info!("Actors 已启动");
+
+                        // 在阻塞线程中枚举屏幕，与数据库初始化并行
+                        {
+                            let capture_state = state_clone.clone();
+                            let capture_handle = app_handle.clone();
+                            tokio::spawn(async move {
+                                report_startup(
+                                    &capture_handle,
+                                    &capture_state,
+                                    StartupStage::Capture,
+                                    StageState::Running,
+                                    None,
+                                );
+                                let capture = capture_state.capture_domain.get_capture().clone();
+                                let count = tokio::task::spawn_blocking(move || capture.screens().len())
+                                    .await
+                                    .unwrap_or(0);
+                                let (stage_state, message) = if count > 0 {
+                                    (StageState::Done, format!("检测到 {} 个屏幕", count))
+                                } else {
+                                    (StageState::Failed, "未找到可用屏幕".to_string())
+                                };
+                                report_startup(
+                                    &capture_handle,
+                                    &capture_state,
+                                    StartupStage::Capture,
+                                    stage_state,
+                                    Some(message),
+                                );
+                            });
+                        }
+
                         // ========== 异步初始化数据库 ==========
                         info!("开始异步初始化数据库...");
+                        report_startup(
+                            &app_handle,
+                            &state_clone,
+                            StartupStage::Database,
+                            StageState::Running,
+                            None,
+                        );
                         let db_result = if let Some(mut db_config) = db_config_to_load {
                             // 如果是 SQLite，检查路径是否为相对路径，如果是则转换为应用数据目录下的绝对路径
                             if let crate::storage::config::DatabaseConfig::SQLite { ref mut db_path } = db_config {
                                 let path = std::path::Path::new(db_path.as_str());
                                 if path.is_relative() {
-                                    let absolute_path = app_dir_clone.join(path);
+                                    let absolute_path = data_dir_clone.join(path);
                                     info!("将相对数据库路径 '{}' 转换为绝对路径: {:?}", db_path, absolute_path);
                                     *db_path = absolute_path.to_string_lossy().to_string();
                                 }
                             }
+                            // MariaDB 本地队列的相对路径同样位于数据目录下
+                            if let crate::storage::config::DatabaseConfig::MariaDB { ref mut connection, .. } = db_config {
+                                let path = std::path::Path::new(connection.local_queue_path.as_str());
+                                if !connection.local_queue_path.trim().is_empty() && path.is_relative() {
+                                    connection.local_queue_path = data_dir_clone.join(path).to_string_lossy().to_string();
+                                }
+                            }
                             info!("使用配置的数据库: {:?}", db_config);
                             Database::from_config(&db_config).await
                         } else {
                             info!("使用默认 SQLite 数据库");
-                            Database::new(&app_dir_clone.join("data.db").to_string_lossy()).await
+                            Database::new(&data_dir_clone.join("data.db").to_string_lossy()).await
                         };
 
                         match db_result {
@@ -2559,14 +5798,50 @@ pub fn run() {
                                 let db = Arc::new(db);
                                 info!("数据库初始化成功，类型: {}", db.db_type());
 
+                                // 数据目录迁移后改写数据库中的文件路径
+                                let location = storage::data_location::DataLocation::load(&app_dir_clone);
+                                if let Some(from) = location.pending_from {
+                                    // 带上末尾分隔符，避免误匹配同名前缀的其他目录
+                                    match db
+                                        .relocate_file_paths(
+                                            &from.join("").to_string_lossy(),
+                                            &data_dir_clone.join("").to_string_lossy(),
+                                        )
+                                        .await
+                                    {
+                                        Ok(updated) => {
+                                            info!("已更新 {} 条文件路径", updated);
+                                            if let Err(e) = storage::data_location::finish_migration(&app_dir_clone) {
+                                                error!("完成数据目录迁移失败: {}", e);
+                                            }
+                                        }
+                                        Err(e) => error!("更新文件路径失败，下次启动重试: {}", e),
+                                    }
+                                }
+
                                 // 设置数据库到 StorageDomain
                                 state_clone.storage_domain.set_database(db.clone()).await;
+                                report_startup(
+                                    &app_handle,
+                                    &state_clone,
+                                    StartupStage::Database,
+                                    StageState::Done,
+                                    None,
+                                );
 
                                 // 初始化存储清理器
+                                report_startup(
+                                    &app_handle,
+                                    &state_clone,
+                                    StartupStage::Cleaner,
+                                    StageState::Running,
+                                    None,
+                                );
                                 let cleaner = Arc::new(StorageCleaner::new(
                                     db.clone(),
                                     frames_dir_clone.clone(),
                                     videos_dir_clone.clone(),
+                                    state_clone.storage_domain.get_media().clone(),
                                 ));
 
                                 // 从配置读取保留天数
@@ -2579,15 +5854,65 @@ pub fn run() {
                                 if let Err(e) = cleaner.set_retention_days(retention_days).await {
                                     error!("设置保留天数失败: {}", e);
                                 }
+                                let persisted =
+                                    state_clone.storage_domain.get_settings().get().await;
+                                cleaner
+                                    .set_kept_frames_days(
+                                        persisted.frame_retention.clone().unwrap_or_default().max_days,
+                                    )
+                                    .await;
+                                if let Err(e) = cleaner
+                                    .set_retention_rules(
+                                        persisted.retention_rules.unwrap_or_default(),
+                                    )
+                                    .await
+                                {
+                                    error!("设置类别保留规则失败: {}", e);
+                                }
+                                if let Err(e) = cleaner
+                                    .set_archive_settings(
+                                        persisted.archive_settings.unwrap_or_default(),
+                                    )
+                                    .await
+                                {
+                                    error!("设置冷存储归档失败: {}", e);
+                                }
+                                cleaner
+                                    .set_retention_frozen(persisted.away_mode.map_or(
+                                        false,
+                                        |away| away.active && away.freeze_retention,
+                                    ))
+                                    .await;
 
                                 // 设置清理器到 StorageDomain
                                 state_clone.storage_domain.set_cleaner(cleaner).await;
+                                report_startup(
+                                    &app_handle,
+                                    &state_clone,
+                                    StartupStage::Cleaner,
+                                    StageState::Done,
+                                    None,
+                                );
 
                                 info!("数据库和存储清理器已就绪");
                             }
                             Err(e) => {
                                 let error_msg = format!("数据库初始化失败: {}", e);
                                 error!("{}", error_msg);
+                                report_startup(
+                                    &app_handle,
+                                    &state_clone,
+                                    StartupStage::Database,
+                                    StageState::Failed,
+                                    Some(error_msg.clone()),
+                                );
+                                report_startup(
+                                    &app_handle,
+                                    &state_clone,
+                                    StartupStage::Cleaner,
+                                    StageState::Failed,
+                                    Some("数据库不可用".to_string()),
+                                );
                                 state_clone
                                     .storage_domain
                                     .set_database_error(error_msg)
@@ -2596,13 +5921,14 @@ pub fn run() {
                             }
                         }
 
-                        // 启动 Actor（在这个长期运行的运行时中）
-                        info!("启动 LLM Manager Actor 和 System Status Actor...");
-                        tokio::spawn(llm_actor.run());
-                        tokio::spawn(status_actor.run());
-                        info!("Actors 已启动");
-
                         // 配置 LLM（Actor 启动后才能配置）
+                        report_startup(
+                            &app_handle,
+                            &state_clone,
+                            StartupStage::Llm,
+                            StageState::Running,
+                            None,
+                        );
                         // 1. 根据配置切换 provider
                         let provider = llm_provider_name.as_str();
                         info!("配置 LLM provider: {}", provider);
@@ -2681,13 +6007,49 @@ pub fn run() {
                                         info!("已从配置文件加载 Codex 设置");
                                     }
                                 }
+                                name if name.starts_with(llm::external::PLUGIN_PREFIX) => {
+                                    if let Err(e) = state_clone
+                                        .analysis_domain
+                                        .get_llm_handle()
+                                        .configure_plugin(
+                                            llm_config.plugin_config.clone().unwrap_or_default(),
+                                        )
+                                        .await
+                                    {
+                                        error!("加载插件配置失败: {}", e);
+                                    } else {
+                                        info!("已从配置文件加载插件 {} 的设置", name);
+                                    }
+                                }
                                 _ => {
                                     warn!("未知的 LLM provider: {}", provider);
                                 }
                             }
                         }
 
-                        // 初始化 Notion 集成
+                        report_startup(
+                            &app_handle,
+                            &state_clone,
+                            StartupStage::Llm,
+                            StageState::Done,
+                            Some(llm_provider_name.clone()),
+                        );
+
+                        // 初始化 Notion 集成（视频上传进度通过 notion-upload-progress 事件推送）
+                        report_startup(
+                            &app_handle,
+                            &state_clone,
+                            StartupStage::Tasks,
+                            StageState::Running,
+                            None,
+                        );
+                        let notion_handle = app_handle.clone();
+                        state_clone
+                            .storage_domain
+                            .get_notion_manager()
+                            .set_progress_listener(Arc::new(move |progress| {
+                                let _ = notion_handle.emit("notion-upload-progress", progress);
+                            }));
                         let config = state_clone.storage_domain.get_settings().get().await;
                         if let Some(notion_config) = config.notion_config {
                             if notion_config.enabled {
@@ -2704,52 +6066,323 @@ pub fn run() {
                             }
                         }
 
+                        // 启动自检（后台执行，完成后通过 startup-report 事件通知前端）
+                        {
+                            let check_state = state_clone.clone();
+                            let check_handle = app_handle.clone();
+                            tokio::spawn(async move {
+                                let report = run_startup_checks(&check_state).await;
+                                let _ = check_handle.emit("startup-report", report);
+                            });
+                        }
+
+                        // 启动新版本检查任务（未开启时仅空转检查配置）
+                        let update_handle = app_handle.clone();
+                        domains::updates::start_update_checker(
+                            state_clone.system_domain.get_http_client().clone(),
+                            state_clone.storage_domain.get_settings().clone(),
+                            move |update| {
+                                use tauri_plugin_notification::NotificationExt;
+                                if let Err(e) = update_handle
+                                    .notification()
+                                    .builder()
+                                    .title(format!("发现新版本 {}", update.latest_version))
+                                    .body(format!(
+                                        "当前版本 {}，打开应用查看更新说明",
+                                        update.current_version
+                                    ))
+                                    .show()
+                                {
+                                    warn!("发送新版本通知失败: {}", e);
+                                }
+                                let _ = update_handle.emit("update-available", update);
+                            },
+                        );
+
+                        // 只读模式（次实例）下不启动截屏、分析和清理，避免与主实例争用数据
+                        let read_only = state_clone.system_domain.is_read_only();
+                        if read_only {
+                            warn!("当前为只读模式，跳过截屏、分析和清理任务的启动");
+                        }
+
+                        // 本机角色：仅采集时不自动分析，仅分析时不截屏
+                        let machine_role = state_clone
+                            .storage_domain
+                            .get_settings()
+                            .get()
+                            .await
+                            .instance_settings
+                            .map(|s| s.machine_role)
+                            .unwrap_or_default();
+                        if machine_role != models::MachineRole::Full {
+                            info!("本机角色: {:?}", machine_role);
+                        }
+
                         // 仅在数据库就绪时启动依赖数据库的组件
-                        if let Some(db) = state_clone.storage_domain.try_get_db().await {
-                            // 创建LLMProcessor并启动事件监听器（包含 Notion 支持）
-                            let llm_processor = Arc::new(llm::LLMProcessor::with_video_and_notion(
-                                state_clone.analysis_domain.get_llm_handle().clone(),
-                                db.clone(),
-                                state_clone.analysis_domain.get_video_processor().clone(),
-                                state_clone.storage_domain.get_settings().clone(),
-                                state_clone.storage_domain.get_notion_manager().clone(),
-                            ));
+                        if !read_only {
+                            if let Some(db) = state_clone.storage_domain.try_get_db().await {
+                                // 创建LLMProcessor并启动事件监听器（包含 Notion 支持）
+                                let llm_processor = Arc::new(llm::LLMProcessor::with_video_and_notion(
+                                    state_clone.analysis_domain.get_llm_handle().clone(),
+                                    db.clone(),
+                                    state_clone.analysis_domain.get_video_processor().clone(),
+                                    state_clone.storage_domain.get_settings().clone(),
+                                    state_clone.storage_domain.get_notion_manager().clone(),
+                                ));
+
+                                // 启动LLM处理器事件监听器
+                                llm_processor
+                                    .start_event_listener(
+                                        state_clone.event_bus.clone(),
+                                        state_clone.capture_domain.get_capture().clone(),
+                                        state_clone.analysis_domain.get_analysis_hold().clone(),
+                                    )
+                                    .await;
+
+                                info!("LLM处理器事件监听器已启动");
+
+                                // 启动截屏暂停记录任务（需在调度器之前订阅事件）
+                                domains::capture_pauses::start_pause_recorder(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                );
+
+                                // 启动自动会议纪要任务（是否生成由设置决定）
+                                domains::meetings::start_meeting_notes_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                    state_clone.analysis_domain.get_llm_handle().clone(),
+                                );
+
+                                // 启动关注关键词检查任务（未设置关键词时跳过）
+                                let keyword_handle = app_handle.clone();
+                                domains::keywords::start_keyword_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                    move |alert| {
+                                        use tauri_plugin_notification::NotificationExt;
+                                        if let Err(e) = keyword_handle
+                                            .notification()
+                                            .builder()
+                                            .title(format!("出现关注内容：{}", alert.keywords.join("、")))
+                                            .body(&alert.excerpt)
+                                            .show()
+                                        {
+                                            warn!("发送关键词提醒通知失败: {}", e);
+                                        }
+                                        let _ = keyword_handle.emit("keyword-hit", alert);
+                                    },
+                                );
+
+                                // 启动时段意图评估任务
+                                domains::intents::start_intent_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                );
+
+                                // 启动隐私敏感度评估任务
+                                domains::privacy::start_privacy_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                );
+
+                                // 启动屏幕文字识别任务（是否识别由设置决定）
+                                domains::text_timeline::start_text_timeline_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                );
 
-                            // 启动LLM处理器事件监听器
-                            llm_processor
-                                .start_event_listener(
+                                // 启动字幕封装任务（是否封装由设置决定）
+                                domains::subtitles::start_subtitle_worker(
                                     state_clone.event_bus.clone(),
-                                    state_clone.capture_domain.get_capture().clone(),
-                                )
-                                .await;
-
-                            info!("LLM处理器事件监听器已启动");
-
-                            // 启动调度器（事件驱动模式）
-                            state_clone
-                                .capture_domain
-                                .get_scheduler()
-                                .clone()
-                                .start(state_clone.event_bus.clone());
-
-                            // 启动存储清理任务
-                            if let Ok(cleaner) = state_clone.storage_domain.get_cleaner().await {
-                                cleaner.start_cleanup_task().await;
-                                info!("存储清理任务已启动");
+                                    state_clone.storage_domain.clone(),
+                                );
+
+                                // 启动卡片预览短片任务（是否生成由设置决定）
+                                domains::card_previews::start_preview_worker(
+                                    state_clone.event_bus.clone(),
+                                    state_clone.storage_domain.clone(),
+                                );
+
+                                // 启动调度器（事件驱动模式），仅分析的机器不截屏
+                                if machine_role.captures() {
+                                    state_clone
+                                        .capture_domain
+                                        .get_scheduler()
+                                        .clone()
+                                        .start(state_clone.event_bus.clone());
+                                }
+
+                                // 启动存储清理任务
+                                if let Ok(cleaner) = state_clone.storage_domain.get_cleaner().await {
+                                    // 存储配额检查（接近配额时降低新视频的画质）
+                                    domains::storage_quota::start_quota_monitor(
+                                        cleaner.clone(),
+                                        state_clone.storage_domain.get_settings().clone(),
+                                    );
+                                    cleaner
+                                        .start_cleanup_task(state_clone.event_bus.clone())
+                                        .await;
+                                    info!("存储清理任务已启动");
+                                } else {
+                                    error!("存储清理器未就绪");
+                                }
+
+                                // 启动旧视频转码任务（未启用时仅空转检查配置）
+                                domains::reencode::start_reencode_worker(
+                                    db.clone(),
+                                    state_clone.storage_domain.get_media().clone(),
+                                    state_clone.storage_domain.get_settings().clone(),
+                                );
+
+                                // 启动 RAG 向量库定时导出任务（未启用时仅空转检查配置）
+                                state_clone
+                                    .storage_domain
+                                    .get_rag_exporter()
+                                    .clone()
+                                    .start_export_task(db.clone());
+
+                                // 启动休息提醒任务
+                                let break_handle = app_handle.clone();
+                                state_clone
+                                    .capture_domain
+                                    .get_break_reminder()
+                                    .clone()
+                                    .start(db.clone(), move |active_minutes| {
+                                        use tauri_plugin_notification::NotificationExt;
+                                        if let Err(e) = break_handle
+                                            .notification()
+                                            .builder()
+                                            .title("该休息一下了")
+                                            .body(format!(
+                                                "已连续使用屏幕 {} 分钟，起身活动一下吧",
+                                                active_minutes
+                                            ))
+                                            .show()
+                                        {
+                                            warn!("发送休息提醒通知失败: {}", e);
+                                        }
+                                        let _ = break_handle.emit("break-reminder", active_minutes);
+                                    });
+
+                                // 启动暂停截屏提醒任务（手动暂停后忘记恢复时提醒）
+                                if machine_role.captures() {
+                                    let pause_handle = app_handle.clone();
+                                    domains::pause_reminder::start_pause_reminder(
+                                        state_clone.capture_domain.get_scheduler().clone(),
+                                        state_clone.storage_domain.get_settings().clone(),
+                                        move |paused_minutes| {
+                                            use tauri_plugin_notification::NotificationExt;
+                                            if let Err(e) = pause_handle
+                                                .notification()
+                                                .builder()
+                                                .title("截屏仍处于暂停状态")
+                                                .body(format!(
+                                                    "截屏已暂停 {} 分钟，打开应用可一键恢复",
+                                                    paused_minutes
+                                                ))
+                                                .show()
+                                            {
+                                                warn!("发送暂停提醒通知失败: {}", e);
+                                            }
+                                            let _ = pause_handle
+                                                .emit("capture-pause-reminder", paused_minutes);
+                                        },
+                                    );
+                                }
+
+                                // 接收次实例转交的深度链接（Windows/Linux）
+                                #[cfg(not(target_os = "macos"))]
+                                if is_primary {
+                                    let link_handle = app_handle.clone();
+                                    domains::deep_link::start_handoff_watcher(
+                                        app_dir_clone.clone(),
+                                        move |url| open_deep_link(&link_handle, &url),
+                                    );
+                                }
+
+                                // 启动专注模式检测任务（仅在专注时段内检测前台应用）
+                                let focus_handle = app_handle.clone();
+                                state_clone
+                                    .capture_domain
+                                    .get_focus_guard()
+                                    .clone()
+                                    .start(db.clone(), move |interception| {
+                                        use tauri_plugin_notification::NotificationExt;
+                                        if let Err(e) = focus_handle
+                                            .notification()
+                                            .builder()
+                                            .title("专注中")
+                                            .body(format!(
+                                                "{} 会打断专注，还剩 {} 分钟",
+                                                interception.app_name, interception.remaining_minutes
+                                            ))
+                                            .show()
+                                        {
+                                            warn!("发送专注提醒通知失败: {}", e);
+                                        }
+                                        let _ = focus_handle.emit("focus-distraction", interception);
+                                    });
+
+                                // 启动剪贴板记录任务（未开启时不读取剪贴板）
+                                Arc::new(domains::clipboard::ClipboardLogger::new(
+                                    state_clone.storage_domain.get_settings().clone(),
+                                ))
+                                .start(db.clone());
+
+                                // 启动音频活动检测任务（未开启时不检测，不录音）
+                                domains::audio_presence::start_detector(
+                                    state_clone.storage_domain.get_settings().clone(),
+                                    db.clone(),
+                                );
+
+                                // 启动异常监控任务（以系统通知提醒）
+                                let notify_handle = app_handle.clone();
+                                domains::insights::start_insight_monitor(db.clone(), move |insight| {
+                                    use tauri_plugin_notification::NotificationExt;
+                                    if let Err(e) = notify_handle
+                                        .notification()
+                                        .builder()
+                                        .title(&insight.title)
+                                        .body(&insight.message)
+                                        .show()
+                                    {
+                                        warn!("发送异常提醒通知失败: {}", e);
+                                    }
+                                });
                             } else {
-                                error!("存储清理器未就绪");
+                                error!("数据库未就绪，跳过数据库相关组件的启动");
                             }
-                        } else {
-                            error!("数据库未就绪，跳过数据库相关组件的启动");
                         }
 
-                        // 周期性扫描视频目录，处理未分析的视频
-                        {
+                        // 周期性扫描视频目录，处理未分析的视频（仅采集的机器交给分析机器处理）
+                        if !read_only && machine_role.analyzes() {
                             let video_state = state_clone.clone();
+                            let backlog_handle = app_handle.clone();
                             tokio::spawn(async move {
+                                let mut backlog_tracker =
+                                    domains::analysis_backlog::BacklogTracker::default();
+                                let mut last_error: Option<String> = None;
                                 loop {
+                                    // 夜间分析时段外只处理用户要求立即分析的视频
+                                    let night_settings = video_state
+                                        .storage_domain
+                                        .get_settings()
+                                        .get()
+                                        .await
+                                        .night_analysis
+                                        .unwrap_or_default();
+                                    let limit = if domains::analysis::analysis_allowed_at(
+                                        &night_settings,
+                                        storage::local_now(),
+                                    ) {
+                                        None
+                                    } else {
+                                        Some(video_state.analysis_domain.prioritized_count())
+                                    };
+
                                     // 直接执行分析，无需 analysis_lock（已移除临时方案）
-                                    match analyze_unprocessed_videos(&video_state, None, false)
+                                    match analyze_unprocessed_videos(&video_state, limit, false)
                                         .await
                                     {
                                         Ok(report) => {
@@ -2759,12 +6392,132 @@ pub fn run() {
                                                     report.processed, report.failed
                                                 );
                                             }
+                                            if report.failed > 0 {
+                                                last_error = report.messages.last().cloned();
+                                            } else if report.processed > 0 {
+                                                last_error = None;
+                                            }
                                         }
                                         Err(e) => {
                                             error!("自动视频分析失败: {}", e);
+                                            last_error = Some(e);
+                                        }
+                                    }
+
+                                    // 积压超过阈值时更新状态栏警告，并按间隔发送通知
+                                    let backlog =
+                                        check_analysis_backlog(&video_state, last_error.clone())
+                                            .await;
+                                    let backlog_settings = video_state
+                                        .storage_domain
+                                        .get_settings()
+                                        .get()
+                                        .await
+                                        .analysis_backlog
+                                        .unwrap_or_default();
+                                    if backlog_tracker.tick(
+                                        storage::local_now(),
+                                        backlog.as_ref().map_or(0, |backlog| backlog.count),
+                                        &backlog_settings,
+                                    ) {
+                                        if let Some(backlog) = backlog {
+                                            use tauri_plugin_notification::NotificationExt;
+                                            if let Err(e) = backlog_handle
+                                                .notification()
+                                                .builder()
+                                                .title(format!(
+                                                    "{} 个视频等待分析",
+                                                    backlog.count
+                                                ))
+                                                .body(format!(
+                                                    "{}，打开应用可一键处理",
+                                                    backlog.message
+                                                ))
+                                                .show()
+                                            {
+                                                warn!("发送分析积压通知失败: {}", e);
+                                            }
+                                            let _ = backlog_handle
+                                                .emit("analysis-backlog", backlog);
+                                        }
+                                    }
+
+                                    // 等待下一轮扫描（额度耗尽时等到重试时间），收到立即分析请求时提前开始
+                                    let wait = video_state
+                                        .analysis_domain
+                                        .quota_remaining()
+                                        .unwrap_or(tokio::time::Duration::from_secs(60));
+                                    tokio::select! {
+                                        _ = tokio::time::sleep(wait) => {}
+                                        _ = video_state.analysis_domain.wait_for_priority() => {}
+                                    }
+                                }
+                            });
+                        }
+
+                        // 启动 LLM 服务商健康探测
+                        if !read_only {
+                            domains::provider_health::start_health_monitor(
+                                state_clone.analysis_domain.get_provider_health().clone(),
+                                state_clone.storage_domain.get_settings().clone(),
+                            );
+                        }
+
+                        // 启动自动化钩子（事件触发用户配置的命令或脚本）
+                        if !read_only {
+                            domains::hooks::start_hook_runner(
+                                state_clone.event_bus.clone(),
+                                state_clone.storage_domain.get_settings().clone(),
+                            );
+                        }
+
+                        // 启动实时活动估计（未启用时仅空转检查配置）
+                        if !read_only && machine_role.captures() {
+                            domains::live_activity::start_live_activity_worker(
+                                state_clone.capture_domain.get_capture().clone(),
+                                state_clone.storage_domain.get_settings().clone(),
+                            );
+                        }
+
+                        // 启动实时状态推送（前端事件 + 本地 WebSocket 服务）
+                        {
+                            let live_state = state_clone.clone();
+                            let live_handle = app_handle.clone();
+                            tokio::spawn(async move {
+                                let live_settings = live_state
+                                    .storage_domain
+                                    .get_settings()
+                                    .get()
+                                    .await
+                                    .live_api
+                                    .unwrap_or_default();
+                                if let Err(e) = live_state
+                                    .system_domain
+                                    .get_live_server()
+                                    .apply(&live_settings, &live_state.storage_domain)
+                                    .await
+                                {
+                                    error!("启动实时状态服务失败: {}", e);
+                                }
+
+                                loop {
+                                    match collect_live_state(&live_state).await {
+                                        Ok(snapshot) => {
+                                            let _ = live_handle.emit(
+                                                domains::live::LIVE_STATE_EVENT,
+                                                &snapshot,
+                                            );
+                                            live_state
+                                                .system_domain
+                                                .get_live_feed()
+                                                .publish(snapshot);
                                         }
+                                        Err(e) => warn!("收集实时状态失败: {}", e),
                                     }
-                                    tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+                                    tokio::time::sleep(tokio::time::Duration::from_secs(
+                                        domains::live::LIVE_TICK_SECS,
+                                    ))
+                                    .await;
                                 }
                             });
                         }
@@ -2773,9 +6526,33 @@ pub fn run() {
                         state_clone
                             .system_domain
                             .get_status_handle()
-                            .set_capturing(true)
+                            .set_capturing(!read_only && machine_role.captures())
                             .await;
 
+                        // 上次退出时处于离开模式：继续暂停截屏并重新记录离开时段
+                        if !read_only && machine_role.captures() {
+                            let away = state_clone
+                                .storage_domain
+                                .get_settings()
+                                .get()
+                                .await
+                                .away_mode
+                                .unwrap_or_default();
+                            if away.active {
+                                if let Err(e) =
+                                    apply_away_mode(&state_clone, true, away.freeze_retention)
+                                        .await
+                                {
+                                    error!("恢复离开模式失败: {}", e);
+                                }
+                            }
+                        }
+
+                        // 无界面模式下通过配置文件调整设置
+                        if headless_mode && !read_only {
+                            headless::start_config_watcher(state_clone.clone());
+                        }
+
                         // 启动系统资源监控任务（每5秒更新一次CPU和内存占用率）
                         {
                             let system_state = state_clone.clone();
@@ -2834,9 +6611,16 @@ pub fn run() {
                         }
 
                         info!("所有后台任务已启动");
+                        report_startup(
+                            &app_handle,
+                            &state_clone,
+                            StartupStage::Tasks,
+                            StageState::Done,
+                            None,
+                        );
 
                         // 在独立的后台任务中处理历史图片（不阻塞启动）
-                        {
+                        if !read_only {
                             let history_state = state_clone.clone();
                             tokio::spawn(async move {
                                 info!("开始处理历史图片，生成视频...");
@@ -2858,46 +6642,182 @@ pub fn run() {
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
         .invoke_handler(tauri::generate_handler![
             get_database_status,
             get_activities,
             get_day_sessions,
             get_day_summary,
+            get_day_types,
+            set_day_type,
             get_session_detail,
             get_app_config,
             update_config,
+            get_device_name,
+            get_startup_progress,
+            reset_device_settings,
             get_anthropic_env,
             add_manual_tag,
             remove_tag,
+            bulk_tag,
             get_system_status,
             toggle_capture,
+            get_away_mode,
+            set_away_mode,
             trigger_analysis,
+            delete_analysis_backlog,
+            reset_all_data,
             generate_video,
             get_video_url,
             get_video_data,
             test_generate_videos,
+            request_confirmation,
             cleanup_storage,
             get_storage_stats,
+            get_storage_breakdown,
+            get_largest_sessions,
             migrate_timezone_to_local,
             refresh_device_info,
             sync_data_to_mariadb,
             configure_qwen,
             configure_llm_provider,
+            list_llm_plugins,
+            list_llm_models,
             test_capture,
             test_llm_api,
             retry_session_analysis,
             regenerate_timeline,
+            rename_session,
+            take_pending_deep_link,
             delete_session,
+            undo_delete_session,
             open_storage_folder,
             get_log_dir,
             open_log_folder,
+            get_recent_logs,
+            get_session_trace,
+            get_event_bus_stats,
             test_notion_connection,
             update_notion_config,
             search_notion_pages,
             create_notion_database,
+            export_rag_now,
+            get_rag_export_state,
+            get_context_switch_stats,
+            export_bi_dataset,
+            export_finetune_dataset,
+            export_compilation,
+            export_time_entries,
+            get_records,
+            get_break_status,
+            get_live_state,
+            get_live_activity,
+            test_automation_hook,
+            snooze_break_reminder,
+            skip_break_reminder,
+            get_break_records,
+            reclassify_history,
+            normalize_existing_subcategories,
+            open_llm_debug_folder,
+            get_session_activity_series,
+            get_session_cards,
+            map_time_to_card,
+            map_card_to_time,
+            get_card_provenance,
+            get_cards_needing_review,
+            resolve_card_review,
+            get_day_review,
+            get_day_gaps,
+            export_standup_note,
+            get_day_timeline_text,
+            generate_meeting_notes,
+            suggest_week_plan,
+            export_week_plan_ics,
+            export_session_subtitles,
+            embed_session_subtitles,
+            ingest_external_events,
+            import_activity_history,
+            get_external_events,
+            get_domain_stats,
+            start_focus_block,
+            stop_focus_block,
+            get_focus_status,
+            get_deflected_distractions,
+            export_day_bundle,
+            export_shareable_session,
+            export_diagnostics_bundle,
+            get_keyword_hits,
+            get_egress_log,
+            get_webhook_schema,
+            get_webhook_deliveries,
+            get_trends,
+            get_monthly_summary,
+            build_text_timeline,
+            get_text_timeline,
+            search_screen_text,
+            get_screen_language_usage,
+            get_errors_seen,
+            get_clipboard_events,
+            search_clipboard,
+            get_session_audio_presence,
+            get_card_previews,
+            add_session_bookmark,
+            get_session_bookmarks,
+            delete_session_bookmark,
+            get_day_mood_strips,
+            get_range_summary,
+            get_confidence_stats,
+            start_block_intent,
+            end_block_intent,
+            get_day_intents,
+            run_data_doctor,
+            rebuild_day,
+            get_outbox_status,
+            get_startup_report,
+            check_for_updates,
+            skip_update_version,
+            submit_day_review,
+            generate_demo_data,
+            remove_demo_data,
+            get_data_directory,
+            check_data_directory,
+            diff_database_snapshots,
+            set_data_directory,
+            get_compression_stats,
+            get_video_reencode_status,
+            preview_retention_policy,
+            preview_garbage_sessions,
+            pin_session,
+            unpin_session,
+            get_pinned_sessions,
+            restore_from_archive,
+            start_capture_preview,
+            stop_capture_preview,
+            get_capture_latency_stats,
+            get_displays,
+            verify_capture_hygiene,
+            prioritize_video,
+            get_unanalyzed_videos,
+            analyze_single_frame,
+            compare_providers,
+            get_provider_comparisons,
+            get_pending_analyses,
+            set_pending_analysis_skipped,
+            release_pending_analysis,
+            run_benchmark,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(move |_app, event| {
+            // 无界面模式没有窗口，阻止"所有窗口关闭"导致的退出（显式 exit 仍然生效）
+            if let tauri::RunEvent::ExitRequested { api, code, .. } = event {
+                if headless_mode && code.is_none() {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
 #[derive(Default)]
 struct VideoAnalysisReport {
@@ -2917,6 +6837,7 @@ struct VideoAnalysisOutcome {
 
 async fn analyze_video_once(
     state: &AppState,
+    llm_handle: &actors::LLMHandle,
     video_path: &Path,
     session_start: chrono::DateTime<chrono::Utc>,
     session_end: chrono::DateTime<chrono::Utc>,
@@ -2930,7 +6851,6 @@ async fn analyze_video_once(
         .unwrap_or("视频");
 
     let persisted_config = state.storage_domain.get_settings().get().await;
-    let llm_handle = state.analysis_domain.get_llm_handle();
 
     // 根据当前 provider 配置 LLM
     let current_provider = persisted_config.llm_provider.as_str();
@@ -2970,6 +6890,10 @@ async fn analyze_video_once(
             // 不需要额外配置，已经在启动时配置过了
             info!("使用 Claude provider 进行视频分析（API key 可选）");
         }
+        name if name.starts_with(llm::external::PLUGIN_PREFIX) => {
+            // 插件在启动或保存配置时已配置
+            info!("使用插件 {} 进行视频分析", name);
+        }
         _ => {
             return Err(format!("不支持的 LLM provider: {}", current_provider));
         }
@@ -3000,7 +6924,15 @@ async fn analyze_video_once(
 
         existing_id
     } else {
-        let (device_name, device_type) = storage::get_device_info();
+        // 视频在其他机器上录制时，会话归属录制的设备
+        let (device_name, device_type) = match video::VideoMetadata::load(video_path).await {
+            Some(video::VideoMetadata {
+                device_name: Some(name),
+                device_type: Some(kind),
+                ..
+            }) => (name, kind),
+            _ => storage::get_device_info(),
+        };
         let temp_session = storage::Session {
             id: None,
             start_time: session_start,
@@ -3012,6 +6944,9 @@ async fn analyze_video_once(
             created_at: Some(now),
             device_name: Some(device_name),
             device_type: Some(device_type),
+            pinned: false,
+            archived: false,
+            archive_key: None,
         };
 
         match state
@@ -3042,54 +6977,130 @@ async fn analyze_video_once(
         .await
         .map_err(|e| e.to_string())?;
 
-    // 设置视频速率乘数（从配置获取）
-    let speed_multiplier = persisted_config.video_config.speed_multiplier;
+    let corrections = state
+        .storage_domain
+        .get_db()
+        .await?
+        .get_recent_card_corrections(llm::personalization::MAX_CORRECTIONS)
+        .await
+        .unwrap_or_default();
     llm_handle
-        .set_video_speed(speed_multiplier)
+        .set_correction_examples(corrections)
         .await
         .map_err(|e| e.to_string())?;
 
-    let analysis = match llm_handle
-        .segment_video_and_generate_timeline(vec![], duration_minutes, None)
+    // 设置视频速率乘数（优先使用视频元数据，旧视频回退到当前配置）
+    // 平均倍速只用于提示词，返回的视频时间按视频元数据分段换算（见 relative_to_absolute_video）
+    let video_metadata = video::VideoMetadata::load(video_path).await;
+    let speed_multiplier = video_metadata
+        .as_ref()
+        .map(|m| m.effective_speed_multiplier())
+        .unwrap_or(persisted_config.video_config.speed_multiplier);
+    llm_handle
+        .set_video_speed(speed_multiplier)
         .await
-    {
-        Ok(res) => res,
-        Err(e) => {
-            let _ = llm_handle.set_video_path(None).await;
-            let error_msg = e.to_string();
-            // 检测是否是视频过短的错误
-            if error_msg.contains("The video file is too short") {
-                return Err(format!("VIDEO_TOO_SHORT:{}", error_msg));
+        .map_err(|e| e.to_string())?;
+    llm_handle
+        .set_video_upload(
+            persisted_config
+                .video_upload_config
+                .clone()
+                .unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 自动分析时，会话完全落在安静时段内则不调用模型，直接生成休息卡片
+    let quiet_zone = if reuse_session.is_none() {
+        domains::quiet_zones::zone_covering(
+            persisted_config.quiet_zones.as_deref().unwrap_or_default(),
+            session_start,
+            session_end,
+        )
+    } else {
+        None
+    };
+
+    let (analysis, sparse) = if let Some(zone) = quiet_zone {
+        info!("视频处于安静时段 {}，跳过 LLM 分析", zone.name);
+        (
+            domains::quiet_zones::break_analysis(&zone, session_start, session_end),
+            true,
+        )
+    } else {
+        match llm_handle
+            .segment_video_and_generate_timeline(vec![], duration_minutes, None)
+            .await
+        {
+            Ok(res) => (res, false),
+            // 帧数太少时保留会话，改为单帧分析生成一张短暂活动卡片
+            Err(e)
+                if e.to_string().contains("VIDEO_TOO_SHORT")
+                    || e.to_string().contains("The video file is too short") =>
+            {
+                warn!("视频过短，改为单帧分析: {}", e);
+                let active_end = video_metadata
+                    .as_ref()
+                    .map(|m| {
+                        let real_secs = m.video_duration_secs() * m.real_seconds_per_video_second();
+                        session_start + chrono::Duration::seconds(real_secs.round() as i64)
+                    })
+                    .map_or(session_end, |end| end.min(session_end));
+                let analysis = domains::sparse_sessions::analyze_sparse_session(
+                    &llm_handle,
+                    &[],
+                    Some(video_path),
+                    session_start,
+                    session_start,
+                    active_end,
+                )
+                .await;
+                (analysis, true)
+            }
+            Err(e) => {
+                let _ = llm_handle.set_video_path(None).await;
+                return Err(e.to_string());
             }
-            return Err(error_msg);
         }
     };
 
     let _ = llm_handle.set_video_path(None).await;
     let _ = llm_handle.set_session_window(None, None).await;
 
+    // 单帧分析的卡片已按会话时间给出，不按视频倍速换算
+    let video_time = if sparse {
+        None
+    } else {
+        llm::analysis_video_time(
+            &analysis,
+            session_start,
+            session_end,
+            video_metadata.as_ref(),
+        )
+    };
+    let to_absolute = |relative: &str| {
+        llm::relative_to_absolute_video(session_start, session_end, relative, video_time)
+    };
+
     let mut segments = analysis.segments;
     for segment in &mut segments {
-        let start_abs =
-            llm::relative_to_absolute(session_start, session_end, &segment.start_timestamp);
-        let end_abs = llm::relative_to_absolute(session_start, session_end, &segment.end_timestamp);
+        let start_abs = to_absolute(&segment.start_timestamp);
+        let end_abs = to_absolute(&segment.end_timestamp);
         segment.start_timestamp = start_abs.to_rfc3339();
         segment.end_timestamp = end_abs.to_rfc3339();
     }
 
     let mut timeline_cards = analysis.timeline_cards;
     for card in &mut timeline_cards {
-        let start_abs = llm::relative_to_absolute(session_start, session_end, &card.start_time);
-        let end_abs = llm::relative_to_absolute(session_start, session_end, &card.end_time);
+        let start_abs = to_absolute(&card.start_time);
+        let end_abs = to_absolute(&card.end_time);
         card.start_time = start_abs.to_rfc3339();
         card.end_time = end_abs.to_rfc3339();
 
         if let Some(distractions) = card.distractions.as_mut() {
             for distraction in distractions {
-                let d_start =
-                    llm::relative_to_absolute(session_start, session_end, &distraction.start_time);
-                let d_end =
-                    llm::relative_to_absolute(session_start, session_end, &distraction.end_time);
+                let d_start = to_absolute(&distraction.start_time);
+                let d_end = to_absolute(&distraction.end_time);
                 distraction.start_time = d_start.to_rfc3339();
                 distraction.end_time = d_end.to_rfc3339();
             }
@@ -3122,7 +7133,7 @@ async fn analyze_video_once(
     }
 
     if !timeline_cards.is_empty() {
-        let card_records: Vec<storage::TimelineCardRecord> = timeline_cards
+        let mut card_records: Vec<storage::TimelineCardRecord> = timeline_cards
             .iter()
             .map(|card| storage::TimelineCardRecord {
                 id: None,
@@ -3143,9 +7154,29 @@ async fn analyze_video_once(
                 app_sites: serde_json::to_string(&card.app_sites)
                     .unwrap_or_else(|_| "{}".to_string()),
                 created_at: now,
+                confidence: Some(domains::review::card_confidence(card)),
+                review_status: None,
+                meeting_notes: None,
+                keyword_tags: None,
+                preview_clip_path: None,
+                privacy_level: None,
             })
             .collect();
 
+        let config = state.storage_domain.get_settings().get().await;
+        let app_profiles = config.app_profiles.unwrap_or_default();
+        let subcategories = domains::subcategories::SubcategoryNormalizer::new(
+            &config.subcategory_synonyms.unwrap_or_default(),
+        );
+        let review_threshold = config
+            .review_confidence_threshold
+            .unwrap_or(domains::review::DEFAULT_REVIEW_THRESHOLD);
+        for card in card_records.iter_mut() {
+            subcategories.apply(card);
+            domains::app_profiles::apply_app_profiles(card, &app_profiles);
+            domains::review::mark_for_review(card, review_threshold);
+        }
+
         if let Err(e) = state
             .storage_domain
             .get_db()
@@ -3157,14 +7188,35 @@ async fn analyze_video_once(
         }
     }
 
-    let summary =
-        llm::build_session_summary(session_start, session_end, &segments, &timeline_cards);
+    let title_strategy = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .session_title_strategy
+        .unwrap_or_default();
+    let summary = llm::build_session_summary(
+        session_start,
+        session_end,
+        &segments,
+        &timeline_cards,
+        title_strategy,
+    );
+    let title = domains::session_titles::dedupe_session_title(
+        &db,
+        session_id,
+        session_start,
+        session_end,
+        &summary.title,
+        &timeline_cards,
+    )
+    .await;
 
     let tags_json = serde_json::to_string(&summary.tags).unwrap_or_else(|_| "[]".to_string());
     if let Err(e) = db
         .update_session(
             session_id,
-            &summary.title,
+            &title,
             &summary.summary,
             Some(&video_path_str),
             &tags_json,
@@ -3174,6 +7226,17 @@ async fn analyze_video_once(
         return Err(format!("更新会话信息失败: {}", e));
     }
 
+    // 分析成功后删除生成视频时保留的截图
+    domains::frame_retention::release(&state.capture_domain.get_capture().frames_dir(), file_stem)
+        .await;
+
+    state
+        .event_bus
+        .publish(event_bus::AppEvent::AnalysisCompleted {
+            session_id,
+            summary: summary.clone(),
+        });
+
     Ok(VideoAnalysisOutcome {
         _session_id: session_id,
         segments_count: segments.len(),
@@ -3182,11 +7245,358 @@ async fn analyze_video_once(
     })
 }
 
-async fn analyze_unprocessed_videos(
+/// 分析队列的共享状态（多个工作者从同一队列取视频）
+struct AnalysisBatch {
+    queue: Vec<PathBuf>,
+    /// 本轮还可以开始分析的视频数
+    remaining: usize,
+    /// 出现错误（限流除外）后不再开始新的分析
+    stopped: bool,
+    report: VideoAnalysisReport,
+    processing_error: Option<String>,
+}
+
+impl AnalysisBatch {
+    fn has_more(&self) -> bool {
+        !self.stopped && self.remaining > 0 && !self.queue.is_empty()
+    }
+
+    /// 上报等待分析的视频数
+    fn report_depth(&self) {
+        domains::telemetry::set(
+            domains::telemetry::Metric::QueueDepth,
+            &[("queue", "video_analysis")],
+            self.queue.len() as f64,
+        );
+    }
+}
+
+/// 分析工作者：循环从队列取视频分析，直到队列为空、预算用完或出错
+///
+/// 触发服务商限流时所有工作者暂停，之后只有第 0 个工作者继续
+async fn run_analysis_worker(
+    state: AppState,
+    llm_handle: actors::LLMHandle,
+    index: usize,
+    batch: Arc<std::sync::Mutex<AnalysisBatch>>,
+) {
+    let provider = state.storage_domain.get_settings().get().await.llm_provider;
+
+    loop {
+        if let Some(wait) = state.analysis_domain.rate_limit_remaining() {
+            info!("服务商限流，{} 秒后继续分析", wait.as_secs());
+            tokio::time::sleep(wait).await;
+        }
+
+        let next = {
+            let mut batch = batch.lock().unwrap();
+            if batch.has_more() {
+                batch.remaining -= 1;
+                // 用户要求立即分析的视频插队
+                let path = match state.analysis_domain.take_prioritized(&batch.queue) {
+                    Some(path) => {
+                        batch.queue.retain(|p| p != &path);
+                        path
+                    }
+                    None => batch.queue.remove(0),
+                };
+                batch.report_depth();
+                Some(path)
+            } else {
+                None
+            }
+        };
+        let Some(video_path) = next else {
+            break;
+        };
+        let video_path = &video_path;
+
+        info!("开始分析视频: {:?}", video_path);
+
+        let video_filename = video_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+
+        // 多台机器分析同一共享存储时，先认领视频避免重复分析（被认领的视频不占本轮预算）
+        let video_key = video_path.to_string_lossy().to_string();
+        let claim_db = state.storage_domain.try_get_db().await;
+        if let Some(db) = &claim_db {
+            if !domains::video_claims::try_claim(db, &video_key).await {
+                let mut batch = batch.lock().unwrap();
+                batch.remaining += 1;
+                batch
+                    .report
+                    .messages
+                    .push(format!("⏭ {}: 已由其他机器分析", video_filename));
+                continue;
+            }
+        }
+
+        let (session_start, session_end) = match parse_video_window_from_stem(video_filename) {
+            Some(window) => window,
+            None => {
+                // 文件名中没有时间窗口时，按当前的会话时长估计
+                let minutes = state
+                    .storage_domain
+                    .get_settings()
+                    .get()
+                    .await
+                    .summary_interval
+                    .max(1);
+                let end = storage::local_now();
+                (end - chrono::Duration::minutes(minutes as i64), end)
+            }
+        };
+
+        let diff = session_end.signed_duration_since(session_start);
+        let duration_minutes = if diff.num_seconds() > 0 {
+            ((diff.num_seconds() as f64) / 60.0).ceil() as u32
+        } else {
+            1
+        };
+
+        let started = std::time::Instant::now();
+        let result = analyze_video_once(
+            &state,
+            &llm_handle,
+            video_path,
+            session_start,
+            session_end,
+            duration_minutes,
+            None,
+        )
+        .instrument(logger::session_span(session_start))
+        .await;
+
+        // 分析失败时释放认领，让其他机器（或下一轮）可以重试
+        if let (Err(_), Some(db)) = (&result, &claim_db) {
+            domains::video_claims::release(db, &video_key).await;
+        }
+
+        // 分析结果计入服务商健康统计（视频本身的问题不算服务商故障）
+        match &result {
+            Err(err) if err.contains("VIDEO_TOO_SHORT") => {}
+            _ => state.analysis_domain.get_provider_health().record(
+                &provider,
+                started.elapsed().as_millis() as u64,
+                result.as_ref().err().cloned(),
+            ),
+        }
+
+        match result {
+            Ok(outcome) => {
+                info!(
+                    "视频分析成功: {} 个片段, {} 个卡片",
+                    outcome.segments_count, outcome.timeline_count
+                );
+                state.analysis_domain.clear_quota_exhausted();
+                let mut batch = batch.lock().unwrap();
+                batch.report.processed += 1;
+                batch.report.messages.push(format!(
+                    "✅ {}: {} 片段, {} 卡片",
+                    video_filename, outcome.segments_count, outcome.timeline_count
+                ));
+            }
+            Err(err) if domains::analysis::is_quota_error(&err) => {
+                let until = state.analysis_domain.note_quota_exhausted(&provider, &err);
+                warn!(
+                    "服务商额度已用完，{} 前暂停自动分析: {}",
+                    until.format("%Y-%m-%d %H:%M"),
+                    err
+                );
+                let mut batch = batch.lock().unwrap();
+                batch.report.failed += 1;
+                batch.report.messages.push(format!(
+                    "⛔ {}: 服务商额度已用完，{} 后自动重试 - {}",
+                    video_filename,
+                    until.format("%Y-%m-%d %H:%M"),
+                    err
+                ));
+                batch.stopped = true;
+                break;
+            }
+            Err(err) if domains::analysis::is_rate_limit_error(&err) => {
+                warn!("分析视频触发服务商限流: {}", err);
+                state.analysis_domain.note_rate_limited();
+                {
+                    let mut batch = batch.lock().unwrap();
+                    batch.report.failed += 1;
+                    batch
+                        .report
+                        .messages
+                        .push(format!("⏸ {}: 服务商限流 - {}", video_filename, err));
+                }
+                if index > 0 {
+                    break;
+                }
+                continue;
+            }
+            Err(err) => {
+                error!("视频分析失败: {}", err);
+
+                let mut batch = batch.lock().unwrap();
+                batch.report.failed += 1;
+                batch
+                    .report
+                    .messages
+                    .push(format!("❌ {}: 分析失败 - {}", video_filename, err));
+                batch.processing_error = Some(err);
+                batch.stopped = true;
+                break;
+            }
+        }
+
+        if batch.lock().unwrap().has_more() {
+            info!("等待2秒后继续分析下一个视频...");
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        }
+    }
+}
+
+/// 统计未分析视频的积压并更新状态栏警告，返回超过阈值时的积压状态
+///
+/// `last_error` 为最近一轮自动分析的失败原因（成功分析过视频后清空）
+async fn check_analysis_backlog(
     state: &AppState,
-    limit: Option<usize>,
-    mark_status: bool,
-) -> Result<VideoAnalysisReport, String> {
+    last_error: Option<String>,
+) -> Option<domains::analysis_backlog::AnalysisBacklogStatus> {
+    let config = state.storage_domain.get_settings().get().await;
+    let settings = config.analysis_backlog.clone().unwrap_or_default();
+    let videos = match list_unanalyzed_videos(state).await {
+        Ok(videos) => videos,
+        Err(e) => {
+            warn!("统计未分析视频失败: {}", e);
+            return domains::analysis_backlog::current_status();
+        }
+    };
+
+    let status = if settings.enabled && videos.len() > settings.threshold {
+        let (cause, message) = domains::analysis_backlog::diagnose(
+            domains::startup_check::missing_api_key(&config),
+            state
+                .analysis_domain
+                .get_provider_health()
+                .unhealthy_reason(&config.llm_provider),
+            last_error,
+        );
+        let oldest = videos
+            .iter()
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(parse_video_window_from_stem)
+            })
+            .map(|(start, _)| start)
+            .min()
+            .map(|start| start.format("%Y-%m-%d %H:%M").to_string());
+        Some(domains::analysis_backlog::AnalysisBacklogStatus {
+            count: videos.len(),
+            threshold: settings.threshold,
+            oldest,
+            cause,
+            message,
+        })
+    } else {
+        None
+    };
+    domains::analysis_backlog::set_current_status(status.clone());
+    status
+}
+
+/// 删除积压的未分析视频（连同旁路文件），返回删除的视频数
+///
+/// 最近 10 分钟内写入的视频可能仍在生成，不删除
+#[tauri::command]
+async fn delete_analysis_backlog(
+    state: tauri::State<'_, AppState>,
+    confirmation_token: Option<String>,
+) -> Result<usize, AppError> {
+    state.system_domain.ensure_writable()?;
+    state.system_domain.get_confirmations().consume(
+        DestructiveAction::DeleteAnalysisBacklog,
+        confirmation_token.as_deref(),
+    )?;
+    let cutoff = std::time::SystemTime::now() - std::time::Duration::from_secs(600);
+    let mut deleted = 0;
+    for path in list_unanalyzed_videos(&state).await? {
+        let recent = tokio::fs::metadata(&path)
+            .await
+            .and_then(|meta| meta.modified())
+            .map_or(true, |modified| modified > cutoff);
+        if recent {
+            continue;
+        }
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            warn!("删除未分析视频失败 {:?}: {}", path, e);
+            continue;
+        }
+        video::VideoMetadata::remove(&path).await;
+        domains::card_previews::remove_previews(&path).await;
+        domains::mood_strip::remove(&path).await;
+        deleted += 1;
+    }
+    info!("已删除 {} 个积压的未分析视频", deleted);
+    check_analysis_backlog(&state, None).await;
+    Ok(deleted)
+}
+
+/// 删除全部数据（截图、视频、数据库、配置和日志），完成后退出应用
+///
+/// `backup_path` 不为空时先把全部数据打包为 zip 备份到该路径；
+/// 运行中无法删除的文件（如打开中的数据库）在下次启动时删除
+#[tauri::command]
+async fn reset_all_data(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    confirmation_token: Option<String>,
+    backup_path: Option<String>,
+) -> Result<domains::data_reset::ResetReport, AppError> {
+    state.system_domain.ensure_writable()?;
+    state.system_domain.get_confirmations().consume(
+        DestructiveAction::ResetAllData,
+        confirmation_token.as_deref(),
+    )?;
+
+    // 先停止截屏，避免删除过程中写入新的截图
+    state.capture_domain.get_scheduler().set_paused(true);
+    state
+        .system_domain
+        .get_status_handle()
+        .set_capturing(false)
+        .await;
+
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let data_dir = storage::data_location::resolve_data_dir(&app_dir);
+    let log_dir = logger::log_dir();
+    let backup = backup_path
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from);
+    warn!("开始删除全部数据: {:?}", data_dir);
+    // 历史日志每隔几秒从内存写回文件，先停止写入并清空，避免删除后又被写回
+    state.system_domain.get_logger().stop_persistence();
+    let report = tokio::task::spawn_blocking(move || {
+        domains::data_reset::reset_all_data(
+            &app_dir,
+            &data_dir,
+            log_dir.as_deref(),
+            backup.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // 留出时间让前端显示结果，然后退出，避免继续写入新的数据
+    let handle = app.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        handle.exit(0);
+    });
+    Ok(report)
+}
+
+/// 视频目录中尚未分析的视频（按文件名排序）
+async fn list_unanalyzed_videos(state: &AppState) -> Result<Vec<PathBuf>, String> {
     use std::collections::HashSet;
 
     let videos_dir = state
@@ -3200,7 +7610,10 @@ async fn analyze_unprocessed_videos(
     if let Ok(mut entries) = tokio::fs::read_dir(&videos_dir).await {
         while let Ok(Some(entry)) = entries.next_entry().await {
             let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("mp4") {
+            // 编码中的视频写在 .part 文件里；其他设备拷贝过来的视频要等写入稳定后再分析
+            if path.extension().and_then(|s| s.to_str()) == Some("mp4")
+                && crate::video::processor::is_video_complete(&path).await
+            {
                 video_files.push(path);
             }
         }
@@ -3209,7 +7622,7 @@ async fn analyze_unprocessed_videos(
     video_files.sort();
 
     if video_files.is_empty() {
-        return Ok(VideoAnalysisReport::default());
+        return Ok(Vec::new());
     }
 
     // 使用新的抽象方法获取已分析的视频路径（支持 SQLite 和 MariaDB）
@@ -3223,15 +7636,48 @@ async fn analyze_unprocessed_videos(
 
     let analyzed_paths: HashSet<String> = analyzed_video_paths.into_iter().collect();
 
-    let mut unanalyzed_videos: Vec<PathBuf> = video_files
+    Ok(video_files
         .into_iter()
         .filter(|path| {
             let path_str = path.to_string_lossy().to_string();
             !analyzed_paths.contains(&path_str)
         })
-        .collect();
+        .collect())
+}
+
+async fn analyze_unprocessed_videos(
+    state: &AppState,
+    limit: Option<usize>,
+    mark_status: bool,
+) -> Result<VideoAnalysisReport, String> {
+    let unanalyzed_videos: Vec<(PathBuf, Option<chrono::DateTime<chrono::Utc>>)> =
+        list_unanalyzed_videos(state)
+            .await?
+            .into_iter()
+            .map(|path| {
+                let start = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(parse_video_window_from_stem)
+                    .map(|(start, _)| start);
+                (path, start)
+            })
+            .collect();
 
-    unanalyzed_videos.sort();
+    // 按分析队列设置排序（今天优先、每日预算等）
+    let queue_settings = state
+        .storage_domain
+        .get_settings()
+        .get()
+        .await
+        .analysis_queue
+        .unwrap_or_default();
+    let unanalyzed_videos = domains::analysis::order_analysis_queue(
+        unanalyzed_videos,
+        &queue_settings,
+        storage::local_now().date_naive(),
+        &state.analysis_domain.prioritized_videos(),
+    );
 
     let total_candidates = unanalyzed_videos.len();
     if total_candidates == 0 {
@@ -3249,6 +7695,35 @@ async fn analyze_unprocessed_videos(
         });
     }
 
+    // 服务商持续不可用时不提交分析，等健康探测恢复后再继续
+    let persisted_config = state.storage_domain.get_settings().get().await;
+    if let Some(reason) = state
+        .analysis_domain
+        .get_provider_health()
+        .unhealthy_reason(&persisted_config.llm_provider)
+    {
+        warn!("跳过视频分析: {}", reason);
+        return Ok(VideoAnalysisReport {
+            total_candidates,
+            messages: vec![format!("⏸ {}", reason)],
+            ..Default::default()
+        });
+    }
+
+    // 额度耗尽时自动扫描不提交分析，等到重试时间再试；用户手动触发的分析照常尝试，用于确认额度是否已恢复
+    if !mark_status {
+        if let Some(quota) = state.analysis_domain.quota_status() {
+            return Ok(VideoAnalysisReport {
+                total_candidates,
+                messages: vec![format!(
+                    "⏸ {} 额度已用完，{} 后自动重试",
+                    quota.provider, quota.until
+                )],
+                ..Default::default()
+            });
+        }
+    }
+
     // 使用单一的原子操作更新状态
     if mark_status {
         state
@@ -3263,89 +7738,69 @@ async fn analyze_unprocessed_videos(
             .await;
     }
 
-    let mut report = VideoAnalysisReport {
-        total_candidates,
-        processed: 0,
-        failed: 0,
-        messages: Vec::new(),
-    };
-
-    let mut processing_error: Option<String> = None;
-
-    for (index, video_path) in unanalyzed_videos.iter().enumerate() {
-        if index >= total_to_process {
-            break;
-        }
-
-        info!("开始分析视频: {:?}", video_path);
-
-        let video_filename = video_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-
-        let (session_start, session_end) = parse_video_window_from_stem(video_filename)
-            .unwrap_or_else(|| {
-                let end = storage::local_now();
-                (end - chrono::Duration::minutes(15), end)
-            });
+    // 多个工作者从同一队列取视频并发分析
+    let workers = domains::analysis::analysis_worker_count(
+        &queue_settings,
+        domains::performance::max_llm_calls(),
+    )
+    .min(total_to_process);
+    let batch = Arc::new(std::sync::Mutex::new(AnalysisBatch {
+        queue: unanalyzed_videos,
+        remaining: total_to_process,
+        stopped: false,
+        report: VideoAnalysisReport {
+            total_candidates,
+            ..Default::default()
+        },
+        processing_error: None,
+    }));
+    batch.lock().unwrap().report_depth();
 
-        let diff = session_end.signed_duration_since(session_start);
-        let duration_minutes = if diff.num_seconds() > 0 {
-            ((diff.num_seconds() as f64) / 60.0).ceil() as u32
-        } else {
-            1
-        };
+    if workers > 1 {
+        info!(
+            "使用 {} 个工作者并发分析 {} 个视频",
+            workers, total_to_process
+        );
+    }
 
-        match analyze_video_once(
-            state,
-            video_path,
-            session_start,
-            session_end,
-            duration_minutes,
-            None,
-        )
-        .await
+    let mut tasks = Vec::with_capacity(workers);
+    for index in 0..workers {
+        let llm_handle = match state
+            .analysis_domain
+            .llm_worker(
+                index,
+                &persisted_config.llm_provider,
+                persisted_config.llm_config.as_ref(),
+            )
+            .await
         {
-            Ok(outcome) => {
-                info!(
-                    "视频分析成功: {} 个片段, {} 个卡片",
-                    outcome.segments_count, outcome.timeline_count
-                );
-                report.processed += 1;
-                report.messages.push(format!(
-                    "✅ {}: {} 片段, {} 卡片",
-                    video_filename, outcome.segments_count, outcome.timeline_count
-                ));
-            }
-            Err(err) => {
-                error!("视频分析失败: {}", err);
-
-                // 如果是视频过短错误，删除视频文件避免反复尝试
-                if err.contains("VIDEO_TOO_SHORT") {
-                    info!("检测到视频过短错误，删除视频文件: {:?}", video_path);
-                    if let Err(e) = tokio::fs::remove_file(video_path).await {
-                        error!("删除视频文件失败: {}", e);
-                    } else {
-                        info!("已删除过短的视频文件: {:?}", video_path);
-                    }
-                }
-
-                report.failed += 1;
-                report
-                    .messages
-                    .push(format!("❌ {}: 分析失败 - {}", video_filename, err));
-                processing_error = Some(err);
+            Ok(handle) => handle,
+            Err(e) => {
+                warn!("创建第 {} 个分析工作者失败: {}", index + 1, e);
                 break;
             }
-        }
-
-        if total_to_process > 1 && (index + 1) < total_to_process {
-            info!("等待2秒后继续分析下一个视频...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        };
+        tasks.push(tokio::spawn(run_analysis_worker(
+            state.clone(),
+            llm_handle,
+            index,
+            batch.clone(),
+        )));
+    }
+    for task in tasks {
+        if let Err(e) = task.await {
+            error!("分析工作者异常退出: {}", e);
         }
     }
 
+    let (report, processing_error) = {
+        let mut batch = batch.lock().unwrap();
+        (
+            std::mem::take(&mut batch.report),
+            batch.processing_error.take(),
+        )
+    };
+
     // 使用单一的原子操作更新所有状态字段
     if mark_status {
         state
@@ -0,0 +1,570 @@
+// 实时状态 WebSocket 服务 - 供 OBS 浏览器源、Stream Deck 插件等本地小部件订阅
+//
+// 默认只监听 127.0.0.1，连接时需提供访问令牌（ws://127.0.0.1:<port>/?token=<token>
+// 或请求头 Authorization: Bearer <token>）。连接建立后立即发送一次当前状态，
+// 之后每次状态刷新都推送一条 JSON 消息。无界面模式下同样可用
+//
+// 同一端口还接受 POST 上报（令牌相同，请求体为 JSON）：
+// - /events：外部活动事件数组，供手机屏幕使用时间导出脚本等写入 external_events
+// - /browser/tab：浏览器扩展上报的活动标签页 {"url", "title", "focused"}，见 domains::browser
+//
+// 开启只读网页后改为监听所有网卡，并接受 GET 请求（令牌相同，见 domains::web_view）：
+// - /web：手机友好的单页，查看当天时间线、总结和统计
+// - /api/day?date=YYYY-MM-DD：页面使用的当天数据（JSON，默认今天）
+// - /api/day/text?date=YYYY-MM-DD&verbosity=brief|normal|detailed&format=text|markdown：
+//   文字版时间线，供终端和读屏软件使用（见 domains::day_text）
+//
+// 开启运行指标后同样监听所有网卡，GET /metrics 返回 Prometheus 文本格式的指标（令牌相同，见 domains::telemetry）
+//
+// 监听所有网卡时，其他设备只能访问只读网页和运行指标；POST 上报和 WebSocket 订阅仍只接受本机连接
+
+use crate::domains::browser::{BrowserTracker, TabUpdate};
+use crate::domains::day_text::{self, TimelineTextFormat, TimelineVerbosity};
+use crate::domains::external_events::{self, ExternalEventInput};
+use crate::domains::live::LiveFeed;
+use crate::domains::storage::StorageDomain;
+use crate::domains::telemetry;
+use crate::domains::web_view::{self, WEB_PAGE};
+use crate::models::LiveApiSettings;
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+/// 上报请求头的最大长度
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+
+/// 上报请求体的最大长度
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// 实时状态服务（可按设置重启）
+pub struct LiveServer {
+    feed: Arc<LiveFeed>,
+    /// 浏览器扩展上报的活动标签页
+    browser: Arc<BrowserTracker>,
+    /// 运行中的服务及其设置
+    running: Mutex<Option<(LiveApiSettings, JoinHandle<()>)>>,
+}
+
+impl LiveServer {
+    pub fn new(feed: Arc<LiveFeed>) -> Self {
+        Self {
+            feed,
+            browser: Arc::new(BrowserTracker::new()),
+            running: Mutex::new(None),
+        }
+    }
+
+    /// 按设置启动、重启或停止服务
+    pub async fn apply(
+        &self,
+        settings: &LiveApiSettings,
+        storage: &Arc<StorageDomain>,
+    ) -> Result<(), String> {
+        let mut running = self.running.lock().await;
+        if let Some((current, handle)) = running.as_ref() {
+            if current == settings && !handle.is_finished() {
+                return Ok(());
+            }
+        }
+        if let Some((_, handle)) = running.take() {
+            handle.abort();
+            // 等待任务结束，确保端口已释放
+            let _ = handle.await;
+            info!("实时状态服务已停止");
+        }
+
+        if !settings.enabled {
+            return Ok(());
+        }
+        if settings.token.trim().is_empty() {
+            return Err("开启实时状态服务前需要设置访问令牌".to_string());
+        }
+
+        // 只读网页和运行指标需要让其他设备访问，改为监听所有网卡
+        let host = if settings.web_ui || settings.metrics {
+            "0.0.0.0"
+        } else {
+            "127.0.0.1"
+        };
+        let listener = TcpListener::bind((host, settings.port))
+            .await
+            .map_err(|e| format!("监听端口 {} 失败: {}", settings.port, e))?;
+        info!("实时状态服务已启动: ws://{}:{}", host, settings.port);
+        if settings.web_ui {
+            info!(
+                "只读网页已开启: http://<本机局域网地址>:{}/web?token=<访问令牌>",
+                settings.port
+            );
+        }
+        if settings.metrics {
+            info!(
+                "运行指标已开启: http://<本机地址>:{}/metrics（Authorization: Bearer <访问令牌>）",
+                settings.port
+            );
+        }
+
+        let feed = self.feed.clone();
+        let storage = storage.clone();
+        let browser = self.browser.clone();
+        let token = Arc::new(settings.token.trim().to_string());
+        let web_ui = settings.web_ui;
+        let metrics = settings.metrics;
+        let handle = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve_connection(
+                            stream,
+                            feed.clone(),
+                            storage.clone(),
+                            browser.clone(),
+                            token.clone(),
+                            web_ui,
+                            metrics,
+                        ));
+                    }
+                    Err(e) => {
+                        error!("接受实时状态连接失败: {}", e);
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+        *running = Some((settings.clone(), handle));
+
+        Ok(())
+    }
+}
+
+/// 连接的处理方式
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    /// 数据上报
+    Post,
+    /// 只读网页或运行指标
+    Get,
+    /// WebSocket 订阅
+    Subscribe,
+    /// 其他设备的上报或订阅请求
+    Forbidden,
+}
+
+/// 按请求方法分流：POST 为数据上报，只读网页的 GET 请求返回页面或数据，其余按 WebSocket 订阅处理；
+/// 上报和订阅只接受本机连接
+fn route(prefix: &[u8], local: bool, web_ui: bool, metrics: bool) -> Route {
+    if (web_ui && is_web_request(prefix)) || (metrics && prefix.starts_with(b"GET /metrics")) {
+        Route::Get
+    } else if !local {
+        Route::Forbidden
+    } else if prefix.starts_with(b"POST ") {
+        Route::Post
+    } else {
+        Route::Subscribe
+    }
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    feed: Arc<LiveFeed>,
+    storage: Arc<StorageDomain>,
+    browser: Arc<BrowserTracker>,
+    token: Arc<String>,
+    web_ui: bool,
+    metrics: bool,
+) {
+    let local = stream.peer_addr().is_ok_and(|peer| peer.ip().is_loopback());
+    let mut prefix = [0u8; 16];
+    let read = stream.peek(&mut prefix).await.unwrap_or(0);
+    match route(&prefix[..read], local, web_ui, metrics) {
+        Route::Post => serve_post(stream, &storage, &browser, &token).await,
+        Route::Get => serve_get(stream, &storage, &token, web_ui, metrics).await,
+        Route::Subscribe => serve_client(stream, feed, token).await,
+        Route::Forbidden => {
+            write_response(
+                &mut stream,
+                "403 Forbidden",
+                "application/json",
+                &serde_json::json!({ "error": "上报和实时订阅只接受本机连接" }).to_string(),
+            )
+            .await
+        }
+    }
+}
+
+/// 是否为只读网页的请求（WebSocket 订阅使用其他路径）
+fn is_web_request(prefix: &[u8]) -> bool {
+    prefix.starts_with(b"GET /web") || prefix.starts_with(b"GET /api/")
+}
+
+/// 写入 HTTP 响应并关闭连接
+async fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        warn!("返回响应失败: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+/// 处理 POST 请求：校验令牌后按路径分发，返回 JSON
+async fn serve_post(
+    mut stream: TcpStream,
+    storage: &StorageDomain,
+    browser: &BrowserTracker,
+    token: &str,
+) {
+    let (status, body) = match handle_post(&mut stream, storage, browser, token).await {
+        Ok(result) => ("200 OK", result.to_string()),
+        Err((status, message)) => (status, serde_json::json!({ "error": message }).to_string()),
+    };
+    write_response(
+        &mut stream,
+        status,
+        "application/json; charset=utf-8",
+        &body,
+    )
+    .await;
+}
+
+/// 处理只读网页和运行指标的 GET 请求：校验令牌后返回页面、当天数据或指标
+async fn serve_get(
+    mut stream: TcpStream,
+    storage: &StorageDomain,
+    token: &str,
+    web_ui: bool,
+    metrics: bool,
+) {
+    let (status, content_type, body) =
+        match handle_get(&mut stream, storage, token, web_ui, metrics).await {
+            Ok((content_type, body)) => ("200 OK", content_type, body),
+            Err((status, message)) => (
+                status,
+                "application/json; charset=utf-8",
+                serde_json::json!({ "error": message }).to_string(),
+            ),
+        };
+    write_response(&mut stream, status, content_type, &body).await;
+}
+
+async fn handle_get(
+    stream: &mut TcpStream,
+    storage: &StorageDomain,
+    token: &str,
+    web_ui: bool,
+    metrics: bool,
+) -> Result<(&'static str, String), (&'static str, String)> {
+    let head = read_head(stream).await?;
+    if head.token() != Some(token) {
+        return Err(("401 Unauthorized", "访问令牌无效".to_string()));
+    }
+
+    match head.path.as_str() {
+        "/metrics" if metrics => Ok((
+            "text/plain; version=0.0.4; charset=utf-8",
+            telemetry::render(),
+        )),
+        _ if !web_ui => Err(("404 Not Found", format!("未知路径: {}", head.path))),
+        "/web" | "/web/" => Ok(("text/html; charset=utf-8", WEB_PAGE.to_string())),
+        "/api/day" => {
+            let date = head
+                .query_param("date")
+                .map(str::to_string)
+                .unwrap_or_else(|| crate::storage::local_now().format("%Y-%m-%d").to_string());
+            if chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+                return Err(("400 Bad Request", format!("日期格式错误: {}", date)));
+            }
+            let day = web_view::build_web_day(storage, &date)
+                .await
+                .map_err(|e| ("500 Internal Server Error", e))?;
+            let body = serde_json::to_string(&day)
+                .map_err(|e| ("500 Internal Server Error", e.to_string()))?;
+            Ok(("application/json; charset=utf-8", body))
+        }
+        "/api/day/text" => {
+            let date = head
+                .query_param("date")
+                .map(str::to_string)
+                .unwrap_or_else(|| crate::storage::local_now().format("%Y-%m-%d").to_string());
+            let verbosity = head
+                .query_param("verbosity")
+                .map(TimelineVerbosity::parse)
+                .transpose()
+                .map_err(|e| ("400 Bad Request", e))?
+                .unwrap_or_default();
+            let format = head
+                .query_param("format")
+                .map(TimelineTextFormat::parse)
+                .transpose()
+                .map_err(|e| ("400 Bad Request", e))?
+                .unwrap_or_default();
+            let db = storage
+                .get_db()
+                .await
+                .map_err(|e| ("503 Service Unavailable", e))?;
+            let text = day_text::get_day_timeline_text(&db, &date, verbosity, format)
+                .await
+                .map_err(|e| ("400 Bad Request", e))?;
+            Ok((format.content_type(), text))
+        }
+        path => Err(("404 Not Found", format!("未知路径: {}", path))),
+    }
+}
+
+/// 已读取的请求头（buffer 中 header_end 之后是已读到的部分请求体）
+struct RequestHead {
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    buffer: Vec<u8>,
+    header_end: usize,
+}
+
+impl RequestHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// 从查询参数 token 或 Authorization: Bearer 请求头中取出令牌
+    fn token(&self) -> Option<&str> {
+        self.query_param("token")
+            .or_else(|| {
+                self.header("authorization")
+                    .and_then(|value| value.strip_prefix("Bearer "))
+            })
+            .map(str::trim)
+    }
+}
+
+/// 读取并解析请求头
+async fn read_head(stream: &mut TcpStream) -> Result<RequestHead, (&'static str, String)> {
+    let bad_request = |message: String| ("400 Bad Request", message);
+
+    let mut buffer = Vec::new();
+    let header_end = loop {
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Err((
+                "431 Request Header Fields Too Large",
+                "请求头过长".to_string(),
+            ));
+        }
+        let mut chunk = [0u8; 4096];
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| bad_request(format!("读取请求失败: {}", e)))?;
+        if read == 0 {
+            return Err(bad_request("请求不完整".to_string()));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    Ok(RequestHead {
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        buffer,
+        header_end,
+    })
+}
+
+async fn handle_post(
+    stream: &mut TcpStream,
+    storage: &StorageDomain,
+    browser: &BrowserTracker,
+    token: &str,
+) -> Result<serde_json::Value, (&'static str, String)> {
+    let bad_request = |message: String| ("400 Bad Request", message);
+
+    let mut head = read_head(stream).await?;
+    if head.token() != Some(token) {
+        return Err(("401 Unauthorized", "访问令牌无效".to_string()));
+    }
+
+    // 读取请求体
+    let length: usize = head
+        .header("content-length")
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| bad_request("缺少 Content-Length".to_string()))?;
+    if length > MAX_BODY_BYTES {
+        return Err(("413 Payload Too Large", "请求体过大".to_string()));
+    }
+    let mut body = head.buffer.split_off(head.header_end);
+    if body.len() < length {
+        let mut rest = vec![0u8; length - body.len()];
+        stream
+            .read_exact(&mut rest)
+            .await
+            .map_err(|e| bad_request(format!("读取请求体失败: {}", e)))?;
+        body.extend_from_slice(&rest);
+    }
+    body.truncate(length);
+
+    let db = storage
+        .get_db()
+        .await
+        .map_err(|e| ("503 Service Unavailable", e))?;
+
+    match head.path.as_str() {
+        "/events" => {
+            let events: Vec<ExternalEventInput> = serde_json::from_slice(&body)
+                .map_err(|e| bad_request(format!("请求体不是有效的事件数组: {}", e)))?;
+            let result = external_events::ingest(&db, &events)
+                .await
+                .map_err(bad_request)?;
+            info!(
+                "收到 {} 个外部事件，新增 {} 个",
+                result.received, result.inserted
+            );
+            Ok(serde_json::to_value(result).unwrap_or_default())
+        }
+        "/browser/tab" => {
+            let update: TabUpdate = serde_json::from_slice(&body)
+                .map_err(|e| bad_request(format!("请求体不是有效的标签页状态: {}", e)))?;
+            browser
+                .update(&db, update)
+                .await
+                .map_err(|e| ("500 Internal Server Error", e))?;
+            Ok(serde_json::json!({ "ok": true }))
+        }
+        path => Err(("404 Not Found", format!("未知路径: {}", path))),
+    }
+}
+
+/// 处理单个订阅者：校验令牌、发送当前状态，再持续推送更新
+async fn serve_client(stream: TcpStream, feed: Arc<LiveFeed>, token: Arc<String>) {
+    let authorize = |request: &Request, response: Response| {
+        if request_token(request).as_deref() == Some(token.as_str()) {
+            Ok(response)
+        } else {
+            let mut rejection = ErrorResponse::new(Some("invalid token".to_string()));
+            *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(rejection)
+        }
+    };
+
+    let mut socket = match tokio_tungstenite::accept_hdr_async(stream, authorize).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("实时状态连接握手失败: {}", e);
+            return;
+        }
+    };
+
+    let mut updates = feed.subscribe();
+    let mut pending = Some(feed.latest());
+    loop {
+        if let Some(state) = pending.take() {
+            let Ok(text) = serde_json::to_string(&state) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(state) => pending = Some(state),
+                // 处理不过来时只推送最新状态
+                Err(broadcast::error::RecvError::Lagged(_)) => pending = Some(feed.latest()),
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// 从查询参数 token 或 Authorization: Bearer 请求头中取出令牌
+fn request_token(request: &Request) -> Option<String> {
+    let from_query = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "token")
+            .map(|(_, value)| value.to_string())
+    });
+
+    from_query.or_else(|| {
+        request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|value| value.trim().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lan_peers_only_reach_read_only_routes() {
+        assert_eq!(route(b"POST /events HTTP", true, true, true), Route::Post);
+        assert_eq!(
+            route(b"GET / HTTP/1.1", true, false, false),
+            Route::Subscribe
+        );
+        assert_eq!(route(b"GET /web?token=", false, true, false), Route::Get);
+        assert_eq!(route(b"GET /metrics HTTP", false, false, true), Route::Get);
+        assert_eq!(
+            route(b"POST /events HTTP", false, true, true),
+            Route::Forbidden
+        );
+        assert_eq!(
+            route(b"POST /browser/tab", false, true, true),
+            Route::Forbidden
+        );
+        assert_eq!(
+            route(b"GET / HTTP/1.1", false, true, true),
+            Route::Forbidden
+        );
+        // 未开启只读网页时其他设备不能访问网页
+        assert_eq!(
+            route(b"GET /web?token=", false, false, true),
+            Route::Forbidden
+        );
+    }
+}
@@ -0,0 +1,340 @@
+// 模型目录 - 从提供商的模型列表接口获取可用模型，供设置页下拉选择
+//
+// - openai（通义千问 / OpenAI 兼容）：GET <base>/models，base 由 chat/completions 地址推导
+// - claude：GET <base>/v1/models（x-api-key 或 Bearer 认证）
+// 列表接口通常不返回视觉能力和上下文长度，按模型名推断并补充已知模型的限制；
+// 只返回支持图像/视频输入的模型
+
+use crate::domains::local_only;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::info;
+
+/// 默认的 DashScope 兼容接口地址
+const DEFAULT_OPENAI_BASE_URL: &str =
+    "https://dashscope.aliyuncs.com/compatible-mode/v1/chat/completions";
+
+/// 默认的 Anthropic 接口地址
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Anthropic API 版本
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 模型信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelInfo {
+    /// 模型 ID（填入配置的 model 字段）
+    pub id: String,
+    /// 显示名称
+    pub display_name: String,
+    /// 上下文长度（token），未知时为空
+    pub context_window: Option<u64>,
+    /// 最大输出长度（token），未知时为空
+    pub max_output_tokens: Option<u64>,
+}
+
+/// 查询提供商支持视觉输入的模型列表
+///
+/// `config` 与设置页提交给 `configure_llm_provider` 的结构相同
+pub async fn list_models(provider: &str, config: &Value) -> Result<Vec<ModelInfo>> {
+    let client = local_only::http_client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let response = models_request(&client, provider, config)?
+        .send()
+        .await
+        .map_err(|e| anyhow!("请求模型列表失败: {}", e))?;
+    let body = read_json(response).await?;
+
+    let mut models = if provider == "openai" {
+        parse_openai_models(&body)
+    } else {
+        parse_anthropic_models(&body)
+    };
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models.dedup_by(|a, b| a.id == b.id);
+    info!("获取到 {} 个视觉模型 (provider={})", models.len(), provider);
+    Ok(models)
+}
+
+/// 探测提供商接口是否可用（不消耗 token）
+///
+/// 部分兼容代理没有模型列表接口，只要服务有响应且不是认证失败、限流或服务端错误即视为可用
+pub async fn probe(provider: &str, config: &Value) -> Result<()> {
+    let client = local_only::http_client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let response = models_request(&client, provider, config)?
+        .send()
+        .await
+        .map_err(|e| anyhow!("请求失败: {}", e))?;
+    let status = response.status();
+    if status.is_server_error() || matches!(status.as_u16(), 401 | 403 | 429) {
+        return Err(anyhow!("接口返回 {}", status));
+    }
+    Ok(())
+}
+
+/// 构造模型列表请求（地址和认证头按提供商区分）
+fn models_request(
+    client: &Client,
+    provider: &str,
+    config: &Value,
+) -> Result<reqwest::RequestBuilder> {
+    match provider {
+        "openai" => openai_models_request(client, config),
+        "claude" | "anthropic" => anthropic_models_request(client, config),
+        _ => Err(anyhow!("提供商 {} 不支持获取模型列表", provider)),
+    }
+}
+
+fn config_str<'a>(config: &'a Value, key: &str) -> Option<&'a str> {
+    config
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// 由 chat/completions 地址推导 /models 地址
+fn openai_models_endpoint(base_url: &str) -> String {
+    let base = base_url.trim().trim_end_matches('/');
+    let base = base.strip_suffix("/chat/completions").unwrap_or(base);
+    format!("{}/models", base)
+}
+
+fn openai_models_request(client: &Client, config: &Value) -> Result<reqwest::RequestBuilder> {
+    let api_key = config_str(config, "api_key").ok_or_else(|| anyhow!("API Key未配置"))?;
+    let endpoint =
+        openai_models_endpoint(config_str(config, "base_url").unwrap_or(DEFAULT_OPENAI_BASE_URL));
+
+    Ok(client
+        .get(&endpoint)
+        .header("Authorization", format!("Bearer {}", api_key)))
+}
+
+fn anthropic_models_request(client: &Client, config: &Value) -> Result<reqwest::RequestBuilder> {
+    let env_token = std::env::var("ANTHROPIC_AUTH_TOKEN")
+        .or_else(|_| std::env::var("ANTHROPIC_API_KEY"))
+        .ok();
+    let env_base_url = std::env::var("ANTHROPIC_BASE_URL").ok();
+
+    let base_url = config_str(config, "base_url")
+        .or(env_base_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty()))
+        .unwrap_or(DEFAULT_ANTHROPIC_BASE_URL)
+        .trim_end_matches('/')
+        .to_string();
+    let base_url = base_url.strip_suffix("/v1").unwrap_or(&base_url);
+
+    let request = client
+        .get(format!("{}/v1/models?limit=1000", base_url))
+        .header("anthropic-version", ANTHROPIC_VERSION);
+    if let Some(token) = config_str(config, "auth_token") {
+        Ok(request.header("Authorization", format!("Bearer {}", token)))
+    } else if let Some(key) = config_str(config, "api_key") {
+        Ok(request.header("x-api-key", key))
+    } else if let Some(token) = env_token {
+        Ok(request
+            .header("x-api-key", token.clone())
+            .header("Authorization", format!("Bearer {}", token)))
+    } else {
+        Err(anyhow!(
+            "Auth Token 未配置，且未找到环境变量 ANTHROPIC_AUTH_TOKEN"
+        ))
+    }
+}
+
+async fn read_json(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    if !status.is_success() {
+        let text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "无法读取错误信息".to_string());
+        return Err(anyhow!("模型列表接口返回错误 ({}): {}", status, text));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| anyhow!("解析模型列表失败: {}", e))
+}
+
+/// 解析 OpenAI 兼容格式 `{ "data": [{ "id": ... }] }`
+fn parse_openai_models(body: &Value) -> Vec<ModelInfo> {
+    model_entries(body)
+        .filter_map(|entry| {
+            let id = entry.get("id").and_then(Value::as_str)?;
+            if !is_vision_model(id) && !declares_image_input(entry) {
+                return None;
+            }
+            let (known_context, known_output) = known_limits(id);
+            Some(ModelInfo {
+                id: id.to_string(),
+                display_name: entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(id)
+                    .to_string(),
+                // OpenRouter / vLLM 等兼容服务会直接返回上下文长度
+                context_window: ["context_length", "context_window", "max_model_len"]
+                    .iter()
+                    .find_map(|key| entry.get(*key).and_then(Value::as_u64))
+                    .or(known_context),
+                max_output_tokens: entry
+                    .pointer("/top_provider/max_completion_tokens")
+                    .and_then(Value::as_u64)
+                    .or(known_output),
+            })
+        })
+        .collect()
+}
+
+/// 解析 Anthropic 格式 `{ "data": [{ "id", "display_name" }] }`
+fn parse_anthropic_models(body: &Value) -> Vec<ModelInfo> {
+    model_entries(body)
+        .filter_map(|entry| {
+            let id = entry.get("id").and_then(Value::as_str)?;
+            if !is_vision_model(id) {
+                return None;
+            }
+            let (known_context, known_output) = known_limits(id);
+            Some(ModelInfo {
+                id: id.to_string(),
+                display_name: entry
+                    .get("display_name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(id)
+                    .to_string(),
+                context_window: entry
+                    .get("max_input_tokens")
+                    .and_then(Value::as_u64)
+                    .or(known_context),
+                max_output_tokens: entry
+                    .get("max_tokens")
+                    .and_then(Value::as_u64)
+                    .or(known_output),
+            })
+        })
+        .collect()
+}
+
+fn model_entries(body: &Value) -> impl Iterator<Item = &Value> {
+    body.get("data")
+        .or_else(|| body.get("models"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// 兼容服务在 architecture.input_modalities 中声明了图像输入
+fn declares_image_input(entry: &Value) -> bool {
+    entry
+        .pointer("/architecture/input_modalities")
+        .and_then(Value::as_array)
+        .is_some_and(|modalities| modalities.iter().any(|m| m.as_str() == Some("image")))
+}
+
+/// 按模型名判断是否支持图像输入
+fn is_vision_model(id: &str) -> bool {
+    let id = id.to_lowercase();
+    // 嵌入、语音、图像生成等模型即使名字里带 vision 相关字样也不能用于分析
+    const EXCLUDED: [&str; 8] = [
+        "embedding",
+        "tts",
+        "whisper",
+        "audio",
+        "realtime",
+        "dall-e",
+        "image-gen",
+        "moderation",
+    ];
+    if EXCLUDED.iter().any(|word| id.contains(word)) {
+        return false;
+    }
+
+    const VISION_MARKERS: [&str; 9] = [
+        "-vl", "vl-", "qvq", "vision", "omni", "4v", "gpt-4o", "gpt-4.1", "gpt-5",
+    ];
+    if VISION_MARKERS.iter().any(|marker| id.contains(marker)) {
+        return true;
+    }
+
+    // Claude 3 之后的模型都支持图像输入；Gemini 全系多模态
+    (id.starts_with("claude-") && !id.starts_with("claude-2") && !id.contains("instant"))
+        || id.starts_with("gemini")
+}
+
+/// 已知模型的上下文长度和最大输出（按前缀匹配，越具体的越靠前）
+fn known_limits(id: &str) -> (Option<u64>, Option<u64>) {
+    const KNOWN: [(&str, u64, u64); 11] = [
+        ("qwen-vl-max", 131_072, 8_192),
+        ("qwen-vl-plus", 131_072, 8_192),
+        ("qwen2.5-vl", 131_072, 8_192),
+        ("qwen3-vl", 262_144, 32_768),
+        ("claude-opus-4", 200_000, 32_000),
+        ("claude-sonnet-4", 200_000, 64_000),
+        ("claude-haiku-4", 200_000, 64_000),
+        ("claude-3-7-sonnet", 200_000, 64_000),
+        ("claude-3", 200_000, 8_192),
+        ("gpt-4o", 128_000, 16_384),
+        ("gpt-4.1", 1_047_576, 32_768),
+    ];
+    let id = id.to_lowercase();
+    KNOWN
+        .iter()
+        .find(|(prefix, _, _)| id.starts_with(prefix))
+        .map(|(_, context, output)| (Some(*context), Some(*output)))
+        .unwrap_or((None, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_models_endpoint_and_parsing() {
+        assert_eq!(
+            openai_models_endpoint(DEFAULT_OPENAI_BASE_URL),
+            "https://dashscope.aliyuncs.com/compatible-mode/v1/models"
+        );
+        assert_eq!(
+            openai_models_endpoint("http://localhost:8000/v1/"),
+            "http://localhost:8000/v1/models"
+        );
+
+        let models = parse_openai_models(&json!({
+            "data": [
+                { "id": "qwen-vl-max-latest" },
+                { "id": "qwen-max" },
+                { "id": "text-embedding-v3" },
+                { "id": "llava-local", "max_model_len": 32768,
+                  "architecture": { "input_modalities": ["text", "image"] } }
+            ]
+        }));
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["qwen-vl-max-latest", "llava-local"]);
+        assert_eq!(models[0].context_window, Some(131_072));
+        assert_eq!(models[1].context_window, Some(32_768));
+        assert_eq!(models[1].max_output_tokens, None);
+
+        let models = parse_anthropic_models(&json!({
+            "data": [
+                { "id": "claude-sonnet-4-5-20250929", "display_name": "Claude Sonnet 4.5" },
+                { "id": "claude-2.1", "display_name": "Claude 2.1" }
+            ]
+        }));
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].display_name, "Claude Sonnet 4.5");
+        assert_eq!(models[0].max_output_tokens, Some(64_000));
+    }
+}
@@ -2,7 +2,10 @@
 //
 // 参考 Ollama provider 的图片处理逻辑：将帧图片转为 base64 发送给 Claude API
 
+use super::personalization;
 use super::plugin::*;
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::storage::CardCorrectionRecord;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
@@ -22,6 +25,9 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tracing::{debug, error, info, warn};
 
+/// 提示词模板版本（修改提示词时递增，记录在 LLM 调用中便于追溯）
+pub const PROMPT_VERSION: &str = "claude-v2";
+
 /// 读取 Claude CLI 的会话令牌（Windows 平台）
 #[cfg(target_os = "windows")]
 pub(crate) fn read_claude_cli_session_token() -> Option<String> {
@@ -118,6 +124,8 @@ pub struct ClaudeProvider {
     /// 当前分析的绝对时间窗口（UTC）
     session_window_start: Option<DateTime<Utc>>,
     session_window_end: Option<DateTime<Utc>>,
+    /// 用户纠正记录（提示词示例）
+    correction_examples: Vec<CardCorrectionRecord>,
 }
 
 impl ClaudeProvider {
@@ -133,6 +141,7 @@ impl ClaudeProvider {
             session_video_path: None,
             session_window_start: None,
             session_window_end: None,
+            correction_examples: Vec::new(),
         }
     }
 
@@ -454,6 +463,20 @@ impl ClaudeProvider {
             text_bytes as f64 / 1024.0,
             request_body_bytes as f64 / 1024.0 / 1024.0
         );
+        egress::record(
+            EgressChannel::Llm,
+            self.base_url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .unwrap_or("https://api.anthropic.com"),
+            if image_count > 0 {
+                EgressContent::Screenshots
+            } else {
+                EgressContent::Text
+            },
+            (request_body_bytes + system_prompt.len()) as u64,
+            self.current_session_id,
+        );
 
         let message_payload = json!({
             "type": "user",
@@ -492,6 +515,7 @@ impl ClaudeProvider {
             latency_ms: None,
             token_usage: None,
             created_at: crate::storage::local_now(),
+            prompt_version: Some(PROMPT_VERSION.to_string()),
         };
 
         let mut options = ClaudeAgentOptions::builder()
@@ -705,6 +729,7 @@ impl ClaudeProvider {
                 .take()
                 .unwrap_or_else(|| anyhow!("Claude Agent 未返回任何内容"));
             llm_record.error_message = Some(err.to_string());
+            crate::llm::debug_archive::archive(&llm_record, &request_snapshot);
             if let Some(ref db) = self.db {
                 let _ = db.insert_llm_call(&llm_record).await;
             }
@@ -712,6 +737,7 @@ impl ClaudeProvider {
         }
 
         llm_record.error_message = stream_error.as_ref().map(|e| e.to_string());
+        crate::llm::debug_archive::archive(&llm_record, &request_snapshot);
 
         if let Some(ref db) = self.db {
             match db.insert_llm_call(&llm_record).await {
@@ -1155,6 +1181,7 @@ Analyze these screenshots from a screen recording session and create meaningful
 - `distractions` 必须是数组，若无干扰请返回 []；如果存在干扰对象，必须包含 `startTime`、`endTime`、`title`、`summary` 字段，均使用 MM:SS 相对时间和中文描述。
 - `appSites.secondary` 必须是数组，若无元素返回 []，不要使用字符串。
 - 如果识别到主要应用/站点，请填写 `appSites.primary`。
+- `confidence` 为 0-1 之间的数字，表示你对卡片内容的把握程度；画面模糊或活动难以判断时给出较低的值。
 - 输出结果禁止使用 Markdown 或代码块标记（不要包裹 ```json）。
 - **重要**: JSON 中必须使用 ASCII 引号 ("), 绝不要使用中文引号 ("" 或 '')。
 - 可以参考历史卡片（如下），保持字段兼容。
@@ -1177,6 +1204,7 @@ Analyze these screenshots from a screen recording session and create meaningful
       "primary": "visualstudio.com",
       "secondary": ["github.com"]
     }},
+    "confidence": 0.9,
     "isUpdated": false
   }}
 ]
@@ -1204,6 +1232,7 @@ Return ONLY the JSON array (确保startTime/endTime等字段存在，并使用
                     secondary: Some(vec![]),
                 },
                 video_preview_path: None,
+                confidence: None,
             };
         }
 
@@ -1265,6 +1294,8 @@ Return ONLY the JSON array (确保startTime/endTime等字段存在，并使用
             .unwrap_or_else(|| first.app_sites.primary.clone());
 
         let video_preview_path = cards.iter().find_map(|c| c.video_preview_path.clone());
+        // 合并后的卡片取各卡片中最低的置信度
+        let confidence = cards.iter().filter_map(|c| c.confidence).reduce(f64::min);
 
         TimelineCard {
             start_time: first.start_time.clone(),
@@ -1284,6 +1315,7 @@ Return ONLY the JSON array (确保startTime/endTime等字段存在，并使用
                 },
             },
             video_preview_path,
+            confidence,
         }
     }
 }
@@ -1299,6 +1331,10 @@ impl LLMProvider for ClaudeProvider {
         self.session_window_end = end;
     }
 
+    fn set_correction_examples(&mut self, corrections: Vec<CardCorrectionRecord>) {
+        self.correction_examples = corrections;
+    }
+
     async fn analyze_frames(&self, frames: Vec<String>) -> Result<SessionSummary> {
         info!("Claude 开始分析 {} 帧图像", frames.len());
 
@@ -1456,7 +1492,8 @@ Return ONLY the JSON object."#
         }
 
         // 添加文本提示
-        let prompt = self.build_segment_prompt(duration);
+        let mut prompt = self.build_segment_prompt(duration);
+        personalization::append_examples(&mut prompt, &self.correction_examples, "");
         user_content.push(json!({
             "type": "text",
             "text": prompt
@@ -1538,6 +1575,11 @@ Return ONLY the JSON object."#
         let mut prompt = self.build_timeline_prompt(&previous_cards);
         prompt.push_str("\n\nCurrent video segments:\n");
         prompt.push_str(&serde_json::to_string_pretty(&segments)?);
+        personalization::append_examples(
+            &mut prompt,
+            &self.correction_examples,
+            &personalization::segments_context(&segments),
+        );
 
         let user_content = vec![json!({
             "type": "text",
@@ -1588,7 +1630,12 @@ Return ONLY the JSON object."#
         Ok(cards)
     }
 
-    async fn generate_day_summary(&self, date: &str, sessions: &[SessionBrief]) -> Result<String> {
+    async fn generate_day_summary(
+        &self,
+        date: &str,
+        sessions: &[SessionBrief],
+        day_type: DayType,
+    ) -> Result<String> {
         let total_minutes: i64 = sessions
             .iter()
             .map(|s| (s.end_time - s.start_time).num_minutes())
@@ -1607,9 +1654,9 @@ Return ONLY the JSON object."#
         }
 
         let prompt = format!(
-            r#"基于以下今日屏幕活动记录，生成一份工作总结：
+            r#"基于以下今日屏幕活动记录，生成一份{}：
 
-日期: {}
+日期: {}（{}）
 会话数: {}
 总时长: {} 分钟
 
@@ -1621,12 +1668,16 @@ Return ONLY the JSON object."#
 2. 重点总结真正在做什么工作/活动
 3. 按时间顺序或主题归纳今天的主要工作内容
 4. 字数控制在 150-200 字以内
+5. {}
 
 请直接返回总结文本（纯文本，不要 JSON，不要 markdown）。"#,
+            day_type.summary_kind(),
             date,
+            day_type.label(),
             sessions.len(),
             total_minutes,
-            sessions_text
+            sessions_text,
+            day_type.tone_requirement()
         );
 
         let user_content = vec![json!({
@@ -1643,6 +1694,16 @@ Return ONLY the JSON object."#
         Ok(response.trim().to_string())
     }
 
+    async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        let user_content = vec![json!({
+            "type": "text",
+            "text": prompt
+        })];
+        let system_prompt = "You are planning a realistic work week.".to_string();
+        self.call_claude_api_with_retry(system_prompt, user_content, "week_plan")
+            .await
+    }
+
     fn name(&self) -> &str {
         "Claude"
     }
@@ -1,6 +1,9 @@
 // Codex CLI 提供商实现 - 使用 codex exec 无头模式
 
+use super::personalization;
 use super::plugin::*;
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::storage::CardCorrectionRecord;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Duration as ChronoDuration, Utc};
@@ -17,6 +20,9 @@ use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 use tracing::warn;
 
+/// 内置提示词模板版本（使用自定义提示词时记为 custom）
+pub const PROMPT_VERSION: &str = "codex-v2";
+
 /// Codex CLI 提供商
 pub struct CodexProvider {
     binary_path: PathBuf,
@@ -36,6 +42,8 @@ pub struct CodexProvider {
     last_call_ids: Mutex<HashMap<String, i64>>,
     session_window_start: Option<DateTime<Utc>>,
     session_window_end: Option<DateTime<Utc>>,
+    /// 用户纠正记录（提示词示例）
+    correction_examples: Vec<CardCorrectionRecord>,
 }
 
 impl CodexProvider {
@@ -59,6 +67,7 @@ impl CodexProvider {
             last_call_ids: Mutex::new(HashMap::new()),
             session_window_start: None,
             session_window_end: None,
+            correction_examples: Vec::new(),
         }
     }
 
@@ -84,6 +93,22 @@ impl CodexProvider {
         }
     }
 
+    /// 调用所用提示词的版本（该调用使用了自定义提示词时为 custom）
+    fn prompt_version(&self, call_type: &str) -> String {
+        let prompt_override = match call_type {
+            "analyze_frames" => &self.summary_prompt_override,
+            "segment_video" => &self.segment_prompt_override,
+            "generate_timeline" => &self.timeline_prompt_override,
+            "generate_day_summary" => &self.day_summary_prompt_override,
+            _ => &None,
+        };
+        if prompt_override.is_some() {
+            "custom".to_string()
+        } else {
+            PROMPT_VERSION.to_string()
+        }
+    }
+
     fn summary_prompt(&self) -> String {
         if let Some(prompt) = &self.summary_prompt_override {
             return prompt.clone();
@@ -153,7 +178,7 @@ impl CodexProvider {
 2. 合并相邻、主题一致的活动
 3. 如有轻微分心，请在 card 内描述，不单独拆分
 4. category 使用 snake_case：work / communication / learning / personal / idle / other
-5. 字段：startTime、endTime、category、subcategory、title、summary、detailedSummary、distractions、appSites
+5. 字段：startTime、endTime、category、subcategory、title、summary、detailedSummary、distractions、appSites、confidence（0-1，对卡片内容的把握程度）
 6. 仅返回 JSON 数组
 
 历史卡片（可选，便于合并）：
@@ -163,7 +188,12 @@ impl CodexProvider {
         )
     }
 
-    fn day_summary_prompt(&self, date: &str, sessions: &[SessionBrief]) -> String {
+    fn day_summary_prompt(
+        &self,
+        date: &str,
+        sessions: &[SessionBrief],
+        day_type: DayType,
+    ) -> String {
         if let Some(prompt) = &self.day_summary_prompt_override {
             return prompt.clone();
         }
@@ -185,12 +215,15 @@ impl CodexProvider {
             .sum();
 
         format!(
-            r#"基于以下 {count} 个会话，概括 {date} 的主要工作（150 字以内，中文）：
+            r#"基于以下 {count} 个会话，为 {date}（{label}）写一份{kind}（150 字以内，中文）：
 总时长：{total} 分钟
 {lines}
-要求重点描述完成的事情、重要节点及未完成事项，语气专业自然。"#,
+要求重点描述完成的事情、重要节点及未完成事项，语气专业自然。{tone}。"#,
             count = sessions.len(),
             date = date,
+            label = day_type.label(),
+            kind = day_type.summary_kind(),
+            tone = day_type.tone_requirement(),
             total = total_minutes,
             lines = lines
         )
@@ -278,6 +311,26 @@ impl CodexProvider {
             .spawn()
             .map_err(|e| anyhow!("启动 codex CLI 失败: {}", e))?;
 
+        // Codex CLI 自行联网，按提示词和图片文件大小记录外发量
+        let mut image_bytes = 0u64;
+        for image in images {
+            image_bytes += tokio::fs::metadata(image)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0);
+        }
+        egress::record(
+            EgressChannel::Llm,
+            "codex-cli",
+            if images.is_empty() {
+                EgressContent::Text
+            } else {
+                EgressContent::Screenshots
+            },
+            prompt.len() as u64 + image_bytes,
+            self.current_session_id,
+        );
+
         let start = Instant::now();
         let output = match timeout(
             Duration::from_secs(self.timeout_secs),
@@ -327,6 +380,7 @@ impl CodexProvider {
             latency_ms: Some(latency_ms),
             token_usage: None,
             created_at: crate::storage::local_now(),
+            prompt_version: Some(self.prompt_version(call_type)),
         };
 
         if !stderr.trim().is_empty() {
@@ -340,6 +394,15 @@ impl CodexProvider {
             record.error_message = Some(truncate_for_log(&stderr, 2000));
         }
 
+        // 调试归档保存完整输出，数据库中只保留截断后的内容
+        let mut archived = record.clone();
+        if output.status.success() {
+            archived.response_body = Some(stdout.clone());
+        } else {
+            archived.error_message = Some(stderr.clone());
+        }
+        crate::llm::debug_archive::archive(&archived, &request_body);
+
         if let Some(db) = self.db.clone() {
             if let Ok(id) = db.insert_llm_call(&record).await {
                 if output.status.success() {
@@ -440,6 +503,7 @@ impl CodexProvider {
                     secondary: None,
                 },
                 video_preview_path: None,
+                confidence: None,
             })
             .collect()
     }
@@ -497,8 +561,10 @@ impl LLMProvider for CodexProvider {
             return Err(anyhow!("采样后没有有效图片路径"));
         }
 
+        let mut prompt = self.segment_prompt(duration);
+        personalization::append_examples(&mut prompt, &self.correction_examples, "");
         let response = self
-            .run_codex_exec(&self.segment_prompt(duration), &images, "segment_video")
+            .run_codex_exec(&prompt, &images, "segment_video")
             .await?;
 
         let mut segments: Vec<VideoSegment> = self.parse_json(&response)?;
@@ -522,6 +588,11 @@ impl LLMProvider for CodexProvider {
         let mut prompt = self.timeline_prompt(&previous_cards);
         prompt.push_str("\n\n当前视频分段：\n");
         prompt.push_str(&serde_json::to_string_pretty(&segments)?);
+        personalization::append_examples(
+            &mut prompt,
+            &self.correction_examples,
+            &personalization::segments_context(&segments),
+        );
 
         let response = self
             .run_codex_exec(&prompt, &[], "generate_timeline")
@@ -548,6 +619,10 @@ impl LLMProvider for CodexProvider {
         self.session_window_end = end;
     }
 
+    fn set_correction_examples(&mut self, corrections: Vec<CardCorrectionRecord>) {
+        self.correction_examples = corrections;
+    }
+
     fn name(&self) -> &str {
         "Codex"
     }
@@ -652,17 +727,26 @@ impl LLMProvider for CodexProvider {
             .and_then(|map| map.get(call_type).copied())
     }
 
-    async fn generate_day_summary(&self, date: &str, sessions: &[SessionBrief]) -> Result<String> {
+    async fn generate_day_summary(
+        &self,
+        date: &str,
+        sessions: &[SessionBrief],
+        day_type: DayType,
+    ) -> Result<String> {
         if sessions.is_empty() {
             return Ok(format!("{} 当天没有记录到任何屏幕活动。", date));
         }
 
-        let prompt = self.day_summary_prompt(date, sessions);
+        let prompt = self.day_summary_prompt(date, sessions, day_type);
         let response = self
             .run_codex_exec(&prompt, &[], "generate_day_summary")
             .await?;
         Ok(response.trim().to_string())
     }
+
+    async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        self.run_codex_exec(prompt, &[], "week_plan").await
+    }
 }
 
 fn truncate_for_log(input: &str, max_len: usize) -> String {
@@ -0,0 +1,220 @@
+// LLM 调试归档 - 按需保存完整的请求/响应报文
+//
+// 数据库中的 llm_calls 记录会通过 sanitize_request_body 去掉图片/视频 base64，
+// 排查分析结果异常时往往需要完整报文，因此提供一个默认关闭的调试模式：
+// - 每次调用的完整请求和响应以 gzip 压缩后写入 llm_debug 目录
+// - 超过保留天数的归档自动删除
+// - 目录总大小超过上限时从最旧的归档开始删除
+// - 报文中链接的查询参数（如 OSS 临时链接的签名）写入前去掉，归档文件不能用来下载视频
+
+use crate::models::LlmDebugArchiveSettings;
+use crate::storage::LLMCallRecord;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// 归档文件扩展名
+const ARCHIVE_EXTENSION: &str = "json.gz";
+
+/// 全局归档配置（目录 + 设置），未配置时不归档
+static ARCHIVE: OnceLock<RwLock<Option<(PathBuf, LlmDebugArchiveSettings)>>> = OnceLock::new();
+
+fn archive_state() -> &'static RwLock<Option<(PathBuf, LlmDebugArchiveSettings)>> {
+    ARCHIVE.get_or_init(|| RwLock::new(None))
+}
+
+/// 配置归档目录和设置（启动时及设置变更时调用）
+pub fn configure(dir: PathBuf, settings: LlmDebugArchiveSettings) {
+    if let Ok(mut state) = archive_state().write() {
+        *state = Some((dir, settings));
+    }
+}
+
+/// 更新归档设置（保持目录不变）
+pub fn update_settings(settings: LlmDebugArchiveSettings) {
+    if let Ok(mut state) = archive_state().write() {
+        if let Some((_, current)) = state.as_mut() {
+            *current = settings;
+        }
+    }
+}
+
+/// 获取归档目录
+pub fn archive_dir() -> Option<PathBuf> {
+    archive_state()
+        .read()
+        .ok()
+        .and_then(|state| state.as_ref().map(|(dir, _)| dir.clone()))
+}
+
+/// 归档一次 LLM 调用（调试模式关闭时直接返回）
+///
+/// 写入在阻塞线程中进行，不影响调用方
+pub fn archive(record: &LLMCallRecord, request: &Value) {
+    let Some((dir, settings)) = archive_state().read().ok().and_then(|state| state.clone()) else {
+        return;
+    };
+    if !settings.enabled {
+        return;
+    }
+
+    let now = crate::storage::local_now();
+    let file_name = format!(
+        "{}_{}_{}.{}",
+        now.format("%Y%m%d_%H%M%S%3f"),
+        record.provider,
+        record.call_type,
+        ARCHIVE_EXTENSION
+    );
+    let mut request = request.clone();
+    redact_value(&mut request);
+    let payload = json!({
+        "provider": record.provider,
+        "model": record.model,
+        "call_type": record.call_type,
+        "session_id": record.session_id,
+        "status_code": record.status_code,
+        "latency_ms": record.latency_ms,
+        "created_at": now.to_rfc3339(),
+        "request": request,
+        "response": record.response_body.as_deref().map(redact_text),
+        "error": record.error_message.as_deref().map(redact_text),
+    });
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = write_archive(&dir, &file_name, &payload, &settings) {
+            warn!("保存 LLM 调试归档失败: {}", e);
+        }
+    });
+}
+
+/// 去掉文本中链接的查询参数（签名、令牌等）
+fn redact_text(text: &str) -> String {
+    static SIGNED_URL: OnceLock<Regex> = OnceLock::new();
+    let re =
+        SIGNED_URL.get_or_init(|| Regex::new(r#"(https?|oss)://[^\s"'?]+\?[^\s"']*"#).unwrap());
+    re.replace_all(text, |caps: &regex::Captures| {
+        let url = caps.get(0).map_or("", |m| m.as_str());
+        format!("{}?<redacted>", url.split('?').next().unwrap_or_default())
+    })
+    .into_owned()
+}
+
+/// 递归去掉请求报文中所有链接的查询参数（包括 video_url）
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            if text.contains("://") {
+                *text = redact_text(text);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => map.values_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// 压缩写入归档文件，并清理过期/超限的旧归档
+fn write_archive(
+    dir: &Path,
+    file_name: &str,
+    payload: &Value,
+    settings: &LlmDebugArchiveSettings,
+) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建归档目录失败: {}", e))?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, payload).map_err(|e| e.to_string())?;
+    let compressed = encoder.finish().map_err(|e| e.to_string())?;
+
+    let max_file_bytes = settings.max_file_mb * 1024 * 1024;
+    if compressed.len() as u64 > max_file_bytes {
+        warn!(
+            "LLM 调试归档压缩后 {} 字节，超过单文件上限 {} MB，已跳过",
+            compressed.len(),
+            settings.max_file_mb
+        );
+        return Ok(());
+    }
+
+    let path = dir.join(file_name);
+    let mut file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+    file.write_all(&compressed).map_err(|e| e.to_string())?;
+    debug!("已保存 LLM 调试归档: {:?}", path);
+
+    prune_archives(dir, settings);
+    Ok(())
+}
+
+/// 删除过期归档，并在总大小超限时从最旧的开始删除
+fn prune_archives(dir: &Path, settings: &LlmDebugArchiveSettings) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files: Vec<(PathBuf, SystemTime, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(ARCHIVE_EXTENSION)
+        })
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            Some((entry.path(), meta.modified().ok()?, meta.len()))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified, _)| *modified);
+
+    let expire_before = SystemTime::now()
+        .checked_sub(Duration::from_secs(
+            settings.retention_days as u64 * 24 * 3600,
+        ))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let max_total_bytes = settings.max_total_mb * 1024 * 1024;
+    let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+
+    for (path, modified, size) in files {
+        if modified >= expire_before && total <= max_total_bytes {
+            break;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(_) => total = total.saturating_sub(size),
+            Err(e) => warn!("删除 LLM 调试归档失败 {:?}: {}", path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_urls_are_redacted() {
+        let mut request = json!({
+            "messages": [{
+                "content": [
+                    {"type": "video_url", "video_url": {"url": "https://bucket.oss-cn-hangzhou.aliyuncs.com/a.mp4?Expires=1&Signature=abc"}},
+                    {"type": "text", "text": "keep 12:30?"}
+                ]
+            }]
+        });
+        redact_value(&mut request);
+        assert_eq!(
+            request["messages"][0]["content"][0]["video_url"]["url"],
+            "https://bucket.oss-cn-hangzhou.aliyuncs.com/a.mp4?<redacted>"
+        );
+        assert_eq!(request["messages"][0]["content"][1]["text"], "keep 12:30?");
+        assert_eq!(
+            redact_text(r#"{"error":"cannot fetch oss://bucket/a.mp4?token=x"}"#),
+            r#"{"error":"cannot fetch oss://bucket/a.mp4?<redacted>"}"#
+        );
+    }
+}
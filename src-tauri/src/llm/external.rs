@@ -0,0 +1,544 @@
+// 外部 LLM 插件 - 通过子进程 JSON-RPC 接入自定义提供商
+//
+// 插件放在 <应用数据目录>/plugins/<插件目录>/ 下，目录中的 plugin.json 为清单：
+//   { "id": "my-llm", "name": "我的模型", "version": "1.0", "description": "...", "command": "./my-llm" }
+// 选择插件时 provider 名称为 plugin:<id>。首次调用时启动插件进程（工作目录为插件目录），
+// 通过标准输入/输出逐行交换 JSON-RPC 2.0 消息，启动后先发送 configure：
+//   configure            { config }                                          -> 任意
+//   segment_video        { frames, duration, video_path, session_start, session_end } -> [VideoSegment]
+//   generate_timeline    { segments, previous_cards, session_start, session_end }     -> [TimelineCard]
+//   analyze_frames       { frames }                                          -> SessionSummary
+//   generate_day_summary { date, sessions, day_type }                        -> 字符串
+//   generate_meeting_notes { frames, context }                               -> MeetingNotes
+// 插件未实现 generate_day_summary 时（返回 -32601）使用默认的规则总结。
+// 只支持子进程方式，不加载动态库：避免 ABI 兼容问题，插件崩溃也不会带垮主进程
+
+use super::plugin::*;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// 插件 provider 名称前缀
+pub const PLUGIN_PREFIX: &str = "plugin:";
+
+/// 插件清单文件名
+pub const MANIFEST_FILE: &str = "plugin.json";
+
+/// JSON-RPC 方法不存在的错误码
+const METHOD_NOT_FOUND: i64 = -32601;
+
+fn default_plugin_timeout_secs() -> u64 {
+    300
+}
+
+static PLUGINS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置插件目录（启动时调用一次）
+pub fn set_plugins_dir(dir: PathBuf) {
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        warn!("创建插件目录失败 {:?}: {}", dir, e);
+    }
+    let _ = PLUGINS_DIR.set(dir);
+}
+
+/// 插件目录
+pub fn plugins_dir() -> Option<&'static Path> {
+    PLUGINS_DIR.get().map(|dir| dir.as_path())
+}
+
+/// 插件清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// 插件 ID（provider 名称为 plugin:<id>）
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 可执行文件（相对路径基于插件目录）
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 插件版本（记录在 LLM 调用中便于追溯）
+    #[serde(default)]
+    pub version: String,
+    /// 单次调用超时（秒）
+    #[serde(default = "default_plugin_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 插件所在目录
+    #[serde(default)]
+    pub dir: PathBuf,
+}
+
+impl PluginManifest {
+    /// provider 名称
+    pub fn provider_name(&self) -> String {
+        format!("{}{}", PLUGIN_PREFIX, self.id)
+    }
+
+    /// LLM 调用记录中的提示词版本（插件自行管理提示词，以插件版本代替）
+    fn prompt_version(&self) -> Option<String> {
+        (!self.version.is_empty()).then(|| format!("{}@{}", self.id, self.version))
+    }
+
+    /// 可执行文件路径：插件目录中存在该文件时使用插件目录下的路径，否则按 PATH 查找
+    fn command_path(&self) -> PathBuf {
+        let local = self.dir.join(&self.command);
+        if local.exists() {
+            local
+        } else {
+            PathBuf::from(&self.command)
+        }
+    }
+}
+
+/// 扫描插件目录下的所有插件（清单无效的插件跳过）
+pub fn discover_plugins(dir: &Path) -> Vec<PluginManifest> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut plugins: Vec<PluginManifest> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let manifest_path = entry.path().join(MANIFEST_FILE);
+            let content = std::fs::read_to_string(&manifest_path).ok()?;
+            match parse_manifest(&content, &entry.path()) {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    warn!("插件清单无效 {:?}: {}", manifest_path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.id.cmp(&b.id));
+    plugins
+}
+
+/// 按 provider 名称（plugin:<id>）查找插件
+pub fn find_plugin(provider_name: &str) -> Result<PluginManifest> {
+    let id = provider_name
+        .strip_prefix(PLUGIN_PREFIX)
+        .ok_or_else(|| anyhow!("不是插件 provider: {}", provider_name))?;
+    let dir = plugins_dir().ok_or_else(|| anyhow!("插件目录未初始化"))?;
+    discover_plugins(dir)
+        .into_iter()
+        .find(|plugin| plugin.id == id)
+        .ok_or_else(|| anyhow!("未找到插件 {}（插件目录: {:?}）", id, dir))
+}
+
+fn parse_manifest(content: &str, dir: &Path) -> Result<PluginManifest> {
+    let mut manifest: PluginManifest = serde_json::from_str(content)?;
+    if manifest.id.trim().is_empty() || manifest.id.contains(char::is_whitespace) {
+        return Err(anyhow!("插件 ID 不能为空或包含空白字符"));
+    }
+    if manifest.command.trim().is_empty() {
+        return Err(anyhow!("插件命令不能为空"));
+    }
+    manifest.dir = dir.to_path_buf();
+    Ok(manifest)
+}
+
+/// 运行中的插件进程
+struct PluginProcess {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+/// 外部插件提供商
+pub struct ExternalProvider {
+    manifest: PluginManifest,
+    config: Value,
+    process: Mutex<Option<PluginProcess>>,
+    video_path: Option<String>,
+    session_start: Option<DateTime<Utc>>,
+    session_end: Option<DateTime<Utc>>,
+    db: Option<Arc<crate::storage::Database>>,
+    current_session_id: Option<i64>,
+    last_call_ids: StdMutex<HashMap<String, i64>>,
+}
+
+impl ExternalProvider {
+    pub fn new(manifest: PluginManifest) -> Self {
+        Self {
+            manifest,
+            config: Value::Null,
+            process: Mutex::new(None),
+            video_path: None,
+            session_start: None,
+            session_end: None,
+            db: None,
+            current_session_id: None,
+            last_call_ids: StdMutex::new(HashMap::new()),
+        }
+    }
+
+    /// 设置视频路径
+    pub fn set_video_path(&mut self, video_path: Option<String>) {
+        self.video_path = video_path;
+    }
+
+    /// 设置数据库连接
+    pub fn set_database(&mut self, db: Arc<crate::storage::Database>) {
+        self.db = Some(db);
+    }
+
+    /// 设置当前会话 ID
+    pub fn set_session_id(&mut self, session_id: i64) {
+        self.current_session_id = Some(session_id);
+    }
+
+    /// 启动插件进程并发送 configure
+    async fn spawn_process(&self) -> Result<PluginProcess> {
+        let mut command = Command::new(self.manifest.command_path());
+        command
+            .args(&self.manifest.args)
+            .current_dir(&self.manifest.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        #[cfg(target_os = "windows")]
+        {
+            // Windows 下隐藏控制台窗口
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| anyhow!("启动插件 {} 失败: {}", self.manifest.id, e))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("无法获取插件输入"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("无法获取插件输出"))?;
+
+        // 插件的标准错误输出写入日志
+        if let Some(stderr) = child.stderr.take() {
+            let plugin_id = self.manifest.id.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    info!("[插件 {}] {}", plugin_id, line);
+                }
+            });
+        }
+
+        info!("插件 {} 已启动", self.manifest.id);
+        let mut process = PluginProcess {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            next_id: 1,
+        };
+        self.exchange(&mut process, "configure", json!({ "config": self.config }))
+            .await?;
+        Ok(process)
+    }
+
+    /// 调用插件方法（进程未启动或已退出时重新启动）
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut guard = self.process.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.spawn_process().await?);
+        }
+        let Some(process) = guard.as_mut() else {
+            return Err(anyhow!("插件进程不可用"));
+        };
+
+        let result = self.exchange(process, method, params).await;
+        if let Err(e) = &result {
+            // 通信失败（进程退出、超时）时丢弃进程，下次调用重新启动
+            if !e.to_string().starts_with("插件返回错误") {
+                *guard = None;
+            }
+        }
+        result
+    }
+
+    /// 发送一条请求并等待对应的响应（忽略无法解析的输出行和通知）
+    async fn exchange(
+        &self,
+        process: &mut PluginProcess,
+        method: &str,
+        params: Value,
+    ) -> Result<Value> {
+        let id = process.next_id;
+        process.next_id += 1;
+
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        process
+            .stdin
+            .write_all(format!("{}\n", request).as_bytes())
+            .await?;
+        process.stdin.flush().await?;
+
+        let timeout = Duration::from_secs(self.manifest.timeout_secs.max(1));
+        let response = tokio::time::timeout(timeout, async {
+            loop {
+                let line = process
+                    .stdout
+                    .next_line()
+                    .await?
+                    .ok_or_else(|| anyhow!("插件 {} 已退出", self.manifest.id))?;
+                match serde_json::from_str::<Value>(&line) {
+                    Ok(message) if message.get("id") == Some(&json!(id)) => {
+                        return Ok::<Value, anyhow::Error>(message)
+                    }
+                    Ok(_) => {}
+                    Err(_) => info!("[插件 {}] {}", self.manifest.id, line),
+                }
+            }
+        })
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "插件 {} 超时（>{} 秒）",
+                self.manifest.id,
+                timeout.as_secs()
+            )
+        })??;
+
+        parse_response(response)
+    }
+
+    /// 调用插件并写入 llm_calls 记录
+    async fn call_recorded(&self, call_type: &str, params: Value) -> Result<Value> {
+        let started = Instant::now();
+        let result = self.call(call_type, params.clone()).await;
+        self.record_call(call_type, &params, &result, started.elapsed())
+            .await;
+        result
+    }
+
+    async fn record_call(
+        &self,
+        call_type: &str,
+        request: &Value,
+        result: &Result<Value>,
+        elapsed: Duration,
+    ) {
+        let record = crate::storage::LLMCallRecord {
+            id: None,
+            session_id: self.current_session_id,
+            provider: self.manifest.provider_name(),
+            model: self.manifest.name.clone(),
+            call_type: call_type.to_string(),
+            request_headers: "{}".to_string(),
+            request_body: request.to_string(),
+            response_headers: None,
+            response_body: result.as_ref().ok().map(|value| value.to_string()),
+            status_code: Some(if result.is_ok() { 200 } else { 500 }),
+            error_message: result.as_ref().err().map(|e| e.to_string()),
+            latency_ms: Some(elapsed.as_millis() as i64),
+            token_usage: None,
+            created_at: crate::storage::local_now(),
+            prompt_version: self.manifest.prompt_version(),
+        };
+        crate::llm::debug_archive::archive(&record, request);
+
+        let Some(db) = self.db.clone() else {
+            return;
+        };
+        if let Ok(id) = db.insert_llm_call(&record).await {
+            if let Ok(mut map) = self.last_call_ids.lock() {
+                map.insert(call_type.to_string(), id);
+            }
+        }
+    }
+
+    fn format_time(time: Option<DateTime<Utc>>) -> Option<String> {
+        time.map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+    }
+}
+
+/// 解析 JSON-RPC 响应，返回 result 或错误
+fn parse_response(response: Value) -> Result<Value> {
+    if let Some(error) = response.get("error") {
+        let code = error.get("code").and_then(Value::as_i64).unwrap_or(0);
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("未知错误");
+        return Err(anyhow!("插件返回错误 ({}): {}", code, message));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+#[async_trait]
+impl LLMProvider for ExternalProvider {
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    async fn analyze_frames(&self, frames: Vec<String>) -> Result<SessionSummary> {
+        let result = self
+            .call_recorded("analyze_frames", json!({ "frames": frames }))
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    async fn segment_video(&self, frames: Vec<String>, duration: u32) -> Result<Vec<VideoSegment>> {
+        let result = self
+            .call_recorded(
+                "segment_video",
+                json!({
+                    "frames": frames,
+                    "duration": duration,
+                    "video_path": self.video_path,
+                    "session_start": Self::format_time(self.session_start),
+                    "session_end": Self::format_time(self.session_end),
+                }),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    async fn generate_timeline(
+        &self,
+        segments: Vec<VideoSegment>,
+        previous_cards: Option<Vec<TimelineCard>>,
+    ) -> Result<Vec<TimelineCard>> {
+        let result = self
+            .call_recorded(
+                "generate_timeline",
+                json!({
+                    "segments": segments,
+                    "previous_cards": previous_cards,
+                    "session_start": Self::format_time(self.session_start),
+                    "session_end": Self::format_time(self.session_end),
+                }),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    fn set_session_window(&mut self, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) {
+        self.session_start = start;
+        self.session_end = end;
+    }
+
+    fn name(&self) -> &str {
+        &self.manifest.id
+    }
+
+    fn configure(&mut self, config: Value) -> Result<()> {
+        self.config = config;
+        // 重新启动进程以应用新配置
+        if let Ok(mut process) = self.process.try_lock() {
+            *process = None;
+        }
+        Ok(())
+    }
+
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn last_llm_call_id(&self, call_type: &str) -> Option<i64> {
+        self.last_call_ids
+            .lock()
+            .ok()
+            .and_then(|map| map.get(call_type).copied())
+    }
+
+    async fn generate_day_summary(
+        &self,
+        date: &str,
+        sessions: &[SessionBrief],
+        day_type: DayType,
+    ) -> Result<String> {
+        match self
+            .call(
+                "generate_day_summary",
+                json!({ "date": date, "sessions": sessions, "day_type": day_type }),
+            )
+            .await
+        {
+            Ok(Value::String(text)) => Ok(text),
+            Ok(other) => Err(anyhow!("插件返回的每日总结不是字符串: {}", other)),
+            Err(e) if e.to_string().contains(&format!("({})", METHOD_NOT_FOUND)) => {
+                Ok(default_day_summary(sessions, day_type))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn generate_meeting_notes(
+        &self,
+        frames: Vec<String>,
+        context: &str,
+    ) -> Result<MeetingNotes> {
+        let value = self
+            .call(
+                "generate_meeting_notes",
+                json!({ "frames": frames, "context": context }),
+            )
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        match self
+            .call("generate_week_plan", json!({ "prompt": prompt }))
+            .await?
+        {
+            Value::String(text) => Ok(text),
+            other => Ok(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_and_response_parsing() {
+        let manifest = parse_manifest(
+            r#"{"id": "local-llm", "name": "本地模型", "command": "./run.sh"}"#,
+            Path::new("/plugins/local-llm"),
+        )
+        .unwrap();
+        assert_eq!(manifest.provider_name(), "plugin:local-llm");
+        assert_eq!(manifest.timeout_secs, 300);
+        assert_eq!(manifest.dir, PathBuf::from("/plugins/local-llm"));
+        assert!(parse_manifest(
+            r#"{"id": "a b", "name": "x", "command": "y"}"#,
+            Path::new("/")
+        )
+        .is_err());
+
+        let result =
+            parse_response(json!({ "jsonrpc": "2.0", "id": 1, "result": [1, 2] })).unwrap();
+        assert_eq!(result, json!([1, 2]));
+
+        let error = parse_response(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "error": { "code": METHOD_NOT_FOUND, "message": "Method not found" }
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("(-32601)"));
+    }
+}
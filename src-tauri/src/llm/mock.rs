@@ -0,0 +1,240 @@
+// 模拟 LLM 提供商 - 返回预设结果，用于端到端测试
+//
+// 不访问网络，按请求的时长生成固定的分段和时间线卡片；
+// 与真实 provider 一样写入 llm_calls 记录（及调试归档）并返回调用 ID，
+// 便于在测试中验证时间转换和记录关联
+
+use super::plugin::*;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 一次模拟调用的记录（供测试断言）
+#[derive(Debug, Clone)]
+pub struct MockCall {
+    /// 调用类型（segment_video / generate_timeline / analyze_frames）
+    pub call_type: String,
+    /// 传入的帧数
+    pub frame_count: usize,
+    /// 传入的时长（分钟）
+    pub duration: u32,
+    /// 调用时设置的视频路径
+    pub video_path: Option<String>,
+}
+
+/// 模拟 LLM 提供商
+pub struct MockProvider {
+    segments: Option<Vec<VideoSegment>>,
+    cards: Option<Vec<TimelineCard>>,
+    video_path: Option<String>,
+    db: Option<Arc<crate::storage::Database>>,
+    current_session_id: Option<i64>,
+    last_call_ids: Mutex<HashMap<String, i64>>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockProvider {
+    /// 创建模拟提供商（按时长自动生成两段结果）
+    pub fn new() -> Self {
+        Self {
+            segments: None,
+            cards: None,
+            video_path: None,
+            db: None,
+            current_session_id: None,
+            last_call_ids: Mutex::new(HashMap::new()),
+            calls: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 使用固定的分段和卡片作为返回结果
+    pub fn with_responses(segments: Vec<VideoSegment>, cards: Vec<TimelineCard>) -> Self {
+        Self {
+            segments: Some(segments),
+            cards: Some(cards),
+            ..Self::new()
+        }
+    }
+
+    /// 获取调用记录的共享句柄（provider 交给 LLMManager 后仍可读取）
+    pub fn calls(&self) -> Arc<Mutex<Vec<MockCall>>> {
+        self.calls.clone()
+    }
+
+    /// 设置数据库连接
+    pub fn set_database(&mut self, db: Arc<crate::storage::Database>) {
+        self.db = Some(db);
+    }
+
+    /// 设置当前会话 ID
+    pub fn set_session_id(&mut self, session_id: i64) {
+        self.current_session_id = Some(session_id);
+    }
+
+    /// 设置视频路径
+    pub fn set_video_path(&mut self, video_path: Option<String>) {
+        self.video_path = video_path;
+    }
+
+    fn push_call(&self, call_type: &str, frame_count: usize, duration: u32) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(MockCall {
+                call_type: call_type.to_string(),
+                frame_count,
+                duration,
+                video_path: self.video_path.clone(),
+            });
+        }
+    }
+
+    /// 与真实 provider 一样写入 llm_calls 记录和调试归档
+    async fn record_call(&self, call_type: &str, request: Value, response: &Value) {
+        let record = crate::storage::LLMCallRecord {
+            id: None,
+            session_id: self.current_session_id,
+            provider: "mock".to_string(),
+            model: "mock".to_string(),
+            call_type: call_type.to_string(),
+            request_headers: "{}".to_string(),
+            request_body: request.to_string(),
+            response_headers: None,
+            response_body: Some(response.to_string()),
+            status_code: Some(200),
+            error_message: None,
+            latency_ms: Some(0),
+            token_usage: None,
+            created_at: crate::storage::local_now(),
+            prompt_version: None,
+        };
+        crate::llm::debug_archive::archive(&record, &request);
+
+        let Some(db) = self.db.clone() else {
+            return;
+        };
+        if let Ok(id) = db.insert_llm_call(&record).await {
+            if let Ok(mut map) = self.last_call_ids.lock() {
+                map.insert(call_type.to_string(), id);
+            }
+        }
+    }
+
+    /// 默认分段：把时长平分为两段
+    fn default_segments(duration: u32) -> Vec<VideoSegment> {
+        let half = duration / 2;
+        vec![
+            VideoSegment {
+                start_timestamp: "00:00".to_string(),
+                end_timestamp: format!("{:02}:00", half),
+                description: "编写代码".to_string(),
+            },
+            VideoSegment {
+                start_timestamp: format!("{:02}:00", half),
+                end_timestamp: format!("{:02}:00", duration),
+                description: "阅读文档".to_string(),
+            },
+        ]
+    }
+
+    /// 默认卡片：每个分段对应一张卡片
+    fn default_cards(segments: &[VideoSegment]) -> Vec<TimelineCard> {
+        segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| TimelineCard {
+                start_time: segment.start_timestamp.clone(),
+                end_time: segment.end_timestamp.clone(),
+                category: if index == 0 { "work" } else { "learning" }.to_string(),
+                subcategory: "mock".to_string(),
+                title: format!("模拟活动 {}", index + 1),
+                summary: segment.description.clone(),
+                detailed_summary: segment.description.clone(),
+                distractions: None,
+                app_sites: AppSites {
+                    primary: "mock".to_string(),
+                    secondary: None,
+                },
+                video_preview_path: None,
+                confidence: None,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockProvider {
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    async fn analyze_frames(&self, frames: Vec<String>) -> Result<SessionSummary> {
+        self.push_call("analyze_frames", frames.len(), 0);
+        Ok(SessionSummary {
+            title: "模拟会话".to_string(),
+            summary: "模拟分析结果".to_string(),
+            ..SessionSummary::default()
+        })
+    }
+
+    async fn segment_video(&self, frames: Vec<String>, duration: u32) -> Result<Vec<VideoSegment>> {
+        self.push_call("segment_video", frames.len(), duration);
+        let segments = self
+            .segments
+            .clone()
+            .unwrap_or_else(|| Self::default_segments(duration));
+
+        self.record_call(
+            "segment_video",
+            json!({ "frames": frames.len(), "duration": duration, "video_path": self.video_path }),
+            &serde_json::to_value(&segments)?,
+        )
+        .await;
+        Ok(segments)
+    }
+
+    async fn generate_timeline(
+        &self,
+        segments: Vec<VideoSegment>,
+        _previous_cards: Option<Vec<TimelineCard>>,
+    ) -> Result<Vec<TimelineCard>> {
+        self.push_call("generate_timeline", 0, 0);
+        let cards = self
+            .cards
+            .clone()
+            .unwrap_or_else(|| Self::default_cards(&segments));
+
+        self.record_call(
+            "generate_timeline",
+            json!({ "segments": segments }),
+            &serde_json::to_value(&cards)?,
+        )
+        .await;
+        Ok(cards)
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn configure(&mut self, _config: Value) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn last_llm_call_id(&self, call_type: &str) -> Option<i64> {
+        self.last_call_ids
+            .lock()
+            .ok()
+            .and_then(|map| map.get(call_type).copied())
+    }
+}
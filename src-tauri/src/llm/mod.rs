@@ -1,26 +1,44 @@
 // LLM模块 - 管理AI分析服务
 
+pub mod catalog;
 pub mod claude;
 pub mod codex;
+pub mod debug_archive;
+pub mod external;
+pub mod mock;
+pub mod oss;
+pub mod personalization;
 pub mod plugin;
 pub mod qwen;
 
 pub use claude::ClaudeProvider;
 pub use codex::CodexProvider;
+pub use external::ExternalProvider;
 pub use plugin::{
-    ActivityCategory, ActivityTag, AppSites, Distraction, KeyMoment, LLMProvider, SessionBrief,
-    SessionSummary, TimelineCard, VideoSegment,
+    ActivityCategory, ActivityTag, AppSites, DayType, Distraction, KeyMoment, LLMProvider,
+    MeetingNotes, SessionBrief, SessionSummary, TimelineCard, VideoSegment,
 };
 pub use qwen::QwenProvider;
 
 use crate::capture::scheduler::SessionProcessor;
+use crate::domains::pipeline::{
+    HeuristicTagger, NotionSyncer, Phase, SessionContext, SessionPipeline, SessionStage,
+    WebhookNotifier,
+};
+use crate::models::{PipelineStep, SessionTitleStrategy};
 use crate::settings::SettingsManager;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use tracing::{error, info, warn, Instrument};
+
+/// 未分析会话的标题
+const NOT_ANALYZED_TITLE: &str = "未分析";
+
+/// 存储离线时分析任务等待重新连接的最长时间
+const STORAGE_RECONNECT_WAIT: std::time::Duration = std::time::Duration::from_secs(30 * 60);
 
 /// LLM管理器
 pub struct LLMManager {
@@ -116,6 +134,23 @@ fn default_video_mode() -> bool {
     true
 }
 
+/// 默认 LLM 配置
+fn default_llm_config(provider: String) -> LLMConfig {
+    LLMConfig {
+        provider,
+        qwen: QwenConfig {
+            api_key: String::new(),
+            model: default_model(),
+            base_url: default_base_url(),
+            use_video_mode: default_video_mode(),
+            video_path: None,
+        },
+        claude: ClaudeConfig::default(),
+        codex: CodexConfig::default(),
+        analysis_params: AnalysisParams::default(),
+    }
+}
+
 /// 分析参数
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AnalysisParams {
@@ -148,23 +183,20 @@ impl LLMManager {
 
         Self {
             provider,
-            config_lock: Arc::new(RwLock::new(LLMConfig {
-                provider: default_provider(),
-                qwen: QwenConfig {
-                    api_key: String::new(),
-                    model: default_model(),
-                    base_url: default_base_url(),
-                    use_video_mode: default_video_mode(),
-                    video_path: None,
-                },
-                claude: ClaudeConfig::default(),
-                codex: CodexConfig::default(),
-                analysis_params: AnalysisParams::default(),
-            })),
+            config_lock: Arc::new(RwLock::new(default_llm_config(default_provider()))),
             http_client: Some(client),
         }
     }
 
+    /// 使用指定的 provider 创建管理器（测试场景，不持有 HTTP 客户端）
+    pub fn with_provider(provider: Box<dyn LLMProvider>, provider_name: &str) -> Self {
+        Self {
+            provider,
+            config_lock: Arc::new(RwLock::new(default_llm_config(provider_name.to_string()))),
+            http_client: None,
+        }
+    }
+
     /// 配置 LLM（支持多 provider）
     pub async fn configure(&mut self, config: QwenConfig) -> Result<()> {
         // 获取当前 provider 类型
@@ -239,6 +271,9 @@ impl LLMManager {
             "codex" => {
                 self.provider = Box::new(CodexProvider::new());
             }
+            name if name.starts_with(external::PLUGIN_PREFIX) => {
+                self.provider = Box::new(ExternalProvider::new(external::find_plugin(name)?));
+            }
             _ => {
                 return Err(anyhow!("不支持的 provider: {}", provider_name));
             }
@@ -291,12 +326,31 @@ impl LLMManager {
         Ok(())
     }
 
+    /// 配置外部插件 provider（配置原样传给插件）
+    pub async fn configure_plugin(&mut self, config: serde_json::Value) -> Result<()> {
+        let provider_name = self.config_lock.read().await.provider.clone();
+        if !provider_name.starts_with(external::PLUGIN_PREFIX) {
+            return Err(anyhow!("当前 provider 不是插件: {}", provider_name));
+        }
+        self.provider.configure(config)?;
+        info!("插件 {} 配置已更新", provider_name);
+        Ok(())
+    }
+
     pub fn set_video_path(&mut self, video_path: Option<String>) {
         if let Some(provider) = self.provider.as_any().downcast_mut::<QwenProvider>() {
             provider.set_video_path(video_path.clone());
         }
 
         if let Some(provider) = self.provider.as_any().downcast_mut::<ClaudeProvider>() {
+            provider.set_video_path(video_path.clone());
+        }
+
+        if let Some(provider) = self.provider.as_any().downcast_mut::<ExternalProvider>() {
+            provider.set_video_path(video_path.clone());
+        }
+
+        if let Some(provider) = self.provider.as_any().downcast_mut::<mock::MockProvider>() {
             provider.set_video_path(video_path);
         }
     }
@@ -309,13 +363,36 @@ impl LLMManager {
         }
     }
 
+    /// 设置视频上传配置
+    pub fn set_video_upload(&mut self, config: crate::models::VideoUploadConfig) {
+        // 只有 Qwen provider 支持视频上传
+        if let Some(provider) = self.provider.as_any().downcast_mut::<QwenProvider>() {
+            provider.set_video_upload(config);
+        }
+    }
+
     /// 设置会话时间范围（用于提示词中的绝对时间）
     pub fn set_session_window(&mut self, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) {
         self.provider.set_session_window(start, end);
     }
 
+    /// 设置用户纠正记录（作为提示词中的个性化示例）
+    pub fn set_correction_examples(
+        &mut self,
+        corrections: Vec<crate::storage::CardCorrectionRecord>,
+    ) {
+        self.provider.set_correction_examples(corrections);
+    }
+
+    /// 本地模式下拒绝在独立进程中联网的服务商（Claude、Codex、插件）
+    async fn ensure_provider_allowed(&self) -> Result<()> {
+        let provider_name = self.config_lock.read().await.provider.clone();
+        crate::domains::local_only::ensure_local_provider(&provider_name).map_err(|e| anyhow!(e))
+    }
+
     /// 分析帧数据
     pub async fn analyze_frames(&mut self, frames: Vec<String>) -> Result<SessionSummary> {
+        self.ensure_provider_allowed().await?;
         let provider_name = {
             let config = self.config_lock.read().await;
             config.provider.clone()
@@ -381,6 +458,24 @@ impl LLMManager {
             info!("已为 Codex provider 设置数据库连接");
             return;
         }
+
+        // 外部插件 provider
+        if let Some(provider) = self.provider.as_any().downcast_mut::<ExternalProvider>() {
+            provider.set_database(db.clone());
+            if let Some(sid) = session_id {
+                provider.set_session_id(sid);
+            }
+            info!("已为插件 provider 设置数据库连接");
+            return;
+        }
+
+        // Mock provider（测试用）
+        if let Some(provider) = self.provider.as_any().downcast_mut::<mock::MockProvider>() {
+            provider.set_database(db);
+            if let Some(sid) = session_id {
+                provider.set_session_id(sid);
+            }
+        }
     }
 
     /// 生成时间线卡片（公开方法）
@@ -389,6 +484,7 @@ impl LLMManager {
         segments: Vec<VideoSegment>,
         previous_cards: Option<Vec<TimelineCard>>,
     ) -> Result<Vec<TimelineCard>> {
+        self.ensure_provider_allowed().await?;
         self.provider
             .generate_timeline(segments, previous_cards)
             .await
@@ -404,8 +500,28 @@ impl LLMManager {
         &self,
         date: &str,
         sessions: &[SessionBrief],
+        day_type: DayType,
     ) -> Result<String> {
-        self.provider.generate_day_summary(date, sessions).await
+        self.ensure_provider_allowed().await?;
+        self.provider
+            .generate_day_summary(date, sessions, day_type)
+            .await
+    }
+
+    /// 生成会议纪要（调用LLM）
+    pub async fn generate_meeting_notes(
+        &self,
+        frames: Vec<String>,
+        context: &str,
+    ) -> Result<MeetingNotes> {
+        self.ensure_provider_allowed().await?;
+        self.provider.generate_meeting_notes(frames, context).await
+    }
+
+    /// 生成周计划（调用LLM）
+    pub async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        self.ensure_provider_allowed().await?;
+        self.provider.generate_week_plan(prompt).await
     }
 
     /// 分析视频并生成时间线（两阶段处理）
@@ -415,6 +531,7 @@ impl LLMManager {
         duration: u32,
         previous_cards: Option<Vec<TimelineCard>>,
     ) -> Result<TimelineAnalysis> {
+        self.ensure_provider_allowed().await?;
         let provider_name = {
             let config = self.config_lock.read().await;
             config.provider.clone()
@@ -467,15 +584,19 @@ impl LLMManager {
             timeline_cards,
             segment_call_id,
             timeline_call_id,
+            segment_call_ids: Vec::new(),
+            timeline_call_ids: Vec::new(),
         })
     }
 }
 
+/// 从时间线卡片生成会话总结，标题按配置的方式选取卡片
 pub fn build_session_summary(
     window_start: chrono::DateTime<chrono::Utc>,
     window_end: chrono::DateTime<chrono::Utc>,
     segments: &[VideoSegment],
     timeline_cards: &[TimelineCard],
+    title_strategy: SessionTitleStrategy,
 ) -> SessionSummary {
     use std::collections::HashMap;
 
@@ -485,8 +606,7 @@ pub fn build_session_summary(
 
     // 遍历所有timeline cards计算时间
     for card in timeline_cards {
-        // 解析时间并计算持续时间（简化处理，假设MM:SS格式）
-        let duration = parse_duration(&card.start_time, &card.end_time).unwrap_or(15.0);
+        let duration = card_duration_secs(card);
         total_duration += duration;
 
         let category = card.category.to_lowercase();
@@ -550,19 +670,29 @@ pub fn build_session_summary(
         });
     }
 
-    // 生成总结
-    let title = timeline_cards
-        .first()
+    // 生成总结：标题取按配置方式选出的卡片，多个阶段时按时长列出各阶段
+    let title = crate::domains::session_titles::pick_title_card(timeline_cards, title_strategy)
         .map(|c| c.title.clone())
         .unwrap_or_else(|| "活动会话".to_string());
 
     let summary = if timeline_cards.len() > 1 {
+        let mut stages: Vec<(&TimelineCard, f32)> = timeline_cards
+            .iter()
+            .map(|card| (card, card_duration_secs(card)))
+            .collect();
+        if title_strategy != SessionTitleStrategy::Chronological {
+            stages.sort_by(|a, b| b.1.total_cmp(&a.1));
+        }
         format!(
             "本次会话包含{}个主要活动阶段。{}",
             timeline_cards.len(),
-            timeline_cards
+            stages
                 .iter()
-                .map(|c| c.title.clone())
+                .map(|(card, secs)| format!(
+                    "{}（约{}分钟）",
+                    card.title,
+                    (secs / 60.0).round().max(1.0)
+                ))
                 .collect::<Vec<_>>()
                 .join("、")
         )
@@ -603,24 +733,18 @@ pub fn build_session_summary(
     }
 }
 
-// 辅助函数：解析持续时间（MM:SS格式）
-fn parse_duration(start: &str, end: &str) -> Option<f32> {
-    // 简单处理MM:SS格式
-    let parse_time = |s: &str| -> Option<f32> {
-        let parts: Vec<&str> = s.split(':').collect();
-        if parts.len() == 2 {
-            let minutes = parts[0].parse::<f32>().ok()?;
-            let seconds = parts[1].parse::<f32>().ok()?;
-            Some(minutes * 60.0 + seconds)
-        } else {
-            None
-        }
+/// 卡片时长（秒），兼容 RFC3339 绝对时间和 MM:SS / HH:MM:SS 相对时间，无法解析时按 15 秒计
+pub(crate) fn card_duration_secs(card: &TimelineCard) -> f32 {
+    let parse = |value: &str| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|time| time.timestamp())
+            .ok()
+            .or_else(|| parse_relative_duration(value).map(|d| d.num_seconds()))
     };
-
-    let start_seconds = parse_time(start)?;
-    let end_seconds = parse_time(end)?;
-
-    Some((end_seconds - start_seconds).abs())
+    match (parse(&card.start_time), parse(&card.end_time)) {
+        (Some(start), Some(end)) => (end - start).abs() as f32,
+        _ => 15.0,
+    }
 }
 
 // 辅助函数：映射类别
@@ -678,6 +802,107 @@ pub(crate) fn relative_to_absolute(
     ts
 }
 
+/// 根据视频元数据推断分析结果中相对时间的缩放比例（没有元数据时按会话时间处理）
+pub(crate) fn analysis_timestamp_scale(
+    analysis: &TimelineAnalysis,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    video: Option<&crate::video::VideoMetadata>,
+) -> f64 {
+    let Some(metadata) = video else {
+        return 1.0;
+    };
+
+    let segment_times = analysis
+        .segments
+        .iter()
+        .flat_map(|s| [s.start_timestamp.as_str(), s.end_timestamp.as_str()]);
+    let card_times = analysis
+        .timeline_cards
+        .iter()
+        .flat_map(|c| [c.start_time.as_str(), c.end_time.as_str()]);
+    let max_relative_secs = segment_times
+        .chain(card_times)
+        .filter_map(parse_relative_duration)
+        .map(|d| d.num_seconds())
+        .max()
+        .unwrap_or(0);
+
+    metadata.timestamp_scale(
+        max_relative_secs as f64,
+        (end - start).num_seconds().max(0) as f64,
+    )
+}
+
+/// 分析结果中的相对时间是否为视频播放位置，是则返回用于换算的视频元数据
+pub(crate) fn analysis_video_time<'a>(
+    analysis: &TimelineAnalysis,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    video: Option<&'a crate::video::VideoMetadata>,
+) -> Option<&'a crate::video::VideoMetadata> {
+    video.filter(|metadata| analysis_timestamp_scale(analysis, start, end, Some(metadata)) != 1.0)
+}
+
+/// 相对时间转绝对时间，`video` 不为空时相对时间是视频播放位置，按视频元数据换算（智能变速时分段换算）
+pub(crate) fn relative_to_absolute_video(
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    relative: &str,
+    video: Option<&crate::video::VideoMetadata>,
+) -> chrono::DateTime<chrono::Utc> {
+    let Some(metadata) = video else {
+        return relative_to_absolute(start, end, relative);
+    };
+    let offset = parse_relative_duration(relative).unwrap_or_else(chrono::Duration::zero);
+    let real_secs = metadata.video_to_real_secs(offset.num_milliseconds() as f64 / 1000.0);
+    let ts = start + chrono::Duration::milliseconds((real_secs * 1000.0) as i64);
+    ts.min(end).max(start)
+}
+
+/// 将分析结果中的相对时间（MM:SS）转换为带时区的绝对时间（RFC3339）
+///
+/// 注意：start/end 虽然类型是 DateTime<Utc>，但通过 local_now() 存储的是本地时间值，
+/// 这里将其重新解释为本地时间并生成带时区标记的格式（如 2025-10-09T12:54:00+08:00）
+pub(crate) fn absolutize_analysis(
+    analysis: &mut TimelineAnalysis,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    video: Option<&crate::video::VideoMetadata>,
+) {
+    use chrono::{Local, TimeZone};
+
+    let video = analysis_video_time(analysis, start, end, video);
+    if video.is_some() {
+        info!("分析结果使用视频时间，按视频元数据换算为会话时间");
+    }
+
+    let to_local = |relative: &str| {
+        let absolute = relative_to_absolute_video(start, end, relative, video);
+        Local
+            .from_local_datetime(&absolute.naive_local())
+            .unwrap()
+            .to_rfc3339()
+    };
+
+    for segment in &mut analysis.segments {
+        segment.start_timestamp = to_local(&segment.start_timestamp);
+        segment.end_timestamp = to_local(&segment.end_timestamp);
+    }
+
+    for card in &mut analysis.timeline_cards {
+        card.start_time = to_local(&card.start_time);
+        card.end_time = to_local(&card.end_time);
+
+        if let Some(distractions) = card.distractions.as_mut() {
+            for distraction in distractions {
+                distraction.start_time = to_local(&distraction.start_time);
+                distraction.end_time = to_local(&distraction.end_time);
+            }
+        }
+    }
+}
+
 /// 将文本中的相对时间（MM:SS 或 HH:MM:SS）转换为绝对时间（HH:MM）
 fn convert_relative_times_in_text(
     text: &str,
@@ -728,6 +953,10 @@ pub struct TimelineAnalysis {
     pub timeline_cards: Vec<TimelineCard>,
     pub segment_call_id: Option<i64>,
     pub timeline_call_id: Option<i64>,
+    /// 分块分析时每个分段/卡片所在块的调用 ID（与 segments/timeline_cards 一一对应），
+    /// 为空时统一使用 segment_call_id/timeline_call_id
+    pub segment_call_ids: Vec<Option<i64>>,
+    pub timeline_call_ids: Vec<Option<i64>>,
 }
 
 impl LLMProcessor {
@@ -782,13 +1011,20 @@ impl LLMProcessor {
         self: Arc<Self>,
         event_bus: Arc<crate::event_bus::EventBus>,
         capture: Arc<crate::capture::ScreenCapture>,
+        analysis_hold: Arc<crate::domains::analysis_hold::AnalysisHold>,
     ) {
-        let mut receiver = event_bus.subscribe();
+        let mut receiver = event_bus.subscribe_topics(
+            "LLM处理器事件监听器",
+            &[crate::event_bus::EventTopic::Capture],
+        );
+
+        // 会话分析串行进行，暂缓中的会话不占用
+        let analysis_order = Arc::new(tokio::sync::Mutex::new(()));
 
         tokio::spawn(async move {
             info!("LLM处理器事件监听器已启动");
 
-            while let Ok(event) = receiver.recv().await {
+            while let Some(event) = receiver.recv().await {
                 match event {
                     crate::event_bus::AppEvent::SessionCompleted {
                         session_id,
@@ -796,67 +1032,132 @@ impl LLMProcessor {
                         window_start,
                         window_end,
                     } => {
-                        info!(
-                            "收到会话完成事件: session_id={}, frames={}, 时间段: {} - {}",
-                            session_id, frame_count, window_start, window_end
-                        );
-
-                        // 发布分析开始事件
-                        event_bus
-                            .publish(crate::event_bus::AppEvent::AnalysisStarted { session_id });
-
-                        // 读取该时间段的所有frames
-                        let frames_result = Self::load_frames_for_window(
-                            &capture,
-                            session_id,
-                            window_start,
-                            window_end,
-                        )
-                        .await;
+                        // 暂缓窗口：期间用户可以选择不分析该会话。登记后在独立任务中等待，
+                        // 只暂缓这一个会话，之后完成的会话照常登记
+                        let hold_minutes =
+                            self.settings.get().await.analysis_hold_minutes.unwrap_or(0);
+                        let hold_id = (hold_minutes > 0).then(|| {
+                            analysis_hold.hold(window_start, window_end, frame_count, hold_minutes)
+                        });
+
+                        let this = self.clone();
+                        let event_bus = event_bus.clone();
+                        let capture = capture.clone();
+                        let analysis_hold = analysis_hold.clone();
+                        let analysis_order = analysis_order.clone();
+                        // 同一会话的日志都带上关联 ID，便于从采集到存储追踪整个流程
+                        let span = crate::logger::session_span(window_start);
+                        tokio::spawn(
+                            async move {
+                                info!(
+                                    "收到会话完成事件: session_id={}, frames={}, 时间段: {} - {}",
+                                    session_id, frame_count, window_start, window_end
+                                );
+
+                                let skip_analysis = match hold_id {
+                                    Some(hold_id) => {
+                                        info!(
+                                            "会话暂缓 {} 分钟后分析 (hold_id={})",
+                                            hold_minutes, hold_id
+                                        );
+                                        analysis_hold.wait(hold_id).await
+                                            == crate::domains::analysis_hold::HoldDecision::Skip
+                                    }
+                                    None => false,
+                                };
+                                // 暂缓各自结束后按顺序逐个分析
+                                let _turn = analysis_order.lock().await;
 
-                        let frames = match frames_result {
-                            Ok(f) => f,
-                            Err(e) => {
-                                error!("读取frames失败: {}", e);
-                                event_bus.publish(crate::event_bus::AppEvent::AnalysisFailed {
+                                // 发布分析开始事件
+                                event_bus.publish(crate::event_bus::AppEvent::AnalysisStarted {
                                     session_id,
-                                    error: e.to_string(),
                                 });
-                                continue;
-                            }
-                        };
-
-                        if frames.is_empty() {
-                            warn!("该时间段没有有效frames，跳过分析");
-                            event_bus.publish(crate::event_bus::AppEvent::AnalysisFailed {
-                                session_id,
-                                error: "没有有效frames".to_string(),
-                            });
-                            continue;
-                        }
 
-                        // 构建SessionWindow
-                        let window = crate::capture::scheduler::SessionWindow {
-                            start: window_start,
-                            end: window_end,
-                        };
-
-                        // 执行分析
-                        match self.process_session(frames, window).await {
-                            Ok(_) => {
-                                info!("会话分析完成: session_id={}", session_id);
-                                // 注意：AnalysisCompleted事件将在未来由独立的分析流程发布
-                                // 当前process_session包含了完整的处理，包括视频生成
-                                // 这里暂时不发布AnalysisCompleted，避免重复处理
-                            }
-                            Err(e) => {
-                                error!("会话分析失败: session_id={}, 错误: {}", session_id, e);
-                                event_bus.publish(crate::event_bus::AppEvent::AnalysisFailed {
+                                // 数据目录在网络共享上时，先等待重新连接，避免把离线的截图当成缺失
+                                let mut storage_dirs = vec![capture.frames_dir()];
+                                if let Some(video_processor) = &this.video_processor {
+                                    storage_dirs.push(video_processor.output_dir.clone());
+                                }
+                                if !crate::storage::availability::wait_until_available(
+                                    &storage_dirs,
+                                    STORAGE_RECONNECT_WAIT,
+                                )
+                                .await
+                                {
+                                    event_bus.publish(crate::event_bus::AppEvent::AnalysisFailed {
+                                        session_id,
+                                        error: "存储不可用（网络共享可能已断开）".to_string(),
+                                    });
+                                    return;
+                                }
+
+                                // 读取该时间段的所有frames
+                                let frames_result = Self::load_frames_for_window(
+                                    &capture,
                                     session_id,
-                                    error: e.to_string(),
-                                });
+                                    window_start,
+                                    window_end,
+                                )
+                                .await;
+
+                                let frames = match frames_result {
+                                    Ok(f) => f,
+                                    Err(e) => {
+                                        error!("读取frames失败: {}", e);
+                                        event_bus.publish(
+                                            crate::event_bus::AppEvent::AnalysisFailed {
+                                                session_id,
+                                                error: e.to_string(),
+                                            },
+                                        );
+                                        return;
+                                    }
+                                };
+
+                                if frames.is_empty() {
+                                    warn!("该时间段没有有效frames，跳过分析");
+                                    event_bus.publish(crate::event_bus::AppEvent::AnalysisFailed {
+                                        session_id,
+                                        error: "没有有效frames".to_string(),
+                                    });
+                                    return;
+                                }
+
+                                // 构建SessionWindow
+                                let window = crate::capture::scheduler::SessionWindow {
+                                    start: window_start,
+                                    end: window_end,
+                                };
+
+                                // 执行分析
+                                match this
+                                    .analyze_window(
+                                        frames,
+                                        window,
+                                        skip_analysis,
+                                        Some(event_bus.as_ref()),
+                                    )
+                                    .await
+                                {
+                                    Ok(_) => {
+                                        info!("会话分析完成: session_id={}", session_id);
+                                    }
+                                    Err(e) => {
+                                        error!(
+                                            "会话分析失败: session_id={}, 错误: {}",
+                                            session_id, e
+                                        );
+                                        event_bus.publish(
+                                            crate::event_bus::AppEvent::AnalysisFailed {
+                                                session_id,
+                                                error: e.to_string(),
+                                            },
+                                        );
+                                    }
+                                }
                             }
-                        }
+                            .instrument(span),
+                        );
                     }
                     _ => {}
                 }
@@ -939,6 +1240,23 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
         &self,
         frames: Vec<crate::capture::ScreenFrame>,
         window: crate::capture::scheduler::SessionWindow,
+    ) -> Result<()> {
+        self.analyze_window(frames, window, false, None).await
+    }
+}
+
+impl LLMProcessor {
+    /// 处理会话：生成视频，再按当前处理方案执行分析、保存和交付步骤
+    ///
+    /// skip_analysis 为 true 时（用户在暂缓窗口内选择不分析），只在本地生成视频，
+    /// 创建一张占位卡片，不调用 LLM，也不同步到远程存储。
+    /// 传入 event_bus 时，会话保存后发布 AnalysisCompleted（使用数据库中的会话 ID）
+    async fn analyze_window(
+        &self,
+        frames: Vec<crate::capture::ScreenFrame>,
+        window: crate::capture::scheduler::SessionWindow,
+        skip_analysis: bool,
+        event_bus: Option<&crate::event_bus::EventBus>,
     ) -> Result<()> {
         // 获取配置
         let config = self.llm_handle.get_config().await?;
@@ -947,18 +1265,70 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
         // 采样帧
         let sampled_frames = self.sample_frames(&frames, params.frame_sampling_interval as usize);
 
-        // 提取文件路径
-        let frame_paths: Vec<String> = sampled_frames.iter().map(|f| f.file_path.clone()).collect();
-
         // 计算视频时长（分钟）
         let duration = window.end - window.start;
         let duration_minutes = (duration.num_seconds().max(0) as f64 / 60.0).ceil() as u32;
 
+        // 长会话按时间分块分析
+        let chunk_settings = self
+            .settings
+            .get()
+            .await
+            .analysis_chunk_settings
+            .unwrap_or_default();
+        let chunks = if chunk_settings.enabled {
+            crate::domains::analysis::plan_analysis_chunks(
+                window.start,
+                window.end,
+                chunk_settings.max_chunk_minutes,
+            )
+        } else {
+            vec![(window.start, window.end)]
+        };
+        let mut chunk_videos: Vec<Option<String>> = vec![None; chunks.len()];
+
+        // 仅采集的机器只生成视频，由分析机器认领后分析（会话在分析时创建）
+        let capture_only = !skip_analysis
+            && self
+                .settings
+                .get()
+                .await
+                .instance_settings
+                .is_some_and(|s| s.machine_role == crate::models::MachineRole::Capture);
+
+        // 当前处理方案中启用的步骤
+        let pipeline_settings = self.settings.get().await.pipeline.unwrap_or_default();
+        let steps = crate::domains::pipeline::ordered_steps(&pipeline_settings);
+        let llm_enabled = steps.contains(&PipelineStep::LlmAnalyzer);
+
+        // 开启夜间分析且不在分析时段内时只生成视频，由周期性扫描在分析时段内分析
+        let night_deferred = !skip_analysis
+            && !capture_only
+            && llm_enabled
+            && !crate::domains::analysis::analysis_allowed_at(
+                &self.settings.get().await.night_analysis.unwrap_or_default(),
+                crate::storage::local_now(),
+            );
+
         // 提取所有帧路径用于视频生成
         let all_frame_paths: Vec<String> = frames.iter().map(|f| f.file_path.clone()).collect();
+        let retain_frames = !skip_analysis
+            && !capture_only
+            && llm_enabled
+            && self
+                .settings
+                .get()
+                .await
+                .frame_retention
+                .is_some_and(|s| s.keep_until_analyzed);
+        let frames_dir = all_frame_paths
+            .first()
+            .and_then(|path| std::path::Path::new(path).parent())
+            .map(|dir| dir.to_path_buf());
 
         // 先生成视频（如果配置了视频处理器）
         let mut video_path = None;
+        let mut video_metadata = None;
         let mut should_persist_frames = true;
         if let Some(ref video_processor) = self.video_processor {
             let app_config = self.settings.get().await;
@@ -977,11 +1347,7 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
                     filtered_frame_paths.len()
                 );
 
-                let file_label = format!(
-                    "{}-{}",
-                    window.start.format("%Y%m%d%H%M"),
-                    window.end.format("%Y%m%d%H%M")
-                );
+                let file_label = window.file_label();
                 let output_path = video_processor
                     .output_dir
                     .join(format!("{}.mp4", file_label));
@@ -990,6 +1356,27 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
                 video_config.speed_multiplier = app_config.video_config.speed_multiplier;
                 video_config.quality = app_config.video_config.quality;
                 video_config.add_timestamp = app_config.video_config.add_timestamp;
+                video_config.smart_speed = app_config.video_config.smart_speed;
+                video_config.layout = app_config.video_config.layout;
+                // 存储接近配额时降低画质和分辨率
+                crate::domains::storage_quota::apply_to_video(&mut video_config);
+
+                // 分块分析时先为每一块单独生成视频（生成完整视频后原始图片会被删除）
+                // 分块视频只用于分析，保持固定倍速
+                if chunks.len() > 1
+                    && !skip_analysis
+                    && !capture_only
+                    && !night_deferred
+                    && llm_enabled
+                {
+                    let chunk_config = crate::video::VideoConfig {
+                        smart_speed: false,
+                        ..video_config.clone()
+                    };
+                    chunk_videos = self
+                        .create_chunk_videos(video_processor, &frames, &chunks, &chunk_config)
+                        .await;
+                }
 
                 match video_processor
                     .create_summary_video(filtered_frame_paths.clone(), &output_path, &video_config)
@@ -997,16 +1384,56 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
                 {
                     Ok(result) => {
                         info!("视频生成成功: {}", result.file_path);
+                        let metadata = crate::video::VideoMetadata::new(
+                            &video_config,
+                            crate::domains::playback::frame_interval_secs(
+                                app_config.capture_interval,
+                            ),
+                            filtered_frame_paths.len(),
+                        )
+                        .with_speed_runs(result.speed_runs.clone());
+                        metadata.save_or_warn(&output_path).await;
+                        // 删除截图前计算会话色带
+                        crate::domains::mood_strip::write_for_video(
+                            frames
+                                .iter()
+                                .map(|frame| (frame.timestamp, frame.file_path.clone()))
+                                .collect(),
+                            &output_path,
+                        )
+                        .await;
+                        if !skip_analysis {
+                            video_processor.publish(&output_path).await;
+                        }
+                        video_metadata = Some(metadata);
                         video_path = Some(result.file_path.clone());
                         should_persist_frames = false;
 
-                        info!("删除 {} 个原始图片文件...", all_frame_paths.len());
-                        for frame_path in &all_frame_paths {
-                            if let Err(e) = tokio::fs::remove_file(frame_path).await {
-                                error!("删除图片文件失败 {}: {}", frame_path, e);
+                        // 删除原始图片前记录截图和视频的大小（压缩统计）
+                        crate::domains::storage_usage::record_encoding(
+                            &self.db,
+                            window.start.date_naive(),
+                            &all_frame_paths,
+                            &result.file_path,
+                        )
+                        .await;
+
+                        // 开启截图保留时移到保留目录，分析成功后再删除
+                        if retain_frames {
+                            crate::domains::frame_retention::keep_frames(
+                                &all_frame_paths,
+                                &file_label,
+                            )
+                            .await;
+                        } else {
+                            info!("删除 {} 个原始图片文件...", all_frame_paths.len());
+                            for frame_path in &all_frame_paths {
+                                if let Err(e) = tokio::fs::remove_file(frame_path).await {
+                                    error!("删除图片文件失败 {}: {}", frame_path, e);
+                                }
                             }
+                            info!("原始图片文件已删除");
                         }
-                        info!("原始图片文件已删除");
                     }
                     Err(e) => {
                         error!("视频生成失败: {}，保留原始图片", e);
@@ -1017,8 +1444,29 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
             }
         }
 
+        if capture_only {
+            return match video_path {
+                Some(path) => {
+                    info!("仅采集模式：视频已生成，等待分析机器分析: {}", path);
+                    Ok(())
+                }
+                None => Err(anyhow!("仅采集模式需要自动生成视频，本次视频未生成")),
+            };
+        }
+
+        if night_deferred {
+            match video_path {
+                Some(ref path) => {
+                    info!("夜间分析：视频已生成，等待分析时段: {}", path);
+                    return Ok(());
+                }
+                // 没有视频时无法推迟，立即分析
+                None => warn!("夜间分析需要自动生成视频，本次视频未生成，立即分析"),
+            }
+        }
+
         // 检查是否有帧，如果没有帧则不创建会话
-        if frame_paths.is_empty() {
+        if sampled_frames.is_empty() {
             warn!("该时间段没有截图帧，跳过会话创建");
             return Err(anyhow!("没有找到截图帧，无法创建会话"));
         }
@@ -1036,126 +1484,116 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
             created_at: None,
             device_name: Some(device_name),
             device_type: Some(device_type),
+            pinned: false,
+            archived: false,
+            archive_key: None,
         };
 
         let session_id = self.db.insert_session(&temp_session).await?;
         info!("创建临时会话: ID={}", session_id);
 
-        // 记录视频路径，用于错误清理
-        let video_path_for_cleanup = video_path.clone();
+        if skip_analysis {
+            return self
+                .save_skipped_session(
+                    session_id,
+                    &window,
+                    video_path,
+                    should_persist_frames.then_some(frames.as_slice()),
+                    "已按用户要求跳过分析，视频仅保存在本地",
+                )
+                .await;
+        }
 
-        // 更新provider的视频路径
-        self.llm_handle.set_video_path(video_path.clone()).await?;
+        // 按当前方案依次执行处理步骤：分析阶段修改卡片，保存会话后执行交付阶段
+        let mut ctx = SessionContext::new(session_id, window, video_path);
+        let max_privacy_level =
+            crate::domains::privacy::max_export_level(&self.settings.get().await);
+        let mut stages: Vec<Box<dyn SessionStage + '_>> = vec![
+            Box::new(LlmAnalyzer {
+                processor: self,
+                frames: &frames,
+                sampled_frames,
+                chunks,
+                chunk_videos,
+                duration_minutes,
+                video_metadata,
+            }),
+            Box::new(HeuristicTagger {
+                db: self.db.clone(),
+                settings: self.settings.clone(),
+            }),
+            Box::new(WebhookNotifier {
+                db: self.db.clone(),
+                url: pipeline_settings.webhook_url.clone(),
+                secret: pipeline_settings.webhook_secret.clone(),
+                max_privacy_level,
+            }),
+        ];
+        if let Some(notion_manager) = &self.notion_manager {
+            stages.push(Box::new(NotionSyncer {
+                db: self.db.clone(),
+                manager: notion_manager.clone(),
+                max_privacy_level,
+            }));
+        }
+        let pipeline = SessionPipeline::new(&steps, stages);
 
-        // 设置provider的数据库连接和session_id
-        self.llm_handle
-            .set_provider_database(self.db.clone(), Some(session_id))
+        pipeline.run_phase(Phase::Analysis, &mut ctx).await?;
+        self.save_session(&mut ctx, should_persist_frames.then_some(frames.as_slice()))
             .await?;
-
-        // 设置视频速率乘数
-        let app_config = self.settings.get().await;
-        let speed_multiplier = app_config.video_config.speed_multiplier;
-        self.llm_handle.set_video_speed(speed_multiplier).await?;
-
-        // 使用两阶段分析：先分段，再生成时间线
-        let analysis = {
-            match self
-                .llm_handle
-                .segment_video_and_generate_timeline(frame_paths, duration_minutes, None)
-                .await
-            {
-                Ok(result) => result,
-                Err(e) => {
-                    // 如果是视频过短错误，清理已创建的资源
-                    if e.to_string().contains("VIDEO_TOO_SHORT") {
-                        error!("检测到视频过短错误，开始清理资源...");
-
-                        // 1. 删除数据库中的会话记录
-                        if let Err(del_err) = self.db.delete_session(session_id).await {
-                            error!("删除会话失败 (ID={}): {}", session_id, del_err);
-                        } else {
-                            info!("已删除会话记录: ID={}", session_id);
-                        }
-
-                        // 2. 删除视频文件（如果存在）
-                        if let Some(ref vp) = video_path_for_cleanup {
-                            if let Err(del_err) = tokio::fs::remove_file(vp).await {
-                                error!("删除视频文件失败 {}: {}", vp, del_err);
-                            } else {
-                                info!("已删除视频文件: {}", vp);
-                            }
-                        }
-                    }
-
-                    return Err(e);
-                }
-            }
-        };
-
-        let TimelineAnalysis {
-            mut segments,
-            mut timeline_cards,
-            segment_call_id,
-            timeline_call_id,
-        } = analysis;
-
-        for segment in &mut segments {
-            let start_abs =
-                relative_to_absolute(window.start, window.end, &segment.start_timestamp);
-            let end_abs = relative_to_absolute(window.start, window.end, &segment.end_timestamp);
-            // 注意：window.start/end 虽然类型是 DateTime<Utc>，但通过 local_now() 存储的是本地时间值
-            // 将其重新解释为本地时间并生成带时区标记的 RFC3339 格式（如 2025-10-09T12:54:00+08:00）
-            use chrono::{Local, TimeZone};
-            segment.start_timestamp = Local
-                .from_local_datetime(&start_abs.naive_local())
-                .unwrap()
-                .to_rfc3339();
-            segment.end_timestamp = Local
-                .from_local_datetime(&end_abs.naive_local())
-                .unwrap()
-                .to_rfc3339();
+        if let (Some(event_bus), Some(summary)) = (event_bus, &ctx.summary) {
+            event_bus.publish(crate::event_bus::AppEvent::AnalysisCompleted {
+                session_id: ctx.session_id,
+                summary: summary.clone(),
+            });
+        }
+        // 分析成功后不再需要保留的截图
+        if let (Some(frames_dir), Some(label)) = (
+            &frames_dir,
+            ctx.video_path.as_deref().and_then(|path| {
+                crate::domains::frame_retention::label_for_video(std::path::Path::new(path))
+            }),
+        ) {
+            crate::domains::frame_retention::release(frames_dir, label).await;
         }
+        pipeline.run_phase(Phase::Delivery, &mut ctx).await
+    }
 
-        for card in &mut timeline_cards {
-            let start_abs = relative_to_absolute(window.start, window.end, &card.start_time);
-            let end_abs = relative_to_absolute(window.start, window.end, &card.end_time);
-            // 同样，将其重新解释为本地时间并生成带时区标记的 RFC3339 格式
-            use chrono::{Local, TimeZone};
-            card.start_time = Local
-                .from_local_datetime(&start_abs.naive_local())
-                .unwrap()
-                .to_rfc3339();
-            card.end_time = Local
-                .from_local_datetime(&end_abs.naive_local())
-                .unwrap()
-                .to_rfc3339();
-
-            if let Some(distractions) = card.distractions.as_mut() {
-                for distraction in distractions {
-                    let d_start =
-                        relative_to_absolute(window.start, window.end, &distraction.start_time);
-                    let d_end =
-                        relative_to_absolute(window.start, window.end, &distraction.end_time);
-                    distraction.start_time = Local
-                        .from_local_datetime(&d_start.naive_local())
-                        .unwrap()
-                        .to_rfc3339();
-                    distraction.end_time = Local
-                        .from_local_datetime(&d_end.naive_local())
-                        .unwrap()
-                        .to_rfc3339();
-                }
-            }
+    /// 保存分析阶段的结果：分段、卡片、标题总结和帧（未执行 LLM 分析时保存占位卡片）
+    async fn save_session(
+        &self,
+        ctx: &mut SessionContext,
+        frames: Option<&[crate::capture::ScreenFrame]>,
+    ) -> Result<()> {
+        let session_id = ctx.session_id;
+        if !ctx.analyzed {
+            ctx.title = NOT_ANALYZED_TITLE.to_string();
+            return self
+                .save_skipped_session(
+                    session_id,
+                    &ctx.window,
+                    ctx.video_path.clone(),
+                    frames,
+                    "当前处理方案未启用 LLM 分析，视频仅保存在本地",
+                )
+                .await;
         }
+        let window = &ctx.window;
 
         // 保存segments到数据库
-        if !segments.is_empty() {
-            let segment_records: Vec<crate::storage::VideoSegmentRecord> = segments
+        if !ctx.segments.is_empty() {
+            let segment_records: Vec<crate::storage::VideoSegmentRecord> = ctx
+                .segments
                 .iter()
-                .map(|seg| crate::storage::VideoSegmentRecord {
+                .enumerate()
+                .map(|(index, seg)| crate::storage::VideoSegmentRecord {
                     id: None,
                     session_id,
-                    llm_call_id: segment_call_id,
+                    llm_call_id: ctx
+                        .segment_call_ids
+                        .get(index)
+                        .copied()
+                        .unwrap_or(ctx.segment_call_id),
                     start_timestamp: seg.start_timestamp.clone(),
                     end_timestamp: seg.end_timestamp.clone(),
                     description: seg.description.clone(),
@@ -1168,52 +1606,52 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
         }
 
         // 保存timeline cards到数据库
-        if !timeline_cards.is_empty() {
-            let card_records: Vec<crate::storage::TimelineCardRecord> = timeline_cards
-                .iter()
-                .map(|card| {
-                    crate::storage::TimelineCardRecord {
-                        id: None,
-                        session_id,
-                        llm_call_id: timeline_call_id,
-                        start_time: card.start_time.clone(),
-                        end_time: card.end_time.clone(),
-                        category: card.category.clone(),
-                        subcategory: card.subcategory.clone(),
-                        title: card.title.clone(),
-                        summary: card.summary.clone(),
-                        detailed_summary: card.detailed_summary.clone(),
-                        distractions: card
-                            .distractions
-                            .as_ref()
-                            .map(|d| serde_json::to_string(d).unwrap_or_default()),
-                        app_sites: serde_json::to_string(&card.app_sites).unwrap_or_default(),
-                        video_preview_path: video_path.clone(), // 使用已生成的视频路径
-                        created_at: crate::storage::local_now(),
-                    }
-                })
-                .collect();
-
-            self.db.insert_timeline_cards(&card_records).await?;
-            info!("保存了 {} 个时间线卡片", card_records.len());
+        if !ctx.card_records.is_empty() {
+            self.db.insert_timeline_cards(&ctx.card_records).await?;
+            info!("保存了 {} 个时间线卡片", ctx.card_records.len());
         }
 
-        // 从timeline卡片生成总结（使用第一个卡片的信息）
-        let summary = build_session_summary(window.start, window.end, &segments, &timeline_cards);
+        // 从timeline卡片生成总结（标题按配置的方式选取卡片）
+        let title_strategy = self
+            .settings
+            .get()
+            .await
+            .session_title_strategy
+            .unwrap_or_default();
+        let summary = build_session_summary(
+            window.start,
+            window.end,
+            &ctx.segments,
+            &ctx.timeline_cards,
+            title_strategy,
+        );
+        // 同一天内标题重复时追加时间段和主要应用
+        let title = crate::domains::session_titles::dedupe_session_title(
+            &self.db,
+            session_id,
+            window.start,
+            window.end,
+            &summary.title,
+            &ctx.timeline_cards,
+        )
+        .await;
 
         // 更新会话信息（之前已经创建了临时会话）
         self.db
             .update_session(
                 session_id,
-                &summary.title,
+                &title,
                 &summary.summary,
-                video_path.as_deref(),
+                ctx.video_path.as_deref(),
                 &serde_json::to_string(&summary.tags)?,
             )
             .await?;
 
         // 保存帧数据（如果没有生成视频则保存路径，否则路径已被删除）
-        if should_persist_frames {
+        if let Some(frames) = frames {
+            let browser_events =
+                crate::domains::browser::load_browser_events(&self.db, window.start, window.end)
+                    .await;
             let db_frames: Vec<crate::storage::Frame> = frames
                 .iter()
                 .map(|f| crate::storage::Frame {
@@ -1221,6 +1659,7 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
                     session_id,
                     timestamp: f.timestamp,
                     file_path: f.file_path.clone(),
+                    metadata: crate::domains::browser::frame_metadata(&browser_events, f.timestamp),
                 })
                 .collect();
 
@@ -1231,25 +1670,426 @@ impl crate::capture::scheduler::SessionProcessor for LLMProcessor {
             "会话已保存到数据库: ID={}, 标题={}",
             session_id, summary.title
         );
+        ctx.title = title;
+        ctx.summary = Some(summary);
+        Ok(())
+    }
+}
 
-        // 异步同步到 Notion（不阻塞主流程）
-        if let Some(notion_manager) = &self.notion_manager {
-            if notion_manager.is_enabled().await {
-                // 获取完整的会话信息
-                if let Ok(session) = self.db.get_session(session_id).await {
-                    info!("触发 Notion 同步：会话 {}", session_id);
-                    notion_manager.sync_session_async(session).await;
-                }
+/// LLM 分析步骤：分段并生成时间线卡片
+struct LlmAnalyzer<'a> {
+    processor: &'a LLMProcessor,
+    frames: &'a [crate::capture::ScreenFrame],
+    sampled_frames: Vec<crate::capture::ScreenFrame>,
+    chunks: Vec<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+    chunk_videos: Vec<Option<String>>,
+    duration_minutes: u32,
+    video_metadata: Option<crate::video::VideoMetadata>,
+}
+
+impl LlmAnalyzer<'_> {
+    /// 删除分块分析用的临时视频
+    async fn remove_chunk_videos(&self) {
+        for chunk_video in self.chunk_videos.iter().flatten() {
+            if let Err(e) = tokio::fs::remove_file(chunk_video).await {
+                warn!("删除分块视频失败 {}: {}", chunk_video, e);
             }
+            crate::video::VideoMetadata::remove(std::path::Path::new(chunk_video)).await;
         }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'a> SessionStage for LlmAnalyzer<'a> {
+    fn step(&self) -> PipelineStep {
+        PipelineStep::LlmAnalyzer
+    }
+
+    async fn run(&self, ctx: &mut SessionContext) -> Result<()> {
+        let processor = self.processor;
+        let session_id = ctx.session_id;
+        let window = ctx.window.clone();
+        let video_path = ctx.video_path.clone();
+        let frame_paths: Vec<String> = self
+            .sampled_frames
+            .iter()
+            .map(|f| f.file_path.clone())
+            .collect();
+
+        // 更新provider的视频路径
+        processor
+            .llm_handle
+            .set_video_path(video_path.clone())
+            .await?;
+
+        // 设置provider的数据库连接和session_id
+        processor
+            .llm_handle
+            .set_provider_database(processor.db.clone(), Some(session_id))
+            .await?;
+
+        // 用户纠正过的卡片作为提示词示例
+        let corrections = processor
+            .db
+            .get_recent_card_corrections(personalization::MAX_CORRECTIONS)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("读取卡片纠正记录失败: {}", e);
+                Vec::new()
+            });
+        processor
+            .llm_handle
+            .set_correction_examples(corrections)
+            .await?;
+
+        // 设置视频速率乘数（优先使用视频生成时的实际倍速）
+        // 平均倍速只用于提示词，返回的视频时间按视频元数据分段换算（见 absolutize_analysis）
+        let app_config = processor.settings.get().await;
+        let speed_multiplier = self
+            .video_metadata
+            .as_ref()
+            .map(|m| m.effective_speed_multiplier())
+            .unwrap_or(app_config.video_config.speed_multiplier);
+        processor
+            .llm_handle
+            .set_video_speed(speed_multiplier)
+            .await?;
+        processor
+            .llm_handle
+            .set_video_upload(app_config.video_upload_config.clone().unwrap_or_default())
+            .await?;
+
+        // 会话完全落在安静时段内时不调用模型，直接生成休息卡片
+        let quiet_zone = crate::domains::quiet_zones::zone_covering(
+            app_config.quiet_zones.as_deref().unwrap_or_default(),
+            window.start,
+            window.end,
+        );
+
+        // 使用两阶段分析：先分段，再生成时间线
+        let analysis = if let Some(zone) = quiet_zone {
+            info!("会话处于安静时段 {}，跳过 LLM 分析", zone.name);
+            self.remove_chunk_videos().await;
+            let mut analysis =
+                crate::domains::quiet_zones::break_analysis(&zone, window.start, window.end);
+            absolutize_analysis(&mut analysis, window.start, window.end, None);
+            analysis
+        } else if self.chunks.len() > 1 {
+            info!(
+                "会话时长 {} 分钟，分 {} 块分析",
+                self.duration_minutes,
+                self.chunks.len()
+            );
+            let result = processor
+                .analyze_in_chunks(&self.sampled_frames, &self.chunks, &self.chunk_videos)
+                .await;
+            self.remove_chunk_videos().await;
+            // 分块视频已删除，分析失败时也要清理provider的视频路径
+            processor.llm_handle.set_video_path(None).await?;
+            result?
+        } else {
+            let still_paths = frame_paths.clone();
+            match processor
+                .llm_handle
+                .segment_video_and_generate_timeline(frame_paths, self.duration_minutes, None)
+                .await
+            {
+                Ok(mut analysis) => {
+                    absolutize_analysis(
+                        &mut analysis,
+                        window.start,
+                        window.end,
+                        self.video_metadata.as_ref(),
+                    );
+                    analysis
+                }
+                // 帧数太少时保留会话，改为单帧分析生成一张短暂活动卡片
+                Err(e) if e.to_string().contains("VIDEO_TOO_SHORT") => {
+                    warn!("视频过短，改为单帧分析: {}", e);
+                    let active_start = self.frames.first().map_or(window.start, |f| f.timestamp);
+                    let active_end = self.frames.last().map_or(window.end, |f| f.timestamp);
+                    let mut analysis = crate::domains::sparse_sessions::analyze_sparse_session(
+                        &processor.llm_handle,
+                        &still_paths,
+                        video_path.as_deref().map(std::path::Path::new),
+                        window.start,
+                        active_start,
+                        active_end,
+                    )
+                    .await;
+                    absolutize_analysis(&mut analysis, window.start, window.end, None);
+                    analysis
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let TimelineAnalysis {
+            segments,
+            timeline_cards,
+            segment_call_id,
+            timeline_call_id,
+            segment_call_ids,
+            timeline_call_ids,
+        } = analysis;
+
+        let card_records: Vec<crate::storage::TimelineCardRecord> = timeline_cards
+            .iter()
+            .enumerate()
+            .map(|(index, card)| {
+                crate::storage::TimelineCardRecord {
+                    id: None,
+                    session_id,
+                    llm_call_id: timeline_call_ids
+                        .get(index)
+                        .copied()
+                        .unwrap_or(timeline_call_id),
+                    start_time: card.start_time.clone(),
+                    end_time: card.end_time.clone(),
+                    category: card.category.clone(),
+                    subcategory: card.subcategory.clone(),
+                    title: card.title.clone(),
+                    summary: card.summary.clone(),
+                    detailed_summary: card.detailed_summary.clone(),
+                    distractions: card
+                        .distractions
+                        .as_ref()
+                        .map(|d| serde_json::to_string(d).unwrap_or_default()),
+                    app_sites: serde_json::to_string(&card.app_sites).unwrap_or_default(),
+                    video_preview_path: video_path.clone(), // 使用已生成的视频路径
+                    created_at: crate::storage::local_now(),
+                    confidence: Some(crate::domains::review::card_confidence(card)),
+                    review_status: None,
+                    meeting_notes: None,
+                    keyword_tags: None,
+                    preview_clip_path: None,
+                    privacy_level: None,
+                }
+            })
+            .collect();
+
+        ctx.segments = segments;
+        ctx.timeline_cards = timeline_cards;
+        ctx.segment_call_id = segment_call_id;
+        ctx.timeline_call_id = timeline_call_id;
+        ctx.segment_call_ids = segment_call_ids;
+        ctx.card_records = card_records;
+        ctx.analyzed = true;
 
         // 清理provider的视频路径，避免影响后续会话
-        self.llm_handle.set_video_path(None).await?;
+        processor.llm_handle.set_video_path(None).await?;
         Ok(())
     }
 }
 
 impl LLMProcessor {
+    /// 保存未分析的会话（用户选择不分析或方案未启用 LLM 分析）：占位标题和一张覆盖整个时间窗的占位卡片
+    async fn save_skipped_session(
+        &self,
+        session_id: i64,
+        window: &crate::capture::scheduler::SessionWindow,
+        video_path: Option<String>,
+        frames: Option<&[crate::capture::ScreenFrame]>,
+        summary: &str,
+    ) -> Result<()> {
+        use chrono::{Local, TimeZone};
+
+        let to_local = |time: chrono::DateTime<chrono::Utc>| {
+            Local
+                .from_local_datetime(&time.naive_local())
+                .unwrap()
+                .to_rfc3339()
+        };
+        let card = crate::storage::TimelineCardRecord {
+            id: None,
+            session_id,
+            llm_call_id: None,
+            start_time: to_local(window.start),
+            end_time: to_local(window.end),
+            category: "other".to_string(),
+            subcategory: "skipped".to_string(),
+            title: NOT_ANALYZED_TITLE.to_string(),
+            summary: summary.to_string(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: video_path.clone(),
+            created_at: crate::storage::local_now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        self.db.insert_timeline_cards(&[card]).await?;
+        self.db
+            .update_session(
+                session_id,
+                NOT_ANALYZED_TITLE,
+                summary,
+                video_path.as_deref(),
+                "[]",
+            )
+            .await?;
+
+        if let Some(frames) = frames {
+            let db_frames: Vec<crate::storage::Frame> = frames
+                .iter()
+                .map(|f| crate::storage::Frame {
+                    id: None,
+                    session_id,
+                    timestamp: f.timestamp,
+                    file_path: f.file_path.clone(),
+                    metadata: None,
+                })
+                .collect();
+            self.db.insert_frames(&db_frames).await?;
+        }
+
+        info!("会话 {} 未分析: {}", session_id, summary);
+        Ok(())
+    }
+
+    /// 为每个分块生成视频，生成失败的分块回退到图片模式
+    async fn create_chunk_videos(
+        &self,
+        video_processor: &crate::video::VideoProcessor,
+        frames: &[crate::capture::ScreenFrame],
+        chunks: &[(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)],
+        video_config: &crate::video::VideoConfig,
+    ) -> Vec<Option<String>> {
+        let frame_interval_secs = crate::domains::playback::frame_interval_secs(
+            self.settings.get().await.capture_interval,
+        );
+        let mut videos = Vec::with_capacity(chunks.len());
+        for (index, (start, end)) in chunks.iter().enumerate() {
+            let is_last = index + 1 == chunks.len();
+            let chunk_frames: Vec<String> = frames
+                .iter()
+                .filter(|f| {
+                    crate::domains::analysis::chunk_contains((*start, *end), is_last, f.timestamp)
+                })
+                .map(|f| f.file_path.clone())
+                .collect();
+            let filtered = crate::video::filter_frames_by_interval(chunk_frames, 5);
+            let frame_count = filtered.len();
+            if filtered.is_empty() {
+                videos.push(None);
+                continue;
+            }
+
+            let output_path = video_processor.temp_dir.join(format!(
+                "chunk-{}-{}.mp4",
+                start.format("%Y%m%d%H%M%S"),
+                index
+            ));
+            match video_processor
+                .create_summary_video(filtered, &output_path, video_config)
+                .await
+            {
+                Ok(result) => {
+                    crate::video::VideoMetadata::new(
+                        video_config,
+                        frame_interval_secs,
+                        frame_count,
+                    )
+                    .save_or_warn(&output_path)
+                    .await;
+                    videos.push(Some(result.file_path));
+                }
+                Err(e) => {
+                    warn!("分块 {} 视频生成失败: {}，该块使用图片模式", index + 1, e);
+                    videos.push(None);
+                }
+            }
+        }
+        videos
+    }
+
+    /// 逐块分析并合并结果（时间转换为绝对时间后拼接）
+    async fn analyze_in_chunks(
+        &self,
+        frames: &[crate::capture::ScreenFrame],
+        chunks: &[(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)],
+        chunk_videos: &[Option<String>],
+    ) -> Result<TimelineAnalysis> {
+        let mut merged = TimelineAnalysis {
+            segments: Vec::new(),
+            timeline_cards: Vec::new(),
+            segment_call_id: None,
+            timeline_call_id: None,
+            segment_call_ids: Vec::new(),
+            timeline_call_ids: Vec::new(),
+        };
+
+        for (index, (start, end)) in chunks.iter().enumerate() {
+            let is_last = index + 1 == chunks.len();
+            let chunk_frames: Vec<String> = frames
+                .iter()
+                .filter(|f| {
+                    crate::domains::analysis::chunk_contains((*start, *end), is_last, f.timestamp)
+                })
+                .map(|f| f.file_path.clone())
+                .collect();
+            if chunk_frames.is_empty() {
+                continue;
+            }
+            let chunk_minutes = ((*end - *start).num_seconds().max(0) as f64 / 60.0).ceil() as u32;
+            info!(
+                "分析第 {}/{} 块: {} - {} ({} 帧)",
+                index + 1,
+                chunks.len(),
+                start.format("%H:%M:%S"),
+                end.format("%H:%M:%S"),
+                chunk_frames.len()
+            );
+
+            let chunk_video = chunk_videos.get(index).cloned().flatten();
+            let chunk_metadata = match &chunk_video {
+                Some(path) => crate::video::VideoMetadata::load(std::path::Path::new(path)).await,
+                None => None,
+            };
+            self.llm_handle.set_video_path(chunk_video).await?;
+            let mut analysis = match self
+                .llm_handle
+                .segment_video_and_generate_timeline(chunk_frames, chunk_minutes, None)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) if e.to_string().contains("VIDEO_TOO_SHORT") => {
+                    warn!("第 {} 块视频过短，跳过", index + 1);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            absolutize_analysis(&mut analysis, *start, *end, chunk_metadata.as_ref());
+            // 每个分段和卡片关联到产生它的那一块的调用；跨块合并的卡片沿用前一块的调用
+            merged.segment_call_ids.extend(std::iter::repeat_n(
+                analysis.segment_call_id,
+                analysis.segments.len(),
+            ));
+            merged.segments.extend(analysis.segments);
+            let card_count = analysis.timeline_cards.len();
+            let stitched = crate::domains::analysis::stitch_timeline_cards(
+                &mut merged.timeline_cards,
+                analysis.timeline_cards,
+            );
+            merged.timeline_call_ids.extend(std::iter::repeat_n(
+                analysis.timeline_call_id,
+                card_count - stitched as usize,
+            ));
+            merged.segment_call_id = merged.segment_call_id.or(analysis.segment_call_id);
+            merged.timeline_call_id = merged.timeline_call_id.or(analysis.timeline_call_id);
+        }
+
+        if merged.segments.is_empty() && merged.timeline_cards.is_empty() {
+            return Err(anyhow!("分块分析未产生任何结果"));
+        }
+        Ok(merged)
+    }
+
     /// 采样帧数据
     fn sample_frames(
         &self,
@@ -0,0 +1,192 @@
+// 阿里云 OSS 客户端 - 用于将会话视频上传到用户自己的 Bucket
+//
+// 使用 OSS V1 签名（HMAC-SHA1）：
+// - PUT 上传对象、DELETE 删除对象使用 Authorization 头签名
+// - 生成带签名的临时 GET 链接供 Qwen 拉取视频，链接过期后即不可访问
+
+use crate::models::OssConfig;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha1::Sha1;
+use tracing::{info, warn};
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// 视频文件的 Content-Type
+const VIDEO_CONTENT_TYPE: &str = "video/mp4";
+
+/// OSS 客户端
+pub struct OssClient {
+    client: Client,
+    config: OssConfig,
+}
+
+impl OssClient {
+    pub fn new(client: Client, config: OssConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// 上传本地文件，返回对象 Key
+    pub async fn put_file(&self, file_path: &str) -> Result<String> {
+        let file_name = std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("无效的文件路径"))?;
+        let key = self.object_key(file_name);
+        let content = tokio::fs::read(file_path).await?;
+
+        let date = http_date();
+        let authorization = self.authorization("PUT", VIDEO_CONTENT_TYPE, &date, &key);
+        let response = self
+            .client
+            .put(self.object_url(&key))
+            .header("Date", &date)
+            .header("Content-Type", VIDEO_CONTENT_TYPE)
+            .header("Authorization", authorization)
+            .body(content)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("OSS 上传失败: {}", error_text));
+        }
+
+        info!("视频已上传到 OSS: {}/{}", self.config.bucket, key);
+        Ok(key)
+    }
+
+    /// 生成带签名的临时下载链接（链接含签名，不要写入日志）
+    pub fn presigned_url(&self, key: &str) -> Result<String> {
+        let expires =
+            chrono::Utc::now().timestamp() + self.config.url_expire_seconds.max(60) as i64;
+        let string_to_sign = string_to_sign("GET", "", &expires.to_string(), &self.resource(key));
+        let signature = sign(&self.config.access_key_secret, &string_to_sign);
+
+        let mut url = reqwest::Url::parse(&self.object_url(key))
+            .map_err(|e| anyhow!("OSS 对象地址格式错误（请检查 Endpoint 和 Bucket）: {}", e))?;
+        url.query_pairs_mut()
+            .append_pair("OSSAccessKeyId", &self.config.access_key_id)
+            .append_pair("Expires", &expires.to_string())
+            .append_pair("Signature", &signature);
+        Ok(url.to_string())
+    }
+
+    /// 删除对象
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        let date = http_date();
+        let authorization = self.authorization("DELETE", "", &date, key);
+        let response = self
+            .client
+            .delete(self.object_url(key))
+            .header("Date", &date)
+            .header("Authorization", authorization)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            warn!("删除 OSS 对象失败 {}: {}", key, error_text);
+            return Err(anyhow!("删除 OSS 对象失败: {}", error_text));
+        }
+
+        info!("已删除 OSS 对象: {}/{}", self.config.bucket, key);
+        Ok(())
+    }
+
+    /// 生成对象 Key（前缀 + 时间戳 + 文件名，避免覆盖同名文件）
+    fn object_key(&self, file_name: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        let name = format!(
+            "{}_{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            file_name
+        );
+        if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        }
+    }
+
+    /// 对象地址（不含签名）
+    pub fn object_url(&self, key: &str) -> String {
+        let endpoint = self
+            .config
+            .endpoint
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/');
+        format!("https://{}.{}/{}", self.config.bucket, endpoint, key)
+    }
+
+    fn resource(&self, key: &str) -> String {
+        format!("/{}/{}", self.config.bucket, key)
+    }
+
+    fn authorization(&self, verb: &str, content_type: &str, date: &str, key: &str) -> String {
+        let string_to_sign = string_to_sign(verb, content_type, date, &self.resource(key));
+        format!(
+            "OSS {}:{}",
+            self.config.access_key_id,
+            sign(&self.config.access_key_secret, &string_to_sign)
+        )
+    }
+}
+
+/// 构建待签名字符串（不含 Content-MD5 和 x-oss- 头）
+fn string_to_sign(verb: &str, content_type: &str, date: &str, resource: &str) -> String {
+    format!("{}\n\n{}\n{}\n{}", verb, content_type, date, resource)
+}
+
+/// HMAC-SHA1 签名并 base64 编码
+fn sign(secret: &str, string_to_sign: &str) -> String {
+    let mut mac = HmacSha1::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(string_to_sign.as_bytes());
+    general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// HTTP Date 头（GMT）
+fn http_date() -> String {
+    chrono::Utc::now()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_hmac_sha1_base64() {
+        let string_to_sign = "PUT\neB5eJF1ptWaXm4bijSPyxw==\ntext/html\nThu, 17 Nov 2005 18:49:58 GMT\nx-oss-magic:abracadabra\nx-oss-meta-author:foo@example.com\n/oss-example/nelson";
+        assert_eq!(
+            sign("OtxrzxIsfpFjA7SwPzILwy8Bw21TLhquhboDYROV", string_to_sign),
+            "8HQ6ejfvfwbs/JyzhzA/ElF4fx8="
+        );
+    }
+
+    #[test]
+    fn test_presigned_url_rejects_malformed_endpoint() {
+        let config = OssConfig {
+            endpoint: "oss-cn-hangzhou.aliyuncs.com".to_string(),
+            bucket: "bad bucket".to_string(),
+            access_key_id: "id".to_string(),
+            access_key_secret: "secret".to_string(),
+            prefix: String::new(),
+            url_expire_seconds: 3600,
+        };
+        let client = OssClient::new(Client::new(), config);
+        assert!(client.presigned_url("videos/a.mp4").is_err());
+    }
+
+    #[test]
+    fn test_string_to_sign_layout() {
+        assert_eq!(
+            string_to_sign("GET", "", "1141889120", "/bucket/videos/a.mp4"),
+            "GET\n\n\n1141889120\n/bucket/videos/a.mp4"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// 个性化示例 - 把用户对卡片的纠正作为 few-shot 示例加入分段/时间线提示词
+//
+// 纠正记录来自复核队列（card_corrections 表）。生成时间线时按与当前分段内容的相关度挑选示例
+// （应用名、原标题中的词出现在分段描述里），分段阶段尚无内容可比较，只按时间取最近的纠正。
+// 示例总长度受预算限制，避免纠正记录增多后挤占提示词
+
+use super::plugin::VideoSegment;
+use crate::storage::CardCorrectionRecord;
+use std::collections::HashSet;
+
+/// 每次分析最多从数据库读取的纠正记录数
+pub const MAX_CORRECTIONS: i64 = 50;
+
+/// 示例部分的字符预算（中文约 1-2 字符/token）
+const EXAMPLE_BUDGET_CHARS: usize = 1200;
+
+/// 最多加入的示例条数
+const MAX_EXAMPLES: usize = 8;
+
+/// 纠正记录中用于匹配的关键词：主要/次要应用，以及原标题和纠正后标题
+fn keywords(correction: &CardCorrectionRecord) -> Vec<String> {
+    let app_sites: serde_json::Value =
+        serde_json::from_str(&correction.app_sites).unwrap_or(serde_json::Value::Null);
+    let mut words: Vec<String> = Vec::new();
+    if let Some(primary) = app_sites["primary"].as_str() {
+        words.push(primary.to_string());
+    }
+    if let Some(secondary) = app_sites["secondary"].as_array() {
+        words.extend(
+            secondary
+                .iter()
+                .filter_map(|s| s.as_str())
+                .map(String::from),
+        );
+    }
+    words.push(correction.original_title.clone());
+    words.push(correction.corrected_title.clone());
+
+    words
+        .into_iter()
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| w.chars().count() >= 2 && w != "unknown")
+        .collect()
+}
+
+fn primary_app(correction: &CardCorrectionRecord) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(&correction.app_sites)
+        .ok()
+        .and_then(|v| v["primary"].as_str().map(|s| s.trim().to_string()))
+        .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("unknown"))
+}
+
+fn render_example(correction: &CardCorrectionRecord) -> String {
+    let app = primary_app(correction)
+        .map(|app| format!("应用 {}：", app))
+        .unwrap_or_default();
+    format!(
+        "- {}\"{}\"（{}/{}）应为 \"{}\"（{}/{}）\n",
+        app,
+        correction.original_title,
+        correction.original_category,
+        correction.original_subcategory,
+        correction.corrected_title,
+        correction.corrected_category,
+        correction.corrected_subcategory
+    )
+}
+
+/// 挑选与 context 最相关的纠正并渲染为提示词片段，没有可用示例时返回 None
+///
+/// `corrections` 应按时间倒序排列；相关度相同时保留较新的纠正，
+/// 纠正结果相同的记录只保留一条
+pub fn few_shot_section(corrections: &[CardCorrectionRecord], context: &str) -> Option<String> {
+    if corrections.is_empty() {
+        return None;
+    }
+
+    let context = context.to_lowercase();
+    let mut scored: Vec<(usize, usize, &CardCorrectionRecord)> = corrections
+        .iter()
+        .enumerate()
+        .map(|(index, correction)| {
+            let score = keywords(correction)
+                .iter()
+                .filter(|word| context.contains(word.as_str()))
+                .count();
+            (score, index, correction)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut seen = HashSet::new();
+    let mut section =
+        String::from("\n\n## 用户习惯（来自用户对以往卡片的纠正，请沿用其中的用词和分类）:\n");
+    let header_len = section.chars().count();
+    let mut count = 0;
+    for (_, _, correction) in scored {
+        let key = (
+            primary_app(correction),
+            correction.corrected_title.clone(),
+            correction.corrected_category.clone(),
+            correction.corrected_subcategory.clone(),
+        );
+        if !seen.insert(key) {
+            continue;
+        }
+        let line = render_example(correction);
+        if section.chars().count() + line.chars().count() - header_len > EXAMPLE_BUDGET_CHARS {
+            break;
+        }
+        section.push_str(&line);
+        count += 1;
+        if count >= MAX_EXAMPLES {
+            break;
+        }
+    }
+
+    (count > 0).then_some(section)
+}
+
+/// 分段描述拼接成的匹配文本（生成时间线时用于挑选示例）
+pub fn segments_context(segments: &[VideoSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.description.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 在提示词末尾追加个性化示例
+pub fn append_examples(prompt: &mut String, corrections: &[CardCorrectionRecord], context: &str) {
+    if let Some(section) = few_shot_section(corrections, context) {
+        prompt.push_str(&section);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correction(app: &str, original: &str, corrected: &str) -> CardCorrectionRecord {
+        CardCorrectionRecord {
+            id: None,
+            card_id: 1,
+            original_title: original.to_string(),
+            original_category: "other".to_string(),
+            original_subcategory: "General".to_string(),
+            corrected_title: corrected.to_string(),
+            corrected_category: "work".to_string(),
+            corrected_subcategory: "Data Analysis".to_string(),
+            summary: String::new(),
+            app_sites: serde_json::json!({ "primary": app, "secondary": [] }).to_string(),
+            created_at: crate::storage::local_now(),
+        }
+    }
+
+    #[test]
+    fn test_relevant_corrections_come_first_and_duplicates_are_dropped() {
+        let corrections = vec![
+            correction("wechat", "聊天", "客户沟通"),
+            correction("Jupyter", "写代码", "项目X数据分析"),
+            correction("Jupyter", "浏览网页", "项目X数据分析"),
+        ];
+
+        let section =
+            few_shot_section(&corrections, "用户在 jupyter notebook 中处理表格数据").unwrap();
+        let lines: Vec<&str> = section.lines().filter(|l| l.starts_with("- ")).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("项目X数据分析"));
+        assert!(lines[1].contains("客户沟通"));
+
+        assert!(few_shot_section(&[], "anything").is_none());
+    }
+}
@@ -1,5 +1,6 @@
 // LLM插件系统 - 定义提供商接口和数据结构
 
+use crate::storage::CardCorrectionRecord;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -103,6 +104,9 @@ pub struct TimelineCard {
     /// 视频预览路径（本地视频文件）
     #[serde(rename = "videoPreviewPath", skip_serializing_if = "Option::is_none")]
     pub video_preview_path: Option<String>,
+    /// 模型自评的置信度（0-1），未返回时由启发式规则估算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
 }
 
 /// 归一化时间线卡片中的字段（主要处理distractions字符串场景）
@@ -528,6 +532,90 @@ pub struct SessionBrief {
     pub summary: String,
 }
 
+/// 每日总结的日期类型，决定总结的模板和语气
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayType {
+    /// 工作日
+    #[default]
+    Workday,
+    /// 周末
+    Weekend,
+    /// 假期（用户标记）
+    Vacation,
+}
+
+impl DayType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "workday" => Some(Self::Workday),
+            "weekend" => Some(Self::Weekend),
+            "vacation" => Some(Self::Vacation),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Workday => "workday",
+            Self::Weekend => "weekend",
+            Self::Vacation => "vacation",
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Workday => "工作日",
+            Self::Weekend => "周末",
+            Self::Vacation => "假期",
+        }
+    }
+
+    /// 提示词中的总结类型
+    pub fn summary_kind(&self) -> &'static str {
+        match self {
+            Self::Workday => "工作总结",
+            Self::Weekend => "周末活动回顾",
+            Self::Vacation => "假期活动回顾",
+        }
+    }
+
+    /// 提示词中的语气要求
+    pub fn tone_requirement(&self) -> &'static str {
+        match self {
+            Self::Workday => "今天是工作日，可以客观指出专注度和效率方面值得注意的地方",
+            Self::Weekend => {
+                "今天是周末，用轻松的语气回顾做了什么，不要评价工作效率或产出，不要因为工作时间少而批评；如果周末仍在工作，可以温和地提醒注意休息"
+            }
+            Self::Vacation => {
+                "今天是用户标记的假期，用轻松的语气回顾，不要评价生产力；如果处理了工作，只简要提及并提醒好好休假"
+            }
+        }
+    }
+}
+
+/// 不调用模型时的默认每日总结
+pub(crate) fn default_day_summary(sessions: &[SessionBrief], day_type: DayType) -> String {
+    let total_minutes: i64 = sessions
+        .iter()
+        .map(|s| (s.end_time - s.start_time).num_minutes())
+        .sum();
+    match day_type {
+        DayType::Workday => format!(
+            "今天共记录了 {} 个工作会话，总计 {} 分钟。",
+            sessions.len(),
+            total_minutes
+        ),
+        _ => format!(
+            "{}共记录了 {} 段屏幕活动，总计 {} 分钟。",
+            day_type.label(),
+            sessions.len(),
+            total_minutes
+        ),
+    }
+}
+
 /// 会话总结
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SessionSummary {
@@ -565,6 +653,26 @@ impl Default for SessionSummary {
     }
 }
 
+/// 会议纪要（会议时段密集采样画面后的分析结果）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MeetingNotes {
+    /// 会议主题
+    #[serde(default)]
+    pub topic: String,
+    /// 画面中可见的议程项
+    #[serde(default)]
+    pub agenda_items: Vec<String>,
+    /// 幻灯片/共享屏幕的标题
+    #[serde(default)]
+    pub slide_titles: Vec<String>,
+    /// 画面中可见的参会人姓名
+    #[serde(default)]
+    pub participants: Vec<String>,
+    /// 会议时长（分钟，按卡片时间计算）
+    #[serde(default)]
+    pub duration_minutes: i64,
+}
+
 /// LLM提供商接口
 #[async_trait]
 pub trait LLMProvider: Send + Sync + std::any::Any {
@@ -627,6 +735,7 @@ pub trait LLMProvider: Send + Sync + std::any::Any {
                     secondary: None,
                 },
                 video_preview_path: None,
+                confidence: None,
             });
         }
         Ok(cards)
@@ -640,6 +749,9 @@ pub trait LLMProvider: Send + Sync + std::any::Any {
     ) {
     }
 
+    /// 设置用户纠正记录（按时间倒序），作为分段/时间线提示词中的示例
+    fn set_correction_examples(&mut self, _corrections: Vec<CardCorrectionRecord>) {}
+
     /// 获取提供商名称
     fn name(&self) -> &str;
 
@@ -667,6 +779,7 @@ pub trait LLMProvider: Send + Sync + std::any::Any {
     /// # 参数
     /// * `date` - 日期 (YYYY-MM-DD)
     /// * `sessions` - 当天的所有会话简要信息（包含时间段和摘要）
+    /// * `day_type` - 日期类型（工作日/周末/假期），决定总结的模板和语气
     ///
     /// # 返回
     /// * 生成的总结文本
@@ -674,17 +787,37 @@ pub trait LLMProvider: Send + Sync + std::any::Any {
         &self,
         _date: &str,
         _sessions: &[SessionBrief],
+        _day_type: DayType,
     ) -> Result<String> {
         // 默认实现：返回简单的基于规则的总结
-        let total_minutes: i64 = _sessions
-            .iter()
-            .map(|s| (s.end_time - s.start_time).num_minutes())
-            .sum();
-        Ok(format!(
-            "今天共记录了 {} 个工作会话，总计 {} 分钟。",
-            _sessions.len(),
-            total_minutes
-        ))
+        Ok(default_day_summary(_sessions, _day_type))
+    }
+
+    /// 生成会议纪要
+    ///
+    /// # 参数
+    /// * `frames` - 会议时段内密集采样的帧图片路径
+    /// * `context` - 时间线卡片的标题和摘要
+    ///
+    /// # 返回
+    /// * 会议纪要（duration_minutes 由调用方填写）
+    async fn generate_meeting_notes(
+        &self,
+        _frames: Vec<String>,
+        _context: &str,
+    ) -> Result<MeetingNotes> {
+        Err(anyhow::anyhow!("当前模型不支持生成会议纪要"))
+    }
+
+    /// 生成周计划
+    ///
+    /// # 参数
+    /// * `prompt` - 由 week_plan 模块根据历史统计构建的提示词
+    ///
+    /// # 返回
+    /// * 模型的原始回复（日程块 JSON，由调用方解析和校验）
+    async fn generate_week_plan(&self, _prompt: &str) -> Result<String> {
+        Err(anyhow::anyhow!("当前模型不支持生成周计划"))
     }
 }
 
@@ -1,6 +1,11 @@
 // 阿里通义千问提供商实现 - 支持视频直接上传分析
 
+use super::oss::OssClient;
+use super::personalization;
 use super::plugin::*;
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::models::{VideoUploadConfig, VideoUploadTarget};
+use crate::storage::CardCorrectionRecord;
 use anyhow::Result;
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
@@ -13,6 +18,9 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tracing::{debug, error, info, warn};
 
+/// 提示词模板版本（修改提示词时递增，记录在 LLM 调用中便于追溯）
+pub const PROMPT_VERSION: &str = "qwen-v2";
+
 /// Qwen提供商（阿里通义千问）
 pub struct QwenProvider {
     api_key: Option<String>,
@@ -30,6 +38,10 @@ pub struct QwenProvider {
     last_call_ids: Mutex<HashMap<String, i64>>,
     /// 视频速率乘数（用于提示词）
     video_speed_multiplier: f32,
+    /// 视频上传配置
+    video_upload: VideoUploadConfig,
+    /// 用户纠正记录（提示词示例）
+    correction_examples: Vec<CardCorrectionRecord>,
 }
 
 impl QwenProvider {
@@ -48,6 +60,8 @@ impl QwenProvider {
             session_video_path: None,
             last_call_ids: Mutex::new(HashMap::new()),
             video_speed_multiplier: 8.0, // 默认8倍速
+            video_upload: VideoUploadConfig::default(),
+            correction_examples: Vec::new(),
         }
     }
 
@@ -85,6 +99,11 @@ impl QwenProvider {
         self.video_speed_multiplier = speed_multiplier;
     }
 
+    /// 设置视频上传配置
+    pub fn set_video_upload(&mut self, config: VideoUploadConfig) {
+        self.video_upload = config;
+    }
+
     fn reset_call_id(&self, call_type: &str) {
         if let Ok(mut map) = self.last_call_ids.lock() {
             map.remove(call_type);
@@ -174,17 +193,81 @@ impl QwenProvider {
     }
 
     /// 上传视频文件到阿里云
-    async fn upload_video(&self, video_path: &str) -> Result<String> {
+    async fn upload_video(&self, video_path: &str) -> Result<UploadedVideo> {
         info!("开始上传视频文件: {}", video_path);
+        let video_bytes = tokio::fs::metadata(video_path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        if self.video_upload.target == VideoUploadTarget::Oss {
+            let oss = self.oss_client()?;
+            let key = oss.put_file(video_path).await?;
+            let url = match oss.presigned_url(&key) {
+                Ok(url) => url,
+                Err(e) => {
+                    let _ = oss.delete_object(&key).await;
+                    return Err(e);
+                }
+            };
+            egress::record(
+                EgressChannel::Llm,
+                &oss.object_url(&key),
+                EgressContent::Video,
+                video_bytes,
+                self.current_session_id,
+            );
+            return Ok(UploadedVideo {
+                url,
+                oss_key: Some(key),
+            });
+        }
 
         // 获取上传凭证
         let policy = self.get_upload_policy().await?;
 
         // 上传文件到OSS
         let oss_url = self.upload_file_to_oss(&policy, video_path).await?;
+        egress::record(
+            EgressChannel::Llm,
+            &policy.upload_host,
+            EgressContent::Video,
+            video_bytes,
+            self.current_session_id,
+        );
 
         info!("视频上传成功: {}", oss_url);
-        Ok(oss_url)
+        Ok(UploadedVideo {
+            url: oss_url,
+            oss_key: None,
+        })
+    }
+
+    /// 分析完成后清理已上传的视频（DashScope 临时存储会自动过期，无需清理）
+    async fn cleanup_uploaded_video(&self, video: &UploadedVideo) {
+        let Some(ref key) = video.oss_key else {
+            return;
+        };
+        if !self.video_upload.cleanup_after_analysis {
+            return;
+        }
+        match self.oss_client() {
+            Ok(oss) => {
+                if let Err(e) = oss.delete_object(key).await {
+                    warn!("清理已上传的视频失败: {}", e);
+                }
+            }
+            Err(e) => warn!("清理已上传的视频失败: {}", e),
+        }
+    }
+
+    fn oss_client(&self) -> Result<OssClient> {
+        let config = self
+            .video_upload
+            .oss
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("未配置阿里云 OSS"))?;
+        Ok(OssClient::new(self.client.clone(), config))
     }
 
     /// 将图片文件转换为base64
@@ -344,6 +427,7 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
       "primary": "vscode",
       "secondary": "github.com"
     }},
+    "confidence": 0.9,  // 0-1，画面模糊、活动难以判断时给出较低的值
     "isUpdated": false  // 是否是对已有卡片的更新
   }}
 ]"#,
@@ -436,9 +520,29 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
                 secondary: None,
             },
             video_preview_path: None,
+            confidence: None,
         })
     }
 
+    /// 记录发往模型服务的请求（数据外发记录）
+    fn record_egress(
+        &self,
+        endpoint: &str,
+        request_body: &serde_json::Value,
+        content: EgressContent,
+    ) {
+        let bytes = serde_json::to_vec(request_body)
+            .map(|body| body.len())
+            .unwrap_or(0);
+        egress::record(
+            EgressChannel::Llm,
+            endpoint,
+            content,
+            bytes as u64,
+            self.current_session_id,
+        );
+    }
+
     /// 调用Qwen API - 支持图片base64模式
     async fn call_qwen_api(
         &self,
@@ -455,6 +559,12 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
 
         self.reset_call_id(call_type);
 
+        let content = if images_base64.is_empty() {
+            EgressContent::Text
+        } else {
+            EgressContent::Screenshots
+        };
+
         // 构建消息内容
         let mut content_parts = vec![];
 
@@ -527,9 +637,12 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             latency_ms: None,
             token_usage: None,
             created_at: crate::storage::local_now(),
+            prompt_version: Some(PROMPT_VERSION.to_string()),
         };
 
         let endpoint = self.base_url.clone();
+        crate::domains::local_only::ensure_local_endpoint(&endpoint)
+            .map_err(|e| anyhow::anyhow!(e))?;
         let response = self
             .client
             .post(&endpoint)
@@ -539,6 +652,7 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             .json(&request_body)
             .send()
             .await?;
+        self.record_egress(&endpoint, &request_body, content);
 
         let status_code = response.status().as_u16() as i32;
         llm_record.status_code = Some(status_code);
@@ -557,6 +671,8 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             llm_record.error_message = Some(error_text.clone());
             llm_record.latency_ms = Some(start_time.elapsed().as_millis() as i64);
 
+            crate::llm::debug_archive::archive(&llm_record, &request_body);
+
             // 保存错误记录
             if let Some(ref db) = self.db {
                 if let Err(e) = db.insert_llm_call(&llm_record).await {
@@ -594,6 +710,8 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             }
         }
 
+        crate::llm::debug_archive::archive(&llm_record, &request_body);
+
         // 保存成功记录
         if let Some(ref db) = self.db {
             match db.insert_llm_call(&llm_record).await {
@@ -673,9 +791,11 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             "temperature": 0.3
         });
 
+        // OSS 临时链接的查询参数包含签名，日志和调用记录中去掉
+        let unsigned_url = video_url.split('?').next().unwrap_or_default();
         debug!(
             "调用Qwen API with video: model={}, video_url={}",
-            self.model, video_url
+            self.model, unsigned_url
         );
 
         // 记录请求信息
@@ -692,7 +812,8 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             })
             .to_string(),
             // 使用 sanitize_request_body 清理图片 base64 数据
-            request_body: crate::llm::sanitize_request_body(&request_body),
+            request_body: crate::llm::sanitize_request_body(&request_body)
+                .replace(video_url.as_str(), unsigned_url),
             response_headers: None,
             response_body: None,
             status_code: None,
@@ -700,9 +821,12 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             latency_ms: None,
             token_usage: None,
             created_at: crate::storage::local_now(),
+            prompt_version: Some(PROMPT_VERSION.to_string()),
         };
 
         let endpoint = self.base_url.clone();
+        crate::domains::local_only::ensure_local_endpoint(&endpoint)
+            .map_err(|e| anyhow::anyhow!(e))?;
         let response = self
             .client
             .post(&endpoint)
@@ -712,6 +836,7 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             .json(&request_body)
             .send()
             .await?;
+        self.record_egress(&endpoint, &request_body, EgressContent::Text);
 
         let status_code = response.status().as_u16() as i32;
         llm_record.status_code = Some(status_code);
@@ -730,6 +855,8 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             llm_record.error_message = Some(error_text.clone());
             llm_record.latency_ms = Some(start_time.elapsed().as_millis() as i64);
 
+            crate::llm::debug_archive::archive(&llm_record, &request_body);
+
             // 保存错误记录
             if let Some(ref db) = self.db {
                 if let Err(e) = db.insert_llm_call(&llm_record).await {
@@ -774,6 +901,8 @@ Create long, meaningful cards that represent cohesive sessions of activity, idea
             }
         }
 
+        crate::llm::debug_archive::archive(&llm_record, &request_body);
+
         // 保存成功记录
         if let Some(ref db) = self.db {
             match db.insert_llm_call(&llm_record).await {
@@ -954,6 +1083,10 @@ impl LLMProvider for QwenProvider {
         self
     }
 
+    fn set_correction_examples(&mut self, corrections: Vec<CardCorrectionRecord>) {
+        self.correction_examples = corrections;
+    }
+
     /// 分析屏幕截图帧
     async fn analyze_frames(&self, frames: Vec<String>) -> Result<SessionSummary> {
         if !self.is_configured() {
@@ -1065,16 +1198,28 @@ impl LLMProvider for QwenProvider {
             duration
         );
 
-        // 检查是否设置了视频路径
-        if let Some(ref video_file) = self.session_video_path {
+        // 检查是否设置了视频路径（本地模式下不上传视频，直接使用图片序列）
+        let video_file = self
+            .session_video_path
+            .as_ref()
+            .filter(|_| !crate::domains::local_only::is_enabled());
+        if let Some(video_file) = video_file {
             // 如果设置了视频路径，尝试上传并使用视频URL模式
-            match self.upload_video(&video_file).await {
-                Ok(video_url) => {
-                    info!("使用视频URL模式分析: {}", video_url);
-                    let prompt = self.build_segment_prompt(duration, self.video_speed_multiplier);
-                    let response = self
-                        .call_qwen_api_with_video(prompt, video_url, "segment_video")
-                        .await?;
+            match self.upload_video(video_file).await {
+                Ok(video) => {
+                    // OSS 临时链接包含签名，只记录对象 Key
+                    info!(
+                        "使用视频URL模式分析: {}",
+                        video.oss_key.as_deref().unwrap_or(&video.url)
+                    );
+                    let mut prompt =
+                        self.build_segment_prompt(duration, self.video_speed_multiplier);
+                    personalization::append_examples(&mut prompt, &self.correction_examples, "");
+                    let result = self
+                        .call_qwen_api_with_video(prompt, video.url.clone(), "segment_video")
+                        .await;
+                    self.cleanup_uploaded_video(&video).await;
+                    let response = result?;
 
                     // 解析响应 - 由于使用了response_format: json_object，直接解析
                     let segments: Vec<VideoSegment> = serde_json::from_str(&response)?;
@@ -1114,7 +1259,8 @@ impl LLMProvider for QwenProvider {
         }
 
         // 调用API进行分段
-        let prompt = self.build_segment_prompt(duration, self.video_speed_multiplier);
+        let mut prompt = self.build_segment_prompt(duration, self.video_speed_multiplier);
+        personalization::append_examples(&mut prompt, &self.correction_examples, "");
         let response = self
             .call_qwen_api(prompt, images_base64, "segment_video")
             .await?;
@@ -1151,6 +1297,11 @@ impl LLMProvider for QwenProvider {
         let mut prompt = self.build_timeline_prompt(&previous_cards);
         prompt.push_str("\n\n当前视频分段:\n");
         prompt.push_str(&serde_json::to_string_pretty(&segments)?);
+        personalization::append_examples(
+            &mut prompt,
+            &self.correction_examples,
+            &personalization::segments_context(&segments),
+        );
 
         // 调用API（不需要图片）
         let response = self
@@ -1266,6 +1417,7 @@ impl LLMProvider for QwenProvider {
         &self,
         date: &str,
         sessions: &[crate::llm::SessionBrief],
+        day_type: DayType,
     ) -> Result<String> {
         let api_key = self
             .api_key
@@ -1319,9 +1471,9 @@ impl LLMProvider for QwenProvider {
 
         // 构建提示词
         let prompt = format!(
-            r#"基于以下今日屏幕活动记录，生成一份工作总结：
+            r#"基于以下今日屏幕活动记录，生成一份{}：
 
-日期: {}
+日期: {}（{}）
 会话数: {}
 总时长: {} 分钟
 
@@ -1335,15 +1487,19 @@ impl LLMProvider for QwenProvider {
 4. 可以提及关键时间段的重要活动
 5. 字数控制在 150-200 字以内
 6. 输出格式要清晰易读，可以使用适当的分段
+7. {}
 
 请直接返回总结文本（只要中文总结，不要标题、不要其他说明）。"#,
+            day_type.summary_kind(),
             date,
+            day_type.label(),
             sessions.len(),
             total_minutes,
-            sessions_text
+            sessions_text,
+            day_type.tone_requirement()
         );
 
-        info!("使用Qwen生成每日总结: {}", date);
+        info!("使用Qwen生成每日总结: {} ({})", date, day_type.label());
 
         // 调用API
         let request_body = json!({
@@ -1358,6 +1514,8 @@ impl LLMProvider for QwenProvider {
             "max_tokens": 10000  // 支持 200 字的中文输出（约 400-500 tokens）
         });
 
+        crate::domains::local_only::ensure_local_endpoint(&self.base_url)
+            .map_err(|e| anyhow::anyhow!(e))?;
         let response = self
             .client
             .post(&self.base_url)
@@ -1366,6 +1524,7 @@ impl LLMProvider for QwenProvider {
             .json(&request_body)
             .send()
             .await?;
+        self.record_egress(&self.base_url, &request_body, EgressContent::Text);
 
         if !response.status().is_success() {
             let status = response.status();
@@ -1387,6 +1546,74 @@ impl LLMProvider for QwenProvider {
             Err(anyhow::anyhow!("Qwen API 返回空结果"))
         }
     }
+
+    /// 生成会议纪要（图片模式，识别幻灯片标题和参会人）
+    async fn generate_meeting_notes(
+        &self,
+        frames: Vec<String>,
+        context: &str,
+    ) -> Result<MeetingNotes> {
+        if !self.is_configured() {
+            return Err(anyhow::anyhow!("Qwen API key未配置，请先配置 API key"));
+        }
+
+        let mut images_base64 = Vec::new();
+        for frame_path in &frames {
+            match self.image_to_base64(frame_path).await {
+                Ok(base64) => images_base64.push(base64),
+                Err(e) => error!("图片转换失败 {}: {}", frame_path, e),
+            }
+        }
+        if images_base64.is_empty() {
+            return Err(anyhow::anyhow!("没有有效的图片可以分析"));
+        }
+
+        let prompt = format!(
+            r#"以下图片按时间顺序截取自一次会议期间的屏幕画面。
+
+时间线卡片信息:
+{}
+
+请识别画面中的文字（幻灯片/共享文档标题、会议软件中显示的参会人姓名、议程），整理成会议纪要。
+要求：
+1. 只记录画面中能看到的内容，看不清或没有出现的字段返回空数组，不要编造
+2. topic 用一句中文概括会议主题
+3. 同一张幻灯片多次出现只记录一次，按首次出现的顺序排列
+
+返回JSON对象：
+{{"topic": "会议主题", "agenda_items": ["议程项"], "slide_titles": ["幻灯片标题"], "participants": ["参会人"]}}"#,
+            context
+        );
+
+        info!("使用Qwen生成会议纪要: {} 帧", images_base64.len());
+        let response = self
+            .call_qwen_api(prompt, images_base64, "meeting_notes")
+            .await?;
+
+        let json_str = match (response.find('{'), response.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &response[start..=end],
+            _ => &response,
+        };
+        Ok(serde_json::from_str(json_str)?)
+    }
+
+    /// 生成周计划（纯文本请求）
+    async fn generate_week_plan(&self, prompt: &str) -> Result<String> {
+        if !self.is_configured() {
+            return Err(anyhow::anyhow!("Qwen API key未配置，请先配置 API key"));
+        }
+        info!("使用Qwen生成周计划");
+        self.call_qwen_api(prompt.to_string(), Vec::new(), "week_plan")
+            .await
+    }
+}
+
+/// 已上传的视频
+struct UploadedVideo {
+    /// 供模型访问的地址
+    url: String,
+    /// 上传到用户 OSS 时的对象 Key（用于分析后清理）
+    oss_key: Option<String>,
 }
 
 /// 上传凭证响应结构
@@ -1,28 +1,173 @@
 // 自定义日志层 - 支持将日志实时推送到前端
+//
+// 最近的日志保存在内存环形缓冲中，并定期写入日志目录下的文件，
+// 重新打开日志面板或重启应用后可以通过 get_recent_logs 查看历史。
+// 推送到前端的日志按每秒条数限流，日志风暴时超出部分只保存不推送，避免界面卡死
+//
+// 每个会话的处理流程（调度 → 视频 → LLM → 存储）都在带 trace_id 的 span 中执行，
+// 日志会带上该会话的关联 ID，get_session_trace 据此取出一个会话的完整日志
 
-use std::sync::{Arc, OnceLock, RwLock};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
+use tracing::span::{Attributes, Id};
 use tracing::subscriber::SetGlobalDefaultError;
-use tracing::{Event, Subscriber};
+use tracing::{Event, Span, Subscriber};
 use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 
 // 全局静态变量保存日志文件 guard，避免资源泄漏
 static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
 
+// 日志目录（初始化日志系统时设置）
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 保留的最近日志条数
+pub const HISTORY_CAPACITY: usize = 5000;
+
+/// 每秒最多推送到前端的日志条数
+const MAX_EMITS_PER_SECOND: usize = 50;
+
+/// 环形缓冲写入文件的间隔
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 历史日志文件名（位于日志目录）
+const HISTORY_FILE_NAME: &str = "recent_logs.jsonl";
+
+/// 查询历史日志时默认返回的条数
+const DEFAULT_QUERY_LIMIT: usize = 1000;
+
+/// 会话日志文件中最多返回的行数
+const MAX_TRACE_FILE_LINES: usize = 5000;
+
+/// 会话 span 中保存关联 ID 的字段名
+const TRACE_ID_FIELD: &str = "trace_id";
+
+/// 日志目录（日志系统尚未初始化时为 None）
+pub fn log_dir() -> Option<PathBuf> {
+    LOG_DIR.get().cloned()
+}
+
+/// 会话的关联 ID（由会话开始时间生成，调度、视频、LLM 和存储各环节得到的 ID 一致）
+pub fn session_trace_id(start: DateTime<Utc>) -> String {
+    start
+        .with_timezone(&Local)
+        .format("session-%Y%m%d-%H%M%S")
+        .to_string()
+}
+
+/// 创建会话 span，在其中产生的日志都会带上该会话的关联 ID
+pub fn session_span(start: DateTime<Utc>) -> Span {
+    tracing::info_span!("session", trace_id = %session_trace_id(start))
+}
+
 /// 日志消息
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct LogMessage {
     pub timestamp: String,
     pub level: String,
     pub target: String,
     pub message: String,
+    /// 所属会话的关联 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+/// 历史日志查询条件
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct LogFilter {
+    /// 最低级别（ERROR/WARN/INFO/DEBUG/TRACE），不设置时返回全部级别
+    pub level: Option<String>,
+    /// 消息或模块包含的关键词（不区分大小写）
+    pub keyword: Option<String>,
+    /// 最多返回的条数（取最新的，默认 1000）
+    pub limit: Option<usize>,
+    /// 只返回该会话关联 ID 的日志
+    pub trace_id: Option<String>,
+}
+
+/// 日志级别的严重程度（数值越小越严重）
+fn level_rank(level: &str) -> u8 {
+    match level.to_uppercase().as_str() {
+        "ERROR" => 0,
+        "WARN" => 1,
+        "INFO" => 2,
+        "DEBUG" => 3,
+        _ => 4,
+    }
+}
+
+impl LogFilter {
+    fn matches(&self, log: &LogMessage) -> bool {
+        if let Some(trace_id) = &self.trace_id {
+            if log.trace_id.as_ref() != Some(trace_id) {
+                return false;
+            }
+        }
+        if let Some(level) = &self.level {
+            if level_rank(&log.level) > level_rank(level) {
+                return false;
+            }
+        }
+        match self.keyword.as_deref().map(str::trim) {
+            Some(keyword) if !keyword.is_empty() => {
+                let keyword = keyword.to_lowercase();
+                log.message.to_lowercase().contains(&keyword)
+                    || log.target.to_lowercase().contains(&keyword)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// 一个会话的日志
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTrace {
+    pub trace_id: String,
+    /// 最近日志缓冲中属于该会话的日志
+    pub logs: Vec<LogMessage>,
+    /// 日志文件中属于该会话的行（最近日志缓冲已被挤出时仍可查看）
+    pub file_lines: Vec<String>,
+}
+
+/// 最近日志的环形缓冲
+#[derive(Default)]
+struct LogHistory {
+    entries: VecDeque<LogMessage>,
+    /// 自上次写入文件后是否有新日志
+    dirty: bool,
+}
+
+impl LogHistory {
+    fn push(&mut self, log: LogMessage) {
+        if self.entries.len() >= HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(log);
+        self.dirty = true;
+    }
+}
+
+/// 推送限流窗口（每秒重置）
+struct RateWindow {
+    started: Instant,
+    sent: usize,
+    dropped: usize,
 }
 
 /// 日志推送器 - 将日志发送到前端
 pub struct LogBroadcaster {
     app_handle: Arc<RwLock<Option<AppHandle>>>,
     enabled: Arc<RwLock<bool>>,
+    history: Mutex<LogHistory>,
+    /// 历史日志文件（None 表示不写文件），写文件期间持有锁
+    persist_path: Mutex<Option<PathBuf>>,
+    rate: Mutex<RateWindow>,
 }
 
 impl LogBroadcaster {
@@ -30,9 +175,183 @@ impl LogBroadcaster {
         Self {
             app_handle: Arc::new(RwLock::new(None)),
             enabled: Arc::new(RwLock::new(true)),
+            history: Mutex::new(LogHistory::default()),
+            persist_path: Mutex::new(None),
+            rate: Mutex::new(RateWindow {
+                started: Instant::now(),
+                sent: 0,
+                dropped: 0,
+            }),
         }
     }
 
+    /// 查询最近的日志（按时间顺序，只返回最新的 limit 条）
+    pub fn recent_logs(&self, filter: &LogFilter) -> Vec<LogMessage> {
+        let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let Ok(history) = self.history.lock() else {
+            return Vec::new();
+        };
+        let mut logs: Vec<LogMessage> = history
+            .entries
+            .iter()
+            .rev()
+            .filter(|log| filter.matches(log))
+            .take(limit)
+            .cloned()
+            .collect();
+        logs.reverse();
+        logs
+    }
+
+    /// 汇总一个会话的日志：最近日志缓冲中的条目，以及会话开始当天和次日日志文件中的相关行
+    pub fn session_trace(&self, start: DateTime<Utc>) -> SessionTrace {
+        let trace_id = session_trace_id(start);
+        let logs = self.recent_logs(&LogFilter {
+            trace_id: Some(trace_id.clone()),
+            limit: Some(HISTORY_CAPACITY),
+            ..Default::default()
+        });
+
+        let mut file_lines = Vec::new();
+        if let Some(log_dir) = LOG_DIR.get() {
+            let date = start.with_timezone(&Local).date_naive();
+            for day in [Some(date), date.succ_opt()].into_iter().flatten() {
+                file_lines.extend(read_trace_lines(log_dir, day, &trace_id));
+            }
+        }
+        if file_lines.len() > MAX_TRACE_FILE_LINES {
+            file_lines.drain(..file_lines.len() - MAX_TRACE_FILE_LINES);
+        }
+
+        SessionTrace {
+            trace_id,
+            logs,
+            file_lines,
+        }
+    }
+
+    /// 从文件恢复历史日志，并在后台定期把环形缓冲写回文件
+    pub fn enable_persistence(self: &Arc<Self>, path: PathBuf) {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            if let Ok(mut history) = self.history.lock() {
+                for line in content.lines() {
+                    if let Ok(log) = serde_json::from_str::<LogMessage>(line) {
+                        history.push(log);
+                    }
+                }
+                history.dirty = false;
+            }
+        }
+
+        if let Ok(mut persist_path) = self.persist_path.lock() {
+            *persist_path = Some(path);
+        }
+
+        let broadcaster = Arc::downgrade(self);
+        std::thread::Builder::new()
+            .name("log-history".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(PERSIST_INTERVAL);
+                let Some(broadcaster) = broadcaster.upgrade() else {
+                    break;
+                };
+                if !broadcaster.persist() {
+                    break;
+                }
+            })
+            .ok();
+    }
+
+    /// 停止写历史日志文件并清空环形缓冲（删除全部数据前调用，避免日志被写回）
+    ///
+    /// 返回时不会再有正在进行的写入
+    pub fn stop_persistence(&self) {
+        if let Ok(mut persist_path) = self.persist_path.lock() {
+            *persist_path = None;
+        }
+        if let Ok(mut history) = self.history.lock() {
+            history.entries.clear();
+            history.dirty = false;
+        }
+    }
+
+    /// 把环形缓冲写入文件（先写临时文件再替换，避免写到一半时损坏），已停止写文件时返回 false
+    fn persist(&self) -> bool {
+        let Ok(persist_path) = self.persist_path.lock() else {
+            return false;
+        };
+        let Some(path) = persist_path.as_deref() else {
+            return false;
+        };
+        let content = {
+            let Ok(mut history) = self.history.lock() else {
+                return true;
+            };
+            if !history.dirty {
+                return true;
+            }
+            history.dirty = false;
+            history
+                .entries
+                .iter()
+                .filter_map(|log| serde_json::to_string(log).ok())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        // 这里不能用 tracing 记录错误，否则写入失败时会不断产生新日志
+        let temp = path.with_extension("jsonl.tmp");
+        if let Err(e) = std::fs::write(&temp, content).and_then(|_| std::fs::rename(&temp, path)) {
+            eprintln!("保存历史日志失败: {}", e);
+        }
+        true
+    }
+
+    /// 保存到环形缓冲，并按限流推送到前端
+    fn record(&self, log: LogMessage) {
+        if let Ok(mut history) = self.history.lock() {
+            history.push(log.clone());
+        }
+
+        if !self.is_enabled() {
+            return;
+        }
+        let summary = {
+            let Ok(mut rate) = self.rate.lock() else {
+                return;
+            };
+            let mut summary = None;
+            if rate.started.elapsed() >= Duration::from_secs(1) {
+                if rate.dropped > 0 {
+                    summary = Some(rate.dropped);
+                }
+                rate.started = Instant::now();
+                rate.sent = 0;
+                rate.dropped = 0;
+            }
+            if rate.sent >= MAX_EMITS_PER_SECOND {
+                rate.dropped += 1;
+                return;
+            }
+            rate.sent += 1;
+            summary
+        };
+
+        if let Some(dropped) = summary {
+            self.emit_log(LogMessage {
+                timestamp: log.timestamp.clone(),
+                level: "WARN".to_string(),
+                target: module_path!().to_string(),
+                message: format!(
+                    "日志过多，已省略 {} 条实时日志（可在历史日志中查看）",
+                    dropped
+                ),
+                trace_id: None,
+            });
+        }
+        self.emit_log(log);
+    }
+
     /// 设置 Tauri App Handle
     pub fn set_app_handle(&self, handle: AppHandle) {
         if let Ok(mut app) = self.app_handle.write() {
@@ -68,6 +387,40 @@ impl LogBroadcaster {
     }
 }
 
+/// 读取某天日志文件中包含关联 ID 的行（按天轮转的文件名为 app.log.YYYY-MM-DD）
+fn read_trace_lines(log_dir: &Path, date: NaiveDate, trace_id: &str) -> Vec<String> {
+    let path = log_dir.join(format!("app.log.{}", date.format("%Y-%m-%d")));
+    std::fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter(|line| line.contains(trace_id))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 保存在 span 扩展中的会话关联 ID
+struct TraceId(String);
+
+/// 从 span 字段中取出关联 ID
+struct TraceIdVisitor(Option<String>);
+
+impl tracing::field::Visit for TraceIdVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == TRACE_ID_FIELD {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == TRACE_ID_FIELD {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
 /// 自定义日志层
 pub struct TauriLogLayer {
     broadcaster: Arc<LogBroadcaster>,
@@ -79,8 +432,19 @@ impl TauriLogLayer {
     }
 }
 
-impl<S: Subscriber> Layer<S> for TauriLogLayer {
-    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+impl<S> Layer<S> for TauriLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = TraceIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(trace_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(TraceId(trace_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
         // 提取日志级别
         let metadata = event.metadata();
         let level = metadata.level().to_string();
@@ -113,16 +477,24 @@ impl<S: Subscriber> Layer<S> for TauriLogLayer {
             .format("%Y-%m-%d %H:%M:%S%.3f")
             .to_string();
 
+        // 最近的会话 span 提供关联 ID
+        let trace_id = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .into_iter()
+                .find_map(|span| span.extensions().get::<TraceId>().map(|id| id.0.clone()))
+        });
+
         // 创建日志消息
         let log = LogMessage {
             timestamp,
             level,
             target,
             message: visitor.message,
+            trace_id,
         };
 
-        // 发送到前端
-        self.broadcaster.emit_log(log);
+        // 保存并发送到前端
+        self.broadcaster.record(log);
     }
 }
 
@@ -148,6 +520,10 @@ pub fn init_with_broadcaster(
 
     // 创建日志目录
     std::fs::create_dir_all(&log_dir).ok();
+    LOG_DIR.set(log_dir.clone()).ok();
+
+    // 恢复并定期保存最近的日志
+    broadcaster.enable_persistence(log_dir.join(HISTORY_FILE_NAME));
 
     // 配置日志输出到文件（每天轮转）
     let file_appender = tracing_appender::rolling::daily(log_dir.clone(), "app.log");
@@ -185,3 +561,75 @@ pub fn init_with_broadcaster(
     eprintln!("日志文件位置: {:?}", log_dir);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(level: &str, message: &str) -> LogMessage {
+        LogMessage {
+            timestamp: "2025-10-09 12:00:00.000".to_string(),
+            level: level.to_string(),
+            target: "screen_analyzer_lib::llm".to_string(),
+            message: message.to_string(),
+            trace_id: None,
+        }
+    }
+
+    #[test]
+    fn test_recent_logs_keep_newest_matching_entries() {
+        let broadcaster = LogBroadcaster::new();
+        broadcaster.set_enabled(false);
+        for i in 0..HISTORY_CAPACITY + 10 {
+            broadcaster.record(log("INFO", &format!("第 {} 条", i)));
+        }
+        broadcaster.record(log("WARN", "视频上传失败"));
+        broadcaster.record(log("ERROR", "分析失败"));
+
+        let all = broadcaster.recent_logs(&LogFilter::default());
+        assert_eq!(all.len(), DEFAULT_QUERY_LIMIT);
+        assert_eq!(all.last().unwrap().message, "分析失败");
+
+        let warnings = broadcaster.recent_logs(&LogFilter {
+            level: Some("warn".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].message, "视频上传失败");
+
+        let matched = broadcaster.recent_logs(&LogFilter {
+            keyword: Some("第 10 条".to_string()),
+            limit: Some(5),
+            ..Default::default()
+        });
+        // 最早的 12 条已被挤出缓冲
+        assert!(matched.is_empty());
+        assert_eq!(
+            broadcaster.history.lock().unwrap().entries.len(),
+            HISTORY_CAPACITY
+        );
+    }
+
+    #[test]
+    fn test_session_span_tags_logs_with_trace_id() {
+        let broadcaster = Arc::new(LogBroadcaster::new());
+        broadcaster.set_enabled(false);
+        let subscriber =
+            tracing_subscriber::registry().with(TauriLogLayer::new(broadcaster.clone()));
+        let start = Utc::now();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("会话外的日志");
+            session_span(start).in_scope(|| {
+                tracing::info!("开始分析会话");
+                tracing::info_span!("llm").in_scope(|| tracing::warn!("LLM 调用重试"));
+            });
+        });
+
+        let trace = broadcaster.session_trace(start);
+        assert_eq!(trace.trace_id, session_trace_id(start));
+        let messages: Vec<&str> = trace.logs.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, ["开始分析会话", "LLM 调用重试"]);
+        assert_eq!(broadcaster.recent_logs(&LogFilter::default()).len(), 3);
+    }
+}
@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // 重新导出其他模块的类型
 pub use crate::llm::plugin::{ActivityCategory, ActivityTag, KeyMoment};
@@ -32,6 +33,1152 @@ pub struct AppConfig {
     pub database_config: Option<DatabaseConfig>,
     /// Notion 配置
     pub notion_config: Option<NotionConfig>,
+    /// 多实例配置
+    pub instance_settings: Option<InstanceSettings>,
+    /// RAG 向量库导出配置
+    pub rag_export_config: Option<RagExportConfig>,
+    /// 休息提醒配置
+    pub break_reminder_settings: Option<BreakReminderSettings>,
+    /// 应用画像规则（覆盖 LLM 类别）
+    pub app_profiles: Option<Vec<AppProfileRule>>,
+    /// LLM 调试归档设置
+    pub llm_debug_archive: Option<LlmDebugArchiveSettings>,
+    /// Qwen 视频上传配置
+    pub video_upload_config: Option<VideoUploadConfig>,
+    /// 长会话分块分析设置
+    pub analysis_chunk_settings: Option<AnalysisChunkSettings>,
+    /// 按类别的保留规则
+    pub retention_rules: Option<Vec<CategoryRetentionRule>>,
+    /// 冷存储归档设置
+    pub archive_settings: Option<ArchiveSettings>,
+    /// 媒体存储设置
+    pub media_store: Option<MediaStoreSettings>,
+    /// 分析队列设置（顺序、每日预算）
+    pub analysis_queue: Option<AnalysisQueueSettings>,
+    /// 会话结束后暂缓分析的分钟数（期间可选择不分析，0 表示立即分析）
+    pub analysis_hold_minutes: Option<u32>,
+    /// 卡片置信度低于该值时进入复核队列（0 表示不复核）
+    pub review_confidence_threshold: Option<f64>,
+    /// 每日专注目标（分钟），用于计算连续达标天数
+    pub daily_focus_goal_minutes: Option<u32>,
+    /// 会话分析完成后自动为会议卡片生成会议纪要
+    pub meeting_notes_enabled: Option<bool>,
+    /// 会话分析完成后把时间线卡片作为字幕轨封装进会话视频
+    pub embed_subtitles: Option<bool>,
+    /// 会话分析完成后为每张卡片截取几秒预览短片（时间线悬浮时播放）
+    pub card_previews: Option<bool>,
+    /// 实时状态推送接口设置
+    pub live_api: Option<LiveApiSettings>,
+    /// 自动化钩子（事件触发的 shell 命令或 Rhai 脚本）
+    pub automation_hooks: Option<Vec<AutomationHook>>,
+    /// 专注模式设置（专注时段内拦截干扰应用）
+    pub focus_mode: Option<FocusModeSettings>,
+    /// 关注关键词（出现在分段描述或卡片摘要中时提醒并标记卡片）
+    pub watch_keywords: Option<Vec<String>>,
+    /// 每日安静时段（会话完全落在时段内时跳过 LLM 分析，生成固定的休息卡片）
+    pub quiet_zones: Option<Vec<QuietZone>>,
+    /// 屏幕文字时间线设置（对会话视频抽帧做 OCR）
+    pub text_timeline: Option<TextTimelineSettings>,
+    /// 实时活动估计设置（会话分析完成前按窗口标题和屏幕文字估计当前活动）
+    pub live_activity: Option<LiveActivitySettings>,
+    /// 剪贴板记录设置（默认关闭）
+    pub clipboard_log: Option<ClipboardLogSettings>,
+    /// 检测麦克风/扬声器是否在使用（不录音），辅助识别通话和会议
+    pub audio_presence_enabled: Option<bool>,
+    /// 存储配额设置（接近配额时自动降低新视频的画质）
+    pub storage_quota: Option<StorageQuotaSettings>,
+    /// 旧视频转码设置（空闲时段把较早的 H.264 视频转为 HEVC/AV1）
+    pub video_reencode: Option<VideoReencodeSettings>,
+    /// 截图保留设置（生成视频后保留原始截图，直到会话分析成功）
+    pub frame_retention: Option<FrameRetentionSettings>,
+    /// 会话窗口的对齐方式
+    pub session_alignment: Option<SessionAlignment>,
+    /// 自适应会话设置（按前台应用切换结束或延长会话）
+    pub adaptive_session: Option<AdaptiveSessionSettings>,
+    /// 会话标题的选取方式
+    pub session_title_strategy: Option<SessionTitleStrategy>,
+    /// 夜间分析设置（白天只生成视频，LLM 分析推迟到配置的时段）
+    pub night_analysis: Option<NightAnalysisSettings>,
+    /// 本地模式：禁止访问本机/局域网以外的网络，只能使用本地模型服务，Notion 同步停用
+    pub local_only: Option<bool>,
+    /// 会话处理流程（按方案配置启发式标注、LLM 分析、Notion 同步和 webhook 的顺序与开关）
+    pub pipeline: Option<PipelineSettings>,
+    /// 子类别同义词表（保存卡片时把 LLM 给出的子类别归一化）
+    pub subcategory_synonyms: Option<Vec<SubcategorySynonym>>,
+    /// 手动暂停截屏后忘记恢复的提醒设置
+    pub pause_reminder_settings: Option<PauseReminderSettings>,
+    /// 未分析视频积压提醒设置
+    pub analysis_backlog: Option<AnalysisBacklogSettings>,
+    /// 导出、分享和 Notion 同步时的隐私过滤设置
+    pub privacy_filter: Option<PrivacyFilterSettings>,
+    /// 导出到 Toggl/Clockify 的计时设置
+    pub time_tracking: Option<TimeTrackingSettings>,
+    /// 性能设置（并发和队列上限）
+    pub performance: Option<PerformanceSettings>,
+    /// 检查新版本的设置
+    pub update_check: Option<UpdateCheckSettings>,
+    /// 离开模式状态（休假等长时间不在电脑前）
+    pub away_mode: Option<AwayModeState>,
+}
+
+/// 多实例设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceSettings {
+    /// 检测到已有实例运行时，是否允许以只读模式启动（否则提示错误并退出）
+    pub allow_read_only_secondary: bool,
+    /// 查看模式：连接共享 MariaDB 浏览所有设备的数据，本机不截屏和分析（重启后生效）
+    #[serde(default)]
+    pub viewer_mode: bool,
+    /// 本机的角色：采集和分析可以分到不同机器上（重启后生效）
+    #[serde(default)]
+    pub machine_role: MachineRole,
+}
+
+impl Default for InstanceSettings {
+    fn default() -> Self {
+        Self {
+            allow_read_only_secondary: true,
+            viewer_mode: false,
+            machine_role: MachineRole::default(),
+        }
+    }
+}
+
+/// 本机在采集/分析分工中的角色
+///
+/// 仅采集的机器只截屏并生成视频（放在共享存储上），由分析机器认领未分析的视频后调用 LLM 分析
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MachineRole {
+    /// 截屏、生成视频并分析
+    #[default]
+    Full,
+    /// 只截屏和生成视频
+    Capture,
+    /// 只分析共享存储中未分析的视频，不截屏
+    Analysis,
+}
+
+impl MachineRole {
+    /// 是否截屏
+    pub fn captures(self) -> bool {
+        self != MachineRole::Analysis
+    }
+
+    /// 是否分析视频
+    pub fn analyzes(self) -> bool {
+        self != MachineRole::Capture
+    }
+}
+
+/// 休息提醒设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakReminderSettings {
+    /// 是否启用休息提醒
+    pub enabled: bool,
+    /// 连续活动多少分钟后提醒休息
+    pub active_minutes_threshold: u64,
+    /// 屏幕无变化多少分钟视为已休息（重新计时）
+    pub idle_reset_minutes: u64,
+    /// 稍后提醒的间隔（分钟）；提醒后继续活动超过该时长视为跳过休息
+    pub snooze_minutes: u64,
+}
+
+impl Default for BreakReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            active_minutes_threshold: 50,
+            idle_reset_minutes: 5,
+            snooze_minutes: 10,
+        }
+    }
+}
+
+/// 暂停截屏提醒设置
+///
+/// 手动暂停截屏超过一定时长、而电脑仍在使用（有键盘鼠标输入）时提醒恢复截屏
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseReminderSettings {
+    /// 是否启用提醒
+    pub enabled: bool,
+    /// 暂停多少分钟后开始提醒
+    pub after_minutes: u64,
+    /// 仍未恢复时再次提醒的间隔（分钟）
+    pub repeat_minutes: u64,
+}
+
+impl Default for PauseReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            after_minutes: 60,
+            repeat_minutes: 60,
+        }
+    }
+}
+
+/// 未分析视频积压提醒设置
+///
+/// 等待分析的视频超过阈值（未配置 API Key、分析反复失败等）时在状态栏警告并发送通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisBacklogSettings {
+    /// 是否启用提醒
+    pub enabled: bool,
+    /// 积压超过多少个视频时提醒
+    pub threshold: usize,
+    /// 仍未处理时再次通知的间隔（小时，0 表示只通知一次）
+    pub repeat_hours: u64,
+}
+
+impl Default for AnalysisBacklogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 20,
+            repeat_hours: 24,
+        }
+    }
+}
+
+/// 卡片的隐私敏感度（分析后按卡片内容和屏幕文字估计）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyLevel {
+    /// 未发现敏感内容
+    #[default]
+    None,
+    /// 私人内容（私聊、求职、个人邮箱等）
+    Low,
+    /// 银行、支付、医疗等敏感信息
+    Medium,
+    /// 画面中出现密码、密钥、证件号等凭据
+    High,
+}
+
+impl PrivacyLevel {
+    /// 数据库中保存的数值
+    pub fn as_i64(self) -> i64 {
+        self as i64
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        match value {
+            i64::MIN..=0 => Self::None,
+            1 => Self::Low,
+            2 => Self::Medium,
+            _ => Self::High,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::None => "无",
+            Self::Low => "低",
+            Self::Medium => "中",
+            Self::High => "高",
+        }
+    }
+}
+
+/// 隐私过滤设置
+///
+/// 导出（离线包、BI 数据集）、会话分享和 Notion 同步默认排除敏感度高于 max_level 的卡片，
+/// Notion 同步时整个会话跳过
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyFilterSettings {
+    /// 允许导出的最高敏感度（设为 high 表示不过滤）
+    pub max_level: PrivacyLevel,
+}
+
+impl Default for PrivacyFilterSettings {
+    fn default() -> Self {
+        Self {
+            max_level: PrivacyLevel::Low,
+        }
+    }
+}
+
+/// 计时服务
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeTrackingProvider {
+    #[default]
+    Toggl,
+    Clockify,
+}
+
+impl TimeTrackingProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Toggl => "toggl",
+            Self::Clockify => "clockify",
+        }
+    }
+}
+
+/// 按卡片类别决定是否导出，以及导出到哪个项目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeTrackingCategoryRule {
+    /// 卡片类别（如 work、personal，不区分大小写）
+    pub category: String,
+    pub include: bool,
+    /// 该类别卡片的默认项目（项目规则未命中时使用）
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// 项目识别规则：卡片标题、摘要、子类别或应用中出现关键词时归入该项目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRule {
+    pub keyword: String,
+    pub project: String,
+}
+
+/// 计时服务导出设置
+///
+/// 时间线卡片导出为 Toggl/Clockify 的时间记录：描述为卡片标题，项目按项目规则、类别规则、
+/// 子类别的顺序确定；导出过的卡片按幂等键记录，重复导出不会产生重复记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeTrackingSettings {
+    #[serde(default)]
+    pub provider: TimeTrackingProvider,
+    /// API 令牌（Toggl 的 API token 或 Clockify 的 API key）
+    #[serde(default)]
+    pub api_token: String,
+    /// 工作区 ID
+    #[serde(default)]
+    pub workspace_id: String,
+    #[serde(default)]
+    pub category_rules: Vec<TimeTrackingCategoryRule>,
+    /// 类别规则中未列出的类别是否导出
+    #[serde(default = "default_true")]
+    pub include_unlisted: bool,
+    #[serde(default)]
+    pub project_rules: Vec<ProjectRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TimeTrackingSettings {
+    fn default() -> Self {
+        let exclude = |category: &str| TimeTrackingCategoryRule {
+            category: category.to_string(),
+            include: false,
+            project: None,
+        };
+        Self {
+            provider: TimeTrackingProvider::default(),
+            api_token: String::new(),
+            workspace_id: String::new(),
+            category_rules: vec![exclude("idle"), exclude("personal")],
+            include_unlisted: true,
+            project_rules: Vec::new(),
+        }
+    }
+}
+
+/// 离开模式状态
+///
+/// 离开期间停止截屏、不发送暂停提醒，可选暂停按保留天数自动清理；
+/// 离开时段记录在截屏暂停记录中（原因为 away），统计平均值时排除这些天
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AwayModeState {
+    /// 是否处于离开模式
+    pub active: bool,
+    /// 进入离开模式的时间（本地时间）
+    pub since: Option<DateTime<Utc>>,
+    /// 离开期间是否暂停自动清理过期数据
+    #[serde(default)]
+    pub freeze_retention: bool,
+}
+
+/// 检查新版本设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckSettings {
+    /// 是否在后台定期检查新版本
+    pub enabled: bool,
+    /// 检查间隔（小时）
+    pub interval_hours: u64,
+    /// 跳过的版本（该版本不再提醒，更新的版本仍会提醒）
+    #[serde(default)]
+    pub skipped_version: Option<String>,
+}
+
+impl Default for UpdateCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            skipped_version: None,
+        }
+    }
+}
+
+/// 专注模式设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusModeSettings {
+    /// 干扰应用（按应用/进程名称匹配，不区分大小写，包含即算）
+    pub distraction_apps: Vec<String>,
+    /// 干扰应用切到前台时的处理方式
+    #[serde(default)]
+    pub action: FocusAction,
+    /// 开始专注时的默认时长（分钟）
+    pub default_minutes: u32,
+}
+
+impl Default for FocusModeSettings {
+    fn default() -> Self {
+        Self {
+            distraction_apps: Vec::new(),
+            action: FocusAction::Alert,
+            default_minutes: 50,
+        }
+    }
+}
+
+/// 专注时段内拦截干扰应用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusAction {
+    /// 只提醒
+    #[default]
+    Alert,
+    /// 提醒并最小化（macOS 隐藏应用，Windows 最小化窗口）
+    Minimize,
+}
+
+impl FocusAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alert => "alert",
+            Self::Minimize => "minimize",
+        }
+    }
+}
+
+/// 屏幕文字时间线设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextTimelineSettings {
+    /// 会话分析完成后自动识别
+    pub enabled: bool,
+    /// 抽帧间隔（真实时间，秒）
+    pub sample_interval_secs: u64,
+    /// tesseract 识别语言（如 chi_sim+eng）
+    pub languages: String,
+    /// tesseract 可执行文件路径（为空时从 PATH 查找）
+    #[serde(default)]
+    pub tesseract_path: Option<String>,
+}
+
+impl Default for TextTimelineSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_interval_secs: 60,
+            languages: "chi_sim+eng".to_string(),
+            tesseract_path: None,
+        }
+    }
+}
+
+/// 实时活动估计的一条规则：窗口标题（含应用名）或屏幕文字包含关键词时归为该类别
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveActivityRule {
+    /// 关键词（不区分大小写）
+    pub keyword: String,
+    pub category: String,
+    #[serde(default)]
+    pub subcategory: String,
+}
+
+/// 实时活动估计设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveActivitySettings {
+    /// 是否启用（需要读取前台窗口标题）
+    pub enabled: bool,
+    /// 窗口标题无法判断时，识别最新一帧截图的文字（使用屏幕文字时间线的 tesseract 设置）
+    pub ocr_enabled: bool,
+    /// 两次文字识别的最小间隔（秒）
+    pub ocr_interval_secs: u64,
+    /// 自定义规则，优先于内置规则
+    #[serde(default)]
+    pub rules: Vec<LiveActivityRule>,
+}
+
+impl Default for LiveActivitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ocr_enabled: false,
+            ocr_interval_secs: 60,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// 剪贴板记录设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipboardLogSettings {
+    /// 记录复制事件（时间、内容哈希和长度）
+    pub enabled: bool,
+    /// 同时保存复制的文本
+    #[serde(default)]
+    pub store_text: bool,
+}
+
+/// 会话窗口的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionAlignment {
+    /// 对齐到整点刻度（会话时长为 15 分钟时即 :00/:15/:30/:45）
+    #[default]
+    Clock,
+    /// 从第一张截图开始计时的滚动窗口
+    FirstActivity,
+    /// 从第一张截图开始，按前台应用切换提前结束或在同一活动中延长
+    Adaptive,
+}
+
+/// 自适应会话设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSessionSettings {
+    /// 其他应用持续占据前台超过多少分钟时结束当前会话
+    pub switch_minutes: u32,
+    /// 同一活动持续时会话最长延长到多少分钟（不短于会话时长）
+    pub max_minutes: u32,
+}
+
+impl Default for AdaptiveSessionSettings {
+    fn default() -> Self {
+        Self {
+            switch_minutes: 3,
+            max_minutes: 45,
+        }
+    }
+}
+
+/// 会话标题的选取方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionTitleStrategy {
+    /// 时长最长的卡片
+    #[default]
+    Longest,
+    /// 时长乘以类别生产力强度最高的卡片
+    MostProductive,
+    /// 第一张卡片（按时间顺序）
+    Chronological,
+}
+
+/// 存储配额设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageQuotaSettings {
+    /// 存储配额（GB，0 表示不限制）
+    pub quota_gb: f64,
+    /// 接近配额时降低新视频的画质和分辨率
+    pub degrade_video: bool,
+    /// 同时降低截图的 JPEG 质量
+    #[serde(default)]
+    pub degrade_frames: bool,
+}
+
+impl Default for StorageQuotaSettings {
+    fn default() -> Self {
+        Self {
+            quota_gb: 0.0,
+            degrade_video: true,
+            degrade_frames: false,
+        }
+    }
+}
+
+/// 旧视频转码使用的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReencodeCodec {
+    /// H.265/HEVC（libx265）
+    #[default]
+    Hevc,
+    /// AV1（libsvtav1）
+    Av1,
+}
+
+/// 截图保留设置：生成视频后暂不删除原始截图，会话分析成功后再删除，
+/// 分析失败或结果不理想时可以用原始截图重新生成视频再分析
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameRetentionSettings {
+    /// 是否保留到分析成功
+    pub keep_until_analyzed: bool,
+    /// 一直未分析成功时最多保留的天数
+    pub max_days: u32,
+}
+
+impl Default for FrameRetentionSettings {
+    fn default() -> Self {
+        Self {
+            keep_until_analyzed: false,
+            max_days: 3,
+        }
+    }
+}
+
+/// 旧视频转码设置：在空闲时段把较早的 H.264 视频转为 HEVC/AV1 以节省空间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoReencodeSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 只转码早于多少天的视频
+    pub older_than_days: u32,
+    /// 目标编码
+    pub codec: ReencodeCodec,
+    /// 目标 CRF（越大体积越小）
+    pub crf: u8,
+    /// 转码时段开始的小时（0-23，本地时间）
+    pub start_hour: u8,
+    /// 转码时段结束的小时（0-23，不含；小于开始小时表示跨越午夜）
+    pub end_hour: u8,
+}
+
+impl Default for VideoReencodeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            older_than_days: 30,
+            codec: ReencodeCodec::Hevc,
+            crf: 30,
+            start_hour: 2,
+            end_hour: 6,
+        }
+    }
+}
+
+/// LLM 调试归档设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmDebugArchiveSettings {
+    /// 是否归档完整的请求/响应报文（含图片/视频 base64，仅调试时开启）
+    pub enabled: bool,
+    /// 归档保留天数
+    pub retention_days: u32,
+    /// 归档目录总大小上限（MB）
+    pub max_total_mb: u64,
+    /// 单个归档文件大小上限（MB，压缩后），超过则不保存
+    pub max_file_mb: u64,
+}
+
+impl Default for LlmDebugArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 3,
+            max_total_mb: 500,
+            max_file_mb: 50,
+        }
+    }
+}
+
+/// Qwen 视频上传方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoUploadTarget {
+    /// DashScope 临时存储（48 小时后自动过期，无需额外配置）
+    Dashscope,
+    /// 用户自己的阿里云 OSS Bucket（通过带签名的临时链接访问）
+    Oss,
+}
+
+/// Qwen 视频上传配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoUploadConfig {
+    /// 上传方式
+    pub target: VideoUploadTarget,
+    /// 阿里云 OSS 配置（target 为 oss 时使用）
+    #[serde(default)]
+    pub oss: Option<OssConfig>,
+    /// 分析完成后删除已上传的对象（仅 OSS 方式支持）
+    pub cleanup_after_analysis: bool,
+}
+
+impl Default for VideoUploadConfig {
+    fn default() -> Self {
+        Self {
+            target: VideoUploadTarget::Dashscope,
+            oss: None,
+            cleanup_after_analysis: true,
+        }
+    }
+}
+
+/// 阿里云 OSS 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OssConfig {
+    /// 地域 Endpoint，如 oss-cn-hangzhou.aliyuncs.com
+    pub endpoint: String,
+    /// Bucket 名称
+    pub bucket: String,
+    pub access_key_id: String,
+    pub access_key_secret: String,
+    /// 对象 Key 前缀
+    #[serde(default)]
+    pub prefix: String,
+    /// 临时下载链接有效期（秒）
+    #[serde(default = "default_oss_url_expire_seconds")]
+    pub url_expire_seconds: u64,
+}
+
+fn default_oss_url_expire_seconds() -> u64 {
+    3600
+}
+
+/// 媒体存储后端（视频的存放位置，也用于冷存储归档目标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaBackend {
+    /// 本地文件夹（如外接硬盘、NAS 挂载目录）
+    Local,
+    /// S3 兼容的对象存储（AWS S3、MinIO、Cloudflare R2 等）
+    S3,
+    /// WebDAV（Nextcloud、群晖等）
+    #[serde(rename = "webdav")]
+    WebDav,
+}
+
+/// 媒体存储设置
+///
+/// 本地数据目录始终作为工作目录；使用远程后端时生成的视频同步到远程，
+/// 本地副本超过缓存天数后删除，回放或重新分析时再拉回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaStoreSettings {
+    /// 存储后端
+    pub backend: MediaBackend,
+    /// S3 配置（backend 为 s3 时使用）
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+    /// WebDAV 配置（backend 为 webdav 时使用）
+    #[serde(default)]
+    pub webdav: Option<WebDavConfig>,
+    /// 同步到远程后本地保留视频的天数（0 表示始终保留本地副本）
+    #[serde(default)]
+    pub local_cache_days: u32,
+    /// 共享文件夹（SMB/NAS 挂载目录，backend 为 local 时设置后视频复制到该目录，供其他设备回放）
+    #[serde(default)]
+    pub shared_dir: String,
+    /// 客户端加密口令（为空不加密）：视频加密后再同步，各设备需设置相同的口令
+    #[serde(default)]
+    pub encryption_passphrase: String,
+}
+
+impl MediaStoreSettings {
+    /// 是否同步到同一个位置（只改本地缓存天数时视频不需要迁移）
+    pub fn same_target(&self, other: &Self) -> bool {
+        let shared_dir = |settings: &Self| settings.shared_dir.trim().to_string();
+        self.backend == other.backend
+            && shared_dir(self) == shared_dir(other)
+            && self.s3 == other.s3
+            && self.webdav == other.webdav
+            && self.encryption_passphrase == other.encryption_passphrase
+    }
+}
+
+impl Default for MediaStoreSettings {
+    fn default() -> Self {
+        Self {
+            backend: MediaBackend::Local,
+            s3: None,
+            webdav: None,
+            local_cache_days: 0,
+            shared_dir: String::new(),
+            encryption_passphrase: String::new(),
+        }
+    }
+}
+
+/// WebDAV 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebDavConfig {
+    /// 目录地址，如 https://dav.example.com/remote.php/dav/files/user/screen-analyzer
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// 冷存储归档设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSettings {
+    /// 启用后，过期会话的视频移到归档目标并保留会话记录，而不是直接删除
+    pub enabled: bool,
+    /// 归档目标
+    pub target: MediaBackend,
+    /// 本地归档目录（target 为 local 时使用）
+    #[serde(default)]
+    pub local_dir: String,
+    /// S3 配置（target 为 s3 时使用）
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+    /// WebDAV 配置（target 为 webdav 时使用）
+    #[serde(default)]
+    pub webdav: Option<WebDavConfig>,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: MediaBackend::Local,
+            local_dir: String::new(),
+            s3: None,
+            webdav: None,
+        }
+    }
+}
+
+/// S3 兼容对象存储配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Config {
+    /// Endpoint，如 https://s3.us-east-1.amazonaws.com 或 http://192.168.1.10:9000
+    pub endpoint: String,
+    /// 区域，如 us-east-1（MinIO 等自建服务一般填 us-east-1，R2 填 auto）
+    pub region: String,
+    /// Bucket 名称
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// 对象 Key 前缀
+    #[serde(default)]
+    pub prefix: String,
+    /// 使用路径风格访问（endpoint/bucket/key），MinIO 等自建服务通常需要开启
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+/// 长会话分块分析设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisChunkSettings {
+    /// 会话超过单块时长时是否自动分块分析
+    pub enabled: bool,
+    /// 单块最长时长（分钟，按实际时间计算）
+    pub max_chunk_minutes: u32,
+}
+
+impl Default for AnalysisChunkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_chunk_minutes: 20,
+        }
+    }
+}
+
+/// 夜间分析设置：会话照常录制并生成视频，LLM 分析只在每天的分析时段内进行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NightAnalysisSettings {
+    /// 是否把分析推迟到分析时段
+    pub enabled: bool,
+    /// 分析时段开始的小时（0-23，本地时间）
+    pub start_hour: u8,
+    /// 分析时段结束的小时（0-23，不含；小于开始小时表示跨越午夜）
+    pub end_hour: u8,
+}
+
+impl Default for NightAnalysisSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_hour: 1,
+            end_hour: 6,
+        }
+    }
+}
+
+/// 每日安静时段：照常截屏录制，但不调用 LLM 分析，直接生成一张固定的休息卡片
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietZone {
+    /// 名称（作为卡片标题，如“午休”）
+    pub name: String,
+    /// 开始时间（HH:MM，本地时间）
+    pub start: String,
+    /// 结束时间（HH:MM，不含；早于开始时间表示跨越午夜）
+    pub end: String,
+}
+
+/// 会话处理流程中的步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStep {
+    /// 启发式标注：按浏览器标签页、音频活动、子类别同义词和应用画像修正卡片，低置信度卡片进入复核
+    HeuristicTagger,
+    /// LLM 分析：分段并生成时间线卡片
+    LlmAnalyzer,
+    /// 同步到 Notion
+    NotionSync,
+    /// 把会话和卡片 POST 到 webhook 地址
+    Webhook,
+}
+
+/// 流程中的一个步骤
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStepConfig {
+    pub step: PipelineStep,
+    pub enabled: bool,
+}
+
+/// 处理流程方案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineProfile {
+    /// 方案名称
+    pub name: String,
+    /// 按顺序执行的步骤
+    pub steps: Vec<PipelineStepConfig>,
+}
+
+impl Default for PipelineProfile {
+    fn default() -> Self {
+        let step = |step, enabled| PipelineStepConfig { step, enabled };
+        Self {
+            name: "默认".to_string(),
+            steps: vec![
+                step(PipelineStep::LlmAnalyzer, true),
+                step(PipelineStep::HeuristicTagger, true),
+                step(PipelineStep::NotionSync, true),
+                step(PipelineStep::Webhook, false),
+            ],
+        }
+    }
+}
+
+/// 会话处理流程设置：可保存多个方案，按当前方案处理新会话
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineSettings {
+    /// 当前使用的方案名称
+    pub active_profile: String,
+    pub profiles: Vec<PipelineProfile>,
+    /// webhook 步骤推送的地址
+    #[serde(default)]
+    pub webhook_url: String,
+    /// webhook 签名密钥（为空时不签名）
+    #[serde(default)]
+    pub webhook_secret: String,
+}
+
+impl Default for PipelineSettings {
+    fn default() -> Self {
+        let profile = PipelineProfile::default();
+        Self {
+            active_profile: profile.name.clone(),
+            profiles: vec![profile],
+            webhook_url: String::new(),
+            webhook_secret: String::new(),
+        }
+    }
+}
+
+/// 未分析视频的分析顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisOrder {
+    /// 从最早的视频开始
+    Oldest,
+    /// 从最新的视频开始
+    Newest,
+    /// 先分析今天的视频（按时间顺序），再从最早的积压视频开始
+    TodayFirst,
+}
+
+/// 分析队列设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisQueueSettings {
+    /// 分析顺序
+    pub order: AnalysisOrder,
+    /// 每轮扫描中每个历史日期最多分析的视频数（0 表示不限，今天的视频不受限制）
+    #[serde(default)]
+    pub per_day_limit: u32,
+    /// 同时分析的视频数（不超过性能设置中的上限，API 配额充足时可调大以加快积压视频的分析）
+    #[serde(default = "default_analysis_workers")]
+    pub workers: u8,
+}
+
+fn default_analysis_workers() -> u8 {
+    1
+}
+
+impl Default for AnalysisQueueSettings {
+    fn default() -> Self {
+        Self {
+            order: AnalysisOrder::TodayFirst,
+            per_day_limit: 0,
+            workers: default_analysis_workers(),
+        }
+    }
+}
+
+/// 性能设置：并发和队列上限，保存后立即生效（事件总线容量除外）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceSettings {
+    /// 同时编码的会话视频数
+    pub max_concurrent_encodes: u8,
+    /// 同时分析的视频数上限，分析队列的并发分析数不超过这个值
+    pub max_concurrent_llm_calls: u8,
+    /// 数据库批量写入时每批的记录数
+    pub db_batch_size: u32,
+    /// 事件总线每个订阅通道的缓冲事件数（全局通道在重启后生效）
+    pub event_bus_capacity: u32,
+}
+
+impl Default for PerformanceSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_encodes: 4,
+            max_concurrent_llm_calls: 4,
+            db_batch_size: 1000,
+            event_bus_capacity: 1000,
+        }
+    }
+}
+
+/// 实时状态推送接口设置（供直播叠加层、Stream Deck 等第三方小部件订阅）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveApiSettings {
+    /// 是否开启本地 WebSocket 推送
+    pub enabled: bool,
+    /// 监听端口（默认只监听 127.0.0.1）
+    pub port: u16,
+    /// 访问令牌，连接时通过 ?token= 或 Authorization: Bearer 提供
+    #[serde(default)]
+    pub token: String,
+    /// 提供只读网页（/web，当天时间线、总结和统计），开启后监听所有网卡供同一网络的手机访问
+    #[serde(default)]
+    pub web_ui: bool,
+    /// 提供 Prometheus 运行指标（/metrics），开启后同样监听所有网卡供监控服务器抓取
+    #[serde(default)]
+    pub metrics: bool,
+}
+
+impl Default for LiveApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17891,
+            token: String::new(),
+            web_ui: false,
+            metrics: false,
+        }
+    }
+}
+
+/// 自动化钩子的触发事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// 会话结束（截屏时间段结束，尚未分析）
+    SessionCompleted,
+    /// 会话分析完成
+    AnalysisCompleted,
+    /// 每日总结生成
+    DaySummaryReady,
+    /// 存储清理完成
+    StorageCleaned,
+}
+
+/// 自动化钩子的执行方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    /// shell 命令（Windows 下为 cmd /C）
+    Shell,
+    /// Rhai 脚本
+    Rhai,
+}
+
+/// 自动化钩子
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationHook {
+    /// 名称（仅用于日志和界面显示）
+    #[serde(default)]
+    pub name: String,
+    /// 是否启用
+    pub enabled: bool,
+    /// 触发事件
+    pub event: HookEvent,
+    /// 执行方式
+    pub kind: HookKind,
+    /// shell 命令或 Rhai 脚本内容
+    pub command: String,
+    /// 超时时间（秒）
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    30
+}
+
+/// 子类别同义词：aliases 中的写法（不区分大小写、空格和连字符）统一为 canonical
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubcategorySynonym {
+    pub canonical: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// 默认的子类别同义词表
+pub fn default_subcategory_synonyms() -> Vec<SubcategorySynonym> {
+    let synonym = |canonical: &str, aliases: &[&str]| SubcategorySynonym {
+        canonical: canonical.to_string(),
+        aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+    };
+    vec![
+        synonym(
+            "Coding",
+            &[
+                "programming",
+                "development",
+                "编程",
+                "写代码",
+                "程序开发",
+                "开发",
+            ],
+        ),
+        synonym(
+            "Meeting",
+            &["meetings", "video call", "会议", "开会", "视频会议"],
+        ),
+        synonym("Documentation", &["docs", "writing docs", "文档", "写文档"]),
+        synonym("Communication", &["chat", "messaging", "沟通", "聊天"]),
+        synonym("Email", &["e-mail", "mail", "邮件"]),
+        synonym("Research", &["reading", "learning", "调研", "学习"]),
+        synonym("Browsing", &["web browsing", "浏览网页"]),
+        synonym("Video", &["watching videos", "youtube", "看视频"]),
+    ]
+}
+
+/// 应用画像规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppProfileRule {
+    /// 应用名或域名（不区分大小写的子串匹配）
+    pub pattern: String,
+    /// 分类
+    pub kind: AppProfileKind,
+}
+
+/// 应用画像分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppProfileKind {
+    /// 始终高效
+    Productive,
+    /// 始终干扰
+    Distraction,
+}
+
+/// 按类别的保留规则（匹配会话的主要类别，覆盖全局保留天数）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryRetentionRule {
+    /// 活动类别（如 work、entertainment，不区分大小写）
+    pub category: String,
+    /// 保留天数
+    pub retention_days: i64,
 }
 
 /// 日志设置
@@ -66,6 +1213,56 @@ pub struct CaptureSettings {
     pub detect_black_screen: bool,
     /// 黑屏检测阈值(0-255)
     pub black_screen_threshold: u8,
+    /// 检测到黑屏时的处理方式
+    #[serde(default)]
+    pub black_screen_mode: BlackScreenMode,
+    /// 是否对偏暗画面做曝光校正（直方图拉伸 + gamma 校正）
+    #[serde(default)]
+    pub normalize_exposure: bool,
+    /// 平均亮度低于该值(0-255)时才做曝光校正
+    #[serde(default = "default_exposure_threshold")]
+    pub exposure_threshold: u8,
+    /// 是否按画面内容自适应选择图片质量（在 min_image_quality 与 image_quality 之间）
+    #[serde(default)]
+    pub adaptive_quality: bool,
+    /// 自适应质量的下限(1-100)
+    #[serde(default = "default_min_image_quality")]
+    pub min_image_quality: u8,
+    /// 是否遮盖截图中本应用的窗口（Windows/Linux，避免视频中出现套娃画面）
+    #[serde(default = "default_exclude_own_window")]
+    pub exclude_own_window: bool,
+    /// 连续多少分钟没有截图（黑屏、锁屏或暂停）时提前结束当前会话（0 表示不提前结束）
+    #[serde(default = "default_blank_session_minutes")]
+    pub blank_session_minutes: u32,
+    /// 是否按画面内容逐帧选择分辨率（文字多的画面用 resolution，视频等画面用 media_resolution）
+    #[serde(default)]
+    pub adaptive_resolution: bool,
+    /// 自适应分辨率时视频、图片等文字少的画面使用的分辨率
+    #[serde(default = "default_media_resolution")]
+    pub media_resolution: CaptureResolution,
+    /// 标记为私人屏幕的显示器 ID：不截取、不合成到截图中，也不会出现在视频里
+    #[serde(default)]
+    pub personal_display_id: Option<u32>,
+}
+
+fn default_min_image_quality() -> u8 {
+    60
+}
+
+fn default_exclude_own_window() -> bool {
+    true
+}
+
+fn default_blank_session_minutes() -> u32 {
+    5
+}
+
+fn default_media_resolution() -> CaptureResolution {
+    CaptureResolution::HD
+}
+
+fn default_exposure_threshold() -> u8 {
+    80
 }
 
 impl Default for CaptureSettings {
@@ -75,14 +1272,39 @@ impl Default for CaptureSettings {
             image_quality: 85,
             detect_black_screen: true,
             black_screen_threshold: 5,
+            black_screen_mode: BlackScreenMode::default(),
+            normalize_exposure: false,
+            exposure_threshold: default_exposure_threshold(),
+            adaptive_quality: false,
+            min_image_quality: default_min_image_quality(),
+            exclude_own_window: default_exclude_own_window(),
+            blank_session_minutes: default_blank_session_minutes(),
+            adaptive_resolution: false,
+            media_resolution: default_media_resolution(),
+            personal_display_id: None,
         }
     }
 }
 
+/// 黑屏帧的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlackScreenMode {
+    /// 跳过不保存（视频中会缺少这段时间）
+    #[default]
+    Skip,
+    /// 保存一张低质量的纯黑占位帧，保持视频时间连续
+    Placeholder,
+    /// 重复上一张正常截图，保持视频时间连续
+    RepeatLast,
+}
+
 /// 截屏分辨率枚举
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CaptureResolution {
+    #[serde(rename = "720p")]
+    HD, // 1280x720
     #[serde(rename = "1080p")]
     FHD, // 1920x1080 (Full HD)
     #[serde(rename = "2k")]
@@ -96,6 +1318,7 @@ pub enum CaptureResolution {
 impl CaptureResolution {
     pub fn dimensions(&self) -> Option<(u32, u32)> {
         match self {
+            Self::HD => Some((1280, 720)),
             Self::FHD => Some((1920, 1080)),
             Self::QHD => Some((2560, 1440)),
             Self::UHD => Some((3840, 2160)),
@@ -105,6 +1328,7 @@ impl CaptureResolution {
 
     pub fn display_name(&self) -> &str {
         match self {
+            Self::HD => "720P (1280×720)",
             Self::FHD => "1080P (1920×1080)",
             Self::QHD => "2K (2560×1440)",
             Self::UHD => "4K (3840×2160)",
@@ -124,6 +1348,12 @@ pub struct VideoSettings {
     pub quality: u8,
     /// 是否添加时间戳
     pub add_timestamp: bool,
+    /// 智能变速（画面静止的片段加速更多）
+    #[serde(default)]
+    pub smart_speed: bool,
+    /// 多屏画面的排列方式
+    #[serde(default)]
+    pub layout: crate::video::VideoLayout,
 }
 
 impl Default for VideoSettings {
@@ -133,10 +1363,27 @@ impl Default for VideoSettings {
             speed_multiplier: 8.0,
             quality: 23,
             add_timestamp: true,
+            smart_speed: false,
+            layout: crate::video::VideoLayout::Native,
         }
     }
 }
 
+/// 单台设备的设置覆盖
+///
+/// 多台设备共用一份配置时（例如配置与共享 MariaDB 放在一起），截屏间隔、视频和截屏设置按设备名分别保存；
+/// 未覆盖的项继承全局默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    /// 截屏间隔（秒）
+    pub capture_interval: Option<u64>,
+    /// 视频配置
+    pub video_config: Option<VideoSettings>,
+    /// 截屏配置
+    pub capture_settings: Option<CaptureSettings>,
+}
+
 /// 持久化的应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistedAppConfig {
@@ -162,6 +1409,138 @@ pub struct PersistedAppConfig {
     pub database_config: Option<DatabaseConfig>,
     /// Notion 配置
     pub notion_config: Option<NotionConfig>,
+    /// 多实例配置
+    #[serde(default)]
+    pub instance_settings: Option<InstanceSettings>,
+    /// RAG 向量库导出配置
+    #[serde(default)]
+    pub rag_export_config: Option<RagExportConfig>,
+    /// 休息提醒配置
+    #[serde(default)]
+    pub break_reminder_settings: Option<BreakReminderSettings>,
+    /// 应用画像规则（覆盖 LLM 类别）
+    #[serde(default)]
+    pub app_profiles: Option<Vec<AppProfileRule>>,
+    /// LLM 调试归档设置
+    #[serde(default)]
+    pub llm_debug_archive: Option<LlmDebugArchiveSettings>,
+    /// Qwen 视频上传配置
+    #[serde(default)]
+    pub video_upload_config: Option<VideoUploadConfig>,
+    /// 长会话分块分析设置
+    #[serde(default)]
+    pub analysis_chunk_settings: Option<AnalysisChunkSettings>,
+    /// 按类别的保留规则
+    #[serde(default)]
+    pub retention_rules: Option<Vec<CategoryRetentionRule>>,
+    /// 冷存储归档设置
+    #[serde(default)]
+    pub archive_settings: Option<ArchiveSettings>,
+    /// 媒体存储设置
+    #[serde(default)]
+    pub media_store: Option<MediaStoreSettings>,
+    /// 分析队列设置（顺序、每日预算）
+    #[serde(default)]
+    pub analysis_queue: Option<AnalysisQueueSettings>,
+    /// 会话结束后暂缓分析的分钟数（期间可选择不分析，0 表示立即分析）
+    #[serde(default)]
+    pub analysis_hold_minutes: Option<u32>,
+    /// 卡片置信度低于该值时进入复核队列（0 表示不复核）
+    #[serde(default)]
+    pub review_confidence_threshold: Option<f64>,
+    /// 每日专注目标（分钟），用于计算连续达标天数
+    #[serde(default)]
+    pub daily_focus_goal_minutes: Option<u32>,
+    /// 会话分析完成后自动为会议卡片生成会议纪要
+    #[serde(default)]
+    pub meeting_notes_enabled: Option<bool>,
+    /// 会话分析完成后把时间线卡片作为字幕轨封装进会话视频
+    #[serde(default)]
+    pub embed_subtitles: Option<bool>,
+    /// 会话分析完成后为每张卡片截取几秒预览短片（时间线悬浮时播放）
+    #[serde(default)]
+    pub card_previews: Option<bool>,
+    /// 实时状态推送接口设置
+    #[serde(default)]
+    pub live_api: Option<LiveApiSettings>,
+    /// 自动化钩子（事件触发的 shell 命令或 Rhai 脚本）
+    #[serde(default)]
+    pub automation_hooks: Option<Vec<AutomationHook>>,
+    /// 专注模式设置
+    #[serde(default)]
+    pub focus_mode: Option<FocusModeSettings>,
+    /// 关注关键词
+    #[serde(default)]
+    pub watch_keywords: Option<Vec<String>>,
+    /// 每日安静时段
+    #[serde(default)]
+    pub quiet_zones: Option<Vec<QuietZone>>,
+    /// 屏幕文字时间线设置
+    #[serde(default)]
+    pub text_timeline: Option<TextTimelineSettings>,
+    /// 实时活动估计设置
+    #[serde(default)]
+    pub live_activity: Option<LiveActivitySettings>,
+    /// 剪贴板记录设置
+    #[serde(default)]
+    pub clipboard_log: Option<ClipboardLogSettings>,
+    /// 是否检测音频活动
+    #[serde(default)]
+    pub audio_presence_enabled: Option<bool>,
+    /// 存储配额设置
+    #[serde(default)]
+    pub storage_quota: Option<StorageQuotaSettings>,
+    /// 旧视频转码设置
+    #[serde(default)]
+    pub video_reencode: Option<VideoReencodeSettings>,
+    /// 截图保留设置
+    #[serde(default)]
+    pub frame_retention: Option<FrameRetentionSettings>,
+    /// 会话窗口的对齐方式
+    #[serde(default)]
+    pub session_alignment: Option<SessionAlignment>,
+    /// 自适应会话设置
+    #[serde(default)]
+    pub adaptive_session: Option<AdaptiveSessionSettings>,
+    /// 会话标题的选取方式
+    #[serde(default)]
+    pub session_title_strategy: Option<SessionTitleStrategy>,
+    /// 夜间分析设置
+    #[serde(default)]
+    pub night_analysis: Option<NightAnalysisSettings>,
+    /// 本地模式
+    #[serde(default)]
+    pub local_only: Option<bool>,
+    /// 会话处理流程
+    #[serde(default)]
+    pub pipeline: Option<PipelineSettings>,
+    /// 子类别同义词表
+    #[serde(default)]
+    pub subcategory_synonyms: Option<Vec<SubcategorySynonym>>,
+    /// 暂停截屏提醒设置
+    #[serde(default)]
+    pub pause_reminder_settings: Option<PauseReminderSettings>,
+    /// 未分析视频积压提醒设置
+    #[serde(default)]
+    pub analysis_backlog: Option<AnalysisBacklogSettings>,
+    /// 隐私过滤设置
+    #[serde(default)]
+    pub privacy_filter: Option<PrivacyFilterSettings>,
+    /// 计时服务导出设置
+    #[serde(default)]
+    pub time_tracking: Option<TimeTrackingSettings>,
+    /// 性能设置
+    #[serde(default)]
+    pub performance: Option<PerformanceSettings>,
+    /// 检查新版本设置
+    #[serde(default)]
+    pub update_check: Option<UpdateCheckSettings>,
+    /// 离开模式状态
+    #[serde(default)]
+    pub away_mode: Option<AwayModeState>,
+    /// 按设备名保存的设置覆盖（截屏间隔、视频和截屏设置）
+    #[serde(default)]
+    pub device_settings: Option<BTreeMap<String, DeviceSettings>>,
 }
 
 impl Default for PersistedAppConfig {
@@ -178,6 +1557,50 @@ impl Default for PersistedAppConfig {
             logger_settings: Some(LoggerSettings::default()),
             database_config: None,
             notion_config: Some(NotionConfig::default()),
+            instance_settings: Some(InstanceSettings::default()),
+            rag_export_config: Some(RagExportConfig::default()),
+            break_reminder_settings: Some(BreakReminderSettings::default()),
+            app_profiles: Some(Vec::new()),
+            llm_debug_archive: Some(LlmDebugArchiveSettings::default()),
+            video_upload_config: Some(VideoUploadConfig::default()),
+            analysis_chunk_settings: Some(AnalysisChunkSettings::default()),
+            retention_rules: Some(Vec::new()),
+            archive_settings: Some(ArchiveSettings::default()),
+            media_store: Some(MediaStoreSettings::default()),
+            analysis_queue: Some(AnalysisQueueSettings::default()),
+            analysis_hold_minutes: Some(0),
+            review_confidence_threshold: Some(0.6),
+            daily_focus_goal_minutes: Some(240),
+            meeting_notes_enabled: Some(false),
+            embed_subtitles: Some(false),
+            card_previews: Some(true),
+            live_api: None,
+            automation_hooks: Some(Vec::new()),
+            focus_mode: Some(FocusModeSettings::default()),
+            watch_keywords: Some(Vec::new()),
+            quiet_zones: Some(Vec::new()),
+            text_timeline: Some(TextTimelineSettings::default()),
+            live_activity: Some(LiveActivitySettings::default()),
+            clipboard_log: Some(ClipboardLogSettings::default()),
+            audio_presence_enabled: Some(false),
+            storage_quota: Some(StorageQuotaSettings::default()),
+            video_reencode: Some(VideoReencodeSettings::default()),
+            frame_retention: Some(FrameRetentionSettings::default()),
+            session_alignment: Some(SessionAlignment::default()),
+            adaptive_session: Some(AdaptiveSessionSettings::default()),
+            session_title_strategy: Some(SessionTitleStrategy::default()),
+            night_analysis: Some(NightAnalysisSettings::default()),
+            local_only: Some(false),
+            pipeline: Some(PipelineSettings::default()),
+            subcategory_synonyms: Some(default_subcategory_synonyms()),
+            pause_reminder_settings: Some(PauseReminderSettings::default()),
+            analysis_backlog: Some(AnalysisBacklogSettings::default()),
+            privacy_filter: Some(PrivacyFilterSettings::default()),
+            time_tracking: Some(TimeTrackingSettings::default()),
+            performance: Some(PerformanceSettings::default()),
+            update_check: Some(UpdateCheckSettings::default()),
+            away_mode: None,
+            device_settings: Some(BTreeMap::new()),
         }
     }
 }
@@ -197,6 +1620,9 @@ pub struct LLMProviderConfig {
     pub auth_token: String,
     #[serde(default)]
     pub codex_config: Option<serde_json::Value>,
+    /// 外部插件的配置（原样传给插件）
+    #[serde(default)]
+    pub plugin_config: Option<serde_json::Value>,
 }
 
 /// UI设置
@@ -268,6 +1694,26 @@ pub struct SystemStatus {
     pub cpu_usage: f32,
     /// 内存占用（MB）
     pub memory_usage: f32,
+    /// 只读模式原因（None 表示可正常截屏和分析）
+    pub read_only_reason: Option<String>,
+    /// 写盘前被丢弃的截图数（磁盘过慢、写盘队列已满等）
+    #[serde(default)]
+    pub dropped_frames: u64,
+    /// LLM 服务商健康状态（最近一小时的延迟和错误率）
+    #[serde(default)]
+    pub provider_health: Vec<crate::domains::provider_health::ProviderHealth>,
+    /// 存储接近配额时的画质降级状态
+    #[serde(default)]
+    pub quality_degrade: crate::domains::storage_quota::QualityDegradeStatus,
+    /// MariaDB 故障切换状态（未使用 MariaDB 或未启用本地队列时为 None）
+    #[serde(default)]
+    pub database_failover: Option<crate::storage::repository::failover::FailoverStatus>,
+    /// 未分析视频积压（未超过提醒阈值时为 None）
+    #[serde(skip_deserializing)]
+    pub analysis_backlog: Option<crate::domains::analysis_backlog::AnalysisBacklogStatus>,
+    /// LLM 服务商额度耗尽状态（额度正常时为 None）
+    #[serde(skip_deserializing)]
+    pub llm_quota: Option<crate::domains::analysis::LlmQuotaStatus>,
 }
 
 impl Default for SystemStatus {
@@ -282,6 +1728,13 @@ impl Default for SystemStatus {
             last_error: None,
             cpu_usage: 0.0,
             memory_usage: 0.0,
+            read_only_reason: None,
+            dropped_frames: 0,
+            provider_health: Vec::new(),
+            quality_degrade: Default::default(),
+            database_failover: None,
+            analysis_backlog: None,
+            llm_quota: None,
         }
     }
 }
@@ -519,14 +1972,28 @@ impl Default for NotionConfig {
 pub struct NotionSyncOptions {
     /// 同步会话记录
     pub sync_sessions: bool,
-    /// 同步视频文件（小于5MB直接上传，大于5MB仅上传链接）
+    /// 同步视频文件（不超过上传上限时上传到 Notion，超过时只添加本地文件链接）
     pub sync_videos: bool,
     /// 同步每日总结
     pub sync_daily_summary: bool,
     /// 同步关键截图
     pub sync_screenshots: bool,
-    /// 视频大小限制（MB）
+    /// 视频大小限制（MB，0 表示只受 Notion 工作区上传上限限制）
     pub video_size_limit_mb: u32,
+    /// 会话同步方式
+    #[serde(default)]
+    pub mode: NotionSyncMode,
+}
+
+/// Notion 会话同步方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotionSyncMode {
+    /// 每个会话一个页面
+    #[default]
+    PerSession,
+    /// 每天一个汇总页面，会话作为折叠块追加
+    DailyDigest,
 }
 
 impl Default for NotionSyncOptions {
@@ -536,7 +2003,51 @@ impl Default for NotionSyncOptions {
             sync_videos: false,        // 默认不同步视频（文件较大）
             sync_daily_summary: false, // 默认不同步每日总结（Notion 会自动总结）
             sync_screenshots: true,
-            video_size_limit_mb: 5,
+            video_size_limit_mb: 0,
+            mode: NotionSyncMode::PerSession,
+        }
+    }
+}
+
+/// RAG 向量库导出配置
+///
+/// 定期将时间线卡片的摘要和元数据写入本地 Qdrant 集合，供外部个人助理工具检索活动历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagExportConfig {
+    /// 是否启用定期导出
+    pub enabled: bool,
+    /// Qdrant 服务地址
+    pub qdrant_url: String,
+    /// Qdrant API Key（本地部署通常为空）
+    #[serde(default)]
+    pub qdrant_api_key: Option<String>,
+    /// 集合名称
+    pub collection: String,
+    /// Embedding 接口地址（OpenAI 兼容的 /embeddings 端点）
+    pub embedding_base_url: String,
+    /// Embedding 接口 API Key
+    pub embedding_api_key: String,
+    /// Embedding 模型
+    pub embedding_model: String,
+    /// 导出间隔（分钟）
+    pub interval_minutes: u64,
+    /// 每批导出的卡片数
+    pub batch_size: i64,
+}
+
+impl Default for RagExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            qdrant_url: "http://localhost:6333".to_string(),
+            qdrant_api_key: None,
+            collection: "screen_activity".to_string(),
+            embedding_base_url: "https://dashscope.aliyuncs.com/compatible-mode/v1/embeddings"
+                .to_string(),
+            embedding_api_key: String::new(),
+            embedding_model: "text-embedding-v3".to_string(),
+            interval_minutes: 60,
+            batch_size: 10,
         }
     }
 }
@@ -3,16 +3,260 @@
 
 use anyhow::{anyhow, Result};
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
-use crate::models::{NotionConfig, Session};
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::models::{NotionConfig, NotionSyncMode, Session};
 
 const NOTION_API_VERSION: &str = "2022-06-28";
 const NOTION_API_BASE: &str = "https://api.notion.com/v1";
 
+/// 超过该大小使用 multi-part 模式上传
+const MULTI_PART_THRESHOLD: u64 = 20 * 1024 * 1024; // 20 MB
+/// multi-part 模式每个分块的大小（Notion 要求 5~20 MB，最后一块除外）
+const PART_SIZE: u64 = 10 * 1024 * 1024; // 10 MB
+
+/// 视频上传进度（通过 notion-upload-progress 事件推送到前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotionUploadProgress {
+    pub file_name: String,
+    pub uploaded_bytes: u64,
+    pub total_bytes: u64,
+    /// 已完成的分块数
+    pub part: u32,
+    pub total_parts: u32,
+}
+
+/// 会话同步时附加的分析字段（由时间线卡片计算）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotionSessionDetails {
+    /// 时长最多的子类别
+    pub subcategory: Option<String>,
+    /// 生产力评分（0-100）
+    pub productivity_score: Option<f64>,
+    /// 专注评分（0-100）
+    pub focus_score: Option<f64>,
+}
+
+/// 活动类别的中文名（与数据库中“类别”“标签”的选项一致）
+fn category_label(category: &crate::models::ActivityCategory) -> &'static str {
+    match category {
+        crate::models::ActivityCategory::Work => "工作",
+        crate::models::ActivityCategory::Communication => "沟通",
+        crate::models::ActivityCategory::Learning => "学习",
+        crate::models::ActivityCategory::Personal => "个人",
+        crate::models::ActivityCategory::Idle => "空闲",
+        crate::models::ActivityCategory::Other => "其他",
+    }
+}
+
+/// 记录发往 Notion 的页面内容（数据外发记录）
+fn record_text_egress(payload: &Value, session_id: Option<i64>) {
+    let bytes = serde_json::to_vec(payload)
+        .map(|body| body.len())
+        .unwrap_or(0);
+    egress::record(
+        EgressChannel::Notion,
+        NOTION_API_BASE,
+        EgressContent::Text,
+        bytes as u64,
+        session_id,
+    );
+}
+
+/// 本地文件路径转为 file:// 链接
+fn file_url(path: &Path) -> String {
+    let local_path = path.to_string_lossy().replace('\\', "/");
+    if local_path.starts_with('/') {
+        format!("file://{}", local_path)
+    } else {
+        format!("file:///{}", local_path)
+    }
+}
+
+/// 相邻两次请求的最小间隔（Notion API 平均每秒最多 3 次请求）
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(350);
+/// 被限流且响应没有 Retry-After 时的等待秒数
+const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
+/// rich_text 单段文本的长度上限
+const MAX_TEXT_CHARS: usize = 2000;
+/// 每日汇总页面的类型
+const DIGEST_PAGE_TYPE: &str = "每日汇总";
+
+/// 每日汇总页面的本地ID（用于查找当天的页面）
+fn digest_local_id(date: &str) -> String {
+    format!("digest-{}", date)
+}
+
+/// 汇总页面中标记会话折叠块的后缀
+fn digest_marker(session_id: i64) -> String {
+    format!("#{}", session_id)
+}
+
+/// 截断到 rich_text 允许的长度
+fn truncate_text(text: &str) -> String {
+    text.chars().take(MAX_TEXT_CHARS).collect()
+}
+
+fn paragraph(text: &str) -> Value {
+    json!({
+        "object": "block",
+        "type": "paragraph",
+        "paragraph": {
+            "rich_text": [{ "type": "text", "text": { "content": truncate_text(text) } }]
+        }
+    })
+}
+
+/// 每日汇总中一个会话的折叠块：标题行为时间、标题和时长，展开后为总结和分析字段
+fn digest_toggle_block(session: &Session, details: &NotionSessionDetails) -> Value {
+    let minutes = (session.end_time - session.start_time).num_minutes();
+    let heading = format!(
+        "{}-{} {}（{} 分钟） ",
+        session.start_time.format("%H:%M"),
+        session.end_time.format("%H:%M"),
+        session.title,
+        minutes
+    );
+
+    let mut facts = Vec::new();
+    if let Some(subcategory) = details.subcategory.as_deref() {
+        facts.push(format!("子类别：{}", subcategory));
+    }
+    if let Some(score) = details.productivity_score {
+        facts.push(format!("生产力 {}", score));
+    }
+    if let Some(score) = details.focus_score {
+        facts.push(format!("专注 {}", score));
+    }
+
+    let mut children = Vec::new();
+    if !session.summary.trim().is_empty() {
+        children.push(paragraph(&session.summary));
+    }
+    if !facts.is_empty() {
+        children.push(paragraph(&facts.join(" · ")));
+    }
+    if let Some(video_path) = session.video_path.as_deref().filter(|p| !p.is_empty()) {
+        children.push(json!({
+            "object": "block",
+            "type": "paragraph",
+            "paragraph": {
+                "rich_text": [
+                    { "type": "text", "text": { "content": "视频：" } },
+                    {
+                        "type": "text",
+                        "text": {
+                            "content": video_path,
+                            "link": { "url": file_url(Path::new(video_path)) }
+                        }
+                    }
+                ]
+            }
+        }));
+    }
+
+    // Notion 只接受 http(s) 链接，深度链接以文本形式给出，复制到浏览器地址栏即可打开
+    if let Some(session_id) = session.id {
+        children.push(paragraph(&format!(
+            "在应用中打开：{}",
+            crate::domains::deep_link::session_link(session_id, None)
+        )));
+    }
+
+    json!({
+        "object": "block",
+        "type": "toggle",
+        "toggle": {
+            "rich_text": [
+                { "type": "text", "text": { "content": truncate_text(&heading) } },
+                {
+                    "type": "text",
+                    "text": { "content": digest_marker(session.id.unwrap_or_default()) },
+                    "annotations": { "code": true, "color": "gray" }
+                }
+            ],
+            "children": children
+        }
+    })
+}
+
+/// 在汇总页面的子块中查找会话的折叠块
+fn find_digest_block(blocks: &[Value], session_id: i64) -> Option<String> {
+    let marker = digest_marker(session_id);
+    blocks
+        .iter()
+        .filter(|block| block["type"].as_str() == Some("toggle"))
+        .find(|block| {
+            block["toggle"]["rich_text"]
+                .as_array()
+                .and_then(|parts| parts.last())
+                .and_then(|part| part["plain_text"].as_str())
+                .is_some_and(|text| text.trim() == marker)
+        })
+        .and_then(|block| block["id"].as_str().map(str::to_string))
+}
+
+/// 上传进度回调
+pub type UploadProgressListener = Arc<dyn Fn(NotionUploadProgress) + Send + Sync>;
+
+/// 文件上传方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UploadPlan {
+    /// 一次上传
+    SinglePart,
+    /// 分块上传
+    MultiPart { parts: u32 },
+}
+
+/// 按文件大小选择上传方式，超过上限时返回 None
+fn upload_plan(file_size: u64, limit: Option<u64>) -> Option<UploadPlan> {
+    if limit.is_some_and(|limit| file_size > limit) {
+        return None;
+    }
+    if file_size > MULTI_PART_THRESHOLD {
+        Some(UploadPlan::MultiPart {
+            parts: file_size.div_ceil(PART_SIZE) as u32,
+        })
+    } else {
+        Some(UploadPlan::SinglePart)
+    }
+}
+
+/// 上传上限（字节）：工作区上限和设置的大小限制（0 表示不限制）取较小值
+fn effective_upload_limit(workspace_limit: Option<u64>, limit_mb: u32) -> Option<u64> {
+    let configured = (limit_mb > 0).then(|| limit_mb as u64 * 1024 * 1024);
+    match (workspace_limit, configured) {
+        (Some(workspace), Some(configured)) => Some(workspace.min(configured)),
+        (workspace, configured) => workspace.or(configured),
+    }
+}
+
+/// 视频文件的 MIME 类型
+fn video_content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("webm") => "video/webm",
+        Some("mkv") => "video/x-matroska",
+        Some("avi") => "video/x-msvideo",
+        _ => "video/mp4",
+    }
+}
+
 /// 获取系统时区
 fn get_system_timezone() -> String {
     // 尝试获取系统时区
@@ -87,6 +331,12 @@ pub struct NotionPage {
 pub struct NotionClient {
     config: NotionConfig,
     client: Client,
+    /// 视频上传进度回调
+    progress_listener: Option<UploadProgressListener>,
+    /// 上次请求时间（限速）
+    last_request: Arc<Mutex<Instant>>,
+    /// 每日汇总页面缓存（日期 → 页面 ID），同时保证汇总更新串行执行
+    digest_pages: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl NotionClient {
@@ -97,11 +347,23 @@ impl NotionClient {
         }
         // database_id 可以为空（用于测试连接或搜索页面）
 
-        let client = Client::builder()
+        let client = crate::domains::local_only::http_client_builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()?;
 
-        Ok(Self { config, client })
+        Ok(Self {
+            config,
+            client,
+            progress_listener: None,
+            last_request: Arc::new(Mutex::new(Instant::now())),
+            digest_pages: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// 设置视频上传进度回调
+    pub fn with_progress_listener(mut self, listener: Option<UploadProgressListener>) -> Self {
+        self.progress_listener = listener;
+        self
     }
 
     /// 获取配置信息（用于调试）
@@ -137,7 +399,11 @@ impl NotionClient {
     }
 
     /// 同步会话到 Notion
-    pub async fn sync_session(&self, session: &Session) -> Result<String> {
+    pub async fn sync_session(
+        &self,
+        session: &Session,
+        details: &NotionSessionDetails,
+    ) -> Result<String> {
         if !self.config.enabled {
             return Ok("Notion 同步已禁用".to_string());
         }
@@ -146,10 +412,14 @@ impl NotionClient {
             return Ok("会话同步已禁用".to_string());
         }
 
+        if self.config.sync_options.mode == NotionSyncMode::DailyDigest {
+            return self.sync_session_digest(session, details).await;
+        }
+
         info!("开始同步会话 {:?} 到 Notion", session.id);
 
         // 构建 Notion 页面属性
-        let properties = self.build_session_properties(session)?;
+        let properties = self.build_session_properties(session, details)?;
 
         // 创建 Notion 页面
         let url = format!("{}/pages", NOTION_API_BASE);
@@ -173,6 +443,7 @@ impl NotionClient {
             error!("同步会话到 Notion 失败: {}", error_text);
             return Err(anyhow!("同步失败: {}", error_text));
         }
+        record_text_egress(&payload, session.id);
 
         let page: Value = response.json().await?;
         let page_id = page["id"].as_str().unwrap_or("unknown").to_string();
@@ -186,7 +457,10 @@ impl NotionClient {
         if self.config.sync_options.sync_videos {
             if let Some(video_path) = &session.video_path {
                 if !video_path.is_empty() {
-                    match self.add_video_to_page(&page_id, video_path).await {
+                    match self
+                        .add_video_to_page(&page_id, video_path, session.id)
+                        .await
+                    {
                         Ok(msg) => info!("视频添加成功: {}", msg),
                         Err(e) => warn!("视频添加失败: {}", e),
                     }
@@ -197,8 +471,198 @@ impl NotionClient {
         Ok(page_id)
     }
 
+    /// 限速发送请求，被限流（429）时按 Retry-After 等待后重试，最多 max_retries 次
+    async fn send_throttled(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            {
+                let mut last = self.last_request.lock().await;
+                let elapsed = last.elapsed();
+                if elapsed < MIN_REQUEST_INTERVAL {
+                    tokio::time::sleep(MIN_REQUEST_INTERVAL - elapsed).await;
+                }
+                *last = Instant::now();
+            }
+
+            let response = request
+                .try_clone()
+                .ok_or_else(|| anyhow!("请求无法重试"))?
+                .header("Authorization", format!("Bearer {}", self.config.api_token))
+                .header("Notion-Version", NOTION_API_VERSION)
+                .send()
+                .await?;
+            if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS
+                || attempt >= self.config.max_retries
+            {
+                return Ok(response);
+            }
+
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+            attempt += 1;
+            warn!(
+                "Notion API 限流，{} 秒后重试 ({}/{})",
+                retry_after, attempt, self.config.max_retries
+            );
+            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+        }
+    }
+
+    /// 发送请求并解析 JSON，失败时返回带上下文的错误
+    async fn request_json(&self, request: reqwest::RequestBuilder, context: &str) -> Result<Value> {
+        let response = self.send_throttled(request).await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("{}失败: {}", context, error_text);
+            return Err(anyhow!("{}失败: {}", context, error_text));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// 按本地ID查找数据库中的页面
+    async fn find_page_by_local_id(&self, local_id: &str) -> Result<Option<String>> {
+        let url = format!(
+            "{}/databases/{}/query",
+            NOTION_API_BASE, self.config.database_id
+        );
+        let filter = json!({
+            "filter": {
+                "property": "本地ID",
+                "rich_text": {
+                    "equals": local_id
+                }
+            }
+        });
+
+        let result = self
+            .request_json(self.client.post(&url).json(&filter), "查询页面")
+            .await?;
+        Ok(result["results"]
+            .as_array()
+            .and_then(|results| results.first())
+            .and_then(|page| page["id"].as_str())
+            .map(str::to_string))
+    }
+
+    /// 创建某天的汇总页面
+    async fn create_digest_page(&self, date: &str) -> Result<String> {
+        let payload = json!({
+            "parent": { "database_id": self.config.database_id },
+            "properties": {
+                "标题": {
+                    "title": [{ "text": { "content": format!("{} 工作记录", date) } }]
+                },
+                "日期": { "date": { "start": date } },
+                "本地ID": {
+                    "rich_text": [{ "text": { "content": digest_local_id(date) } }]
+                },
+                "类型": { "select": { "name": DIGEST_PAGE_TYPE } }
+            }
+        });
+
+        let page = self
+            .request_json(
+                self.client
+                    .post(format!("{}/pages", NOTION_API_BASE))
+                    .json(&payload),
+                "创建每日汇总页面",
+            )
+            .await?;
+        let page_id = page["id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("未获取到页面 ID"))?
+            .to_string();
+        info!("已创建 {} 的每日汇总页面: {}", date, page_id);
+        Ok(page_id)
+    }
+
+    /// 获取页面的所有子块
+    async fn list_children(&self, block_id: &str) -> Result<Vec<Value>> {
+        let mut blocks = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut url = format!(
+                "{}/blocks/{}/children?page_size=100",
+                NOTION_API_BASE, block_id
+            );
+            if let Some(cursor) = &cursor {
+                url.push_str(&format!("&start_cursor={}", cursor));
+            }
+            let result = self
+                .request_json(self.client.get(&url), "获取页面内容")
+                .await?;
+            blocks.extend(result["results"].as_array().cloned().unwrap_or_default());
+            cursor = result["next_cursor"].as_str().map(str::to_string);
+            if !result["has_more"].as_bool().unwrap_or(false) || cursor.is_none() {
+                return Ok(blocks);
+            }
+        }
+    }
+
+    /// 每日汇总模式：把会话作为折叠块追加到当天的汇总页面
+    ///
+    /// 会话重新同步（如重新分析）时，新的折叠块插入到旧块之后再删除旧块，保持页面中的顺序
+    async fn sync_session_digest(
+        &self,
+        session: &Session,
+        details: &NotionSessionDetails,
+    ) -> Result<String> {
+        let session_id = session
+            .id
+            .ok_or_else(|| anyhow!("会话没有 ID，无法同步到每日汇总"))?;
+        let date = session.start_time.format("%Y-%m-%d").to_string();
+        info!("同步会话 {} 到 {} 的每日汇总", session_id, date);
+
+        // 持有缓存锁直到更新完成，避免并发创建重复的汇总页面
+        let mut pages = self.digest_pages.lock().await;
+        let page_id = match pages.get(&date) {
+            Some(page_id) => page_id.clone(),
+            None => {
+                let page_id = match self.find_page_by_local_id(&digest_local_id(&date)).await? {
+                    Some(page_id) => page_id,
+                    None => self.create_digest_page(&date).await?,
+                };
+                pages.insert(date.clone(), page_id.clone());
+                page_id
+            }
+        };
+
+        let existing = find_digest_block(&self.list_children(&page_id).await?, session_id);
+        let mut payload = json!({ "children": [digest_toggle_block(session, details)] });
+        if let Some(block_id) = &existing {
+            payload["after"] = json!(block_id);
+        }
+        self.request_json(
+            self.client
+                .patch(format!("{}/blocks/{}/children", NOTION_API_BASE, page_id))
+                .json(&payload),
+            "追加会话到每日汇总",
+        )
+        .await?;
+        record_text_egress(&payload, Some(session_id));
+
+        if let Some(block_id) = existing {
+            self.request_json(
+                self.client
+                    .delete(format!("{}/blocks/{}", NOTION_API_BASE, block_id)),
+                "删除旧的会话折叠块",
+            )
+            .await?;
+        }
+
+        Ok(page_id)
+    }
+
     /// 构建会话的 Notion 属性
-    fn build_session_properties(&self, session: &Session) -> Result<Value> {
+    fn build_session_properties(
+        &self,
+        session: &Session,
+        details: &NotionSessionDetails,
+    ) -> Result<Value> {
         use chrono::Local;
 
         // 问题分析：
@@ -269,21 +733,27 @@ impl NotionClient {
             {
                 // 获取主要类别（第一个标签的类别）
                 if let Some(first_tag) = activity_tags.first() {
-                    let category_name = match first_tag.category {
-                        crate::models::ActivityCategory::Work => "工作",
-                        crate::models::ActivityCategory::Communication => "沟通",
-                        crate::models::ActivityCategory::Learning => "学习",
-                        crate::models::ActivityCategory::Personal => "个人",
-                        crate::models::ActivityCategory::Idle => "空闲",
-                        crate::models::ActivityCategory::Other => "其他",
-                    };
-
                     properties["类别"] = json!({
                         "select": {
-                            "name": category_name
+                            "name": category_label(&first_tag.category)
                         }
                     });
 
+                    // 所有标签的类别（去重，保持占比顺序）
+                    let mut labels: Vec<&str> = Vec::new();
+                    for tag in &activity_tags {
+                        let label = category_label(&tag.category);
+                        if !labels.contains(&label) {
+                            labels.push(label);
+                        }
+                    }
+                    properties["标签"] = json!({
+                        "multi_select": labels
+                            .iter()
+                            .map(|label| json!({ "name": label }))
+                            .collect::<Vec<_>>()
+                    });
+
                     // 收集所有关键词（翻译为中文）
                     let mut keywords = Vec::new();
                     for tag in activity_tags {
@@ -320,11 +790,60 @@ impl NotionClient {
             }
         });
 
+        // 分析字段
+        if let Some(subcategory) = details.subcategory.as_deref().filter(|s| !s.is_empty()) {
+            // select 选项不能包含逗号
+            properties["子类别"] = json!({
+                "select": { "name": subcategory.replace(',', " ") }
+            });
+        }
+        if let Some(score) = details.productivity_score {
+            properties["生产力评分"] = json!({ "number": score });
+        }
+        if let Some(score) = details.focus_score {
+            properties["专注评分"] = json!({ "number": score });
+        }
+        if let Some(video_path) = session.video_path.as_deref().filter(|p| !p.is_empty()) {
+            properties["视频"] = json!({ "url": file_url(Path::new(video_path)) });
+        }
+
         Ok(properties)
     }
 
+    /// 工作区允许的单个文件上传上限（字节），获取失败时返回 None
+    async fn workspace_upload_limit(&self) -> Option<u64> {
+        let response = self
+            .client
+            .get(format!("{}/users/me", NOTION_API_BASE))
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", NOTION_API_VERSION)
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let bot: Value = response.json().await.ok()?;
+        bot["bot"]["workspace_limits"]["max_file_upload_size_in_bytes"].as_u64()
+    }
+
+    /// 推送上传进度
+    fn report_progress(&self, progress: NotionUploadProgress) {
+        if let Some(listener) = &self.progress_listener {
+            listener(progress);
+        }
+    }
+
     /// 添加视频到页面内容
-    async fn add_video_to_page(&self, page_id: &str, video_path: &str) -> Result<String> {
+    ///
+    /// 通过 Notion 文件上传接口上传：小文件一次上传，大文件按 PART_SIZE 分块上传（逐块读取，不整体载入内存）；
+    /// 超过工作区上限或设置的大小限制时，只在页面中添加本地文件链接
+    async fn add_video_to_page(
+        &self,
+        page_id: &str,
+        video_path: &str,
+        session_id: Option<i64>,
+    ) -> Result<String> {
         info!("上传视频到 Notion 页面: {}", page_id);
 
         let path = Path::new(video_path);
@@ -334,49 +853,56 @@ impl NotionClient {
 
         // 获取文件信息
         let metadata = fs::metadata(path).await?;
-        let size_mb = metadata.len() / (1024 * 1024);
         let file_size = metadata.len();
+        let size_mb = file_size / (1024 * 1024);
         let file_name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("video.mp4");
+        let content_type = video_content_type(path);
 
-        // 检查文件大小限制
-        if size_mb > self.config.sync_options.video_size_limit_mb as u64 {
-            warn!(
-                "视频文件 {} 大小 {}MB 超过限制 {}MB，跳过上传",
-                file_name, size_mb, self.config.sync_options.video_size_limit_mb
-            );
-            return Ok(format!("文件过大（{}MB），已跳过", size_mb));
-        }
-
-        // 确定是否需要使用 multi-part 模式
-        const MULTI_PART_THRESHOLD: u64 = 20 * 1024 * 1024; // 20 MB
-        const PART_SIZE: u64 = 10 * 1024 * 1024; // 10 MB 每个分块
-        let use_multi_part = file_size > MULTI_PART_THRESHOLD;
+        // 检查文件大小限制（工作区上限和设置的限制取较小值）
+        let workspace_limit = self.workspace_upload_limit().await;
+        let limit = effective_upload_limit(
+            workspace_limit,
+            self.config.sync_options.video_size_limit_mb,
+        );
+        let plan = match upload_plan(file_size, limit) {
+            Some(plan) => plan,
+            None => {
+                warn!(
+                    "视频文件 {} 大小 {}MB 超过上传上限 {}MB，改为添加本地文件链接",
+                    file_name,
+                    size_mb,
+                    limit.unwrap_or_default() / (1024 * 1024)
+                );
+                self.add_local_video_link(page_id, path).await?;
+                return Ok(format!("文件过大（{}MB），已添加本地文件链接", size_mb));
+            }
+        };
 
         // 步骤1: 创建 FileUpload 对象
         info!("步骤1: 创建 FileUpload 对象");
         let create_url = format!("{}/file_uploads", NOTION_API_BASE);
 
-        let create_payload = if use_multi_part {
-            let number_of_parts = ((file_size + PART_SIZE - 1) / PART_SIZE) as i32;
-            info!(
-                "文件大小 {} MB 超过 20 MB，使用 multi-part 模式（{} 个块）",
-                size_mb, number_of_parts
-            );
+        let create_payload = match plan {
+            UploadPlan::MultiPart { parts } => {
+                info!(
+                    "文件大小 {} MB 超过 20 MB，使用 multi-part 模式（{} 个块）",
+                    size_mb, parts
+                );
 
-            json!({
-                "mode": "multi_part",
-                "number_of_parts": number_of_parts,
-                "filename": file_name,
-                "content_type": "video/mp4"
-            })
-        } else {
-            json!({
+                json!({
+                    "mode": "multi_part",
+                    "number_of_parts": parts,
+                    "filename": file_name,
+                    "content_type": content_type
+                })
+            }
+            UploadPlan::SinglePart => json!({
                 "filename": file_name,
-                "content_type": "video/mp4"
-            })
+                "content_type": content_type
+            }),
         };
 
         let response = self
@@ -407,40 +933,55 @@ impl NotionClient {
 
         // 步骤2: 上传文件内容
         info!("步骤2: 上传文件内容 (文件大小: {} MB)", size_mb);
-        let file_bytes = fs::read(path).await?;
 
         // 创建一个有更长超时时间的客户端（文件上传可能需要更长时间）
-        let upload_client = reqwest::Client::builder()
+        let upload_client = crate::domains::local_only::http_client_builder()
             .timeout(std::time::Duration::from_secs(300)) // 5分钟超时
             .build()?;
 
-        if use_multi_part {
-            // Multi-part 模式：分块上传
+        let total_parts = match plan {
+            UploadPlan::MultiPart { parts } => parts,
+            UploadPlan::SinglePart => 1,
+        };
+        let progress = |part: u32, uploaded_bytes: u64| NotionUploadProgress {
+            file_name: file_name.to_string(),
+            uploaded_bytes,
+            total_bytes: file_size,
+            part,
+            total_parts,
+        };
+        self.report_progress(progress(0, 0));
+
+        if let UploadPlan::MultiPart { .. } = plan {
+            // Multi-part 模式：逐块读取并上传
+            let mut file = fs::File::open(path).await?;
             let mut offset: u64 = 0;
-            let mut part_number = 1;
+            let mut part_number: u32 = 1;
             const MAX_RETRIES: u32 = 3;
             const RETRY_DELAY_MS: u64 = 2000;
 
             while offset < file_size {
                 let chunk_size = std::cmp::min(PART_SIZE, file_size - offset);
-                let chunk = &file_bytes[offset as usize..(offset + chunk_size) as usize];
+                let mut chunk = vec![0u8; chunk_size as usize];
+                file.read_exact(&mut chunk).await?;
 
                 info!(
-                    "上传第 {} 块 ({} MB)...",
+                    "上传第 {}/{} 块 ({} MB)...",
                     part_number,
+                    total_parts,
                     chunk_size / (1024 * 1024)
                 );
 
                 // 带重试的上传逻辑
                 let mut retry_count = 0;
-                let upload_success = loop {
+                loop {
                     let form = reqwest::multipart::Form::new()
                         .text("part_number", part_number.to_string())
                         .part(
                             "file",
-                            reqwest::multipart::Part::bytes(chunk.to_vec())
+                            reqwest::multipart::Part::bytes(chunk.clone())
                                 .file_name(file_name.to_string())
-                                .mime_str("video/mp4")?,
+                                .mime_str(content_type)?,
                         );
 
                     let upload_response = upload_client
@@ -454,7 +995,7 @@ impl NotionClient {
                     match upload_response {
                         Ok(resp) if resp.status().is_success() => {
                             info!("第 {} 块上传成功", part_number);
-                            break true;
+                            break;
                         }
                         Ok(resp) => {
                             let status = resp.status();
@@ -498,13 +1039,10 @@ impl NotionClient {
                                 .await;
                         }
                     }
-                };
-
-                if !upload_success {
-                    return Err(anyhow!("上传第 {} 块失败，已达到最大重试次数", part_number));
                 }
 
                 offset += chunk_size;
+                self.report_progress(progress(part_number, offset));
                 part_number += 1;
             }
 
@@ -531,11 +1069,12 @@ impl NotionClient {
             info!("Multi-part 上传完成");
         } else {
             // Single-part 模式：一次性上传
+            let file_bytes = fs::read(path).await?;
             let form = reqwest::multipart::Form::new().part(
                 "file",
                 reqwest::multipart::Part::bytes(file_bytes)
                     .file_name(file_name.to_string())
-                    .mime_str("video/mp4")?,
+                    .mime_str(content_type)?,
             );
 
             let upload_response = upload_client
@@ -552,6 +1091,7 @@ impl NotionClient {
                 return Err(anyhow!("上传文件内容失败: {}", error_text));
             }
 
+            self.report_progress(progress(1, file_size));
             info!("文件内容上传成功");
         }
 
@@ -615,10 +1155,72 @@ impl NotionClient {
             }
         }
 
+        egress::record(
+            EgressChannel::Notion,
+            NOTION_API_BASE,
+            EgressContent::Video,
+            file_size,
+            session_id,
+        );
         info!("视频上传并添加到页面成功");
         Ok(format!("视频已上传: {} ({} MB)", file_name, size_mb))
     }
 
+    /// 视频无法上传时，在页面中添加本地文件链接
+    async fn add_local_video_link(&self, page_id: &str, path: &Path) -> Result<()> {
+        let blocks = json!({
+            "children": [
+                {
+                    "object": "block",
+                    "type": "heading_2",
+                    "heading_2": {
+                        "rich_text": [{
+                            "type": "text",
+                            "text": { "content": "📹 会话视频" }
+                        }]
+                    }
+                },
+                {
+                    "object": "block",
+                    "type": "paragraph",
+                    "paragraph": {
+                        "rich_text": [
+                            {
+                                "type": "text",
+                                "text": { "content": "视频超过 Notion 上传上限，本地文件：" }
+                            },
+                            {
+                                "type": "text",
+                                "text": {
+                                    "content": path.to_string_lossy(),
+                                    "link": { "url": file_url(path) }
+                                },
+                                "annotations": { "code": true }
+                            }
+                        ]
+                    }
+                }
+            ]
+        });
+
+        let response = self
+            .client
+            .patch(format!("{}/blocks/{}/children", NOTION_API_BASE, page_id))
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", NOTION_API_VERSION)
+            .header("Content-Type", "application/json")
+            .json(&blocks)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            warn!("添加本地视频链接失败: {}", error_text);
+            return Err(anyhow!("添加本地视频链接失败: {}", error_text));
+        }
+        Ok(())
+    }
+
     /// 上传视频到 Notion（如果启用且文件大小合适）
     pub async fn upload_video(&self, _session_id: i64, video_path: &str) -> Result<String> {
         if !self.config.sync_options.sync_videos {
@@ -633,8 +1235,9 @@ impl NotionClient {
         // 检查文件大小
         let metadata = fs::metadata(path).await?;
         let size_mb = metadata.len() / (1024 * 1024);
+        let limit = effective_upload_limit(None, self.config.sync_options.video_size_limit_mb);
 
-        if size_mb > self.config.sync_options.video_size_limit_mb as u64 {
+        if upload_plan(metadata.len(), limit).is_none() {
             warn!(
                 "视频文件 {} 大小 {}MB 超过限制 {}MB，跳过上传",
                 video_path, size_mb, self.config.sync_options.video_size_limit_mb
@@ -701,6 +1304,7 @@ impl NotionClient {
             error!("同步每日总结到 Notion 失败: {}", error_text);
             return Err(anyhow!("同步失败: {}", error_text));
         }
+        record_text_egress(&payload, None);
 
         let page: Value = response.json().await?;
         let page_id = page["id"].as_str().unwrap_or("unknown");
@@ -830,6 +1434,34 @@ impl NotionClient {
                 },
                 "类型": {
                     "select": {}
+                },
+                "子类别": {
+                    "select": {}
+                },
+                "标签": {
+                    "multi_select": {
+                        "options": [
+                            { "name": "工作", "color": "blue" },
+                            { "name": "沟通", "color": "green" },
+                            { "name": "学习", "color": "purple" },
+                            { "name": "个人", "color": "orange" },
+                            { "name": "空闲", "color": "gray" },
+                            { "name": "其他", "color": "default" }
+                        ]
+                    }
+                },
+                "生产力评分": {
+                    "number": {
+                        "format": "number"
+                    }
+                },
+                "专注评分": {
+                    "number": {
+                        "format": "number"
+                    }
+                },
+                "视频": {
+                    "url": {}
                 }
             }
         });
@@ -859,40 +1491,10 @@ impl NotionClient {
 
     /// 检查会话是否已经同步（通过本地ID）
     pub async fn is_session_synced(&self, session_id: i64) -> Result<bool> {
-        let url = format!(
-            "{}/databases/{}/query",
-            NOTION_API_BASE, self.config.database_id
-        );
-
-        let filter = json!({
-            "filter": {
-                "property": "本地ID",
-                "rich_text": {
-                    "equals": session_id.to_string()
-                }
-            }
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.config.api_token))
-            .header("Notion-Version", NOTION_API_VERSION)
-            .header("Content-Type", "application/json")
-            .json(&filter)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("查询失败: {}", error_text));
-        }
-
-        let result: Value = response.json().await?;
-        let empty_vec = vec![];
-        let results = result["results"].as_array().unwrap_or(&empty_vec);
-
-        Ok(!results.is_empty())
+        Ok(self
+            .find_page_by_local_id(&session_id.to_string())
+            .await?
+            .is_some())
     }
 }
 
@@ -925,4 +1527,122 @@ mod tests {
         let client = NotionClient::new(config);
         assert!(client.is_err());
     }
+
+    #[test]
+    fn test_upload_plan_respects_limits() {
+        const MB: u64 = 1024 * 1024;
+        // 工作区上限 5GB、未设置限制时按工作区上限
+        let limit = effective_upload_limit(Some(5 * 1024 * MB), 0);
+        assert_eq!(upload_plan(3 * MB, limit), Some(UploadPlan::SinglePart));
+        assert_eq!(
+            upload_plan(45 * MB, limit),
+            Some(UploadPlan::MultiPart { parts: 5 })
+        );
+
+        // 免费工作区 5MB 上限优先于更大的设置
+        let limit = effective_upload_limit(Some(5 * MB), 100);
+        assert_eq!(upload_plan(6 * MB, limit), None);
+        assert_eq!(effective_upload_limit(None, 50), Some(50 * MB));
+        assert_eq!(video_content_type(Path::new("a.WEBM")), "video/webm");
+    }
+
+    #[test]
+    fn test_session_properties_include_analysis_fields() {
+        let client = NotionClient::new(NotionConfig {
+            enabled: true,
+            api_token: "test_token".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let start = crate::storage::local_now();
+        let session = Session {
+            id: Some(7),
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(30),
+            title: "接口开发".to_string(),
+            summary: String::new(),
+            video_path: Some("/data/videos/session_7.mp4".to_string()),
+            tags: r#"[{"category":"work","confidence":0.7,"keywords":["coding"]},
+                      {"category":"learning","confidence":0.3,"keywords":[]}]"#
+                .to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let details = NotionSessionDetails {
+            subcategory: Some("编码".to_string()),
+            productivity_score: Some(86.0),
+            focus_score: Some(72.0),
+        };
+
+        let properties = client.build_session_properties(&session, &details).unwrap();
+        assert_eq!(properties["类别"]["select"]["name"], "工作");
+        assert_eq!(
+            properties["标签"]["multi_select"],
+            json!([{ "name": "工作" }, { "name": "学习" }])
+        );
+        assert_eq!(properties["子类别"]["select"]["name"], "编码");
+        assert_eq!(properties["生产力评分"]["number"], 86.0);
+        assert_eq!(properties["专注评分"]["number"], 72.0);
+        assert_eq!(properties["时长"]["number"], 30);
+        assert_eq!(
+            properties["视频"]["url"],
+            "file:///data/videos/session_7.mp4"
+        );
+    }
+
+    #[test]
+    fn test_digest_toggle_block_is_found_by_session_marker() {
+        let start = crate::storage::local_now();
+        let session = Session {
+            id: Some(42),
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(25),
+            title: "接口开发".to_string(),
+            summary: "实现同步接口".to_string(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let details = NotionSessionDetails {
+            subcategory: Some("编码".to_string()),
+            productivity_score: Some(80.0),
+            focus_score: None,
+        };
+
+        let block = digest_toggle_block(&session, &details);
+        let rich_text = block["toggle"]["rich_text"].as_array().unwrap();
+        assert!(rich_text[0]["text"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("接口开发（25 分钟）"));
+        assert_eq!(rich_text[1]["text"]["content"], "#42");
+        assert_eq!(
+            block["toggle"]["children"][1]["paragraph"]["rich_text"][0]["text"]["content"],
+            "子类别：编码 · 生产力 80"
+        );
+
+        // Notion 返回的块带 plain_text，"#142" 不会误匹配 "#42"
+        let returned = |id: &str, marker: &str| {
+            json!({
+                "id": id,
+                "type": "toggle",
+                "toggle": { "rich_text": [
+                    { "plain_text": "09:00-09:25 接口开发（25 分钟） " },
+                    { "plain_text": marker }
+                ] }
+            })
+        };
+        let children = vec![returned("a", "#142"), returned("b", "#42")];
+        assert_eq!(find_digest_block(&children, 42), Some("b".to_string()));
+        assert_eq!(find_digest_block(&children, 7), None);
+    }
 }
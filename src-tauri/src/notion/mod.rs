@@ -3,18 +3,99 @@
 
 pub mod client;
 
-pub use client::{NotionClient, NotionPage};
+pub use client::{
+    NotionClient, NotionPage, NotionSessionDetails, NotionUploadProgress, UploadProgressListener,
+};
 
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{error, info};
 
+use crate::domains::live::FOCUS_PRODUCTIVITY;
+use crate::domains::metrics::{build_activity_series, ActivitySpan};
 use crate::models::{NotionConfig, Session};
+use crate::storage::{Database, TimelineCardRecord, VideoSegmentRecord};
+use std::collections::HashMap;
+
+/// 本地模式下停用 Notion 同步
+fn ensure_sync_allowed() -> Result<()> {
+    if crate::domains::local_only::is_enabled() {
+        return Err(anyhow::anyhow!("本地模式已开启，Notion 同步已停用"));
+    }
+    Ok(())
+}
+
+/// 由时间线卡片计算会话同步到 Notion 的分析字段
+///
+/// 生产力评分为非空闲分钟的平均生产力强度，专注评分为其中专注（且未被干扰）分钟的占比
+pub fn session_details(
+    session: &Session,
+    cards: &[TimelineCardRecord],
+    segments: &[VideoSegmentRecord],
+) -> NotionSessionDetails {
+    let points = build_activity_series(session.start_time, session.end_time, cards, segments);
+    let active: Vec<_> = points.iter().filter(|p| p.category != "idle").collect();
+    let percent = |value: f64| (value * 1000.0).round() / 10.0;
+    let (productivity_score, focus_score) = if active.is_empty() {
+        (None, None)
+    } else {
+        let count = active.len() as f64;
+        let productivity: f64 = active.iter().map(|p| p.productivity as f64).sum();
+        let focused = active
+            .iter()
+            .filter(|p| !p.distracted && p.productivity >= FOCUS_PRODUCTIVITY)
+            .count();
+        (
+            Some(percent(productivity / count)),
+            Some(percent(focused as f64 / count)),
+        )
+    };
+
+    let mut subcategories: HashMap<&str, i64> = HashMap::new();
+    for card in cards {
+        let subcategory = card.subcategory.trim();
+        if subcategory.is_empty() {
+            continue;
+        }
+        let minutes = ActivitySpan::from_card(card, "")
+            .map(|span| span.duration_minutes())
+            .unwrap_or(0);
+        *subcategories.entry(subcategory).or_default() += minutes;
+    }
+    let subcategory = subcategories
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(subcategory, _)| subcategory.to_string());
+
+    NotionSessionDetails {
+        subcategory,
+        productivity_score,
+        focus_score,
+    }
+}
+
+/// 读取会话的卡片和分段并计算分析字段（读取失败时返回空字段，不影响同步）
+pub async fn load_session_details(db: &Database, session: &Session) -> NotionSessionDetails {
+    let Some(session_id) = session.id else {
+        return NotionSessionDetails::default();
+    };
+    let cards = db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .unwrap_or_default();
+    let segments = db
+        .get_video_segments_by_session(session_id)
+        .await
+        .unwrap_or_default();
+    session_details(session, &cards, &segments)
+}
 
 /// Notion 同步管理器
 pub struct NotionManager {
     client: Arc<RwLock<Option<NotionClient>>>,
+    /// 视频上传进度回调（初始化客户端时传入）
+    progress_listener: std::sync::RwLock<Option<UploadProgressListener>>,
 }
 
 impl NotionManager {
@@ -22,6 +103,14 @@ impl NotionManager {
     pub fn new() -> Self {
         Self {
             client: Arc::new(RwLock::new(None)),
+            progress_listener: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// 设置视频上传进度回调（在 initialize 之前调用）
+    pub fn set_progress_listener(&self, listener: UploadProgressListener) {
+        if let Ok(mut guard) = self.progress_listener.write() {
+            *guard = Some(listener);
         }
     }
 
@@ -32,7 +121,12 @@ impl NotionManager {
         if config.enabled {
             match NotionClient::new(config.clone()) {
                 Ok(notion_client) => {
-                    *client = Some(notion_client);
+                    let listener = self
+                        .progress_listener
+                        .read()
+                        .ok()
+                        .and_then(|guard| guard.clone());
+                    *client = Some(notion_client.with_progress_listener(listener));
                     info!("Notion 客户端已初始化");
                     Ok(())
                 }
@@ -59,7 +153,11 @@ impl NotionManager {
     }
 
     /// 同步会话（异步，不阻塞主流程）
-    pub async fn sync_session_async(&self, session: Session) {
+    pub async fn sync_session_async(&self, session: Session, details: NotionSessionDetails) {
+        if crate::domains::local_only::is_enabled() {
+            info!("本地模式已开启，跳过 Notion 同步");
+            return;
+        }
         let client = self.client.read().await;
         if let Some(c) = &*client {
             let c = c.clone();
@@ -69,11 +167,11 @@ impl NotionManager {
                 // 记录当前配置（用于调试）
                 let cfg = c.get_config();
                 info!(
-                    "Notion 同步配置: sync_videos={}, video_size_limit={}MB",
+                    "Notion 同步配置: sync_videos={}, video_size_limit={}MB（0 表示按工作区上限）",
                     cfg.sync_options.sync_videos, cfg.sync_options.video_size_limit_mb
                 );
 
-                match c.sync_session(&session).await {
+                match c.sync_session(&session, &details).await {
                     Ok(page_id) => {
                         info!(
                             "会话 {:?} 成功同步到 Notion，页面 ID: {}",
@@ -89,16 +187,22 @@ impl NotionManager {
     }
 
     /// 同步会话（同步方式，等待结果）
-    pub async fn sync_session(&self, session: &Session) -> Result<String> {
+    pub async fn sync_session(
+        &self,
+        session: &Session,
+        details: &NotionSessionDetails,
+    ) -> Result<String> {
+        ensure_sync_allowed()?;
         let client = self.client.read().await;
         match &*client {
-            Some(c) => c.sync_session(session).await,
+            Some(c) => c.sync_session(session, details).await,
             None => Ok("Notion 客户端未初始化".to_string()),
         }
     }
 
     /// 同步每日总结
     pub async fn sync_daily_summary(&self, date: &str, summary: &str) -> Result<String> {
+        ensure_sync_allowed()?;
         let client = self.client.read().await;
         match &*client {
             Some(c) => c.sync_daily_summary(date, summary).await,
@@ -106,9 +210,9 @@ impl NotionManager {
         }
     }
 
-    /// 检查是否已启用
+    /// 检查是否已启用（本地模式下视为未启用）
     pub async fn is_enabled(&self) -> bool {
-        self.client.read().await.is_some()
+        !crate::domains::local_only::is_enabled() && self.client.read().await.is_some()
     }
 
     /// 搜索可用的页面和数据库
@@ -0,0 +1,322 @@
+// RAG 导出模块
+// 定期将时间线卡片摘要及元数据写入本地向量库（Qdrant），供外部个人助理工具检索
+// 以卡片 ID 作为向量点 ID，并记录已导出的最大卡片 ID，实现增量导出
+// 卡片修改按 updated_at 水位重新导出；会话被删除或重新分析时，按 session_id 删除旧的向量点后重写
+
+pub mod qdrant;
+
+pub use qdrant::{QdrantClient, VectorPoint};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::models::RagExportConfig;
+use crate::settings::SettingsManager;
+use crate::storage::{Database, TimelineCardRecord};
+
+/// 导出进度（持久化到应用数据目录）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RagExportState {
+    /// 导出目标集合（集合变化时从头导出）
+    pub collection: String,
+    /// 已导出的最大卡片 ID
+    pub last_card_id: i64,
+    /// 上次导出时间
+    pub last_export_at: Option<String>,
+    /// 已重新导出的最后一次卡片修改时间
+    #[serde(default)]
+    pub last_updated_at: Option<DateTime<Utc>>,
+    /// 同一修改时间下已重新导出的最大卡片 ID
+    #[serde(default)]
+    pub last_updated_id: i64,
+    /// 卡片被删除或重新分析、需要重写向量点的会话
+    #[serde(default)]
+    pub stale_sessions: BTreeSet<i64>,
+}
+
+/// 单次导出结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RagExportReport {
+    /// 本次导出的卡片数
+    pub exported: usize,
+    /// 导出后的最大卡片 ID
+    pub last_card_id: i64,
+}
+
+/// RAG 导出器
+pub struct RagExporter {
+    settings: Arc<SettingsManager>,
+    state_path: PathBuf,
+    client: reqwest::Client,
+    /// 防止定时任务与手动触发并发导出
+    running: Mutex<()>,
+}
+
+impl RagExporter {
+    /// 创建导出器，进度文件保存在 `state_path`
+    pub fn new(settings: Arc<SettingsManager>, state_path: PathBuf) -> Self {
+        let client = crate::domains::local_only::http_client_builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            settings,
+            state_path,
+            client,
+            running: Mutex::new(()),
+        }
+    }
+
+    /// 启动定时导出任务（每次循环重新读取配置，修改设置后无需重启）
+    pub fn start_export_task(self: Arc<Self>, db: Arc<Database>) {
+        self.clone().start_removal_listener(&db);
+
+        tokio::spawn(async move {
+            loop {
+                let config = self.current_config().await;
+                let interval = config.interval_minutes.max(5);
+
+                if config.enabled {
+                    match self.export_incremental(&db).await {
+                        Ok(report) if report.exported > 0 => {
+                            info!(
+                                "RAG 导出完成: {} 张卡片，最大卡片 ID {}",
+                                report.exported, report.last_card_id
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("RAG 导出失败: {}", e),
+                    }
+                }
+
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval * 60)).await;
+            }
+        });
+    }
+
+    /// 记录卡片被删除的会话，下次导出时重写这些会话的向量点
+    fn start_removal_listener(self: Arc<Self>, db: &Database) {
+        let mut removals = db.subscribe_card_removals();
+        tokio::spawn(async move {
+            loop {
+                match removals.recv().await {
+                    Ok(session_id) => self.mark_session_stale(session_id).await,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("RAG 导出漏收 {} 条卡片删除通知", skipped)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn mark_session_stale(&self, session_id: i64) {
+        let _guard = self.running.lock().await;
+        let mut state = self.load_state().await;
+        // 尚未导出过卡片时向量库中没有需要清理的点
+        if state.last_card_id == 0 || !state.stale_sessions.insert(session_id) {
+            return;
+        }
+        if let Err(e) = self.save_state(&state).await {
+            warn!("保存 RAG 导出进度失败: {}", e);
+        }
+    }
+
+    /// 执行一次增量导出
+    pub async fn export_incremental(&self, db: &Database) -> Result<RagExportReport> {
+        let _guard = self.running.lock().await;
+        let config = self.current_config().await;
+        let qdrant = QdrantClient::new(self.client.clone(), &config);
+
+        let mut state = self.load_state().await;
+        if state.collection != config.collection {
+            if !state.collection.is_empty() {
+                warn!(
+                    "RAG 导出集合已从 {} 变更为 {}，将从头导出",
+                    state.collection, config.collection
+                );
+            }
+            state = RagExportState {
+                collection: config.collection.clone(),
+                ..Default::default()
+            };
+        }
+
+        // 敏感度高于导出上限的卡片不写入向量库
+        let max_level = crate::domains::privacy::max_export_level(&self.settings.get().await);
+        let batch_size = config.batch_size.max(1);
+        let mut exported = 0;
+        let mut collection_ready = false;
+
+        loop {
+            let cards = db
+                .get_timeline_cards_after(state.last_card_id, batch_size)
+                .await?;
+            if cards.is_empty() {
+                break;
+            }
+            let Some(max_id) = cards.iter().filter_map(|c| c.id).max() else {
+                break;
+            };
+            let (cards, _) = crate::domains::privacy::filter_cards(cards, max_level);
+
+            exported += self
+                .write_cards(&qdrant, &config, &cards, &mut collection_ready)
+                .await?;
+            state.last_card_id = max_id;
+            state.last_export_at = Some(crate::storage::local_now().to_rfc3339());
+            // 每批写入后保存进度，中途失败时下次从断点继续
+            self.save_state(&state).await?;
+        }
+
+        // 删除会话的旧向量点，再写入会话现存的卡片（新卡片已由上面的增量导出写入）
+        while let Some(session_id) = state.stale_sessions.first().copied() {
+            qdrant.delete_session_points(session_id).await?;
+
+            let cards = db.get_timeline_cards_by_session(session_id).await?;
+            let (cards, _) = crate::domains::privacy::filter_cards(cards, max_level);
+            exported += self
+                .write_cards(&qdrant, &config, &cards, &mut collection_ready)
+                .await?;
+
+            state.stale_sessions.remove(&session_id);
+            self.save_state(&state).await?;
+        }
+
+        // 重新导出已导出后又被修改的卡片，修改后超出导出上限的卡片从向量库删除
+        loop {
+            let updated = db
+                .get_timeline_cards_updated_after(
+                    state.last_updated_at.unwrap_or(DateTime::UNIX_EPOCH),
+                    state.last_updated_id,
+                    batch_size,
+                )
+                .await?;
+            let Some(last) = updated.last() else {
+                break;
+            };
+            let (last_updated_at, last_updated_id) = (last.updated_at, last.card.id.unwrap_or(0));
+
+            let (cards, hidden): (Vec<_>, Vec<_>) = updated
+                .into_iter()
+                .map(|u| u.card)
+                .filter(|c| c.id.is_some_and(|id| id <= state.last_card_id))
+                .partition(|c| crate::domains::privacy::card_level(c) <= max_level);
+
+            let hidden_ids: Vec<u64> = hidden
+                .iter()
+                .filter_map(|c| c.id)
+                .map(|id| id as u64)
+                .collect();
+            qdrant.delete_points(&hidden_ids).await?;
+            exported += self
+                .write_cards(&qdrant, &config, &cards, &mut collection_ready)
+                .await?;
+
+            state.last_updated_at = Some(last_updated_at);
+            state.last_updated_id = last_updated_id;
+            self.save_state(&state).await?;
+        }
+
+        Ok(RagExportReport {
+            exported,
+            last_card_id: state.last_card_id,
+        })
+    }
+
+    /// 向量化卡片并写入向量库，返回写入的卡片数
+    async fn write_cards(
+        &self,
+        qdrant: &QdrantClient,
+        config: &RagExportConfig,
+        cards: &[TimelineCardRecord],
+        collection_ready: &mut bool,
+    ) -> Result<usize> {
+        if cards.is_empty() {
+            return Ok(0);
+        }
+
+        let texts: Vec<String> = cards.iter().map(card_to_text).collect();
+        let vectors = qdrant::embed_texts(&self.client, config, &texts).await?;
+
+        if !*collection_ready {
+            qdrant.ensure_collection(vectors[0].len()).await?;
+            *collection_ready = true;
+        }
+
+        let points: Vec<VectorPoint> = cards
+            .iter()
+            .zip(vectors)
+            .filter_map(|(card, vector)| {
+                card.id.map(|id| VectorPoint {
+                    id: id as u64,
+                    vector,
+                    payload: card_payload(card),
+                })
+            })
+            .collect();
+        qdrant.upsert_points(&points).await?;
+
+        Ok(points.len())
+    }
+
+    /// 获取导出进度
+    pub async fn get_state(&self) -> RagExportState {
+        self.load_state().await
+    }
+
+    async fn current_config(&self) -> RagExportConfig {
+        self.settings
+            .get()
+            .await
+            .rag_export_config
+            .unwrap_or_default()
+    }
+
+    async fn load_state(&self) -> RagExportState {
+        match tokio::fs::read(&self.state_path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(_) => RagExportState::default(),
+        }
+    }
+
+    async fn save_state(&self, state: &RagExportState) -> Result<()> {
+        let json = serde_json::to_string_pretty(state)?;
+        tokio::fs::write(&self.state_path, json).await?;
+        Ok(())
+    }
+}
+
+/// 生成用于向量化的文本
+fn card_to_text(card: &TimelineCardRecord) -> String {
+    format!(
+        "{}\n{} / {}\n{}\n{}",
+        card.title, card.category, card.subcategory, card.summary, card.detailed_summary
+    )
+}
+
+/// 生成向量点的元数据
+fn card_payload(card: &TimelineCardRecord) -> serde_json::Value {
+    json!({
+        "card_id": card.id,
+        "session_id": card.session_id,
+        "start_time": card.start_time,
+        "end_time": card.end_time,
+        "category": card.category,
+        "subcategory": card.subcategory,
+        "title": card.title,
+        "summary": card.summary,
+        "detailed_summary": card.detailed_summary,
+        "app_sites": serde_json::from_str::<serde_json::Value>(&card.app_sites)
+            .unwrap_or(serde_json::Value::Null),
+    })
+}
@@ -0,0 +1,211 @@
+// Qdrant 与 Embedding 客户端
+// 通过 HTTP 接口写入向量点，Embedding 使用 OpenAI 兼容的 /embeddings 端点
+
+use anyhow::{anyhow, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::models::RagExportConfig;
+
+/// 待写入的向量点
+#[derive(Debug, Clone)]
+pub struct VectorPoint {
+    /// 点 ID（使用时间线卡片 ID，重复写入即覆盖）
+    pub id: u64,
+    /// 向量
+    pub vector: Vec<f32>,
+    /// 元数据
+    pub payload: Value,
+}
+
+/// Qdrant HTTP 客户端
+#[derive(Clone)]
+pub struct QdrantClient {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    collection: String,
+}
+
+impl QdrantClient {
+    /// 创建客户端
+    pub fn new(client: Client, config: &RagExportConfig) -> Self {
+        Self {
+            client,
+            base_url: config.qdrant_url.trim_end_matches('/').to_string(),
+            api_key: config.qdrant_api_key.clone().filter(|k| !k.is_empty()),
+            collection: config.collection.clone(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(key) => builder.header("api-key", key),
+            None => builder,
+        }
+    }
+
+    /// 确保集合存在（不存在时按向量维度创建）
+    pub async fn ensure_collection(&self, vector_size: usize) -> Result<()> {
+        let path = format!("/collections/{}", self.collection);
+        let response = self.request(reqwest::Method::GET, &path).send().await?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+        if response.status() != StatusCode::NOT_FOUND {
+            let error_text = response.text().await?;
+            return Err(anyhow!("查询 Qdrant 集合失败: {}", error_text));
+        }
+
+        let response = self
+            .request(reqwest::Method::PUT, &path)
+            .json(&json!({
+                "vectors": { "size": vector_size, "distance": "Cosine" }
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("创建 Qdrant 集合失败: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// 写入或覆盖向量点
+    pub async fn upsert_points(&self, points: &[VectorPoint]) -> Result<()> {
+        let body = json!({
+            "points": points
+                .iter()
+                .map(|p| json!({ "id": p.id, "vector": p.vector, "payload": p.payload }))
+                .collect::<Vec<_>>()
+        });
+
+        let response = self
+            .request(
+                reqwest::Method::PUT,
+                &format!("/collections/{}/points?wait=true", self.collection),
+            )
+            .json(&body)
+            .send()
+            .await?;
+        let bytes = serde_json::to_vec(&body).map(|b| b.len()).unwrap_or(0);
+        egress::record(
+            EgressChannel::Rag,
+            &self.base_url,
+            EgressContent::Text,
+            bytes as u64,
+            None,
+        );
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("写入 Qdrant 失败: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// 删除指定 ID 的向量点
+    pub async fn delete_points(&self, ids: &[u64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.delete(json!({ "points": ids })).await
+    }
+
+    /// 删除会话的全部向量点（按元数据中的 session_id 匹配）
+    pub async fn delete_session_points(&self, session_id: i64) -> Result<()> {
+        self.delete(json!({
+            "filter": {
+                "must": [{ "key": "session_id", "match": { "value": session_id } }]
+            }
+        }))
+        .await
+    }
+
+    async fn delete(&self, selector: Value) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/collections/{}/points/delete?wait=true", self.collection),
+            )
+            .json(&selector)
+            .send()
+            .await?;
+
+        // 集合尚未创建时没有需要删除的点
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        let error_text = response.text().await?;
+        Err(anyhow!("删除 Qdrant 向量点失败: {}", error_text))
+    }
+}
+
+/// 调用 Embedding 接口，返回与输入顺序一致的向量
+pub async fn embed_texts(
+    client: &Client,
+    config: &RagExportConfig,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    if config.embedding_api_key.trim().is_empty() {
+        return Err(anyhow!("Embedding API Key 未配置"));
+    }
+
+    let response = client
+        .post(&config.embedding_base_url)
+        .bearer_auth(&config.embedding_api_key)
+        .json(&json!({
+            "model": config.embedding_model,
+            "input": texts,
+        }))
+        .send()
+        .await?;
+    egress::record(
+        EgressChannel::Rag,
+        &config.embedding_base_url,
+        EgressContent::Text,
+        texts.iter().map(|text| text.len() as u64).sum(),
+        None,
+    );
+
+    if !response.status().is_success() {
+        let error_text = response.text().await?;
+        return Err(anyhow!("Embedding 请求失败: {}", error_text));
+    }
+
+    let body: Value = response.json().await?;
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Embedding 响应缺少 data 字段"))?;
+
+    let mut vectors = vec![Vec::new(); texts.len()];
+    for (position, item) in data.iter().enumerate() {
+        let index = item["index"]
+            .as_u64()
+            .map(|i| i as usize)
+            .unwrap_or(position);
+        let vector: Vec<f32> = item["embedding"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Embedding 响应缺少 embedding 字段"))?
+            .iter()
+            .filter_map(|v| v.as_f64().map(|f| f as f32))
+            .collect();
+        if let Some(slot) = vectors.get_mut(index) {
+            *slot = vector;
+        }
+    }
+
+    if vectors.iter().any(|v| v.is_empty()) {
+        return Err(anyhow!("Embedding 响应数量与输入不一致"));
+    }
+
+    Ok(vectors)
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use anyhow::Result;
@@ -5,8 +6,13 @@ use tokio::sync::RwLock;
 
 use crate::models::{AppConfig, PersistedAppConfig};
 
+/// 配置管理
+///
+/// 截屏间隔、视频和截屏设置按设备名保存在 device_settings 中，读取时覆盖全局默认值，
+/// 多台设备共用一份配置时互不影响；其余设置为全局设置
 pub struct SettingsManager {
     path: PathBuf,
+    device_name: String,
     data: RwLock<PersistedAppConfig>,
 }
 
@@ -28,14 +34,22 @@ impl SettingsManager {
             }
         };
 
+        apply_language(&initial);
         Ok(Self {
             path,
+            device_name: crate::storage::get_device_info().0,
             data: RwLock::new(initial),
         })
     }
 
+    /// 获取本机生效的配置（已应用本机的设备覆盖）
     pub async fn get(&self) -> PersistedAppConfig {
-        self.data.read().await.clone()
+        resolve_for_device(&self.data.read().await, &self.device_name)
+    }
+
+    /// 本机的设备名（设备覆盖的键）
+    pub fn device_name(&self) -> &str {
+        &self.device_name
     }
 
     pub async fn update(&self, update: AppConfig) -> Result<PersistedAppConfig> {
@@ -47,24 +61,16 @@ impl SettingsManager {
         if let Some(provider) = update.llm_provider {
             config.llm_provider = provider;
         }
-        if let Some(interval) = update.capture_interval {
-            config.capture_interval = interval;
-        }
         if let Some(interval) = update.summary_interval {
             config.summary_interval = interval;
         }
-        if let Some(video) = update.video_config {
-            config.video_config = video;
-        }
         if let Some(ui) = update.ui_settings {
+            crate::i18n::set_language(&ui.language);
             config.ui_settings = Some(ui);
         }
         if let Some(llm) = update.llm_config {
             config.llm_config = Some(llm);
         }
-        if let Some(capture) = update.capture_settings {
-            config.capture_settings = Some(capture);
-        }
         if let Some(logger) = update.logger_settings {
             config.logger_settings = Some(logger);
         }
@@ -74,9 +80,188 @@ impl SettingsManager {
         if let Some(notion) = update.notion_config {
             config.notion_config = Some(notion);
         }
+        if let Some(instance) = update.instance_settings {
+            config.instance_settings = Some(instance);
+        }
+        if let Some(rag) = update.rag_export_config {
+            config.rag_export_config = Some(rag);
+        }
+        if let Some(breaks) = update.break_reminder_settings {
+            config.break_reminder_settings = Some(breaks);
+        }
+        if let Some(profiles) = update.app_profiles {
+            config.app_profiles = Some(profiles);
+        }
+        if let Some(synonyms) = update.subcategory_synonyms {
+            config.subcategory_synonyms = Some(synonyms);
+        }
+        if let Some(pause_reminder) = update.pause_reminder_settings {
+            config.pause_reminder_settings = Some(pause_reminder);
+        }
+        if let Some(analysis_backlog) = update.analysis_backlog {
+            config.analysis_backlog = Some(analysis_backlog);
+        }
+        if let Some(privacy_filter) = update.privacy_filter {
+            config.privacy_filter = Some(privacy_filter);
+        }
+        if let Some(time_tracking) = update.time_tracking {
+            config.time_tracking = Some(time_tracking);
+        }
+        if let Some(performance) = update.performance {
+            config.performance = Some(performance);
+        }
+        if let Some(update_check) = update.update_check {
+            config.update_check = Some(update_check);
+        }
+        if let Some(away_mode) = update.away_mode {
+            config.away_mode = Some(away_mode);
+        }
+        if let Some(archive_settings) = update.llm_debug_archive {
+            config.llm_debug_archive = Some(archive_settings);
+        }
+        if let Some(upload_config) = update.video_upload_config {
+            config.video_upload_config = Some(upload_config);
+        }
+        if let Some(chunk_settings) = update.analysis_chunk_settings {
+            config.analysis_chunk_settings = Some(chunk_settings);
+        }
+        if let Some(rules) = update.retention_rules {
+            config.retention_rules = Some(rules);
+        }
+        if let Some(value) = update.archive_settings {
+            config.archive_settings = Some(value);
+        }
+        if let Some(value) = update.media_store {
+            config.media_store = Some(value);
+        }
+        if let Some(value) = update.analysis_queue {
+            config.analysis_queue = Some(value);
+        }
+        if let Some(value) = update.analysis_hold_minutes {
+            config.analysis_hold_minutes = Some(value);
+        }
+        if let Some(value) = update.review_confidence_threshold {
+            config.review_confidence_threshold = Some(value);
+        }
+        if let Some(value) = update.daily_focus_goal_minutes {
+            config.daily_focus_goal_minutes = Some(value);
+        }
+        if let Some(value) = update.meeting_notes_enabled {
+            config.meeting_notes_enabled = Some(value);
+        }
+        if let Some(value) = update.embed_subtitles {
+            config.embed_subtitles = Some(value);
+        }
+        if let Some(value) = update.card_previews {
+            config.card_previews = Some(value);
+        }
+        if let Some(value) = update.live_api {
+            config.live_api = Some(value);
+        }
+        if let Some(value) = update.automation_hooks {
+            config.automation_hooks = Some(value);
+        }
+        if let Some(value) = update.focus_mode {
+            config.focus_mode = Some(value);
+        }
+        if let Some(value) = update.watch_keywords {
+            config.watch_keywords = Some(value);
+        }
+        if let Some(value) = update.quiet_zones {
+            config.quiet_zones = Some(value);
+        }
+        if let Some(value) = update.text_timeline {
+            config.text_timeline = Some(value);
+        }
+        if let Some(value) = update.live_activity {
+            config.live_activity = Some(value);
+        }
+        if let Some(value) = update.clipboard_log {
+            config.clipboard_log = Some(value);
+        }
+        if let Some(value) = update.audio_presence_enabled {
+            config.audio_presence_enabled = Some(value);
+        }
+        if let Some(value) = update.storage_quota {
+            config.storage_quota = Some(value);
+        }
+        if let Some(value) = update.video_reencode {
+            config.video_reencode = Some(value);
+        }
+        if let Some(value) = update.frame_retention {
+            config.frame_retention = Some(value);
+        }
+        if let Some(value) = update.session_alignment {
+            config.session_alignment = Some(value);
+        }
+        if let Some(value) = update.adaptive_session {
+            config.adaptive_session = Some(value);
+        }
+        if let Some(value) = update.session_title_strategy {
+            config.session_title_strategy = Some(value);
+        }
+        if let Some(value) = update.night_analysis {
+            config.night_analysis = Some(value);
+        }
+        if let Some(value) = update.local_only {
+            config.local_only = Some(value);
+        }
+        if let Some(value) = update.pipeline {
+            config.pipeline = Some(value);
+        }
+
+        // 截屏间隔、视频和截屏设置只写入本机的设备覆盖
+        if update.capture_interval.is_some()
+            || update.video_config.is_some()
+            || update.capture_settings.is_some()
+        {
+            let device = config
+                .device_settings
+                .get_or_insert_with(BTreeMap::new)
+                .entry(self.device_name.clone())
+                .or_default();
+            if let Some(interval) = update.capture_interval {
+                device.capture_interval = Some(interval);
+            }
+            if let Some(video) = update.video_config {
+                device.video_config = Some(video);
+            }
+            if let Some(capture) = update.capture_settings {
+                device.capture_settings = Some(capture);
+            }
+        }
+
+        self.save(&config).await?;
+        Ok(resolve_for_device(&config, &self.device_name))
+    }
 
+    /// 清除本机的设备覆盖，恢复继承全局默认值
+    pub async fn reset_device_settings(&self) -> Result<PersistedAppConfig> {
+        let mut config = self.data.write().await;
+        if let Some(devices) = config.device_settings.as_mut() {
+            devices.remove(&self.device_name);
+        }
         self.save(&config).await?;
-        Ok(config.clone())
+        Ok(resolve_for_device(&config, &self.device_name))
+    }
+
+    /// 配置文件路径
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// 从磁盘重新加载配置（外部修改了配置文件时使用），返回内容是否发生变化
+    pub async fn reload(&self) -> Result<bool> {
+        let bytes = tokio::fs::read(&self.path).await?;
+        let loaded = serde_json::from_slice::<PersistedAppConfig>(&bytes)?;
+
+        let mut config = self.data.write().await;
+        let changed = serde_json::to_value(&*config)? != serde_json::to_value(&loaded)?;
+        if changed {
+            apply_language(&loaded);
+            *config = loaded;
+        }
+        Ok(changed)
     }
 
     async fn save(&self, config: &PersistedAppConfig) -> Result<()> {
@@ -85,3 +270,72 @@ impl SettingsManager {
         Ok(())
     }
 }
+
+/// 按配置中的界面语言设置命令提示的语言
+fn apply_language(config: &PersistedAppConfig) {
+    let language = config
+        .ui_settings
+        .as_ref()
+        .map(|ui| ui.language.as_str())
+        .unwrap_or_default();
+    crate::i18n::set_language(language);
+}
+
+/// 用设备覆盖替换全局默认值，得到该设备生效的配置
+fn resolve_for_device(config: &PersistedAppConfig, device_name: &str) -> PersistedAppConfig {
+    let mut resolved = config.clone();
+    let Some(device) = config
+        .device_settings
+        .as_ref()
+        .and_then(|devices| devices.get(device_name))
+    else {
+        return resolved;
+    };
+    if let Some(interval) = device.capture_interval {
+        resolved.capture_interval = interval;
+    }
+    if let Some(video) = &device.video_config {
+        resolved.video_config = video.clone();
+    }
+    if let Some(capture) = &device.capture_settings {
+        resolved.capture_settings = Some(capture.clone());
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CaptureResolution, CaptureSettings, DeviceSettings};
+
+    #[test]
+    fn test_device_overrides_inherit_global_defaults() {
+        let mut config = PersistedAppConfig::default();
+        config.capture_interval = 2;
+        config.device_settings = Some(BTreeMap::from([(
+            "workstation".to_string(),
+            DeviceSettings {
+                capture_settings: Some(CaptureSettings {
+                    resolution: CaptureResolution::UHD,
+                    ..CaptureSettings::default()
+                }),
+                ..DeviceSettings::default()
+            },
+        )]));
+
+        let workstation = resolve_for_device(&config, "workstation");
+        assert!(matches!(
+            workstation.capture_settings.unwrap().resolution,
+            CaptureResolution::UHD
+        ));
+        // 未覆盖的项继承全局默认值
+        assert_eq!(workstation.capture_interval, 2);
+
+        let laptop = resolve_for_device(&config, "laptop");
+        assert!(matches!(
+            laptop.capture_settings.unwrap().resolution,
+            CaptureResolution::FHD
+        ));
+        assert_eq!(laptop.capture_interval, 2);
+    }
+}
@@ -0,0 +1,167 @@
+// 冷存储归档 - 把过期会话的视频移到外接硬盘、S3 兼容对象存储或 WebDAV
+//
+// 启用归档后，清理任务不再删除过期会话，而是把视频（及元数据 sidecar）上传到
+// 归档目标，删除本地视频和截图，会话记录标记为已归档并保存归档 Key；
+// 需要回放或重新分析时再通过 restore_session 把视频拉回本地。
+// 归档目标复用 media_store 中的存储后端
+
+use super::media_store::{create_store, MediaStore};
+use super::{Database, Session};
+use crate::models::{ArchiveSettings, MediaBackend};
+use crate::video::VideoMetadata;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 按归档设置创建归档目标（不检查是否启用，关闭归档后仍可恢复已归档的会话）
+pub fn archive_store(settings: &ArchiveSettings) -> Result<Arc<dyn MediaStore>> {
+    let local_dir = settings.local_dir.trim();
+    if settings.target == MediaBackend::Local && local_dir.is_empty() {
+        return Err(anyhow!("未设置本地归档目录"));
+    }
+    create_store(
+        settings.target,
+        Path::new(local_dir),
+        settings.s3.as_ref(),
+        settings.webdav.as_ref(),
+    )
+}
+
+/// 视频元数据 sidecar 的归档 Key
+fn sidecar_key(key: &str) -> String {
+    VideoMetadata::sidecar_path(Path::new(key))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 生成归档 Key：按会话日期分目录
+fn archive_key(session: &Session, video_path: &Path) -> Result<String> {
+    let file_name = video_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("无效的视频路径: {:?}", video_path))?;
+    Ok(format!(
+        "{}/{}",
+        session.start_time.format("%Y-%m-%d"),
+        file_name
+    ))
+}
+
+/// 归档会话：上传视频和元数据、标记数据库后删除本地视频和截图，返回归档 Key
+///
+/// 从归档恢复过的会话已有归档 Key，不再重复上传
+pub async fn archive_session(
+    store: &dyn MediaStore,
+    db: &Database,
+    session: &Session,
+    frame_paths: &[String],
+) -> Result<String> {
+    let session_id = session.id.ok_or_else(|| anyhow!("会话缺少 ID"))?;
+    let video_path = session
+        .video_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("会话 {} 没有视频", session_id))?;
+
+    let key = match &session.archive_key {
+        Some(key) => key.clone(),
+        None => {
+            let key = archive_key(session, &video_path)?;
+            store.put(&key, &video_path).await?;
+            let sidecar = VideoMetadata::sidecar_path(&video_path);
+            if sidecar.is_file() {
+                store.put(&sidecar_key(&key), &sidecar).await?;
+            }
+            key
+        }
+    };
+
+    db.mark_session_archived(session_id, &key).await?;
+
+    if let Err(e) = tokio::fs::remove_file(&video_path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("删除已归档的视频失败 {:?}: {}", video_path, e);
+        }
+    }
+    VideoMetadata::remove(&video_path).await;
+    for frame_path in frame_paths {
+        let _ = tokio::fs::remove_file(frame_path).await;
+    }
+
+    info!("会话 {} 已归档: {}", session_id, key);
+    Ok(key)
+}
+
+/// 从归档恢复会话视频到本地视频目录，返回本地视频路径
+///
+/// 归档中的副本保留，之后再次过期时只删除本地文件
+pub async fn restore_session(
+    store: &dyn MediaStore,
+    db: &Database,
+    session: &Session,
+    videos_dir: &Path,
+) -> Result<String> {
+    let session_id = session.id.ok_or_else(|| anyhow!("会话缺少 ID"))?;
+    let key = match (&session.archived, &session.archive_key) {
+        (true, Some(key)) => key,
+        _ => return Err(anyhow!("会话 {} 未归档", session_id)),
+    };
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    let video_path = videos_dir.join(file_name);
+
+    store.fetch(key, &video_path).await?;
+    if let Err(e) = store
+        .fetch(&sidecar_key(key), &VideoMetadata::sidecar_path(&video_path))
+        .await
+    {
+        // 旧视频可能没有元数据，回放时会按默认参数换算时间
+        warn!("恢复视频元数据失败 {}: {}", key, e);
+    }
+
+    let video_path = video_path.to_string_lossy().to_string();
+    db.mark_session_restored(session_id, &video_path).await?;
+
+    info!("会话 {} 已从归档恢复: {}", session_id, video_path);
+    Ok(video_path)
+}
+
+/// 删除会话的归档副本（视频和元数据）
+pub async fn delete_archived(store: &dyn MediaStore, key: &str) -> Result<()> {
+    store.delete(key).await?;
+    if let Err(e) = store.delete(&sidecar_key(key)).await {
+        warn!("删除归档的视频元数据失败 {}: {}", key, e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_key_uses_session_date() {
+        let session = Session {
+            id: Some(1),
+            start_time: chrono::NaiveDate::from_ymd_opt(2025, 3, 4)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap()
+                .and_utc(),
+            end_time: chrono::Utc::now(),
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+            created_at: None,
+            device_name: None,
+            device_type: None,
+            pinned: false,
+            archived: false,
+            archive_key: None,
+        };
+        let key = archive_key(&session, Path::new("/data/videos/session_1.mp4")).unwrap();
+        assert_eq!(key, "2025-03-04/session_1.mp4");
+        assert!(sidecar_key(&key).starts_with("2025-03-04/session_1.mp4"));
+    }
+}
@@ -0,0 +1,101 @@
+// 存储可用性检测 - 数据目录位于网络共享（NAS/SMB/NFS）时防止断线误操作
+//
+// 共享断开时挂载点可能变成空目录、访问卡住或直接报错。为了区分"目录为空"
+// 和"共享未挂载"，在 frames/videos 目录中放置一个标记文件：读不到标记文件时
+// 视为存储离线，清理任务跳过本轮，分析任务等待重新连接，
+// 避免把暂时访问不到的文件当成缺失或孤立文件处理
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 标记文件名
+pub const MARKER_FILE: &str = ".screen-analyzer-storage";
+
+/// 单次检测的超时时间（网络共享断开时文件操作可能长时间阻塞）
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 等待重新连接时的重试间隔（指数退避）
+const RETRY_INITIAL: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// 确保目录中存在标记文件
+///
+/// 目录为空时无法判断是新目录还是未挂载的共享，只有 `allow_empty` 为 true
+/// （本地默认目录、刚迁移完成的目录）时才会在空目录中创建标记
+pub fn ensure_marker(dir: &Path, allow_empty: bool) -> std::io::Result<bool> {
+    let marker = dir.join(MARKER_FILE);
+    if marker.is_file() {
+        return Ok(true);
+    }
+
+    let is_empty = std::fs::read_dir(dir)?.next().is_none();
+    if is_empty && !allow_empty {
+        warn!("目录 {:?} 为空且没有存储标记，可能是未挂载的网络共享", dir);
+        return Ok(false);
+    }
+
+    std::fs::write(&marker, "screen-analyzer storage marker\n")?;
+    Ok(true)
+}
+
+/// 目录是否可访问（标记文件可读，且在超时时间内返回）
+pub async fn is_available(dir: &Path) -> bool {
+    let marker = dir.join(MARKER_FILE);
+    let check = tokio::task::spawn_blocking(move || marker.is_file());
+    matches!(
+        tokio::time::timeout(CHECK_TIMEOUT, check).await,
+        Ok(Ok(true))
+    )
+}
+
+/// 所有目录是否都可访问
+pub async fn all_available(dirs: &[PathBuf]) -> bool {
+    for dir in dirs {
+        if !is_available(dir).await {
+            return false;
+        }
+    }
+    true
+}
+
+/// 等待存储重新连接，超过最长等待时间仍不可用时返回 false
+pub async fn wait_until_available(dirs: &[PathBuf], max_wait: Duration) -> bool {
+    if all_available(dirs).await {
+        return true;
+    }
+
+    warn!("存储不可用（网络共享可能已断开），等待重新连接: {:?}", dirs);
+    let started = tokio::time::Instant::now();
+    let mut delay = RETRY_INITIAL;
+
+    while started.elapsed() < max_wait {
+        tokio::time::sleep(delay).await;
+        if all_available(dirs).await {
+            info!("存储已重新连接，耗时 {} 秒", started.elapsed().as_secs());
+            return true;
+        }
+        delay = (delay * 2).min(RETRY_MAX);
+    }
+
+    warn!("等待 {} 秒后存储仍不可用", max_wait.as_secs());
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_empty_dir_needs_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_available(dir.path()).await);
+
+        // 空目录不会被自动认定为可用
+        assert!(!ensure_marker(dir.path(), false).unwrap());
+        assert!(!is_available(dir.path()).await);
+
+        assert!(ensure_marker(dir.path(), true).unwrap());
+        assert!(is_available(dir.path()).await);
+    }
+}
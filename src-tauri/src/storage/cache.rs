@@ -206,6 +206,14 @@ impl DatabaseRepository for CachedRepository {
         Ok(())
     }
 
+    async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()> {
+        self.inner.update_sessions_tags(updates).await?;
+        for (session_id, _) in updates {
+            self.invalidate_session(*session_id).await;
+        }
+        Ok(())
+    }
+
     async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()> {
         self.inner
             .update_session_video_path(session_id, video_path)
@@ -226,6 +234,22 @@ impl DatabaseRepository for CachedRepository {
         Ok(())
     }
 
+    async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        self.inner.restore_session_snapshot(snapshot).await?;
+        self.clear_cache().await;
+        Ok(())
+    }
+
+    async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>> {
+        self.inner
+            .get_empty_sessions(cutoff_date, device_name)
+            .await
+    }
+
     async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64> {
         let count = self.inner.delete_old_sessions(cutoff_date).await?;
         self.clear_cache().await;
@@ -294,6 +318,10 @@ impl DatabaseRepository for CachedRepository {
         self.inner.get_llm_calls_by_session(session_id).await
     }
 
+    async fn get_llm_call_ids(&self) -> Result<Vec<i64>> {
+        self.inner.get_llm_call_ids().await
+    }
+
     async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>> {
         self.inner.get_recent_llm_errors(limit).await
     }
@@ -338,10 +366,33 @@ impl DatabaseRepository for CachedRepository {
         self.inner.get_timeline_cards_by_session(session_id).await
     }
 
+    async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>> {
+        self.inner.get_timeline_card(card_id).await
+    }
+
     async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
         self.inner.get_recent_timeline_cards(limit).await
     }
 
+    async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        self.inner.get_timeline_cards_after(after_id, limit).await
+    }
+
+    async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>> {
+        self.inner
+            .get_timeline_cards_updated_after(after, after_id, limit)
+            .await
+    }
+
     async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()> {
         self.inner
             .delete_timeline_cards_by_session(session_id)
@@ -352,6 +403,10 @@ impl DatabaseRepository for CachedRepository {
         self.inner.get_stats().await
     }
 
+    async fn get_session_row_usage(&self, session_id: Option<i64>) -> Result<Vec<SessionRowUsage>> {
+        self.inner.get_session_row_usage(session_id).await
+    }
+
     async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
         self.inner.get_analyzed_video_paths().await
     }
@@ -368,6 +423,471 @@ impl DatabaseRepository for CachedRepository {
         self.inner.delete_day_summary(date).await
     }
 
+    async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()> {
+        self.inner.set_day_summary_finalized(date, finalized).await
+    }
+
+    async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64> {
+        self.inner.insert_break_record(record).await
+    }
+
+    async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>> {
+        self.inner.get_break_records_by_date(date).await
+    }
+
+    async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64> {
+        self.inner.insert_deflected_distraction(record).await
+    }
+
+    async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>> {
+        self.inner.get_deflected_distractions_by_date(date).await
+    }
+
+    async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64> {
+        self.inner.insert_capture_pause(record).await
+    }
+
+    async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()> {
+        self.inner.end_capture_pause(id, end_time).await
+    }
+
+    async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        self.inner.close_open_capture_pauses(end_time).await
+    }
+
+    async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>> {
+        self.inner.get_capture_pauses_between(start, end).await
+    }
+
+    async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64> {
+        self.inner.insert_block_intent(record).await
+    }
+
+    async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        self.inner.end_open_block_intents(end_time).await
+    }
+
+    async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>> {
+        self.inner.get_block_intents_between(start, end).await
+    }
+
+    async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()> {
+        self.inner
+            .update_block_intent_alignment(id, alignment, aligned_minutes, active_minutes)
+            .await
+    }
+
+    async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64> {
+        self.inner.insert_external_events(events).await
+    }
+
+    async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>> {
+        self.inner.get_external_events_between(start, end).await
+    }
+
+    async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_classification(card_id, category, distractions)
+            .await
+    }
+
+    async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64> {
+        let updated = self
+            .inner
+            .rename_timeline_card_subcategory(from, to)
+            .await?;
+        if updated > 0 {
+            self.clear_cache().await;
+        }
+        Ok(updated)
+    }
+
+    async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        self.inner.get_cards_needing_review(limit).await
+    }
+
+    async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_review(card_id, title, category, subcategory, review_status)
+            .await
+    }
+
+    async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_meeting_notes(card_id, meeting_notes)
+            .await
+    }
+
+    async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_keyword_tags(card_id, keyword_tags)
+            .await
+    }
+
+    async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_preview_clip(card_id, preview_clip_path)
+            .await
+    }
+
+    async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()> {
+        self.inner
+            .update_timeline_card_privacy_level(card_id, level)
+            .await
+    }
+
+    async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()> {
+        self.inner
+            .update_timeline_card_times(card_id, start_time, end_time)
+            .await
+    }
+
+    async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()> {
+        self.inner
+            .update_video_segment_times(segment_id, start_timestamp, end_timestamp)
+            .await
+    }
+
+    async fn replace_keyword_hits(&self, session_id: i64, hits: &[KeywordHitRecord]) -> Result<()> {
+        self.inner.replace_keyword_hits(session_id, hits).await
+    }
+
+    async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        self.inner.get_keyword_hits_between(start, end).await
+    }
+
+    async fn get_keyword_hits_by_session(&self, session_id: i64) -> Result<Vec<KeywordHitRecord>> {
+        self.inner.get_keyword_hits_by_session(session_id).await
+    }
+
+    async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()> {
+        self.inner.replace_screen_text(session_id, records).await
+    }
+
+    async fn get_screen_text_by_session(&self, session_id: i64) -> Result<Vec<ScreenTextRecord>> {
+        self.inner.get_screen_text_by_session(session_id).await
+    }
+
+    async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        self.inner.get_screen_text_between(start, end).await
+    }
+
+    async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        self.inner
+            .search_screen_text(pattern, language, limit)
+            .await
+    }
+
+    async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()> {
+        self.inner.replace_error_moments(session_id, records).await
+    }
+
+    async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        self.inner.get_error_moments_between(start, end).await
+    }
+
+    async fn get_error_moments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        self.inner.get_error_moments_by_session(session_id).await
+    }
+
+    async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()> {
+        self.inner.upsert_audio_presence(record).await
+    }
+
+    async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>> {
+        self.inner.get_audio_presence_between(start, end).await
+    }
+
+    async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64> {
+        self.inner.insert_clipboard_event(record).await
+    }
+
+    async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        self.inner.get_clipboard_events_between(start, end).await
+    }
+
+    async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        self.inner
+            .search_clipboard_events(content_hash, pattern, limit)
+            .await
+    }
+
+    async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64> {
+        self.inner.insert_card_correction(record).await
+    }
+
+    async fn get_recent_card_corrections(&self, limit: i64) -> Result<Vec<CardCorrectionRecord>> {
+        self.inner.get_recent_card_corrections(limit).await
+    }
+
+    async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()> {
+        self.inner.save_personal_record(record).await
+    }
+
+    async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>> {
+        self.inner.get_personal_records().await
+    }
+
+    async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()> {
+        self.inner.set_day_type(record).await
+    }
+
+    async fn clear_day_type(&self, date: &str) -> Result<()> {
+        self.inner.clear_day_type(date).await
+    }
+
+    async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>> {
+        self.inner.get_day_types_between(start_date, end_date).await
+    }
+
+    async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64> {
+        let updated = self
+            .inner
+            .relocate_file_paths(old_prefix, new_prefix)
+            .await?;
+        self.clear_cache().await;
+        Ok(updated)
+    }
+
+    async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()> {
+        self.inner.add_storage_usage(usage).await
+    }
+
+    async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>> {
+        self.inner
+            .get_storage_usage(device_name, start_date, end_date)
+            .await
+    }
+
+    async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()> {
+        self.inner.set_session_pinned(session_id, pinned).await?;
+        self.invalidate_session(session_id).await;
+        Ok(())
+    }
+
+    async fn get_pinned_sessions(&self) -> Result<Vec<Session>> {
+        self.inner.get_pinned_sessions().await
+    }
+
+    async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()> {
+        self.inner
+            .mark_session_archived(session_id, archive_key)
+            .await?;
+        self.invalidate_session(session_id).await;
+        Ok(())
+    }
+
+    async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()> {
+        self.inner
+            .mark_session_restored(session_id, video_path)
+            .await?;
+        self.invalidate_session(session_id).await;
+        Ok(())
+    }
+
+    async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()> {
+        self.inner.add_pending_deletion(record).await
+    }
+
+    async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>> {
+        self.inner.get_pending_deletions().await
+    }
+
+    async fn remove_pending_deletion(&self, session_id: i64) -> Result<()> {
+        self.inner.remove_pending_deletion(session_id).await
+    }
+
+    async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.inner
+            .claim_video(video_path, owner, now, lease_until)
+            .await
+    }
+
+    async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()> {
+        self.inner.release_video_claim(video_path, owner).await
+    }
+
+    async fn insert_provider_comparison(&self, record: &ProviderComparisonRecord) -> Result<i64> {
+        self.inner.insert_provider_comparison(record).await
+    }
+
+    async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>> {
+        self.inner.get_provider_comparisons(session_id).await
+    }
+
+    async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64> {
+        self.inner.insert_egress_event(record).await
+    }
+
+    async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>> {
+        self.inner.get_egress_events_between(start, end).await
+    }
+
+    async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64> {
+        self.inner.insert_session_bookmark(record).await
+    }
+
+    async fn get_session_bookmarks(&self, session_id: i64) -> Result<Vec<SessionBookmarkRecord>> {
+        self.inner.get_session_bookmarks(session_id).await
+    }
+
+    async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()> {
+        self.inner.delete_session_bookmark(bookmark_id).await
+    }
+
+    async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>> {
+        self.inner.get_time_entry_export(idempotency_key).await
+    }
+
+    async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>> {
+        self.inner
+            .get_time_entry_exports_by_session(session_id)
+            .await
+    }
+
+    async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()> {
+        self.inner.insert_time_entry_export(record).await
+    }
+
+    async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64> {
+        self.inner.insert_webhook_delivery(record).await
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        self.inner.get_webhook_deliveries(session_id, limit).await
+    }
+
     async fn initialize_tables(&self) -> Result<()> {
         self.inner.initialize_tables().await
     }
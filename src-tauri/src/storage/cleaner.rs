@@ -1,13 +1,20 @@
 // 存储清理模块 - 自动清理过期数据
 
-use super::Database;
+use super::archive;
+use super::media_store::{MediaLibrary, MediaStore};
+use super::trash;
+use super::{Database, PendingDeletionRecord, Session, SessionRowUsage, TimelineCardRecord};
+use crate::domains::storage_usage::files_size;
+use crate::event_bus::{AppEvent, EventBus};
+use crate::models::{ArchiveSettings, CategoryRetentionRule};
 use anyhow::Result;
-use chrono::{Duration as ChronoDuration, Utc};
-use std::path::PathBuf;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// 存储清理器
 pub struct StorageCleaner {
@@ -21,17 +28,39 @@ pub struct StorageCleaner {
     frames_dir: PathBuf,
     /// 视频文件目录
     videos_dir: PathBuf,
+    /// 按类别的保留规则
+    retention_rules: Arc<RwLock<Vec<CategoryRetentionRule>>>,
+    /// 冷存储归档设置
+    archive_settings: Arc<RwLock<ArchiveSettings>>,
+    /// 媒体库（视频在存储后端中的副本）
+    media: Arc<MediaLibrary>,
+    /// 离开模式下暂停自动清理（手动清理不受影响）
+    retention_frozen: Arc<RwLock<bool>>,
+    /// 生成视频后保留的截图最多保留的天数（见 frame_retention）
+    kept_frames_days: Arc<RwLock<u32>>,
 }
 
 impl StorageCleaner {
     /// 创建新的清理器
-    pub fn new(db: Arc<Database>, frames_dir: PathBuf, videos_dir: PathBuf) -> Self {
+    pub fn new(
+        db: Arc<Database>,
+        frames_dir: PathBuf,
+        videos_dir: PathBuf,
+        media: Arc<MediaLibrary>,
+    ) -> Self {
         Self {
             db,
             retention_days: Arc::new(RwLock::new(7)), // 默认保留7天
             max_retention_days: 30,                   // 最大保留30天
             frames_dir,
             videos_dir,
+            retention_rules: Arc::new(RwLock::new(Vec::new())),
+            archive_settings: Arc::new(RwLock::new(ArchiveSettings::default())),
+            media,
+            retention_frozen: Arc::new(RwLock::new(false)),
+            kept_frames_days: Arc::new(RwLock::new(
+                crate::models::FrameRetentionSettings::default().max_days,
+            )),
         }
     }
 
@@ -58,8 +87,110 @@ impl StorageCleaner {
         *self.retention_days.read().await
     }
 
-    /// 启动自动清理任务
-    pub async fn start_cleanup_task(self: Arc<Self>) {
+    /// 设置按类别的保留规则
+    pub async fn set_retention_rules(&self, rules: Vec<CategoryRetentionRule>) -> Result<()> {
+        validate_rules(&rules)?;
+        info!("类别保留规则已更新: {} 条", rules.len());
+        *self.retention_rules.write().await = rules;
+        Ok(())
+    }
+
+    /// 设置冷存储归档
+    pub async fn set_archive_settings(&self, settings: ArchiveSettings) -> Result<()> {
+        validate_archive_settings(&settings)?;
+        info!(
+            "冷存储归档已{}",
+            if settings.enabled { "启用" } else { "关闭" }
+        );
+        *self.archive_settings.write().await = settings;
+        Ok(())
+    }
+
+    /// 设置保留截图的最长天数
+    pub async fn set_kept_frames_days(&self, days: u32) {
+        *self.kept_frames_days.write().await = days.max(1);
+    }
+
+    /// 暂停或恢复自动清理（离开模式）
+    pub async fn set_retention_frozen(&self, frozen: bool) {
+        info!("自动清理已{}", if frozen { "暂停" } else { "恢复" });
+        *self.retention_frozen.write().await = frozen;
+    }
+
+    /// 按当前设置创建归档目标
+    pub async fn archive_store(&self) -> Result<Arc<dyn MediaStore>> {
+        archive::archive_store(&*self.archive_settings.read().await)
+    }
+
+    /// 从归档恢复会话视频，返回本地视频路径
+    pub async fn restore_archived_session(&self, session: &Session) -> Result<String> {
+        let store = self.archive_store().await?;
+        archive::restore_session(store.as_ref(), &self.db, session, &self.videos_dir).await
+    }
+
+    /// 回收站目录（数据目录下的 trash）
+    pub fn trash_dir(&self) -> PathBuf {
+        self.frames_dir
+            .parent()
+            .map(|p| p.join("trash"))
+            .unwrap_or_else(|| self.frames_dir.join("trash"))
+    }
+
+    /// 删除会话，撤销窗口内可恢复
+    pub async fn delete_session_with_undo(&self, session_id: i64) -> Result<PendingDeletionRecord> {
+        trash::stage_session_deletion(&self.db, &self.trash_dir(), session_id).await
+    }
+
+    /// 撤销删除会话（按原 ID 恢复），返回会话 ID
+    pub async fn undo_session_deletion(&self, session_id: i64) -> Result<i64> {
+        trash::undo_session_deletion(&self.db, &self.trash_dir(), session_id).await
+    }
+
+    /// 彻底删除已过撤销窗口的会话，同时删除归档和存储后端中的副本
+    pub async fn purge_deleted_sessions(&self) -> Result<usize> {
+        let purged =
+            trash::purge_expired(&self.db, &self.trash_dir(), crate::storage::local_now()).await?;
+
+        for session in &purged {
+            if let Some(archive_key) = &session.archive_key {
+                match self.archive_store().await {
+                    Ok(store) => {
+                        if let Err(e) = archive::delete_archived(store.as_ref(), archive_key).await
+                        {
+                            error!("删除归档视频失败: {}", e);
+                        }
+                    }
+                    Err(e) => error!("无法访问归档目标，归档视频未删除: {}", e),
+                }
+            }
+            if let Some(video_path) = &session.video_path {
+                if let Err(e) = self
+                    .media
+                    .remove_remote_video(std::path::Path::new(video_path))
+                    .await
+                {
+                    warn!("删除远程视频副本失败 {}: {}", video_path, e);
+                }
+            }
+        }
+
+        Ok(purged.len())
+    }
+
+    /// 启动自动清理任务（每轮清理后发布 StorageCleaned 事件）
+    pub async fn start_cleanup_task(self: Arc<Self>, event_bus: Arc<EventBus>) {
+        // 回收站每分钟检查一次，撤销窗口结束后尽快删除文件
+        let trash_cleaner = self.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = trash_cleaner.purge_deleted_sessions().await {
+                    error!("清理回收站失败: {}", e);
+                }
+            }
+        });
+
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_secs(3600)); // 每小时检查一次
             info!("存储清理任务已启动，每小时检查一次");
@@ -67,36 +198,142 @@ impl StorageCleaner {
             loop {
                 interval.tick().await;
 
+                // 离开模式下不按保留天数清理，避免离开期间数据过期
+                if *self.retention_frozen.read().await {
+                    info!("离开模式中，跳过本轮自动清理");
+                    continue;
+                }
+
                 // 执行清理
-                if let Err(e) = self.perform_cleanup().await {
-                    error!("清理任务执行失败: {}", e);
+                match self.perform_cleanup().await {
+                    Ok(outcome) => outcome.publish(&event_bus),
+                    Err(e) => error!("清理任务执行失败: {}", e),
                 }
             }
         });
     }
 
     /// 执行清理操作
-    pub async fn perform_cleanup(&self) -> Result<()> {
+    pub async fn perform_cleanup(&self) -> Result<CleanupOutcome> {
+        // 存储离线时（网络共享断开）跳过本轮，避免删除数据库记录后留下无法清理的文件
+        let storage_dirs = [self.frames_dir.clone(), self.videos_dir.clone()];
+        if !super::availability::all_available(&storage_dirs).await {
+            warn!("存储目录不可用（网络共享可能已断开），跳过本轮清理");
+            return Ok(CleanupOutcome::default());
+        }
+
+        // 1. 按保留策略找出要删除的会话（包括关联的文件路径）
         let retention_days = *self.retention_days.read().await;
-        let cutoff_date = crate::storage::local_now() - ChronoDuration::days(retention_days);
-        info!("开始清理 {} 之前的数据", cutoff_date.format("%Y-%m-%d"));
+        let rules = self.retention_rules.read().await.clone();
+        let expired = self.plan_expired_sessions(retention_days, &rules).await?;
+        info!(
+            "开始清理：全局保留 {} 天，类别规则 {} 条，{} 个会话已过期",
+            retention_days,
+            rules.len(),
+            expired.len()
+        );
 
-        // 1. 获取要删除的会话详情（包括关联的文件路径）
-        let old_sessions = self.get_old_sessions_with_files(&cutoff_date).await?;
+        // 启用归档时，有视频的过期会话移到归档目标，不删除记录
+        let archiving = self.archive_settings.read().await.enabled;
+        let archive_store = if archiving {
+            match self.archive_store().await {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    error!("归档目标配置无效，跳过本轮清理: {}", e);
+                    return Ok(CleanupOutcome::default());
+                }
+            }
+        } else {
+            None
+        };
 
-        // 2. 删除数据库中的记录
-        let deleted_count = self.db.delete_old_sessions(cutoff_date).await?;
+        // 2. 删除数据库中的记录，成功后再删除关联的文件
+        let mut deleted_count = 0;
+        let mut archived_count = 0;
+        let mut session_files = Vec::new();
+        for session in expired {
+            match self.archive_decision(&session.session, archiving).await {
+                ArchiveDecision::Delete => {}
+                ArchiveDecision::Skip => continue,
+                ArchiveDecision::MarkArchived => {
+                    let archive_key = session.session.archive_key.as_deref().unwrap_or_default();
+                    match self
+                        .db
+                        .mark_session_archived(session.session_id, archive_key)
+                        .await
+                    {
+                        Ok(()) => archived_count += 1,
+                        Err(e) => error!("标记会话 {} 为已归档失败: {}", session.session_id, e),
+                    }
+                    continue;
+                }
+                ArchiveDecision::Archive => {
+                    let store = match &archive_store {
+                        Some(store) => store.clone(),
+                        None => match self.archive_store().await {
+                            Ok(store) => store,
+                            Err(e) => {
+                                warn!("归档目标不可用，保留会话 {}: {}", session.session_id, e);
+                                continue;
+                            }
+                        },
+                    };
+                    // 归档失败（如外接硬盘未连接）时保留会话，下一轮再试
+                    match archive::archive_session(
+                        store.as_ref(),
+                        &self.db,
+                        &session.session,
+                        &session.files.frame_paths,
+                    )
+                    .await
+                    {
+                        Ok(_) => {
+                            archived_count += 1;
+                            // 视频已移到归档目标，删除存储后端中的副本
+                            if let Some(video_path) = &session.files.video_path {
+                                if let Err(e) = self
+                                    .media
+                                    .remove_remote_video(std::path::Path::new(video_path))
+                                    .await
+                                {
+                                    warn!("删除存储后端中的视频失败 {}: {}", video_path, e);
+                                }
+                            }
+                        }
+                        Err(e) => error!("归档会话 {} 失败: {}", session.session_id, e),
+                    }
+                    continue;
+                }
+            }
+
+            match self.db.delete_session(session.session_id).await {
+                Ok(()) => {
+                    deleted_count += 1;
+                    session_files.push(session.files);
+                }
+                Err(e) => error!("删除过期会话 {} 失败: {}", session.session_id, e),
+            }
+        }
 
         // 3. 删除关联的文件
-        let failed_files = if deleted_count > 0 {
-            self.cleanup_files(old_sessions).await?
-        } else {
-            Vec::new()
-        };
+        let (failed_files, space_freed) = self.cleanup_files(session_files).await?;
+
+        // 清理空会话（崩溃、截屏权限失败等遗留的无卡片、无截图、无视频的会话）
+        deleted_count += self.prune_garbage_sessions().await?;
 
         // 4. 清理孤立文件（没有数据库记录的文件）
         self.cleanup_orphaned_files().await?;
 
+        // 删除超过保留天数仍未分析成功的截图
+        crate::domains::frame_retention::purge_expired(
+            &self.frames_dir,
+            *self.kept_frames_days.read().await,
+        )
+        .await;
+
+        // 使用远程存储后端时，删除超过本地缓存天数的视频副本
+        self.evict_local_videos().await?;
+
         // 5. 记录清理结果
         if !failed_files.is_empty() {
             error!("清理完成，但有 {} 个文件删除失败", failed_files.len());
@@ -105,23 +342,109 @@ impl StorageCleaner {
             }
         }
 
-        info!("清理完成，删除了 {} 个会话", deleted_count);
-        Ok(())
+        info!(
+            "清理完成，删除了 {} 个会话，归档了 {} 个会话",
+            deleted_count, archived_count
+        );
+        Ok(CleanupOutcome {
+            sessions_deleted: deleted_count,
+            space_freed,
+        })
     }
 
-    /// 获取要删除的会话及其文件信息
-    /// 获取旧会话及其关联的文件路径
-    async fn get_old_sessions_with_files(
-        &self,
-        cutoff_date: &chrono::DateTime<Utc>,
-    ) -> Result<Vec<SessionFiles>> {
-        // 1. 查询所有过期会话
-        let old_sessions = self.db.get_old_sessions(*cutoff_date).await?;
+    /// 过期会话的处理方式
+    ///
+    /// 启用归档或已有归档副本（从归档恢复过）的会话只归档、不删除记录，
+    /// 删除会连同恢复的视频一起删掉，并留下无人引用的归档副本。
+    /// 只归档仍有视频的会话，没有视频可归档的会话按 nothing_to_archive 处理，不会一直留在过期列表里；
+    /// 从归档恢复的视频保留一段时间供回放和重新分析
+    async fn archive_decision(&self, session: &Session, archiving: bool) -> ArchiveDecision {
+        if !archiving && session.archive_key.is_none() {
+            return ArchiveDecision::Delete;
+        }
+        let Some(video_path) = session
+            .video_path
+            .as_deref()
+            .filter(|path| !path.trim().is_empty())
+        else {
+            return Self::nothing_to_archive(session);
+        };
+        // 本地和存储后端都没有视频（视频所在目录不可访问时视为存在）：没有可归档的内容，
+        // 存储后端暂时连不上时下一轮再判断
+        let local = self.media.local_path_for(Path::new(video_path));
+        if !local_video_exists(&local).await {
+            match self.media.has_remote_copy(Path::new(video_path)).await {
+                Ok(true) => {}
+                Ok(false) => return Self::nothing_to_archive(session),
+                Err(e) => {
+                    warn!(
+                        "无法确认存储后端中的视频，下一轮再归档 {}: {}",
+                        video_path, e
+                    );
+                    return ArchiveDecision::Skip;
+                }
+            }
+        }
+        // 本地副本已被缓存清理时先从存储后端拉回，存储后端暂时连不上时下一轮再试
+        let local = match self
+            .media
+            .ensure_local_video(std::path::Path::new(video_path))
+            .await
+        {
+            Ok(local) => local,
+            Err(e) => {
+                warn!("会话视频暂时不可用，下一轮再归档 {}: {}", video_path, e);
+                return ArchiveDecision::Skip;
+            }
+        };
+        let Ok(meta) = tokio::fs::metadata(&local).await else {
+            return Self::nothing_to_archive(session);
+        };
+        if session.archive_key.is_some() {
+            let restored_recently = meta
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|elapsed| elapsed < RESTORED_KEEP);
+            if restored_recently {
+                return ArchiveDecision::Skip;
+            }
+        }
+        ArchiveDecision::Archive
+    }
 
-        let mut session_files = Vec::new();
+    /// 过期会话没有视频可归档：有归档副本（从归档恢复后视频又丢失）的重新标记为已归档，否则删除
+    fn nothing_to_archive(session: &Session) -> ArchiveDecision {
+        if session.archive_key.is_some() {
+            ArchiveDecision::MarkArchived
+        } else {
+            ArchiveDecision::Delete
+        }
+    }
+
+    /// 按保留策略找出过期会话及其关联的文件路径
+    ///
+    /// 先取最短保留期之前的会话作为候选，再按会话的主要类别匹配规则，
+    /// 未匹配规则或没有卡片的会话使用全局保留天数
+    async fn plan_expired_sessions(
+        &self,
+        retention_days: i64,
+        rules: &[CategoryRetentionRule],
+    ) -> Result<Vec<ExpiredSession>> {
+        let now = crate::storage::local_now();
+        let min_days = rules
+            .iter()
+            .map(|rule| rule.retention_days)
+            .chain(std::iter::once(retention_days))
+            .min()
+            .unwrap_or(retention_days);
+        let candidates = self
+            .db
+            .get_old_sessions(now - ChronoDuration::days(min_days))
+            .await?;
 
-        // 2. 对每个会话，获取其关联的文件路径
-        for session in old_sessions {
+        let mut expired = Vec::new();
+        for session in candidates {
             let session_id = match session.id {
                 Some(id) => id,
                 None => {
@@ -130,69 +453,328 @@ impl StorageCleaner {
                 }
             };
 
-            // 3. 获取所有帧文件路径
+            // 置顶的会话不参与自动清理，已归档的会话本地没有文件
+            if session.pinned || session.archived {
+                continue;
+            }
+
+            let category = if rules.is_empty() {
+                None
+            } else {
+                let cards = self.db.get_timeline_cards_by_session(session_id).await?;
+                dominant_category(&cards)
+            };
+            let days = retention_days_for(category.as_deref(), retention_days, rules);
+            if session.start_time >= now - ChronoDuration::days(days) {
+                continue;
+            }
+
+            // 获取所有帧文件路径和视频文件路径
             let frames = self.db.get_frames_by_session(session_id).await?;
             let frame_paths: Vec<String> = frames.iter().map(|f| f.file_path.clone()).collect();
 
-            // 4. 获取视频文件路径
-            let video_path = session.video_path.clone();
+            expired.push(ExpiredSession {
+                session_id,
+                category,
+                retention_days: days,
+                files: SessionFiles {
+                    frame_paths,
+                    video_path: session.video_path.clone(),
+                },
+                session,
+            });
+        }
+
+        Ok(expired)
+    }
+
+    /// 找出空会话：本设备开始超过 GARBAGE_MIN_AGE 的会话中，没有卡片、没有截图记录、视频也不存在的
+    ///
+    /// 置顶和已归档的会话不算；其他设备的会话可能还在等待分析或正在重新分析，不由本机清理。
+    /// 使用远程存储后端时本地没有视频不代表视频不存在，只看是否记录了视频路径；
+    /// 视频所在目录不可访问（网络共享断开）时同样视为视频存在
+    async fn plan_garbage_sessions(&self) -> Result<Vec<Session>> {
+        let cutoff = crate::storage::local_now() - ChronoDuration::from_std(GARBAGE_MIN_AGE)?;
+        let (device_name, _) = crate::storage::get_device_info();
+        let remote = self.media.is_remote().await;
+        let mut garbage = Vec::new();
+        for session in self.db.get_empty_sessions(cutoff, &device_name).await? {
+            let local_video_exists = match &session.video_path {
+                Some(path) if !path.trim().is_empty() => local_video_exists(Path::new(path)).await,
+                _ => false,
+            };
+            if !has_video(session.video_path.as_deref(), local_video_exists, remote) {
+                garbage.push(session);
+            }
+        }
+        Ok(garbage)
+    }
+
+    /// 预览会被清理的空会话（不实际删除）
+    pub async fn preview_garbage_sessions(&self) -> Result<Vec<GarbageSession>> {
+        Ok(self
+            .plan_garbage_sessions()
+            .await?
+            .into_iter()
+            .filter_map(|session| {
+                Some(GarbageSession {
+                    session_id: session.id?,
+                    title: session.title,
+                    start_time: session.start_time,
+                    end_time: session.end_time,
+                    video_path: session.video_path,
+                })
+            })
+            .collect())
+    }
+
+    /// 删除空会话（先移入回收站，撤销窗口内可恢复），返回删除的会话数
+    async fn prune_garbage_sessions(&self) -> Result<usize> {
+        let mut deleted = 0;
+        for session in self.plan_garbage_sessions().await? {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            match self.delete_session_with_undo(session_id).await {
+                Ok(_) => {
+                    deleted += 1;
+                    // 记录了但已不存在的视频，顺带清理可能残留的旁路文件
+                    if let Some(video_path) = &session.video_path {
+                        let path = std::path::Path::new(video_path);
+                        crate::video::VideoMetadata::remove(path).await;
+                        crate::domains::card_previews::remove_previews(path).await;
+                        crate::domains::mood_strip::remove(path).await;
+                    }
+                }
+                Err(e) => error!("删除空会话 {} 失败: {}", session_id, e),
+            }
+        }
+        if deleted > 0 {
+            info!("已清理 {} 个空会话", deleted);
+        }
+        Ok(deleted)
+    }
+
+    /// 预览保留策略会删除的会话（不实际删除）
+    ///
+    /// 传入的保留天数和规则用于预览尚未保存的设置，为空时使用当前设置
+    pub async fn preview_cleanup(
+        &self,
+        retention_days: Option<i64>,
+        rules: Option<Vec<CategoryRetentionRule>>,
+    ) -> Result<RetentionPreview> {
+        let retention_days = match retention_days {
+            Some(days) => days,
+            None => *self.retention_days.read().await,
+        };
+        let rules = match rules {
+            Some(rules) => {
+                validate_rules(&rules)?;
+                rules
+            }
+            None => self.retention_rules.read().await.clone(),
+        };
 
-            // 5. 添加到结果列表
-            session_files.push(SessionFiles {
-                frame_paths,
-                video_path,
+        let expired = self.plan_expired_sessions(retention_days, &rules).await?;
+        let mut sessions = Vec::with_capacity(expired.len());
+        let mut total_bytes = 0;
+        for session in expired {
+            let mut paths = session.files.frame_paths.clone();
+            paths.extend(session.files.video_path.clone());
+            let bytes = files_size(&paths).await;
+            total_bytes += bytes;
+            sessions.push(RetentionPreviewItem {
+                session_id: session.session_id,
+                title: session.session.title,
+                start_time: session.session.start_time,
+                category: session.category,
+                retention_days: session.retention_days,
+                bytes,
             });
         }
 
-        info!("找到 {} 个过期会话，准备清理相关文件", session_files.len());
-        Ok(session_files)
+        Ok(RetentionPreview {
+            total_sessions: sessions.len(),
+            total_bytes,
+            sessions,
+        })
     }
 
-    /// 清理文件，返回失败列表
-    async fn cleanup_files(&self, sessions: Vec<SessionFiles>) -> Result<Vec<(String, String)>> {
+    /// 清理文件，返回删除失败的文件和释放的本地空间（字节）
+    async fn cleanup_files(
+        &self,
+        sessions: Vec<SessionFiles>,
+    ) -> Result<(Vec<(String, String)>, u64)> {
         let mut failed_files = Vec::new();
+        let mut space_freed = 0u64;
 
         for session in sessions {
             // 删除帧文件
             for frame_path in session.frame_paths {
+                let size = files_size(std::slice::from_ref(&frame_path)).await;
                 if let Err(e) = tokio::fs::remove_file(&frame_path).await {
+                    // 文件已不存在（如视频生成后已清理截图）不算失败
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        continue;
+                    }
                     error!("删除帧文件失败 {}: {}", frame_path, e);
                     failed_files.push((frame_path.clone(), e.to_string()));
+                } else {
+                    space_freed += size as u64;
                 }
             }
 
             // 删除视频文件
             if let Some(video_path) = session.video_path {
+                let size = files_size(std::slice::from_ref(&video_path)).await;
                 if let Err(e) = tokio::fs::remove_file(&video_path).await {
-                    error!("删除视频文件失败 {}: {}", video_path, e);
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        error!("删除视频文件失败 {}: {}", video_path, e);
+                        failed_files.push((video_path.clone(), e.to_string()));
+                    }
+                } else {
+                    space_freed += size as u64;
+                }
+                crate::video::VideoMetadata::remove(std::path::Path::new(&video_path)).await;
+                crate::domains::card_previews::remove_previews(std::path::Path::new(&video_path))
+                    .await;
+                crate::domains::mood_strip::remove(std::path::Path::new(&video_path)).await;
+
+                // 删除存储后端中的副本
+                if let Err(e) = self
+                    .media
+                    .remove_remote_video(std::path::Path::new(&video_path))
+                    .await
+                {
+                    error!("删除存储后端中的视频失败 {}: {}", video_path, e);
                     failed_files.push((video_path.clone(), e.to_string()));
                 }
             }
         }
 
-        Ok(failed_files)
+        Ok((failed_files, space_freed))
+    }
+
+    /// 删除超过本地缓存天数的视频副本（存储后端中仍保留，回放时拉回）
+    async fn evict_local_videos(&self) -> Result<()> {
+        let cache_days = self.media.local_cache_days().await;
+        if cache_days == 0 {
+            return Ok(());
+        }
+
+        let cutoff = crate::storage::local_now() - ChronoDuration::days(cache_days as i64);
+        let mut evicted = 0;
+        for session in self.db.get_old_sessions(cutoff).await? {
+            let Some(video_path) = &session.video_path else {
+                continue;
+            };
+            match self
+                .media
+                .evict_local_video(std::path::Path::new(video_path))
+                .await
+            {
+                Ok(true) => evicted += 1,
+                Ok(false) => {}
+                Err(e) => warn!("清理本地视频副本失败 {}: {}", video_path, e),
+            }
+        }
+
+        if evicted > 0 {
+            info!("已删除 {} 个超过 {} 天的本地视频副本", evicted, cache_days);
+        }
+        Ok(())
+    }
+
+    /// 切换媒体存储后端前把本地缓存清理掉的视频从当前后端拉回，返回拉回的数量
+    ///
+    /// 切换后旧后端不再被读取，这些视频会无法回放；任一视频拉不回来时返回错误，由调用方取消切换
+    pub async fn pull_back_evicted_videos(&self) -> Result<usize> {
+        if !self.media.is_remote().await {
+            return Ok(0);
+        }
+        let mut pulled = 0;
+        let mut failed = Vec::new();
+        for session in self.db.get_all_sessions().await? {
+            let Some(video_path) = session.video_path.as_deref().filter(|_| !session.archived)
+            else {
+                continue;
+            };
+            match self.media.pull_back_video(Path::new(video_path)).await {
+                Ok(true) => pulled += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("拉回视频失败 {}: {}", video_path, e);
+                    failed.push(video_path.to_string());
+                }
+            }
+        }
+        if let Some(first) = failed.first() {
+            return Err(anyhow::anyhow!(
+                "{} 个视频无法从当前存储后端拉回（如 {}），请在存储后端可用时再切换",
+                failed.len(),
+                first
+            ));
+        }
+        if pulled > 0 {
+            info!("切换存储后端前已拉回 {} 个视频", pulled);
+        }
+        Ok(pulled)
     }
 
     /// 清理孤立文件（数据库中没有记录的文件）
     async fn cleanup_orphaned_files(&self) -> Result<()> {
+        let protected = self.pinned_file_paths().await?;
+
         // 清理frames目录中的孤立文件
         if self.frames_dir.exists() {
-            self.cleanup_orphaned_in_dir(&self.frames_dir).await?;
+            self.cleanup_orphaned_in_dir(&self.frames_dir, &protected)
+                .await?;
         }
 
         // 清理videos目录中的孤立文件
         if self.videos_dir.exists() {
-            self.cleanup_orphaned_in_dir(&self.videos_dir).await?;
+            self.cleanup_orphaned_in_dir(&self.videos_dir, &protected)
+                .await?;
         }
 
         Ok(())
     }
 
+    /// 置顶会话的文件（视频、视频元数据和截图），清理孤立文件时跳过
+    async fn pinned_file_paths(&self) -> Result<HashSet<PathBuf>> {
+        let mut paths = HashSet::new();
+        for session in self.db.get_pinned_sessions().await? {
+            if let Some(video_path) = &session.video_path {
+                let video_path = PathBuf::from(video_path);
+                paths.insert(crate::video::VideoMetadata::sidecar_path(&video_path));
+                paths.insert(video_path);
+            }
+            if let Some(session_id) = session.id {
+                for frame in self.db.get_frames_by_session(session_id).await? {
+                    paths.insert(PathBuf::from(frame.file_path));
+                }
+            }
+        }
+        Ok(paths)
+    }
+
     /// 清理指定目录中的孤立文件
-    async fn cleanup_orphaned_in_dir(&self, dir: &PathBuf) -> Result<()> {
+    async fn cleanup_orphaned_in_dir(
+        &self,
+        dir: &PathBuf,
+        protected: &HashSet<PathBuf>,
+    ) -> Result<()> {
+        // 按最长的保留期判断，避免按类别延长保留的会话文件被当作孤立文件删除
         let retention_secs = {
             let retention_days = *self.retention_days.read().await;
-            let clamped_days = retention_days.max(0) as u64;
+            let longest_days = self
+                .retention_rules
+                .read()
+                .await
+                .iter()
+                .map(|rule| rule.retention_days)
+                .fold(retention_days, i64::max);
+            let clamped_days = longest_days.max(0) as u64;
             clamped_days.saturating_mul(86_400)
         };
 
@@ -201,6 +783,16 @@ impl StorageCleaner {
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
 
+            // 存储标记文件用于检测网络共享是否在线，不能当作孤立文件删除
+            if entry.file_name() == super::availability::MARKER_FILE {
+                continue;
+            }
+
+            // 置顶会话的文件始终保留
+            if protected.contains(&path) {
+                continue;
+            }
+
             // 使用异步方法获取文件元数据，而不是同步的 is_file()
             if let Ok(metadata) = tokio::fs::metadata(&path).await {
                 // 检查是否是文件（而不是目录）
@@ -210,8 +802,17 @@ impl StorageCleaner {
                             .duration_since(modified)
                             .unwrap_or_default();
 
-                        // 如果文件超过保留期限，删除它
-                        if age.as_secs() > retention_secs {
+                        // 崩溃或强制退出时遗留的 .part 视频（编码最长 10 分钟）超过 1 小时即删除，
+                        // 其他文件超过保留期限时删除
+                        let is_partial = path
+                            .to_string_lossy()
+                            .ends_with(crate::video::processor::PARTIAL_SUFFIX);
+                        let limit_secs = if is_partial {
+                            STALE_PARTIAL_SECS
+                        } else {
+                            retention_secs
+                        };
+                        if age.as_secs() > limit_secs {
                             if let Err(e) = tokio::fs::remove_file(&path).await {
                                 error!("删除孤立文件失败 {:?}: {}", path, e);
                             } else {
@@ -227,7 +828,7 @@ impl StorageCleaner {
     }
 
     /// 手动触发清理
-    pub async fn trigger_cleanup(&self) -> Result<()> {
+    pub async fn trigger_cleanup(&self) -> Result<CleanupOutcome> {
         info!("手动触发存储清理");
         self.perform_cleanup().await
     }
@@ -251,6 +852,89 @@ impl StorageCleaner {
         })
     }
 
+    /// 按日期和类型统计存储占用，并列出占用最大的会话
+    ///
+    /// 数据库没有按行统计大小，各天的数据库占用按当天记录数（会话 + 帧）占比分摊
+    pub async fn get_storage_breakdown(&self, largest_limit: usize) -> Result<StorageBreakdown> {
+        let (_, _, db_size) = self.db.get_stats().await?;
+        let sessions = self.all_session_storage().await?;
+        Ok(build_storage_breakdown(sessions, db_size, largest_limit))
+    }
+
+    /// 占用最大的会话（按视频、截图和数据库行的合计降序）
+    pub async fn get_largest_sessions(&self, limit: usize) -> Result<Vec<SessionStorage>> {
+        let mut sessions = self.all_session_storage().await?;
+        sessions.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes));
+        sessions.truncate(limit);
+        Ok(sessions)
+    }
+
+    /// 单个会话的存储占用
+    pub async fn get_session_storage(&self, session: &Session) -> Result<SessionStorage> {
+        let usage = match session.id {
+            Some(session_id) => self.db.get_session_row_usage(Some(session_id)).await?,
+            None => Vec::new(),
+        };
+        self.session_storage(session, usage.first()).await
+    }
+
+    async fn all_session_storage(&self) -> Result<Vec<SessionStorage>> {
+        let usage: HashMap<i64, SessionRowUsage> = self
+            .db
+            .get_session_row_usage(None)
+            .await?
+            .into_iter()
+            .map(|usage| (usage.session_id, usage))
+            .collect();
+
+        let mut sessions = Vec::new();
+        for session in self.db.get_all_sessions().await? {
+            let Some(session_id) = session.id else {
+                continue;
+            };
+            sessions.push(
+                self.session_storage(&session, usage.get(&session_id))
+                    .await?,
+            );
+        }
+        Ok(sessions)
+    }
+
+    /// 统计会话的视频、仍在磁盘上的截图和数据库行占用
+    async fn session_storage(
+        &self,
+        session: &Session,
+        usage: Option<&SessionRowUsage>,
+    ) -> Result<SessionStorage> {
+        let session_id = session.id.unwrap_or_default();
+        let frame_paths: Vec<String> = self
+            .db
+            .get_frames_by_session(session_id)
+            .await?
+            .into_iter()
+            .map(|f| f.file_path)
+            .collect();
+        let frames_bytes = files_size(&frame_paths).await;
+        let videos_bytes = match &session.video_path {
+            Some(video_path) => files_size(&[video_path.clone()]).await,
+            None => 0,
+        };
+        let (database_rows, database_bytes) =
+            usage.map_or((0, 0), |usage| (usage.row_count, usage.bytes));
+
+        Ok(SessionStorage {
+            session_id,
+            title: session.title.clone(),
+            date: session.start_time.format("%Y-%m-%d").to_string(),
+            frame_count: frame_paths.len() as i64,
+            frames_bytes,
+            videos_bytes,
+            database_rows,
+            database_bytes,
+            total_bytes: frames_bytes + videos_bytes + database_bytes,
+        })
+    }
+
     /// 计算目录大小
     async fn calculate_dir_size(&self, dir: &PathBuf) -> Result<i64> {
         if !dir.exists() {
@@ -278,6 +962,240 @@ pub struct SessionFiles {
     pub video_path: Option<String>,
 }
 
+/// 类别规则允许的最大保留天数
+const MAX_RULE_RETENTION_DAYS: i64 = 365;
+
+/// 从归档恢复的视频在本地保留的时长，之后再次过期时只删除本地文件
+const RESTORED_KEEP: Duration = Duration::from_secs(24 * 3600);
+
+/// 空会话至少开始多久后才清理（刚创建、仍在处理的会话可能暂时没有卡片）
+const GARBAGE_MIN_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// 遗留的 .part 视频超过该秒数后删除
+const STALE_PARTIAL_SECS: u64 = 3600;
+
+/// 过期会话的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveDecision {
+    /// 移到归档目标，保留会话记录
+    Archive,
+    /// 本轮保留（视频暂时拉不回来或刚从归档恢复），下一轮再判断
+    Skip,
+    /// 已有归档副本但本地视频已不存在，恢复已归档状态
+    MarkArchived,
+    /// 未启用归档，或没有视频可归档，删除会话和文件
+    Delete,
+}
+
+/// 过期会话
+struct ExpiredSession {
+    session_id: i64,
+    session: Session,
+    category: Option<String>,
+    retention_days: i64,
+    files: SessionFiles,
+}
+
+/// 保留策略预览中的会话
+#[derive(Debug, serde::Serialize)]
+pub struct RetentionPreviewItem {
+    pub session_id: i64,
+    pub title: String,
+    #[serde(serialize_with = "super::models::serialize_datetime_as_local")]
+    pub start_time: DateTime<Utc>,
+    /// 会话的主要类别（没有卡片时为空）
+    pub category: Option<String>,
+    /// 适用的保留天数
+    pub retention_days: i64,
+    /// 关联文件大小
+    pub bytes: i64,
+}
+
+/// 空会话（没有卡片、截图和视频）
+#[derive(Debug, serde::Serialize)]
+pub struct GarbageSession {
+    pub session_id: i64,
+    pub title: String,
+    #[serde(serialize_with = "super::models::serialize_datetime_as_local")]
+    pub start_time: DateTime<Utc>,
+    #[serde(serialize_with = "super::models::serialize_datetime_as_local")]
+    pub end_time: DateTime<Utc>,
+    /// 记录的视频路径（文件已不存在）
+    pub video_path: Option<String>,
+}
+
+/// 本地视频是否存在；所在目录不可访问（网络共享断开）时无法判断，按存在处理
+async fn local_video_exists(path: &Path) -> bool {
+    match path.parent() {
+        Some(dir) if !crate::storage::availability::is_available(dir).await => true,
+        _ => tokio::fs::try_exists(path).await.unwrap_or(true),
+    }
+}
+
+/// 会话的视频是否还在
+///
+/// 没有记录视频路径时视为没有视频；使用远程存储后端时本地副本可能已被清理，记录了路径就视为存在
+fn has_video(video_path: Option<&str>, local_exists: bool, remote: bool) -> bool {
+    match video_path.map(str::trim) {
+        None | Some("") => false,
+        Some(_) => local_exists || remote,
+    }
+}
+
+/// 保留策略预览
+#[derive(Debug, serde::Serialize)]
+pub struct RetentionPreview {
+    pub total_sessions: usize,
+    pub total_bytes: i64,
+    pub sessions: Vec<RetentionPreviewItem>,
+}
+
+/// 校验类别保留规则
+pub fn validate_rules(rules: &[CategoryRetentionRule]) -> Result<()> {
+    for rule in rules {
+        if rule.category.trim().is_empty() {
+            return Err(anyhow::anyhow!("保留规则的类别不能为空"));
+        }
+        if rule.retention_days < 1 || rule.retention_days > MAX_RULE_RETENTION_DAYS {
+            return Err(anyhow::anyhow!(
+                "类别 {} 的保留天数必须在 1 到 {} 天之间",
+                rule.category,
+                MAX_RULE_RETENTION_DAYS
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 校验冷存储归档设置：启用时归档目标必须可用（本地目录、S3/WebDAV 配置完整）
+pub fn validate_archive_settings(settings: &ArchiveSettings) -> Result<()> {
+    if settings.enabled {
+        archive::archive_store(settings)?;
+    }
+    Ok(())
+}
+
+/// 会话的主要类别（按卡片时长累计，时间无法解析的卡片按 1 秒计）
+fn dominant_category(cards: &[TimelineCardRecord]) -> Option<String> {
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    for card in cards {
+        let seconds = match (
+            chrono::DateTime::parse_from_rfc3339(&card.start_time),
+            chrono::DateTime::parse_from_rfc3339(&card.end_time),
+        ) {
+            (Ok(start), Ok(end)) => (end - start).num_seconds().max(1),
+            _ => 1,
+        };
+        *totals.entry(card.category.to_lowercase()).or_default() += seconds;
+    }
+
+    totals
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(category, _)| category)
+}
+
+/// 类别适用的保留天数（没有匹配规则时使用全局保留天数）
+fn retention_days_for(
+    category: Option<&str>,
+    default_days: i64,
+    rules: &[CategoryRetentionRule],
+) -> i64 {
+    category
+        .and_then(|category| {
+            rules
+                .iter()
+                .find(|rule| rule.category.eq_ignore_ascii_case(category))
+        })
+        .map(|rule| rule.retention_days)
+        .unwrap_or(default_days)
+}
+
+/// 汇总每日存储占用（日期升序），会话按占用降序截取前 largest_limit 个
+fn build_storage_breakdown(
+    sessions: Vec<SessionStorage>,
+    db_size: i64,
+    largest_limit: usize,
+) -> StorageBreakdown {
+    let mut by_date: BTreeMap<String, DayStorage> = BTreeMap::new();
+    for session in &sessions {
+        let day = by_date
+            .entry(session.date.clone())
+            .or_insert_with(|| DayStorage {
+                date: session.date.clone(),
+                ..Default::default()
+            });
+        day.session_count += 1;
+        day.frame_count += session.frame_count;
+        day.frames_bytes += session.frames_bytes;
+        day.videos_bytes += session.videos_bytes;
+    }
+
+    let total_records: i64 = by_date
+        .values()
+        .map(|d| d.session_count + d.frame_count)
+        .sum();
+    let mut days: Vec<DayStorage> = by_date.into_values().collect();
+    for day in &mut days {
+        if total_records > 0 {
+            day.database_bytes = db_size * (day.session_count + day.frame_count) / total_records;
+        }
+        day.total_bytes = day.frames_bytes + day.videos_bytes + day.database_bytes;
+    }
+
+    let mut largest_sessions = sessions;
+    largest_sessions.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes));
+    largest_sessions.truncate(largest_limit);
+
+    StorageBreakdown {
+        oldest_date: days.first().map(|d| d.date.clone()),
+        days,
+        largest_sessions,
+    }
+}
+
+/// 某一天的存储占用
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct DayStorage {
+    /// 日期（YYYY-MM-DD）
+    pub date: String,
+    pub session_count: i64,
+    pub frame_count: i64,
+    pub frames_bytes: i64,
+    pub videos_bytes: i64,
+    /// 按记录数分摊的数据库占用（估算值）
+    pub database_bytes: i64,
+    pub total_bytes: i64,
+}
+
+/// 单个会话的存储占用
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionStorage {
+    pub session_id: i64,
+    pub title: String,
+    /// 会话开始日期（YYYY-MM-DD）
+    pub date: String,
+    pub frame_count: i64,
+    pub frames_bytes: i64,
+    pub videos_bytes: i64,
+    /// 数据库中的行数（会话、截图、LLM 调用、视频分段和时间线卡片）
+    pub database_rows: i64,
+    /// 数据库行的文本字段字节数（不含索引和页开销）
+    pub database_bytes: i64,
+    pub total_bytes: i64,
+}
+
+/// 按日期和类型拆分的存储占用
+#[derive(Debug, serde::Serialize)]
+pub struct StorageBreakdown {
+    /// 每日占用（日期升序）
+    pub days: Vec<DayStorage>,
+    /// 最早的数据日期
+    pub oldest_date: Option<String>,
+    /// 占用最大的会话（降序）
+    pub largest_sessions: Vec<SessionStorage>,
+}
+
 /// 清理结果
 #[derive(Debug, Default)]
 pub struct CleanupResult {
@@ -289,6 +1207,25 @@ pub struct CleanupResult {
     pub failed_files: Vec<(String, String)>,
 }
 
+/// 一轮清理的结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanupOutcome {
+    /// 删除的会话数（不含归档）
+    pub sessions_deleted: usize,
+    /// 释放的本地空间（字节）
+    pub space_freed: u64,
+}
+
+impl CleanupOutcome {
+    /// 发布 StorageCleaned 事件
+    pub fn publish(&self, event_bus: &EventBus) {
+        event_bus.publish(AppEvent::StorageCleaned {
+            sessions_deleted: self.sessions_deleted,
+            space_freed: self.space_freed,
+        });
+    }
+}
+
 /// 存储统计信息
 #[derive(Debug, serde::Serialize)]
 pub struct StorageStats {
@@ -300,3 +1237,268 @@ pub struct StorageStats {
     pub total_size: i64,
     pub retention_days: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(category: &str, start: &str, end: &str) -> TimelineCardRecord {
+        TimelineCardRecord {
+            id: None,
+            session_id: 1,
+            llm_call_id: None,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            category: category.to_string(),
+            subcategory: String::new(),
+            title: String::new(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: Utc::now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        }
+    }
+
+    #[test]
+    fn test_has_video() {
+        assert!(!has_video(None, false, false));
+        assert!(!has_video(Some("  "), false, true));
+        // 视频文件已丢失
+        assert!(!has_video(Some("/videos/a.mp4"), false, false));
+        assert!(has_video(Some("/videos/a.mp4"), true, false));
+        // 远程存储后端中可能还有
+        assert!(has_video(Some("/videos/a.mp4"), false, true));
+    }
+
+    #[test]
+    fn test_dominant_category_weights_by_duration() {
+        let cards = vec![
+            card(
+                "Work",
+                "2025-10-09T09:00:00+08:00",
+                "2025-10-09T09:10:00+08:00",
+            ),
+            card(
+                "entertainment",
+                "2025-10-09T09:10:00+08:00",
+                "2025-10-09T09:13:00+08:00",
+            ),
+            card(
+                "entertainment",
+                "2025-10-09T09:13:00+08:00",
+                "2025-10-09T09:15:00+08:00",
+            ),
+        ];
+        assert_eq!(dominant_category(&cards).as_deref(), Some("work"));
+        assert_eq!(dominant_category(&[]), None);
+    }
+
+    #[test]
+    fn test_retention_days_for_category() {
+        let rules = vec![
+            CategoryRetentionRule {
+                category: "work".to_string(),
+                retention_days: 90,
+            },
+            CategoryRetentionRule {
+                category: "entertainment".to_string(),
+                retention_days: 7,
+            },
+        ];
+        assert_eq!(retention_days_for(Some("work"), 14, &rules), 90);
+        assert_eq!(retention_days_for(Some("learning"), 14, &rules), 14);
+        assert_eq!(retention_days_for(None, 14, &rules), 14);
+    }
+
+    #[test]
+    fn test_invalid_archive_settings_are_rejected() {
+        use crate::models::MediaBackend;
+
+        let settings = |target: MediaBackend, local_dir: &str| ArchiveSettings {
+            enabled: true,
+            target,
+            local_dir: local_dir.to_string(),
+            ..ArchiveSettings::default()
+        };
+        assert!(validate_archive_settings(&settings(MediaBackend::Local, "  ")).is_err());
+        assert!(validate_archive_settings(&settings(MediaBackend::S3, "")).is_err());
+        assert!(validate_archive_settings(&settings(MediaBackend::Local, "/archive")).is_ok());
+        // 关闭时不校验目标
+        assert!(validate_archive_settings(&ArchiveSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn test_storage_breakdown_groups_by_day() {
+        let session = |id: i64, date: &str, frames: i64, bytes: i64| SessionStorage {
+            session_id: id,
+            title: String::new(),
+            date: date.to_string(),
+            frame_count: frames,
+            frames_bytes: bytes,
+            videos_bytes: 0,
+            database_rows: frames + 1,
+            database_bytes: 0,
+            total_bytes: bytes,
+        };
+        let breakdown = build_storage_breakdown(
+            vec![
+                session(1, "2025-10-09", 9, 100),
+                session(2, "2025-10-08", 4, 300),
+                session(3, "2025-10-09", 4, 50),
+            ],
+            200,
+            2,
+        );
+
+        assert_eq!(breakdown.oldest_date.as_deref(), Some("2025-10-08"));
+        assert_eq!(breakdown.days.len(), 2);
+        assert_eq!(breakdown.days[1].frames_bytes, 150);
+        assert_eq!(breakdown.days[0].database_bytes, 50);
+        assert_eq!(breakdown.days[1].database_bytes, 150);
+        let ids: Vec<i64> = breakdown
+            .largest_sessions
+            .iter()
+            .map(|s| s.session_id)
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_restored_session_survives_cleanup() {
+        let root = tempfile::tempdir().unwrap();
+        let frames_dir = root.path().join("frames");
+        let videos_dir = root.path().join("videos");
+        let archive_dir = root.path().join("archive");
+        for dir in [&frames_dir, &videos_dir, &archive_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        crate::storage::availability::ensure_marker(&frames_dir, true).unwrap();
+        crate::storage::availability::ensure_marker(&videos_dir, true).unwrap();
+
+        let db = Arc::new(
+            Database::new_sqlite(&root.path().join("data.db").to_string_lossy())
+                .await
+                .unwrap(),
+        );
+        let video_path = videos_dir.join("session_1.mp4");
+        std::fs::write(&video_path, b"video").unwrap();
+        let start = crate::storage::local_now() - ChronoDuration::days(30);
+        let session_id = db
+            .insert_session(&Session {
+                id: None,
+                start_time: start,
+                end_time: start + ChronoDuration::minutes(15),
+                title: String::new(),
+                summary: String::new(),
+                video_path: Some(video_path.to_string_lossy().into_owned()),
+                tags: "[]".to_string(),
+                created_at: None,
+                device_name: None,
+                device_type: None,
+                pinned: false,
+                archived: false,
+                archive_key: None,
+            })
+            .await
+            .unwrap();
+
+        let media = Arc::new(MediaLibrary::new(root.path().to_path_buf()));
+        let cleaner = StorageCleaner::new(db.clone(), frames_dir, videos_dir, media);
+        cleaner
+            .set_archive_settings(ArchiveSettings {
+                enabled: true,
+                local_dir: archive_dir.to_string_lossy().into_owned(),
+                ..ArchiveSettings::default()
+            })
+            .await
+            .unwrap();
+
+        cleaner.perform_cleanup().await.unwrap();
+        let archived = db.get_session(session_id).await.unwrap();
+        assert!(archived.archived);
+        assert!(!video_path.exists());
+
+        cleaner.restore_archived_session(&archived).await.unwrap();
+        assert!(video_path.exists());
+
+        // 恢复的视频仍在保留期内：再次清理时保留会话和视频，归档副本也不受影响
+        let outcome = cleaner.perform_cleanup().await.unwrap();
+        assert_eq!(outcome.sessions_deleted, 0);
+        let restored = db.get_session(session_id).await.unwrap();
+        assert!(!restored.archived);
+        assert!(video_path.exists());
+        let key = restored.archive_key.unwrap();
+        assert!(archive_dir.join(key).exists());
+    }
+
+    #[tokio::test]
+    async fn test_garbage_sweep_skips_foreign_and_offline_sessions() {
+        let root = tempfile::tempdir().unwrap();
+        let frames_dir = root.path().join("frames");
+        let videos_dir = root.path().join("videos");
+        for dir in [&frames_dir, &videos_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+            crate::storage::availability::ensure_marker(dir, true).unwrap();
+        }
+
+        let db = Arc::new(
+            Database::new_sqlite(&root.path().join("data.db").to_string_lossy())
+                .await
+                .unwrap(),
+        );
+        let start = crate::storage::local_now() - ChronoDuration::days(3);
+        let insert = |video_path: std::path::PathBuf, device_name: Option<&str>| {
+            let db = db.clone();
+            let device_name = device_name.map(str::to_string);
+            async move {
+                db.insert_session(&Session {
+                    id: None,
+                    start_time: start,
+                    end_time: start + ChronoDuration::minutes(15),
+                    title: String::new(),
+                    summary: String::new(),
+                    video_path: Some(video_path.to_string_lossy().into_owned()),
+                    tags: "[]".to_string(),
+                    created_at: None,
+                    device_name,
+                    device_type: None,
+                    pinned: false,
+                    archived: false,
+                    archive_key: None,
+                })
+                .await
+                .unwrap()
+            }
+        };
+        // 其他设备的会话（视频在那台机器上）、断开的网络共享上的会话、本机视频已丢失的会话
+        insert(videos_dir.join("foreign.mp4"), Some("other-machine")).await;
+        insert(root.path().join("share/videos/offline.mp4"), None).await;
+        let missing = insert(videos_dir.join("missing.mp4"), None).await;
+
+        let media = Arc::new(MediaLibrary::new(root.path().to_path_buf()));
+        let cleaner = StorageCleaner::new(db.clone(), frames_dir, videos_dir, media);
+        let garbage: Vec<i64> = cleaner
+            .preview_garbage_sessions()
+            .await
+            .unwrap()
+            .iter()
+            .map(|s| s.session_id)
+            .collect();
+        assert_eq!(garbage, vec![missing]);
+
+        // 清理走回收站，撤销窗口内可以恢复
+        assert_eq!(cleaner.prune_garbage_sessions().await.unwrap(), 1);
+        let pending = db.get_pending_deletions().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].session_id, missing);
+        cleaner.undo_session_deletion(missing).await.unwrap();
+    }
+}
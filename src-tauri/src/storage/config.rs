@@ -25,9 +25,64 @@ pub enum DatabaseConfig {
         username: String,
         /// 密码
         password: String,
+        /// 表前缀（多人共用一个数据库时用于区分各自的表，如 "alice_"）
+        #[serde(default)]
+        table_prefix: String,
+        /// 从该前缀的表迁移数据（当前前缀的表中还没有数据时复制；空字符串表示无前缀的表）
+        #[serde(default)]
+        migrate_from_prefix: Option<String>,
+        /// 连接池、重连和本地队列设置
+        #[serde(default)]
+        connection: MariaDbConnectionSettings,
     },
 }
 
+/// MariaDB 连接池、重连和本地队列设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MariaDbConnectionSettings {
+    /// 连接池最大连接数
+    pub max_connections: u32,
+    /// 连接池最小连接数
+    pub min_connections: u32,
+    /// 建立连接的超时时间（秒）
+    pub connect_timeout_secs: u64,
+    /// 从连接池获取连接的超时时间（秒）
+    pub acquire_timeout_secs: u64,
+    /// 空闲连接的回收时间（秒）
+    pub idle_timeout_secs: u64,
+    /// 连接的最长存活时间（秒）
+    pub max_lifetime_secs: u64,
+    /// 启动时连接失败的重试次数
+    pub connect_retries: u32,
+    /// 连续多少次连接错误后切换到本地队列
+    pub failure_threshold: u32,
+    /// 重连的初始间隔（秒），每次失败后翻倍
+    pub retry_base_secs: u64,
+    /// 重连的最长间隔（秒）
+    pub retry_max_secs: u64,
+    /// 远程数据库不可用时暂存数据的本地 SQLite 路径（相对路径位于数据目录下；空字符串表示不启用）
+    pub local_queue_path: String,
+}
+
+impl Default for MariaDbConnectionSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 20,
+            min_connections: 2,
+            connect_timeout_secs: 30,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 180,
+            max_lifetime_secs: 1800,
+            connect_retries: 3,
+            failure_threshold: 3,
+            retry_base_secs: 5,
+            retry_max_secs: 300,
+            local_queue_path: "data/mariadb-queue.db".to_string(),
+        }
+    }
+}
+
 impl Default for DatabaseConfig {
     fn default() -> Self {
         DatabaseConfig::SQLite {
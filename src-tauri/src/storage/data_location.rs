@@ -0,0 +1,419 @@
+// 数据目录位置 - 支持把截图、视频和数据库迁移到其他磁盘
+//
+// 配置文件和实例锁始终留在系统应用数据目录，数据目录的实际位置记录在
+// 应用数据目录下的 data_location.json 中。迁移分两步完成：
+// 1. 运行中先把已有文件复制到新目录（可显示进度），写入 pending_from 后重启
+// 2. 启动时在打开数据库前补齐增量文件和 data.db，数据库打开后批量改写文件路径，
+//    最后删除旧目录中的数据并清除 pending_from
+// 任一步骤失败时旧数据保持不动，下次启动会重新尝试
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// 位置记录文件名（位于应用数据目录）
+const LOCATION_FILE: &str = "data_location.json";
+
+/// 需要迁移的子目录
+pub const DATA_SUBDIRS: [&str; 3] = ["frames", "videos", "llm_debug"];
+
+/// 默认 SQLite 数据库文件（包括 WAL 模式的附属文件）
+pub const DATABASE_FILES: [&str; 3] = ["data.db", "data.db-wal", "data.db-shm"];
+
+/// 写入探测允许的最大延迟（毫秒），超过说明磁盘或网络共享过慢
+const MAX_PROBE_LATENCY_MS: u64 = 2000;
+
+/// 每复制多少个文件上报一次进度
+const PROGRESS_EVERY_FILES: u64 = 50;
+
+/// 数据目录位置记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataLocation {
+    /// 自定义数据目录，None 表示使用应用数据目录
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// 迁移来源目录（重启后需要完成迁移时设置）
+    #[serde(default)]
+    pub pending_from: Option<PathBuf>,
+}
+
+impl DataLocation {
+    /// 读取位置记录，文件不存在或损坏时返回默认值
+    pub fn load(app_dir: &Path) -> Self {
+        let path = app_dir.join(LOCATION_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("解析数据目录记录失败，使用默认目录: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 保存位置记录（先写临时文件再重命名，避免写到一半）
+    pub fn save(&self, app_dir: &Path) -> Result<()> {
+        let path = app_dir.join(LOCATION_FILE);
+        let tmp_path = app_dir.join(format!("{}.tmp", LOCATION_FILE));
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// 当前生效的数据目录
+    pub fn resolve(&self, app_dir: &Path) -> PathBuf {
+        self.data_dir
+            .clone()
+            .unwrap_or_else(|| app_dir.to_path_buf())
+    }
+}
+
+/// 当前生效的数据目录
+pub fn resolve_data_dir(app_dir: &Path) -> PathBuf {
+    DataLocation::load(app_dir).resolve(app_dir)
+}
+
+/// 目标目录检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDirCheck {
+    /// 检查的目录
+    pub path: String,
+    /// 是否可写
+    pub writable: bool,
+    /// 写入+同步+读回的耗时（毫秒）
+    pub latency_ms: Option<u64>,
+    /// 可用空间（字节），无法获取时为 None
+    pub free_bytes: Option<u64>,
+    /// 当前数据占用的空间（字节）
+    pub required_bytes: u64,
+    /// 是否可以迁移到该目录
+    pub ok: bool,
+    /// 检查结论
+    pub message: String,
+}
+
+/// 检查目标目录是否适合作为数据目录（可写、延迟可接受、空间足够）
+pub fn check_data_directory(target: &Path, current: &Path) -> DataDirCheck {
+    let required_bytes = data_size(current);
+    let mut check = DataDirCheck {
+        path: target.to_string_lossy().to_string(),
+        writable: false,
+        latency_ms: None,
+        free_bytes: None,
+        required_bytes,
+        ok: false,
+        message: String::new(),
+    };
+
+    if let Err(e) = validate_target_path(target, current) {
+        check.message = e.to_string();
+        return check;
+    }
+    if target.join(DATABASE_FILES[0]).exists() {
+        check.message = "目标目录已包含数据库文件，请选择空目录".to_string();
+        return check;
+    }
+
+    match probe_write(target) {
+        Ok(latency_ms) => {
+            check.writable = true;
+            check.latency_ms = Some(latency_ms);
+        }
+        Err(e) => {
+            check.message = format!("目录不可写: {}", e);
+            return check;
+        }
+    }
+
+    check.free_bytes = available_space(target);
+
+    let latency_ms = check.latency_ms.unwrap_or_default();
+    if latency_ms > MAX_PROBE_LATENCY_MS {
+        check.message = format!("写入延迟过高（{} ms），不适合存放截图和视频", latency_ms);
+    } else if check.free_bytes.is_some_and(|free| free < required_bytes) {
+        check.message = format!(
+            "可用空间不足：需要 {} MB，仅剩 {} MB",
+            required_bytes / 1024 / 1024,
+            check.free_bytes.unwrap_or_default() / 1024 / 1024
+        );
+    } else {
+        check.ok = true;
+        check.message = "目录可用".to_string();
+    }
+
+    check
+}
+
+/// 目标路径必须是绝对路径，且不能与当前数据目录互相嵌套
+fn validate_target_path(target: &Path, current: &Path) -> Result<()> {
+    if !target.is_absolute() {
+        return Err(anyhow!("请选择绝对路径"));
+    }
+    if target == current {
+        return Err(anyhow!("新目录与当前数据目录相同"));
+    }
+    if target.starts_with(current) || current.starts_with(target) {
+        return Err(anyhow!(
+            "新目录不能位于当前数据目录内部，也不能包含当前数据目录"
+        ));
+    }
+    Ok(())
+}
+
+/// 写入探测文件并读回，返回耗时
+fn probe_write(target: &Path) -> Result<u64> {
+    std::fs::create_dir_all(target).context("创建目录失败")?;
+
+    let probe_path = target.join(".screen-analyzer-probe");
+    let payload = vec![0x5a_u8; 256 * 1024];
+    let started = Instant::now();
+
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&probe_path)?;
+        file.write_all(&payload)?;
+        file.sync_all()?;
+
+        let mut read_back = Vec::with_capacity(payload.len());
+        std::fs::File::open(&probe_path)?.read_to_end(&mut read_back)?;
+        if read_back != payload {
+            return Err(anyhow!("读回内容不一致"));
+        }
+        Ok(())
+    })();
+
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let _ = std::fs::remove_file(&probe_path);
+    result.map(|_| latency_ms)
+}
+
+/// 目标路径所在磁盘的可用空间（取挂载点最长匹配的磁盘）
+pub fn available_space(target: &Path) -> Option<u64> {
+    let target = target
+        .canonicalize()
+        .unwrap_or_else(|_| target.to_path_buf());
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| target.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// 当前数据目录中需要迁移的数据大小
+pub fn data_size(data_dir: &Path) -> u64 {
+    let files = DATA_SUBDIRS
+        .iter()
+        .flat_map(|dir| collect_files(&data_dir.join(dir)))
+        .map(|(_, size)| size)
+        .sum::<u64>();
+    let database = DATABASE_FILES
+        .iter()
+        .filter_map(|name| std::fs::metadata(data_dir.join(name)).ok())
+        .map(|meta| meta.len())
+        .sum::<u64>();
+    files + database
+}
+
+/// 递归列出目录下的所有文件及大小
+pub fn collect_files(dir: &Path) -> Vec<(PathBuf, u64)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else {
+                files.push((entry.path(), meta.len()));
+            }
+        }
+    }
+    files
+}
+
+/// 迁移进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationProgress {
+    pub copied_bytes: u64,
+    pub total_bytes: u64,
+    pub copied_files: u64,
+    pub total_files: u64,
+    /// 正在复制的文件
+    pub current: Option<String>,
+}
+
+/// 把截图、视频和调试归档复制到新目录（不含数据库）
+///
+/// 目标中已存在且大小相同的文件会跳过，因此可以重复执行以补齐增量
+pub fn copy_data(
+    from: &Path,
+    to: &Path,
+    mut on_progress: impl FnMut(&MigrationProgress),
+) -> Result<MigrationProgress> {
+    let files: Vec<(PathBuf, u64)> = DATA_SUBDIRS
+        .iter()
+        .flat_map(|dir| collect_files(&from.join(dir)))
+        .collect();
+
+    let mut progress = MigrationProgress {
+        copied_bytes: 0,
+        total_bytes: files.iter().map(|(_, size)| size).sum(),
+        copied_files: 0,
+        total_files: files.len() as u64,
+        current: None,
+    };
+    on_progress(&progress);
+
+    for (source, size) in files {
+        let relative = source.strip_prefix(from)?;
+        let dest = to.join(relative);
+
+        let up_to_date = std::fs::metadata(&dest)
+            .map(|meta| meta.len() == size)
+            .unwrap_or(false);
+        if !up_to_date {
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            copy_file(&source, &dest).with_context(|| format!("复制文件失败: {:?}", source))?;
+        }
+
+        progress.copied_bytes += size;
+        progress.copied_files += 1;
+        if progress.copied_files % PROGRESS_EVERY_FILES == 0 {
+            progress.current = Some(relative.to_string_lossy().to_string());
+            on_progress(&progress);
+        }
+    }
+
+    progress.current = None;
+    on_progress(&progress);
+    Ok(progress)
+}
+
+/// 先复制到临时文件再重命名，避免中断时留下半个文件
+fn copy_file(source: &Path, dest: &Path) -> Result<()> {
+    let mut tmp = dest.as_os_str().to_owned();
+    tmp.push(".partial");
+    let tmp = PathBuf::from(tmp);
+    std::fs::copy(source, &tmp)?;
+    std::fs::rename(&tmp, dest)?;
+    Ok(())
+}
+
+/// 启动时（打开数据库前）补齐待完成的迁移，返回生效的数据目录
+///
+/// 复制失败时回退到旧目录继续运行，下次启动再重试
+pub fn prepare_data_dir(app_dir: &Path) -> PathBuf {
+    let mut location = DataLocation::load(app_dir);
+    let (Some(from), Some(to)) = (location.pending_from.clone(), location.data_dir.clone()) else {
+        return location.resolve(app_dir);
+    };
+
+    info!("继续数据目录迁移: {:?} -> {:?}", from, to);
+    let result = copy_data(&from, &to, |_| {}).and_then(|_| copy_database(&from, &to));
+    match result {
+        Ok(()) => to,
+        Err(e) => {
+            warn!("数据目录迁移失败，继续使用旧目录 {:?}: {}", from, e);
+            location.data_dir = if from == app_dir {
+                None
+            } else {
+                Some(from.clone())
+            };
+            location.pending_from = None;
+            if let Err(e) = location.save(app_dir) {
+                warn!("回退数据目录记录失败: {}", e);
+            }
+            from
+        }
+    }
+}
+
+/// 复制默认 SQLite 数据库（此时数据库尚未打开）
+///
+/// 目标中已有数据库说明上次启动已复制过并可能写入了新数据，不再覆盖
+fn copy_database(from: &Path, to: &Path) -> Result<()> {
+    if to.join(DATABASE_FILES[0]).exists() {
+        return Ok(());
+    }
+    for name in DATABASE_FILES {
+        let source = from.join(name);
+        if source.exists() {
+            copy_file(&source, &to.join(name))
+                .with_context(|| format!("复制数据库文件失败: {:?}", source))?;
+        }
+    }
+    Ok(())
+}
+
+/// 数据库路径改写完成后删除旧目录中的数据并清除迁移标记
+pub fn finish_migration(app_dir: &Path) -> Result<()> {
+    let mut location = DataLocation::load(app_dir);
+    let Some(from) = location.pending_from.take() else {
+        return Ok(());
+    };
+
+    for dir in DATA_SUBDIRS {
+        let path = from.join(dir);
+        if path.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                warn!("删除旧数据目录失败 {:?}: {}", path, e);
+            }
+        }
+    }
+    for name in DATABASE_FILES {
+        let _ = std::fs::remove_file(from.join(name));
+    }
+
+    location.save(app_dir)?;
+    info!("数据目录迁移完成，已清理旧目录 {:?}", from);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_data_skips_up_to_date_files() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(from.path().join("frames/2025")).unwrap();
+        std::fs::write(from.path().join("frames/2025/a.jpg"), b"frame").unwrap();
+        std::fs::create_dir_all(from.path().join("videos")).unwrap();
+        std::fs::write(from.path().join("videos/b.mp4"), b"video!").unwrap();
+
+        let progress = copy_data(from.path(), to.path(), |_| {}).unwrap();
+        assert_eq!(progress.total_files, 2);
+        assert_eq!(progress.copied_bytes, 11);
+        assert_eq!(
+            std::fs::read(to.path().join("frames/2025/a.jpg")).unwrap(),
+            b"frame"
+        );
+
+        // 再次执行只补齐增量，已复制的文件保持不变
+        std::fs::write(to.path().join("videos/b.mp4"), b"VIDEO!").unwrap();
+        copy_data(from.path(), to.path(), |_| {}).unwrap();
+        assert_eq!(
+            std::fs::read(to.path().join("videos/b.mp4")).unwrap(),
+            b"VIDEO!"
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_nested_target() {
+        let current = Path::new("/data/screen-analyzer");
+        assert!(validate_target_path(Path::new("/data/screen-analyzer/sub"), current).is_err());
+        assert!(validate_target_path(Path::new("relative"), current).is_err());
+        assert!(validate_target_path(Path::new("/mnt/nas/screen-analyzer"), current).is_ok());
+    }
+}
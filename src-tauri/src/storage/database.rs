@@ -3,18 +3,33 @@
 use super::cache::CachedRepository;
 use super::config::DatabaseConfig;
 use super::models::*;
-use super::repository::{mariadb::MariaDbRepository, sqlite::SqliteRepository, DatabaseRepository};
+use super::repository::failover::{FailoverRepository, FailoverStatus};
+use super::repository::mariadb::{MariaDbOptions, MariaDbRepository};
+use super::repository::outbox::OutboxStatus;
+use super::repository::{sqlite::SqliteRepository, DatabaseRepository};
+use crate::domains::telemetry;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tracing::info;
 
+/// 故障切换检查间隔（秒）
+const FAILOVER_CHECK_SECS: u64 = 5;
+
+/// 卡片删除通知的缓冲数量
+const CARD_REMOVAL_CAPACITY: usize = 1024;
+
 /// 数据库管理器 - 对外统一接口
 pub struct Database {
     /// 底层仓库（带缓存）
     repository: Arc<CachedRepository>,
     /// 数据库类型标识
     db_type: String,
+    /// MariaDB 故障切换（启用本地队列时）
+    failover: Option<Arc<FailoverRepository>>,
+    /// 会话卡片被删除时广播会话ID（删除会话、重新分析）
+    card_removals: broadcast::Sender<i64>,
 }
 
 impl Database {
@@ -28,7 +43,22 @@ impl Database {
                 database,
                 username,
                 password,
-            } => Self::new_mariadb(host, *port, database, username, password).await,
+                table_prefix,
+                migrate_from_prefix,
+                connection,
+            } => {
+                Self::new_mariadb(&MariaDbOptions {
+                    host,
+                    port: *port,
+                    database,
+                    username,
+                    password,
+                    table_prefix,
+                    migrate_from_prefix: migrate_from_prefix.as_deref(),
+                    connection,
+                })
+                .await
+            }
         }
     }
 
@@ -40,30 +70,73 @@ impl Database {
         Ok(Self {
             repository: Arc::new(cached_repo),
             db_type: "sqlite".to_string(),
+            failover: None,
+            card_removals: broadcast::channel(CARD_REMOVAL_CAPACITY).0,
         })
     }
 
     /// 创建 MariaDB 数据库连接
-    pub async fn new_mariadb(
-        host: &str,
-        port: u16,
-        database: &str,
-        username: &str,
-        password: &str,
-    ) -> Result<Self> {
-        let mariadb_repo = MariaDbRepository::new(host, port, database, username, password).await?;
-        let cached_repo = CachedRepository::new(Arc::new(mariadb_repo));
+    ///
+    /// 设置了本地队列路径时，远程数据库不可用期间的读写转到本地队列，恢复后写回远程
+    pub async fn new_mariadb(options: &MariaDbOptions<'_>) -> Result<Self> {
+        let mariadb_repo = Arc::new(MariaDbRepository::new(options).await?);
+        let queue_path = options.connection.local_queue_path.trim();
+        if queue_path.is_empty() {
+            return Ok(Self {
+                repository: Arc::new(CachedRepository::new(mariadb_repo)),
+                db_type: "mariadb".to_string(),
+                failover: None,
+            });
+        }
+
+        let failover =
+            Arc::new(FailoverRepository::new(mariadb_repo, queue_path, options.connection).await?);
+        let repository = Arc::new(CachedRepository::new(failover.clone()));
+        Self::start_failover_monitor(&failover, &repository);
 
         Ok(Self {
-            repository: Arc::new(cached_repo),
+            repository,
             db_type: "mariadb".to_string(),
+            failover: Some(failover),
+            card_removals: broadcast::channel(CARD_REMOVAL_CAPACITY).0,
         })
     }
 
+    /// 定期检查故障切换状态，读写目标切换后清空缓存（数据库释放后结束）
+    fn start_failover_monitor(failover: &Arc<FailoverRepository>, cache: &Arc<CachedRepository>) {
+        let failover = Arc::downgrade(failover);
+        let cache = Arc::downgrade(cache);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(FAILOVER_CHECK_SECS)).await;
+                let (Some(failover), Some(cache)) = (failover.upgrade(), cache.upgrade()) else {
+                    break;
+                };
+                failover.check().await;
+                if failover.take_switched() {
+                    cache.clear_cache().await;
+                }
+            }
+        });
+    }
+
+    /// MariaDB 故障切换状态（未启用本地队列时返回 None）
+    pub fn failover_status(&self) -> Option<FailoverStatus> {
+        self.failover.as_ref().map(|failover| failover.status())
+    }
+
+    /// MariaDB 写入 outbox 状态（未启用本地队列时 enabled 为 false）
+    pub async fn outbox_status(&self) -> Result<OutboxStatus> {
+        match &self.failover {
+            Some(failover) => failover.outbox_status().await,
+            None => Ok(OutboxStatus::default()),
+        }
+    }
+
     // ========== 会话操作 ==========
 
     pub async fn insert_session(&self, session: &Session) -> Result<i64> {
-        self.repository.insert_session(session).await
+        telemetry::time_db("insert_session", self.repository.insert_session(session)).await
     }
 
     pub async fn insert_sessions(&self, sessions: &[Session]) -> Result<Vec<i64>> {
@@ -71,15 +144,23 @@ impl Database {
     }
 
     pub async fn get_session(&self, session_id: i64) -> Result<Session> {
-        self.repository.get_session(session_id).await
+        telemetry::time_db("get_session", self.repository.get_session(session_id)).await
     }
 
     pub async fn get_session_detail(&self, session_id: i64) -> Result<SessionDetail> {
-        self.repository.get_session_detail(session_id).await
+        telemetry::time_db(
+            "get_session_detail",
+            self.repository.get_session_detail(session_id),
+        )
+        .await
     }
 
     pub async fn get_sessions_by_date(&self, date: &str) -> Result<Vec<Session>> {
-        self.repository.get_sessions_by_date(date).await
+        telemetry::time_db(
+            "get_sessions_by_date",
+            self.repository.get_sessions_by_date(date),
+        )
+        .await
     }
 
     pub async fn get_all_sessions(&self) -> Result<Vec<Session>> {
@@ -94,15 +175,22 @@ impl Database {
         video_path: Option<&str>,
         tags: &str,
     ) -> Result<()> {
-        self.repository
-            .update_session(session_id, title, summary, video_path, tags)
-            .await
+        telemetry::time_db(
+            "update_session",
+            self.repository
+                .update_session(session_id, title, summary, video_path, tags),
+        )
+        .await
     }
 
     pub async fn update_session_tags(&self, session_id: i64, tags: &str) -> Result<()> {
         self.repository.update_session_tags(session_id, tags).await
     }
 
+    pub async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()> {
+        self.repository.update_sessions_tags(updates).await
+    }
+
     pub async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()> {
         self.repository
             .update_session_video_path(session_id, video_path)
@@ -114,13 +202,29 @@ impl Database {
     }
 
     pub async fn delete_session(&self, session_id: i64) -> Result<()> {
-        self.repository.delete_session(session_id).await
+        self.repository.delete_session(session_id).await?;
+        let _ = self.card_removals.send(session_id);
+        Ok(())
+    }
+
+    pub async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        self.repository.restore_session_snapshot(snapshot).await
     }
 
     pub async fn get_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<Vec<Session>> {
         self.repository.get_old_sessions(cutoff_date).await
     }
 
+    pub async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>> {
+        self.repository
+            .get_empty_sessions(cutoff_date, device_name)
+            .await
+    }
+
     pub async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64> {
         self.repository.delete_old_sessions(cutoff_date).await
     }
@@ -132,7 +236,7 @@ impl Database {
     }
 
     pub async fn insert_frames(&self, frames: &[Frame]) -> Result<()> {
-        self.repository.insert_frames(frames).await
+        telemetry::time_db("insert_frames", self.repository.insert_frames(frames)).await
     }
 
     pub async fn get_frames_by_session(&self, session_id: i64) -> Result<Vec<Frame>> {
@@ -152,13 +256,19 @@ impl Database {
     // ========== LLM 调用记录 ==========
 
     pub async fn insert_llm_call(&self, record: &LLMCallRecord) -> Result<i64> {
-        self.repository.insert_llm_call(record).await
+        // 每次 LLM 调用都会写入记录，在这里统一上报耗时和错误
+        telemetry::record_llm_call(record);
+        telemetry::time_db("insert_llm_call", self.repository.insert_llm_call(record)).await
     }
 
     pub async fn get_llm_calls_by_session(&self, session_id: i64) -> Result<Vec<LLMCallRecord>> {
         self.repository.get_llm_calls_by_session(session_id).await
     }
 
+    pub async fn get_llm_call_ids(&self) -> Result<Vec<i64>> {
+        self.repository.get_llm_call_ids().await
+    }
+
     pub async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>> {
         self.repository.get_recent_llm_errors(limit).await
     }
@@ -176,7 +286,11 @@ impl Database {
     }
 
     pub async fn insert_video_segments(&self, segments: &[VideoSegmentRecord]) -> Result<()> {
-        self.repository.insert_video_segments(segments).await
+        telemetry::time_db(
+            "insert_video_segments",
+            self.repository.insert_video_segments(segments),
+        )
+        .await
     }
 
     pub async fn get_video_segments_by_session(
@@ -201,7 +315,11 @@ impl Database {
     }
 
     pub async fn insert_timeline_cards(&self, cards: &[TimelineCardRecord]) -> Result<()> {
-        self.repository.insert_timeline_cards(cards).await
+        telemetry::time_db(
+            "insert_timeline_cards",
+            self.repository.insert_timeline_cards(cards),
+        )
+        .await
     }
 
     pub async fn get_timeline_cards_by_session(
@@ -213,14 +331,46 @@ impl Database {
             .await
     }
 
+    pub async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>> {
+        self.repository.get_timeline_card(card_id).await
+    }
+
     pub async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
         self.repository.get_recent_timeline_cards(limit).await
     }
 
+    pub async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        self.repository
+            .get_timeline_cards_after(after_id, limit)
+            .await
+    }
+
+    pub async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>> {
+        self.repository
+            .get_timeline_cards_updated_after(after, after_id, limit)
+            .await
+    }
+
     pub async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()> {
         self.repository
             .delete_timeline_cards_by_session(session_id)
-            .await
+            .await?;
+        let _ = self.card_removals.send(session_id);
+        Ok(())
+    }
+
+    /// 订阅会话卡片删除通知（收到的是会话ID）
+    pub fn subscribe_card_removals(&self) -> broadcast::Receiver<i64> {
+        self.card_removals.subscribe()
     }
 
     // ========== 统计信息 ==========
@@ -229,6 +379,13 @@ impl Database {
         self.repository.get_stats().await
     }
 
+    pub async fn get_session_row_usage(
+        &self,
+        session_id: Option<i64>,
+    ) -> Result<Vec<SessionRowUsage>> {
+        self.repository.get_session_row_usage(session_id).await
+    }
+
     pub async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
         self.repository.get_analyzed_video_paths().await
     }
@@ -389,6 +546,546 @@ impl Database {
         self.repository.delete_day_summary(date).await
     }
 
+    pub async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()> {
+        self.repository
+            .set_day_summary_finalized(date, finalized)
+            .await
+    }
+
+    // ========== 休息记录 ==========
+
+    pub async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64> {
+        self.repository.insert_break_record(record).await
+    }
+
+    pub async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>> {
+        self.repository.get_break_records_by_date(date).await
+    }
+
+    // ========== 专注拦截记录 ==========
+
+    pub async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64> {
+        self.repository.insert_deflected_distraction(record).await
+    }
+
+    pub async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>> {
+        self.repository
+            .get_deflected_distractions_by_date(date)
+            .await
+    }
+
+    // ========== 截屏暂停记录 ==========
+
+    pub async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64> {
+        self.repository.insert_capture_pause(record).await
+    }
+
+    pub async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()> {
+        self.repository.end_capture_pause(id, end_time).await
+    }
+
+    pub async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        self.repository.close_open_capture_pauses(end_time).await
+    }
+
+    pub async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>> {
+        self.repository.get_capture_pauses_between(start, end).await
+    }
+
+    // ========== 时段意图 ==========
+
+    pub async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64> {
+        self.repository.insert_block_intent(record).await
+    }
+
+    pub async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        self.repository.end_open_block_intents(end_time).await
+    }
+
+    pub async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>> {
+        self.repository.get_block_intents_between(start, end).await
+    }
+
+    pub async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()> {
+        self.repository
+            .update_block_intent_alignment(id, alignment, aligned_minutes, active_minutes)
+            .await
+    }
+
+    // ========== 外部活动事件 ==========
+
+    pub async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64> {
+        self.repository.insert_external_events(events).await
+    }
+
+    pub async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>> {
+        self.repository
+            .get_external_events_between(start, end)
+            .await
+    }
+
+    // ========== 时间线卡片分类 ==========
+
+    pub async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_classification(card_id, category, distractions)
+            .await
+    }
+
+    pub async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64> {
+        self.repository
+            .rename_timeline_card_subcategory(from, to)
+            .await
+    }
+
+    // ========== 卡片复核 ==========
+
+    pub async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        self.repository.get_cards_needing_review(limit).await
+    }
+
+    pub async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_review(card_id, title, category, subcategory, review_status)
+            .await
+    }
+
+    pub async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_meeting_notes(card_id, meeting_notes)
+            .await
+    }
+
+    pub async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_keyword_tags(card_id, keyword_tags)
+            .await
+    }
+
+    pub async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_preview_clip(card_id, preview_clip_path)
+            .await
+    }
+
+    pub async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()> {
+        self.repository
+            .update_timeline_card_privacy_level(card_id, level)
+            .await
+    }
+
+    pub async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()> {
+        self.repository
+            .update_timeline_card_times(card_id, start_time, end_time)
+            .await
+    }
+
+    pub async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()> {
+        self.repository
+            .update_video_segment_times(segment_id, start_timestamp, end_timestamp)
+            .await
+    }
+
+    // ========== 关注关键词命中 ==========
+
+    pub async fn replace_keyword_hits(
+        &self,
+        session_id: i64,
+        hits: &[KeywordHitRecord],
+    ) -> Result<()> {
+        self.repository.replace_keyword_hits(session_id, hits).await
+    }
+
+    pub async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        self.repository.get_keyword_hits_between(start, end).await
+    }
+
+    pub async fn get_keyword_hits_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        self.repository
+            .get_keyword_hits_by_session(session_id)
+            .await
+    }
+
+    // ========== 屏幕文字 ==========
+
+    pub async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()> {
+        self.repository
+            .replace_screen_text(session_id, records)
+            .await
+    }
+
+    pub async fn get_screen_text_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        self.repository.get_screen_text_by_session(session_id).await
+    }
+
+    pub async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        self.repository.get_screen_text_between(start, end).await
+    }
+
+    pub async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        self.repository
+            .search_screen_text(pattern, language, limit)
+            .await
+    }
+
+    // ========== 报错时刻 ==========
+
+    pub async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()> {
+        self.repository
+            .replace_error_moments(session_id, records)
+            .await
+    }
+
+    pub async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        self.repository.get_error_moments_between(start, end).await
+    }
+
+    pub async fn get_error_moments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        self.repository
+            .get_error_moments_by_session(session_id)
+            .await
+    }
+
+    // ========== 音频活动 ==========
+
+    pub async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()> {
+        self.repository.upsert_audio_presence(record).await
+    }
+
+    pub async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>> {
+        self.repository.get_audio_presence_between(start, end).await
+    }
+
+    // ========== 剪贴板记录 ==========
+
+    pub async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64> {
+        self.repository.insert_clipboard_event(record).await
+    }
+
+    pub async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        self.repository
+            .get_clipboard_events_between(start, end)
+            .await
+    }
+
+    pub async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        self.repository
+            .search_clipboard_events(content_hash, pattern, limit)
+            .await
+    }
+
+    pub async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64> {
+        self.repository.insert_card_correction(record).await
+    }
+
+    pub async fn get_recent_card_corrections(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<CardCorrectionRecord>> {
+        self.repository.get_recent_card_corrections(limit).await
+    }
+
+    // ========== 文件路径迁移 ==========
+
+    pub async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64> {
+        self.repository
+            .relocate_file_paths(old_prefix, new_prefix)
+            .await
+    }
+
+    // ========== 个人纪录 ==========
+
+    pub async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()> {
+        self.repository.save_personal_record(record).await
+    }
+
+    pub async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>> {
+        self.repository.get_personal_records().await
+    }
+
+    // ========== 日期类型 ==========
+
+    pub async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()> {
+        self.repository.set_day_type(record).await
+    }
+
+    pub async fn clear_day_type(&self, date: &str) -> Result<()> {
+        self.repository.clear_day_type(date).await
+    }
+
+    pub async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>> {
+        self.repository
+            .get_day_types_between(start_date, end_date)
+            .await
+    }
+
+    // ========== 存储用量统计 ==========
+
+    pub async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()> {
+        self.repository.add_storage_usage(usage).await
+    }
+
+    pub async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>> {
+        self.repository
+            .get_storage_usage(device_name, start_date, end_date)
+            .await
+    }
+
+    // ========== 会话置顶 ==========
+
+    pub async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()> {
+        self.repository.set_session_pinned(session_id, pinned).await
+    }
+
+    pub async fn get_pinned_sessions(&self) -> Result<Vec<Session>> {
+        self.repository.get_pinned_sessions().await
+    }
+
+    // ========== 会话归档 ==========
+
+    pub async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()> {
+        self.repository
+            .mark_session_archived(session_id, archive_key)
+            .await
+    }
+
+    pub async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()> {
+        self.repository
+            .mark_session_restored(session_id, video_path)
+            .await
+    }
+
+    // ========== 待删除会话 ==========
+
+    pub async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()> {
+        self.repository.add_pending_deletion(record).await
+    }
+
+    pub async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>> {
+        self.repository.get_pending_deletions().await
+    }
+
+    pub async fn remove_pending_deletion(&self, session_id: i64) -> Result<()> {
+        self.repository.remove_pending_deletion(session_id).await
+    }
+
+    // ========== 待分析视频认领 ==========
+
+    pub async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.repository
+            .claim_video(video_path, owner, now, lease_until)
+            .await
+    }
+
+    pub async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()> {
+        self.repository.release_video_claim(video_path, owner).await
+    }
+
+    // ========== 服务商对比 ==========
+
+    pub async fn insert_provider_comparison(
+        &self,
+        record: &ProviderComparisonRecord,
+    ) -> Result<i64> {
+        self.repository.insert_provider_comparison(record).await
+    }
+
+    pub async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>> {
+        self.repository.get_provider_comparisons(session_id).await
+    }
+
+    // ========== 数据外发记录 ==========
+
+    pub async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64> {
+        self.repository.insert_egress_event(record).await
+    }
+
+    pub async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>> {
+        self.repository.get_egress_events_between(start, end).await
+    }
+
+    // ========== 会话书签 ==========
+
+    pub async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64> {
+        self.repository.insert_session_bookmark(record).await
+    }
+
+    pub async fn get_session_bookmarks(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<SessionBookmarkRecord>> {
+        self.repository.get_session_bookmarks(session_id).await
+    }
+
+    pub async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()> {
+        self.repository.delete_session_bookmark(bookmark_id).await
+    }
+
+    // ========== 计时服务导出记录 ==========
+
+    pub async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>> {
+        self.repository.get_time_entry_export(idempotency_key).await
+    }
+
+    pub async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>> {
+        self.repository
+            .get_time_entry_exports_by_session(session_id)
+            .await
+    }
+
+    pub async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()> {
+        self.repository.insert_time_entry_export(record).await
+    }
+
+    // ========== webhook 投递记录 ==========
+
+    pub async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64> {
+        self.repository.insert_webhook_delivery(record).await
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        self.repository
+            .get_webhook_deliveries(session_id, limit)
+            .await
+    }
+
     // ========== 数据库维护操作 ==========
 
     /// 迁移数据库时区：将 UTC 时间转换为本地时间
@@ -0,0 +1,88 @@
+// 媒体文件的客户端加密 - 视频同步到共享位置前加密，拉回本地后解密
+//
+// 格式：魔数 + 16 字节盐 + 12 字节随机数 + ChaCha20-Poly1305 密文。
+// 每个文件使用随机盐，由口令经 PBKDF2-HMAC-SHA256 派生密钥，存储后端只能看到密文。
+// 没有魔数的文件视为开启加密前同步的明文，解密时原样返回
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::Sha256;
+
+/// 加密文件的魔数
+const MAGIC: &[u8] = b"SAENC1\0";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// PBKDF2 迭代次数
+const KDF_ROUNDS: u32 = 100_000;
+
+fn cipher(passphrase: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+/// 是否为加密文件
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 用口令加密文件内容
+pub fn encrypt(plain: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = cipher(passphrase, &salt)
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| anyhow!("加密失败"))?;
+
+    let mut output = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(MAGIC);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// 用口令解密文件内容（明文文件原样返回）
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) {
+        return Ok(data.to_vec());
+    }
+    let body = &data[MAGIC.len()..];
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err(anyhow!("加密文件不完整"));
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    cipher(passphrase, salt)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("解密失败：口令错误或文件已损坏"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip_and_wrong_passphrase() {
+        let video = b"fake mp4 content".to_vec();
+        let encrypted = encrypt(&video, "correct horse").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted
+            .windows(video.len())
+            .any(|w| w == video.as_slice()));
+
+        assert_eq!(decrypt(&encrypted, "correct horse").unwrap(), video);
+        assert!(decrypt(&encrypted, "wrong").is_err());
+        assert!(decrypt(&encrypted[..MAGIC.len() + 4], "correct horse").is_err());
+
+        // 开启加密前同步的明文文件原样返回
+        assert_eq!(decrypt(&video, "correct horse").unwrap(), video);
+    }
+}
@@ -0,0 +1,620 @@
+// 媒体存储抽象 - 视频等媒体文件的存放位置（本地文件系统 / S3 / WebDAV）
+//
+// 媒体文件以相对数据目录的 Key 标识（如 videos/session_1.mp4）。截屏、FFmpeg 编码和
+// LLM 分析都需要本地文件，因此本地数据目录始终作为工作目录：截图只是生成视频前的
+// 临时文件，只保存在本地；生成的视频通过 MediaLibrary 同步到存储后端（S3、WebDAV
+// 或 SMB/NAS 共享文件夹），本地副本缺失时（被本地缓存清理，或在其他设备上生成）
+// 在回放和重新分析前从后端拉回。设置了加密口令时视频加密后再同步。
+// 其他设备记录的视频路径指向那台设备的数据目录，按 videos/ 之后的部分映射到本机数据目录。
+// 冷存储归档复用这里的后端实现
+
+use super::media_crypto;
+use super::s3::{encode_path, S3Client};
+use crate::domains::egress::{self, EgressChannel, EgressContent};
+use crate::models::{MediaBackend, MediaStoreSettings, S3Config, WebDavConfig};
+use crate::video::VideoMetadata;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Method, StatusCode};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// 媒体存储后端
+#[async_trait]
+pub trait MediaStore: Send + Sync {
+    /// 后端类型
+    fn backend(&self) -> MediaBackend;
+
+    /// 上传本地文件到 Key
+    async fn put(&self, key: &str, local: &Path) -> Result<()>;
+
+    /// 下载 Key 到本地文件
+    async fn fetch(&self, key: &str, local: &Path) -> Result<()>;
+
+    /// 删除 Key（不存在时忽略）
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Key 是否存在
+    async fn exists(&self, key: &str) -> Result<bool>;
+}
+
+/// 远程存储使用的 HTTP 客户端（本地模式下只能访问本机/局域网地址）
+fn http_client() -> Result<Client> {
+    Ok(crate::domains::local_only::http_client_builder().build()?)
+}
+
+/// 记录上传到远程存储的文件（数据外发记录），视频之外的文件（如元数据）按文本计
+fn record_egress(destination: &str, key: &str, bytes: u64) {
+    let is_video = matches!(
+        Path::new(key).extension().and_then(|ext| ext.to_str()),
+        Some("mp4" | "webm" | "mkv" | "mov")
+    );
+    let content = if is_video {
+        EgressContent::Video
+    } else {
+        EgressContent::Text
+    };
+    egress::record(EgressChannel::MediaStore, destination, content, bytes, None);
+}
+
+/// 按后端类型创建媒体存储
+pub fn create_store(
+    backend: MediaBackend,
+    local_dir: &Path,
+    s3: Option<&S3Config>,
+    webdav: Option<&WebDavConfig>,
+) -> Result<Arc<dyn MediaStore>> {
+    let store: Arc<dyn MediaStore> = match backend {
+        MediaBackend::Local => Arc::new(LocalMediaStore::new(local_dir.to_path_buf())),
+        MediaBackend::S3 => {
+            let config = s3.cloned().ok_or_else(|| anyhow!("未配置 S3"))?;
+            Arc::new(S3MediaStore {
+                client: S3Client::new(http_client()?, config)?,
+            })
+        }
+        MediaBackend::WebDav => {
+            let config = webdav.ok_or_else(|| anyhow!("未配置 WebDAV"))?;
+            Arc::new(WebDavMediaStore::new(http_client()?, config)?)
+        }
+    };
+    Ok(store)
+}
+
+// ==================== 本地文件系统 ====================
+
+/// 本地文件夹
+pub struct LocalMediaStore {
+    root: PathBuf,
+}
+
+impl LocalMediaStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+#[async_trait]
+impl MediaStore for LocalMediaStore {
+    fn backend(&self) -> MediaBackend {
+        MediaBackend::Local
+    }
+
+    async fn put(&self, key: &str, local: &Path) -> Result<()> {
+        // 外接硬盘未连接时目录不存在，不能在系统盘上重新创建
+        if !self.root.is_dir() {
+            return Err(anyhow!(
+                "存储目录不可用（外接硬盘未连接？）: {:?}",
+                self.root
+            ));
+        }
+        let target = self.root.join(key);
+        if target == local {
+            return Ok(());
+        }
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        copy_atomically(local, &target).await
+    }
+
+    async fn fetch(&self, key: &str, local: &Path) -> Result<()> {
+        let source = self.root.join(key);
+        if source == local {
+            return if source.is_file() {
+                Ok(())
+            } else {
+                Err(anyhow!("文件不存在: {:?}", source))
+            };
+        }
+        copy_atomically(&source, local).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(tokio::fs::metadata(self.root.join(key)).await.is_ok())
+    }
+}
+
+// ==================== S3 ====================
+
+/// S3 兼容对象存储
+pub struct S3MediaStore {
+    client: S3Client,
+}
+
+#[async_trait]
+impl MediaStore for S3MediaStore {
+    fn backend(&self) -> MediaBackend {
+        MediaBackend::S3
+    }
+
+    async fn put(&self, key: &str, local: &Path) -> Result<()> {
+        let content = tokio::fs::read(local).await?;
+        let bytes = content.len() as u64;
+        self.client
+            .put_object(&self.client.full_key(key), content)
+            .await?;
+        record_egress(self.client.endpoint(), key, bytes);
+        Ok(())
+    }
+
+    async fn fetch(&self, key: &str, local: &Path) -> Result<()> {
+        let content = self.client.get_object(&self.client.full_key(key)).await?;
+        write_atomically(local, content).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client.delete_object(&self.client.full_key(key)).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.client.object_exists(&self.client.full_key(key)).await
+    }
+}
+
+// ==================== WebDAV ====================
+
+/// WebDAV 存储
+pub struct WebDavMediaStore {
+    client: Client,
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+impl WebDavMediaStore {
+    pub fn new(client: Client, config: &WebDavConfig) -> Result<Self> {
+        let base_url = config.url.trim().trim_end_matches('/').to_string();
+        reqwest::Url::parse(&base_url).map_err(|e| anyhow!("WebDAV 地址格式错误: {}", e))?;
+        Ok(Self {
+            client,
+            base_url,
+            username: config.username.clone(),
+            password: config.password.clone(),
+        })
+    }
+
+    fn request(&self, method: Method, key: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}/{}", self.base_url, encode_path(key)))
+            .basic_auth(&self.username, Some(&self.password))
+    }
+
+    /// 逐级创建 Key 所在的目录（已存在时服务器返回 405）
+    async fn ensure_parent_dirs(&self, key: &str) -> Result<()> {
+        let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL 是合法的 HTTP 方法");
+        let segments: Vec<&str> = key.split('/').collect();
+        for depth in 1..segments.len() {
+            let dir = segments[..depth].join("/");
+            let status = self.request(mkcol.clone(), &dir).send().await?.status();
+            if !status.is_success() && status != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(anyhow!("创建 WebDAV 目录 {} 失败: {}", dir, status));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MediaStore for WebDavMediaStore {
+    fn backend(&self) -> MediaBackend {
+        MediaBackend::WebDav
+    }
+
+    async fn put(&self, key: &str, local: &Path) -> Result<()> {
+        self.ensure_parent_dirs(key).await?;
+        let content = tokio::fs::read(local).await?;
+        let bytes = content.len() as u64;
+        let response = self.request(Method::PUT, key).body(content).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("WebDAV 上传失败: {}", response.status()));
+        }
+        record_egress(&self.base_url, key, bytes);
+        Ok(())
+    }
+
+    async fn fetch(&self, key: &str, local: &Path) -> Result<()> {
+        let response = self.request(Method::GET, key).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("WebDAV 下载失败: {}", response.status()));
+        }
+        write_atomically(local, response.bytes().await?.to_vec()).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let status = self.request(Method::DELETE, key).send().await?.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND {
+            return Err(anyhow!("删除 WebDAV 文件失败: {}", status));
+        }
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        match self.request(Method::HEAD, key).send().await?.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            status => Err(anyhow!("查询 WebDAV 文件失败: {}", status)),
+        }
+    }
+}
+
+// ==================== 客户端加密 ====================
+
+/// 客户端加密：上传前加密、下载后解密，存储后端只保存密文
+pub struct EncryptedMediaStore {
+    inner: Arc<dyn MediaStore>,
+    passphrase: Arc<str>,
+}
+
+impl EncryptedMediaStore {
+    pub fn new(inner: Arc<dyn MediaStore>, passphrase: &str) -> Self {
+        Self {
+            inner,
+            passphrase: Arc::from(passphrase),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaStore for EncryptedMediaStore {
+    fn backend(&self) -> MediaBackend {
+        self.inner.backend()
+    }
+
+    async fn put(&self, key: &str, local: &Path) -> Result<()> {
+        let plain = tokio::fs::read(local).await?;
+        let passphrase = self.passphrase.clone();
+        let encrypted =
+            tokio::task::spawn_blocking(move || media_crypto::encrypt(&plain, &passphrase))
+                .await??;
+        let work = tempfile::tempdir()?;
+        let staged = work.path().join("upload");
+        tokio::fs::write(&staged, encrypted).await?;
+        self.inner.put(key, &staged).await
+    }
+
+    async fn fetch(&self, key: &str, local: &Path) -> Result<()> {
+        let work = tempfile::tempdir()?;
+        let staged = work.path().join("download");
+        self.inner.fetch(key, &staged).await?;
+        let data = tokio::fs::read(&staged).await?;
+        let passphrase = self.passphrase.clone();
+        let plain = tokio::task::spawn_blocking(move || media_crypto::decrypt(&data, &passphrase))
+            .await??;
+        write_atomically(local, plain).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.inner.exists(key).await
+    }
+}
+
+// ==================== 本地工作目录 + 存储后端 ====================
+
+/// 其他设备记录的路径映射到本机数据目录：取最后一个 videos 目录之后的部分
+///
+/// Windows 和 Unix 的路径分隔符都能识别，路径中没有 videos 目录时返回 None
+pub fn map_to_data_dir(local_root: &Path, stored: &str) -> Option<PathBuf> {
+    let parts: Vec<&str> = stored
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty())
+        .collect();
+    let index = parts.iter().rposition(|part| *part == "videos")?;
+    if index + 1 >= parts.len() {
+        return None;
+    }
+    Some(
+        parts[index..]
+            .iter()
+            .fold(local_root.to_path_buf(), |path, part| path.join(part)),
+    )
+}
+
+/// 媒体库：本地数据目录作为工作目录，视频同步到配置的存储后端
+pub struct MediaLibrary {
+    /// 本地数据目录（Key 的根目录）
+    local_root: PathBuf,
+    /// 同步目标（只使用本地数据目录时为 None）
+    store: RwLock<Option<Arc<dyn MediaStore>>>,
+    /// 同步到远程后本地保留视频的天数（0 表示始终保留）
+    local_cache_days: RwLock<u32>,
+}
+
+impl MediaLibrary {
+    /// 创建只使用本地数据目录的媒体库
+    pub fn new(local_root: PathBuf) -> Self {
+        Self {
+            store: RwLock::new(None),
+            local_root,
+            local_cache_days: RwLock::new(0),
+        }
+    }
+
+    /// 应用媒体存储设置
+    pub async fn configure(&self, settings: &MediaStoreSettings) -> Result<()> {
+        let shared_dir = settings.shared_dir.trim();
+        let store: Option<Arc<dyn MediaStore>> = match settings.backend {
+            MediaBackend::Local if shared_dir.is_empty() => None,
+            MediaBackend::Local => Some(Arc::new(LocalMediaStore::new(PathBuf::from(shared_dir)))),
+            backend => Some(create_store(
+                backend,
+                &self.local_root,
+                settings.s3.as_ref(),
+                settings.webdav.as_ref(),
+            )?),
+        };
+        let encrypted = !settings.encryption_passphrase.is_empty();
+        let store = store.map(|store| -> Arc<dyn MediaStore> {
+            if encrypted {
+                Arc::new(EncryptedMediaStore::new(
+                    store,
+                    &settings.encryption_passphrase,
+                ))
+            } else {
+                store
+            }
+        });
+        *self.store.write().await = store;
+        *self.local_cache_days.write().await = settings.local_cache_days;
+        info!(
+            "媒体存储后端: {:?}{}{}",
+            settings.backend,
+            if shared_dir.is_empty() {
+                String::new()
+            } else {
+                format!("（共享文件夹 {}）", shared_dir)
+            },
+            if encrypted { "，客户端加密" } else { "" }
+        );
+        Ok(())
+    }
+
+    /// 是否同步到远程后端（含共享文件夹）
+    pub async fn is_remote(&self) -> bool {
+        self.store.read().await.is_some()
+    }
+
+    /// 本地缓存天数（本地后端时为 0）
+    pub async fn local_cache_days(&self) -> u32 {
+        if self.is_remote().await {
+            *self.local_cache_days.read().await
+        } else {
+            0
+        }
+    }
+
+    /// 本地路径对应的 Key（不在数据目录中的文件返回 None）
+    fn key_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.local_root).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("/"))
+    }
+
+    /// 数据库中记录的视频路径对应的本地路径（其他设备记录的路径映射到本机数据目录）
+    pub fn local_path_for(&self, stored: &Path) -> PathBuf {
+        if stored.starts_with(&self.local_root) || stored.is_file() {
+            return stored.to_path_buf();
+        }
+        map_to_data_dir(&self.local_root, &stored.to_string_lossy())
+            .unwrap_or_else(|| stored.to_path_buf())
+    }
+
+    /// 远程后端和文件的 Key（不同步或文件不在数据目录中时为 None）
+    async fn remote_key(&self, path: &Path) -> Option<(Arc<dyn MediaStore>, String)> {
+        let store = self.store.read().await.clone()?;
+        match self.key_for(path) {
+            Some(key) => Some((store, key)),
+            None => {
+                warn!("文件不在数据目录中，不同步到存储后端: {:?}", path);
+                None
+            }
+        }
+    }
+
+    /// 把本地生成的视频（及元数据）同步到存储后端，本地后端为空操作
+    pub async fn publish_video(&self, video_path: &Path) -> Result<()> {
+        let Some((store, key)) = self.remote_key(video_path).await else {
+            return Ok(());
+        };
+        store.put(&key, video_path).await?;
+
+        let sidecar = VideoMetadata::sidecar_path(video_path);
+        if sidecar.is_file() {
+            if let Some(sidecar_key) = self.key_for(&sidecar) {
+                store.put(&sidecar_key, &sidecar).await?;
+            }
+        }
+        info!("视频已同步到存储后端: {}", key);
+        Ok(())
+    }
+
+    /// 确保视频在本地可用，本地缺失时从存储后端拉回，返回本地路径
+    ///
+    /// `video_path` 为数据库中记录的路径，可能是其他设备上的路径
+    pub async fn ensure_local_video(&self, video_path: &Path) -> Result<PathBuf> {
+        let local = self.local_path_for(video_path);
+        let video_path = local.as_path();
+        if video_path.is_file() {
+            return Ok(local);
+        }
+        let Some((store, key)) = self.remote_key(video_path).await else {
+            return Err(anyhow!("视频文件不存在: {:?}", video_path));
+        };
+
+        if let Some(parent) = video_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        store.fetch(&key, video_path).await?;
+
+        let sidecar = VideoMetadata::sidecar_path(video_path);
+        if let Some(sidecar_key) = self.key_for(&sidecar) {
+            if let Err(e) = store.fetch(&sidecar_key, &sidecar).await {
+                // 旧视频可能没有元数据，回放时会按默认参数换算时间
+                warn!("拉取视频元数据失败 {}: {}", sidecar_key, e);
+            }
+        }
+        info!("已从存储后端拉回视频: {}", key);
+        Ok(local)
+    }
+
+    /// 存储后端中是否有视频的副本（不同步到存储后端时为 false）
+    pub async fn has_remote_copy(&self, video_path: &Path) -> Result<bool> {
+        let local = self.local_path_for(video_path);
+        match self.remote_key(&local).await {
+            Some((store, key)) => store.exists(&key).await,
+            None => Ok(false),
+        }
+    }
+
+    /// 本地副本已被缓存清理、存储后端中有副本时拉回，返回是否拉回（切换存储后端前调用）
+    pub async fn pull_back_video(&self, video_path: &Path) -> Result<bool> {
+        if self.local_path_for(video_path).is_file() || !self.has_remote_copy(video_path).await? {
+            return Ok(false);
+        }
+        self.ensure_local_video(video_path).await?;
+        Ok(true)
+    }
+
+    /// 删除视频（及元数据）在存储后端中的副本，本地文件由调用方删除
+    pub async fn remove_remote_video(&self, video_path: &Path) -> Result<()> {
+        let Some((store, key)) = self.remote_key(video_path).await else {
+            return Ok(());
+        };
+        store.delete(&key).await?;
+        if let Some(sidecar_key) = self.key_for(&VideoMetadata::sidecar_path(video_path)) {
+            if let Err(e) = store.delete(&sidecar_key).await {
+                warn!("删除远程视频元数据失败 {}: {}", sidecar_key, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 远程已有副本时删除视频的本地副本，返回是否删除
+    pub async fn evict_local_video(&self, video_path: &Path) -> Result<bool> {
+        if !video_path.is_file() {
+            return Ok(false);
+        }
+        let Some((store, key)) = self.remote_key(video_path).await else {
+            return Ok(false);
+        };
+        // 先确认远程副本存在，同步失败的视频不能删除
+        if !store.exists(&key).await? {
+            store.put(&key, video_path).await?;
+        }
+        tokio::fs::remove_file(video_path).await?;
+        VideoMetadata::remove(video_path).await;
+        Ok(true)
+    }
+}
+
+/// 写入过程中使用的临时文件名，完成后再重命名，避免留下不完整的文件
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+async fn copy_atomically(from: &Path, to: &Path) -> Result<()> {
+    let partial = partial_path(to);
+    tokio::fs::copy(from, &partial).await?;
+    tokio::fs::rename(&partial, to).await?;
+    Ok(())
+}
+
+async fn write_atomically(path: &Path, content: Vec<u8>) -> Result<()> {
+    let partial = partial_path(path);
+    tokio::fs::write(&partial, content).await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_store_roundtrip() {
+        let root = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let store = LocalMediaStore::new(root.path().to_path_buf());
+
+        let local = work.path().join("session_1.mp4");
+        tokio::fs::write(&local, b"video").await.unwrap();
+        store.put("2025-03-04/session_1.mp4", &local).await.unwrap();
+        assert!(store.exists("2025-03-04/session_1.mp4").await.unwrap());
+
+        let restored = work.path().join("restored.mp4");
+        store
+            .fetch("2025-03-04/session_1.mp4", &restored)
+            .await
+            .unwrap();
+        assert_eq!(tokio::fs::read(&restored).await.unwrap(), b"video");
+
+        store.delete("2025-03-04/session_1.mp4").await.unwrap();
+        store.delete("2025-03-04/session_1.mp4").await.unwrap();
+        assert!(!store.exists("2025-03-04/session_1.mp4").await.unwrap());
+    }
+
+    #[test]
+    fn test_key_for_relative_to_data_dir() {
+        let library = MediaLibrary::new(PathBuf::from("/data"));
+        assert_eq!(
+            library.key_for(Path::new("/data/videos/session_1.mp4")),
+            Some("videos/session_1.mp4".to_string())
+        );
+        assert_eq!(library.key_for(Path::new("/other/session_1.mp4")), None);
+    }
+
+    #[test]
+    fn test_paths_from_other_devices_map_to_data_dir() {
+        let root = Path::new("/data");
+        assert_eq!(
+            map_to_data_dir(
+                root,
+                r"C:\Users\me\AppData\screen-analyzer\videos\2025-03-04_09-00.mp4"
+            ),
+            Some(PathBuf::from("/data/videos/2025-03-04_09-00.mp4"))
+        );
+        assert_eq!(
+            map_to_data_dir(root, "/Users/me/Library/data/videos/chunks/a.mp4"),
+            Some(PathBuf::from("/data/videos/chunks/a.mp4"))
+        );
+        assert_eq!(map_to_data_dir(root, "/tmp/videos"), None);
+        assert_eq!(map_to_data_dir(root, "/tmp/session_1.mp4"), None);
+    }
+}
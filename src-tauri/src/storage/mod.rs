@@ -1,12 +1,19 @@
 // 存储模块 - 统一的数据库抽象层
 
 // 子模块
+pub mod archive;
+pub mod availability;
 pub mod cache;
 pub mod cleaner;
 pub mod config;
+pub mod data_location;
 pub mod database;
+pub mod media_crypto;
+pub mod media_store;
 pub mod models;
 pub mod repository;
+pub mod s3;
+pub mod trash;
 
 // 重新导出主要类型
 pub use cache::CachedRepository;
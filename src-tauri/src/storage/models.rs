@@ -13,18 +13,45 @@ pub fn local_now() -> DateTime<Utc> {
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Session {
     pub id: Option<i64>,
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub start_time: DateTime<Utc>,
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub end_time: DateTime<Utc>,
     pub title: String,
     pub summary: String,
     pub video_path: Option<String>,
     pub tags: String, // JSON序列化的标签
-    #[serde(serialize_with = "serialize_datetime_as_local_option")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local_option",
+        deserialize_with = "deserialize_datetime_from_local_option",
+        default
+    )]
     pub created_at: Option<DateTime<Utc>>,
     pub device_name: Option<String>, // 设备名称
     pub device_type: Option<String>, // 设备类型(desktop, laptop, tablet等)
+    #[serde(default)]
+    #[sqlx(default)]
+    pub pinned: bool, // 置顶的会话不会被自动清理
+    #[serde(default)]
+    #[sqlx(default)]
+    pub archived: bool, // 视频已移到冷存储，本地不再保留
+    #[serde(default)]
+    #[sqlx(default)]
+    pub archive_key: Option<String>, // 归档对象的 Key（本地归档目录中的相对路径或 S3 对象 Key）
+}
+
+/// 会话在数据库中的行数和文本字段字节数（不含索引和页开销）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SessionRowUsage {
+    pub session_id: i64,
+    pub row_count: i64, // 会话、截图、LLM 调用、视频分段和时间线卡片的行数
+    pub bytes: i64,
 }
 
 /// 帧数据结构
@@ -32,9 +59,15 @@ pub struct Session {
 pub struct Frame {
     pub id: Option<i64>,
     pub session_id: i64,
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub timestamp: DateTime<Utc>,
     pub file_path: String,
+    /// 截取时的上下文（JSON，如浏览器扩展上报的活动标签页）
+    #[serde(default)]
+    pub metadata: Option<String>,
 }
 
 /// 活动数据结构（用于日历视图）
@@ -44,6 +77,9 @@ pub struct Activity {
     pub session_count: i32,
     pub total_duration_minutes: i32,
     pub main_categories: Vec<String>,
+    /// 外部来源（手机、浏览器扩展等）上报的活动时长
+    #[serde(default)]
+    pub external_minutes: i32,
 }
 
 /// 会话详情数据结构
@@ -52,6 +88,9 @@ pub struct SessionDetail {
     pub session: Session,
     pub frames: Vec<Frame>,
     pub tags: Vec<crate::models::ActivityTag>,
+    /// 存储占用（视频、保留的截图和数据库行），由命令层填充
+    #[serde(default)]
+    pub storage: Option<super::cleaner::SessionStorage>,
 }
 
 /// LLM调用记录
@@ -70,8 +109,14 @@ pub struct LLMCallRecord {
     pub error_message: Option<String>,
     pub latency_ms: Option<i64>,     // 调用延迟（毫秒）
     pub token_usage: Option<String>, // JSON格式的token使用情况
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub prompt_version: Option<String>, // 生成请求所用的提示词模板版本
 }
 
 /// 视频分段记录
@@ -83,7 +128,10 @@ pub struct VideoSegmentRecord {
     pub start_timestamp: String,  // RFC3339 格式（如 2025-10-09T12:54:00+08:00，包含时区）
     pub end_timestamp: String,    // RFC3339 格式（如 2025-10-09T12:54:00+08:00，包含时区）
     pub description: String,
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub created_at: DateTime<Utc>,
 }
 
@@ -103,10 +151,279 @@ pub struct TimelineCardRecord {
     pub distractions: Option<String>,       // JSON格式的干扰活动
     pub app_sites: String,                  // JSON格式的应用/网站信息
     pub video_preview_path: Option<String>, // 本地视频文件路径
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub confidence: Option<f64>, // 卡片置信度（0-1）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub review_status: Option<String>, // 复核状态：pending / confirmed / corrected，无需复核时为空
+    #[serde(default)]
+    #[sqlx(default)]
+    pub meeting_notes: Option<String>, // JSON格式的会议纪要（仅会议卡片生成后有值）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub keyword_tags: Option<String>, // JSON数组，卡片命中的关注关键词
+    #[serde(default)]
+    #[sqlx(default)]
+    pub preview_clip_path: Option<String>, // 卡片预览短片（截取卡片中段的几秒视频）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub privacy_level: Option<i64>, // 隐私敏感度（0-3，见 PrivacyLevel），未评估时为空
+}
+
+/// 修改过的时间线卡片及修改时间（只有标题、分类、时间和敏感度的修改会更新修改时间）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UpdatedTimelineCard {
+    #[sqlx(flatten)]
+    pub card: TimelineCardRecord,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 关注关键词命中记录
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KeywordHitRecord {
+    pub id: Option<i64>,
+    pub session_id: i64,
+    pub card_id: Option<i64>,
+    pub keyword: String,
+    pub source: String,  // card（卡片标题/摘要）或 segment（视频分段描述）
+    pub excerpt: String, // 命中位置附近的文本
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub occurred_at: DateTime<Utc>, // 命中内容对应的活动时间
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 屏幕文字记录（按会话分段保存 OCR 识别出的新出现文字）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScreenTextRecord {
+    pub id: Option<i64>,
+    pub session_id: i64,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub segment_start: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub segment_end: DateTime<Utc>,
+    pub text: String,             // 分段内首次出现的文字行（换行分隔，已去重）
+    pub language: Option<String>, // 分段内屏幕文字的主要语言（chinese、english、code、other）
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 报错时刻记录（屏幕文字中识别出的堆栈、编译错误和错误码）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ErrorMomentRecord {
+    pub id: Option<i64>,
+    pub session_id: i64,
+    pub kind: String,       // stack_trace、compiler_error、exception 或 error_code
+    pub error_text: String, // 提取出的报错文本
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub occurred_at: DateTime<Utc>, // 首次出现在屏幕上的时间
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub created_at: DateTime<Utc>,
 }
 
+/// 剪贴板记录（默认只记录哈希和长度，开启后才保存文本）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClipboardEventRecord {
+    pub id: Option<i64>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub copied_at: DateTime<Utc>,
+    pub content_hash: String, // SHA-256（十六进制）
+    pub char_count: i64,
+    pub line_count: i64,
+    pub kind: String,             // code、url 或 text
+    pub app_name: Option<String>, // 复制时的前台应用
+    pub content: Option<String>,  // 文本内容（仅开启保存文本时）
+}
+
+/// 每分钟的音频活动（只记录麦克风/扬声器是否在使用，不保存音频）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AudioPresenceRecord {
+    pub id: Option<i64>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub minute: DateTime<Utc>, // 所在分钟的开始时间
+    pub mic_active: bool,
+    pub speaker_active: bool,
+}
+
+/// 休息记录
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BreakRecord {
+    pub id: Option<i64>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub reminded_at: DateTime<Utc>, // 提醒时间
+    pub outcome: String,     // taken（已休息）或 skipped（跳过）
+    pub active_minutes: i64, // 提醒时已连续活动的分钟数
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub resolved_at: DateTime<Utc>, // 休息开始或确认跳过的时间
+    pub device_name: Option<String>,
+}
+
+/// 专注时段内拦截的干扰（干扰应用切到前台时提醒或最小化）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeflectedDistractionRecord {
+    pub id: Option<i64>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub intercepted_at: DateTime<Utc>,
+    pub app_name: String,
+    pub action: String, // alert（提醒）或 minimize（最小化）
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub focus_started_at: DateTime<Utc>, // 所在专注时段的开始时间
+    pub device_name: Option<String>,
+}
+
+/// 截屏暂停记录（锁屏、用户手动暂停等导致截屏停止的时间段）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CapturePauseRecord {
+    pub id: Option<i64>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub start_time: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local_option",
+        deserialize_with = "deserialize_datetime_from_local_option",
+        default
+    )]
+    pub end_time: Option<DateTime<Utc>>, // 仍在暂停中时为空
+    pub reason: String, // screen_locked（锁屏）或 user（手动暂停）
+}
+
+/// 用户声明的时段意图（如“写设计文档”）及分析后的吻合度
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BlockIntentRecord {
+    pub id: Option<i64>,
+    pub intent: String,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub start_time: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local_option",
+        deserialize_with = "deserialize_datetime_from_local_option",
+        default
+    )]
+    pub end_time: Option<DateTime<Utc>>, // 仍在进行中时为空
+    pub device_name: Option<String>,
+    pub alignment: Option<f64>, // 与意图吻合的活动占比（0-1），尚未评估或没有活动时为空
+    pub aligned_minutes: Option<i64>,
+    pub active_minutes: Option<i64>,
+}
+
+/// 外部活动事件（手机屏幕使用时间导出、浏览器扩展上报的活动标签页等）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExternalEventRecord {
+    pub id: Option<i64>,
+    pub source: String, // 来源，如 phone、browser
+    pub category: String,
+    pub title: String,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub start_time: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub end_time: DateTime<Utc>,
+    pub metadata: Option<String>, // 来源附带的原始数据（JSON）
+}
+
+/// 卡片纠正记录（用户在复核队列中修改的卡片，用作后续提示词的示例）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CardCorrectionRecord {
+    pub id: Option<i64>,
+    pub card_id: i64,
+    pub original_title: String,
+    pub original_category: String,
+    pub original_subcategory: String,
+    pub corrected_title: String,
+    pub corrected_category: String,
+    pub corrected_subcategory: String,
+    pub summary: String,   // 卡片摘要（提供示例上下文）
+    pub app_sites: String, // JSON格式的应用/网站信息
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 个人纪录（专注时长、连续达标天数等，由时间线卡片计算后保存）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PersonalRecord {
+    pub record_key: String, // 纪录类型，如 longest_focus_block、best_day:2025-10
+    pub value: i64,         // 纪录数值（分钟或天数）
+    pub date: String,       // 创造纪录的日期 (YYYY-MM-DD)
+    pub detail: Option<String>, // 补充说明，如专注时段 09:00-11:30
+}
+
+/// 用户标记的日期类型（工作日/周末/假期），用于选择每日总结的模板
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DayTypeRecord {
+    pub date: String,         // YYYY-MM-DD
+    pub day_type: String,     // workday / weekend / vacation
+    pub note: Option<String>, // 备注，如 "国庆假期"
+}
+
+/// 每日存储用量（按设备统计，用于压缩率和存储预测）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StorageUsageRecord {
+    pub date: String, // YYYY-MM-DD
+    pub device_name: String,
+    pub captured_bytes: i64,  // 原始截图大小
+    pub captured_frames: i64, // 原始截图数量
+    pub encoded_bytes: i64,   // 生成的视频大小
+    pub encoded_videos: i64,  // 生成的视频数量
+}
+
 /// 每日总结记录
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct DaySummaryRecord {
@@ -122,10 +439,123 @@ pub struct DaySummaryRecord {
     pub usage_patterns: String,   // JSON 格式的使用模式
     pub active_device_count: i32, // 活跃设备数量
     pub llm_call_id: Option<i64>, // 关联的 LLM 调用记录
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub created_at: DateTime<Utc>, // 创建时间
-    #[serde(serialize_with = "serialize_datetime_as_local")]
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
     pub updated_at: DateTime<Utc>, // 更新时间
+    #[serde(default)]
+    #[sqlx(default)]
+    pub finalized: bool, // 用户完成当日复核后锁定，不再被重新生成
+}
+
+/// 待删除会话记录（撤销窗口内可恢复）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PendingDeletionRecord {
+    pub session_id: i64,
+    pub title: String,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub deleted_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub expires_at: DateTime<Utc>, // 到期后彻底删除
+    pub snapshot: String,     // JSON 格式的会话及关联记录快照
+    pub staged_files: String, // JSON 格式的暂存文件列表（原路径 -> 暂存路径）
+}
+
+/// 服务商对比结果（同一会话视频交给不同服务商分析的输出，用于比较分类效果）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProviderComparisonRecord {
+    pub id: Option<i64>,
+    pub session_id: i64,
+    pub run_id: String, // 同一次对比的各服务商结果共用一个 run_id
+    pub provider: String,
+    pub model: String,
+    pub error_message: Option<String>, // 分析失败时的错误信息
+    pub latency_ms: i64,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    #[sqlx(rename = "cards_json")]
+    pub timeline_cards: String, // JSON 格式的时间线卡片（时间已换算为绝对时间）
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 数据外发记录（用户内容离开本机的一次传输）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EgressRecord {
+    pub id: Option<i64>,
+    pub channel: String,     // llm / notion / media_store / rag
+    pub destination: String, // 目标主机
+    pub category: String,    // screenshots / video / text
+    pub bytes: i64,
+    pub session_id: Option<i64>, // 关联的会话（会话删除后仍保留记录）
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 会话书签（回看视频时标记的时刻和备注）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SessionBookmarkRecord {
+    pub id: Option<i64>,
+    pub session_id: i64,
+    pub video_time: f64, // 视频播放位置（秒）
+    pub note: String,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 已导出到计时服务（Toggl/Clockify）的时间记录，按幂等键避免重复导出
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TimeEntryExportRecord {
+    pub idempotency_key: String, // 服务、工作区和卡片时间段的摘要
+    pub provider: String,        // toggl / clockify
+    pub session_id: i64,
+    pub card_id: Option<i64>,
+    pub external_id: String, // 计时服务中的记录 ID
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub exported_at: DateTime<Utc>,
+}
+
+/// webhook 投递记录（一次投递及其重试的最终结果）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDeliveryRecord {
+    pub id: Option<i64>,
+    pub delivery_id: String, // 随请求头发送的投递 ID，重试时不变
+    pub session_id: i64,
+    pub url: String,
+    pub status: String, // delivered / failed
+    pub attempts: i64,
+    pub response_status: Option<i64>, // 最后一次请求的 HTTP 状态码（请求未发出时为空）
+    pub error: Option<String>,
+    pub payload_bytes: i64,
+    #[serde(
+        serialize_with = "serialize_datetime_as_local",
+        deserialize_with = "deserialize_datetime_from_local"
+    )]
+    pub created_at: DateTime<Utc>,
 }
 
 // 自定义序列化：NaiveDate -> String (YYYY-MM-DD)
@@ -148,7 +578,10 @@ where
 /// 自定义序列化：DateTime<Utc> -> 不带时区标记的字符串
 /// 将数据库中的本地时间序列化为 "YYYY-MM-DD HH:MM:SS" 格式（不带Z后缀）
 /// 这样前端 dayjs 会将其视为本地时间，不会再进行时区转换
-fn serialize_datetime_as_local<S>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_datetime_as_local<S>(
+    dt: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -157,6 +590,45 @@ where
     serializer.serialize_str(&dt.format("%Y-%m-%dT%H:%M:%S").to_string())
 }
 
+/// 解析本地时间字符串（与 serialize_datetime_as_local 对应，也兼容带时区的 RFC3339）
+fn parse_local_datetime(value: &str) -> Option<DateTime<Utc>> {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .map(|naive| naive.and_utc())
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc))
+        })
+}
+
+/// 自定义反序列化：不带时区标记的字符串 -> DateTime<Utc>
+pub(crate) fn deserialize_datetime_from_local<'de, D>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_local_datetime(&value)
+        .ok_or_else(|| serde::de::Error::custom(format!("无效的时间: {}", value)))
+}
+
+/// 自定义反序列化：Option<不带时区标记的字符串> -> Option<DateTime<Utc>>
+fn deserialize_datetime_from_local_option<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => parse_local_datetime(&value)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("无效的时间: {}", value))),
+        None => Ok(None),
+    }
+}
+
 /// 自定义序列化：Option<DateTime<Utc>> -> Option<不带时区标记的字符串>
 fn serialize_datetime_as_local_option<S>(
     dt: &Option<DateTime<Utc>>,
@@ -170,3 +642,24 @@ where
         None => serializer.serialize_none(),
     }
 }
+
+/// 会话及其关联记录的快照（回收站撤销删除时按原 ID 恢复）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub session: Session,
+    pub frames: Vec<Frame>,
+    pub llm_calls: Vec<LLMCallRecord>,
+    pub segments: Vec<VideoSegmentRecord>,
+    pub cards: Vec<TimelineCardRecord>,
+    // 以下字段在旧版本的快照中不存在
+    #[serde(default)]
+    pub bookmarks: Vec<SessionBookmarkRecord>,
+    #[serde(default)]
+    pub keyword_hits: Vec<KeywordHitRecord>,
+    #[serde(default)]
+    pub screen_text: Vec<ScreenTextRecord>,
+    #[serde(default)]
+    pub error_moments: Vec<ErrorMomentRecord>,
+    #[serde(default)]
+    pub time_entry_exports: Vec<TimeEntryExportRecord>,
+}
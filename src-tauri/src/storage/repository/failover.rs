@@ -0,0 +1,1424 @@
+// 远程数据库故障切换 - MariaDB 不可用时把读写转到本地 SQLite 队列，恢复后写回远程
+//
+// 连续出现连接错误（连接失败、连接池超时、连接被断开）达到阈值后断路器打开，之后的读取和会话写入都转到本地队列，
+// 降级期间只能看到本地队列中的数据；不随会话写回的写入（日总结、休息记录、剪贴板事件、书签等）在降级期间直接返回错误。
+// 后台按指数退避间隔探测远程数据库，恢复后关闭断路器，把队列中的会话连同帧、LLM 调用、视频分段、时间线卡片、
+// 关键词命中、屏幕文字和报错时刻写回远程并从队列中删除，写回中断时按记录的远程 ID 续写，不会重复写入。
+// 队列的自增 ID 从 LOCAL_ID_BASE 开始，恢复后仍引用队列 ID 的操作只会找不到记录，不会误改远程的同 ID 数据。
+// 未降级时遇到连接错误的分析结果写入进入 outbox（见 outbox 模块），恢复后先按顺序重放 outbox。
+// 引用占位会话 ID 的其他写入（删除、置顶、归档等）在重放前直接返回错误，避免把占位 ID 写进数据库
+
+use super::mariadb::MariaDbRepository;
+use super::outbox::{is_placeholder, Outbox, OutboxOp, OutboxStatus, ReplayOutcome};
+use super::sqlite::SqliteRepository;
+use super::DatabaseRepository;
+use crate::storage::config::MariaDbConnectionSettings;
+use crate::storage::models::*;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 本地队列自增 ID 的起始值（远离远程数据库的 ID）
+pub const LOCAL_ID_BASE: i64 = 1_000_000_000_000;
+
+/// 会话相关的表，队列 ID 从 LOCAL_ID_BASE 开始
+const QUEUED_TABLES: &[&str] = &[
+    "sessions",
+    "frames",
+    "llm_calls",
+    "video_segments",
+    "timeline_cards",
+];
+
+/// 第 attempt 次（从 0 开始）重试前的等待时间：初始间隔每次翻倍，不超过最长间隔
+pub fn backoff_delay(attempt: u32, base_secs: u64, max_secs: u64) -> Duration {
+    let base_secs = base_secs.max(1);
+    let secs = base_secs.saturating_mul(1u64 << attempt.min(20));
+    Duration::from_secs(secs.min(max_secs.max(base_secs)))
+}
+
+/// 是否为连接错误（远程数据库不可达，而不是 SQL 本身的错误）
+pub fn is_connection_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+            )
+        )
+    })
+}
+
+/// 断路器
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    retry_base_secs: u64,
+    retry_max_secs: u64,
+    /// 连续失败次数
+    failures: u32,
+    open: bool,
+    /// 打开后探测失败的次数
+    probe_attempts: u32,
+    next_probe: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, retry_base_secs: u64, retry_max_secs: u64) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            retry_base_secs,
+            retry_max_secs,
+            failures: 0,
+            open: false,
+            probe_attempts: 0,
+            next_probe: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn record_success(&mut self) {
+        if !self.open {
+            self.failures = 0;
+        }
+    }
+
+    /// 记录一次连接错误，返回断路器是否因此打开
+    pub fn record_failure(&mut self, now: Instant) -> bool {
+        if self.open {
+            return false;
+        }
+        self.failures += 1;
+        if self.failures < self.threshold {
+            return false;
+        }
+        self.open = true;
+        self.probe_attempts = 0;
+        self.next_probe = Some(now + backoff_delay(0, self.retry_base_secs, self.retry_max_secs));
+        true
+    }
+
+    /// 是否到了探测远程数据库的时间
+    pub fn probe_due(&self, now: Instant) -> bool {
+        self.open && self.next_probe.map_or(true, |next| now >= next)
+    }
+
+    /// 探测失败，间隔加倍后再试
+    pub fn probe_failed(&mut self, now: Instant) {
+        self.probe_attempts += 1;
+        self.next_probe = Some(
+            now + backoff_delay(
+                self.probe_attempts,
+                self.retry_base_secs,
+                self.retry_max_secs,
+            ),
+        );
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.failures = 0;
+        self.probe_attempts = 0;
+        self.next_probe = None;
+    }
+}
+
+/// 故障切换状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailoverStatus {
+    /// 是否处于降级模式（读写本地队列）
+    pub degraded: bool,
+    /// 进入降级模式的时间（本地时间，YYYY-MM-DD HH:MM:SS）
+    pub since: Option<String>,
+    /// 最近一次连接错误
+    pub last_error: Option<String>,
+    /// 最近一次写回远程的会话数
+    pub last_synced_sessions: usize,
+}
+
+/// 带故障切换的 MariaDB 仓库
+pub struct FailoverRepository {
+    remote: Arc<MariaDbRepository>,
+    local: SqliteRepository,
+    breaker: Mutex<CircuitBreaker>,
+    status: Mutex<FailoverStatus>,
+    /// 读写目标切换过（上层需要清空缓存）
+    switched: AtomicBool,
+    sync_lock: tokio::sync::Mutex<()>,
+    /// 暂存写入的 outbox
+    outbox: Outbox,
+}
+
+/// outbox 支持的写入的去向
+enum WriteTarget {
+    Remote,
+    /// 引用了本地队列中的记录，或降级期间新建的会话
+    Local,
+    Outbox,
+}
+
+macro_rules! route {
+    ($self:ident . $method:ident ( $($arg:expr),* )) => {{
+        if $self.is_degraded() {
+            $self.local.$method($($arg),*).await
+        } else {
+            let result = $self.remote.$method($($arg),*).await;
+            if $self.observe(&result) {
+                // 本次操作触发了切换，转到本地队列执行
+                $self.local.$method($($arg),*).await
+            } else {
+                result
+            }
+        }
+    }};
+}
+
+/// 不经过 outbox 的写入：只引用队列记录的写本地队列（随会话写回远程），
+/// 其余写入在降级期间直接返回错误，遇到连接错误也不转到本地，避免写进队列后无法写回
+macro_rules! route_write {
+    ($self:ident, [$($id:expr),*], $method:ident ( $($arg:expr),* )) => {{
+        let queued = false $(|| $id >= LOCAL_ID_BASE)*;
+        if queued {
+            $self.local.$method($($arg),*).await
+        } else if $self.is_degraded() {
+            Err(anyhow!(
+                "远程数据库不可用，暂时无法保存（{}），请在恢复连接后重试",
+                stringify!($method)
+            ))
+        } else {
+            let result = $self.remote.$method($($arg),*).await;
+            $self.observe(&result);
+            result
+        }
+    }};
+}
+
+/// outbox 支持的写入：按去向执行，远程写入遇到连接错误时转入 outbox
+macro_rules! write_through {
+    ($self:ident, $target:expr, $method:ident ( $($arg:expr),* ), $op:expr, $on_queued:expr) => {{
+        match $target {
+            WriteTarget::Local => $self.local.$method($($arg),*).await,
+            WriteTarget::Outbox => $self.outbox.push(&$op).await.map($on_queued),
+            WriteTarget::Remote => match $self.remote.$method($($arg),*).await {
+                Err(e) if is_connection_error(&e) => {
+                    warn!("写入远程数据库失败，暂存到 outbox: {}", e);
+                    $self.observe::<()>(&Err(e));
+                    $self.outbox.push(&$op).await.map($on_queued)
+                }
+                result => {
+                    $self.observe(&result);
+                    result
+                }
+            },
+        }
+    }};
+}
+
+impl FailoverRepository {
+    /// 创建故障切换仓库，`queue_path` 为本地队列的 SQLite 路径
+    pub async fn new(
+        remote: Arc<MariaDbRepository>,
+        queue_path: &str,
+        settings: &MariaDbConnectionSettings,
+    ) -> Result<Self> {
+        let local = SqliteRepository::new(queue_path).await?;
+        // 让队列的自增 ID 从 LOCAL_ID_BASE 开始
+        for table in QUEUED_TABLES {
+            sqlx::query("INSERT INTO sqlite_sequence (name, seq) SELECT ?, 0 WHERE NOT EXISTS (SELECT 1 FROM sqlite_sequence WHERE name = ?)")
+                .bind(*table)
+                .bind(*table)
+                .execute(local.get_pool())
+                .await?;
+            sqlx::query("UPDATE sqlite_sequence SET seq = ? WHERE name = ? AND seq < ?")
+                .bind(LOCAL_ID_BASE)
+                .bind(*table)
+                .bind(LOCAL_ID_BASE)
+                .execute(local.get_pool())
+                .await?;
+        }
+        // 已写入远程的队列会话，写回中断后重试时复用远程 ID
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS queue_sync_ids (local_id INTEGER PRIMARY KEY, remote_id INTEGER NOT NULL)",
+        )
+        .execute(local.get_pool())
+        .await?;
+        info!("MariaDB 本地队列: {}", queue_path);
+        let outbox = Outbox::open(local.get_pool().clone()).await?;
+
+        Ok(Self {
+            remote,
+            local,
+            breaker: Mutex::new(CircuitBreaker::new(
+                settings.failure_threshold,
+                settings.retry_base_secs,
+                settings.retry_max_secs,
+            )),
+            status: Mutex::new(FailoverStatus::default()),
+            switched: AtomicBool::new(false),
+            sync_lock: tokio::sync::Mutex::new(()),
+            outbox,
+        })
+    }
+
+    /// 根据写入引用的 ID 决定去向：引用队列记录的写本地，引用占位 ID 或 outbox 中还有写入时追加到 outbox
+    fn write_target(&self, ids: impl IntoIterator<Item = i64>) -> WriteTarget {
+        let mut placeholder = false;
+        for id in ids {
+            if id >= LOCAL_ID_BASE {
+                return WriteTarget::Local;
+            }
+            placeholder |= is_placeholder(id);
+        }
+        if placeholder || self.is_degraded() || self.outbox.pending() > 0 {
+            WriteTarget::Outbox
+        } else {
+            WriteTarget::Remote
+        }
+    }
+
+    /// 引用的会话还在 outbox 中等待写入远程时返回错误
+    fn ensure_not_placeholder(session_id: i64) -> Result<()> {
+        if is_placeholder(session_id) {
+            return Err(anyhow!(
+                "会话尚未写入远程数据库（占位 ID {}），请在 outbox 重放后重试",
+                session_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// 引用的会话还在本地队列中时返回错误（写回时不会带上这类记录）
+    fn ensure_not_queued(session_id: i64) -> Result<()> {
+        if session_id >= LOCAL_ID_BASE {
+            return Err(anyhow!(
+                "会话还在本地队列中（ID {}），请在写回远程数据库后重试",
+                session_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// outbox 状态
+    pub async fn outbox_status(&self) -> Result<OutboxStatus> {
+        self.outbox.status().await
+    }
+
+    fn is_degraded(&self) -> bool {
+        self.breaker
+            .lock()
+            .map(|breaker| breaker.is_open())
+            .unwrap_or(false)
+    }
+
+    /// 记录远程操作的结果，返回是否因此切换到本地队列
+    fn observe<T>(&self, result: &Result<T>) -> bool {
+        let Ok(mut breaker) = self.breaker.lock() else {
+            return false;
+        };
+        let error = match result {
+            Ok(_) => {
+                breaker.record_success();
+                return false;
+            }
+            Err(e) if is_connection_error(e) => e,
+            Err(_) => return false,
+        };
+        if !breaker.record_failure(Instant::now()) {
+            return false;
+        }
+        warn!("远程数据库不可用，切换到本地队列: {}", error);
+        if let Ok(mut status) = self.status.lock() {
+            status.degraded = true;
+            status.since = Some(
+                crate::storage::local_now()
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+            );
+            status.last_error = Some(error.to_string());
+        }
+        self.switched.store(true, Ordering::SeqCst);
+        true
+    }
+
+    /// 当前的故障切换状态
+    pub fn status(&self) -> FailoverStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    /// 读写目标是否切换过（读取后清除）
+    pub fn take_switched(&self) -> bool {
+        self.switched.swap(false, Ordering::SeqCst)
+    }
+
+    /// 定期调用：降级时到时间就探测远程数据库，恢复后写回队列中的数据
+    pub async fn check(&self) {
+        let now = Instant::now();
+        let probe_due = self
+            .breaker
+            .lock()
+            .map(|breaker| breaker.probe_due(now))
+            .unwrap_or(false);
+        if probe_due {
+            if let Err(e) = self.remote.ping().await {
+                if let Ok(mut breaker) = self.breaker.lock() {
+                    breaker.probe_failed(now);
+                }
+                if let Ok(mut status) = self.status.lock() {
+                    status.last_error = Some(e.to_string());
+                }
+                return;
+            }
+            info!("远程数据库已恢复，切换回远程");
+            if let Ok(mut breaker) = self.breaker.lock() {
+                breaker.close();
+            }
+            if let Ok(mut status) = self.status.lock() {
+                status.degraded = false;
+                status.since = None;
+            }
+            self.switched.store(true, Ordering::SeqCst);
+        }
+
+        if self.is_degraded() {
+            return;
+        }
+        // 先按顺序重放 outbox，全部重放后才写回队列
+        if self.outbox.pending() > 0 {
+            let _guard = self.sync_lock.lock().await;
+            match self.outbox.replay(self.remote.as_ref()).await {
+                Ok(ReplayOutcome::Done(count)) => {
+                    info!("outbox 重放完成: {} 条写入", count);
+                    self.switched.store(true, Ordering::SeqCst);
+                }
+                Ok(ReplayOutcome::Interrupted(count, e)) => {
+                    warn!("outbox 重放中断（已重放 {} 条）: {}", count, e);
+                    if count > 0 {
+                        self.switched.store(true, Ordering::SeqCst);
+                    }
+                    self.observe::<()>(&Err(e));
+                    return;
+                }
+                Err(e) => {
+                    error!("读取 outbox 失败: {}", e);
+                    return;
+                }
+            }
+        }
+
+        // 恢复后（或上次写回中断时）把队列中的数据写回远程
+        match self.sync_queue().await {
+            Ok(0) => {}
+            Ok(count) => {
+                info!("已把本地队列中的 {} 个会话写回远程数据库", count);
+                if let Ok(mut status) = self.status.lock() {
+                    status.last_synced_sessions = count;
+                }
+                self.switched.store(true, Ordering::SeqCst);
+            }
+            Err(e) => {
+                error!("写回本地队列失败，稍后重试: {}", e);
+                self.observe::<()>(&Err(e));
+            }
+        }
+    }
+
+    /// 把队列中的会话写回远程，写回成功的会话从队列中删除
+    ///
+    /// 以会话为单位写回，会话连同帧、LLM 调用、视频分段、时间线卡片、关键词命中、屏幕文字和报错时刻一起写回。
+    /// 远程会话 ID 写入后立即记录，写回中途失败时该会话保留在队列中，下次复用远程 ID 并先清掉已写入的子记录，不会重复写入会话
+    async fn sync_queue(&self) -> Result<usize> {
+        let _guard = self.sync_lock.lock().await;
+        let sessions = self.local.get_all_sessions().await?;
+        let pool = self.local.get_pool();
+        let mut synced = 0;
+        for session in sessions {
+            let Some(local_id) = session.id else {
+                continue;
+            };
+            let mapped: Option<i64> =
+                sqlx::query_scalar("SELECT remote_id FROM queue_sync_ids WHERE local_id = ?")
+                    .bind(local_id)
+                    .fetch_optional(pool)
+                    .await?;
+            let remote_id = match mapped {
+                Some(remote_id) => {
+                    // 上次写回中断：清掉已写入的子记录后重新写入
+                    self.remote
+                        .delete_timeline_cards_by_session(remote_id)
+                        .await?;
+                    self.remote
+                        .delete_video_segments_by_session(remote_id)
+                        .await?;
+                    self.remote.delete_llm_calls_by_session(remote_id).await?;
+                    self.remote.delete_frames_by_session(remote_id).await?;
+                    remote_id
+                }
+                None => {
+                    let remote_id = self.remote.insert_session(&session).await?;
+                    sqlx::query("INSERT INTO queue_sync_ids (local_id, remote_id) VALUES (?, ?)")
+                        .bind(local_id)
+                        .bind(remote_id)
+                        .execute(pool)
+                        .await?;
+                    remote_id
+                }
+            };
+
+            let mut frames = self.local.get_frames_by_session(local_id).await?;
+            for frame in &mut frames {
+                frame.session_id = remote_id;
+            }
+            if !frames.is_empty() {
+                self.remote.insert_frames(&frames).await?;
+            }
+
+            let mut call_ids = HashMap::new();
+            for mut call in self.local.get_llm_calls_by_session(local_id).await? {
+                let local_call_id = call.id;
+                call.session_id = Some(remote_id);
+                let remote_call_id = self.remote.insert_llm_call(&call).await?;
+                if let Some(local_call_id) = local_call_id {
+                    call_ids.insert(local_call_id, remote_call_id);
+                }
+            }
+
+            for mut segment in self.local.get_video_segments_by_session(local_id).await? {
+                segment.session_id = remote_id;
+                segment.llm_call_id = segment
+                    .llm_call_id
+                    .and_then(|id| call_ids.get(&id).copied());
+                self.remote.insert_video_segment(&segment).await?;
+            }
+
+            let mut card_ids = HashMap::new();
+            for mut card in self.local.get_timeline_cards_by_session(local_id).await? {
+                let local_card_id = card.id;
+                card.session_id = remote_id;
+                card.llm_call_id = card.llm_call_id.and_then(|id| call_ids.get(&id).copied());
+                let remote_card_id = self.remote.insert_timeline_card(&card).await?;
+                if let Some(local_card_id) = local_card_id {
+                    card_ids.insert(local_card_id, remote_card_id);
+                }
+            }
+
+            // 以下三类记录按会话整体替换，重复写回也不会重复
+            let mut hits = self.local.get_keyword_hits_by_session(local_id).await?;
+            for hit in &mut hits {
+                hit.session_id = remote_id;
+                hit.card_id = hit.card_id.and_then(|id| card_ids.get(&id).copied());
+            }
+            self.remote.replace_keyword_hits(remote_id, &hits).await?;
+
+            let mut screen_text = self.local.get_screen_text_by_session(local_id).await?;
+            for record in &mut screen_text {
+                record.session_id = remote_id;
+            }
+            self.remote
+                .replace_screen_text(remote_id, &screen_text)
+                .await?;
+
+            let mut error_moments = self.local.get_error_moments_by_session(local_id).await?;
+            for record in &mut error_moments {
+                record.session_id = remote_id;
+            }
+            self.remote
+                .replace_error_moments(remote_id, &error_moments)
+                .await?;
+
+            self.local
+                .delete_timeline_cards_by_session(local_id)
+                .await?;
+            self.local
+                .delete_video_segments_by_session(local_id)
+                .await?;
+            self.local.delete_llm_calls_by_session(local_id).await?;
+            self.local.delete_frames_by_session(local_id).await?;
+            self.local.delete_session(local_id).await?;
+            sqlx::query("DELETE FROM queue_sync_ids WHERE local_id = ?")
+                .bind(local_id)
+                .execute(pool)
+                .await?;
+            info!("队列会话 {} 已写回远程，新 ID {}", local_id, remote_id);
+            synced += 1;
+        }
+        Ok(synced)
+    }
+}
+
+#[async_trait]
+impl DatabaseRepository for FailoverRepository {
+    async fn insert_session(&self, session: &Session) -> Result<i64> {
+        // 降级期间新建的会话写入本地队列，降级期间也能读到
+        let target = if self.is_degraded() {
+            WriteTarget::Local
+        } else {
+            self.write_target([])
+        };
+        write_through!(
+            self,
+            target,
+            insert_session(session),
+            OutboxOp::InsertSession {
+                session: session.clone()
+            },
+            |id| id
+        )
+    }
+
+    async fn insert_sessions(&self, sessions: &[Session]) -> Result<Vec<i64>> {
+        route_write!(self, [], insert_sessions(sessions))
+    }
+
+    async fn get_session(&self, session_id: i64) -> Result<Session> {
+        route!(self.get_session(session_id))
+    }
+
+    async fn get_session_detail(&self, session_id: i64) -> Result<SessionDetail> {
+        route!(self.get_session_detail(session_id))
+    }
+
+    async fn get_sessions_by_date(&self, date: &str) -> Result<Vec<Session>> {
+        route!(self.get_sessions_by_date(date))
+    }
+
+    async fn get_all_sessions(&self) -> Result<Vec<Session>> {
+        route!(self.get_all_sessions())
+    }
+
+    async fn update_session(
+        &self,
+        session_id: i64,
+        title: &str,
+        summary: &str,
+        video_path: Option<&str>,
+        tags: &str,
+    ) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            update_session(session_id, title, summary, video_path, tags),
+            OutboxOp::UpdateSession {
+                session_id,
+                title: title.to_string(),
+                summary: summary.to_string(),
+                video_path: video_path.map(str::to_string),
+                tags: tags.to_string(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn update_session_tags(&self, session_id: i64, tags: &str) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            update_session_tags(session_id, tags),
+            OutboxOp::UpdateSessionTags {
+                session_id,
+                tags: tags.to_string(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()> {
+        route_write!(self, [], update_sessions_tags(updates))
+    }
+
+    async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            update_session_video_path(session_id, video_path),
+            OutboxOp::UpdateSessionVideoPath {
+                session_id,
+                video_path: video_path.to_string(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn update_device_info_for_all_sessions(&self) -> Result<u64> {
+        route_write!(self, [], update_device_info_for_all_sessions())
+    }
+
+    async fn delete_session(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(self, [session_id], delete_session(session_id))
+    }
+
+    async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        let session_id = snapshot.session.id.unwrap_or_default();
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(self, [session_id], restore_session_snapshot(snapshot))
+    }
+
+    async fn get_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<Vec<Session>> {
+        route!(self.get_old_sessions(cutoff_date))
+    }
+
+    async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>> {
+        route!(self.get_empty_sessions(cutoff_date, device_name))
+    }
+
+    async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64> {
+        route_write!(self, [], delete_old_sessions(cutoff_date))
+    }
+
+    async fn insert_frame(&self, frame: &Frame) -> Result<i64> {
+        route_write!(self, [frame.session_id], insert_frame(frame))
+    }
+
+    async fn insert_frames(&self, frames: &[Frame]) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target(frames.iter().map(|frame| frame.session_id)),
+            insert_frames(frames),
+            OutboxOp::InsertFrames {
+                frames: frames.to_vec()
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_frames_by_session(&self, session_id: i64) -> Result<Vec<Frame>> {
+        route!(self.get_frames_by_session(session_id))
+    }
+
+    async fn delete_frames_by_session(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(self, [session_id], delete_frames_by_session(session_id))
+    }
+
+    async fn get_activities(&self, start_date: &str, end_date: &str) -> Result<Vec<Activity>> {
+        route!(self.get_activities(start_date, end_date))
+    }
+
+    async fn insert_llm_call(&self, record: &LLMCallRecord) -> Result<i64> {
+        write_through!(
+            self,
+            self.write_target(record.session_id),
+            insert_llm_call(record),
+            OutboxOp::InsertLlmCall {
+                record: record.clone()
+            },
+            |id| id
+        )
+    }
+
+    async fn get_llm_calls_by_session(&self, session_id: i64) -> Result<Vec<LLMCallRecord>> {
+        route!(self.get_llm_calls_by_session(session_id))
+    }
+
+    async fn get_llm_call_ids(&self) -> Result<Vec<i64>> {
+        route!(self.get_llm_call_ids())
+    }
+
+    async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>> {
+        route!(self.get_recent_llm_errors(limit))
+    }
+
+    async fn delete_llm_calls_by_session(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(self, [session_id], delete_llm_calls_by_session(session_id))
+    }
+
+    async fn insert_video_segment(&self, segment: &VideoSegmentRecord) -> Result<i64> {
+        route_write!(self, [segment.session_id], insert_video_segment(segment))
+    }
+
+    async fn insert_video_segments(&self, segments: &[VideoSegmentRecord]) -> Result<()> {
+        let ids = segments
+            .iter()
+            .flat_map(|segment| std::iter::once(segment.session_id).chain(segment.llm_call_id));
+        write_through!(
+            self,
+            self.write_target(ids),
+            insert_video_segments(segments),
+            OutboxOp::InsertVideoSegments {
+                segments: segments.to_vec()
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_video_segments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<VideoSegmentRecord>> {
+        route!(self.get_video_segments_by_session(session_id))
+    }
+
+    async fn delete_video_segments_by_session(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(
+            self,
+            [session_id],
+            delete_video_segments_by_session(session_id)
+        )
+    }
+
+    async fn insert_timeline_card(&self, card: &TimelineCardRecord) -> Result<i64> {
+        route_write!(self, [card.session_id], insert_timeline_card(card))
+    }
+
+    async fn insert_timeline_cards(&self, cards: &[TimelineCardRecord]) -> Result<()> {
+        let ids = cards
+            .iter()
+            .flat_map(|card| std::iter::once(card.session_id).chain(card.llm_call_id));
+        write_through!(
+            self,
+            self.write_target(ids),
+            insert_timeline_cards(cards),
+            OutboxOp::InsertTimelineCards {
+                cards: cards.to_vec()
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_timeline_cards_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        route!(self.get_timeline_cards_by_session(session_id))
+    }
+
+    async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>> {
+        route!(self.get_timeline_card(card_id))
+    }
+
+    async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        route!(self.get_recent_timeline_cards(limit))
+    }
+
+    async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        route!(self.get_timeline_cards_after(after_id, limit))
+    }
+
+    async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>> {
+        route!(self.get_timeline_cards_updated_after(after, after_id, limit))
+    }
+
+    async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(
+            self,
+            [session_id],
+            delete_timeline_cards_by_session(session_id)
+        )
+    }
+
+    async fn get_stats(&self) -> Result<(i64, i64, i64)> {
+        route!(self.get_stats())
+    }
+
+    async fn get_session_row_usage(&self, session_id: Option<i64>) -> Result<Vec<SessionRowUsage>> {
+        route!(self.get_session_row_usage(session_id))
+    }
+
+    async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
+        route!(self.get_analyzed_video_paths())
+    }
+
+    async fn save_day_summary(&self, date: &str, summary: &DaySummaryRecord) -> Result<()> {
+        route_write!(self, [], save_day_summary(date, summary))
+    }
+
+    async fn get_day_summary(&self, date: &str) -> Result<Option<DaySummaryRecord>> {
+        route!(self.get_day_summary(date))
+    }
+
+    async fn delete_day_summary(&self, date: &str) -> Result<()> {
+        route_write!(self, [], delete_day_summary(date))
+    }
+
+    async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()> {
+        route_write!(self, [], set_day_summary_finalized(date, finalized))
+    }
+
+    async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64> {
+        route_write!(self, [], insert_break_record(record))
+    }
+
+    async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>> {
+        route!(self.get_break_records_by_date(date))
+    }
+
+    async fn replace_keyword_hits(&self, session_id: i64, hits: &[KeywordHitRecord]) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            replace_keyword_hits(session_id, hits),
+            OutboxOp::ReplaceKeywordHits {
+                session_id,
+                hits: hits.to_vec(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        route!(self.get_keyword_hits_between(start, end))
+    }
+
+    async fn get_keyword_hits_by_session(&self, session_id: i64) -> Result<Vec<KeywordHitRecord>> {
+        route!(self.get_keyword_hits_by_session(session_id))
+    }
+
+    async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            replace_screen_text(session_id, records),
+            OutboxOp::ReplaceScreenText {
+                session_id,
+                records: records.to_vec(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_screen_text_by_session(&self, session_id: i64) -> Result<Vec<ScreenTextRecord>> {
+        route!(self.get_screen_text_by_session(session_id))
+    }
+
+    async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        route!(self.get_screen_text_between(start, end))
+    }
+
+    async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        route!(self.search_screen_text(pattern, language, limit))
+    }
+
+    async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()> {
+        write_through!(
+            self,
+            self.write_target([session_id]),
+            replace_error_moments(session_id, records),
+            OutboxOp::ReplaceErrorMoments {
+                session_id,
+                records: records.to_vec(),
+            },
+            |_| ()
+        )
+    }
+
+    async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        route!(self.get_error_moments_between(start, end))
+    }
+
+    async fn get_error_moments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        route!(self.get_error_moments_by_session(session_id))
+    }
+
+    async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64> {
+        route_write!(self, [], insert_clipboard_event(record))
+    }
+
+    async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        route!(self.get_clipboard_events_between(start, end))
+    }
+
+    async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        route!(self.search_clipboard_events(content_hash, pattern, limit))
+    }
+
+    async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()> {
+        route_write!(self, [], upsert_audio_presence(record))
+    }
+
+    async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>> {
+        route!(self.get_audio_presence_between(start, end))
+    }
+
+    async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64> {
+        route_write!(self, [], insert_deflected_distraction(record))
+    }
+
+    async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>> {
+        route!(self.get_deflected_distractions_by_date(date))
+    }
+
+    async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64> {
+        route_write!(self, [], insert_capture_pause(record))
+    }
+
+    async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()> {
+        route_write!(self, [], end_capture_pause(id, end_time))
+    }
+
+    async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        route_write!(self, [], close_open_capture_pauses(end_time))
+    }
+
+    async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>> {
+        route!(self.get_capture_pauses_between(start, end))
+    }
+
+    async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64> {
+        route_write!(self, [], insert_block_intent(record))
+    }
+
+    async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        route_write!(self, [], end_open_block_intents(end_time))
+    }
+
+    async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>> {
+        route!(self.get_block_intents_between(start, end))
+    }
+
+    async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [],
+            update_block_intent_alignment(id, alignment, aligned_minutes, active_minutes)
+        )
+    }
+
+    async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64> {
+        route_write!(self, [], insert_external_events(events))
+    }
+
+    async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>> {
+        route!(self.get_external_events_between(start, end))
+    }
+
+    async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_classification(card_id, category, distractions)
+        )
+    }
+
+    async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64> {
+        route_write!(self, [], rename_timeline_card_subcategory(from, to))
+    }
+
+    async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        route!(self.get_cards_needing_review(limit))
+    }
+
+    async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_review(card_id, title, category, subcategory, review_status)
+        )
+    }
+
+    async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_meeting_notes(card_id, meeting_notes)
+        )
+    }
+
+    async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_keyword_tags(card_id, keyword_tags)
+        )
+    }
+
+    async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_preview_clip(card_id, preview_clip_path)
+        )
+    }
+
+    async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_privacy_level(card_id, level)
+        )
+    }
+
+    async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [card_id],
+            update_timeline_card_times(card_id, start_time, end_time)
+        )
+    }
+
+    async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()> {
+        route_write!(
+            self,
+            [segment_id],
+            update_video_segment_times(segment_id, start_timestamp, end_timestamp)
+        )
+    }
+
+    async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64> {
+        route_write!(self, [], insert_card_correction(record))
+    }
+
+    async fn get_recent_card_corrections(&self, limit: i64) -> Result<Vec<CardCorrectionRecord>> {
+        route!(self.get_recent_card_corrections(limit))
+    }
+
+    async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()> {
+        route_write!(self, [], save_personal_record(record))
+    }
+
+    async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>> {
+        route!(self.get_personal_records())
+    }
+
+    async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()> {
+        route_write!(self, [], set_day_type(record))
+    }
+
+    async fn clear_day_type(&self, date: &str) -> Result<()> {
+        route_write!(self, [], clear_day_type(date))
+    }
+
+    async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>> {
+        route!(self.get_day_types_between(start_date, end_date))
+    }
+
+    async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64> {
+        route_write!(self, [], relocate_file_paths(old_prefix, new_prefix))
+    }
+
+    async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()> {
+        route_write!(self, [], add_storage_usage(usage))
+    }
+
+    async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>> {
+        route!(self.get_storage_usage(device_name, start_date, end_date))
+    }
+
+    async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(self, [session_id], set_session_pinned(session_id, pinned))
+    }
+
+    async fn get_pinned_sessions(&self) -> Result<Vec<Session>> {
+        route!(self.get_pinned_sessions())
+    }
+
+    async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(
+            self,
+            [session_id],
+            mark_session_archived(session_id, archive_key)
+        )
+    }
+
+    async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        route_write!(
+            self,
+            [session_id],
+            mark_session_restored(session_id, video_path)
+        )
+    }
+
+    async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()> {
+        Self::ensure_not_placeholder(record.session_id)?;
+        Self::ensure_not_queued(record.session_id)?;
+        route_write!(self, [], add_pending_deletion(record))
+    }
+
+    async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>> {
+        route!(self.get_pending_deletions())
+    }
+
+    async fn remove_pending_deletion(&self, session_id: i64) -> Result<()> {
+        Self::ensure_not_placeholder(session_id)?;
+        Self::ensure_not_queued(session_id)?;
+        route_write!(self, [], remove_pending_deletion(session_id))
+    }
+
+    async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        route_write!(self, [], claim_video(video_path, owner, now, lease_until))
+    }
+
+    async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()> {
+        route_write!(self, [], release_video_claim(video_path, owner))
+    }
+
+    async fn insert_provider_comparison(&self, record: &ProviderComparisonRecord) -> Result<i64> {
+        Self::ensure_not_placeholder(record.session_id)?;
+        Self::ensure_not_queued(record.session_id)?;
+        route_write!(self, [], insert_provider_comparison(record))
+    }
+
+    async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>> {
+        route!(self.get_provider_comparisons(session_id))
+    }
+
+    async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64> {
+        route_write!(self, [], insert_egress_event(record))
+    }
+
+    async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>> {
+        route!(self.get_egress_events_between(start, end))
+    }
+
+    async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64> {
+        Self::ensure_not_placeholder(record.session_id)?;
+        Self::ensure_not_queued(record.session_id)?;
+        route_write!(self, [], insert_session_bookmark(record))
+    }
+
+    async fn get_session_bookmarks(&self, session_id: i64) -> Result<Vec<SessionBookmarkRecord>> {
+        route!(self.get_session_bookmarks(session_id))
+    }
+
+    async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()> {
+        route_write!(self, [], delete_session_bookmark(bookmark_id))
+    }
+
+    async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>> {
+        route!(self.get_time_entry_export(idempotency_key))
+    }
+
+    async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>> {
+        route!(self.get_time_entry_exports_by_session(session_id))
+    }
+
+    async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()> {
+        route_write!(self, [], insert_time_entry_export(record))
+    }
+
+    async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64> {
+        Self::ensure_not_placeholder(record.session_id)?;
+        Self::ensure_not_queued(record.session_id)?;
+        route_write!(self, [], insert_webhook_delivery(record))
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        route!(self.get_webhook_deliveries(session_id, limit))
+    }
+
+    async fn initialize_tables(&self) -> Result<()> {
+        route!(self.initialize_tables())
+    }
+
+    async fn migrate_timezone_to_local(&self) -> Result<(u64, u64, u64, u64, u64, u64)> {
+        route_write!(self, [], migrate_timezone_to_local())
+    }
+
+    fn db_type(&self) -> &str {
+        "mariadb"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_threshold_and_backs_off() {
+        assert_eq!(backoff_delay(0, 5, 300), Duration::from_secs(5));
+        assert_eq!(backoff_delay(3, 5, 300), Duration::from_secs(40));
+        assert_eq!(backoff_delay(10, 5, 300), Duration::from_secs(300));
+
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, 5, 300);
+        assert!(!breaker.record_failure(start));
+        breaker.record_success();
+        assert!(!breaker.record_failure(start));
+        assert!(!breaker.record_failure(start));
+        assert!(breaker.record_failure(start));
+        assert!(breaker.is_open());
+
+        assert!(!breaker.probe_due(start + Duration::from_secs(4)));
+        assert!(breaker.probe_due(start + Duration::from_secs(5)));
+        breaker.probe_failed(start + Duration::from_secs(5));
+        assert!(!breaker.probe_due(start + Duration::from_secs(14)));
+        assert!(breaker.probe_due(start + Duration::from_secs(15)));
+
+        breaker.close();
+        assert!(!breaker.is_open());
+        assert!(!breaker.probe_due(start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_connection_errors_are_detected() {
+        assert!(is_connection_error(&anyhow::Error::from(
+            sqlx::Error::PoolTimedOut
+        )));
+        assert!(is_connection_error(&anyhow::Error::from(sqlx::Error::Io(
+            std::io::Error::from(std::io::ErrorKind::ConnectionRefused)
+        ))));
+        assert!(!is_connection_error(&anyhow::Error::from(
+            sqlx::Error::RowNotFound
+        )));
+    }
+    #[test]
+    fn test_queued_sessions_reject_unsynced_writes() {
+        assert!(FailoverRepository::ensure_not_queued(42).is_ok());
+        assert!(FailoverRepository::ensure_not_queued(LOCAL_ID_BASE).is_err());
+        assert!(FailoverRepository::ensure_not_queued(LOCAL_ID_BASE + 7).is_err());
+    }
+}
@@ -1,45 +1,182 @@
 // MariaDB 数据库实现
 
+use super::failover::backoff_delay;
 use super::DatabaseRepository;
-use crate::storage::config::get_device_info;
+use crate::storage::config::{get_device_info, MariaDbConnectionSettings};
 use crate::storage::models::*;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
 use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
 use sqlx::Row;
-use tracing::info;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// 所有表（按外键依赖排序，被引用的表在前）
+const TABLES: &[&str] = &[
+    "sessions",
+    "frames",
+    "llm_calls",
+    "video_segments",
+    "timeline_cards",
+    "day_summaries",
+    "card_corrections",
+    "break_records",
+    "keyword_hits",
+    "screen_text",
+    "error_moments",
+    "clipboard_events",
+    "audio_presence",
+    "deflected_distractions",
+    "storage_usage",
+    "capture_pauses",
+    "block_intents",
+    "external_events",
+    "personal_records",
+    "day_types",
+    "pending_deletions",
+    "video_claims",
+    "provider_comparisons",
+    "egress_log",
+    "session_bookmarks",
+    "time_entry_exports",
+    "webhook_deliveries",
+];
+
+/// 表前缀的最大长度（MariaDB 表名最长 64 个字符）
+const MAX_TABLE_PREFIX_LEN: usize = 32;
+
+/// 表名出现的位置：大写的 FROM/JOIN/INTO/UPDATE/TABLE/REFERENCES/EXISTS 以及 CREATE INDEX 的 ON 之后，
+/// 和表同名的列名、别名以及字符串字面量中的文字不会被改写
+fn table_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(&format!(
+            r"\b((?:FROM|JOIN|INTO|UPDATE|TABLE|REFERENCES|EXISTS|ON)\s+)({})\b",
+            TABLES.join("|")
+        ))
+        .unwrap()
+    })
+}
+
+/// 检查表前缀：只能包含字母、数字和下划线
+pub fn validate_table_prefix(prefix: &str) -> Result<()> {
+    if prefix.len() > MAX_TABLE_PREFIX_LEN {
+        return Err(anyhow::anyhow!(
+            "表前缀不能超过 {} 个字符",
+            MAX_TABLE_PREFIX_LEN
+        ));
+    }
+    if !prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(anyhow::anyhow!(
+            "表前缀只能包含字母、数字和下划线: {}",
+            prefix
+        ));
+    }
+    Ok(())
+}
+
+/// 为 SQL 中的表名加上前缀
+fn prefix_tables(query: &str, prefix: &str) -> String {
+    if prefix.is_empty() {
+        return query.to_string();
+    }
+    table_pattern()
+        .replace_all(query, |caps: &Captures| {
+            format!("{}{}{}", &caps[1], prefix, &caps[2])
+        })
+        .into_owned()
+}
+
+/// MariaDB 连接参数
+pub struct MariaDbOptions<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    pub database: &'a str,
+    pub username: &'a str,
+    pub password: &'a str,
+    /// 表前缀（空表示不加前缀）
+    pub table_prefix: &'a str,
+    /// 从该前缀的表迁移数据
+    pub migrate_from_prefix: Option<&'a str>,
+    /// 连接池和重连设置
+    pub connection: &'a MariaDbConnectionSettings,
+}
 
 /// MariaDB 数据库实现
+///
+/// 多个用户共用一个 MariaDB 数据库时，可以为每个安装设置不同的表前缀（如 alice_sessions），
+/// 所有 SQL 在执行前通过 `sql()` 把表名替换为带前缀的表名
 pub struct MariaDbRepository {
     pool: MySqlPool,
+    /// 表前缀（空表示不加前缀）
+    table_prefix: String,
 }
 
 impl MariaDbRepository {
-    /// 创建新的 MariaDB 数据库连接
-    pub async fn new(
-        host: &str,
-        port: u16,
-        database: &str,
-        username: &str,
-        password: &str,
-    ) -> Result<Self> {
+    /// 创建新的 MariaDB 数据库连接，失败时按退避间隔重试
+    ///
+    /// `migrate_from_prefix` 不为空时，若当前前缀的表中还没有数据，从该前缀的表复制数据
+    pub async fn new(options: &MariaDbOptions<'_>) -> Result<Self> {
+        let connection = options.connection;
+        let mut attempt = 0;
+        loop {
+            match Self::connect(options).await {
+                Ok(repo) => return Ok(repo),
+                Err(e) if attempt < connection.connect_retries => {
+                    let delay = backoff_delay(
+                        attempt,
+                        connection.retry_base_secs,
+                        connection.retry_max_secs,
+                    );
+                    warn!(
+                        "连接 MariaDB 失败（第 {} 次），{} 秒后重试: {}",
+                        attempt + 1,
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn connect(options: &MariaDbOptions<'_>) -> Result<Self> {
+        let MariaDbOptions {
+            host,
+            port,
+            database,
+            username,
+            password,
+            table_prefix,
+            migrate_from_prefix,
+            connection,
+        } = *options;
+        validate_table_prefix(table_prefix)?;
         info!(
-            "初始化 MariaDB 数据库: {}@{}:{}/{}",
-            username, host, port, database
+            "初始化 MariaDB 数据库: {}@{}:{}/{}（表前缀: {:?}）",
+            username, host, port, database, table_prefix
         );
 
         // 先连接到 MySQL 服务器（不指定数据库），检查并创建数据库
         // 添加连接超时参数
         let server_url = format!(
-            "mysql://{}:{}@{}:{}?connect_timeout=30",
-            username, password, host, port
+            "mysql://{}:{}@{}:{}?connect_timeout={}",
+            username, password, host, port, connection.connect_timeout_secs
         );
 
         info!("连接到 MariaDB 服务器检查数据库是否存在...");
         let server_pool = MySqlPoolOptions::new()
             .max_connections(1)
-            .acquire_timeout(std::time::Duration::from_secs(30))
+            .acquire_timeout(std::time::Duration::from_secs(
+                connection.acquire_timeout_secs,
+            ))
             .connect(&server_url)
             .await
             .map_err(|e| {
@@ -76,18 +213,20 @@ impl MariaDbRepository {
         // 连接到指定数据库
         // 添加连接超时参数
         let connection_url = format!(
-            "mysql://{}:{}@{}:{}/{}?connect_timeout=30",
-            username, password, host, port, database
+            "mysql://{}:{}@{}:{}/{}?connect_timeout={}",
+            username, password, host, port, database, connection.connect_timeout_secs
         );
 
         // 创建连接池
         info!("创建 MariaDB 连接池...");
         let pool = MySqlPoolOptions::new()
-            .max_connections(20)
-            .min_connections(2)
-            .idle_timeout(std::time::Duration::from_secs(180))
-            .max_lifetime(std::time::Duration::from_secs(1800))
-            .acquire_timeout(std::time::Duration::from_secs(30))
+            .max_connections(connection.max_connections.max(1))
+            .min_connections(connection.min_connections.min(connection.max_connections))
+            .idle_timeout(std::time::Duration::from_secs(connection.idle_timeout_secs))
+            .max_lifetime(std::time::Duration::from_secs(connection.max_lifetime_secs))
+            .acquire_timeout(std::time::Duration::from_secs(
+                connection.acquire_timeout_secs,
+            ))
             .connect(&connection_url)
             .await
             .map_err(|e| {
@@ -102,10 +241,13 @@ impl MariaDbRepository {
 
         info!("MariaDB 连接池创建成功");
 
-        let repo = Self { pool };
+        let repo = Self {
+            pool,
+            table_prefix: table_prefix.to_string(),
+        };
 
         // 检查表是否存在，不存在则初始化
-        let tables_exist = repo.check_tables_exist().await?;
+        let tables_exist = repo.check_tables_exist(table_prefix).await?;
         if !tables_exist {
             info!("MariaDB 表不存在，开始初始化表结构");
             repo.initialize_tables().await?;
@@ -113,11 +255,29 @@ impl MariaDbRepository {
             info!("MariaDB 表已存在，直接使用");
         }
 
+        if let Some(source_prefix) = migrate_from_prefix {
+            if source_prefix != table_prefix {
+                validate_table_prefix(source_prefix)?;
+                repo.migrate_from_prefix(source_prefix).await?;
+            }
+        }
+
         Ok(repo)
     }
 
-    /// 检查所有必要的表是否存在
-    async fn check_tables_exist(&self) -> Result<bool> {
+    /// 检查远程数据库是否可以连接
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// 为 SQL 中的表名加上当前的表前缀
+    fn sql(&self, query: &str) -> String {
+        prefix_tables(query, &self.table_prefix)
+    }
+
+    /// 检查指定前缀的所有必要的表是否存在
+    async fn check_tables_exist(&self, prefix: &str) -> Result<bool> {
         let tables = vec![
             "sessions",
             "frames",
@@ -131,7 +291,7 @@ impl MariaDbRepository {
             let count: i64 = sqlx::query_scalar(
                 "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = ?",
             )
-            .bind(table)
+            .bind(format!("{}{}", prefix, table))
             .fetch_one(&self.pool)
             .await?;
 
@@ -143,6 +303,82 @@ impl MariaDbRepository {
         Ok(true)
     }
 
+    /// 表的所有列名
+    async fn table_columns(&self, table: &str) -> Result<Vec<String>> {
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position",
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(columns)
+    }
+
+    /// 当前前缀的表中是否已有指定列（迁移时检查）
+    async fn column_exists(&self, table: &str, column: &str) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = ? AND column_name = ?",
+        )
+        .bind(format!("{}{}", self.table_prefix, table))
+        .bind(column)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// 从另一个前缀的表复制数据（如开始使用前缀前的无前缀表）
+    ///
+    /// 只在当前前缀的表中还没有会话时复制，已有数据时跳过，重复启动不会重复复制；
+    /// 原来的表保持不变。两边都有的列才会复制，兼容旧版本缺少的列
+    async fn migrate_from_prefix(&self, source_prefix: &str) -> Result<()> {
+        if !self.check_tables_exist(source_prefix).await? {
+            warn!("迁移来源前缀 {:?} 的表不存在，跳过迁移", source_prefix);
+            return Ok(());
+        }
+        let existing: i64 = sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions"))
+            .fetch_one(&self.pool)
+            .await?;
+        if existing > 0 {
+            info!("当前前缀的表中已有数据，跳过迁移");
+            return Ok(());
+        }
+
+        info!(
+            "从前缀 {:?} 的表迁移数据到前缀 {:?}",
+            source_prefix, self.table_prefix
+        );
+        let mut tx = self.pool.begin().await?;
+        for table in TABLES {
+            let source = format!("{}{}", source_prefix, table);
+            let target = format!("{}{}", self.table_prefix, table);
+            let source_columns = self.table_columns(&source).await?;
+            if source_columns.is_empty() {
+                continue;
+            }
+            let target_columns = self.table_columns(&target).await?;
+            let columns: Vec<String> = target_columns
+                .into_iter()
+                .filter(|column| source_columns.contains(column))
+                .map(|column| format!("`{}`", column))
+                .collect();
+            if columns.is_empty() {
+                continue;
+            }
+            let columns = columns.join(", ");
+            let copied = sqlx::query(&format!(
+                "INSERT INTO `{}` ({}) SELECT {} FROM `{}`",
+                target, columns, columns, source
+            ))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            info!("迁移表 {} -> {}: {} 行", source, target, copied);
+        }
+        tx.commit().await?;
+        info!("表前缀迁移完成");
+        Ok(())
+    }
+
     /// 获取连接池引用（用于向后兼容）
     pub fn get_pool(&self) -> &MySqlPool {
         &self.pool
@@ -154,12 +390,12 @@ impl DatabaseRepository for MariaDbRepository {
     // ========== 会话操作 ==========
 
     async fn insert_session(&self, session: &Session) -> Result<i64> {
-        let result = sqlx::query(
+        let result = sqlx::query(&self.sql(
             r#"
             INSERT INTO sessions (start_time, end_time, title, summary, video_path, tags, device_name, device_type)
             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         "#,
-        )
+        ))
         .bind(&session.start_time)
         .bind(&session.end_time)
         .bind(&session.title)
@@ -179,12 +415,12 @@ impl DatabaseRepository for MariaDbRepository {
         let mut tx = self.pool.begin().await?;
 
         for session in sessions {
-            let result = sqlx::query(
+            let result = sqlx::query(&self.sql(
                 r#"
                 INSERT INTO sessions (start_time, end_time, title, summary, video_path, tags, device_name, device_type)
                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-            )
+            ))
             .bind(&session.start_time)
             .bind(&session.end_time)
             .bind(&session.title)
@@ -204,14 +440,15 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn get_session(&self, session_id: i64) -> Result<Session> {
-        let session = sqlx::query_as::<_, Session>(
+        let session = sqlx::query_as::<_, Session>(&self.sql(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             WHERE id = ?
             "#,
-        )
+        ))
         .bind(session_id)
         .fetch_one(&self.pool)
         .await?;
@@ -228,6 +465,7 @@ impl DatabaseRepository for MariaDbRepository {
             session,
             frames,
             tags,
+            storage: None,
         })
     }
 
@@ -236,15 +474,16 @@ impl DatabaseRepository for MariaDbRepository {
         let start_datetime = format!("{} 00:00:00", date);
         let end_datetime = format!("{} 23:59:59", date);
 
-        let sessions = sqlx::query_as::<_, Session>(
+        let sessions = sqlx::query_as::<_, Session>(&self.sql(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             WHERE start_time >= ? AND start_time <= ?
             ORDER BY start_time DESC
             "#,
-        )
+        ))
         .bind(&start_datetime)
         .bind(&end_datetime)
         .fetch_all(&self.pool)
@@ -254,14 +493,15 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn get_all_sessions(&self) -> Result<Vec<Session>> {
-        let sessions = sqlx::query_as::<_, Session>(
+        let sessions = sqlx::query_as::<_, Session>(&self.sql(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             ORDER BY start_time
             "#,
-        )
+        ))
         .fetch_all(&self.pool)
         .await?;
 
@@ -276,9 +516,9 @@ impl DatabaseRepository for MariaDbRepository {
         video_path: Option<&str>,
         tags: &str,
     ) -> Result<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             "UPDATE sessions SET title = ?, summary = ?, video_path = ?, tags = ? WHERE id = ?",
-        )
+        ))
         .bind(title)
         .bind(summary)
         .bind(video_path)
@@ -291,7 +531,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn update_session_tags(&self, session_id: i64, tags: &str) -> Result<()> {
-        sqlx::query("UPDATE sessions SET tags = ? WHERE id = ?")
+        sqlx::query(&self.sql("UPDATE sessions SET tags = ? WHERE id = ?"))
             .bind(tags)
             .bind(session_id)
             .execute(&self.pool)
@@ -300,8 +540,22 @@ impl DatabaseRepository for MariaDbRepository {
         Ok(())
     }
 
+    async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()> {
+        let sql = self.sql("UPDATE sessions SET tags = ? WHERE id = ?");
+        let mut tx = self.pool.begin().await?;
+        for (session_id, tags) in updates {
+            sqlx::query(&sql)
+                .bind(tags)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
     async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()> {
-        sqlx::query("UPDATE sessions SET video_path = ? WHERE id = ?")
+        sqlx::query(&self.sql("UPDATE sessions SET video_path = ? WHERE id = ?"))
             .bind(video_path)
             .bind(session_id)
             .execute(&self.pool)
@@ -313,9 +567,9 @@ impl DatabaseRepository for MariaDbRepository {
     async fn update_device_info_for_all_sessions(&self) -> Result<u64> {
         let (device_name, device_type) = get_device_info();
 
-        let result = sqlx::query(
-            "UPDATE sessions SET device_name = ?, device_type = ? WHERE device_name IS NULL OR device_type = 'desktop'"
-        )
+        let result = sqlx::query(&self.sql(
+            "UPDATE sessions SET device_name = ?, device_type = ? WHERE device_name IS NULL OR device_type = 'desktop'",
+        ))
         .bind(&device_name)
         .bind(&device_type)
         .execute(&self.pool)
@@ -334,7 +588,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_session(&self, session_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM sessions WHERE id = ?")
+        sqlx::query(&self.sql("DELETE FROM sessions WHERE id = ?"))
             .bind(session_id)
             .execute(&self.pool)
             .await?;
@@ -343,13 +597,268 @@ impl DatabaseRepository for MariaDbRepository {
         Ok(())
     }
 
+    async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        let session = &snapshot.session;
+        let session_id = session
+            .id
+            .ok_or_else(|| anyhow::anyhow!("会话快照缺少会话 ID"))?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO sessions (
+                id, start_time, end_time, title, summary, video_path, tags, created_at,
+                device_name, device_type, pinned, archived, archive_key
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(session_id)
+        .bind(session.start_time)
+        .bind(session.end_time)
+        .bind(&session.title)
+        .bind(&session.summary)
+        .bind(&session.video_path)
+        .bind(&session.tags)
+        .bind(session.created_at)
+        .bind(&session.device_name)
+        .bind(&session.device_type)
+        .bind(session.pinned)
+        .bind(session.archived)
+        .bind(&session.archive_key)
+        .execute(&mut *tx)
+        .await?;
+
+        for frame in &snapshot.frames {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO frames (id, session_id, timestamp, file_path, metadata)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(frame.id)
+            .bind(session_id)
+            .bind(frame.timestamp)
+            .bind(&frame.file_path)
+            .bind(&frame.metadata)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for call in &snapshot.llm_calls {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO llm_calls (
+                    id, session_id, provider, model, call_type,
+                    request_headers, request_body, response_headers, response_body,
+                    status_code, error_message, latency_ms, token_usage, created_at,
+                    prompt_version
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(call.id)
+            .bind(session_id)
+            .bind(&call.provider)
+            .bind(&call.model)
+            .bind(&call.call_type)
+            .bind(&call.request_headers)
+            .bind(&call.request_body)
+            .bind(&call.response_headers)
+            .bind(&call.response_body)
+            .bind(call.status_code)
+            .bind(&call.error_message)
+            .bind(call.latency_ms)
+            .bind(&call.token_usage)
+            .bind(call.created_at)
+            .bind(&call.prompt_version)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for segment in &snapshot.segments {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO video_segments (
+                    id, session_id, llm_call_id, start_timestamp, end_timestamp,
+                    description, created_at
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(segment.id)
+            .bind(session_id)
+            .bind(segment.llm_call_id)
+            .bind(&segment.start_timestamp)
+            .bind(&segment.end_timestamp)
+            .bind(&segment.description)
+            .bind(segment.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for card in &snapshot.cards {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO timeline_cards (
+                    id, session_id, llm_call_id, start_time, end_time,
+                    category, subcategory, title, summary, detailed_summary,
+                    distractions, app_sites, video_preview_path, created_at,
+                    confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                    privacy_level
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(card.id)
+            .bind(session_id)
+            .bind(card.llm_call_id)
+            .bind(&card.start_time)
+            .bind(&card.end_time)
+            .bind(&card.category)
+            .bind(&card.subcategory)
+            .bind(&card.title)
+            .bind(&card.summary)
+            .bind(&card.detailed_summary)
+            .bind(&card.distractions)
+            .bind(&card.app_sites)
+            .bind(&card.video_preview_path)
+            .bind(card.created_at)
+            .bind(card.confidence)
+            .bind(&card.review_status)
+            .bind(&card.meeting_notes)
+            .bind(&card.keyword_tags)
+            .bind(&card.preview_clip_path)
+            .bind(card.privacy_level)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for bookmark in &snapshot.bookmarks {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO session_bookmarks (id, session_id, video_time, note, created_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(bookmark.id)
+            .bind(session_id)
+            .bind(bookmark.video_time)
+            .bind(&bookmark.note)
+            .bind(bookmark.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for hit in &snapshot.keyword_hits {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO keyword_hits (id, session_id, card_id, keyword, source, excerpt, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(hit.id)
+            .bind(session_id)
+            .bind(hit.card_id)
+            .bind(&hit.keyword)
+            .bind(&hit.source)
+            .bind(&hit.excerpt)
+            .bind(hit.occurred_at)
+            .bind(hit.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for record in &snapshot.screen_text {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO screen_text (id, session_id, segment_start, segment_end, text, language, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(record.id)
+            .bind(session_id)
+            .bind(record.segment_start)
+            .bind(record.segment_end)
+            .bind(&record.text)
+            .bind(&record.language)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for record in &snapshot.error_moments {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO error_moments (id, session_id, kind, error_text, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(record.id)
+            .bind(session_id)
+            .bind(&record.kind)
+            .bind(&record.error_text)
+            .bind(record.occurred_at)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // 导出记录不随会话删除，已存在的跳过
+        for export in &snapshot.time_entry_exports {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT IGNORE INTO time_entry_exports
+                    (idempotency_key, provider, session_id, card_id, external_id, exported_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(&export.idempotency_key)
+            .bind(&export.provider)
+            .bind(session_id)
+            .bind(export.card_id)
+            .bind(&export.external_id)
+            .bind(export.exported_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        info!("已按原 ID 恢复会话: {}", session_id);
+        Ok(())
+    }
+
     async fn get_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<Vec<Session>> {
-        let sessions = sqlx::query_as::<_, Session>(
-            "SELECT id, start_time, end_time, title, summary, video_path, tags, created_at, device_name, device_type
+        let sessions = sqlx::query_as::<_, Session>(&self.sql(
+            "SELECT id, start_time, end_time, title, summary, video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
              FROM sessions
-             WHERE start_time < ?"
-        )
+             WHERE start_time < ?",
+        ))
+        .bind(cutoff_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(&self.sql(
+            "SELECT s.id, s.start_time, s.end_time, s.title, s.summary, s.video_path, s.tags, s.created_at,
+                   s.device_name, s.device_type, s.pinned, s.archived, s.archive_key
+             FROM sessions s
+             WHERE s.start_time < ? AND s.pinned = 0 AND s.archived = 0
+               AND (s.device_name = ? OR s.device_name IS NULL)
+               AND NOT EXISTS (SELECT 1 FROM timeline_cards c WHERE c.session_id = s.id)
+               AND NOT EXISTS (SELECT 1 FROM frames f WHERE f.session_id = s.id)
+             ORDER BY s.start_time",
+        ))
         .bind(cutoff_date)
+        .bind(device_name)
         .fetch_all(&self.pool)
         .await?;
 
@@ -357,7 +866,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64> {
-        let result = sqlx::query("DELETE FROM sessions WHERE start_time < ?")
+        let result = sqlx::query(&self.sql("DELETE FROM sessions WHERE start_time < ?"))
             .bind(cutoff_date)
             .execute(&self.pool)
             .await?;
@@ -374,15 +883,16 @@ impl DatabaseRepository for MariaDbRepository {
     // ========== 帧操作 ==========
 
     async fn insert_frame(&self, frame: &Frame) -> Result<i64> {
-        let result = sqlx::query(
+        let result = sqlx::query(&self.sql(
             r#"
-            INSERT INTO frames (session_id, timestamp, file_path)
-            VALUES (?, ?, ?)
+            INSERT INTO frames (session_id, timestamp, file_path, metadata)
+            VALUES (?, ?, ?, ?)
         "#,
-        )
+        ))
         .bind(frame.session_id)
         .bind(&frame.timestamp)
         .bind(&frame.file_path)
+        .bind(&frame.metadata)
         .execute(&self.pool)
         .await?;
 
@@ -393,15 +903,16 @@ impl DatabaseRepository for MariaDbRepository {
         let mut tx = self.pool.begin().await?;
 
         for frame in frames {
-            sqlx::query(
+            sqlx::query(&self.sql(
                 r#"
-                INSERT INTO frames (session_id, timestamp, file_path)
-                VALUES (?, ?, ?)
+                INSERT INTO frames (session_id, timestamp, file_path, metadata)
+                VALUES (?, ?, ?, ?)
             "#,
-            )
+            ))
             .bind(frame.session_id)
             .bind(&frame.timestamp)
             .bind(&frame.file_path)
+            .bind(&frame.metadata)
             .execute(&mut *tx)
             .await?;
         }
@@ -411,14 +922,14 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn get_frames_by_session(&self, session_id: i64) -> Result<Vec<Frame>> {
-        let frames = sqlx::query_as::<_, Frame>(
+        let frames = sqlx::query_as::<_, Frame>(&self.sql(
             r#"
-            SELECT id, session_id, timestamp, file_path
+            SELECT id, session_id, timestamp, file_path, metadata
             FROM frames
             WHERE session_id = ?
             ORDER BY timestamp
             "#,
-        )
+        ))
         .bind(session_id)
         .fetch_all(&self.pool)
         .await?;
@@ -427,7 +938,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_frames_by_session(&self, session_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM frames WHERE session_id = ?")
+        sqlx::query(&self.sql("DELETE FROM frames WHERE session_id = ?"))
             .bind(session_id)
             .execute(&self.pool)
             .await?;
@@ -442,7 +953,7 @@ impl DatabaseRepository for MariaDbRepository {
         let start_datetime = format!("{} 00:00:00", start_date);
         let end_datetime = format!("{} 23:59:59", end_date);
 
-        let rows = sqlx::query(
+        let rows = sqlx::query(&self.sql(
             r#"
             SELECT
                 DATE_FORMAT(DATE(start_time), '%Y-%m-%d') as date,
@@ -453,8 +964,8 @@ impl DatabaseRepository for MariaDbRepository {
             WHERE start_time >= ? AND start_time <= ?
             GROUP BY DATE_FORMAT(DATE(start_time), '%Y-%m-%d')
             ORDER BY date DESC
-            "#
-        )
+            "#,
+        ))
         .bind(&start_datetime)
         .bind(&end_datetime)
         .fetch_all(&self.pool)
@@ -476,6 +987,7 @@ impl DatabaseRepository for MariaDbRepository {
                 session_count: session_count as i32,
                 total_duration_minutes: total_duration_minutes.unwrap_or(0) as i32,
                 main_categories,
+                external_minutes: 0,
             });
         }
 
@@ -487,10 +999,11 @@ impl DatabaseRepository for MariaDbRepository {
     async fn insert_llm_call(&self, record: &LLMCallRecord) -> Result<i64> {
         // 检查 session_id 是否存在（如果不是 NULL）
         if let Some(sid) = record.session_id {
-            let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = ?")
-                .bind(sid)
-                .fetch_one(&self.pool)
-                .await?;
+            let exists: i64 =
+                sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions WHERE id = ?"))
+                    .bind(sid)
+                    .fetch_one(&self.pool)
+                    .await?;
 
             if exists == 0 {
                 return Err(anyhow::anyhow!(
@@ -500,16 +1013,17 @@ impl DatabaseRepository for MariaDbRepository {
             }
         }
 
-        let result = sqlx::query(
+        let result = sqlx::query(&self.sql(
             r#"
             INSERT INTO llm_calls (
                 session_id, provider, model, call_type,
                 request_headers, request_body, response_headers, response_body,
-                status_code, error_message, latency_ms, token_usage, created_at
+                status_code, error_message, latency_ms, token_usage, created_at,
+                prompt_version
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
-        )
+        ))
         .bind(&record.session_id)
         .bind(&record.provider)
         .bind(&record.model)
@@ -523,6 +1037,7 @@ impl DatabaseRepository for MariaDbRepository {
         .bind(&record.latency_ms)
         .bind(&record.token_usage)
         .bind(&record.created_at)
+        .bind(&record.prompt_version)
         .execute(&self.pool)
         .await?;
 
@@ -530,13 +1045,13 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn get_llm_calls_by_session(&self, session_id: i64) -> Result<Vec<LLMCallRecord>> {
-        let records = sqlx::query_as::<_, LLMCallRecord>(
+        let records = sqlx::query_as::<_, LLMCallRecord>(&self.sql(
             r#"
             SELECT * FROM llm_calls
             WHERE session_id = ?
             ORDER BY created_at DESC
             "#,
-        )
+        ))
         .bind(session_id)
         .fetch_all(&self.pool)
         .await?;
@@ -544,15 +1059,23 @@ impl DatabaseRepository for MariaDbRepository {
         Ok(records)
     }
 
+    async fn get_llm_call_ids(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>(&self.sql("SELECT id FROM llm_calls"))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids)
+    }
+
     async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>> {
-        let records = sqlx::query_as::<_, LLMCallRecord>(
+        let records = sqlx::query_as::<_, LLMCallRecord>(&self.sql(
             r#"
             SELECT * FROM llm_calls
             WHERE error_message IS NOT NULL
             ORDER BY created_at DESC
             LIMIT ?
             "#,
-        )
+        ))
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
@@ -561,7 +1084,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_llm_calls_by_session(&self, session_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM llm_calls WHERE session_id = ?")
+        sqlx::query(&self.sql("DELETE FROM llm_calls WHERE session_id = ?"))
             .bind(session_id)
             .execute(&self.pool)
             .await?;
@@ -573,10 +1096,11 @@ impl DatabaseRepository for MariaDbRepository {
 
     async fn insert_video_segment(&self, segment: &VideoSegmentRecord) -> Result<i64> {
         // 检查 session_id 是否存在
-        let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = ?")
-            .bind(segment.session_id)
-            .fetch_one(&self.pool)
-            .await?;
+        let exists: i64 =
+            sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions WHERE id = ?"))
+                .bind(segment.session_id)
+                .fetch_one(&self.pool)
+                .await?;
 
         if exists == 0 {
             return Err(anyhow::anyhow!(
@@ -585,7 +1109,7 @@ impl DatabaseRepository for MariaDbRepository {
             ));
         }
 
-        let result = sqlx::query(
+        let result = sqlx::query(&self.sql(
             r#"
             INSERT INTO video_segments (
                 session_id, llm_call_id, start_timestamp, end_timestamp,
@@ -593,7 +1117,7 @@ impl DatabaseRepository for MariaDbRepository {
             )
             VALUES (?, ?, ?, ?, ?, ?)
         "#,
-        )
+        ))
         .bind(&segment.session_id)
         .bind(&segment.llm_call_id)
         .bind(&segment.start_timestamp)
@@ -613,10 +1137,11 @@ impl DatabaseRepository for MariaDbRepository {
 
         // 检查所有 session_id 是否存在
         for segment in segments {
-            let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = ?")
-                .bind(segment.session_id)
-                .fetch_one(&self.pool)
-                .await?;
+            let exists: i64 =
+                sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions WHERE id = ?"))
+                    .bind(segment.session_id)
+                    .fetch_one(&self.pool)
+                    .await?;
 
             if exists == 0 {
                 return Err(anyhow::anyhow!(
@@ -629,7 +1154,7 @@ impl DatabaseRepository for MariaDbRepository {
         let mut tx = self.pool.begin().await?;
 
         for segment in segments {
-            sqlx::query(
+            sqlx::query(&self.sql(
                 r#"
                 INSERT INTO video_segments (
                     session_id, llm_call_id, start_timestamp, end_timestamp,
@@ -637,7 +1162,7 @@ impl DatabaseRepository for MariaDbRepository {
                 )
                 VALUES (?, ?, ?, ?, ?, ?)
             "#,
-            )
+            ))
             .bind(&segment.session_id)
             .bind(&segment.llm_call_id)
             .bind(&segment.start_timestamp)
@@ -656,13 +1181,13 @@ impl DatabaseRepository for MariaDbRepository {
         &self,
         session_id: i64,
     ) -> Result<Vec<VideoSegmentRecord>> {
-        let segments = sqlx::query_as::<_, VideoSegmentRecord>(
+        let segments = sqlx::query_as::<_, VideoSegmentRecord>(&self.sql(
             r#"
             SELECT * FROM video_segments
             WHERE session_id = ?
             ORDER BY start_timestamp
             "#,
-        )
+        ))
         .bind(session_id)
         .fetch_all(&self.pool)
         .await?;
@@ -671,7 +1196,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_video_segments_by_session(&self, session_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM video_segments WHERE session_id = ?")
+        sqlx::query(&self.sql("DELETE FROM video_segments WHERE session_id = ?"))
             .bind(session_id)
             .execute(&self.pool)
             .await?;
@@ -683,10 +1208,11 @@ impl DatabaseRepository for MariaDbRepository {
 
     async fn insert_timeline_card(&self, card: &TimelineCardRecord) -> Result<i64> {
         // 检查 session_id 是否存在
-        let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = ?")
-            .bind(card.session_id)
-            .fetch_one(&self.pool)
-            .await?;
+        let exists: i64 =
+            sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions WHERE id = ?"))
+                .bind(card.session_id)
+                .fetch_one(&self.pool)
+                .await?;
 
         if exists == 0 {
             return Err(anyhow::anyhow!(
@@ -695,16 +1221,18 @@ impl DatabaseRepository for MariaDbRepository {
             ));
         }
 
-        let result = sqlx::query(
+        let result = sqlx::query(&self.sql(
             r#"
             INSERT INTO timeline_cards (
                 session_id, llm_call_id, start_time, end_time,
                 category, subcategory, title, summary, detailed_summary,
-                distractions, app_sites, video_preview_path, created_at
+                distractions, app_sites, video_preview_path, created_at,
+                confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                privacy_level
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
-        )
+        ))
         .bind(&card.session_id)
         .bind(&card.llm_call_id)
         .bind(&card.start_time)
@@ -718,6 +1246,12 @@ impl DatabaseRepository for MariaDbRepository {
         .bind(&card.app_sites)
         .bind(&card.video_preview_path)
         .bind(&card.created_at)
+        .bind(card.confidence)
+        .bind(&card.review_status)
+        .bind(&card.meeting_notes)
+        .bind(&card.keyword_tags)
+        .bind(&card.preview_clip_path)
+        .bind(card.privacy_level)
         .execute(&self.pool)
         .await?;
 
@@ -731,10 +1265,11 @@ impl DatabaseRepository for MariaDbRepository {
 
         // 检查所有 session_id 是否存在
         for card in cards {
-            let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = ?")
-                .bind(card.session_id)
-                .fetch_one(&self.pool)
-                .await?;
+            let exists: i64 =
+                sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions WHERE id = ?"))
+                    .bind(card.session_id)
+                    .fetch_one(&self.pool)
+                    .await?;
 
             if exists == 0 {
                 return Err(anyhow::anyhow!(
@@ -747,16 +1282,18 @@ impl DatabaseRepository for MariaDbRepository {
         let mut tx = self.pool.begin().await?;
 
         for card in cards {
-            sqlx::query(
+            sqlx::query(&self.sql(
                 r#"
                 INSERT INTO timeline_cards (
                     session_id, llm_call_id, start_time, end_time,
                     category, subcategory, title, summary, detailed_summary,
-                    distractions, app_sites, video_preview_path, created_at
+                    distractions, app_sites, video_preview_path, created_at,
+                    confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                    privacy_level
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-            )
+            ))
             .bind(&card.session_id)
             .bind(&card.llm_call_id)
             .bind(&card.start_time)
@@ -770,6 +1307,12 @@ impl DatabaseRepository for MariaDbRepository {
             .bind(&card.app_sites)
             .bind(&card.video_preview_path)
             .bind(&card.created_at)
+            .bind(card.confidence)
+            .bind(&card.review_status)
+            .bind(&card.meeting_notes)
+            .bind(&card.keyword_tags)
+            .bind(&card.preview_clip_path)
+            .bind(card.privacy_level)
             .execute(&mut *tx)
             .await?;
         }
@@ -782,13 +1325,13 @@ impl DatabaseRepository for MariaDbRepository {
         &self,
         session_id: i64,
     ) -> Result<Vec<TimelineCardRecord>> {
-        let cards = sqlx::query_as::<_, TimelineCardRecord>(
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(&self.sql(
             r#"
             SELECT * FROM timeline_cards
             WHERE session_id = ?
             ORDER BY start_time
             "#,
-        )
+        ))
         .bind(session_id)
         .fetch_all(&self.pool)
         .await?;
@@ -796,14 +1339,70 @@ impl DatabaseRepository for MariaDbRepository {
         Ok(cards)
     }
 
-    async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
-        let cards = sqlx::query_as::<_, TimelineCardRecord>(
-            r#"
-            SELECT * FROM timeline_cards
+    async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>> {
+        let card = sqlx::query_as::<_, TimelineCardRecord>(
+            &self.sql("SELECT * FROM timeline_cards WHERE id = ?"),
+        )
+        .bind(card_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(card)
+    }
+
+    async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(&self.sql(
+            r#"
+            SELECT * FROM timeline_cards
             ORDER BY created_at DESC
             LIMIT ?
             "#,
-        )
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(&self.sql(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE id > ?
+            ORDER BY id ASC
+            LIMIT ?
+            "#,
+        ))
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>> {
+        let cards = sqlx::query_as::<_, UpdatedTimelineCard>(&self.sql(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE updated_at > ? OR (updated_at = ? AND id > ?)
+            ORDER BY updated_at ASC, id ASC
+            LIMIT ?
+            "#,
+        ))
+        .bind(after)
+        .bind(after)
+        .bind(after_id)
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
@@ -812,7 +1411,7 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM timeline_cards WHERE session_id = ?")
+        sqlx::query(&self.sql("DELETE FROM timeline_cards WHERE session_id = ?"))
             .bind(session_id)
             .execute(&self.pool)
             .await?;
@@ -823,208 +1422,2228 @@ impl DatabaseRepository for MariaDbRepository {
     // ========== 统计信息 ==========
 
     async fn get_stats(&self) -> Result<(i64, i64, i64)> {
-        let session_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions")
+        let session_count: i64 = sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM sessions"))
             .fetch_one(&self.pool)
             .await?;
 
-        let frame_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM frames")
+        let frame_count: i64 = sqlx::query_scalar(&self.sql("SELECT COUNT(*) FROM frames"))
             .fetch_one(&self.pool)
             .await?;
 
         // MariaDB 数据库大小查询 - 使用 CAST 转换为 SIGNED
-        let total_size: i64 = sqlx::query_scalar(
-            "SELECT CAST(COALESCE(SUM(data_length + index_length), 0) AS SIGNED) FROM information_schema.tables WHERE table_schema = DATABASE()",
-        )
+        // 只统计本安装（当前表前缀）的表，共用数据库时不计入其他人的表
+        // 表名写在字符串字面量里，prefix_tables 不会替换，这里直接拼上前缀
+        let tables: Vec<String> = TABLES
+            .iter()
+            .map(|table| format!("'{}{}'", self.table_prefix, table))
+            .collect();
+        let total_size: i64 = sqlx::query_scalar(&format!(
+            "SELECT CAST(COALESCE(SUM(data_length + index_length), 0) AS SIGNED) FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name IN ({})",
+            tables.join(", ")
+        ))
         .fetch_one(&self.pool)
         .await?;
 
-        Ok((session_count, frame_count, total_size))
-    }
-
-    async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
-        let rows = sqlx::query(
+        Ok((session_count, frame_count, total_size))
+    }
+
+    async fn get_session_row_usage(&self, session_id: Option<i64>) -> Result<Vec<SessionRowUsage>> {
+        // MariaDB 的 LENGTH 返回字节数；SUM 的结果为 DECIMAL，转换为 SIGNED
+        let records = sqlx::query_as::<_, SessionRowUsage>(&self.sql(
+            r#"
+            SELECT usage_rows.session_id,
+                   CAST(SUM(usage_rows.row_count) AS SIGNED) AS row_count,
+                   CAST(SUM(usage_rows.bytes) AS SIGNED) AS bytes
+            FROM (
+                SELECT id AS session_id, 1 AS row_count,
+                       LENGTH(title) + LENGTH(summary) + LENGTH(tags) AS bytes
+                FROM sessions
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(file_path) + COALESCE(LENGTH(metadata), 0))
+                FROM frames GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(request_headers) + LENGTH(request_body)
+                           + COALESCE(LENGTH(response_headers), 0)
+                           + COALESCE(LENGTH(response_body), 0)
+                           + COALESCE(LENGTH(error_message), 0))
+                FROM llm_calls WHERE session_id IS NOT NULL GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(description) + LENGTH(start_timestamp) + LENGTH(end_timestamp))
+                FROM video_segments GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(title) + LENGTH(summary) + LENGTH(detailed_summary)
+                           + LENGTH(app_sites) + COALESCE(LENGTH(distractions), 0))
+                FROM timeline_cards GROUP BY session_id
+            ) AS usage_rows
+            WHERE ? IS NULL OR usage_rows.session_id = ?
+            GROUP BY usage_rows.session_id
+            "#,
+        ))
+        .bind(session_id)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query(&self.sql(
+            r#"
+            SELECT DISTINCT video_path
+            FROM sessions
+            WHERE video_path IS NOT NULL
+              AND summary != '{}'
+              AND summary != ''
+            "#,
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut paths = Vec::new();
+        for row in rows {
+            if let Ok(Some(path)) = row.try_get::<Option<String>, _>("video_path") {
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    // ========== 休息记录 ==========
+
+    async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO break_records (reminded_at, outcome, active_minutes, resolved_at, device_name)
+            VALUES (?, ?, ?, ?, ?)
+        "#,
+        ))
+        .bind(&record.reminded_at)
+        .bind(&record.outcome)
+        .bind(record.active_minutes)
+        .bind(&record.resolved_at)
+        .bind(&record.device_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>> {
+        let start_datetime = format!("{} 00:00:00", date);
+        let end_datetime = format!("{} 23:59:59", date);
+
+        let records = sqlx::query_as::<_, BreakRecord>(&self.sql(
+            r#"
+            SELECT * FROM break_records
+            WHERE reminded_at >= ? AND reminded_at <= ?
+            ORDER BY reminded_at
+            "#,
+        ))
+        .bind(&start_datetime)
+        .bind(&end_datetime)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 关注关键词命中 ==========
+
+    async fn replace_keyword_hits(&self, session_id: i64, hits: &[KeywordHitRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql("DELETE FROM keyword_hits WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for hit in hits {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO keyword_hits (session_id, card_id, keyword, source, excerpt, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(session_id)
+            .bind(hit.card_id)
+            .bind(&hit.keyword)
+            .bind(&hit.source)
+            .bind(&hit.excerpt)
+            .bind(hit.occurred_at)
+            .bind(hit.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        let records = sqlx::query_as::<_, KeywordHitRecord>(&self.sql(
+            r#"
+            SELECT * FROM keyword_hits
+            WHERE occurred_at >= ? AND occurred_at < ?
+            ORDER BY occurred_at
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_keyword_hits_by_session(&self, session_id: i64) -> Result<Vec<KeywordHitRecord>> {
+        let records = sqlx::query_as::<_, KeywordHitRecord>(
+            &self.sql("SELECT * FROM keyword_hits WHERE session_id = ? ORDER BY occurred_at, id"),
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 屏幕文字 ==========
+
+    async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql("DELETE FROM screen_text WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for record in records {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO screen_text (session_id, segment_start, segment_end, text, language, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(session_id)
+            .bind(record.segment_start)
+            .bind(record.segment_end)
+            .bind(&record.text)
+            .bind(&record.language)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_screen_text_by_session(&self, session_id: i64) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(
+            &self.sql("SELECT * FROM screen_text WHERE session_id = ? ORDER BY segment_start"),
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(&self.sql(
+            r#"
+            SELECT * FROM screen_text
+            WHERE segment_start >= ? AND segment_start < ?
+            ORDER BY segment_start
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(&self.sql(
+            r#"
+            SELECT * FROM screen_text
+            WHERE text LIKE ? ESCAPE '!' AND (? IS NULL OR language = ?)
+            ORDER BY segment_start DESC
+            LIMIT ?
+            "#,
+        ))
+        .bind(pattern)
+        .bind(language)
+        .bind(language)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 报错时刻 ==========
+
+    async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql("DELETE FROM error_moments WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for record in records {
+            sqlx::query(&self.sql(
+                r#"
+                INSERT INTO error_moments (session_id, kind, error_text, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(session_id)
+            .bind(&record.kind)
+            .bind(&record.error_text)
+            .bind(record.occurred_at)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        let records = sqlx::query_as::<_, ErrorMomentRecord>(&self.sql(
+            r#"
+            SELECT * FROM error_moments
+            WHERE occurred_at >= ? AND occurred_at < ?
+            ORDER BY occurred_at
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_error_moments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        let records = sqlx::query_as::<_, ErrorMomentRecord>(
+            &self.sql("SELECT * FROM error_moments WHERE session_id = ? ORDER BY occurred_at, id"),
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 音频活动 ==========
+
+    async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO audio_presence (minute, mic_active, speaker_active)
+            VALUES (?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                mic_active = GREATEST(mic_active, VALUES(mic_active)),
+                speaker_active = GREATEST(speaker_active, VALUES(speaker_active))
+            "#,
+        ))
+        .bind(record.minute)
+        .bind(record.mic_active)
+        .bind(record.speaker_active)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>> {
+        let records = sqlx::query_as::<_, AudioPresenceRecord>(&self.sql(
+            r#"
+            SELECT * FROM audio_presence
+            WHERE minute >= ? AND minute < ?
+            ORDER BY minute
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 剪贴板记录 ==========
+
+    async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO clipboard_events (copied_at, content_hash, char_count, line_count, kind, app_name, content)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.copied_at)
+        .bind(&record.content_hash)
+        .bind(record.char_count)
+        .bind(record.line_count)
+        .bind(&record.kind)
+        .bind(&record.app_name)
+        .bind(&record.content)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        let records = sqlx::query_as::<_, ClipboardEventRecord>(&self.sql(
+            r#"
+            SELECT * FROM clipboard_events
+            WHERE copied_at >= ? AND copied_at < ?
+            ORDER BY copied_at
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        let records = sqlx::query_as::<_, ClipboardEventRecord>(&self.sql(
+            r#"
+            SELECT * FROM clipboard_events
+            WHERE content_hash = ? OR content LIKE ? ESCAPE '!'
+            ORDER BY copied_at DESC
+            LIMIT ?
+            "#,
+        ))
+        .bind(content_hash)
+        .bind(pattern)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 专注拦截记录 ==========
+
+    async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO deflected_distractions (intercepted_at, app_name, action, focus_started_at, device_name)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.intercepted_at)
+        .bind(&record.app_name)
+        .bind(&record.action)
+        .bind(record.focus_started_at)
+        .bind(&record.device_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>> {
+        let start_datetime = format!("{} 00:00:00", date);
+        let end_datetime = format!("{} 23:59:59", date);
+
+        let records = sqlx::query_as::<_, DeflectedDistractionRecord>(&self.sql(
+            r#"
+            SELECT * FROM deflected_distractions
+            WHERE intercepted_at >= ? AND intercepted_at <= ?
+            ORDER BY intercepted_at
+            "#,
+        ))
+        .bind(&start_datetime)
+        .bind(&end_datetime)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 截屏暂停记录 ==========
+
+    async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64> {
+        let result = sqlx::query(
+            &self.sql("INSERT INTO capture_pauses (start_time, end_time, reason) VALUES (?, ?, ?)"),
+        )
+        .bind(record.start_time)
+        .bind(record.end_time)
+        .bind(&record.reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()> {
+        sqlx::query(&self.sql("UPDATE capture_pauses SET end_time = ? WHERE id = ?"))
+            .bind(end_time)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        let result =
+            sqlx::query(&self.sql("UPDATE capture_pauses SET end_time = ? WHERE end_time IS NULL"))
+                .bind(end_time)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>> {
+        let records = sqlx::query_as::<_, CapturePauseRecord>(&self.sql(
+            r#"
+            SELECT * FROM capture_pauses
+            WHERE start_time < ? AND (end_time IS NULL OR end_time > ?)
+            ORDER BY start_time
+            "#,
+        ))
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 时段意图 ==========
+
+    async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            "INSERT INTO block_intents (intent, start_time, end_time, device_name) VALUES (?, ?, ?, ?)",
+        ))
+        .bind(&record.intent)
+        .bind(record.start_time)
+        .bind(record.end_time)
+        .bind(&record.device_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        let result =
+            sqlx::query(&self.sql("UPDATE block_intents SET end_time = ? WHERE end_time IS NULL"))
+                .bind(end_time)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>> {
+        let records = sqlx::query_as::<_, BlockIntentRecord>(&self.sql(
+            r#"
+            SELECT * FROM block_intents
+            WHERE start_time < ? AND (end_time IS NULL OR end_time > ?)
+            ORDER BY start_time
+            "#,
+        ))
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()> {
+        sqlx::query(&self.sql(
+            "UPDATE block_intents SET alignment = ?, aligned_minutes = ?, active_minutes = ? WHERE id = ?",
+        ))
+        .bind(alignment)
+        .bind(aligned_minutes)
+        .bind(active_minutes)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ========== 外部活动事件 ==========
+
+    async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for event in events {
+            let result = sqlx::query(&self.sql(
+                r#"
+                INSERT IGNORE INTO external_events (source, category, title, start_time, end_time, metadata)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(&event.source)
+            .bind(&event.category)
+            .bind(&event.title)
+            .bind(event.start_time)
+            .bind(event.end_time)
+            .bind(&event.metadata)
+            .execute(&mut *tx)
+            .await?;
+            inserted += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(inserted)
+    }
+
+    async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>> {
+        let records = sqlx::query_as::<_, ExternalEventRecord>(&self.sql(
+            r#"
+            SELECT * FROM external_events
+            WHERE start_time < ? AND end_time > ?
+            ORDER BY start_time
+            "#,
+        ))
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 时间线卡片分类 ==========
+
+    async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(&self.sql(
+            "UPDATE timeline_cards SET updated_at = ?, category = ?, distractions = ? WHERE id = ?",
+        ))
+        .bind(crate::storage::local_now())
+        .bind(category)
+        .bind(distractions)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE timeline_cards SET updated_at = ?, subcategory = ? WHERE subcategory = ?",
+        ))
+        .bind(crate::storage::local_now())
+        .bind(to)
+        .bind(from)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ========== 卡片复核 ==========
+
+    async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(&self.sql(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE review_status = 'pending'
+            ORDER BY start_time DESC
+            LIMIT ?
+            "#,
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE timeline_cards SET updated_at = ?, title = ?, category = ?, subcategory = ?, review_status = ? WHERE id = ?",
+        ))
+        .bind(crate::storage::local_now())
+        .bind(title)
+        .bind(category)
+        .bind(subcategory)
+        .bind(review_status)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()> {
+        let result =
+            sqlx::query(&self.sql("UPDATE timeline_cards SET keyword_tags = ? WHERE id = ?"))
+                .bind(keyword_tags)
+                .bind(card_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()> {
+        let result =
+            sqlx::query(&self.sql("UPDATE timeline_cards SET preview_clip_path = ? WHERE id = ?"))
+                .bind(preview_clip_path)
+                .bind(card_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()> {
+        let result = sqlx::query(
+            &self.sql("UPDATE timeline_cards SET updated_at = ?, privacy_level = ? WHERE id = ?"),
+        )
+        .bind(crate::storage::local_now())
+        .bind(level)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE timeline_cards SET updated_at = ?, start_time = ?, end_time = ? WHERE id = ?",
+        ))
+        .bind(crate::storage::local_now())
+        .bind(start_time)
+        .bind(end_time)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()> {
+        let result =
+            sqlx::query(&self.sql(
+                "UPDATE video_segments SET start_timestamp = ?, end_timestamp = ? WHERE id = ?",
+            ))
+            .bind(start_timestamp)
+            .bind(end_timestamp)
+            .bind(segment_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("视频分段不存在: {}", segment_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()> {
+        let result =
+            sqlx::query(&self.sql("UPDATE timeline_cards SET meeting_notes = ? WHERE id = ?"))
+                .bind(meeting_notes)
+                .bind(card_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO card_corrections (
+                card_id, original_title, original_category, original_subcategory,
+                corrected_title, corrected_category, corrected_subcategory,
+                summary, app_sites, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.card_id)
+        .bind(&record.original_title)
+        .bind(&record.original_category)
+        .bind(&record.original_subcategory)
+        .bind(&record.corrected_title)
+        .bind(&record.corrected_category)
+        .bind(&record.corrected_subcategory)
+        .bind(&record.summary)
+        .bind(&record.app_sites)
+        .bind(&record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_recent_card_corrections(&self, limit: i64) -> Result<Vec<CardCorrectionRecord>> {
+        let records = sqlx::query_as::<_, CardCorrectionRecord>(
+            &self.sql("SELECT * FROM card_corrections ORDER BY created_at DESC LIMIT ?"),
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 文件路径迁移 ==========
+
+    async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64> {
+        // 按字符数截取，避免多字节路径被截断
+        let prefix_len = old_prefix.chars().count() as i64;
+        let mut updated = 0;
+        for (table, column) in [
+            ("sessions", "video_path"),
+            ("frames", "file_path"),
+            ("timeline_cards", "video_preview_path"),
+            ("timeline_cards", "preview_clip_path"),
+        ] {
+            let sql = format!(
+                "UPDATE {table} SET {column} = CONCAT(?, SUBSTRING({column}, ?)) WHERE LEFT({column}, ?) = ?",
+                table = table,
+                column = column
+            );
+            let result = sqlx::query(&self.sql(&sql))
+                .bind(new_prefix)
+                .bind(prefix_len + 1)
+                .bind(prefix_len)
+                .bind(old_prefix)
+                .execute(&self.pool)
+                .await?;
+            updated += result.rows_affected();
+        }
+
+        Ok(updated)
+    }
+
+    // ========== 个人纪录 ==========
+
+    async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO personal_records (record_key, value, date, detail)
+            VALUES (?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                value = VALUES(value),
+                date = VALUES(date),
+                detail = VALUES(detail)
+            "#,
+        ))
+        .bind(&record.record_key)
+        .bind(record.value)
+        .bind(&record.date)
+        .bind(&record.detail)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>> {
+        let records = sqlx::query_as::<_, PersonalRecord>(&self.sql(
+            "SELECT record_key, value, date, detail FROM personal_records ORDER BY record_key",
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 日期类型 ==========
+
+    async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO day_types (date, day_type, note)
+            VALUES (?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                day_type = VALUES(day_type),
+                note = VALUES(note)
+            "#,
+        ))
+        .bind(&record.date)
+        .bind(&record.day_type)
+        .bind(&record.note)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear_day_type(&self, date: &str) -> Result<()> {
+        sqlx::query(&self.sql("DELETE FROM day_types WHERE date = ?"))
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>> {
+        let records = sqlx::query_as::<_, DayTypeRecord>(&self.sql(
+            "SELECT date, day_type, note FROM day_types WHERE date >= ? AND date <= ? ORDER BY date",
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 存储用量统计 ==========
+
+    async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO storage_usage (date, device_name, captured_bytes, captured_frames, encoded_bytes, encoded_videos)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                captured_bytes = captured_bytes + VALUES(captured_bytes),
+                captured_frames = captured_frames + VALUES(captured_frames),
+                encoded_bytes = encoded_bytes + VALUES(encoded_bytes),
+                encoded_videos = encoded_videos + VALUES(encoded_videos)
+            "#,
+        ))
+        .bind(&usage.date)
+        .bind(&usage.device_name)
+        .bind(usage.captured_bytes)
+        .bind(usage.captured_frames)
+        .bind(usage.encoded_bytes)
+        .bind(usage.encoded_videos)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>> {
+        let records = sqlx::query_as::<_, StorageUsageRecord>(&self.sql(
+            r#"
+            SELECT date, device_name, captured_bytes, captured_frames, encoded_bytes, encoded_videos
+            FROM storage_usage
+            WHERE device_name = ? AND date >= ? AND date <= ?
+            ORDER BY date
+            "#,
+        ))
+        .bind(device_name)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 会话置顶 ==========
+
+    async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()> {
+        let result = sqlx::query(&self.sql("UPDATE sessions SET pinned = ? WHERE id = ?"))
+            .bind(pinned)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+        Ok(())
+    }
+
+    async fn get_pinned_sessions(&self) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(&self.sql(
+            r#"
+            SELECT id, start_time, end_time, title, summary,
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
+            FROM sessions
+            WHERE pinned = TRUE
+            ORDER BY start_time
+            "#,
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    // ========== 会话归档 ==========
+
+    async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(&self.sql(
+            "UPDATE sessions SET archived = TRUE, archive_key = ?, video_path = NULL WHERE id = ?",
+        ))
+        .bind(archive_key)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+
+        // 截图文件随归档删除，帧记录不再有意义
+        sqlx::query(&self.sql("DELETE FROM frames WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()> {
+        let result = sqlx::query(
+            &self.sql("UPDATE sessions SET archived = FALSE, video_path = ? WHERE id = ?"),
+        )
+        .bind(video_path)
+        .bind(session_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+        Ok(())
+    }
+
+    // ========== 待删除会话 ==========
+
+    async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO pending_deletions (session_id, title, deleted_at, expires_at, snapshot, staged_files)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.session_id)
+        .bind(&record.title)
+        .bind(&record.deleted_at)
+        .bind(&record.expires_at)
+        .bind(&record.snapshot)
+        .bind(&record.staged_files)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>> {
+        let records = sqlx::query_as::<_, PendingDeletionRecord>(&self.sql(
+            r#"
+            SELECT session_id, title, deleted_at, expires_at, snapshot, staged_files
+            FROM pending_deletions
+            ORDER BY deleted_at
+            "#,
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn remove_pending_deletion(&self, session_id: i64) -> Result<()> {
+        sqlx::query(&self.sql("DELETE FROM pending_deletions WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ========== 待分析视频认领 ==========
+
+    async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        // 清理已过期的认领，续期自己的认领，没有认领记录时再插入（主键冲突说明已被其他机器认领）
+        sqlx::query(&self.sql("DELETE FROM video_claims WHERE lease_until < ?"))
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        let result = sqlx::query(&self.sql(
+            r#"
+            UPDATE video_claims SET claimed_at = ?, lease_until = ?
+            WHERE video_path = ? AND owner = ?
+            "#,
+        ))
+        .bind(now)
+        .bind(lease_until)
+        .bind(video_path)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT IGNORE INTO video_claims (video_path, owner, claimed_at, lease_until)
+            VALUES (?, ?, ?, ?)
+            "#,
+        ))
+        .bind(video_path)
+        .bind(owner)
+        .bind(now)
+        .bind(lease_until)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()> {
+        sqlx::query(&self.sql("DELETE FROM video_claims WHERE video_path = ? AND owner = ?"))
+            .bind(video_path)
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ========== 服务商对比 ==========
+
+    async fn insert_provider_comparison(&self, record: &ProviderComparisonRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO provider_comparisons (
+                session_id, run_id, provider, model, error_message, latency_ms,
+                input_tokens, output_tokens, cards_json, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.session_id)
+        .bind(&record.run_id)
+        .bind(&record.provider)
+        .bind(&record.model)
+        .bind(&record.error_message)
+        .bind(record.latency_ms)
+        .bind(record.input_tokens)
+        .bind(record.output_tokens)
+        .bind(&record.timeline_cards)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>> {
+        let records = sqlx::query_as::<_, ProviderComparisonRecord>(&self.sql(
+            r#"
+            SELECT id, session_id, run_id, provider, model, error_message, latency_ms,
+                   input_tokens, output_tokens, cards_json, created_at
+            FROM provider_comparisons
+            WHERE session_id = ?
+            ORDER BY created_at DESC, id
+            "#,
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 数据外发记录 ==========
+
+    async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO egress_log (channel, destination, category, bytes, session_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(&record.channel)
+        .bind(&record.destination)
+        .bind(&record.category)
+        .bind(record.bytes)
+        .bind(record.session_id)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>> {
+        let records = sqlx::query_as::<_, EgressRecord>(&self.sql(
+            r#"
+            SELECT * FROM egress_log
+            WHERE created_at >= ? AND created_at < ?
+            ORDER BY created_at, id
+            "#,
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 会话书签 ==========
+
+    async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO session_bookmarks (session_id, video_time, note, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        ))
+        .bind(record.session_id)
+        .bind(record.video_time)
+        .bind(&record.note)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_session_bookmarks(&self, session_id: i64) -> Result<Vec<SessionBookmarkRecord>> {
+        let records = sqlx::query_as::<_, SessionBookmarkRecord>(&self.sql(
+            r#"
+            SELECT * FROM session_bookmarks
+            WHERE session_id = ?
+            ORDER BY video_time, id
+            "#,
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()> {
+        sqlx::query(&self.sql("DELETE FROM session_bookmarks WHERE id = ?"))
+            .bind(bookmark_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // ========== 计时服务导出记录 ==========
+
+    async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>> {
+        let record = sqlx::query_as::<_, TimeEntryExportRecord>(
+            &self.sql("SELECT * FROM time_entry_exports WHERE idempotency_key = ?"),
+        )
+        .bind(idempotency_key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>> {
+        let records = sqlx::query_as::<_, TimeEntryExportRecord>(
+            &self.sql("SELECT * FROM time_entry_exports WHERE session_id = ? ORDER BY exported_at"),
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT IGNORE INTO time_entry_exports
+                (idempotency_key, provider, session_id, card_id, external_id, exported_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(&record.idempotency_key)
+        .bind(&record.provider)
+        .bind(record.session_id)
+        .bind(record.card_id)
+        .bind(&record.external_id)
+        .bind(record.exported_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ========== webhook 投递记录 ==========
+
+    async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64> {
+        let result = sqlx::query(&self.sql(
+            r#"
+            INSERT INTO webhook_deliveries
+                (delivery_id, session_id, url, status, attempts, response_status, error, payload_bytes, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(&record.delivery_id)
+        .bind(record.session_id)
+        .bind(&record.url)
+        .bind(&record.status)
+        .bind(record.attempts)
+        .bind(record.response_status)
+        .bind(&record.error)
+        .bind(record.payload_bytes)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        let records = sqlx::query_as::<_, WebhookDeliveryRecord>(&self.sql(
+            r#"
+            SELECT * FROM webhook_deliveries
+            WHERE ? IS NULL OR session_id = ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?
+            "#,
+        ))
+        .bind(session_id)
+        .bind(session_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 数据库初始化 ==========
+
+    async fn initialize_tables(&self) -> Result<()> {
+        // 创建会话表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS sessions (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                title TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                video_path TEXT,
+                tags TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                device_name VARCHAR(255),
+                device_type VARCHAR(50),
+                pinned BOOLEAN NOT NULL DEFAULT FALSE,
+                archived BOOLEAN NOT NULL DEFAULT FALSE,
+                archive_key VARCHAR(1024)
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 数据库迁移: 为已存在的sessions表添加置顶字段
+        if !self.column_exists("sessions", "pinned").await? {
+            info!("迁移数据库: 添加pinned字段");
+            sqlx::query(
+                &self.sql("ALTER TABLE sessions ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE"),
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        // 数据库迁移: 为已存在的sessions表添加归档字段
+        if !self.column_exists("sessions", "archived").await? {
+            info!("迁移数据库: 添加archived和archive_key字段");
+            sqlx::query(
+                &self
+                    .sql("ALTER TABLE sessions ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE"),
+            )
+            .execute(&self.pool)
+            .await?;
+            sqlx::query(&self.sql("ALTER TABLE sessions ADD COLUMN archive_key VARCHAR(1024)"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 创建帧表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS frames (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                timestamp DATETIME NOT NULL,
+                file_path TEXT NOT NULL,
+                metadata TEXT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建索引（MySQL 不支持 CREATE INDEX IF NOT EXISTS，需要忽略已存在错误）
+        let _ =
+            sqlx::query(&self.sql("CREATE INDEX idx_sessions_start_time ON sessions(start_time)"))
+                .execute(&self.pool)
+                .await;
+        let _ = sqlx::query(&self.sql("CREATE INDEX idx_frames_session_id ON frames(session_id)"))
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_sessions_start_end ON sessions(start_time, end_time)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_frames_session_timestamp ON frames(session_id, timestamp)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建LLM调用记录表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_calls (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT,
+                provider VARCHAR(100) NOT NULL,
+                model VARCHAR(100) NOT NULL,
+                call_type VARCHAR(100) NOT NULL,
+                request_headers TEXT NOT NULL,
+                request_body TEXT NOT NULL,
+                response_headers TEXT,
+                response_body TEXT,
+                status_code INT,
+                error_message TEXT,
+                latency_ms BIGINT,
+                token_usage TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                prompt_version VARCHAR(100),
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 数据库迁移: 为已存在的llm_calls表添加提示词版本字段
+        if !self.column_exists("llm_calls", "prompt_version").await? {
+            info!("迁移数据库: 添加prompt_version字段");
+            sqlx::query(&self.sql("ALTER TABLE llm_calls ADD COLUMN prompt_version VARCHAR(100)"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的frames表添加上下文字段
+        if !self.column_exists("frames", "metadata").await? {
+            info!("迁移数据库: 添加frames.metadata字段");
+            sqlx::query(&self.sql("ALTER TABLE frames ADD COLUMN metadata TEXT NULL"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 创建视频分段表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS video_segments (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                llm_call_id BIGINT,
+                start_timestamp VARCHAR(50) NOT NULL,
+                end_timestamp VARCHAR(50) NOT NULL,
+                description TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建时间线卡片表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS timeline_cards (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                llm_call_id BIGINT,
+                start_time VARCHAR(50) NOT NULL,
+                end_time VARCHAR(50) NOT NULL,
+                category VARCHAR(100) NOT NULL,
+                subcategory VARCHAR(100) NOT NULL,
+                title TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                detailed_summary TEXT NOT NULL,
+                distractions TEXT,
+                app_sites TEXT NOT NULL,
+                video_preview_path TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                confidence DOUBLE,
+                review_status VARCHAR(32),
+                meeting_notes TEXT,
+                keyword_tags TEXT,
+                preview_clip_path TEXT,
+                privacy_level BIGINT,
+                updated_at DATETIME NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建每日总结表（缓存）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS day_summaries (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                date DATE NOT NULL UNIQUE,
+                summary_text TEXT NOT NULL,
+                device_stats TEXT NOT NULL,
+                parallel_work TEXT NOT NULL,
+                usage_patterns TEXT NOT NULL,
+                active_device_count INT NOT NULL,
+                llm_call_id BIGINT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+                finalized BOOLEAN NOT NULL DEFAULT FALSE,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建额外的索引（忽略已存在错误）
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_llm_calls_session_id ON llm_calls(session_id)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_llm_calls_created_at ON llm_calls(created_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_video_segments_session_id ON video_segments(session_id)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_timeline_cards_session_id ON timeline_cards(session_id)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 数据库迁移: 为已存在的day_summaries表添加锁定字段
+        if !self.column_exists("day_summaries", "finalized").await? {
+            info!("迁移数据库: 添加finalized字段");
+            sqlx::query(&self.sql(
+                "ALTER TABLE day_summaries ADD COLUMN finalized BOOLEAN NOT NULL DEFAULT FALSE",
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加置信度和复核状态字段
+        if !self.column_exists("timeline_cards", "confidence").await? {
+            info!("迁移数据库: 添加confidence和review_status字段");
+            sqlx::query(&self.sql("ALTER TABLE timeline_cards ADD COLUMN confidence DOUBLE"))
+                .execute(&self.pool)
+                .await?;
+            sqlx::query(
+                &self.sql("ALTER TABLE timeline_cards ADD COLUMN review_status VARCHAR(32)"),
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        let _ =
+            sqlx::query(&self.sql(
+                "CREATE INDEX idx_timeline_cards_review_status ON timeline_cards(review_status)",
+            ))
+            .execute(&self.pool)
+            .await;
+
+        // 数据库迁移: 为已存在的timeline_cards表添加会议纪要字段
+        if !self
+            .column_exists("timeline_cards", "meeting_notes")
+            .await?
+        {
+            info!("迁移数据库: 添加meeting_notes字段");
+            sqlx::query(&self.sql("ALTER TABLE timeline_cards ADD COLUMN meeting_notes TEXT"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加关注关键词字段
+        if !self.column_exists("timeline_cards", "keyword_tags").await? {
+            info!("迁移数据库: 添加keyword_tags字段");
+            sqlx::query(&self.sql("ALTER TABLE timeline_cards ADD COLUMN keyword_tags TEXT"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加卡片预览短片字段
+        if !self
+            .column_exists("timeline_cards", "preview_clip_path")
+            .await?
+        {
+            info!("迁移数据库: 添加preview_clip_path字段");
+            sqlx::query(&self.sql("ALTER TABLE timeline_cards ADD COLUMN preview_clip_path TEXT"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加隐私敏感度字段
+        if !self
+            .column_exists("timeline_cards", "privacy_level")
+            .await?
+        {
+            info!("迁移数据库: 添加privacy_level字段");
+            sqlx::query(&self.sql("ALTER TABLE timeline_cards ADD COLUMN privacy_level BIGINT"))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加修改时间字段（RAG 导出据此同步卡片修改）
+        if !self.column_exists("timeline_cards", "updated_at").await? {
+            info!("迁移数据库: 添加updated_at字段");
+            sqlx::query(
+                &self.sql("ALTER TABLE timeline_cards ADD COLUMN updated_at DATETIME NULL"),
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_timeline_cards_updated_at ON timeline_cards(updated_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建卡片纠正记录表（不设外键，卡片删除后纠正示例仍然保留）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS card_corrections (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                card_id BIGINT NOT NULL,
+                original_title TEXT NOT NULL,
+                original_category VARCHAR(100) NOT NULL,
+                original_subcategory VARCHAR(100) NOT NULL,
+                corrected_title TEXT NOT NULL,
+                corrected_category VARCHAR(100) NOT NULL,
+                corrected_subcategory VARCHAR(100) NOT NULL,
+                summary TEXT NOT NULL,
+                app_sites TEXT NOT NULL,
+                created_at DATETIME NOT NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建休息记录表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS break_records (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                reminded_at DATETIME NOT NULL,
+                outcome VARCHAR(32) NOT NULL,
+                active_minutes BIGINT NOT NULL,
+                resolved_at DATETIME NOT NULL,
+                device_name VARCHAR(255)
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_break_records_reminded_at ON break_records(reminded_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建关注关键词命中表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS keyword_hits (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                card_id BIGINT,
+                keyword VARCHAR(255) NOT NULL,
+                source VARCHAR(32) NOT NULL,
+                excerpt TEXT NOT NULL,
+                occurred_at DATETIME NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_keyword_hits_occurred_at ON keyword_hits(occurred_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_keyword_hits_session ON keyword_hits(session_id)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建屏幕文字表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS screen_text (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                segment_start DATETIME NOT NULL,
+                segment_end DATETIME NOT NULL,
+                text MEDIUMTEXT NOT NULL,
+                language VARCHAR(32) NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_screen_text_session ON screen_text(session_id)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 数据库迁移: 为已存在的screen_text表添加语言字段
+        if !self.column_exists("screen_text", "language").await? {
+            info!("迁移数据库: 添加screen_text.language字段");
+            sqlx::query(&self.sql("ALTER TABLE screen_text ADD COLUMN language VARCHAR(32) NULL"))
+                .execute(&self.pool)
+                .await?;
+        }
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_screen_text_start ON screen_text(segment_start)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建报错时刻表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS error_moments (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                kind VARCHAR(32) NOT NULL,
+                error_text TEXT NOT NULL,
+                occurred_at DATETIME NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_error_moments_occurred_at ON error_moments(occurred_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建剪贴板记录表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS clipboard_events (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                copied_at DATETIME NOT NULL,
+                content_hash CHAR(64) NOT NULL,
+                char_count BIGINT NOT NULL,
+                line_count BIGINT NOT NULL,
+                kind VARCHAR(16) NOT NULL,
+                app_name VARCHAR(255),
+                content MEDIUMTEXT
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_clipboard_events_copied_at ON clipboard_events(copied_at)"),
+        )
+        .execute(&self.pool)
+        .await;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_clipboard_events_hash ON clipboard_events(content_hash)"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        // 创建音频活动表（每分钟一条）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS audio_presence (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                minute DATETIME NOT NULL UNIQUE,
+                mic_active BOOLEAN NOT NULL DEFAULT FALSE,
+                speaker_active BOOLEAN NOT NULL DEFAULT FALSE
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建专注拦截记录表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS deflected_distractions (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                intercepted_at DATETIME NOT NULL,
+                app_name VARCHAR(255) NOT NULL,
+                action VARCHAR(32) NOT NULL,
+                focus_started_at DATETIME NOT NULL,
+                device_name VARCHAR(255)
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(&self.sql(
+            "CREATE INDEX idx_deflected_distractions_intercepted_at ON deflected_distractions(intercepted_at)",
+        ))
+        .execute(&self.pool)
+        .await;
+
+        // 创建每日存储用量表
+        sqlx::query(&self.sql(
             r#"
-            SELECT DISTINCT video_path
-            FROM sessions
-            WHERE video_path IS NOT NULL
-              AND summary != '{}'
-              AND summary != ''
-            "#,
-        )
-        .fetch_all(&self.pool)
+            CREATE TABLE IF NOT EXISTS storage_usage (
+                date VARCHAR(10) NOT NULL,
+                device_name VARCHAR(255) NOT NULL,
+                captured_bytes BIGINT NOT NULL DEFAULT 0,
+                captured_frames BIGINT NOT NULL DEFAULT 0,
+                encoded_bytes BIGINT NOT NULL DEFAULT 0,
+                encoded_videos BIGINT NOT NULL DEFAULT 0,
+                PRIMARY KEY (date, device_name)
+            )
+        "#,
+        ))
+        .execute(&self.pool)
         .await?;
 
-        let mut paths = Vec::new();
-        for row in rows {
-            if let Ok(Some(path)) = row.try_get::<Option<String>, _>("video_path") {
-                paths.push(path);
-            }
-        }
-
-        Ok(paths)
-    }
-
-    // ========== 数据库初始化 ==========
+        // 创建截屏暂停记录表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS capture_pauses (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NULL,
+                reason VARCHAR(32) NOT NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_capture_pauses_start_time ON capture_pauses(start_time)"),
+        )
+        .execute(&self.pool)
+        .await;
 
-    async fn initialize_tables(&self) -> Result<()> {
-        // 创建会话表
-        sqlx::query(
+        // 创建时段意图表
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS sessions (
+            CREATE TABLE IF NOT EXISTS block_intents (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                intent TEXT NOT NULL,
                 start_time DATETIME NOT NULL,
-                end_time DATETIME NOT NULL,
-                title TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                video_path TEXT,
-                tags TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                end_time DATETIME NULL,
                 device_name VARCHAR(255),
-                device_type VARCHAR(50)
+                alignment DOUBLE,
+                aligned_minutes BIGINT,
+                active_minutes BIGINT
             )
         "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_block_intents_start_time ON block_intents(start_time)"),
+        )
+        .execute(&self.pool)
+        .await;
 
-        // 创建帧表
-        sqlx::query(
+        // 创建外部活动事件表
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS frames (
+            CREATE TABLE IF NOT EXISTS external_events (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
-                session_id BIGINT NOT NULL,
-                timestamp DATETIME NOT NULL,
-                file_path TEXT NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                source VARCHAR(64) NOT NULL,
+                category VARCHAR(64) NOT NULL,
+                title VARCHAR(255) NOT NULL,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                metadata TEXT,
+                UNIQUE KEY uniq_external_event (source, start_time, title)
             )
         "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
-
-        // 创建索引（MySQL 不支持 CREATE INDEX IF NOT EXISTS，需要忽略已存在错误）
-        let _ = sqlx::query("CREATE INDEX idx_sessions_start_time ON sessions(start_time)")
-            .execute(&self.pool)
-            .await;
-        let _ = sqlx::query("CREATE INDEX idx_frames_session_id ON frames(session_id)")
-            .execute(&self.pool)
-            .await;
-        let _ =
-            sqlx::query("CREATE INDEX idx_sessions_start_end ON sessions(start_time, end_time)")
-                .execute(&self.pool)
-                .await;
         let _ = sqlx::query(
-            "CREATE INDEX idx_frames_session_timestamp ON frames(session_id, timestamp)",
+            &self.sql("CREATE INDEX idx_external_events_start_time ON external_events(start_time)"),
         )
         .execute(&self.pool)
         .await;
 
-        // 创建LLM调用记录表
-        sqlx::query(
+        // 创建个人纪录表
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS llm_calls (
+            CREATE TABLE IF NOT EXISTS personal_records (
+                record_key VARCHAR(64) PRIMARY KEY,
+                value BIGINT NOT NULL,
+                date VARCHAR(10) NOT NULL,
+                detail TEXT
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建日期类型表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS day_types (
+                date VARCHAR(10) PRIMARY KEY,
+                day_type VARCHAR(16) NOT NULL,
+                note TEXT
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建待删除会话表（撤销删除用）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_deletions (
+                session_id BIGINT PRIMARY KEY,
+                title VARCHAR(512) NOT NULL,
+                deleted_at DATETIME NOT NULL,
+                expires_at DATETIME NOT NULL,
+                snapshot LONGTEXT NOT NULL,
+                staged_files LONGTEXT NOT NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建待分析视频认领表（多台机器分析同一共享存储时避免重复分析）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS video_claims (
+                video_path VARCHAR(512) PRIMARY KEY,
+                owner VARCHAR(255) NOT NULL,
+                claimed_at DATETIME NOT NULL,
+                lease_until DATETIME NOT NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // 创建服务商对比结果表
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS provider_comparisons (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
-                session_id BIGINT,
+                session_id BIGINT NOT NULL,
+                run_id VARCHAR(64) NOT NULL,
                 provider VARCHAR(100) NOT NULL,
                 model VARCHAR(100) NOT NULL,
-                call_type VARCHAR(100) NOT NULL,
-                request_headers TEXT NOT NULL,
-                request_body TEXT NOT NULL,
-                response_headers TEXT,
-                response_body TEXT,
-                status_code INT,
                 error_message TEXT,
-                latency_ms BIGINT,
-                token_usage TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                latency_ms BIGINT NOT NULL,
+                input_tokens BIGINT,
+                output_tokens BIGINT,
+                cards_json LONGTEXT NOT NULL,
+                created_at DATETIME NOT NULL,
                 FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
             )
         "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
+        let _ = sqlx::query(&self.sql(
+            "CREATE INDEX idx_provider_comparisons_session ON provider_comparisons(session_id)",
+        ))
+        .execute(&self.pool)
+        .await;
 
-        // 创建视频分段表
-        sqlx::query(
+        // 数据库迁移: 卡片 JSON 列原名 timeline_cards，和表名相同，加表前缀时会被一起改名
+        if !self
+            .column_exists("provider_comparisons", "cards_json")
+            .await?
+        {
+            info!("迁移数据库: provider_comparisons 的 timeline_cards 字段改名为 cards_json");
+            sqlx::query(&self.sql(&format!(
+                "ALTER TABLE provider_comparisons CHANGE COLUMN `{}timeline_cards` cards_json LONGTEXT NOT NULL",
+                self.table_prefix
+            )))
+            .execute(&self.pool)
+            .await?;
+        }
+
+        // 创建数据外发记录表（不随会话删除，便于事后核实）
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS video_segments (
+            CREATE TABLE IF NOT EXISTS egress_log (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
-                session_id BIGINT NOT NULL,
-                llm_call_id BIGINT,
-                start_timestamp VARCHAR(50) NOT NULL,
-                end_timestamp VARCHAR(50) NOT NULL,
-                description TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                channel VARCHAR(32) NOT NULL,
+                destination VARCHAR(255) NOT NULL,
+                category VARCHAR(32) NOT NULL,
+                bytes BIGINT NOT NULL,
+                session_id BIGINT,
+                created_at DATETIME NOT NULL
             )
         "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
+        let _ = sqlx::query(
+            &self.sql("CREATE INDEX idx_egress_log_created_at ON egress_log(created_at)"),
+        )
+        .execute(&self.pool)
+        .await;
 
-        // 创建时间线卡片表
-        sqlx::query(
+        // 创建会话书签表
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS timeline_cards (
+            CREATE TABLE IF NOT EXISTS session_bookmarks (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
                 session_id BIGINT NOT NULL,
-                llm_call_id BIGINT,
-                start_time VARCHAR(50) NOT NULL,
-                end_time VARCHAR(50) NOT NULL,
-                category VARCHAR(100) NOT NULL,
-                subcategory VARCHAR(100) NOT NULL,
-                title TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                detailed_summary TEXT NOT NULL,
-                distractions TEXT,
-                app_sites TEXT NOT NULL,
-                video_preview_path TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                video_time DOUBLE NOT NULL,
+                note TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
             )
         "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+        let _ = sqlx::query(
+            &self
+                .sql("CREATE INDEX idx_session_bookmarks_session ON session_bookmarks(session_id)"),
         )
         .execute(&self.pool)
+        .await;
+
+        // 创建计时服务导出记录表（不随会话删除，避免重新导出产生重复记录）
+        sqlx::query(&self.sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS time_entry_exports (
+                idempotency_key VARCHAR(64) PRIMARY KEY,
+                provider VARCHAR(16) NOT NULL,
+                session_id BIGINT NOT NULL,
+                card_id BIGINT,
+                external_id VARCHAR(64) NOT NULL,
+                exported_at DATETIME NOT NULL
+            )
+        "#,
+        ))
+        .execute(&self.pool)
         .await?;
 
-        // 创建每日总结表（缓存）
-        sqlx::query(
+        // 创建 webhook 投递记录表（不随会话删除，便于核对推送历史）
+        sqlx::query(&self.sql(
             r#"
-            CREATE TABLE IF NOT EXISTS day_summaries (
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
                 id BIGINT PRIMARY KEY AUTO_INCREMENT,
-                date DATE NOT NULL UNIQUE,
-                summary_text TEXT NOT NULL,
-                device_stats TEXT NOT NULL,
-                parallel_work TEXT NOT NULL,
-                usage_patterns TEXT NOT NULL,
-                active_device_count INT NOT NULL,
-                llm_call_id BIGINT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                delivery_id VARCHAR(64) NOT NULL,
+                session_id BIGINT NOT NULL,
+                url TEXT NOT NULL,
+                status VARCHAR(16) NOT NULL,
+                attempts BIGINT NOT NULL,
+                response_status BIGINT,
+                error TEXT,
+                payload_bytes BIGINT NOT NULL,
+                created_at DATETIME NOT NULL
             )
         "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
-
-        // 创建额外的索引（忽略已存在错误）
-        let _ = sqlx::query("CREATE INDEX idx_llm_calls_session_id ON llm_calls(session_id)")
-            .execute(&self.pool)
-            .await;
-        let _ = sqlx::query("CREATE INDEX idx_llm_calls_created_at ON llm_calls(created_at)")
+        let _ =
+            sqlx::query(&self.sql(
+                "CREATE INDEX idx_webhook_deliveries_session ON webhook_deliveries(session_id)",
+            ))
             .execute(&self.pool)
             .await;
-        let _ =
-            sqlx::query("CREATE INDEX idx_video_segments_session_id ON video_segments(session_id)")
-                .execute(&self.pool)
-                .await;
-        let _ =
-            sqlx::query("CREATE INDEX idx_timeline_cards_session_id ON timeline_cards(session_id)")
-                .execute(&self.pool)
-                .await;
 
         info!("MariaDB 数据库表初始化完成");
         Ok(())
@@ -1032,14 +3651,14 @@ impl DatabaseRepository for MariaDbRepository {
 
     async fn save_day_summary(&self, date: &str, summary: &DaySummaryRecord) -> Result<()> {
         // 使用 REPLACE INTO 实现 upsert (MariaDB/MySQL 语法)
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             REPLACE INTO day_summaries (
                 date, summary_text, device_stats, parallel_work, usage_patterns,
                 active_device_count, llm_call_id, updated_at
             ) VALUES (?, ?, ?, ?, ?, ?, ?, NOW())
             "#,
-        )
+        ))
         .bind(date)
         .bind(&summary.summary_text)
         .bind(&summary.device_stats)
@@ -1054,11 +3673,11 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn get_day_summary(&self, date: &str) -> Result<Option<DaySummaryRecord>> {
-        let result = sqlx::query_as::<_, DaySummaryRecord>(
+        let result = sqlx::query_as::<_, DaySummaryRecord>(&self.sql(
             r#"
             SELECT * FROM day_summaries WHERE date = ?
             "#,
-        )
+        ))
         .bind(date)
         .fetch_optional(&self.pool)
         .await?;
@@ -1067,11 +3686,11 @@ impl DatabaseRepository for MariaDbRepository {
     }
 
     async fn delete_day_summary(&self, date: &str) -> Result<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             DELETE FROM day_summaries WHERE date = ?
             "#,
-        )
+        ))
         .bind(date)
         .execute(&self.pool)
         .await?;
@@ -1079,6 +3698,16 @@ impl DatabaseRepository for MariaDbRepository {
         Ok(())
     }
 
+    async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()> {
+        sqlx::query(&self.sql("UPDATE day_summaries SET finalized = ? WHERE date = ?"))
+            .bind(finalized)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     fn db_type(&self) -> &str {
         "mariadb"
     }
@@ -1095,12 +3724,12 @@ impl DatabaseRepository for MariaDbRepository {
         );
 
         // 更新 sessions 表
-        let sessions_updated = sqlx::query(
+        let sessions_updated = sqlx::query(&self.sql(
             "UPDATE sessions SET
              start_time = DATE_ADD(start_time, INTERVAL ? HOUR),
              end_time = DATE_ADD(end_time, INTERVAL ? HOUR),
              created_at = DATE_ADD(created_at, INTERVAL ? HOUR)",
-        )
+        ))
         .bind(local_offset)
         .bind(local_offset)
         .bind(local_offset)
@@ -1109,45 +3738,49 @@ impl DatabaseRepository for MariaDbRepository {
         .rows_affected();
 
         // 更新 frames 表
-        let frames_updated =
-            sqlx::query("UPDATE frames SET timestamp = DATE_ADD(timestamp, INTERVAL ? HOUR)")
-                .bind(local_offset)
-                .execute(&self.pool)
-                .await?
-                .rows_affected();
-
-        // 更新 llm_calls 表
-        let llm_calls_updated =
-            sqlx::query("UPDATE llm_calls SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)")
-                .bind(local_offset)
-                .execute(&self.pool)
-                .await?
-                .rows_affected();
-
-        // 更新 video_segments 表
-        let video_segments_updated = sqlx::query(
-            "UPDATE video_segments SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)",
+        let frames_updated = sqlx::query(
+            &self.sql("UPDATE frames SET timestamp = DATE_ADD(timestamp, INTERVAL ? HOUR)"),
         )
         .bind(local_offset)
         .execute(&self.pool)
         .await?
         .rows_affected();
 
-        // 更新 timeline_cards 表
-        let timeline_cards_updated = sqlx::query(
-            "UPDATE timeline_cards SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)",
+        // 更新 llm_calls 表
+        let llm_calls_updated = sqlx::query(
+            &self.sql("UPDATE llm_calls SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)"),
         )
         .bind(local_offset)
         .execute(&self.pool)
         .await?
         .rows_affected();
 
+        // 更新 video_segments 表
+        let video_segments_updated =
+            sqlx::query(&self.sql(
+                "UPDATE video_segments SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)",
+            ))
+            .bind(local_offset)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        // 更新 timeline_cards 表
+        let timeline_cards_updated =
+            sqlx::query(&self.sql(
+                "UPDATE timeline_cards SET created_at = DATE_ADD(created_at, INTERVAL ? HOUR)",
+            ))
+            .bind(local_offset)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
         // 更新 day_summaries 表
-        let day_summaries_updated = sqlx::query(
+        let day_summaries_updated = sqlx::query(&self.sql(
             "UPDATE day_summaries SET
              created_at = DATE_ADD(created_at, INTERVAL ? HOUR),
              updated_at = DATE_ADD(updated_at, INTERVAL ? HOUR)",
-        )
+        ))
         .bind(local_offset)
         .bind(local_offset)
         .execute(&self.pool)
@@ -1170,3 +3803,173 @@ impl DatabaseRepository for MariaDbRepository {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_names_get_prefix() {
+        assert_eq!(
+            prefix_tables(
+                "SELECT s.id FROM sessions s JOIN timeline_cards c ON c.session_id = s.id WHERE table_name = 'frames'",
+                "alice_"
+            ),
+            "SELECT s.id FROM alice_sessions s JOIN alice_timeline_cards c ON c.session_id = s.id WHERE table_name = 'frames'"
+        );
+        assert_eq!(
+            prefix_tables(
+                "CREATE INDEX idx_sessions_start_time ON sessions(start_time)",
+                "bob_"
+            ),
+            "CREATE INDEX idx_sessions_start_time ON bob_sessions(start_time)"
+        );
+        assert_eq!(
+            prefix_tables("SELECT * FROM frames", ""),
+            "SELECT * FROM frames"
+        );
+        assert!(validate_table_prefix("team_01_").is_ok());
+        assert!(validate_table_prefix("bad-prefix").is_err());
+        assert!(validate_table_prefix("x; DROP TABLE sessions").is_err());
+    }
+
+    #[test]
+    fn test_prefix_skips_columns_aliases_and_literals() {
+        let schema = prefix_tables(
+            "CREATE TABLE IF NOT EXISTS keyword_hits (
+                id BIGINT PRIMARY KEY AUTO_INCREMENT,
+                session_id BIGINT NOT NULL,
+                frames TEXT,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )",
+            "t_",
+        );
+        assert!(schema.contains("EXISTS t_keyword_hits ("));
+        assert!(schema.contains("REFERENCES t_sessions(id)"));
+        assert!(schema.contains("                frames TEXT,"));
+
+        assert_eq!(
+            prefix_tables(
+                "SELECT COUNT(*) AS frames FROM frames WHERE note = 'from sessions'",
+                "t_"
+            ),
+            "SELECT COUNT(*) AS frames FROM t_frames WHERE note = 'from sessions'"
+        );
+        assert_eq!(
+            prefix_tables(
+                "UPDATE sessions SET archived = 1 WHERE NOT EXISTS (SELECT 1 FROM timeline_cards c WHERE c.session_id = sessions_id)",
+                "t_"
+            ),
+            "UPDATE t_sessions SET archived = 1 WHERE NOT EXISTS (SELECT 1 FROM t_timeline_cards c WHERE c.session_id = sessions_id)"
+        );
+        assert_eq!(
+            prefix_tables(
+                "INSERT INTO provider_comparisons (session_id, cards_json) VALUES (?, ?)",
+                "t_"
+            ),
+            "INSERT INTO t_provider_comparisons (session_id, cards_json) VALUES (?, ?)"
+        );
+    }
+
+    /// 需要本地 MariaDB：MARIADB_TEST_HOST / MARIADB_TEST_USER / MARIADB_TEST_PASSWORD，
+    /// 运行 cargo test -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_schema_and_queries_with_prefix() {
+        let host = std::env::var("MARIADB_TEST_HOST").unwrap_or_else(|_| "127.0.0.1".into());
+        let username = std::env::var("MARIADB_TEST_USER").unwrap_or_else(|_| "root".into());
+        let password = std::env::var("MARIADB_TEST_PASSWORD").unwrap_or_default();
+        let connection = MariaDbConnectionSettings {
+            connect_retries: 0,
+            ..MariaDbConnectionSettings::default()
+        };
+        let repo = MariaDbRepository::new(&MariaDbOptions {
+            host: &host,
+            port: 3306,
+            database: "screen_analyzer_test",
+            username: &username,
+            password: &password,
+            table_prefix: "prefix_test_",
+            migrate_from_prefix: None,
+            connection: &connection,
+        })
+        .await
+        .unwrap();
+
+        let now = Utc::now();
+        let session_id = repo
+            .insert_session(&Session {
+                id: None,
+                start_time: now,
+                end_time: now,
+                title: "prefix".to_string(),
+                summary: String::new(),
+                video_path: Some("/old/videos/a.mp4".to_string()),
+                tags: "[]".to_string(),
+                created_at: None,
+                device_name: None,
+                device_type: None,
+                pinned: false,
+                archived: false,
+                archive_key: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(repo.get_session(session_id).await.unwrap().title, "prefix");
+
+        repo.insert_provider_comparison(&ProviderComparisonRecord {
+            id: None,
+            session_id,
+            run_id: "run".to_string(),
+            provider: "qwen".to_string(),
+            model: "qwen-vl".to_string(),
+            error_message: None,
+            latency_ms: 1,
+            input_tokens: None,
+            output_tokens: None,
+            timeline_cards: "[]".to_string(),
+            created_at: now,
+        })
+        .await
+        .unwrap();
+        let comparisons = repo.get_provider_comparisons(session_id).await.unwrap();
+        assert_eq!(comparisons[0].timeline_cards, "[]");
+
+        repo.insert_timeline_card(&TimelineCardRecord {
+            id: None,
+            session_id,
+            llm_call_id: None,
+            start_time: now.to_rfc3339(),
+            end_time: now.to_rfc3339(),
+            category: "work".to_string(),
+            subcategory: "coding".to_string(),
+            title: "prefix".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "[]".to_string(),
+            video_preview_path: Some("/old/videos/a.mp4".to_string()),
+            created_at: now,
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: Some("/old/clips/a.mp4".to_string()),
+            privacy_level: None,
+        })
+        .await
+        .unwrap();
+
+        assert!(repo.relocate_file_paths("/old", "/new").await.unwrap() >= 3);
+        let card = &repo
+            .get_timeline_cards_by_session(session_id)
+            .await
+            .unwrap()[0];
+        assert_eq!(
+            card.video_preview_path.as_deref(),
+            Some("/new/videos/a.mp4")
+        );
+        assert_eq!(card.preview_clip_path.as_deref(), Some("/new/clips/a.mp4"));
+        repo.delete_session(session_id).await.unwrap();
+    }
+}
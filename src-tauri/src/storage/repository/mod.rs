@@ -1,6 +1,8 @@
 // Repository 抽象层 - 定义数据库操作接口
 
+pub mod failover;
 pub mod mariadb;
+pub mod outbox;
 pub mod sqlite;
 
 use super::models::*;
@@ -44,6 +46,9 @@ pub trait DatabaseRepository: Send + Sync {
     /// 更新会话标签
     async fn update_session_tags(&self, session_id: i64, tags: &str) -> Result<()>;
 
+    /// 在一个事务中批量更新会话标签：(会话ID, 标签JSON)
+    async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()>;
+
     /// 更新会话视频路径
     async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()>;
 
@@ -53,9 +58,19 @@ pub trait DatabaseRepository: Send + Sync {
     /// 删除会话
     async fn delete_session(&self, session_id: i64) -> Result<()>;
 
+    /// 在一个事务中按原 ID 写回会话快照及其关联记录（撤销删除）
+    async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()>;
+
     /// 获取过期会话（用于清理前获取文件路径）
     async fn get_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<Vec<Session>>;
 
+    /// 获取本设备（或未记录设备）开始时间早于指定时间、既没有时间线卡片也没有截图记录的会话（不含置顶和已归档的会话）
+    async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>>;
+
     /// 删除过期会话
     async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64>;
 
@@ -86,6 +101,9 @@ pub trait DatabaseRepository: Send + Sync {
     /// 获取会话的 LLM 调用记录
     async fn get_llm_calls_by_session(&self, session_id: i64) -> Result<Vec<LLMCallRecord>>;
 
+    /// 获取全部 LLM 调用记录的 ID（数据体检检查引用是否存在）
+    async fn get_llm_call_ids(&self) -> Result<Vec<i64>>;
+
     /// 获取最近的 LLM 调用错误
     async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>>;
 
@@ -123,9 +141,27 @@ pub trait DatabaseRepository: Send + Sync {
         session_id: i64,
     ) -> Result<Vec<TimelineCardRecord>>;
 
+    /// 按 ID 获取时间线卡片
+    async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>>;
+
     /// 获取最近的时间线卡片
     async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>>;
 
+    /// 获取 ID 大于指定值的时间线卡片（按 ID 升序，用于增量导出）
+    async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>>;
+
+    /// 获取修改位置 (updated_at, id) 在指定位置之后的时间线卡片（按修改时间和 ID 升序，用于增量导出卡片修改）
+    async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>>;
+
     /// 删除会话的时间线卡片
     async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()>;
 
@@ -134,6 +170,9 @@ pub trait DatabaseRepository: Send + Sync {
     /// 获取数据库统计信息 (会话数, 帧数, 数据库大小)
     async fn get_stats(&self) -> Result<(i64, i64, i64)>;
 
+    /// 按会话统计数据库行数和文本字段字节数（session_id 为空时统计所有会话）
+    async fn get_session_row_usage(&self, session_id: Option<i64>) -> Result<Vec<SessionRowUsage>>;
+
     /// 获取已分析的视频路径列表
     async fn get_analyzed_video_paths(&self) -> Result<Vec<String>>;
 
@@ -148,6 +187,399 @@ pub trait DatabaseRepository: Send + Sync {
     /// 删除某一天的总结
     async fn delete_day_summary(&self, date: &str) -> Result<()>;
 
+    /// 设置某一天的总结是否已锁定（完成当日复核）
+    async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()>;
+
+    // ========== 休息记录 ==========
+
+    /// 插入休息记录
+    async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64>;
+
+    /// 获取某一天的休息记录
+    async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>>;
+
+    // ========== 关注关键词命中 ==========
+
+    /// 替换会话的关键词命中记录（重新分析后旧记录作废）
+    async fn replace_keyword_hits(&self, session_id: i64, hits: &[KeywordHitRecord]) -> Result<()>;
+
+    /// 获取时间范围内的关键词命中记录（按活动时间）
+    async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>>;
+
+    /// 获取会话的关键词命中记录
+    async fn get_keyword_hits_by_session(&self, session_id: i64) -> Result<Vec<KeywordHitRecord>>;
+
+    // ========== 屏幕文字 ==========
+
+    /// 替换会话的屏幕文字记录（重新识别后旧记录作废）
+    async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()>;
+
+    /// 获取会话的屏幕文字记录（按分段开始时间）
+    async fn get_screen_text_by_session(&self, session_id: i64) -> Result<Vec<ScreenTextRecord>>;
+
+    /// 获取时间范围内的屏幕文字记录（按分段开始时间）
+    async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>>;
+
+    /// 搜索包含指定文字的屏幕文字记录（pattern 为 LIKE 模式，以 ! 转义），最新的在前；
+    /// 指定 language 时只搜索该语言的分段
+    async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>>;
+
+    // ========== 报错时刻 ==========
+
+    /// 替换会话的报错时刻（重新识别后旧记录作废）
+    async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()>;
+
+    /// 获取时间范围内的报错时刻（按出现时间）
+    async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>>;
+
+    /// 获取会话的报错时刻（按出现时间）
+    async fn get_error_moments_by_session(&self, session_id: i64)
+        -> Result<Vec<ErrorMomentRecord>>;
+
+    // ========== 剪贴板记录 ==========
+
+    /// 保存剪贴板记录
+    async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64>;
+
+    /// 获取时间范围内的剪贴板记录（按复制时间）
+    async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>>;
+
+    /// 按内容哈希或文本（pattern 为 LIKE 模式，以 ! 转义）查找剪贴板记录，最新的在前
+    async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>>;
+
+    // ========== 音频活动 ==========
+
+    /// 保存一分钟的音频活动（同一分钟已有记录时合并，任一次检测到使用即为使用）
+    async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()>;
+
+    /// 获取时间范围内的音频活动（按分钟）
+    async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>>;
+
+    // ========== 专注拦截记录 ==========
+
+    /// 记录一次专注时段内拦截的干扰
+    async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64>;
+
+    /// 获取某一天拦截的干扰
+    async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>>;
+
+    // ========== 截屏暂停记录 ==========
+
+    /// 记录一次截屏暂停，返回记录ID
+    async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64>;
+
+    /// 结束截屏暂停
+    async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()>;
+
+    /// 结束所有未结束的暂停（上次退出时仍在暂停中），返回更新的行数
+    async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64>;
+
+    /// 获取与时间范围有重叠的暂停记录
+    async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>>;
+
+    // ========== 时段意图 ==========
+
+    /// 记录一个时段意图，返回记录ID
+    async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64>;
+
+    /// 结束所有仍在进行中的时段意图，返回更新的行数
+    async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64>;
+
+    /// 获取与时间范围有重叠的时段意图
+    async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>>;
+
+    /// 保存时段意图的吻合度
+    async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()>;
+
+    // ========== 外部活动事件 ==========
+
+    /// 写入外部活动事件，已存在的相同事件（来源、时间和标题相同）被忽略，返回新增条数
+    async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64>;
+
+    /// 获取与时间范围有重叠的外部活动事件
+    async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>>;
+
+    // ========== 时间线卡片分类 ==========
+
+    /// 更新时间线卡片的类别和干扰活动（用于应用画像重新分类）
+    async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()>;
+
+    /// 把所有子类别为 from 的卡片改为 to，返回修改的卡片数
+    async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64>;
+
+    // ========== 卡片复核 ==========
+
+    /// 获取待复核的时间线卡片（按开始时间倒序）
+    async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>>;
+
+    /// 更新卡片的标题、类别和复核状态（确认或纠正）
+    async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()>;
+
+    /// 保存卡片的会议纪要（JSON）
+    async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()>;
+
+    /// 保存卡片命中的关注关键词（JSON 数组，为空时清除）
+    async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()>;
+
+    /// 保存卡片预览短片路径（为空时清除）
+    async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()>;
+
+    /// 保存卡片的隐私敏感度（0-3，见 PrivacyLevel）
+    async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()>;
+
+    /// 更新卡片的开始/结束时间（修复时间格式）
+    async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()>;
+
+    /// 更新视频分段的开始/结束时间（修复时间格式）
+    async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()>;
+
+    /// 插入卡片纠正记录
+    async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64>;
+
+    /// 获取最近的卡片纠正记录
+    async fn get_recent_card_corrections(&self, limit: i64) -> Result<Vec<CardCorrectionRecord>>;
+
+    // ========== 个人纪录 ==========
+
+    /// 保存个人纪录（同一类型的纪录会被覆盖）
+    async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()>;
+
+    /// 获取所有个人纪录
+    async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>>;
+
+    // ========== 日期类型 ==========
+
+    /// 标记日期类型（同一天的标记会被覆盖）
+    async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()>;
+
+    /// 清除日期类型标记，恢复按星期自动判断
+    async fn clear_day_type(&self, date: &str) -> Result<()>;
+
+    /// 获取日期范围内（含首尾，YYYY-MM-DD）的日期类型标记
+    async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>>;
+
+    // ========== 文件路径迁移 ==========
+
+    /// 将以 old_prefix 开头的文件路径替换为 new_prefix 开头（数据目录迁移），返回更新的行数
+    async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64>;
+
+    // ========== 存储用量统计 ==========
+
+    /// 累加某天的存储用量（同一天同一设备的记录会合并）
+    async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()>;
+
+    /// 获取设备在日期范围内的每日存储用量（闭区间，YYYY-MM-DD）
+    async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>>;
+
+    // ========== 会话置顶 ==========
+
+    /// 设置会话是否置顶（置顶的会话不会被自动清理）
+    async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()>;
+
+    /// 获取所有置顶的会话
+    async fn get_pinned_sessions(&self) -> Result<Vec<Session>>;
+
+    // ========== 会话归档 ==========
+
+    /// 标记会话已归档（清空本地视频路径并删除帧记录）
+    async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()>;
+
+    /// 标记会话已从归档恢复
+    async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()>;
+
+    // ========== 待删除会话 ==========
+
+    /// 登记待删除会话
+    async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()>;
+
+    /// 获取所有待删除会话（按删除时间排序）
+    async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>>;
+
+    /// 移除待删除会话记录
+    async fn remove_pending_deletion(&self, session_id: i64) -> Result<()>;
+
+    // ========== 待分析视频认领 ==========
+
+    /// 认领待分析视频：没有认领、认领已过期或已由 owner 认领时写入认领（有效期到 lease_until）并返回 true
+    async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    /// 释放 owner 对视频的认领
+    async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()>;
+
+    // ========== 服务商对比 ==========
+
+    /// 保存一个服务商的对比结果
+    async fn insert_provider_comparison(&self, record: &ProviderComparisonRecord) -> Result<i64>;
+
+    /// 获取会话的全部对比结果（按时间倒序）
+    async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>>;
+
+    // ========== 数据外发记录 ==========
+
+    /// 保存一条数据外发记录
+    async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64>;
+
+    /// 获取时间范围内的数据外发记录（按时间顺序）
+    async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>>;
+
+    // ========== 会话书签 ==========
+
+    /// 保存一个会话书签
+    async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64>;
+
+    /// 获取会话的书签（按视频位置排序）
+    async fn get_session_bookmarks(&self, session_id: i64) -> Result<Vec<SessionBookmarkRecord>>;
+
+    /// 删除书签
+    async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()>;
+
+    // ========== 计时服务导出记录 ==========
+
+    /// 按幂等键获取导出记录
+    async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>>;
+
+    /// 获取会话的导出记录
+    async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>>;
+
+    /// 保存导出记录
+    async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()>;
+
+    // ========== webhook 投递记录 ==========
+
+    /// 保存一条 webhook 投递记录
+    async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64>;
+
+    /// 获取最近的 webhook 投递记录（按时间倒序，session_id 为空时不限会话）
+    async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>>;
+
     // ========== 数据库初始化和元数据 ==========
 
     /// 初始化数据库表结构
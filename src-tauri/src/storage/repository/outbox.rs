@@ -0,0 +1,532 @@
+// 写前日志（outbox）- MariaDB 短暂不可用时暂存分析结果的写入，恢复后按顺序重放
+//
+// 写入会话、帧、LLM 调用、视频分段、时间线卡片、更新会话，以及替换会话的关键词命中、屏幕文字和报错时刻时，
+// 如果遇到连接错误，或 outbox 中还有未重放的写入
+// （保证顺序），把这次写入序列化后追加到本地队列 SQLite 的 outbox 表，不再丢失。
+// 暂存的插入返回占位 ID（负数，-条目 ID），后续写入引用占位 ID 时同样进入 outbox；
+// 重放时按条目顺序执行，把占位 ID 替换为远程数据库返回的真实 ID。占位 ID 与真实 ID 的对应关系
+// 与条目的删除在同一个事务中保存，重放中断后可以继续
+
+use super::failover::is_connection_error;
+use super::DatabaseRepository;
+use crate::storage::models::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 非连接错误的重放失败达到该次数后丢弃条目，避免一条坏数据阻塞后面的写入
+const MAX_REPLAY_ATTEMPTS: i64 = 5;
+
+/// 暂存的写入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum OutboxOp {
+    InsertSession {
+        session: Session,
+    },
+    InsertFrames {
+        frames: Vec<Frame>,
+    },
+    InsertLlmCall {
+        record: LLMCallRecord,
+    },
+    InsertVideoSegments {
+        segments: Vec<VideoSegmentRecord>,
+    },
+    InsertTimelineCards {
+        cards: Vec<TimelineCardRecord>,
+    },
+    UpdateSession {
+        session_id: i64,
+        title: String,
+        summary: String,
+        video_path: Option<String>,
+        tags: String,
+    },
+    UpdateSessionTags {
+        session_id: i64,
+        tags: String,
+    },
+    UpdateSessionVideoPath {
+        session_id: i64,
+        video_path: String,
+    },
+    ReplaceKeywordHits {
+        session_id: i64,
+        hits: Vec<KeywordHitRecord>,
+    },
+    ReplaceScreenText {
+        session_id: i64,
+        records: Vec<ScreenTextRecord>,
+    },
+    ReplaceErrorMoments {
+        session_id: i64,
+        records: Vec<ErrorMomentRecord>,
+    },
+}
+
+/// 是否为占位 ID
+pub fn is_placeholder(id: i64) -> bool {
+    id < 0
+}
+
+impl OutboxOp {
+    fn name(&self) -> &'static str {
+        match self {
+            OutboxOp::InsertSession { .. } => "insert_session",
+            OutboxOp::InsertFrames { .. } => "insert_frames",
+            OutboxOp::InsertLlmCall { .. } => "insert_llm_call",
+            OutboxOp::InsertVideoSegments { .. } => "insert_video_segments",
+            OutboxOp::InsertTimelineCards { .. } => "insert_timeline_cards",
+            OutboxOp::UpdateSession { .. } => "update_session",
+            OutboxOp::UpdateSessionTags { .. } => "update_session_tags",
+            OutboxOp::UpdateSessionVideoPath { .. } => "update_session_video_path",
+            OutboxOp::ReplaceKeywordHits { .. } => "replace_keyword_hits",
+            OutboxOp::ReplaceScreenText { .. } => "replace_screen_text",
+            OutboxOp::ReplaceErrorMoments { .. } => "replace_error_moments",
+        }
+    }
+
+    /// 把占位 ID 替换为真实 ID（找不到对应关系时返回错误）
+    pub fn resolve(&mut self, ids: &HashMap<i64, i64>) -> Result<()> {
+        let resolve = |id: &mut i64| -> Result<()> {
+            if is_placeholder(*id) {
+                *id = *ids
+                    .get(id)
+                    .ok_or_else(|| anyhow!("找不到占位 ID {} 对应的记录", id))?;
+            }
+            Ok(())
+        };
+        let resolve_option = |id: &mut Option<i64>| match id {
+            Some(id) => resolve(id),
+            None => Ok(()),
+        };
+        match self {
+            OutboxOp::InsertSession { .. } => {}
+            OutboxOp::InsertFrames { frames } => {
+                for frame in frames {
+                    resolve(&mut frame.session_id)?;
+                }
+            }
+            OutboxOp::InsertLlmCall { record } => resolve_option(&mut record.session_id)?,
+            OutboxOp::InsertVideoSegments { segments } => {
+                for segment in segments {
+                    resolve(&mut segment.session_id)?;
+                    resolve_option(&mut segment.llm_call_id)?;
+                }
+            }
+            OutboxOp::InsertTimelineCards { cards } => {
+                for card in cards {
+                    resolve(&mut card.session_id)?;
+                    resolve_option(&mut card.llm_call_id)?;
+                }
+            }
+            OutboxOp::UpdateSession { session_id, .. }
+            | OutboxOp::UpdateSessionTags { session_id, .. }
+            | OutboxOp::UpdateSessionVideoPath { session_id, .. } => resolve(session_id)?,
+            OutboxOp::ReplaceKeywordHits { session_id, hits } => {
+                resolve(session_id)?;
+                for hit in hits {
+                    resolve(&mut hit.session_id)?;
+                }
+            }
+            OutboxOp::ReplaceScreenText {
+                session_id,
+                records,
+            } => {
+                resolve(session_id)?;
+                for record in records {
+                    resolve(&mut record.session_id)?;
+                }
+            }
+            OutboxOp::ReplaceErrorMoments {
+                session_id,
+                records,
+            } => {
+                resolve(session_id)?;
+                for record in records {
+                    resolve(&mut record.session_id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 在远程数据库上执行，返回插入记录的 ID
+    async fn apply(&self, remote: &dyn DatabaseRepository) -> Result<Option<i64>> {
+        match self {
+            OutboxOp::InsertSession { session } => remote.insert_session(session).await.map(Some),
+            OutboxOp::InsertFrames { frames } => remote.insert_frames(frames).await.map(|_| None),
+            OutboxOp::InsertLlmCall { record } => remote.insert_llm_call(record).await.map(Some),
+            OutboxOp::InsertVideoSegments { segments } => {
+                remote.insert_video_segments(segments).await.map(|_| None)
+            }
+            OutboxOp::InsertTimelineCards { cards } => {
+                remote.insert_timeline_cards(cards).await.map(|_| None)
+            }
+            OutboxOp::UpdateSession {
+                session_id,
+                title,
+                summary,
+                video_path,
+                tags,
+            } => remote
+                .update_session(*session_id, title, summary, video_path.as_deref(), tags)
+                .await
+                .map(|_| None),
+            OutboxOp::UpdateSessionTags { session_id, tags } => remote
+                .update_session_tags(*session_id, tags)
+                .await
+                .map(|_| None),
+            OutboxOp::UpdateSessionVideoPath {
+                session_id,
+                video_path,
+            } => remote
+                .update_session_video_path(*session_id, video_path)
+                .await
+                .map(|_| None),
+            OutboxOp::ReplaceKeywordHits { session_id, hits } => remote
+                .replace_keyword_hits(*session_id, hits)
+                .await
+                .map(|_| None),
+            OutboxOp::ReplaceScreenText {
+                session_id,
+                records,
+            } => remote
+                .replace_screen_text(*session_id, records)
+                .await
+                .map(|_| None),
+            OutboxOp::ReplaceErrorMoments {
+                session_id,
+                records,
+            } => remote
+                .replace_error_moments(*session_id, records)
+                .await
+                .map(|_| None),
+        }
+    }
+}
+
+/// outbox 状态
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutboxStatus {
+    /// 是否启用（MariaDB 模式且启用了本地队列）
+    pub enabled: bool,
+    /// 待重放的写入数
+    pub pending: usize,
+    /// 最早的待重放写入的时间（本地时间）
+    pub oldest: Option<String>,
+    /// 已重放的写入数（本次运行）
+    pub replayed: u64,
+    /// 最近一次重放完成的时间（本地时间）
+    pub last_replay: Option<String>,
+    /// 最近一次重放失败的原因
+    pub last_error: Option<String>,
+}
+
+/// 一次重放的结果
+pub enum ReplayOutcome {
+    /// 全部重放完成（数量）
+    Done(usize),
+    /// 遇到连接错误，停止重放（已重放的数量，错误）
+    Interrupted(usize, anyhow::Error),
+}
+
+/// 本地队列 SQLite 中的 outbox
+pub struct Outbox {
+    pool: SqlitePool,
+    pending: AtomicUsize,
+    replayed: AtomicU64,
+    last_replay: Mutex<Option<String>>,
+    last_error: Mutex<Option<String>>,
+}
+
+fn local_now_string() -> String {
+    crate::storage::local_now()
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+impl Outbox {
+    /// 打开 outbox（表不存在时创建）
+    pub async fn open(pool: SqlitePool) -> Result<Self> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS outbox_ids (placeholder INTEGER PRIMARY KEY, real_id INTEGER NOT NULL)",
+        )
+        .execute(&pool)
+        .await?;
+
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox")
+            .fetch_one(&pool)
+            .await?;
+        if pending > 0 {
+            info!("outbox 中有 {} 条待重放的写入", pending);
+        }
+        Ok(Self {
+            pool,
+            pending: AtomicUsize::new(pending as usize),
+            replayed: AtomicU64::new(0),
+            last_replay: Mutex::new(None),
+            last_error: Mutex::new(None),
+        })
+    }
+
+    /// 待重放的写入数
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// 追加一条写入，返回占位 ID
+    pub async fn push(&self, op: &OutboxOp) -> Result<i64> {
+        let id =
+            sqlx::query("INSERT INTO outbox (created_at, operation, payload) VALUES (?, ?, ?)")
+                .bind(local_now_string())
+                .bind(op.name())
+                .bind(serde_json::to_string(op)?)
+                .execute(&self.pool)
+                .await?
+                .last_insert_rowid();
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        info!("写入已暂存到 outbox: {} (#{})", op.name(), id);
+        Ok(-id)
+    }
+
+    /// 按顺序重放所有写入
+    pub async fn replay(&self, remote: &dyn DatabaseRepository) -> Result<ReplayOutcome> {
+        let rows = sqlx::query("SELECT id, payload, attempts FROM outbox ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        if rows.is_empty() {
+            return Ok(ReplayOutcome::Done(0));
+        }
+        let mut ids: HashMap<i64, i64> = sqlx::query("SELECT placeholder, real_id FROM outbox_ids")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| (row.get::<i64, _>(0), row.get::<i64, _>(1)))
+            .collect();
+
+        let mut replayed = 0;
+        for row in rows {
+            let entry_id: i64 = row.get(0);
+            let payload: String = row.get(1);
+            let attempts: i64 = row.get(2);
+
+            let result = match serde_json::from_str::<OutboxOp>(&payload) {
+                Ok(mut op) => match op.resolve(&ids) {
+                    Ok(()) => op.apply(remote).await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(anyhow!("无法解析暂存的写入: {}", e)),
+            };
+
+            match result {
+                Ok(real_id) => {
+                    let mut tx = self.pool.begin().await?;
+                    if let Some(real_id) = real_id {
+                        sqlx::query(
+                            "INSERT OR REPLACE INTO outbox_ids (placeholder, real_id) VALUES (?, ?)",
+                        )
+                        .bind(-entry_id)
+                        .bind(real_id)
+                        .execute(&mut *tx)
+                        .await?;
+                        ids.insert(-entry_id, real_id);
+                    }
+                    sqlx::query("DELETE FROM outbox WHERE id = ?")
+                        .bind(entry_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    self.pending.fetch_sub(1, Ordering::SeqCst);
+                    self.replayed.fetch_add(1, Ordering::SeqCst);
+                    replayed += 1;
+                }
+                Err(e) if is_connection_error(&e) => {
+                    self.set_last_error(&e);
+                    return Ok(ReplayOutcome::Interrupted(replayed, e));
+                }
+                Err(e) => {
+                    self.set_last_error(&e);
+                    if attempts + 1 >= MAX_REPLAY_ATTEMPTS {
+                        warn!(
+                            "outbox 条目 #{} 重放失败 {} 次，已丢弃: {}",
+                            entry_id,
+                            attempts + 1,
+                            e
+                        );
+                        sqlx::query("DELETE FROM outbox WHERE id = ?")
+                            .bind(entry_id)
+                            .execute(&self.pool)
+                            .await?;
+                        self.pending.fetch_sub(1, Ordering::SeqCst);
+                        continue;
+                    }
+                    sqlx::query(
+                        "UPDATE outbox SET attempts = attempts + 1, last_error = ? WHERE id = ?",
+                    )
+                    .bind(e.to_string())
+                    .bind(entry_id)
+                    .execute(&self.pool)
+                    .await?;
+                    // 保持顺序：这一条没有写入时不重放后面的写入
+                    return Ok(ReplayOutcome::Interrupted(replayed, e));
+                }
+            }
+        }
+
+        // 全部重放完成后，占位 ID 不会再被引用
+        sqlx::query("DELETE FROM outbox_ids")
+            .execute(&self.pool)
+            .await?;
+        if let Ok(mut last_replay) = self.last_replay.lock() {
+            *last_replay = Some(local_now_string());
+        }
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = None;
+        }
+        Ok(ReplayOutcome::Done(replayed))
+    }
+
+    fn set_last_error(&self, error: &anyhow::Error) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(error.to_string());
+        }
+    }
+
+    /// 当前状态
+    pub async fn status(&self) -> Result<OutboxStatus> {
+        let oldest: Option<String> =
+            sqlx::query_scalar("SELECT created_at FROM outbox ORDER BY id LIMIT 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(OutboxStatus {
+            enabled: true,
+            pending: self.pending(),
+            oldest,
+            replayed: self.replayed.load(Ordering::SeqCst),
+            last_replay: self
+                .last_replay
+                .lock()
+                .map(|value| value.clone())
+                .unwrap_or_default(),
+            last_error: self
+                .last_error
+                .lock()
+                .map(|value| value.clone())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_resolve_to_real_ids() {
+        let card = TimelineCardRecord {
+            id: None,
+            session_id: -3,
+            llm_call_id: Some(-4),
+            start_time: "2025-10-09T10:00:00+08:00".to_string(),
+            end_time: "2025-10-09T10:10:00+08:00".to_string(),
+            category: "work".to_string(),
+            subcategory: String::new(),
+            title: "编码".to_string(),
+            summary: String::new(),
+            detailed_summary: String::new(),
+            distractions: None,
+            app_sites: "{}".to_string(),
+            video_preview_path: None,
+            created_at: chrono::Utc::now(),
+            confidence: None,
+            review_status: None,
+            meeting_notes: None,
+            keyword_tags: None,
+            preview_clip_path: None,
+            privacy_level: None,
+        };
+        let mut op = OutboxOp::InsertTimelineCards {
+            cards: vec![
+                card.clone(),
+                TimelineCardRecord {
+                    session_id: 42,
+                    llm_call_id: None,
+                    ..card
+                },
+            ],
+        };
+
+        // 序列化后仍能还原
+        let payload = serde_json::to_string(&op).unwrap();
+        assert!(payload.contains("\"op\":\"insert_timeline_cards\""));
+        let restored: OutboxOp = serde_json::from_str(&payload).unwrap();
+        assert_eq!(restored.name(), "insert_timeline_cards");
+
+        let ids = HashMap::from([(-3, 120), (-4, 77)]);
+        op.resolve(&ids).unwrap();
+        let OutboxOp::InsertTimelineCards { cards } = &op else {
+            unreachable!()
+        };
+        assert_eq!((cards[0].session_id, cards[0].llm_call_id), (120, Some(77)));
+        assert_eq!((cards[1].session_id, cards[1].llm_call_id), (42, None));
+
+        let mut update = OutboxOp::UpdateSession {
+            session_id: -9,
+            title: String::new(),
+            summary: String::new(),
+            video_path: None,
+            tags: "[]".to_string(),
+        };
+        assert!(update.resolve(&ids).is_err());
+    }
+
+    #[test]
+    fn test_session_data_replacements_resolve_placeholders() {
+        let now = chrono::Utc::now();
+        let mut op = OutboxOp::ReplaceScreenText {
+            session_id: -3,
+            records: vec![ScreenTextRecord {
+                id: None,
+                session_id: -3,
+                segment_start: now,
+                segment_end: now,
+                text: "cargo build".to_string(),
+                language: Some("code".to_string()),
+                created_at: now,
+            }],
+        };
+        let payload = serde_json::to_string(&op).unwrap();
+        assert!(payload.contains("\"op\":\"replace_screen_text\""));
+
+        op.resolve(&HashMap::from([(-3, 120)])).unwrap();
+        let OutboxOp::ReplaceScreenText {
+            session_id,
+            records,
+        } = &op
+        else {
+            unreachable!()
+        };
+        assert_eq!((*session_id, records[0].session_id), (120, 120));
+    }
+}
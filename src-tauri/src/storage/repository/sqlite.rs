@@ -107,7 +107,8 @@ impl DatabaseRepository for SqliteRepository {
         let session = sqlx::query_as::<_, Session>(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             WHERE id = ?
             "#,
@@ -128,6 +129,7 @@ impl DatabaseRepository for SqliteRepository {
             session,
             frames,
             tags,
+            storage: None,
         })
     }
 
@@ -136,7 +138,8 @@ impl DatabaseRepository for SqliteRepository {
         let sessions = sqlx::query_as::<_, Session>(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             WHERE DATE(start_time) = ?
             ORDER BY start_time DESC
@@ -153,7 +156,8 @@ impl DatabaseRepository for SqliteRepository {
         let sessions = sqlx::query_as::<_, Session>(
             r#"
             SELECT id, start_time, end_time, title, summary,
-                   video_path, tags, created_at, device_name, device_type
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
             FROM sessions
             ORDER BY start_time
             "#,
@@ -196,6 +200,19 @@ impl DatabaseRepository for SqliteRepository {
         Ok(())
     }
 
+    async fn update_sessions_tags(&self, updates: &[(i64, String)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (session_id, tags) in updates {
+            sqlx::query("UPDATE sessions SET tags = ? WHERE id = ?")
+                .bind(tags)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
     async fn update_session_video_path(&self, session_id: i64, video_path: &str) -> Result<()> {
         sqlx::query("UPDATE sessions SET video_path = ? WHERE id = ?")
             .bind(video_path)
@@ -239,9 +256,241 @@ impl DatabaseRepository for SqliteRepository {
         Ok(())
     }
 
+    async fn restore_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        let session = &snapshot.session;
+        let session_id = session
+            .id
+            .ok_or_else(|| anyhow::anyhow!("会话快照缺少会话 ID"))?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (
+                id, start_time, end_time, title, summary, video_path, tags, created_at,
+                device_name, device_type, pinned, archived, archive_key
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(session_id)
+        .bind(session.start_time)
+        .bind(session.end_time)
+        .bind(&session.title)
+        .bind(&session.summary)
+        .bind(&session.video_path)
+        .bind(&session.tags)
+        .bind(session.created_at)
+        .bind(&session.device_name)
+        .bind(&session.device_type)
+        .bind(session.pinned)
+        .bind(session.archived)
+        .bind(&session.archive_key)
+        .execute(&mut *tx)
+        .await?;
+
+        for frame in &snapshot.frames {
+            sqlx::query(
+                r#"
+                INSERT INTO frames (id, session_id, timestamp, file_path, metadata)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(frame.id)
+            .bind(session_id)
+            .bind(frame.timestamp)
+            .bind(&frame.file_path)
+            .bind(&frame.metadata)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for call in &snapshot.llm_calls {
+            sqlx::query(
+                r#"
+                INSERT INTO llm_calls (
+                    id, session_id, provider, model, call_type,
+                    request_headers, request_body, response_headers, response_body,
+                    status_code, error_message, latency_ms, token_usage, created_at,
+                    prompt_version
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(call.id)
+            .bind(session_id)
+            .bind(&call.provider)
+            .bind(&call.model)
+            .bind(&call.call_type)
+            .bind(&call.request_headers)
+            .bind(&call.request_body)
+            .bind(&call.response_headers)
+            .bind(&call.response_body)
+            .bind(call.status_code)
+            .bind(&call.error_message)
+            .bind(call.latency_ms)
+            .bind(&call.token_usage)
+            .bind(call.created_at)
+            .bind(&call.prompt_version)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for segment in &snapshot.segments {
+            sqlx::query(
+                r#"
+                INSERT INTO video_segments (
+                    id, session_id, llm_call_id, start_timestamp, end_timestamp,
+                    description, created_at
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(segment.id)
+            .bind(session_id)
+            .bind(segment.llm_call_id)
+            .bind(&segment.start_timestamp)
+            .bind(&segment.end_timestamp)
+            .bind(&segment.description)
+            .bind(segment.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for card in &snapshot.cards {
+            sqlx::query(
+                r#"
+                INSERT INTO timeline_cards (
+                    id, session_id, llm_call_id, start_time, end_time,
+                    category, subcategory, title, summary, detailed_summary,
+                    distractions, app_sites, video_preview_path, created_at,
+                    confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                    privacy_level
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(card.id)
+            .bind(session_id)
+            .bind(card.llm_call_id)
+            .bind(&card.start_time)
+            .bind(&card.end_time)
+            .bind(&card.category)
+            .bind(&card.subcategory)
+            .bind(&card.title)
+            .bind(&card.summary)
+            .bind(&card.detailed_summary)
+            .bind(&card.distractions)
+            .bind(&card.app_sites)
+            .bind(&card.video_preview_path)
+            .bind(card.created_at)
+            .bind(card.confidence)
+            .bind(&card.review_status)
+            .bind(&card.meeting_notes)
+            .bind(&card.keyword_tags)
+            .bind(&card.preview_clip_path)
+            .bind(card.privacy_level)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for bookmark in &snapshot.bookmarks {
+            sqlx::query(
+                r#"
+                INSERT INTO session_bookmarks (id, session_id, video_time, note, created_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(bookmark.id)
+            .bind(session_id)
+            .bind(bookmark.video_time)
+            .bind(&bookmark.note)
+            .bind(bookmark.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for hit in &snapshot.keyword_hits {
+            sqlx::query(
+                r#"
+                INSERT INTO keyword_hits (id, session_id, card_id, keyword, source, excerpt, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(hit.id)
+            .bind(session_id)
+            .bind(hit.card_id)
+            .bind(&hit.keyword)
+            .bind(&hit.source)
+            .bind(&hit.excerpt)
+            .bind(hit.occurred_at)
+            .bind(hit.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for record in &snapshot.screen_text {
+            sqlx::query(
+                r#"
+                INSERT INTO screen_text (id, session_id, segment_start, segment_end, text, language, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(record.id)
+            .bind(session_id)
+            .bind(record.segment_start)
+            .bind(record.segment_end)
+            .bind(&record.text)
+            .bind(&record.language)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for record in &snapshot.error_moments {
+            sqlx::query(
+                r#"
+                INSERT INTO error_moments (id, session_id, kind, error_text, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(record.id)
+            .bind(session_id)
+            .bind(&record.kind)
+            .bind(&record.error_text)
+            .bind(record.occurred_at)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // 导出记录不随会话删除，已存在的跳过
+        for export in &snapshot.time_entry_exports {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO time_entry_exports
+                    (idempotency_key, provider, session_id, card_id, external_id, exported_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&export.idempotency_key)
+            .bind(&export.provider)
+            .bind(session_id)
+            .bind(export.card_id)
+            .bind(&export.external_id)
+            .bind(export.exported_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        info!("已按原 ID 恢复会话: {}", session_id);
+        Ok(())
+    }
+
     async fn get_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<Vec<Session>> {
         let sessions = sqlx::query_as::<_, Session>(
-            "SELECT id, start_time, end_time, title, summary, video_path, tags, created_at, device_name, device_type
+            "SELECT id, start_time, end_time, title, summary, video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
              FROM sessions
              WHERE start_time < ?"
         )
@@ -252,6 +501,29 @@ impl DatabaseRepository for SqliteRepository {
         Ok(sessions)
     }
 
+    async fn get_empty_sessions(
+        &self,
+        cutoff_date: DateTime<Utc>,
+        device_name: &str,
+    ) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(
+            "SELECT s.id, s.start_time, s.end_time, s.title, s.summary, s.video_path, s.tags, s.created_at,
+                   s.device_name, s.device_type, s.pinned, s.archived, s.archive_key
+             FROM sessions s
+             WHERE s.start_time < ? AND s.pinned = 0 AND s.archived = 0
+               AND (s.device_name = ? OR s.device_name IS NULL)
+               AND NOT EXISTS (SELECT 1 FROM timeline_cards c WHERE c.session_id = s.id)
+               AND NOT EXISTS (SELECT 1 FROM frames f WHERE f.session_id = s.id)
+             ORDER BY s.start_time",
+        )
+        .bind(cutoff_date)
+        .bind(device_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
     async fn delete_old_sessions(&self, cutoff_date: DateTime<Utc>) -> Result<u64> {
         let result = sqlx::query("DELETE FROM sessions WHERE start_time < ?")
             .bind(cutoff_date)
@@ -272,13 +544,14 @@ impl DatabaseRepository for SqliteRepository {
     async fn insert_frame(&self, frame: &Frame) -> Result<i64> {
         let result = sqlx::query(
             r#"
-            INSERT INTO frames (session_id, timestamp, file_path)
-            VALUES (?1, ?2, ?3)
+            INSERT INTO frames (session_id, timestamp, file_path, metadata)
+            VALUES (?1, ?2, ?3, ?4)
         "#,
         )
         .bind(frame.session_id)
         .bind(&frame.timestamp)
         .bind(&frame.file_path)
+        .bind(&frame.metadata)
         .execute(&self.pool)
         .await?;
 
@@ -291,13 +564,14 @@ impl DatabaseRepository for SqliteRepository {
         for frame in frames {
             sqlx::query(
                 r#"
-                INSERT INTO frames (session_id, timestamp, file_path)
-                VALUES (?1, ?2, ?3)
+                INSERT INTO frames (session_id, timestamp, file_path, metadata)
+                VALUES (?1, ?2, ?3, ?4)
             "#,
             )
             .bind(frame.session_id)
             .bind(&frame.timestamp)
             .bind(&frame.file_path)
+            .bind(&frame.metadata)
             .execute(&mut *tx)
             .await?;
         }
@@ -309,7 +583,7 @@ impl DatabaseRepository for SqliteRepository {
     async fn get_frames_by_session(&self, session_id: i64) -> Result<Vec<Frame>> {
         let frames = sqlx::query_as::<_, Frame>(
             r#"
-            SELECT id, session_id, timestamp, file_path
+            SELECT id, session_id, timestamp, file_path, metadata
             FROM frames
             WHERE session_id = ?
             ORDER BY timestamp
@@ -372,6 +646,7 @@ impl DatabaseRepository for SqliteRepository {
                 session_count,
                 total_duration_minutes: total_duration_minutes.unwrap_or(0),
                 main_categories,
+                external_minutes: 0,
             });
         }
 
@@ -386,9 +661,10 @@ impl DatabaseRepository for SqliteRepository {
             INSERT INTO llm_calls (
                 session_id, provider, model, call_type,
                 request_headers, request_body, response_headers, response_body,
-                status_code, error_message, latency_ms, token_usage, created_at
+                status_code, error_message, latency_ms, token_usage, created_at,
+                prompt_version
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
         "#,
         )
         .bind(&record.session_id)
@@ -404,6 +680,7 @@ impl DatabaseRepository for SqliteRepository {
         .bind(&record.latency_ms)
         .bind(&record.token_usage)
         .bind(&record.created_at)
+        .bind(&record.prompt_version)
         .execute(&self.pool)
         .await?;
 
@@ -425,6 +702,14 @@ impl DatabaseRepository for SqliteRepository {
         Ok(records)
     }
 
+    async fn get_llm_call_ids(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>("SELECT id FROM llm_calls")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids)
+    }
+
     async fn get_recent_llm_errors(&self, limit: i64) -> Result<Vec<LLMCallRecord>> {
         let records = sqlx::query_as::<_, LLMCallRecord>(
             r#"
@@ -536,9 +821,11 @@ impl DatabaseRepository for SqliteRepository {
             INSERT INTO timeline_cards (
                 session_id, llm_call_id, start_time, end_time,
                 category, subcategory, title, summary, detailed_summary,
-                distractions, app_sites, video_preview_path, created_at
+                distractions, app_sites, video_preview_path, created_at,
+                confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                privacy_level
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
         "#,
         )
         .bind(&card.session_id)
@@ -554,6 +841,12 @@ impl DatabaseRepository for SqliteRepository {
         .bind(&card.app_sites)
         .bind(&card.video_preview_path)
         .bind(&card.created_at)
+        .bind(card.confidence)
+        .bind(&card.review_status)
+        .bind(&card.meeting_notes)
+        .bind(&card.keyword_tags)
+        .bind(&card.preview_clip_path)
+        .bind(card.privacy_level)
         .execute(&self.pool)
         .await?;
 
@@ -569,9 +862,11 @@ impl DatabaseRepository for SqliteRepository {
                 INSERT INTO timeline_cards (
                     session_id, llm_call_id, start_time, end_time,
                     category, subcategory, title, summary, detailed_summary,
-                    distractions, app_sites, video_preview_path, created_at
+                    distractions, app_sites, video_preview_path, created_at,
+                    confidence, review_status, meeting_notes, keyword_tags, preview_clip_path,
+                    privacy_level
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
             "#,
             )
             .bind(&card.session_id)
@@ -587,6 +882,12 @@ impl DatabaseRepository for SqliteRepository {
             .bind(&card.app_sites)
             .bind(&card.video_preview_path)
             .bind(&card.created_at)
+            .bind(card.confidence)
+            .bind(&card.review_status)
+            .bind(&card.meeting_notes)
+            .bind(&card.keyword_tags)
+            .bind(&card.preview_clip_path)
+            .bind(card.privacy_level)
             .execute(&mut *tx)
             .await?;
         }
@@ -613,6 +914,16 @@ impl DatabaseRepository for SqliteRepository {
         Ok(cards)
     }
 
+    async fn get_timeline_card(&self, card_id: i64) -> Result<Option<TimelineCardRecord>> {
+        let card =
+            sqlx::query_as::<_, TimelineCardRecord>("SELECT * FROM timeline_cards WHERE id = ?")
+                .bind(card_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(card)
+    }
+
     async fn get_recent_timeline_cards(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
         let cards = sqlx::query_as::<_, TimelineCardRecord>(
             r#"
@@ -628,6 +939,51 @@ impl DatabaseRepository for SqliteRepository {
         Ok(cards)
     }
 
+    async fn get_timeline_cards_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TimelineCardRecord>> {
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE id > ?
+            ORDER BY id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn get_timeline_cards_updated_after(
+        &self,
+        after: DateTime<Utc>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UpdatedTimelineCard>> {
+        let cards = sqlx::query_as::<_, UpdatedTimelineCard>(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE updated_at > ? OR (updated_at = ? AND id > ?)
+            ORDER BY updated_at ASC, id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(after)
+        .bind(after)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
     async fn delete_timeline_cards_by_session(&self, session_id: i64) -> Result<()> {
         sqlx::query("DELETE FROM timeline_cards WHERE session_id = ?")
             .bind(session_id)
@@ -657,6 +1013,51 @@ impl DatabaseRepository for SqliteRepository {
         Ok((session_count, frame_count, total_size))
     }
 
+    async fn get_session_row_usage(&self, session_id: Option<i64>) -> Result<Vec<SessionRowUsage>> {
+        // LENGTH 对文本返回字符数，转为 BLOB 后才是字节数
+        let records = sqlx::query_as::<_, SessionRowUsage>(
+            r#"
+            SELECT session_id, SUM(row_count) AS row_count, SUM(bytes) AS bytes FROM (
+                SELECT id AS session_id, 1 AS row_count,
+                       LENGTH(CAST(title AS BLOB)) + LENGTH(CAST(summary AS BLOB))
+                       + LENGTH(CAST(tags AS BLOB)) AS bytes
+                FROM sessions
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(CAST(file_path AS BLOB))
+                           + COALESCE(LENGTH(CAST(metadata AS BLOB)), 0))
+                FROM frames GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(CAST(request_headers AS BLOB)) + LENGTH(CAST(request_body AS BLOB))
+                           + COALESCE(LENGTH(CAST(response_headers AS BLOB)), 0)
+                           + COALESCE(LENGTH(CAST(response_body AS BLOB)), 0)
+                           + COALESCE(LENGTH(CAST(error_message AS BLOB)), 0))
+                FROM llm_calls WHERE session_id IS NOT NULL GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(CAST(description AS BLOB)) + LENGTH(CAST(start_timestamp AS BLOB))
+                           + LENGTH(CAST(end_timestamp AS BLOB)))
+                FROM video_segments GROUP BY session_id
+                UNION ALL
+                SELECT session_id, COUNT(*),
+                       SUM(LENGTH(CAST(title AS BLOB)) + LENGTH(CAST(summary AS BLOB))
+                           + LENGTH(CAST(detailed_summary AS BLOB)) + LENGTH(CAST(app_sites AS BLOB))
+                           + COALESCE(LENGTH(CAST(distractions AS BLOB)), 0))
+                FROM timeline_cards GROUP BY session_id
+            )
+            WHERE ? IS NULL OR session_id = ?
+            GROUP BY session_id
+            "#,
+        )
+        .bind(session_id)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
     async fn get_analyzed_video_paths(&self) -> Result<Vec<String>> {
         let rows = sqlx::query(
             r#"
@@ -680,70 +1081,1422 @@ impl DatabaseRepository for SqliteRepository {
         Ok(paths)
     }
 
-    // ========== 数据库初始化 ==========
+    // ========== 休息记录 ==========
 
-    async fn initialize_tables(&self) -> Result<()> {
-        // 创建会话表
-        sqlx::query(
+    async fn insert_break_record(&self, record: &BreakRecord) -> Result<i64> {
+        let result = sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS sessions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                start_time DATETIME NOT NULL,
-                end_time DATETIME NOT NULL,
-                title TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                video_path TEXT,
-                tags TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                device_name TEXT,
-                device_type TEXT
-            )
+            INSERT INTO break_records (reminded_at, outcome, active_minutes, resolved_at, device_name)
+            VALUES (?1, ?2, ?3, ?4, ?5)
         "#,
         )
+        .bind(&record.reminded_at)
+        .bind(&record.outcome)
+        .bind(record.active_minutes)
+        .bind(&record.resolved_at)
+        .bind(&record.device_name)
         .execute(&self.pool)
         .await?;
 
-        // 创建帧表
-        sqlx::query(
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_break_records_by_date(&self, date: &str) -> Result<Vec<BreakRecord>> {
+        let records = sqlx::query_as::<_, BreakRecord>(
             r#"
-            CREATE TABLE IF NOT EXISTS frames (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                timestamp DATETIME NOT NULL,
-                file_path TEXT NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
-            )
-        "#,
+            SELECT * FROM break_records
+            WHERE DATE(reminded_at) = ?
+            ORDER BY reminded_at
+            "#,
         )
-        .execute(&self.pool)
+        .bind(date)
+        .fetch_all(&self.pool)
         .await?;
 
-        // 创建索引
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_start_time ON sessions(start_time)")
-            .execute(&self.pool)
+        Ok(records)
+    }
+
+    // ========== 关注关键词命中 ==========
+
+    async fn replace_keyword_hits(&self, session_id: i64, hits: &[KeywordHitRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM keyword_hits WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
             .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_frames_session_id ON frames(session_id)")
-            .execute(&self.pool)
+
+        for hit in hits {
+            sqlx::query(
+                r#"
+                INSERT INTO keyword_hits (session_id, card_id, keyword, source, excerpt, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(session_id)
+            .bind(hit.card_id)
+            .bind(&hit.keyword)
+            .bind(&hit.source)
+            .bind(&hit.excerpt)
+            .bind(hit.occurred_at)
+            .bind(hit.created_at)
+            .execute(&mut *tx)
             .await?;
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_sessions_start_end ON sessions(start_time, end_time)",
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_keyword_hits_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KeywordHitRecord>> {
+        let records = sqlx::query_as::<_, KeywordHitRecord>(
+            r#"
+            SELECT * FROM keyword_hits
+            WHERE occurred_at >= ? AND occurred_at < ?
+            ORDER BY occurred_at
+            "#,
         )
-        .execute(&self.pool)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
         .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_frames_session_timestamp ON frames(session_id, timestamp)")
-            .execute(&self.pool)
-            .await?;
 
-        // 创建LLM调用记录表
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS llm_calls (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER,
-                provider TEXT NOT NULL,
-                model TEXT NOT NULL,
-                call_type TEXT NOT NULL,
-                request_headers TEXT NOT NULL,
+        Ok(records)
+    }
+
+    async fn get_keyword_hits_by_session(&self, session_id: i64) -> Result<Vec<KeywordHitRecord>> {
+        let records = sqlx::query_as::<_, KeywordHitRecord>(
+            "SELECT * FROM keyword_hits WHERE session_id = ? ORDER BY occurred_at, id",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 屏幕文字 ==========
+
+    async fn replace_screen_text(
+        &self,
+        session_id: i64,
+        records: &[ScreenTextRecord],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM screen_text WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for record in records {
+            sqlx::query(
+                r#"
+                INSERT INTO screen_text (session_id, segment_start, segment_end, text, language, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(session_id)
+            .bind(record.segment_start)
+            .bind(record.segment_end)
+            .bind(&record.text)
+            .bind(&record.language)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_screen_text_by_session(&self, session_id: i64) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(
+            "SELECT * FROM screen_text WHERE session_id = ? ORDER BY segment_start",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_screen_text_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(
+            r#"
+            SELECT * FROM screen_text
+            WHERE segment_start >= ? AND segment_start < ?
+            ORDER BY segment_start
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn search_screen_text(
+        &self,
+        pattern: &str,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScreenTextRecord>> {
+        let records = sqlx::query_as::<_, ScreenTextRecord>(
+            r#"
+            SELECT * FROM screen_text
+            WHERE text LIKE ? ESCAPE '!' AND (? IS NULL OR language = ?)
+            ORDER BY segment_start DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(pattern)
+        .bind(language)
+        .bind(language)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 报错时刻 ==========
+
+    async fn replace_error_moments(
+        &self,
+        session_id: i64,
+        records: &[ErrorMomentRecord],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM error_moments WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for record in records {
+            sqlx::query(
+                r#"
+                INSERT INTO error_moments (session_id, kind, error_text, occurred_at, created_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(session_id)
+            .bind(&record.kind)
+            .bind(&record.error_text)
+            .bind(record.occurred_at)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_error_moments_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        let records = sqlx::query_as::<_, ErrorMomentRecord>(
+            r#"
+            SELECT * FROM error_moments
+            WHERE occurred_at >= ? AND occurred_at < ?
+            ORDER BY occurred_at
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn get_error_moments_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ErrorMomentRecord>> {
+        let records = sqlx::query_as::<_, ErrorMomentRecord>(
+            "SELECT * FROM error_moments WHERE session_id = ? ORDER BY occurred_at, id",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 音频活动 ==========
+
+    async fn upsert_audio_presence(&self, record: &AudioPresenceRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audio_presence (minute, mic_active, speaker_active)
+            VALUES (?, ?, ?)
+            ON CONFLICT(minute) DO UPDATE SET
+                mic_active = MAX(mic_active, excluded.mic_active),
+                speaker_active = MAX(speaker_active, excluded.speaker_active)
+            "#,
+        )
+        .bind(record.minute)
+        .bind(record.mic_active)
+        .bind(record.speaker_active)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_audio_presence_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AudioPresenceRecord>> {
+        let records = sqlx::query_as::<_, AudioPresenceRecord>(
+            r#"
+            SELECT * FROM audio_presence
+            WHERE minute >= ? AND minute < ?
+            ORDER BY minute
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 剪贴板记录 ==========
+
+    async fn insert_clipboard_event(&self, record: &ClipboardEventRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO clipboard_events (copied_at, content_hash, char_count, line_count, kind, app_name, content)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.copied_at)
+        .bind(&record.content_hash)
+        .bind(record.char_count)
+        .bind(record.line_count)
+        .bind(&record.kind)
+        .bind(&record.app_name)
+        .bind(&record.content)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_clipboard_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        let records = sqlx::query_as::<_, ClipboardEventRecord>(
+            r#"
+            SELECT * FROM clipboard_events
+            WHERE copied_at >= ? AND copied_at < ?
+            ORDER BY copied_at
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn search_clipboard_events(
+        &self,
+        content_hash: &str,
+        pattern: &str,
+        limit: i64,
+    ) -> Result<Vec<ClipboardEventRecord>> {
+        let records = sqlx::query_as::<_, ClipboardEventRecord>(
+            r#"
+            SELECT * FROM clipboard_events
+            WHERE content_hash = ? OR content LIKE ? ESCAPE '!'
+            ORDER BY copied_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(content_hash)
+        .bind(pattern)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 专注拦截记录 ==========
+
+    async fn insert_deflected_distraction(
+        &self,
+        record: &DeflectedDistractionRecord,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO deflected_distractions (intercepted_at, app_name, action, focus_started_at, device_name)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.intercepted_at)
+        .bind(&record.app_name)
+        .bind(&record.action)
+        .bind(record.focus_started_at)
+        .bind(&record.device_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_deflected_distractions_by_date(
+        &self,
+        date: &str,
+    ) -> Result<Vec<DeflectedDistractionRecord>> {
+        let records = sqlx::query_as::<_, DeflectedDistractionRecord>(
+            r#"
+            SELECT * FROM deflected_distractions
+            WHERE DATE(intercepted_at) = ?
+            ORDER BY intercepted_at
+            "#,
+        )
+        .bind(date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 截屏暂停记录 ==========
+
+    async fn insert_capture_pause(&self, record: &CapturePauseRecord) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO capture_pauses (start_time, end_time, reason) VALUES (?, ?, ?)",
+        )
+        .bind(record.start_time)
+        .bind(record.end_time)
+        .bind(&record.reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn end_capture_pause(&self, id: i64, end_time: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE capture_pauses SET end_time = ? WHERE id = ?")
+            .bind(end_time)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn close_open_capture_pauses(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("UPDATE capture_pauses SET end_time = ? WHERE end_time IS NULL")
+            .bind(end_time)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_capture_pauses_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePauseRecord>> {
+        let records = sqlx::query_as::<_, CapturePauseRecord>(
+            r#"
+            SELECT * FROM capture_pauses
+            WHERE start_time < ? AND (end_time IS NULL OR end_time > ?)
+            ORDER BY start_time
+            "#,
+        )
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 时段意图 ==========
+
+    async fn insert_block_intent(&self, record: &BlockIntentRecord) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO block_intents (intent, start_time, end_time, device_name) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&record.intent)
+        .bind(record.start_time)
+        .bind(record.end_time)
+        .bind(&record.device_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn end_open_block_intents(&self, end_time: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("UPDATE block_intents SET end_time = ? WHERE end_time IS NULL")
+            .bind(end_time)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_block_intents_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlockIntentRecord>> {
+        let records = sqlx::query_as::<_, BlockIntentRecord>(
+            r#"
+            SELECT * FROM block_intents
+            WHERE start_time < ? AND (end_time IS NULL OR end_time > ?)
+            ORDER BY start_time
+            "#,
+        )
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn update_block_intent_alignment(
+        &self,
+        id: i64,
+        alignment: Option<f64>,
+        aligned_minutes: i64,
+        active_minutes: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE block_intents SET alignment = ?, aligned_minutes = ?, active_minutes = ? WHERE id = ?",
+        )
+        .bind(alignment)
+        .bind(aligned_minutes)
+        .bind(active_minutes)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ========== 外部活动事件 ==========
+
+    async fn insert_external_events(&self, events: &[ExternalEventRecord]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for event in events {
+            let result = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO external_events (source, category, title, start_time, end_time, metadata)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&event.source)
+            .bind(&event.category)
+            .bind(&event.title)
+            .bind(event.start_time)
+            .bind(event.end_time)
+            .bind(&event.metadata)
+            .execute(&mut *tx)
+            .await?;
+            inserted += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(inserted)
+    }
+
+    async fn get_external_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ExternalEventRecord>> {
+        let records = sqlx::query_as::<_, ExternalEventRecord>(
+            r#"
+            SELECT * FROM external_events
+            WHERE start_time < ? AND end_time > ?
+            ORDER BY start_time
+            "#,
+        )
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 时间线卡片分类 ==========
+
+    async fn update_timeline_card_classification(
+        &self,
+        card_id: i64,
+        category: &str,
+        distractions: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE timeline_cards SET updated_at = ?, category = ?, distractions = ? WHERE id = ?",
+        )
+        .bind(crate::storage::local_now())
+        .bind(category)
+        .bind(distractions)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn rename_timeline_card_subcategory(&self, from: &str, to: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE timeline_cards SET updated_at = ?, subcategory = ? WHERE subcategory = ?",
+        )
+        .bind(crate::storage::local_now())
+        .bind(to)
+        .bind(from)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ========== 卡片复核 ==========
+
+    async fn get_cards_needing_review(&self, limit: i64) -> Result<Vec<TimelineCardRecord>> {
+        let cards = sqlx::query_as::<_, TimelineCardRecord>(
+            r#"
+            SELECT * FROM timeline_cards
+            WHERE review_status = 'pending'
+            ORDER BY start_time DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn update_timeline_card_review(
+        &self,
+        card_id: i64,
+        title: &str,
+        category: &str,
+        subcategory: &str,
+        review_status: &str,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE timeline_cards SET updated_at = ?, title = ?, category = ?, subcategory = ?, review_status = ? WHERE id = ?",
+        )
+        .bind(crate::storage::local_now())
+        .bind(title)
+        .bind(category)
+        .bind(subcategory)
+        .bind(review_status)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_keyword_tags(
+        &self,
+        card_id: i64,
+        keyword_tags: Option<&str>,
+    ) -> Result<()> {
+        let result = sqlx::query("UPDATE timeline_cards SET keyword_tags = ? WHERE id = ?")
+            .bind(keyword_tags)
+            .bind(card_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_preview_clip(
+        &self,
+        card_id: i64,
+        preview_clip_path: Option<&str>,
+    ) -> Result<()> {
+        let result = sqlx::query("UPDATE timeline_cards SET preview_clip_path = ? WHERE id = ?")
+            .bind(preview_clip_path)
+            .bind(card_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_privacy_level(&self, card_id: i64, level: i64) -> Result<()> {
+        let result =
+            sqlx::query("UPDATE timeline_cards SET updated_at = ?, privacy_level = ? WHERE id = ?")
+                .bind(crate::storage::local_now())
+                .bind(level)
+                .bind(card_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_times(
+        &self,
+        card_id: i64,
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE timeline_cards SET updated_at = ?, start_time = ?, end_time = ? WHERE id = ?",
+        )
+        .bind(crate::storage::local_now())
+        .bind(start_time)
+        .bind(end_time)
+        .bind(card_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn update_video_segment_times(
+        &self,
+        segment_id: i64,
+        start_timestamp: &str,
+        end_timestamp: &str,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE video_segments SET start_timestamp = ?, end_timestamp = ? WHERE id = ?",
+        )
+        .bind(start_timestamp)
+        .bind(end_timestamp)
+        .bind(segment_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("视频分段不存在: {}", segment_id));
+        }
+        Ok(())
+    }
+
+    async fn update_timeline_card_meeting_notes(
+        &self,
+        card_id: i64,
+        meeting_notes: &str,
+    ) -> Result<()> {
+        let result = sqlx::query("UPDATE timeline_cards SET meeting_notes = ? WHERE id = ?")
+            .bind(meeting_notes)
+            .bind(card_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("卡片不存在: {}", card_id));
+        }
+        Ok(())
+    }
+
+    async fn insert_card_correction(&self, record: &CardCorrectionRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO card_corrections (
+                card_id, original_title, original_category, original_subcategory,
+                corrected_title, corrected_category, corrected_subcategory,
+                summary, app_sites, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.card_id)
+        .bind(&record.original_title)
+        .bind(&record.original_category)
+        .bind(&record.original_subcategory)
+        .bind(&record.corrected_title)
+        .bind(&record.corrected_category)
+        .bind(&record.corrected_subcategory)
+        .bind(&record.summary)
+        .bind(&record.app_sites)
+        .bind(&record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_recent_card_corrections(&self, limit: i64) -> Result<Vec<CardCorrectionRecord>> {
+        let records = sqlx::query_as::<_, CardCorrectionRecord>(
+            "SELECT * FROM card_corrections ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 文件路径迁移 ==========
+
+    async fn relocate_file_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<u64> {
+        // 按字符数截取，避免多字节路径被截断
+        let prefix_len = old_prefix.chars().count() as i64;
+        let mut updated = 0;
+        for (table, column) in [
+            ("sessions", "video_path"),
+            ("frames", "file_path"),
+            ("timeline_cards", "video_preview_path"),
+            ("timeline_cards", "preview_clip_path"),
+        ] {
+            let sql = format!(
+                "UPDATE {table} SET {column} = ? || substr({column}, ?) WHERE substr({column}, 1, ?) = ?",
+                table = table,
+                column = column
+            );
+            let result = sqlx::query(&sql)
+                .bind(new_prefix)
+                .bind(prefix_len + 1)
+                .bind(prefix_len)
+                .bind(old_prefix)
+                .execute(&self.pool)
+                .await?;
+            updated += result.rows_affected();
+        }
+
+        Ok(updated)
+    }
+
+    // ========== 个人纪录 ==========
+
+    async fn save_personal_record(&self, record: &PersonalRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO personal_records (record_key, value, date, detail)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(record_key) DO UPDATE SET
+                value = excluded.value,
+                date = excluded.date,
+                detail = excluded.detail
+            "#,
+        )
+        .bind(&record.record_key)
+        .bind(record.value)
+        .bind(&record.date)
+        .bind(&record.detail)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_personal_records(&self) -> Result<Vec<PersonalRecord>> {
+        let records = sqlx::query_as::<_, PersonalRecord>(
+            "SELECT record_key, value, date, detail FROM personal_records ORDER BY record_key",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 日期类型 ==========
+
+    async fn set_day_type(&self, record: &DayTypeRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO day_types (date, day_type, note)
+            VALUES (?, ?, ?)
+            ON CONFLICT(date) DO UPDATE SET
+                day_type = excluded.day_type,
+                note = excluded.note
+            "#,
+        )
+        .bind(&record.date)
+        .bind(&record.day_type)
+        .bind(&record.note)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear_day_type(&self, date: &str) -> Result<()> {
+        sqlx::query("DELETE FROM day_types WHERE date = ?")
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_day_types_between(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<DayTypeRecord>> {
+        let records = sqlx::query_as::<_, DayTypeRecord>(
+            "SELECT date, day_type, note FROM day_types WHERE date >= ? AND date <= ? ORDER BY date",
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 存储用量统计 ==========
+
+    async fn add_storage_usage(&self, usage: &StorageUsageRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO storage_usage (date, device_name, captured_bytes, captured_frames, encoded_bytes, encoded_videos)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(date, device_name) DO UPDATE SET
+                captured_bytes = captured_bytes + excluded.captured_bytes,
+                captured_frames = captured_frames + excluded.captured_frames,
+                encoded_bytes = encoded_bytes + excluded.encoded_bytes,
+                encoded_videos = encoded_videos + excluded.encoded_videos
+            "#,
+        )
+        .bind(&usage.date)
+        .bind(&usage.device_name)
+        .bind(usage.captured_bytes)
+        .bind(usage.captured_frames)
+        .bind(usage.encoded_bytes)
+        .bind(usage.encoded_videos)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_storage_usage(
+        &self,
+        device_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<StorageUsageRecord>> {
+        let records = sqlx::query_as::<_, StorageUsageRecord>(
+            r#"
+            SELECT date, device_name, captured_bytes, captured_frames, encoded_bytes, encoded_videos
+            FROM storage_usage
+            WHERE device_name = ? AND date >= ? AND date <= ?
+            ORDER BY date
+            "#,
+        )
+        .bind(device_name)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 会话置顶 ==========
+
+    async fn set_session_pinned(&self, session_id: i64, pinned: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE sessions SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+        Ok(())
+    }
+
+    async fn get_pinned_sessions(&self) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(
+            r#"
+            SELECT id, start_time, end_time, title, summary,
+                   video_path, tags, created_at, device_name, device_type, pinned,
+                   archived, archive_key
+            FROM sessions
+            WHERE pinned = 1
+            ORDER BY start_time
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    // ========== 会话归档 ==========
+
+    async fn mark_session_archived(&self, session_id: i64, archive_key: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE sessions SET archived = 1, archive_key = ?, video_path = NULL WHERE id = ?",
+        )
+        .bind(archive_key)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+
+        // 截图文件随归档删除，帧记录不再有意义
+        sqlx::query("DELETE FROM frames WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn mark_session_restored(&self, session_id: i64, video_path: &str) -> Result<()> {
+        let result = sqlx::query("UPDATE sessions SET archived = 0, video_path = ? WHERE id = ?")
+            .bind(video_path)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("会话不存在: {}", session_id));
+        }
+        Ok(())
+    }
+
+    // ========== 待删除会话 ==========
+
+    async fn add_pending_deletion(&self, record: &PendingDeletionRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO pending_deletions (session_id, title, deleted_at, expires_at, snapshot, staged_files)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.session_id)
+        .bind(&record.title)
+        .bind(&record.deleted_at)
+        .bind(&record.expires_at)
+        .bind(&record.snapshot)
+        .bind(&record.staged_files)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_pending_deletions(&self) -> Result<Vec<PendingDeletionRecord>> {
+        let records = sqlx::query_as::<_, PendingDeletionRecord>(
+            r#"
+            SELECT session_id, title, deleted_at, expires_at, snapshot, staged_files
+            FROM pending_deletions
+            ORDER BY deleted_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn remove_pending_deletion(&self, session_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM pending_deletions WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ========== 待分析视频认领 ==========
+
+    async fn claim_video(
+        &self,
+        video_path: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        // 清理已过期的认领，续期自己的认领，没有认领记录时再插入（主键冲突说明已被其他机器认领）
+        sqlx::query("DELETE FROM video_claims WHERE lease_until < ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE video_claims SET claimed_at = ?, lease_until = ?
+            WHERE video_path = ? AND owner = ?
+            "#,
+        )
+        .bind(now)
+        .bind(lease_until)
+        .bind(video_path)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO video_claims (video_path, owner, claimed_at, lease_until)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(video_path)
+        .bind(owner)
+        .bind(now)
+        .bind(lease_until)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release_video_claim(&self, video_path: &str, owner: &str) -> Result<()> {
+        sqlx::query("DELETE FROM video_claims WHERE video_path = ? AND owner = ?")
+            .bind(video_path)
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ========== 服务商对比 ==========
+
+    async fn insert_provider_comparison(&self, record: &ProviderComparisonRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO provider_comparisons (
+                session_id, run_id, provider, model, error_message, latency_ms,
+                input_tokens, output_tokens, cards_json, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.session_id)
+        .bind(&record.run_id)
+        .bind(&record.provider)
+        .bind(&record.model)
+        .bind(&record.error_message)
+        .bind(record.latency_ms)
+        .bind(record.input_tokens)
+        .bind(record.output_tokens)
+        .bind(&record.timeline_cards)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_provider_comparisons(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ProviderComparisonRecord>> {
+        let records = sqlx::query_as::<_, ProviderComparisonRecord>(
+            r#"
+            SELECT id, session_id, run_id, provider, model, error_message, latency_ms,
+                   input_tokens, output_tokens, cards_json, created_at
+            FROM provider_comparisons
+            WHERE session_id = ?
+            ORDER BY created_at DESC, id
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 数据外发记录 ==========
+
+    async fn insert_egress_event(&self, record: &EgressRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO egress_log (channel, destination, category, bytes, session_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record.channel)
+        .bind(&record.destination)
+        .bind(&record.category)
+        .bind(record.bytes)
+        .bind(record.session_id)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_egress_events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<EgressRecord>> {
+        let records = sqlx::query_as::<_, EgressRecord>(
+            r#"
+            SELECT * FROM egress_log
+            WHERE created_at >= ? AND created_at < ?
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 会话书签 ==========
+
+    async fn insert_session_bookmark(&self, record: &SessionBookmarkRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO session_bookmarks (session_id, video_time, note, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.session_id)
+        .bind(record.video_time)
+        .bind(&record.note)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_session_bookmarks(&self, session_id: i64) -> Result<Vec<SessionBookmarkRecord>> {
+        let records = sqlx::query_as::<_, SessionBookmarkRecord>(
+            r#"
+            SELECT * FROM session_bookmarks
+            WHERE session_id = ?
+            ORDER BY video_time, id
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn delete_session_bookmark(&self, bookmark_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM session_bookmarks WHERE id = ?")
+            .bind(bookmark_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // ========== 计时服务导出记录 ==========
+
+    async fn get_time_entry_export(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<Option<TimeEntryExportRecord>> {
+        let record = sqlx::query_as::<_, TimeEntryExportRecord>(
+            "SELECT * FROM time_entry_exports WHERE idempotency_key = ?",
+        )
+        .bind(idempotency_key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn get_time_entry_exports_by_session(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<TimeEntryExportRecord>> {
+        let records = sqlx::query_as::<_, TimeEntryExportRecord>(
+            "SELECT * FROM time_entry_exports WHERE session_id = ? ORDER BY exported_at",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn insert_time_entry_export(&self, record: &TimeEntryExportRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO time_entry_exports
+                (idempotency_key, provider, session_id, card_id, external_id, exported_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record.idempotency_key)
+        .bind(&record.provider)
+        .bind(record.session_id)
+        .bind(record.card_id)
+        .bind(&record.external_id)
+        .bind(record.exported_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ========== webhook 投递记录 ==========
+
+    async fn insert_webhook_delivery(&self, record: &WebhookDeliveryRecord) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (delivery_id, session_id, url, status, attempts, response_status, error, payload_bytes, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record.delivery_id)
+        .bind(record.session_id)
+        .bind(&record.url)
+        .bind(&record.status)
+        .bind(record.attempts)
+        .bind(record.response_status)
+        .bind(&record.error)
+        .bind(record.payload_bytes)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        session_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        let records = sqlx::query_as::<_, WebhookDeliveryRecord>(
+            r#"
+            SELECT * FROM webhook_deliveries
+            WHERE ? IS NULL OR session_id = ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(session_id)
+        .bind(session_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    // ========== 数据库初始化 ==========
+
+    async fn initialize_tables(&self) -> Result<()> {
+        // 创建会话表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                title TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                video_path TEXT,
+                tags TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                device_name TEXT,
+                device_type TEXT,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                archived INTEGER NOT NULL DEFAULT 0,
+                archive_key TEXT
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建帧表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS frames (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                timestamp DATETIME NOT NULL,
+                file_path TEXT NOT NULL,
+                metadata TEXT,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建索引
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_start_time ON sessions(start_time)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_frames_session_id ON frames(session_id)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_sessions_start_end ON sessions(start_time, end_time)",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_frames_session_timestamp ON frames(session_id, timestamp)")
+            .execute(&self.pool)
+            .await?;
+
+        // 创建LLM调用记录表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_calls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                call_type TEXT NOT NULL,
+                request_headers TEXT NOT NULL,
                 request_body TEXT NOT NULL,
                 response_headers TEXT,
                 response_body TEXT,
@@ -752,125 +2505,748 @@ impl DatabaseRepository for SqliteRepository {
                 latency_ms INTEGER,
                 token_usage TEXT,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                prompt_version TEXT,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建视频分段表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS video_segments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                llm_call_id INTEGER,
+                start_timestamp TEXT NOT NULL,
+                end_timestamp TEXT NOT NULL,
+                description TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建时间线卡片表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS timeline_cards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                llm_call_id INTEGER,
+                start_time TEXT NOT NULL,
+                end_time TEXT NOT NULL,
+                category TEXT NOT NULL,
+                subcategory TEXT NOT NULL,
+                title TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                detailed_summary TEXT NOT NULL,
+                distractions TEXT,
+                app_sites TEXT NOT NULL,
+                video_preview_path TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                confidence REAL,
+                review_status TEXT,
+                meeting_notes TEXT,
+                keyword_tags TEXT,
+                preview_clip_path TEXT,
+                privacy_level INTEGER,
+                updated_at DATETIME,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建每日总结表（缓存）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS day_summaries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date DATE NOT NULL UNIQUE,
+                summary_text TEXT NOT NULL,
+                device_stats TEXT NOT NULL,
+                parallel_work TEXT NOT NULL,
+                usage_patterns TEXT NOT NULL,
+                active_device_count INTEGER NOT NULL,
+                llm_call_id INTEGER,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                finalized INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建额外的索引
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_llm_calls_session_id ON llm_calls(session_id)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_llm_calls_created_at ON llm_calls(created_at)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_video_segments_session_id ON video_segments(session_id)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_timeline_cards_session_id ON timeline_cards(session_id)")
+            .execute(&self.pool)
+            .await?;
+
+        // 数据库迁移: 为已存在的sessions表添加设备字段
+        let check_device_name = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name='device_name'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if check_device_name == 0 {
+            info!("迁移数据库: 添加device_name和device_type字段");
+            sqlx::query("ALTER TABLE sessions ADD COLUMN device_name TEXT")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE sessions ADD COLUMN device_type TEXT")
+                .execute(&self.pool)
+                .await?;
+
+            let (device_name, device_type) = get_device_info();
+
+            sqlx::query(
+                "UPDATE sessions SET device_name = ?, device_type = ? WHERE device_name IS NULL",
+            )
+            .bind(&device_name)
+            .bind(&device_type)
+            .execute(&self.pool)
+            .await?;
+
+            info!(
+                "设备信息迁移完成: device_name={}, device_type={}",
+                device_name, device_type
+            );
+        }
+
+        // 数据库迁移: 为已存在的sessions表添加置顶字段
+        let check_pinned = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name='pinned'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if check_pinned == 0 {
+            info!("迁移数据库: 添加pinned字段");
+            sqlx::query("ALTER TABLE sessions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的sessions表添加归档字段
+        let check_archived = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name='archived'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_archived == 0 {
+            info!("迁移数据库: 添加archived和archive_key字段");
+            sqlx::query("ALTER TABLE sessions ADD COLUMN archived INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE sessions ADD COLUMN archive_key TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的llm_calls表添加提示词版本字段
+        let check_prompt_version = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('llm_calls') WHERE name='prompt_version'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_prompt_version == 0 {
+            info!("迁移数据库: 添加prompt_version字段");
+            sqlx::query("ALTER TABLE llm_calls ADD COLUMN prompt_version TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的frames表添加上下文字段
+        let check_frame_metadata = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('frames') WHERE name='metadata'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_frame_metadata == 0 {
+            info!("迁移数据库: 添加frames.metadata字段");
+            sqlx::query("ALTER TABLE frames ADD COLUMN metadata TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的day_summaries表添加锁定字段
+        let check_finalized = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('day_summaries') WHERE name='finalized'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_finalized == 0 {
+            info!("迁移数据库: 添加finalized字段");
+            sqlx::query(
+                "ALTER TABLE day_summaries ADD COLUMN finalized INTEGER NOT NULL DEFAULT 0",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加置信度和复核状态字段
+        let check_confidence = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='confidence'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_confidence == 0 {
+            info!("迁移数据库: 添加confidence和review_status字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN confidence REAL")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN review_status TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_timeline_cards_review_status ON timeline_cards(review_status)")
+            .execute(&self.pool)
+            .await?;
+
+        // 数据库迁移: 为已存在的timeline_cards表添加会议纪要字段
+        let check_meeting_notes = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='meeting_notes'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_meeting_notes == 0 {
+            info!("迁移数据库: 添加meeting_notes字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN meeting_notes TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加关注关键词字段
+        let check_keyword_tags = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='keyword_tags'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_keyword_tags == 0 {
+            info!("迁移数据库: 添加keyword_tags字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN keyword_tags TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加卡片预览短片字段
+        let check_preview_clip = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='preview_clip_path'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_preview_clip == 0 {
+            info!("迁移数据库: 添加preview_clip_path字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN preview_clip_path TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加隐私敏感度字段
+        let check_privacy_level = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='privacy_level'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_privacy_level == 0 {
+            info!("迁移数据库: 添加privacy_level字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN privacy_level INTEGER")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // 数据库迁移: 为已存在的timeline_cards表添加修改时间字段（RAG 导出据此同步卡片修改）
+        let check_updated_at = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('timeline_cards') WHERE name='updated_at'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_updated_at == 0 {
+            info!("迁移数据库: 添加updated_at字段");
+            sqlx::query("ALTER TABLE timeline_cards ADD COLUMN updated_at DATETIME")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_timeline_cards_updated_at ON timeline_cards(updated_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建卡片纠正记录表（不设外键，卡片删除后纠正示例仍然保留）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS card_corrections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                card_id INTEGER NOT NULL,
+                original_title TEXT NOT NULL,
+                original_category TEXT NOT NULL,
+                original_subcategory TEXT NOT NULL,
+                corrected_title TEXT NOT NULL,
+                corrected_category TEXT NOT NULL,
+                corrected_subcategory TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                app_sites TEXT NOT NULL,
+                created_at DATETIME NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建休息记录表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS break_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                reminded_at DATETIME NOT NULL,
+                outcome TEXT NOT NULL,
+                active_minutes INTEGER NOT NULL,
+                resolved_at DATETIME NOT NULL,
+                device_name TEXT
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_break_records_reminded_at ON break_records(reminded_at)")
+            .execute(&self.pool)
+            .await?;
+
+        // 创建关注关键词命中表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS keyword_hits (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                card_id INTEGER,
+                keyword TEXT NOT NULL,
+                source TEXT NOT NULL,
+                excerpt TEXT NOT NULL,
+                occurred_at DATETIME NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_keyword_hits_occurred_at ON keyword_hits(occurred_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_keyword_hits_session ON keyword_hits(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建屏幕文字表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS screen_text (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                segment_start DATETIME NOT NULL,
+                segment_end DATETIME NOT NULL,
+                text TEXT NOT NULL,
+                language TEXT,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_screen_text_session ON screen_text(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 数据库迁移: 为已存在的screen_text表添加语言字段
+        let check_text_language = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('screen_text') WHERE name='language'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_text_language == 0 {
+            info!("迁移数据库: 添加screen_text.language字段");
+            sqlx::query("ALTER TABLE screen_text ADD COLUMN language TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_screen_text_start ON screen_text(segment_start)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建报错时刻表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS error_moments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                error_text TEXT NOT NULL,
+                occurred_at DATETIME NOT NULL,
+                created_at DATETIME NOT NULL,
                 FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
             )
         "#,
         )
         .execute(&self.pool)
         .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_error_moments_occurred_at ON error_moments(occurred_at)",
+        )
+        .execute(&self.pool)
+        .await?;
 
-        // 创建视频分段表
+        // 创建剪贴板记录表
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS video_segments (
+            CREATE TABLE IF NOT EXISTS clipboard_events (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                llm_call_id INTEGER,
-                start_timestamp TEXT NOT NULL,
-                end_timestamp TEXT NOT NULL,
-                description TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                copied_at DATETIME NOT NULL,
+                content_hash TEXT NOT NULL,
+                char_count INTEGER NOT NULL,
+                line_count INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                app_name TEXT,
+                content TEXT
             )
         "#,
         )
         .execute(&self.pool)
         .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_events_copied_at ON clipboard_events(copied_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_events_hash ON clipboard_events(content_hash)",
+        )
+        .execute(&self.pool)
+        .await?;
 
-        // 创建时间线卡片表
+        // 创建音频活动表（每分钟一条）
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS timeline_cards (
+            CREATE TABLE IF NOT EXISTS audio_presence (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                llm_call_id INTEGER,
-                start_time TEXT NOT NULL,
-                end_time TEXT NOT NULL,
-                category TEXT NOT NULL,
-                subcategory TEXT NOT NULL,
-                title TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                detailed_summary TEXT NOT NULL,
-                distractions TEXT,
-                app_sites TEXT NOT NULL,
-                video_preview_path TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                minute DATETIME NOT NULL UNIQUE,
+                mic_active BOOLEAN NOT NULL DEFAULT 0,
+                speaker_active BOOLEAN NOT NULL DEFAULT 0
             )
         "#,
         )
         .execute(&self.pool)
         .await?;
 
-        // 创建每日总结表（缓存）
+        // 创建专注拦截记录表
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS day_summaries (
+            CREATE TABLE IF NOT EXISTS deflected_distractions (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                date DATE NOT NULL UNIQUE,
-                summary_text TEXT NOT NULL,
-                device_stats TEXT NOT NULL,
-                parallel_work TEXT NOT NULL,
-                usage_patterns TEXT NOT NULL,
-                active_device_count INTEGER NOT NULL,
-                llm_call_id INTEGER,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (llm_call_id) REFERENCES llm_calls(id) ON DELETE SET NULL
+                intercepted_at DATETIME NOT NULL,
+                app_name TEXT NOT NULL,
+                action TEXT NOT NULL,
+                focus_started_at DATETIME NOT NULL,
+                device_name TEXT
             )
         "#,
         )
         .execute(&self.pool)
         .await?;
-
-        // 创建额外的索引
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_llm_calls_session_id ON llm_calls(session_id)")
-            .execute(&self.pool)
-            .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_llm_calls_created_at ON llm_calls(created_at)")
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_deflected_distractions_intercepted_at ON deflected_distractions(intercepted_at)")
             .execute(&self.pool)
             .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_video_segments_session_id ON video_segments(session_id)")
+
+        // 创建每日存储用量表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS storage_usage (
+                date TEXT NOT NULL,
+                device_name TEXT NOT NULL,
+                captured_bytes INTEGER NOT NULL DEFAULT 0,
+                captured_frames INTEGER NOT NULL DEFAULT 0,
+                encoded_bytes INTEGER NOT NULL DEFAULT 0,
+                encoded_videos INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (date, device_name)
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建截屏暂停记录表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS capture_pauses (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME,
+                reason TEXT NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_capture_pauses_start_time ON capture_pauses(start_time)")
             .execute(&self.pool)
             .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_timeline_cards_session_id ON timeline_cards(session_id)")
+
+        // 创建时段意图表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS block_intents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                intent TEXT NOT NULL,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME,
+                device_name TEXT,
+                alignment REAL,
+                aligned_minutes INTEGER,
+                active_minutes INTEGER
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_block_intents_start_time ON block_intents(start_time)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建外部活动事件表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS external_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source TEXT NOT NULL,
+                category TEXT NOT NULL,
+                title TEXT NOT NULL,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                metadata TEXT,
+                UNIQUE (source, start_time, title)
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_external_events_start_time ON external_events(start_time)")
             .execute(&self.pool)
             .await?;
 
-        // 数据库迁移: 为已存在的sessions表添加设备字段
-        let check_device_name = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name='device_name'",
+        // 创建个人纪录表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS personal_records (
+                record_key TEXT PRIMARY KEY,
+                value INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                detail TEXT
+            )
+        "#,
         )
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        if check_device_name == 0 {
-            info!("迁移数据库: 添加device_name和device_type字段");
-            sqlx::query("ALTER TABLE sessions ADD COLUMN device_name TEXT")
-                .execute(&self.pool)
-                .await?;
-            sqlx::query("ALTER TABLE sessions ADD COLUMN device_type TEXT")
-                .execute(&self.pool)
-                .await?;
+        // 创建日期类型表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS day_types (
+                date TEXT PRIMARY KEY,
+                day_type TEXT NOT NULL,
+                note TEXT
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
-            let (device_name, device_type) = get_device_info();
+        // 创建待删除会话表（撤销删除用）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_deletions (
+                session_id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                deleted_at DATETIME NOT NULL,
+                expires_at DATETIME NOT NULL,
+                snapshot TEXT NOT NULL,
+                staged_files TEXT NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建待分析视频认领表（多台机器分析同一共享存储时避免重复分析）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS video_claims (
+                video_path TEXT PRIMARY KEY,
+                owner TEXT NOT NULL,
+                claimed_at DATETIME NOT NULL,
+                lease_until DATETIME NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建服务商对比结果表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS provider_comparisons (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                run_id TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                error_message TEXT,
+                latency_ms INTEGER NOT NULL,
+                input_tokens INTEGER,
+                output_tokens INTEGER,
+                cards_json TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_provider_comparisons_session ON provider_comparisons(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
 
+        // 数据库迁移: 卡片 JSON 列原名 timeline_cards，和表名相同，MariaDB 加表前缀时会被一起改名
+        let check_old_cards = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('provider_comparisons') WHERE name='timeline_cards'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if check_old_cards > 0 {
+            info!("迁移数据库: provider_comparisons 的 timeline_cards 字段改名为 cards_json");
             sqlx::query(
-                "UPDATE sessions SET device_name = ?, device_type = ? WHERE device_name IS NULL",
+                "ALTER TABLE provider_comparisons RENAME COLUMN timeline_cards TO cards_json",
             )
-            .bind(&device_name)
-            .bind(&device_type)
             .execute(&self.pool)
             .await?;
-
-            info!(
-                "设备信息迁移完成: device_name={}, device_type={}",
-                device_name, device_type
-            );
         }
 
+        // 创建数据外发记录表（不随会话删除，便于事后核实）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS egress_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                category TEXT NOT NULL,
+                bytes INTEGER NOT NULL,
+                session_id INTEGER,
+                created_at DATETIME NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_egress_log_created_at ON egress_log(created_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建会话书签表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_bookmarks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                video_time REAL NOT NULL,
+                note TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_session_bookmarks_session ON session_bookmarks(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建计时服务导出记录表（不随会话删除，避免重新导出产生重复记录）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS time_entry_exports (
+                idempotency_key TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                session_id INTEGER NOT NULL,
+                card_id INTEGER,
+                external_id TEXT NOT NULL,
+                exported_at DATETIME NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // 创建 webhook 投递记录表（不随会话删除，便于核对推送历史）
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                delivery_id TEXT NOT NULL,
+                session_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                response_status INTEGER,
+                error TEXT,
+                payload_bytes INTEGER NOT NULL,
+                created_at DATETIME NOT NULL
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_session ON webhook_deliveries(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
         info!("SQLite 数据库表初始化完成");
         Ok(())
     }
@@ -924,6 +3300,16 @@ impl DatabaseRepository for SqliteRepository {
         Ok(())
     }
 
+    async fn set_day_summary_finalized(&self, date: &str, finalized: bool) -> Result<()> {
+        sqlx::query("UPDATE day_summaries SET finalized = ? WHERE date = ?")
+            .bind(finalized)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     fn db_type(&self) -> &str {
         "sqlite"
     }
@@ -0,0 +1,243 @@
+// S3 兼容对象存储客户端 - 用于冷存储归档（AWS S3、MinIO、Cloudflare R2 等）
+//
+// 使用 AWS Signature V4（HMAC-SHA256）签名，请求体不参与签名（UNSIGNED-PAYLOAD），
+// 支持虚拟主机风格（bucket.endpoint/key）和路径风格（endpoint/bucket/key）
+
+use crate::models::S3Config;
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// S3 客户端
+pub struct S3Client {
+    client: Client,
+    config: S3Config,
+}
+
+impl S3Client {
+    pub fn new(client: Client, config: S3Config) -> Result<Self> {
+        if config.bucket.trim().is_empty() {
+            return Err(anyhow!("S3 Bucket 不能为空"));
+        }
+        if config.access_key_id.trim().is_empty() || config.secret_access_key.trim().is_empty() {
+            return Err(anyhow!("S3 访问密钥不能为空"));
+        }
+        endpoint_url(&config.endpoint)?;
+        Ok(Self { client, config })
+    }
+
+    /// 服务地址
+    pub fn endpoint(&self) -> &str {
+        &self.config.endpoint
+    }
+
+    /// 加上配置的前缀得到完整对象 Key
+    pub fn full_key(&self, key: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", prefix, key)
+        }
+    }
+
+    /// 上传对象
+    pub async fn put_object(&self, key: &str, content: Vec<u8>) -> Result<()> {
+        let response = self
+            .signed_request(Method::PUT, key)?
+            .body(content)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("S3 上传失败: {}", error_text));
+        }
+
+        info!("已上传 S3 对象: {}/{}", self.config.bucket, key);
+        Ok(())
+    }
+
+    /// 下载对象
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self.signed_request(Method::GET, key)?.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("S3 下载失败: {}", error_text));
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// 对象是否存在
+    pub async fn object_exists(&self, key: &str) -> Result<bool> {
+        let response = self.signed_request(Method::HEAD, key)?.send().await?;
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status => Err(anyhow!("查询 S3 对象失败: {}", status)),
+        }
+    }
+
+    /// 删除对象
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        let response = self.signed_request(Method::DELETE, key)?.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("删除 S3 对象失败: {}", error_text));
+        }
+
+        info!("已删除 S3 对象: {}/{}", self.config.bucket, key);
+        Ok(())
+    }
+
+    /// 构建带签名头的请求
+    fn signed_request(&self, method: Method, key: &str) -> Result<reqwest::RequestBuilder> {
+        let endpoint = endpoint_url(&self.config.endpoint)?;
+        let mut host = endpoint
+            .host_str()
+            .ok_or_else(|| anyhow!("S3 Endpoint 缺少主机名"))?
+            .to_string();
+        if let Some(port) = endpoint.port() {
+            host = format!("{}:{}", host, port);
+        }
+
+        let key_path = encode_path(key);
+        let (host, path) = if self.config.path_style {
+            (host, format!("/{}/{}", self.config.bucket, key_path))
+        } else {
+            (
+                format!("{}.{}", self.config.bucket, host),
+                format!("/{}", key_path),
+            )
+        };
+        let url = format!("{}://{}{}", endpoint.scheme(), host, path);
+
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            path,
+            host,
+            UNSIGNED_PAYLOAD,
+            amz_date,
+            SIGNED_HEADERS,
+            UNSIGNED_PAYLOAD
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key = signing_key(
+            &self.config.secret_access_key,
+            &date,
+            &self.config.region,
+            "s3",
+        );
+        let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id, scope, SIGNED_HEADERS, signature
+        );
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", amz_date)
+            .header("Authorization", authorization))
+    }
+}
+
+/// 解析 Endpoint（未写协议时默认 https）
+fn endpoint_url(endpoint: &str) -> Result<Url> {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.is_empty() {
+        return Err(anyhow!("S3 Endpoint 不能为空"));
+    }
+    let with_scheme = if endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("https://{}", endpoint)
+    };
+    Url::parse(&with_scheme).map_err(|e| anyhow!("S3 Endpoint 格式错误: {}", e))
+}
+
+/// 按 S3 规则对 Key 的每一段做 URI 编码（保留 '/'），WebDAV 地址也使用同样的编码
+pub(crate) fn encode_path(key: &str) -> String {
+    key.split('/')
+        .map(|segment| {
+            segment
+                .bytes()
+                .map(|b| match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                        (b as char).to_string()
+                    }
+                    _ => format!("%{:02X}", b),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 派生签名密钥
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, service.as_bytes());
+    hmac_sha256(&k_service, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key_derivation() {
+        // AWS 文档中的签名密钥派生示例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_encode_path_keeps_slashes() {
+        assert_eq!(
+            encode_path("archive/2025-01-02/会话 1.mp4"),
+            "archive/2025-01-02/%E4%BC%9A%E8%AF%9D%201.mp4"
+        );
+    }
+}
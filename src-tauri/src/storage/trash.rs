@@ -0,0 +1,351 @@
+// 会话回收站 - 删除会话后保留一段可撤销的时间
+//
+// 删除时先把会话及其关联记录（帧、LLM 调用、视频分段、时间线卡片、书签、关键词命中、屏幕文字、
+// 报错时刻和计时服务导出记录）的快照写入 pending_deletions 表，再把视频和截图移到数据目录下的
+// trash/<session_id>/，最后删除数据库记录。撤销窗口内在一个事务中按原 ID 写回记录
+// （其他地方对会话和卡片 ID 的引用保持有效），再把文件移回原位；窗口结束后由清理任务删除暂存文件。
+// 快照先于文件移动落库，中途崩溃也能找回文件
+
+use super::{Database, PendingDeletionRecord, Session, SessionSnapshot};
+use crate::video::VideoMetadata;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 撤销窗口（分钟）
+pub const UNDO_WINDOW_MINUTES: i64 = 5;
+
+/// 暂存到回收站的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedFile {
+    pub original: String,
+    pub staged: String,
+}
+
+/// 删除会话：记录快照、暂存文件，再删除数据库记录
+pub async fn stage_session_deletion(
+    db: &Database,
+    trash_dir: &Path,
+    session_id: i64,
+) -> Result<PendingDeletionRecord> {
+    let snapshot = SessionSnapshot {
+        session: db.get_session(session_id).await?,
+        frames: db.get_frames_by_session(session_id).await?,
+        llm_calls: db.get_llm_calls_by_session(session_id).await?,
+        segments: db.get_video_segments_by_session(session_id).await?,
+        cards: db.get_timeline_cards_by_session(session_id).await?,
+        bookmarks: db.get_session_bookmarks(session_id).await?,
+        keyword_hits: db.get_keyword_hits_by_session(session_id).await?,
+        screen_text: db.get_screen_text_by_session(session_id).await?,
+        error_moments: db.get_error_moments_by_session(session_id).await?,
+        time_entry_exports: db.get_time_entry_exports_by_session(session_id).await?,
+    };
+
+    let session_trash = trash_dir.join(session_id.to_string());
+    let staged_files = plan_staged_files(&snapshot, &session_trash);
+
+    let deleted_at = crate::storage::local_now();
+    let record = PendingDeletionRecord {
+        session_id,
+        title: snapshot.session.title.clone(),
+        deleted_at,
+        expires_at: deleted_at + Duration::minutes(UNDO_WINDOW_MINUTES),
+        snapshot: serde_json::to_string(&snapshot)?,
+        staged_files: serde_json::to_string(&staged_files)?,
+    };
+    db.add_pending_deletion(&record).await?;
+
+    tokio::fs::create_dir_all(&session_trash).await?;
+    for file in &staged_files {
+        if let Err(e) = move_file(Path::new(&file.original), Path::new(&file.staged)).await {
+            warn!("暂存文件失败 {}: {}", file.original, e);
+        }
+    }
+
+    if let Err(e) = db.delete_session(session_id).await {
+        // 数据库记录删不掉时把文件放回去，保持会话完整
+        restore_files(&staged_files).await;
+        let _ = tokio::fs::remove_dir_all(&session_trash).await;
+        db.remove_pending_deletion(session_id).await?;
+        return Err(e);
+    }
+
+    info!(
+        "会话 {} 已移入回收站，{} 分钟内可撤销",
+        session_id, UNDO_WINDOW_MINUTES
+    );
+    Ok(record)
+}
+
+/// 撤销删除：按原 ID 写回记录并移回文件，返回会话 ID
+pub async fn undo_session_deletion(
+    db: &Database,
+    trash_dir: &Path,
+    session_id: i64,
+) -> Result<i64> {
+    let record = db
+        .get_pending_deletions()
+        .await?
+        .into_iter()
+        .find(|r| r.session_id == session_id)
+        .ok_or_else(|| anyhow!("会话 {} 不在回收站中或撤销时间已过", session_id))?;
+
+    let snapshot: SessionSnapshot = serde_json::from_str(&record.snapshot)?;
+    let staged_files: Vec<StagedFile> = serde_json::from_str(&record.staged_files)?;
+
+    // 记录在一个事务中按原 ID 写回，失败时文件仍留在回收站，可以再次撤销
+    db.restore_session_snapshot(&snapshot).await?;
+    restore_files(&staged_files).await;
+
+    db.remove_pending_deletion(session_id).await?;
+    let _ = tokio::fs::remove_dir_all(trash_dir.join(session_id.to_string())).await;
+
+    info!("已撤销删除会话 {}", session_id);
+    Ok(session_id)
+}
+
+/// 彻底删除已过撤销窗口的会话，返回这些会话（供调用方清理归档和远程副本）
+pub async fn purge_expired(
+    db: &Database,
+    trash_dir: &Path,
+    now: DateTime<Utc>,
+) -> Result<Vec<Session>> {
+    let mut purged = Vec::new();
+    for record in db.get_pending_deletions().await? {
+        if record.expires_at > now {
+            continue;
+        }
+
+        let session_trash = trash_dir.join(record.session_id.to_string());
+        if let Err(e) = tokio::fs::remove_dir_all(&session_trash).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("删除回收站文件失败 {:?}: {}", session_trash, e);
+                continue;
+            }
+        }
+        db.remove_pending_deletion(record.session_id).await?;
+
+        match serde_json::from_str::<SessionSnapshot>(&record.snapshot) {
+            Ok(snapshot) => purged.push(snapshot.session),
+            Err(e) => warn!("解析会话快照失败 {}: {}", record.session_id, e),
+        }
+    }
+
+    if !purged.is_empty() {
+        info!("已彻底删除 {} 个回收站中的会话", purged.len());
+    }
+    Ok(purged)
+}
+
+/// 规划需要暂存的文件（视频、视频元数据和截图），文件名加序号避免重名
+fn plan_staged_files(snapshot: &SessionSnapshot, session_trash: &Path) -> Vec<StagedFile> {
+    let mut originals: Vec<PathBuf> = Vec::new();
+    if let Some(video_path) = &snapshot.session.video_path {
+        let video_path = PathBuf::from(video_path);
+        originals.push(VideoMetadata::sidecar_path(&video_path));
+        originals.push(video_path);
+    }
+    originals.extend(snapshot.frames.iter().map(|f| PathBuf::from(&f.file_path)));
+
+    originals
+        .into_iter()
+        .enumerate()
+        .map(|(index, original)| {
+            let name = original
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            StagedFile {
+                staged: session_trash
+                    .join(format!("{}_{}", index, name))
+                    .to_string_lossy()
+                    .to_string(),
+                original: original.to_string_lossy().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// 把暂存的文件移回原位
+async fn restore_files(staged_files: &[StagedFile]) {
+    for file in staged_files {
+        if let Err(e) = move_file(Path::new(&file.staged), Path::new(&file.original)).await {
+            warn!("恢复文件失败 {}: {}", file.original, e);
+        }
+    }
+}
+
+/// 移动文件，跨磁盘时退回复制后删除；源文件不存在时忽略
+async fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if !tokio::fs::try_exists(from).await.unwrap_or(false) {
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if tokio::fs::rename(from, to).await.is_err() {
+        tokio::fs::copy(from, to).await?;
+        tokio::fs::remove_file(from).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Frame;
+
+    #[test]
+    fn test_snapshot_round_trips_through_json() {
+        let now = crate::storage::local_now();
+        let snapshot = SessionSnapshot {
+            session: Session {
+                id: Some(3),
+                start_time: now,
+                end_time: now,
+                title: "写代码".to_string(),
+                summary: String::new(),
+                video_path: Some("/videos/a.mp4".to_string()),
+                tags: "[]".to_string(),
+                created_at: Some(now),
+                device_name: None,
+                device_type: None,
+                pinned: true,
+                archived: false,
+                archive_key: None,
+            },
+            frames: vec![Frame {
+                id: Some(1),
+                session_id: 3,
+                timestamp: now,
+                file_path: "/frames/1.jpg".to_string(),
+                metadata: None,
+            }],
+            llm_calls: Vec::new(),
+            segments: Vec::new(),
+            cards: Vec::new(),
+            bookmarks: Vec::new(),
+            keyword_hits: Vec::new(),
+            screen_text: Vec::new(),
+            error_moments: Vec::new(),
+            time_entry_exports: Vec::new(),
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let parsed: SessionSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.session.start_time.timestamp(), now.timestamp());
+        assert!(parsed.session.pinned);
+
+        // 旧版本的快照没有书签等字段
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy.as_object_mut().unwrap().remove("bookmarks");
+        legacy.as_object_mut().unwrap().remove("time_entry_exports");
+        let legacy: SessionSnapshot = serde_json::from_value(legacy).unwrap();
+        assert!(legacy.bookmarks.is_empty() && legacy.time_entry_exports.is_empty());
+
+        let staged = plan_staged_files(&parsed, Path::new("/data/trash/3"));
+        assert_eq!(staged.len(), 3);
+        assert_eq!(staged[1].original, "/videos/a.mp4");
+        assert!(staged[2].staged.ends_with("2_1.jpg"));
+    }
+    #[tokio::test]
+    async fn test_undo_restores_original_ids() {
+        use crate::storage::{KeywordHitRecord, SessionBookmarkRecord, TimelineCardRecord};
+
+        let root = tempfile::tempdir().unwrap();
+        let db = Database::new_sqlite(&root.path().join("data.db").to_string_lossy())
+            .await
+            .unwrap();
+        let now = crate::storage::local_now();
+        let session_id = db
+            .insert_session(&Session {
+                id: None,
+                start_time: now,
+                end_time: now,
+                title: "写代码".to_string(),
+                summary: String::new(),
+                video_path: None,
+                tags: "[]".to_string(),
+                created_at: None,
+                device_name: None,
+                device_type: None,
+                pinned: true,
+                archived: false,
+                archive_key: None,
+            })
+            .await
+            .unwrap();
+        db.set_session_pinned(session_id, true).await.unwrap();
+        let card_id = db
+            .insert_timeline_card(&TimelineCardRecord {
+                id: None,
+                session_id,
+                llm_call_id: None,
+                start_time: now.to_rfc3339(),
+                end_time: now.to_rfc3339(),
+                category: "work".to_string(),
+                subcategory: String::new(),
+                title: "写代码".to_string(),
+                summary: String::new(),
+                detailed_summary: String::new(),
+                distractions: None,
+                app_sites: "{}".to_string(),
+                video_preview_path: None,
+                created_at: now,
+                confidence: None,
+                review_status: None,
+                meeting_notes: None,
+                keyword_tags: None,
+                preview_clip_path: None,
+                privacy_level: None,
+            })
+            .await
+            .unwrap();
+        db.insert_session_bookmark(&SessionBookmarkRecord {
+            id: None,
+            session_id,
+            video_time: 3.0,
+            note: "这里".to_string(),
+            created_at: now,
+        })
+        .await
+        .unwrap();
+        db.replace_keyword_hits(
+            session_id,
+            &[KeywordHitRecord {
+                id: None,
+                session_id,
+                card_id: Some(card_id),
+                keyword: "rust".to_string(),
+                source: "card".to_string(),
+                excerpt: "rust".to_string(),
+                occurred_at: now,
+                created_at: now,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let trash_dir = root.path().join("trash");
+        stage_session_deletion(&db, &trash_dir, session_id)
+            .await
+            .unwrap();
+        assert!(db.get_session(session_id).await.is_err());
+
+        assert_eq!(
+            undo_session_deletion(&db, &trash_dir, session_id)
+                .await
+                .unwrap(),
+            session_id
+        );
+        assert!(db.get_session(session_id).await.unwrap().pinned);
+        let cards = db.get_timeline_cards_by_session(session_id).await.unwrap();
+        assert_eq!(cards[0].id, Some(card_id));
+        assert_eq!(db.get_session_bookmarks(session_id).await.unwrap().len(), 1);
+        let hits = db.get_keyword_hits_by_session(session_id).await.unwrap();
+        assert_eq!(hits[0].card_id, Some(card_id));
+        assert!(db.get_pending_deletions().await.unwrap().is_empty());
+    }
+}
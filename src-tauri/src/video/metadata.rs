@@ -0,0 +1,273 @@
+// 视频元数据 - 记录生成会话视频时使用的倍速/帧率/抽帧间隔
+//
+// 视频时间与真实时间的换算依赖生成视频时的参数，修改设置后旧视频仍按旧参数生成，
+// 因此在视频旁边写一个同名的 sidecar JSON（如 xxx.mp4.meta.json），
+// 换算时优先读取它，没有元数据的旧视频再回退到当前设置。
+// 智能变速生成的视频中每帧展示时长不同，sidecar 中额外记录按帧顺序的展示时长（speed_runs），
+// 回放和模型返回的视频时间都按它分段换算；发给模型的倍速和判断是否为视频时间使用整段视频的平均倍速
+
+use super::VideoConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// sidecar 文件后缀
+const SIDECAR_SUFFIX: &str = ".meta.json";
+
+/// 智能变速视频中展示时长相同的一段连续帧
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedRun {
+    /// 帧数
+    pub frames: usize,
+    /// 每帧在加速前展示的秒数
+    pub display_secs: f64,
+}
+
+/// 智能变速的时间映射（按帧顺序的展示时长）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedMap {
+    /// 相邻两帧之间的真实秒数
+    pub frame_interval_secs: f64,
+    pub runs: Vec<SpeedRun>,
+}
+
+impl SpeedMap {
+    /// 加速前的展示时长合计（秒）
+    pub fn display_duration_secs(&self) -> f64 {
+        self.runs
+            .iter()
+            .map(|run| run.frames as f64 * run.display_secs)
+            .sum()
+    }
+
+    /// 加速前的展示位置 → 距视频开始的真实秒数
+    pub fn display_to_real(&self, display_secs: f64) -> f64 {
+        let (mut display, mut real) = (0.0, 0.0);
+        for run in &self.runs {
+            let run_display = run.frames as f64 * run.display_secs;
+            if display_secs <= display + run_display {
+                let frames = (display_secs - display).max(0.0) / run.display_secs.max(0.001);
+                return real + frames * self.frame_interval_secs;
+            }
+            display += run_display;
+            real += run.frames as f64 * self.frame_interval_secs;
+        }
+        real
+    }
+
+    /// 距视频开始的真实秒数 → 加速前的展示位置
+    pub fn real_to_display(&self, real_secs: f64) -> f64 {
+        let (mut display, mut real) = (0.0, 0.0);
+        for run in &self.runs {
+            let run_real = run.frames as f64 * self.frame_interval_secs;
+            if real_secs <= real + run_real {
+                let frames = (real_secs - real).max(0.0) / self.frame_interval_secs.max(0.001);
+                return display + frames * run.display_secs;
+            }
+            display += run.frames as f64 * run.display_secs;
+            real += run_real;
+        }
+        display
+    }
+}
+
+/// 会话视频元数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    /// 输出帧率
+    pub fps: u32,
+    /// 播放速度倍数
+    pub speed_multiplier: f32,
+    /// 相邻两帧之间的真实秒数（抽帧间隔 × 截屏间隔）
+    pub frame_interval_secs: f64,
+    /// 每帧在加速前展示的秒数
+    pub frame_display_secs: f64,
+    /// 视频包含的帧数
+    pub frame_count: usize,
+    /// 智能变速时按帧顺序的展示时长，为空表示每帧都展示 frame_display_secs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speed_runs: Vec<SpeedRun>,
+    /// 录制视频的设备（在其他机器上分析时用于标记会话来源）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_type: Option<String>,
+}
+
+impl VideoMetadata {
+    /// 根据视频配置创建元数据（每帧加速前展示 1 秒，与 plan_frames 一致）
+    pub fn new(config: &VideoConfig, frame_interval_secs: f64, frame_count: usize) -> Self {
+        let (device_name, device_type) = crate::storage::get_device_info();
+        Self {
+            fps: config.fps,
+            speed_multiplier: config.speed_multiplier,
+            frame_interval_secs,
+            frame_display_secs: 1.0,
+            frame_count,
+            speed_runs: Vec::new(),
+            device_name: Some(device_name),
+            device_type: Some(device_type),
+        }
+    }
+
+    /// 记录智能变速生成的每帧展示时长
+    pub fn with_speed_runs(mut self, speed_runs: Vec<SpeedRun>) -> Self {
+        self.speed_runs = speed_runs;
+        self
+    }
+
+    /// 智能变速的时间映射，固定倍速的视频返回 None
+    pub fn speed_map(&self) -> Option<SpeedMap> {
+        (!self.speed_runs.is_empty()).then(|| SpeedMap {
+            frame_interval_secs: self.frame_interval_secs,
+            runs: self.speed_runs.clone(),
+        })
+    }
+
+    /// 视频中 1 秒对应的真实秒数（智能变速时为整段视频的平均值）
+    pub fn real_seconds_per_video_second(&self) -> f64 {
+        if !self.speed_runs.is_empty() {
+            let video_secs = self.video_duration_secs();
+            if video_secs > 0.0 {
+                return self.frame_count as f64 * self.frame_interval_secs / video_secs;
+            }
+        }
+        self.speed_multiplier.max(0.01) as f64 * self.frame_interval_secs
+            / self.frame_display_secs.max(0.01)
+    }
+
+    /// 视频播放位置（秒）→ 距视频开始的真实秒数（智能变速时按变速映射分段换算）
+    pub fn video_to_real_secs(&self, video_secs: f64) -> f64 {
+        let video_secs = video_secs.max(0.0);
+        match self.speed_map() {
+            Some(map) => map.display_to_real(video_secs * self.speed_multiplier.max(0.01) as f64),
+            None => video_secs * self.real_seconds_per_video_second(),
+        }
+    }
+
+    /// 按每帧展示 frame_display_secs 折算的平均倍速（提示词中的视频倍速）
+    pub fn effective_speed_multiplier(&self) -> f32 {
+        (self.real_seconds_per_video_second() * self.frame_display_secs
+            / self.frame_interval_secs.max(0.001)) as f32
+    }
+
+    /// 视频实际播放时长（秒）
+    pub fn video_duration_secs(&self) -> f64 {
+        let display_secs = match self.speed_map() {
+            Some(map) => map.display_duration_secs(),
+            None => self.frame_count as f64 * self.frame_display_secs,
+        };
+        display_secs / self.speed_multiplier.max(0.01) as f64
+    }
+
+    /// 推断 LLM 返回的相对时间的缩放比例
+    ///
+    /// 提示词要求模型返回会话相对时间，但模型有时直接给出视频播放位置：
+    /// 当会话明显长于视频、且所有时间戳都落在视频时长以内时，按视频时间换算，否则视为会话时间
+    pub fn timestamp_scale(&self, max_relative_secs: f64, session_secs: f64) -> f64 {
+        let video_secs = self.video_duration_secs();
+        let looks_like_video_time = video_secs > 0.0
+            && session_secs > video_secs * 2.0
+            && max_relative_secs > 0.0
+            && max_relative_secs <= video_secs + self.frame_display_secs;
+        if looks_like_video_time {
+            self.real_seconds_per_video_second()
+        } else {
+            1.0
+        }
+    }
+
+    /// 视频对应的 sidecar 文件路径
+    pub fn sidecar_path(video_path: &Path) -> PathBuf {
+        let mut path = video_path.as_os_str().to_owned();
+        path.push(SIDECAR_SUFFIX);
+        PathBuf::from(path)
+    }
+
+    /// 写入视频旁的 sidecar 文件
+    pub async fn save(&self, video_path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(Self::sidecar_path(video_path), content).await?;
+        Ok(())
+    }
+
+    /// 写入 sidecar，失败只记录警告（元数据缺失时换算会回退到当前设置）
+    pub async fn save_or_warn(&self, video_path: &Path) {
+        if let Err(e) = self.save(video_path).await {
+            warn!("保存视频元数据失败 {:?}: {}", video_path, e);
+        }
+    }
+
+    /// 读取视频的元数据，旧视频没有 sidecar 时返回 None
+    pub async fn load(video_path: &Path) -> Option<Self> {
+        let content = tokio::fs::read_to_string(Self::sidecar_path(video_path))
+            .await
+            .ok()?;
+        match serde_json::from_str(&content) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                debug!("解析视频元数据失败 {:?}: {}", video_path, e);
+                None
+            }
+        }
+    }
+
+    /// 删除视频的 sidecar 文件（视频删除时调用）
+    pub async fn remove(video_path: &Path) {
+        let _ = tokio::fs::remove_file(Self::sidecar_path(video_path)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_scale_detects_video_time() {
+        // 15 分钟会话、每 5 秒一帧、8 倍速：视频约 22.5 秒，视频 1 秒 = 真实 40 秒
+        let metadata = VideoMetadata::new(&VideoConfig::default(), 5.0, 180);
+        assert_eq!(metadata.video_duration_secs(), 22.5);
+        assert_eq!(metadata.real_seconds_per_video_second(), 40.0);
+
+        assert_eq!(metadata.timestamp_scale(20.0, 900.0), 40.0);
+        assert_eq!(metadata.timestamp_scale(600.0, 900.0), 1.0);
+    }
+
+    #[test]
+    fn test_speed_map_converts_piecewise() {
+        // 前 10 帧静止（每帧 0.25 秒），后 10 帧有变化（每帧 1 秒），每帧间隔 5 秒、8 倍速
+        let metadata = VideoMetadata::new(&VideoConfig::default(), 5.0, 20).with_speed_runs(vec![
+            SpeedRun {
+                frames: 10,
+                display_secs: 0.25,
+            },
+            SpeedRun {
+                frames: 10,
+                display_secs: 1.0,
+            },
+        ]);
+        let map = metadata.speed_map().unwrap();
+        assert_eq!(map.display_duration_secs(), 12.5);
+        assert_eq!(metadata.video_duration_secs(), 12.5 / 8.0);
+
+        assert_eq!(map.display_to_real(2.5), 50.0);
+        assert_eq!(map.display_to_real(3.5), 55.0);
+        assert_eq!(map.real_to_display(55.0), 3.5);
+        assert_eq!(map.real_to_display(25.0), 1.25);
+        assert_eq!(
+            metadata.real_seconds_per_video_second(),
+            100.0 / (12.5 / 8.0)
+        );
+        // 视频第 1 秒是加速前的第 8 秒：前 10 帧占 2.5 秒，之后每秒一帧
+        assert_eq!(metadata.video_to_real_secs(1.0), 50.0 + 5.5 * 5.0);
+    }
+
+    #[test]
+    fn test_sidecar_path_appends_suffix() {
+        assert_eq!(
+            VideoMetadata::sidecar_path(Path::new("/videos/a.mp4")),
+            PathBuf::from("/videos/a.mp4.meta.json")
+        );
+    }
+}
@@ -1,9 +1,13 @@
 // 视频处理模块 - 负责将截图序列生成视频
 
 pub mod ffmpeg_helper;
+pub mod metadata;
 pub mod processor;
 
-pub use processor::{filter_frames_by_interval, VideoConfig, VideoFormat, VideoProcessor};
+pub use metadata::{SpeedMap, SpeedRun, VideoMetadata};
+pub use processor::{
+    filter_frames_by_interval, ScreenRegion, VideoConfig, VideoFormat, VideoLayout, VideoProcessor,
+};
 
 use anyhow::Result;
 use std::path::{Path, PathBuf};
@@ -55,6 +59,68 @@ pub struct VideoResult {
     pub fps: f32,
     /// 生成耗时（毫秒）
     pub processing_time_ms: u64,
+    /// 智能变速时按帧顺序的展示时长（固定倍速时为空）
+    #[serde(default)]
+    pub speed_runs: Vec<SpeedRun>,
+}
+
+/// 需要模糊的画面区域（坐标和宽高均为占画面的比例，取值 0~1）
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BlurRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BlurRegion {
+    /// 裁剪到画面范围内，过小的区域视为无效
+    pub fn clamped(&self) -> Option<Self> {
+        let x = self.x.clamp(0.0, 1.0);
+        let y = self.y.clamp(0.0, 1.0);
+        let width = self.width.min(1.0 - x);
+        let height = self.height.min(1.0 - y);
+        (width >= 0.01 && height >= 0.01).then_some(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// 生成模糊区域的 filter_complex：把每个区域裁剪出来高斯模糊后叠加回原位置
+pub fn blur_filter(regions: &[BlurRegion]) -> String {
+    let regions: Vec<BlurRegion> = regions.iter().filter_map(BlurRegion::clamped).collect();
+    if regions.is_empty() {
+        return "[0:v]null[out]".to_string();
+    }
+
+    let sources: String = (0..regions.len()).map(|i| format!("[s{}]", i)).collect();
+    let mut filters = vec![format!("[0:v]split={}[v0]{}", regions.len() + 1, sources)];
+    for (i, region) in regions.iter().enumerate() {
+        filters.push(format!(
+            "[s{i}]crop=iw*{w:.4}:ih*{h:.4}:iw*{x:.4}:ih*{y:.4},gblur=sigma=30[b{i}]",
+            i = i,
+            w = region.width,
+            h = region.height,
+            x = region.x,
+            y = region.y
+        ));
+        let output = if i + 1 == regions.len() {
+            "[out]".to_string()
+        } else {
+            format!("[v{}]", i + 1)
+        };
+        filters.push(format!(
+            "[v{i}][b{i}]overlay=main_w*{x:.4}:main_h*{y:.4}{output}",
+            i = i,
+            x = region.x,
+            y = region.y,
+            output = output
+        ));
+    }
+    filters.join(";")
 }
 
 /// 视频工具函数
@@ -187,6 +253,315 @@ impl VideoUtils {
         Ok(())
     }
 
+    /// 按原始分辨率抽取单帧（用于识别画面中的文字）
+    pub async fn extract_frame(
+        video_path: &Path,
+        output_path: &Path,
+        time_offset: f32,
+    ) -> Result<()> {
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&[
+            "-ss",
+            &time_offset.to_string(),
+            "-i",
+            video_path.to_str().unwrap(),
+            "-vframes",
+            "1",
+            "-q:v",
+            "2",
+            "-y",
+            output_path.to_str().unwrap(),
+        ]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let status = command.status().await?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("抽帧失败"));
+        }
+
+        Ok(())
+    }
+
+    /// 截取一小段视频作为预览短片（缩小到 320 宽、去掉音轨的 WebM）
+    pub async fn generate_preview_clip(
+        video_path: &Path,
+        output_path: &Path,
+        start_offset: f64,
+        duration: f64,
+    ) -> Result<()> {
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&[
+            "-ss",
+            &format!("{:.3}", start_offset.max(0.0)),
+            "-i",
+            video_path.to_str().unwrap(),
+            "-t",
+            &format!("{:.3}", duration.max(0.1)),
+            "-an",
+            "-vf",
+            "scale=320:-2",
+            "-c:v",
+            "libvpx-vp9",
+            "-b:v",
+            "0",
+            "-crf",
+            "40",
+            "-deadline",
+            "realtime",
+            "-cpu-used",
+            "8",
+            "-y",
+            output_path.to_str().unwrap(),
+        ]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = command.output().await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "预览短片生成失败: {}",
+                stderr.lines().last().unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 截取一段视频并统一转码（缩放并补边到指定尺寸、固定帧率、去掉音轨的 H.264），
+    /// 不同会话的片段转码后可直接用 concat 无损拼接
+    pub async fn cut_normalized_clip(
+        video_path: &Path,
+        output_path: &Path,
+        start_offset: f64,
+        duration: f64,
+        size: (u32, u32),
+    ) -> Result<()> {
+        let filter = format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps}",
+            w = size.0,
+            h = size.1,
+            fps = NORMALIZED_FPS
+        );
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&[
+            "-ss",
+            &format!("{:.3}", start_offset.max(0.0)),
+            "-i",
+            video_path.to_str().unwrap(),
+            "-t",
+            &format!("{:.3}", duration.max(0.1)),
+            "-an",
+            "-vf",
+            filter.as_str(),
+        ]);
+        command.args(NORMALIZED_ENCODE_ARGS);
+        command.args(&["-y", output_path.to_str().unwrap()]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = command.output().await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "截取视频片段失败: {}",
+                stderr.lines().last().unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    /// 生成纯色背景、居中显示文字的标题卡视频（编码参数与 cut_normalized_clip 一致）
+    ///
+    /// 文字通过 textfile 传入，避免转义；找不到可显示中文的系统字体时只生成纯色过场
+    pub async fn generate_title_card(
+        output_path: &Path,
+        text: &str,
+        duration: f64,
+        size: (u32, u32),
+    ) -> Result<()> {
+        let text_path = output_path.with_extension("txt");
+        tokio::fs::write(&text_path, text).await?;
+
+        let mut filter = "setsar=1".to_string();
+        if let Some(font) = title_font() {
+            filter = format!(
+                "drawtext=fontfile='{}':textfile='{}':expansion=none:fontcolor=white:fontsize={}:line_spacing=16:x=(w-text_w)/2:y=(h-text_h)/2,{}",
+                escape_filter_path(&font),
+                escape_filter_path(&text_path),
+                size.1 / 15,
+                filter
+            );
+        }
+        let source = format!(
+            "color=c=0x1f2937:s={}x{}:d={:.3}:r={}",
+            size.0,
+            size.1,
+            duration.max(0.1),
+            NORMALIZED_FPS
+        );
+
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&["-f", "lavfi", "-i", source.as_str(), "-vf", filter.as_str()]);
+        command.args(NORMALIZED_ENCODE_ARGS);
+        command.args(&["-y", output_path.to_str().unwrap()]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = command.output().await;
+        tokio::fs::remove_file(&text_path).await.ok();
+        let output = output?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "生成标题卡失败: {}",
+                stderr.lines().last().unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    /// 把字幕文件作为字幕轨封装进视频（视频流直接复制，已有的字幕轨会被替换）
+    pub async fn embed_subtitles(video_path: &Path, subtitle_path: &Path) -> Result<()> {
+        // MP4 只支持 mov_text，WebM 只支持 WebVTT
+        let extension = video_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let subtitle_codec = match extension.as_str() {
+            "mp4" | "mov" => "mov_text",
+            "webm" => "webvtt",
+            _ => "srt",
+        };
+        // 写到 .part 临时文件，避免未分析视频扫描把半成品当作新视频
+        let output_path = processor::partial_path(video_path);
+
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&[
+            "-i",
+            video_path.to_str().unwrap(),
+            "-i",
+            subtitle_path.to_str().unwrap(),
+            "-map",
+            "0:v",
+            "-map",
+            "1:0",
+            "-c:v",
+            "copy",
+            "-c:s",
+            subtitle_codec,
+            "-metadata:s:s:0",
+            "language=chi",
+            "-f",
+            processor::muxer_for(video_path),
+            "-y",
+            output_path.to_str().unwrap(),
+        ]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let status = command.status().await?;
+        if !status.success() {
+            tokio::fs::remove_file(&output_path).await.ok();
+            return Err(anyhow::anyhow!("封装字幕失败"));
+        }
+
+        tokio::fs::rename(&output_path, video_path).await?;
+        Ok(())
+    }
+
+    /// 模糊视频中的若干区域，输出到新文件（区域按画面宽高的比例给出，与分辨率无关）
+    pub async fn blur_regions(
+        input_path: &Path,
+        output_path: &Path,
+        regions: &[BlurRegion],
+    ) -> Result<()> {
+        let codec = match output_path.extension().and_then(|ext| ext.to_str()) {
+            Some("webm") => "libvpx-vp9",
+            _ => "libx264",
+        };
+
+        let filter = blur_filter(regions);
+        let ffmpeg_path = crate::video::ffmpeg_helper::ensure_ffmpeg_extracted().await?;
+        let mut command = tokio::process::Command::new(&ffmpeg_path);
+        command.args(&[
+            "-i",
+            input_path.to_str().unwrap(),
+            "-filter_complex",
+            filter.as_str(),
+            "-map",
+            "[out]",
+            "-c:v",
+            codec,
+            "-crf",
+            "23",
+            "-pix_fmt",
+            "yuv420p",
+            "-y",
+            output_path.to_str().unwrap(),
+        ]);
+
+        // Windows下隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let status = command.status().await?;
+        if !status.success() {
+            tokio::fs::remove_file(output_path).await.ok();
+            return Err(anyhow::anyhow!("模糊视频区域失败"));
+        }
+        Ok(())
+    }
+
     /// 合并多个视频片段
     pub async fn concatenate_videos(video_paths: Vec<PathBuf>, output_path: &Path) -> Result<()> {
         // 创建临时文件列表
@@ -235,6 +610,40 @@ impl VideoUtils {
     }
 }
 
+/// 统一转码的帧率
+const NORMALIZED_FPS: u32 = 30;
+
+/// 统一转码的编码参数
+const NORMALIZED_ENCODE_ARGS: &[&str] = &[
+    "-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-pix_fmt", "yuv420p",
+];
+
+/// 可显示中文的系统字体（按平台依次尝试）
+const TITLE_FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+];
+
+fn title_font() -> Option<PathBuf> {
+    TITLE_FONT_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// 转义滤镜参数中的路径（统一用 / 分隔，Windows 盘符的冒号需要转义）
+fn escape_filter_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+}
+
 /// 视频信息
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VideoInfo {
@@ -1,11 +1,18 @@
 // 视频处理器 - 负责将截图序列转换为视频
 
-use super::{VideoResult, VideoTask, VideoTaskStatus};
+use super::{SpeedRun, VideoResult, VideoTask, VideoTaskStatus};
+use crate::capture::image_fingerprint;
+use crate::domains::telemetry::{self, Metric};
+use crate::storage::media_store::MediaLibrary;
 use anyhow::Result;
-use image::GenericImageView;
+use rayon::prelude::*;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// 查询各屏幕在合成截图中的位置（截屏模块在首次使用时才枚举屏幕）
+pub type ScreenRegionSource = Arc<dyn Fn() -> Vec<ScreenRegion> + Send + Sync>;
 
 /// 视频处理器
 pub struct VideoProcessor {
@@ -15,6 +22,10 @@ pub struct VideoProcessor {
     pub temp_dir: PathBuf,
     /// FFmpeg路径（可选自定义路径）
     pub ffmpeg_path: String,
+    /// 媒体库（生成的视频同步到存储后端）
+    media: Option<Arc<MediaLibrary>>,
+    /// 各屏幕在合成截图中的位置（多屏排列时使用）
+    screen_regions: Option<ScreenRegionSource>,
 }
 
 /// 视频配置
@@ -34,6 +45,15 @@ pub struct VideoConfig {
     pub format: VideoFormat,
     /// 是否添加时间戳水印
     pub add_timestamp: bool,
+    /// 智能变速：画面静止的片段加速更多，画面变化的片段正常播放
+    #[serde(default)]
+    pub smart_speed: bool,
+    /// 多屏画面的排列方式
+    #[serde(default)]
+    pub layout: VideoLayout,
+    /// 输出高度上限（超过时按比例缩小，存储紧张时降级使用）
+    #[serde(default)]
+    pub max_height: Option<u32>,
 }
 
 impl Default for VideoConfig {
@@ -46,8 +66,164 @@ impl Default for VideoConfig {
             preset: "fast".to_string(),
             format: VideoFormat::Mp4,
             add_timestamp: true,
+            smart_speed: false,
+            layout: VideoLayout::Native,
+            max_height: None,
+        }
+    }
+}
+
+/// 多屏画面在视频中的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoLayout {
+    /// 保持屏幕的实际摆放位置（截图原样）
+    #[default]
+    Native,
+    /// 所有屏幕等高横向排列
+    Horizontal,
+    /// 网格排列
+    Grid,
+    /// 主屏全画面，其余屏幕以小窗显示在右下角
+    PictureInPicture,
+}
+
+/// 单个屏幕在合成截图中的位置（占整张截图宽高的比例）
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScreenRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// 是否为系统主屏
+    pub primary: bool,
+}
+
+/// 画中画小窗占画面宽高的比例
+const PIP_SCALE: u32 = 4;
+/// 画中画小窗距画面边缘的像素
+const PIP_MARGIN: u32 = 16;
+
+/// 缩放到 width×height 以内并居中补黑边
+fn fit_filter(width: u32, height: u32) -> String {
+    format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+        w = width,
+        h = height
+    )
+}
+
+/// 按多屏排列方式生成 FFmpeg 滤镜图
+///
+/// 输入为合成截图，先按屏幕位置裁出各屏画面再重新排列，输出 width×height 的画面；
+/// 保持原样或只有一个屏幕时返回 None
+pub fn layout_filter(
+    layout: VideoLayout,
+    regions: &[ScreenRegion],
+    (width, height): (u32, u32),
+) -> Option<String> {
+    if layout == VideoLayout::Native || regions.len() < 2 {
+        return None;
+    }
+
+    let count = regions.len();
+    // 主屏排在最前面（画中画时作为背景）
+    let mut ordered: Vec<&ScreenRegion> = regions.iter().collect();
+    ordered.sort_by_key(|region| !region.primary);
+
+    let mut graph = format!(
+        "split={}{}",
+        count,
+        (0..count).map(|i| format!("[s{}]", i)).collect::<String>()
+    );
+    let crop = |region: &ScreenRegion| {
+        format!(
+            "crop=w=iw*{:.6}:h=ih*{:.6}:x=iw*{:.6}:y=ih*{:.6}",
+            region.width, region.height, region.x, region.y
+        )
+    };
+
+    match layout {
+        VideoLayout::Native => unreachable!(),
+        VideoLayout::Horizontal => {
+            for (index, region) in ordered.iter().enumerate() {
+                graph.push_str(&format!(
+                    ";[s{i}]{},scale=-2:{}[c{i}]",
+                    crop(region),
+                    height,
+                    i = index
+                ));
+            }
+            graph.push_str(&format!(
+                ";{}hstack=inputs={},{}",
+                (0..count).map(|i| format!("[c{}]", i)).collect::<String>(),
+                count,
+                fit_filter(width, height)
+            ));
+        }
+        VideoLayout::Grid => {
+            let columns = (count as f64).sqrt().ceil() as usize;
+            let rows = count.div_ceil(columns);
+            // 单元格尺寸取偶数，满足 yuv420p 编码要求
+            let cell_width = (width / columns as u32) & !1;
+            let cell_height = (height / rows as u32) & !1;
+            for (index, region) in ordered.iter().enumerate() {
+                graph.push_str(&format!(
+                    ";[s{i}]{},{}[c{i}]",
+                    crop(region),
+                    fit_filter(cell_width, cell_height),
+                    i = index
+                ));
+            }
+            let positions: Vec<String> = (0..count)
+                .map(|index| {
+                    format!(
+                        "{}_{}",
+                        (index % columns) as u32 * cell_width,
+                        (index / columns) as u32 * cell_height
+                    )
+                })
+                .collect();
+            graph.push_str(&format!(
+                ";{}xstack=inputs={}:layout={}:fill=black,{}",
+                (0..count).map(|i| format!("[c{}]", i)).collect::<String>(),
+                count,
+                positions.join("|"),
+                fit_filter(width, height)
+            ));
+        }
+        VideoLayout::PictureInPicture => {
+            let (pip_width, pip_height) = ((width / PIP_SCALE) & !1, (height / PIP_SCALE) & !1);
+            graph.push_str(&format!(
+                ";[s0]{},{}[o0]",
+                crop(ordered[0]),
+                fit_filter(width, height)
+            ));
+            for (index, region) in ordered.iter().enumerate().skip(1) {
+                graph.push_str(&format!(
+                    ";[s{i}]{},scale={}:{}:force_original_aspect_ratio=decrease[c{i}]",
+                    crop(region),
+                    pip_width,
+                    pip_height,
+                    i = index
+                ));
+                // 小窗从右下角开始向上排列
+                let bottom = height - PIP_MARGIN - (index as u32 - 1) * (pip_height + PIP_MARGIN);
+                graph.push_str(&format!(
+                    ";[o{}][c{i}]overlay=x={}-overlay_w:y={}-overlay_h",
+                    index - 1,
+                    width - PIP_MARGIN,
+                    bottom,
+                    i = index
+                ));
+                if index + 1 < count {
+                    graph.push_str(&format!("[o{}]", index));
+                }
+            }
         }
     }
+
+    Some(graph)
 }
 
 /// 视频格式
@@ -80,6 +256,64 @@ impl VideoFormat {
     }
 }
 
+/// 写入中视频的文件名后缀：FFmpeg 先写到 xxx.mp4.part，完成后再改名为 xxx.mp4，
+/// 扫描未分析视频时只认完整的视频扩展名，不会拿到写了一半的文件
+pub const PARTIAL_SUFFIX: &str = ".part";
+
+/// 视频最后修改后至少经过这么久才视为写入完成（秒）
+///
+/// 针对不经过 .part 改名写入的视频，如其他设备通过同步盘、共享文件夹拷贝过来的视频
+pub const WRITE_SETTLE_SECS: u64 = 30;
+
+/// 写入中的临时路径（xxx.mp4 -> xxx.mp4.part）
+pub fn partial_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.as_os_str().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// 按最终文件的扩展名选择封装格式（.part 临时文件无法让 FFmpeg 推断格式）
+pub fn muxer_for(output_path: &Path) -> &'static str {
+    match output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "webm" => "webm",
+        "avi" => "avi",
+        "mkv" => "matroska",
+        "mov" => "mov",
+        _ => "mp4",
+    }
+}
+
+/// 按文件大小和最后修改时间判断视频是否已写入完成
+pub fn is_write_settled(
+    len: u64,
+    modified: std::time::SystemTime,
+    now: std::time::SystemTime,
+) -> bool {
+    len > 0
+        && now
+            .duration_since(modified)
+            .is_ok_and(|age| age.as_secs() >= WRITE_SETTLE_SECS)
+}
+
+/// 视频是否可以作为分析候选（存在、非空且最近没有被写入）
+pub async fn is_video_complete(path: &Path) -> bool {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata
+            .modified()
+            .map(|modified| {
+                is_write_settled(metadata.len(), modified, std::time::SystemTime::now())
+            })
+            .unwrap_or(true),
+        Err(_) => false,
+    }
+}
+
 /// 帧过滤函数：每N秒选择一张图片
 ///
 /// # 参数
@@ -101,6 +335,119 @@ pub fn filter_frames_by_interval(frames: Vec<String>, interval_seconds: usize) -
         .collect()
 }
 
+/// 智能变速时静止画面每帧展示的秒数（有变化的帧展示 1 秒）
+const SMART_STATIC_DISPLAY_SECS: f64 = 0.25;
+/// 相邻帧指纹差异超过该位数视为画面变化（与屏幕活动检测一致）
+const SMART_CHANGE_BITS: u32 = 2;
+
+/// 按相邻帧的画面变化计算每帧展示时长
+///
+/// 画面发生变化的帧及其前一帧正常展示，连续静止的帧按 SMART_STATIC_DISPLAY_SECS 展示；
+/// 无法读取的帧（指纹为 None）视为有变化
+pub fn smart_display_secs(fingerprints: &[Option<u64>]) -> Vec<f64> {
+    let changed: Vec<bool> = fingerprints
+        .iter()
+        .enumerate()
+        .map(
+            |(index, fingerprint)| match (index.checked_sub(1), fingerprint) {
+                (Some(prev), Some(current)) => fingerprints[prev]
+                    .is_none_or(|prev| (prev ^ current).count_ones() > SMART_CHANGE_BITS),
+                _ => true,
+            },
+        )
+        .collect();
+
+    (0..changed.len())
+        .map(|index| {
+            if changed[index] || changed.get(index + 1).copied().unwrap_or(false) {
+                1.0
+            } else {
+                SMART_STATIC_DISPLAY_SECS
+            }
+        })
+        .collect()
+}
+
+/// 通过管道送入 FFmpeg 时最多预读的帧数（限制内存占用，同时让读盘与编码并行）
+const FRAME_READAHEAD: usize = 8;
+
+/// 管道输入的帧率：固定倍速时每帧 1 秒；智能变速时按最短展示时长换算，
+/// 每帧按展示时长重复写入若干次
+fn pipe_input_fps(smart_speed: bool) -> u32 {
+    if smart_speed {
+        (1.0 / SMART_STATIC_DISPLAY_SECS).round() as u32
+    } else {
+        1
+    }
+}
+
+/// 按输入帧率把每帧展示时长换算为重复写入的次数（至少 1 次）
+fn pipe_repeats(display_secs: &[f64], input_fps: u32) -> Vec<u32> {
+    display_secs
+        .iter()
+        .map(|secs| ((secs * input_fps as f64).round() as u32).max(1))
+        .collect()
+}
+
+/// 送入 FFmpeg 的帧：路径与重复次数，以及智能变速的分段
+struct FramePlan {
+    frames: Vec<(String, u32)>,
+    input_fps: u32,
+    speed_runs: Vec<SpeedRun>,
+}
+
+/// 把每帧展示时长合并为连续的段
+pub fn speed_runs(display_secs: &[f64]) -> Vec<SpeedRun> {
+    let mut runs: Vec<SpeedRun> = Vec::new();
+    for &secs in display_secs {
+        match runs.last_mut() {
+            Some(run) if run.display_secs == secs => run.frames += 1,
+            _ => runs.push(SpeedRun {
+                frames: 1,
+                display_secs: secs,
+            }),
+        }
+    }
+    runs
+}
+
+/// 逐帧读取截图写入 FFmpeg 标准输入，最多预读 FRAME_READAHEAD 帧，返回写入的帧数
+///
+/// 读取失败的帧跳过；写完后关闭标准输入，FFmpeg 随之结束编码
+async fn feed_frames(
+    frames: Vec<(String, u32)>,
+    mut stdin: tokio::process::ChildStdin,
+) -> std::io::Result<usize> {
+    use tokio::io::AsyncWriteExt;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<(Vec<u8>, u32)>(FRAME_READAHEAD);
+    let reader = tokio::spawn(async move {
+        for (path, repeats) in frames {
+            match tokio::fs::read(&path).await {
+                Ok(data) => {
+                    if tx.send((data, repeats)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("读取帧失败，已跳过 {}: {}", path, e),
+            }
+        }
+    });
+
+    let mut fed = 0;
+    while let Some((data, repeats)) = rx.recv().await {
+        for _ in 0..repeats {
+            if let Err(e) = stdin.write_all(&data).await {
+                reader.abort();
+                return Err(e);
+            }
+        }
+        fed += 1;
+    }
+    stdin.shutdown().await?;
+    Ok(fed)
+}
+
 impl VideoProcessor {
     /// 创建新的视频处理器
     pub fn new(output_dir: PathBuf, temp_dir: PathBuf) -> Result<Self> {
@@ -118,9 +465,32 @@ impl VideoProcessor {
             output_dir,
             temp_dir,
             ffmpeg_path,
+            media: None,
+            screen_regions: None,
         })
     }
 
+    /// 设置各屏幕在合成截图中的位置，多屏排列方式依赖它裁出各屏画面
+    pub fn with_screen_regions(mut self, screen_regions: ScreenRegionSource) -> Self {
+        self.screen_regions = Some(screen_regions);
+        self
+    }
+
+    /// 设置媒体库，生成的视频通过 publish 同步到存储后端
+    pub fn with_media(mut self, media: Arc<MediaLibrary>) -> Self {
+        self.media = Some(media);
+        self
+    }
+
+    /// 把生成的视频（及元数据）同步到存储后端，失败只记录日志，本地视频仍可使用
+    pub async fn publish(&self, video_path: &Path) {
+        if let Some(media) = &self.media {
+            if let Err(e) = media.publish_video(video_path).await {
+                error!("同步视频到存储后端失败 {:?}: {}", video_path, e);
+            }
+        }
+    }
+
     /// 设置自定义FFmpeg路径
     pub fn set_ffmpeg_path(&mut self, path: String) {
         self.ffmpeg_path = path;
@@ -136,9 +506,14 @@ impl VideoProcessor {
         let start_time = Instant::now();
 
         info!(
-            "开始生成视频: {} 帧, 速度 {}x",
+            "开始生成视频: {} 帧, 速度 {}x{}",
             frames.len(),
-            config.speed_multiplier
+            config.speed_multiplier,
+            if config.smart_speed {
+                "（智能变速）"
+            } else {
+                ""
+            }
         );
 
         // 验证输入
@@ -156,8 +531,8 @@ impl VideoProcessor {
             info!("检测图片分辨率，采样 {} 张...", sample_count);
 
             for (idx, frame_path) in frames.iter().take(sample_count).enumerate() {
-                if let Ok(img) = image::open(frame_path) {
-                    let (width, height) = img.dimensions();
+                // 只读取文件头获取尺寸，不解码整张图片
+                if let Ok((width, height)) = image::image_dimensions(frame_path) {
                     info!("图片 #{}: {}x{}", idx + 1, width, height);
 
                     // 取各维度的最大值，确保视频容器能容纳所有图片
@@ -169,8 +544,27 @@ impl VideoProcessor {
             info!("输出视频分辨率: {}x{}", resolution.0, resolution.1);
         }
 
-        // 生成帧列表文件
-        let frame_list_path = self.create_frame_list(&frames).await?;
+        // 按高度上限等比缩小（宽高保持偶数，满足 H.264 编码要求）
+        if let Some(max_height) = config.max_height.filter(|max| *max > 0) {
+            if resolution.1 > max_height {
+                let width = resolution.0 as u64 * max_height as u64 / resolution.1 as u64;
+                resolution = ((width as u32).max(2) & !1, max_height.max(2) & !1);
+                info!(
+                    "按高度上限缩小输出分辨率: {}x{}",
+                    resolution.0, resolution.1
+                );
+            }
+        }
+
+        // 同时编码的视频数受性能设置限制，名额在本次编码结束时归还
+        let _encode_permit = crate::domains::performance::acquire_encode().await;
+
+        // 确定送入的帧和每帧重复次数
+        let plan = self.plan_frames(&frames, config.smart_speed).await?;
+        let speed_runs = plan.speed_runs;
+
+        // 先写到 .part 临时文件，编码完成后再改名，避免扫描时拿到写了一半的视频
+        let partial = partial_path(output_path);
 
         // 构建FFmpeg命令
         let mut command = tokio::process::Command::new(&self.ffmpeg_path);
@@ -184,25 +578,37 @@ impl VideoProcessor {
             command.creation_flags(CREATE_NO_WINDOW);
         }
 
-        // 基础参数
+        // 基础参数：截图（JPEG）通过标准输入逐帧送入，不再生成上千行的帧列表文件
         command
             .arg("-f")
-            .arg("concat")
-            .arg("-safe")
-            .arg("0")
+            .arg("image2pipe")
+            .arg("-c:v")
+            .arg("mjpeg")
+            .arg("-framerate")
+            .arg(plan.input_fps.to_string())
             .arg("-i")
-            .arg(&frame_list_path);
+            .arg("-");
 
         // 视频滤镜
         let mut filters = vec![];
 
-        // 如果需要缩放，使用scale滤镜
-        // 使用force_original_aspect_ratio=increase确保图片不会比目标小
-        // pad确保最终尺寸正确
-        filters.push(format!(
-            "scale=w={}:h={}:force_original_aspect_ratio=increase,crop={}:{}",
-            resolution.0, resolution.1, resolution.0, resolution.1
-        ));
+        let screen_regions = self
+            .screen_regions
+            .as_ref()
+            .map(|source| source())
+            .unwrap_or_default();
+        if let Some(layout) = layout_filter(config.layout, &screen_regions, resolution) {
+            // 多屏重新排列，滤镜图的输出已是目标分辨率
+            filters.push(layout);
+        } else {
+            // 如果需要缩放，使用scale滤镜
+            // 使用force_original_aspect_ratio=increase确保图片不会比目标小
+            // pad确保最终尺寸正确
+            filters.push(format!(
+                "scale=w={}:h={}:force_original_aspect_ratio=increase,crop={}:{}",
+                resolution.0, resolution.1, resolution.0, resolution.1
+            ));
+        }
 
         // 速度调整
         if config.speed_multiplier != 1.0 {
@@ -240,37 +646,92 @@ impl VideoProcessor {
             .arg("yuv420p") // 兼容性
             .arg("-movflags")
             .arg("+faststart") // 优化流媒体播放
+            .arg("-f")
+            .arg(muxer_for(output_path))
             .arg("-y") // 覆盖输出文件
-            .arg(output_path);
+            .arg(&partial);
+
+        command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
 
         debug!("FFmpeg命令: {:?}", command);
         info!("开始执行FFmpeg命令，可能需要一些时间...");
 
-        // 执行命令，设置10分钟超时
-        let output = tokio::time::timeout(std::time::Duration::from_secs(600), command.output())
-            .await
-            .map_err(|_| anyhow::anyhow!("FFmpeg 执行超时(10分钟)"))?
+        let mut child = command
+            .spawn()
             .map_err(|e| anyhow::anyhow!("FFmpeg 执行失败: {}", e))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("无法获取 FFmpeg 输入"))?;
+        let feeder = tokio::spawn(feed_frames(plan.frames, stdin));
+
+        // 等待编码完成，设置10分钟超时（超时后丢弃子进程即结束 FFmpeg）
+        let output = tokio::time::timeout(
+            std::time::Duration::from_secs(600),
+            child.wait_with_output(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("FFmpeg 执行超时(10分钟)"))
+        .and_then(|output| output.map_err(|e| anyhow::anyhow!("FFmpeg 执行失败: {}", e)));
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                feeder.abort();
+                tokio::fs::remove_file(&partial).await.ok();
+                return Err(e);
+            }
+        };
 
         info!("FFmpeg命令执行完成");
 
-        // 清理临时文件
-        tokio::fs::remove_file(frame_list_path).await.ok();
-
         if !output.status.success() {
+            feeder.abort();
+            tokio::fs::remove_file(&partial).await.ok();
             let stderr = String::from_utf8_lossy(&output.stderr);
             error!("FFmpeg错误: {}", stderr);
             return Err(anyhow::anyhow!("视频生成失败: {}", stderr));
         }
 
+        let fed = match feeder.await {
+            Ok(Ok(fed)) => fed,
+            Ok(Err(e)) => {
+                tokio::fs::remove_file(&partial).await.ok();
+                return Err(anyhow::anyhow!("送入视频帧失败: {}", e));
+            }
+            Err(e) => {
+                tokio::fs::remove_file(&partial).await.ok();
+                return Err(anyhow::anyhow!("送入视频帧失败: {}", e));
+            }
+        };
+        debug!("已通过管道送入 {} 帧", fed);
+
+        tokio::fs::rename(&partial, output_path).await?;
+
         // 获取文件信息
         let metadata = tokio::fs::metadata(output_path).await?;
         let file_size = metadata.len();
 
         let processing_time_ms = start_time.elapsed().as_millis() as u64;
+        telemetry::observe(
+            Metric::VideoEncodeSeconds,
+            &[],
+            start_time.elapsed().as_secs_f64(),
+        );
 
         // 计算视频时长
-        let duration = frames.len() as f32 / config.fps as f32 / config.speed_multiplier;
+        let duration = if speed_runs.is_empty() {
+            frames.len() as f32 / config.fps as f32 / config.speed_multiplier
+        } else {
+            let display_secs: f64 = speed_runs
+                .iter()
+                .map(|run| run.frames as f64 * run.display_secs)
+                .sum();
+            display_secs as f32 / config.speed_multiplier
+        };
 
         let result = VideoResult {
             file_path: output_path.to_string_lossy().to_string(),
@@ -279,18 +740,19 @@ impl VideoProcessor {
             resolution,
             fps: config.fps as f32,
             processing_time_ms,
+            speed_runs,
         };
 
         info!("视频生成成功: {:?}", result);
         Ok(result)
     }
 
-    /// 创建帧列表文件
-    async fn create_frame_list(&self, frames: &[String]) -> Result<PathBuf> {
+    /// 筛选存在的帧文件，智能变速时按画面变化计算每帧的重复次数
+    async fn plan_frames(&self, frames: &[String], smart_speed: bool) -> Result<FramePlan> {
         // 检查文件是否存在
         info!("检查 {} 个帧文件路径...", frames.len());
 
-        let valid_frames: Vec<&String> = frames
+        let valid_frames: Vec<String> = frames
             .iter()
             .filter(|path| {
                 let exists = std::path::Path::new(path).exists();
@@ -300,6 +762,7 @@ impl VideoProcessor {
                 }
                 exists
             })
+            .cloned()
             .collect();
 
         if valid_frames.is_empty() {
@@ -312,25 +775,33 @@ impl VideoProcessor {
 
         info!("找到 {} 个有效帧文件", valid_frames.len());
 
-        let list_path = self
-            .temp_dir
-            .join(format!("frames_{}.txt", uuid::Uuid::new_v4()));
-
-        let mut content = String::new();
-        for frame in &valid_frames {
-            let normalized = frame.replace('\\', "/");
-            content.push_str(&format!("file '{}'\n", normalized));
-            content.push_str("duration 1\n"); // 每张图片展示1秒
-        }
-
-        // 最后一帧需要特殊处理
-        if let Some(last) = valid_frames.last() {
-            let normalized = last.replace('\\', "/");
-            content.push_str(&format!("file '{}\n", normalized));
-        }
+        // 固定倍速时每张图片展示1秒，智能变速时按画面变化决定
+        let display_secs = if smart_speed {
+            let paths = valid_frames.clone();
+            let fingerprints = tokio::task::spawn_blocking(move || {
+                paths
+                    .par_iter()
+                    .map(|path| image::open(path).ok().map(|img| image_fingerprint(&img)))
+                    .collect::<Vec<_>>()
+            })
+            .await?;
+            smart_display_secs(&fingerprints)
+        } else {
+            vec![1.0; valid_frames.len()]
+        };
 
-        tokio::fs::write(&list_path, content).await?;
-        Ok(list_path)
+        let input_fps = pipe_input_fps(smart_speed);
+        let repeats = pipe_repeats(&display_secs, input_fps);
+        let speed_runs = if smart_speed {
+            speed_runs(&display_secs)
+        } else {
+            Vec::new()
+        };
+        Ok(FramePlan {
+            frames: valid_frames.into_iter().zip(repeats).collect(),
+            input_fps,
+            speed_runs,
+        })
     }
 
     /// 生成延时摄影视频（极速版本）
@@ -428,6 +899,23 @@ mod tests {
         assert!(processor.is_ok());
     }
 
+    #[test]
+    fn test_partial_naming_and_write_settle() {
+        let output = Path::new("/data/videos/session_1.mp4");
+        let partial = partial_path(output);
+        assert_eq!(partial, Path::new("/data/videos/session_1.mp4.part"));
+        // 扫描只认 mp4 扩展名，写入中的文件不会成为分析候选
+        assert_eq!(partial.extension().and_then(|e| e.to_str()), Some("part"));
+        assert_eq!(muxer_for(output), "mp4");
+        assert_eq!(muxer_for(Path::new("a.mkv")), "matroska");
+
+        let now = std::time::SystemTime::now();
+        let settled = now - std::time::Duration::from_secs(WRITE_SETTLE_SECS);
+        assert!(is_write_settled(1024, settled, now));
+        assert!(!is_write_settled(1024, now, now));
+        assert!(!is_write_settled(0, settled, now));
+    }
+
     #[test]
     fn test_video_format_extension() {
         assert_eq!(VideoFormat::Mp4.extension(), "mp4");
@@ -444,4 +932,72 @@ mod tests {
         assert_eq!(config.resolution, (1920, 1080));
         assert_eq!(config.quality, 23);
     }
+
+    #[test]
+    fn test_layout_filter_arranges_screens() {
+        let regions = [
+            ScreenRegion {
+                x: 0.5,
+                y: 0.0,
+                width: 0.5,
+                height: 1.0,
+                primary: true,
+            },
+            ScreenRegion {
+                x: 0.0,
+                y: 0.0,
+                width: 0.5,
+                height: 1.0,
+                primary: false,
+            },
+            ScreenRegion {
+                x: 0.0,
+                y: 0.5,
+                width: 0.5,
+                height: 0.5,
+                primary: false,
+            },
+        ];
+
+        assert!(layout_filter(VideoLayout::Native, &regions, (1920, 1080)).is_none());
+        assert!(layout_filter(VideoLayout::Grid, &regions[..1], (1920, 1080)).is_none());
+
+        let horizontal = layout_filter(VideoLayout::Horizontal, &regions, (1920, 1080)).unwrap();
+        assert!(horizontal.starts_with("split=3[s0][s1][s2];[s0]crop=w=iw*0.500000"));
+        assert!(horizontal.contains("x=iw*0.500000"));
+        assert!(horizontal.contains("[c0][c1][c2]hstack=inputs=3"));
+
+        let grid = layout_filter(VideoLayout::Grid, &regions, (1920, 1080)).unwrap();
+        assert!(grid.contains("layout=0_0|960_0|0_540:fill=black"));
+
+        let pip = layout_filter(VideoLayout::PictureInPicture, &regions, (1920, 1080)).unwrap();
+        assert!(pip.contains("[o0][c1]overlay=x=1904-overlay_w:y=1064-overlay_h[o1]"));
+        assert!(pip.ends_with("[o1][c2]overlay=x=1904-overlay_w:y=778-overlay_h"));
+    }
+
+    #[test]
+    fn test_smart_speed_slows_down_changes() {
+        // 第 4 帧画面变化，其余帧静止
+        let fingerprints = [
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(u64::MAX),
+            Some(u64::MAX),
+        ];
+        let display_secs = smart_display_secs(&fingerprints);
+        assert_eq!(display_secs, vec![1.0, 0.25, 0.25, 1.0, 1.0, 0.25]);
+
+        let runs = speed_runs(&display_secs);
+        assert_eq!(runs.len(), 4);
+        assert_eq!(runs[1].frames, 2);
+        assert_eq!(runs[2].frames, 2);
+        assert_eq!(runs[2].display_secs, 1.0);
+
+        // 管道输入按 4fps 送入，展示 1 秒的帧重复写入 4 次
+        assert_eq!(pipe_input_fps(true), 4);
+        assert_eq!(pipe_repeats(&display_secs, 4), vec![4, 1, 1, 4, 4, 1]);
+        assert_eq!(pipe_repeats(&[1.0, 1.0], pipe_input_fps(false)), vec![1, 1]);
+    }
 }
@@ -0,0 +1,309 @@
+// 端到端流水线测试 - 合成截图 → 视频生成 → 分析 → 存储
+//
+// 使用 MockProvider 代替真实 LLM，校验相对时间转换和 LLM 调用记录关联；
+// 本机没有 FFmpeg 时跳过视频生成，走图片模式
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use screen_analyzer_lib::actors::LLMManagerActor;
+use screen_analyzer_lib::capture::scheduler::{SessionProcessor, SessionWindow};
+use screen_analyzer_lib::capture::{ScreenCapture, ScreenFrame};
+use screen_analyzer_lib::domains::analysis_hold::AnalysisHold;
+use screen_analyzer_lib::event_bus::{AppEvent, EventBus, EventTopic};
+use screen_analyzer_lib::llm::mock::{MockCall, MockProvider};
+use screen_analyzer_lib::llm::plugin::{AppSites, TimelineCard, VideoSegment};
+use screen_analyzer_lib::llm::{LLMManager, LLMProcessor};
+use screen_analyzer_lib::settings::SettingsManager;
+use screen_analyzer_lib::storage::Database;
+use screen_analyzer_lib::video::{VideoMetadata, VideoProcessor, VideoUtils};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+struct Pipeline {
+    dir: tempfile::TempDir,
+    frames_dir: std::path::PathBuf,
+    db: Arc<Database>,
+    processor: LLMProcessor,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+    with_video: bool,
+}
+
+/// 搭建完整流水线：临时目录 + SQLite + 设置 + MockProvider Actor
+async fn setup(provider: MockProvider) -> Pipeline {
+    let dir = tempfile::tempdir().unwrap();
+    let frames_dir = dir.path().join("frames");
+    std::fs::create_dir_all(&frames_dir).unwrap();
+
+    let db_path = dir.path().join("data.db");
+    let db = Arc::new(
+        Database::new_sqlite(&db_path.to_string_lossy())
+            .await
+            .unwrap(),
+    );
+    let settings = Arc::new(
+        SettingsManager::new(dir.path().join("config.json"))
+            .await
+            .unwrap(),
+    );
+
+    let calls = provider.calls();
+    let (actor, handle) =
+        LLMManagerActor::new(LLMManager::with_provider(Box::new(provider), "mock"));
+    tokio::spawn(actor.run());
+
+    let with_video = VideoUtils::check_ffmpeg().await.unwrap_or(false);
+    let processor = if with_video {
+        let video_processor =
+            VideoProcessor::new(dir.path().join("videos"), dir.path().join("temp")).unwrap();
+        LLMProcessor::with_video_processor(handle, db.clone(), Arc::new(video_processor), settings)
+    } else {
+        LLMProcessor::new(handle, db.clone(), settings)
+    };
+
+    Pipeline {
+        dir,
+        frames_dir,
+        db,
+        processor,
+        calls,
+        with_video,
+    }
+}
+
+/// 生成合成截图（每秒一帧，颜色按分钟变化）
+fn write_frames(dir: &Path, start: DateTime<Utc>, minutes: i64) -> Vec<ScreenFrame> {
+    (0..minutes * 60)
+        .map(|second| {
+            let timestamp = start + Duration::seconds(second);
+            let shade = ((second / 60) * 20 % 255) as u8;
+            let path = dir.join(format!("{}.png", timestamp.format("%Y%m%d%H%M%S")));
+            image::RgbImage::from_pixel(64, 36, image::Rgb([shade, 128, 255 - shade]))
+                .save(&path)
+                .unwrap();
+            ScreenFrame {
+                timestamp,
+                file_path: path.to_string_lossy().to_string(),
+                screen_id: 0,
+            }
+        })
+        .collect()
+}
+
+/// 解析 RFC3339 时间并取其本地时间值（与会话时间的存储方式一致）
+fn local_value(value: &str) -> chrono::NaiveDateTime {
+    DateTime::parse_from_rfc3339(value).unwrap().naive_local()
+}
+
+fn card(start: &str, end: &str, title: &str) -> TimelineCard {
+    TimelineCard {
+        start_time: start.to_string(),
+        end_time: end.to_string(),
+        category: "work".to_string(),
+        subcategory: "coding".to_string(),
+        title: title.to_string(),
+        summary: String::new(),
+        detailed_summary: String::new(),
+        distractions: None,
+        app_sites: AppSites {
+            primary: "editor".to_string(),
+            secondary: None,
+        },
+        video_preview_path: None,
+        confidence: None,
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_converts_times_and_links_llm_calls() {
+    let pipeline = setup(MockProvider::new()).await;
+    let start = Utc.with_ymd_and_hms(2025, 10, 9, 9, 0, 0).unwrap();
+    let window = SessionWindow {
+        start,
+        end: start + Duration::minutes(10),
+    };
+    let frames = write_frames(&pipeline.frames_dir, start, 10);
+
+    pipeline
+        .processor
+        .process_session(frames, window)
+        .await
+        .unwrap();
+
+    let sessions = pipeline.db.get_all_sessions().await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    let session = &sessions[0];
+    let session_id = session.id.unwrap();
+
+    // 相对时间 MM:SS 转换为会话内的绝对时间
+    let cards = pipeline
+        .db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .unwrap();
+    assert_eq!(cards.len(), 2);
+    let mut cards = cards;
+    cards.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+    assert_eq!(local_value(&cards[0].start_time), start.naive_utc());
+    assert_eq!(
+        local_value(&cards[1].start_time),
+        (start + Duration::minutes(5)).naive_utc()
+    );
+    assert_eq!(
+        local_value(&cards[1].end_time),
+        (start + Duration::minutes(10)).naive_utc()
+    );
+
+    // 分段和卡片关联到对应的 LLM 调用记录
+    let llm_calls = pipeline
+        .db
+        .get_llm_calls_by_session(session_id)
+        .await
+        .unwrap();
+    let call_id = |call_type: &str| {
+        llm_calls
+            .iter()
+            .find(|c| c.call_type == call_type)
+            .and_then(|c| c.id)
+    };
+    assert!(call_id("generate_timeline").is_some());
+    assert!(cards
+        .iter()
+        .all(|c| c.llm_call_id == call_id("generate_timeline")));
+
+    let segments = pipeline
+        .db
+        .get_video_segments_by_session(session_id)
+        .await
+        .unwrap();
+    assert_eq!(segments.len(), 2);
+    assert!(segments
+        .iter()
+        .all(|s| s.llm_call_id == call_id("segment_video")));
+
+    // 存储占用统计包含会话、分段、卡片和 LLM 调用的数据库行
+    let usage = pipeline
+        .db
+        .get_session_row_usage(Some(session_id))
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 1);
+    assert!(usage[0].row_count >= 1 + (segments.len() + cards.len() + llm_calls.len()) as i64);
+    assert!(usage[0].bytes > 0);
+
+    // provider 收到的时长和视频路径
+    let calls = pipeline.calls.lock().unwrap().clone();
+    let segment_call = calls
+        .iter()
+        .find(|c| c.call_type == "segment_video")
+        .unwrap();
+    assert_eq!(segment_call.duration, 10);
+
+    if pipeline.with_video {
+        let video_path = session.video_path.clone().expect("应生成会话视频");
+        assert_eq!(segment_call.video_path.as_ref(), Some(&video_path));
+        let metadata = VideoMetadata::load(Path::new(&video_path))
+            .await
+            .expect("应写入视频元数据");
+        assert_eq!(metadata.frame_count, 120);
+    } else {
+        assert!(session.video_path.is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_clamps_times_outside_window() {
+    let provider = MockProvider::with_responses(
+        vec![VideoSegment {
+            start_timestamp: "00:00".to_string(),
+            end_timestamp: "30:00".to_string(),
+            description: "编写代码".to_string(),
+        }],
+        vec![card("02:00", "30:00", "超出会话范围")],
+    );
+    let pipeline = setup(provider).await;
+    let start = Utc.with_ymd_and_hms(2025, 10, 9, 14, 0, 0).unwrap();
+    let window = SessionWindow {
+        start,
+        end: start + Duration::minutes(5),
+    };
+    let frames = write_frames(&pipeline.frames_dir, start, 5);
+
+    pipeline
+        .processor
+        .process_session(frames, window)
+        .await
+        .unwrap();
+
+    let session_id = pipeline.db.get_all_sessions().await.unwrap()[0].id.unwrap();
+    let cards = pipeline
+        .db
+        .get_timeline_cards_by_session(session_id)
+        .await
+        .unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(
+        local_value(&cards[0].start_time),
+        (start + Duration::minutes(2)).naive_utc()
+    );
+    assert_eq!(
+        local_value(&cards[0].end_time),
+        (start + Duration::minutes(5)).naive_utc()
+    );
+}
+
+#[tokio::test]
+async fn test_listener_publishes_analysis_completed_with_session_id() {
+    let Pipeline {
+        dir,
+        frames_dir,
+        db,
+        processor,
+        ..
+    } = setup(MockProvider::new()).await;
+    let videos_dir = dir.path().join("videos");
+    for storage_dir in [&frames_dir, &videos_dir] {
+        std::fs::create_dir_all(storage_dir).unwrap();
+        screen_analyzer_lib::storage::availability::ensure_marker(storage_dir, true).unwrap();
+    }
+
+    // 截屏按毫秒时间戳命名为 jpg，由监听器从截图目录读取
+    let start = Utc.with_ymd_and_hms(2025, 10, 9, 16, 0, 0).unwrap();
+    let end = start + Duration::minutes(5);
+    for second in 0..5 * 60 {
+        let timestamp = start + Duration::seconds(second);
+        image::RgbImage::from_pixel(64, 36, image::Rgb([40, 128, 200]))
+            .save(frames_dir.join(format!("{}.jpg", timestamp.timestamp_millis())))
+            .unwrap();
+    }
+
+    let event_bus = Arc::new(EventBus::new(64));
+    let mut analysis_events = event_bus.subscribe_topics("测试", &[EventTopic::Analysis]);
+    let capture = Arc::new(ScreenCapture::new(frames_dir.clone()).unwrap());
+    Arc::new(processor)
+        .start_event_listener(event_bus.clone(), capture, Arc::new(AnalysisHold::new()))
+        .await;
+
+    event_bus.publish(AppEvent::SessionCompleted {
+        session_id: start.timestamp_millis(),
+        frame_count: 300,
+        window_start: start,
+        window_end: end,
+    });
+
+    let completed_id = tokio::time::timeout(std::time::Duration::from_secs(60), async {
+        loop {
+            match analysis_events.recv().await {
+                Some(AppEvent::AnalysisCompleted { session_id, .. }) => break session_id,
+                Some(AppEvent::AnalysisFailed { error, .. }) => panic!("分析失败: {}", error),
+                Some(_) => continue,
+                None => panic!("事件总线已关闭"),
+            }
+        }
+    })
+    .await
+    .expect("应收到 AnalysisCompleted 事件");
+
+    // 事件中的是数据库会话 ID，而不是时间窗口的时间戳
+    let sessions = db.get_all_sessions().await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(Some(completed_id), sessions[0].id);
+}
@@ -1,10 +1,14 @@
 // Pinia Store - 活动状态管理
 
+import { h } from 'vue'
 import { defineStore } from 'pinia'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke, invokeConfirmed } from '../utils/invoke'
 import { ElMessage } from 'element-plus'
 import dayjs from 'dayjs'
 
+// 撤销删除的时间窗口（与后端 trash::UNDO_WINDOW_MINUTES 一致）
+const UNDO_WINDOW_MINUTES = 5
+
 export const useActivityStore = defineStore('activity', {
   state: () => ({
     // 活动列表
@@ -48,6 +52,8 @@ export const useActivityStore = defineStore('activity', {
     },
     // LLM提供商列表
     llmProviders: [],
+    // 待提示的新版本（update-available 事件或手动检查的结果）
+    availableUpdate: null,
     // 加载状态
     loading: {
       activities: false,
@@ -209,6 +215,17 @@ export const useActivityStore = defineStore('activity', {
       }
     },
 
+    // 立即分析指定视频（插到分析队列最前面）
+    async prioritizeVideo(path) {
+      try {
+        await invoke('prioritize_video', { path })
+        ElMessage.success('已加入优先分析')
+      } catch (error) {
+        ElMessage.error('优先分析失败: ' + error)
+        console.error('Failed to prioritize video:', error)
+      }
+    },
+
     async retrySessionAnalysis(sessionId) {
       if (!sessionId) {
         ElMessage.warning('请选择需要重新解析的会话')
@@ -229,9 +246,8 @@ export const useActivityStore = defineStore('activity', {
           this.fetchDaySessions(this.selectedDate)
         ])
       } catch (error) {
-        const errorStr = String(error)
         // 检测视频过短的错误
-        if (errorStr.includes('VIDEO_TOO_SHORT')) {
+        if (error?.code === 'video_too_short') {
           const { ElMessageBox } = await import('element-plus')
           ElMessageBox.confirm(
             '该会话时长过短（少于15分钟），无法进行AI分析。是否删除该会话？',
@@ -244,7 +260,7 @@ export const useActivityStore = defineStore('activity', {
           ).then(async () => {
             try {
               await this.deleteSession(sessionId)
-              ElMessage.success('会话已删除')
+              this.showDeletedWithUndo(sessionId)
             } catch (deleteError) {
               ElMessage.error('删除失败: ' + deleteError)
             }
@@ -261,10 +277,22 @@ export const useActivityStore = defineStore('activity', {
       }
     },
 
+    // 置顶 / 取消置顶会话（置顶的会话不会被自动清理）
+    async setSessionPinned(sessionId, pinned) {
+      await invoke(pinned ? 'pin_session' : 'unpin_session', { sessionId })
+      if (this.selectedSession?.session?.id === sessionId) {
+        this.selectedSession.session.pinned = pinned
+      }
+      const session = this.daySessions?.find(s => s.id === sessionId)
+      if (session) {
+        session.pinned = pinned
+      }
+    },
+
     // 删除会话
     async deleteSession(sessionId) {
       try {
-        await invoke('delete_session', { sessionId })
+        await invokeConfirmed('delete_session', 'delete_session', { sessionId })
         // 刷新列表
         await this.fetchDaySessions(this.selectedDate)
         // 刷新月度活动数据
@@ -282,6 +310,42 @@ export const useActivityStore = defineStore('activity', {
       }
     },
 
+    // 撤销删除会话（删除后 5 分钟内有效），会话按原 ID 恢复
+    async undoDeleteSession(sessionId) {
+      const restoredId = await invoke('undo_delete_session', { sessionId })
+      await this.fetchDaySessions(this.selectedDate)
+      const current = dayjs(this.selectedDate)
+      await this.fetchActivities(
+        current.startOf('month').format('YYYY-MM-DD'),
+        current.endOf('month').format('YYYY-MM-DD')
+      )
+      return restoredId
+    },
+
+    // 提示会话已删除，撤销入口在整个撤销窗口内保留（可手动关闭）
+    showDeletedWithUndo(sessionId) {
+      const message = ElMessage({
+        type: 'success',
+        duration: UNDO_WINDOW_MINUTES * 60 * 1000,
+        showClose: true,
+        message: h('span', [
+          `会话已删除，${UNDO_WINDOW_MINUTES} 分钟内可`,
+          h('a', {
+            style: 'cursor: pointer; color: var(--el-color-primary)',
+            onClick: async () => {
+              message.close()
+              try {
+                await this.undoDeleteSession(sessionId)
+                ElMessage.success('已撤销删除')
+              } catch (error) {
+                ElMessage.error('撤销失败: ' + error)
+              }
+            }
+          }, '撤销')
+        ])
+      })
+    },
+
     // 生成视频
     async generateVideo(sessionId, speedMultiplier = 20, silent = false) {
       try {
@@ -328,7 +392,7 @@ export const useActivityStore = defineStore('activity', {
     // 清理存储
     async cleanupStorage() {
       try {
-        await invoke('cleanup_storage')
+        await invokeConfirmed('cleanup_storage', 'cleanup_storage')
         ElMessage.success('存储清理完成')
         await this.fetchSystemStatus()
       } catch (error) {
@@ -403,6 +467,54 @@ export const useActivityStore = defineStore('activity', {
       }
     },
 
+    // 按筛选条件批量添加/移除标签（先统计受影响的会话数，确认后写入）
+    async bulkTag(filter, tag, remove = false) {
+      try {
+        const preview = await invoke('bulk_tag', { filter, tag, remove, dryRun: true })
+        if (preview.changed === 0) {
+          ElMessage.info(`符合条件的 ${preview.matched} 个会话无需修改`)
+          return null
+        }
+        const { ElMessageBox } = await import('element-plus')
+        const skipped = preview.skipped > 0 ? `（${preview.skipped} 个会话标签无法解析，将跳过）` : ''
+        try {
+          await ElMessageBox.confirm(
+            `符合条件的会话 ${preview.matched} 个，将${remove ? '移除' : '添加'}标签的会话 ${preview.changed} 个${skipped}。确定继续？`,
+            remove ? '批量移除标签' : '批量添加标签',
+            { confirmButtonText: '确定', cancelButtonText: '取消', type: 'warning' }
+          )
+        } catch {
+          return null
+        }
+        const report = await invoke('bulk_tag', { filter, tag, remove, dryRun: false })
+        ElMessage.success(`已更新 ${report.changed} 个会话的标签`)
+        await this.fetchDaySessions(this.selectedDate)
+        if (this.selectedSession?.session?.id) {
+          await this.fetchSessionDetail(this.selectedSession.session.id)
+        }
+        return report
+      } catch (error) {
+        ElMessage.error('批量修改标签失败: ' + error)
+        console.error('Failed to bulk tag sessions:', error)
+        return null
+      }
+    },
+
+    // 修改会话标题
+    async renameSession(sessionId, title) {
+      try {
+        await invoke('rename_session', { sessionId, title })
+        ElMessage.success('标题已修改')
+        if (this.selectedSession?.session?.id === sessionId) {
+          await this.fetchSessionDetail(sessionId)
+        }
+        await this.fetchDaySessions(this.selectedDate)
+      } catch (error) {
+        ElMessage.error('修改标题失败: ' + error)
+        console.error('Failed to rename session:', error)
+      }
+    },
+
     // 初始化
     async initialize() {
       await Promise.all([
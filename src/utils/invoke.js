@@ -0,0 +1,37 @@
+// Tauri 命令调用 - 把后端返回的 { code, message, detail } 错误包装成 CommandError
+
+import { invoke as tauriInvoke } from '@tauri-apps/api/core'
+
+// 命令错误：code 区分错误种类（database_not_ready、missing_api_key、video_missing 等），
+// message 面向用户，detail 为后端原始错误信息
+export class CommandError extends Error {
+  constructor({ code, message, detail }) {
+    super(message || detail || '未知错误')
+    this.name = 'CommandError'
+    this.code = code || 'internal'
+    this.detail = detail || this.message
+  }
+
+  // 保持与原先字符串错误一致的拼接效果（'失败: ' + error）
+  toString() {
+    return this.message
+  }
+}
+
+export async function invoke(command, args) {
+  try {
+    return await tauriInvoke(command, args)
+  } catch (error) {
+    if (error && typeof error === 'object' && 'code' in error) {
+      throw new CommandError(error)
+    }
+    throw error
+  }
+}
+
+// 调用需要确认令牌的危险命令（delete_session、cleanup_storage、migrate_timezone_to_local、delete_analysis_backlog、reset_all_data）
+// 调用方应先完成用户确认，这里只负责申请令牌并随命令传入
+export async function invokeConfirmed(command, action, args = {}) {
+  const { token } = await invoke('request_confirmation', { action })
+  return invoke(command, { ...args, confirmationToken: token })
+}